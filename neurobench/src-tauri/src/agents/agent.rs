@@ -36,6 +36,9 @@ pub struct AgentResponse {
     pub message: String,
     pub tool_calls: Vec<ToolCall>,
     pub suggestions: Vec<String>,
+    /// Reasoning the model showed before answering
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<super::ReasoningTrace>,
 }
 
 /// Agent trait - all agents must implement this
@@ -90,6 +90,7 @@ Always generate complete, compilable code. Never leave TODO placeholders."#.to_s
             message: "Code Agent processing...".to_string(),
             tool_calls: Vec::new(),
             suggestions: Vec::new(),
+            reasoning: None,
         })
     }
 }
@@ -92,6 +92,7 @@ Be thorough but concise. Focus on embedded-specific issues."#.to_string()
             message: "Debug Agent processing...".to_string(),
             tool_calls: Vec::new(),
             suggestions: Vec::new(),
+            reasoning: None,
         })
     }
 }
@@ -0,0 +1,482 @@
+// C Semantic Diff
+// Describes code changes per function instead of per line
+
+use super::{create_text_diff, DiffHunk};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// What changed about a function
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    FunctionAdded,
+    FunctionRemoved,
+    ParameterAdded,
+    ParameterRemoved,
+    ParameterChanged,
+    ReturnTypeChanged,
+    BodyChanged,
+}
+
+/// A function-level change with the text hunks that make it up
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticChange {
+    pub kind: ChangeKind,
+    pub symbol_name: String,
+    pub change_description: String,
+    pub text_hunks: Vec<DiffHunk>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct CParam {
+    name: String,
+    ty: String,
+}
+
+/// A function definition or prototype
+#[derive(Debug, Clone)]
+struct CFunction {
+    name: String,
+    return_type: String,
+    params: Vec<CParam>,
+    /// Body with comments removed and whitespace collapsed, `None` for prototypes
+    body: Option<String>,
+    /// 1-indexed inclusive line ranges
+    header_lines: (usize, usize),
+    body_lines: Option<(usize, usize)>,
+}
+
+/// Blank out comments and preprocessor lines, keeping byte offsets and newlines
+fn mask_comments(src: &str) -> String {
+    let mut out = src.as_bytes().to_vec();
+    let bytes = src.as_bytes();
+    let mut i = 0;
+    let mut line_start = true;
+    let blank = |out: &mut Vec<u8>, from: usize, to: usize| {
+        for b in &mut out[from..to] {
+            if *b != b'\n' {
+                *b = b' ';
+            }
+        }
+    };
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                let end = src[i..].find('\n').map_or(bytes.len(), |e| i + e);
+                blank(&mut out, i, end);
+                i = end;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = src[i + 2..].find("*/").map_or(bytes.len(), |e| i + 2 + e + 2);
+                blank(&mut out, i, end);
+                i = end;
+            }
+            b'"' | b'\'' => {
+                // Skip literals so comment markers inside them survive
+                let quote = bytes[i];
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote && bytes[i] != b'\n' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+                line_start = false;
+            }
+            b'#' if line_start => {
+                let mut end = i;
+                loop {
+                    end = src[end..].find('\n').map_or(bytes.len(), |e| end + e);
+                    if end >= bytes.len() || !src[..end].trim_end().ends_with('\\') {
+                        break;
+                    }
+                    end += 1;
+                }
+                blank(&mut out, i, end);
+                i = end;
+            }
+            b'\n' => {
+                line_start = true;
+                i += 1;
+            }
+            b' ' | b'\t' | b'\r' => i += 1,
+            _ => {
+                line_start = false;
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).unwrap_or_default()
+}
+
+/// Index of the quote ending the literal that starts at `start`
+fn skip_literal(bytes: &[u8], start: usize) -> usize {
+    let quote = bytes[start];
+    let mut i = start + 1;
+    while i < bytes.len() && bytes[i] != quote {
+        i += if bytes[i] == b'\\' { 2 } else { 1 };
+    }
+    i.min(bytes.len().saturating_sub(1))
+}
+
+/// Index of the `}` matching the `{` at `open`
+fn matching_brace(bytes: &[u8], open: usize) -> usize {
+    let mut depth = 0;
+    let mut i = open;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' | b'\'' => i = skip_literal(bytes, i),
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    bytes.len().saturating_sub(1)
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Type spelled with single spaces and `*` attached: `const uint8_t *`
+fn normalize_type(ty: &str) -> String {
+    let ty = collapse_whitespace(ty).replace(" *", "*");
+    match ty.find('*') {
+        Some(star) => format!("{} {}", ty[..star].trim_end(), &ty[star..]),
+        None => ty,
+    }
+}
+
+/// Split on commas outside parentheses and brackets
+fn split_params(list: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    for (i, c) in list.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(list[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(list[start..].trim());
+    parts.into_iter().filter(|p| !p.is_empty()).collect()
+}
+
+fn parse_param(index: usize, text: &str) -> Option<CParam> {
+    if text == "void" || text == "..." {
+        return None;
+    }
+    // Function pointer: `void (*callback)(int)`
+    if let Some(open) = text.find("(*") {
+        let name_end = text[open..].find(')').map_or(text.len(), |e| open + e);
+        let name = text[open + 2..name_end].trim();
+        let ty = format!("{}(*){}", &text[..open], &text[name_end + 1..]);
+        return Some(CParam { name: name.to_string(), ty: collapse_whitespace(&ty) });
+    }
+    let (text, array) = match text.find('[') {
+        Some(bracket) => (text[..bracket].trim_end(), true),
+        None => (text, false),
+    };
+    let name_start = text.rfind(|c: char| !(c.is_alphanumeric() || c == '_')).map_or(0, |i| i + 1);
+    let (ty, name) = text.split_at(name_start);
+    if ty.trim().is_empty() || matches!(name, "int" | "char" | "float" | "double" | "long" | "short" | "unsigned") {
+        // Unnamed parameter in a prototype
+        return Some(CParam { name: format!("arg{}", index + 1), ty: normalize_type(text) });
+    }
+    let mut ty = normalize_type(ty);
+    if array {
+        ty.push_str(" *");
+        ty = normalize_type(&ty);
+    }
+    Some(CParam { name: name.to_string(), ty })
+}
+
+/// Parse `header` (everything before `{` or `;`) as a function signature
+fn parse_header(header: &str) -> Option<(String, String, Vec<CParam>)> {
+    let header = header.trim();
+    if !header.ends_with(')') || header.contains('=') {
+        return None;
+    }
+    // Parameter list is the last balanced parenthesis group
+    let mut depth = 0;
+    let mut open = None;
+    for (i, c) in header.char_indices().rev() {
+        match c {
+            ')' => depth += 1,
+            '(' => {
+                depth -= 1;
+                if depth == 0 {
+                    open = Some(i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let open = open?;
+    let before = header[..open].trim_end();
+    let name_start = before.rfind(|c: char| !(c.is_alphanumeric() || c == '_')).map_or(0, |i| i + 1);
+    let (return_type, name) = before.split_at(name_start);
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let words: Vec<&str> = return_type.split_whitespace()
+        .filter(|w| !matches!(*w, "static" | "inline" | "extern"))
+        .collect();
+    if words.is_empty() || words.contains(&"typedef") {
+        return None;
+    }
+    let params = split_params(&header[open + 1..header.len() - 1])
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, p)| parse_param(i, p))
+        .collect();
+    Some((name.to_string(), normalize_type(&words.join(" ")), params))
+}
+
+/// Top-level function definitions and prototypes; definitions win over prototypes
+fn parse_functions(src: &str) -> Vec<CFunction> {
+    let masked = mask_comments(src);
+    let bytes = masked.as_bytes();
+    let line_of = |offset: usize| masked[..offset.min(masked.len())].matches('\n').count() + 1;
+
+    let mut functions: Vec<CFunction> = Vec::new();
+    let mut boundary = 0;
+    // `extern "C" {` blocks do not hide the functions they contain
+    let mut extern_blocks = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' | b'\'' => i = skip_literal(bytes, i),
+            b'{' => {
+                let header = &masked[boundary..i];
+                let header_start = boundary + (header.len() - header.trim_start().len());
+                let close = matching_brace(bytes, i);
+                if let Some((name, return_type, params)) = parse_header(header) {
+                    functions.retain(|f| f.name != name);
+                    functions.push(CFunction {
+                        name,
+                        return_type,
+                        params,
+                        body: Some(collapse_whitespace(&masked[i + 1..close])),
+                        header_lines: (line_of(header_start), line_of(i)),
+                        body_lines: Some((line_of(i), line_of(close))),
+                    });
+                    i = close;
+                } else if collapse_whitespace(header).starts_with("extern") {
+                    extern_blocks += 1;
+                } else {
+                    i = close;
+                }
+                boundary = i + 1;
+            }
+            b'}' if extern_blocks > 0 => {
+                extern_blocks -= 1;
+                boundary = i + 1;
+            }
+            b';' => {
+                let header = &masked[boundary..i];
+                if let Some((name, return_type, params)) = parse_header(header) {
+                    if !functions.iter().any(|f| f.name == name) {
+                        let header_start = boundary + (header.len() - header.trim_start().len());
+                        functions.push(CFunction {
+                            name,
+                            return_type,
+                            params,
+                            body: None,
+                            header_lines: (line_of(header_start), line_of(i)),
+                            body_lines: None,
+                        });
+                    }
+                }
+                boundary = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    functions
+}
+
+fn overlaps(start: usize, count: usize, range: (usize, usize)) -> bool {
+    // Pure insertions sit between lines; count them for the line they precede
+    let end = start + count.max(1) - 1;
+    start <= range.1 && end >= range.0
+}
+
+/// Hunks touching `old` lines of the original or `new` lines of the modified file
+fn hunks_for(hunks: &[DiffHunk], old: Option<(usize, usize)>, new: Option<(usize, usize)>) -> Vec<DiffHunk> {
+    hunks.iter()
+        .filter(|h| {
+            old.is_some_and(|r| h.old_count > 0 && overlaps(h.old_start, h.old_count, r))
+                || new.is_some_and(|r| h.new_count > 0 && overlaps(h.new_start, h.new_count, r))
+        })
+        .cloned()
+        .collect()
+}
+
+fn span(function: &CFunction) -> (usize, usize) {
+    (function.header_lines.0, function.body_lines.map_or(function.header_lines.1, |b| b.1))
+}
+
+/// Function-level changes between two versions of a C source file
+pub fn ast_diff_c(original: &str, modified: &str) -> Vec<SemanticChange> {
+    let hunks = create_text_diff(original, modified);
+    if hunks.is_empty() {
+        return Vec::new();
+    }
+    let old_functions = parse_functions(original);
+    let new_functions = parse_functions(modified);
+    let new_by_name: HashMap<&str, &CFunction> = new_functions.iter().map(|f| (f.name.as_str(), f)).collect();
+    let mut changes = Vec::new();
+    let mut change = |kind, symbol: &str, description: String, text_hunks| {
+        changes.push(SemanticChange {
+            kind,
+            symbol_name: symbol.to_string(),
+            change_description: description,
+            text_hunks,
+        });
+    };
+
+    for old in &old_functions {
+        let name = old.name.as_str();
+        let Some(new) = new_by_name.get(name) else {
+            change(ChangeKind::FunctionRemoved, name, format!("removed function `{}`", name), hunks_for(&hunks, Some(span(old)), None));
+            continue;
+        };
+        let header_hunks = || hunks_for(&hunks, Some(old.header_lines), Some(new.header_lines));
+
+        if old.return_type != new.return_type {
+            change(
+                ChangeKind::ReturnTypeChanged,
+                name,
+                format!("changed return type of `{}` from `{}` to `{}`", name, old.return_type, new.return_type),
+                header_hunks(),
+            );
+        }
+
+        for param in new.params.iter().filter(|p| !old.params.iter().any(|o| o.name == p.name)) {
+            change(ChangeKind::ParameterAdded, name, format!("added parameter `{}` to `{}`", param.name, name), header_hunks());
+        }
+        for param in old.params.iter().filter(|p| !new.params.iter().any(|n| n.name == p.name)) {
+            change(ChangeKind::ParameterRemoved, name, format!("removed parameter `{}` from `{}`", param.name, name), header_hunks());
+        }
+        for param in &old.params {
+            if let Some(updated) = new.params.iter().find(|n| n.name == param.name && n.ty != param.ty) {
+                change(
+                    ChangeKind::ParameterChanged,
+                    name,
+                    format!("changed type of parameter `{}` in `{}` from `{}` to `{}`", param.name, name, param.ty, updated.ty),
+                    header_hunks(),
+                );
+            }
+        }
+        let old_names: Vec<&str> = old.params.iter().map(|p| p.name.as_str()).collect();
+        let new_names: Vec<&str> = new.params.iter().map(|p| p.name.as_str()).filter(|n| old_names.contains(n)).collect();
+        let kept: Vec<&str> = old_names.iter().copied().filter(|n| new_names.contains(n)).collect();
+        if kept != new_names {
+            change(ChangeKind::ParameterChanged, name, format!("reordered parameters of `{}`", name), header_hunks());
+        }
+
+        if let (Some(old_body), Some(new_body)) = (&old.body, &new.body) {
+            if old_body != new_body {
+                let body_hunks = hunks_for(&hunks, old.body_lines, new.body_lines);
+                let added: usize = body_hunks.iter().map(|h| h.new_count).sum();
+                let removed: usize = body_hunks.iter().map(|h| h.old_count).sum();
+                change(
+                    ChangeKind::BodyChanged,
+                    name,
+                    format!("changed body of `{}` (+{} -{} lines)", name, added, removed),
+                    body_hunks,
+                );
+            }
+        }
+    }
+
+    for new in new_functions.iter().filter(|f| !old_functions.iter().any(|o| o.name == f.name)) {
+        change(
+            ChangeKind::FunctionAdded,
+            &new.name,
+            format!("added function `{}`", new.name),
+            hunks_for(&hunks, None, Some(span(new))),
+        );
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &str = r#"#include "uart.h"
+
+/* Bring up the UART { not a block } */
+void uart_init(uint32_t baud)
+{
+    USART2->BRR = SystemCoreClock / baud;
+}
+
+int uart_write(const uint8_t *buf, int len);
+
+static void legacy_flush(void)
+{
+    while (!(USART2->SR & USART_SR_TC)) {}
+}
+"#;
+
+    const MODIFIED: &str = r#"#include "uart.h"
+
+/* Bring up the UART { not a block } */
+HAL_StatusTypeDef uart_init(uint32_t baud,
+                            uint32_t timeout_ms)
+{
+    USART2->BRR = SystemCoreClock / baud;
+    return HAL_OK;
+}
+
+int uart_write(const uint8_t *buf, size_t len);
+
+bool uart_ready(void)
+{
+    return (USART2->SR & USART_SR_TXE) != 0;
+}
+"#;
+
+    #[test]
+    fn test_ast_diff_c() {
+        let changes = ast_diff_c(ORIGINAL, MODIFIED);
+        let descriptions: Vec<&str> = changes.iter().map(|c| c.change_description.as_str()).collect();
+        assert_eq!(descriptions, vec![
+            "changed return type of `uart_init` from `void` to `HAL_StatusTypeDef`",
+            "added parameter `timeout_ms` to `uart_init`",
+            "changed body of `uart_init` (+1 -0 lines)",
+            "changed type of parameter `len` in `uart_write` from `int` to `size_t`",
+            "removed function `legacy_flush`",
+            "added function `uart_ready`",
+        ]);
+
+        let added = changes.iter().find(|c| c.kind == ChangeKind::ParameterAdded).unwrap();
+        assert_eq!(added.symbol_name, "uart_init");
+        assert_eq!(added.text_hunks.len(), 1);
+        assert_eq!(added.text_hunks[0].new_lines.len(), 2);
+        assert!(changes.iter().all(|c| !c.text_hunks.is_empty()));
+    }
+
+    #[test]
+    fn test_comment_only_change() {
+        let original = "int f(int a)\n{\n    return a; // old\n}\n";
+        let modified = "int f(int a)\n{\n    return a; // new\n}\n";
+        assert!(ast_diff_c(original, modified).is_empty());
+        assert!(ast_diff_c(original, original).is_empty());
+    }
+}
@@ -10,6 +10,11 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::path::PathBuf;
 use chrono::{DateTime, Utc};
+use super::reasoning::ReasoningTrace;
+
+pub mod c_diff;
+
+pub use c_diff::{ast_diff_c, ChangeKind, SemanticChange};
 
 /// A patch that can be applied to project resources
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +25,9 @@ pub struct Patch {
     pub target: PatchTarget,
     pub operations: PatchOperations,
     pub reversible: bool,
+    /// Function-level description of code changes, empty for JSON patches
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub semantic_changes: Vec<SemanticChange>,
 }
 
 impl Patch {
@@ -31,6 +39,7 @@ impl Patch {
             target,
             operations,
             reversible: true,
+            semantic_changes: Vec::new(),
         }
     }
     
@@ -55,6 +64,57 @@ impl Patch {
             PatchOperations::TextDiff(hunks),
         )
     }
+    
+    /// Operations that undo this patch when applied to its result
+    ///
+    /// `original` is the document the patch was applied to. JSON ops are
+    /// inverted one at a time against the intermediate document and returned
+    /// in reverse order; `None` when an op (move/copy/test) has no inverse.
+    pub fn inverse(&self, original: &Value) -> Option<PatchOperations> {
+        match &self.operations {
+            PatchOperations::Json(ops) => {
+                let mut doc = original.clone();
+                let mut inverse = Vec::with_capacity(ops.len());
+                for op in ops {
+                    inverse.push(op.inverse(&doc)?);
+                    op.apply(&mut doc).ok()?;
+                }
+                inverse.reverse();
+                Some(PatchOperations::Json(inverse))
+            }
+            PatchOperations::TextDiff(hunks) => {
+                // Hunk positions must be in terms of the patched file
+                let mut offset: i64 = 0;
+                let inverse = hunks.iter().map(|hunk| {
+                    let start = (hunk.old_start as i64 + offset) as usize;
+                    offset += hunk.new_count as i64 - hunk.old_count as i64;
+                    DiffHunk {
+                        old_start: start,
+                        old_count: hunk.new_count,
+                        new_start: hunk.old_start,
+                        new_count: hunk.old_count,
+                        old_lines: hunk.new_lines.clone(),
+                        new_lines: hunk.old_lines.clone(),
+                        context_before: hunk.context_before.clone(),
+                        context_after: hunk.context_after.clone(),
+                    }
+                }).collect();
+                Some(PatchOperations::TextDiff(inverse))
+            }
+        }
+    }
+    
+    /// Text diff patch between two versions of a C file, with semantic changes
+    pub fn c_source_diff(
+        description: impl Into<String>,
+        file_path: PathBuf,
+        original: &str,
+        modified: &str,
+    ) -> Self {
+        let mut patch = Self::text_diff(description, file_path, create_text_diff(original, modified));
+        patch.semantic_changes = ast_diff_c(original, modified);
+        patch
+    }
 }
 
 /// What the patch targets
@@ -116,26 +176,26 @@ impl JsonPatchOp {
     pub fn apply(&self, doc: &mut Value) -> Result<(), PatchError> {
         match self {
             JsonPatchOp::Add { path, value } => {
-                json_pointer_set(doc, path, value.clone())
+                json_pointer_set(doc, path, value.clone(), true)
             }
             JsonPatchOp::Remove { path } => {
                 json_pointer_remove(doc, path)
             }
             JsonPatchOp::Replace { path, value } => {
-                json_pointer_set(doc, path, value.clone())
+                json_pointer_set(doc, path, value.clone(), false)
             }
             JsonPatchOp::Move { from, path } => {
                 let value = json_pointer_get(doc, from)?
                     .ok_or_else(|| PatchError::PathNotFound(from.clone()))?
                     .clone();
                 json_pointer_remove(doc, from)?;
-                json_pointer_set(doc, path, value)
+                json_pointer_set(doc, path, value, true)
             }
             JsonPatchOp::Copy { from, path } => {
                 let value = json_pointer_get(doc, from)?
                     .ok_or_else(|| PatchError::PathNotFound(from.clone()))?
                     .clone();
-                json_pointer_set(doc, path, value)
+                json_pointer_set(doc, path, value, true)
             }
             JsonPatchOp::Test { path, value } => {
                 let current = json_pointer_get(doc, path)?
@@ -152,7 +212,18 @@ impl JsonPatchOp {
     pub fn inverse(&self, original: &Value) -> Option<JsonPatchOp> {
         match self {
             JsonPatchOp::Add { path, .. } => {
-                Some(JsonPatchOp::remove(path))
+                // Array adds insert: the new element sits at the index, or at the
+                // array's current length for "-" and indices past the end
+                let (parent, index) = path.rsplit_once('/')?;
+                if let Ok(Some(Value::Array(arr))) = json_pointer_get(original, parent) {
+                    let index = index.parse().map_or(arr.len(), |i: usize| i.min(arr.len()));
+                    return Some(JsonPatchOp::remove(format!("{}/{}", parent, index)));
+                }
+                // Add over an existing member overwrites it
+                match json_pointer_get(original, path) {
+                    Ok(Some(existing)) => Some(JsonPatchOp::replace(path, existing.clone())),
+                    _ => Some(JsonPatchOp::remove(path)),
+                }
             }
             JsonPatchOp::Remove { path } => {
                 json_pointer_get(original, path).ok().flatten().map(|v| {
@@ -253,46 +324,34 @@ impl DiffHunk {
 
 /// Create text diff between two strings
 pub fn create_text_diff(old: &str, new: &str) -> Vec<DiffHunk> {
-    // Simple line-by-line diff using LCS
+    // Line-by-line diff along a longest common subsequence
     let old_lines: Vec<&str> = old.lines().collect();
     let new_lines: Vec<&str> = new.lines().collect();
-    
+    let (n, m) = (old_lines.len(), new_lines.len());
+
+    // Common prefix and suffix match trivially, only the middle needs the LCS
+    let prefix = old_lines.iter().zip(&new_lines).take_while(|(a, b)| a == b).count();
+    let suffix = old_lines[prefix..].iter().rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut matches: Vec<(usize, usize)> = (0..prefix).map(|k| (k, k)).collect();
+    lcs_matches(&old_lines[prefix..n - suffix], &new_lines[prefix..m - suffix], prefix, prefix, &mut matches);
+    matches.extend((0..suffix).map(|k| (n - suffix + k, m - suffix + k)));
+    // Sentinel so the changes after the last common line form a hunk
+    matches.push((n, m));
+
     let mut hunks = Vec::new();
-    let mut i = 0;
-    let mut j = 0;
-    
-    while i < old_lines.len() || j < new_lines.len() {
-        // Skip matching lines
-        while i < old_lines.len() && j < new_lines.len() && old_lines[i] == new_lines[j] {
-            i += 1;
-            j += 1;
-        }
-        
-        if i >= old_lines.len() && j >= new_lines.len() {
-            break;
-        }
-        
-        // Collect differing lines
-        let old_start = i + 1; // 1-indexed
-        let new_start = j + 1;
-        let mut old_chunk = Vec::new();
-        let mut new_chunk = Vec::new();
-        
-        // Find next matching point
-        while i < old_lines.len() && (j >= new_lines.len() || !old_lines[i..].contains(&new_lines.get(j).unwrap_or(&""))) {
-            old_chunk.push(old_lines[i].to_string());
-            i += 1;
-        }
-        while j < new_lines.len() && (i >= old_lines.len() || !new_lines[j..].contains(&old_lines.get(i).unwrap_or(&""))) {
-            new_chunk.push(new_lines[j].to_string());
-            j += 1;
-        }
-        
-        if !old_chunk.is_empty() || !new_chunk.is_empty() {
+    let (mut i, mut j) = (0, 0);
+    for (mi, mj) in matches {
+        if mi > i || mj > j {
+            let old_chunk: Vec<String> = old_lines[i..mi].iter().map(|l| l.to_string()).collect();
+            let new_chunk: Vec<String> = new_lines[j..mj].iter().map(|l| l.to_string()).collect();
             hunks.push(DiffHunk {
-                old_start,
+                old_start: i + 1, // 1-indexed
                 old_count: old_chunk.len(),
-                new_start,
+                new_start: j + 1,
                 new_count: new_chunk.len(),
                 old_lines: old_chunk,
                 new_lines: new_chunk,
@@ -300,11 +359,66 @@ pub fn create_text_diff(old: &str, new: &str) -> Vec<DiffHunk> {
                 context_after: vec![],
             });
         }
+        i = mi + 1;
+        j = mj + 1;
     }
-    
+
     hunks
 }
 
+/// Matching line pairs of a longest common subsequence, in order (Hirschberg)
+///
+/// Linear space: only two rows of the LCS table are kept at a time, so large
+/// files cost O(n + m) memory instead of the full O(n * m) table.
+fn lcs_matches(a: &[&str], b: &[&str], a_off: usize, b_off: usize, out: &mut Vec<(usize, usize)>) {
+    if a.is_empty() || b.is_empty() {
+        return;
+    }
+    if a.len() == 1 {
+        if let Some(j) = b.iter().position(|line| *line == a[0]) {
+            out.push((a_off, b_off + j));
+        }
+        return;
+    }
+
+    // Split `a` in half and find where the LCS crosses into `b`
+    let mid = a.len() / 2;
+    let forward = lcs_row(a[..mid].iter(), b.iter(), b.len());
+    let backward = lcs_row(a[mid..].iter().rev(), b.iter().rev(), b.len());
+    let split = (0..=b.len())
+        .max_by_key(|&j| (forward[j] + backward[b.len() - j], std::cmp::Reverse(j)))
+        .unwrap_or(0);
+
+    lcs_matches(&a[..mid], &b[..split], a_off, b_off, out);
+    lcs_matches(&a[mid..], &b[split..], a_off + mid, b_off + split, out);
+}
+
+/// Last row of the LCS table: entry `j` is the LCS length of `a` and the first `j` lines of `b`
+fn lcs_row<T: PartialEq>(a: impl Iterator<Item = T>, b: impl Iterator<Item = T> + Clone, b_len: usize) -> Vec<usize> {
+    let mut prev = vec![0usize; b_len + 1];
+    let mut cur = vec![0usize; b_len + 1];
+    for x in a {
+        for (j, y) in b.clone().enumerate() {
+            cur[j + 1] = if x == y { prev[j] + 1 } else { prev[j + 1].max(cur[j]) };
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev
+}
+
+/// Unified diff between two versions of a file, empty when they match
+pub fn unified_diff(name: &str, old: &str, new: &str) -> String {
+    let hunks = create_text_diff(old, new);
+    if hunks.is_empty() {
+        return String::new();
+    }
+    let mut diff = format!("--- a/{name}\n+++ b/{name}\n", name = name);
+    for hunk in hunks {
+        diff.push_str(&hunk.to_unified());
+    }
+    diff
+}
+
 // ==================== Patch Application ====================
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -339,14 +453,27 @@ pub fn apply_patch(patch: &Patch, doc: &mut Value) -> Result<(), PatchError> {
             }
             Ok(())
         }
-        PatchOperations::TextDiff(_) => {
-            Err(PatchError::TypeMismatch(
+        PatchOperations::TextDiff(hunks) => match doc {
+            // File content passed as a JSON string
+            Value::String(content) => {
+                *content = apply_text_patch(hunks, content)?;
+                Ok(())
+            }
+            _ => Err(PatchError::TypeMismatch(
                 "Cannot apply text diff to JSON value".to_string()
-            ))
-        }
+            )),
+        },
     }
 }
 
+/// Apply a patch, leaving `doc` untouched if any operation fails
+pub fn apply_patch_atomic(patch: &Patch, doc: &mut Value) -> Result<(), PatchError> {
+    let mut patched = doc.clone();
+    apply_patch(patch, &mut patched)?;
+    *doc = patched;
+    Ok(())
+}
+
 /// Apply a text diff patch to file content
 pub fn apply_text_patch(hunks: &[DiffHunk], content: &str) -> Result<String, PatchError> {
     let mut lines: Vec<&str> = content.lines().collect();
@@ -395,6 +522,12 @@ pub struct AuditEntry {
     pub action: AuditAction,
     pub patch: Patch,
     pub status: AuditStatus,
+    /// Operations that revert the patch, captured from the document it was applied to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inverse: Option<PatchOperations>,
+    /// Why the agent proposed the patch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<ReasoningTrace>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -416,17 +549,37 @@ pub enum AuditStatus {
     RolledBack,
 }
 
+/// Maximum number of applied patches kept for undo
+pub const MAX_UNDO: usize = 50;
+
 /// Audit log for tracking all patches
 pub struct AuditLog {
     entries: Vec<AuditEntry>,
+    /// Entry ids of undoable patches, most recent last
+    undo_stack: Vec<String>,
+    redo_stack: Vec<String>,
 }
 
 impl AuditLog {
     pub fn new() -> Self {
-        Self { entries: Vec::new() }
+        Self {
+            entries: Vec::new(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+        }
     }
     
     pub fn record_proposal(&mut self, agent_id: impl Into<String>, patch: Patch) -> String {
+        self.record_proposal_with_reasoning(agent_id, patch, None)
+    }
+    
+    /// Record a proposal together with the reasoning that led to it
+    pub fn record_proposal_with_reasoning(
+        &mut self,
+        agent_id: impl Into<String>,
+        patch: Patch,
+        reasoning: Option<ReasoningTrace>,
+    ) -> String {
         let entry = AuditEntry {
             id: uuid::Uuid::new_v4().to_string(),
             timestamp: Utc::now(),
@@ -434,6 +587,8 @@ impl AuditLog {
             action: AuditAction::Proposed,
             patch,
             status: AuditStatus::Pending,
+            inverse: None,
+            reasoning,
         };
         let id = entry.id.clone();
         self.entries.push(entry);
@@ -447,6 +602,83 @@ impl AuditLog {
         }
     }
     
+    /// Record a patch applied to `original`, making it undoable
+    ///
+    /// A new change invalidates anything that was undone before it.
+    pub fn record_applied_to(&mut self, entry_id: &str, original: &Value) {
+        let Some(entry) = self.entries.iter_mut().find(|e| e.id == entry_id) else {
+            return;
+        };
+        entry.inverse = entry.patch.inverse(original);
+        let undoable = entry.inverse.is_some();
+        self.record_applied(entry_id);
+        
+        if undoable {
+            self.undo_stack.retain(|id| id != entry_id);
+            self.undo_stack.push(entry_id.to_string());
+            if self.undo_stack.len() > MAX_UNDO {
+                self.undo_stack.remove(0);
+            }
+        }
+        self.redo_stack.clear();
+    }
+    
+    /// Roll back the most recently applied patch by applying its inverse to `doc`
+    ///
+    /// History only changes once the inverse applied cleanly; on error `doc`
+    /// and both stacks are left as they were.
+    pub fn undo(&mut self, doc: &mut Value) -> Result<Option<Patch>, PatchError> {
+        let Some(entry_id) = self.undo_stack.last().cloned() else {
+            return Ok(None);
+        };
+        let Some(entry) = self.entries.iter_mut().find(|e| e.id == entry_id) else {
+            return Ok(None);
+        };
+        let Some(operations) = entry.inverse.clone() else {
+            return Ok(None);
+        };
+        let undo = Patch::new(format!("Undo: {}", entry.patch.description), entry.patch.target.clone(), operations);
+        apply_patch_atomic(&undo, doc)?;
+        
+        entry.action = AuditAction::RolledBack;
+        entry.status = AuditStatus::RolledBack;
+        self.undo_stack.pop();
+        self.redo_stack.push(entry_id);
+        Ok(Some(undo))
+    }
+    
+    /// Re-apply the most recently undone patch to `doc`
+    pub fn redo(&mut self, doc: &mut Value) -> Result<Option<Patch>, PatchError> {
+        let Some(entry_id) = self.redo_stack.last().cloned() else {
+            return Ok(None);
+        };
+        let Some(entry) = self.entries.iter_mut().find(|e| e.id == entry_id) else {
+            return Ok(None);
+        };
+        let redo = entry.patch.clone();
+        apply_patch_atomic(&redo, doc)?;
+        
+        entry.action = AuditAction::Applied;
+        entry.status = AuditStatus::Applied;
+        self.redo_stack.pop();
+        self.undo_stack.push(entry_id);
+        Ok(Some(redo))
+    }
+    
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+    
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+    
+    /// Forget undo/redo history, e.g. when the project is closed
+    pub fn clear_history(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+    
     pub fn record_rejected(&mut self, entry_id: &str) {
         if let Some(entry) = self.entries.iter_mut().find(|e| e.id == entry_id) {
             entry.action = AuditAction::Rejected;
@@ -454,6 +686,10 @@ impl AuditLog {
         }
     }
     
+    pub fn get(&self, entry_id: &str) -> Option<&AuditEntry> {
+        self.entries.iter().find(|e| e.id == entry_id)
+    }
+    
     pub fn get_pending(&self) -> Vec<&AuditEntry> {
         self.entries.iter()
             .filter(|e| matches!(e.status, AuditStatus::Pending))
@@ -510,7 +746,8 @@ fn json_pointer_get<'a>(doc: &'a Value, path: &str) -> Result<Option<&'a Value>,
     Ok(Some(current))
 }
 
-fn json_pointer_set(doc: &mut Value, path: &str, value: Value) -> Result<(), PatchError> {
+/// Set the value at `path`; `insert` shifts array elements up (RFC 6902 add) instead of overwriting
+fn json_pointer_set(doc: &mut Value, path: &str, value: Value, insert: bool) -> Result<(), PatchError> {
     let parts = parse_json_pointer(path)?;
     
     if parts.is_empty() {
@@ -543,7 +780,9 @@ fn json_pointer_set(doc: &mut Value, path: &str, value: Value) -> Result<(), Pat
                 };
                 
                 if is_last {
-                    if idx < arr.len() {
+                    if idx < arr.len() && insert {
+                        arr.insert(idx, value.clone());
+                    } else if idx < arr.len() {
                         arr[idx] = value.clone();
                     } else {
                         arr.push(value.clone());
@@ -612,6 +851,137 @@ fn json_pointer_remove(doc: &mut Value, path: &str) -> Result<(), PatchError> {
 mod tests {
     use super::*;
     
+    #[test]
+    fn test_audit_log_get() {
+        let mut log = AuditLog::new();
+        let patch = Patch::json_patch("Rename state", PatchTarget::FsmGraph, vec![JsonPatchOp::replace("/name", serde_json::json!("IDLE"))]);
+        let id = log.record_proposal("agent", patch);
+        log.record_applied(&id);
+        assert!(matches!(log.get(&id).unwrap().status, AuditStatus::Applied));
+        assert!(log.get("missing").is_none());
+        assert_eq!(unified_diff("fsm.json", "a\nb", "a\nc"), "--- a/fsm.json\n+++ b/fsm.json\n@@ -2,1 +2,1 @@\n-b\n+c\n");
+    }
+    
+    #[test]
+    fn test_undo_redo() {
+        let mut log = AuditLog::new();
+        let original = serde_json::json!({"name": "FSM", "states": [{"id": "idle"}], "initial": "idle"});
+        let patch = Patch::json_patch("Add running state", PatchTarget::FsmGraph, vec![
+            JsonPatchOp::add("/states/-", serde_json::json!({"id": "running"})),
+            JsonPatchOp::replace("/initial", serde_json::json!("running")),
+            JsonPatchOp::remove("/name"),
+        ]);
+        let mut doc = original.clone();
+        apply_patch(&patch, &mut doc).unwrap();
+        let modified = doc.clone();
+        
+        let id = log.record_proposal("agent", patch);
+        assert!(!log.can_undo());
+        log.record_applied_to(&id, &original);
+        assert!(log.can_undo() && !log.can_redo());
+        
+        let undo = log.undo(&mut doc).unwrap().unwrap();
+        assert_eq!(undo.description, "Undo: Add running state");
+        assert_eq!(doc, original);
+        assert!(matches!(log.get(&id).unwrap().status, AuditStatus::RolledBack));
+        assert!(!log.can_undo() && log.can_redo());
+        
+        assert!(log.redo(&mut doc).unwrap().is_some());
+        assert_eq!(doc, modified);
+        assert!(log.undo(&mut doc).unwrap().is_some());
+        
+        // A fresh change drops the redo history
+        let next = log.record_proposal("agent", Patch::json_patch("Rename", PatchTarget::FsmGraph, vec![JsonPatchOp::add("/name", serde_json::json!("Blinky"))]));
+        log.record_applied_to(&next, &original);
+        assert!(!log.can_redo());
+        
+        for _ in 0..MAX_UNDO + 5 {
+            let id = log.record_proposal("agent", Patch::json_patch("Rename", PatchTarget::FsmGraph, vec![JsonPatchOp::replace("/initial", serde_json::json!("x"))]));
+            log.record_applied_to(&id, &original);
+        }
+        assert_eq!(log.undo_stack.len(), MAX_UNDO);
+        log.clear_history();
+        assert!(!log.can_undo() && log.undo(&mut doc).unwrap().is_none());
+    }
+    
+    #[test]
+    fn test_failed_undo_keeps_history() {
+        let mut log = AuditLog::new();
+        let original = serde_json::json!({"states": [{"id": "idle"}], "initial": "idle"});
+        let patch = Patch::json_patch("Add mode", PatchTarget::FsmGraph, vec![
+            JsonPatchOp::add("/mode", serde_json::json!("auto")),
+        ]);
+        let id = log.record_proposal("agent", patch);
+        log.record_applied_to(&id, &original);
+        
+        // The document no longer has the member the inverse removes
+        let mut doc = original.clone();
+        assert!(log.undo(&mut doc).is_err());
+        assert_eq!(doc, original);
+        assert!(log.can_undo() && !log.can_redo());
+        assert!(matches!(log.get(&id).unwrap().status, AuditStatus::Applied));
+        
+        let mut doc = serde_json::json!({"states": [{"id": "idle"}], "initial": "idle", "mode": "auto"});
+        assert!(log.undo(&mut doc).unwrap().is_some());
+        assert_eq!(doc, original);
+        
+        // Redo fails the same way without losing the entry
+        let mut broken = serde_json::json!([]);
+        assert!(log.redo(&mut broken).is_err());
+        assert!(log.can_redo() && !log.can_undo());
+        assert!(matches!(log.get(&id).unwrap().status, AuditStatus::RolledBack));
+    }
+    
+    #[test]
+    fn test_add_past_array_end_inverse() {
+        let original = serde_json::json!({"states": ["idle", "run"]});
+        let op = JsonPatchOp::add("/states/7", serde_json::json!("fault"));
+        let inverse = op.inverse(&original).unwrap();
+        assert!(matches!(&inverse, JsonPatchOp::Remove { path } if path == "/states/2"));
+        
+        let mut doc = original.clone();
+        op.apply(&mut doc).unwrap();
+        inverse.apply(&mut doc).unwrap();
+        assert_eq!(doc, original);
+    }
+    
+    #[test]
+    fn test_remove_middle_element_undo() {
+        let mut log = AuditLog::new();
+        let original = serde_json::json!({"states": ["idle", "run", "fault"]});
+        let patch = Patch::json_patch("Drop run", PatchTarget::FsmGraph, vec![JsonPatchOp::remove("/states/1")]);
+        let mut doc = original.clone();
+        apply_patch(&patch, &mut doc).unwrap();
+        assert_eq!(doc, serde_json::json!({"states": ["idle", "fault"]}));
+        
+        let id = log.record_proposal("agent", patch);
+        log.record_applied_to(&id, &original);
+        assert!(log.undo(&mut doc).unwrap().is_some());
+        assert_eq!(doc, original);
+        
+        // An add in the middle of an array inserts, and its inverse removes that slot
+        let op = JsonPatchOp::add("/states/1", serde_json::json!("boot"));
+        let mut doc = original.clone();
+        op.apply(&mut doc).unwrap();
+        assert_eq!(doc, serde_json::json!({"states": ["idle", "boot", "run", "fault"]}));
+        op.inverse(&original).unwrap().apply(&mut doc).unwrap();
+        assert_eq!(doc, original);
+    }
+    
+    #[test]
+    fn test_text_patch_inverse() {
+        let old = "a\nb\nc\nd\ne";
+        let new = "a\nx\ny\nc\ne";
+        let patch = Patch::text_diff("Edit", PathBuf::from("main.c"), create_text_diff(old, new));
+        let PatchOperations::TextDiff(hunks) = &patch.operations else { unreachable!() };
+        assert_eq!(apply_text_patch(hunks, old).unwrap(), new);
+        
+        let Some(PatchOperations::TextDiff(inverse)) = patch.inverse(&Value::String(old.to_string())) else {
+            panic!("text patch should be invertible");
+        };
+        assert_eq!(apply_text_patch(&inverse, new).unwrap(), old);
+    }
+    
     #[test]
     fn test_json_patch_add() {
         let mut doc = serde_json::json!({"foo": "bar"});
@@ -646,6 +1016,24 @@ mod tests {
         assert_eq!(hunks[0].old_lines, vec!["line2"]);
         assert_eq!(hunks[0].new_lines, vec!["modified"]);
     }
+
+    #[test]
+    fn test_text_diff_large_files() {
+        // Every third line changes, so the split recursion runs all the way down
+        let old: Vec<String> = (0..3_000).map(|i| format!("line {}", i)).collect();
+        let new: Vec<String> = (0..3_000)
+            .map(|i| if i % 3 == 0 { format!("changed {}", i) } else { format!("line {}", i) })
+            .collect();
+        let (old, new) = (old.join("\n"), new.join("\n"));
+        let hunks = create_text_diff(&old, &new);
+        assert_eq!(hunks.len(), 1_000);
+        assert!(hunks.iter().all(|h| h.old_count == 1 && h.new_count == 1));
+        assert_eq!(apply_text_patch(&hunks, &old).unwrap(), new);
+
+        let hunks = create_text_diff("a\nb\nc", "x\na\nc\ny");
+        assert_eq!(hunks.len(), 3);
+        assert_eq!(apply_text_patch(&hunks, "a\nb\nc").unwrap(), "x\na\nc\ny");
+    }
     
     #[test]
     fn test_apply_text_patch() {
@@ -95,6 +95,7 @@ Include all relevant sections."#.to_string()
             message: "Docs Agent processing...".to_string(),
             tool_calls: Vec::new(),
             suggestions: Vec::new(),
+            reasoning: None,
         })
     }
 }
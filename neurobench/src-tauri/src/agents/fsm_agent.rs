@@ -85,6 +85,7 @@ Be helpful, specific, and practical. Focus on embedded systems use cases."#.to_s
             message: "FSM Agent processing...".to_string(),
             tool_calls: Vec::new(),
             suggestions: Vec::new(),
+            reasoning: None,
         })
     }
 }
@@ -92,6 +92,7 @@ Always reference specific datasheet sections when relevant."#.to_string()
             message: "Hardware Agent processing...".to_string(),
             tool_calls: Vec::new(),
             suggestions: Vec::new(),
+            reasoning: None,
         })
     }
 }
@@ -0,0 +1,833 @@
+// Workspace Indexer
+// Symbol index of a project's C/C++ sources so the AI can see existing code
+
+use crate::jobs::{
+    CancelReason, EmitterMessage, InternalErrorCode, JobEmitter, JobKind, JobManager, JobRecord, JobTerminal,
+};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tokio::sync::mpsc::unbounded_channel;
+
+/// Symbols put into the AI chat context per message
+const CONTEXT_SYMBOLS: usize = 8;
+
+/// Indexer errors
+#[derive(Debug, Error)]
+pub enum IndexError {
+    #[error("Workspace index is not open")]
+    NotOpen,
+
+    #[error("Project directory not found: {0}")]
+    NotFound(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+lazy_static::lazy_static! {
+    /// Index database opened at startup
+    static ref INDEX: Mutex<Option<Index>> = Mutex::new(None);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolKind {
+    Function,
+    Global,
+    Type,
+}
+
+impl SymbolKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SymbolKind::Function => "function",
+            SymbolKind::Global => "global",
+            SymbolKind::Type => "type",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "function" => SymbolKind::Function,
+            "global" => SymbolKind::Global,
+            _ => SymbolKind::Type,
+        }
+    }
+}
+
+/// Symbol extracted from one file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Symbol {
+    pub kind: SymbolKind,
+    pub name: String,
+    /// Declaration as written, bodies elided
+    pub signature: String,
+    pub line: usize,
+}
+
+/// Everything extracted from one file
+#[derive(Debug, Clone, Default)]
+pub struct FileSymbols {
+    pub symbols: Vec<Symbol>,
+    pub includes: Vec<String>,
+}
+
+/// Search result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub file: String,
+    pub line: usize,
+    pub kind: SymbolKind,
+    pub name: String,
+    pub signature: String,
+    pub score: f32,
+}
+
+/// Source file finished indexing, payload of `index:file_processed`
+#[derive(Debug, Clone, Serialize)]
+pub struct FileProcessed {
+    pub file: String,
+    pub symbols: usize,
+    pub processed: usize,
+    pub total: usize,
+}
+
+// ==================== Extraction ====================
+
+/// Blank out comments, and braces and semicolons inside literals, keeping newlines so lines still match
+fn strip_comments(code: &str) -> String {
+    let mut out = String::with_capacity(code.len());
+    let mut chars = code.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'/') => {
+                while let Some(&next) = chars.peek() {
+                    if next == '\n' {
+                        break;
+                    }
+                    chars.next();
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        out.push('\n');
+                    }
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+                out.push(' ');
+            }
+            '"' | '\'' => {
+                out.push(c);
+                let mut escaped = false;
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                    if next == c && !escaped {
+                        out.push(c);
+                        break;
+                    }
+                    out.push(if matches!(next, '{' | '}' | ';') { ' ' } else { next });
+                    escaped = next == '\\' && !escaped;
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Last identifier in `text`
+fn last_ident(text: &str) -> Option<&str> {
+    let end = text.rfind(is_ident)? + 1;
+    let start = text[..end].rfind(|c: char| !is_ident(c)).map(|i| i + 1).unwrap_or(0);
+    let ident = &text[start..end];
+    (!ident.starts_with(|c: char| c.is_ascii_digit())).then_some(ident)
+}
+
+/// Classify one top-level statement, function bodies already removed
+fn classify(statement: &str, line: usize) -> Option<Symbol> {
+    let text = statement.split_whitespace().collect::<Vec<_>>().join(" ");
+    let text = text.trim_end_matches(';').trim().to_string();
+    if text.is_empty() || text.starts_with("extern \"C\"") {
+        return None;
+    }
+    let symbol = |kind, name: &str| Some(Symbol { kind, name: name.to_string(), signature: text.clone(), line });
+
+    if text.starts_with("typedef ") {
+        // Function pointer typedefs name themselves inside `(*name)`
+        let name = match text.find("(*") {
+            Some(i) => last_ident(&text[i..text[i..].find(')').map(|j| i + j).unwrap_or(text.len())]),
+            None => last_ident(&text),
+        };
+        return symbol(SymbolKind::Type, name?);
+    }
+    if ["struct ", "enum ", "union "].iter().any(|p| text.starts_with(p)) && text.contains("{}") {
+        let name = last_ident(&text[..text.find("{}").unwrap()])?;
+        return symbol(SymbolKind::Type, name);
+    }
+
+    let assignment = text.find('=');
+    match text.find('(') {
+        Some(paren) if !matches!(assignment, Some(eq) if eq < paren) => {
+            let name = last_ident(&text[..paren])?;
+            if matches!(name, "if" | "while" | "for" | "switch" | "return" | "sizeof") {
+                return None;
+            }
+            symbol(SymbolKind::Function, name)
+        }
+        _ => {
+            let declarator = &text[..assignment.unwrap_or(text.len())];
+            let declarator = &declarator[..declarator.find('[').unwrap_or(declarator.len())];
+            // A lone type (`int;`) or forward declaration (`struct uart;`) has no variable name
+            let words = declarator.split_whitespace().count();
+            let is_tagged = ["struct ", "enum ", "union "].iter().any(|p| text.starts_with(p));
+            if words < 2 || (is_tagged && words == 2) {
+                return None;
+            }
+            symbol(SymbolKind::Global, last_ident(declarator)?)
+        }
+    }
+}
+
+/// Extract functions, globals, types and `#include`s from C/C++ source
+pub fn extract_symbols(code: &str) -> FileSymbols {
+    let code = strip_comments(code);
+    let mut result = FileSymbols::default();
+
+    let mut depth = 0usize;
+    let mut statement = String::new();
+    let mut statement_line = 0usize;
+    // Header before a top-level `{`, and whether its body is a function's
+    let mut header: Option<(String, bool)> = None;
+    // `extern "C" {` blocks don't nest their contents
+    let mut transparent_blocks = 0usize;
+    let mut line = 1usize;
+    let mut at_line_start = true;
+
+    let mut chars = code.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\n' {
+            line += 1;
+            at_line_start = true;
+            if depth == 0 {
+                statement.push(' ');
+            }
+            continue;
+        }
+        if at_line_start && c == '#' && depth == 0 {
+            let mut directive = String::new();
+            while let Some(&next) = chars.peek() {
+                if next == '\n' {
+                    if directive.ends_with('\\') {
+                        directive.pop();
+                        chars.next();
+                        line += 1;
+                        continue;
+                    }
+                    break;
+                }
+                directive.push(next);
+                chars.next();
+            }
+            let directive = directive.trim_start();
+            if let Some(rest) = directive.strip_prefix("include") {
+                let rest = rest.trim();
+                let header = rest.trim_start_matches(['<', '"']);
+                if let Some(end) = header.find(['>', '"']) {
+                    result.includes.push(header[..end].to_string());
+                }
+            }
+            continue;
+        }
+        if !c.is_whitespace() {
+            at_line_start = false;
+        }
+
+        match c {
+            '{' => {
+                if depth == 0 {
+                    let text = statement.trim().to_string();
+                    if text.starts_with("extern \"C\"") || text.starts_with("namespace") {
+                        transparent_blocks += 1;
+                        statement.clear();
+                        continue;
+                    }
+                    let is_function = text.contains('(')
+                        && !text.contains('=')
+                        && !["struct", "enum", "union", "typedef"].iter().any(|p| text.starts_with(p));
+                    header = Some((text, is_function));
+                }
+                depth += 1;
+            }
+            '}' => {
+                if depth == 0 {
+                    transparent_blocks = transparent_blocks.saturating_sub(1);
+                    statement.clear();
+                    continue;
+                }
+                depth -= 1;
+                if depth == 0 {
+                    match header.take() {
+                        Some((text, true)) => {
+                            result.symbols.extend(classify(&text, statement_line));
+                            statement.clear();
+                        }
+                        Some((text, false)) => {
+                            statement = format!("{} {{}}", text);
+                        }
+                        None => {}
+                    }
+                }
+            }
+            ';' if depth == 0 => {
+                result.symbols.extend(classify(&statement, statement_line));
+                statement.clear();
+            }
+            _ if depth == 0 => {
+                if statement.trim().is_empty() && !c.is_whitespace() {
+                    statement.clear();
+                    statement_line = line;
+                }
+                statement.push(c);
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+// ==================== Index Store ====================
+
+/// Split identifiers and words into lowercase search terms
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for word in text.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut current = String::new();
+        let mut prev_lower = false;
+        for c in word.chars() {
+            if c.is_ascii_uppercase() && prev_lower && !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+            current.push(c.to_ascii_lowercase());
+        }
+        if !current.is_empty() {
+            tokens.push(current);
+        }
+    }
+    tokens.retain(|t| t.len() > 1);
+    tokens
+}
+
+/// Symbol index stored in SQLite
+pub struct Index {
+    conn: Connection,
+    /// Tokenized symbols of the last queried root, dropped whenever the index changes
+    cache: Option<QueryCache>,
+}
+
+/// `root` as a prefix that only matches files below it, not sibling directories
+fn root_prefix(root: &str) -> String {
+    if root.ends_with(std::path::MAIN_SEPARATOR) {
+        root.to_string()
+    } else {
+        format!("{}{}", root, std::path::MAIN_SEPARATOR)
+    }
+}
+
+impl Index {
+    /// Open (or create) the index database at `path`
+    pub fn open(path: &Path) -> Result<Self, IndexError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, IndexError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, IndexError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS symbols (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                file TEXT NOT NULL,
+                line INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                name TEXT NOT NULL,
+                signature TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS symbols_file ON symbols(file);
+            CREATE TABLE IF NOT EXISTS includes (
+                file TEXT NOT NULL,
+                header TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS includes_file ON includes(file);",
+        )?;
+        Ok(Self { conn, cache: None })
+    }
+
+    /// Replace everything indexed for `file`
+    pub fn replace_file(&mut self, file: &str, extracted: &FileSymbols) -> Result<(), IndexError> {
+        self.cache = None;
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM symbols WHERE file = ?1", params![file])?;
+        tx.execute("DELETE FROM includes WHERE file = ?1", params![file])?;
+        for symbol in &extracted.symbols {
+            tx.execute(
+                "INSERT INTO symbols (file, line, kind, name, signature) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![file, symbol.line as i64, symbol.kind.as_str(), symbol.name, symbol.signature],
+            )?;
+        }
+        for header in &extracted.includes {
+            tx.execute("INSERT INTO includes (file, header) VALUES (?1, ?2)", params![file, header])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Drop files below `root`, before re-indexing it
+    pub fn clear_root(&mut self, root: &str) -> Result<(), IndexError> {
+        self.cache = None;
+        // A plain prefix compare: LIKE would need escaping and ignores ASCII case
+        let prefix = root_prefix(root);
+        self.conn.execute("DELETE FROM symbols WHERE substr(file, 1, length(?1)) = ?1", params![prefix])?;
+        self.conn.execute("DELETE FROM includes WHERE substr(file, 1, length(?1)) = ?1", params![prefix])?;
+        Ok(())
+    }
+
+    /// Headers `file` includes
+    pub fn includes(&self, file: &str) -> Result<Vec<String>, IndexError> {
+        let mut stmt = self.conn.prepare("SELECT header FROM includes WHERE file = ?1 ORDER BY rowid")?;
+        let headers = stmt.query_map(params![file], |row| row.get(0))?.collect::<Result<Vec<String>, _>>()?;
+        Ok(headers)
+    }
+
+    /// Best `top_k` symbols for `query` across every indexed project, ranked by TF-IDF
+    pub fn query(&mut self, query: &str, top_k: usize) -> Result<Vec<IndexEntry>, IndexError> {
+        self.ranked(query, None, top_k)
+    }
+
+    /// Best `top_k` symbols for `query` among files below `root`
+    pub fn query_in(&mut self, query: &str, root: &str, top_k: usize) -> Result<Vec<IndexEntry>, IndexError> {
+        self.ranked(query, Some(root_prefix(root)), top_k)
+    }
+
+    fn ranked(&mut self, query: &str, prefix: Option<String>, top_k: usize) -> Result<Vec<IndexEntry>, IndexError> {
+        let terms = tokenize(query);
+        if terms.is_empty() || top_k == 0 {
+            return Ok(Vec::new());
+        }
+
+        let cache = match self.cache.take() {
+            Some(cache) if cache.prefix == prefix => cache,
+            _ => QueryCache::load(&self.conn, prefix)?,
+        };
+        let results = cache.rank(&terms, top_k);
+        self.cache = Some(cache);
+        Ok(results)
+    }
+}
+
+/// Symbols below one root, tokenized once for ranking
+///
+/// Each symbol is a document of its name (counted twice), signature and
+/// file name terms.
+struct QueryCache {
+    prefix: Option<String>,
+    entries: Vec<IndexEntry>,
+    documents: Vec<Vec<String>>,
+    document_frequency: HashMap<String, usize>,
+}
+
+impl QueryCache {
+    fn load(conn: &Connection, prefix: Option<String>) -> Result<Self, IndexError> {
+        let mut stmt = conn.prepare(
+            "SELECT file, line, kind, name, signature FROM symbols
+             WHERE ?1 IS NULL OR substr(file, 1, length(?1)) = ?1",
+        )?;
+        let entries = stmt.query_map(params![prefix], |row| {
+            Ok(IndexEntry {
+                file: row.get(0)?,
+                line: row.get::<_, i64>(1)? as usize,
+                kind: SymbolKind::parse(&row.get::<_, String>(2)?),
+                name: row.get(3)?,
+                signature: row.get(4)?,
+                score: 0.0,
+            })
+        })?.collect::<Result<Vec<_>, _>>()?;
+
+        let documents: Vec<Vec<String>> = entries.iter().map(|e| {
+            let stem = Path::new(&e.file).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            let mut doc = tokenize(&e.name);
+            doc.extend(tokenize(&e.name));
+            doc.extend(tokenize(&e.signature));
+            doc.extend(tokenize(&stem));
+            doc
+        }).collect();
+
+        let mut document_frequency: HashMap<String, usize> = HashMap::new();
+        for doc in &documents {
+            let mut seen: Vec<&String> = doc.iter().collect();
+            seen.sort_unstable();
+            seen.dedup();
+            for term in seen {
+                *document_frequency.entry(term.clone()).or_default() += 1;
+            }
+        }
+
+        Ok(Self { prefix, entries, documents, document_frequency })
+    }
+
+    fn rank(&self, terms: &[String], top_k: usize) -> Vec<IndexEntry> {
+        let total = self.documents.len() as f32;
+        let mut scored: Vec<IndexEntry> = self.entries.iter().zip(&self.documents).filter_map(|(entry, doc)| {
+            let score: f32 = terms.iter().map(|term| {
+                let tf = doc.iter().filter(|t| *t == term).count() as f32 / doc.len().max(1) as f32;
+                let df = self.document_frequency.get(term).copied().unwrap_or(0) as f32;
+                tf * (1.0 + total / (1.0 + df)).ln()
+            }).sum();
+            (score > 0.0).then(|| IndexEntry { score, ..entry.clone() })
+        }).collect();
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+        scored.truncate(top_k);
+        scored
+    }
+}
+
+// ==================== Workspace Walk ====================
+
+/// Walks a project and extracts symbols from its sources
+pub struct WorkspaceIndexer {
+    root: PathBuf,
+}
+
+impl WorkspaceIndexer {
+    /// Index below `root`; the path is canonicalized so chat queries can scope to it
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        Self { root: root.canonicalize().unwrap_or(root) }
+    }
+
+    /// C/C++ sources and headers below the root, skipping build output
+    pub fn source_files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        let mut pending = vec![self.root.clone()];
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+            for path in entries.flatten().map(|e| e.path()) {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                if path.is_dir() {
+                    if !matches!(name.as_ref(), "build" | "target" | "node_modules") && !name.starts_with('.') {
+                        pending.push(path);
+                    }
+                } else if path.extension().is_some_and(|ext| matches!(ext.to_str(), Some("c" | "h" | "cpp" | "hpp" | "cc"))) {
+                    files.push(path);
+                }
+            }
+        }
+        files.sort();
+        files
+    }
+
+    /// Extract symbols from one file
+    pub fn scan_file(&self, path: &Path) -> Result<FileSymbols, IndexError> {
+        let bytes = std::fs::read(path)?;
+        Ok(extract_symbols(&String::from_utf8_lossy(&bytes)))
+    }
+
+    /// Index every source into `index`, reporting each file; stops early when `stop` is set
+    pub fn index_into(
+        &self,
+        index: &Mutex<Option<Index>>,
+        stop: impl Fn() -> bool,
+        mut on_file: impl FnMut(FileProcessed),
+    ) -> Result<usize, IndexError> {
+        if !self.root.is_dir() {
+            return Err(IndexError::NotFound(self.root.display().to_string()));
+        }
+        with_store(index, |idx| idx.clear_root(&self.root.to_string_lossy()))?;
+
+        let files = self.source_files();
+        let total = files.len();
+        for (i, path) in files.iter().enumerate() {
+            if stop() {
+                break;
+            }
+            let file = path.to_string_lossy().to_string();
+            // Unreadable files are skipped rather than failing the whole index
+            let extracted = self.scan_file(path).unwrap_or_default();
+            with_store(index, |idx| idx.replace_file(&file, &extracted))?;
+            on_file(FileProcessed { file, symbols: extracted.symbols.len(), processed: i + 1, total });
+        }
+        Ok(total)
+    }
+}
+
+fn with_store<T>(index: &Mutex<Option<Index>>, f: impl FnOnce(&mut Index) -> Result<T, IndexError>) -> Result<T, IndexError> {
+    f(index.lock().unwrap().as_mut().ok_or(IndexError::NotOpen)?)
+}
+
+// ==================== Shared Index ====================
+
+pub fn default_index_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("neurobench")
+        .join("index.db")
+}
+
+/// Open the shared index database
+pub fn open_index() {
+    let path = default_index_path();
+    match Index::open(&path) {
+        Ok(index) => *INDEX.lock().unwrap() = Some(index),
+        Err(e) => log::warn!("Failed to open workspace index {}: {}", path.display(), e),
+    }
+}
+
+/// Search the shared index
+pub fn query_index(query: &str, top_k: usize) -> Result<Vec<IndexEntry>, IndexError> {
+    with_store(&INDEX, |idx| idx.query(query, top_k))
+}
+
+/// Indexed symbols of the active project relevant to a chat message, formatted for the AI context
+///
+/// Blocks on the shared index, so async callers run it with `spawn_blocking`.
+pub fn index_context(message: &str) -> Option<String> {
+    let project = crate::commands::project::workspace::active_project_dir()?;
+    let root = project.canonicalize().unwrap_or(project);
+    let entries = with_store(&INDEX, |idx| idx.query_in(message, &root.to_string_lossy(), CONTEXT_SYMBOLS)).ok()?;
+    if entries.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = entries.iter()
+        .map(|e| format!("- {} ({}:{})", e.signature, e.file, e.line))
+        .collect();
+    Some(format!("Relevant symbols in the workspace:\n{}", lines.join("\n")))
+}
+
+// ==================== Index Job Runner ====================
+
+/// Index a project as a job, each file is reported as an `index:file_processed` event
+pub async fn run_index_job(
+    job_manager: Arc<JobManager>,
+    project_path: PathBuf,
+    emit_event: impl Fn(String, serde_json::Value) + Send + Sync + 'static,
+) -> Result<String, String> {
+    if !project_path.is_dir() {
+        return Err(IndexError::NotFound(project_path.display().to_string()).to_string());
+    }
+
+    let (record, _tx) = job_manager.create_job(JobKind::Index);
+    let job_id = record.id.clone();
+
+    tokio::spawn(async move {
+        run_index_worker(record, project_path, job_manager, emit_event).await;
+    });
+
+    Ok(job_id)
+}
+
+async fn run_index_worker(
+    record: Arc<JobRecord>,
+    project_path: PathBuf,
+    job_manager: Arc<JobManager>,
+    emit_event: impl Fn(String, serde_json::Value) + Send + Sync,
+) {
+    let mut emitter = JobEmitter::new(&record);
+    let start = std::time::Instant::now();
+    let scheduler = job_manager.scheduler();
+
+    // Background priority: wait behind other index jobs
+    if scheduler.acquire(&record.id, JobKind::Index, &record.cancel_token).await.is_none() {
+        let terminal = JobTerminal::Cancelled { reason: CancelReason::UserRequest };
+        if let Some((event_name, payload)) = emitter.process(EmitterMessage::Terminal { terminal }).await {
+            emit_event(event_name, payload);
+        }
+        job_manager.finish_job(&record.id).await;
+        return;
+    }
+
+    let (tx, mut rx) = unbounded_channel();
+    let cancel = record.cancel_token.clone();
+    let worker = tokio::task::spawn_blocking(move || {
+        WorkspaceIndexer::new(project_path).index_into(&INDEX, || cancel.is_cancelled(), |file| {
+            let _ = tx.send(file);
+        })
+    });
+
+    while let Some(file) = rx.recv().await {
+        let percent = file.processed as f32 * 100.0 / file.total.max(1) as f32;
+        let message = Some(format!("{}/{} files", file.processed, file.total));
+        if let Some((event_name, payload)) = emitter.process(EmitterMessage::Custom {
+            event_suffix: "file_processed".to_string(),
+            payload: serde_json::json!({
+                "type": "file_processed",
+                "file": file.file,
+                "symbols": file.symbols,
+                "processed": file.processed,
+                "total": file.total,
+            }),
+        }).await {
+            emit_event(event_name, payload);
+        }
+        if let Some((event_name, payload)) = emitter.process(EmitterMessage::Progress {
+            phase: "indexing".to_string(),
+            percent,
+            message,
+        }).await {
+            emit_event(event_name, payload);
+        }
+    }
+    scheduler.release(&record.id);
+
+    let terminal = match worker.await {
+        _ if record.is_cancelled() => JobTerminal::Cancelled { reason: CancelReason::UserRequest },
+        Ok(Ok(_)) => JobTerminal::Completed {
+            success: true,
+            exit_code: Some(0),
+            duration_ms: start.elapsed().as_millis() as u64,
+        },
+        Ok(Err(e)) => JobTerminal::InternalError {
+            error_code: InternalErrorCode::IoError,
+            message: e.to_string(),
+            retryable: matches!(e, IndexError::NotOpen),
+        },
+        Err(e) => JobTerminal::InternalError {
+            error_code: InternalErrorCode::Unknown,
+            message: e.to_string(),
+            retryable: false,
+        },
+    };
+
+    if let Some((event_name, payload)) = emitter.process(EmitterMessage::Terminal { terminal }).await {
+        emit_event(event_name, payload);
+    }
+    job_manager.finish_job(&record.id).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UART_C: &str = r#"
+#include "uart.h"
+#include <stdint.h>
+
+/* Ring buffer for received bytes */
+static uint8_t rx_buffer[256];
+volatile uint32_t uart_error_count = 0;
+
+typedef struct {
+    uint8_t *data;
+    uint16_t head;
+} RingBuffer;
+
+typedef void (*uart_callback_t)(uint8_t byte);
+
+enum UartState { UART_IDLE, UART_BUSY };
+
+int uart_send(const uint8_t *data, uint16_t len);
+
+void uart_init(uint32_t baud)
+{
+    if (baud == 0) { return; }
+    // configure "baud; rate"
+}
+
+static void USART2_IRQHandler(void) {
+    uart_error_count++;
+}
+"#;
+
+    #[test]
+    fn test_extract_symbols() {
+        let extracted = extract_symbols(UART_C);
+        assert_eq!(extracted.includes, vec!["uart.h", "stdint.h"]);
+
+        let names: Vec<(SymbolKind, &str, usize)> = extracted.symbols.iter().map(|s| (s.kind, s.name.as_str(), s.line)).collect();
+        assert_eq!(names, vec![
+            (SymbolKind::Global, "rx_buffer", 6),
+            (SymbolKind::Global, "uart_error_count", 7),
+            (SymbolKind::Type, "RingBuffer", 9),
+            (SymbolKind::Type, "uart_callback_t", 14),
+            (SymbolKind::Type, "UartState", 16),
+            (SymbolKind::Function, "uart_send", 18),
+            (SymbolKind::Function, "uart_init", 20),
+            (SymbolKind::Function, "USART2_IRQHandler", 26),
+        ]);
+        assert_eq!(extracted.symbols[6].signature, "void uart_init(uint32_t baud)");
+    }
+
+    #[test]
+    fn test_index_and_query() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::create_dir_all(dir.path().join("build")).unwrap();
+        std::fs::write(dir.path().join("src/uart.c"), UART_C).unwrap();
+        std::fs::write(dir.path().join("src/gpio.h"), "void gpio_toggle(uint8_t pin);\nextern uint32_t gpio_mask;\n").unwrap();
+        std::fs::write(dir.path().join("build/gen.c"), "void generated(void);").unwrap();
+
+        let store = Mutex::new(Some(Index::open_in_memory().unwrap()));
+        let indexer = WorkspaceIndexer::new(dir.path());
+        let mut processed = Vec::new();
+        let total = indexer.index_into(&store, || false, |file| processed.push(file)).unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(processed.last().unwrap().processed, 2);
+
+        let mut guard = store.lock().unwrap();
+        let index = guard.as_mut().unwrap();
+        let results = index.query("toggle a gpio pin", 3).unwrap();
+        assert_eq!(results[0].name, "gpio_toggle");
+        let results = index.query("uart init baud", 1).unwrap();
+        assert_eq!(results[0].name, "uart_init");
+        assert_eq!(results[0].line, 20);
+        assert!(index.query("watchdog", 5).unwrap().is_empty());
+
+        let root = dir.path().canonicalize().unwrap();
+        let uart = root.join("src/uart.c").to_string_lossy().to_string();
+        assert_eq!(index.includes(&uart).unwrap(), vec!["uart.h", "stdint.h"]);
+    }
+
+    #[test]
+    fn test_query_and_clear_are_scoped_to_root() {
+        let mut index = Index::open_in_memory().unwrap();
+        let sep = std::path::MAIN_SEPARATOR;
+        let app = format!("{sep}work{sep}app");
+        let sibling = format!("{sep}work{sep}app_2");
+        let gpio = extract_symbols("void gpio_toggle(uint8_t pin);");
+        index.replace_file(&format!("{app}{sep}gpio.c"), &gpio).unwrap();
+        index.replace_file(&format!("{sibling}{sep}gpio.c"), &gpio).unwrap();
+
+        assert_eq!(index.query("gpio toggle", 5).unwrap().len(), 2);
+        let scoped = index.query_in("gpio toggle", &app, 5).unwrap();
+        assert_eq!(scoped.len(), 1);
+        assert!(scoped[0].file.starts_with(&format!("{app}{sep}")));
+
+        // Clearing app leaves app_2 alone
+        index.clear_root(&app).unwrap();
+        assert!(index.query_in("gpio toggle", &app, 5).unwrap().is_empty());
+        assert_eq!(index.query_in("gpio toggle", &sibling, 5).unwrap().len(), 1);
+    }
+}
@@ -10,8 +10,11 @@ pub mod code_agent;
 pub mod debug_agent;
 pub mod hardware_agent;
 pub mod docs_agent;
+pub mod test_agent;
 pub mod typed_tools;
 pub mod diff_engine;
+pub mod indexer;
+pub mod reasoning;
 
 #[cfg(test)]
 mod tests;
@@ -22,3 +25,4 @@ pub use orchestrator::*;
 pub use tools::*;
 pub use typed_tools::{ToolDef, ToolRegistry, ToolContext, ToolPermission, ToolCategory, create_default_registry};
 pub use diff_engine::{Patch, PatchTarget, PatchOperations, JsonPatchOp, DiffHunk, AuditLog};
+pub use reasoning::{ReasoningTrace, ThoughtStep};
@@ -1,17 +1,56 @@
 // Agent Orchestrator
 // Routes requests to agents and manages execution
 
-use super::{AgentContext, AgentInfo, AgentResponse, AgentRegistry};
+use super::{AgentContext, AgentInfo, AgentResponse, AgentRegistry, ReasoningTrace, ToolRegistry};
 use crate::ai::AIService;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// Name of the tool agents use to hand work to another agent
+pub const DELEGATE_TOOL: &str = "delegate_to_agent";
+
+/// Longest chain of agents one request may pass through
+pub const MAX_DELEGATION_DEPTH: usize = 3;
+
+/// One hop of a delegation, emitted as `agent:delegated`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegationEvent {
+    pub from: String,
+    pub to: String,
+    pub message_preview: String,
+    pub depth: usize,
+}
+
+/// Target and message of a `delegate_to_agent` tool call
+pub fn delegation_request(call: &super::ToolCall) -> Option<(String, String)> {
+    if call.tool != DELEGATE_TOOL {
+        return None;
+    }
+    let to = call.params.get("to_agent")?.as_str()?;
+    let message = call.params.get("message")?.as_str()?;
+    Some((to.to_string(), message.to_string()))
+}
+
+fn preview(message: &str) -> String {
+    const LIMIT: usize = 80;
+    match message.char_indices().nth(LIMIT) {
+        Some((end, _)) => format!("{}…", &message[..end]),
+        None => message.to_string(),
+    }
+}
+
 /// Orchestrator manages agent routing and execution
 pub struct Orchestrator {
     registry: AgentRegistry,
+    /// Schemas the agents' tool calls are checked against
+    tools: ToolRegistry,
     context: Arc<RwLock<AgentContext>>,
     ai_service: AIService,
     active_agent: Option<String>,
+    /// Latest reasoning trace of each agent, attached to the patches it proposes
+    reasoning: RwLock<HashMap<String, ReasoningTrace>>,
 }
 
 impl Orchestrator {
@@ -24,12 +63,15 @@ impl Orchestrator {
         registry.register(Box::new(super::debug_agent::DebugAgent::new()));
         registry.register(Box::new(super::hardware_agent::HardwareAgent::new()));
         registry.register(Box::new(super::docs_agent::DocsAgent::new()));
+        registry.register(Box::new(super::test_agent::TestAgent::new()));
         
         Self {
             registry,
+            tools: super::create_default_registry(),
             context: Arc::new(RwLock::new(AgentContext::default())),
             ai_service: AIService::new(),
             active_agent: Some("fsm".to_string()),
+            reasoning: RwLock::new(HashMap::new()),
         }
     }
     
@@ -55,44 +97,152 @@ impl Orchestrator {
             .map(|a| a.info())
     }
     
+    /// Reasoning behind `agent_id`'s latest answer, if the model showed any
+    pub async fn last_reasoning(&self, agent_id: &str) -> Option<ReasoningTrace> {
+        self.reasoning.read().await.get(agent_id).cloned()
+    }
+    
     /// Process a message with the active agent
     pub async fn process(&self, message: &str) -> Result<AgentResponse, String> {
+        self.process_with_history(message, None).await
+    }
+    
+    /// Process a message with earlier conversation from a chat session
+    pub async fn process_with_history(&self, message: &str, history: Option<&str>) -> Result<AgentResponse, String> {
         let agent_id = self.active_agent.as_ref()
             .ok_or_else(|| "No active agent".to_string())?;
+        self.run_agent(agent_id, message, message, history).await
+    }
+    
+    /// Let one agent hand a request to another
+    pub async fn delegate(&self, message: &str, from_agent: &str, to_agent: &str) -> Result<AgentResponse, String> {
+        self.delegate_with_events(message, from_agent, to_agent, |_| {}).await
+    }
+    
+    /// Delegate, following further delegations the target makes up to `MAX_DELEGATION_DEPTH`
+    pub async fn delegate_with_events(
+        &self,
+        message: &str,
+        from_agent: &str,
+        to_agent: &str,
+        on_delegate: impl Fn(DelegationEvent),
+    ) -> Result<AgentResponse, String> {
+        let mut chain = vec![from_agent.to_string()];
+        let (mut from, mut to, mut message) = (from_agent.to_string(), to_agent.to_string(), message.to_string());
         
+        loop {
+            if chain.len() > MAX_DELEGATION_DEPTH {
+                return Err(format!("Delegation depth limit ({}) reached: {}", MAX_DELEGATION_DEPTH, chain.join(" -> ")));
+            }
+            if chain.contains(&to) {
+                return Err(format!("Delegation loop: {} -> {}", chain.join(" -> "), to));
+            }
+            if self.registry.get(&to).is_none() {
+                return Err(format!("Agent '{}' not found", to));
+            }
+            
+            on_delegate(DelegationEvent {
+                from: from.clone(),
+                to: to.clone(),
+                message_preview: preview(&message),
+                depth: chain.len(),
+            });
+            chain.push(to.clone());
+            
+            let request = format!("Request delegated from the {} agent:\n{}", from, message);
+            let response = self.run_agent(&to, &request, &message, None).await?;
+            let next = response.tool_calls.iter().find_map(delegation_request);
+            match next {
+                Some((next_to, next_message)) => {
+                    from = std::mem::replace(&mut to, next_to);
+                    message = next_message;
+                }
+                None => return Ok(response),
+            }
+        }
+    }
+    
+    /// Run one agent on a request, recording the exchange in the shared context
+    async fn run_agent(&self, agent_id: &str, request: &str, user_message: &str, history: Option<&str>) -> Result<AgentResponse, String> {
         let agent = self.registry.get(agent_id)
             .ok_or_else(|| format!("Agent '{}' not found", agent_id))?;
         
         // Get context
         let mut context = self.context.write().await;
-        context.add_user_message(message);
+        context.add_user_message(user_message);
         
         // Build prompt with system prompt and context
         let system_prompt = agent.system_prompt();
-        let context_str = context.to_prompt_context();
+        let context_str = match history {
+            Some(history) => format!("{}\n\n{}", context.to_prompt_context(), history),
+            None => context.to_prompt_context(),
+        };
         
+        let others: Vec<String> = self.registry.list().into_iter()
+            .map(|a| a.id)
+            .filter(|id| id != agent_id)
+            .collect();
         let full_prompt = format!(
-            "{}\n\n## User Request:\n{}",
+            "{}\n\n## Delegation:\nTo hand a sub-task to another agent ({}), use [TOOL:{}:{{\"to_agent\":\"code\",\"message\":\"...\"}}]\n\n## User Request:\n{}",
             system_prompt,
-            message
+            others.join(", "),
+            DELEGATE_TOOL,
+            request
         );
         
         // Call AI service with context
         let response = self.ai_service.chat(&full_prompt, Some(&context_str)).await?;
         
         // Parse response for tool calls
-        let agent_response = self.parse_response(&response);
+        let mut agent_response = self.parse_response(&response);
+        
+        // Give the model one chance to fix tool arguments that fail their schema
+        let issues = self.tool_call_issues(&agent_response.tool_calls);
+        if !issues.is_empty() {
+            let retry_prompt = format!(
+                "{}\n\n## Invalid tool calls:\n{}\nRepeat your answer with corrected tool arguments.",
+                full_prompt,
+                issues.join("\n")
+            );
+            let response = self.ai_service.chat(&retry_prompt, Some(&context_str)).await?;
+            agent_response = self.parse_response(&response);
+            agent_response.tool_calls.retain(|call| self.tool_call_issues(std::slice::from_ref(call)).is_empty());
+        }
         
         // Add to conversation history
         context.add_assistant_message(&agent_response.message);
         
+        // Keep only the reasoning behind the latest answer
+        let mut reasoning = self.reasoning.write().await;
+        match &agent_response.reasoning {
+            Some(trace) => reasoning.insert(agent_id.to_string(), trace.clone()),
+            None => reasoning.remove(agent_id),
+        };
+        
         Ok(agent_response)
     }
     
-    /// Parse LLM response for tool calls and suggestions
+    /// Schema problems in calls to registered tools, one `- tool: message` line each
+    ///
+    /// Calls to tools outside the registry are left for the agent to handle.
+    fn tool_call_issues(&self, calls: &[super::ToolCall]) -> Vec<String> {
+        calls.iter()
+            .filter(|call| self.tools.get(&call.tool).is_some())
+            .flat_map(|call| {
+                self.tools.validate_input(&call.tool, &call.params).into_iter()
+                    .map(move |issue| format!("- {}: {}", call.tool, issue.message))
+            })
+            .collect()
+    }
+    
+    /// Parse LLM response for reasoning, tool calls and suggestions
     fn parse_response(&self, response: &str) -> AgentResponse {
         let mut tool_calls = Vec::new();
         let mut suggestions = Vec::new();
+        
+        // Tool calls the model only considered while thinking must not run
+        let (answer, reasoning) = super::reasoning::extract_reasoning(response);
+        let response = answer.as_str();
         let mut message = response.to_string();
         
         // Look for tool call patterns: [TOOL:name:params]
@@ -128,6 +278,7 @@ impl Orchestrator {
             message: message.trim().to_string(),
             tool_calls,
             suggestions,
+            reasoning,
         }
     }
     
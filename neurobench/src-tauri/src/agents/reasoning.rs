@@ -0,0 +1,157 @@
+// Reasoning Traces
+// Captures the model's reasoning so patch proposals can be explained later
+
+use serde::{Deserialize, Serialize};
+
+/// One step of a model's reasoning
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThoughtStep {
+    pub thought: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observation: Option<String>,
+}
+
+impl ThoughtStep {
+    pub fn thought(text: impl Into<String>) -> Self {
+        Self { thought: text.into(), action: None, observation: None }
+    }
+}
+
+/// Reasoning behind one agent answer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReasoningTrace {
+    pub steps: Vec<ThoughtStep>,
+    pub final_answer: String,
+    /// 0.0-1.0 as stated by the model, 0.0 when it gave none
+    pub confidence: f32,
+}
+
+/// Split a model response into the visible answer and its reasoning trace
+///
+/// Understands `<think>...</think>` blocks (DeepSeek-R1, QwQ) and a JSON answer of the
+/// form `{"reasoning": [...], "final_answer": "...", "confidence": 0.8}`.
+pub fn extract_reasoning(response: &str) -> (String, Option<ReasoningTrace>) {
+    if let Some((thinking, answer)) = split_think_tags(response) {
+        let confidence = stated_confidence(&thinking).or_else(|| stated_confidence(&answer)).unwrap_or(0.0);
+        let trace = ReasoningTrace {
+            steps: parse_steps(&thinking),
+            final_answer: answer.clone(),
+            confidence,
+        };
+        return (answer, Some(trace));
+    }
+
+    match parse_json_trace(response) {
+        Some(trace) => (trace.final_answer.clone(), Some(trace)),
+        None => (response.to_string(), None),
+    }
+}
+
+/// Text inside the think tags and the answer around them
+///
+/// R1 distills often drop the opening tag, so a lone `</think>` ends a block
+/// that started at the beginning of the response.
+fn split_think_tags(response: &str) -> Option<(String, String)> {
+    if !response.contains("</think>") {
+        return None;
+    }
+
+    let mut thinking = Vec::new();
+    let mut answer = String::new();
+    let mut rest = response;
+    while let Some(end) = rest.find("</think>") {
+        let (before, inner) = match rest[..end].find("<think>") {
+            Some(start) => (&rest[..start], &rest[start + "<think>".len()..end]),
+            None if thinking.is_empty() && answer.is_empty() => ("", &rest[..end]),
+            None => (&rest[..end], ""),
+        };
+        answer.push_str(before);
+        thinking.push(inner.trim());
+        rest = &rest[end + "</think>".len()..];
+    }
+    answer.push_str(rest);
+
+    Some((thinking.join("\n\n"), answer.trim().to_string()))
+}
+
+/// ReAct-style `Thought:`/`Action:`/`Observation:` lines, or one thought per paragraph
+fn parse_steps(thinking: &str) -> Vec<ThoughtStep> {
+    let mut steps: Vec<ThoughtStep> = Vec::new();
+    let mut react = false;
+
+    for line in thinking.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(text) = strip_label(line, "thought") {
+            react = true;
+            steps.push(ThoughtStep::thought(text));
+        } else if let Some(text) = strip_label(line, "action") {
+            react = true;
+            match steps.last_mut() {
+                Some(step) if step.action.is_none() => step.action = Some(text.to_string()),
+                _ => steps.push(ThoughtStep { action: Some(text.to_string()), ..ThoughtStep::thought("") }),
+            }
+        } else if let Some(text) = strip_label(line, "observation") {
+            react = true;
+            match steps.last_mut() {
+                Some(step) if step.observation.is_none() => step.observation = Some(text.to_string()),
+                _ => steps.push(ThoughtStep { observation: Some(text.to_string()), ..ThoughtStep::thought("") }),
+            }
+        } else if strip_label(line, "confidence").is_some() {
+            continue;
+        } else if let Some(step) = steps.last_mut().filter(|_| react) {
+            // Continuation of the previous labelled line
+            let field = step.observation.as_mut().or(step.action.as_mut()).unwrap_or(&mut step.thought);
+            field.push('\n');
+            field.push_str(line);
+        }
+    }
+
+    if react {
+        return steps;
+    }
+    thinking
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(ThoughtStep::thought)
+        .collect()
+}
+
+fn strip_label<'a>(line: &'a str, label: &str) -> Option<&'a str> {
+    let head = line.get(..label.len())?;
+    let rest = line[label.len()..].strip_prefix(':')?;
+    head.eq_ignore_ascii_case(label).then(|| rest.trim())
+}
+
+/// `Confidence: 0.8` or `Confidence: 80%`
+fn stated_confidence(text: &str) -> Option<f32> {
+    text.lines().find_map(|line| {
+        let value = strip_label(line.trim(), "confidence")?;
+        let (number, percent) = match value.strip_suffix('%') {
+            Some(number) => (number, true),
+            None => (value, false),
+        };
+        let number: f32 = number.trim().parse().ok()?;
+        Some(if percent || number > 1.0 { number / 100.0 } else { number }.clamp(0.0, 1.0))
+    })
+}
+
+fn parse_json_trace(response: &str) -> Option<ReasoningTrace> {
+    let body = response.trim();
+    let body = body
+        .strip_prefix("```json")
+        .or_else(|| body.strip_prefix("```"))
+        .and_then(|b| b.strip_suffix("```"))
+        .unwrap_or(body);
+    let value: serde_json::Value = serde_json::from_str(body.trim()).ok()?;
+
+    let steps = value.get("reasoning")?.as_array()?.iter().filter_map(|step| match step {
+        serde_json::Value::String(text) => Some(ThoughtStep::thought(text.as_str())),
+        other => serde_json::from_value(other.clone()).ok(),
+    }).collect();
+    let final_answer = value.get("final_answer").or_else(|| value.get("answer"))?.as_str()?.to_string();
+    let confidence = value.get("confidence").and_then(|c| c.as_f64()).unwrap_or(0.0) as f32;
+
+    Some(ReasoningTrace { steps, final_answer, confidence: confidence.clamp(0.0, 1.0) })
+}
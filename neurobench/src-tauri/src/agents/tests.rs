@@ -425,4 +425,75 @@ mod tests {
         assert_eq!(result.message, "Something failed");
         assert!(result.data.is_none());
     }
+
+    // ==================== Delegation Tests ====================
+
+    #[tokio::test]
+    async fn test_delegation_guards() {
+        let orchestrator = crate::agents::Orchestrator::new();
+        let err = orchestrator.delegate("generate HAL code", "fsm", "fsm").await.unwrap_err();
+        assert!(err.contains("loop"), "{}", err);
+        let err = orchestrator.delegate("generate HAL code", "fsm", "nobody").await.unwrap_err();
+        assert!(err.contains("not found"), "{}", err);
+    }
+
+    #[test]
+    fn test_delegate_to_agent_tool() {
+        let registry = crate::agents::create_default_registry();
+        let ctx = crate::agents::ToolContext::new("fsm");
+        let output = registry.execute(
+            crate::agents::DELEGATE_TOOL,
+            json!({ "to_agent": "code", "message": "Write the IDLE entry action" }),
+            &ctx,
+        ).unwrap();
+        assert_eq!(output["to_agent"], "code");
+
+        let call = crate::agents::ToolCall { tool: crate::agents::DELEGATE_TOOL.to_string(), params: output };
+        assert_eq!(
+            crate::agents::delegation_request(&call),
+            Some(("code".to_string(), "Write the IDLE entry action".to_string()))
+        );
+        assert!(registry.execute(crate::agents::DELEGATE_TOOL, json!({ "to_agent": "fsm", "message": "x" }), &ctx).is_err());
+    }
+
+    // ==================== Reasoning Trace Tests ====================
+
+    #[test]
+    fn test_reasoning_from_think_tags() {
+        use crate::agents::reasoning::extract_reasoning;
+
+        let response = "<think>\nThought: The timer ISR must clear UIF\nAction: read stm32f4xx_it.c\nObservation: UIF is never cleared\n\nConfidence: 85%\n</think>\nClear TIM2->SR in the handler.";
+        let (answer, trace) = extract_reasoning(response);
+        let trace = trace.unwrap();
+        assert_eq!(answer, "Clear TIM2->SR in the handler.");
+        assert_eq!(trace.final_answer, answer);
+        assert_eq!(trace.steps.len(), 1);
+        assert_eq!(trace.steps[0].action.as_deref(), Some("read stm32f4xx_it.c"));
+        assert_eq!(trace.steps[0].observation.as_deref(), Some("UIF is never cleared"));
+        assert!((trace.confidence - 0.85).abs() < 1e-6);
+
+        // Missing opening tag, plain paragraphs
+        let (answer, trace) = extract_reasoning("First idea\n\nSecond idea</think>Done");
+        assert_eq!(answer, "Done");
+        assert_eq!(trace.unwrap().steps.len(), 2);
+
+        let (answer, trace) = extract_reasoning("No reasoning here");
+        assert_eq!(answer, "No reasoning here");
+        assert!(trace.is_none());
+    }
+
+    #[test]
+    fn test_reasoning_from_json() {
+        use crate::agents::reasoning::{extract_reasoning, ThoughtStep};
+
+        let response = r#"```json
+{"reasoning": ["Debounce needs 20 ms", {"thought": "Use SysTick", "action": "add_node"}], "final_answer": "Added a DEBOUNCE state", "confidence": 0.7}
+```"#;
+        let (answer, trace) = extract_reasoning(response);
+        let trace = trace.unwrap();
+        assert_eq!(answer, "Added a DEBOUNCE state");
+        assert_eq!(trace.steps[0], ThoughtStep::thought("Debounce needs 20 ms"));
+        assert_eq!(trace.steps[1].action.as_deref(), Some("add_node"));
+        assert!((trace.confidence - 0.7).abs() < 1e-6);
+    }
 }
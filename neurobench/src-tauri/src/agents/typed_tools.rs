@@ -20,6 +20,18 @@ pub struct ToolError {
     pub code: String,
     pub message: String,
     pub recoverable: bool,
+    /// Input fields that failed schema validation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub issues: Vec<ValidationIssue>,
+}
+
+/// One input field that does not match a tool's schema
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    /// Path into the input, e.g. `params.pins[2].mode`
+    pub field: String,
+    /// Full sentence including the field, e.g. `params.baud_rate must be between 300 and 4000000`
+    pub message: String,
 }
 
 impl ToolError {
@@ -28,6 +40,16 @@ impl ToolError {
             code: "VALIDATION_ERROR".to_string(),
             message: msg.into(),
             recoverable: true,
+            issues: Vec::new(),
+        }
+    }
+    
+    /// Input rejected by the tool's schema; recoverable since the caller can fix its arguments
+    pub fn invalid_input(issues: Vec<ValidationIssue>) -> Self {
+        Self {
+            message: issues.iter().map(|i| i.message.as_str()).collect::<Vec<_>>().join("; "),
+            issues,
+            ..Self::validation("")
         }
     }
     
@@ -36,6 +58,7 @@ impl ToolError {
             code: "EXECUTION_ERROR".to_string(),
             message: msg.into(),
             recoverable: false,
+            issues: Vec::new(),
         }
     }
     
@@ -44,6 +67,7 @@ impl ToolError {
             code: "PERMISSION_DENIED".to_string(),
             message: msg.into(),
             recoverable: false,
+            issues: Vec::new(),
         }
     }
 }
@@ -71,6 +95,10 @@ pub struct JsonSchema {
     pub items: Option<Box<JsonSchema>>,
     #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
     pub enum_values: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<f64>,
 }
 
 impl JsonSchema {
@@ -82,6 +110,8 @@ impl JsonSchema {
             required: None,
             items: None,
             enum_values: None,
+            minimum: None,
+            maximum: None,
         }
     }
     
@@ -92,6 +122,13 @@ impl JsonSchema {
         }
     }
     
+    pub fn integer() -> Self {
+        Self {
+            schema_type: "integer".to_string(),
+            ..Self::string()
+        }
+    }
+    
     pub fn boolean() -> Self {
         Self {
             schema_type: "boolean".to_string(),
@@ -121,6 +158,27 @@ impl JsonSchema {
         self
     }
     
+    pub fn with_range(mut self, minimum: f64, maximum: f64) -> Self {
+        self.minimum = Some(minimum);
+        self.maximum = Some(maximum);
+        self
+    }
+    
+    pub fn with_enum(mut self, values: Vec<Value>) -> Self {
+        self.enum_values = Some(values);
+        self
+    }
+    
+    /// Sub-schema at a JSON pointer into the instance (`/pins/0/mode`)
+    fn at_pointer(&self, pointer: &str) -> Option<&JsonSchema> {
+        pointer.split('/').skip(1).try_fold(self, |schema, segment| {
+            match &schema.items {
+                Some(items) if segment.parse::<usize>().is_ok() => Some(items.as_ref()),
+                _ => schema.properties.as_ref()?.get(&unescape_pointer(segment)),
+            }
+        })
+    }
+    
     pub fn with_property(mut self, name: impl Into<String>, schema: JsonSchema, required: bool) -> Self {
         let name = name.into();
         if let Some(ref mut props) = self.properties {
@@ -294,13 +352,41 @@ impl ToolRegistry {
             .collect()
     }
     
-    /// Execute a tool by name
+    /// Execute a tool by name, after checking the input against its schema
     pub fn execute(&self, name: &str, input: Value, ctx: &ToolContext) -> ToolResult {
         let tool = self.get(name)
             .ok_or_else(|| ToolError::validation(format!("Unknown tool: {}", name)))?;
+        let issues = self.validate_input(name, &input);
+        if !issues.is_empty() {
+            return Err(ToolError::invalid_input(issues));
+        }
         tool.execute(input, ctx)
     }
     
+    /// Check `input` against the tool's input schema without running it
+    ///
+    /// Field paths start at `params`, matching how agents write tool calls.
+    pub fn validate_input(&self, tool_name: &str, input: &Value) -> Vec<ValidationIssue> {
+        let Some(tool) = self.get(tool_name) else {
+            return vec![ValidationIssue {
+                field: "tool".to_string(),
+                message: format!("Unknown tool: {}", tool_name),
+            }];
+        };
+        let schema = serde_json::to_value(&tool.input_schema).unwrap_or(Value::Null);
+        let validator = match jsonschema::validator_for(&schema) {
+            Ok(validator) => validator,
+            Err(e) => return vec![ValidationIssue {
+                field: "params".to_string(),
+                message: format!("Tool '{}' has an invalid input schema: {}", tool_name, e),
+            }],
+        };
+        
+        validator.iter_errors(input)
+            .map(|error| describe_violation(&tool.input_schema, &error))
+            .collect()
+    }
+    
     /// Get schemas for all tools (for AI function calling)
     pub fn get_schemas(&self) -> Vec<Value> {
         self.tools.values().map(|t| {
@@ -313,6 +399,58 @@ impl ToolRegistry {
     }
 }
 
+/// `params.pins[0].mode` from the JSON pointer `/pins/0/mode`
+fn field_path(pointer: &str) -> String {
+    let mut path = "params".to_string();
+    for segment in pointer.split('/').skip(1) {
+        if segment.parse::<usize>().is_ok() {
+            path.push_str(&format!("[{}]", segment));
+        } else {
+            path.push('.');
+            path.push_str(&unescape_pointer(segment));
+        }
+    }
+    path
+}
+
+fn unescape_pointer(segment: &str) -> String {
+    segment.replace("~1", "/").replace("~0", "~")
+}
+
+/// Readable sentence for one schema violation
+fn describe_violation(schema: &JsonSchema, error: &jsonschema::ValidationError) -> ValidationIssue {
+    use jsonschema::error::ValidationErrorKind as Kind;
+    
+    let pointer = error.instance_path.as_str();
+    let mut field = field_path(pointer);
+    let range = || schema.at_pointer(pointer).and_then(|s| Some((s.minimum?, s.maximum?)));
+    let problem = match &error.kind {
+        Kind::Required { property } => {
+            field = format!("{}.{}", field, property.as_str().unwrap_or_default());
+            "is required".to_string()
+        }
+        Kind::Type { kind } => match kind {
+            jsonschema::error::TypeKind::Single(expected) => format!("must be of type {}", expected),
+            jsonschema::error::TypeKind::Multiple(_) => "has the wrong type".to_string(),
+        },
+        Kind::Minimum { limit } | Kind::Maximum { limit } => match range() {
+            Some((min, max)) => format!("must be between {} and {}", min, max),
+            None if matches!(error.kind, Kind::Minimum { .. }) => format!("must be at least {}", limit),
+            None => format!("must be at most {}", limit),
+        },
+        Kind::Enum { options } => {
+            let options: Vec<String> = options.as_array().into_iter().flatten().map(|o| o.to_string()).collect();
+            format!("must be one of {}", options.join(", "))
+        }
+        _ => error.to_string(),
+    };
+    
+    ValidationIssue {
+        message: format!("{} {}", field, problem),
+        field,
+    }
+}
+
 impl Default for ToolRegistry {
     fn default() -> Self {
         Self::new()
@@ -342,6 +480,12 @@ pub fn create_default_registry() -> ToolRegistry {
     // Build Tools
     registry.register(create_run_build_tool());
     
+    // Test Tools
+    registry.register(create_generate_tests_tool());
+    
+    // Agent Tools
+    registry.register(create_delegate_to_agent_tool());
+    
     registry
 }
 
@@ -579,6 +723,70 @@ fn create_run_build_tool() -> ToolDef {
     .with_permissions(vec![ToolPermission::RunBuild])
 }
 
+fn create_generate_tests_tool() -> ToolDef {
+    ToolDef::new(
+        "generate_tests",
+        "Generate unit tests for a function in the given C or Rust code",
+        JsonSchema::object()
+            .with_property("code", JsonSchema::string().with_description("Source that defines the function"), true)
+            .with_property("function", JsonSchema::string().with_description("Name of the function to test"), true)
+            .with_property("framework", JsonSchema::string().with_description("Framework: unity, cpputest, rust (default unity)"), false),
+        JsonSchema::object()
+            .with_property("tests", JsonSchema::string(), true)
+            .with_property("framework", JsonSchema::string(), true),
+        |input, _ctx| {
+            let code = input.get("code").and_then(|v| v.as_str())
+                .ok_or_else(|| ToolError::validation("Missing 'code' field"))?;
+            let function = input.get("function").and_then(|v| v.as_str())
+                .ok_or_else(|| ToolError::validation("Missing 'function' field"))?;
+            let framework = input.get("framework").and_then(|v| v.as_str()).unwrap_or("unity");
+            
+            // The code is passed in, so the tool reads nothing from the project
+            let tests = super::test_agent::TestAgent::new()
+                .generate_tests(code, function, framework)
+                .map_err(ToolError::validation)?;
+            Ok(serde_json::json!({
+                "tests": tests,
+                "framework": framework
+            }))
+        },
+    )
+    .with_category(ToolCategory::General)
+}
+
+fn create_delegate_to_agent_tool() -> ToolDef {
+    ToolDef::new(
+        super::orchestrator::DELEGATE_TOOL,
+        "Hand a sub-task to another agent (fsm, code, debug, hardware, docs)",
+        JsonSchema::object()
+            .with_property("to_agent", JsonSchema::string().with_description("Id of the agent to delegate to"), true)
+            .with_property("message", JsonSchema::string().with_description("What the other agent should do"), true),
+        JsonSchema::object()
+            .with_property("delegate", JsonSchema::boolean(), true)
+            .with_property("to_agent", JsonSchema::string(), true)
+            .with_property("message", JsonSchema::string(), true),
+        |input, ctx| {
+            let to_agent = input.get("to_agent").and_then(|v| v.as_str())
+                .ok_or_else(|| ToolError::validation("Missing 'to_agent' field"))?;
+            let message = input.get("message").and_then(|v| v.as_str())
+                .filter(|m| !m.trim().is_empty())
+                .ok_or_else(|| ToolError::validation("Missing 'message' field"))?;
+            if to_agent == ctx.agent_id {
+                return Err(ToolError::validation("An agent cannot delegate to itself"));
+            }
+            
+            // The orchestrator runs the target agent; this only validates the request
+            Ok(serde_json::json!({
+                "delegate": true,
+                "from_agent": ctx.agent_id,
+                "to_agent": to_agent,
+                "message": message
+            }))
+        },
+    )
+    .with_category(ToolCategory::General)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -592,6 +800,23 @@ mod tests {
         assert!(registry.get("unknown_tool").is_none());
     }
     
+    #[test]
+    fn test_generate_tests_tool() {
+        let registry = create_default_registry();
+        let ctx = ToolContext::new("test");
+        
+        let input = serde_json::json!({
+            "code": "pub fn checked_scale(value: u16, factor: u16) -> Option<u16> { value.checked_mul(factor) }",
+            "function": "checked_scale",
+            "framework": "rust"
+        });
+        let output = registry.execute("generate_tests", input, &ctx).unwrap();
+        assert!(output["tests"].as_str().unwrap().contains("checked_scale(u16::MAX, u16::MAX)"));
+        
+        let missing = serde_json::json!({ "code": "void f(void) {}", "function": "g" });
+        assert!(registry.execute("generate_tests", missing, &ctx).is_err());
+    }
+    
     #[test]
     fn test_tool_execution() {
         let registry = create_default_registry();
@@ -622,6 +847,48 @@ mod tests {
         assert_eq!(result.unwrap_err().code, "PERMISSION_DENIED");
     }
     
+    #[test]
+    fn test_validate_input() {
+        let mut registry = ToolRegistry::new();
+        registry.register(ToolDef::new(
+            "configure_uart",
+            "Configure a UART",
+            JsonSchema::object()
+                .with_property("baud_rate", JsonSchema::integer().with_range(300.0, 4_000_000.0), true)
+                .with_property("parity", JsonSchema::string().with_enum(vec!["none".into(), "even".into(), "odd".into()]), false)
+                .with_property("pins", JsonSchema::array(JsonSchema::object().with_property("name", JsonSchema::string(), true)), false),
+            JsonSchema::object(),
+            |_input, _ctx| Ok(serde_json::json!({ "success": true })),
+        ));
+        
+        assert!(registry.validate_input("configure_uart", &serde_json::json!({ "baud_rate": 115200 })).is_empty());
+        
+        let issues = registry.validate_input("configure_uart", &serde_json::json!({
+            "baud_rate": 12_000_000,
+            "parity": "mark",
+            "pins": [{ "name": "PA9" }, {}]
+        }));
+        let mut messages: Vec<&str> = issues.iter().map(|i| i.message.as_str()).collect();
+        messages.sort();
+        assert_eq!(messages, vec![
+            "params.baud_rate must be between 300 and 4000000",
+            "params.parity must be one of \"none\", \"even\", \"odd\"",
+            "params.pins[1].name is required",
+        ]);
+        
+        let issues = registry.validate_input("configure_uart", &serde_json::json!({ "baud_rate": "fast" }));
+        assert_eq!(issues, vec![ValidationIssue {
+            field: "params.baud_rate".to_string(),
+            message: "params.baud_rate must be of type integer".to_string(),
+        }]);
+        
+        let err = registry.execute("configure_uart", serde_json::json!({}), &ToolContext::new("test_agent")).unwrap_err();
+        assert_eq!(err.code, "VALIDATION_ERROR");
+        assert!(err.recoverable);
+        assert_eq!(err.issues[0].field, "params.baud_rate");
+        assert_eq!(registry.validate_input("nope", &serde_json::json!({}))[0].field, "tool");
+    }
+    
     #[test]
     fn test_get_schemas() {
         let registry = create_default_registry();
@@ -29,6 +29,15 @@ impl GeminiClient {
     }
     
     pub async fn generate(&self, prompt: &str) -> Result<String, String> {
+        self.generate_with_mime(prompt, None).await
+    }
+    
+    /// Generate with structured output, the response text is a JSON document
+    pub async fn generate_json(&self, prompt: &str) -> Result<String, String> {
+        self.generate_with_mime(prompt, Some("application/json")).await
+    }
+    
+    async fn generate_with_mime(&self, prompt: &str, mime_type: Option<&str>) -> Result<String, String> {
         let api_key = self.api_key.as_ref()
             .ok_or("GEMINI_API_KEY not configured")?;
         
@@ -41,6 +50,7 @@ impl GeminiClient {
             generation_config: Some(GenerationConfig {
                 temperature: 0.7,
                 max_output_tokens: 4096,
+                response_mime_type: mime_type.map(|m| m.to_string()),
             }),
         };
         
@@ -96,6 +106,8 @@ struct Part {
 struct GenerationConfig {
     temperature: f32,
     max_output_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_mime_type: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -0,0 +1,198 @@
+// Conversation Memory
+// Per-session chat history, folded into a running summary as it grows
+
+use super::{AIService, Role};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+/// Turns kept verbatim before older ones are summarized
+pub const DEFAULT_MAX_TURNS: usize = 20;
+
+/// One message in a conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Turn {
+    pub role: Role,
+    pub content: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Recent turns plus a summary of everything before them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationMemory {
+    pub turns: Vec<Turn>,
+    pub summary: Option<String>,
+    pub max_turns: usize,
+    /// Turns taken out for a summary that has not come back yet
+    #[serde(skip)]
+    summarizing: Vec<Turn>,
+}
+
+impl ConversationMemory {
+    pub fn new(max_turns: usize) -> Self {
+        Self { turns: Vec::new(), summary: None, max_turns: max_turns.max(2), summarizing: Vec::new() }
+    }
+
+    pub fn push(&mut self, role: Role, content: impl Into<String>) {
+        self.turns.push(Turn { role, content: content.into(), timestamp: Utc::now() });
+    }
+
+    pub fn needs_summary(&self) -> bool {
+        self.turns.len() > self.max_turns
+    }
+
+    /// Take out the oldest turns to fold into the summary, keeping the newest half verbatim
+    ///
+    /// `None` when the session is short enough or another summary is still
+    /// pending, so only one caller ever compacts the same turns.
+    pub fn take_turns_to_summarize(&mut self) -> Option<Vec<Turn>> {
+        if !self.needs_summary() || !self.summarizing.is_empty() {
+            return None;
+        }
+        let count = self.turns.len() - self.max_turns / 2;
+        self.summarizing = self.turns.drain(..count).collect();
+        Some(self.summarizing.clone())
+    }
+
+    /// Replace the taken turns with `summary`
+    pub fn apply_summary(&mut self, summary: String) {
+        self.summarizing.clear();
+        self.summary = Some(summary);
+    }
+
+    /// Put the taken turns back after a failed summary
+    pub fn restore_turns(&mut self) {
+        let taken = std::mem::take(&mut self.summarizing);
+        self.turns.splice(..0, taken);
+    }
+
+    /// Summary and recent turns as prompt context
+    pub fn to_context(&self) -> String {
+        let mut context = String::new();
+        if let Some(summary) = &self.summary {
+            context.push_str(&format!("Summary of earlier conversation:\n{}\n\n", summary));
+        }
+        if !self.summarizing.is_empty() || !self.turns.is_empty() {
+            context.push_str("Recent conversation:\n");
+            for turn in self.summarizing.iter().chain(&self.turns) {
+                context.push_str(&format!("{}: {}\n", role_label(turn.role), turn.content));
+            }
+        }
+        context
+    }
+}
+
+impl Default for ConversationMemory {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_TURNS)
+    }
+}
+
+pub(crate) fn role_label(role: Role) -> &'static str {
+    match role {
+        Role::System => "Summary",
+        Role::User => "User",
+        Role::Assistant => "Assistant",
+    }
+}
+
+/// Chat memories keyed by session id
+pub type SessionStore = Mutex<HashMap<String, ConversationMemory>>;
+
+/// Prompt context for a session, `None` for new or empty sessions
+pub async fn session_context(sessions: &SessionStore, session_id: &str) -> Option<String> {
+    let sessions = sessions.lock().await;
+    sessions.get(session_id)
+        .map(|memory| memory.to_context())
+        .filter(|context| !context.is_empty())
+}
+
+/// Record an exchange and summarize older turns once the session grows too long
+pub async fn record_exchange(
+    sessions: &SessionStore,
+    session_id: &str,
+    user_message: &str,
+    assistant_message: &str,
+    ai_service: &AIService,
+) {
+    // Check and take the turns under one lock so concurrent calls cannot both compact
+    let (older, previous_summary) = {
+        let mut sessions = sessions.lock().await;
+        let memory = sessions.entry(session_id.to_string()).or_default();
+        memory.push(Role::User, user_message);
+        memory.push(Role::Assistant, assistant_message);
+        let Some(older) = memory.take_turns_to_summarize() else {
+            return;
+        };
+        (older, memory.summary.clone())
+    };
+
+    // Carry the previous summary forward so nothing is lost between compactions
+    let mut turns = Vec::with_capacity(older.len() + 1);
+    if let Some(summary) = previous_summary {
+        turns.push(Turn { role: Role::System, content: summary, timestamp: Utc::now() });
+    }
+    turns.extend(older);
+
+    let summary = ai_service.summarize_conversation(&turns).await;
+    let mut sessions = sessions.lock().await;
+    let Some(memory) = sessions.get_mut(session_id) else { return };
+    match summary {
+        Ok(summary) => memory.apply_summary(summary),
+        Err(e) => {
+            log::warn!("Could not summarize session {}: {}", session_id, e);
+            memory.restore_turns();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_compaction() {
+        let mut memory = ConversationMemory::new(4);
+        for i in 0..3 {
+            memory.push(Role::User, format!("question {}", i));
+            memory.push(Role::Assistant, format!("answer {}", i));
+        }
+        assert!(memory.needs_summary());
+        let older = memory.take_turns_to_summarize().unwrap();
+        assert_eq!(older.len(), 4);
+        assert_eq!(older[0].content, "question 0");
+
+        // A second caller finds nothing to take while the summary is pending
+        for i in 3..5 {
+            memory.push(Role::User, format!("question {}", i));
+            memory.push(Role::Assistant, format!("answer {}", i));
+        }
+        assert!(memory.needs_summary());
+        assert!(memory.take_turns_to_summarize().is_none());
+        assert!(memory.to_context().starts_with("Recent conversation:\nUser: question 0\n"));
+
+        // A failed summary puts the turns back in order
+        memory.restore_turns();
+        assert_eq!(memory.turns.len(), 10);
+        assert_eq!(memory.turns[0].content, "question 0");
+        memory.turns.truncate(6);
+
+        assert_eq!(memory.take_turns_to_summarize().unwrap().len(), 4);
+        memory.apply_summary("Discussed UART setup".to_string());
+        assert_eq!(memory.turns.len(), 2);
+        assert!(!memory.needs_summary());
+        assert_eq!(
+            memory.to_context(),
+            "Summary of earlier conversation:\nDiscussed UART setup\n\nRecent conversation:\nUser: question 2\nAssistant: answer 2\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_session_context() {
+        let sessions = SessionStore::default();
+        assert!(session_context(&sessions, "s1").await.is_none());
+        record_exchange(&sessions, "s1", "hello", "hi", &AIService::new()).await;
+        assert_eq!(session_context(&sessions, "s1").await.unwrap(), "Recent conversation:\nUser: hello\nAssistant: hi\n");
+    }
+}
@@ -2,6 +2,7 @@
 // Multi-LLM integration for code generation and assistance
 
 pub mod gemini;
+pub mod memory;
 pub mod service;
 pub mod providers;
 
@@ -2,6 +2,8 @@
 // High-level AI operations for embedded systems assistance
 
 use super::gemini::GeminiClient;
+use super::memory::{role_label, Turn};
+use crate::agents::diff_engine::unified_diff;
 use crate::core::*;
 
 pub struct AIService {
@@ -169,6 +171,52 @@ Output ONLY the JSON, nothing else."#,
         self.gemini.generate(&prompt).await
     }
     
+    /// Explain in plain English what changed between two versions of some code
+    pub async fn explain_diff(&self, original: &str, modified: &str, language: &str) -> Result<String, String> {
+        let diff = unified_diff(language, original, modified);
+        if diff.is_empty() {
+            return Ok("No changes.".to_string());
+        }
+
+        let prompt = format!(
+            r#"Explain this change to some {} in plain English for an embedded engineer reviewing it.
+
+```diff
+{}
+```
+
+Describe what changed, then why it was likely done. Mention any behaviour that could break.
+Keep it to a short paragraph or a few bullet points."#,
+            language, diff
+        );
+        
+        self.gemini.generate(&prompt).await
+    }
+    
+    /// Condense conversation turns into a short summary for later context
+    pub async fn summarize_conversation(&self, turns: &[Turn]) -> Result<String, String> {
+        let transcript = turns.iter()
+            .map(|t| format!("{}: {}", role_label(t.role), t.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let prompt = format!(
+            r#"Summarize this conversation between an embedded engineer and an assistant so it can be continued later.
+
+{}
+
+Keep decisions, requirements, hardware details (MCU, pins, peripherals, clock settings) and open questions.
+Drop pleasantries. Use at most 150 words. Output only the summary."#,
+            transcript
+        );
+        
+        self.gemini.generate(&prompt).await
+    }
+    
+    /// Send a prompt and request a JSON response
+    pub async fn generate_json(&self, prompt: &str) -> Result<String, String> {
+        self.gemini.generate_json(prompt).await
+    }
+    
     /// Chat with AI assistant
     pub async fn chat(&self, message: &str, context: Option<&str>) -> Result<String, String> {
         let system_context = r#"You are NeuroBench AI, an expert assistant for embedded systems design.
@@ -0,0 +1,121 @@
+// Compilation Database
+// compile_commands.json for clangd and other LSP servers
+
+use super::BuildConfig;
+use crate::toolchain::streaming_build::StreamingBuildConfig;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// File name clangd looks for in the project root
+pub const COMPILE_COMMANDS_FILE: &str = "compile_commands.json";
+
+const COMPILER: &str = "arm-none-eabi-gcc";
+const CPU_FLAGS: &[&str] = &["-mcpu=cortex-m4", "-mthumb", "-mfloat-abi=hard", "-mfpu=fpv4-sp-d16"];
+
+/// One entry of a JSON compilation database
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompileCommand {
+    pub directory: String,
+    pub file: String,
+    pub arguments: Vec<String>,
+}
+
+/// Compiler flags for a `BuildConfig`, unquoted, one argument each
+pub(crate) fn compile_flags(config: &BuildConfig) -> Vec<String> {
+    let mut flags = vec![format!("-{}", config.optimization), "-Wall".to_string(), "-Wextra".to_string()];
+    if config.debug_symbols {
+        flags.push("-g3".to_string());
+    }
+    flags.extend(CPU_FLAGS.iter().map(|f| f.to_string()));
+    flags.extend(config.defines.iter().map(|d| format!("-D{}", d)));
+    flags.extend(config.include_paths.iter().map(|i| format!("-I{}", i)));
+    if config.use_lto {
+        flags.extend(super::lto::lto_compile_flags(config.lto_type));
+    }
+    flags
+}
+
+fn entries<'a>(directory: &Path, flags: &[String], sources: impl Iterator<Item = &'a Path>) -> Vec<CompileCommand> {
+    sources
+        .map(|source| {
+            let file = source.to_string_lossy().to_string();
+            let mut arguments = vec![COMPILER.to_string()];
+            arguments.extend(flags.iter().cloned());
+            arguments.extend(["-c".to_string(), file.clone()]);
+            CompileCommand {
+                directory: directory.to_string_lossy().to_string(),
+                file,
+                arguments,
+            }
+        })
+        .collect()
+}
+
+/// Entries for a `BuildConfig` whose relative paths are based at `directory`
+pub fn compile_commands(config: &BuildConfig, directory: &Path) -> Vec<CompileCommand> {
+    entries(directory, &compile_flags(config), config.source_files.iter().map(Path::new))
+}
+
+/// Entries matching the compiler invocations of a streaming build
+pub fn streaming_compile_commands(config: &StreamingBuildConfig) -> Vec<CompileCommand> {
+    entries(&config.project_path, &config.compile_flags(), config.source_files.iter().map(PathBuf::as_path))
+}
+
+/// Compilation database for `config`, relative paths based at the current directory
+pub fn generate_compile_commands(config: &BuildConfig) -> String {
+    let directory = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    generate_compile_commands_in(config, &directory)
+}
+
+/// Compilation database for `config`, relative paths based at `directory`
+pub fn generate_compile_commands_in(config: &BuildConfig, directory: &Path) -> String {
+    to_json(&compile_commands(config, directory))
+}
+
+fn to_json(commands: &[CompileCommand]) -> String {
+    serde_json::to_string_pretty(commands).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// Write `compile_commands.json` into `project_root`
+pub fn write_compile_commands(project_root: &Path, commands: &[CompileCommand]) -> std::io::Result<PathBuf> {
+    let path = project_root.join(COMPILE_COMMANDS_FILE);
+    std::fs::write(&path, to_json(commands))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::LtoType;
+
+    #[test]
+    fn test_compile_commands() {
+        let config = BuildConfig {
+            system: "make".to_string(),
+            target: "firmware".to_string(),
+            optimization: "O2".to_string(),
+            debug_symbols: true,
+            defines: vec!["STM32F407xx".to_string(), "HSE_VALUE=8000000".to_string()],
+            include_paths: vec!["inc".to_string()],
+            source_files: vec!["src/main.c".to_string(), "src/uart.c".to_string()],
+            linker_script: None,
+            use_lto: false,
+            lto_type: LtoType::Full,
+            lto_job_count: None,
+        };
+
+        let json = generate_compile_commands_in(&config, Path::new("/work/blinky"));
+        let commands: Vec<CompileCommand> = serde_json::from_str(&json).unwrap();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[1].directory, "/work/blinky");
+        assert_eq!(commands[1].file, "src/uart.c");
+        for flag in ["-mcpu=cortex-m4", "-mthumb", "-DHSE_VALUE=8000000", "-Iinc", "-g3"] {
+            assert!(commands[0].arguments.contains(&flag.to_string()), "missing {}", flag);
+        }
+        assert_eq!(commands[0].arguments.last().unwrap(), "src/main.c");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_compile_commands(dir.path(), &commands).unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), json);
+    }
+}
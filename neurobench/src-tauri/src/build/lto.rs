@@ -0,0 +1,111 @@
+// Link-Time Optimization
+// GCC LTO flags and the pre-link check that every object carries LTO data
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// LTO mode
+///
+/// GCC has no ThinLTO; `Thin` maps to its partitioned (WHOPR) mode, which
+/// optimizes partitions in parallel. `Full` optimizes the whole program as a
+/// single partition. `Fat` also keeps regular code in the objects, so they
+/// still link without LTO.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LtoType {
+    #[default]
+    Full,
+    Thin,
+    Fat,
+}
+
+/// Flags added to every compile when LTO is on
+pub fn lto_compile_flags(lto_type: LtoType) -> Vec<String> {
+    let mut flags = vec!["-flto".to_string()];
+    if lto_type == LtoType::Fat {
+        flags.push("-ffat-lto-objects".to_string());
+    }
+    flags
+}
+
+/// Flags added to the link when LTO is on
+///
+/// `-fuse-linker-plugin` makes the driver pass `liblto_plugin.so` to ld, so
+/// symbol resolution sees the whole program. `jobs` runs that many LTRANS
+/// jobs in parallel.
+pub fn lto_link_flags(lto_type: LtoType, jobs: Option<u8>) -> Vec<String> {
+    let mut flags = vec![
+        match jobs {
+            Some(n) if n > 0 => format!("-flto={}", n),
+            _ => "-flto".to_string(),
+        },
+        "-fuse-linker-plugin".to_string(),
+    ];
+    match lto_type {
+        LtoType::Full => flags.push("-flto-partition=one".to_string()),
+        LtoType::Thin => flags.push("-flto-partition=balanced".to_string()),
+        LtoType::Fat => {}
+    }
+    flags
+}
+
+/// Whether an object holds LTO data: GCC `.gnu.lto_*` sections or LLVM bitcode
+fn has_lto_data(bytes: &[u8]) -> bool {
+    const GCC_SECTION: &[u8] = b".gnu.lto_";
+    bytes.starts_with(b"BC\xC0\xDE") || bytes.windows(GCC_SECTION.len()).any(|w| w == GCC_SECTION)
+}
+
+/// Check that every object was compiled with `-flto` before an LTO link
+///
+/// Mixing in plain objects silently drops them from cross-module optimization,
+/// and stale objects from a non-LTO build are the usual cause.
+pub fn verify_lto_objects(objects: &[PathBuf]) -> Result<(), String> {
+    let missing: Vec<String> = objects.iter()
+        .filter(|obj| !std::fs::read(obj).map(|bytes| has_lto_data(&bytes)).unwrap_or(false))
+        .map(|obj| obj.display().to_string())
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "LTO link needs every object compiled with -flto; rebuild these from clean: {}",
+            missing.join(", ")
+        ))
+    }
+}
+
+/// Makefile recipe line that fails the link when an object lacks LTO data
+pub(crate) fn makefile_lto_check(objects: &str) -> String {
+    format!(
+        "\t@missing=\"$$(grep -L -F .gnu.lto_ {})\"; if [ -n \"$$missing\" ]; then \
+echo \"LTO link needs every object compiled with -flto; rebuild these from clean: $$missing\" >&2; exit 1; fi\n",
+        objects
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lto_flags() {
+        assert_eq!(lto_compile_flags(LtoType::Full), vec!["-flto"]);
+        assert_eq!(lto_compile_flags(LtoType::Fat), vec!["-flto", "-ffat-lto-objects"]);
+        assert_eq!(lto_link_flags(LtoType::Full, None), vec!["-flto", "-fuse-linker-plugin", "-flto-partition=one"]);
+        assert_eq!(lto_link_flags(LtoType::Thin, Some(4)), vec!["-flto=4", "-fuse-linker-plugin", "-flto-partition=balanced"]);
+    }
+
+    #[test]
+    fn test_verify_lto_objects() {
+        let dir = tempfile::tempdir().unwrap();
+        let lto = dir.path().join("main.o");
+        let plain = dir.path().join("uart.o");
+        std::fs::write(&lto, b"\x7fELF\0.gnu.lto_main.0\0").unwrap();
+        std::fs::write(&plain, b"\x7fELF\0.text\0").unwrap();
+
+        assert!(verify_lto_objects(std::slice::from_ref(&lto)).is_ok());
+        let err = verify_lto_objects(&[lto, plain]).unwrap_err();
+        assert!(err.contains("uart.o"));
+        assert!(!err.contains("main.o"));
+    }
+}
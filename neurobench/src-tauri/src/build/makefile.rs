@@ -0,0 +1,280 @@
+// Incremental Makefile Generator
+// GCC dependency tracking, parallel builds and clangd integration
+
+use super::compile_db::compile_flags;
+use super::lto::{lto_link_flags, makefile_lto_check};
+use super::BuildConfig;
+use std::path::Path;
+
+/// Quote a path for the shell when it contains spaces
+fn quote(arg: &str) -> String {
+    if arg.contains(' ') {
+        format!("\"{}\"", arg)
+    } else {
+        arg.to_string()
+    }
+}
+
+/// Escape spaces for use in a rule's target or prerequisite list
+fn escape_prerequisite(path: &str) -> String {
+    path.replace(' ', "\\ ")
+}
+
+/// Object path under `$(BUILD_DIR)`, spaces replaced so objects are plain make words
+fn object_path(source: &str) -> String {
+    let object = Path::new(source).with_extension("o");
+    format!("$(BUILD_DIR)/{}", object.to_string_lossy().replace(' ', "_"))
+}
+
+/// Extra flags C++ sources get on top of `CFLAGS`
+const CXX_FLAGS: &[&str] = &["-fno-exceptions", "-fno-rtti"];
+
+/// Source extensions with a pattern rule, and the recipe that compiles each
+const RECIPES: &[(&str, &str)] = &[
+    ("c", "$(CC) $(CFLAGS) $(DEPFLAGS)"),
+    ("cpp", "$(CXX) $(CXXFLAGS) $(DEPFLAGS)"),
+    // Plain assembly is not preprocessed, so there are no dependencies to track
+    ("s", "$(CC) $(CFLAGS)"),
+    ("S", "$(CC) $(ASFLAGS) $(DEPFLAGS)"),
+];
+
+/// Compile recipe for `source`, by extension; unknown extensions build as C
+fn recipe(source: &str) -> &'static str {
+    let extension = Path::new(source).extension().and_then(|e| e.to_str()).unwrap_or("c");
+    RECIPES.iter().find(|(ext, _)| *ext == extension).map_or(RECIPES[0].1, |(_, recipe)| recipe)
+}
+
+fn is_cpp(source: &str) -> bool {
+    Path::new(source).extension().is_some_and(|e| e == "cpp")
+}
+
+/// Single-quote a line for `printf` inside a recipe
+fn recipe_literal(line: &str) -> String {
+    format!("'{}'", line.replace('$', "$$").replace('\'', "'\\''"))
+}
+
+/// Generate a Makefile that only rebuilds what changed
+///
+/// Objects get `.d` files from `-MMD -MP`, so header edits rebuild their
+/// dependents. `all` re-invokes make with `-j$(JOBS)`. C, C++ and assembly
+/// (`.s`, and `.S` through the preprocessor) each have a pattern rule; sources
+/// with spaces in their path get an explicit rule since pattern rules cannot
+/// match them. Any C++ source makes g++ the linker.
+pub fn generate_incremental_makefile(config: &BuildConfig) -> String {
+    let target = config.target.replace(' ', "_");
+    let flags = compile_flags(config);
+    let mut makefile = String::new();
+
+    makefile.push_str("# Auto-generated incremental Makefile for NeuroBench project\n\n");
+
+    makefile.push_str("# Toolchain\n");
+    makefile.push_str("PREFIX = arm-none-eabi-\n");
+    makefile.push_str("CC = $(PREFIX)gcc\n");
+    makefile.push_str("CXX = $(PREFIX)g++\n");
+    let linker = if config.source_files.iter().any(|s| is_cpp(s)) { "$(CXX)" } else { "$(CC)" };
+    makefile.push_str(&format!("LINK = {}\n", linker));
+    makefile.push_str("OBJCOPY = $(PREFIX)objcopy\n");
+    makefile.push_str("SIZE = $(PREFIX)size\n");
+    makefile.push_str("JOBS ?= $(shell nproc 2>/dev/null || echo 1)\n\n");
+
+    makefile.push_str("# Project\n");
+    makefile.push_str(&format!("TARGET = {}\n", target));
+    makefile.push_str("BUILD_DIR = build\n");
+    makefile.push_str("ELF = $(BUILD_DIR)/$(TARGET).elf\n");
+    makefile.push_str("BIN = $(BUILD_DIR)/$(TARGET).bin\n\n");
+
+    makefile.push_str("# Compiler flags\n");
+    let quoted: Vec<String> = flags.iter().map(|f| quote(f)).collect();
+    makefile.push_str(&format!("CFLAGS = {}\n", quoted.join(" ")));
+    makefile.push_str(&format!("CXXFLAGS = $(CFLAGS) {}\n", CXX_FLAGS.join(" ")));
+    makefile.push_str("ASFLAGS = $(CFLAGS) -x assembler-with-cpp\n");
+    makefile.push_str("DEPFLAGS = -MMD -MP\n\n");
+
+    makefile.push_str("# Linker flags\n");
+    makefile.push_str("LDFLAGS =");
+    if let Some(script) = &config.linker_script {
+        makefile.push_str(&format!(" {}", quote(&format!("-T{}", script))));
+    }
+    makefile.push_str(" -Wl,--gc-sections");
+    if config.use_lto {
+        makefile.push_str(&format!(" {}", lto_link_flags(config.lto_type, config.lto_job_count).join(" ")));
+    }
+    makefile.push_str("\n\n");
+
+    makefile.push_str("# Objects and dependency files\n");
+    makefile.push_str("OBJS =");
+    for source in &config.source_files {
+        makefile.push_str(&format!(" \\\n    {}", object_path(source)));
+    }
+    makefile.push_str("\nDEPS = $(OBJS:.o=.d)\n\n");
+
+    makefile.push_str("# Rules\n");
+    makefile.push_str("all:\n");
+    makefile.push_str("\t@$(MAKE) --no-print-directory -j$(JOBS) $(BIN)\n\n");
+
+    for (extension, recipe) in RECIPES {
+        makefile.push_str(&format!("$(BUILD_DIR)/%.o: %.{}\n", extension));
+        makefile.push_str("\t@mkdir -p \"$(@D)\"\n");
+        makefile.push_str(&format!("\t{} -c \"$<\" -o \"$@\"\n\n", recipe));
+    }
+
+    for source in config.source_files.iter().filter(|s| s.contains(' ')) {
+        makefile.push_str(&format!("{}: {}\n", object_path(source), escape_prerequisite(source)));
+        makefile.push_str("\t@mkdir -p \"$(@D)\"\n");
+        makefile.push_str(&format!("\t{} -c \"$<\" -o \"$@\"\n\n", recipe(source)));
+    }
+
+    makefile.push_str("$(ELF): $(OBJS)\n");
+    if config.use_lto {
+        makefile.push_str(&makefile_lto_check("$(OBJS)"));
+    }
+    makefile.push_str("\t$(LINK) $(CFLAGS) $(LDFLAGS) $(OBJS) -o \"$@\"\n\n");
+
+    makefile.push_str("$(BIN): $(ELF)\n");
+    makefile.push_str("\t$(OBJCOPY) -O binary \"$<\" \"$@\"\n\n");
+
+    makefile.push_str("size: $(ELF)\n");
+    makefile.push_str("\t$(SIZE) \"$<\"\n\n");
+
+    makefile.push_str("flash: all\n");
+    makefile.push_str("\tst-flash write \"$(BIN)\" 0x8000000\n\n");
+
+    makefile.push_str("clean:\n");
+    makefile.push_str("\trm -rf $(BUILD_DIR) compile_commands.json compile_flags.txt\n\n");
+
+    // clangd: full compile database, and the one-flag-per-line fallback
+    makefile.push_str("compile_commands.json: Makefile\n");
+    makefile.push_str("\t@printf '%s\\n' '[' > $@\n");
+    for (i, source) in config.source_files.iter().enumerate() {
+        let mut arguments = vec![if is_cpp(source) { "arm-none-eabi-g++" } else { "arm-none-eabi-gcc" }.to_string()];
+        arguments.extend(flags.iter().cloned());
+        if is_cpp(source) {
+            arguments.extend(CXX_FLAGS.iter().map(|f| f.to_string()));
+        } else if source.ends_with(".S") {
+            arguments.extend(["-x".to_string(), "assembler-with-cpp".to_string()]);
+        }
+        arguments.extend(["-c".to_string(), source.clone(), "-o".to_string(), object_path(source).replace("$(BUILD_DIR)", "build")]);
+        let entry = serde_json::json!({ "file": source, "arguments": arguments });
+        let mut line = entry.to_string();
+        // The directory is only known when make runs
+        line.insert_str(1, "\"directory\":\"__CURDIR__\",");
+        if i + 1 < config.source_files.len() {
+            line.push(',');
+        }
+        let literal = recipe_literal(&line).replace("__CURDIR__", "'\"$(CURDIR)\"'");
+        makefile.push_str(&format!("\t@printf '%s\\n' {} >> $@\n", literal));
+    }
+    makefile.push_str("\t@printf '%s\\n' ']' >> $@\n\n");
+
+    makefile.push_str("compile_flags.txt: Makefile\n");
+    makefile.push_str("\t@printf '%s\\n' > $@");
+    for flag in &flags {
+        makefile.push_str(&format!(" {}", recipe_literal(flag)));
+    }
+    makefile.push_str("\n\n");
+
+    makefile.push_str("-include $(DEPS)\n\n");
+    makefile.push_str(".PHONY: all clean size flash\n");
+
+    makefile
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::LtoType;
+
+    #[test]
+    fn test_incremental_makefile() {
+        let config = BuildConfig {
+            system: "make".to_string(),
+            target: "firmware".to_string(),
+            optimization: "O2".to_string(),
+            debug_symbols: false,
+            defines: vec!["STM32F407xx".to_string()],
+            include_paths: vec!["inc".to_string(), "my drivers/inc".to_string()],
+            source_files: vec!["src/main.c".to_string(), "my drivers/uart.c".to_string()],
+            linker_script: Some("stm32f407.ld".to_string()),
+            use_lto: false,
+            lto_type: LtoType::Full,
+            lto_job_count: None,
+        };
+
+        let makefile = generate_incremental_makefile(&config);
+        assert!(makefile.contains("DEPFLAGS = -MMD -MP"));
+        assert!(makefile.contains("-include $(DEPS)"));
+        assert!(makefile.contains("$(BUILD_DIR)/%.o: %.c"));
+        assert!(makefile.contains("-j$(JOBS)"));
+        assert!(makefile.contains("\"-Imy drivers/inc\""));
+        assert!(makefile.contains("$(BUILD_DIR)/src/main.o"));
+        assert!(makefile.contains("$(BUILD_DIR)/my_drivers/uart.o: my\\ drivers/uart.c\n"));
+        assert!(makefile.contains("'\"$(CURDIR)\"'"));
+        assert!(makefile.contains("'-Imy drivers/inc'"));
+        for target in ["all:", "clean:", "size:", "flash:", "compile_commands.json:", "compile_flags.txt:"] {
+            assert!(makefile.contains(target), "missing {}", target);
+        }
+        assert!(!makefile.contains("-flto"));
+        assert!(makefile.contains("\t$(LINK) $(CFLAGS) $(LDFLAGS) $(OBJS)"));
+        assert!(makefile.contains("LINK = $(CC)\n"));
+    }
+
+    #[test]
+    fn test_incremental_makefile_mixed_languages() {
+        let config = BuildConfig {
+            system: "make".to_string(),
+            target: "firmware".to_string(),
+            optimization: "O2".to_string(),
+            debug_symbols: false,
+            defines: vec![],
+            include_paths: vec![],
+            source_files: vec![
+                "src/main.cpp".to_string(),
+                "src/hal.c".to_string(),
+                "startup/startup_stm32f407xx.s".to_string(),
+                "src/vectors.S".to_string(),
+                "my drivers/motor.cpp".to_string(),
+            ],
+            linker_script: None,
+            use_lto: false,
+            lto_type: LtoType::Full,
+            lto_job_count: None,
+        };
+
+        let makefile = generate_incremental_makefile(&config);
+        assert!(makefile.contains("CXX = $(PREFIX)g++\n"));
+        assert!(makefile.contains("CXXFLAGS = $(CFLAGS) -fno-exceptions -fno-rtti\n"));
+        assert!(makefile.contains("$(BUILD_DIR)/%.o: %.cpp\n\t@mkdir -p \"$(@D)\"\n\t$(CXX) $(CXXFLAGS) $(DEPFLAGS) -c"));
+        assert!(makefile.contains("$(BUILD_DIR)/%.o: %.s\n\t@mkdir -p \"$(@D)\"\n\t$(CC) $(CFLAGS) -c"));
+        assert!(makefile.contains("$(BUILD_DIR)/%.o: %.S\n\t@mkdir -p \"$(@D)\"\n\t$(CC) $(ASFLAGS) $(DEPFLAGS) -c"));
+        assert!(makefile.contains("$(BUILD_DIR)/startup/startup_stm32f407xx.o"));
+        assert!(makefile.contains("$(BUILD_DIR)/my_drivers/motor.o: my\\ drivers/motor.cpp\n\t@mkdir -p \"$(@D)\"\n\t$(CXX) $(CXXFLAGS)"));
+        // C++ objects need libstdc++ support code, so g++ links
+        assert!(makefile.contains("LINK = $(CXX)\n"));
+        assert!(makefile.contains("\"arguments\":[\"arm-none-eabi-g++\""));
+        assert!(makefile.contains("\"arguments\":[\"arm-none-eabi-gcc\""));
+    }
+
+    #[test]
+    fn test_incremental_makefile_lto() {
+        let config = BuildConfig {
+            system: "make".to_string(),
+            target: "firmware".to_string(),
+            optimization: "Os".to_string(),
+            debug_symbols: false,
+            defines: vec![],
+            include_paths: vec![],
+            source_files: vec!["src/main.c".to_string()],
+            linker_script: None,
+            use_lto: true,
+            lto_type: LtoType::Thin,
+            lto_job_count: Some(8),
+        };
+
+        let makefile = generate_incremental_makefile(&config);
+        assert!(makefile.contains("CFLAGS = -Os -Wall -Wextra"));
+        assert!(makefile.contains(" -flto\n"));
+        assert!(makefile.contains("LDFLAGS = -Wl,--gc-sections -flto=8 -fuse-linker-plugin -flto-partition=balanced\n"));
+        assert!(makefile.contains("grep -L -F .gnu.lto_ $(OBJS)"));
+        assert!(makefile.contains("'-flto'"));
+    }
+}
@@ -4,6 +4,12 @@
 use serde::{Deserialize, Serialize};
 use std::process::Command;
 
+pub mod compile_db;
+pub mod lto;
+pub mod makefile;
+
+pub use lto::LtoType;
+
 /// Build system type
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BuildSystem {
@@ -24,6 +30,14 @@ pub struct BuildConfig {
     pub include_paths: Vec<String>,
     pub source_files: Vec<String>,
     pub linker_script: Option<String>,
+    /// Link-time optimization
+    #[serde(default)]
+    pub use_lto: bool,
+    #[serde(default)]
+    pub lto_type: LtoType,
+    /// Parallel LTRANS jobs for the LTO link
+    #[serde(default)]
+    pub lto_job_count: Option<u8>,
 }
 
 /// Build result
@@ -86,6 +100,10 @@ pub fn generate_makefile(config: &BuildConfig) -> String {
     for include in &config.include_paths {
         makefile.push_str(&format!("CFLAGS += -I{}\n", include));
     }
+
+    if config.use_lto {
+        makefile.push_str(&format!("CFLAGS += {}\n", lto::lto_compile_flags(config.lto_type).join(" ")));
+    }
     makefile.push_str("\n");
     
     // Linker
@@ -93,7 +111,11 @@ pub fn generate_makefile(config: &BuildConfig) -> String {
     if let Some(script) = &config.linker_script {
         makefile.push_str(&format!("LDFLAGS = -T{}\n", script));
     }
-    makefile.push_str("LDFLAGS += -Wl,--gc-sections\n\n");
+    makefile.push_str("LDFLAGS += -Wl,--gc-sections\n");
+    if config.use_lto {
+        makefile.push_str(&format!("LDFLAGS += {}\n", lto::lto_link_flags(config.lto_type, config.lto_job_count).join(" ")));
+    }
+    makefile.push_str("\n");
     
     // Rules
     makefile.push_str("# Objects\n");
@@ -107,6 +129,9 @@ pub fn generate_makefile(config: &BuildConfig) -> String {
     makefile.push_str("\t$(CC) $(CFLAGS) -c $< -o $@\n\n");
     
     makefile.push_str("$(BUILD_DIR)/$(TARGET).elf: $(OBJS)\n");
+    if config.use_lto {
+        makefile.push_str(&lto::makefile_lto_check("$^"));
+    }
     makefile.push_str("\t$(CC) $(CFLAGS) $(LDFLAGS) $^ -o $@\n");
     makefile.push_str("\t$(SIZE) $@\n\n");
     
@@ -229,10 +254,20 @@ mod tests {
             include_paths: vec!["inc".to_string()],
             source_files: vec!["main.c".to_string()],
             linker_script: Some("stm32f407.ld".to_string()),
+            use_lto: false,
+            lto_type: LtoType::Full,
+            lto_job_count: None,
         };
         
         let makefile = generate_makefile(&config);
         assert!(makefile.contains("TARGET = firmware"));
+        assert!(!makefile.contains("-flto"));
+
+        let lto = BuildConfig { use_lto: true, lto_type: LtoType::Fat, lto_job_count: Some(4), ..config };
+        let makefile = generate_makefile(&lto);
+        assert!(makefile.contains("CFLAGS += -flto -ffat-lto-objects\n"));
+        assert!(makefile.contains("LDFLAGS += -flto=4 -fuse-linker-plugin\n"));
+        assert!(makefile.contains("grep -L -F .gnu.lto_ $^"));
     }
 
     #[test]
@@ -246,6 +281,9 @@ mod tests {
             include_paths: vec!["inc".to_string()],
             source_files: vec!["main.c".to_string()],
             linker_script: Some("stm32f407.ld".to_string()),
+            use_lto: false,
+            lto_type: LtoType::Full,
+            lto_job_count: None,
         };
         
         let cmake = generate_cmake(&config);
@@ -0,0 +1,296 @@
+// Cloud Share Client
+// Uploads and downloads shared projects over HTTP with SHA-256 integrity checks
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Placeholder server used until the user configures their own
+pub const DEFAULT_SERVER_URL: &str = "https://share.neurobench.example/api";
+
+/// Attempts per request, including the first
+pub const MAX_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry, doubled for each further retry
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Cloud client errors
+#[derive(Debug, Error)]
+pub enum CloudError {
+    #[error("Invalid share ID: {0}")]
+    InvalidShareId(String),
+
+    #[error("Invalid server URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Server returned {status}: {message}")]
+    Status { status: u16, message: String },
+
+    #[error("Invalid server response: {0}")]
+    InvalidResponse(String),
+
+    #[error("Integrity check failed: expected {expected}, got {actual}")]
+    IntegrityMismatch { expected: String, actual: String },
+}
+
+/// Progress reported while uploading
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UploadProgress {
+    Started { bytes_total: usize, content_hash: String },
+    Attempt { attempt: u32, max_attempts: u32 },
+    Retrying { attempt: u32, delay_ms: u64, reason: String },
+    Completed { bytes_sent: usize, url: String },
+}
+
+#[derive(Debug, Serialize)]
+struct UploadRequest<'a> {
+    share_id: &'a str,
+    data: &'a str,
+    content_hash: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct UploadResponse {
+    content_hash: String,
+    #[serde(default)]
+    url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DownloadResponse {
+    data: String,
+    content_hash: String,
+}
+
+/// Lowercase hex SHA-256 of `data`
+pub fn sha256_hex(data: &str) -> String {
+    format!("{:x}", Sha256::digest(data.as_bytes()))
+}
+
+/// User-configured server URL, or the placeholder when unset
+pub fn resolve_server_url(server_url: Option<&str>) -> String {
+    server_url
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .unwrap_or(DEFAULT_SERVER_URL)
+        .to_string()
+}
+
+/// `{server}/projects/{share_id}`
+fn project_url(server_url: &str, share_id: &str) -> Result<String, CloudError> {
+    let server = server_url.trim().trim_end_matches('/');
+    if !(server.starts_with("http://") || server.starts_with("https://")) {
+        return Err(CloudError::InvalidUrl(server_url.to_string()));
+    }
+    let valid_id = !share_id.is_empty()
+        && share_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid_id {
+        return Err(CloudError::InvalidShareId(share_id.to_string()));
+    }
+    Ok(format!("{}/projects/{}", server, share_id))
+}
+
+fn verify_hash(expected: &str, actual: &str) -> Result<(), CloudError> {
+    if expected.eq_ignore_ascii_case(actual) {
+        Ok(())
+    } else {
+        Err(CloudError::IntegrityMismatch {
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        })
+    }
+}
+
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+}
+
+/// Upload project JSON, returning the share URL
+pub async fn upload_project(share_id: &str, data: &str, server_url: &str) -> Result<String, CloudError> {
+    upload_project_with_progress(share_id, data, server_url, |_| {}).await
+}
+
+/// Upload project JSON, reporting each attempt and retry
+///
+/// The server echoes the hash of what it stored; a mismatch means the
+/// upload was corrupted and is reported as an error.
+pub async fn upload_project_with_progress(
+    share_id: &str,
+    data: &str,
+    server_url: &str,
+    on_progress: impl Fn(UploadProgress),
+) -> Result<String, CloudError> {
+    let url = project_url(server_url, share_id)?;
+    let content_hash = sha256_hex(data);
+    on_progress(UploadProgress::Started {
+        bytes_total: data.len(),
+        content_hash: content_hash.clone(),
+    });
+
+    let client = http_client();
+    let body = UploadRequest { share_id, data, content_hash: &content_hash };
+    let mut attempt = 0;
+    let response = send_with_retry(
+        || {
+            attempt += 1;
+            on_progress(UploadProgress::Attempt { attempt, max_attempts: MAX_ATTEMPTS });
+            client.put(&url).json(&body).send()
+        },
+        |attempt, delay, reason| on_progress(UploadProgress::Retrying {
+            attempt,
+            delay_ms: delay.as_millis() as u64,
+            reason,
+        }),
+    ).await?;
+
+    let uploaded: UploadResponse = response.json().await
+        .map_err(|e| CloudError::InvalidResponse(e.to_string()))?;
+    verify_hash(&uploaded.content_hash, &content_hash)?;
+
+    let share_url = uploaded.url.unwrap_or(url);
+    on_progress(UploadProgress::Completed { bytes_sent: data.len(), url: share_url.clone() });
+    Ok(share_url)
+}
+
+/// Download project JSON, verified against the server's `content_hash`
+pub async fn download_project(share_id: &str, server_url: &str) -> Result<String, CloudError> {
+    let url = project_url(server_url, share_id)?;
+    let client = http_client();
+    let response = send_with_retry(|| client.get(&url).send(), |_, _, _| {}).await?;
+
+    let downloaded: DownloadResponse = response.json().await
+        .map_err(|e| CloudError::InvalidResponse(e.to_string()))?;
+    verify_hash(&downloaded.content_hash, &sha256_hex(&downloaded.data))?;
+    Ok(downloaded.data)
+}
+
+/// Connection failures, timeouts, 429 and 5xx are worth another try
+async fn send_with_retry<F, Fut>(
+    mut send: F,
+    on_retry: impl Fn(u32, Duration, String),
+) -> Result<reqwest::Response, CloudError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<reqwest::Response, reqwest::Error>>,
+{
+    let mut attempt = 1;
+    loop {
+        let result = send().await;
+        let retry_reason = match &result {
+            Ok(response) if response.status().is_server_error()
+                || response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS => Some(response.status().to_string()),
+            Err(e) if e.is_connect() || e.is_timeout() => Some(e.to_string()),
+            _ => None,
+        };
+
+        match retry_reason {
+            Some(reason) if attempt < MAX_ATTEMPTS => {
+                let delay = RETRY_BASE_DELAY * 2u32.pow(attempt - 1);
+                on_retry(attempt, delay, reason);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            _ => {
+                let response = result?;
+                let status = response.status();
+                if !status.is_success() {
+                    let message = response.text().await.unwrap_or_default();
+                    return Err(CloudError::Status { status: status.as_u16(), message });
+                }
+                return Ok(response);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve one canned response per connection, recording request bodies
+    async fn mock_server(responses: Vec<(u16, String)>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let bodies = Arc::new(Mutex::new(Vec::new()));
+        let recorded = bodies.clone();
+
+        tokio::spawn(async move {
+            for (status, body) in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some((head, rest)) = text.split_once("\r\n\r\n") {
+                        let length = head.lines()
+                            .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                            .unwrap_or(0);
+                        if rest.len() >= length || n == 0 {
+                            recorded.lock().unwrap().push(rest.to_string());
+                            break;
+                        }
+                    }
+                }
+                let response = format!(
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status, body.len(), body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (url, bodies)
+    }
+
+    #[tokio::test]
+    async fn test_upload_retries_and_verifies_hash() {
+        let data = r#"{"name":"Blinky"}"#;
+        let hash = sha256_hex(data);
+        let ok = serde_json::json!({ "content_hash": hash }).to_string();
+        let (server, bodies) = mock_server(vec![(503, "busy".to_string()), (200, ok)]).await;
+
+        let events = Mutex::new(Vec::new());
+        let url = upload_project_with_progress("abc123", data, &server, |p| events.lock().unwrap().push(p)).await.unwrap();
+        assert_eq!(url, format!("{}/projects/abc123", server));
+
+        let events = events.into_inner().unwrap();
+        assert!(events.iter().any(|p| matches!(p, UploadProgress::Retrying { attempt: 1, .. })));
+        assert!(matches!(events.last(), Some(UploadProgress::Completed { .. })));
+
+        let sent: serde_json::Value = serde_json::from_str(&bodies.lock().unwrap()[1]).unwrap();
+        assert_eq!(sent["content_hash"], hash.as_str());
+        assert_eq!(sent["data"], data);
+
+        let (server, _) = mock_server(vec![(404, "not found".to_string())]).await;
+        assert!(matches!(upload_project("abc123", data, &server).await, Err(CloudError::Status { status: 404, .. })));
+        assert!(matches!(upload_project("../etc", data, &server).await, Err(CloudError::InvalidShareId(_))));
+    }
+
+    #[tokio::test]
+    async fn test_download_integrity() {
+        let data = r#"{"name":"Blinky"}"#;
+        let good = serde_json::json!({ "data": data, "content_hash": sha256_hex(data) }).to_string();
+        let tampered = serde_json::json!({ "data": r#"{"name":"Evil"}"#, "content_hash": sha256_hex(data) }).to_string();
+        let (server, _) = mock_server(vec![(200, good), (200, tampered)]).await;
+
+        assert_eq!(download_project("abc123", &server).await.unwrap(), data);
+        assert!(matches!(download_project("abc123", &server).await, Err(CloudError::IntegrityMismatch { .. })));
+        assert_eq!(resolve_server_url(Some("  ")), DEFAULT_SERVER_URL);
+    }
+}
@@ -7,6 +7,9 @@ use std::fs;
 use std::path::Path;
 use chrono::{DateTime, Utc};
 
+pub mod client;
+pub mod sync;
+
 /// Project export format
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectExport {
@@ -0,0 +1,409 @@
+// Project Synchronization
+// Diffs and three-way merges shared projects so concurrent edits are not overwritten
+
+use super::client::{self, CloudError};
+use crate::agents::diff_engine::DiffHunk;
+use crate::commands::project::{migrate, ProjectData, CURRENT_SCHEMA_VERSION};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Merge errors
+#[derive(Debug, Error)]
+pub enum MergeError {
+    #[error("Duplicate {target:?} id '{id}' in {side} project")]
+    DuplicateId { target: ChangeTarget, id: String, side: &'static str },
+
+    #[error("Invalid project: {0}")]
+    InvalidProject(String),
+}
+
+/// Part of a project a change applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeTarget {
+    Node,
+    Edge,
+    Setting,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// One changed node, edge or setting, as a hunk of pretty-printed JSON lines
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectChange {
+    pub target: ChangeTarget,
+    pub kind: ChangeKind,
+    pub id: String,
+    pub hunk: DiffHunk,
+}
+
+/// Changes that turn the remote project into the local one
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectDiff {
+    pub changes: Vec<ProjectChange>,
+}
+
+impl ProjectDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn hunks(&self) -> Vec<DiffHunk> {
+        self.changes.iter().map(|c| c.hunk.clone()).collect()
+    }
+
+    pub fn count(&self, target: ChangeTarget, kind: ChangeKind) -> usize {
+        self.changes.iter().filter(|c| c.target == target && c.kind == kind).count()
+    }
+}
+
+/// Same element changed differently on both sides; `None` means deleted or absent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conflict {
+    pub target: ChangeTarget,
+    pub id: String,
+    pub base: Option<Value>,
+    pub local: Option<Value>,
+    pub remote: Option<Value>,
+}
+
+const SETTINGS: [&str; 4] = ["name", "description", "mcu", "language"];
+
+/// Project-level values merged as a whole; the pinout counts as one setting
+fn settings(project: &ProjectData) -> Vec<(String, Value)> {
+    let values = [&project.name, &project.description, &project.mcu, &project.language];
+    SETTINGS.iter()
+        .zip(values)
+        .map(|(key, value)| (key.to_string(), Value::String(value.clone())))
+        .chain(std::iter::once(("pins".to_string(), serde_json::to_value(&project.pins).unwrap_or_default())))
+        .collect()
+}
+
+/// Node/edge `id`, falling back to the array position when missing
+fn element_id(element: &Value, index: usize) -> String {
+    match &element["id"] {
+        Value::String(id) => id.clone(),
+        Value::Number(n) => n.to_string(),
+        _ => format!("#{}", index),
+    }
+}
+
+fn keyed(elements: &[Value]) -> Vec<(String, Value)> {
+    elements.iter()
+        .enumerate()
+        .map(|(i, e)| (element_id(e, i), e.clone()))
+        .collect()
+}
+
+fn json_lines(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(s) => vec![s.clone()],
+        _ => serde_json::to_string_pretty(value)
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_string)
+            .collect(),
+    }
+}
+
+fn diff_elements(target: ChangeTarget, local: &[(String, Value)], remote: &[(String, Value)], changes: &mut Vec<ProjectChange>) {
+    let remote_index: HashMap<&str, (usize, &Value)> = remote.iter()
+        .enumerate()
+        .map(|(i, (id, v))| (id.as_str(), (i, v)))
+        .collect();
+    let local_ids: HashMap<&str, usize> = local.iter()
+        .enumerate()
+        .map(|(i, (id, _))| (id.as_str(), i))
+        .collect();
+
+    for (position, (id, value)) in local.iter().enumerate() {
+        let change = match remote_index.get(id.as_str()) {
+            None => Some((ChangeKind::Added, DiffHunk::insert(position, json_lines(value)))),
+            Some((old, old_value)) if *old_value != value => Some((
+                ChangeKind::Modified,
+                DiffHunk::replace(*old, json_lines(old_value), json_lines(value)),
+            )),
+            Some(_) => None,
+        };
+        if let Some((kind, hunk)) = change {
+            changes.push(ProjectChange { target, kind, id: id.clone(), hunk });
+        }
+    }
+
+    for (position, (id, value)) in remote.iter().enumerate() {
+        if !local_ids.contains_key(id.as_str()) {
+            changes.push(ProjectChange {
+                target,
+                kind: ChangeKind::Removed,
+                id: id.clone(),
+                hunk: DiffHunk::delete(position, json_lines(value)),
+            });
+        }
+    }
+}
+
+/// Added, removed and modified nodes, edges and settings from `remote` to `local`
+pub fn compute_project_diff(local: &ProjectData, remote: &ProjectData) -> ProjectDiff {
+    let mut changes = Vec::new();
+    diff_elements(ChangeTarget::Node, &keyed(&local.nodes), &keyed(&remote.nodes), &mut changes);
+    diff_elements(ChangeTarget::Edge, &keyed(&local.edges), &keyed(&remote.edges), &mut changes);
+    diff_elements(ChangeTarget::Setting, &settings(local), &settings(remote), &mut changes);
+    ProjectDiff { changes }
+}
+
+fn index_unique<'a>(
+    target: ChangeTarget,
+    side: &'static str,
+    elements: &'a [(String, Value)],
+) -> Result<HashMap<&'a str, &'a Value>, MergeError> {
+    let mut index = HashMap::new();
+    for (id, value) in elements {
+        if index.insert(id.as_str(), value).is_some() {
+            return Err(MergeError::DuplicateId { target, id: id.clone(), side });
+        }
+    }
+    Ok(index)
+}
+
+/// Three-way merge of keyed elements, keeping local order with remote additions appended
+fn merge_elements(
+    target: ChangeTarget,
+    base: &[(String, Value)],
+    local: &[(String, Value)],
+    remote: &[(String, Value)],
+    conflicts: &mut Vec<Conflict>,
+) -> Result<Vec<(String, Value)>, MergeError> {
+    let base_index = index_unique(target, "base", base)?;
+    let local_index = index_unique(target, "local", local)?;
+    let remote_index = index_unique(target, "remote", remote)?;
+
+    let ids = local.iter()
+        .chain(remote.iter().filter(|(id, _)| !local_index.contains_key(id.as_str())))
+        .chain(base.iter().filter(|(id, _)| !local_index.contains_key(id.as_str()) && !remote_index.contains_key(id.as_str())))
+        .map(|(id, _)| id.as_str());
+
+    let mut merged = Vec::new();
+    for id in ids {
+        let b = base_index.get(id).copied();
+        let l = local_index.get(id).copied();
+        let r = remote_index.get(id).copied();
+
+        let chosen = if l == r || r == b {
+            l
+        } else if l == b {
+            r
+        } else {
+            // Keep the local side in the merge until the conflict is resolved, so an
+            // element deleted locally but edited remotely stays deleted for now
+            conflicts.push(Conflict {
+                target,
+                id: id.to_string(),
+                base: b.cloned(),
+                local: l.cloned(),
+                remote: r.cloned(),
+            });
+            l
+        };
+        if let Some(value) = chosen {
+            merged.push((id.to_string(), value.clone()));
+        }
+    }
+    Ok(merged)
+}
+
+/// Three-way merge of nodes, edges and settings
+///
+/// Changes made on only one side are applied. Elements changed differently
+/// on both sides are returned as conflicts and keep their local value.
+pub fn merge_projects(
+    base: &ProjectData,
+    local: &ProjectData,
+    remote: &ProjectData,
+) -> Result<(ProjectData, Vec<Conflict>), MergeError> {
+    let mut conflicts = Vec::new();
+    let nodes = merge_elements(ChangeTarget::Node, &keyed(&base.nodes), &keyed(&local.nodes), &keyed(&remote.nodes), &mut conflicts)?;
+    let edges = merge_elements(ChangeTarget::Edge, &keyed(&base.edges), &keyed(&local.edges), &keyed(&remote.edges), &mut conflicts)?;
+    let merged_settings: HashMap<String, Value> = merge_elements(
+        ChangeTarget::Setting,
+        &settings(base),
+        &settings(local),
+        &settings(remote),
+        &mut conflicts,
+    )?.into_iter().collect();
+
+    let setting = |key: &str| merged_settings.get(key)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| MergeError::InvalidProject(format!("missing setting '{}'", key)));
+
+    let merged = ProjectData {
+        schema_version: CURRENT_SCHEMA_VERSION.to_string(),
+        name: setting("name")?,
+        description: setting("description")?,
+        nodes: nodes.into_iter().map(|(_, v)| v).collect(),
+        edges: edges.into_iter().map(|(_, v)| v).collect(),
+        mcu: setting("mcu")?,
+        language: setting("language")?,
+        pins: match merged_settings.get("pins") {
+            Some(pins) => serde_json::from_value(pins.clone()).map_err(|e| MergeError::InvalidProject(e.to_string()))?,
+            None => vec![],
+        },
+    };
+    Ok((merged, conflicts))
+}
+
+/// Parse project JSON, migrating older schema versions
+pub fn parse_project(json: &str) -> Result<ProjectData, MergeError> {
+    let data: Value = serde_json::from_str(json)
+        .map_err(|e| MergeError::InvalidProject(e.to_string()))?;
+    let version = migrate::schema_version(&data);
+    migrate::migrate_project(data, &version, CURRENT_SCHEMA_VERSION)
+        .map_err(|e| MergeError::InvalidProject(e.to_string()))
+}
+
+// ==================== Sync ====================
+
+/// Result of syncing a local project with its shared copy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncOutcome {
+    pub project: ProjectData,
+    pub diff: ProjectDiff,
+    pub conflicts: Vec<Conflict>,
+    pub uploaded: bool,
+    pub url: Option<String>,
+}
+
+/// Last synced copy, the merge base for the next sync: `<project>.nbp.sync-base`
+pub fn sync_base_path(local_path: &Path) -> PathBuf {
+    let mut name = local_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".sync-base");
+    local_path.with_file_name(name)
+}
+
+/// Download, diff, merge and re-upload a shared project
+///
+/// With conflicts nothing is written or uploaded, so the user can resolve
+/// them first. Before the first sync there is no merge base, so nodes and
+/// edges that differ between the two copies are reported as conflicts.
+pub async fn sync_project(local_path: &Path, share_id: &str, server_url: &str) -> Result<SyncOutcome, String> {
+    let read = |path: &Path| std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e));
+    let local = parse_project(&read(local_path)?).map_err(|e| e.to_string())?;
+
+    let remote = match client::download_project(share_id, server_url).await {
+        Ok(json) => Some(parse_project(&json).map_err(|e| e.to_string())?),
+        Err(CloudError::Status { status: 404, .. }) => None,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let (merged, diff, conflicts) = match &remote {
+        Some(remote) => {
+            let base_path = sync_base_path(local_path);
+            let base = if base_path.exists() {
+                parse_project(&read(&base_path)?).map_err(|e| e.to_string())?
+            } else {
+                ProjectData {
+                    nodes: vec![],
+                    edges: vec![],
+                    ..remote.clone()
+                }
+            };
+            let diff = compute_project_diff(&local, remote);
+            let (merged, conflicts) = merge_projects(&base, &local, remote).map_err(|e| e.to_string())?;
+            (merged, diff, conflicts)
+        }
+        None => (local.clone(), ProjectDiff::default(), vec![]),
+    };
+
+    if !conflicts.is_empty() {
+        log::warn!("Sync of {} has {} conflict(s)", share_id, conflicts.len());
+        return Ok(SyncOutcome { project: merged, diff, conflicts, uploaded: false, url: None });
+    }
+
+    let json = serde_json::to_string_pretty(&merged).map_err(|e| e.to_string())?;
+    let url = client::upload_project(share_id, &json, server_url).await.map_err(|e| e.to_string())?;
+    std::fs::write(local_path, &json).map_err(|e| format!("Failed to write project: {}", e))?;
+    std::fs::write(sync_base_path(local_path), &json).map_err(|e| format!("Failed to write sync base: {}", e))?;
+
+    log::info!("Synced {} with {}", local_path.display(), url);
+    Ok(SyncOutcome { project: merged, diff, conflicts, uploaded: true, url: Some(url) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn project(nodes: Vec<Value>, mcu: &str) -> ProjectData {
+        ProjectData {
+            schema_version: CURRENT_SCHEMA_VERSION.to_string(),
+            name: "Blinky".to_string(),
+            description: String::new(),
+            nodes,
+            edges: vec![json!({ "id": "e1", "source": "a", "target": "b" })],
+            mcu: mcu.to_string(),
+            language: "c".to_string(),
+            pins: vec![],
+        }
+    }
+
+    #[test]
+    fn test_compute_project_diff() {
+        let remote = project(vec![json!({ "id": "a", "label": "IDLE" }), json!({ "id": "b", "label": "RUN" })], "STM32F4");
+        let local = project(vec![json!({ "id": "a", "label": "WAIT" }), json!({ "id": "c", "label": "STOP" })], "RP2040");
+
+        let diff = compute_project_diff(&local, &remote);
+        assert_eq!(diff.count(ChangeTarget::Node, ChangeKind::Modified), 1);
+        assert_eq!(diff.count(ChangeTarget::Node, ChangeKind::Added), 1);
+        assert_eq!(diff.count(ChangeTarget::Node, ChangeKind::Removed), 1);
+        assert_eq!(diff.count(ChangeTarget::Setting, ChangeKind::Modified), 1);
+        assert_eq!(diff.count(ChangeTarget::Edge, ChangeKind::Modified), 0);
+
+        let mcu = diff.changes.iter().find(|c| c.target == ChangeTarget::Setting).unwrap();
+        assert_eq!(mcu.hunk.old_lines, vec!["STM32F4"]);
+        assert_eq!(mcu.hunk.new_lines, vec!["RP2040"]);
+        assert!(compute_project_diff(&local, &local).is_empty());
+    }
+
+    #[test]
+    fn test_three_way_merge() {
+        let base = project(vec![json!({ "id": "a", "label": "IDLE" }), json!({ "id": "b", "label": "RUN" })], "STM32F4");
+        // Local renames `a` and adds `c`; remote deletes `b` and changes the MCU
+        let local = project(vec![json!({ "id": "a", "label": "WAIT" }), json!({ "id": "b", "label": "RUN" }), json!({ "id": "c" })], "STM32F4");
+        let remote = project(vec![json!({ "id": "a", "label": "IDLE" })], "ESP32");
+
+        let (merged, conflicts) = merge_projects(&base, &local, &remote).unwrap();
+        assert!(conflicts.is_empty());
+        let ids: Vec<_> = merged.nodes.iter().map(|n| n["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, vec!["a", "c"]);
+        assert_eq!(merged.nodes[0]["label"], "WAIT");
+        assert_eq!(merged.mcu, "ESP32");
+
+        let remote = project(vec![json!({ "id": "a", "label": "SLEEP" }), json!({ "id": "b", "label": "RUN" })], "STM32F4");
+        let (merged, conflicts) = merge_projects(&base, &local, &remote).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].id, "a");
+        assert_eq!(conflicts[0].remote.as_ref().unwrap()["label"], "SLEEP");
+        assert_eq!(merged.nodes[0]["label"], "WAIT");
+
+        // Local deletes `b` while remote edits it: a conflict, and `b` is not brought back
+        let local = project(vec![json!({ "id": "a", "label": "IDLE" })], "STM32F4");
+        let remote = project(vec![json!({ "id": "a", "label": "IDLE" }), json!({ "id": "b", "label": "STOP" })], "STM32F4");
+        let (merged, conflicts) = merge_projects(&base, &local, &remote).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!((conflicts[0].id.as_str(), conflicts[0].local.is_none()), ("b", true));
+        assert_eq!(merged.nodes.len(), 1);
+
+        let duplicate = project(vec![json!({ "id": "a" }), json!({ "id": "a" })], "STM32F4");
+        assert!(matches!(merge_projects(&base, &duplicate, &remote), Err(MergeError::DuplicateId { .. })));
+    }
+}
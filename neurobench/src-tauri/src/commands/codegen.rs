@@ -2,6 +2,7 @@
 
 use serde::{Deserialize, Serialize};
 use crate::core::*;
+use regex::Regex;
 
 /// Supported code generation targets
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -13,6 +14,9 @@ pub enum CodeTarget {
     Python,
     Verilog,
     MicroPython,
+    CircuitPython,
+    #[serde(rename = "rust_embedded")]
+    RustEmbedded,
 }
 
 /// Generate code from FSM
@@ -23,6 +27,7 @@ pub fn generate_code(
 ) -> Result<GeneratedCode, String> {
     log::info!("Generating {:?} code for project: {}", target, project.name);
     
+    let mut manifest = None;
     let code = match target {
         CodeTarget::C => generate_c(&project),
         CodeTarget::Cpp => generate_cpp(&project),
@@ -30,12 +35,25 @@ pub fn generate_code(
         CodeTarget::Python => generate_python(&project),
         CodeTarget::Verilog => generate_verilog(&project),
         CodeTarget::MicroPython => generate_micropython(&project),
+        CodeTarget::CircuitPython => generate_circuitpython(&project),
+        CodeTarget::RustEmbedded => {
+            let (code, cargo_toml) = generate_rust_embedded(&project);
+            manifest = Some(cargo_toml);
+            code
+        }
+    };
+    
+    // Embedded Rust is a binary crate, the code goes in src/main.rs next to Cargo.toml
+    let filename = match target {
+        CodeTarget::RustEmbedded => "main.rs".to_string(),
+        _ => format!("{}.{}", project.name.to_lowercase().replace(" ", "_"), extension_for(target)),
     };
     
     Ok(GeneratedCode {
         target,
-        filename: format!("{}.{}", project.name.to_lowercase().replace(" ", "_"), extension_for(target)),
+        filename,
         code,
+        manifest,
     })
 }
 
@@ -49,14 +67,123 @@ pub fn get_supported_targets() -> Vec<CodeTargetInfo> {
         CodeTargetInfo { target: CodeTarget::Python, name: "Python".to_string(), extension: "py".to_string() },
         CodeTargetInfo { target: CodeTarget::Verilog, name: "Verilog".to_string(), extension: "v".to_string() },
         CodeTargetInfo { target: CodeTarget::MicroPython, name: "MicroPython".to_string(), extension: "py".to_string() },
+        CodeTargetInfo { target: CodeTarget::CircuitPython, name: "CircuitPython".to_string(), extension: "py".to_string() },
+        CodeTargetInfo { target: CodeTarget::RustEmbedded, name: "Rust (embedded HAL)".to_string(), extension: "rs".to_string() },
     ]
 }
 
+/// Diagram formats that can go in a bundle next to the code targets
+const BUNDLE_EXPORTS: &[&str] = &["plantuml", "scxml"];
+
+/// Generate several targets at once, keyed by target name
+///
+/// `targets` takes any code target name plus `plantuml` and `scxml`;
+/// `generate_all` expands to every one of them. With `zip_bundle` the files
+/// are also written to a ZIP archive in the temp directory, one folder per
+/// target, and its path is returned as `zip_path`.
+#[tauri::command]
+pub fn generate_code_bundle(
+    nodes: Vec<FSMNode>,
+    edges: Vec<FSMEdge>,
+    targets: Vec<String>,
+    mcu: String,
+    zip_bundle: Option<bool>,
+) -> Result<serde_json::Value, String> {
+    let mut names: Vec<String> = Vec::new();
+    for target in &targets {
+        let target = target.trim().to_lowercase();
+        let expanded = if target == "generate_all" {
+            get_supported_targets().iter()
+                .map(|t| serde_json::to_value(t.target).ok().and_then(|v| v.as_str().map(String::from)).unwrap_or_default())
+                .chain(BUNDLE_EXPORTS.iter().map(|e| e.to_string()))
+                .collect()
+        } else {
+            vec![target]
+        };
+        for name in expanded {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    if names.is_empty() {
+        return Err("No targets selected".to_string());
+    }
+
+    let graph = super::fsm::graph_from_parts(nodes.clone(), edges.clone())?;
+    let mut project = FSMProject::new("State Machine");
+    project.nodes = nodes;
+    project.edges = edges;
+    project.target_mcu = Some(mcu);
+    let stem = project.name.to_lowercase().replace(' ', "_");
+
+    // (target, files as (path in the archive, contents))
+    let mut bundle: Vec<(String, Vec<(String, String)>)> = Vec::new();
+    for name in names {
+        let files = match name.as_str() {
+            "plantuml" => vec![(format!("{}.puml", stem), crate::core::export::plantuml::to_plantuml(&graph))],
+            "scxml" => vec![(format!("{}.scxml", stem), crate::core::export::scxml::to_scxml(&graph, &project.name))],
+            _ => {
+                let target: CodeTarget = serde_json::from_value(serde_json::Value::String(name.clone()))
+                    .map_err(|_| format!("Unknown target: {}", name))?;
+                let generated = generate_code(project.clone(), target)?;
+                match generated.manifest {
+                    Some(manifest) => vec![
+                        (format!("src/{}", generated.filename), generated.code),
+                        ("Cargo.toml".to_string(), manifest),
+                    ],
+                    None => vec![(generated.filename, generated.code)],
+                }
+            }
+        };
+        bundle.push((name, files));
+    }
+
+    let mut response = serde_json::Map::new();
+    for (name, files) in &bundle {
+        let (filename, code) = &files[0];
+        let mut entry = serde_json::json!({
+            "filename": filename.rsplit('/').next().unwrap_or(filename),
+            "code": code,
+        });
+        if let Some((_, manifest)) = files.get(1) {
+            entry["manifest"] = serde_json::Value::String(manifest.clone());
+        }
+        response.insert(name.clone(), entry);
+    }
+
+    if zip_bundle.unwrap_or(false) {
+        let path = std::env::temp_dir().join(format!("neurobench_bundle_{}.zip", uuid::Uuid::new_v4()));
+        write_zip(&path, &bundle).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        log::info!("Wrote code bundle with {} targets to {}", bundle.len(), path.display());
+        response.insert("zip_path".to_string(), serde_json::Value::String(path.display().to_string()));
+    }
+    Ok(serde_json::Value::Object(response))
+}
+
+fn write_zip(path: &std::path::Path, bundle: &[(String, Vec<(String, String)>)]) -> zip::result::ZipResult<()> {
+    use std::io::Write;
+
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(path)?);
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (target, files) in bundle {
+        for (name, contents) in files {
+            zip.start_file(format!("{}/{}", target, name), options)?;
+            zip.write_all(contents.as_bytes())?;
+        }
+    }
+    zip.finish()?;
+    Ok(())
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GeneratedCode {
     pub target: CodeTarget,
     pub filename: String,
     pub code: String,
+    /// Project manifest to save next to the code (Cargo.toml for embedded Rust)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -70,8 +197,8 @@ fn extension_for(target: CodeTarget) -> &'static str {
     match target {
         CodeTarget::C => "c",
         CodeTarget::Cpp => "cpp",
-        CodeTarget::Rust => "rs",
-        CodeTarget::Python | CodeTarget::MicroPython => "py",
+        CodeTarget::Rust | CodeTarget::RustEmbedded => "rs",
+        CodeTarget::Python | CodeTarget::MicroPython | CodeTarget::CircuitPython => "py",
         CodeTarget::Verilog => "v",
     }
 }
@@ -269,7 +396,671 @@ endmodule
 }
 
 fn generate_micropython(project: &FSMProject) -> String {
-    generate_python(project) // Similar to Python for now
+    generate_python_fsm(project, PythonFlavor::MicroPython)
+}
+
+fn generate_circuitpython(project: &FSMProject) -> String {
+    generate_python_fsm(project, PythonFlavor::CircuitPython)
+}
+
+/// HAL crate family picked from the project's target MCU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RustHal {
+    Stm32f4,
+    EspIdf,
+    Rp2040,
+}
+
+impl RustHal {
+    fn for_mcu(mcu: &str) -> Self {
+        let mcu = mcu.to_uppercase();
+        if mcu.contains("ESP32") {
+            RustHal::EspIdf
+        } else if mcu.contains("RP2040") {
+            RustHal::Rp2040
+        } else {
+            RustHal::Stm32f4
+        }
+    }
+
+    fn crate_name(&self) -> &'static str {
+        match self {
+            RustHal::Stm32f4 => "stm32f4xx-hal",
+            RustHal::EspIdf => "esp-idf-hal",
+            RustHal::Rp2040 => "rp2040-hal",
+        }
+    }
+}
+
+/// Rust target triple for the MCU
+fn rust_target_triple(mcu: &str) -> &'static str {
+    let mcu = mcu.to_uppercase();
+    if mcu.contains("ESP32S3") || mcu.contains("ESP32-S3") {
+        "xtensa-esp32s3-espidf"
+    } else if mcu.contains("ESP32C3") || mcu.contains("ESP32-C3") {
+        "riscv32imc-esp-espidf"
+    } else if mcu.contains("ESP32") {
+        "xtensa-esp32-espidf"
+    } else if mcu.contains("RP2040") {
+        "thumbv6m-none-eabi"
+    } else {
+        "thumbv7em-none-eabihf"
+    }
+}
+
+/// `stm32f4xx-hal` chip feature, e.g. "STM32F407VG" -> "stm32f407"
+fn stm32f4_feature(mcu: &str) -> String {
+    let mcu = mcu.to_lowercase();
+    match mcu.get(..9) {
+        Some(chip) if chip.starts_with("stm32f4") && chip[7..].chars().all(|c| c.is_ascii_digit()) => chip.to_string(),
+        _ => "stm32f411".to_string(),
+    }
+}
+
+/// CamelCase identifier for a state or event label, e.g. "led on" -> "LedOn"
+fn camel_case_ident(label: &str) -> String {
+    let mut name: String = label
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let lower = word.to_lowercase();
+            let mut chars = lower.chars();
+            chars.next().map(|c| c.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+        })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, 'S');
+    }
+    name
+}
+
+/// UPPER_SNAKE identifier for a state label, e.g. "led on" -> "LED_ON"
+fn upper_snake_ident(label: &str) -> String {
+    let name = label
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_uppercase())
+        .collect::<Vec<_>>()
+        .join("_");
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("S_{}", name)
+    } else {
+        name
+    }
+}
+
+/// Unique identifiers for a list of labels, in order
+fn unique_idents<'a>(labels: impl Iterator<Item = &'a str>, ident: fn(&str) -> String) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for label in labels {
+        let base = ident(label);
+        let mut name = base.clone();
+        let mut n = 2;
+        while names.contains(&name) {
+            name = format!("{}{}", base, n);
+            n += 1;
+        }
+        names.push(name);
+    }
+    names
+}
+
+/// Output pin operation recognised in an action statement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PinOp {
+    High,
+    Low,
+    Toggle,
+}
+
+/// Names an action may use for the output pin, `self.` optional
+const OUTPUT_PIN_NAMES: &[&str] = &["led", "output"];
+
+/// Recognise `led = true`, `HAL_GPIO_WritePin(.., GPIO_PIN_SET)`, `led.toggle()` and friends
+///
+/// Assignments and method calls only count when their target is the output pin,
+/// so `ready = true` or `toggle_count = 0` stay TODOs.
+fn pin_op(stmt: &str) -> Option<PinOp> {
+    let compact: String = stmt.to_lowercase().split_whitespace().collect();
+    if compact.starts_with("hal_gpio_togglepin(") {
+        return Some(PinOp::Toggle);
+    }
+    if compact.starts_with("hal_gpio_writepin(") {
+        return match compact.rsplit(',').next()? {
+            "gpio_pin_set)" => Some(PinOp::High),
+            "gpio_pin_reset)" => Some(PinOp::Low),
+            _ => None,
+        };
+    }
+
+    let target = Regex::new(r"^(?:self\.)?([a-z_][a-z0-9_]*)([=.])(.*)$").unwrap();
+    let caps = target.captures(&compact)?;
+    if !OUTPUT_PIN_NAMES.contains(&&caps[1]) {
+        return None;
+    }
+    match (&caps[2], &caps[3]) {
+        ("=", "true" | "1" | "high") => Some(PinOp::High),
+        ("=", "false" | "0" | "low") => Some(PinOp::Low),
+        (".", "toggle()") => Some(PinOp::Toggle),
+        (".", "set_high()" | "high()" | "on()") => Some(PinOp::High),
+        (".", "set_low()" | "low()" | "off()") => Some(PinOp::Low),
+        _ => None,
+    }
+}
+
+/// Input level (`true` = high) a guard waits for, `None` if it doesn't read a pin
+fn guard_pin_level(guard: &str) -> Option<bool> {
+    let compact: String = guard.to_lowercase().split_whitespace().collect();
+    let reads_pin = ["button", "pin", "input", "is_high", "is_low", "hal_gpio_readpin"].iter().any(|k| compact.contains(k));
+    if !reads_pin {
+        return None;
+    }
+    let low = compact.starts_with('!') || compact.contains("is_low") || compact.ends_with("==0")
+        || compact.ends_with("==false") || compact.contains("gpio_pin_reset");
+    Some(!low)
+}
+
+/// Split action code into trimmed statements
+fn action_statements(code: &str) -> impl Iterator<Item = &str> {
+    code.split([';', '\n']).map(str::trim).filter(|stmt| !stmt.is_empty())
+}
+
+/// Translate a node/edge action into HAL pin calls, keeping anything else as a comment
+fn rust_hal_action(code: &str, indent: &str) -> String {
+    action_statements(code)
+        .map(|stmt| match pin_op(stmt) {
+            Some(op) => {
+                let call = match op {
+                    PinOp::High => "self.output.set_high().ok();",
+                    PinOp::Low => "self.output.set_low().ok();",
+                    PinOp::Toggle => "self.output.toggle().ok();",
+                };
+                format!("{}{} // {}", indent, call, stmt)
+            }
+            None => format!("{}// TODO: {}", indent, stmt),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Translate a guard that reads the input pin, `None` if it needs hand-porting
+fn rust_hal_guard(guard: &str) -> Option<&'static str> {
+    guard_pin_level(guard).map(|high| {
+        if high { "self.input.is_high().unwrap_or(false)" } else { "self.input.is_low().unwrap_or(false)" }
+    })
+}
+
+fn generate_rust_embedded(project: &FSMProject) -> (String, String) {
+    let mcu = project.target_mcu.clone().unwrap_or_else(|| "STM32F4".to_string());
+    let hal = RustHal::for_mcu(&mcu);
+    let triple = rust_target_triple(&mcu);
+
+    let states = unique_idents(project.nodes.iter().map(|n| n.label.as_str()), camel_case_ident);
+    let state_of: std::collections::HashMap<_, _> = project.nodes.iter().zip(&states).map(|(n, s)| (n.id, s.as_str())).collect();
+    let initial = project.nodes.iter()
+        .position(|n| n.node_type == NodeType::Input)
+        .map(|i| states[i].clone())
+        .or_else(|| states.first().cloned())
+        .unwrap_or_else(|| "Idle".to_string());
+
+    let mut event_labels: Vec<&str> = Vec::new();
+    for label in project.edges.iter().filter_map(|e| e.label.as_deref()) {
+        if !event_labels.contains(&label) {
+            event_labels.push(label);
+        }
+    }
+    let events = unique_idents(event_labels.iter().copied(), camel_case_ident);
+
+    // Event-triggered transitions first so unlabeled ones don't shadow them
+    let mut edges: Vec<&FSMEdge> = project.edges.iter()
+        .filter(|e| state_of.contains_key(&e.source) && state_of.contains_key(&e.target))
+        .collect();
+    edges.sort_by_key(|e| e.label.is_none());
+
+    let mut arms = Vec::new();
+    let mut guard_fns = Vec::new();
+    for edge in edges {
+        let event = match &edge.label {
+            Some(label) => {
+                let index = event_labels.iter().position(|l| l == label).unwrap();
+                format!("Some(Event::{})", events[index])
+            }
+            None => "_".to_string(),
+        };
+        let guard = edge.guard.as_deref().map(|g| match rust_hal_guard(g) {
+            Some(pin) => format!(" if {}", pin),
+            None => {
+                let name = format!("guard_{}", guard_fns.len());
+                guard_fns.push(format!(
+                    "    /// Guard `{g}`\n    fn {name}(&mut self) -> bool {{\n        // TODO: port `{g}` to Rust\n        false\n    }}"
+                ));
+                format!(" if self.{}()", name)
+            }
+        }).unwrap_or_default();
+        let mut body = format!("                self.on_exit(State::{});\n", state_of[&edge.source]);
+        if let Some(action) = &edge.action {
+            body.push_str(&rust_hal_action(action, "                "));
+            body.push('\n');
+        }
+        arms.push(format!(
+            "            (State::{}, {}){} => {{\n{}                State::{}\n            }}",
+            state_of[&edge.source], event, guard, body, state_of[&edge.target]
+        ));
+    }
+
+    let step_body = if arms.is_empty() {
+        "        let _ = event;".to_string()
+    } else {
+        format!(
+            "        let current = self.state;\n        let next = match (current, event) {{\n{}\n            _ => return,\n        }};\n        self.state = next;\n        self.on_entry(next);",
+            arms.join("\n")
+        )
+    };
+
+    let action_match = |arg: &str, action_of: &dyn Fn(&FSMNode) -> Option<&String>| {
+        let mut arms: Vec<String> = project.nodes.iter().zip(&states)
+            .filter_map(|(n, s)| action_of(n).map(|a| format!("            State::{} => {{\n{}\n            }}", s, rust_hal_action(a, "                "))))
+            .collect();
+        if arms.is_empty() {
+            return format!("        let _ = {};", arg);
+        }
+        if arms.len() < states.len() {
+            arms.push("            _ => {}".to_string());
+        }
+        format!("        match {} {{\n{}\n        }}", arg, arms.join("\n"))
+    };
+    let entry_body = action_match("state", &|n| n.entry_action.as_ref());
+    let exit_body = action_match("state", &|n| n.exit_action.as_ref());
+
+    let (attrs, imports, main_fn) = match hal {
+        RustHal::Stm32f4 => (
+            "\n#![no_std]\n#![no_main]\n",
+            "use cortex_m_rt::entry;\nuse panic_halt as _;\nuse stm32f4xx_hal::{pac, prelude::*};\n",
+            r#"#[entry]
+fn main() -> ! {
+    let dp = pac::Peripherals::take().unwrap();
+    let gpioa = dp.GPIOA.split();
+    let gpioc = dp.GPIOC.split();
+
+    let button = gpioc.pc13.into_pull_up_input();
+    let led = gpioa.pa5.into_push_pull_output();
+    let mut fsm = StateMachine::new(button, led);
+
+    loop {
+        fsm.step(None);
+    }
+}"#,
+        ),
+        RustHal::Rp2040 => (
+            "\n#![no_std]\n#![no_main]\n",
+            "use panic_halt as _;\nuse rp2040_hal::{self as hal, gpio::Pins, pac, Sio};\n\n/// Second-stage bootloader for the W25Q080 flash on the Pico\n#[link_section = \".boot2\"]\n#[used]\npub static BOOT2: [u8; 256] = rp2040_boot2::BOOT_LOADER_W25Q080;\n",
+            r#"#[hal::entry]
+fn main() -> ! {
+    let mut pac = pac::Peripherals::take().unwrap();
+    let sio = Sio::new(pac.SIO);
+    let pins = Pins::new(pac.IO_BANK0, pac.PADS_BANK0, sio.gpio_bank0, &mut pac.RESETS);
+
+    let button = pins.gpio15.into_pull_up_input();
+    let led = pins.gpio25.into_push_pull_output();
+    let mut fsm = StateMachine::new(button, led);
+
+    loop {
+        fsm.step(None);
+    }
+}"#,
+        ),
+        RustHal::EspIdf => (
+            "",
+            "use esp_idf_hal::delay::FreeRtos;\nuse esp_idf_hal::gpio::PinDriver;\nuse esp_idf_hal::peripherals::Peripherals;\n",
+            r#"fn main() {
+    esp_idf_sys::link_patches();
+    let peripherals = Peripherals::take().unwrap();
+
+    let button = PinDriver::input(peripherals.pins.gpio0).unwrap();
+    let led = PinDriver::output(peripherals.pins.gpio2).unwrap();
+    let mut fsm = StateMachine::new(button, led);
+
+    loop {
+        fsm.step(None);
+        FreeRtos::delay_ms(10);
+    }
+}"#,
+        ),
+    };
+
+    let guard_fns = if guard_fns.is_empty() { String::new() } else { format!("\n\n{}", guard_fns.join("\n\n")) };
+    let code = format!(r#"//! {name} - Generated by NeuroBench
+//! Target: {mcu} ({hal_crate}, {triple})
+{attrs}
+use embedded_hal::digital::{{InputPin, StatefulOutputPin}};
+{imports}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {{
+{state_variants}
+}}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {{
+{event_variants}
+}}
+
+/// State machine driving one output pin from one input pin
+pub struct StateMachine<I: InputPin, O: StatefulOutputPin> {{
+    state: State,
+    input: I,
+    output: O,
+}}
+
+impl<I: InputPin, O: StatefulOutputPin> StateMachine<I, O> {{
+    pub fn new(input: I, output: O) -> Self {{
+        let mut fsm = Self {{ state: State::{initial}, input, output }};
+        fsm.on_entry(State::{initial});
+        fsm
+    }}
+
+    pub fn state(&self) -> State {{
+        self.state
+    }}
+
+    /// Run one step; `None` only fires transitions without an event
+    pub fn step(&mut self, event: Option<Event>) {{
+{step_body}
+    }}
+
+    fn on_entry(&mut self, state: State) {{
+{entry_body}
+    }}
+
+    fn on_exit(&mut self, state: State) {{
+{exit_body}
+    }}{guard_fns}
+}}
+
+{main_fn}
+"#,
+        name = project.name,
+        hal_crate = hal.crate_name(),
+        state_variants = states.iter().map(|s| format!("    {},", s)).collect::<Vec<_>>().join("\n"),
+        event_variants = events.iter().map(|e| format!("    {},", e)).collect::<Vec<_>>().join("\n"),
+    );
+
+    let crate_name: String = project.name.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let crate_name = if crate_name.is_empty() { "firmware".to_string() } else { crate_name };
+    let dependencies = match hal {
+        RustHal::Stm32f4 => format!(
+            "cortex-m = {{ version = \"0.7\", features = [\"critical-section-single-core\"] }}\ncortex-m-rt = \"0.7\"\npanic-halt = \"1.0\"\nstm32f4xx-hal = {{ version = \"0.21\", features = [\"{}\"] }}\n",
+            stm32f4_feature(&mcu)
+        ),
+        RustHal::Rp2040 => "cortex-m = \"0.7\"\ncortex-m-rt = \"0.7\"\npanic-halt = \"1.0\"\nrp2040-hal = { version = \"0.10\", features = [\"rt\", \"critical-section-impl\"] }\nrp2040-boot2 = \"0.3\"\n".to_string(),
+        RustHal::EspIdf => "esp-idf-hal = \"0.45\"\nesp-idf-sys = { version = \"0.36\", features = [\"binstart\"] }\n\n[build-dependencies]\nembuild = \"0.33\"\n".to_string(),
+    };
+    let build_note = match hal {
+        RustHal::EspIdf => "# ESP-IDF also needs build.rs calling embuild::espidf::sysenv::output()\n# and the espup toolchain (cargo +esp for Xtensa targets)",
+        _ => "# cortex-m-rt also needs a memory.x with the chip's FLASH and RAM regions",
+    };
+    let cargo_toml = format!(r#"# {name} - Generated by NeuroBench
+# Build: cargo build --release --target {triple}
+{build_note}
+
+[package]
+name = "{crate_name}"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+embedded-hal = "1.0"
+{dependencies}
+[profile.release]
+opt-level = "s"
+debug = true
+lto = true
+
+[package.metadata.neurobench]
+target = "{triple}"
+mcu = "{mcu}"
+"#,
+        name = project.name,
+    );
+
+    (code, cargo_toml)
+}
+
+/// Python flavour for on-device FSM code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PythonFlavor {
+    MicroPython,
+    CircuitPython,
+}
+
+/// Translate a C/JavaScript guard expression into Python
+fn python_expr(expr: &str) -> String {
+    let expr = expr.replace("&&", " and ").replace("||", " or ").replace("!=", "\u{1}");
+    let expr = Regex::new(r"!\s*").unwrap().replace_all(&expr, "not ").replace('\u{1}', "!=");
+    let expr = Regex::new(r"\btrue\b").unwrap().replace_all(&expr, "True");
+    let expr = Regex::new(r"\bfalse\b").unwrap().replace_all(&expr, "False");
+    let expr = Regex::new(r"\bnull\b").unwrap().replace_all(&expr, "None");
+    expr.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Translate a node/edge action into pin calls for the flavour, keeping anything else as a comment
+fn python_action(code: &str, flavor: PythonFlavor, indent: &str) -> Vec<String> {
+    action_statements(code)
+        .map(|stmt| match (pin_op(stmt), flavor) {
+            (Some(PinOp::High), PythonFlavor::MicroPython) => format!("{}self.led.value(1)  # {}", indent, stmt),
+            (Some(PinOp::Low), PythonFlavor::MicroPython) => format!("{}self.led.value(0)  # {}", indent, stmt),
+            (Some(PinOp::Toggle), PythonFlavor::MicroPython) => format!("{}self.led.value(not self.led.value())  # {}", indent, stmt),
+            (Some(PinOp::High), PythonFlavor::CircuitPython) => format!("{}self.led.value = True  # {}", indent, stmt),
+            (Some(PinOp::Low), PythonFlavor::CircuitPython) => format!("{}self.led.value = False  # {}", indent, stmt),
+            (Some(PinOp::Toggle), PythonFlavor::CircuitPython) => format!("{}self.led.value = not self.led.value  # {}", indent, stmt),
+            (None, _) => format!("{}# TODO: {}", indent, stmt),
+        })
+        .collect()
+}
+
+/// Python condition for a guard, reading the button pin when the guard mentions one
+///
+/// Other variables become attributes of the FSM and are added to `inputs`, so
+/// `__init__` can define them.
+fn python_guard(guard: &str, flavor: PythonFlavor, inputs: &mut std::collections::BTreeSet<String>) -> String {
+    match (guard_pin_level(guard), flavor) {
+        (Some(high), PythonFlavor::MicroPython) => format!("self.button.value() == {}", high as u8),
+        (Some(true), PythonFlavor::CircuitPython) => "self.button.value".to_string(),
+        (Some(false), PythonFlavor::CircuitPython) => "not self.button.value".to_string(),
+        (None, _) => python_guard_attributes(&python_expr(guard), inputs),
+    }
+}
+
+/// Prefix the bare variables of a Python expression with `self.`
+///
+/// Keywords, constants, `event`, calls, attributes and string contents are left alone.
+fn python_guard_attributes(expr: &str, inputs: &mut std::collections::BTreeSet<String>) -> String {
+    const RESERVED: &[&str] = &["and", "or", "not", "in", "is", "if", "else", "True", "False", "None", "event", "self"];
+    let chars: Vec<char> = expr.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '"' || c == '\'' {
+            let end = chars[i + 1..].iter().position(|&q| q == c).map_or(chars.len(), |p| i + p + 2);
+            out.extend(&chars[i..end]);
+            i = end;
+        } else if c.is_ascii_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let attribute = start > 0 && chars[start - 1] == '.';
+            let call = chars[i..].iter().find(|c| !c.is_whitespace()) == Some(&'(');
+            if c.is_ascii_digit() || attribute || call || RESERVED.contains(&word.as_str()) {
+                out.push_str(&word);
+            } else {
+                out.push_str("self.");
+                out.push_str(&word);
+                inputs.insert(word);
+            }
+        } else {
+            out.push(c);
+            i += 1;
+        }
+    }
+    out
+}
+
+/// FSM class with one handler method per state and an asyncio task to drive it
+fn generate_python_fsm(project: &FSMProject, flavor: PythonFlavor) -> String {
+    let mcu = project.target_mcu.clone().unwrap_or_else(|| "RP2040".to_string());
+    let esp = mcu.to_uppercase().contains("ESP32");
+    let class_name = camel_case_ident(&project.name);
+    let states = unique_idents(project.nodes.iter().map(|n| n.label.as_str()), upper_snake_ident);
+    let state_of: std::collections::HashMap<_, _> = project.nodes.iter().zip(&states).map(|(n, s)| (n.id, s.as_str())).collect();
+    let initial = project.nodes.iter()
+        .position(|n| n.node_type == NodeType::Input)
+        .map(|i| states[i].clone())
+        .or_else(|| states.first().cloned())
+        .unwrap_or_else(|| "IDLE".to_string());
+
+    let mut handlers = Vec::new();
+    let mut actions = Vec::new();
+    let mut guard_inputs = std::collections::BTreeSet::new();
+    for (node, state) in project.nodes.iter().zip(&states) {
+        let method = state.to_lowercase();
+
+        // Event-triggered transitions first so unconditional ones don't shadow them
+        let mut edges: Vec<&FSMEdge> = project.edges.iter()
+            .filter(|e| e.source == node.id && state_of.contains_key(&e.target))
+            .collect();
+        edges.sort_by_key(|e| e.label.is_none());
+
+        let mut body = Vec::new();
+        for edge in edges {
+            let mut conditions = Vec::new();
+            if let Some(event) = &edge.label {
+                conditions.push(format!("event == \"{}\"", event.replace('"', "\\\"")));
+            }
+            if let Some(guard) = &edge.guard {
+                conditions.push(python_guard(guard, flavor, &mut guard_inputs));
+            }
+            let unconditional = conditions.is_empty();
+            let indent = if unconditional { "        " } else { "            " };
+            if !unconditional {
+                body.push(format!("        if {}:", conditions.join(" and ")));
+            }
+            body.push(format!("{}self.exit_{}()", indent, method));
+            if let Some(action) = &edge.action {
+                body.extend(python_action(action, flavor, indent));
+            }
+            body.push(format!("{}self.enter(self.{})", indent, state_of[&edge.target]));
+            body.push(format!("{}return", indent));
+            if unconditional {
+                break;
+            }
+        }
+        if body.is_empty() {
+            body.push("        pass".to_string());
+        }
+        handlers.push(format!("    def state_{}(self, event):\n{}", method, body.join("\n")));
+
+        for (kind, code) in [("enter", &node.entry_action), ("exit", &node.exit_action)] {
+            let lines = code.as_deref().map(|c| python_action(c, flavor, "        ")).unwrap_or_default();
+            let lines = if lines.is_empty() { "        pass".to_string() } else { lines.join("\n") };
+            actions.push(format!("    def {}_{}(self):\n{}", kind, method, lines));
+        }
+    }
+
+    // Attributes the class already has must not be reset by a guard input
+    let builtin = ["led", "button", "events", "state"];
+    let inputs: String = guard_inputs.iter()
+        .filter(|name| !builtin.contains(&name.as_str()) && !states.contains(name))
+        .map(|name| format!("        self.{} = 0  # guard input, set by the application\n", name))
+        .collect();
+
+    let (title, imports, setup) = match flavor {
+        PythonFlavor::MicroPython => (
+            "MicroPython",
+            "import asyncio\nfrom machine import Pin",
+            if esp {
+                "    led = Pin(2, Pin.OUT)\n    button = Pin(0, Pin.IN, Pin.PULL_UP)"
+            } else {
+                "    led = Pin(\"LED\", Pin.OUT)\n    button = Pin(15, Pin.IN, Pin.PULL_UP)"
+            },
+        ),
+        PythonFlavor::CircuitPython => (
+            "CircuitPython (needs the asyncio library from the Adafruit bundle)",
+            "import asyncio\nimport board\nimport digitalio",
+            if esp {
+                "    led = digitalio.DigitalInOut(board.LED)\n    led.direction = digitalio.Direction.OUTPUT\n    button = digitalio.DigitalInOut(board.IO0)\n    button.switch_to_input(pull=digitalio.Pull.UP)"
+            } else {
+                "    led = digitalio.DigitalInOut(board.LED)\n    led.direction = digitalio.Direction.OUTPUT\n    button = digitalio.DigitalInOut(board.GP15)\n    button.switch_to_input(pull=digitalio.Pull.UP)"
+            },
+        ),
+    };
+
+    format!(r#""""
+{name} - Generated by NeuroBench
+{title} state machine for {mcu}, driven by an asyncio task
+"""
+
+{imports}
+
+
+class {class_name}:
+{state_consts}
+
+    def __init__(self, led, button):
+        self.led = led
+        self.button = button
+        self.events = []
+{inputs}        self.state = self.{initial}
+        self.enter_{initial_method}()
+
+    def post(self, event):
+        """Queue an event for the next step (safe to call from a pin IRQ)"""
+        self.events.append(event)
+
+    def step(self):
+        """Handle one queued event, or check the event-less transitions"""
+        event = self.events.pop(0) if self.events else None
+        getattr(self, "state_" + self.state.lower())(event)
+
+    def enter(self, state):
+        self.state = state
+        getattr(self, "enter_" + state.lower())()
+
+    async def run(self, period_ms=10):
+        """asyncio task: step the machine every period_ms"""
+        while True:
+            self.step()
+            await asyncio.sleep(period_ms / 1000)
+
+    # --- State handlers ---
+
+{handlers}
+
+    # --- Entry/exit actions ---
+
+{actions}
+
+
+async def main():
+{setup}
+    fsm = {class_name}(led, button)
+    task = asyncio.create_task(fsm.run())
+    await asyncio.gather(task)
+
+
+asyncio.run(main())
+"#,
+        name = project.name,
+        state_consts = states.iter().map(|s| format!("    {} = \"{}\"", s, s)).collect::<Vec<_>>().join("\n"),
+        initial_method = initial.to_lowercase(),
+        handlers = handlers.join("\n\n"),
+        actions = actions.join("\n\n"),
+    )
 }
 
 fn generate_switch_cases(nodes: &[FSMNode]) -> String {
@@ -281,3 +1072,116 @@ fn generate_switch_cases(nodes: &[FSMNode]) -> String {
         .collect::<Vec<_>>()
         .join("\n")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blinky(mcu: &str) -> FSMProject {
+        let mut project = FSMProject::new("Blinky Demo");
+        project.target_mcu = Some(mcu.to_string());
+        let off = FSMNode::new("LED_OFF", NodeType::Input).with_entry_action("led = false;");
+        let on = FSMNode::new("led on", NodeType::Process);
+        project.edges.push(FSMEdge::new(off.id, on.id).with_label("PRESS").with_action("HAL_GPIO_WritePin(GPIOA, GPIO_PIN_5, GPIO_PIN_SET);"));
+        project.edges.push(FSMEdge::new(on.id, off.id).with_guard("!button"));
+        project.edges.push(FSMEdge::new(on.id, on.id).with_guard("count > 10"));
+        project.nodes = vec![off, on];
+        project
+    }
+
+    #[test]
+    fn test_code_bundle() {
+        let project = blinky("STM32F407VG");
+        let targets = vec!["C".to_string(), "scxml".to_string(), "rust_embedded".to_string()];
+        let bundle = generate_code_bundle(project.nodes.clone(), project.edges.clone(), targets, "STM32F407VG".to_string(), None).unwrap();
+        assert_eq!(bundle["c"]["filename"], "state_machine.c");
+        assert!(bundle["c"]["code"].as_str().unwrap().contains("void fsm_process_event(uint8_t event)"));
+        assert!(bundle["scxml"]["code"].as_str().unwrap().contains("initial=\"LED_OFF\""));
+        assert!(bundle["rust_embedded"]["manifest"].as_str().unwrap().contains("stm32f4xx-hal"));
+        assert!(bundle.get("zip_path").is_none());
+
+        let all = generate_code_bundle(project.nodes.clone(), project.edges.clone(), vec!["generate_all".to_string()], "RP2040".to_string(), Some(true)).unwrap();
+        assert_eq!(all.as_object().unwrap().len(), get_supported_targets().len() + BUNDLE_EXPORTS.len() + 1);
+        let path = std::path::PathBuf::from(all["zip_path"].as_str().unwrap());
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        assert!(archive.by_name("plantuml/state_machine.puml").is_ok());
+        assert!(archive.by_name("rust_embedded/src/main.rs").is_ok());
+        assert!(archive.by_name("rust_embedded/Cargo.toml").is_ok());
+        drop(archive);
+        std::fs::remove_file(path).unwrap();
+
+        assert!(generate_code_bundle(project.nodes, project.edges, vec!["cobol".to_string()], String::new(), None).is_err());
+    }
+
+    #[test]
+    fn test_rust_embedded_stm32() {
+        let generated = generate_code(blinky("STM32F407VG"), CodeTarget::RustEmbedded).unwrap();
+        let code = &generated.code;
+        assert_eq!(generated.filename, "main.rs");
+        assert!(code.contains("pub enum State {\n    LedOff,\n    LedOn,\n}"));
+        assert!(code.contains("(State::LedOff, Some(Event::Press)) => {"));
+        assert!(code.contains("self.output.set_high().ok();"));
+        assert!(code.contains("(State::LedOn, _) if self.input.is_low().unwrap_or(false) => {"));
+        assert!(code.contains("fn guard_0(&mut self) -> bool"));
+        assert!(code.contains("State::LedOff => {\n                self.output.set_low().ok();"));
+        assert!(code.contains("use stm32f4xx_hal::{pac, prelude::*};"));
+
+        assert_eq!(pin_op("self.led.toggle()"), Some(PinOp::Toggle));
+        assert_eq!(pin_op("output = 1"), Some(PinOp::High));
+        assert_eq!(pin_op("HAL_GPIO_WritePin(GPIOA, GPIO_PIN_5, GPIO_PIN_RESET)"), Some(PinOp::Low));
+        assert_eq!(pin_op("ready = true"), None);
+        assert_eq!(pin_op("toggle_count = 0"), None);
+        assert_eq!(pin_op("led == 1"), None);
+        assert_eq!(rust_hal_action("error_flag = 1", ""), "// TODO: error_flag = 1");
+
+        let manifest = generated.manifest.unwrap();
+        assert!(manifest.contains("name = \"blinky-demo\""));
+        assert!(manifest.contains("features = [\"stm32f407\"]"));
+        assert!(manifest.contains("--target thumbv7em-none-eabihf"));
+    }
+
+    #[test]
+    fn test_rust_embedded_hal_selection() {
+        let rp = generate_code(blinky("RP2040"), CodeTarget::RustEmbedded).unwrap();
+        assert!(rp.code.contains("#[hal::entry]"));
+        assert!(rp.manifest.unwrap().contains("rp2040-hal"));
+
+        let esp = generate_code(blinky("ESP32-C3"), CodeTarget::RustEmbedded).unwrap();
+        assert!(esp.code.contains("PinDriver::output"));
+        assert!(!esp.code.contains("#![no_std]"));
+        assert!(esp.manifest.unwrap().contains("riscv32imc-esp-espidf"));
+
+        assert!(get_supported_targets().iter().any(|t| t.target == CodeTarget::RustEmbedded));
+        assert_eq!(serde_json::to_value(CodeTarget::RustEmbedded).unwrap(), "rust_embedded");
+    }
+
+    #[test]
+    fn test_python_fsm_flavors() {
+        let micro = generate_code(blinky("RP2040"), CodeTarget::MicroPython).unwrap().code;
+        assert!(micro.contains("class BlinkyDemo:"));
+        assert!(micro.contains("    LED_OFF = \"LED_OFF\""));
+        assert!(micro.contains("    def state_led_off(self, event):\n        if event == \"PRESS\":"));
+        assert!(micro.contains("            self.led.value(1)  # HAL_GPIO_WritePin"));
+        assert!(micro.contains("        if self.button.value() == 0:"));
+        assert!(micro.contains("        if self.count > 10:"));
+        assert!(micro.contains("        self.events = []\n        self.count = 0  # guard input, set by the application\n"));
+        assert!(micro.contains("    def enter_led_off(self):\n        self.led.value(0)"));
+        assert!(micro.contains("asyncio.create_task(fsm.run())"));
+        assert!(micro.contains("from machine import Pin"));
+
+        let circuit = generate_code(blinky("RP2040"), CodeTarget::CircuitPython).unwrap().code;
+        assert!(circuit.contains("import digitalio"));
+        assert!(circuit.contains("if not self.button.value:"));
+        assert_eq!(python_expr("!ready && (x != 1 || done == true)"), "not ready and (x != 1 or done == True)");
+        let mut inputs = std::collections::BTreeSet::new();
+        assert_eq!(
+            python_guard_attributes("not ready and abs(cfg.limit - x) > 0x10 and event != \"x\"", &mut inputs),
+            "not self.ready and abs(self.cfg.limit - self.x) > 0x10 and event != \"x\""
+        );
+        assert_eq!(inputs.into_iter().collect::<Vec<_>>(), vec!["cfg", "ready", "x"]);
+
+        // Only the output pin becomes a pin write; other flags stay for hand-porting
+        let lines = python_action("ready = true; self.led = 0", PythonFlavor::MicroPython, "");
+        assert_eq!(lines, vec!["# TODO: ready = true".to_string(), "self.led.value(0)  # self.led = 0".to_string()]);
+    }
+}
@@ -2,6 +2,31 @@
 
 use crate::core::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::Instant;
+
+/// What a stepped simulation carries from one `simulate_step` call to the next
+#[derive(Debug, Default)]
+struct SimulationSession {
+    /// Variables that transition guards are evaluated against
+    variables: SimulationContext,
+    /// State the last step ended in and when it was entered, so `elapsed_ms()`
+    /// keeps counting while the simulation stays in that state
+    state_entered: Option<(NodeId, Instant)>,
+}
+
+lazy_static::lazy_static! {
+    static ref SIMULATION: RwLock<SimulationSession> = RwLock::new(SimulationSession::default());
+
+    /// Settings applied to every simulation step, e.g. the action language
+    static ref SIMULATION_CONFIG: RwLock<SimulationConfig> = RwLock::new(SimulationConfig::default());
+}
+
+/// Current simulation variables, for seeding an executor context
+pub fn simulation_variables() -> SimulationContext {
+    SIMULATION.read().map(|session| session.variables.clone()).unwrap_or_default()
+}
 
 /// Add a node to the FSM
 #[tauri::command]
@@ -77,15 +102,149 @@ pub fn update_edge(
     Ok(true)
 }
 
-/// Execute a single simulation step
+/// Execute a single simulation step from `current_node` (the start node when absent)
+///
+/// Guards see the variables from `set_simulation_variables`; actions that change
+/// them are kept for the next step. `elapsed_ms()` counts from when the
+/// simulation entered its current state, across calls.
 #[tauri::command]
-pub fn simulate_step() -> Result<SimulationStepResult, String> {
-    log::debug!("Simulation step requested");
+pub fn simulate_step(
+    nodes: Vec<FSMNode>,
+    edges: Vec<FSMEdge>,
+    current_node: Option<String>,
+    event: Option<String>,
+) -> Result<SimulationStepResult, String> {
+    let config = SIMULATION_CONFIG.read().map_err(|e| e.to_string())?.clone();
+    let mut session = SIMULATION.write().map_err(|e| e.to_string())?;
+    step_session(&mut session, config, nodes, edges, current_node, event.as_deref())
+}
+
+fn step_session(
+    session: &mut SimulationSession,
+    config: SimulationConfig,
+    nodes: Vec<FSMNode>,
+    edges: Vec<FSMEdge>,
+    current_node: Option<String>,
+    event: Option<&str>,
+) -> Result<SimulationStepResult, String> {
+    let mut executor = FSMExecutor::new(graph_from_parts(nodes, edges)?).with_config(config);
+    match current_node {
+        Some(id) => executor.start_at(id.parse().map_err(|_| "Invalid node ID")?)?,
+        None => executor.start()?,
+    }
+    if let Some((node, entered)) = session.state_entered {
+        if executor.current_node() == Some(node) {
+            executor.set_state_entered(entered);
+        }
+    }
+    
+    let (_, vars, _) = executor.simulate_step_with_actions(event, session.variables.clone())?;
+    session.variables = vars;
+    session.state_entered = executor.current_node().map(|node| (node, executor.state_entered()));
+    
+    log::debug!("Simulation step to {:?}", executor.current_node());
     Ok(SimulationStepResult {
-        status: SimulationStatus::Stepping,
-        current_node: None,
-        step_count: 0,
-        logs: vec![],
+        status: executor.status(),
+        current_node: executor.current_node().map(|id| id.to_string()),
+        step_count: executor.step_count(),
+        logs: executor.logs().to_vec(),
+    })
+}
+
+/// Replace the variables that transition guards are evaluated against
+#[tauri::command]
+pub fn set_simulation_variables(vars: HashMap<String, serde_json::Value>) -> Result<usize, String> {
+    let mut session = SIMULATION.write().map_err(|e| e.to_string())?;
+    log::debug!("Setting {} simulation variables", vars.len());
+    session.variables = vars;
+    Ok(session.variables.len())
+}
+
+/// Current simulation settings
+#[tauri::command]
+pub fn get_simulation_config() -> Result<SimulationConfig, String> {
+    Ok(SIMULATION_CONFIG.read().map_err(|e| e.to_string())?.clone())
+}
+
+/// Replace the simulation settings used by later `simulate_step` calls
+#[tauri::command]
+pub fn set_simulation_config(config: SimulationConfig) -> Result<SimulationConfig, String> {
+    if config.simulation_action_language == ActionLanguage::Lua && !cfg!(feature = "lua") {
+        return Err(crate::core::engine::actions::ActionError::LuaUnavailable.to_string());
+    }
+    log::debug!("Simulation actions run as {:?}", config.simulation_action_language);
+    *SIMULATION_CONFIG.write().map_err(|e| e.to_string())? = config.clone();
+    Ok(config)
+}
+
+/// Build a graph from the editor's nodes and edges, rejecting dangling edges
+pub(super) fn graph_from_parts(nodes: Vec<FSMNode>, edges: Vec<FSMEdge>) -> Result<FSMGraph, String> {
+    let mut graph = FSMGraph::new();
+    for node in nodes {
+        graph.add_node(node);
+    }
+    for edge in edges {
+        if graph.get_node(edge.source).is_none() || graph.get_node(edge.target).is_none() {
+            return Err(format!("Edge {} references a missing node", edge.id));
+        }
+        graph.add_edge(edge);
+    }
+    Ok(graph)
+}
+
+/// Export the FSM as a Graphviz DOT document
+#[tauri::command]
+pub fn fsm_export_dot(nodes: Vec<FSMNode>, edges: Vec<FSMEdge>) -> Result<String, String> {
+    Ok(graph_from_parts(nodes, edges)?.to_dot())
+}
+
+/// Import nodes and edges from a Graphviz DOT document
+#[tauri::command]
+pub fn fsm_import_dot(dot: String) -> Result<(Vec<FSMNode>, Vec<FSMEdge>), String> {
+    let graph = FSMGraph::from_dot(&dot).map_err(|e| e.to_string())?;
+    log::info!("Imported {} nodes and {} edges from DOT", graph.node_count(), graph.edge_count());
+    Ok((graph.nodes().cloned().collect(), graph.edges().cloned().collect()))
+}
+
+/// Export the FSM as a PlantUML state diagram
+#[tauri::command]
+pub fn fsm_export_plantuml(nodes: Vec<FSMNode>, edges: Vec<FSMEdge>) -> Result<String, String> {
+    Ok(crate::core::export::plantuml::to_plantuml(&graph_from_parts(nodes, edges)?))
+}
+
+/// Import nodes and edges from a PlantUML state diagram
+#[tauri::command]
+pub fn fsm_import_plantuml(text: String) -> Result<(Vec<FSMNode>, Vec<FSMEdge>), String> {
+    let graph = crate::core::export::plantuml::from_plantuml(&text).map_err(|e| e.to_string())?;
+    log::info!("Imported {} states and {} transitions from PlantUML", graph.node_count(), graph.edge_count());
+    Ok((graph.nodes().cloned().collect(), graph.edges().cloned().collect()))
+}
+
+/// FSM recovered from C source, with how confident the import is
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CImportResult {
+    pub nodes: Vec<FSMNode>,
+    pub edges: Vec<FSMEdge>,
+    pub state_variable: String,
+    pub confidence: f32,
+}
+
+/// Import nodes and edges from a hand-written C `switch (state)` machine
+#[tauri::command]
+pub fn fsm_import_from_c(code: String) -> Result<CImportResult, String> {
+    let import = crate::core::import::c_fsm::import_c_fsm(&code).map_err(|e| e.to_string())?;
+    log::info!(
+        "Imported {} states and {} transitions from C switch on {} (confidence {:.2})",
+        import.graph.node_count(),
+        import.graph.edge_count(),
+        import.state_variable,
+        import.confidence
+    );
+    Ok(CImportResult {
+        nodes: import.graph.nodes().cloned().collect(),
+        edges: import.graph.edges().cloned().collect(),
+        state_variable: import.state_variable,
+        confidence: import.confidence,
     })
 }
 
@@ -99,6 +258,7 @@ pub fn simulate_run() -> Result<SimulationStatus, String> {
 /// Stop simulation
 #[tauri::command]
 pub fn simulate_stop() -> Result<SimulationStatus, String> {
+    SIMULATION.write().map_err(|e| e.to_string())?.state_entered = None;
     log::info!("Simulation stopped");
     Ok(SimulationStatus::Idle)
 }
@@ -110,3 +270,56 @@ pub struct SimulationStepResult {
     pub step_count: u64,
     pub logs: Vec<LogEntry>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn test_simulate_step_uses_simulation_variables() {
+        let idle = FSMNode::new("IDLE", NodeType::Input);
+        let armed = FSMNode::new("ARMED", NodeType::Output);
+        let edge = FSMEdge::new(idle.id, armed.id).with_guard("armed");
+        let nodes = vec![idle, armed.clone()];
+        let edges = vec![edge];
+        let step = |session: &mut SimulationSession| {
+            step_session(session, SimulationConfig::default(), nodes.clone(), edges.clone(), None, None).unwrap()
+        };
+
+        let mut session = SimulationSession::default();
+        session.variables.insert("armed".to_string(), serde_json::json!(false));
+        assert_eq!(step(&mut session).step_count, 0);
+
+        session.variables.insert("armed".to_string(), serde_json::json!(true));
+        let stepped = step(&mut session);
+        assert_eq!(stepped.current_node, Some(armed.id.to_string()));
+        assert_eq!(stepped.step_count, 1);
+    }
+
+    #[test]
+    fn test_elapsed_ms_spans_steps() {
+        let idle = FSMNode::new("IDLE", NodeType::Input);
+        let done = FSMNode::new("DONE", NodeType::Output);
+        let nodes = vec![idle.clone(), done.clone()];
+        let edges = vec![FSMEdge::new(idle.id, done.id).with_guard("elapsed_ms() > 1000")];
+        let step = |session: &mut SimulationSession| {
+            step_session(session, SimulationConfig::default(), nodes.clone(), edges.clone(), Some(idle.id.to_string()), None)
+                .unwrap()
+        };
+
+        // Entering IDLE starts the clock, which a blocked step leaves running
+        let mut session = SimulationSession::default();
+        assert_eq!(step(&mut session).step_count, 0);
+        let (node, entered) = session.state_entered.unwrap();
+        assert_eq!(node, idle.id);
+        step(&mut session);
+        assert_eq!(session.state_entered.unwrap().1, entered);
+
+        session.state_entered = Some((idle.id, Instant::now() - Duration::from_millis(1500)));
+        let stepped = step(&mut session);
+        assert_eq!(stepped.current_node, Some(done.id.to_string()));
+        assert_eq!(session.state_entered.unwrap().0, done.id);
+    }
+}
@@ -0,0 +1,168 @@
+// Firmware Metadata
+// Reads version information embedded in ELF images
+
+use object::{Object, ObjectSection, ObjectSymbol};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::Path;
+use thiserror::Error;
+
+/// Version information baked into a firmware image
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FirmwareMetadata {
+    pub version: String,
+    pub build_date: String,
+    pub git_hash: String,
+    pub mcu_target: String,
+}
+
+#[derive(Debug, Error)]
+pub enum ElfError {
+    #[error("Failed to read ELF: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid ELF file: {0}")]
+    Parse(String),
+
+    #[error("No .metadata section or _firmware_version symbol found")]
+    NoMetadata,
+}
+
+/// Read firmware metadata from a `.metadata` section, or from
+/// `_firmware_version` and the related `_firmware_*` string symbols
+pub fn read_firmware_metadata(elf_path: &Path) -> Result<FirmwareMetadata, ElfError> {
+    let bytes = std::fs::read(elf_path)?;
+    let file = object::File::parse(bytes.as_slice()).map_err(|e| ElfError::Parse(e.to_string()))?;
+
+    if let Some(section) = file.section_by_name(".metadata") {
+        let data = section.data().map_err(|e| ElfError::Parse(e.to_string()))?;
+        return parse_metadata_block(data);
+    }
+
+    let version = symbol_string(&file, "_firmware_version").ok_or(ElfError::NoMetadata)?;
+    Ok(FirmwareMetadata {
+        version,
+        build_date: symbol_string(&file, "_firmware_build_date").unwrap_or_default(),
+        git_hash: symbol_string(&file, "_firmware_git_hash").unwrap_or_default(),
+        mcu_target: symbol_string(&file, "_firmware_mcu_target").unwrap_or_default(),
+    })
+}
+
+/// NUL-terminated string stored at a symbol's address
+fn symbol_string(file: &object::File, name: &str) -> Option<String> {
+    let symbol = file.symbols().find(|s| s.name() == Ok(name))?;
+    let address = symbol.address();
+    let section = file.sections().find(|s| address >= s.address() && address < s.address() + s.size())?;
+    let data = section.data().ok()?;
+    let start = (address - section.address()) as usize;
+    let bytes = data.get(start..)?;
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len()).min(256);
+    let text = String::from_utf8_lossy(&bytes[..end]).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// Parse `key=value` (or `key: value`) entries separated by newlines or NULs
+pub fn parse_metadata_block(data: &[u8]) -> Result<FirmwareMetadata, ElfError> {
+    let text = String::from_utf8_lossy(data);
+    let mut metadata = FirmwareMetadata::default();
+    for entry in text.split(['\n', '\0']) {
+        let Some((key, value)) = entry.split_once('=').or_else(|| entry.split_once(':')) else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "version" | "fw_version" => metadata.version = value,
+            "build_date" | "date" => metadata.build_date = value,
+            "git_hash" | "git" | "commit" => metadata.git_hash = value,
+            "mcu_target" | "mcu" | "target" => metadata.mcu_target = value,
+            _ => {}
+        }
+    }
+    if metadata.version.is_empty() {
+        return Err(ElfError::NoMetadata);
+    }
+    Ok(metadata)
+}
+
+/// Compare dotted versions numerically, ignoring a `v` prefix and `-`/`+` suffixes
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn parts(version: &str) -> Vec<u64> {
+        let core = version.trim().trim_start_matches(['v', 'V']);
+        let core = core.split(['-', '+']).next().unwrap_or_default();
+        core.split('.').map(|p| p.parse().unwrap_or(0)).collect()
+    }
+    let (a, b) = (parts(a), parts(b));
+    for i in 0..a.len().max(b.len()) {
+        match a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0)) {
+            Ordering::Equal => continue,
+            other => return other,
+        }
+    }
+    Ordering::Equal
+}
+
+/// Find the version a running firmware reports in its RTT output
+pub fn version_from_rtt<'a>(lines: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let re = regex::Regex::new(r"(?i)version\s*[:=]?\s*(v?\d+(?:\.\d+)*(?:[-+][\w.]+)?)").ok()?;
+    lines.into_iter()
+        .filter_map(|line| re.captures(line).map(|c| c[1].to_string()))
+        .last()
+}
+
+/// Result of comparing an ELF against the firmware on the device
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionComparison {
+    pub elf_version: String,
+    pub device_version: String,
+    /// "newer", "same" or "older", describing the ELF relative to the device
+    pub relation: &'static str,
+    pub is_downgrade: bool,
+}
+
+pub fn compare_firmware(metadata: &FirmwareMetadata, device_version: &str) -> VersionComparison {
+    let ordering = compare_versions(&metadata.version, device_version);
+    VersionComparison {
+        elf_version: metadata.version.clone(),
+        device_version: device_version.to_string(),
+        relation: match ordering {
+            Ordering::Greater => "newer",
+            Ordering::Equal => "same",
+            Ordering::Less => "older",
+        },
+        is_downgrade: ordering == Ordering::Less,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_metadata_block() {
+        let block = b"version=1.4.2\0build_date=2026-03-01\0git_hash=a1b2c3d\0mcu_target=STM32F407VG\0\0\0";
+        let metadata = parse_metadata_block(block).unwrap();
+        assert_eq!(metadata.version, "1.4.2");
+        assert_eq!(metadata.git_hash, "a1b2c3d");
+        assert_eq!(metadata.mcu_target, "STM32F407VG");
+        assert!(matches!(parse_metadata_block(b"build_date=today"), Err(ElfError::NoMetadata)));
+
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(tmp.path(), b"not an elf").unwrap();
+        assert!(matches!(read_firmware_metadata(tmp.path()), Err(ElfError::Parse(_))));
+    }
+
+    #[test]
+    fn test_downgrade_detection() {
+        assert_eq!(compare_versions("v1.10.0", "1.9.3"), Ordering::Greater);
+        assert_eq!(compare_versions("1.2", "1.2.0-rc1"), Ordering::Equal);
+
+        let rtt = ["boot ok", "Firmware version: 2.0.1", "tick"];
+        let device = version_from_rtt(rtt).unwrap();
+        assert_eq!(device, "2.0.1");
+
+        let elf = FirmwareMetadata { version: "1.9.0".to_string(), ..Default::default() };
+        let comparison = compare_firmware(&elf, &device);
+        assert_eq!(comparison.relation, "older");
+        assert!(comparison.is_downgrade);
+    }
+}
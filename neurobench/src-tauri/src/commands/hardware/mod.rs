@@ -0,0 +1,199 @@
+// Hardware Interface Commands
+
+pub mod elf_info;
+
+use crate::hal::usb_db::{parse_usb_id, DeviceType, Protocol, UsbDatabase};
+use serde::{Deserialize, Serialize};
+
+/// Detected hardware device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectedDevice {
+    pub name: String,
+    pub port: String,
+    pub vid: String,
+    pub pid: String,
+    pub serial: Option<String>,
+    pub device_type: DeviceType,
+    pub protocols: Vec<Protocol>,
+}
+
+/// Telemetry data from connected device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceTelemetry {
+    pub cpu_load: f32,
+    pub memory_used: u32,
+    pub memory_total: u32,
+    pub current_state: Option<String>,
+    pub uptime_ms: u64,
+}
+
+/// List available serial ports
+#[tauri::command]
+pub fn list_serial_ports() -> Result<Vec<serde_json::Value>, String> {
+    match serialport::available_ports() {
+        Ok(ports) => {
+            let result: Vec<serde_json::Value> = ports.iter().map(|p| {
+                let port_type = match &p.port_type {
+                    serialport::SerialPortType::UsbPort(info) => {
+                        serde_json::json!({
+                            "type": "USB",
+                            "vid": format!("{:04X}", info.vid),
+                            "pid": format!("{:04X}", info.pid),
+                            "manufacturer": info.manufacturer.clone().unwrap_or_default(),
+                            "product": info.product.clone().unwrap_or_default(),
+                            "serial": info.serial_number.clone().unwrap_or_default(),
+                        })
+                    },
+                    serialport::SerialPortType::PciPort => serde_json::json!({"type": "PCI"}),
+                    serialport::SerialPortType::BluetoothPort => serde_json::json!({"type": "Bluetooth"}),
+                    serialport::SerialPortType::Unknown => serde_json::json!({"type": "Unknown"}),
+                };
+                serde_json::json!({
+                    "name": p.port_name,
+                    "info": port_type,
+                })
+            }).collect();
+            log::info!("Found {} serial ports", result.len());
+            Ok(result)
+        },
+        Err(e) => Err(format!("Failed to list ports: {}", e)),
+    }
+}
+
+/// Match USB ports from `list_serial_ports` against the device database
+pub fn identify_ports(ports: &[serde_json::Value], db: &UsbDatabase) -> Vec<DetectedDevice> {
+    ports.iter().filter_map(|port| {
+        let info = &port["info"];
+        if info["type"] != "USB" {
+            return None;
+        }
+        let vid = info["vid"].as_str()?;
+        let pid = info["pid"].as_str()?;
+        let serial = info["serial"].as_str().filter(|s| !s.is_empty()).map(String::from);
+        let entry = db.lookup(parse_usb_id(vid).ok()?, parse_usb_id(pid).ok()?);
+
+        Some(DetectedDevice {
+            name: match entry {
+                Some(entry) => entry.name.clone(),
+                None => info["product"].as_str().filter(|s| !s.is_empty()).unwrap_or("Unknown USB device").to_string(),
+            },
+            port: port["name"].as_str().unwrap_or_default().to_string(),
+            vid: vid.to_string(),
+            pid: pid.to_string(),
+            serial,
+            device_type: entry.map_or(DeviceType::Unknown, |e| e.device_type),
+            protocols: entry.map(|e| e.protocols.clone()).unwrap_or_default(),
+        })
+    }).collect()
+}
+
+/// Detect connected debug probes and USB-serial adapters
+#[tauri::command]
+pub fn detect_devices() -> Result<Vec<DetectedDevice>, String> {
+    log::info!("Scanning for connected devices...");
+    let ports = list_serial_ports()?;
+    let devices = identify_ports(&ports, UsbDatabase::bundled());
+    log::info!("Found {} devices", devices.len());
+    Ok(devices)
+}
+
+/// Look up a device in the USB database by hex VID/PID
+#[tauri::command]
+pub fn hardware_get_device_info(vid: String, pid: String) -> Result<serde_json::Value, String> {
+    let entry = UsbDatabase::bundled()
+        .lookup(parse_usb_id(&vid)?, parse_usb_id(&pid)?)
+        .ok_or_else(|| format!("Unknown USB device {}:{}", vid, pid))?;
+    serde_json::to_value(entry).map_err(|e| e.to_string())
+}
+
+/// Read the version metadata embedded in an ELF
+#[tauri::command]
+pub fn hardware_read_elf_metadata(elf_path: String) -> Result<serde_json::Value, String> {
+    let metadata = elf_info::read_firmware_metadata(std::path::Path::new(&elf_path))
+        .map_err(|e| e.to_string())?;
+    serde_json::to_value(metadata).map_err(|e| e.to_string())
+}
+
+/// Connect to a specific device
+#[tauri::command]
+pub fn connect_device(device_id: String) -> Result<bool, String> {
+    log::info!("Connecting to device: {}", device_id);
+    
+    // TODO: Implement actual connection with probe-rs
+    
+    Ok(true)
+}
+
+/// Disconnect from current device
+#[tauri::command]
+pub fn disconnect_device() -> Result<bool, String> {
+    log::info!("Disconnecting from current device");
+    
+    // TODO: Implement actual disconnection
+    
+    Ok(true)
+}
+
+/// Flash firmware to connected device
+#[tauri::command]
+pub fn flash_firmware(firmware_path: String, device_id: String) -> Result<FlashResult, String> {
+    log::info!("Flashing firmware {} to device {}", firmware_path, device_id);
+    
+    // TODO: Implement actual flashing with probe-rs
+    
+    Ok(FlashResult {
+        success: true,
+        bytes_written: 0,
+        duration_ms: 0,
+        message: "Flash simulation - probe-rs not yet enabled".to_string(),
+    })
+}
+
+/// Read telemetry from connected device
+#[tauri::command]
+pub fn read_telemetry() -> Result<DeviceTelemetry, String> {
+    log::debug!("Reading telemetry from device");
+    
+    // TODO: Implement actual telemetry reading via RTT
+    
+    Ok(DeviceTelemetry {
+        cpu_load: 12.5,
+        memory_used: 8192,
+        memory_total: 65536,
+        current_state: Some("IDLE".to_string()),
+        uptime_ms: 123456,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlashResult {
+    pub success: bool,
+    pub bytes_written: usize,
+    pub duration_ms: u64,
+    pub message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identify_ports() {
+        let ports = vec![
+            serde_json::json!({ "name": "/dev/ttyACM0", "info": { "type": "USB", "vid": "0483", "pid": "374B", "serial": "066D" } }),
+            serde_json::json!({ "name": "/dev/ttyUSB0", "info": { "type": "USB", "vid": "1234", "pid": "5678", "product": "Widget", "serial": "" } }),
+            serde_json::json!({ "name": "/dev/ttyS0", "info": { "type": "PCI" } }),
+        ];
+        let devices = identify_ports(&ports, UsbDatabase::bundled());
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].name, "ST-Link V2-1");
+        assert_eq!(devices[0].serial.as_deref(), Some("066D"));
+        assert_eq!(devices[0].device_type, DeviceType::DebugProbe);
+        assert_eq!(devices[1].name, "Widget");
+        assert_eq!(devices[1].device_type, DeviceType::Unknown);
+        assert!(devices[1].protocols.is_empty());
+
+        assert!(hardware_get_device_info("10C4".into(), "EA60".into()).is_ok());
+        assert!(hardware_get_device_info("FFFF".into(), "0001".into()).is_err());
+    }
+}
@@ -0,0 +1,316 @@
+// Include Dependency Analysis
+// Builds a project's #include graph from `gcc -MM -H` for visualization
+
+use super::workspace::collect_files;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+/// Extensions scanned as translation units
+const SOURCE_EXTENSIONS: &[&str] = &["c", "cpp", "cc"];
+/// Extensions scanned as headers
+const HEADER_EXTENSIONS: &[&str] = &["h", "hpp", "hh"];
+/// Headers listed in `most_included`
+pub const MOST_INCLUDED_LIMIT: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DepNodeKind {
+    Source,
+    Header,
+}
+
+/// File in the dependency graph, identified by its project-relative path
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DepNode {
+    pub id: String,
+    pub kind: DepNodeKind,
+    /// Sources that depend on this file, directly or through other headers
+    pub included_by: usize,
+}
+
+/// `from` has an `#include` of `to`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DepEdge {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DependencyGraph {
+    pub nodes: Vec<DepNode>,
+    pub edges: Vec<DepEdge>,
+}
+
+/// What one `gcc -MM -H` run found
+#[derive(Debug, Clone, Default)]
+pub struct FileScan {
+    pub file: String,
+    /// Prerequisites of the Makefile rule, the file itself excluded
+    pub deps: BTreeSet<String>,
+    /// Direct includes from the `-H` tree, system headers skipped
+    ///
+    /// gcc prints a guarded header only where it is first included, so later
+    /// includes of it in the same translation unit have no edge.
+    pub includes: Vec<(String, String)>,
+}
+
+impl FileScan {
+    /// Combine the Makefile rule on stdout with the `-H` include tree on stderr
+    pub fn from_output(file: &str, stdout: &str, stderr: &str) -> Self {
+        let file = normalize(file);
+        let deps: BTreeSet<String> = parse_make_rule(stdout).into_iter()
+            .map(|dep| normalize(&dep))
+            .filter(|dep| *dep != file)
+            .collect();
+
+        // -MM leaves system headers out of the rule, so anything not in it is skipped
+        // and its project includes hang off the nearest kept ancestor
+        let mut includes = Vec::new();
+        let mut parents: Vec<(usize, String)> = vec![(0, file.clone())];
+        for (depth, header) in parse_include_tree(stderr) {
+            let header = normalize(&header);
+            while parents.last().is_some_and(|(d, _)| *d >= depth) {
+                parents.pop();
+            }
+            if !deps.contains(&header) && header != file {
+                continue;
+            }
+            if let Some((_, parent)) = parents.last() {
+                includes.push((parent.clone(), header.clone()));
+            }
+            parents.push((depth, header));
+        }
+
+        Self { file, deps, includes }
+    }
+}
+
+/// Prerequisites of the first rule in Makefile dependency output
+pub fn parse_make_rule(output: &str) -> Vec<String> {
+    let joined = output.replace("\\\r\n", " ").replace("\\\n", " ");
+    let Some((_, prerequisites)) = joined.split_once(": ") else {
+        return Vec::new();
+    };
+    let prerequisites = prerequisites.lines().next().unwrap_or_default();
+
+    // Spaces inside a path are escaped as `\ `
+    let mut deps = Vec::new();
+    let mut current = String::new();
+    let mut chars = prerequisites.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&' ') => current.push(chars.next().unwrap()),
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    deps.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        deps.push(current);
+    }
+    deps
+}
+
+/// `(depth, path)` for each `. path` line gcc's `-H` prints
+pub fn parse_include_tree(stderr: &str) -> Vec<(usize, String)> {
+    stderr.lines().filter_map(|line| {
+        let depth = line.chars().take_while(|&c| c == '.').count();
+        let path = line[depth..].strip_prefix(' ')?;
+        (depth > 0).then(|| (depth, path.trim().to_string()))
+    }).collect()
+}
+
+/// Lexically resolve `.` and `..` so `src/../inc/a.h` and `inc/a.h` are one node
+fn normalize(path: &str) -> String {
+    let mut parts: Vec<Component> = Vec::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(parts.last(), Some(Component::Normal(_))) => {
+                parts.pop();
+            }
+            other => parts.push(other),
+        }
+    }
+    parts.iter().collect::<PathBuf>().to_string_lossy().replace('\\', "/")
+}
+
+fn is_header(path: &str) -> bool {
+    Path::new(path).extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| HEADER_EXTENSIONS.contains(&ext))
+}
+
+impl DependencyGraph {
+    pub fn from_scans(scans: &[FileScan]) -> Self {
+        let mut included_by: BTreeMap<String, usize> = BTreeMap::new();
+        for scan in scans {
+            included_by.entry(scan.file.clone()).or_default();
+            for (from, to) in &scan.includes {
+                included_by.entry(from.clone()).or_default();
+                included_by.entry(to.clone()).or_default();
+            }
+        }
+        for scan in scans.iter().filter(|scan| !is_header(&scan.file)) {
+            for dep in &scan.deps {
+                if let Some(count) = included_by.get_mut(dep) {
+                    *count += 1;
+                }
+            }
+        }
+
+        let nodes = included_by.into_iter().map(|(id, included_by)| DepNode {
+            kind: if is_header(&id) { DepNodeKind::Header } else { DepNodeKind::Source },
+            id,
+            included_by,
+        }).collect();
+        let edges: BTreeSet<DepEdge> = scans.iter()
+            .flat_map(|scan| &scan.includes)
+            .map(|(from, to)| DepEdge { from: from.clone(), to: to.clone() })
+            .collect();
+
+        Self { nodes, edges: edges.into_iter().collect() }
+    }
+
+    /// Headers that the most sources depend on, most first
+    pub fn most_included(&self, limit: usize) -> Vec<String> {
+        let mut headers: Vec<&DepNode> = self.nodes.iter()
+            .filter(|node| node.kind == DepNodeKind::Header && node.included_by > 0)
+            .collect();
+        headers.sort_by(|a, b| b.included_by.cmp(&a.included_by).then_with(|| a.id.cmp(&b.id)));
+        headers.into_iter().take(limit).map(|node| node.id.clone()).collect()
+    }
+
+    /// Header include cycles, each starting at its smallest path
+    ///
+    /// Include guards keep these from recursing forever, but a cycle still means
+    /// declarations depend on which header happened to be included first.
+    pub fn include_cycles(&self) -> Vec<Vec<String>> {
+        let mut adjacency: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for edge in self.edges.iter().filter(|edge| is_header(&edge.from)) {
+            adjacency.entry(&edge.from).or_default().push(&edge.to);
+        }
+
+        let mut cycles = BTreeSet::new();
+        let mut done = BTreeSet::new();
+        for &start in adjacency.keys() {
+            let mut path = Vec::new();
+            find_cycles(start, &adjacency, &mut path, &mut done, &mut cycles);
+        }
+        cycles.into_iter().collect()
+    }
+}
+
+fn find_cycles<'a>(
+    node: &'a str,
+    adjacency: &BTreeMap<&'a str, Vec<&'a str>>,
+    path: &mut Vec<&'a str>,
+    done: &mut BTreeSet<&'a str>,
+    cycles: &mut BTreeSet<Vec<String>>,
+) {
+    if let Some(pos) = path.iter().position(|&n| n == node) {
+        let mut cycle: Vec<String> = path[pos..].iter().map(|n| n.to_string()).collect();
+        let min = cycle.iter().enumerate().min_by_key(|(_, n)| n.as_str()).map_or(0, |(i, _)| i);
+        cycle.rotate_left(min);
+        cycles.insert(cycle);
+        return;
+    }
+    if done.contains(node) {
+        return;
+    }
+
+    path.push(node);
+    for &next in adjacency.get(node).into_iter().flatten() {
+        find_cycles(next, adjacency, path, done, cycles);
+    }
+    path.pop();
+    done.insert(node);
+}
+
+/// Scan every source and header below `project_path` with `compiler -MM -H`
+///
+/// Headers are scanned too so cycles among headers no source includes are found.
+pub fn analyze_project(project_path: &Path, compiler: &Path) -> Result<DependencyGraph, String> {
+    let relative = |path: &Path| path.strip_prefix(project_path).unwrap_or(path).to_path_buf();
+    let sources: Vec<PathBuf> = collect_files(project_path, SOURCE_EXTENSIONS).iter().map(|p| relative(p)).collect();
+    if sources.is_empty() {
+        return Err(format!("No C/C++ sources in {}", project_path.display()));
+    }
+    let headers: Vec<PathBuf> = collect_files(project_path, HEADER_EXTENSIONS).iter().map(|p| relative(p)).collect();
+    let include_dirs: BTreeSet<PathBuf> = headers.iter()
+        .map(|h| h.parent().map(Path::to_path_buf).unwrap_or_default())
+        .collect();
+
+    let mut scans = Vec::new();
+    for file in sources.iter().chain(&headers) {
+        let mut cmd = Command::new(compiler);
+        // -MG lists headers it cannot find (vendor HALs) instead of failing
+        cmd.current_dir(project_path).arg("-MM").arg("-MG").arg("-H");
+        for dir in &include_dirs {
+            cmd.arg(format!("-I{}", if dir.as_os_str().is_empty() { Path::new(".") } else { dir }.display()));
+        }
+        let output = cmd.arg(file).output()
+            .map_err(|e| format!("Failed to run {}: {}", compiler.display(), e))?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            log::warn!("Dependency scan of {} failed: {}", file.display(), stderr.lines().last().unwrap_or_default());
+            continue;
+        }
+        scans.push(FileScan::from_output(&file.to_string_lossy(), &String::from_utf8_lossy(&output.stdout), &stderr));
+    }
+
+    Ok(DependencyGraph::from_scans(&scans))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gcc_output() {
+        let stdout = "main.o: src/main.c inc/a.h inc/b.h inc/a.h \\\n inc/my\\ config.h src/../inc/c.h\n";
+        assert_eq!(parse_make_rule(stdout), vec![
+            "src/main.c", "inc/a.h", "inc/b.h", "inc/a.h", "inc/my config.h", "src/../inc/c.h",
+        ]);
+
+        let stderr = ". inc/a.h\n.. inc/b.h\n... inc/a.h\n. /usr/include/stdint.h\n.. inc/my config.h\n. src/../inc/c.h\n\
+                      Multiple include guards may be useful for:\n/usr/include/stdint.h\n";
+        let scan = FileScan::from_output("src/main.c", stdout, stderr);
+        assert_eq!(scan.deps.len(), 4);
+        assert_eq!(scan.includes, vec![
+            ("src/main.c".to_string(), "inc/a.h".to_string()),
+            ("inc/a.h".to_string(), "inc/b.h".to_string()),
+            ("inc/b.h".to_string(), "inc/a.h".to_string()),
+            // Included from a system header, so attached to the source
+            ("src/main.c".to_string(), "inc/my config.h".to_string()),
+            ("src/main.c".to_string(), "inc/c.h".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_graph_most_included_and_cycles() {
+        let scan = |file: &str, includes: &[(&str, &str)]| FileScan {
+            file: file.to_string(),
+            deps: includes.iter().map(|(_, to)| to.to_string()).filter(|to| to != file).collect(),
+            includes: includes.iter().map(|(a, b)| (a.to_string(), b.to_string())).collect(),
+        };
+        let graph = DependencyGraph::from_scans(&[
+            scan("src/main.c", &[("src/main.c", "inc/a.h"), ("inc/a.h", "inc/b.h"), ("inc/b.h", "inc/a.h"), ("src/main.c", "inc/config.h")]),
+            scan("src/uart.c", &[("src/uart.c", "inc/config.h")]),
+            scan("inc/b.h", &[("inc/b.h", "inc/a.h"), ("inc/a.h", "inc/b.h")]),
+        ]);
+
+        assert_eq!(graph.nodes.len(), 5);
+        assert_eq!(graph.edges.len(), 5);
+        assert_eq!(graph.most_included(2), vec!["inc/config.h", "inc/a.h"]);
+        assert_eq!(graph.include_cycles(), vec![vec!["inc/a.h".to_string(), "inc/b.h".to_string()]]);
+
+        let acyclic = DependencyGraph::from_scans(&[scan("src/uart.c", &[("src/uart.c", "inc/config.h")])]);
+        assert!(acyclic.include_cycles().is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+// Project Schema Migration
+// Upgrades older `.nbp` project JSON step by step to the current schema
+
+use super::ProjectData;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+/// Migration errors
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    #[error("Unknown schema version: {0}")]
+    UnknownVersion(String),
+
+    #[error("No migration path from v{from} to v{to}")]
+    NoPath { from: String, to: String },
+
+    #[error("Invalid project data: {0}")]
+    InvalidProject(String),
+
+    #[error("Failed to deserialize migrated project: {0}")]
+    Deserialize(#[from] serde_json::Error),
+}
+
+/// One schema upgrade step
+pub trait ProjectMigration: Send + Sync {
+    fn source_version(&self) -> &str;
+    fn target_version(&self) -> &str;
+    fn migrate(&self, data: Value) -> Result<Value, MigrationError>;
+}
+
+/// v1 -> v2: version stamp, description, and `mcu`/`language` always present
+///
+/// v1 files predate `schema_version` and some stored the FSM-era `target_mcu`.
+struct V1ToV2;
+
+impl ProjectMigration for V1ToV2 {
+    fn source_version(&self) -> &str {
+        "1"
+    }
+
+    fn target_version(&self) -> &str {
+        "2"
+    }
+
+    fn migrate(&self, mut data: Value) -> Result<Value, MigrationError> {
+        let obj = data.as_object_mut()
+            .ok_or_else(|| MigrationError::InvalidProject("expected a JSON object".to_string()))?;
+
+        if !obj.contains_key("name") {
+            return Err(MigrationError::InvalidProject("missing 'name'".to_string()));
+        }
+        if !obj.contains_key("mcu") {
+            let mcu = obj.remove("target_mcu")
+                .filter(|v| v.is_string())
+                .unwrap_or_else(|| json!("STM32F4"));
+            obj.insert("mcu".to_string(), mcu);
+        }
+        obj.entry("language").or_insert_with(|| json!("c"));
+        obj.entry("description").or_insert_with(|| json!(""));
+        obj.entry("nodes").or_insert_with(|| json!([]));
+        obj.entry("edges").or_insert_with(|| json!([]));
+        obj.insert("schema_version".to_string(), json!(self.target_version()));
+        Ok(data)
+    }
+}
+
+/// Every known migration step, oldest first
+fn migrations() -> Vec<Box<dyn ProjectMigration>> {
+    vec![Box::new(V1ToV2)]
+}
+
+/// Schema version stored in project JSON, "1" when absent
+pub fn schema_version(data: &Value) -> String {
+    match &data["schema_version"] {
+        Value::String(v) => v.clone(),
+        Value::Number(n) => n.to_string(),
+        _ => "1".to_string(),
+    }
+}
+
+/// Apply migrations from `from_version` up to `to_version` and deserialize
+pub fn migrate_project(data: Value, from_version: &str, to_version: &str) -> Result<ProjectData, MigrationError> {
+    let steps = migrations();
+    let known = |v: &str| v == "1" || steps.iter().any(|s| s.target_version() == v);
+    for version in [from_version, to_version] {
+        if !known(version) {
+            return Err(MigrationError::UnknownVersion(version.to_string()));
+        }
+    }
+
+    let mut data = data;
+    let mut current = from_version.to_string();
+    while current != to_version {
+        let step = steps.iter()
+            .find(|s| s.source_version() == current)
+            .ok_or_else(|| MigrationError::NoPath {
+                from: from_version.to_string(),
+                to: to_version.to_string(),
+            })?;
+        data = step.migrate(data)?;
+        current = step.target_version().to_string();
+    }
+
+    Ok(serde_json::from_value(data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_v1_project() {
+        let v1 = json!({
+            "name": "Traffic Light",
+            "nodes": [{ "id": "n1", "label": "RED" }],
+            "edges": [],
+            "target_mcu": "RP2040"
+        });
+        assert_eq!(schema_version(&v1), "1");
+
+        let project = migrate_project(v1, "1", "2").unwrap();
+        assert_eq!(project.schema_version, "2");
+        assert_eq!(project.name, "Traffic Light");
+        assert_eq!(project.mcu, "RP2040");
+        assert_eq!(project.language, "c");
+        assert_eq!(project.description, "");
+        assert_eq!(project.nodes.len(), 1);
+
+        let value = serde_json::to_value(&project).unwrap();
+        for field in ["schema_version", "name", "description", "nodes", "edges", "mcu", "language"] {
+            assert!(value.get(field).is_some(), "missing {}", field);
+        }
+    }
+
+    #[test]
+    fn test_migration_errors() {
+        let v2 = json!({ "schema_version": "2", "name": "A", "nodes": [], "edges": [], "mcu": "ESP32", "language": "cpp" });
+        assert_eq!(migrate_project(v2.clone(), "2", "2").unwrap().mcu, "ESP32");
+        assert!(matches!(migrate_project(v2.clone(), "2", "1"), Err(MigrationError::NoPath { .. })));
+        assert!(matches!(migrate_project(v2, "9", "2"), Err(MigrationError::UnknownVersion(_))));
+        assert!(matches!(migrate_project(json!([]), "1", "2"), Err(MigrationError::InvalidProject(_))));
+    }
+}
@@ -2,9 +2,38 @@
 
 use crate::core::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use uuid::Uuid;
 
+pub mod dependencies;
+pub mod migrate;
+pub mod workspace;
+
+/// Schema version written to new `.nbp` files
+pub const CURRENT_SCHEMA_VERSION: &str = "2";
+
+/// Project data for save/load (`.nbp` files)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectData {
+    #[serde(default = "current_schema_version")]
+    pub schema_version: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub nodes: Vec<serde_json::Value>,
+    pub edges: Vec<serde_json::Value>,
+    pub mcu: String,
+    pub language: String,
+    /// Pinout from the pin planner, also used for terminal pin completion
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pins: Vec<crate::pins::PinConfig>,
+}
+
+fn current_schema_version() -> String {
+    CURRENT_SCHEMA_VERSION.to_string()
+}
+
 /// Create a new project
 #[tauri::command]
 pub fn create_project(name: String, target_mcu: Option<String>) -> Result<FSMProject, String> {
@@ -69,6 +98,25 @@ pub fn load_project(path: String) -> Result<FSMProject, String> {
     Ok(project)
 }
 
+/// Create a project on disk from a template
+#[tauri::command]
+pub fn create_project_from_template(
+    template_id: String,
+    project_name: String,
+    output_dir: String,
+    params: Option<HashMap<String, String>>,
+) -> Result<ProjectData, String> {
+    let project = crate::templates::instantiate(
+        &template_id,
+        &project_name,
+        std::path::Path::new(&output_dir),
+        params.unwrap_or_default(),
+    ).map_err(|e| e.to_string())?;
+    
+    log::info!("Created project {} from template {} in {}", project_name, template_id, output_dir);
+    Ok(project)
+}
+
 /// List saved projects in a directory
 #[tauri::command]
 pub fn list_projects(directory: Option<String>) -> Result<Vec<ProjectInfo>, String> {
@@ -102,6 +150,31 @@ pub fn list_projects(directory: Option<String>) -> Result<Vec<ProjectInfo>, Stri
     Ok(projects)
 }
 
+/// Analyze the `#include` graph of a project's sources
+#[tauri::command]
+pub async fn analyze_dependencies(project_path: String) -> Result<serde_json::Value, String> {
+    let compiler = which::which("arm-none-eabi-gcc")
+        .unwrap_or_else(|_| PathBuf::from("arm-none-eabi-gcc"));
+    let root = PathBuf::from(&project_path);
+    let graph = tokio::task::spawn_blocking(move || dependencies::analyze_project(&root, &compiler))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let cycles = graph.include_cycles();
+    for cycle in &cycles {
+        log::warn!("Include cycle in {}: {}", project_path, cycle.join(" -> "));
+    }
+
+    Ok(serde_json::json!({
+        "node_count": graph.nodes.len(),
+        "edge_count": graph.edges.len(),
+        "most_included": graph.most_included(dependencies::MOST_INCLUDED_LIMIT),
+        "cycles": cycles,
+        "nodes": graph.nodes,
+        "edges": graph.edges,
+    }))
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProjectInfo {
     pub id: Uuid,
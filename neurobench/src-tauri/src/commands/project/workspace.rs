@@ -0,0 +1,407 @@
+// Project Workspace Commands
+// Multi-project workspaces stored as `workspace.nbw` JSON files
+
+use crate::toolchain::{BuildConfig, BuildResult};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// Workspace file name inside a workspace directory
+pub const WORKSPACE_FILE: &str = "workspace.nbw";
+
+lazy_static::lazy_static! {
+    /// Directory and contents of the last loaded or modified workspace, its active
+    /// project drives AI generation
+    static ref ACTIVE_WORKSPACE: RwLock<Option<(PathBuf, WorkspaceConfig)>> = RwLock::new(None);
+}
+
+/// Project entry in a workspace
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProjectRef {
+    /// Project directory or file, relative to the workspace when inside it
+    pub path: String,
+    pub name: String,
+    pub mcu: String,
+}
+
+/// Workspace file contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkspaceConfig {
+    pub name: String,
+    #[serde(default)]
+    pub projects: Vec<ProjectRef>,
+    #[serde(default)]
+    pub active_project: Option<String>,
+    #[serde(default)]
+    pub shared_snippets: Vec<String>,
+}
+
+impl WorkspaceConfig {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), ..Default::default() }
+    }
+
+    /// Currently active project entry
+    pub fn active(&self) -> Option<&ProjectRef> {
+        let name = self.active_project.as_ref()?;
+        self.projects.iter().find(|p| &p.name == name)
+    }
+}
+
+/// Build outcome for one workspace project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectBuildResult {
+    pub project: String,
+    pub success: bool,
+    pub result: Option<BuildResult>,
+    pub error: Option<String>,
+}
+
+/// `workspace.nbw` inside a directory, or the given `.nbw` file
+fn workspace_file(path: &str) -> PathBuf {
+    let path = PathBuf::from(path);
+    if path.extension().is_some_and(|ext| ext == "nbw") {
+        path
+    } else {
+        path.join(WORKSPACE_FILE)
+    }
+}
+
+fn workspace_dir(file: &Path) -> PathBuf {
+    file.parent().map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn read_workspace(file: &Path) -> Result<WorkspaceConfig, String> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|e| format!("Failed to read workspace: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse workspace: {}", e))
+}
+
+fn write_workspace(file: &Path, config: &WorkspaceConfig) -> Result<(), String> {
+    if let Some(parent) = file.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Serialization error: {}", e))?;
+    std::fs::write(file, json).map_err(|e| format!("Failed to save: {}", e))?;
+    *ACTIVE_WORKSPACE.write().unwrap() = Some((workspace_dir(file), config.clone()));
+    Ok(())
+}
+
+/// Active project of the current workspace
+pub fn active_project() -> Option<ProjectRef> {
+    ACTIVE_WORKSPACE.read().unwrap().as_ref()?.1.active().cloned()
+}
+
+/// Directory of the active project, resolved against its workspace
+pub fn active_project_dir() -> Option<PathBuf> {
+    let guard = ACTIVE_WORKSPACE.read().unwrap();
+    let (dir, config) = guard.as_ref()?;
+    let project = dir.join(&config.active()?.path);
+    Some(if project.is_file() { workspace_dir(&project) } else { project })
+}
+
+/// MCU of the active project, for AI generation defaults
+pub fn active_mcu() -> Option<String> {
+    active_project().map(|p| p.mcu).filter(|mcu| !mcu.is_empty())
+}
+
+/// One-line description of the active project for AI prompts
+pub fn active_project_context() -> Option<String> {
+    let project = active_project()?;
+    Some(match active_mcu() {
+        Some(mcu) => format!("Active project: {} (target MCU: {})", project.name, mcu),
+        None => format!("Active project: {}", project.name),
+    })
+}
+
+/// Read name and MCU from a project file, or a directory containing one
+fn describe_project(path: &Path) -> Result<(String, String), String> {
+    let file = if path.is_dir() {
+        std::fs::read_dir(path)
+            .map_err(|e| format!("Failed to read project directory: {}", e))?
+            .flatten()
+            .map(|e| e.path())
+            .find(|p| {
+                let name = p.file_name().unwrap_or_default().to_string_lossy();
+                name.ends_with(".nbp") || name.ends_with(".neurobench.json")
+            })
+    } else if path.exists() {
+        Some(path.to_path_buf())
+    } else {
+        return Err(format!("Project not found: {}", path.display()));
+    };
+
+    let fallback_name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let Some(file) = file else {
+        return Ok((fallback_name, String::new()));
+    };
+
+    let content = std::fs::read_to_string(&file)
+        .map_err(|e| format!("Failed to read project: {}", e))?;
+    let data: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse project: {}", e))?;
+
+    let name = data["name"].as_str().map(str::to_string).unwrap_or(fallback_name);
+    // `.nbp` files store `mcu`, FSM projects store `target_mcu`
+    let mcu = data["mcu"].as_str()
+        .or_else(|| data["target_mcu"].as_str())
+        .unwrap_or_default()
+        .to_string();
+    Ok((name, mcu))
+}
+
+/// Create an empty workspace
+#[tauri::command]
+pub fn create_workspace(path: String, name: String) -> Result<WorkspaceConfig, String> {
+    let file = workspace_file(&path);
+    if file.exists() {
+        return Err(format!("Workspace already exists: {}", file.display()));
+    }
+    let config = WorkspaceConfig::new(&name);
+    write_workspace(&file, &config)?;
+    log::info!("Created workspace {} at {:?}", name, file);
+    Ok(config)
+}
+
+/// Load a workspace and make it current
+#[tauri::command]
+pub fn load_workspace(path: String) -> Result<WorkspaceConfig, String> {
+    let file = workspace_file(&path);
+    let config = read_workspace(&file)?;
+    *ACTIVE_WORKSPACE.write().unwrap() = Some((workspace_dir(&file), config.clone()));
+    Ok(config)
+}
+
+/// Save a workspace
+#[tauri::command]
+pub fn save_workspace(path: String, config: WorkspaceConfig) -> Result<(), String> {
+    write_workspace(&workspace_file(&path), &config)
+}
+
+/// Add a project, the first one added becomes active
+#[tauri::command]
+pub fn add_project_to_workspace(workspace_path: String, project_path: String) -> Result<WorkspaceConfig, String> {
+    let file = workspace_file(&workspace_path);
+    let dir = workspace_dir(&file);
+    let mut config = read_workspace(&file)?;
+
+    let project = dir.join(&project_path);
+    let (name, mcu) = describe_project(&project)?;
+    if config.projects.iter().any(|p| p.name == name) {
+        return Err(format!("Workspace already has a project named '{}'", name));
+    }
+
+    let stored = project.strip_prefix(&dir).unwrap_or(&project);
+    config.projects.push(ProjectRef {
+        path: stored.to_string_lossy().to_string(),
+        name: name.clone(),
+        mcu,
+    });
+    if config.active_project.is_none() {
+        config.active_project = Some(name);
+    }
+
+    write_workspace(&file, &config)?;
+    Ok(config)
+}
+
+/// Remove a project by name
+#[tauri::command]
+pub fn remove_project_from_workspace(workspace_path: String, project_name: String) -> Result<WorkspaceConfig, String> {
+    let file = workspace_file(&workspace_path);
+    let mut config = read_workspace(&file)?;
+
+    let before = config.projects.len();
+    config.projects.retain(|p| p.name != project_name);
+    if config.projects.len() == before {
+        return Err(format!("Project not found in workspace: {}", project_name));
+    }
+    if config.active_project.as_deref() == Some(project_name.as_str()) {
+        config.active_project = config.projects.first().map(|p| p.name.clone());
+    }
+
+    write_workspace(&file, &config)?;
+    Ok(config)
+}
+
+/// Switch the active project
+#[tauri::command]
+pub fn set_active_project(workspace_path: String, project_name: String) -> Result<WorkspaceConfig, String> {
+    let file = workspace_file(&workspace_path);
+    let mut config = read_workspace(&file)?;
+    if !config.projects.iter().any(|p| p.name == project_name) {
+        return Err(format!("Project not found in workspace: {}", project_name));
+    }
+    config.active_project = Some(project_name);
+    write_workspace(&file, &config)?;
+    Ok(config)
+}
+
+/// Build every project in the workspace one after another
+#[tauri::command]
+pub async fn build_workspace(workspace_path: String) -> Result<Vec<ProjectBuildResult>, String> {
+    let file = workspace_file(&workspace_path);
+    let config = read_workspace(&file)?;
+    let dir = workspace_dir(&file);
+
+    tokio::task::spawn_blocking(move || build_projects(&config, &dir, toolchain_build))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Build each project with `build`, continuing past failures
+fn build_projects(
+    config: &WorkspaceConfig,
+    dir: &Path,
+    build: impl Fn(&BuildConfig) -> Result<BuildResult, String>,
+) -> Vec<ProjectBuildResult> {
+    config.projects.iter().map(|project| {
+        let mut root = dir.join(&project.path);
+        if root.is_file() {
+            root = workspace_dir(&root);
+        }
+        let build_config = BuildConfig {
+            source_files: collect_sources(&root),
+            include_paths: ["", "inc", "include"].iter()
+                .map(|sub| root.join(sub))
+                .filter(|p| p.is_dir())
+                .collect(),
+            mcu_target: cpu_target(&project.mcu).to_string(),
+            project_path: root,
+            ..Default::default()
+        };
+
+        log::info!("Building workspace project {}", project.name);
+        match build(&build_config) {
+            Ok(result) => ProjectBuildResult {
+                project: project.name.clone(),
+                success: result.success,
+                result: Some(result),
+                error: None,
+            },
+            Err(e) => ProjectBuildResult {
+                project: project.name.clone(),
+                success: false,
+                result: None,
+                error: Some(e),
+            },
+        }
+    }).collect()
+}
+
+fn toolchain_build(config: &BuildConfig) -> Result<BuildResult, String> {
+    use crate::toolchain::Toolchain;
+
+    let toolchains = crate::toolchain::discovery::discover_all();
+    let tc = toolchains.first()
+        .ok_or_else(|| "No suitable toolchain found. Install ARM GCC".to_string())?;
+    crate::toolchain::arm_gcc::ArmGcc::new(tc.clone()).build(config).map_err(|e| e.to_string())
+}
+
+/// C, C++ and assembly sources below a project root, skipping build output
+fn collect_sources(root: &Path) -> Vec<PathBuf> {
+    collect_files(root, &["c", "cpp", "s", "S"])
+}
+
+/// Files with one of `extensions` below a project root, skipping build output
+pub(super) fn collect_files(root: &Path, extensions: &[&str]) -> Vec<PathBuf> {
+    let mut sources = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+        for path in entries.flatten().map(|e| e.path()) {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if path.is_dir() {
+                if name != "build" && !name.starts_with('.') {
+                    pending.push(path);
+                }
+            } else if path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| extensions.contains(&ext)) {
+                sources.push(path);
+            }
+        }
+    }
+    sources.sort();
+    sources
+}
+
+/// GCC CPU target for an MCU family name
+fn cpu_target(mcu: &str) -> &'static str {
+    let mcu = mcu.to_uppercase();
+    if mcu.starts_with("STM32F1") || mcu.starts_with("LPC1768") {
+        "cortex-m3"
+    } else if mcu.starts_with("STM32H7") || mcu.starts_with("STM32F7") {
+        "cortex-m7"
+    } else if mcu.starts_with("RP2040") {
+        "cortex-m0+"
+    } else if mcu.starts_with("STM32F4") || mcu.starts_with("STM32G4") || mcu.starts_with("NRF52") {
+        "cortex-m4f"
+    } else {
+        "cortex-m4"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace_add_remove_projects() {
+        let dir = tempfile::tempdir().unwrap();
+        let ws = dir.path().to_string_lossy().to_string();
+        std::fs::create_dir(dir.path().join("blinky")).unwrap();
+        std::fs::write(dir.path().join("blinky/blinky.nbp"), r#"{"name":"Blinky","mcu":"STM32F4","nodes":[],"edges":[],"language":"c"}"#).unwrap();
+        std::fs::write(dir.path().join("sensor.neurobench.json"), r#"{"name":"Sensor","target_mcu":"RP2040"}"#).unwrap();
+
+        create_workspace(ws.clone(), "Lab".to_string()).unwrap();
+        assert!(create_workspace(ws.clone(), "Lab".to_string()).is_err());
+
+        let config = add_project_to_workspace(ws.clone(), "blinky".to_string()).unwrap();
+        assert_eq!(config.projects[0], ProjectRef { path: "blinky".into(), name: "Blinky".into(), mcu: "STM32F4".into() });
+        assert_eq!(config.active_project.as_deref(), Some("Blinky"));
+
+        add_project_to_workspace(ws.clone(), "sensor.neurobench.json".to_string()).unwrap();
+        assert!(add_project_to_workspace(ws.clone(), "blinky".to_string()).is_err());
+
+        let config = remove_project_from_workspace(ws.clone(), "Blinky".to_string()).unwrap();
+        assert_eq!(config.active_project.as_deref(), Some("Sensor"));
+
+        let loaded = load_workspace(dir.path().join(WORKSPACE_FILE).to_string_lossy().to_string()).unwrap();
+        assert_eq!(loaded.projects.len(), 1);
+        assert_eq!(loaded.active().unwrap().mcu, "RP2040");
+    }
+
+    #[test]
+    fn test_build_projects_sequentially() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("a/src")).unwrap();
+        std::fs::write(dir.path().join("a/src/main.c"), "int main(void) { return 0; }").unwrap();
+        std::fs::create_dir_all(dir.path().join("b/build")).unwrap();
+        std::fs::write(dir.path().join("b/build/stale.c"), "").unwrap();
+
+        let mut config = WorkspaceConfig::new("Lab");
+        config.projects.push(ProjectRef { path: "a".into(), name: "A".into(), mcu: "STM32F1".into() });
+        config.projects.push(ProjectRef { path: "b".into(), name: "B".into(), mcu: "RP2040".into() });
+
+        let order = std::cell::RefCell::new(Vec::new());
+        let results = build_projects(&config, dir.path(), |build| {
+            order.borrow_mut().push(build.mcu_target.clone());
+            if build.source_files.is_empty() {
+                return Err("No sources".to_string());
+            }
+            Ok(BuildResult {
+                success: true,
+                elf_path: None,
+                binary_path: None,
+                errors: vec![],
+                warnings: vec![],
+                duration_ms: 1,
+                output: String::new(),
+            })
+        });
+
+        assert_eq!(*order.borrow(), vec!["cortex-m3", "cortex-m0+"]);
+        assert!(results[0].success);
+        assert_eq!(results[1].error.as_deref(), Some("No sources"));
+    }
+}
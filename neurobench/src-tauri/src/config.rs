@@ -0,0 +1,155 @@
+// Application Configuration
+// User settings persisted to ~/.neurobench/config.toml between sessions
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Most recent projects kept in the config
+pub const MAX_RECENT_PROJECTS: usize = 10;
+
+/// Persistent user settings
+///
+/// Fields missing from an older config file fall back to their defaults, so
+/// adding a field here is enough to migrate existing installs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub ai_provider: String,
+    pub ai_model: String,
+    pub theme: String,
+    pub recent_projects: Vec<String>,
+    /// Toolchain id -> install path overrides
+    pub toolchain_paths: HashMap<String, String>,
+    pub qemu_path: Option<String>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            ai_provider: "gemini".to_string(),
+            ai_model: "gemini-1.5-flash".to_string(),
+            theme: "dracula".to_string(),
+            recent_projects: Vec::new(),
+            toolchain_paths: HashMap::new(),
+            qemu_path: None,
+        }
+    }
+}
+
+impl AppConfig {
+    /// Move `path` to the front of the recent projects list
+    pub fn add_recent_project(&mut self, path: &str) {
+        self.recent_projects.retain(|p| p != path);
+        self.recent_projects.insert(0, path.to_string());
+        self.recent_projects.truncate(MAX_RECENT_PROJECTS);
+    }
+}
+
+/// Config file location, `~/.neurobench/config.toml`
+pub fn config_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".neurobench")
+        .join("config.toml")
+}
+
+/// Load the user config, creating or migrating the file as needed
+pub fn load_config() -> AppConfig {
+    load_config_from(&config_path())
+}
+
+/// Save the user config
+pub fn save_config(config: &AppConfig) -> Result<(), io::Error> {
+    save_config_to(&config_path(), config)
+}
+
+/// Load a config file
+///
+/// A missing file is created with defaults. A file written by an older
+/// version is rewritten with the new fields filled in. An unreadable file is
+/// left alone and the defaults are used for this session.
+pub fn load_config_from(path: &Path) -> AppConfig {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            let config = AppConfig::default();
+            if let Err(e) = save_config_to(path, &config) {
+                log::warn!("Failed to create config {}: {}", path.display(), e);
+            }
+            return config;
+        }
+        Err(e) => {
+            log::warn!("Failed to read config {}: {}", path.display(), e);
+            return AppConfig::default();
+        }
+    };
+
+    let parsed = toml::from_str::<toml::Table>(&text)
+        .and_then(|table| Ok((table.clone(), toml::Value::Table(table).try_into::<AppConfig>()?)));
+    let (table, config) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            log::warn!("Invalid config {}, using defaults: {}", path.display(), e);
+            return AppConfig::default();
+        }
+    };
+
+    // Rewrite only when the file lacks fields added since it was saved
+    let missing_fields = toml::Value::try_from(&config).ok()
+        .and_then(|current| current.as_table().map(|t| t.keys().any(|key| !table.contains_key(key))))
+        .unwrap_or(false);
+    if missing_fields {
+        if let Err(e) = save_config_to(path, &config) {
+            log::warn!("Failed to migrate config {}: {}", path.display(), e);
+        }
+    }
+    config
+}
+
+/// Save a config file, creating its directory
+pub fn save_config_to(path: &Path, config: &AppConfig) -> Result<(), io::Error> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let text = toml::to_string_pretty(config).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    std::fs::write(path, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_run_creates_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".neurobench").join("config.toml");
+
+        let config = load_config_from(&path);
+        assert_eq!(config, AppConfig::default());
+        assert!(path.exists());
+
+        let mut changed = config.clone();
+        changed.theme = "nord".to_string();
+        changed.toolchain_paths.insert("arm-gcc".to_string(), "/opt/arm/bin".to_string());
+        changed.add_recent_project("/work/sensor");
+        save_config_to(&path, &changed).unwrap();
+        assert_eq!(load_config_from(&path), changed);
+    }
+
+    #[test]
+    fn test_old_file_is_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "theme = \"monokai\"\n").unwrap();
+
+        let config = load_config_from(&path);
+        assert_eq!(config.theme, "monokai");
+        assert_eq!(config.ai_model, AppConfig::default().ai_model);
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("ai_provider"));
+        assert!(text.contains("theme = \"monokai\""));
+    }
+}
@@ -0,0 +1,261 @@
+// Action Runner
+// Executes node and edge action code against simulation variables
+
+use super::guard::GuardEvaluator;
+use crate::core::types::{ActionLanguage, SimulationContext};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ActionError {
+    #[error("Cannot simulate `{statement}`: {reason}")]
+    Statement { statement: String, reason: String },
+
+    #[error("Lua error: {0}")]
+    Lua(String),
+
+    #[error("Lua actions require building with the `lua` feature")]
+    LuaUnavailable,
+}
+
+/// An action run during a simulation step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutedAction {
+    /// Where the code came from, e.g. `exit IDLE` or `edge GO`
+    pub origin: String,
+    pub code: String,
+    /// Function calls that were recorded but have no simulated effect
+    pub calls: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// Runs action snippets in the configured language
+pub struct ActionRunner {
+    language: ActionLanguage,
+}
+
+impl ActionRunner {
+    pub fn new(language: ActionLanguage) -> Self {
+        Self { language }
+    }
+
+    /// Run `code`, updating `vars` with its assignments
+    pub fn run(&self, origin: &str, code: &str, vars: &mut SimulationContext) -> ExecutedAction {
+        let mut calls = Vec::new();
+        let result = match self.language {
+            ActionLanguage::PseudoC => run_pseudo_c(code, vars, &mut calls),
+            ActionLanguage::Lua => run_lua(code, vars),
+        };
+        ExecutedAction {
+            origin: origin.to_string(),
+            code: code.to_string(),
+            calls,
+            error: result.err().map(|e| e.to_string()),
+        }
+    }
+}
+
+/// Simulate C-like statements: assignments, `+=`-style updates, `++`/`--`
+/// and bare function calls, which are recorded but otherwise ignored.
+/// Branches, loops, returns and blocks are an error.
+fn run_pseudo_c(code: &str, vars: &mut SimulationContext, calls: &mut Vec<String>) -> Result<(), ActionError> {
+    let code: String = code.lines()
+        .map(|line| line.split("//").next().unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    for statement in code.split([';', '\n']).map(str::trim).filter(|s| !s.is_empty()) {
+        let fail = |reason: String| ActionError::Statement { statement: statement.to_string(), reason };
+
+        // Running either branch or one loop pass would misreport the variables
+        if is_control_flow(statement) {
+            return Err(fail("unsupported control flow".to_string()));
+        }
+
+        if let Some(name) = statement.strip_suffix("++").or_else(|| statement.strip_suffix("--")) {
+            let delta = if statement.ends_with("++") { 1.0 } else { -1.0 };
+            let name = name.trim();
+            let current = vars.get(name).and_then(|v| v.as_f64()).unwrap_or(0.0);
+            vars.insert(name.to_string(), number_value(current + delta));
+            continue;
+        }
+
+        match split_assignment(statement) {
+            Some((target, op, expr)) => {
+                // Drop a leading type, e.g. `uint8_t count = 0`
+                let name = target.split_whitespace().last().unwrap_or_default().trim_start_matches('*');
+                let rhs = match op {
+                    Some(op) => format!("({}) {} ({})", name, op, expr),
+                    None => expr.to_string(),
+                };
+                let value = GuardEvaluator::new(vars).value(&rhs).map_err(|e| fail(e.to_string()))?;
+                let value = value.as_f64().map(number_value).unwrap_or(value);
+                vars.insert(name.to_string(), value);
+            }
+            None if statement.ends_with(')') && statement.contains('(') => calls.push(statement.to_string()),
+            None => return Err(fail("unsupported statement".to_string())),
+        }
+    }
+    Ok(())
+}
+
+/// Statement that starts a branch, loop or return, or opens or closes a block
+fn is_control_flow(statement: &str) -> bool {
+    let keyword = statement.split(|c: char| !c.is_ascii_alphanumeric() && c != '_').next().unwrap_or_default();
+    matches!(keyword, "if" | "else" | "while" | "for" | "switch" | "return") || statement.contains(['{', '}'])
+}
+
+/// Split `x = e` or `x += e` into (target, compound operator, expression)
+fn split_assignment(statement: &str) -> Option<(&str, Option<&str>, &str)> {
+    let bytes = statement.as_bytes();
+    let index = (0..bytes.len()).find(|&i| {
+        bytes[i] == b'='
+            && bytes.get(i + 1) != Some(&b'=')
+            && !(i > 0 && matches!(bytes[i - 1], b'=' | b'!' | b'<' | b'>'))
+    })?;
+    let (lhs, expr) = (&statement[..index], statement[index + 1..].trim());
+    for op in ["+", "-", "*", "/", "%"] {
+        if let Some(target) = lhs.strip_suffix(op) {
+            return Some((target.trim(), Some(op), expr));
+        }
+    }
+    Some((lhs.trim(), None, expr))
+}
+
+/// Keep integral results as JSON integers so `count = count + 1` stays `1`, not `1.0`
+fn number_value(n: f64) -> serde_json::Value {
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        serde_json::Value::from(n as i64)
+    } else {
+        serde_json::Value::from(n)
+    }
+}
+
+/// Lua VM instructions an action may run before it is aborted
+#[cfg(feature = "lua")]
+const LUA_INSTRUCTION_BUDGET: u64 = 1_000_000;
+
+/// Instructions between budget checks
+#[cfg(feature = "lua")]
+const LUA_HOOK_INTERVAL: u32 = 1000;
+
+/// Heap an action may allocate, so `string.rep` cannot exhaust memory
+#[cfg(feature = "lua")]
+const LUA_MEMORY_LIMIT: usize = 16 * 1024 * 1024;
+
+/// Base library functions that reach the filesystem or compile new code
+#[cfg(feature = "lua")]
+const LUA_BLOCKED_GLOBALS: [&str; 4] = ["dofile", "loadfile", "load", "require"];
+
+#[cfg(feature = "lua")]
+fn run_lua(code: &str, vars: &mut SimulationContext) -> Result<(), ActionError> {
+    use mlua::{HookTriggers, Lua, LuaOptions, LuaSerdeExt, StdLib};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    let lua_error = |e: mlua::Error| ActionError::Lua(e.to_string());
+    // No io/os/package: actions can only touch simulation variables. The
+    // base library is always loaded, so its file and loader functions are
+    // removed by hand.
+    let lua = Lua::new_with(StdLib::MATH | StdLib::STRING | StdLib::TABLE, LuaOptions::default()).map_err(lua_error)?;
+    let globals = lua.globals();
+    for name in LUA_BLOCKED_GLOBALS {
+        globals.set(name, mlua::Value::Nil).map_err(lua_error)?;
+    }
+    lua.set_memory_limit(LUA_MEMORY_LIMIT).map_err(lua_error)?;
+
+    // An endless loop would otherwise hang the simulation step
+    let executed = AtomicU64::new(0);
+    lua.set_hook(HookTriggers::new().every_nth_instruction(LUA_HOOK_INTERVAL), move |_, _| {
+        let count = executed.fetch_add(LUA_HOOK_INTERVAL as u64, Ordering::Relaxed) + LUA_HOOK_INTERVAL as u64;
+        if count > LUA_INSTRUCTION_BUDGET {
+            return Err(mlua::Error::RuntimeError(format!(
+                "action exceeded {} instructions",
+                LUA_INSTRUCTION_BUDGET
+            )));
+        }
+        Ok(())
+    });
+
+    let builtins: std::collections::HashSet<String> = globals.clone()
+        .pairs::<String, mlua::Value>()
+        .filter_map(|pair| pair.ok().map(|(name, _)| name))
+        .collect();
+    for (name, value) in vars.iter() {
+        globals.set(name.as_str(), lua.to_value(value).map_err(lua_error)?).map_err(lua_error)?;
+    }
+
+    lua.load(code).set_name("action").exec().map_err(lua_error)?;
+
+    for pair in globals.pairs::<String, mlua::Value>() {
+        let (name, value) = pair.map_err(lua_error)?;
+        if builtins.contains(&name) || matches!(value, mlua::Value::Function(_)) {
+            continue;
+        }
+        vars.insert(name, lua.from_value(value).map_err(lua_error)?);
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "lua"))]
+fn run_lua(_code: &str, _vars: &mut SimulationContext) -> Result<(), ActionError> {
+    Err(ActionError::LuaUnavailable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_pseudo_c_actions() {
+        let runner = ActionRunner::new(ActionLanguage::PseudoC);
+        let mut vars = SimulationContext::from([("count".to_string(), json!(2))]);
+        let action = runner.run("entry RUN", "led = true; // on\nuint8_t duty = count * 10;\ncount++;\nHAL_GPIO_WritePin(LED_GPIO_Port, LED_Pin, 1);\ncount += 3;", &mut vars);
+
+        assert!(action.error.is_none(), "{:?}", action.error);
+        assert_eq!(vars["led"], json!(true));
+        assert_eq!(vars["duty"], json!(20));
+        assert_eq!(vars["count"], json!(6));
+        assert_eq!(action.calls, vec!["HAL_GPIO_WritePin(LED_GPIO_Port, LED_Pin, 1)"]);
+
+        let failed = runner.run("edge GO", "while (1) {", &mut vars);
+        assert!(failed.error.unwrap().contains("unsupported"));
+
+        // Branches are rejected rather than run as an assignment or a call
+        for code in ["if (count > 3) count = 0;", "if (ready) start();", "else { count = 1; }", "return;", "{ count = 9; }"] {
+            let failed = runner.run("edge GO", code, &mut vars);
+            assert!(failed.error.unwrap().contains("control flow"), "{} should be rejected", code);
+            assert!(failed.calls.is_empty());
+        }
+        assert_eq!(vars["count"], json!(6));
+        assert!(runner.run("edge GO", "format_count(count)", &mut vars).error.is_none());
+    }
+
+    #[cfg(feature = "lua")]
+    #[test]
+    fn test_lua_actions() {
+        let runner = ActionRunner::new(ActionLanguage::Lua);
+        let mut vars = SimulationContext::from([("count".to_string(), json!(2))]);
+        let action = runner.run("entry RUN", "led = true\ncount = count + 1", &mut vars);
+        assert!(action.error.is_none(), "{:?}", action.error);
+        assert_eq!(vars["led"], json!(true));
+        assert_eq!(vars["count"].as_f64(), Some(3.0));
+        assert!(runner.run("entry RUN", "os.exit()", &mut vars).error.is_some());
+    }
+
+    #[cfg(feature = "lua")]
+    #[test]
+    fn test_lua_sandbox() {
+        let runner = ActionRunner::new(ActionLanguage::Lua);
+        let mut vars = SimulationContext::new();
+        for code in ["dofile('/etc/passwd')", "loadfile('/etc/passwd')", "load('x = 1')()", "require('os')"] {
+            let action = runner.run("entry RUN", code, &mut vars);
+            assert!(action.error.is_some(), "{} should fail", code);
+        }
+        assert!(!vars.contains_key("x"));
+
+        let hung = runner.run("entry RUN", "while true do end", &mut vars);
+        assert!(hung.error.unwrap().contains("instructions"));
+        assert!(runner.run("entry RUN", "s = string.rep('x', 1 << 30)", &mut vars).error.is_some());
+    }
+}
@@ -0,0 +1,402 @@
+// Guard Expression Evaluator
+// Evaluates transition guards like `x > 5 && !button_pressed` against simulation variables
+
+use serde_json::Value;
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum GuardError {
+    #[error("Invalid guard expression: {0}")]
+    Parse(String),
+
+    #[error("Unknown variable '{0}'")]
+    UnknownVariable(String),
+
+    #[error("Unknown function '{0}'")]
+    UnknownFunction(String),
+
+    #[error("Type error: {0}")]
+    Type(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, GuardError> {
+    const OPS: [&str; 14] = ["&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "%"];
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let number = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+                Some(hex) => i64::from_str_radix(hex, 16).map(|n| n as f64).ok(),
+                None => text.parse().ok(),
+            };
+            tokens.push(Token::Number(number.ok_or_else(|| GuardError::Parse(format!("bad number '{}'", text)))?));
+        } else if c == '"' || c == '\'' {
+            let end = chars[i + 1..].iter().position(|&q| q == c)
+                .ok_or_else(|| GuardError::Parse("unterminated string".to_string()))?;
+            tokens.push(Token::Str(chars[i + 1..i + 1 + end].iter().collect()));
+            i += end + 2;
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            match word.as_str() {
+                "and" => tokens.push(Token::Op("&&")),
+                "or" => tokens.push(Token::Op("||")),
+                "not" => tokens.push(Token::Op("!")),
+                _ => tokens.push(Token::Ident(word)),
+            }
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else if c == ',' {
+            tokens.push(Token::Comma);
+            i += 1;
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let op = OPS.iter().find(|op| rest.starts_with(**op))
+                .ok_or_else(|| GuardError::Parse(format!("unexpected '{}'", c)))?;
+            tokens.push(Token::Op(op));
+            i += op.len();
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Literal(Value),
+    Variable(String),
+    Call(String, Vec<Expr>),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+}
+
+/// Recursive-descent parser; precedence from loosest: `||`, `&&`, comparison, `+ -`, `* / %`, unary
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_op(&mut self, ops: &[&str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn binary(&mut self, ops: &[&str], next: fn(&mut Self) -> Result<Expr, GuardError>) -> Result<Expr, GuardError> {
+        let mut left = next(self)?;
+        while let Some(op) = self.eat_op(ops) {
+            left = Expr::Binary(op, Box::new(left), Box::new(next(self)?));
+        }
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Expr, GuardError> {
+        self.binary(&["||"], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr, GuardError> {
+        self.binary(&["&&"], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, GuardError> {
+        let left = self.additive()?;
+        match self.eat_op(&["==", "!=", "<", "<=", ">", ">="]) {
+            Some(op) => Ok(Expr::Binary(op, Box::new(left), Box::new(self.additive()?))),
+            None => Ok(left),
+        }
+    }
+
+    fn additive(&mut self) -> Result<Expr, GuardError> {
+        self.binary(&["+", "-"], Self::multiplicative)
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, GuardError> {
+        self.binary(&["*", "/", "%"], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr, GuardError> {
+        match self.eat_op(&["!", "-"]) {
+            Some("!") => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some(_) => Ok(Expr::Neg(Box::new(self.unary()?))),
+            None => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, GuardError> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Literal(Value::from(n))),
+            Some(Token::Str(s)) => Ok(Expr::Literal(Value::String(s))),
+            Some(Token::Ident(name)) => match name.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                _ if self.peek() == Some(&Token::LParen) => {
+                    self.pos += 1;
+                    let mut args = Vec::new();
+                    if self.peek() != Some(&Token::RParen) {
+                        loop {
+                            args.push(self.or()?);
+                            if self.peek() != Some(&Token::Comma) {
+                                break;
+                            }
+                            self.pos += 1;
+                        }
+                    }
+                    self.expect_rparen()?;
+                    Ok(Expr::Call(name, args))
+                }
+                _ => Ok(Expr::Variable(name)),
+            },
+            Some(Token::LParen) => {
+                let inner = self.or()?;
+                self.expect_rparen()?;
+                Ok(inner)
+            }
+            Some(token) => Err(GuardError::Parse(format!("unexpected {:?}", token))),
+            None => Err(GuardError::Parse("unexpected end of expression".to_string())),
+        }
+    }
+
+    fn expect_rparen(&mut self) -> Result<(), GuardError> {
+        match self.next() {
+            Some(Token::RParen) => Ok(()),
+            _ => Err(GuardError::Parse("expected ')'".to_string())),
+        }
+    }
+}
+
+/// Evaluates guard expressions against named simulation variables
+///
+/// Supports `== != < <= > >=`, `&& || !` (or `and or not`), arithmetic,
+/// dotted variable paths into JSON objects and the functions
+/// `elapsed_ms()`, `abs(x)`, `min(a, b)` and `max(a, b)`.
+pub struct GuardEvaluator<'a> {
+    variables: &'a HashMap<String, Value>,
+    elapsed_ms: u64,
+}
+
+impl<'a> GuardEvaluator<'a> {
+    pub fn new(variables: &'a HashMap<String, Value>) -> Self {
+        Self { variables, elapsed_ms: 0 }
+    }
+
+    /// Value returned by `elapsed_ms()`
+    pub fn with_elapsed_ms(mut self, elapsed_ms: u64) -> Self {
+        self.elapsed_ms = elapsed_ms;
+        self
+    }
+
+    /// Evaluate a guard; an empty guard always passes
+    pub fn evaluate(&self, expression: &str) -> Result<bool, GuardError> {
+        if expression.trim().is_empty() {
+            return Ok(true);
+        }
+        Ok(truthy(&self.value(expression)?))
+    }
+
+    /// Evaluate an expression to a JSON value
+    pub fn value(&self, expression: &str) -> Result<Value, GuardError> {
+        let mut parser = Parser { tokens: tokenize(expression)?, pos: 0 };
+        let expr = parser.or()?;
+        if let Some(token) = parser.peek() {
+            return Err(GuardError::Parse(format!("unexpected {:?}", token)));
+        }
+        self.eval(&expr)
+    }
+
+    fn eval(&self, expr: &Expr) -> Result<Value, GuardError> {
+        match expr {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Variable(name) => self.lookup(name),
+            Expr::Call(name, args) => {
+                let args = args.iter().map(|a| self.eval(a)).collect::<Result<Vec<_>, _>>()?;
+                self.call(name, &args)
+            }
+            Expr::Not(inner) => Ok(Value::Bool(!truthy(&self.eval(inner)?))),
+            Expr::Neg(inner) => Ok(Value::from(-number(&self.eval(inner)?)?)),
+            Expr::Binary("&&", l, r) => Ok(Value::Bool(truthy(&self.eval(l)?) && truthy(&self.eval(r)?))),
+            Expr::Binary("||", l, r) => Ok(Value::Bool(truthy(&self.eval(l)?) || truthy(&self.eval(r)?))),
+            Expr::Binary(op, l, r) => binary(op, &self.eval(l)?, &self.eval(r)?),
+        }
+    }
+
+    fn lookup(&self, path: &str) -> Result<Value, GuardError> {
+        let mut parts = path.split('.');
+        let root = parts.next().unwrap_or_default();
+        let mut value = self.variables.get(root)
+            .ok_or_else(|| GuardError::UnknownVariable(path.to_string()))?;
+        for part in parts {
+            value = value.get(part).ok_or_else(|| GuardError::UnknownVariable(path.to_string()))?;
+        }
+        Ok(value.clone())
+    }
+
+    fn call(&self, name: &str, args: &[Value]) -> Result<Value, GuardError> {
+        let arity = |n: usize| {
+            if args.len() == n { Ok(()) } else { Err(GuardError::Type(format!("{}() takes {} argument(s)", name, n))) }
+        };
+        match name {
+            "elapsed_ms" => {
+                arity(0)?;
+                Ok(Value::from(self.elapsed_ms))
+            }
+            "abs" => {
+                arity(1)?;
+                Ok(Value::from(number(&args[0])?.abs()))
+            }
+            "min" | "max" => {
+                arity(2)?;
+                let (a, b) = (number(&args[0])?, number(&args[1])?);
+                Ok(Value::from(if name == "min" { a.min(b) } else { a.max(b) }))
+            }
+            _ => Err(GuardError::UnknownFunction(name.to_string())),
+        }
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+fn number(value: &Value) -> Result<f64, GuardError> {
+    match value {
+        Value::Number(n) => n.as_f64().ok_or_else(|| GuardError::Type("number out of range".to_string())),
+        Value::Bool(b) => Ok(if *b { 1.0 } else { 0.0 }),
+        other => Err(GuardError::Type(format!("expected a number, got {}", other))),
+    }
+}
+
+fn binary(op: &str, left: &Value, right: &Value) -> Result<Value, GuardError> {
+    match op {
+        "==" => Ok(Value::Bool(values_equal(left, right))),
+        "!=" => Ok(Value::Bool(!values_equal(left, right))),
+        "<" | "<=" | ">" | ">=" => {
+            let ordering = match (left, right) {
+                (Value::String(a), Value::String(b)) => a.cmp(b),
+                _ => number(left)?.partial_cmp(&number(right)?)
+                    .ok_or_else(|| GuardError::Type("cannot compare NaN".to_string()))?,
+            };
+            Ok(Value::Bool(match op {
+                "<" => ordering.is_lt(),
+                "<=" => ordering.is_le(),
+                ">" => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            }))
+        }
+        "+" => match (left, right) {
+            (Value::String(a), Value::String(b)) => Ok(Value::String(format!("{}{}", a, b))),
+            _ => Ok(Value::from(number(left)? + number(right)?)),
+        },
+        "-" => Ok(Value::from(number(left)? - number(right)?)),
+        "*" => Ok(Value::from(number(left)? * number(right)?)),
+        "/" | "%" => {
+            let divisor = number(right)?;
+            if divisor == 0.0 {
+                return Err(GuardError::Type("division by zero".to_string()));
+            }
+            let dividend = number(left)?;
+            Ok(Value::from(if op == "/" { dividend / divisor } else { dividend % divisor }))
+        }
+        _ => Err(GuardError::Parse(format!("unknown operator '{}'", op))),
+    }
+}
+
+/// Numbers compare by value so `1 == 1.0`; everything else structurally
+fn values_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        _ => left == right,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn vars() -> HashMap<String, Value> {
+        HashMap::from([
+            ("x".to_string(), json!(7)),
+            ("y".to_string(), json!(0)),
+            ("button_pressed".to_string(), json!(true)),
+            ("mode".to_string(), json!("idle")),
+            ("sensor".to_string(), json!({ "temp": 41.5 })),
+        ])
+    }
+
+    #[test]
+    fn test_guard_expressions() {
+        let vars = vars();
+        let guard = GuardEvaluator::new(&vars).with_elapsed_ms(1500);
+        assert!(guard.evaluate("x > 5 && y == 0").unwrap());
+        assert!(guard.evaluate("button_pressed != false").unwrap());
+        assert!(guard.evaluate("!(x < 5) || mode == 'busy'").unwrap());
+        assert!(guard.evaluate("elapsed_ms() > 1000 and sensor.temp >= 40").unwrap());
+        assert!(guard.evaluate("x * 2 - 4 == 10 && max(x, 0x10) == 16").unwrap());
+        assert!(!guard.evaluate("mode == \"busy\"").unwrap());
+        assert!(guard.evaluate("  ").unwrap());
+    }
+
+    #[test]
+    fn test_guard_errors() {
+        let vars = vars();
+        let guard = GuardEvaluator::new(&vars);
+        assert_eq!(guard.evaluate("z > 1"), Err(GuardError::UnknownVariable("z".to_string())));
+        assert_eq!(guard.evaluate("now() > 1"), Err(GuardError::UnknownFunction("now".to_string())));
+        assert!(matches!(guard.evaluate("x > "), Err(GuardError::Parse(_))));
+        assert!(matches!(guard.evaluate("(x > 1"), Err(GuardError::Parse(_))));
+        assert!(matches!(guard.evaluate("mode > 1"), Err(GuardError::Type(_))));
+    }
+}
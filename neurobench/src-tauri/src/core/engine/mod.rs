@@ -0,0 +1,368 @@
+// FSM Executor
+// Runs the state machine simulation
+
+pub mod actions;
+pub mod guard;
+
+use super::types::*;
+use super::graph::FSMGraph;
+use actions::{ActionRunner, ExecutedAction};
+use guard::GuardEvaluator;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use chrono::Utc;
+
+/// FSM Executor for running simulations
+pub struct FSMExecutor {
+    graph: Arc<Mutex<FSMGraph>>,
+    current_node: Option<NodeId>,
+    context: SimulationContext,
+    status: SimulationStatus,
+    logs: Vec<LogEntry>,
+    step_count: u64,
+    state_entered: Instant,
+    config: SimulationConfig,
+}
+
+impl FSMExecutor {
+    pub fn new(graph: FSMGraph) -> Self {
+        Self {
+            graph: Arc::new(Mutex::new(graph)),
+            current_node: None,
+            context: SimulationContext::new(),
+            status: SimulationStatus::Idle,
+            logs: vec![],
+            step_count: 0,
+            state_entered: Instant::now(),
+            config: SimulationConfig::default(),
+        }
+    }
+    
+    pub fn with_config(mut self, config: SimulationConfig) -> Self {
+        self.config = config;
+        self
+    }
+    
+    /// Start simulation from the initial node
+    pub fn start(&mut self) -> Result<(), String> {
+        let (start_id, entry_action) = {
+            let graph = self.graph.lock().map_err(|e| e.to_string())?;
+            let start_node = graph.find_start_node()
+                .ok_or("No start node found in the FSM")?;
+            (start_node.id, start_node.entry_action.clone())
+        };
+        
+        self.current_node = Some(start_id);
+        self.status = SimulationStatus::Running;
+        self.step_count = 0;
+        self.state_entered = Instant::now();
+        self.context.clear();
+        
+        self.log(LogLevel::Info, "SYSTEM", "Simulation started");
+        
+        if let Some(action) = entry_action {
+            self.log(LogLevel::Debug, "EXEC", &format!("Entry: {}", action));
+        }
+        
+        Ok(())
+    }
+    
+    /// Resume a simulation in `node_id`, e.g. where the editor last left it
+    pub fn start_at(&mut self, node_id: NodeId) -> Result<(), String> {
+        let label = {
+            let graph = self.graph.lock().map_err(|e| e.to_string())?;
+            graph.get_node(node_id).ok_or("Node not found")?.label.clone()
+        };
+        
+        self.current_node = Some(node_id);
+        self.status = SimulationStatus::Running;
+        self.step_count = 0;
+        self.state_entered = Instant::now();
+        self.context.clear();
+        
+        self.log(LogLevel::Info, "SYSTEM", &format!("Simulation resumed in {}", label));
+        Ok(())
+    }
+    
+    /// Stop the simulation
+    pub fn stop(&mut self) {
+        self.status = SimulationStatus::Idle;
+        self.current_node = None;
+        self.log(LogLevel::Info, "SYSTEM", "Simulation stopped");
+    }
+    
+    /// Execute a single step
+    pub fn step(&mut self) -> Result<StepResult, String> {
+        self.advance(None, false).map(|(result, _)| result)
+    }
+    
+    /// Step on `event` with `vars` as the context, running exit, edge and entry actions
+    ///
+    /// Returns the resulting state, the updated variables and the actions that ran.
+    pub fn simulate_step_with_actions(
+        &mut self,
+        event: Option<&str>,
+        vars: SimulationContext,
+    ) -> Result<(Option<NodeId>, SimulationContext, Vec<ExecutedAction>), String> {
+        self.context = vars;
+        let (_, executed) = self.advance(event, true)?;
+        Ok((self.current_node, self.context.clone(), executed))
+    }
+    
+    /// Take the first enabled transition, optionally restricted to edges labelled `event`
+    fn advance(&mut self, event: Option<&str>, run_actions: bool) -> Result<(StepResult, Vec<ExecutedAction>), String> {
+        if self.status != SimulationStatus::Running && self.status != SimulationStatus::Stepping {
+            return Err("Simulation not running".to_string());
+        }
+        
+        let current_id = self.current_node
+            .ok_or("No current state")?;
+        
+        // Extract all data we need from the graph first
+        let mut guard_errors = Vec::new();
+        let step_data = {
+            let graph = self.graph.lock().map_err(|e| e.to_string())?;
+            
+            let edges = graph.get_outgoing(current_id);
+            
+            if edges.is_empty() {
+                let node = graph.get_node(current_id).ok_or("Node not found")?;
+                if node.node_type == NodeType::Output {
+                    return Ok((StepResult::Completed, vec![]));
+                } else {
+                    return Ok((StepResult::Deadlock, vec![]));
+                }
+            }
+            
+            // First edge whose guard holds; a guard that fails to evaluate blocks its edge
+            let evaluator = GuardEvaluator::new(&self.context)
+                .with_elapsed_ms(self.state_entered.elapsed().as_millis() as u64);
+            let edge = edges.iter()
+                .filter(|edge| event.is_none() || edge.label.is_none() || edge.label.as_deref() == event)
+                .find(|edge| match edge.guard.as_deref() {
+                    None => true,
+                    Some(guard) => evaluator.evaluate(guard).unwrap_or_else(|e| {
+                        guard_errors.push(format!("[{}] {}", guard, e));
+                        false
+                    }),
+                });
+            let Some(edge) = edge else {
+                drop(graph);
+                for error in guard_errors {
+                    self.log(LogLevel::Warning, "GUARD", &error);
+                }
+                return Ok((StepResult::Blocked, vec![]));
+            };
+            let next_node_id = edge.target;
+            let transition_label = edge.label.clone().unwrap_or_else(|| "→".to_string());
+            let edge_action = edge.action.clone();
+            
+            let exit_action = graph.get_node(current_id)
+                .and_then(|n| n.exit_action.clone());
+            let from_label = graph.get_node(current_id)
+                .map(|n| n.label.clone())
+                .unwrap_or_default();
+            let to_label = graph.get_node(next_node_id)
+                .map(|n| n.label.clone())
+                .unwrap_or_default();
+            let entry_action = graph.get_node(next_node_id)
+                .and_then(|n| n.entry_action.clone());
+            
+            (next_node_id, transition_label, exit_action, edge_action, from_label, to_label, entry_action)
+        };
+        
+        let (next_node_id, transition_label, exit_action, edge_action, from_label, to_label, entry_action) = step_data;
+        
+        for error in guard_errors {
+            self.log(LogLevel::Warning, "GUARD", &error);
+        }
+        
+        // Now we can log without holding the lock
+        let mut executed = Vec::new();
+        if let Some(action) = exit_action {
+            self.log(LogLevel::Debug, "EXEC", &format!("Exit: {}", action));
+            if run_actions {
+                executed.push(self.run_action(&format!("exit {}", from_label), &action));
+            }
+        }
+        
+        self.log(LogLevel::Info, "TRANSITION", &format!("{} --[{}]--> {}", from_label, transition_label, to_label));
+        if let (Some(action), true) = (edge_action, run_actions) {
+            executed.push(self.run_action(&format!("edge {}", transition_label), &action));
+        }
+        
+        if let Some(action) = entry_action {
+            self.log(LogLevel::Debug, "EXEC", &format!("Entry: {}", action));
+            if run_actions {
+                executed.push(self.run_action(&format!("entry {}", to_label), &action));
+            }
+        }
+        
+        // Update state
+        self.current_node = Some(next_node_id);
+        self.step_count += 1;
+        self.state_entered = Instant::now();
+        
+        Ok((StepResult::Transitioned { 
+            from: current_id, 
+            to: next_node_id 
+        }, executed))
+    }
+    
+    fn run_action(&mut self, origin: &str, code: &str) -> ExecutedAction {
+        let runner = ActionRunner::new(self.config.simulation_action_language);
+        let executed = runner.run(origin, code, &mut self.context);
+        if let Some(error) = &executed.error {
+            self.log(LogLevel::Warning, "ACTION", &format!("{}: {}", origin, error));
+        }
+        executed
+    }
+    
+    /// Trigger an event to cause a transition
+    pub fn trigger_event(&mut self, event: &str) -> Result<(), String> {
+        self.advance(Some(event), false).map(|_| ())
+    }
+    
+    /// Get current simulation status
+    pub fn status(&self) -> SimulationStatus {
+        self.status
+    }
+    
+    /// Get current node ID
+    pub fn current_node(&self) -> Option<NodeId> {
+        self.current_node
+    }
+    
+    /// When the current state was entered, the zero point of `elapsed_ms()`
+    pub fn state_entered(&self) -> Instant {
+        self.state_entered
+    }
+    
+    /// Carry over the entry time of a state resumed with `start_at`
+    pub fn set_state_entered(&mut self, entered: Instant) {
+        self.state_entered = entered;
+    }
+    
+    /// Get the context variables
+    pub fn context(&self) -> &SimulationContext {
+        &self.context
+    }
+    
+    /// Set a context variable
+    pub fn set_context(&mut self, key: impl Into<String>, value: serde_json::Value) {
+        self.context.insert(key.into(), value);
+    }
+    
+    /// Get logs
+    pub fn logs(&self) -> &[LogEntry] {
+        &self.logs
+    }
+    
+    /// Clear logs
+    pub fn clear_logs(&mut self) {
+        self.logs.clear();
+    }
+    
+    /// Get step count
+    pub fn step_count(&self) -> u64 {
+        self.step_count
+    }
+    
+    fn log(&mut self, level: LogLevel, source: &str, message: &str) {
+        self.logs.push(LogEntry {
+            timestamp: Utc::now(),
+            level,
+            source: source.to_string(),
+            message: message.to_string(),
+        });
+    }
+}
+
+/// Result of a simulation step
+#[derive(Debug, Clone)]
+pub enum StepResult {
+    Transitioned { from: NodeId, to: NodeId },
+    Completed,
+    Deadlock,
+    /// Every outgoing guard evaluated false; the state is unchanged
+    Blocked,
+    Breakpoint(NodeId),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_executor_basic() {
+        let mut graph = FSMGraph::new();
+        
+        let start = FSMNode::new("START", NodeType::Input);
+        let end = FSMNode::new("END", NodeType::Output);
+        
+        let start_id = graph.add_node(start);
+        let end_id = graph.add_node(end);
+        
+        graph.add_edge(FSMEdge::new(start_id, end_id).with_label("GO"));
+        
+        let mut executor = FSMExecutor::new(graph);
+        
+        assert!(executor.start().is_ok());
+        assert_eq!(executor.status(), SimulationStatus::Running);
+        
+        let result = executor.step();
+        assert!(result.is_ok());
+        
+        match result.unwrap() {
+            StepResult::Transitioned { to, .. } => {
+                assert_eq!(to, end_id);
+            }
+            _ => panic!("Expected transition"),
+        }
+    }
+    
+    #[test]
+    fn test_executor_guards() {
+        let mut graph = FSMGraph::new();
+        let idle = graph.add_node(FSMNode::new("IDLE", NodeType::Input));
+        let heat = graph.add_node(FSMNode::new("HEAT", NodeType::Output));
+        let cool = graph.add_node(FSMNode::new("COOL", NodeType::Output));
+        graph.add_edge(FSMEdge::new(idle, heat).with_guard("temp < 20"));
+        graph.add_edge(FSMEdge::new(idle, cool).with_guard("temp > 30 && fan_ok"));
+        
+        let mut executor = FSMExecutor::new(graph);
+        executor.start().unwrap();
+        executor.set_context("temp", serde_json::json!(25));
+        executor.set_context("fan_ok", serde_json::json!(true));
+        assert!(matches!(executor.step().unwrap(), StepResult::Blocked));
+        assert_eq!(executor.current_node(), Some(idle));
+        
+        executor.set_context("temp", serde_json::json!(35));
+        match executor.step().unwrap() {
+            StepResult::Transitioned { to, .. } => assert_eq!(to, cool),
+            other => panic!("Expected transition, got {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_simulate_step_with_actions() {
+        let mut graph = FSMGraph::new();
+        let mut idle = FSMNode::new("IDLE", NodeType::Input);
+        idle.exit_action = Some("presses++;".to_string());
+        let idle = graph.add_node(idle);
+        let on = graph.add_node(FSMNode::new("ON", NodeType::Process).with_entry_action("led = true;"));
+        graph.add_edge(FSMEdge::new(idle, on).with_label("BUTTON").with_guard("enabled").with_action("brightness = 50;"));
+        
+        let mut executor = FSMExecutor::new(graph);
+        executor.start().unwrap();
+        let vars = SimulationContext::from([("enabled".to_string(), serde_json::json!(true))]);
+        let (state, vars, executed) = executor.simulate_step_with_actions(Some("BUTTON"), vars).unwrap();
+        
+        assert_eq!(state, Some(on));
+        assert_eq!(vars["led"], serde_json::json!(true));
+        assert_eq!(vars["brightness"], serde_json::json!(50));
+        assert_eq!(vars["presses"], serde_json::json!(1));
+        let origins: Vec<_> = executed.iter().map(|a| a.origin.as_str()).collect();
+        assert_eq!(origins, ["exit IDLE", "edge BUTTON", "entry ON"]);
+    }
+}
@@ -0,0 +1,5 @@
+// FSM Export
+// Writes FSM graphs in other diagram formats
+
+pub mod plantuml;
+pub mod scxml;
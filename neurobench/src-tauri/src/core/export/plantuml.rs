@@ -0,0 +1,440 @@
+// PlantUML State Diagram Export/Import
+// Writes FSM graphs as UML state diagrams and reads back the state and transition syntax
+
+use crate::core::graph::FSMGraph;
+use crate::core::types::*;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum PlantUmlParseError {
+    #[error("Line {line}: {message}")]
+    Syntax { line: usize, message: String },
+
+    #[error("Line {line}: {feature} is not supported")]
+    Unsupported { line: usize, feature: String },
+
+    #[error("State {0} is missing its closing brace")]
+    UnclosedState(String),
+}
+
+/// Keep code on one line: backslashes doubled, newlines as `\n`
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                out.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                out.push('\\');
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn is_plain_name(label: &str) -> bool {
+    let mut chars = label.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Render as a PlantUML state diagram
+///
+/// Input nodes get a `[*] -->` arrow and Output nodes a `--> [*]` arrow.
+/// Entry and exit actions become `X : entry: ...` description lines, edges
+/// `X --> Y : event [guard] / action`, Decision nodes `<<choice>>` and Group
+/// nodes empty composite `state X { }` blocks. Labels that are not plain
+/// identifiers are declared as `state "label" as Sn`.
+pub fn to_plantuml(graph: &FSMGraph) -> String {
+    let mut nodes: Vec<&FSMNode> = graph.nodes().collect();
+    nodes.sort_by(|a, b| a.label.cmp(&b.label).then(a.id.cmp(&b.id)));
+
+    let mut label_counts: HashMap<&str, usize> = HashMap::new();
+    for node in &nodes {
+        *label_counts.entry(node.label.as_str()).or_default() += 1;
+    }
+    let mut names: HashMap<NodeId, String> = HashMap::new();
+    let mut aliased = HashSet::new();
+    for (i, node) in nodes.iter().enumerate() {
+        let name = if is_plain_name(&node.label) && label_counts[node.label.as_str()] == 1 {
+            node.label.clone()
+        } else {
+            let mut alias = format!("S{}", i);
+            while label_counts.contains_key(alias.as_str()) {
+                alias.push('_');
+            }
+            aliased.insert(node.id);
+            alias
+        };
+        names.insert(node.id, name);
+    }
+
+    let mut uml = String::from("@startuml\nhide empty description\n");
+    if !nodes.is_empty() {
+        uml.push('\n');
+    }
+    for node in &nodes {
+        let name = &names[&node.id];
+        let declaration = if aliased.contains(&node.id) {
+            format!("state \"{}\" as {}", node.label.replace('"', "'").replace('\n', " "), name)
+        } else {
+            format!("state {}", name)
+        };
+        let suffix = match node.node_type {
+            NodeType::Decision => " <<choice>>",
+            NodeType::Group => " {\n}",
+            _ => "",
+        };
+        let _ = writeln!(uml, "{}{}", declaration, suffix);
+        if let Some(entry) = &node.entry_action {
+            let _ = writeln!(uml, "{} : entry: {}", name, escape(entry));
+        }
+        if let Some(exit) = &node.exit_action {
+            let _ = writeln!(uml, "{} : exit: {}", name, escape(exit));
+        }
+        if let Some(description) = &node.description {
+            let _ = writeln!(uml, "{} : {}", name, escape(description));
+        }
+    }
+
+    let mut transitions = Vec::new();
+    for node in nodes.iter().filter(|n| n.node_type == NodeType::Input) {
+        transitions.push(format!("[*] --> {}", names[&node.id]));
+    }
+    let mut edges: Vec<&FSMEdge> = graph.edges().filter(|e| names.contains_key(&e.source) && names.contains_key(&e.target)).collect();
+    edges.sort_by(|a, b| (&names[&a.source], &names[&a.target], &a.label).cmp(&(&names[&b.source], &names[&b.target], &b.label)));
+    for edge in edges {
+        let mut label = Vec::new();
+        if let Some(event) = &edge.label {
+            label.push(escape(event));
+        }
+        if let Some(guard) = &edge.guard {
+            label.push(format!("[{}]", escape(guard)));
+        }
+        if let Some(action) = &edge.action {
+            label.push(format!("/ {}", escape(action)));
+        }
+        let arrow = format!("{} --> {}", names[&edge.source], names[&edge.target]);
+        transitions.push(if label.is_empty() { arrow } else { format!("{} : {}", arrow, label.join(" ")) });
+    }
+    for node in nodes.iter().filter(|n| n.node_type == NodeType::Output) {
+        transitions.push(format!("{} --> [*]", names[&node.id]));
+    }
+    if !transitions.is_empty() {
+        uml.push('\n');
+        for line in transitions {
+            let _ = writeln!(uml, "{}", line);
+        }
+    }
+
+    uml.push_str("@enduml\n");
+    uml
+}
+
+#[derive(Default)]
+struct ParsedState {
+    label: String,
+    stereotype: Option<String>,
+    composite: bool,
+    initial: bool,
+    is_final: bool,
+    entry: Option<String>,
+    exit: Option<String>,
+    description: Option<String>,
+}
+
+/// Parse a PlantUML state diagram: state declarations, description lines and transitions
+///
+/// The graph has no state hierarchy, so states inside composite
+/// `state X { ... }` blocks are flattened and X becomes a Group node;
+/// `[*]` arrows inside a block are dropped. Notes, comments and
+/// layout directives are skipped.
+pub fn from_plantuml(text: &str) -> Result<FSMGraph, PlantUmlParseError> {
+    let declaration = Regex::new(
+        r#"^state\s+(?:"([^"]*)"\s+as\s+([\w.]+)|([\w.]+))\s*(?:<<\s*(\w+)\s*>>)?\s*(\{)?\s*(?::\s*(.*))?$"#,
+    ).unwrap();
+    let transition = Regex::new(r"^(\[\*\]|[\w.]+)\s*-[^>\s]*>\s*(\[\*\]|[\w.]+)\s*(?::\s*(.*))?$").unwrap();
+    let description = Regex::new(r"^([\w.]+)\s*:\s*(.*)$").unwrap();
+
+    let mut states: Vec<(String, ParsedState)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut state = |name: &str, states: &mut Vec<(String, ParsedState)>| -> usize {
+        *index.entry(name.to_string()).or_insert_with(|| {
+            states.push((name.to_string(), ParsedState { label: name.to_string(), ..Default::default() }));
+            states.len() - 1
+        })
+    };
+    let mut transitions: Vec<(usize, usize, Option<String>)> = Vec::new();
+    let mut open_blocks: Vec<String> = Vec::new();
+    let mut in_note = false;
+
+    for (i, raw) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let line = raw.trim();
+        let lower = line.to_lowercase();
+
+        if in_note {
+            in_note = !(lower == "end note" || lower == "endnote");
+            continue;
+        }
+        if line.is_empty() || line.starts_with('\'') || lower.starts_with("@startuml") || lower.starts_with("@enduml") {
+            continue;
+        }
+        if ["hide ", "show ", "skinparam", "title ", "scale ", "caption ", "left to right", "top to bottom"]
+            .iter()
+            .any(|directive| lower.starts_with(directive))
+        {
+            continue;
+        }
+        if lower.starts_with("note ") {
+            in_note = !line.contains(':');
+            continue;
+        }
+        if line == "}" {
+            if open_blocks.pop().is_none() {
+                return Err(PlantUmlParseError::Syntax { line: line_no, message: "unmatched }".to_string() });
+            }
+            continue;
+        }
+        if line == "--" || line == "||" {
+            return Err(PlantUmlParseError::Unsupported { line: line_no, feature: "concurrent region".to_string() });
+        }
+
+        if let Some(caps) = declaration.captures(line) {
+            let name = caps.get(2).or(caps.get(3)).map(|m| m.as_str()).unwrap_or_default();
+            let idx = state(name, &mut states);
+            let parsed = &mut states[idx].1;
+            if let Some(label) = caps.get(1) {
+                parsed.label = label.as_str().to_string();
+            }
+            if let Some(stereotype) = caps.get(4) {
+                parsed.stereotype = Some(stereotype.as_str().to_lowercase());
+            }
+            if let Some(text) = caps.get(6) {
+                add_description(parsed, text.as_str());
+            }
+            if caps.get(5).is_some() {
+                parsed.composite = true;
+                open_blocks.push(name.to_string());
+            }
+        } else if let Some(caps) = transition.captures(line) {
+            let (from, to) = (&caps[1], &caps[2]);
+            let nested = !open_blocks.is_empty();
+            match (from, to) {
+                ("[*]", "[*]") => {}
+                ("[*]", target) => {
+                    let idx = state(target, &mut states);
+                    states[idx].1.initial |= !nested;
+                }
+                (source, "[*]") => {
+                    let idx = state(source, &mut states);
+                    states[idx].1.is_final |= !nested;
+                }
+                (source, target) => {
+                    let source = state(source, &mut states);
+                    let target = state(target, &mut states);
+                    transitions.push((source, target, caps.get(3).map(|m| m.as_str().to_string())));
+                }
+            }
+        } else if let Some(caps) = description.captures(line) {
+            let idx = state(&caps[1], &mut states);
+            add_description(&mut states[idx].1, &caps[2]);
+        } else if lower.starts_with("state ") {
+            return Err(PlantUmlParseError::Syntax { line: line_no, message: format!("invalid state declaration: {}", line) });
+        } else {
+            return Err(PlantUmlParseError::Syntax { line: line_no, message: format!("unrecognized statement: {}", line) });
+        }
+    }
+    if let Some(name) = open_blocks.pop() {
+        return Err(PlantUmlParseError::UnclosedState(name));
+    }
+
+    let mut graph = FSMGraph::new();
+    let mut ids = Vec::with_capacity(states.len());
+    for (i, (_, parsed)) in states.into_iter().enumerate() {
+        let node_type = if parsed.stereotype.as_deref() == Some("choice") {
+            NodeType::Decision
+        } else if parsed.composite {
+            NodeType::Group
+        } else if parsed.initial {
+            NodeType::Input
+        } else if parsed.is_final {
+            NodeType::Output
+        } else {
+            NodeType::Process
+        };
+        let mut node = FSMNode::new(parsed.label, node_type)
+            .with_position(100.0 + 200.0 * (i % 5) as f64, 100.0 + 150.0 * (i / 5) as f64);
+        node.entry_action = parsed.entry;
+        node.exit_action = parsed.exit;
+        node.description = parsed.description;
+        ids.push(graph.add_node(node));
+    }
+    for (source, target, label) in transitions {
+        let mut edge = FSMEdge::new(ids[source], ids[target]);
+        if let Some(label) = label {
+            (edge.label, edge.guard, edge.action) = split_transition_label(&label);
+        }
+        graph.add_edge(edge);
+    }
+    Ok(graph)
+}
+
+/// Route a `X : text` line to the entry action, exit action or description
+fn add_description(state: &mut ParsedState, text: &str) {
+    let text = text.trim();
+    // `entry:` or `exit/` in any case; the rest of the line keeps its own case
+    let action = |keyword: &str| {
+        text.get(..keyword.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(keyword))
+            .and_then(|_| text[keyword.len()..].strip_prefix([':', '/']))
+    };
+    let (slot, body) = if let Some(body) = action("entry") {
+        (&mut state.entry, body)
+    } else if let Some(body) = action("exit") {
+        (&mut state.exit, body)
+    } else {
+        (&mut state.description, text)
+    };
+    let body = unescape(body.trim());
+    *slot = Some(match slot.take() {
+        Some(existing) => format!("{}\n{}", existing, body),
+        None => body,
+    });
+}
+
+/// Split `event [guard] / action` into its parts; the guard may hold nested brackets
+fn split_transition_label(label: &str) -> (Option<String>, Option<String>, Option<String>) {
+    let label = label.trim();
+    let event_end = label.find(['[', '/']).unwrap_or(label.len());
+    let event = label[..event_end].trim();
+    let mut rest = &label[event_end..];
+
+    let mut guard = None;
+    if rest.starts_with('[') {
+        let mut depth = 0;
+        for (i, c) in rest.char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        guard = Some(rest[1..i].trim());
+                        rest = rest[i + 1..].trim_start();
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    let action = rest.strip_prefix('/').map(str::trim);
+
+    let part = |text: Option<&str>| text.filter(|t| !t.is_empty()).map(unescape);
+    (part(Some(event)), part(guard), part(action))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KNOWN: &str = "@startuml
+hide empty description
+
+state CHECK <<choice>>
+state DONE
+DONE : exit: log(\"done\\\\n\");
+state IDLE
+IDLE : entry: led = false;\\ntimer_stop();
+state \"Run fast\" as S3
+S3 : Motor at full speed
+
+[*] --> IDLE
+CHECK --> DONE : [count >= limit[0]]
+CHECK --> S3 : [count < limit[0]] / count = count / 2;
+IDLE --> S3 : START [speed > 0]
+S3 --> CHECK : TICK
+DONE --> [*]
+@enduml
+";
+
+    #[test]
+    fn test_plantuml_round_trip() {
+        let graph = from_plantuml(KNOWN).unwrap();
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(graph.edge_count(), 4);
+
+        let idle = graph.find_start_node().unwrap();
+        assert_eq!(idle.label, "IDLE");
+        assert_eq!(idle.entry_action.as_deref(), Some("led = false;\ntimer_stop();"));
+        let done = graph.nodes().find(|n| n.label == "DONE").unwrap();
+        assert_eq!(done.node_type, NodeType::Output);
+        assert_eq!(done.exit_action.as_deref(), Some("log(\"done\\n\");"));
+        let run = graph.nodes().find(|n| n.label == "Run fast").unwrap();
+        assert_eq!(run.description.as_deref(), Some("Motor at full speed"));
+        let check = graph.nodes().find(|n| n.label == "CHECK").unwrap();
+        assert_eq!(check.node_type, NodeType::Decision);
+
+        let start = graph.get_outgoing(idle.id)[0];
+        assert_eq!((start.label.as_deref(), start.guard.as_deref()), (Some("START"), Some("speed > 0")));
+        let back = graph.get_outgoing(check.id).into_iter().find(|e| e.target == run.id).unwrap();
+        assert_eq!(back.label, None);
+        assert_eq!(back.guard.as_deref(), Some("count < limit[0]"));
+        assert_eq!(back.action.as_deref(), Some("count = count / 2;"));
+
+        assert_eq!(to_plantuml(&graph), KNOWN);
+    }
+
+    #[test]
+    fn test_parse_handwritten_plantuml() {
+        let uml = r#"
+            @startuml
+            ' motor controller
+            [*] -> Off
+            state Running {
+                [*] --> Slow
+                Slow -down-> Fast : SPEED_UP
+                Fast --> [*]
+            }
+            note right of Off
+              waits for the button
+            end note
+            Off -[#green]-> Running : BUTTON / motor_on();
+            Running --> Off : BUTTON / motor_off();
+            @enduml
+        "#;
+        let graph = from_plantuml(uml).unwrap();
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(graph.edge_count(), 3);
+        assert_eq!(graph.find_start_node().unwrap().label, "Off");
+        assert_eq!(graph.nodes().find(|n| n.label == "Running").unwrap().node_type, NodeType::Group);
+        assert_eq!(graph.nodes().find(|n| n.label == "Fast").unwrap().node_type, NodeType::Process);
+        assert!(graph.edges().any(|e| e.action.as_deref() == Some("motor_off();")));
+
+        assert_eq!(from_plantuml("state Open {\nA --> B\n").unwrap_err(), PlantUmlParseError::UnclosedState("Open".to_string()));
+        assert!(matches!(from_plantuml("state A {\n--\n}"), Err(PlantUmlParseError::Unsupported { line: 2, .. })));
+        assert!(matches!(from_plantuml("A <-- B"), Err(PlantUmlParseError::Syntax { line: 1, .. })));
+    }
+
+    #[test]
+    fn test_descriptions_with_non_ascii_text() {
+        // `İ` lowercases to three bytes, which used to shift the slice offsets
+        let graph = from_plantuml("state A\nA : Entry: İ = 1;\nA : EXIT/ grüße();\nA : İ entry: not an action\n").unwrap();
+        let a = graph.nodes().next().unwrap();
+        assert_eq!(a.entry_action.as_deref(), Some("İ = 1;"));
+        assert_eq!(a.exit_action.as_deref(), Some("grüße();"));
+        assert_eq!(a.description.as_deref(), Some("İ entry: not an action"));
+    }
+}
@@ -0,0 +1,135 @@
+// SCXML Export
+// Writes FSM graphs as W3C State Chart XML
+
+use crate::core::graph::FSMGraph;
+use crate::core::types::*;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// XML ID from a label: anything but letters, digits and `_` becomes `_`
+fn xml_id(label: &str) -> String {
+    let mut id: String = label.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect();
+    if !id.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        id.insert(0, '_');
+    }
+    id
+}
+
+/// Render as an SCXML document
+///
+/// The first Input node (by label) is the initial state. Output nodes
+/// without outgoing edges become `<final>` states. Entry/exit actions and
+/// transition actions are kept as `<script>` bodies and guards as `cond`,
+/// with the ECMAScript data model since the C expressions mostly read as JS.
+pub fn to_scxml(graph: &FSMGraph, name: &str) -> String {
+    let mut nodes: Vec<&FSMNode> = graph.nodes().collect();
+    nodes.sort_by(|a, b| a.label.cmp(&b.label).then(a.id.cmp(&b.id)));
+
+    let mut ids: HashMap<NodeId, String> = HashMap::new();
+    let mut used = HashSet::new();
+    for node in &nodes {
+        let base = xml_id(&node.label);
+        let mut id = base.clone();
+        let mut n = 2;
+        while !used.insert(id.clone()) {
+            id = format!("{}_{}", base, n);
+            n += 1;
+        }
+        ids.insert(node.id, id);
+    }
+
+    let initial = nodes.iter()
+        .find(|n| n.node_type == NodeType::Input)
+        .or(nodes.first())
+        .map(|n| format!(" initial=\"{}\"", ids[&n.id]))
+        .unwrap_or_default();
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<scxml xmlns=\"http://www.w3.org/2005/07/scxml\" version=\"1.0\" name=\"{}\"{} datamodel=\"ecmascript\">\n",
+        escape(name),
+        initial
+    );
+
+    let mut edges: Vec<&FSMEdge> = graph.edges().filter(|e| ids.contains_key(&e.source) && ids.contains_key(&e.target)).collect();
+    edges.sort_by(|a, b| (&ids[&a.source], &ids[&a.target], &a.label).cmp(&(&ids[&b.source], &ids[&b.target], &b.label)));
+
+    for node in &nodes {
+        let id = &ids[&node.id];
+        let outgoing: Vec<&&FSMEdge> = edges.iter().filter(|e| e.source == node.id).collect();
+        let element = if node.node_type == NodeType::Output && outgoing.is_empty() { "final" } else { "state" };
+        if node.entry_action.is_none() && node.exit_action.is_none() && outgoing.is_empty() {
+            let _ = writeln!(xml, "  <{} id=\"{}\"/>", element, id);
+            continue;
+        }
+
+        let _ = writeln!(xml, "  <{} id=\"{}\">", element, id);
+        if let Some(entry) = &node.entry_action {
+            let _ = writeln!(xml, "    <onentry><script>{}</script></onentry>", escape(entry));
+        }
+        if let Some(exit) = &node.exit_action {
+            let _ = writeln!(xml, "    <onexit><script>{}</script></onexit>", escape(exit));
+        }
+        for edge in outgoing {
+            let mut attributes = String::new();
+            if let Some(event) = &edge.label {
+                let _ = write!(attributes, " event=\"{}\"", escape(event));
+            }
+            if let Some(guard) = &edge.guard {
+                let _ = write!(attributes, " cond=\"{}\"", escape(guard));
+            }
+            let _ = write!(attributes, " target=\"{}\"", ids[&edge.target]);
+            match &edge.action {
+                Some(action) => {
+                    let _ = writeln!(xml, "    <transition{}><script>{}</script></transition>", attributes, escape(action));
+                }
+                None => {
+                    let _ = writeln!(xml, "    <transition{}/>", attributes);
+                }
+            }
+        }
+        let _ = writeln!(xml, "  </{}>", element);
+    }
+    xml.push_str("</scxml>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_scxml() {
+        let mut graph = FSMGraph::new();
+        let idle = FSMNode::new("Idle", NodeType::Input).with_entry_action("count = 0;");
+        let busy = FSMNode::new("Busy 1", NodeType::Process);
+        let done = FSMNode::new("Done", NodeType::Output);
+        let (idle_id, busy_id, done_id) = (idle.id, busy.id, done.id);
+        graph.add_node(idle);
+        graph.add_node(busy);
+        graph.add_node(done);
+        graph.add_edge(FSMEdge::new(idle_id, busy_id).with_label("START").with_guard("count < 3 && ready"));
+        graph.add_edge(FSMEdge::new(busy_id, done_id).with_action("count++;"));
+
+        assert_eq!(
+            to_scxml(&graph, "Worker"),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<scxml xmlns="http://www.w3.org/2005/07/scxml" version="1.0" name="Worker" initial="Idle" datamodel="ecmascript">
+  <state id="Busy_1">
+    <transition target="Done"><script>count++;</script></transition>
+  </state>
+  <final id="Done"/>
+  <state id="Idle">
+    <onentry><script>count = 0;</script></onentry>
+    <transition event="START" cond="count &lt; 3 &amp;&amp; ready" target="Busy_1"/>
+  </state>
+</scxml>
+"#
+        );
+    }
+}
@@ -0,0 +1,481 @@
+// Graphviz DOT Export/Import
+// Writes FSM graphs as DOT and reads back the subset of DOT we emit
+
+use super::FSMGraph;
+use crate::core::types::*;
+use std::collections::HashMap;
+use std::fmt::Write;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum DotParseError {
+    #[error("Line {line}: {message}")]
+    Syntax { line: usize, message: String },
+
+    #[error("Line {line}: {feature} is not supported")]
+    Unsupported { line: usize, feature: String },
+
+    #[error("Unexpected end of DOT input")]
+    UnexpectedEof,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn node_type_name(node_type: NodeType) -> String {
+    serde_json::to_value(node_type).ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default()
+}
+
+impl FSMGraph {
+    /// Render as a Graphviz digraph, laid out left to right
+    ///
+    /// Initial states are circles, final states double circles and everything
+    /// else boxes. Actions, guards and node types are kept as extra attributes
+    /// so `from_dot` can read the graph back.
+    pub fn to_dot(&self) -> String {
+        let mut nodes: Vec<&FSMNode> = self.nodes().collect();
+        nodes.sort_by(|a, b| a.label.cmp(&b.label).then(a.id.cmp(&b.id)));
+        let ids: HashMap<NodeId, String> = nodes.iter().enumerate()
+            .map(|(i, n)| (n.id, format!("n{}", i)))
+            .collect();
+
+        let mut dot = String::from("digraph FSM {\n    rankdir=LR;\n    node [fontname=\"Helvetica\"];\n    edge [fontname=\"Helvetica\"];\n\n");
+        for node in &nodes {
+            let (state, shape) = match node.node_type {
+                NodeType::Input => ("initial", "circle"),
+                NodeType::Output => ("final", "doublecircle"),
+                _ => ("normal", "box"),
+            };
+            let mut attrs = vec![
+                format!("label=\"{}\"", escape(&node.label)),
+                format!("shape={}", shape),
+                format!("state=\"{}\"", state),
+                format!("node_type=\"{}\"", node_type_name(node.node_type)),
+            ];
+            let mut tooltip = Vec::new();
+            if let Some(entry) = &node.entry_action {
+                tooltip.push(format!("entry: {}", entry));
+                attrs.push(format!("entry=\"{}\"", escape(entry)));
+            }
+            if let Some(exit) = &node.exit_action {
+                tooltip.push(format!("exit: {}", exit));
+                attrs.push(format!("exit=\"{}\"", escape(exit)));
+            }
+            if !tooltip.is_empty() {
+                attrs.push(format!("tooltip=\"{}\"", escape(&tooltip.join("\n"))));
+            }
+            let _ = writeln!(dot, "    {} [{}];", ids[&node.id], attrs.join(", "));
+        }
+
+        let mut edges: Vec<&FSMEdge> = self.edges().filter(|e| ids.contains_key(&e.source) && ids.contains_key(&e.target)).collect();
+        edges.sort_by(|a, b| (&ids[&a.source], &ids[&a.target], &a.label).cmp(&(&ids[&b.source], &ids[&b.target], &b.label)));
+        if !edges.is_empty() {
+            dot.push('\n');
+        }
+        for edge in edges {
+            let mut attrs = Vec::new();
+            let label = match (&edge.label, &edge.guard) {
+                (Some(event), Some(guard)) => format!("{} [{}]", event, guard),
+                (Some(event), None) => event.clone(),
+                (None, Some(guard)) => format!("[{}]", guard),
+                (None, None) => String::new(),
+            };
+            if !label.is_empty() {
+                attrs.push(format!("label=\"{}\"", escape(&label)));
+            }
+            if let Some(event) = &edge.label {
+                attrs.push(format!("event=\"{}\"", escape(event)));
+            }
+            if let Some(guard) = &edge.guard {
+                attrs.push(format!("guard=\"{}\"", escape(guard)));
+            }
+            if let Some(action) = &edge.action {
+                attrs.push(format!("action=\"{}\"", escape(action)));
+            }
+            let attrs = if attrs.is_empty() { String::new() } else { format!(" [{}]", attrs.join(", ")) };
+            let _ = writeln!(dot, "    {} -> {}{};", ids[&edge.source], ids[&edge.target], attrs);
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Parse a DOT digraph: node, edge and attribute statements, without subgraphs or ports
+    pub fn from_dot(input: &str) -> Result<FSMGraph, DotParseError> {
+        let tokens = tokenize(input)?;
+        DotParser { tokens, pos: 0, nodes: Vec::new(), index: HashMap::new(), edges: Vec::new(), node_defaults: HashMap::new() }
+            .parse()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Id(String),
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Equals,
+    Semi,
+    Comma,
+    Edge,
+    Other(char),
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Tok, usize)>, DotParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let (mut i, mut line) = (0, 1);
+    let mut line_start = true;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\n' => {
+                line += 1;
+                line_start = true;
+                i += 1;
+                continue;
+            }
+            _ if c.is_whitespace() => i += 1,
+            '#' if line_start => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    if chars[i] == '\n' {
+                        line += 1;
+                    }
+                    i += 1;
+                }
+                i += 2;
+            }
+            '"' => {
+                let start_line = line;
+                let mut text = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(DotParseError::Syntax { line: start_line, message: "unterminated string".to_string() }),
+                        Some('"') => break,
+                        Some('\\') => {
+                            match chars.get(i + 1) {
+                                Some('n') | Some('l') | Some('r') => text.push('\n'),
+                                Some('"') => text.push('"'),
+                                Some('\\') => text.push('\\'),
+                                Some('\n') => line += 1, // line continuation
+                                Some(other) => {
+                                    text.push('\\');
+                                    text.push(*other);
+                                }
+                                None => {}
+                            }
+                            i += 1;
+                        }
+                        Some(ch) => {
+                            if *ch == '\n' {
+                                line += 1;
+                            }
+                            text.push(*ch);
+                        }
+                    }
+                    i += 1;
+                }
+                i += 1;
+                tokens.push((Tok::Id(text), start_line));
+            }
+            '-' if matches!(chars.get(i + 1), Some('>') | Some('-')) => {
+                tokens.push((Tok::Edge, line));
+                i += 2;
+            }
+            '{' | '}' | '[' | ']' | '=' | ';' | ',' => {
+                tokens.push((match c {
+                    '{' => Tok::LBrace,
+                    '}' => Tok::RBrace,
+                    '[' => Tok::LBracket,
+                    ']' => Tok::RBracket,
+                    '=' => Tok::Equals,
+                    ';' => Tok::Semi,
+                    _ => Tok::Comma,
+                }, line));
+                i += 1;
+            }
+            _ if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.' || (chars[i] == '-' && i == start)) {
+                    i += 1;
+                }
+                tokens.push((Tok::Id(chars[start..i].iter().collect()), line));
+            }
+            _ => {
+                tokens.push((Tok::Other(c), line));
+                i += 1;
+            }
+        }
+        line_start = false;
+    }
+    Ok(tokens)
+}
+
+type Attrs = HashMap<String, String>;
+
+struct DotParser {
+    tokens: Vec<(Tok, usize)>,
+    pos: usize,
+    nodes: Vec<(String, Attrs)>,
+    index: HashMap<String, usize>,
+    edges: Vec<(String, String, Attrs)>,
+    node_defaults: Attrs,
+}
+
+impl DotParser {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens.get(self.pos).or(self.tokens.last()).map(|(_, l)| *l).unwrap_or(1)
+    }
+
+    fn next(&mut self) -> Result<Tok, DotParseError> {
+        let token = self.tokens.get(self.pos).map(|(t, _)| t.clone()).ok_or(DotParseError::UnexpectedEof)?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Tok) -> Result<(), DotParseError> {
+        let line = self.line();
+        match self.next()? {
+            token if token == expected => Ok(()),
+            token => Err(DotParseError::Syntax { line, message: format!("expected {:?}, found {:?}", expected, token) }),
+        }
+    }
+
+    fn id(&mut self) -> Result<String, DotParseError> {
+        let line = self.line();
+        match self.next()? {
+            Tok::Id(id) => Ok(id),
+            Tok::Other('<') => Err(DotParseError::Unsupported { line, feature: "HTML label".to_string() }),
+            token => Err(DotParseError::Syntax { line, message: format!("expected identifier, found {:?}", token) }),
+        }
+    }
+
+    fn parse(mut self) -> Result<FSMGraph, DotParseError> {
+        if self.peek() == Some(&Tok::Id("strict".to_string())) {
+            self.pos += 1;
+        }
+        let line = self.line();
+        match self.id()?.to_lowercase().as_str() {
+            "digraph" => {}
+            "graph" => return Err(DotParseError::Unsupported { line, feature: "undirected graph".to_string() }),
+            other => return Err(DotParseError::Syntax { line, message: format!("expected digraph, found {}", other) }),
+        }
+        if matches!(self.peek(), Some(Tok::Id(_))) {
+            self.pos += 1;
+        }
+        self.expect(Tok::LBrace)?;
+
+        loop {
+            let line = self.line();
+            match self.peek() {
+                Some(Tok::RBrace) => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(Tok::Semi) | Some(Tok::Comma) => self.pos += 1,
+                Some(Tok::LBrace) => return Err(DotParseError::Unsupported { line, feature: "anonymous subgraph".to_string() }),
+                None => return Err(DotParseError::UnexpectedEof),
+                _ => self.statement()?,
+            }
+        }
+        Ok(self.build())
+    }
+
+    fn statement(&mut self) -> Result<(), DotParseError> {
+        let line = self.line();
+        let id = self.id()?;
+        match id.as_str() {
+            "subgraph" => return Err(DotParseError::Unsupported { line, feature: "subgraph".to_string() }),
+            "graph" | "edge" if self.peek() == Some(&Tok::LBracket) => {
+                self.attr_lists()?;
+                return Ok(());
+            }
+            "node" if self.peek() == Some(&Tok::LBracket) => {
+                let defaults = self.attr_lists()?;
+                self.node_defaults.extend(defaults);
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        match self.peek() {
+            Some(Tok::Equals) => {
+                // Graph attribute such as rankdir=LR
+                self.pos += 1;
+                self.id()?;
+            }
+            Some(Tok::Other(':')) => return Err(DotParseError::Unsupported { line, feature: "node port".to_string() }),
+            Some(Tok::Edge) => {
+                let mut chain = vec![id];
+                while self.peek() == Some(&Tok::Edge) {
+                    self.pos += 1;
+                    chain.push(self.id()?);
+                }
+                let attrs = self.attr_lists()?;
+                for name in &chain {
+                    self.node(name);
+                }
+                for pair in chain.windows(2) {
+                    self.edges.push((pair[0].clone(), pair[1].clone(), attrs.clone()));
+                }
+            }
+            _ => {
+                let attrs = self.attr_lists()?;
+                let index = self.node(&id);
+                self.nodes[index].1.extend(attrs);
+            }
+        }
+        Ok(())
+    }
+
+    fn attr_lists(&mut self) -> Result<Attrs, DotParseError> {
+        let mut attrs = Attrs::new();
+        while self.peek() == Some(&Tok::LBracket) {
+            self.pos += 1;
+            loop {
+                match self.peek() {
+                    Some(Tok::RBracket) => {
+                        self.pos += 1;
+                        break;
+                    }
+                    Some(Tok::Comma) | Some(Tok::Semi) => self.pos += 1,
+                    _ => {
+                        let key = self.id()?;
+                        self.expect(Tok::Equals)?;
+                        attrs.insert(key, self.id()?);
+                    }
+                }
+            }
+        }
+        Ok(attrs)
+    }
+
+    /// Index of a node, declaring it with the current defaults on first use
+    fn node(&mut self, name: &str) -> usize {
+        if let Some(&index) = self.index.get(name) {
+            return index;
+        }
+        self.nodes.push((name.to_string(), self.node_defaults.clone()));
+        self.index.insert(name.to_string(), self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    fn build(self) -> FSMGraph {
+        let mut graph = FSMGraph::new();
+        let mut ids = HashMap::new();
+
+        for (i, (name, attrs)) in self.nodes.iter().enumerate() {
+            let node_type = attrs.get("node_type")
+                .and_then(|t| serde_json::from_value(serde_json::Value::String(t.clone())).ok())
+                .unwrap_or_else(|| match (attrs.get("state").map(String::as_str), attrs.get("shape").map(String::as_str)) {
+                    (Some("initial"), _) | (_, Some("circle")) | (_, Some("Mcircle")) | (_, Some("point")) => NodeType::Input,
+                    (Some("final"), _) | (_, Some("doublecircle")) | (_, Some("Msquare")) => NodeType::Output,
+                    _ => NodeType::Process,
+                });
+            let label = attrs.get("label").filter(|l| !l.is_empty()).unwrap_or(name);
+            let mut node = FSMNode::new(label.clone(), node_type)
+                .with_position(100.0 + 200.0 * (i % 5) as f64, 100.0 + 150.0 * (i / 5) as f64);
+            node.entry_action = attrs.get("entry").cloned();
+            node.exit_action = attrs.get("exit").cloned();
+            ids.insert(name.clone(), graph.add_node(node));
+        }
+
+        for (source, target, attrs) in &self.edges {
+            let mut edge = FSMEdge::new(ids[source], ids[target]);
+            let (label_event, label_guard) = split_edge_label(attrs.get("label").map(String::as_str).unwrap_or_default());
+            edge.label = attrs.get("event").cloned().or(label_event);
+            edge.guard = attrs.get("guard").cloned().or(label_guard);
+            edge.action = attrs.get("action").cloned();
+            graph.add_edge(edge);
+        }
+        graph
+    }
+}
+
+/// Split an edge label like `EVENT [guard]` into its event and guard
+fn split_edge_label(label: &str) -> (Option<String>, Option<String>) {
+    let label = label.trim();
+    let (event, guard) = match (label.find('['), label.ends_with(']')) {
+        (Some(open), true) => (&label[..open], Some(label[open + 1..label.len() - 1].trim().to_string())),
+        _ => (label, None),
+    };
+    let event = event.trim();
+    ((!event.is_empty()).then(|| event.to_string()), guard.filter(|g| !g.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dot_round_trip() {
+        let mut graph = FSMGraph::new();
+        let idle = graph.add_node(FSMNode::new("IDLE", NodeType::Input).with_entry_action("led = false;"));
+        let run = graph.add_node(FSMNode::new("RUN \"fast\"", NodeType::Process));
+        let done = graph.add_node(FSMNode::new("DONE", NodeType::Output));
+        graph.add_edge(FSMEdge::new(idle, run).with_label("START").with_guard("speed > 0"));
+        graph.add_edge(FSMEdge::new(run, done).with_label("STOP").with_action("count++;"));
+
+        let dot = graph.to_dot();
+        assert!(dot.contains("rankdir=LR;"));
+        assert!(dot.contains("shape=circle"));
+        assert!(dot.contains("shape=doublecircle"));
+        assert!(dot.contains("label=\"START [speed > 0]\""));
+        assert!(dot.contains("tooltip=\"entry: led = false;\""));
+
+        let parsed = FSMGraph::from_dot(&dot).unwrap();
+        assert_eq!(parsed.node_count(), 3);
+        assert_eq!(parsed.edge_count(), 2);
+        let run = parsed.nodes().find(|n| n.label == "RUN \"fast\"").unwrap();
+        assert_eq!(run.node_type, NodeType::Process);
+        let start = parsed.find_start_node().unwrap();
+        assert_eq!(start.entry_action.as_deref(), Some("led = false;"));
+        let edge = parsed.get_outgoing(start.id)[0];
+        assert_eq!((edge.label.as_deref(), edge.guard.as_deref()), (Some("START"), Some("speed > 0")));
+        assert_eq!(parsed.get_outgoing(run.id)[0].action.as_deref(), Some("count++;"));
+    }
+
+    #[test]
+    fn test_parse_handwritten_dot() {
+        let dot = r#"
+            // traffic light
+            digraph lights {
+                rankdir = LR
+                node [shape=box]
+                red [shape=circle]
+                red -> green -> yellow [label="TIMER [elapsed_ms() > 500]"];
+                yellow -> red /* wrap */
+            }
+        "#;
+        let graph = FSMGraph::from_dot(dot).unwrap();
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.edge_count(), 3);
+        assert_eq!(graph.find_start_node().unwrap().label, "red");
+        let guarded = graph.edges().filter(|e| e.guard.as_deref() == Some("elapsed_ms() > 500")).count();
+        assert_eq!(guarded, 2);
+
+        assert!(matches!(FSMGraph::from_dot("graph g { a -- b }"), Err(DotParseError::Unsupported { .. })));
+        assert!(matches!(FSMGraph::from_dot("digraph { subgraph x { a } }"), Err(DotParseError::Unsupported { line: 1, .. })));
+        assert_eq!(FSMGraph::from_dot("digraph { a -> ").unwrap_err(), DotParseError::UnexpectedEof);
+    }
+}
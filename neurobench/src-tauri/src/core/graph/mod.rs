@@ -1,9 +1,13 @@
 // FSM Graph Operations
 // Provides graph manipulation utilities
 
+pub mod dot;
+
 use super::types::*;
 use std::collections::HashMap;
 
+pub use dot::DotParseError;
+
 /// FSM Graph structure for efficient lookups
 #[derive(Debug, Clone, Default)]
 pub struct FSMGraph {
@@ -0,0 +1,475 @@
+// C Switch-Case FSM Import
+// Recovers states and transitions from hand-written `switch (state)` machines
+
+use crate::core::graph::FSMGraph;
+use crate::core::types::*;
+use regex::Regex;
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ParseError {
+    #[error("No switch on a state or mode variable found")]
+    NoStateSwitch,
+
+    #[error("Line {line}: unbalanced {what}")]
+    Unbalanced { line: usize, what: &'static str },
+
+    #[error("switch ({0}) has no case labels")]
+    NoCases(String),
+}
+
+/// A state machine recovered from C source
+#[derive(Debug, Clone)]
+pub struct CFsmImport {
+    pub graph: FSMGraph,
+    /// Expression the switch dispatches on, e.g. `ctx->state`
+    pub state_variable: String,
+    /// 0.0-1.0 estimate of how much of the machine was understood
+    pub confidence: f32,
+}
+
+/// Extract an FSM from the `switch` on a state or mode variable in `code`
+pub fn extract_fsm_from_c(code: &str) -> Result<FSMGraph, ParseError> {
+    import_c_fsm(code).map(|import| import.graph)
+}
+
+/// Extract an FSM along with the state variable and a confidence score
+///
+/// Each `case` label becomes a node. Assignments to the state variable (or
+/// calls like `set_state(X)`) inside a case become edges, guarded by the
+/// enclosing `if`/`else` conditions. `default:` is treated as error handling
+/// and skipped. When several switches qualify, the one with most cases wins.
+pub fn import_c_fsm(code: &str) -> Result<CFsmImport, ParseError> {
+    let code = strip_comments_and_strings(code);
+    let switch = find_state_switch(&code)?;
+    let body = &code[switch.body_start..switch.body_end];
+
+    let groups = split_cases(body);
+    let case_labels: Vec<String> = groups.iter().flat_map(|(labels, _)| labels.iter().flatten().cloned()).collect();
+    if case_labels.is_empty() {
+        return Err(ParseError::NoCases(switch.variable));
+    }
+
+    let var = variable_pattern(&switch.variable);
+    let assign = Regex::new(&format!(r"(?:^|[^\w.>])({})\s*=\s*([^=;][^;]*);", var)).unwrap();
+    let setter = Regex::new(r"\b(?:set|change|goto|enter|transition)_?\w*\s*\(\s*([A-Za-z_]\w*)\s*\)").unwrap();
+
+    let mut transitions: Vec<(String, String, Option<String>)> = Vec::new();
+    let (mut sites, mut resolved) = (0usize, 0usize);
+    for (labels, case_body) in &groups {
+        for (raw, guard) in transitions_in(case_body, &assign, &setter, &case_labels) {
+            sites += 1;
+            let Some(target) = state_name(&raw) else { continue };
+            if case_labels.contains(&target) {
+                resolved += 1;
+            }
+            for source in labels.iter().flatten() {
+                let transition = (source.clone(), target.clone(), guard.clone());
+                if !transitions.contains(&transition) {
+                    transitions.push(transition);
+                }
+            }
+        }
+    }
+
+    // Initial state: an assignment outside the switch (declaration or init), else the first case
+    let outside = format!("{}{}", &code[..switch.body_start], &code[switch.body_end..]);
+    let initial = Regex::new(&format!(r"(?:^|[^\w.>]){}\s*=\s*([^=;][^;]*);", var)).unwrap()
+        .captures_iter(&outside)
+        .filter_map(|c| state_name(&c[1]))
+        .find(|name| case_labels.contains(name))
+        .unwrap_or_else(|| case_labels[0].clone());
+
+    // Case labels first, then targets that have no case of their own
+    let mut states = case_labels.clone();
+    for (_, target, _) in &transitions {
+        if !states.contains(target) {
+            states.push(target.clone());
+        }
+    }
+
+    let mut graph = FSMGraph::new();
+    let mut ids = HashMap::new();
+    for (i, state) in states.iter().enumerate() {
+        let upper = state.to_uppercase();
+        let node_type = if *state == initial {
+            NodeType::Input
+        } else if upper.contains("ERROR") || upper.contains("FAULT") {
+            NodeType::Error
+        } else if !transitions.iter().any(|(source, target, _)| source == state && target != state) {
+            NodeType::Output
+        } else {
+            NodeType::Process
+        };
+        let node = FSMNode::new(state.clone(), node_type)
+            .with_position(100.0 + 200.0 * (i % 5) as f64, 100.0 + 150.0 * (i / 5) as f64);
+        ids.insert(state.clone(), graph.add_node(node));
+    }
+    for (source, target, guard) in &transitions {
+        let mut edge = FSMEdge::new(ids[source], ids[target]);
+        edge.guard = guard.clone();
+        graph.add_edge(edge);
+    }
+
+    let with_transitions = case_labels.iter()
+        .filter(|label| transitions.iter().any(|(source, _, _)| source == *label))
+        .count();
+    let target_score = if sites == 0 { 0.0 } else { resolved as f32 / sites as f32 };
+    let case_score = with_transitions as f32 / case_labels.len() as f32;
+    let mut confidence = 0.4 + 0.3 * target_score + 0.3 * case_score;
+    if case_labels.len() < 2 {
+        confidence *= 0.5;
+    }
+
+    Ok(CFsmImport { graph, state_variable: switch.variable, confidence })
+}
+
+struct StateSwitch {
+    variable: String,
+    body_start: usize,
+    body_end: usize,
+}
+
+/// Blank out comments and string/char literal contents, keeping newlines
+fn strip_comments_and_strings(code: &str) -> String {
+    let chars: Vec<char> = code.chars().collect();
+    let mut out = String::with_capacity(code.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                out.push(if chars[i] == '\n' { '\n' } else { ' ' });
+                i += 1;
+            }
+            i += 2;
+            out.push(' ');
+        } else if c == '"' || c == '\'' {
+            out.push(c);
+            i += 1;
+            while i < chars.len() && chars[i] != c && chars[i] != '\n' {
+                if chars[i] == '\\' {
+                    i += 1;
+                    out.push(' ');
+                }
+                out.push(' ');
+                i += 1;
+            }
+            if i < chars.len() {
+                out.push(chars[i]);
+                i += 1;
+            }
+        } else {
+            out.push(c);
+            i += 1;
+        }
+    }
+    out
+}
+
+fn is_ident(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+/// Whether the keyword `word` starts at `i`
+fn word_at(bytes: &[u8], i: usize, word: &str) -> bool {
+    bytes[i..].starts_with(word.as_bytes())
+        && (i == 0 || !is_ident(bytes[i - 1]))
+        && !bytes.get(i + word.len()).is_some_and(|&b| is_ident(b))
+}
+
+/// Index of the bracket closing the one at `open`
+fn matching(bytes: &[u8], open: usize) -> Option<usize> {
+    let (open_ch, close_ch) = (bytes[open], if bytes[open] == b'(' { b')' } else { b'}' });
+    let mut depth = 0;
+    for (i, &b) in bytes.iter().enumerate().skip(open) {
+        if b == open_ch {
+            depth += 1;
+        } else if b == close_ch {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+fn line_of(code: &str, index: usize) -> usize {
+    code[..index].matches('\n').count() + 1
+}
+
+fn skip_whitespace(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+        i += 1;
+    }
+    i
+}
+
+/// Find the switch on a state/mode variable with the most case labels
+fn find_state_switch(code: &str) -> Result<StateSwitch, ParseError> {
+    let bytes = code.as_bytes();
+    let mut best: Option<(usize, StateSwitch)> = None;
+
+    for m in Regex::new(r"\bswitch\s*\(").unwrap().find_iter(code) {
+        let open = m.end() - 1;
+        let close = matching(bytes, open).ok_or(ParseError::Unbalanced { line: line_of(code, open), what: "parentheses" })?;
+        let variable: String = code[open + 1..close].split_whitespace().collect();
+        let lower = variable.to_lowercase();
+        if !lower.contains("state") && !lower.contains("mode") {
+            continue;
+        }
+
+        let brace = skip_whitespace(bytes, close + 1);
+        if bytes.get(brace) != Some(&b'{') {
+            continue;
+        }
+        let end = matching(bytes, brace).ok_or(ParseError::Unbalanced { line: line_of(code, brace), what: "braces" })?;
+        let cases = split_cases(&code[brace + 1..end]).iter().map(|(labels, _)| labels.len()).sum::<usize>();
+        if best.as_ref().is_none_or(|(count, _)| cases > *count) {
+            best = Some((cases, StateSwitch { variable, body_start: brace + 1, body_end: end }));
+        }
+    }
+    best.map(|(_, switch)| switch).ok_or(ParseError::NoStateSwitch)
+}
+
+/// Split a switch body into fall-through label groups and their code
+///
+/// Labels are `Some(name)` for `case name:` and `None` for `default:`. Only
+/// labels at the top level of the body count, so nested switches are left
+/// inside their case.
+fn split_cases(body: &str) -> Vec<(Vec<Option<String>>, String)> {
+    let bytes = body.as_bytes();
+    let mut labels: Vec<(usize, usize, Option<String>)> = Vec::new();
+    let mut depth = 0i32;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' | b'(' => depth += 1,
+            b'}' | b')' => depth -= 1,
+            _ if depth == 0 && word_at(bytes, i, "case") => {
+                // The label ends at the first ':' that isn't part of '::'
+                let mut j = i + 4;
+                while j < bytes.len() && !(bytes[j] == b':' && bytes.get(j + 1) != Some(&b':') && bytes[j - 1] != b':') {
+                    j += 1;
+                }
+                let name: String = body[i + 4..j.min(bytes.len())].split_whitespace().collect();
+                labels.push((i, (j + 1).min(bytes.len()), Some(name)));
+                i = j;
+            }
+            _ if depth == 0 && word_at(bytes, i, "default") => {
+                let colon = skip_whitespace(bytes, i + 7);
+                if bytes.get(colon) == Some(&b':') {
+                    labels.push((i, colon + 1, None));
+                    i = colon;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    let mut groups: Vec<(Vec<Option<String>>, String)> = Vec::new();
+    let mut pending: Vec<Option<String>> = Vec::new();
+    for (k, (_, code_start, name)) in labels.iter().enumerate() {
+        let code_end = labels.get(k + 1).map_or(body.len(), |(start, _, _)| *start);
+        pending.push(name.clone());
+        let code = &body[*code_start..code_end];
+        if !code.trim().is_empty() || k + 1 == labels.len() {
+            groups.push((std::mem::take(&mut pending), code.to_string()));
+        }
+    }
+    groups
+}
+
+/// Regex for the state variable, tolerant of spacing around `->` and `.`
+fn variable_pattern(variable: &str) -> String {
+    variable
+        .split("->")
+        .map(|part| part.split('.').map(regex::escape).collect::<Vec<_>>().join(r"\s*\.\s*"))
+        .collect::<Vec<_>>()
+        .join(r"\s*->\s*")
+}
+
+/// State name from an assigned expression like `STATE_RUN` or `(state_t)STATE_RUN`
+fn state_name(expr: &str) -> Option<String> {
+    let re = Regex::new(r"^(?:\(\s*\w+\s*\)\s*)?\(?\s*([A-Za-z0-9_][\w:]*)\s*\)?$").unwrap();
+    re.captures(expr.trim()).map(|c| c[1].to_string())
+}
+
+fn negate(condition: &str) -> String {
+    if condition.bytes().all(is_ident) {
+        format!("!{}", condition)
+    } else {
+        format!("!({})", condition)
+    }
+}
+
+/// Transition sites in a case body with the `if`/`else` guard around each
+fn transitions_in(body: &str, assign: &Regex, setter: &Regex, states: &[String]) -> Vec<(String, Option<String>)> {
+    let mut sites: Vec<(usize, String)> = assign.captures_iter(body)
+        .map(|c| (c.get(1).unwrap().start(), c[2].trim().to_string()))
+        .chain(setter.captures_iter(body)
+            .filter(|c| states.iter().any(|s| s == &c[1]))
+            .map(|c| (c.get(0).unwrap().start(), c[1].to_string())))
+        .collect();
+    sites.sort_by_key(|(pos, _)| *pos);
+    let mut sites = sites.into_iter().peekable();
+
+    let bytes = body.as_bytes();
+    let mut blocks: Vec<Option<String>> = Vec::new(); // Guard opened by each enclosing brace
+    let mut chains: Vec<Vec<String>> = vec![Vec::new()]; // if/else-if conditions per block
+    let mut pending: Option<String> = None; // Guard waiting for its statement or block
+    let mut after_else = false;
+    let mut out = Vec::new();
+
+    let mut i = 0;
+    while i < bytes.len() {
+        while let Some((_, target)) = sites.next_if(|(pos, _)| *pos <= i) {
+            let guards: Vec<&str> = blocks.iter().flatten().chain(pending.iter()).map(String::as_str).collect();
+            out.push((target, (!guards.is_empty()).then(|| guards.join(" && "))));
+        }
+
+        if word_at(bytes, i, "if") {
+            let open = skip_whitespace(bytes, i + 2);
+            if let Some(close) = (bytes.get(open) == Some(&b'(')).then(|| matching(bytes, open)).flatten() {
+                let condition: String = body[open + 1..close].split_whitespace().collect::<Vec<_>>().join(" ");
+                let chain = chains.last_mut().unwrap();
+                if !after_else {
+                    chain.clear();
+                }
+                let mut guard: Vec<String> = chain.iter().map(|c| negate(c)).collect();
+                guard.push(condition.clone());
+                chain.push(condition);
+                pending = Some(guard.join(" && "));
+                after_else = false;
+                i = close + 1;
+                continue;
+            }
+        } else if word_at(bytes, i, "else") {
+            let next = skip_whitespace(bytes, i + 4);
+            if next < bytes.len() && word_at(bytes, next, "if") {
+                after_else = true;
+            } else {
+                let chain = std::mem::take(chains.last_mut().unwrap());
+                pending = (!chain.is_empty()).then(|| chain.iter().map(|c| negate(c)).collect::<Vec<_>>().join(" && "));
+            }
+            i = next;
+            continue;
+        }
+
+        match bytes[i] {
+            b'{' => {
+                blocks.push(pending.take());
+                chains.push(Vec::new());
+            }
+            b'}' => {
+                blocks.pop();
+                if chains.len() > 1 {
+                    chains.pop();
+                }
+            }
+            b';' => pending = None,
+            // Skip call arguments and loop headers so their ';' don't end the statement
+            b'(' => {
+                if let Some(close) = matching(bytes, i) {
+                    i = close;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRAFFIC: &str = r#"
+        typedef enum { ST_IDLE, ST_RUN, ST_ERROR, ST_DONE } state_t;
+        static state_t g_state = ST_IDLE;
+
+        void fsm_step(void) {
+            switch (g_state) {
+                case ST_IDLE:
+                    if (button_pressed()) {
+                        g_state = ST_RUN;   /* start */
+                    }
+                    break;
+                case ST_RUN:
+                    if (fault) g_state = ST_ERROR;
+                    else if (count >= 10) g_state = ST_DONE;
+                    else count++;
+                    break;
+                case ST_ERROR:
+                    set_state(ST_IDLE);
+                    break;
+                case ST_DONE:
+                    break;
+                default:
+                    g_state = ST_ERROR;
+                    break;
+            }
+        }
+    "#;
+
+    fn guard_between(graph: &FSMGraph, from: &str, to: &str) -> Option<String> {
+        let source = graph.nodes().find(|n| n.label == from).unwrap().id;
+        let target = graph.nodes().find(|n| n.label == to).unwrap().id;
+        graph.get_outgoing(source).into_iter().find(|e| e.target == target).unwrap().guard.clone()
+    }
+
+    #[test]
+    fn test_extract_switch_fsm() {
+        let import = import_c_fsm(TRAFFIC).unwrap();
+        let graph = &import.graph;
+        assert_eq!(import.state_variable, "g_state");
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(graph.edge_count(), 4);
+        assert_eq!(graph.find_start_node().unwrap().label, "ST_IDLE");
+
+        assert_eq!(guard_between(graph, "ST_IDLE", "ST_RUN").as_deref(), Some("button_pressed()"));
+        assert_eq!(guard_between(graph, "ST_RUN", "ST_ERROR").as_deref(), Some("fault"));
+        assert_eq!(guard_between(graph, "ST_RUN", "ST_DONE").as_deref(), Some("!fault && count >= 10"));
+        assert_eq!(guard_between(graph, "ST_ERROR", "ST_IDLE"), None);
+
+        let done = graph.nodes().find(|n| n.label == "ST_DONE").unwrap();
+        assert_eq!(done.node_type, NodeType::Output);
+        assert!(import.confidence > 0.8);
+    }
+
+    #[test]
+    fn test_struct_member_and_fallthrough() {
+        let code = r#"
+            switch (ctx -> mode) {
+                case MODE_A:
+                case MODE_B:
+                    ctx->mode = (mode_t)MODE_C;
+                    break;
+                case MODE_C: {
+                    if (x) { ctx->mode = MODE_A; } else { ctx->mode = next_mode(); }
+                    break;
+                }
+            }
+        "#;
+        let import = import_c_fsm(code).unwrap();
+        assert_eq!(import.state_variable, "ctx->mode");
+        assert_eq!(import.graph.node_count(), 3);
+        assert_eq!(import.graph.edge_count(), 3);
+        assert_eq!(guard_between(&import.graph, "MODE_C", "MODE_A").as_deref(), Some("x"));
+        // The computed target is not understood
+        assert!(import.confidence < 1.0);
+
+        assert_eq!(extract_fsm_from_c("switch (cmd) { case 1: break; }").unwrap_err(), ParseError::NoStateSwitch);
+        assert!(matches!(extract_fsm_from_c("switch (state) { case A: "), Err(ParseError::Unbalanced { line: 1, .. })));
+    }
+}
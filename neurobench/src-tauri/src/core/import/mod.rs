@@ -0,0 +1,4 @@
+// FSM Import
+// Recovers FSM graphs from existing firmware sources
+
+pub mod c_fsm;
@@ -4,6 +4,8 @@
 pub mod types;
 pub mod engine;
 pub mod graph;
+pub mod import;
+pub mod export;
 
 pub use types::*;
 pub use engine::FSMExecutor;
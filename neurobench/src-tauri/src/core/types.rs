@@ -126,6 +126,10 @@ pub struct FSMEdge {
     // Guard condition (JavaScript expression)
     pub guard: Option<String>,
     
+    // Code run when the transition fires
+    #[serde(default)]
+    pub action: Option<String>,
+    
     // Runtime state
     #[serde(default)]
     pub is_traversing: bool,
@@ -139,6 +143,7 @@ impl FSMEdge {
             target,
             label: None,
             guard: None,
+            action: None,
             is_traversing: false,
         }
     }
@@ -152,6 +157,11 @@ impl FSMEdge {
         self.guard = Some(guard.into());
         self
     }
+    
+    pub fn with_action(mut self, code: impl Into<String>) -> Self {
+        self.action = Some(code.into());
+        self
+    }
 }
 
 /// Simulation state
@@ -200,6 +210,22 @@ impl FSMProject {
     }
 }
 
+/// Language that node and edge actions are simulated in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionLanguage {
+    #[default]
+    PseudoC,
+    Lua,
+}
+
+/// Simulation settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SimulationConfig {
+    #[serde(default)]
+    pub simulation_action_language: ActionLanguage,
+}
+
 /// Context variables during simulation
 pub type SimulationContext = std::collections::HashMap<String, serde_json::Value>;
 
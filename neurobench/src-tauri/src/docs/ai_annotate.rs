@@ -0,0 +1,274 @@
+// AI Doxygen Annotation
+// Per-function Doxygen comments generated by the AI service
+
+use super::{generate_function_doc, locate_functions, FunctionDoc, ParamDoc};
+use crate::ai::AIService;
+use serde::{Deserialize, Serialize};
+
+/// Lines of code sent to the AI on each side of the function signature
+const CONTEXT_LINES: usize = 10;
+
+/// Source language of the annotated code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    C,
+    Cpp,
+    Rust,
+}
+
+impl Language {
+    /// Guess the language from a file name, defaulting to C
+    pub fn from_filename(filename: &str) -> Self {
+        match filename.rsplit('.').next().map(|e| e.to_lowercase()).as_deref() {
+            Some("rs") => Language::Rust,
+            Some("cpp") | Some("cc") | Some("cxx") | Some("hpp") => Language::Cpp,
+            _ => Language::C,
+        }
+    }
+}
+
+/// Structured response requested from the AI
+#[derive(Debug, Deserialize)]
+struct AiFunctionDoc {
+    brief: String,
+    #[serde(default)]
+    params: Vec<AiParamDoc>,
+    #[serde(default)]
+    returns: Option<String>,
+    #[serde(default)]
+    notes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AiParamDoc {
+    name: String,
+    #[serde(default)]
+    direction: Option<String>,
+    description: String,
+}
+
+/// Insert AI-generated Doxygen comments above every undocumented function
+pub async fn generate_doxygen_comments(
+    code: &str,
+    language: Language,
+    ai_service: &AIService,
+) -> Result<String, String> {
+    if !ai_service.is_available() {
+        return Err("AI not available. Set GEMINI_API_KEY.".to_string());
+    }
+
+    let lines: Vec<&str> = code.lines().collect();
+    let functions = match language {
+        Language::Rust => locate_rust_functions(code),
+        Language::C | Language::Cpp => locate_functions(code),
+    };
+
+    let mut comments: Vec<(usize, String)> = Vec::new();
+    for (line_index, extracted) in functions {
+        let anchor = comment_anchor(&lines, line_index);
+        if is_documented(&lines, anchor) {
+            continue;
+        }
+
+        let context = context_window(&lines, line_index);
+        let prompt = build_prompt(&extracted, &context, language);
+
+        // Fall back to the extracted skeleton if the response does not parse
+        let doc = match ai_service.generate_json(&prompt).await {
+            Ok(response) => parse_ai_doc(&response, &extracted).unwrap_or(extracted),
+            Err(e) => {
+                log::warn!("AI annotation failed for {}: {}", extracted.name, e);
+                extracted
+            }
+        };
+
+        let indent: String = lines[line_index].chars().take_while(|c| c.is_whitespace()).collect();
+        comments.push((anchor, render_comment(&doc, language, &indent)));
+    }
+
+    Ok(insert_comments(&lines, comments))
+}
+
+/// Source around a function signature, the signature line in the middle
+fn context_window(lines: &[&str], line_index: usize) -> String {
+    let start = line_index.saturating_sub(CONTEXT_LINES);
+    let end = (line_index + CONTEXT_LINES + 1).min(lines.len());
+    lines[start..end].join("\n")
+}
+
+fn build_prompt(doc: &FunctionDoc, context: &str, language: Language) -> String {
+    let lang = match language {
+        Language::C => "C",
+        Language::Cpp => "C++",
+        Language::Rust => "Rust",
+    };
+    format!(
+        r#"You are documenting embedded {lang} firmware. Write Doxygen documentation for the function `{name}`.
+
+```
+{context}
+```
+
+Respond with JSON only, using this exact structure:
+{{
+  "brief": "one-line summary",
+  "params": [{{"name": "param", "direction": "in|out|inout", "description": "..."}}],
+  "returns": "description of the return value or null",
+  "notes": ["timing, reentrancy or hardware side effects worth noting"]
+}}"#,
+        lang = lang,
+        name = doc.name,
+        context = context,
+    )
+}
+
+/// Merge the AI response into the extracted function skeleton
+fn parse_ai_doc(response: &str, extracted: &FunctionDoc) -> Option<FunctionDoc> {
+    let json = response.trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim();
+    let ai: AiFunctionDoc = serde_json::from_str(json).ok()?;
+
+    let params = extracted.params.iter()
+        .map(|p| {
+            let ai_param = ai.params.iter().find(|a| a.name == p.name);
+            ParamDoc {
+                name: p.name.clone(),
+                param_type: p.param_type.clone(),
+                description: ai_param.map(|a| a.description.clone()).unwrap_or_else(|| p.description.clone()),
+                direction: ai_param.and_then(|a| a.direction.clone()).unwrap_or_else(|| p.direction.clone()),
+            }
+        })
+        .collect();
+
+    Some(FunctionDoc {
+        name: extracted.name.clone(),
+        brief: ai.brief,
+        description: String::new(),
+        params,
+        returns: if extracted.returns.is_some() { ai.returns.or(extracted.returns.clone()) } else { None },
+        notes: ai.notes,
+        examples: vec![],
+    })
+}
+
+fn render_comment(doc: &FunctionDoc, language: Language, indent: &str) -> String {
+    let block = generate_function_doc(doc);
+    let lines = block.lines().map(|line| match language {
+        // Rust uses outer doc comments with the same Doxygen tags
+        Language::Rust => match line.trim() {
+            "/**" | "*/" => None,
+            l => Some(format!("{}///{}", indent, l.trim_start_matches('*'))),
+        },
+        _ => Some(format!("{}{}", indent, line)),
+    });
+    lines.flatten().collect::<Vec<_>>().join("\n")
+}
+
+/// Line the comment goes above, skipping attributes on the function
+fn comment_anchor(lines: &[&str], line_index: usize) -> usize {
+    let mut anchor = line_index;
+    while anchor > 0 && lines[anchor - 1].trim().starts_with("#[") {
+        anchor -= 1;
+    }
+    anchor
+}
+
+/// A function is documented when a comment ends right above it
+fn is_documented(lines: &[&str], anchor: usize) -> bool {
+    anchor > 0 && {
+        let prev = lines[anchor - 1].trim();
+        prev.ends_with("*/") || prev.starts_with("///")
+    }
+}
+
+fn insert_comments(lines: &[&str], mut comments: Vec<(usize, String)>) -> String {
+    comments.sort_by_key(|(index, _)| *index);
+    let mut output = Vec::with_capacity(lines.len() + comments.len());
+    let mut pending = comments.into_iter().peekable();
+    for (index, line) in lines.iter().enumerate() {
+        while let Some((_, comment)) = pending.next_if(|(i, _)| *i == index) {
+            output.push(comment);
+        }
+        output.push(line.to_string());
+    }
+    output.join("\n") + "\n"
+}
+
+/// Find `fn` definitions in Rust code
+fn locate_rust_functions(code: &str) -> Vec<(usize, FunctionDoc)> {
+    let mut functions = Vec::new();
+    for (line_index, line) in code.lines().enumerate() {
+        let trimmed = line.trim();
+        let Some(fn_at) = trimmed.find("fn ") else { continue };
+        let prefix = &trimmed[..fn_at];
+        if !prefix.split_whitespace().all(|w| matches!(w, "pub" | "pub(crate)" | "async" | "const" | "unsafe" | "extern")) {
+            continue;
+        }
+        let rest = &trimmed[fn_at + 3..];
+        let Some(paren) = rest.find('(') else { continue };
+        let name = rest[..paren].split('<').next().unwrap_or("").trim().to_string();
+
+        let params = rest[paren + 1..].split(')').next().unwrap_or("")
+            .split(',')
+            .filter_map(|p| {
+                let (name, ty) = p.split_once(':')?;
+                Some(ParamDoc {
+                    name: name.trim().to_string(),
+                    param_type: ty.trim().to_string(),
+                    description: "Parameter description".to_string(),
+                    direction: "in".to_string(),
+                })
+            })
+            .collect();
+        let returns = trimmed.split_once("->")
+            .map(|(_, r)| format!("{} value", r.trim_end_matches('{').trim()));
+
+        functions.push((line_index, FunctionDoc {
+            brief: format!("{} function", name),
+            name,
+            description: String::new(),
+            params,
+            returns,
+            notes: vec![],
+            examples: vec![],
+        }));
+    }
+    functions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ai_doc_and_insert() {
+        let code = "#include <stdint.h>\n\nint adc_read(uint8_t channel) {\n    return 0;\n}\n";
+        let (index, extracted) = locate_functions(code).remove(0);
+        let response = r#"{"brief": "Read one ADC channel", "params": [{"name": "channel", "direction": "in", "description": "ADC channel"}], "returns": "Raw 12-bit sample", "notes": ["Blocks until conversion completes"]}"#;
+
+        let doc = parse_ai_doc(response, &extracted).unwrap();
+        assert_eq!(doc.params[0].param_type, "uint8_t");
+
+        let lines: Vec<&str> = code.lines().collect();
+        let annotated = insert_comments(&lines, vec![(index, render_comment(&doc, Language::C, ""))]);
+        assert!(annotated.contains(" * @param channel [in] ADC channel"));
+        assert!(annotated.find("@note").unwrap() < annotated.find("int adc_read").unwrap());
+    }
+
+    #[test]
+    fn test_context_window_is_centered() {
+        let code: Vec<String> = (0..40).map(|i| format!("line {}", i)).collect();
+        let lines: Vec<&str> = code.iter().map(String::as_str).collect();
+
+        let context = context_window(&lines, 20);
+        assert!(context.starts_with("line 10\n"));
+        assert!(context.ends_with("\nline 30"));
+
+        // Clamped at both ends of the file
+        assert!(context_window(&lines, 2).starts_with("line 0\n"));
+        assert!(context_window(&lines, 38).ends_with("\nline 39"));
+    }
+}
@@ -4,6 +4,10 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+pub mod ai_annotate;
+pub mod register_map;
+pub mod requirements;
+
 /// Function documentation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FunctionDoc {
@@ -107,10 +111,15 @@ pub fn generate_function_doc(doc: &FunctionDoc) -> String {
 
 /// Extract function info from C code
 pub fn extract_functions(code: &str) -> Vec<FunctionDoc> {
+    locate_functions(code).into_iter().map(|(_, doc)| doc).collect()
+}
+
+/// Extract function info from C code along with the definition line index
+pub fn locate_functions(code: &str) -> Vec<(usize, FunctionDoc)> {
     let mut functions = Vec::new();
     
     // Simple regex-like parsing for function declarations
-    for line in code.lines() {
+    for (line_index, line) in code.lines().enumerate() {
         let trimmed = line.trim();
         
         // Look for function definitions
@@ -153,7 +162,7 @@ pub fn extract_functions(code: &str) -> Vec<FunctionDoc> {
                         }
                     }
                     
-                    functions.push(FunctionDoc {
+                    functions.push((line_index, FunctionDoc {
                         name: func_name.to_string(),
                         brief: format!("{} function", func_name),
                         description: String::new(),
@@ -165,7 +174,7 @@ pub fn extract_functions(code: &str) -> Vec<FunctionDoc> {
                         },
                         notes: vec![],
                         examples: vec![],
-                    });
+                    }));
                 }
             }
         }
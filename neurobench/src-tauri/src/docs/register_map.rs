@@ -0,0 +1,330 @@
+// Register Map Documentation
+// Markdown/HTML register reference generated from the register database
+
+use crate::drivers::mcu::McuFamily;
+use crate::registers::{self, Peripheral, Register, RegisterField};
+
+/// Generate a Markdown register map for a peripheral
+///
+/// Registers are listed by address with anchor links from the summary table
+/// to each register section. Unused bit ranges are shown as reserved.
+pub fn generate_register_docs(peripheral: &str, mcu: McuFamily) -> String {
+    let Some(periph) = find_peripheral(peripheral) else {
+        let available: Vec<String> = registers::get_peripherals().into_iter().map(|p| p.name).collect();
+        return format!(
+            "# {} Register Map\n\nNo register definitions found for `{}`.\n\nAvailable peripherals: {}\n",
+            peripheral, peripheral, available.join(", ")
+        );
+    };
+
+    let mut registers = periph.registers.clone();
+    registers.sort_by_key(|r| r.address);
+
+    let mut md = String::new();
+    md.push_str(&format!("# {} Register Map\n\n", periph.name));
+    md.push_str(&format!("{}\n\n", periph.description));
+    md.push_str(&format!("- **MCU:** {}\n", mcu.display_name()));
+    md.push_str(&format!("- **Base address:** `0x{:08X}`\n\n", periph.base_address));
+    if mcu != McuFamily::STM32F4 {
+        md.push_str(&format!(
+            "> Register definitions follow the STM32F4 layout. Check addresses against the {} reference manual.\n\n",
+            mcu.display_name()
+        ));
+    }
+
+    md.push_str("## Summary\n\n");
+    md.push_str("| Register | Offset | Address | Reset | Description |\n");
+    md.push_str("|----------|--------|---------|-------|-------------|\n");
+    for reg in &registers {
+        md.push_str(&format!(
+            "| [{}](#{}) | `0x{:02X}` | `0x{:08X}` | `0x{:08X}` | {} |\n",
+            reg.name,
+            slugify(&reg.name),
+            reg.address - periph.base_address,
+            reg.address,
+            reg.reset_value,
+            reg.description
+        ));
+    }
+    md.push('\n');
+
+    for reg in &registers {
+        md.push_str(&register_section(&periph, reg));
+    }
+
+    md
+}
+
+fn find_peripheral(name: &str) -> Option<Peripheral> {
+    let upper = name.trim().to_uppercase();
+    if let Some(port) = upper.strip_prefix("GPIO").and_then(|p| p.chars().next()) {
+        return Some(registers::get_gpio_registers(port));
+    }
+    registers::get_peripherals().into_iter().find(|p| p.name == upper)
+}
+
+fn register_section(periph: &Peripheral, reg: &Register) -> String {
+    let mut md = String::new();
+    md.push_str(&format!("## {}\n\n", reg.name));
+    md.push_str(&format!("{}\n\n", reg.description));
+    md.push_str(&format!(
+        "- **Address:** `0x{:08X}` ({} + `0x{:02X}`)\n",
+        reg.address,
+        periph.name,
+        reg.address - periph.base_address
+    ));
+    md.push_str(&format!("- **Size:** {} bits\n", reg.size));
+    md.push_str(&format!("- **Reset value:** `0x{:08X}`\n\n", reg.reset_value));
+
+    md.push_str("| Bits | Field | Access | Reset | Description |\n");
+    md.push_str("|------|-------|--------|-------|-------------|\n");
+    for row in field_rows(reg) {
+        match row {
+            FieldRow::Field(field) => md.push_str(&format!(
+                "| {} | {} | {} | {} | {} |\n",
+                bit_range(field.bit_offset as u32, field.bit_width as u32),
+                field.name,
+                access_notation(&field.access),
+                field_reset(reg.reset_value, field),
+                field_description(field)
+            )),
+            FieldRow::Reserved { offset, width } => md.push_str(&format!(
+                "| {} | - | Res | - | Reserved, keep at reset value |\n",
+                bit_range(offset, width)
+            )),
+        }
+    }
+    md.push_str("\n[Back to summary](#summary)\n\n");
+    md
+}
+
+enum FieldRow<'a> {
+    Field(&'a RegisterField),
+    Reserved { offset: u32, width: u32 },
+}
+
+/// Fields from the most significant bit down, with gaps as reserved ranges
+fn field_rows(reg: &Register) -> Vec<FieldRow<'_>> {
+    let mut fields: Vec<&RegisterField> = reg.fields.iter().collect();
+    fields.sort_by_key(|f| std::cmp::Reverse(f.bit_offset));
+
+    let mut rows = Vec::new();
+    let mut next_free = reg.size as u32;
+    for field in fields {
+        let top = field.bit_offset as u32 + field.bit_width as u32;
+        if top < next_free {
+            rows.push(FieldRow::Reserved { offset: top, width: next_free - top });
+        }
+        rows.push(FieldRow::Field(field));
+        next_free = field.bit_offset as u32;
+    }
+    if next_free > 0 {
+        rows.push(FieldRow::Reserved { offset: 0, width: next_free });
+    }
+    rows
+}
+
+fn bit_range(offset: u32, width: u32) -> String {
+    if width <= 1 {
+        offset.to_string()
+    } else {
+        format!("{}:{}", offset + width - 1, offset)
+    }
+}
+
+fn access_notation(access: &str) -> &'static str {
+    match access.to_lowercase().as_str() {
+        "r" => "R",
+        "w" => "W",
+        "rw" => "R/W",
+        "rc_w1" => "RC/W1",
+        _ => "Res",
+    }
+}
+
+fn field_reset(reset_value: u32, field: &RegisterField) -> String {
+    match field.mask() {
+        Some(mask) => format!("{}", (reset_value & mask) >> field.bit_offset),
+        None => "-".to_string(),
+    }
+}
+
+fn field_description(field: &RegisterField) -> String {
+    if field.values.is_empty() {
+        return field.description.clone();
+    }
+    let values: Vec<String> = field.values.iter()
+        .map(|v| format!("`{}`: {}", v.value, v.name))
+        .collect();
+    format!("{} ({})", field.description, values.join(", "))
+}
+
+/// Anchor id for a heading, matching GitHub-style slugs
+fn slugify(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            'a'..='z' | '0'..='9' | '-' | '_' => Some(c),
+            ' ' => Some('-'),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Convert the Markdown subset used by the generated docs to HTML
+///
+/// Supports headings (with slug ids), tables, bullet lists, blockquotes,
+/// paragraphs and inline code, bold and links.
+pub fn markdown_to_html(markdown: &str) -> String {
+    let mut html = String::new();
+    let mut lines = markdown.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        if trimmed.starts_with('#') {
+            let level = trimmed.chars().take_while(|&c| c == '#').count().min(6);
+            let text = trimmed[level..].trim();
+            html.push_str(&format!("<h{l} id=\"{}\">{}</h{l}>\n", slugify(text), inline(text), l = level));
+        } else if trimmed.starts_with('|') {
+            let mut rows = vec![trimmed];
+            while let Some(next) = lines.next_if(|l| l.trim().starts_with('|')) {
+                rows.push(next.trim());
+            }
+            html.push_str(&table(&rows));
+        } else if let Some(item) = trimmed.strip_prefix("- ") {
+            html.push_str("<ul>\n");
+            html.push_str(&format!("<li>{}</li>\n", inline(item)));
+            while let Some(next) = lines.next_if(|l| l.trim().starts_with("- ")) {
+                html.push_str(&format!("<li>{}</li>\n", inline(&next.trim()[2..])));
+            }
+            html.push_str("</ul>\n");
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            html.push_str(&format!("<blockquote><p>{}</p></blockquote>\n", inline(quote.trim())));
+        } else {
+            let mut text = trimmed.to_string();
+            while let Some(next) = lines.next_if(|l| is_paragraph_line(l)) {
+                text.push(' ');
+                text.push_str(next.trim());
+            }
+            html.push_str(&format!("<p>{}</p>\n", inline(&text)));
+        }
+    }
+
+    html
+}
+
+fn is_paragraph_line(line: &str) -> bool {
+    let t = line.trim();
+    !t.is_empty() && !t.starts_with('#') && !t.starts_with('|') && !t.starts_with("- ") && !t.starts_with('>')
+}
+
+fn table(rows: &[&str]) -> String {
+    let cells = |row: &str| -> Vec<String> {
+        row.trim_matches('|').split('|').map(|c| c.trim().to_string()).collect()
+    };
+    let is_separator = |row: &str| row.chars().all(|c| matches!(c, '|' | '-' | ':' | ' '));
+
+    let mut html = String::from("<table>\n");
+    let mut body = rows;
+    if rows.len() >= 2 && is_separator(rows[1]) {
+        html.push_str("<thead><tr>");
+        for cell in cells(rows[0]) {
+            html.push_str(&format!("<th>{}</th>", inline(&cell)));
+        }
+        html.push_str("</tr></thead>\n");
+        body = &rows[2..];
+    }
+    html.push_str("<tbody>\n");
+    for row in body {
+        html.push_str("<tr>");
+        for cell in cells(row) {
+            html.push_str(&format!("<td>{}</td>", inline(&cell)));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</tbody>\n</table>\n");
+    html
+}
+
+/// Inline formatting: `code`, **bold** and [text](href)
+fn inline(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('`') {
+            if let Some(end) = after.find('`') {
+                out.push_str(&format!("<code>{}</code>", escape(&after[..end])));
+                rest = &after[end + 1..];
+                continue;
+            }
+        } else if let Some(after) = rest.strip_prefix("**") {
+            if let Some(end) = after.find("**") {
+                out.push_str(&format!("<strong>{}</strong>", inline(&after[..end])));
+                rest = &after[end + 2..];
+                continue;
+            }
+        } else if let Some(after) = rest.strip_prefix('[') {
+            if let Some((label, href, consumed)) = parse_link(after) {
+                out.push_str(&format!("<a href=\"{}\">{}</a>", escape(href), inline(label)));
+                rest = &after[consumed..];
+                continue;
+            }
+        }
+
+        let c = rest.chars().next().unwrap();
+        out.push_str(&escape(&c.to_string()));
+        rest = &rest[c.len_utf8()..];
+    }
+
+    out
+}
+
+/// Parse `label](href)` and return the byte count consumed
+fn parse_link(text: &str) -> Option<(&str, &str, usize)> {
+    let close = text.find("](")?;
+    let end = text[close + 2..].find(')')? + close + 2;
+    Some((&text[..close], &text[close + 2..end], end + 1))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gpio_register_docs() {
+        let md = generate_register_docs("gpioa", McuFamily::STM32F4);
+        assert!(md.starts_with("# GPIOA Register Map"));
+        assert!(md.contains("| [IDR](#idr) | `0x10` | `0x40020010` |"));
+        assert!(md.contains("| 31:30 | MODER15 | R/W | 0 |"));
+        assert!(md.contains("| 31:16 | - | Res |"));
+        assert!(md.contains("| 0 | IDR0 | R | 0 |"));
+        assert!(!md.contains("STM32F4 layout"));
+
+        // Summary rows follow register addresses
+        assert!(md.find("[PUPDR]").unwrap() < md.find("[IDR]").unwrap());
+    }
+
+    #[test]
+    fn test_markdown_to_html() {
+        let md = generate_register_docs("RCC", McuFamily::STM32H7);
+        let html = markdown_to_html(&md);
+        assert!(html.contains("<h2 id=\"cr\">CR</h2>"));
+        assert!(html.contains("<a href=\"#cr\">CR</a>"));
+        assert!(html.contains("<th>Bits</th>"));
+        assert!(html.contains("<blockquote>"));
+        assert!(html.contains("<td>R/W</td>"));
+    }
+}
@@ -0,0 +1,319 @@
+// Requirements Traceability
+// IEEE 830-style traceability matrix from `/* REQ-xxx */` tags in source code
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// Requirement priority
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReqPriority {
+    Critical,
+    High,
+    Medium,
+    Low,
+}
+
+impl ReqPriority {
+    fn label(&self) -> &'static str {
+        match self {
+            ReqPriority::Critical => "Critical",
+            ReqPriority::High => "High",
+            ReqPriority::Medium => "Medium",
+            ReqPriority::Low => "Low",
+        }
+    }
+}
+
+/// A single software requirement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Requirement {
+    pub id: String,
+    pub description: String,
+    pub priority: ReqPriority,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Requirements and the source files to trace them through
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequirementsConfig {
+    pub requirements: Vec<Requirement>,
+    pub code_files: Vec<String>,
+}
+
+/// Place in the code tagged with a requirement id
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceLink {
+    pub requirement: String,
+    pub file: String,
+    pub line: usize,
+}
+
+/// Function with no requirement tag in its body or leading comment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UntaggedFunction {
+    pub name: String,
+    pub file: String,
+    pub line: usize,
+}
+
+/// Result of tracing requirements through the code
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TraceabilityReport {
+    pub links: Vec<TraceLink>,
+    /// Requirement ids with no tag anywhere in the code
+    pub unimplemented: Vec<String>,
+    pub untagged_functions: Vec<UntaggedFunction>,
+    /// Tags that don't name a known requirement
+    pub unknown_tags: Vec<TraceLink>,
+    pub unreadable_files: Vec<String>,
+}
+
+impl TraceabilityReport {
+    /// Share of requirements with at least one link, in percent
+    pub fn coverage_percent(&self, total: usize) -> f32 {
+        if total == 0 {
+            return 100.0;
+        }
+        (total - self.unimplemented.len()) as f32 * 100.0 / total as f32
+    }
+}
+
+/// Requirement tags in comments, as (1-based line, id)
+fn find_tags(code: &str) -> Vec<(usize, String)> {
+    let comment = Regex::new(r"(?s)/\*.*?\*/|//[^\n]*").unwrap();
+    let id = Regex::new(r"\bREQ-[A-Za-z0-9_]+(?:[.-][A-Za-z0-9_]+)*").unwrap();
+
+    let mut tags = Vec::new();
+    for c in comment.find_iter(code) {
+        for m in id.find_iter(c.as_str()) {
+            let line = code[..c.start() + m.start()].matches('\n').count() + 1;
+            tags.push((line, m.as_str().to_string()));
+        }
+    }
+    tags
+}
+
+/// Line range (1-based, inclusive) a function's tags may appear in:
+/// its leading comment block through its closing brace
+fn function_span(lines: &[&str], def_index: usize) -> (usize, usize) {
+    let mut start = def_index;
+    while start > 0 {
+        let prev = lines[start - 1].trim();
+        let is_comment = prev.starts_with("//") || prev.starts_with("/*") || prev.starts_with('*') || prev.ends_with("*/");
+        if !is_comment {
+            break;
+        }
+        start -= 1;
+    }
+
+    let mut depth = 0i32;
+    let mut opened = false;
+    for (i, line) in lines.iter().enumerate().skip(def_index) {
+        for c in line.chars() {
+            match c {
+                '{' => {
+                    depth += 1;
+                    opened = true;
+                }
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        if opened && depth <= 0 {
+            return (start + 1, i + 1);
+        }
+    }
+    (start + 1, lines.len())
+}
+
+/// Trace requirements through already-loaded sources, given as (path, contents)
+pub fn trace_requirements(requirements: &[Requirement], sources: &[(String, String)]) -> TraceabilityReport {
+    let known: HashSet<&str> = requirements.iter().map(|r| r.id.as_str()).collect();
+    let mut report = TraceabilityReport::default();
+
+    for (file, code) in sources {
+        let tags = find_tags(code);
+        for (line, id) in &tags {
+            let link = TraceLink { requirement: id.clone(), file: file.clone(), line: *line };
+            if known.contains(id.as_str()) {
+                report.links.push(link);
+            } else {
+                report.unknown_tags.push(link);
+            }
+        }
+
+        let lines: Vec<&str> = code.lines().collect();
+        for (def_index, func) in super::locate_functions(code) {
+            let (start, end) = function_span(&lines, def_index);
+            if !tags.iter().any(|(line, _)| (start..=end).contains(line)) {
+                report.untagged_functions.push(UntaggedFunction {
+                    name: func.name,
+                    file: file.clone(),
+                    line: def_index + 1,
+                });
+            }
+        }
+    }
+
+    let traced: HashSet<&str> = report.links.iter().map(|l| l.requirement.as_str()).collect();
+    report.unimplemented = requirements.iter()
+        .filter(|r| !traced.contains(r.id.as_str()))
+        .map(|r| r.id.clone())
+        .collect();
+    report
+}
+
+/// Read the configured code files and trace the requirements through them
+pub fn build_traceability(config: &RequirementsConfig) -> TraceabilityReport {
+    let mut sources = Vec::new();
+    let mut unreadable = Vec::new();
+    for path in &config.code_files {
+        match std::fs::read_to_string(path) {
+            Ok(code) => sources.push((path.clone(), code)),
+            Err(_) => unreadable.push(path.clone()),
+        }
+    }
+    let mut report = trace_requirements(&config.requirements, &sources);
+    report.unreadable_files = unreadable;
+    report
+}
+
+/// Render a traceability report as a Markdown matrix
+pub fn render_traceability_markdown(requirements: &[Requirement], report: &TraceabilityReport) -> String {
+    let mut by_req: BTreeMap<&str, Vec<&TraceLink>> = BTreeMap::new();
+    for link in &report.links {
+        by_req.entry(link.requirement.as_str()).or_default().push(link);
+    }
+
+    let mut md = String::from("# Requirements Traceability Matrix\n\n");
+    md.push_str(&format!(
+        "- **Requirements:** {}\n- **Traced:** {}\n- **Coverage:** {:.1}%\n\n",
+        requirements.len(),
+        requirements.len() - report.unimplemented.len(),
+        report.coverage_percent(requirements.len())
+    ));
+
+    md.push_str("## Traceability\n\n");
+    md.push_str("| Requirement | Priority | Description | Implemented In | Status |\n");
+    md.push_str("|-------------|----------|-------------|----------------|--------|\n");
+    for req in requirements {
+        let (locations, status) = match by_req.get(req.id.as_str()) {
+            Some(links) => (
+                links.iter().map(|l| format!("`{}:{}`", l.file, l.line)).collect::<Vec<_>>().join("<br>"),
+                "Traced",
+            ),
+            None => ("-".to_string(), "**Not implemented**"),
+        };
+        md.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            req.id,
+            req.priority.label(),
+            req.description.replace('|', "\\|"),
+            locations,
+            status
+        ));
+    }
+
+    if !report.unimplemented.is_empty() {
+        md.push_str("\n## Unimplemented Requirements\n\n");
+        for req in requirements.iter().filter(|r| report.unimplemented.contains(&r.id)) {
+            md.push_str(&format!("- **{}** ({}): {}\n", req.id, req.priority.label(), req.description));
+        }
+    }
+
+    if !report.untagged_functions.is_empty() {
+        md.push_str("\n## Code Without Requirement Tags\n\n");
+        md.push_str("| Function | Location |\n|----------|----------|\n");
+        for func in &report.untagged_functions {
+            md.push_str(&format!("| `{}` | `{}:{}` |\n", func.name, func.file, func.line));
+        }
+    }
+
+    if !report.unknown_tags.is_empty() {
+        md.push_str("\n## Unknown Requirement Tags\n\n");
+        for link in &report.unknown_tags {
+            md.push_str(&format!("- `{}` at `{}:{}`\n", link.requirement, link.file, link.line));
+        }
+    }
+
+    if !report.unreadable_files.is_empty() {
+        md.push_str("\n## Unreadable Files\n\n");
+        for file in &report.unreadable_files {
+            md.push_str(&format!("- `{}`\n", file));
+        }
+    }
+    md
+}
+
+/// Generate a Markdown traceability matrix for the configured requirements and code
+pub fn generate_traceability_matrix(config: &RequirementsConfig) -> String {
+    render_traceability_markdown(&config.requirements, &build_traceability(config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn req(id: &str, priority: ReqPriority) -> Requirement {
+        Requirement { id: id.to_string(), description: format!("{} description", id), priority, tags: vec![] }
+    }
+
+    const MOTOR_C: &str = "\
+#include \"motor.h\"
+
+/* REQ-001: stop the motor on overcurrent */
+void motor_stop(void) {
+    pwm_set(0);
+}
+
+void motor_start(uint8_t duty) {
+    // REQ-002, REQ-009
+    pwm_set(duty);
+}
+
+int motor_debug(void) {
+    return 0;
+}
+";
+
+    #[test]
+    fn test_trace_requirements() {
+        let requirements = vec![req("REQ-001", ReqPriority::Critical), req("REQ-002", ReqPriority::High), req("REQ-003", ReqPriority::Low)];
+        let report = trace_requirements(&requirements, &[("src/motor.c".to_string(), MOTOR_C.to_string())]);
+
+        assert_eq!(report.links, vec![
+            TraceLink { requirement: "REQ-001".to_string(), file: "src/motor.c".to_string(), line: 3 },
+            TraceLink { requirement: "REQ-002".to_string(), file: "src/motor.c".to_string(), line: 9 },
+        ]);
+        assert_eq!(report.unimplemented, vec!["REQ-003".to_string()]);
+        assert_eq!(report.unknown_tags[0].requirement, "REQ-009");
+        assert_eq!(report.untagged_functions, vec![UntaggedFunction {
+            name: "motor_debug".to_string(),
+            file: "src/motor.c".to_string(),
+            line: 13,
+        }]);
+    }
+
+    #[test]
+    fn test_markdown_matrix() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("motor.c");
+        std::fs::write(&path, MOTOR_C).unwrap();
+        let file = path.to_string_lossy().to_string();
+
+        let config = RequirementsConfig {
+            requirements: vec![req("REQ-001", ReqPriority::Critical), req("REQ-003", ReqPriority::Low)],
+            code_files: vec![file.clone(), "missing.c".to_string()],
+        };
+        let md = generate_traceability_matrix(&config);
+        assert!(md.contains("- **Coverage:** 50.0%"));
+        assert!(md.contains(&format!("| REQ-001 | Critical | REQ-001 description | `{}:3` | Traced |", file)));
+        assert!(md.contains("| REQ-003 | Low | REQ-003 description | - | **Not implemented** |"));
+        assert!(md.contains("- **REQ-003** (Low): REQ-003 description"));
+        assert!(md.contains(&format!("| `motor_debug` | `{}:13` |", file)));
+        assert!(md.contains("- `missing.c`"));
+    }
+}
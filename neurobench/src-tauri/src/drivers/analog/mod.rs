@@ -3,6 +3,8 @@
 
 use serde::{Deserialize, Serialize};
 
+pub mod temp_calibration;
+
 /// ADC Resolution options
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum AdcResolution {
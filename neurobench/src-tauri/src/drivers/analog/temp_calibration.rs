@@ -0,0 +1,220 @@
+// Internal Temperature Sensor Calibration
+// Converts STM32 internal sensor readings with the factory TS_CAL values
+
+use crate::drivers::McuFamily;
+
+/// Factory calibration of one family's internal temperature sensor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TempSensorCalibration {
+    /// Reading at `cal1_temp_c`
+    pub cal1_addr: u32,
+    /// Reading at `cal2_temp_c`
+    pub cal2_addr: u32,
+    pub cal1_temp_c: i32,
+    pub cal2_temp_c: i32,
+    /// VDDA the calibration was taken at
+    pub cal_vdda_mv: u32,
+    /// ADC resolution the calibration was taken at
+    pub cal_bits: u8,
+    pub adc_instance: &'static str,
+    /// ADC input the sensor is wired to
+    pub adc_input: &'static str,
+    /// Sample time long enough for the sensor's ~10 us minimum
+    pub sample_time: &'static str,
+}
+
+/// Calibration data for `mcu`, from the datasheet's "Temperature sensor calibration values"
+///
+/// STM32F1 has no factory calibration and non-STM32 parts have no TS_CAL values.
+pub fn temp_sensor_calibration(mcu: McuFamily) -> Option<TempSensorCalibration> {
+    match mcu {
+        McuFamily::STM32F4 => Some(TempSensorCalibration {
+            cal1_addr: 0x1FFF_7A2C,
+            cal2_addr: 0x1FFF_7A2E,
+            cal1_temp_c: 30,
+            cal2_temp_c: 110,
+            cal_vdda_mv: 3300,
+            cal_bits: 12,
+            adc_instance: "ADC1",
+            adc_input: "ADC1_IN16 (IN18 on F42x/F43x)",
+            sample_time: "ADC_SAMPLETIME_480CYCLES",
+        }),
+        McuFamily::STM32H7 => Some(TempSensorCalibration {
+            cal1_addr: 0x1FF1_E820,
+            cal2_addr: 0x1FF1_E840,
+            cal1_temp_c: 30,
+            cal2_temp_c: 110,
+            cal_vdda_mv: 3300,
+            cal_bits: 16,
+            adc_instance: "ADC3",
+            adc_input: "ADC3_INP18",
+            sample_time: "ADC_SAMPLETIME_810CYCLES_5",
+        }),
+        // TS_CAL2 is taken at 110 C on STM32L47x/L48x, the parts NeuroBench targets
+        McuFamily::STM32L4 => Some(TempSensorCalibration {
+            cal1_addr: 0x1FFF_75A8,
+            cal2_addr: 0x1FFF_75CA,
+            cal1_temp_c: 30,
+            cal2_temp_c: 110,
+            cal_vdda_mv: 3000,
+            cal_bits: 12,
+            adc_instance: "ADC1",
+            adc_input: "ADC1_IN17",
+            sample_time: "ADC_SAMPLETIME_640CYCLES_5",
+        }),
+        McuFamily::STM32G4 => Some(TempSensorCalibration {
+            cal1_addr: 0x1FFF_75A8,
+            cal2_addr: 0x1FFF_75CA,
+            cal1_temp_c: 30,
+            cal2_temp_c: 130,
+            cal_vdda_mv: 3000,
+            cal_bits: 12,
+            adc_instance: "ADC1",
+            adc_input: "ADC1_IN16",
+            sample_time: "ADC_SAMPLETIME_640CYCLES_5",
+        }),
+        _ => None,
+    }
+}
+
+/// Generate the calibrated temperature sensor driver for `mcu`
+pub fn generate_temp_calibration(mcu: McuFamily) -> String {
+    let Some(cal) = temp_sensor_calibration(mcu) else {
+        return format!("/* {} has no factory temperature sensor calibration */\n", mcu.display_name());
+    };
+
+    // F4's ADC numbers ranks directly and has no single-ended/differential inputs
+    let (rank, channel_extra) = if mcu == McuFamily::STM32F4 {
+        ("1", String::new())
+    } else {
+        (
+            "ADC_REGULAR_RANK_1",
+            "\n    sConfig.SingleDiff = ADC_SINGLE_ENDED;\n    sConfig.OffsetNumber = ADC_OFFSET_NONE;".to_string(),
+        )
+    };
+
+    format!(r#"/**
+ * Internal Temperature Sensor - {mcu}
+ * Auto-generated by NeuroBench
+ *
+ * Two-point calibration with the factory TS_CAL values:
+ *   T = (TSVCAL2_TEMP_C - TSVCAL1_TEMP_C) / (TSVCAL2 - TSVCAL1) * (raw - TSVCAL1) + TSVCAL1_TEMP_C
+ * Configure {instance} for {bits}-bit resolution so readings match the calibration.
+ */
+
+#include <stdint.h>
+#include <stdbool.h>
+
+// Factory calibration ({cal_vdda} mV VDDA, {bits}-bit)
+#define TSVCAL1_ADDR            0x{cal1_addr:08X}UL
+#define TSVCAL2_ADDR            0x{cal2_addr:08X}UL
+#define TSVCAL1                 (*(const volatile uint16_t *)TSVCAL1_ADDR)
+#define TSVCAL2                 (*(const volatile uint16_t *)TSVCAL2_ADDR)
+#define TSVCAL1_TEMP_C          {cal1_temp}
+#define TSVCAL2_TEMP_C          {cal2_temp}
+#define TSVCAL_VDDA_MV          {cal_vdda}
+
+// {input}
+#define ADC_TEMP_SENSOR_CHANNEL ADC_CHANNEL_TEMPSENSOR
+
+extern ADC_HandleTypeDef h{instance_lower};
+
+/**
+ * Read the sensor once (blocking)
+ */
+uint16_t temp_sensor_read_raw(void) {{
+    ADC_ChannelConfTypeDef sConfig = {{0}};
+    sConfig.Channel = ADC_TEMP_SENSOR_CHANNEL;
+    sConfig.Rank = {rank};
+    sConfig.SamplingTime = {sample_time};{channel_extra}
+    HAL_ADC_ConfigChannel(&h{instance_lower}, &sConfig);
+
+    HAL_ADC_Start(&h{instance_lower});
+    HAL_ADC_PollForConversion(&h{instance_lower}, HAL_MAX_DELAY);
+    uint16_t raw = (uint16_t)HAL_ADC_GetValue(&h{instance_lower});
+    HAL_ADC_Stop(&h{instance_lower});
+    return raw;
+}}
+
+/**
+ * Convert a reading to degrees Celsius
+ * vdda_mv: actual VDDA, the reading is rescaled to the calibration VDDA
+ */
+float temp_sensor_to_celsius(uint16_t raw, uint32_t vdda_mv) {{
+    int32_t ts_data = (int32_t)((uint32_t)raw * vdda_mv / TSVCAL_VDDA_MV);
+    int32_t cal1 = (int32_t)TSVCAL1;
+    int32_t cal2 = (int32_t)TSVCAL2;
+
+    return (float)(TSVCAL2_TEMP_C - TSVCAL1_TEMP_C) * (float)(ts_data - cal1) / (float)(cal2 - cal1)
+        + (float)TSVCAL1_TEMP_C;
+}}
+
+/* ==================== Example: filtered reading ====================
+ * Moving average over the circular buffer driver, generated with
+ * name "temp_buffer" and element type uint16_t. Call temp_buffer_init() first.
+ */
+
+bool temp_buffer_push(uint16_t value);
+bool temp_buffer_pop(uint16_t *value);
+bool temp_buffer_is_full(void);
+uint32_t temp_buffer_available(void);
+
+static uint32_t temp_sum;
+
+float temp_sensor_read_filtered(uint32_t vdda_mv) {{
+    uint16_t oldest;
+    if (temp_buffer_is_full() && temp_buffer_pop(&oldest)) {{
+        temp_sum -= oldest;
+    }}
+
+    uint16_t raw = temp_sensor_read_raw();
+    temp_buffer_push(raw);
+    temp_sum += raw;
+
+    return temp_sensor_to_celsius((uint16_t)(temp_sum / temp_buffer_available()), vdda_mv);
+}}
+"#,
+        mcu = mcu.display_name(),
+        instance = cal.adc_instance,
+        instance_lower = cal.adc_instance.to_lowercase(),
+        bits = cal.cal_bits,
+        cal_vdda = cal.cal_vdda_mv,
+        cal1_addr = cal.cal1_addr,
+        cal2_addr = cal.cal2_addr,
+        cal1_temp = cal.cal1_temp_c,
+        cal2_temp = cal.cal2_temp_c,
+        input = cal.adc_input,
+        rank = rank,
+        sample_time = cal.sample_time,
+        channel_extra = channel_extra,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_f4_calibration_addresses() {
+        let code = generate_temp_calibration(McuFamily::STM32F4);
+        assert!(code.contains("#define TSVCAL1_ADDR            0x1FFF7A2CUL"));
+        assert!(code.contains("#define TSVCAL2_ADDR            0x1FFF7A2EUL"));
+        assert!(code.contains("#define ADC_TEMP_SENSOR_CHANNEL"));
+        assert!(code.contains("sConfig.Rank = 1;"));
+        assert!(code.contains("temp_buffer_push(raw)"));
+    }
+
+    #[test]
+    fn test_family_differences() {
+        let h7 = generate_temp_calibration(McuFamily::STM32H7);
+        assert!(h7.contains("0x1FF1E820UL"));
+        assert!(h7.contains("HAL_ADC_Start(&hadc3)"));
+        assert!(h7.contains("sConfig.SingleDiff = ADC_SINGLE_ENDED;"));
+
+        let l4 = temp_sensor_calibration(McuFamily::STM32L4).unwrap();
+        assert_eq!((l4.cal1_addr, l4.cal_vdda_mv), (0x1FFF_75A8, 3000));
+
+        assert!(temp_sensor_calibration(McuFamily::STM32F1).is_none());
+        assert!(generate_temp_calibration(McuFamily::ESP32).contains("no factory temperature sensor calibration"));
+    }
+}
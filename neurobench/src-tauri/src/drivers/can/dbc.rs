@@ -0,0 +1,731 @@
+// CAN Database (DBC) Parser
+// Reads BO_/SG_ definitions and generates signal pack/unpack code
+
+use crate::drivers::mcu::McuFamily;
+use crate::drivers::templates::{DriverOutput, PeripheralType};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use thiserror::Error;
+
+/// Bit 31 of a BO_ identifier marks a 29-bit extended frame
+const DBC_EXTENDED_FLAG: u32 = 0x8000_0000;
+
+/// Pseudo-message that holds signals not assigned to any frame
+const INDEPENDENT_SIGNALS: &str = "VECTOR__INDEPENDENT_SIG_MSG";
+
+#[derive(Debug, Error, PartialEq)]
+pub enum DbcParseError {
+    #[error("Line {line}: {message}")]
+    Syntax { line: usize, message: String },
+
+    #[error("Line {line}: signal {signal} is defined outside a BO_ message")]
+    OrphanSignal { line: usize, signal: String },
+
+    #[error("Line {line}: signal {signal} does not fit in the {dlc}-byte frame of {message}")]
+    SignalOutOfRange { line: usize, signal: String, message: String, dlc: u8 },
+
+    #[error("No BO_ messages found")]
+    NoMessages,
+}
+
+/// Signal byte order (`@1` Intel, `@0` Motorola)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ByteOrder {
+    LittleEndian,
+    BigEndian,
+}
+
+/// Multiplexing role of a signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Multiplex {
+    None,
+    /// `M`: selects which multiplexed signals are present
+    Multiplexor,
+    /// `m<n>`: present only when the multiplexor equals n
+    Multiplexed(u32),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanSignal {
+    pub name: String,
+    /// DBC start bit: LSB for little endian, MSB for big endian
+    pub start_bit: u16,
+    pub length: u16,
+    pub byte_order: ByteOrder,
+    pub is_signed: bool,
+    pub factor: f64,
+    pub offset: f64,
+    pub min: f64,
+    pub max: f64,
+    pub unit: String,
+    pub receivers: Vec<String>,
+    pub multiplex: Multiplex,
+}
+
+/// Part of a signal that lives in one payload byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitSegment {
+    pub byte: usize,
+    /// Position of the segment's lowest bit within the byte
+    pub byte_shift: u16,
+    /// Position of the segment's lowest bit within the raw value
+    pub value_shift: u16,
+    pub bits: u16,
+}
+
+impl CanSignal {
+    /// Byte-wise layout of the raw value in the payload
+    pub fn segments(&self) -> Vec<BitSegment> {
+        let mut segments = Vec::new();
+        let mut byte = (self.start_bit / 8) as usize;
+        let mut bit = self.start_bit % 8;
+        let mut remaining = self.length;
+        while remaining > 0 {
+            let segment = match self.byte_order {
+                ByteOrder::LittleEndian => {
+                    let bits = remaining.min(8 - bit);
+                    BitSegment { byte, byte_shift: bit, value_shift: self.length - remaining, bits }
+                }
+                // Motorola numbering walks from the MSB down, then on to bit 7 of the next byte
+                ByteOrder::BigEndian => {
+                    let bits = remaining.min(bit + 1);
+                    BitSegment { byte, byte_shift: bit + 1 - bits, value_shift: remaining - bits, bits }
+                }
+            };
+            segments.push(segment);
+            remaining -= segment.bits;
+            byte += 1;
+            bit = match self.byte_order {
+                ByteOrder::LittleEndian => 0,
+                ByteOrder::BigEndian => 7,
+            };
+        }
+        segments
+    }
+
+    /// Last payload byte the signal touches
+    pub fn end_byte(&self) -> usize {
+        self.segments().last().map_or(0, |s| s.byte)
+    }
+
+    /// Signal is decoded to its raw integer rather than a scaled float
+    pub fn is_integer(&self) -> bool {
+        self.factor == 1.0 && self.offset == 0.0
+    }
+
+    fn c_type(&self) -> &'static str {
+        if !self.is_integer() {
+            return if self.length > 24 { "double" } else { "float" };
+        }
+        match (self.is_signed, self.length) {
+            (false, 0..=8) => "uint8_t",
+            (false, 9..=16) => "uint16_t",
+            (false, 17..=32) => "uint32_t",
+            (false, _) => "uint64_t",
+            (true, 0..=8) => "int8_t",
+            (true, 9..=16) => "int16_t",
+            (true, 17..=32) => "int32_t",
+            (true, _) => "int64_t",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanMessage {
+    /// Identifier without the DBC extended flag
+    pub id: u32,
+    pub extended: bool,
+    pub name: String,
+    pub dlc: u8,
+    pub transmitter: String,
+    pub signals: Vec<CanSignal>,
+}
+
+impl CanMessage {
+    pub fn multiplexor(&self) -> Option<&CanSignal> {
+        self.signals.iter().find(|s| s.multiplex == Multiplex::Multiplexor)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CanDatabase {
+    pub version: String,
+    pub nodes: Vec<String>,
+    pub messages: Vec<CanMessage>,
+}
+
+impl CanDatabase {
+    pub fn signal_count(&self) -> usize {
+        self.messages.iter().map(|m| m.signals.len()).sum()
+    }
+}
+
+/// Parse DBC text; attributes, comments and value tables are skipped
+pub fn parse_dbc(dbc_content: &str) -> Result<CanDatabase, DbcParseError> {
+    let mut db = CanDatabase::default();
+    let mut in_message = false;
+    let mut in_independent = false;
+
+    for (index, raw_line) in dbc_content.lines().enumerate() {
+        let line_no = index + 1;
+        let line = raw_line.trim();
+        let syntax = |message: &str| DbcParseError::Syntax { line: line_no, message: message.to_string() };
+
+        if let Some(rest) = line.strip_prefix("VERSION") {
+            db.version = rest.trim().trim_matches('"').to_string();
+        } else if let Some(rest) = line.strip_prefix("BU_:") {
+            db.nodes = rest.split_whitespace().map(str::to_string).collect();
+        } else if let Some(rest) = line.strip_prefix("BO_ ") {
+            let message = parse_message(rest).map_err(|e| syntax(&e))?;
+            in_independent = message.name == INDEPENDENT_SIGNALS;
+            in_message = !in_independent;
+            if in_message {
+                db.messages.push(message);
+            }
+        } else if let Some(rest) = line.strip_prefix("SG_ ") {
+            let signal = parse_signal(rest).map_err(|e| syntax(&e))?;
+            if in_independent {
+                continue;
+            }
+            if !in_message {
+                return Err(DbcParseError::OrphanSignal { line: line_no, signal: signal.name });
+            }
+            let message = db.messages.last_mut().expect("in_message implies a message");
+            if signal.end_byte() >= message.dlc as usize {
+                return Err(DbcParseError::SignalOutOfRange {
+                    line: line_no,
+                    signal: signal.name,
+                    message: message.name.clone(),
+                    dlc: message.dlc,
+                });
+            }
+            message.signals.push(signal);
+        } else if !line.is_empty() && !raw_line.starts_with(char::is_whitespace) {
+            // Any other top-level keyword closes the current message
+            in_message = false;
+            in_independent = false;
+        }
+    }
+
+    if db.messages.is_empty() {
+        return Err(DbcParseError::NoMessages);
+    }
+    Ok(db)
+}
+
+/// `<id> <name>: <dlc> <transmitter>`
+fn parse_message(rest: &str) -> Result<CanMessage, String> {
+    let (head, tail) = rest.split_once(':').ok_or("BO_ is missing ':'")?;
+    let mut head = head.split_whitespace();
+    let raw_id: u32 = head.next().and_then(|id| id.parse().ok()).ok_or("Invalid BO_ identifier")?;
+    let name = head.next().ok_or("BO_ is missing a message name")?.to_string();
+    let mut tail = tail.split_whitespace();
+    let dlc: u8 = tail.next().and_then(|dlc| dlc.parse().ok()).ok_or("Invalid BO_ DLC")?;
+    if dlc > 64 {
+        return Err(format!("DLC {} exceeds the 64-byte CAN FD maximum", dlc));
+    }
+    let extended = raw_id & DBC_EXTENDED_FLAG != 0;
+    Ok(CanMessage {
+        id: raw_id & !DBC_EXTENDED_FLAG,
+        extended,
+        name,
+        dlc,
+        transmitter: tail.next().unwrap_or("Vector__XXX").to_string(),
+        signals: Vec::new(),
+    })
+}
+
+/// `<name> [M|m<n>] : <start>|<length>@<order><sign> (<factor>,<offset>) [<min>|<max>] "<unit>" <receivers>`
+fn parse_signal(rest: &str) -> Result<CanSignal, String> {
+    let (head, tail) = rest.split_once(':').ok_or("SG_ is missing ':'")?;
+    let mut head = head.split_whitespace();
+    let name = head.next().ok_or("SG_ is missing a signal name")?.to_string();
+    let multiplex = match head.next() {
+        None => Multiplex::None,
+        Some("M") => Multiplex::Multiplexor,
+        Some(mux) => {
+            // Extended multiplexing (`m1M`) is treated as a plain multiplexed signal
+            let value = mux.strip_prefix('m').map(|v| v.trim_end_matches('M'));
+            Multiplex::Multiplexed(value.and_then(|v| v.parse().ok()).ok_or(format!("Invalid multiplexer '{}'", mux))?)
+        }
+    };
+
+    let tail = tail.trim();
+    let (layout, tail) = tail.split_once(char::is_whitespace).ok_or("SG_ is missing scaling")?;
+    let (position, encoding) = layout.split_once('@').ok_or("SG_ layout is missing '@'")?;
+    let (start, length) = position.split_once('|').ok_or("SG_ layout is missing '|'")?;
+    let start_bit: u16 = start.parse().map_err(|_| format!("Invalid start bit '{}'", start))?;
+    let length: u16 = length.parse().map_err(|_| format!("Invalid length '{}'", length))?;
+    if !(1..=64).contains(&length) {
+        return Err(format!("Signal length {} is outside 1..64", length));
+    }
+    let byte_order = match encoding.chars().next() {
+        Some('1') => ByteOrder::LittleEndian,
+        Some('0') => ByteOrder::BigEndian,
+        _ => return Err(format!("Invalid byte order in '{}'", layout)),
+    };
+    let is_signed = match encoding.chars().nth(1) {
+        Some('+') => false,
+        Some('-') => true,
+        _ => return Err(format!("Invalid sign in '{}'", layout)),
+    };
+
+    let (factor, offset) = parse_pair(tail, '(', ')', ',')?;
+    if factor == 0.0 {
+        return Err("Signal factor must not be zero".to_string());
+    }
+    let (min, max) = parse_pair(tail, '[', ']', '|')?;
+
+    let after_range = &tail[tail.find(']').map_or(0, |i| i + 1)..];
+    let (unit, receivers) = match after_range.find('"') {
+        Some(open) => {
+            let close = after_range[open + 1..].find('"').ok_or("Unterminated unit string")? + open + 1;
+            (after_range[open + 1..close].to_string(), &after_range[close + 1..])
+        }
+        None => (String::new(), after_range),
+    };
+
+    Ok(CanSignal {
+        name,
+        start_bit,
+        length,
+        byte_order,
+        is_signed,
+        factor,
+        offset,
+        min,
+        max,
+        unit,
+        receivers: receivers
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|r| !r.is_empty())
+            .map(str::to_string)
+            .collect(),
+        multiplex,
+    })
+}
+
+fn parse_pair(text: &str, open: char, close: char, separator: char) -> Result<(f64, f64), String> {
+    let start = text.find(open).ok_or(format!("SG_ is missing '{}'", open))?;
+    let end = text[start..].find(close).ok_or(format!("SG_ is missing '{}'", close))? + start;
+    let (a, b) = text[start + 1..end]
+        .split_once(separator)
+        .ok_or(format!("SG_ is missing '{}'", separator))?;
+    let parse = |v: &str| v.trim().parse::<f64>().map_err(|_| format!("Invalid number '{}'", v.trim()));
+    Ok((parse(a)?, parse(b)?))
+}
+
+/// C literal for a scale constant, `f`-suffixed for float signals
+fn c_number(value: f64, c_type: &str) -> String {
+    let mut text = format!("{:?}", value);
+    if !text.contains(['.', 'e']) {
+        text.push_str(".0");
+    }
+    if c_type == "float" {
+        text.push('f');
+    }
+    text
+}
+
+fn mask(bits: u16) -> String {
+    if bits >= 64 {
+        "0xFFFFFFFFFFFFFFFFULL".to_string()
+    } else {
+        format!("0x{:X}ULL", (1u64 << bits) - 1)
+    }
+}
+
+fn pack_signal(code: &mut String, signal: &CanSignal, indent: &str) {
+    let c_type = signal.c_type();
+    let field = format!("msg->{}", signal.name);
+    if signal.is_integer() {
+        let _ = writeln!(code, "{indent}raw = (uint64_t){field};");
+    } else {
+        let round = if c_type == "float" { "roundf" } else { "round" };
+        let offset = c_number(signal.offset, c_type);
+        let factor = c_number(signal.factor, c_type);
+        let value = if signal.min < signal.max {
+            let min = c_number(signal.min, c_type);
+            let max = c_number(signal.max, c_type);
+            format!("({field} < {min} ? {min} : ({field} > {max} ? {max} : {field}))")
+        } else {
+            field
+        };
+        let _ = writeln!(code, "{indent}raw = (uint64_t)(int64_t){round}(({value} - {offset}) / {factor});");
+    }
+    for segment in signal.segments() {
+        let value = if segment.value_shift == 0 { "raw".to_string() } else { format!("(raw >> {})", segment.value_shift) };
+        let _ = writeln!(
+            code,
+            "{indent}data[{}] |= (uint8_t)(({value} & 0x{:X}U) << {});",
+            segment.byte, (1u16 << segment.bits) - 1, segment.byte_shift
+        );
+    }
+}
+
+fn unpack_signal(code: &mut String, signal: &CanSignal, indent: &str) {
+    let _ = writeln!(code, "{indent}raw = 0;");
+    for segment in signal.segments() {
+        let _ = writeln!(
+            code,
+            "{indent}raw |= (uint64_t)((data[{}] >> {}) & 0x{:X}U) << {};",
+            segment.byte, segment.byte_shift, (1u16 << segment.bits) - 1, segment.value_shift
+        );
+    }
+    if signal.is_signed && signal.length < 64 {
+        let _ = writeln!(
+            code,
+            "{indent}if (raw & (1ULL << {})) raw |= ~{};  // Sign-extend",
+            signal.length - 1, mask(signal.length)
+        );
+    }
+    let c_type = signal.c_type();
+    let raw = if signal.is_signed { "(int64_t)raw" } else { "raw" };
+    if signal.is_integer() {
+        let _ = writeln!(code, "{indent}msg->{} = ({c_type}){raw};", signal.name);
+    } else {
+        let _ = writeln!(
+            code,
+            "{indent}msg->{} = ({c_type}){raw} * {} + {};",
+            signal.name, c_number(signal.factor, c_type), c_number(signal.offset, c_type)
+        );
+    }
+}
+
+fn send_function(message: &CanMessage, mcu: McuFamily) -> Option<String> {
+    let name = &message.name;
+    let upper = message.name.to_uppercase();
+    match mcu {
+        McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32L4 if message.dlc <= 8 => Some(format!(r#"
+bool can_send_{name}(const {name}_t *msg) {{
+    CAN_TxHeaderTypeDef header = {{0}};
+    uint8_t data[8];
+    uint32_t mailbox;
+
+    can_pack_{name}(msg, data);
+    header.{id_field} = CAN_{upper}_ID;
+    header.IDE = {ide};
+    header.RTR = CAN_RTR_DATA;
+    header.DLC = CAN_{upper}_DLC;
+    return HAL_CAN_AddTxMessage(&hcan1, &header, data, &mailbox) == HAL_OK;
+}}
+"#,
+            id_field = if message.extended { "ExtId" } else { "StdId" },
+            ide = if message.extended { "CAN_ID_EXT" } else { "CAN_ID_STD" })),
+        McuFamily::STM32H7 | McuFamily::STM32G4 => Some(format!(r#"
+bool can_send_{name}(const {name}_t *msg) {{
+    FDCAN_TxHeaderTypeDef header = {{0}};
+    uint8_t data[{buffer}];
+
+    can_pack_{name}(msg, data);
+    header.Identifier = CAN_{upper}_ID;
+    header.IdType = {id_type};
+    header.TxFrameType = FDCAN_DATA_FRAME;
+    header.DataLength = FDCAN_DLC_BYTES_{dlc};
+    header.ErrorStateIndicator = FDCAN_ESI_ACTIVE;
+    header.BitRateSwitch = FDCAN_BRS_OFF;
+    header.FDFormat = {format};
+    header.TxEventFifoControl = FDCAN_NO_TX_EVENTS;
+    return HAL_FDCAN_AddMessageToTxFifoQ(&hfdcan1, &header, data) == HAL_OK;
+}}
+"#,
+            buffer = message.dlc.max(1),
+            dlc = message.dlc,
+            id_type = if message.extended { "FDCAN_EXTENDED_ID" } else { "FDCAN_STANDARD_ID" },
+            format = if message.dlc > 8 { "FDCAN_FD_CAN" } else { "FDCAN_CLASSIC_CAN" })),
+        McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3 if message.dlc <= 8 => Some(format!(r#"
+bool can_send_{name}(const {name}_t *msg) {{
+    twai_message_t frame = {{0}};
+
+    can_pack_{name}(msg, frame.data);
+    frame.identifier = CAN_{upper}_ID;
+    frame.extd = CAN_{upper}_EXTENDED;
+    frame.data_length_code = CAN_{upper}_DLC;
+    return twai_transmit(&frame, pdMS_TO_TICKS(10)) == ESP_OK;
+}}
+"#)),
+        _ => None,
+    }
+}
+
+/// Generate pack/unpack functions for every message in the database
+pub fn generate_can_from_dbc(db: &CanDatabase, mcu: McuFamily) -> DriverOutput {
+    let mut declarations = String::new();
+    let mut functions = String::new();
+
+    for message in &db.messages {
+        let name = &message.name;
+        let upper = name.to_uppercase();
+        let _ = write!(declarations, r#"
+// {name}: 0x{id:X} ({kind}, {dlc} bytes, sent by {tx})
+#define CAN_{upper}_ID        0x{id:X}U
+#define CAN_{upper}_DLC       {dlc}
+#define CAN_{upper}_EXTENDED  {extended}
+
+typedef struct {{
+"#,
+            id = message.id,
+            kind = if message.extended { "extended" } else { "standard" },
+            dlc = message.dlc,
+            tx = message.transmitter,
+            extended = message.extended as u8);
+        if message.signals.is_empty() {
+            declarations.push_str("    uint8_t reserved;  // No signals\n");
+        }
+        for signal in &message.signals {
+            let mut comment = String::new();
+            if !signal.unit.is_empty() {
+                let _ = write!(comment, "{} ", signal.unit);
+            }
+            if signal.min < signal.max {
+                let _ = write!(comment, "[{} .. {}] ", signal.min, signal.max);
+            }
+            if let Multiplex::Multiplexed(value) = signal.multiplex {
+                let _ = write!(comment, "(mux {}) ", value);
+            } else if signal.multiplex == Multiplex::Multiplexor {
+                comment.push_str("(multiplexor) ");
+            }
+            let comment = comment.trim_end();
+            if comment.is_empty() {
+                let _ = writeln!(declarations, "    {} {};", signal.c_type(), signal.name);
+            } else {
+                let _ = writeln!(declarations, "    {} {};  // {}", signal.c_type(), signal.name, comment);
+            }
+        }
+        let _ = write!(declarations, r#"}} {name}_t;
+
+// data must hold CAN_{upper}_DLC bytes
+void can_pack_{name}(const {name}_t *msg, uint8_t *data);
+void can_unpack_{name}(const uint8_t *data, {name}_t *msg);
+"#);
+
+        // The multiplexor has to be known before the signals it selects
+        let mux = message.multiplexor();
+        let ordered: Vec<&CanSignal> = mux.into_iter()
+            .chain(message.signals.iter().filter(|s| s.multiplex != Multiplex::Multiplexor))
+            .collect();
+        let used = if ordered.is_empty() { "    (void)msg;\n" } else { "    uint64_t raw;\n" };
+
+        let _ = write!(functions, "\nvoid can_pack_{name}(const {name}_t *msg, uint8_t *data) {{\n{used}\n    memset(data, 0, CAN_{upper}_DLC);\n");
+        for signal in &ordered {
+            match (signal.multiplex, mux) {
+                (Multiplex::Multiplexed(value), Some(mux)) => {
+                    let _ = writeln!(functions, "    if (msg->{} == {}) {{", mux.name, value);
+                    pack_signal(&mut functions, signal, "        ");
+                    functions.push_str("    }\n");
+                }
+                _ => pack_signal(&mut functions, signal, "    "),
+            }
+        }
+        functions.push_str("}\n");
+
+        let used = if ordered.is_empty() { "    (void)data;\n    (void)msg;\n" } else { "    uint64_t raw;\n" };
+        let _ = write!(functions, "\nvoid can_unpack_{name}(const uint8_t *data, {name}_t *msg) {{\n{used}\n");
+        for signal in &ordered {
+            match (signal.multiplex, mux) {
+                (Multiplex::Multiplexed(value), Some(mux)) => {
+                    let _ = writeln!(functions, "    if (msg->{} == {}) {{", mux.name, value);
+                    unpack_signal(&mut functions, signal, "        ");
+                    functions.push_str("    }\n");
+                }
+                _ => unpack_signal(&mut functions, signal, "    "),
+            }
+        }
+        functions.push_str("}\n");
+
+        if let Some(send) = send_function(message, mcu) {
+            let _ = writeln!(declarations, "bool can_send_{name}(const {name}_t *msg);");
+            functions.push_str(&send);
+        }
+    }
+
+    let (platform_include, handle) = match mcu {
+        McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32L4 => ("#include \"main.h\"\n", "\nextern CAN_HandleTypeDef hcan1;\n"),
+        McuFamily::STM32H7 | McuFamily::STM32G4 => ("#include \"main.h\"\n", "\nextern FDCAN_HandleTypeDef hfdcan1;\n"),
+        McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3 => ("#include \"freertos/FreeRTOS.h\"\n#include \"driver/twai.h\"\n", ""),
+        _ => ("", ""),
+    };
+
+    let header = format!(r#"/**
+ * CAN Database{version}
+ * Auto-generated by NeuroBench from DBC
+ * Messages: {count}, signals: {signals}
+ */
+
+#ifndef CAN_DBC_H
+#define CAN_DBC_H
+
+#include <stdbool.h>
+#include <stdint.h>
+{declarations}
+#endif // CAN_DBC_H
+"#,
+        version = if db.version.is_empty() { String::new() } else { format!(" {}", db.version) },
+        count = db.messages.len(),
+        signals = db.signal_count());
+
+    let source = format!(r#"/**
+ * CAN Database Pack/Unpack
+ * Auto-generated by NeuroBench from DBC
+ * Target: {mcu}
+ */
+
+#include "can_dbc.h"
+#include <math.h>
+#include <string.h>
+{platform_include}{handle}{functions}"#,
+        mcu = mcu.display_name());
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: Some(generate_example(db, mcu)),
+        peripheral_type: PeripheralType::CAN,
+    }
+}
+
+fn generate_example(db: &CanDatabase, mcu: McuFamily) -> String {
+    let mut dispatch = String::new();
+    for message in &db.messages {
+        let name = &message.name;
+        let upper = name.to_uppercase();
+        let _ = write!(dispatch, r#"    case CAN_{upper}_ID: {{
+        {name}_t msg;
+        can_unpack_{name}(data, &msg);
+        // ... use msg
+        break;
+    }}
+"#);
+    }
+
+    let first = &db.messages[0];
+    let send = if send_function(first, mcu).is_some() {
+        format!("    {name}_t msg = {{0}};\n    can_send_{name}(&msg);\n", name = first.name)
+    } else {
+        format!(
+            "    {name}_t msg = {{0}};\n    uint8_t data[CAN_{upper}_DLC > 0 ? CAN_{upper}_DLC : 1];\n    can_pack_{name}(&msg, data);\n    // ... hand data to the CAN controller\n",
+            name = first.name,
+            upper = first.name.to_uppercase()
+        )
+    };
+
+    format!(r#"/**
+ * CAN Database Example
+ * Decode received frames by identifier and transmit a packed message
+ */
+
+#include "can_dbc.h"
+
+void can_dbc_dispatch(uint32_t id, const uint8_t *data) {{
+    switch (id) {{
+{dispatch}    default:
+        break;
+    }}
+}}
+
+void can_dbc_example(void) {{
+{send}}}
+"#)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DBC: &str = r#"VERSION "1.2"
+
+NS_ :
+    CM_
+    BA_
+
+BU_: Engine Dash
+
+BO_ 2364540158 EEC1: 8 Engine
+ SG_ EngineSpeed : 24|16@1+ (0.125,0) [0|8031.875] "rpm" Dash
+ SG_ Torque : 16|8@1- (1,-125) [-125|125] "%" Dash
+
+BO_ 256 Status: 4 Dash
+ SG_ Mode M : 7|4@0+ (1,0) [0|15] "" Engine
+ SG_ Temp m1 : 11|12@0- (0.1,0) [-40|150] "degC" Engine
+ SG_ Fault m2 : 8|1@1+ (1,0) [0|1] "" Engine
+
+BO_ 3221225472 VECTOR__INDEPENDENT_SIG_MSG: 0 Vector__XXX
+ SG_ Unused : 0|8@1+ (1,0) [0|0] "" Vector__XXX
+
+CM_ SG_ 256 Mode "Operating mode";
+"#;
+
+    #[test]
+    fn test_parse_dbc() {
+        let db = parse_dbc(DBC).unwrap();
+        assert_eq!(db.version, "1.2");
+        assert_eq!(db.nodes, vec!["Engine", "Dash"]);
+        assert_eq!(db.messages.len(), 2);
+
+        let eec1 = &db.messages[0];
+        assert_eq!((eec1.id, eec1.extended, eec1.dlc), (0x0CF004FE, true, 8));
+        let speed = &eec1.signals[0];
+        assert_eq!((speed.start_bit, speed.length, speed.byte_order), (24, 16, ByteOrder::LittleEndian));
+        assert_eq!((speed.factor, speed.max, speed.unit.as_str()), (0.125, 8031.875, "rpm"));
+        assert!(eec1.signals[1].is_signed);
+
+        let status = &db.messages[1];
+        assert_eq!(status.multiplexor().unwrap().name, "Mode");
+        assert_eq!(status.signals[1].multiplex, Multiplex::Multiplexed(1));
+        assert_eq!(status.signals[2].receivers, vec!["Engine"]);
+    }
+
+    #[test]
+    fn test_signal_segments() {
+        let db = parse_dbc(DBC).unwrap();
+        assert_eq!(db.messages[0].signals[0].segments(), vec![
+            BitSegment { byte: 3, byte_shift: 0, value_shift: 0, bits: 8 },
+            BitSegment { byte: 4, byte_shift: 0, value_shift: 8, bits: 8 },
+        ]);
+        // Motorola: MSB at bit 11 (byte 1 bit 3), continuing into byte 2
+        assert_eq!(db.messages[1].signals[1].segments(), vec![
+            BitSegment { byte: 1, byte_shift: 0, value_shift: 8, bits: 4 },
+            BitSegment { byte: 2, byte_shift: 0, value_shift: 0, bits: 8 },
+        ]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse_dbc("VERSION \"\"\n").unwrap_err(), DbcParseError::NoMessages);
+        assert!(matches!(
+            parse_dbc(" SG_ Speed : 0|8@1+ (1,0) [0|0] \"\" X\n"),
+            Err(DbcParseError::OrphanSignal { line: 1, .. })
+        ));
+        assert!(matches!(
+            parse_dbc("BO_ 1 Short: 2 X\n SG_ Wide : 8|16@1+ (1,0) [0|0] \"\" X\n"),
+            Err(DbcParseError::SignalOutOfRange { line: 2, dlc: 2, .. })
+        ));
+        assert!(matches!(
+            parse_dbc("BO_ 1 Msg: 8 X\n SG_ Bad : 0|8@2+ (1,0) [0|0] \"\" X\n"),
+            Err(DbcParseError::Syntax { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_generate_can_from_dbc() {
+        let db = parse_dbc(DBC).unwrap();
+        let output = generate_can_from_dbc(&db, McuFamily::STM32F4);
+        let header = output.header_file.unwrap();
+        assert!(header.contains("#define CAN_EEC1_ID        0xCF004FEU"));
+        assert!(header.contains("    float EngineSpeed;  // rpm [0 .. 8031.875]"));
+        assert!(header.contains("    uint8_t Mode;  // [0 .. 15] (multiplexor)"));
+        assert!(header.contains("void can_pack_EEC1(const EEC1_t *msg, uint8_t *data);"));
+
+        let source = &output.source_file;
+        assert!(source.contains("raw = (uint64_t)(int64_t)roundf(((msg->EngineSpeed < 0.0f ? 0.0f"));
+        assert!(source.contains("    data[4] |= (uint8_t)(((raw >> 8) & 0xFFU) << 0);"));
+        assert!(source.contains("    if (msg->Mode == 1) {\n        raw = (uint64_t)(int64_t)roundf("));
+        assert!(source.contains("if (raw & (1ULL << 11)) raw |= ~0xFFFULL;"));
+        assert!(source.contains("header.IDE = CAN_ID_EXT;"));
+
+        let fdcan = generate_can_from_dbc(&db, McuFamily::STM32H7);
+        assert!(fdcan.source_file.contains("header.DataLength = FDCAN_DLC_BYTES_4;"));
+        let none = generate_can_from_dbc(&db, McuFamily::RP2040);
+        assert!(!none.source_file.contains("can_send_"));
+    }
+}
@@ -1,6 +1,9 @@
 // CAN Bus Protocol Stack Generator
 // Generates CAN bus drivers for automotive/industrial applications
 
+pub mod dbc;
+
+use super::hal_abstraction;
 use super::templates::*;
 
 /// CAN configuration
@@ -27,6 +30,7 @@ pub fn generate_can_driver(config: &CanConfig, _arch: &McuArch, lang: &DriverLan
         DriverLanguage::C => generate_can_c(config),
         DriverLanguage::Cpp => generate_can_cpp(config),
         DriverLanguage::Rust => generate_can_rust(config),
+        DriverLanguage::Portable => hal_abstraction::not_portable("CAN", PeripheralType::CAN),
     }
 }
 
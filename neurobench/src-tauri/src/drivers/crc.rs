@@ -0,0 +1,469 @@
+// CRC / Checksum Generator
+// Generates STM32 hardware CRC or table-driven software CRC code
+
+use super::mcu::McuFamily;
+use super::templates::*;
+use serde::{Deserialize, Serialize};
+
+/// Standard CRC presets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrcPolynomial {
+    Crc32Mpeg2,
+    Crc32,
+    Crc16Ccitt,
+    Crc16,
+    Crc8,
+}
+
+impl CrcPolynomial {
+    /// Generator polynomial, normal (MSB-first) form
+    pub fn value(&self) -> u32 {
+        match self {
+            CrcPolynomial::Crc32Mpeg2 | CrcPolynomial::Crc32 => 0x04C1_1DB7,
+            CrcPolynomial::Crc16Ccitt => 0x1021,
+            CrcPolynomial::Crc16 => 0x8005,
+            CrcPolynomial::Crc8 => 0x07,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CrcPolynomial::Crc32Mpeg2 => "CRC-32/MPEG-2",
+            CrcPolynomial::Crc32 => "CRC-32",
+            CrcPolynomial::Crc16Ccitt => "CRC-16/CCITT-FALSE",
+            CrcPolynomial::Crc16 => "CRC-16/ARC",
+            CrcPolynomial::Crc8 => "CRC-8/SMBUS",
+        }
+    }
+}
+
+/// CRC configuration in Rocksoft model terms
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrcConfig {
+    pub polynomial: CrcPolynomial,
+    pub width: u8,
+    pub initial_value: u32,
+    pub input_reflected: bool,
+    pub output_reflected: bool,
+    pub final_xor: u32,
+    pub use_hardware: bool,
+}
+
+impl CrcConfig {
+    /// The catalogued parameters for `polynomial`
+    pub fn preset(polynomial: CrcPolynomial) -> Self {
+        let (width, initial_value, reflected, final_xor) = match polynomial {
+            CrcPolynomial::Crc32Mpeg2 => (32, 0xFFFF_FFFF, false, 0),
+            CrcPolynomial::Crc32 => (32, 0xFFFF_FFFF, true, 0xFFFF_FFFF),
+            CrcPolynomial::Crc16Ccitt => (16, 0xFFFF, false, 0),
+            CrcPolynomial::Crc16 => (16, 0, true, 0),
+            CrcPolynomial::Crc8 => (8, 0, false, 0),
+        };
+        Self {
+            polynomial,
+            width,
+            initial_value,
+            input_reflected: reflected,
+            output_reflected: reflected,
+            final_xor,
+            use_hardware: false,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(8..=32).contains(&self.width) {
+            return Err(format!("CRC width must be between 8 and 32 bits, got {}", self.width));
+        }
+        if self.width < 32 && self.initial_value > self.mask() {
+            return Err(format!("Initial value 0x{:X} does not fit in {} bits", self.initial_value, self.width));
+        }
+        Ok(())
+    }
+
+    fn mask(&self) -> u32 {
+        if self.width >= 32 { u32::MAX } else { (1u32 << self.width) - 1 }
+    }
+
+    /// Compute the CRC on the host, bit by bit
+    pub fn compute(&self, data: &[u8]) -> u32 {
+        let width = self.width as u32;
+        let top = 1u32 << (width - 1);
+        let poly = self.polynomial.value() & self.mask();
+        let mut crc = self.initial_value & self.mask();
+        for &byte in data {
+            let byte = if self.input_reflected { byte.reverse_bits() } else { byte };
+            for bit in (0..8).rev() {
+                let input = (byte >> bit) & 1 == 1;
+                let feedback = (crc & top != 0) ^ input;
+                crc = (crc << 1) & self.mask();
+                if feedback {
+                    crc ^= poly;
+                }
+            }
+        }
+        if self.output_reflected {
+            crc = crc.reverse_bits() >> (32 - width);
+        }
+        (crc ^ self.final_xor) & self.mask()
+    }
+
+    /// 256-entry lookup table for the byte-at-a-time algorithm
+    ///
+    /// Reflected configurations get a reflected table so the generated loop
+    /// can shift right without reversing every byte.
+    pub fn table(&self) -> Vec<u32> {
+        let width = self.width as u32;
+        let poly = self.polynomial.value() & self.mask();
+        (0..256u32).map(|i| {
+            if self.input_reflected {
+                let reflected_poly = poly.reverse_bits() >> (32 - width);
+                (0..8).fold(i, |crc, _| if crc & 1 != 0 { (crc >> 1) ^ reflected_poly } else { crc >> 1 })
+            } else {
+                let top = 1u32 << (width - 1);
+                let mut crc = if width >= 8 { i << (width - 8) } else { i };
+                for _ in 0..8 {
+                    crc = if crc & top != 0 { (crc << 1) ^ poly } else { crc << 1 };
+                }
+                crc & self.mask()
+            }
+        }).collect()
+    }
+}
+
+impl Default for CrcConfig {
+    fn default() -> Self {
+        Self::preset(CrcPolynomial::Crc32)
+    }
+}
+
+/// Whether `mcu`'s CRC unit can compute `config` in hardware
+///
+/// F1/F4 have a fixed CRC-32/MPEG-2 unit; H7/L4/G4 are programmable and handle
+/// any width and reflection, with the final XOR done in software.
+pub fn hardware_supported(config: &CrcConfig, mcu: McuFamily) -> bool {
+    match mcu {
+        McuFamily::STM32F1 | McuFamily::STM32F4 => {
+            config.polynomial == CrcPolynomial::Crc32Mpeg2
+                && config.width == 32
+                && config.initial_value == 0xFFFF_FFFF
+                && !config.input_reflected
+                && !config.output_reflected
+        }
+        McuFamily::STM32H7 | McuFamily::STM32L4 | McuFamily::STM32G4 => matches!(config.width, 8 | 16 | 32),
+        _ => false,
+    }
+}
+
+/// Generate CRC driver code
+pub fn generate_crc_driver(config: &CrcConfig, mcu: McuFamily) -> DriverOutput {
+    let hardware = config.use_hardware && hardware_supported(config, mcu);
+    let c_type = match config.width {
+        0..=8 => "uint8_t",
+        9..=16 => "uint16_t",
+        _ => "uint32_t",
+    };
+    let hex_digits = (config.width as usize).div_ceil(4);
+    let check = config.compute(b"123456789");
+    let name = config.polynomial.name();
+    let mode = if hardware { "STM32 hardware CRC unit" } else { "software, table-driven" };
+    let fallback_note = if config.use_hardware && !hardware {
+        format!(" * Note: {} has no CRC unit for this configuration; using software\n", mcu.display_name())
+    } else {
+        String::new()
+    };
+
+    let header = format!(r#"/**
+ * CRC Driver: {name}
+ * Auto-generated by NeuroBench
+ * Implementation: {mode}
+ */
+
+#ifndef CRC_DRIVER_H
+#define CRC_DRIVER_H
+
+#include <stdint.h>
+#include <stdbool.h>
+#include <stddef.h>
+
+#define CRC_WIDTH        {width}
+#define CRC_POLYNOMIAL   0x{poly:0digits$X}
+#define CRC_INIT         0x{init:0digits$X}
+#define CRC_FINAL_XOR    0x{xorout:0digits$X}
+#define CRC_CHECK_VALUE  0x{check:0digits$X}  // CRC of "123456789"
+
+typedef {c_type} crc_t;
+
+void crc_driver_init(void);
+crc_t crc_calculate(const uint8_t *data, size_t len);
+bool crc_verify(const uint8_t *data, size_t len, crc_t expected);
+bool crc_self_test(void);
+
+#endif // CRC_DRIVER_H
+"#,
+        width = config.width,
+        poly = config.polynomial.value() & config.mask(),
+        init = config.initial_value & config.mask(),
+        xorout = config.final_xor & config.mask(),
+        digits = hex_digits,
+    );
+
+    let body = if hardware {
+        hardware_source(config, mcu)
+    } else {
+        software_source(config, c_type, hex_digits)
+    };
+
+    let source = format!(r#"/**
+ * CRC Driver: {name}
+ * Auto-generated by NeuroBench
+ * Input reflected: {refin}, output reflected: {refout}
+{fallback_note} */
+
+#include "crc_driver.h"
+{body}
+bool crc_verify(const uint8_t *data, size_t len, crc_t expected) {{
+    return crc_calculate(data, len) == expected;
+}}
+
+bool crc_self_test(void) {{
+    static const uint8_t vector[] = {{ '1', '2', '3', '4', '5', '6', '7', '8', '9' }};
+    return crc_verify(vector, sizeof(vector), (crc_t)CRC_CHECK_VALUE);
+}}
+"#,
+        refin = config.input_reflected,
+        refout = config.output_reflected,
+    );
+
+    let example = r#"/**
+ * CRC Example
+ * Validates a firmware image with its appended CRC
+ */
+
+#include "crc_driver.h"
+
+extern const uint8_t __app_start[];
+extern const uint32_t __app_size;
+
+int main(void) {
+    HAL_Init();
+    crc_driver_init();
+
+    if (!crc_self_test()) {
+        Error_Handler();
+    }
+
+    const crc_t *stored = (const crc_t *)(__app_start + __app_size);
+    if (!crc_verify(__app_start, __app_size, *stored)) {
+        Error_Handler();  // Corrupted image
+    }
+
+    while (1) {
+    }
+}
+"#.to_string();
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: Some(example),
+        peripheral_type: PeripheralType::CRC,
+    }
+}
+
+fn software_source(config: &CrcConfig, c_type: &str, hex_digits: usize) -> String {
+    let rows: Vec<String> = config.table()
+        .chunks(8)
+        .map(|row| {
+            let cells: Vec<String> = row.iter().map(|v| format!("0x{:0width$X}", v, width = hex_digits)).collect();
+            format!("    {},", cells.join(", "))
+        })
+        .collect();
+    let table = rows.join("\n");
+
+    let width = config.width;
+    let update = if config.input_reflected {
+        "        crc = (crc >> 8) ^ crc_table[(crc ^ *data++) & 0xFF];".to_string()
+    } else if width >= 8 {
+        format!("        crc = (crc_t)((crc << 8) ^ crc_table[((crc >> {}) ^ *data++) & 0xFF]);", width - 8)
+    } else {
+        format!("        crc = crc_table[((crc << {}) ^ *data++) & 0xFF];", 8 - width)
+    };
+    // The reflected table already yields a reflected result; only mismatched
+    // input/output reflection needs an explicit bit reversal
+    let finish = if config.input_reflected != config.output_reflected {
+        r#"    crc_t reflected = 0;
+    for (int i = 0; i < CRC_WIDTH; i++) {
+        if (crc & (1UL << i)) {
+            reflected |= (crc_t)(1UL << (CRC_WIDTH - 1 - i));
+        }
+    }
+    crc = reflected;
+"#.to_string()
+    } else {
+        String::new()
+    };
+
+    // A reflected register starts from the reflected initial value
+    let register_init = if config.input_reflected {
+        (config.initial_value & config.mask()).reverse_bits() >> (32 - width as u32)
+    } else {
+        config.initial_value & config.mask()
+    };
+
+    format!(r#"
+#define CRC_INIT_REGISTER 0x{register_init:0hex_digits$X}
+
+static const {c_type} crc_table[256] = {{
+{table}
+}};
+
+void crc_driver_init(void) {{
+    // Table is in flash; nothing to set up
+}}
+
+crc_t crc_calculate(const uint8_t *data, size_t len) {{
+    crc_t crc = (crc_t)CRC_INIT_REGISTER;
+    while (len--) {{
+{update}
+    }}
+{finish}    return (crc_t)(crc ^ CRC_FINAL_XOR);
+}}
+"#)
+}
+
+fn hardware_source(config: &CrcConfig, mcu: McuFamily) -> String {
+    let hal_header = match mcu {
+        McuFamily::STM32F1 => "stm32f1xx_hal.h",
+        McuFamily::STM32H7 => "stm32h7xx_hal.h",
+        McuFamily::STM32L4 => "stm32l4xx_hal.h",
+        McuFamily::STM32G4 => "stm32g4xx_hal.h",
+        _ => "stm32f4xx_hal.h",
+    };
+
+    if matches!(mcu, McuFamily::STM32F1 | McuFamily::STM32F4) {
+        // Fixed unit: CRC-32/MPEG-2 over 32-bit words; feed the tail bytes in software
+        return format!(r#"#include "{hal_header}"
+
+CRC_HandleTypeDef hcrc;
+
+void crc_driver_init(void) {{
+    __HAL_RCC_CRC_CLK_ENABLE();
+    hcrc.Instance = CRC;
+    HAL_CRC_Init(&hcrc);
+}}
+
+crc_t crc_calculate(const uint8_t *data, size_t len) {{
+    size_t words = len / 4;
+    uint32_t crc = CRC_INIT;
+    if (words > 0) {{
+        // The unit consumes words MSB-first, so bytes must be packed big-endian
+        __HAL_CRC_DR_RESET(&hcrc);
+        for (size_t i = 0; i < words; i++) {{
+            const uint8_t *p = data + i * 4;
+            hcrc.Instance->DR = (uint32_t)p[0] << 24 | (uint32_t)p[1] << 16 | (uint32_t)p[2] << 8 | p[3];
+        }}
+        crc = hcrc.Instance->DR;
+    }}
+    for (size_t i = words * 4; i < len; i++) {{
+        crc ^= (uint32_t)data[i] << 24;
+        for (int bit = 0; bit < 8; bit++) {{
+            crc = (crc & 0x80000000u) ? (crc << 1) ^ CRC_POLYNOMIAL : crc << 1;
+        }}
+    }}
+    return crc ^ CRC_FINAL_XOR;
+}}
+"#);
+    }
+
+    let length = match config.width {
+        8 => "CRC_POLYLENGTH_8B",
+        16 => "CRC_POLYLENGTH_16B",
+        _ => "CRC_POLYLENGTH_32B",
+    };
+    let input_inversion = if config.input_reflected { "CRC_INPUTDATA_INVERSION_BYTE" } else { "CRC_INPUTDATA_INVERSION_NONE" };
+    let output_inversion = if config.output_reflected { "CRC_OUTPUTDATA_INVERSION_ENABLE" } else { "CRC_OUTPUTDATA_INVERSION_DISABLE" };
+
+    format!(r#"#include "{hal_header}"
+
+CRC_HandleTypeDef hcrc;
+
+void crc_driver_init(void) {{
+    __HAL_RCC_CRC_CLK_ENABLE();
+    hcrc.Instance = CRC;
+    hcrc.Init.DefaultPolynomialUse = DEFAULT_POLYNOMIAL_DISABLE;
+    hcrc.Init.GeneratingPolynomial = CRC_POLYNOMIAL;
+    hcrc.Init.CRCLength = {length};
+    hcrc.Init.DefaultInitValueUse = DEFAULT_INIT_VALUE_DISABLE;
+    hcrc.Init.InitValue = CRC_INIT;
+    hcrc.Init.InputDataInversionMode = {input_inversion};
+    hcrc.Init.OutputDataInversionMode = {output_inversion};
+    hcrc.InputDataFormat = CRC_INPUTDATA_FORMAT_BYTES;
+    HAL_CRC_Init(&hcrc);
+}}
+
+crc_t crc_calculate(const uint8_t *data, size_t len) {{
+    // Final XOR is not part of the hardware unit
+    uint32_t crc = HAL_CRC_Calculate(&hcrc, (uint32_t *)data, (uint32_t)len);
+    return (crc_t)(crc ^ CRC_FINAL_XOR);
+}}
+"#)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECK: &[u8] = b"123456789";
+
+    #[test]
+    fn test_known_check_values() {
+        let expected = [
+            (CrcPolynomial::Crc32Mpeg2, 0x0376_E6E7),
+            (CrcPolynomial::Crc32, 0xCBF4_3926),
+            (CrcPolynomial::Crc16Ccitt, 0x29B1),
+            (CrcPolynomial::Crc16, 0xBB3D),
+            (CrcPolynomial::Crc8, 0xF4),
+        ];
+        for (polynomial, check) in expected {
+            assert_eq!(CrcConfig::preset(polynomial).compute(CHECK), check, "{}", polynomial.name());
+        }
+    }
+
+    #[test]
+    fn test_table_matches_bitwise() {
+        // Run the generated C loop's algorithm against the emitted table
+        for polynomial in [CrcPolynomial::Crc32, CrcPolynomial::Crc16Ccitt, CrcPolynomial::Crc16, CrcPolynomial::Crc8] {
+            let config = CrcConfig::preset(polynomial);
+            let table = config.table();
+            let width = config.width as u32;
+            let mask = config.mask();
+            let mut crc = if config.input_reflected {
+                config.initial_value.reverse_bits() >> (32 - width)
+            } else {
+                config.initial_value
+            };
+            for &byte in CHECK {
+                crc = if config.input_reflected {
+                    (crc >> 8) ^ table[((crc ^ byte as u32) & 0xFF) as usize]
+                } else {
+                    ((crc << 8) ^ table[(((crc >> (width - 8)) ^ byte as u32) & 0xFF) as usize]) & mask
+                };
+            }
+            assert_eq!((crc ^ config.final_xor) & mask, config.compute(CHECK), "{}", polynomial.name());
+        }
+    }
+
+    #[test]
+    fn test_hardware_selection() {
+        let mpeg2 = CrcConfig { use_hardware: true, ..CrcConfig::preset(CrcPolynomial::Crc32Mpeg2) };
+        let output = generate_crc_driver(&mpeg2, McuFamily::STM32F4);
+        assert!(output.source_file.contains("__HAL_CRC_DR_RESET"));
+        assert!(output.header_file.unwrap().contains("#define CRC_CHECK_VALUE  0x0376E6E7"));
+
+        // F4's fixed unit cannot do reflected CRC-32, so fall back to the table
+        let crc32 = CrcConfig { use_hardware: true, ..CrcConfig::preset(CrcPolynomial::Crc32) };
+        let output = generate_crc_driver(&crc32, McuFamily::STM32F4);
+        assert!(output.source_file.contains("crc_table[256]"));
+        assert!(generate_crc_driver(&crc32, McuFamily::STM32G4).source_file.contains("CRC_INPUTDATA_INVERSION_BYTE"));
+    }
+}
@@ -0,0 +1,307 @@
+// Button Debounce Generator
+// Generates a timer-driven debounce state machine with press, long-press and repeat events
+
+use super::templates::*;
+use serde::{Deserialize, Serialize};
+
+/// Debounce configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebounceConfig {
+    pub num_buttons: u8,
+    pub debounce_ms: u32,
+    pub long_press_ms: u32,
+    pub repeat_enabled: bool,
+    pub repeat_interval_ms: u32,
+}
+
+impl Default for DebounceConfig {
+    fn default() -> Self {
+        Self {
+            num_buttons: 4,
+            debounce_ms: 20,
+            long_press_ms: 800,
+            repeat_enabled: false,
+            repeat_interval_ms: 150,
+        }
+    }
+}
+
+impl DebounceConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.num_buttons == 0 {
+            return Err("At least one button is required".to_string());
+        }
+        if self.long_press_ms <= self.debounce_ms {
+            return Err(format!(
+                "Long press ({} ms) must be longer than the debounce time ({} ms)",
+                self.long_press_ms, self.debounce_ms
+            ));
+        }
+        if self.repeat_enabled && self.repeat_interval_ms == 0 {
+            return Err("Repeat interval must be non-zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Generate button debounce driver code
+pub fn generate_debounce_driver(config: &DebounceConfig) -> DriverOutput {
+    let num_buttons = config.num_buttons;
+    let debounce_ms = config.debounce_ms;
+    let long_press_ms = config.long_press_ms;
+    let repeat_enabled = config.repeat_enabled as u8;
+    let repeat_interval_ms = config.repeat_interval_ms;
+
+    let header = format!(r#"/**
+ * Button Debounce Driver
+ * Auto-generated by NeuroBench
+ * Buttons: {num_buttons}, debounce: {debounce_ms} ms, long press: {long_press_ms} ms
+ */
+
+#ifndef BUTTON_DEBOUNCE_H
+#define BUTTON_DEBOUNCE_H
+
+#include <stdint.h>
+#include <stdbool.h>
+
+#define BUTTON_COUNT              {num_buttons}
+#define BUTTON_DEBOUNCE_MS        {debounce_ms}
+#define BUTTON_LONG_PRESS_MS      {long_press_ms}
+#define BUTTON_REPEAT_ENABLED     {repeat_enabled}
+#define BUTTON_REPEAT_INTERVAL_MS {repeat_interval_ms}
+#define BUTTON_TICK_MS            1   // Period of button_tick()
+#define BUTTON_EVENT_QUEUE_LEN    4   // Pending events per button
+
+typedef enum {{
+    BUTTON_EVENT_NONE = 0,
+    BUTTON_EVENT_PRESSED,     // Debounced press
+    BUTTON_EVENT_RELEASED,    // Released before the long-press time
+    BUTTON_EVENT_LONG_PRESS,  // Held for BUTTON_LONG_PRESS_MS
+    BUTTON_EVENT_REPEAT,      // Still held, every BUTTON_REPEAT_INTERVAL_MS
+    BUTTON_EVENT_LONG_RELEASED,
+}} ButtonEvent;
+
+typedef void (*button_callback_t)(uint8_t button, ButtonEvent event);
+
+// Implement for your board: true while button n is physically pressed
+bool button_hw_read(uint8_t n);
+
+void button_init(void);
+void button_tick(void);  // Call every BUTTON_TICK_MS, e.g. from SysTick
+ButtonEvent button_get_event(uint8_t n);
+bool button_is_pressed(uint8_t n);
+void button_set_callback(button_callback_t callback);
+
+#endif // BUTTON_DEBOUNCE_H
+"#);
+
+    let source = format!(r#"/**
+ * Button Debounce Driver
+ * Auto-generated by NeuroBench
+ *
+ * Per-button state machine, advanced from button_tick():
+ *
+ *   RAW --press--> DEBOUNCING --stable {debounce_ms} ms--> PRESSED --{long_press_ms} ms--> HELD --> REPEAT
+ *    ^                 |                                  |                 |            |
+ *    +----bounce-------+                                  +-----stable release-----------+
+ */
+
+#include "button_debounce.h"
+#include <stddef.h>
+
+typedef enum {{
+    BTN_STATE_RAW,
+    BTN_STATE_DEBOUNCING,
+    BTN_STATE_PRESSED,
+    BTN_STATE_HELD,
+    BTN_STATE_REPEAT,
+}} button_state_t;
+
+typedef struct {{
+    button_state_t state;
+    uint32_t timer_ms;          // Time in the current state
+    uint32_t release_ms;        // Time the input has read released
+    volatile uint8_t head;
+    volatile uint8_t tail;
+    ButtonEvent queue[BUTTON_EVENT_QUEUE_LEN];
+}} button_t;
+
+static button_t buttons[BUTTON_COUNT];
+static button_callback_t button_callback = NULL;
+
+__attribute__((weak)) bool button_hw_read(uint8_t n) {{
+    (void)n;
+    return false;
+}}
+
+static void button_push(uint8_t n, ButtonEvent event) {{
+    button_t *btn = &buttons[n];
+    uint8_t next = (uint8_t)((btn->head + 1) % BUTTON_EVENT_QUEUE_LEN);
+    if (next != btn->tail) {{
+        btn->queue[btn->head] = event;
+        btn->head = next;
+    }}
+    if (button_callback) {{
+        button_callback(n, event);  // Runs in timer interrupt context
+    }}
+}}
+
+void button_init(void) {{
+    for (uint8_t i = 0; i < BUTTON_COUNT; i++) {{
+        buttons[i].state = BTN_STATE_RAW;
+        buttons[i].timer_ms = 0;
+        buttons[i].release_ms = 0;
+        buttons[i].head = 0;
+        buttons[i].tail = 0;
+    }}
+}}
+
+// Returns true once a release has been stable for the debounce time
+static bool button_released(button_t *btn, bool pressed) {{
+    if (pressed) {{
+        btn->release_ms = 0;
+        return false;
+    }}
+    btn->release_ms += BUTTON_TICK_MS;
+    return btn->release_ms >= BUTTON_DEBOUNCE_MS;
+}}
+
+void button_tick(void) {{
+    for (uint8_t i = 0; i < BUTTON_COUNT; i++) {{
+        button_t *btn = &buttons[i];
+        bool pressed = button_hw_read(i);
+        btn->timer_ms += BUTTON_TICK_MS;
+
+        switch (btn->state) {{
+            case BTN_STATE_RAW:
+                if (pressed) {{
+                    btn->state = BTN_STATE_DEBOUNCING;
+                    btn->timer_ms = 0;
+                }}
+                break;
+
+            case BTN_STATE_DEBOUNCING:
+                if (!pressed) {{
+                    btn->state = BTN_STATE_RAW;  // Bounce or glitch
+                }} else if (btn->timer_ms >= BUTTON_DEBOUNCE_MS) {{
+                    btn->state = BTN_STATE_PRESSED;
+                    btn->timer_ms = 0;
+                    btn->release_ms = 0;
+                    button_push(i, BUTTON_EVENT_PRESSED);
+                }}
+                break;
+
+            case BTN_STATE_PRESSED:
+                if (button_released(btn, pressed)) {{
+                    btn->state = BTN_STATE_RAW;
+                    button_push(i, BUTTON_EVENT_RELEASED);
+                }} else if (btn->timer_ms >= BUTTON_LONG_PRESS_MS) {{
+                    btn->state = BTN_STATE_HELD;
+                    btn->timer_ms = 0;
+                    button_push(i, BUTTON_EVENT_LONG_PRESS);
+                }}
+                break;
+
+            case BTN_STATE_HELD:
+            case BTN_STATE_REPEAT:
+                if (button_released(btn, pressed)) {{
+                    btn->state = BTN_STATE_RAW;
+                    button_push(i, BUTTON_EVENT_LONG_RELEASED);
+                }}
+#if BUTTON_REPEAT_ENABLED
+                else if (btn->timer_ms >= BUTTON_REPEAT_INTERVAL_MS) {{
+                    btn->state = BTN_STATE_REPEAT;
+                    btn->timer_ms = 0;
+                    button_push(i, BUTTON_EVENT_REPEAT);
+                }}
+#endif
+                break;
+        }}
+    }}
+}}
+
+ButtonEvent button_get_event(uint8_t n) {{
+    if (n >= BUTTON_COUNT) {{
+        return BUTTON_EVENT_NONE;
+    }}
+    button_t *btn = &buttons[n];
+    if (btn->tail == btn->head) {{
+        return BUTTON_EVENT_NONE;
+    }}
+    ButtonEvent event = btn->queue[btn->tail];
+    btn->tail = (uint8_t)((btn->tail + 1) % BUTTON_EVENT_QUEUE_LEN);
+    return event;
+}}
+
+bool button_is_pressed(uint8_t n) {{
+    return n < BUTTON_COUNT && buttons[n].state >= BTN_STATE_PRESSED;
+}}
+
+void button_set_callback(button_callback_t callback) {{
+    button_callback = callback;
+}}
+"#);
+
+    let example = format!(r#"/**
+ * Button Debounce Example
+ * Polls events from the main loop; button_tick() runs from SysTick
+ */
+
+#include "button_debounce.h"
+#include "main.h"
+#include <stdio.h>
+
+static GPIO_TypeDef *const button_ports[BUTTON_COUNT] = {{ {ports} }};
+static const uint16_t button_pins[BUTTON_COUNT] = {{ {pins} }};
+
+// Active-low buttons with pull-ups
+bool button_hw_read(uint8_t n) {{
+    return HAL_GPIO_ReadPin(button_ports[n], button_pins[n]) == GPIO_PIN_RESET;
+}}
+
+// HAL calls this every 1 ms from SysTick_Handler
+void HAL_SYSTICK_Callback(void) {{
+    button_tick();
+}}
+
+int main(void) {{
+    HAL_Init();
+    SystemClock_Config();
+    MX_GPIO_Init();
+    button_init();
+
+    while (1) {{
+        for (uint8_t i = 0; i < BUTTON_COUNT; i++) {{
+            switch (button_get_event(i)) {{
+                case BUTTON_EVENT_PRESSED:
+                    printf("Button %u pressed\n", i);
+                    break;
+                case BUTTON_EVENT_LONG_PRESS:
+                    printf("Button %u long press\n", i);
+                    break;
+                case BUTTON_EVENT_REPEAT:
+                    printf("Button %u repeat\n", i);
+                    break;
+                case BUTTON_EVENT_RELEASED:
+                case BUTTON_EVENT_LONG_RELEASED:
+                    printf("Button %u released\n", i);
+                    break;
+                default:
+                    break;
+            }}
+        }}
+        HAL_Delay(5);
+    }}
+}}
+"#,
+        ports = (0..num_buttons).map(|_| "GPIOA").collect::<Vec<_>>().join(", "),
+        pins = (0..num_buttons).map(|i| format!("GPIO_PIN_{}", i % 16)).collect::<Vec<_>>().join(", "),
+    );
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: Some(example),
+        peripheral_type: PeripheralType::GPIO,
+    }
+}
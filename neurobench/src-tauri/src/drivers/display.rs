@@ -0,0 +1,847 @@
+// Display Driver Generator
+// Generates text/graphics drivers for small OLED and TFT panels on top of the generated I2C/SPI drivers
+
+use super::mcu::McuFamily;
+use super::soft_i2c::{gpio_include, parse_pin, PinRef};
+use super::templates::*;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Supported display controllers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayController {
+    Ssd1306,
+    Sh1106,
+    Ili9341,
+    St7789,
+}
+
+/// How the display is wired to the MCU
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayInterface {
+    I2c,
+    Spi,
+}
+
+impl DisplayController {
+    /// 1 bit per pixel OLED with a RAM framebuffer, rather than an RGB565 TFT
+    pub fn is_monochrome(&self) -> bool {
+        matches!(self, DisplayController::Ssd1306 | DisplayController::Sh1106)
+    }
+
+    pub fn interfaces(&self) -> &'static [DisplayInterface] {
+        if self.is_monochrome() {
+            &[DisplayInterface::I2c, DisplayInterface::Spi]
+        } else {
+            &[DisplayInterface::Spi]
+        }
+    }
+
+    /// Most common panel resolution, landscape for the TFTs
+    pub fn default_size(&self) -> (u16, u16) {
+        if self.is_monochrome() {
+            (128, 64)
+        } else {
+            (320, 240)
+        }
+    }
+}
+
+/// Display driver configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplayConfig {
+    pub controller: DisplayController,
+    pub interface: DisplayInterface,
+    pub width: u16,
+    pub height: u16,
+    /// Chip-select pin such as "PA4"; the SPI driver's own CS is used when unset
+    pub cs_pin: Option<String>,
+    /// Data/command select, SPI only
+    pub dc_pin: String,
+    /// Hardware reset; TFTs get a software reset instead when unset
+    pub rst_pin: Option<String>,
+}
+
+impl DisplayConfig {
+    pub fn new(controller: DisplayController, interface: DisplayInterface) -> Self {
+        let (width, height) = controller.default_size();
+        Self {
+            controller,
+            interface,
+            width,
+            height,
+            cs_pin: None,
+            dc_pin: "PB0".to_string(),
+            rst_pin: None,
+        }
+    }
+
+    pub fn validate(&self, mcu: McuFamily) -> Result<(), String> {
+        if !matches!(mcu, McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32H7 | McuFamily::STM32L4 | McuFamily::STM32G4) {
+            return Err(format!("Display drivers use the STM32 HAL I2C/SPI drivers; {} is not supported", mcu.display_name()));
+        }
+        if !self.controller.interfaces().contains(&self.interface) {
+            return Err(format!("{:?} does not support the {:?} interface", self.controller, self.interface));
+        }
+
+        if self.controller.is_monochrome() {
+            if self.width == 0 || self.width > 128 {
+                return Err(format!("{:?} width must be 1-128 pixels, got {}", self.controller, self.width));
+            }
+            if self.height == 0 || self.height > 64 || !self.height.is_multiple_of(8) {
+                return Err(format!("{:?} height must be a multiple of 8 up to 64, got {}", self.controller, self.height));
+            }
+        } else if self.width == 0 || self.height == 0 || self.width.min(self.height) > 240 || self.width.max(self.height) > 320 {
+            return Err(format!("{:?} panels are at most 240x320, got {}x{}", self.controller, self.width, self.height));
+        }
+
+        let mut pins = Vec::new();
+        if self.interface == DisplayInterface::Spi {
+            pins.push(("DC", &self.dc_pin));
+            pins.extend(self.cs_pin.iter().map(|pin| ("CS", pin)));
+        }
+        pins.extend(self.rst_pin.iter().map(|pin| ("RST", pin)));
+        let mut parsed = Vec::new();
+        for (name, pin) in pins {
+            let p = parse_pin(mcu, pin)
+                .ok_or_else(|| format!("Invalid {} pin for {}: {}", name, mcu.display_name(), pin))?;
+            if parsed.contains(&p) {
+                return Err(format!("{} pin {} is already used", name, pin));
+            }
+            parsed.push(p);
+        }
+        Ok(())
+    }
+
+    /// Control pins the driver drives as GPIO outputs
+    fn control_pins(&self, mcu: McuFamily) -> Vec<(&'static str, PinRef)> {
+        let mut pins = Vec::new();
+        if self.interface == DisplayInterface::Spi {
+            pins.push(("DC", &self.dc_pin));
+            pins.extend(self.cs_pin.iter().map(|pin| ("CS", pin)));
+        }
+        pins.extend(self.rst_pin.iter().map(|pin| ("RST", pin)));
+        pins.into_iter()
+            .filter_map(|(name, pin)| parse_pin(mcu, pin).map(|p| (name, p)))
+            .collect()
+    }
+}
+
+/// One controller command with its parameter bytes
+struct InitCommand {
+    cmd: u8,
+    args: Vec<u8>,
+    delay_ms: u8,
+    comment: &'static str,
+}
+
+fn cmd(cmd: u8, args: &[u8], comment: &'static str) -> InitCommand {
+    InitCommand { cmd, args: args.to_vec(), delay_ms: 0, comment }
+}
+
+fn cmd_delay(cmd: u8, args: &[u8], delay_ms: u8, comment: &'static str) -> InitCommand {
+    InitCommand { cmd, args: args.to_vec(), delay_ms, comment }
+}
+
+/// Power-up command sequence for the controller and panel size
+fn init_sequence(config: &DisplayConfig) -> Vec<InitCommand> {
+    let (width, height) = (config.width, config.height);
+    match config.controller {
+        DisplayController::Ssd1306 => {
+            // 128x32 panels wire the COM lines sequentially and need less contrast
+            let (com_pins, contrast) = if height > 32 { (0x12, 0xCF) } else { (0x02, 0x8F) };
+            vec![
+                cmd(0xAE, &[], "Display off"),
+                cmd(0xD5, &[0x80], "Clock divide ratio / oscillator frequency"),
+                cmd(0xA8, &[(height - 1) as u8], "Multiplex ratio"),
+                cmd(0xD3, &[0x00], "Display offset"),
+                cmd(0x40, &[], "Start line 0"),
+                cmd(0x8D, &[0x14], "Charge pump on (internal VCC)"),
+                cmd(0x20, &[0x00], "Horizontal addressing mode"),
+                cmd(0xA1, &[], "Segment remap: column 127 is SEG0"),
+                cmd(0xC8, &[], "COM scan direction: remapped"),
+                cmd(0xDA, &[com_pins], "COM pins hardware configuration"),
+                cmd(0x81, &[contrast], "Contrast"),
+                cmd(0xD9, &[0xF1], "Pre-charge period"),
+                cmd(0xDB, &[0x40], "VCOMH deselect level"),
+                cmd(0xA4, &[], "Display follows RAM"),
+                cmd(0xA6, &[], "Normal (not inverted)"),
+                cmd(0x2E, &[], "Scrolling off"),
+                cmd(0xAF, &[], "Display on"),
+            ]
+        }
+        DisplayController::Sh1106 => vec![
+            cmd(0xAE, &[], "Display off"),
+            cmd(0xD5, &[0x80], "Clock divide ratio / oscillator frequency"),
+            cmd(0xA8, &[(height - 1) as u8], "Multiplex ratio"),
+            cmd(0xD3, &[0x00], "Display offset"),
+            cmd(0x40, &[], "Start line 0"),
+            cmd(0xAD, &[0x8B], "DC-DC converter on"),
+            cmd(0xA1, &[], "Segment remap"),
+            cmd(0xC8, &[], "COM scan direction: remapped"),
+            cmd(0xDA, &[0x12], "COM pins hardware configuration"),
+            cmd(0x81, &[0x80], "Contrast"),
+            cmd(0xD9, &[0x22], "Pre-charge period"),
+            cmd(0xDB, &[0x35], "VCOM deselect level"),
+            cmd(0xA4, &[], "Display follows RAM"),
+            cmd(0xA6, &[], "Normal (not inverted)"),
+            cmd(0xAF, &[], "Display on"),
+        ],
+        DisplayController::Ili9341 => {
+            // MV swaps rows and columns for landscape; BGR panel order
+            let madctl = if width > height { 0x28 } else { 0x48 };
+            let mut commands = Vec::new();
+            if config.rst_pin.is_none() {
+                commands.push(cmd_delay(0x01, &[], 150, "Software reset"));
+            }
+            commands.extend([
+                cmd(0xCF, &[0x00, 0xC1, 0x30], "Power control B"),
+                cmd(0xED, &[0x64, 0x03, 0x12, 0x81], "Power on sequence control"),
+                cmd(0xE8, &[0x85, 0x00, 0x78], "Driver timing control A"),
+                cmd(0xCB, &[0x39, 0x2C, 0x00, 0x34, 0x02], "Power control A"),
+                cmd(0xF7, &[0x20], "Pump ratio control"),
+                cmd(0xEA, &[0x00, 0x00], "Driver timing control B"),
+                cmd(0xC0, &[0x23], "Power control 1: GVDD 4.6 V"),
+                cmd(0xC1, &[0x10], "Power control 2"),
+                cmd(0xC5, &[0x3E, 0x28], "VCOM control 1"),
+                cmd(0xC7, &[0x86], "VCOM control 2"),
+                cmd(0x36, &[madctl], "Memory access control (orientation)"),
+                cmd(0x3A, &[0x55], "Pixel format: 16-bit RGB565"),
+                cmd(0xB1, &[0x00, 0x18], "Frame rate: 79 Hz"),
+                cmd(0xB6, &[0x08, 0x82, 0x27], "Display function control"),
+                cmd(0xF2, &[0x00], "3-gamma off"),
+                cmd(0x26, &[0x01], "Gamma curve 1"),
+                cmd(0xE0, &[0x0F, 0x31, 0x2B, 0x0C, 0x0E, 0x08, 0x4E, 0xF1, 0x37, 0x07, 0x10, 0x03, 0x0E, 0x09, 0x00], "Positive gamma correction"),
+                cmd(0xE1, &[0x00, 0x0E, 0x14, 0x03, 0x11, 0x07, 0x31, 0xC1, 0x48, 0x08, 0x0F, 0x0C, 0x31, 0x36, 0x0F], "Negative gamma correction"),
+                cmd_delay(0x11, &[], 120, "Sleep out"),
+                cmd(0x29, &[], "Display on"),
+            ]);
+            commands
+        }
+        DisplayController::St7789 => {
+            // MX|MV for landscape; RGB panel order
+            let madctl = if width > height { 0x60 } else { 0x00 };
+            let mut commands = Vec::new();
+            if config.rst_pin.is_none() {
+                commands.push(cmd_delay(0x01, &[], 150, "Software reset"));
+            }
+            commands.extend([
+                cmd_delay(0x11, &[], 120, "Sleep out"),
+                cmd(0x3A, &[0x55], "Pixel format: 16-bit RGB565"),
+                cmd(0x36, &[madctl], "Memory access control (orientation)"),
+                cmd(0x21, &[], "Inversion on (IPS panels)"),
+                cmd_delay(0x13, &[], 10, "Normal display mode"),
+                cmd(0x29, &[], "Display on"),
+            ]);
+            commands
+        }
+    }
+}
+
+/// Init sequence as a C table: command, argument count (bit 7: a delay in ms follows the arguments), arguments
+fn init_table(config: &DisplayConfig) -> String {
+    let mut table = String::from("#define DISPLAY_INIT_DELAY 0x80\n\nstatic const uint8_t display_init_cmds[] = {\n");
+    for command in init_sequence(config) {
+        let mut bytes = vec![format!("0x{:02X}", command.cmd)];
+        let argc = command.args.len() as u8;
+        if command.delay_ms > 0 {
+            bytes.push(format!("{} | DISPLAY_INIT_DELAY", argc));
+        } else {
+            bytes.push(argc.to_string());
+        }
+        bytes.extend(command.args.iter().map(|b| format!("0x{:02X}", b)));
+        if command.delay_ms > 0 {
+            bytes.push(command.delay_ms.to_string());
+        }
+        let _ = writeln!(table, "    {},  // {}", bytes.join(", "), command.comment);
+    }
+    table.push_str("};\n");
+    table
+}
+
+/// Generate a display driver using the generated I2C1/SPI1 driver as transport
+pub fn generate_display_driver(config: &DisplayConfig, mcu: McuFamily) -> DriverOutput {
+    let controller = config.controller;
+    let width = config.width;
+    let height = config.height;
+    let mono = controller.is_monochrome();
+    let (color_note, extra_api) = if mono {
+        (
+            "0 = off, anything else = on",
+            "// Drawing goes to a RAM framebuffer; send it to the panel\nvoid display_update(void);\n",
+        )
+    } else {
+        (
+            "RGB565",
+            "// Text colors for display_write_char\nvoid display_set_text_color(uint16_t fg, uint16_t bg);\n\n// Drawing goes straight to the panel; kept for API compatibility with the OLED drivers\nvoid display_update(void);\n",
+        )
+    };
+
+    let header = format!(r#"/**
+ * {controller:?} Display Driver
+ * Auto-generated by NeuroBench
+ * Interface: {interface:?}, {width}x{height}
+ */
+
+#ifndef DISPLAY_DRIVER_H
+#define DISPLAY_DRIVER_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#define DISPLAY_WIDTH  {width}
+#define DISPLAY_HEIGHT {height}
+
+// Colors: {color_note}
+#define DISPLAY_BLACK  0x0000
+#define DISPLAY_WHITE  0xFFFF
+
+bool display_init(void);
+void display_clear(void);
+
+// Text uses a 5x7 font in 6x8 cells; '\n' moves to the next line
+void display_set_cursor(uint16_t x, uint16_t y);
+void display_write_char(char c);
+void display_write_string(const char *text);
+
+void display_fill_rect(uint16_t x, uint16_t y, uint16_t w, uint16_t h, uint16_t color);
+
+// 1 bit per pixel, rows of (w + 7) / 8 bytes, MSB is the leftmost pixel
+void display_draw_bitmap(uint16_t x, uint16_t y, uint16_t w, uint16_t h, const uint8_t *bitmap);
+
+{extra_api}
+#endif // DISPLAY_DRIVER_H
+"#,
+        interface = config.interface,
+    );
+
+    let hal_header = gpio_include(mcu);
+    let drawing = if mono { mono_source(config) } else { color_source() };
+    let source = format!(r#"/**
+ * {controller:?} Display Driver
+ * Auto-generated by NeuroBench
+ */
+
+#include "display_driver.h"
+{hal_header}
+#include <string.h>
+{transport}
+{init_table}{FONT_5X7}{drawing}"#,
+        transport = transport_source(config, mcu),
+        init_table = init_table(config),
+    );
+
+    let example = format!(r#"/**
+ * Display Example
+ * Prints a counter under a title bar
+ */
+
+#include "display_driver.h"
+#include "main.h"
+#include <stdio.h>
+
+int main(void) {{
+    HAL_Init();
+    SystemClock_Config();
+
+    {bus}_Init();
+    if (!display_init()) {{
+        Error_Handler();
+    }}
+
+    display_clear();
+    display_fill_rect(0, 0, DISPLAY_WIDTH, 10, DISPLAY_WHITE);
+    display_fill_rect(1, 1, DISPLAY_WIDTH - 2, 8, DISPLAY_BLACK);
+    display_set_cursor(2, 1);
+    display_write_string("NeuroBench");
+
+    char line[24];
+    for (uint32_t count = 0;; count++) {{
+        snprintf(line, sizeof(line), "Count: %lu", (unsigned long)count);
+        display_set_cursor(0, 16);
+        display_write_string(line);
+        display_update();
+        HAL_Delay(500);
+    }}
+}}
+"#, bus = match config.interface {
+        DisplayInterface::I2c => "I2C1",
+        DisplayInterface::Spi => "SPI1",
+    });
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: Some(example),
+        peripheral_type: match config.interface {
+            DisplayInterface::I2c => PeripheralType::I2C,
+            DisplayInterface::Spi => PeripheralType::SPI,
+        },
+    }
+}
+
+fn gpio_names(p: PinRef) -> (String, String) {
+    (format!("GPIO{}", (b'A' + p.port) as char), format!("GPIO_PIN_{}", p.pin))
+}
+
+/// Command/data writes on top of the generated bus driver, plus control pin setup and reset
+fn transport_source(config: &DisplayConfig, mcu: McuFamily) -> String {
+    let pins = config.control_pins(mcu);
+    let pin = |name: &str| pins.iter().find(|(n, _)| *n == name).map(|(_, p)| gpio_names(*p));
+
+    let mut source = match config.interface {
+        DisplayInterface::I2c => r#"#include "i2c1_driver.h"
+
+#ifndef DISPLAY_I2C_ADDR
+#define DISPLAY_I2C_ADDR 0x3C  // 0x3D with the address jumper set
+#endif
+
+// Transport: generated I2C1 driver; the control byte selects command (0x00) or data (0x40)
+static void display_write_command(uint8_t cmd, const uint8_t *args, uint8_t argc) {
+    uint8_t buf[16];
+    buf[0] = cmd;
+    memcpy(&buf[1], args, argc);
+    I2C1_Write(DISPLAY_I2C_ADDR, 0x00, buf, 1 + argc);
+}
+
+static void display_write_data(const uint8_t *data, uint16_t len) {
+    I2C1_Write(DISPLAY_I2C_ADDR, 0x40, data, len);
+}
+"#.to_string(),
+        DisplayInterface::Spi => {
+            let (dc_port, dc_bit) = pin("DC").unwrap_or_default();
+            let select = match pin("CS") {
+                Some((port, bit)) => format!("    HAL_GPIO_WritePin({port}, {bit}, active ? GPIO_PIN_RESET : GPIO_PIN_SET);"),
+                None => "    if (active) {\n        SPI1_CS_Low();\n    } else {\n        SPI1_CS_High();\n    }".to_string(),
+            };
+            // OLED command parameters are commands too; TFT parameters are sent as data
+            let args_dc = if config.controller.is_monochrome() { "" } else { "        DISPLAY_DC_DATA();\n" };
+            format!(r#"#include "spi1_driver.h"
+
+#define DISPLAY_DC_COMMAND() HAL_GPIO_WritePin({dc_port}, {dc_bit}, GPIO_PIN_RESET)
+#define DISPLAY_DC_DATA()    HAL_GPIO_WritePin({dc_port}, {dc_bit}, GPIO_PIN_SET)
+
+// Transport: generated SPI1 driver, DC low for commands
+static void display_select(bool active) {{
+{select}
+}}
+
+static void display_write_command(uint8_t cmd, const uint8_t *args, uint8_t argc) {{
+    display_select(true);
+    DISPLAY_DC_COMMAND();
+    SPI1_Transmit(&cmd, 1);
+    if (argc > 0) {{
+{args_dc}        SPI1_Transmit(args, argc);
+    }}
+    display_select(false);
+}}
+
+static void display_write_data(const uint8_t *data, uint16_t len) {{
+    display_select(true);
+    DISPLAY_DC_DATA();
+    SPI1_Transmit(data, len);
+    display_select(false);
+}}
+"#)
+        }
+    };
+
+    if pins.is_empty() {
+        source.push_str("\nstatic void display_pins_init(void) {\n}\n");
+        return source;
+    }
+
+    let mut ports: Vec<u8> = pins.iter().map(|(_, p)| p.port).collect();
+    ports.sort_unstable();
+    ports.dedup();
+    source.push_str("\nstatic void display_pins_init(void) {\n    GPIO_InitTypeDef gpio = {0};\n\n");
+    for port in ports {
+        let _ = writeln!(source, "    __HAL_RCC_GPIO{}_CLK_ENABLE();", (b'A' + port) as char);
+    }
+    source.push_str("\n    gpio.Mode = GPIO_MODE_OUTPUT_PP;\n    gpio.Pull = GPIO_NOPULL;\n    gpio.Speed = GPIO_SPEED_FREQ_HIGH;\n");
+    for (name, p) in &pins {
+        let (port, bit) = gpio_names(*p);
+        // CS idles deselected, RST released
+        let idle = if *name == "DC" { "GPIO_PIN_RESET" } else { "GPIO_PIN_SET" };
+        let _ = write!(source, "\n    // {name}\n    HAL_GPIO_WritePin({port}, {bit}, {idle});\n    gpio.Pin = {bit};\n    HAL_GPIO_Init({port}, &gpio);\n");
+    }
+    if let Some((port, bit)) = pin("RST") {
+        let _ = write!(source, "\n    // Hardware reset\n    HAL_GPIO_WritePin({port}, {bit}, GPIO_PIN_RESET);\n    HAL_Delay(10);\n    HAL_GPIO_WritePin({port}, {bit}, GPIO_PIN_SET);\n    HAL_Delay(120);\n");
+    }
+    source.push_str("}\n");
+    source
+}
+
+/// Framebuffer drawing for the 1 bpp OLED controllers
+fn mono_source(config: &DisplayConfig) -> String {
+    let update = match config.controller {
+        DisplayController::Sh1106 => r#"void display_update(void) {
+    // Page addressing only; the 132-column RAM starts 2 columns left of the glass
+    for (uint8_t page = 0; page < DISPLAY_HEIGHT / 8; page++) {
+        display_write_command(0xB0 | page, NULL, 0);
+        display_write_command(0x02, NULL, 0);
+        display_write_command(0x10, NULL, 0);
+        display_write_data(&framebuffer[page * DISPLAY_WIDTH], DISPLAY_WIDTH);
+    }
+}"#,
+        _ => r#"void display_update(void) {
+    static const uint8_t columns[] = {0, DISPLAY_WIDTH - 1};
+    static const uint8_t pages[] = {0, DISPLAY_HEIGHT / 8 - 1};
+    display_write_command(0x21, columns, 2);
+    display_write_command(0x22, pages, 2);
+    display_write_data(framebuffer, sizeof(framebuffer));
+}"#,
+    };
+
+    format!(r#"
+static uint8_t framebuffer[DISPLAY_WIDTH * DISPLAY_HEIGHT / 8];  // One byte = 8 vertical pixels of a page
+static uint16_t cursor_x = 0;
+static uint16_t cursor_y = 0;
+
+static void display_set_pixel(uint16_t x, uint16_t y, bool on) {{
+    if (x >= DISPLAY_WIDTH || y >= DISPLAY_HEIGHT) {{
+        return;
+    }}
+    uint8_t *byte = &framebuffer[(y / 8) * DISPLAY_WIDTH + x];
+    if (on) {{
+        *byte |= (uint8_t)(1U << (y & 7));
+    }} else {{
+        *byte &= (uint8_t)~(1U << (y & 7));
+    }}
+}}
+
+bool display_init(void) {{
+    display_pins_init();
+
+    const uint8_t *p = display_init_cmds;
+    const uint8_t *end = display_init_cmds + sizeof(display_init_cmds);
+    while (p < end) {{
+        uint8_t cmd = *p++;
+        uint8_t argc = *p++;
+        display_write_command(cmd, p, argc & 0x7F);
+        p += argc & 0x7F;
+        if (argc & DISPLAY_INIT_DELAY) {{
+            HAL_Delay(*p++);
+        }}
+    }}
+
+    display_clear();
+    display_update();
+    return true;
+}}
+
+void display_clear(void) {{
+    memset(framebuffer, 0, sizeof(framebuffer));
+    cursor_x = 0;
+    cursor_y = 0;
+}}
+
+void display_set_cursor(uint16_t x, uint16_t y) {{
+    cursor_x = x;
+    cursor_y = y;
+}}
+
+void display_write_char(char c) {{
+    if (c == '\n') {{
+        cursor_x = 0;
+        cursor_y += 8;
+        return;
+    }}
+    if (c < 0x20 || c > 0x7E) {{
+        c = '?';
+    }}
+    if (cursor_x + 6 > DISPLAY_WIDTH) {{
+        cursor_x = 0;
+        cursor_y += 8;
+    }}
+
+    const uint8_t *glyph = &font_5x7[(c - 0x20) * 5];
+    for (uint8_t col = 0; col < 6; col++) {{
+        uint8_t bits = col < 5 ? glyph[col] : 0x00;
+        for (uint8_t row = 0; row < 8; row++) {{
+            display_set_pixel(cursor_x + col, cursor_y + row, (bits >> row) & 1U);
+        }}
+    }}
+    cursor_x += 6;
+}}
+
+void display_write_string(const char *text) {{
+    while (*text) {{
+        display_write_char(*text++);
+    }}
+}}
+
+void display_fill_rect(uint16_t x, uint16_t y, uint16_t w, uint16_t h, uint16_t color) {{
+    for (uint16_t row = y; row < y + h && row < DISPLAY_HEIGHT; row++) {{
+        for (uint16_t col = x; col < x + w && col < DISPLAY_WIDTH; col++) {{
+            display_set_pixel(col, row, color != 0);
+        }}
+    }}
+}}
+
+void display_draw_bitmap(uint16_t x, uint16_t y, uint16_t w, uint16_t h, const uint8_t *bitmap) {{
+    uint16_t stride = (w + 7) / 8;
+    for (uint16_t row = 0; row < h; row++) {{
+        for (uint16_t col = 0; col < w; col++) {{
+            bool on = bitmap[row * stride + col / 8] & (0x80U >> (col & 7));
+            display_set_pixel(x + col, y + row, on);
+        }}
+    }}
+}}
+
+{update}
+"#)
+}
+
+/// Direct-to-panel RGB565 drawing for the TFT controllers
+fn color_source() -> String {
+    r#"
+#define DISPLAY_CHUNK_PIXELS 32  // Pixels buffered per SPI transfer
+
+static uint16_t cursor_x = 0;
+static uint16_t cursor_y = 0;
+static uint16_t text_fg = DISPLAY_WHITE;
+static uint16_t text_bg = DISPLAY_BLACK;
+
+// Column/row address window, then start a memory write
+static void display_set_window(uint16_t x0, uint16_t y0, uint16_t x1, uint16_t y1) {
+    uint8_t columns[] = {x0 >> 8, x0 & 0xFF, x1 >> 8, x1 & 0xFF};
+    uint8_t rows[] = {y0 >> 8, y0 & 0xFF, y1 >> 8, y1 & 0xFF};
+    display_write_command(0x2A, columns, 4);
+    display_write_command(0x2B, rows, 4);
+    display_write_command(0x2C, NULL, 0);
+}
+
+static void display_push_color(uint16_t color, uint32_t count) {
+    uint8_t chunk[DISPLAY_CHUNK_PIXELS * 2];
+    for (uint16_t i = 0; i < DISPLAY_CHUNK_PIXELS; i++) {
+        chunk[2 * i] = color >> 8;
+        chunk[2 * i + 1] = color & 0xFF;
+    }
+    while (count > 0) {
+        uint16_t n = count > DISPLAY_CHUNK_PIXELS ? DISPLAY_CHUNK_PIXELS : (uint16_t)count;
+        display_write_data(chunk, n * 2);
+        count -= n;
+    }
+}
+
+bool display_init(void) {
+    display_pins_init();
+
+    const uint8_t *p = display_init_cmds;
+    const uint8_t *end = display_init_cmds + sizeof(display_init_cmds);
+    while (p < end) {
+        uint8_t cmd = *p++;
+        uint8_t argc = *p++;
+        display_write_command(cmd, p, argc & 0x7F);
+        p += argc & 0x7F;
+        if (argc & DISPLAY_INIT_DELAY) {
+            HAL_Delay(*p++);
+        }
+    }
+
+    display_clear();
+    return true;
+}
+
+void display_clear(void) {
+    display_fill_rect(0, 0, DISPLAY_WIDTH, DISPLAY_HEIGHT, DISPLAY_BLACK);
+    cursor_x = 0;
+    cursor_y = 0;
+}
+
+void display_set_text_color(uint16_t fg, uint16_t bg) {
+    text_fg = fg;
+    text_bg = bg;
+}
+
+void display_set_cursor(uint16_t x, uint16_t y) {
+    cursor_x = x;
+    cursor_y = y;
+}
+
+void display_write_char(char c) {
+    if (c == '\n') {
+        cursor_x = 0;
+        cursor_y += 8;
+        return;
+    }
+    if (c < 0x20 || c > 0x7E) {
+        c = '?';
+    }
+    if (cursor_x + 6 > DISPLAY_WIDTH) {
+        cursor_x = 0;
+        cursor_y += 8;
+    }
+    if (cursor_y + 8 > DISPLAY_HEIGHT) {
+        return;
+    }
+
+    // Whole 6x8 cell in one window, row by row
+    const uint8_t *glyph = &font_5x7[(c - 0x20) * 5];
+    uint8_t pixels[6 * 8 * 2];
+    for (uint8_t row = 0; row < 8; row++) {
+        for (uint8_t col = 0; col < 6; col++) {
+            bool on = col < 5 && ((glyph[col] >> row) & 1U);
+            uint16_t color = on ? text_fg : text_bg;
+            pixels[(row * 6 + col) * 2] = color >> 8;
+            pixels[(row * 6 + col) * 2 + 1] = color & 0xFF;
+        }
+    }
+    display_set_window(cursor_x, cursor_y, cursor_x + 5, cursor_y + 7);
+    display_write_data(pixels, sizeof(pixels));
+    cursor_x += 6;
+}
+
+void display_write_string(const char *text) {
+    while (*text) {
+        display_write_char(*text++);
+    }
+}
+
+void display_fill_rect(uint16_t x, uint16_t y, uint16_t w, uint16_t h, uint16_t color) {
+    if (x >= DISPLAY_WIDTH || y >= DISPLAY_HEIGHT || w == 0 || h == 0) {
+        return;
+    }
+    if (x + w > DISPLAY_WIDTH) {
+        w = DISPLAY_WIDTH - x;
+    }
+    if (y + h > DISPLAY_HEIGHT) {
+        h = DISPLAY_HEIGHT - y;
+    }
+    display_set_window(x, y, x + w - 1, y + h - 1);
+    display_push_color(color, (uint32_t)w * h);
+}
+
+// Set bits in the text foreground color, clear bits in the background color
+void display_draw_bitmap(uint16_t x, uint16_t y, uint16_t w, uint16_t h, const uint8_t *bitmap) {
+    if (x + w > DISPLAY_WIDTH || y + h > DISPLAY_HEIGHT || w == 0 || h == 0) {
+        return;
+    }
+    uint16_t stride = (w + 7) / 8;
+    uint8_t chunk[DISPLAY_CHUNK_PIXELS * 2];
+    uint16_t n = 0;
+
+    display_set_window(x, y, x + w - 1, y + h - 1);
+    for (uint16_t row = 0; row < h; row++) {
+        for (uint16_t col = 0; col < w; col++) {
+            bool on = bitmap[row * stride + col / 8] & (0x80U >> (col & 7));
+            uint16_t color = on ? text_fg : text_bg;
+            chunk[2 * n] = color >> 8;
+            chunk[2 * n + 1] = color & 0xFF;
+            if (++n == DISPLAY_CHUNK_PIXELS) {
+                display_write_data(chunk, sizeof(chunk));
+                n = 0;
+            }
+        }
+    }
+    if (n > 0) {
+        display_write_data(chunk, n * 2);
+    }
+}
+
+void display_update(void) {
+}
+"#.to_string()
+}
+
+/// Classic 5x7 ASCII font, 0x20-0x7E, one byte per column with the LSB at the top
+const FONT_5X7: &str = r#"
+static const uint8_t font_5x7[] = {
+    0x00, 0x00, 0x00, 0x00, 0x00,  // ' '
+    0x00, 0x00, 0x5F, 0x00, 0x00,  // '!'
+    0x00, 0x07, 0x00, 0x07, 0x00,  // '"'
+    0x14, 0x7F, 0x14, 0x7F, 0x14,  // '#'
+    0x24, 0x2A, 0x7F, 0x2A, 0x12,  // '$'
+    0x23, 0x13, 0x08, 0x64, 0x62,  // '%'
+    0x36, 0x49, 0x55, 0x22, 0x50,  // '&'
+    0x00, 0x05, 0x03, 0x00, 0x00,  // '''
+    0x00, 0x1C, 0x22, 0x41, 0x00,  // '('
+    0x00, 0x41, 0x22, 0x1C, 0x00,  // ')'
+    0x08, 0x2A, 0x1C, 0x2A, 0x08,  // '*'
+    0x08, 0x08, 0x3E, 0x08, 0x08,  // '+'
+    0x00, 0x50, 0x30, 0x00, 0x00,  // ','
+    0x08, 0x08, 0x08, 0x08, 0x08,  // '-'
+    0x00, 0x60, 0x60, 0x00, 0x00,  // '.'
+    0x20, 0x10, 0x08, 0x04, 0x02,  // '/'
+    0x3E, 0x51, 0x49, 0x45, 0x3E,  // '0'
+    0x00, 0x42, 0x7F, 0x40, 0x00,  // '1'
+    0x42, 0x61, 0x51, 0x49, 0x46,  // '2'
+    0x21, 0x41, 0x45, 0x4B, 0x31,  // '3'
+    0x18, 0x14, 0x12, 0x7F, 0x10,  // '4'
+    0x27, 0x45, 0x45, 0x45, 0x39,  // '5'
+    0x3C, 0x4A, 0x49, 0x49, 0x30,  // '6'
+    0x01, 0x71, 0x09, 0x05, 0x03,  // '7'
+    0x36, 0x49, 0x49, 0x49, 0x36,  // '8'
+    0x06, 0x49, 0x49, 0x29, 0x1E,  // '9'
+    0x00, 0x36, 0x36, 0x00, 0x00,  // ':'
+    0x00, 0x56, 0x36, 0x00, 0x00,  // ';'
+    0x08, 0x14, 0x22, 0x41, 0x00,  // '<'
+    0x14, 0x14, 0x14, 0x14, 0x14,  // '='
+    0x00, 0x41, 0x22, 0x14, 0x08,  // '>'
+    0x02, 0x01, 0x51, 0x09, 0x06,  // '?'
+    0x32, 0x49, 0x79, 0x41, 0x3E,  // '@'
+    0x7E, 0x11, 0x11, 0x11, 0x7E,  // 'A'
+    0x7F, 0x49, 0x49, 0x49, 0x36,  // 'B'
+    0x3E, 0x41, 0x41, 0x41, 0x22,  // 'C'
+    0x7F, 0x41, 0x41, 0x22, 0x1C,  // 'D'
+    0x7F, 0x49, 0x49, 0x49, 0x41,  // 'E'
+    0x7F, 0x09, 0x09, 0x09, 0x01,  // 'F'
+    0x3E, 0x41, 0x49, 0x49, 0x7A,  // 'G'
+    0x7F, 0x08, 0x08, 0x08, 0x7F,  // 'H'
+    0x00, 0x41, 0x7F, 0x41, 0x00,  // 'I'
+    0x20, 0x40, 0x41, 0x3F, 0x01,  // 'J'
+    0x7F, 0x08, 0x14, 0x22, 0x41,  // 'K'
+    0x7F, 0x40, 0x40, 0x40, 0x40,  // 'L'
+    0x7F, 0x02, 0x0C, 0x02, 0x7F,  // 'M'
+    0x7F, 0x04, 0x08, 0x10, 0x7F,  // 'N'
+    0x3E, 0x41, 0x41, 0x41, 0x3E,  // 'O'
+    0x7F, 0x09, 0x09, 0x09, 0x06,  // 'P'
+    0x3E, 0x41, 0x51, 0x21, 0x5E,  // 'Q'
+    0x7F, 0x09, 0x19, 0x29, 0x46,  // 'R'
+    0x46, 0x49, 0x49, 0x49, 0x31,  // 'S'
+    0x01, 0x01, 0x7F, 0x01, 0x01,  // 'T'
+    0x3F, 0x40, 0x40, 0x40, 0x3F,  // 'U'
+    0x1F, 0x20, 0x40, 0x20, 0x1F,  // 'V'
+    0x3F, 0x40, 0x38, 0x40, 0x3F,  // 'W'
+    0x63, 0x14, 0x08, 0x14, 0x63,  // 'X'
+    0x07, 0x08, 0x70, 0x08, 0x07,  // 'Y'
+    0x61, 0x51, 0x49, 0x45, 0x43,  // 'Z'
+    0x00, 0x7F, 0x41, 0x41, 0x00,  // '['
+    0x02, 0x04, 0x08, 0x10, 0x20,  // backslash
+    0x00, 0x41, 0x41, 0x7F, 0x00,  // ']'
+    0x04, 0x02, 0x01, 0x02, 0x04,  // '^'
+    0x40, 0x40, 0x40, 0x40, 0x40,  // '_'
+    0x00, 0x01, 0x02, 0x04, 0x00,  // '`'
+    0x20, 0x54, 0x54, 0x54, 0x78,  // 'a'
+    0x7F, 0x48, 0x44, 0x44, 0x38,  // 'b'
+    0x38, 0x44, 0x44, 0x44, 0x20,  // 'c'
+    0x38, 0x44, 0x44, 0x48, 0x7F,  // 'd'
+    0x38, 0x54, 0x54, 0x54, 0x18,  // 'e'
+    0x08, 0x7E, 0x09, 0x01, 0x02,  // 'f'
+    0x0C, 0x52, 0x52, 0x52, 0x3E,  // 'g'
+    0x7F, 0x08, 0x04, 0x04, 0x78,  // 'h'
+    0x00, 0x44, 0x7D, 0x40, 0x00,  // 'i'
+    0x20, 0x40, 0x44, 0x3D, 0x00,  // 'j'
+    0x7F, 0x10, 0x28, 0x44, 0x00,  // 'k'
+    0x00, 0x41, 0x7F, 0x40, 0x00,  // 'l'
+    0x7C, 0x04, 0x18, 0x04, 0x78,  // 'm'
+    0x7C, 0x08, 0x04, 0x04, 0x78,  // 'n'
+    0x38, 0x44, 0x44, 0x44, 0x38,  // 'o'
+    0x7C, 0x14, 0x14, 0x14, 0x08,  // 'p'
+    0x08, 0x14, 0x14, 0x18, 0x7C,  // 'q'
+    0x7C, 0x08, 0x04, 0x04, 0x08,  // 'r'
+    0x48, 0x54, 0x54, 0x54, 0x20,  // 's'
+    0x04, 0x3F, 0x44, 0x40, 0x20,  // 't'
+    0x3C, 0x40, 0x40, 0x20, 0x7C,  // 'u'
+    0x1C, 0x20, 0x40, 0x20, 0x1C,  // 'v'
+    0x3C, 0x40, 0x30, 0x40, 0x3C,  // 'w'
+    0x44, 0x28, 0x10, 0x28, 0x44,  // 'x'
+    0x0C, 0x50, 0x50, 0x50, 0x3C,  // 'y'
+    0x44, 0x64, 0x54, 0x4C, 0x44,  // 'z'
+    0x00, 0x08, 0x36, 0x41, 0x00,  // '{'
+    0x00, 0x00, 0x7F, 0x00, 0x00,  // '|'
+    0x00, 0x41, 0x36, 0x08, 0x00,  // '}'
+    0x08, 0x04, 0x08, 0x10, 0x08,  // '~'
+};
+"#;
@@ -0,0 +1,297 @@
+// DMA Ping-Pong Buffer Generator
+// Generates circular DMA double buffering driven by half/full transfer interrupts
+
+use super::mcu::stm32::Stm32Hal;
+use super::mcu::{McuFamily, McuHal};
+use super::templates::*;
+use serde::{Deserialize, Serialize};
+
+/// Element types accepted for the DMA buffer, with their size in bytes
+const ELEMENT_TYPES: &[(&str, usize)] = &[
+    ("uint8_t", 1),
+    ("int8_t", 1),
+    ("uint16_t", 2),
+    ("int16_t", 2),
+    ("uint32_t", 4),
+    ("int32_t", 4),
+    ("float", 4),
+];
+
+/// Ping-pong DMA configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DmaPingPongConfig {
+    pub peripheral: String,
+    pub buffer_size: usize,
+    pub element_type: String,
+    pub callback_on_half: bool,
+}
+
+impl Default for DmaPingPongConfig {
+    fn default() -> Self {
+        Self {
+            peripheral: "ADC1".to_string(),
+            buffer_size: 256,
+            element_type: "uint16_t".to_string(),
+            callback_on_half: true,
+        }
+    }
+}
+
+/// HAL handle and callbacks for the peripheral feeding the DMA stream
+struct DmaSource {
+    handle: String,
+    start: String,
+    stop: String,
+    half_callback: &'static str,
+    full_callback: &'static str,
+    handle_type: &'static str,
+    callback_arg: &'static str,
+    dma_field: &'static str,
+}
+
+impl DmaPingPongConfig {
+    pub fn element_size(&self) -> Option<usize> {
+        ELEMENT_TYPES.iter().find(|(name, _)| *name == self.element_type).map(|(_, size)| *size)
+    }
+
+    pub fn validate(&self, mcu: McuFamily) -> Result<(), String> {
+        // Circular DMA with half/full callbacks is generated against the STM32 HAL only
+        match mcu {
+            McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32H7 | McuFamily::STM32L4 | McuFamily::STM32G4 => {}
+            _ => return Err(format!("Ping-pong DMA buffers use the STM32 HAL; {} is not supported", mcu.display_name())),
+        }
+        if self.buffer_size == 0 {
+            return Err("Buffer size must be non-zero".to_string());
+        }
+        if 2 * self.buffer_size > u16::MAX as usize {
+            return Err(format!("Buffer size {} exceeds the DMA transfer count limit", self.buffer_size));
+        }
+        let size = self.element_size().ok_or_else(|| format!("Unsupported element type: {}", self.element_type))?;
+        let source = self.source()?;
+        if source.handle_type == "UART_HandleTypeDef" && size != 1 {
+            return Err("UART DMA reception requires a byte-sized element type".to_string());
+        }
+        Ok(())
+    }
+
+    fn source(&self) -> Result<DmaSource, String> {
+        let peripheral = self.peripheral.to_uppercase();
+        let number = peripheral.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+        if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("Unsupported DMA peripheral: {}", self.peripheral));
+        }
+        let total = "2 * DMA_PINGPONG_SIZE";
+        let source = match &peripheral[..peripheral.len() - number.len()] {
+            "ADC" => DmaSource {
+                handle: format!("hadc{number}"),
+                start: format!("HAL_ADC_Start_DMA(&hadc{number}, (uint32_t *)dma_pingpong, {total})"),
+                stop: format!("HAL_ADC_Stop_DMA(&hadc{number})"),
+                half_callback: "HAL_ADC_ConvHalfCpltCallback(ADC_HandleTypeDef *hadc)",
+                full_callback: "HAL_ADC_ConvCpltCallback(ADC_HandleTypeDef *hadc)",
+                handle_type: "ADC_HandleTypeDef",
+                callback_arg: "hadc",
+                dma_field: "DMA_Handle",
+            },
+            "USART" | "UART" => DmaSource {
+                handle: format!("huart{number}"),
+                start: format!("HAL_UART_Receive_DMA(&huart{number}, (uint8_t *)dma_pingpong, {total})"),
+                stop: format!("HAL_UART_DMAStop(&huart{number})"),
+                half_callback: "HAL_UART_RxHalfCpltCallback(UART_HandleTypeDef *huart)",
+                full_callback: "HAL_UART_RxCpltCallback(UART_HandleTypeDef *huart)",
+                handle_type: "UART_HandleTypeDef",
+                callback_arg: "huart",
+                dma_field: "hdmarx",
+            },
+            "SPI" => DmaSource {
+                handle: format!("hspi{number}"),
+                start: format!("HAL_SPI_Receive_DMA(&hspi{number}, (uint8_t *)dma_pingpong, {total})"),
+                stop: format!("HAL_SPI_DMAStop(&hspi{number})"),
+                half_callback: "HAL_SPI_RxHalfCpltCallback(SPI_HandleTypeDef *hspi)",
+                full_callback: "HAL_SPI_RxCpltCallback(SPI_HandleTypeDef *hspi)",
+                handle_type: "SPI_HandleTypeDef",
+                callback_arg: "hspi",
+                dma_field: "hdmarx",
+            },
+            _ => return Err(format!("Unsupported DMA peripheral: {}", self.peripheral)),
+        };
+        Ok(source)
+    }
+}
+
+/// Generate ping-pong DMA buffer driver code
+pub fn generate_dma_pingpong(config: &DmaPingPongConfig, mcu: McuFamily) -> DriverOutput {
+    let hal_header = Stm32Hal::new(mcu).include_headers()[0];
+    let source = config.source().unwrap_or_else(|_| DmaPingPongConfig::default().source().unwrap());
+    let DmaSource { handle, start, stop, half_callback, full_callback, handle_type, callback_arg, dma_field } = source;
+    let peripheral = config.peripheral.to_uppercase();
+    let size = config.buffer_size;
+    let elem = &config.element_type;
+
+    let block_len = if config.callback_on_half { "DMA_PINGPONG_SIZE" } else { "2 * DMA_PINGPONG_SIZE" };
+
+    // H7 DMA cannot see DTCM and the buffer lives behind the D-cache
+    let (placement, cache_invalidate) = if mcu == McuFamily::STM32H7 {
+        (
+            "__attribute__((section(\".dma_buffer\"), aligned(32))) ",
+            format!("    SCB_InvalidateDCache_by_Addr((void *)data, (int32_t)(({block_len}) * sizeof(dma_elem_t)));\n"),
+        )
+    } else {
+        ("", String::new())
+    };
+
+    let half_handler = if config.callback_on_half {
+        format!(r#"
+// Half transfer: DMA is now filling buffer 1, buffer 0 is complete
+void {half_callback} {{
+    if ({callback_arg} == &{handle}) {{
+        dma_pingpong_complete(0);
+    }}
+}}
+"#)
+    } else {
+        String::new()
+    };
+
+    let (full_index, full_comment, disable_ht) = if config.callback_on_half {
+        ("1", "Transfer complete: DMA wrapped to buffer 0, buffer 1 is complete", String::new())
+    } else {
+        (
+            "0",
+            "Transfer complete: both halves are filled, delivered as one block",
+            format!("    // Half-transfer events are not used\n    __HAL_DMA_DISABLE_IT({handle}.{dma_field}, DMA_IT_HT);\n"),
+        )
+    };
+    let header = format!(r#"/**
+ * DMA Ping-Pong Buffer Driver
+ * Auto-generated by NeuroBench
+ * Peripheral: {peripheral}, {size} x {elem} per buffer
+ */
+
+#ifndef DMA_PINGPONG_H
+#define DMA_PINGPONG_H
+
+#include <stdint.h>
+#include <stddef.h>
+#include "{hal_header}"
+
+#define DMA_PINGPONG_SIZE {size}
+
+typedef {elem} dma_elem_t;
+
+extern {handle_type} {handle};
+
+HAL_StatusTypeDef dma_pingpong_start(void);
+HAL_StatusTypeDef dma_pingpong_stop(void);
+uint8_t dma_pingpong_active_buffer(void);
+
+// Called from DMA interrupt context with the buffer that just completed
+void process_buffer(const dma_elem_t *data, size_t len);
+
+#endif // DMA_PINGPONG_H
+"#);
+
+    let source_file = format!(r#"/**
+ * DMA Ping-Pong Buffer Driver
+ * Auto-generated by NeuroBench
+ *
+ * A single circular DMA transfer covers both buffers back to back. The
+ * half-transfer interrupt fires when buffer 0 is full and the transfer
+ * complete interrupt when buffer 1 is full, so the CPU always works on
+ * the half the DMA is not writing.
+ */
+
+#include "dma_pingpong.h"
+
+static {placement}dma_elem_t dma_pingpong[2][DMA_PINGPONG_SIZE];
+static volatile uint8_t active_buffer = 0;
+
+__attribute__((weak)) void process_buffer(const dma_elem_t *data, size_t len) {{
+    (void)data;
+    (void)len;
+}}
+
+static void dma_pingpong_complete(uint8_t index) {{
+    const dma_elem_t *data = dma_pingpong[index];
+    active_buffer = index ^ 1;
+{cache_invalidate}    process_buffer(data, {block_len});
+}}
+
+HAL_StatusTypeDef dma_pingpong_start(void) {{
+    active_buffer = 0;
+    // DMA stream must be configured as DMA_CIRCULAR in the MSP init
+    HAL_StatusTypeDef status = {start};
+{disable_ht}    return status;
+}}
+
+HAL_StatusTypeDef dma_pingpong_stop(void) {{
+    return {stop};
+}}
+
+uint8_t dma_pingpong_active_buffer(void) {{
+    return active_buffer;
+}}
+{half_handler}
+// {full_comment}
+void {full_callback} {{
+    if ({callback_arg} == &{handle}) {{
+        dma_pingpong_complete({full_index});
+    }}
+}}
+"#);
+
+    let example = r#"/**
+ * DMA Ping-Pong Example
+ * Double-buffered ADC sampling: averages each completed buffer in the main loop
+ */
+
+#include "dma_pingpong.h"
+#include "main.h"
+#include <stdio.h>
+
+static volatile const dma_elem_t *ready_data = NULL;
+static volatile size_t ready_len = 0;
+static volatile uint32_t dropped = 0;
+
+// Keep the interrupt short: hand the completed buffer to the main loop
+void process_buffer(const dma_elem_t *data, size_t len) {
+    if (ready_data != NULL) {
+        dropped++;  // Main loop did not finish the previous block in time
+    }
+    ready_data = data;
+    ready_len = len;
+}
+
+int main(void) {
+    HAL_Init();
+    SystemClock_Config();
+    MX_GPIO_Init();
+    MX_DMA_Init();      // Circular mode, half-word alignment for 12-bit samples
+    MX_ADC1_Init();     // Triggered by a timer for a fixed sample rate
+    MX_TIM2_Init();
+
+    dma_pingpong_start();
+    HAL_TIM_Base_Start(&htim2);
+
+    while (1) {
+        if (ready_data != NULL) {
+            const dma_elem_t *data = (const dma_elem_t *)ready_data;
+            size_t len = ready_len;
+            ready_data = NULL;
+
+            float sum = 0.0f;
+            for (size_t i = 0; i < len; i++) {
+                sum += data[i];
+            }
+            printf("Block mean: %.1f (dropped: %lu)\n", sum / len, (unsigned long)dropped);
+        }
+    }
+}
+"#.to_string();
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file,
+        example_file: Some(example),
+        peripheral_type: PeripheralType::DMA,
+    }
+}
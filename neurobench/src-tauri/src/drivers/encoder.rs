@@ -0,0 +1,413 @@
+// Encoder Interface Generator
+// Generates quadrature and step/direction position counters with velocity estimation
+
+use super::mcu::McuFamily;
+use super::soft_i2c::{gpio_include, gpio_init_prelude, gpio_line, parse_pin, PinRef};
+use super::templates::*;
+use serde::{Deserialize, Serialize};
+
+/// Encoder counting mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EncoderMode {
+    /// One count per A cycle
+    QuadratureX1,
+    /// Both edges of A
+    QuadratureX2,
+    /// Both edges of A and B
+    QuadratureX4,
+    /// Pulses on A, direction level on B
+    StepDirection,
+}
+
+impl EncoderMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EncoderMode::QuadratureX1 => "Quadrature x1",
+            EncoderMode::QuadratureX2 => "Quadrature x2",
+            EncoderMode::QuadratureX4 => "Quadrature x4",
+            EncoderMode::StepDirection => "Step/direction",
+        }
+    }
+}
+
+/// Encoder configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncoderConfig {
+    pub timer_instance: String,
+    pub mode: EncoderMode,
+    /// Counter wraps at this value; also the counts per revolution for RPM
+    pub count_range: i32,
+    /// Input filter setting (ICxF, 0-15)
+    pub filter_prescaler: u8,
+    pub pin_a: String,
+    pub pin_b: String,
+}
+
+impl Default for EncoderConfig {
+    fn default() -> Self {
+        Self {
+            timer_instance: "TIM3".to_string(),
+            mode: EncoderMode::QuadratureX4,
+            count_range: 4096,
+            filter_prescaler: 6,
+            pin_a: "PA6".to_string(),
+            pin_b: "PA7".to_string(),
+        }
+    }
+}
+
+impl EncoderConfig {
+    pub fn validate(&self, mcu: McuFamily) -> Result<(), String> {
+        let pin_a = parse_pin(mcu, &self.pin_a)
+            .ok_or_else(|| format!("Invalid encoder pin A for {}: {}", mcu.display_name(), self.pin_a))?;
+        let pin_b = parse_pin(mcu, &self.pin_b)
+            .ok_or_else(|| format!("Invalid encoder pin B for {}: {}", mcu.display_name(), self.pin_b))?;
+        if pin_a == pin_b {
+            return Err("Encoder pins A and B must be different".to_string());
+        }
+        if self.filter_prescaler > 15 {
+            return Err(format!("Input filter must be 0-15, got {}", self.filter_prescaler));
+        }
+        if is_stm32(mcu) {
+            let timer = self.timer_instance.to_uppercase();
+            let mut max_range = match timer.as_str() {
+                "TIM2" | "TIM5" => i32::MAX as i64,
+                "TIM1" | "TIM3" | "TIM4" | "TIM8" => 65536,
+                _ => return Err(format!("{} has no encoder interface", self.timer_instance)),
+            };
+            if self.mode == EncoderMode::QuadratureX1 {
+                max_range /= 2;  // Timer counts x2 and the driver divides
+            }
+            if self.count_range < 2 || self.count_range as i64 > max_range {
+                return Err(format!("Count range for {} must be 2-{}, got {}", timer, max_range, self.count_range));
+            }
+        } else if self.count_range < 2 {
+            return Err(format!("Count range must be at least 2, got {}", self.count_range));
+        }
+        Ok(())
+    }
+}
+
+/// Step/direction without a hardware clock+direction encoder mode (all but STM32G4)
+fn uses_dir_exti(mode: EncoderMode, mcu: McuFamily) -> bool {
+    mode == EncoderMode::StepDirection && mcu != McuFamily::STM32G4
+}
+
+pub(super) fn is_stm32(mcu: McuFamily) -> bool {
+    matches!(
+        mcu,
+        McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32H7 | McuFamily::STM32L4 | McuFamily::STM32G4
+    )
+}
+
+/// Generate encoder driver code
+pub fn generate_encoder_driver(config: &EncoderConfig, mcu: McuFamily) -> DriverOutput {
+    let timer = config.timer_instance.to_uppercase();
+    let mode_name = config.mode.name();
+    let count_range = config.count_range;
+    let pin_a = &config.pin_a;
+    let pin_b = &config.pin_b;
+    let isr_prototype = if !is_stm32(mcu) {
+        "\n// Call from the encoder pin edge interrupt(s)\nvoid encoder_edge_isr(void);\n"
+    } else if uses_dir_exti(config.mode, mcu) {
+        "\n// Call from the DIR pin EXTI handler\nvoid encoder_dir_exti_callback(void);\n"
+    } else {
+        ""
+    };
+
+    let header = format!(r#"/**
+ * Encoder Driver - {mode_name}
+ * Auto-generated by NeuroBench
+ * A: {pin_a}, B: {pin_b}, range: {count_range} counts
+ */
+
+#ifndef ENCODER_H
+#define ENCODER_H
+
+#include <stdint.h>
+
+#define ENCODER_COUNT_RANGE {count_range}
+#ifndef ENCODER_COUNTS_PER_REV
+#define ENCODER_COUNTS_PER_REV ENCODER_COUNT_RANGE
+#endif
+
+void encoder_init(void);
+int32_t encoder_get_count(void);    // 0 .. ENCODER_COUNT_RANGE - 1
+void encoder_reset(void);
+
+// Call every sample_period_ms; returns speed since the previous call
+float encoder_get_velocity_rpm(uint32_t sample_period_ms);
+{isr_prototype}
+#endif // ENCODER_H
+"#);
+
+    let backend = if is_stm32(mcu) {
+        stm32_timer_backend(config, mcu, &timer)
+    } else {
+        software_backend(config, mcu)
+    };
+
+    let source = format!(r#"/**
+ * Encoder Driver - {mode_name}
+ * Auto-generated by NeuroBench
+ */
+
+#include "encoder.h"
+
+static int32_t last_count = 0;  // Velocity reference, cleared by encoder_reset()
+{backend}
+float encoder_get_velocity_rpm(uint32_t sample_period_ms) {{
+    int32_t count = encoder_get_count();
+    int32_t delta = count - last_count;
+    last_count = count;
+
+    // Shortest way around the wrap point
+    if (delta > ENCODER_COUNT_RANGE / 2) {{
+        delta -= ENCODER_COUNT_RANGE;
+    }} else if (delta < -(ENCODER_COUNT_RANGE / 2)) {{
+        delta += ENCODER_COUNT_RANGE;
+    }}
+    if (sample_period_ms == 0) {{
+        return 0.0f;
+    }}
+    return ((float)delta * 60000.0f) / ((float)ENCODER_COUNTS_PER_REV * (float)sample_period_ms);
+}}
+"#);
+
+    let example = r#"/**
+ * Encoder Example
+ * Prints position and speed every 100 ms
+ */
+
+#include "encoder.h"
+#include "main.h"
+#include <stdio.h>
+
+int main(void) {
+    HAL_Init();
+    SystemClock_Config();
+
+    encoder_init();
+    encoder_reset();
+
+    while (1) {
+        HAL_Delay(100);
+        float rpm = encoder_get_velocity_rpm(100);
+        printf("count=%ld rpm=%.1f\n", (long)encoder_get_count(), rpm);
+    }
+}
+"#;
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: Some(example.to_string()),
+        peripheral_type: PeripheralType::Timer,
+    }
+}
+
+/// GPIO alternate function of the timer channel pins (not used on STM32F1)
+pub(super) fn timer_af(timer: &str) -> u8 {
+    match timer {
+        "TIM1" | "TIM2" => 1,
+        "TIM3" | "TIM4" | "TIM5" => 2,
+        _ => 3,
+    }
+}
+
+/// Hardware encoder interface on an STM32 general-purpose/advanced timer
+fn stm32_timer_backend(config: &EncoderConfig, mcu: McuFamily, timer: &str) -> String {
+    let timer_lower = timer.to_lowercase();
+    let pin_a = parse_pin(mcu, &config.pin_a).unwrap_or(PinRef { port: 0, pin: 6 });
+    let pin_b = parse_pin(mcu, &config.pin_b).unwrap_or(PinRef { port: 0, pin: 7 });
+    let filter = config.filter_prescaler;
+    let hal_header = gpio_include(mcu);
+
+    let gpio_init = |p: PinRef| {
+        let port_char = (b'A' + p.port) as char;
+        if mcu == McuFamily::STM32F1 {
+            format!(
+                "    __HAL_RCC_GPIO{port_char}_CLK_ENABLE();\n    gpio.Pin = GPIO_PIN_{pin};\n    gpio.Mode = GPIO_MODE_INPUT;\n    gpio.Pull = GPIO_PULLUP;\n    HAL_GPIO_Init(GPIO{port_char}, &gpio);\n",
+                pin = p.pin,
+            )
+        } else {
+            format!(
+                "    __HAL_RCC_GPIO{port_char}_CLK_ENABLE();\n    gpio.Pin = GPIO_PIN_{pin};\n    gpio.Mode = GPIO_MODE_AF_PP;\n    gpio.Pull = GPIO_PULLUP;\n    gpio.Speed = GPIO_SPEED_FREQ_LOW;\n    gpio.Alternate = GPIO_AF{af}_{timer};\n    HAL_GPIO_Init(GPIO{port_char}, &gpio);\n",
+                pin = p.pin,
+                af = timer_af(timer),
+            )
+        }
+    };
+    // In step/direction via EXTI, pin B is a plain GPIO input set up below
+    let pins_init = if uses_dir_exti(config.mode, mcu) {
+        gpio_init(pin_a)
+    } else {
+        format!("{}{}", gpio_init(pin_a), gpio_init(pin_b))
+    };
+
+    // X1 runs the timer in x2 on TI1 and divides in software
+    let (count_div, slave_init) = match config.mode {
+        EncoderMode::QuadratureX4 => (1, encoder_init_block(&timer_lower, "TIM_ENCODERMODE_TI12", filter)),
+        EncoderMode::QuadratureX2 => (1, encoder_init_block(&timer_lower, "TIM_ENCODERMODE_TI1", filter)),
+        EncoderMode::QuadratureX1 => (2, encoder_init_block(&timer_lower, "TIM_ENCODERMODE_TI1", filter)),
+        EncoderMode::StepDirection if mcu == McuFamily::STM32G4 => {
+            (1, encoder_init_block(&timer_lower, "TIM_ENCODERMODE_CLOCKPLUSDIRECTION_X1", filter))
+        }
+        EncoderMode::StepDirection => (1, step_dir_init_block(timer, &timer_lower, pin_b, filter)),
+    };
+    let step_dir_isr = if uses_dir_exti(config.mode, mcu) {
+        let port_char = (b'A' + pin_b.port) as char;
+        format!(r#"
+// Direction pin edge: counting direction follows the DIR level
+void encoder_dir_exti_callback(void) {{
+    if (HAL_GPIO_ReadPin(GPIO{port_char}, GPIO_PIN_{pin})) {{
+        {timer}->CR1 |= TIM_CR1_DIR;
+    }} else {{
+        {timer}->CR1 &= ~TIM_CR1_DIR;
+    }}
+}}
+"#, pin = pin_b.pin)
+    } else {
+        String::new()
+    };
+    let period = config.count_range as i64 * count_div - 1;
+
+    format!(r#"{hal_header}
+
+#define ENCODER_COUNT_DIV {count_div}  // Hardware counts per reported count
+
+TIM_HandleTypeDef h{timer_lower};
+
+void encoder_init(void) {{
+    GPIO_InitTypeDef gpio = {{0}};
+
+    __HAL_RCC_{timer}_CLK_ENABLE();
+{pins_init}
+    h{timer_lower}.Instance = {timer};
+    h{timer_lower}.Init.Prescaler = 0;
+    h{timer_lower}.Init.CounterMode = TIM_COUNTERMODE_UP;
+    h{timer_lower}.Init.Period = {period};  // ENCODER_COUNT_RANGE * ENCODER_COUNT_DIV - 1
+    h{timer_lower}.Init.ClockDivision = TIM_CLOCKDIVISION_DIV1;
+    h{timer_lower}.Init.AutoReloadPreload = TIM_AUTORELOAD_PRELOAD_DISABLE;
+{slave_init}}}
+{step_dir_isr}
+int32_t encoder_get_count(void) {{
+    return (int32_t)(__HAL_TIM_GET_COUNTER(&h{timer_lower}) / ENCODER_COUNT_DIV);
+}}
+
+void encoder_reset(void) {{
+    __HAL_TIM_SET_COUNTER(&h{timer_lower}, 0);
+    last_count = 0;
+}}
+"#)
+}
+
+/// `TIM_Encoder_InitTypeDef` setup for the given encoder mode
+fn encoder_init_block(timer_lower: &str, mode: &str, filter: u8) -> String {
+    format!(r#"
+    TIM_Encoder_InitTypeDef encoder = {{0}};
+    encoder.EncoderMode = {mode};
+    encoder.IC1Polarity = TIM_ICPOLARITY_RISING;
+    encoder.IC1Selection = TIM_ICSELECTION_DIRECTTI;
+    encoder.IC1Prescaler = TIM_ICPSC_DIV1;
+    encoder.IC1Filter = {filter};
+    encoder.IC2Polarity = TIM_ICPOLARITY_RISING;
+    encoder.IC2Selection = TIM_ICSELECTION_DIRECTTI;
+    encoder.IC2Prescaler = TIM_ICPSC_DIV1;
+    encoder.IC2Filter = {filter};
+    HAL_TIM_Encoder_Init(&h{timer_lower}, &encoder);
+    HAL_TIM_Encoder_Start(&h{timer_lower}, TIM_CHANNEL_ALL);
+"#)
+}
+
+/// Step on TI1 as external clock, direction from an EXTI on pin B
+fn step_dir_init_block(timer: &str, timer_lower: &str, dir_pin: PinRef, filter: u8) -> String {
+    let port_char = (b'A' + dir_pin.port) as char;
+    let pin = dir_pin.pin;
+    format!(r#"
+    HAL_TIM_Base_Init(&h{timer_lower});
+
+    TIM_SlaveConfigTypeDef slave = {{0}};
+    slave.SlaveMode = TIM_SLAVEMODE_EXTERNAL1;
+    slave.InputTrigger = TIM_TS_TI1FP1;
+    slave.TriggerPolarity = TIM_TRIGGERPOLARITY_RISING;
+    slave.TriggerFilter = {filter};
+    HAL_TIM_SlaveConfigSynchro(&h{timer_lower}, &slave);
+    HAL_TIM_Base_Start(&h{timer_lower});
+
+    // DIR input: any edge updates {timer}->CR1.DIR (call encoder_dir_exti_callback from the EXTI handler)
+    gpio.Pin = GPIO_PIN_{pin};
+    gpio.Mode = GPIO_MODE_IT_RISING_FALLING;
+    gpio.Pull = GPIO_PULLUP;
+    HAL_GPIO_Init(GPIO{port_char}, &gpio);
+    encoder_dir_exti_callback();
+"#)
+}
+
+/// Edge-interrupt decoder for families without a timer encoder interface
+fn software_backend(config: &EncoderConfig, mcu: McuFamily) -> String {
+    let pin_a = parse_pin(mcu, &config.pin_a).unwrap_or(PinRef { port: 0, pin: 0 });
+    let pin_b = parse_pin(mcu, &config.pin_b).unwrap_or(PinRef { port: 0, pin: 1 });
+    let (a_macros, a_init) = gpio_line(mcu, "ENCODER_A", pin_a, true);
+    let (b_macros, b_init) = gpio_line(mcu, "ENCODER_B", pin_b, true);
+    let include = gpio_include(mcu);
+    let prelude = gpio_init_prelude(mcu, true);
+
+    let edge_handler = match config.mode {
+        EncoderMode::QuadratureX4 => r#"// Call on every edge of A and B
+void encoder_edge_isr(void) {
+    // Gray code transition table indexed by (previous AB << 2) | AB
+    static const int8_t steps[16] = {0, 1, -1, 0, -1, 0, 0, 1, 1, 0, 0, -1, 0, -1, 1, 0};
+    static uint8_t prev = 0;
+    uint8_t state = (uint8_t)((ENCODER_A_READ() << 1) | ENCODER_B_READ());
+    encoder_add(steps[(prev << 2) | state]);
+    prev = state;
+}
+"#,
+        EncoderMode::QuadratureX2 => r#"// Call on both edges of A
+void encoder_edge_isr(void) {
+    encoder_add(ENCODER_A_READ() == ENCODER_B_READ() ? -1 : 1);
+}
+"#,
+        EncoderMode::QuadratureX1 => r#"// Call on the rising edge of A
+void encoder_edge_isr(void) {
+    encoder_add(ENCODER_B_READ() ? -1 : 1);
+}
+"#,
+        EncoderMode::StepDirection => r#"// Call on the rising edge of the step input (A)
+void encoder_edge_isr(void) {
+    encoder_add(ENCODER_B_READ() ? 1 : -1);
+}
+"#,
+    };
+
+    format!(r#"{include}
+
+// Pins are only read; the LOW/RELEASE macros are unused
+{a_macros}{b_macros}
+static volatile int32_t encoder_count = 0;
+
+static void encoder_add(int32_t step) {{
+    int32_t count = encoder_count + step;
+    if (count >= ENCODER_COUNT_RANGE) {{
+        count -= ENCODER_COUNT_RANGE;
+    }} else if (count < 0) {{
+        count += ENCODER_COUNT_RANGE;
+    }}
+    encoder_count = count;
+}}
+
+void encoder_init(void) {{
+{prelude}{a_init}{b_init}}}
+
+{edge_handler}
+int32_t encoder_get_count(void) {{
+    return encoder_count;
+}}
+
+void encoder_reset(void) {{
+    encoder_count = 0;
+    last_count = 0;
+}}
+"#)
+}
@@ -0,0 +1,441 @@
+// Protocol Framer Generator
+// Generates packet framing (COBS, SLIP, PPP/HDLC) with stateful byte-by-byte decoders
+
+use super::templates::*;
+use serde::{Deserialize, Serialize};
+
+/// Framing scheme for delimiting packets on a byte stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrameProtocol {
+    Cobs,
+    Slip,
+    PppHdlc,
+    CustomDelimiter(u8),
+}
+
+impl FrameProtocol {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FrameProtocol::Cobs => "COBS",
+            FrameProtocol::Slip => "SLIP (RFC 1055)",
+            FrameProtocol::PppHdlc => "PPP HDLC-like (RFC 1662)",
+            FrameProtocol::CustomDelimiter(_) => "Custom delimiter",
+        }
+    }
+
+    /// Worst-case encoded size of a `len` byte payload, including delimiters
+    pub fn max_encoded_size(&self, len: usize) -> usize {
+        match self {
+            // One code byte per 254 data bytes (at least one), plus the 0x00 delimiter
+            FrameProtocol::Cobs => len + len / 254 + 1 + 1,
+            // Every byte escaped, leading and trailing END
+            FrameProtocol::Slip => 2 * len + 2,
+            // Every byte and both FCS bytes escaped, opening and closing flag
+            FrameProtocol::PppHdlc => 2 * (len + 2) + 2,
+            FrameProtocol::CustomDelimiter(_) => 2 * len + 1,
+        }
+    }
+}
+
+/// Escape byte for custom delimiter framing, chosen so neither it nor an
+/// escaped byte (`byte ^ 0x20`) can collide with the delimiter
+fn custom_escape(delimiter: u8) -> u8 {
+    if delimiter == 0x1B || delimiter == 0x3B { 0x7D } else { 0x1B }
+}
+
+/// Generate framer encode/decode code
+pub fn generate_framer(protocol: FrameProtocol, max_frame_size: usize) -> DriverOutput {
+    let max_encoded = protocol.max_encoded_size(max_frame_size);
+    let name = protocol.name();
+
+    let (defines, decoder_state, codec) = match protocol {
+        FrameProtocol::Cobs => (
+            "#define FRAME_DELIMITER 0x00\n".to_string(),
+            "    uint8_t remaining;      // Data bytes left in the current COBS block\n    uint8_t pending_zero;   // Block ended without 0xFF: a zero follows\n",
+            COBS_CODEC.to_string(),
+        ),
+        FrameProtocol::Slip => (
+            "#define SLIP_END     0xC0\n#define SLIP_ESC     0xDB\n#define SLIP_ESC_END 0xDC\n#define SLIP_ESC_ESC 0xDD\n".to_string(),
+            "    uint8_t escaped;\n",
+            SLIP_CODEC.to_string(),
+        ),
+        FrameProtocol::PppHdlc => (
+            "#define PPP_FLAG     0x7E\n#define PPP_ESC      0x7D\n#define PPP_XOR      0x20\n#define PPP_FCS_INIT 0xFFFF\n#define PPP_FCS_GOOD 0xF0B8\n".to_string(),
+            "    uint8_t escaped;\n",
+            PPP_CODEC.to_string(),
+        ),
+        FrameProtocol::CustomDelimiter(delimiter) => (
+            format!(
+                "#define FRAME_DELIMITER 0x{:02X}\n#define FRAME_ESC       0x{:02X}\n#define FRAME_XOR       0x20\n",
+                delimiter,
+                custom_escape(delimiter)
+            ),
+            "    uint8_t escaped;\n",
+            CUSTOM_CODEC.to_string(),
+        ),
+    };
+
+    let header = format!(r#"/**
+ * Protocol Framer - {name}
+ * Auto-generated by NeuroBench
+ */
+
+#ifndef FRAMER_H
+#define FRAMER_H
+
+#include <stdint.h>
+#include <stddef.h>
+
+#define FRAME_MAX_SIZE    {max_frame_size}
+#define FRAME_MAX_ENCODED {max_encoded}  // Worst case including delimiters
+
+{defines}
+#define FRAME_ERR_OVERFLOW  (-1)
+#define FRAME_ERR_ENCODING  (-2)
+#define FRAME_ERR_CHECKSUM  (-3)
+
+typedef struct {{
+    uint8_t buf[FRAME_MAX_SIZE];
+    size_t len;
+    uint8_t discard;        // Drop bytes until the next delimiter
+{decoder_state}}} frame_decoder_t;
+
+// Encode len bytes into out (FRAME_MAX_ENCODED bytes), returns encoded length or 0 if too long
+size_t frame_encode(const uint8_t *in, size_t len, uint8_t *out);
+
+void frame_decoder_init(frame_decoder_t *d);
+
+// Feed one received byte. Returns the frame length when d->buf holds a
+// complete frame, 0 while a frame is in progress, or FRAME_ERR_* on error.
+// Safe to call from a UART RX interrupt.
+int frame_decoder_feed(frame_decoder_t *d, uint8_t byte);
+
+#endif // FRAMER_H
+"#);
+
+    let source = format!(r#"/**
+ * Protocol Framer - {name}
+ * Auto-generated by NeuroBench
+ */
+
+#include "framer.h"
+#include <string.h>
+
+void frame_decoder_init(frame_decoder_t *d) {{
+    memset(d, 0, sizeof(*d));
+}}
+
+static int frame_append(frame_decoder_t *d, uint8_t byte) {{
+    if (d->len >= FRAME_MAX_SIZE) {{
+        d->discard = 1;
+        return FRAME_ERR_OVERFLOW;
+    }}
+    d->buf[d->len++] = byte;
+    return 0;
+}}
+{codec}"#);
+
+    let example = format!(r#"/**
+ * Protocol Framer Example - {name}
+ * Decodes frames in the UART RX interrupt and echoes them back encoded
+ */
+
+#include "framer.h"
+#include "main.h"
+#include <string.h>
+
+extern UART_HandleTypeDef huart2;
+
+static frame_decoder_t rx_decoder;
+static uint8_t rx_byte;
+static uint8_t rx_frame[FRAME_MAX_SIZE];
+static volatile int rx_frame_len = 0;
+
+void HAL_UART_RxCpltCallback(UART_HandleTypeDef *huart) {{
+    if (huart == &huart2) {{
+        int result = frame_decoder_feed(&rx_decoder, rx_byte);
+        if (result > 0 && rx_frame_len == 0) {{
+            memcpy(rx_frame, rx_decoder.buf, (size_t)result);
+            rx_frame_len = result;
+        }}
+        HAL_UART_Receive_IT(&huart2, &rx_byte, 1);
+    }}
+}}
+
+int main(void) {{
+    HAL_Init();
+    SystemClock_Config();
+    MX_USART2_UART_Init();
+
+    frame_decoder_init(&rx_decoder);
+    HAL_UART_Receive_IT(&huart2, &rx_byte, 1);
+
+    static uint8_t tx_buf[FRAME_MAX_ENCODED];
+    while (1) {{
+        if (rx_frame_len > 0) {{
+            size_t n = frame_encode(rx_frame, (size_t)rx_frame_len, tx_buf);
+            rx_frame_len = 0;
+            HAL_UART_Transmit(&huart2, tx_buf, (uint16_t)n, HAL_MAX_DELAY);
+        }}
+    }}
+}}
+"#);
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: Some(example),
+        peripheral_type: PeripheralType::UART,
+    }
+}
+
+const COBS_CODEC: &str = r#"
+/*
+ * Consistent Overhead Byte Stuffing: each block starts with a code byte
+ * giving the distance to the next zero (0xFF = 254 data bytes, no zero).
+ * Overhead is one byte per 254 data bytes, at least one.
+ */
+size_t frame_encode(const uint8_t *in, size_t len, uint8_t *out) {
+    if (len > FRAME_MAX_SIZE) {
+        return 0;
+    }
+    size_t code_idx = 0;
+    size_t o = 1;
+    uint8_t code = 1;
+
+    for (size_t i = 0; i < len; i++) {
+        if (in[i] == 0x00) {
+            out[code_idx] = code;
+            code_idx = o++;
+            code = 1;
+        } else {
+            out[o++] = in[i];
+            if (++code == 0xFF) {
+                out[code_idx] = code;
+                code_idx = o++;
+                code = 1;
+            }
+        }
+    }
+    out[code_idx] = code;
+    out[o++] = FRAME_DELIMITER;
+    return o;
+}
+
+int frame_decoder_feed(frame_decoder_t *d, uint8_t byte) {
+    if (byte == FRAME_DELIMITER) {
+        int result = (int)d->len;
+        if (d->discard) {
+            result = 0;
+        } else if (d->remaining != 0) {
+            result = FRAME_ERR_ENCODING;  // Block cut short by delimiter
+        }
+        // The zero implied by the last block is the delimiter itself
+        d->len = 0;
+        d->remaining = 0;
+        d->pending_zero = 0;
+        d->discard = 0;
+        return result;
+    }
+    if (d->discard) {
+        return 0;
+    }
+
+    if (d->remaining == 0) {
+        if (d->pending_zero && frame_append(d, 0x00) < 0) {
+            return FRAME_ERR_OVERFLOW;
+        }
+        d->remaining = (uint8_t)(byte - 1);
+        d->pending_zero = (byte != 0xFF);
+        return 0;
+    }
+    d->remaining--;
+    return frame_append(d, byte);
+}
+"#;
+
+const SLIP_CODEC: &str = r#"
+/*
+ * SLIP (RFC 1055): END terminates a frame, END and ESC in the payload
+ * are sent as ESC ESC_END and ESC ESC_ESC. A leading END flushes any
+ * line noise received before the frame.
+ */
+size_t frame_encode(const uint8_t *in, size_t len, uint8_t *out) {
+    if (len > FRAME_MAX_SIZE) {
+        return 0;
+    }
+    size_t o = 0;
+    out[o++] = SLIP_END;
+    for (size_t i = 0; i < len; i++) {
+        switch (in[i]) {
+            case SLIP_END:
+                out[o++] = SLIP_ESC;
+                out[o++] = SLIP_ESC_END;
+                break;
+            case SLIP_ESC:
+                out[o++] = SLIP_ESC;
+                out[o++] = SLIP_ESC_ESC;
+                break;
+            default:
+                out[o++] = in[i];
+                break;
+        }
+    }
+    out[o++] = SLIP_END;
+    return o;
+}
+
+int frame_decoder_feed(frame_decoder_t *d, uint8_t byte) {
+    if (byte == SLIP_END) {
+        int result = d->discard ? 0 : (int)d->len;  // Back-to-back ENDs give empty frames, ignored
+        d->len = 0;
+        d->escaped = 0;
+        d->discard = 0;
+        return result;
+    }
+    if (d->discard) {
+        return 0;
+    }
+    if (byte == SLIP_ESC) {
+        d->escaped = 1;
+        return 0;
+    }
+    if (d->escaped) {
+        d->escaped = 0;
+        if (byte == SLIP_ESC_END) {
+            byte = SLIP_END;
+        } else if (byte == SLIP_ESC_ESC) {
+            byte = SLIP_ESC;
+        } else {
+            d->discard = 1;
+            return FRAME_ERR_ENCODING;
+        }
+    }
+    return frame_append(d, byte);
+}
+"#;
+
+const PPP_CODEC: &str = r#"
+/*
+ * PPP in HDLC-like framing (RFC 1662): frames are delimited by 0x7E,
+ * 0x7E, 0x7D and control characters are escaped as 0x7D, byte ^ 0x20,
+ * and a 16-bit FCS (CRC-16/X.25) is appended little-endian. The decoder
+ * strips and checks the FCS; address/control fields are left to the caller.
+ */
+static uint16_t ppp_fcs_update(uint16_t fcs, uint8_t byte) {
+    fcs ^= byte;
+    for (int i = 0; i < 8; i++) {
+        fcs = (fcs & 1) ? (uint16_t)((fcs >> 1) ^ 0x8408) : (uint16_t)(fcs >> 1);
+    }
+    return fcs;
+}
+
+static size_t ppp_put(uint8_t *out, size_t o, uint8_t byte) {
+    if (byte == PPP_FLAG || byte == PPP_ESC || byte < 0x20) {
+        out[o++] = PPP_ESC;
+        out[o++] = byte ^ PPP_XOR;
+    } else {
+        out[o++] = byte;
+    }
+    return o;
+}
+
+size_t frame_encode(const uint8_t *in, size_t len, uint8_t *out) {
+    if (len > FRAME_MAX_SIZE) {
+        return 0;
+    }
+    uint16_t fcs = PPP_FCS_INIT;
+    size_t o = 0;
+    out[o++] = PPP_FLAG;
+    for (size_t i = 0; i < len; i++) {
+        fcs = ppp_fcs_update(fcs, in[i]);
+        o = ppp_put(out, o, in[i]);
+    }
+    fcs ^= 0xFFFF;
+    o = ppp_put(out, o, (uint8_t)(fcs & 0xFF));
+    o = ppp_put(out, o, (uint8_t)(fcs >> 8));
+    out[o++] = PPP_FLAG;
+    return o;
+}
+
+int frame_decoder_feed(frame_decoder_t *d, uint8_t byte) {
+    if (byte == PPP_FLAG) {
+        int result = 0;
+        if (!d->discard && d->len > 0) {
+            // FCS over payload plus received FCS leaves the magic residue
+            uint16_t fcs = PPP_FCS_INIT;
+            for (size_t i = 0; i < d->len; i++) {
+                fcs = ppp_fcs_update(fcs, d->buf[i]);
+            }
+            if (d->len < 2 || d->escaped) {
+                result = FRAME_ERR_ENCODING;
+            } else if (fcs != PPP_FCS_GOOD) {
+                result = FRAME_ERR_CHECKSUM;
+            } else {
+                result = (int)(d->len - 2);
+            }
+        }
+        d->len = 0;
+        d->escaped = 0;
+        d->discard = 0;
+        return result;
+    }
+    if (d->discard) {
+        return 0;
+    }
+    if (byte == PPP_ESC) {
+        d->escaped = 1;
+        return 0;
+    }
+    if (d->escaped) {
+        d->escaped = 0;
+        byte ^= PPP_XOR;
+    }
+    return frame_append(d, byte);
+}
+"#;
+
+const CUSTOM_CODEC: &str = r#"
+/*
+ * Delimiter framing: FRAME_DELIMITER ends a frame, and the delimiter or
+ * escape byte inside the payload is sent as FRAME_ESC, byte ^ FRAME_XOR.
+ */
+size_t frame_encode(const uint8_t *in, size_t len, uint8_t *out) {
+    if (len > FRAME_MAX_SIZE) {
+        return 0;
+    }
+    size_t o = 0;
+    for (size_t i = 0; i < len; i++) {
+        if (in[i] == FRAME_DELIMITER || in[i] == FRAME_ESC) {
+            out[o++] = FRAME_ESC;
+            out[o++] = in[i] ^ FRAME_XOR;
+        } else {
+            out[o++] = in[i];
+        }
+    }
+    out[o++] = FRAME_DELIMITER;
+    return o;
+}
+
+int frame_decoder_feed(frame_decoder_t *d, uint8_t byte) {
+    if (byte == FRAME_DELIMITER) {
+        int result = (d->discard || d->escaped) ? 0 : (int)d->len;
+        d->len = 0;
+        d->escaped = 0;
+        d->discard = 0;
+        return result;
+    }
+    if (d->discard) {
+        return 0;
+    }
+    if (byte == FRAME_ESC) {
+        d->escaped = 1;
+        return 0;
+    }
+    if (d->escaped) {
+        d->escaped = 0;
+        byte ^= FRAME_XOR;
+    }
+    return frame_append(d, byte);
+}
+"#;
@@ -1,6 +1,7 @@
 // GPIO Driver Generator
 // Generates GPIO drivers for various MCU architectures
 
+use super::hal_abstraction;
 use super::templates::*;
 
 /// Generate GPIO driver code
@@ -9,6 +10,10 @@ pub fn generate_gpio_driver(config: &GpioConfig, arch: &McuArch, lang: &DriverLa
         DriverLanguage::C => generate_gpio_c(config, arch),
         DriverLanguage::Cpp => generate_gpio_cpp(config, arch),
         DriverLanguage::Rust => generate_gpio_rust(config, arch),
+        DriverLanguage::Portable => hal_abstraction::generate_portable_driver(
+            &hal_abstraction::PortablePeripheral::Gpio(config.clone()),
+            hal_abstraction::HalBackend::for_arch(arch).hal().as_ref(),
+        ),
     }
 }
 
@@ -0,0 +1,973 @@
+// Portable HAL Abstraction
+// Drivers written against a small C HAL interface, bound to STM32 HAL, ESP-IDF or Arduino
+
+use super::templates::*;
+
+/// C signature of each HAL operation, in `peripheral_hal_t` field order
+const HAL_OPS: &[(&str, &str, &str)] = &[
+    ("init_gpio", "int", "uint8_t port, uint8_t pin, uint8_t mode, uint8_t pull"),
+    ("read_gpio", "bool", "uint8_t port, uint8_t pin"),
+    ("write_gpio", "void", "uint8_t port, uint8_t pin, bool value"),
+    ("init_uart", "int", "uint8_t instance, uint32_t baud"),
+    ("uart_transmit", "int", "uint8_t instance, const uint8_t *data, uint16_t len, uint32_t timeout_ms"),
+    ("uart_receive", "int", "uint8_t instance, uint8_t *data, uint16_t len, uint32_t timeout_ms"),
+    ("init_spi", "int", "uint8_t instance, uint32_t clock_hz, uint8_t mode"),
+    ("spi_transfer", "int", "uint8_t instance, const uint8_t *tx, uint8_t *rx, uint16_t len"),
+];
+
+/// Timeout portable UART drivers pass to the HAL
+pub const PORTABLE_UART_TIMEOUT_MS: u32 = 100;
+
+/// A backend for portable drivers
+///
+/// Each operation method returns the C body of the matching `peripheral_hal_t`
+/// function. The functions take only primitive arguments (port and pin numbers,
+/// peripheral instance numbers, baud rates), so portable drivers never see vendor
+/// handle types.
+///
+/// UART numbers follow the vendor's naming: USART2 is 2 on STM32, UART_NUM_2 is 2
+/// on ESP-IDF, Serial1 is 1 on Arduino. SPI instance N is the Nth bus free for
+/// application use: SPI1 on STM32, SPI2_HOST on ESP-IDF (SPI1 drives the flash)
+/// and the default `SPI` bus on Arduino.
+pub trait PeripheralHal {
+    /// Prefix for the backend's C symbols, e.g. `stm32`
+    fn id(&self) -> &'static str;
+    fn display_name(&self) -> &'static str;
+    /// Generated backend file name
+    fn file_name(&self) -> String {
+        format!("hal_{}.c", self.id())
+    }
+    fn includes(&self) -> &'static [&'static str];
+    /// Lookup helpers and state shared by the operations
+    fn prelude(&self) -> String;
+    /// Reject a GPIO port/pin the backend cannot map to a real pin
+    fn check_gpio(&self, _port: &str, _pin: u8) -> Result<(), String> {
+        Ok(())
+    }
+    /// UART instance numbers the backend maps to a real peripheral
+    fn uart_instances(&self) -> std::ops::RangeInclusive<u8>;
+    /// SPI instance numbers the backend maps to a real bus
+    fn spi_instances(&self) -> std::ops::RangeInclusive<u8>;
+
+    fn init_gpio(&self) -> String;
+    fn read_gpio(&self) -> String;
+    fn write_gpio(&self) -> String;
+    fn init_uart(&self) -> String;
+    fn uart_transmit(&self) -> String;
+    fn uart_receive(&self) -> String;
+    fn init_spi(&self) -> String;
+    fn spi_transfer(&self) -> String;
+}
+
+/// Backend selected by name in the UI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HalBackend {
+    Stm32,
+    EspIdf,
+    Arduino,
+}
+
+impl HalBackend {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().replace(['-', ' '], "_").as_str() {
+            "stm32" | "stm32_hal" => Some(HalBackend::Stm32),
+            "esp_idf" | "espidf" | "esp32" => Some(HalBackend::EspIdf),
+            "arduino" => Some(HalBackend::Arduino),
+            _ => None,
+        }
+    }
+
+    /// Backend that matches a driver's target architecture
+    pub fn for_arch(arch: &McuArch) -> Self {
+        match arch {
+            McuArch::Esp32 => HalBackend::EspIdf,
+            McuArch::Avr => HalBackend::Arduino,
+            _ => HalBackend::Stm32,
+        }
+    }
+
+    pub fn hal(&self) -> Box<dyn PeripheralHal> {
+        match self {
+            HalBackend::Stm32 => Box::new(Stm32HalImpl),
+            HalBackend::EspIdf => Box::new(EspIdfHalImpl),
+            HalBackend::Arduino => Box::new(ArduinoHalImpl),
+        }
+    }
+}
+
+// ==================== Interface ====================
+
+/// `peripheral_hal_t` and its constants, guarded so several portable drivers can share it
+pub fn generate_hal_interface() -> String {
+    let fields: String = HAL_OPS
+        .iter()
+        .map(|(name, ret, params)| format!("    {} (*{})({});\n", ret, name, params))
+        .collect();
+
+    format!(r#"#ifndef PERIPHERAL_HAL_H
+#define PERIPHERAL_HAL_H
+
+#include <stdint.h>
+#include <stdbool.h>
+
+#ifdef __cplusplus
+extern "C" {{
+#endif
+
+#define PHAL_OK     0
+#define PHAL_ERROR  (-1)
+
+#define PHAL_GPIO_INPUT   0u
+#define PHAL_GPIO_OUTPUT  1u
+#define PHAL_GPIO_ANALOG  2u
+
+#define PHAL_PULL_NONE  0u
+#define PHAL_PULL_UP    1u
+#define PHAL_PULL_DOWN  2u
+
+/**
+ * Peripheral HAL interface
+ * Portable drivers only call through this table; a backend file defines it.
+ */
+typedef struct {{
+    const char *name;
+{fields}}} peripheral_hal_t;
+
+/** Backend linked into the firmware (hal_<backend>.c) */
+extern const peripheral_hal_t peripheral_hal;
+
+#ifdef __cplusplus
+}}
+#endif
+
+#endif // PERIPHERAL_HAL_H
+"#)
+}
+
+/// Backend file defining `peripheral_hal` for `hal`, including the driver header `header_name`
+pub fn generate_hal_backend(hal: &dyn PeripheralHal, header_name: &str) -> String {
+    let bodies = [
+        hal.init_gpio(),
+        hal.read_gpio(),
+        hal.write_gpio(),
+        hal.init_uart(),
+        hal.uart_transmit(),
+        hal.uart_receive(),
+        hal.init_spi(),
+        hal.spi_transfer(),
+    ];
+    let id = hal.id();
+
+    let mut out = format!(
+        "/**\n * Peripheral HAL backend: {}\n * Auto-generated by NeuroBench\n */\n\n#include \"{}\"\n",
+        hal.display_name(),
+        header_name,
+    );
+    for include in hal.includes() {
+        out.push_str(&format!("#include {}\n", include));
+    }
+    out.push('\n');
+    out.push_str(&hal.prelude());
+
+    for ((name, ret, params), body) in HAL_OPS.iter().zip(&bodies) {
+        out.push_str(&format!("\nstatic {} {}_{}({}) {{\n{}}}\n", ret, id, name, params, body));
+    }
+
+    out.push_str(&format!("\nconst peripheral_hal_t peripheral_hal = {{\n    \"{}\",\n", hal.display_name()));
+    for (name, _, _) in HAL_OPS {
+        out.push_str(&format!("    {}_{},\n", id, name));
+    }
+    out.push_str("};\n");
+    out
+}
+
+// ==================== Portable Drivers ====================
+
+/// Peripheral a portable driver is generated for
+#[derive(Debug, Clone)]
+pub enum PortablePeripheral {
+    Gpio(GpioConfig),
+    Uart(UartConfig),
+    Spi(SpiConfig),
+}
+
+/// Reject a pin or instance number `hal` has no mapping for
+///
+/// Checked before generation, so a driver never compiles only to return
+/// `PHAL_ERROR` from its init call on the target.
+pub fn check_portable(peripheral: &PortablePeripheral, hal: &dyn PeripheralHal) -> Result<(), String> {
+    let (kind, instance, instances) = match peripheral {
+        PortablePeripheral::Gpio(config) => return hal.check_gpio(&config.port, config.pin),
+        PortablePeripheral::Uart(config) => ("UART", instance_number(&config.instance), hal.uart_instances()),
+        PortablePeripheral::Spi(config) => ("SPI", instance_number(&config.instance), hal.spi_instances()),
+    };
+    if !instances.contains(&instance) {
+        return Err(format!(
+            "{} has no {} instance {}; use {}{}-{}",
+            hal.display_name(), kind, instance, kind, instances.start(), instances.end()
+        ));
+    }
+    Ok(())
+}
+
+/// Generate a driver that only calls `peripheral_hal`
+///
+/// `header_file` carries the HAL interface and `source_file` is identical for every
+/// backend; the backend binding for `hal` is returned in `example_file`.
+///
+/// A peripheral `check_portable` rejects gets a source holding only an `#error`.
+pub fn generate_portable_driver(peripheral: &PortablePeripheral, hal: &dyn PeripheralHal) -> DriverOutput {
+    if let Err(reason) = check_portable(peripheral, hal) {
+        let (name, peripheral_type) = match peripheral {
+            PortablePeripheral::Gpio(_) => ("GPIO", PeripheralType::GPIO),
+            PortablePeripheral::Uart(_) => ("UART", PeripheralType::UART),
+            PortablePeripheral::Spi(_) => ("SPI", PeripheralType::SPI),
+        };
+        return error_output(&format!("{} cannot be bound to {}", name, hal.display_name()), &reason, peripheral_type);
+    }
+
+    let (header_name, api, source, peripheral_type) = match peripheral {
+        PortablePeripheral::Gpio(config) => portable_gpio(config),
+        PortablePeripheral::Uart(config) => portable_uart(config),
+        PortablePeripheral::Spi(config) => portable_spi(config),
+    };
+    let guard = header_name.replace('.', "_").to_uppercase();
+
+    let header = format!(
+        "/**\n * Portable driver: {header_name}\n * Auto-generated by NeuroBench\n */\n\n{}\n#ifndef {guard}\n#define {guard}\n\n#ifdef __cplusplus\nextern \"C\" {{\n#endif\n\n{api}\n#ifdef __cplusplus\n}}\n#endif\n\n#endif // {guard}\n",
+        generate_hal_interface(),
+    );
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: Some(generate_hal_backend(hal, &header_name)),
+        peripheral_type,
+    }
+}
+
+/// Output for a peripheral with no portable driver yet
+///
+/// The source is a single `#error`, so a project that picks it up fails to build with
+/// the reason instead of compiling STM32 HAL code that only looks portable.
+pub fn not_portable(name: &str, peripheral_type: PeripheralType) -> DriverOutput {
+    error_output(
+        &format!("{} has no portable driver", name),
+        &format!("{} has no portable HAL driver yet; generate it as C, C++ or Rust", name),
+        peripheral_type,
+    )
+}
+
+/// Output whose source is a single `#error` with `reason`
+fn error_output(title: &str, reason: &str, peripheral_type: PeripheralType) -> DriverOutput {
+    DriverOutput {
+        header_file: None,
+        source_file: format!(
+            "/**\n * {}\n * Auto-generated by NeuroBench\n */\n\n#error \"{}\"\n",
+            title,
+            reason.replace('"', "\\\""),
+        ),
+        example_file: None,
+        peripheral_type,
+    }
+}
+
+/// First number in an instance name: `USART2` -> 2
+fn instance_number(instance: &str) -> u8 {
+    let digits: String = instance.chars().skip_while(|c| !c.is_ascii_digit()).take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().unwrap_or(1)
+}
+
+fn portable_gpio(config: &GpioConfig) -> (String, String, String, PeripheralType) {
+    let port = config.port.to_uppercase();
+    let port_index = port.bytes().next().map(|b| b.saturating_sub(b'A')).unwrap_or(0);
+    let pin = config.pin;
+    let name = format!("P{}{}", port, pin);
+    let header_name = format!("gpio_{}{}_portable.h", port, pin);
+
+    let (mode, note) = match config.mode {
+        GpioMode::Input => ("PHAL_GPIO_INPUT", ""),
+        GpioMode::Output => ("PHAL_GPIO_OUTPUT", ""),
+        GpioMode::Analog => ("PHAL_GPIO_ANALOG", ""),
+        GpioMode::AlternateFunction => (
+            "PHAL_GPIO_OUTPUT",
+            "    // Alternate functions are backend specific: route the pin mux outside this driver\n",
+        ),
+    };
+    let pull = match config.pull {
+        GpioPull::None => "PHAL_PULL_NONE",
+        GpioPull::Up | GpioPull::PullUp => "PHAL_PULL_UP",
+        GpioPull::Down | GpioPull::PullDown => "PHAL_PULL_DOWN",
+    };
+    let initial = match config.initial_state {
+        Some(state) => format!("    {}_Write({});\n", name, state),
+        None => String::new(),
+    };
+
+    let api = format!(r#"// Port {port} is bank {port_index}
+#define {name}_PORT  {port_index}u
+#define {name}_PIN   {pin}u
+
+int  {name}_Init(void);
+void {name}_Write(bool state);
+bool {name}_Read(void);
+void {name}_Toggle(void);
+"#);
+
+    let source = format!(r#"/**
+ * Portable GPIO driver for {name}
+ * Auto-generated by NeuroBench
+ */
+
+#include "{header_name}"
+
+int {name}_Init(void) {{
+{note}    int status = peripheral_hal.init_gpio({name}_PORT, {name}_PIN, {mode}, {pull});
+    if (status != PHAL_OK) {{
+        return status;
+    }}
+{initial}    return PHAL_OK;
+}}
+
+void {name}_Write(bool state) {{
+    peripheral_hal.write_gpio({name}_PORT, {name}_PIN, state);
+}}
+
+bool {name}_Read(void) {{
+    return peripheral_hal.read_gpio({name}_PORT, {name}_PIN);
+}}
+
+void {name}_Toggle(void) {{
+    peripheral_hal.write_gpio({name}_PORT, {name}_PIN, !peripheral_hal.read_gpio({name}_PORT, {name}_PIN));
+}}
+"#);
+
+    (header_name, api, source, PeripheralType::GPIO)
+}
+
+fn portable_uart(config: &UartConfig) -> (String, String, String, PeripheralType) {
+    let instance = instance_number(&config.instance);
+    let name = format!("UART{}", instance);
+    let header_name = format!("uart{}_portable.h", instance);
+    let baud = config.baud_rate;
+
+    let api = format!(r#"#define {name}_INSTANCE    {instance}u
+#define {name}_BAUD        {baud}u
+#define {name}_TIMEOUT_MS  {PORTABLE_UART_TIMEOUT_MS}u
+
+int {name}_Init(void);
+int {name}_Write(const uint8_t *data, uint16_t len);
+int {name}_Read(uint8_t *data, uint16_t len);
+int {name}_Print(const char *text);
+"#);
+
+    let source = format!(r#"/**
+ * Portable UART driver for {name}
+ * Auto-generated by NeuroBench
+ */
+
+#include "{header_name}"
+#include <string.h>
+
+int {name}_Init(void) {{
+    return peripheral_hal.init_uart({name}_INSTANCE, {name}_BAUD);
+}}
+
+int {name}_Write(const uint8_t *data, uint16_t len) {{
+    return peripheral_hal.uart_transmit({name}_INSTANCE, data, len, {name}_TIMEOUT_MS);
+}}
+
+int {name}_Read(uint8_t *data, uint16_t len) {{
+    return peripheral_hal.uart_receive({name}_INSTANCE, data, len, {name}_TIMEOUT_MS);
+}}
+
+int {name}_Print(const char *text) {{
+    return {name}_Write((const uint8_t *)text, (uint16_t)strlen(text));
+}}
+"#);
+
+    (header_name, api, source, PeripheralType::UART)
+}
+
+fn portable_spi(config: &SpiConfig) -> (String, String, String, PeripheralType) {
+    let instance = instance_number(&config.instance);
+    let name = format!("SPI{}", instance);
+    let header_name = format!("spi{}_portable.h", instance);
+    let clock_hz = config.clock_hz;
+    let mode = match config.mode {
+        SpiMode::Mode0 => 0,
+        SpiMode::Mode1 => 1,
+        SpiMode::Mode2 => 2,
+        SpiMode::Mode3 => 3,
+    };
+
+    let api = format!(r#"#define {name}_INSTANCE  {instance}u
+#define {name}_CLOCK_HZ  {clock_hz}u
+#define {name}_MODE      {mode}u
+
+int {name}_Init(void);
+int {name}_Transfer(const uint8_t *tx, uint8_t *rx, uint16_t len);
+int {name}_Write(const uint8_t *data, uint16_t len);
+"#);
+
+    let source = format!(r#"/**
+ * Portable SPI driver for {name}
+ * Auto-generated by NeuroBench
+ */
+
+#include "{header_name}"
+#include <stddef.h>
+
+int {name}_Init(void) {{
+    return peripheral_hal.init_spi({name}_INSTANCE, {name}_CLOCK_HZ, {name}_MODE);
+}}
+
+int {name}_Transfer(const uint8_t *tx, uint8_t *rx, uint16_t len) {{
+    return peripheral_hal.spi_transfer({name}_INSTANCE, tx, rx, len);
+}}
+
+int {name}_Write(const uint8_t *data, uint16_t len) {{
+    return peripheral_hal.spi_transfer({name}_INSTANCE, data, NULL, len);
+}}
+"#);
+
+    (header_name, api, source, PeripheralType::SPI)
+}
+
+// ==================== Backends ====================
+
+/// STM32Cube HAL; pin muxing stays in the CubeMX `HAL_*_MspInit` callbacks
+pub struct Stm32HalImpl;
+
+impl PeripheralHal for Stm32HalImpl {
+    fn id(&self) -> &'static str {
+        "stm32"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "STM32 HAL"
+    }
+
+    fn includes(&self) -> &'static [&'static str] {
+        // CubeMX's main.h pulls in the HAL header of whichever family the project targets
+        &["\"main.h\"", "<stddef.h>"]
+    }
+
+    // Ports beyond D are only present on larger packages; the prelude returns
+    // NULL for ports the target lacks
+    fn check_gpio(&self, port: &str, pin: u8) -> Result<(), String> {
+        if !matches!(port.to_uppercase().as_str(), "A" | "B" | "C" | "D" | "E" | "F" | "G" | "H") {
+            return Err(format!("STM32 HAL has no GPIO port {}; use ports A-H", port));
+        }
+        if pin > 15 {
+            return Err(format!("P{}{} is not an STM32 pin; pins are numbered 0-15", port, pin));
+        }
+        Ok(())
+    }
+
+    fn uart_instances(&self) -> std::ops::RangeInclusive<u8> {
+        1..=3
+    }
+
+    fn spi_instances(&self) -> std::ops::RangeInclusive<u8> {
+        1..=3
+    }
+
+    fn prelude(&self) -> String {
+        r#"#define STM32_PORT(n, X) case n: if (enable_clock) { __HAL_RCC_GPIO##X##_CLK_ENABLE(); } return GPIO##X;
+
+static GPIO_TypeDef *stm32_port(uint8_t port, bool enable_clock) {
+    switch (port) {
+    STM32_PORT(0, A)
+    STM32_PORT(1, B)
+    STM32_PORT(2, C)
+#ifdef GPIOD
+    STM32_PORT(3, D)
+#endif
+#ifdef GPIOE
+    STM32_PORT(4, E)
+#endif
+#ifdef GPIOF
+    STM32_PORT(5, F)
+#endif
+#ifdef GPIOG
+    STM32_PORT(6, G)
+#endif
+#ifdef GPIOH
+    STM32_PORT(7, H)
+#endif
+    default: return NULL;
+    }
+}
+
+static UART_HandleTypeDef stm32_uarts[4];
+
+static UART_HandleTypeDef *stm32_uart(uint8_t instance) {
+    USART_TypeDef *regs;
+    switch (instance) {
+    case 1: regs = USART1; break;
+    case 2: regs = USART2; break;
+#ifdef USART3
+    case 3: regs = USART3; break;
+#endif
+    default: return NULL;
+    }
+    stm32_uarts[instance].Instance = regs;
+    return &stm32_uarts[instance];
+}
+
+static SPI_HandleTypeDef stm32_spis[4];
+
+static SPI_HandleTypeDef *stm32_spi(uint8_t instance) {
+    SPI_TypeDef *regs;
+    switch (instance) {
+    case 1: regs = SPI1; break;
+#ifdef SPI2
+    case 2: regs = SPI2; break;
+#endif
+#ifdef SPI3
+    case 3: regs = SPI3; break;
+#endif
+    default: return NULL;
+    }
+    stm32_spis[instance].Instance = regs;
+    return &stm32_spis[instance];
+}
+"#.to_string()
+    }
+
+    fn init_gpio(&self) -> String {
+        r#"    GPIO_TypeDef *gpio = stm32_port(port, true);
+    if (gpio == NULL || pin > 15) {
+        return PHAL_ERROR;
+    }
+
+    GPIO_InitTypeDef init = {0};
+    init.Pin = (uint16_t)(1u << pin);
+    init.Mode = mode == PHAL_GPIO_OUTPUT ? GPIO_MODE_OUTPUT_PP
+              : mode == PHAL_GPIO_ANALOG ? GPIO_MODE_ANALOG : GPIO_MODE_INPUT;
+    init.Pull = pull == PHAL_PULL_UP ? GPIO_PULLUP
+              : pull == PHAL_PULL_DOWN ? GPIO_PULLDOWN : GPIO_NOPULL;
+    init.Speed = GPIO_SPEED_FREQ_LOW;
+    HAL_GPIO_Init(gpio, &init);
+    return PHAL_OK;
+"#.to_string()
+    }
+
+    fn read_gpio(&self) -> String {
+        r#"    GPIO_TypeDef *gpio = stm32_port(port, false);
+    return gpio != NULL && HAL_GPIO_ReadPin(gpio, (uint16_t)(1u << pin)) == GPIO_PIN_SET;
+"#.to_string()
+    }
+
+    fn write_gpio(&self) -> String {
+        r#"    GPIO_TypeDef *gpio = stm32_port(port, false);
+    if (gpio != NULL) {
+        HAL_GPIO_WritePin(gpio, (uint16_t)(1u << pin), value ? GPIO_PIN_SET : GPIO_PIN_RESET);
+    }
+"#.to_string()
+    }
+
+    fn init_uart(&self) -> String {
+        r#"    UART_HandleTypeDef *huart = stm32_uart(instance);
+    if (huart == NULL) {
+        return PHAL_ERROR;
+    }
+
+    huart->Init.BaudRate = baud;
+    huart->Init.WordLength = UART_WORDLENGTH_8B;
+    huart->Init.StopBits = UART_STOPBITS_1;
+    huart->Init.Parity = UART_PARITY_NONE;
+    huart->Init.Mode = UART_MODE_TX_RX;
+    huart->Init.HwFlowCtl = UART_HWCONTROL_NONE;
+    huart->Init.OverSampling = UART_OVERSAMPLING_16;
+    return HAL_UART_Init(huart) == HAL_OK ? PHAL_OK : PHAL_ERROR;
+"#.to_string()
+    }
+
+    fn uart_transmit(&self) -> String {
+        r#"    UART_HandleTypeDef *huart = stm32_uart(instance);
+    if (huart == NULL) {
+        return PHAL_ERROR;
+    }
+    return HAL_UART_Transmit(huart, (uint8_t *)data, len, timeout_ms) == HAL_OK ? PHAL_OK : PHAL_ERROR;
+"#.to_string()
+    }
+
+    fn uart_receive(&self) -> String {
+        r#"    UART_HandleTypeDef *huart = stm32_uart(instance);
+    if (huart == NULL) {
+        return PHAL_ERROR;
+    }
+    return HAL_UART_Receive(huart, data, len, timeout_ms) == HAL_OK ? PHAL_OK : PHAL_ERROR;
+"#.to_string()
+    }
+
+    fn init_spi(&self) -> String {
+        r#"    SPI_HandleTypeDef *hspi = stm32_spi(instance);
+    if (hspi == NULL) {
+        return PHAL_ERROR;
+    }
+
+    // SPI1 is clocked from APB2, the others from APB1; pick the fastest clock <= clock_hz
+    uint32_t pclk = instance == 1 ? HAL_RCC_GetPCLK2Freq() : HAL_RCC_GetPCLK1Freq();
+    uint32_t prescaler = 0;
+    while (prescaler < 7 && (pclk >> (prescaler + 1)) > clock_hz) {
+        prescaler++;
+    }
+
+    hspi->Init.Mode = SPI_MODE_MASTER;
+    hspi->Init.Direction = SPI_DIRECTION_2LINES;
+    hspi->Init.DataSize = SPI_DATASIZE_8BIT;
+    hspi->Init.CLKPolarity = (mode & 2u) ? SPI_POLARITY_HIGH : SPI_POLARITY_LOW;
+    hspi->Init.CLKPhase = (mode & 1u) ? SPI_PHASE_2EDGE : SPI_PHASE_1EDGE;
+    hspi->Init.NSS = SPI_NSS_SOFT;
+    hspi->Init.BaudRatePrescaler = prescaler << SPI_CR1_BR_Pos;
+    hspi->Init.FirstBit = SPI_FIRSTBIT_MSB;
+    hspi->Init.TIMode = SPI_TIMODE_DISABLE;
+    hspi->Init.CRCCalculation = SPI_CRCCALCULATION_DISABLE;
+    return HAL_SPI_Init(hspi) == HAL_OK ? PHAL_OK : PHAL_ERROR;
+"#.to_string()
+    }
+
+    fn spi_transfer(&self) -> String {
+        r#"    SPI_HandleTypeDef *hspi = stm32_spi(instance);
+    HAL_StatusTypeDef status;
+    if (hspi == NULL) {
+        return PHAL_ERROR;
+    }
+
+    if (tx != NULL && rx != NULL) {
+        status = HAL_SPI_TransmitReceive(hspi, (uint8_t *)tx, rx, len, HAL_MAX_DELAY);
+    } else if (tx != NULL) {
+        status = HAL_SPI_Transmit(hspi, (uint8_t *)tx, len, HAL_MAX_DELAY);
+    } else {
+        status = HAL_SPI_Receive(hspi, rx, len, HAL_MAX_DELAY);
+    }
+    return status == HAL_OK ? PHAL_OK : PHAL_ERROR;
+"#.to_string()
+    }
+}
+
+/// ESP-IDF drivers; GPIO port N is the bank of pins 32*N..32*N+31
+///
+/// ESP32 pins are plain numbers, so only port A (GPIO0-31) and port B (GPIO32-39)
+/// exist. STM32-style names such as PC13 are rejected rather than mapped to GPIO77.
+pub struct EspIdfHalImpl;
+
+/// Highest ESP32 GPIO number
+const ESP32_GPIO_MAX: u32 = 39;
+
+impl PeripheralHal for EspIdfHalImpl {
+    fn id(&self) -> &'static str {
+        "esp_idf"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "ESP-IDF"
+    }
+
+    fn includes(&self) -> &'static [&'static str] {
+        &[
+            "\"freertos/FreeRTOS.h\"",
+            "\"driver/gpio.h\"",
+            "\"driver/uart.h\"",
+            "\"driver/spi_master.h\"",
+            "\"soc/soc_caps.h\"",
+        ]
+    }
+
+    fn uart_instances(&self) -> std::ops::RangeInclusive<u8> {
+        0..=2
+    }
+
+    // SPI2_HOST and SPI3_HOST; the ESP32-C3 has only the first
+    fn spi_instances(&self) -> std::ops::RangeInclusive<u8> {
+        1..=2
+    }
+
+    fn check_gpio(&self, port: &str, pin: u8) -> Result<(), String> {
+        let bank = match port.to_uppercase().as_str() {
+            "A" => 0,
+            "B" => 1,
+            _ => return Err(format!("ESP-IDF has no GPIO port {}; use port A (GPIO0-31) or B (GPIO32-39)", port)),
+        };
+        let gpio = bank * 32 + u32::from(pin);
+        if pin >= 32 || gpio > ESP32_GPIO_MAX {
+            return Err(format!("P{}{} is not an ESP32 pin; GPIO numbers stop at {}", port, pin, ESP32_GPIO_MAX));
+        }
+        Ok(())
+    }
+
+    fn prelude(&self) -> String {
+        r#"// VSPI default pins; override to match the board
+#ifndef PHAL_SPI_MOSI_PIN
+#define PHAL_SPI_MOSI_PIN 23
+#endif
+#ifndef PHAL_SPI_MISO_PIN
+#define PHAL_SPI_MISO_PIN 19
+#endif
+#ifndef PHAL_SPI_SCLK_PIN
+#define PHAL_SPI_SCLK_PIN 18
+#endif
+
+#define ESP_UART_RX_BUFFER 256
+
+// Port N, pin P is GPIO 32*N+P; anything past the chip's last GPIO is GPIO_NUM_NC
+static gpio_num_t esp_gpio(uint8_t port, uint8_t pin) {
+    uint32_t num = port * 32u + pin;
+    return (pin < 32u && GPIO_IS_VALID_GPIO(num)) ? (gpio_num_t)num : GPIO_NUM_NC;
+}
+
+// SPI1 drives the flash chip, so instance 1 is SPI2_HOST and instance 2 SPI3_HOST
+static spi_device_handle_t esp_spi_devices[SOC_SPI_PERIPH_NUM - 1];
+
+static spi_device_handle_t *esp_spi(uint8_t instance) {
+    if (instance < 1 || instance >= SOC_SPI_PERIPH_NUM) {
+        return NULL;
+    }
+    return &esp_spi_devices[instance - 1];
+}
+"#.to_string()
+    }
+
+    fn init_gpio(&self) -> String {
+        r#"    gpio_num_t gpio = esp_gpio(port, pin);
+    if (gpio == GPIO_NUM_NC) {
+        return PHAL_ERROR;
+    }
+
+    gpio_config_t conf = {
+        .pin_bit_mask = 1ULL << gpio,
+        // Outputs keep their input stage so read_gpio() reports the driven level
+        .mode = mode == PHAL_GPIO_OUTPUT ? GPIO_MODE_INPUT_OUTPUT
+              : mode == PHAL_GPIO_ANALOG ? GPIO_MODE_DISABLE : GPIO_MODE_INPUT,
+        .pull_up_en = pull == PHAL_PULL_UP ? GPIO_PULLUP_ENABLE : GPIO_PULLUP_DISABLE,
+        .pull_down_en = pull == PHAL_PULL_DOWN ? GPIO_PULLDOWN_ENABLE : GPIO_PULLDOWN_DISABLE,
+        .intr_type = GPIO_INTR_DISABLE,
+    };
+    return gpio_config(&conf) == ESP_OK ? PHAL_OK : PHAL_ERROR;
+"#.to_string()
+    }
+
+    fn read_gpio(&self) -> String {
+        r#"    gpio_num_t gpio = esp_gpio(port, pin);
+    return gpio != GPIO_NUM_NC && gpio_get_level(gpio) != 0;
+"#.to_string()
+    }
+
+    fn write_gpio(&self) -> String {
+        r#"    gpio_num_t gpio = esp_gpio(port, pin);
+    if (gpio != GPIO_NUM_NC) {
+        gpio_set_level(gpio, value ? 1 : 0);
+    }
+"#.to_string()
+    }
+
+    fn init_uart(&self) -> String {
+        r#"    const uart_config_t conf = {
+        .baud_rate = (int)baud,
+        .data_bits = UART_DATA_8_BITS,
+        .parity = UART_PARITY_DISABLE,
+        .stop_bits = UART_STOP_BITS_1,
+        .flow_ctrl = UART_HW_FLOWCTRL_DISABLE,
+    };
+    if (uart_param_config((uart_port_t)instance, &conf) != ESP_OK) {
+        return PHAL_ERROR;
+    }
+    // Pins stay on the chip's default IOMUX routing
+    return uart_driver_install((uart_port_t)instance, ESP_UART_RX_BUFFER, 0, 0, NULL, 0) == ESP_OK ? PHAL_OK : PHAL_ERROR;
+"#.to_string()
+    }
+
+    fn uart_transmit(&self) -> String {
+        r#"    if (uart_write_bytes((uart_port_t)instance, (const char *)data, len) < 0) {
+        return PHAL_ERROR;
+    }
+    return uart_wait_tx_done((uart_port_t)instance, pdMS_TO_TICKS(timeout_ms)) == ESP_OK ? PHAL_OK : PHAL_ERROR;
+"#.to_string()
+    }
+
+    fn uart_receive(&self) -> String {
+        "    return uart_read_bytes((uart_port_t)instance, data, len, pdMS_TO_TICKS(timeout_ms)) == len ? PHAL_OK : PHAL_ERROR;\n".to_string()
+    }
+
+    fn init_spi(&self) -> String {
+        r#"    spi_device_handle_t *device = esp_spi(instance);
+    if (device == NULL) {
+        return PHAL_ERROR;
+    }
+
+    spi_host_device_t host = (spi_host_device_t)instance;  // SPI2_HOST is 1
+    const spi_bus_config_t bus = {
+        .mosi_io_num = PHAL_SPI_MOSI_PIN,
+        .miso_io_num = PHAL_SPI_MISO_PIN,
+        .sclk_io_num = PHAL_SPI_SCLK_PIN,
+        .quadwp_io_num = -1,
+        .quadhd_io_num = -1,
+    };
+    if (spi_bus_initialize(host, &bus, SPI_DMA_CH_AUTO) != ESP_OK) {
+        return PHAL_ERROR;
+    }
+
+    const spi_device_interface_config_t config = {
+        .clock_speed_hz = (int)clock_hz,
+        .mode = mode,
+        .spics_io_num = -1,
+        .queue_size = 1,
+    };
+    return spi_bus_add_device(host, &config, device) == ESP_OK ? PHAL_OK : PHAL_ERROR;
+"#.to_string()
+    }
+
+    fn spi_transfer(&self) -> String {
+        r#"    spi_device_handle_t *device = esp_spi(instance);
+    if (device == NULL || *device == NULL) {
+        return PHAL_ERROR;
+    }
+
+    spi_transaction_t transaction = {
+        .length = (size_t)len * 8,
+        .tx_buffer = tx,
+        .rx_buffer = rx,
+    };
+    return spi_device_polling_transmit(*device, &transaction) == ESP_OK ? PHAL_OK : PHAL_ERROR;
+"#.to_string()
+    }
+}
+
+/// Arduino core (C++); GPIO port N, pin P is Arduino pin 32*N+P
+pub struct ArduinoHalImpl;
+
+impl PeripheralHal for ArduinoHalImpl {
+    fn id(&self) -> &'static str {
+        "arduino"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Arduino"
+    }
+
+    fn file_name(&self) -> String {
+        "hal_arduino.cpp".to_string()
+    }
+
+    fn uart_instances(&self) -> std::ops::RangeInclusive<u8> {
+        0..=1
+    }
+
+    // Only the default SPI bus is portable across cores
+    fn spi_instances(&self) -> std::ops::RangeInclusive<u8> {
+        1..=1
+    }
+
+    fn includes(&self) -> &'static [&'static str] {
+        &["<Arduino.h>", "<SPI.h>"]
+    }
+
+    fn prelude(&self) -> String {
+        r#"static uint8_t arduino_pin(uint8_t port, uint8_t pin) {
+    return (uint8_t)(port * 32u + pin);
+}
+
+static HardwareSerial *arduino_uart(uint8_t instance) {
+    switch (instance) {
+    case 0: return &Serial;
+#if defined(HAVE_HWSERIAL1) || defined(SERIAL_PORT_HARDWARE1)
+    case 1: return &Serial1;
+#endif
+    default: return NULL;
+    }
+}
+
+static SPISettings arduino_spi_settings;
+"#.to_string()
+    }
+
+    fn init_gpio(&self) -> String {
+        r#"    uint8_t p = arduino_pin(port, pin);
+    if (mode == PHAL_GPIO_OUTPUT) {
+        pinMode(p, OUTPUT);
+    } else if (pull == PHAL_PULL_UP) {
+        pinMode(p, INPUT_PULLUP);
+#ifdef INPUT_PULLDOWN
+    } else if (pull == PHAL_PULL_DOWN) {
+        pinMode(p, INPUT_PULLDOWN);
+#endif
+    } else {
+        pinMode(p, INPUT);
+    }
+    return PHAL_OK;
+"#.to_string()
+    }
+
+    fn read_gpio(&self) -> String {
+        "    return digitalRead(arduino_pin(port, pin)) == HIGH;\n".to_string()
+    }
+
+    fn write_gpio(&self) -> String {
+        "    digitalWrite(arduino_pin(port, pin), value ? HIGH : LOW);\n".to_string()
+    }
+
+    fn init_uart(&self) -> String {
+        r#"    HardwareSerial *serial = arduino_uart(instance);
+    if (serial == NULL) {
+        return PHAL_ERROR;
+    }
+    serial->begin(baud);
+    return PHAL_OK;
+"#.to_string()
+    }
+
+    fn uart_transmit(&self) -> String {
+        r#"    HardwareSerial *serial = arduino_uart(instance);
+    if (serial == NULL) {
+        return PHAL_ERROR;
+    }
+    // write() blocks until buffered, so the timeout does not apply
+    (void)timeout_ms;
+    serial->write(data, len);
+    serial->flush();
+    return PHAL_OK;
+"#.to_string()
+    }
+
+    fn uart_receive(&self) -> String {
+        r#"    HardwareSerial *serial = arduino_uart(instance);
+    if (serial == NULL) {
+        return PHAL_ERROR;
+    }
+    serial->setTimeout(timeout_ms);
+    return serial->readBytes(data, len) == len ? PHAL_OK : PHAL_ERROR;
+"#.to_string()
+    }
+
+    fn init_spi(&self) -> String {
+        r#"    static const uint8_t modes[] = { SPI_MODE0, SPI_MODE1, SPI_MODE2, SPI_MODE3 };
+    if (instance != 1) {
+        return PHAL_ERROR;
+    }
+    arduino_spi_settings = SPISettings(clock_hz, MSBFIRST, modes[mode & 3u]);
+    SPI.begin();
+    return PHAL_OK;
+"#.to_string()
+    }
+
+    fn spi_transfer(&self) -> String {
+        r#"    if (instance != 1) {
+        return PHAL_ERROR;
+    }
+
+    SPI.beginTransaction(arduino_spi_settings);
+    for (uint16_t i = 0; i < len; i++) {
+        uint8_t in = SPI.transfer(tx != NULL ? tx[i] : 0xFF);
+        if (rx != NULL) {
+            rx[i] = in;
+        }
+    }
+    SPI.endTransaction();
+    return PHAL_OK;
+"#.to_string()
+    }
+}
@@ -1,6 +1,7 @@
 // I2C Driver Generator
 // Generates I2C drivers for various MCU architectures
 
+use super::hal_abstraction;
 use super::templates::*;
 
 /// Generate I2C driver code
@@ -9,6 +10,7 @@ pub fn generate_i2c_driver(config: &I2cConfig, arch: &McuArch, lang: &DriverLang
         DriverLanguage::C => generate_i2c_c(config, arch),
         DriverLanguage::Cpp => generate_i2c_cpp(config, arch),
         DriverLanguage::Rust => generate_i2c_rust(config, arch),
+        DriverLanguage::Portable => hal_abstraction::not_portable("I2C", PeripheralType::I2C),
     }
 }
 
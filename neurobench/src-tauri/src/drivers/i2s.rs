@@ -0,0 +1,545 @@
+// I2S Audio Driver Generator
+// Generates STM32 HAL I2S setup with circular DMA for audio DACs/ADCs (PCM5102, WM8960)
+
+use super::mcu::McuFamily;
+use super::templates::*;
+use serde::{Deserialize, Serialize};
+
+/// Which side drives the bit clock and in which direction audio flows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum I2sRole {
+    MasterTx,
+    MasterRx,
+    SlaveTx,
+    SlaveRx,
+}
+
+impl I2sRole {
+    pub fn is_master(&self) -> bool {
+        matches!(self, I2sRole::MasterTx | I2sRole::MasterRx)
+    }
+
+    pub fn is_transmit(&self) -> bool {
+        matches!(self, I2sRole::MasterTx | I2sRole::SlaveTx)
+    }
+
+    fn hal_mode(&self) -> &'static str {
+        match self {
+            I2sRole::MasterTx => "I2S_MODE_MASTER_TX",
+            I2sRole::MasterRx => "I2S_MODE_MASTER_RX",
+            I2sRole::SlaveTx => "I2S_MODE_SLAVE_TX",
+            I2sRole::SlaveRx => "I2S_MODE_SLAVE_RX",
+        }
+    }
+}
+
+/// Frame format on the serial data line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum I2sStandard {
+    Philips,
+    MsbJustified,
+    LsbJustified,
+    Pcm,
+}
+
+impl I2sStandard {
+    pub fn name(&self) -> &'static str {
+        match self {
+            I2sStandard::Philips => "I2S Philips",
+            I2sStandard::MsbJustified => "MSB-justified (left)",
+            I2sStandard::LsbJustified => "LSB-justified (right)",
+            I2sStandard::Pcm => "PCM short frame",
+        }
+    }
+
+    fn hal_standard(&self) -> &'static str {
+        match self {
+            I2sStandard::Philips => "I2S_STANDARD_PHILIPS",
+            I2sStandard::MsbJustified => "I2S_STANDARD_MSB",
+            I2sStandard::LsbJustified => "I2S_STANDARD_LSB",
+            I2sStandard::Pcm => "I2S_STANDARD_PCM_SHORT",
+        }
+    }
+}
+
+/// I2S configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct I2sConfig {
+    /// SPI/I2S instance, e.g. `SPI2` (`I2S2` is accepted)
+    pub instance: String,
+    pub role: I2sRole,
+    pub standard: I2sStandard,
+    pub sample_rate: u32,
+    pub data_bits: u8,
+    /// Bits per channel slot on the wire, 16 or 32
+    pub channel_length: u8,
+    /// Output MCK at 256 x Fs for codecs without their own clock
+    pub mck_enabled: bool,
+    pub dma_enabled: bool,
+    /// I2S kernel clock (PLLI2S or the SPI123 mux) when the project fixes it;
+    /// otherwise `HAL_I2S_Init` derives the divider from the clock read at runtime
+    #[serde(default)]
+    pub kernel_clock_hz: Option<u32>,
+}
+
+impl Default for I2sConfig {
+    /// 48 kHz 16-bit stereo to a PCM5102-style DAC
+    fn default() -> Self {
+        Self {
+            instance: "SPI2".to_string(),
+            role: I2sRole::MasterTx,
+            standard: I2sStandard::Philips,
+            sample_rate: 48_000,
+            data_bits: 16,
+            channel_length: 16,
+            mck_enabled: true,
+            dma_enabled: true,
+            kernel_clock_hz: None,
+        }
+    }
+}
+
+/// Divider settings for a master I2S clock
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct I2sClock {
+    /// I2SDIV register value
+    pub divider: u32,
+    /// ODD bit, adds one to the effective divider
+    pub odd: bool,
+    pub actual_sample_rate: u32,
+    /// 0 when MCK output is off
+    pub mck_hz: u32,
+}
+
+impl I2sClock {
+    /// Sample rate error in parts per million
+    pub fn error_ppm(&self, sample_rate: u32) -> i64 {
+        (self.actual_sample_rate as i64 - sample_rate as i64) * 1_000_000 / sample_rate as i64
+    }
+}
+
+impl I2sConfig {
+    /// Instance normalised to the HAL register block name (`I2S2` -> `SPI2`)
+    pub fn spi_instance(&self) -> String {
+        let upper = self.instance.trim().to_uppercase();
+        match upper.strip_prefix("I2S") {
+            Some(number) => format!("SPI{}", number),
+            None => upper,
+        }
+    }
+
+    fn instance_number(&self) -> Option<u8> {
+        self.spi_instance().strip_prefix("SPI")?.parse().ok()
+    }
+
+    /// HAL data format for the data/channel length pair
+    fn hal_data_format(&self) -> Option<&'static str> {
+        match (self.data_bits, self.channel_length) {
+            (16, 16) => Some("I2S_DATAFORMAT_16B"),
+            (16, 32) => Some("I2S_DATAFORMAT_16B_EXTENDED"),
+            (24, 32) => Some("I2S_DATAFORMAT_24B"),
+            (32, 32) => Some("I2S_DATAFORMAT_32B"),
+            _ => None,
+        }
+    }
+
+    /// Prescaler for a master clock generated from `i2s_clock_hz`
+    ///
+    /// With MCK on the frame is always 256 I2S clocks per sample; without it
+    /// the divider runs straight off the bit clock (2 x channel length).
+    /// Picks the nearest divider like `HAL_I2S_Init` does.
+    pub fn clock(&self, i2s_clock_hz: u32) -> Result<I2sClock, String> {
+        if self.sample_rate == 0 {
+            return Err("Sample rate must be non-zero".to_string());
+        }
+        let clocks_per_sample = if self.mck_enabled { 256 } else { 2 * self.channel_length as u64 };
+        let scaled = i2s_clock_hz as u64 * 10 / (clocks_per_sample * self.sample_rate as u64);
+        let total = ((scaled + 5) / 10) as u32;
+        let divider = total / 2;
+        if !(2..=255).contains(&divider) {
+            return Err(format!(
+                "{} Hz cannot be derived from a {} Hz I2S clock (divider {} outside 2-255)",
+                self.sample_rate, i2s_clock_hz, divider
+            ));
+        }
+        Ok(I2sClock {
+            divider,
+            odd: total % 2 == 1,
+            actual_sample_rate: (i2s_clock_hz as u64 / (clocks_per_sample * total as u64)) as u32,
+            mck_hz: if self.mck_enabled { i2s_clock_hz / total } else { 0 },
+        })
+    }
+
+    pub fn validate(&self, mcu: McuFamily) -> Result<(), String> {
+        if !supports_i2s(mcu) {
+            return Err(format!("I2S generation is not available for {}", mcu.display_name()));
+        }
+        if !matches!(self.instance_number(), Some(1..=3)) {
+            return Err(format!("Invalid I2S instance: {}", self.instance));
+        }
+        if self.hal_data_format().is_none() {
+            return Err(format!(
+                "{}-bit data in a {}-bit channel is not supported (use 16/16, 16/32, 24/32 or 32/32)",
+                self.data_bits, self.channel_length
+            ));
+        }
+        if !(8_000..=192_000).contains(&self.sample_rate) {
+            return Err(format!("Sample rate must be 8000-192000 Hz, got {}", self.sample_rate));
+        }
+        if self.mck_enabled && !self.role.is_master() {
+            return Err("MCK output needs a master role".to_string());
+        }
+        // Only a clock the user gave can rule the rate out; an unknown one is checked by HAL_I2S_Init
+        if let (true, Some(kernel_clock)) = (self.role.is_master(), self.kernel_clock_hz) {
+            self.clock(kernel_clock)?;
+        }
+        Ok(())
+    }
+}
+
+/// Families with an SPI/I2S block in the HAL (L4 routes audio through SAI)
+pub fn supports_i2s(mcu: McuFamily) -> bool {
+    matches!(mcu, McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32H7 | McuFamily::STM32G4)
+}
+
+/// `HAL_RCCEx_GetPeriphCLKFreq` body returning the kernel clock of SPI/I2S `instance`
+fn kernel_clock_query(mcu: McuFamily, instance: u8) -> String {
+    match mcu {
+        McuFamily::STM32F1 => format!("    return HAL_RCCEx_GetPeriphCLKFreq(RCC_PERIPHCLK_I2S{});\n", instance),
+        McuFamily::STM32H7 => "    return HAL_RCCEx_GetPeriphCLKFreq(RCC_PERIPHCLK_SPI123);\n".to_string(),
+        McuFamily::STM32F4 => format!(
+            // F412/F413/F446 clock the APB1 and APB2 I2S blocks separately
+            "#if defined(RCC_PERIPHCLK_I2S_APB1)\n    return HAL_RCCEx_GetPeriphCLKFreq({});\n#else\n    return HAL_RCCEx_GetPeriphCLKFreq(RCC_PERIPHCLK_I2S);\n#endif\n",
+            if matches!(instance, 2 | 3) { "RCC_PERIPHCLK_I2S_APB1" } else { "RCC_PERIPHCLK_I2S_APB2" }
+        ),
+        _ => "    return HAL_RCCEx_GetPeriphCLKFreq(RCC_PERIPHCLK_I2S);\n".to_string(),
+    }
+}
+
+fn hal_header(mcu: McuFamily) -> &'static str {
+    match mcu {
+        McuFamily::STM32F1 => "stm32f1xx_hal.h",
+        McuFamily::STM32H7 => "stm32h7xx_hal.h",
+        McuFamily::STM32G4 => "stm32g4xx_hal.h",
+        _ => "stm32f4xx_hal.h",
+    }
+}
+
+/// STM32F4 DMA stream and channel serving an SPI/I2S instance in one direction
+fn f4_dma_stream(instance: u8, transmit: bool) -> Option<(&'static str, &'static str, &'static str)> {
+    match (instance, transmit) {
+        (1, true) => Some(("DMA2_Stream3", "DMA_CHANNEL_3", "DMA2_Stream3_IRQn")),
+        (1, false) => Some(("DMA2_Stream0", "DMA_CHANNEL_3", "DMA2_Stream0_IRQn")),
+        (2, true) => Some(("DMA1_Stream4", "DMA_CHANNEL_0", "DMA1_Stream4_IRQn")),
+        (2, false) => Some(("DMA1_Stream3", "DMA_CHANNEL_0", "DMA1_Stream3_IRQn")),
+        (3, true) => Some(("DMA1_Stream5", "DMA_CHANNEL_0", "DMA1_Stream5_IRQn")),
+        (3, false) => Some(("DMA1_Stream0", "DMA_CHANNEL_0", "DMA1_Stream0_IRQn")),
+        _ => None,
+    }
+}
+
+/// Generate I2S driver code
+pub fn generate_i2s_driver(config: &I2sConfig, mcu: McuFamily) -> DriverOutput {
+    let instance = config.spi_instance();
+    let number = config.instance_number().unwrap_or(2);
+    let handle = format!("hi2s{}", number);
+    let transmit = config.role.is_transmit();
+    let direction = if transmit { "TX" } else { "RX" };
+    let sample_rate = config.sample_rate;
+    let data_bits = config.data_bits;
+    let channel_length = config.channel_length;
+    let standard_name = config.standard.name();
+    let sample_type = if data_bits == 16 { "int16_t" } else { "int32_t" };
+
+    let fixed_clock = config.kernel_clock_hz.and_then(|hz| Some((hz, config.clock(hz).ok()?)));
+    let clock_defines = match fixed_clock {
+        Some((kernel_clock, clock)) if config.role.is_master() => format!(
+            "#define I2S_KERNEL_CLOCK_HZ {kernel_clock}U\n#define I2S_DIVIDER         {divider}U  // ODD = {odd}\n#define I2S_ACTUAL_FS_HZ    {actual}U  // {ppm:+} ppm\n#define I2S_MCK_HZ          {mck}U\n",
+            divider = clock.divider,
+            odd = clock.odd as u8,
+            actual = clock.actual_sample_rate,
+            ppm = clock.error_ppm(sample_rate),
+            mck = clock.mck_hz,
+        ),
+        None if config.role.is_master() => {
+            "// HAL_I2S_Init derives I2SDIV from i2s_kernel_clock_hz() and fails if no divider fits\n".to_string()
+        }
+        _ => "// Bit clock is supplied by the external master\n".to_string(),
+    };
+    let (clock_prototype, clock_function) = if config.role.is_master() {
+        (
+            "\n// I2S kernel clock as configured in RCC, for checking the achieved sample rate\nuint32_t i2s_kernel_clock_hz(void);\n",
+            format!("\nuint32_t i2s_kernel_clock_hz(void) {{\n{}}}\n", kernel_clock_query(mcu, number)),
+        )
+    } else {
+        ("", String::new())
+    };
+
+    let (buffer_qualifier, call) = if transmit { ("const ", "Transmit") } else { ("", "Receive") };
+    // 24/32-bit samples move as two half-words, most significant first
+    let pack_macro = if data_bits == 16 {
+        ""
+    } else {
+        "\n// Swap half-words of a 32-bit sample to/from wire order\n#define I2S_PACK(x) ((int32_t)(((uint32_t)(x) << 16) | ((uint32_t)(x) >> 16)))\n"
+    };
+    let io_prototypes = if config.dma_enabled {
+        format!(r#"// Start circular DMA over a buffer of 2 * I2S_HALF_FRAMES stereo frames
+HAL_StatusTypeDef i2s_start(void);
+HAL_StatusTypeDef i2s_stop(void);
+
+// Called from DMA interrupt context with the half that is free to {verb}
+void i2s_buffer_callback({sample_type} *half, uint32_t frames);
+"#,
+            verb = if transmit { "refill" } else { "process" },
+        )
+    } else {
+        format!("HAL_StatusTypeDef i2s_{lower}({buffer_qualifier}{sample_type} *frames, uint32_t count, uint32_t timeout_ms);\n",
+            lower = call.to_lowercase())
+    };
+
+    let header = format!(r#"/**
+ * I2S Audio Driver - {instance} {direction}
+ * Auto-generated by NeuroBench
+ * {standard_name}, {sample_rate} Hz, {data_bits}-bit data in {channel_length}-bit slots
+ */
+
+#ifndef I2S_DRIVER_H
+#define I2S_DRIVER_H
+
+#include "{hal}"
+#include <stdint.h>
+
+#define I2S_SAMPLE_RATE     {sample_rate}U
+#define I2S_CHANNELS        2
+#ifndef I2S_HALF_FRAMES
+#define I2S_HALF_FRAMES     256
+#endif
+{clock_defines}{pack_macro}
+extern I2S_HandleTypeDef {handle};
+
+void i2s_init(void);
+{io_prototypes}{clock_prototype}
+#endif // I2S_DRIVER_H
+"#,
+        hal = hal_header(mcu),
+    );
+
+    let family_fields = match mcu {
+        McuFamily::STM32F4 => format!(
+            "    {handle}.Init.ClockSource = I2S_CLOCK_PLL;\n    {handle}.Init.FullDuplexMode = I2S_FULLDUPLEXMODE_DISABLE;\n"
+        ),
+        McuFamily::STM32H7 => format!(
+            "    {handle}.Init.FirstBit = I2S_FIRSTBIT_MSB;\n    {handle}.Init.WSInversion = I2S_WS_INVERSION_DISABLE;\n    {handle}.Init.Data24BitAlignment = I2S_DATA_24BIT_ALIGNMENT_RIGHT;\n    {handle}.Init.MasterKeepIOState = I2S_MASTER_KEEP_IO_STATE_ENABLE;\n"
+        ),
+        _ => String::new(),
+    };
+    let mck = if config.mck_enabled { "I2S_MCLKOUTPUT_ENABLE" } else { "I2S_MCLKOUTPUT_DISABLE" };
+
+    let dma_setup = match (config.dma_enabled, mcu) {
+        (true, McuFamily::STM32F4) => {
+            let (stream, channel, irq) = f4_dma_stream(number, transmit).unwrap_or(("DMA1_Stream4", "DMA_CHANNEL_0", "DMA1_Stream4_IRQn"));
+            let (dma_direction, link) = if transmit { ("DMA_MEMORY_TO_PERIPH", "hdmatx") } else { ("DMA_PERIPH_TO_MEMORY", "hdmarx") };
+            let dma_clock = &stream[..4];
+            format!(r#"
+DMA_HandleTypeDef hdma_i2s;
+
+static void i2s_dma_init(void) {{
+    __HAL_RCC_{dma_clock}_CLK_ENABLE();
+
+    hdma_i2s.Instance = {stream};
+    hdma_i2s.Init.Channel = {channel};
+    hdma_i2s.Init.Direction = {dma_direction};
+    hdma_i2s.Init.PeriphInc = DMA_PINC_DISABLE;
+    hdma_i2s.Init.MemInc = DMA_MINC_ENABLE;
+    hdma_i2s.Init.PeriphDataAlignment = DMA_PDATAALIGN_HALFWORD;
+    hdma_i2s.Init.MemDataAlignment = DMA_MDATAALIGN_HALFWORD;
+    hdma_i2s.Init.Mode = DMA_CIRCULAR;
+    hdma_i2s.Init.Priority = DMA_PRIORITY_HIGH;
+    hdma_i2s.Init.FIFOMode = DMA_FIFOMODE_DISABLE;
+    HAL_DMA_Init(&hdma_i2s);
+    __HAL_LINKDMA(&{handle}, {link}, hdma_i2s);
+
+    HAL_NVIC_SetPriority({irq}, 5, 0);
+    HAL_NVIC_EnableIRQ({irq});
+}}
+
+void {isr}(void) {{
+    HAL_DMA_IRQHandler(&hdma_i2s);
+}}
+"#,
+                isr = irq.replace("_IRQn", "_IRQHandler"),
+            )
+        }
+        (true, _) => "\n// DMA request routing and the stream IRQ handler come from HAL_I2S_MspInit (CubeMX)\nstatic void i2s_dma_init(void) {\n}\n".to_string(),
+        (false, _) => String::new(),
+    };
+
+    let io = if config.dma_enabled {
+        let half = if transmit { "TxHalfCplt" } else { "RxHalfCplt" };
+        let full = if transmit { "TxCplt" } else { "RxCplt" };
+        format!(r#"
+static {sample_type} i2s_buffer[2 * I2S_HALF_FRAMES * I2S_CHANNELS];
+
+HAL_StatusTypeDef i2s_start(void) {{
+    // Size counts samples, whatever the data width
+    return HAL_I2S_{call}_DMA(&{handle}, (uint16_t *)i2s_buffer, 2 * I2S_HALF_FRAMES * I2S_CHANNELS);
+}}
+
+HAL_StatusTypeDef i2s_stop(void) {{
+    return HAL_I2S_DMAStop(&{handle});
+}}
+
+void HAL_I2S_{half}Callback(I2S_HandleTypeDef *hi2s) {{
+    if (hi2s == &{handle}) {{
+        i2s_buffer_callback(&i2s_buffer[0], I2S_HALF_FRAMES);
+    }}
+}}
+
+void HAL_I2S_{full}Callback(I2S_HandleTypeDef *hi2s) {{
+    if (hi2s == &{handle}) {{
+        i2s_buffer_callback(&i2s_buffer[I2S_HALF_FRAMES * I2S_CHANNELS], I2S_HALF_FRAMES);
+    }}
+}}
+
+__attribute__((weak)) void i2s_buffer_callback({sample_type} *half, uint32_t frames) {{
+    (void)half;
+    (void)frames;
+}}
+"#)
+    } else {
+        format!(r#"
+HAL_StatusTypeDef i2s_{lower}({buffer_qualifier}{sample_type} *frames, uint32_t count, uint32_t timeout_ms) {{
+    return HAL_I2S_{call}(&{handle}, (uint16_t *)frames, (uint16_t)(count * I2S_CHANNELS), timeout_ms);
+}}
+"#,
+            lower = call.to_lowercase(),
+        )
+    };
+    let dma_init_call = if config.dma_enabled { "    i2s_dma_init();\n" } else { "" };
+
+    let source = format!(r#"/**
+ * I2S Audio Driver - {instance} {direction}
+ * Auto-generated by NeuroBench
+ */
+
+#include "i2s_driver.h"
+
+I2S_HandleTypeDef {handle};
+{dma_setup}
+void i2s_init(void) {{
+    __HAL_RCC_{instance}_CLK_ENABLE();
+    {handle}.Instance = {instance};
+    {handle}.Init.Mode = {mode};
+    {handle}.Init.Standard = {standard};
+    {handle}.Init.DataFormat = {format};
+    {handle}.Init.MCLKOutput = {mck};
+    {handle}.Init.AudioFreq = I2S_SAMPLE_RATE;
+    {handle}.Init.CPOL = I2S_CPOL_LOW;
+{family_fields}    if (HAL_I2S_Init(&{handle}) != HAL_OK) {{
+        Error_Handler();
+    }}
+{dma_init_call}}}
+{clock_function}{io}"#,
+        mode = config.role.hal_mode(),
+        standard = config.standard.hal_standard(),
+        format = config.hal_data_format().unwrap_or("I2S_DATAFORMAT_16B"),
+    );
+
+    let example = if config.dma_enabled && transmit {
+        format!(r#"/**
+ * I2S Example
+ * Plays a 1 kHz tone through the DAC
+ */
+
+#include "i2s_driver.h"
+#include <math.h>
+
+void i2s_buffer_callback({sample_type} *half, uint32_t frames) {{
+    static float phase = 0.0f;
+    const float step = 2.0f * 3.14159265f * 1000.0f / I2S_SAMPLE_RATE;
+    for (uint32_t i = 0; i < frames; i++) {{
+        {sample_type} sample = ({sample_type})(sinf(phase) * 0.5f * {full_scale});
+        half[2 * i] = {store};      // Left
+        half[2 * i + 1] = {store};  // Right
+        phase += step;
+        if (phase > 2.0f * 3.14159265f) {{
+            phase -= 2.0f * 3.14159265f;
+        }}
+    }}
+}}
+
+int main(void) {{
+    HAL_Init();
+    i2s_init();
+    i2s_start();
+
+    while (1) {{
+    }}
+}}
+"#,
+            full_scale = if data_bits == 16 { "32767.0f" } else { "2147483647.0f" },
+            store = if data_bits == 16 { "sample" } else { "I2S_PACK(sample)" },
+        )
+    } else if config.dma_enabled {
+        format!(r#"/**
+ * I2S Example
+ * Tracks the peak level from a microphone/ADC
+ */
+
+#include "i2s_driver.h"
+#include <stdlib.h>
+
+volatile uint32_t peak_level;
+
+void i2s_buffer_callback({sample_type} *half, uint32_t frames) {{
+    uint32_t peak = 0;
+    for (uint32_t i = 0; i < frames * I2S_CHANNELS; i++) {{
+        uint32_t level = (uint32_t)labs((long){load});
+        if (level > peak) {{
+            peak = level;
+        }}
+    }}
+    peak_level = peak;
+}}
+
+int main(void) {{
+    HAL_Init();
+    i2s_init();
+    i2s_start();
+
+    while (1) {{
+    }}
+}}
+"#,
+            load = if data_bits == 16 { "half[i]" } else { "I2S_PACK(half[i])" },
+        )
+    } else {
+        format!(r#"/**
+ * I2S Example
+ * Blocking transfer of one block of frames
+ */
+
+#include "i2s_driver.h"
+
+static {sample_type} frames[I2S_HALF_FRAMES * I2S_CHANNELS];
+
+int main(void) {{
+    HAL_Init();
+    i2s_init();
+
+    while (1) {{
+        i2s_{lower}(frames, I2S_HALF_FRAMES, 100);
+    }}
+}}
+"#,
+            lower = call.to_lowercase(),
+        )
+    };
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: Some(example),
+        peripheral_type: PeripheralType::I2S,
+    }
+}
@@ -0,0 +1,409 @@
+// MicroPython Driver Generator
+// Maps the C driver configs onto machine.UART / SPI / I2C / Pin / ADC modules for prototyping
+
+use super::mcu::McuFamily;
+use super::soft_i2c::parse_pin;
+use super::templates::*;
+
+/// Peripheral a MicroPython driver is generated for, with its C driver config
+#[derive(Debug, Clone)]
+pub enum MicroPythonPeripheral {
+    Uart(UartConfig),
+    Spi(SpiConfig),
+    I2c(I2cConfig),
+    Gpio(GpioConfig),
+    Adc(AdcConfig),
+}
+
+impl MicroPythonPeripheral {
+    /// Parse a peripheral name ("uart", "spi", ...) and its JSON config
+    pub fn from_json(peripheral: &str, config: serde_json::Value) -> Result<Self, String> {
+        fn parse<T: serde::de::DeserializeOwned>(config: serde_json::Value) -> Result<T, String> {
+            serde_json::from_value(config).map_err(|e| format!("Invalid config: {}", e))
+        }
+        match peripheral.to_lowercase().as_str() {
+            "uart" | "usart" => Ok(Self::Uart(parse(config)?)),
+            "spi" => Ok(Self::Spi(parse(config)?)),
+            "i2c" => Ok(Self::I2c(parse(config)?)),
+            "gpio" | "pin" => Ok(Self::Gpio(parse(config)?)),
+            "adc" => Ok(Self::Adc(parse(config)?)),
+            other => Err(format!("No MicroPython mapping for peripheral: {}", other)),
+        }
+    }
+
+    fn peripheral_type(&self) -> PeripheralType {
+        match self {
+            Self::Uart(_) => PeripheralType::UART,
+            Self::Spi(_) => PeripheralType::SPI,
+            Self::I2c(_) => PeripheralType::I2C,
+            Self::Gpio(_) => PeripheralType::GPIO,
+            Self::Adc(_) => PeripheralType::ADC,
+        }
+    }
+}
+
+fn is_esp32(mcu: McuFamily) -> bool {
+    matches!(mcu, McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3)
+}
+
+/// STM32 port buses have fixed pins, the machine module takes no pin arguments
+fn is_stm32(mcu: McuFamily) -> bool {
+    matches!(mcu, McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32H7 | McuFamily::STM32L4 | McuFamily::STM32G4)
+}
+
+/// Bus number from an instance name ("USART2" -> 2), clamped to the port's valid ids
+fn bus_id(instance: &str, mcu: McuFamily, peripheral: &MicroPythonPeripheral) -> u8 {
+    let digits: String = instance.chars().rev().take_while(|c| c.is_ascii_digit()).collect();
+    let n: u8 = digits.chars().rev().collect::<String>().parse().unwrap_or(1);
+    match (mcu, peripheral) {
+        (McuFamily::RP2040, _) => n.min(1),
+        // UART0 carries the REPL; SPI 1/2 are HSPI/VSPI
+        (m, MicroPythonPeripheral::Uart(_) | MicroPythonPeripheral::Spi(_)) if is_esp32(m) => n.clamp(1, 2),
+        (m, _) if is_esp32(m) => n.min(1),
+        _ => n.max(1),
+    }
+}
+
+/// `Pin(...)` constructor argument for a pin name
+fn pin_arg(mcu: McuFamily, name: &str) -> Result<String, String> {
+    let pin = parse_pin(mcu, name).ok_or_else(|| format!("Invalid pin for {}: {}", mcu.display_name(), name))?;
+    Ok(if is_stm32(mcu) {
+        format!("\"{}{}\"", (b'A' + pin.port) as char, pin.pin)
+    } else {
+        pin.pin.to_string()
+    })
+}
+
+/// Configured pin, or the board default for the bus
+fn pin_or(mcu: McuFamily, name: &Option<String>, default: u8) -> Result<String, String> {
+    match name {
+        Some(name) => pin_arg(mcu, name),
+        None => Ok(default.to_string()),
+    }
+}
+
+/// Default (tx, rx) / (sck, mosi, miso, cs) / (sda, scl) pins for a bus
+fn default_pins(mcu: McuFamily, peripheral: &MicroPythonPeripheral, id: u8) -> Vec<u8> {
+    match (mcu, peripheral) {
+        (m, _) if is_stm32(m) => vec![],
+        (McuFamily::RP2040, MicroPythonPeripheral::Uart(_)) => if id == 0 { vec![0, 1] } else { vec![4, 5] },
+        (McuFamily::RP2040, MicroPythonPeripheral::Spi(_)) => if id == 0 { vec![18, 19, 16, 17] } else { vec![10, 11, 12, 13] },
+        (McuFamily::RP2040, MicroPythonPeripheral::I2c(_)) => if id == 0 { vec![4, 5] } else { vec![6, 7] },
+        (McuFamily::ESP32C3, MicroPythonPeripheral::Uart(_)) => vec![4, 5],
+        (McuFamily::ESP32C3, MicroPythonPeripheral::Spi(_)) => vec![6, 7, 2, 10],
+        (McuFamily::ESP32C3, MicroPythonPeripheral::I2c(_)) => vec![8, 9],
+        (McuFamily::ESP32S3, MicroPythonPeripheral::Uart(_)) => vec![17, 18],
+        (McuFamily::ESP32S3, MicroPythonPeripheral::Spi(_)) => vec![12, 11, 13, 10],
+        (McuFamily::ESP32S3, MicroPythonPeripheral::I2c(_)) => vec![8, 9],
+        (_, MicroPythonPeripheral::Uart(_)) => if id == 1 { vec![4, 5] } else { vec![17, 16] },
+        (_, MicroPythonPeripheral::Spi(_)) => if id == 1 { vec![14, 13, 12, 15] } else { vec![18, 23, 19, 5] },
+        (_, MicroPythonPeripheral::I2c(_)) => vec![21, 22],
+        _ => vec![],
+    }
+}
+
+fn header(title: &str, mcu: McuFamily) -> String {
+    format!("\"\"\"\n{} - Generated by NeuroBench\nMicroPython driver for {}\n\"\"\"\n", title, mcu.display_name())
+}
+
+/// Generate a MicroPython driver module and a `main.py` usage example
+pub fn generate_micropython_driver(peripheral: &MicroPythonPeripheral, mcu: McuFamily) -> Result<DriverOutput, String> {
+    if !is_esp32(mcu) && !is_stm32(mcu) && mcu != McuFamily::RP2040 {
+        return Err(format!("No MicroPython port for {}", mcu.display_name()));
+    }
+
+    let (source, example) = match peripheral {
+        MicroPythonPeripheral::Uart(config) => uart_driver(config, mcu, peripheral)?,
+        MicroPythonPeripheral::Spi(config) => spi_driver(config, mcu, peripheral)?,
+        MicroPythonPeripheral::I2c(config) => i2c_driver(config, mcu, peripheral)?,
+        MicroPythonPeripheral::Gpio(config) => gpio_driver(config, mcu)?,
+        MicroPythonPeripheral::Adc(config) => adc_driver(config, mcu)?,
+    };
+
+    Ok(DriverOutput {
+        header_file: None,
+        source_file: source,
+        example_file: Some(example),
+        peripheral_type: peripheral.peripheral_type(),
+    })
+}
+
+fn uart_driver(config: &UartConfig, mcu: McuFamily, peripheral: &MicroPythonPeripheral) -> Result<(String, String), String> {
+    let id = bus_id(&config.instance, mcu, peripheral);
+    let parity = match config.parity {
+        UartParity::None => "None",
+        UartParity::Even => "0",
+        UartParity::Odd => "1",
+    };
+    let stop = match config.stop_bits {
+        StopBits::One | StopBits::OnePointFive => 1,
+        StopBits::Two => 2,
+    };
+    let mut args = format!("{}, baudrate={}, bits={}, parity={}, stop={}", id, config.baud_rate, config.data_bits, parity, stop);
+    if !is_stm32(mcu) {
+        let pins = default_pins(mcu, peripheral, id);
+        let tx = pin_or(mcu, &config.tx_pin, pins[0])?;
+        let rx = pin_or(mcu, &config.rx_pin, pins[1])?;
+        args.push_str(&format!(", tx=Pin({}), rx=Pin({})", tx, rx));
+    }
+    if config.flow_control {
+        args.push_str(", flow=UART.RTS | UART.CTS");
+    }
+
+    let source = format!(r#"{header}
+from machine import UART, Pin
+
+uart = UART({args})
+
+
+def write(data):
+    """Send bytes or str, returns the number of bytes written"""
+    return uart.write(data)
+
+
+def read(nbytes=None):
+    """Read what has arrived (up to nbytes), None if nothing is waiting"""
+    return uart.read(nbytes) if nbytes else uart.read()
+
+
+def readline():
+    return uart.readline()
+
+
+def available():
+    """Number of bytes waiting in the receive buffer"""
+    return uart.any()
+"#,
+        header = header("UART driver", mcu),
+    );
+    let example = r#"import time
+import uart_driver
+
+uart_driver.write("hello\r\n")
+while True:
+    if uart_driver.available():
+        line = uart_driver.readline()
+        if line:
+            uart_driver.write(line)  # echo
+    time.sleep_ms(10)
+"#.to_string();
+    Ok((source, example))
+}
+
+fn spi_driver(config: &SpiConfig, mcu: McuFamily, peripheral: &MicroPythonPeripheral) -> Result<(String, String), String> {
+    let id = bus_id(&config.instance, mcu, peripheral);
+    let (polarity, phase) = match config.mode {
+        SpiMode::Mode0 => (0, 0),
+        SpiMode::Mode1 => (0, 1),
+        SpiMode::Mode2 => (1, 0),
+        SpiMode::Mode3 => (1, 1),
+    };
+    let first_bit = match config.bit_order {
+        BitOrder::MsbFirst => "SPI.MSB",
+        BitOrder::LsbFirst => "SPI.LSB",
+    };
+    let mut args = format!(
+        "{}, baudrate={}, polarity={}, phase={}, bits={}, firstbit={}",
+        id, config.clock_hz, polarity, phase, config.data_bits, first_bit
+    );
+    let pins = default_pins(mcu, peripheral, id);
+    if !is_stm32(mcu) {
+        let sck = pin_or(mcu, &config.sck_pin, pins[0])?;
+        let mosi = pin_or(mcu, &config.mosi_pin, pins[1])?;
+        let miso = pin_or(mcu, &config.miso_pin, pins[2])?;
+        args.push_str(&format!(", sck=Pin({}), mosi=Pin({}), miso=Pin({})", sck, mosi, miso));
+    }
+    let cs = match (&config.cs_pin, pins.get(3)) {
+        (Some(name), _) => pin_arg(mcu, name)?,
+        (None, Some(default)) => default.to_string(),
+        (None, None) => "\"A4\"".to_string(),
+    };
+
+    let source = format!(r#"{header}
+from machine import SPI, Pin
+
+spi = SPI({args})
+cs = Pin({cs}, Pin.OUT, value=1)
+
+
+def transfer(data):
+    """Full-duplex transfer, returns the bytes clocked in"""
+    rx = bytearray(len(data))
+    cs.value(0)
+    try:
+        spi.write_readinto(data, rx)
+    finally:
+        cs.value(1)
+    return rx
+
+
+def write(data):
+    cs.value(0)
+    try:
+        spi.write(data)
+    finally:
+        cs.value(1)
+
+
+def read(nbytes, fill=0x00):
+    cs.value(0)
+    try:
+        return spi.read(nbytes, fill)
+    finally:
+        cs.value(1)
+"#,
+        header = header("SPI driver", mcu),
+    );
+    let example = r#"import spi_driver
+
+# Read a JEDEC ID (e.g. SPI flash): command 0x9F, then 3 bytes
+rx = spi_driver.transfer(bytes([0x9F, 0, 0, 0]))
+print("JEDEC ID:", rx[1:].hex())
+"#.to_string();
+    Ok((source, example))
+}
+
+fn i2c_driver(config: &I2cConfig, mcu: McuFamily, peripheral: &MicroPythonPeripheral) -> Result<(String, String), String> {
+    let id = bus_id(&config.instance, mcu, peripheral);
+    let freq = match config.speed {
+        I2cSpeed::Standard => 100_000,
+        I2cSpeed::Fast => 400_000,
+        I2cSpeed::FastPlus => 1_000_000,
+    };
+    let mut args = id.to_string();
+    if !is_stm32(mcu) {
+        let pins = default_pins(mcu, peripheral, id);
+        let sda = pin_or(mcu, &config.sda_pin, pins[0])?;
+        let scl = pin_or(mcu, &config.scl_pin, pins[1])?;
+        args.push_str(&format!(", sda=Pin({}), scl=Pin({})", sda, scl));
+    }
+    args.push_str(&format!(", freq={}", freq));
+    let address = config.address.map_or("None".to_string(), |a| format!("0x{:02X}", a));
+
+    let source = format!(r#"{header}
+from machine import I2C, Pin
+
+i2c = I2C({args})
+DEVICE_ADDR = {address}
+
+
+def scan():
+    """Addresses of the devices that ACK"""
+    return i2c.scan()
+
+
+def write_reg(reg, value, addr=DEVICE_ADDR):
+    i2c.writeto_mem(addr, reg, bytes([value]))
+
+
+def read_reg(reg, addr=DEVICE_ADDR):
+    return i2c.readfrom_mem(addr, reg, 1)[0]
+
+
+def read_regs(reg, nbytes, addr=DEVICE_ADDR):
+    return i2c.readfrom_mem(addr, reg, nbytes)
+"#,
+        header = header("I2C driver", mcu),
+    );
+    let example = r#"import i2c_driver
+
+devices = i2c_driver.scan()
+print("Found:", [hex(a) for a in devices])
+if devices:
+    addr = i2c_driver.DEVICE_ADDR or devices[0]
+    print("WHO_AM_I:", hex(i2c_driver.read_reg(0x0F, addr=addr)))
+"#.to_string();
+    Ok((source, example))
+}
+
+fn gpio_driver(config: &GpioConfig, mcu: McuFamily) -> Result<(String, String), String> {
+    let name = if is_stm32(mcu) { format!("P{}{}", config.port.trim_start_matches("GPIO"), config.pin) } else { config.pin.to_string() };
+    let pin = pin_arg(mcu, &name)?;
+    let pull = match config.pull {
+        GpioPull::None => "None",
+        GpioPull::Up | GpioPull::PullUp => "Pin.PULL_UP",
+        GpioPull::Down | GpioPull::PullDown => "Pin.PULL_DOWN",
+    };
+    let (ctor, body, example) = match config.mode {
+        GpioMode::Output | GpioMode::AlternateFunction => (
+            format!("Pin({}, Pin.OUT, value={})", pin, config.initial_state.unwrap_or(false) as u8),
+            r#"
+
+def on():
+    pin.value(1)
+
+
+def off():
+    pin.value(0)
+
+
+def toggle():
+    pin.value(not pin.value())
+"#,
+            "import time\nimport gpio_driver\n\nwhile True:\n    gpio_driver.toggle()\n    time.sleep_ms(500)\n",
+        ),
+        GpioMode::Input | GpioMode::Analog => (
+            format!("Pin({}, Pin.IN, {})", pin, pull),
+            r#"
+
+def value():
+    return pin.value()
+
+
+def on_change(handler, trigger=Pin.IRQ_FALLING | Pin.IRQ_RISING):
+    """Call handler(pin) from the pin interrupt"""
+    pin.irq(trigger=trigger, handler=handler)
+"#,
+            "import gpio_driver\n\ngpio_driver.on_change(lambda p: print(\"pin:\", p.value()))\n",
+        ),
+    };
+    let source = format!("{}\nfrom machine import Pin\n\npin = {}\n{}", header("GPIO driver", mcu), ctor, body);
+    Ok((source, example.to_string()))
+}
+
+fn adc_driver(config: &AdcConfig, mcu: McuFamily) -> Result<(String, String), String> {
+    // machine.ADC takes the GPIO wired to each channel
+    let pins: Vec<String> = config.channels.iter().map(|&ch| match mcu {
+        McuFamily::RP2040 => Ok(if ch == 4 { "4".to_string() } else { format!("Pin({})", 26 + ch as u32) }),
+        McuFamily::ESP32 => [36u8, 37, 38, 39, 32, 33, 34, 35].get(ch as usize)
+            .map(|p| format!("Pin({})", p))
+            .ok_or_else(|| format!("ESP32 ADC1 has channels 0-7, got {}", ch)),
+        McuFamily::ESP32S3 => Ok(format!("Pin({})", ch as u32 + 1)),
+        McuFamily::ESP32C3 => Ok(format!("Pin({})", ch)),
+        _ => Ok(format!("Pin(\"A{}\")", ch)),
+    }).collect::<Result<_, String>>()?;
+    if pins.is_empty() {
+        return Err("ADC config needs at least one channel".to_string());
+    }
+    let atten = if is_esp32(mcu) { ", atten=ADC.ATTN_11DB" } else { "" };
+
+    let source = format!(r#"{header}
+from machine import ADC, Pin
+
+VREF = 3.3
+channels = [
+{channels}
+]
+
+
+def read_raw(index=0):
+    """16-bit reading, scaled up from the {bits}-bit converter"""
+    return channels[index].read_u16()
+
+
+def read_voltage(index=0):
+    return read_raw(index) * VREF / 65535
+"#,
+        header = header("ADC driver", mcu),
+        channels = pins.iter().map(|p| format!("    ADC({}{}),", p, atten)).collect::<Vec<_>>().join("\n"),
+        bits = config.resolution_bits,
+    );
+    let example = r#"import time
+import adc_driver
+
+while True:
+    print("{:.3f} V".format(adc_driver.read_voltage(0)))
+    time.sleep_ms(200)
+"#.to_string();
+    Ok((source, example))
+}
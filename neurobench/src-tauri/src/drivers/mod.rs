@@ -2,11 +2,27 @@
 // Generates embedded peripheral drivers for various MCU architectures
 
 pub mod templates;
+pub mod hal_abstraction;
 pub mod gpio;
 pub mod uart;
 pub mod spi;
 pub mod i2c;
+pub mod i2s;
 pub mod can;
+pub mod usb;
+pub mod usb_dfu;
+pub mod sdmmc;
+pub mod sensors;
+pub mod display;
+pub mod crc;
+pub mod debounce;
+pub mod dma_buffer;
+pub mod framing;
+pub mod soft_i2c;
+pub mod onewire;
+pub mod micropython;
+pub mod encoder;
+pub mod motor;
 pub mod modbus;
 pub mod pins;
 pub mod rtos;
@@ -19,6 +35,7 @@ pub mod rtos_gen;
 pub mod wireless;
 pub mod dsp;
 pub mod security;
+pub mod power;
 pub mod export;
 
 pub use generator::*;
@@ -1,6 +1,7 @@
 // Modbus Protocol Stack Generator
 // Generates Modbus RTU/TCP drivers for industrial automation
 
+use super::hal_abstraction;
 use super::templates::*;
 
 /// Modbus configuration
@@ -24,6 +25,7 @@ pub fn generate_modbus_driver(config: &ModbusConfig, _arch: &McuArch, lang: &Dri
         DriverLanguage::C => generate_modbus_c(config),
         DriverLanguage::Cpp => generate_modbus_cpp(config),
         DriverLanguage::Rust => generate_modbus_rust(config),
+        DriverLanguage::Portable => hal_abstraction::not_portable("Modbus", PeripheralType::Modbus),
     }
 }
 
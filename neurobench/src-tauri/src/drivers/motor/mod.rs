@@ -0,0 +1,4 @@
+// Motor Control Module
+// Step/direction stepper drivers
+
+pub mod stepper;
@@ -0,0 +1,547 @@
+// Stepper Motor Driver Generator
+// Generates a step/direction driver whose STEP pulses come from a timer PWM output
+
+use crate::drivers::encoder::{is_stm32, timer_af};
+use crate::drivers::mcu::McuFamily;
+use crate::drivers::soft_i2c::{gpio_include, parse_pin, PinRef};
+use crate::drivers::templates::*;
+use serde::{Deserialize, Serialize};
+
+/// Timer counter clock the step periods are counted in
+const TICK_HZ: u32 = 1_000_000;
+
+/// Stepper configuration for a step/direction driver chip (A4988, DRV8825, TMC2209, ...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepperConfig {
+    /// Must be a PWM channel output of `timer_instance`
+    pub step_pin: String,
+    pub dir_pin: String,
+    /// Active-low enable input of the driver chip
+    pub en_pin: Option<String>,
+    pub timer_instance: String,
+    /// Fastest step rate, in microsteps per second
+    pub max_freq_hz: u32,
+    pub microstepping: u8,
+    /// Full steps per revolution, usually 200
+    pub steps_per_rev: u32,
+    /// Home switch, active low with pull-up; stops moves toward home when hit
+    #[serde(default)]
+    pub limit_pin: Option<String>,
+}
+
+impl Default for StepperConfig {
+    fn default() -> Self {
+        Self {
+            step_pin: "PA6".to_string(),
+            dir_pin: "PA5".to_string(),
+            en_pin: Some("PA4".to_string()),
+            timer_instance: "TIM3".to_string(),
+            max_freq_hz: 20_000,
+            microstepping: 16,
+            steps_per_rev: 200,
+            limit_pin: None,
+        }
+    }
+}
+
+impl StepperConfig {
+    pub fn validate(&self, mcu: McuFamily) -> Result<(), String> {
+        if !is_stm32(mcu) {
+            return Err(format!("Stepper step generation needs an STM32 timer; {} is not supported", mcu.display_name()));
+        }
+        let timer = self.timer_instance.to_uppercase();
+        if update_irq(&timer, mcu).is_none() {
+            return Err(format!("{} cannot generate step pulses; use TIM1-TIM5 or TIM8", self.timer_instance));
+        }
+
+        let mut pins = vec![("STEP", &self.step_pin), ("DIR", &self.dir_pin)];
+        pins.extend(self.en_pin.iter().map(|pin| ("EN", pin)));
+        pins.extend(self.limit_pin.iter().map(|pin| ("limit", pin)));
+        let mut parsed = Vec::new();
+        for (name, pin) in pins {
+            let p = parse_pin(mcu, pin)
+                .ok_or_else(|| format!("Invalid {} pin for {}: {}", name, mcu.display_name(), pin))?;
+            if parsed.contains(&p) {
+                return Err(format!("{} pin {} is already used", name, pin));
+            }
+            parsed.push(p);
+        }
+        if timer_channel(&timer, &self.step_pin).is_none() {
+            return Err(format!("{} is not a {} channel output", self.step_pin, timer));
+        }
+
+        let max_freq = TICK_HZ / 4;
+        if self.max_freq_hz < min_freq_hz() || self.max_freq_hz > max_freq {
+            return Err(format!("Max step frequency must be {}-{} Hz, got {}", min_freq_hz(), max_freq, self.max_freq_hz));
+        }
+        if !self.microstepping.is_power_of_two() {
+            return Err(format!("Microstepping must be 1, 2, 4, ... 128, got {}", self.microstepping));
+        }
+        if self.steps_per_rev == 0 {
+            return Err("Steps per revolution must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Slowest step rate a 16-bit auto-reload allows at the tick rate
+fn min_freq_hz() -> u32 {
+    TICK_HZ / 65536 + 1
+}
+
+/// Channel of `timer` on `pin`, using the default (non-remapped) pin assignment
+fn timer_channel(timer: &str, pin: &str) -> Option<u8> {
+    let channels: &[(&[&str], u8)] = match timer {
+        "TIM1" => &[(&["PA8", "PE9"], 1), (&["PA9", "PE11"], 2), (&["PA10", "PE13"], 3), (&["PA11", "PE14"], 4)],
+        "TIM2" => &[(&["PA0", "PA5", "PA15"], 1), (&["PA1", "PB3"], 2), (&["PA2", "PB10"], 3), (&["PA3", "PB11"], 4)],
+        "TIM3" => &[(&["PA6", "PB4", "PC6"], 1), (&["PA7", "PB5", "PC7"], 2), (&["PB0", "PC8"], 3), (&["PB1", "PC9"], 4)],
+        "TIM4" => &[(&["PB6", "PD12"], 1), (&["PB7", "PD13"], 2), (&["PB8", "PD14"], 3), (&["PB9", "PD15"], 4)],
+        "TIM5" => &[(&["PA0"], 1), (&["PA1"], 2), (&["PA2"], 3), (&["PA3"], 4)],
+        "TIM8" => &[(&["PC6"], 1), (&["PC7"], 2), (&["PC8"], 3), (&["PC9"], 4)],
+        _ => return None,
+    };
+    let pin = pin.trim().to_uppercase();
+    channels.iter().find(|(pins, _)| pins.contains(&pin.as_str())).map(|(_, channel)| *channel)
+}
+
+/// Update interrupt of the timer; TIM1/TIM8 share theirs with another timer on most families
+fn update_irq(timer: &str, mcu: McuFamily) -> Option<String> {
+    let irq = match (timer, mcu) {
+        ("TIM2" | "TIM3" | "TIM4" | "TIM5", _) => format!("{}_IRQn", timer),
+        ("TIM1", McuFamily::STM32F1 | McuFamily::STM32H7) => "TIM1_UP_IRQn".to_string(),
+        ("TIM1", McuFamily::STM32F4) => "TIM1_UP_TIM10_IRQn".to_string(),
+        ("TIM1", _) => "TIM1_UP_TIM16_IRQn".to_string(),
+        ("TIM8", McuFamily::STM32F4 | McuFamily::STM32H7) => "TIM8_UP_TIM13_IRQn".to_string(),
+        ("TIM8", _) => "TIM8_UP_IRQn".to_string(),
+        _ => return None,
+    };
+    Some(irq)
+}
+
+/// APB bus of the timer and the C condition that its prescaler divides PCLK
+///
+/// TIM1 and TIM8 sit on APB2, the rest on APB1. STM32H7 keeps the APB
+/// prescalers in D2CFGR instead of CFGR.
+fn timer_bus(timer: &str, mcu: McuFamily) -> (u8, String) {
+    let bus = if matches!(timer, "TIM1" | "TIM8") { 2 } else { 1 };
+    let divided = match mcu {
+        McuFamily::STM32H7 => format!("(RCC->D2CFGR & RCC_D2CFGR_D2PPRE{bus}) != RCC_D2CFGR_D2PPRE{bus}_DIV1"),
+        _ => format!("(RCC->CFGR & RCC_CFGR_PPRE{bus}) != RCC_CFGR_PPRE{bus}_DIV1"),
+    };
+    (bus, divided)
+}
+
+/// Generate the stepper driver (STM32 HAL; call `validate` first)
+///
+/// Each timer period is one step: PWM mode 2 puts the pulse at the end of
+/// the period, and the update interrupt counts it and loads the next period
+/// into the preloaded ARR. Moves follow a trapezoidal profile using the
+/// integer step-period recurrence from Atmel AVR446, falling back to a
+/// triangle when the move is too short to reach cruise speed.
+pub fn generate_stepper_driver(config: &StepperConfig, mcu: McuFamily) -> DriverOutput {
+    let timer = config.timer_instance.to_uppercase();
+    let timer_lower = timer.to_lowercase();
+    let channel = timer_channel(&timer, &config.step_pin).unwrap_or(1);
+    let irq = update_irq(&timer, mcu).unwrap_or_else(|| format!("{}_IRQn", timer));
+    let irq_handler = format!("{}Handler", irq.trim_end_matches('n'));
+    let (apb, apb_divided) = timer_bus(&timer, mcu);
+    let pin = |name: &str| parse_pin(mcu, name).unwrap_or(PinRef { port: 0, pin: 0 });
+    let gpio = |p: PinRef| (format!("GPIO{}", (b'A' + p.port) as char), format!("GPIO_PIN_{}", p.pin));
+
+    let steps_per_rev = config.steps_per_rev;
+    let microsteps = config.microstepping;
+    let max_freq = config.max_freq_hz;
+    let step_pin = &config.step_pin;
+    let dir_pin = &config.dir_pin;
+    let has_limit = config.limit_pin.is_some();
+
+    let header = format!(r#"/**
+ * Stepper Motor Driver
+ * Auto-generated by NeuroBench
+ * STEP: {step_pin} ({timer} CH{channel}), DIR: {dir_pin}, {steps_per_rev} steps/rev at 1/{microsteps}
+ */
+
+#ifndef STEPPER_H
+#define STEPPER_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#define STEPPER_STEPS_PER_REV {steps_per_rev}
+#define STEPPER_MICROSTEPS    {microsteps}
+#define STEPPER_MAX_FREQ_HZ   {max_freq}  // Microsteps per second
+#define STEPPER_HOME_DIR      (-1)  // Direction of the home switch
+
+void stepper_init(void);
+void stepper_enable(bool enable);
+
+// Cruise speed and acceleration of the following moves; 0 acceleration disables the ramp
+void stepper_set_speed_rpm(float rpm);
+void stepper_set_acceleration(float steps_per_s2);
+
+// Start a move in the background; -1 while another move is running
+int stepper_step_relative(int32_t steps);
+int stepper_step_absolute(int32_t position);
+
+// Run toward the home switch and zero the position there; blocks, -1 on timeout
+int stepper_home(float rpm, uint32_t timeout_ms);
+
+// Stop at once; steps can be lost at high speed
+void stepper_stop(void);
+bool stepper_is_running(void);
+int32_t stepper_get_position(void);
+bool stepper_limit_hit(void);  // Last move was stopped by the limit switch
+
+// Timer update interrupt; called from {irq_handler} unless STEPPER_NO_IRQ_HANDLER is defined
+void stepper_timer_isr(void);
+
+#endif // STEPPER_H
+"#);
+
+    let (step_port, step_bit) = gpio(pin(step_pin));
+    let (dir_port, dir_bit) = gpio(pin(dir_pin));
+    let mut clocks: Vec<PinRef> = [Some(pin(step_pin)), Some(pin(dir_pin))]
+        .into_iter()
+        .chain([config.en_pin.as_deref().map(pin), config.limit_pin.as_deref().map(pin)])
+        .flatten()
+        .collect();
+    clocks.sort_by_key(|p| p.port);
+    clocks.dedup_by_key(|p| p.port);
+    let clock_enables: String = clocks.iter()
+        .map(|p| format!("    __HAL_RCC_GPIO{}_CLK_ENABLE();\n", (b'A' + p.port) as char))
+        .collect();
+    let alternate = if mcu == McuFamily::STM32F1 {
+        String::new()
+    } else {
+        format!("    gpio.Alternate = GPIO_AF{}_{};\n", timer_af(&timer), timer)
+    };
+
+    let (en_macros, en_init) = match &config.en_pin {
+        Some(en) => {
+            let (port, bit) = gpio(pin(en));
+            (
+                format!("#define STEPPER_EN_WRITE(on) HAL_GPIO_WritePin({port}, {bit}, (on) ? GPIO_PIN_RESET : GPIO_PIN_SET)  // Active low\n"),
+                format!("\n    // EN: driver disabled until stepper_enable(true)\n    HAL_GPIO_WritePin({port}, {bit}, GPIO_PIN_SET);\n    gpio.Pin = {bit};\n    HAL_GPIO_Init({port}, &gpio);\n"),
+            )
+        }
+        None => ("#define STEPPER_EN_WRITE(on) ((void)(on))  // No EN pin; driver always enabled\n".to_string(), String::new()),
+    };
+    let (limit_macros, limit_init, limit_check, home) = match &config.limit_pin {
+        Some(limit) => {
+            let (port, bit) = gpio(pin(limit));
+            (
+                format!("#define STEPPER_LIMIT_ACTIVE() (HAL_GPIO_ReadPin({port}, {bit}) == GPIO_PIN_RESET)\n"),
+                format!("\n    // Limit switch: active low\n    gpio.Pin = {bit};\n    gpio.Mode = GPIO_MODE_INPUT;\n    gpio.Pull = GPIO_PULLUP;\n    HAL_GPIO_Init({port}, &gpio);\n"),
+                r#"
+    if (direction == STEPPER_HOME_DIR && STEPPER_LIMIT_ACTIVE()) {
+        limit_hit = true;
+        stepper_stop();
+        return;
+    }"#.to_string(),
+                r#"int stepper_home(float rpm, uint32_t timeout_ms) {
+    if (running) {
+        return -1;
+    }
+    if (!STEPPER_LIMIT_ACTIVE()) {
+        // Constant speed, no ramp, until the switch stops the move
+        start_move(UINT32_MAX, STEPPER_HOME_DIR, rpm_to_steps(rpm), 0.0f);
+        uint32_t start = HAL_GetTick();
+        while (running) {
+            if (HAL_GetTick() - start >= timeout_ms) {
+                stepper_stop();
+                return -1;
+            }
+        }
+        if (!limit_hit) {
+            return -1;
+        }
+    }
+    position = 0;
+    return 0;
+}"#.to_string(),
+            )
+        }
+        None => (
+            String::new(),
+            String::new(),
+            String::new(),
+            r#"// No limit switch configured: the current position becomes home
+int stepper_home(float rpm, uint32_t timeout_ms) {
+    (void)rpm;
+    (void)timeout_ms;
+    if (running) {
+        return -1;
+    }
+    position = 0;
+    return 0;
+}"#.to_string(),
+        ),
+    };
+
+    let hal_header = gpio_include(mcu);
+    let source = format!(r#"/**
+ * Stepper Motor Driver
+ * Auto-generated by NeuroBench
+ *
+ * STEP pulses come from {timer} CH{channel} in PWM mode 2, one pulse per
+ * timer period; the update interrupt counts steps and sets the next period.
+ */
+
+#include "stepper.h"
+{hal_header}
+#include <math.h>
+
+#ifndef STEPPER_TIMER_CLOCK_HZ
+// Timer kernel clock: PCLK{apb}, doubled when the APB{apb} prescaler divides it
+static uint32_t stepper_timer_clock(void) {{
+    uint32_t pclk = HAL_RCC_GetPCLK{apb}Freq();
+    return ({apb_divided}) ? 2U * pclk : pclk;
+}}
+#define STEPPER_TIMER_CLOCK_HZ stepper_timer_clock()
+#endif
+#define STEPPER_TICK_HZ       {TICK_HZ}U  // Counter clock
+#define STEPPER_MIN_FREQ_HZ   {min_freq}U  // Slowest rate a 16-bit ARR allows
+#ifndef STEPPER_DIR_INVERT
+#define STEPPER_DIR_INVERT    0
+#endif
+
+{en_macros}{limit_macros}
+TIM_HandleTypeDef h{timer_lower};
+
+static volatile int32_t position = 0;
+static volatile bool running = false;
+static volatile bool limit_hit = false;
+static int8_t direction = 1;
+
+static float cruise_steps = STEPPER_MAX_FREQ_HZ / 4.0f;  // Steps per second
+static float accel_steps2 = STEPPER_MAX_FREQ_HZ;          // Steps per second^2; full speed in 1 s
+
+static uint32_t total_steps;
+static uint32_t steps_done;
+static uint32_t accel_end;     // Last step of the acceleration ramp
+static uint32_t decel_start;   // First step of the deceleration ramp
+static uint32_t period_q8;     // Current step period in ticks, 8 fractional bits
+static uint32_t min_period_q8; // Period at cruise speed
+
+static float rpm_to_steps(float rpm) {{
+    float steps = rpm * (float)(STEPPER_STEPS_PER_REV * STEPPER_MICROSTEPS) / 60.0f;
+    if (steps > STEPPER_MAX_FREQ_HZ) {{
+        steps = STEPPER_MAX_FREQ_HZ;
+    }} else if (steps < STEPPER_MIN_FREQ_HZ) {{
+        steps = STEPPER_MIN_FREQ_HZ;
+    }}
+    return steps;
+}}
+
+static void load_period(uint32_t q8) {{
+    uint32_t ticks = q8 >> 8;
+    if (ticks < 4U) {{
+        ticks = 4U;
+    }} else if (ticks > 65536U) {{
+        ticks = 65536U;
+    }}
+    // Preloaded: takes effect from the next period
+    __HAL_TIM_SET_AUTORELOAD(&h{timer_lower}, ticks - 1U);
+    __HAL_TIM_SET_COMPARE(&h{timer_lower}, TIM_CHANNEL_{channel}, ticks / 2U);
+}}
+
+static void start_move(uint32_t steps, int8_t dir, float speed, float accel) {{
+    direction = dir;
+    HAL_GPIO_WritePin({dir_port}, {dir_bit}, ((dir > 0) != STEPPER_DIR_INVERT) ? GPIO_PIN_SET : GPIO_PIN_RESET);
+
+    total_steps = steps;
+    steps_done = 0;
+    limit_hit = false;
+    min_period_q8 = (uint32_t)((float)STEPPER_TICK_HZ * 256.0f / speed);
+
+    if (accel > 0.0f) {{
+        // Trapezoid: ramp up over v^2 / 2a steps, or half the move when it is shorter
+        float ramp = speed * speed / (2.0f * accel);
+        accel_end = ramp < (float)(steps / 2U) ? (uint32_t)ramp : steps / 2U;
+        // First period c0 = 0.676 * f * sqrt(2 / a) (AVR446, corrected for the first step)
+        float c0 = 0.676f * (float)STEPPER_TICK_HZ * 256.0f * sqrtf(2.0f / accel);
+        period_q8 = c0 > 65536.0f * 256.0f ? 65536U * 256U : (uint32_t)c0;
+        if (period_q8 < min_period_q8) {{
+            period_q8 = min_period_q8;
+        }}
+    }} else {{
+        accel_end = 0;
+        period_q8 = min_period_q8;
+    }}
+    decel_start = steps - accel_end;
+
+    load_period(period_q8);
+    h{timer_lower}.Instance->EGR = TIM_EGR_UG;  // Copy the preloaded ARR/CCR and restart the counter
+    __HAL_TIM_CLEAR_FLAG(&h{timer_lower}, TIM_FLAG_UPDATE);
+    running = true;
+    __HAL_TIM_ENABLE_IT(&h{timer_lower}, TIM_IT_UPDATE);
+    HAL_TIM_PWM_Start(&h{timer_lower}, TIM_CHANNEL_{channel});
+}}
+
+void stepper_init(void) {{
+    GPIO_InitTypeDef gpio = {{0}};
+
+{clock_enables}
+    // STEP: timer channel output
+    gpio.Pin = {step_bit};
+    gpio.Mode = GPIO_MODE_AF_PP;
+    gpio.Pull = GPIO_NOPULL;
+    gpio.Speed = GPIO_SPEED_FREQ_HIGH;
+{alternate}    HAL_GPIO_Init({step_port}, &gpio);
+
+    // DIR
+    gpio.Mode = GPIO_MODE_OUTPUT_PP;
+    gpio.Pin = {dir_bit};
+    HAL_GPIO_Init({dir_port}, &gpio);
+{en_init}{limit_init}
+    __HAL_RCC_{timer}_CLK_ENABLE();
+    h{timer_lower}.Instance = {timer};
+    h{timer_lower}.Init.Prescaler = STEPPER_TIMER_CLOCK_HZ / STEPPER_TICK_HZ - 1U;
+    h{timer_lower}.Init.CounterMode = TIM_COUNTERMODE_UP;
+    h{timer_lower}.Init.Period = 0xFFFF;
+    h{timer_lower}.Init.ClockDivision = TIM_CLOCKDIVISION_DIV1;
+    h{timer_lower}.Init.AutoReloadPreload = TIM_AUTORELOAD_PRELOAD_ENABLE;
+    HAL_TIM_PWM_Init(&h{timer_lower});
+
+    // PWM mode 2: low for the first half of the period, so each pulse ends at the update event
+    TIM_OC_InitTypeDef oc = {{0}};
+    oc.OCMode = TIM_OCMODE_PWM2;
+    oc.Pulse = 0x7FFF;
+    oc.OCPolarity = TIM_OCPOLARITY_HIGH;
+    oc.OCFastMode = TIM_OCFAST_DISABLE;
+    HAL_TIM_PWM_ConfigChannel(&h{timer_lower}, &oc, TIM_CHANNEL_{channel});
+
+    HAL_NVIC_SetPriority({irq}, 1, 0);
+    HAL_NVIC_EnableIRQ({irq});
+}}
+
+void stepper_enable(bool enable) {{
+    STEPPER_EN_WRITE(enable);
+}}
+
+void stepper_set_speed_rpm(float rpm) {{
+    cruise_steps = rpm_to_steps(rpm);
+}}
+
+void stepper_set_acceleration(float steps_per_s2) {{
+    accel_steps2 = steps_per_s2 > 0.0f ? steps_per_s2 : 0.0f;
+}}
+
+int stepper_step_relative(int32_t steps) {{
+    if (running) {{
+        return -1;
+    }}
+    if (steps != 0) {{
+        uint32_t count = steps > 0 ? (uint32_t)steps : (uint32_t)(-(int64_t)steps);
+        start_move(count, steps > 0 ? 1 : -1, cruise_steps, accel_steps2);
+    }}
+    return 0;
+}}
+
+int stepper_step_absolute(int32_t target) {{
+    return stepper_step_relative(target - position);
+}}
+
+{home}
+
+void stepper_stop(void) {{
+    HAL_TIM_PWM_Stop(&h{timer_lower}, TIM_CHANNEL_{channel});
+    __HAL_TIM_DISABLE_IT(&h{timer_lower}, TIM_IT_UPDATE);
+    running = false;
+}}
+
+bool stepper_is_running(void) {{
+    return running;
+}}
+
+int32_t stepper_get_position(void) {{
+    return position;
+}}
+
+bool stepper_limit_hit(void) {{
+    return limit_hit;
+}}
+
+void stepper_timer_isr(void) {{
+    if (!__HAL_TIM_GET_FLAG(&h{timer_lower}, TIM_FLAG_UPDATE)) {{
+        return;
+    }}
+    __HAL_TIM_CLEAR_FLAG(&h{timer_lower}, TIM_FLAG_UPDATE);
+    if (!running) {{
+        return;
+    }}
+
+    // The pulse of the period that just ended has gone out
+    position += direction;
+    steps_done++;
+{limit_check}
+    if (steps_done >= total_steps) {{
+        stepper_stop();
+        return;
+    }}
+
+    if (steps_done < accel_end) {{
+        // c_n = c_(n-1) - 2 c_(n-1) / (4n + 1)
+        period_q8 -= (2U * period_q8) / (4U * steps_done + 1U);
+        if (period_q8 < min_period_q8) {{
+            period_q8 = min_period_q8;
+        }}
+    }} else if (steps_done >= decel_start) {{
+        // Mirror of the ramp up, counting the remaining steps down
+        uint32_t remaining = total_steps - steps_done;
+        period_q8 += (2U * period_q8) / (4U * remaining - 1U);
+    }} else {{
+        period_q8 = min_period_q8;
+    }}
+    load_period(period_q8);
+}}
+
+#ifndef STEPPER_NO_IRQ_HANDLER
+void {irq_handler}(void) {{
+    stepper_timer_isr();
+}}
+#endif
+"#, min_freq = min_freq_hz());
+
+    let example = format!(r#"/**
+ * Stepper Example
+ * Homes{home_note}, then moves one revolution out and back
+ */
+
+#include "stepper.h"
+#include "main.h"
+
+#define ONE_REV (STEPPER_STEPS_PER_REV * STEPPER_MICROSTEPS)
+
+int main(void) {{
+    HAL_Init();
+    SystemClock_Config();
+
+    stepper_init();
+    stepper_enable(true);
+    stepper_set_acceleration(ONE_REV * 2.0f);
+
+    if (stepper_home(30.0f, 10000) != 0) {{
+        Error_Handler();
+    }}
+
+    stepper_set_speed_rpm(120.0f);
+    while (1) {{
+        stepper_step_absolute(ONE_REV);
+        while (stepper_is_running()) {{ }}
+        HAL_Delay(500);
+
+        stepper_step_absolute(0);
+        while (stepper_is_running()) {{ }}
+        HAL_Delay(500);
+    }}
+}}
+"#, home_note = if has_limit { " against the limit switch" } else { " (no switch: zeroes the position)" });
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: Some(example),
+        peripheral_type: PeripheralType::PWM,
+    }
+}
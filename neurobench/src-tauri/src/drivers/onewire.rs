@@ -0,0 +1,435 @@
+// 1-Wire Driver Generator
+// Generates a register-level 1-Wire master with ROM search and DS18B20 temperature readout
+
+use super::mcu::McuFamily;
+use super::soft_i2c::{gpio_include, gpio_init_prelude, gpio_line, parse_pin, PinRef};
+use super::templates::*;
+use serde::{Deserialize, Serialize};
+
+/// 1-Wire bus configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OneWireConfig {
+    pub data_pin: String,
+    /// Devices powered from DQ: hold a strong pull-up during conversions
+    pub use_parasite_power: bool,
+    pub max_devices: u8,
+}
+
+impl Default for OneWireConfig {
+    fn default() -> Self {
+        Self {
+            data_pin: "PA1".to_string(),
+            use_parasite_power: false,
+            max_devices: 4,
+        }
+    }
+}
+
+impl OneWireConfig {
+    pub fn validate(&self, mcu: McuFamily) -> Result<(), String> {
+        if parse_pin(mcu, &self.data_pin).is_none() {
+            return Err(format!("Invalid data pin for {}: {}", mcu.display_name(), self.data_pin));
+        }
+        if self.max_devices == 0 || self.max_devices > 32 {
+            return Err(format!("Max devices must be 1-32, got {}", self.max_devices));
+        }
+        Ok(())
+    }
+}
+
+/// Output latch high/low for the strong parasite-power pull-up
+fn latch_macros(mcu: McuFamily, p: PinRef) -> String {
+    let (high, low) = match mcu {
+        McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32H7 | McuFamily::STM32L4 | McuFamily::STM32G4 => {
+            let port = format!("GPIO{}", (b'A' + p.port) as char);
+            (
+                format!("({port}->BSRR = (1U << {}))", p.pin),
+                format!("({port}->BSRR = (1U << {}))", p.pin + 16),
+            )
+        }
+        McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3 => {
+            (format!("gpio_set_level({}, 1)", p.pin), format!("gpio_set_level({}, 0)", p.pin))
+        }
+        McuFamily::RP2040 => (format!("gpio_put({}, 1)", p.pin), format!("gpio_put({}, 0)", p.pin)),
+        McuFamily::NRF52832 | McuFamily::NRF52840 => {
+            let n = p.port as u32 * 32 + p.pin as u32;
+            (format!("nrf_gpio_pin_set({n})"), format!("nrf_gpio_pin_clear({n})"))
+        }
+        McuFamily::LPC1768 => (
+            format!("(LPC_GPIO{}->FIOSET = (1U << {}))", p.port, p.pin),
+            format!("(LPC_GPIO{}->FIOCLR = (1U << {}))", p.port, p.pin),
+        ),
+        McuFamily::LPC5500 => (
+            format!("(GPIO->SET[{}] = (1U << {}))", p.port, p.pin),
+            format!("(GPIO->CLR[{}] = (1U << {}))", p.port, p.pin),
+        ),
+    };
+    format!("#define ONEWIRE_DQ_LATCH_HIGH() {high}\n#define ONEWIRE_DQ_LATCH_LOW()  {low}\n")
+}
+
+/// Interrupt masking around time slots, plus any header it needs
+fn critical_section(mcu: McuFamily) -> &'static str {
+    match mcu {
+        McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3 => r#"#include "freertos/FreeRTOS.h"
+
+static portMUX_TYPE onewire_mux = portMUX_INITIALIZER_UNLOCKED;
+#define ONEWIRE_CRITICAL_ENTER() taskENTER_CRITICAL(&onewire_mux)
+#define ONEWIRE_CRITICAL_EXIT()  taskEXIT_CRITICAL(&onewire_mux)
+"#,
+        McuFamily::RP2040 => r#"#include "hardware/sync.h"
+
+#define ONEWIRE_CRITICAL_ENTER() uint32_t onewire_irq = save_and_disable_interrupts()
+#define ONEWIRE_CRITICAL_EXIT()  restore_interrupts(onewire_irq)
+"#,
+        _ => r#"
+#define ONEWIRE_CRITICAL_ENTER() uint32_t onewire_irq = __get_PRIMASK(); __disable_irq()
+#define ONEWIRE_CRITICAL_EXIT()  __set_PRIMASK(onewire_irq)
+"#,
+    }
+}
+
+/// Generate 1-Wire master driver code
+pub fn generate_onewire_driver(config: &OneWireConfig, mcu: McuFamily) -> DriverOutput {
+    let pin = parse_pin(mcu, &config.data_pin).unwrap_or(PinRef { port: 0, pin: 0 });
+    // The latch stays low and DQ is driven by switching direction, which also
+    // lets the strong pull-up drive the line high for parasite power
+    let (dq_macros, dq_init) = gpio_line(mcu, "ONEWIRE_DQ", pin, true);
+    let include = gpio_include(mcu);
+    let init_prelude = gpio_init_prelude(mcu, true);
+    let latch = latch_macros(mcu, pin);
+    let critical = critical_section(mcu);
+    let cpu_mhz = mcu.max_frequency_mhz();
+    let loops_per_us = (cpu_mhz / ONEWIRE_CYCLES_PER_LOOP).max(1);
+    let max_devices = config.max_devices;
+    let parasite = config.use_parasite_power as u8;
+    let data_pin = &config.data_pin;
+    let mcu_name = mcu.display_name();
+
+    let header = format!(r#"/**
+ * 1-Wire Driver
+ * Auto-generated by NeuroBench
+ * MCU: {mcu_name}, DQ: {data_pin}, parasite power: {parasite}
+ */
+
+#ifndef ONEWIRE_H
+#define ONEWIRE_H
+
+#include <stdint.h>
+#include <stdbool.h>
+
+#define ONEWIRE_MAX_DEVICES   {max_devices}
+#define ONEWIRE_PARASITE_POWER {parasite}
+
+#define ONEWIRE_CMD_SEARCH_ROM 0xF0
+#define ONEWIRE_CMD_MATCH_ROM  0x55
+#define ONEWIRE_CMD_SKIP_ROM   0xCC
+
+#define DS18B20_FAMILY_CODE    0x28
+#define DS18B20_CMD_CONVERT_T  0x44
+#define DS18B20_CMD_READ_SCRATCH 0xBE
+
+void onewire_init(void);
+bool onewire_reset(void);                  // true if a presence pulse was seen
+void onewire_write_bit(uint8_t bit);
+uint8_t onewire_read_bit(void);
+void onewire_write_byte(uint8_t byte);
+uint8_t onewire_read_byte(void);
+uint8_t onewire_crc8(const uint8_t *data, uint8_t len);
+
+// Enumerate the bus with the ROM search algorithm, returns the device count
+uint8_t onewire_search_all(void);
+uint8_t onewire_device_count(void);
+const uint8_t *onewire_device_rom(uint8_t index);
+
+// Convert and read one sensor found by onewire_search_all(), NAN on error
+float ds18b20_read_temperature(uint8_t device_index);
+
+#endif // ONEWIRE_H
+"#);
+
+    let source = format!(r#"/**
+ * 1-Wire Driver
+ * Auto-generated by NeuroBench
+ * DQ needs an external 4.7k pull-up to VDD
+ */
+
+#include "onewire.h"
+{include}
+#include <math.h>
+#include <string.h>
+{critical}
+// Delay loop calibrated for {cpu_mhz} MHz core clock
+#define ONEWIRE_LOOPS_PER_US {loops_per_us}UL
+
+{dq_macros}{latch}
+static uint8_t device_roms[ONEWIRE_MAX_DEVICES][8];
+static uint8_t device_count = 0;
+
+// Search state (Maxim application note 187)
+static uint8_t search_rom[8];
+static uint8_t last_discrepancy;
+static bool last_device_flag;
+
+static void onewire_delay_us(uint32_t us) {{
+    for (volatile uint32_t i = 0; i < us * ONEWIRE_LOOPS_PER_US; i++) {{
+        __asm volatile ("nop");
+    }}
+}}
+
+void onewire_init(void) {{
+{init_prelude}{dq_init}}}
+
+// Reset: 480 us low, presence pulse sampled 70 us after release
+bool onewire_reset(void) {{
+    ONEWIRE_DQ_LOW();
+    onewire_delay_us(480);
+    ONEWIRE_CRITICAL_ENTER();
+    ONEWIRE_DQ_RELEASE();
+    onewire_delay_us(70);
+    bool present = !ONEWIRE_DQ_READ();
+    ONEWIRE_CRITICAL_EXIT();
+    onewire_delay_us(410);
+    return present;
+}}
+
+// Write slot: 1 = 6 us low then 64 us high, 0 = 60 us low then 10 us recovery
+void onewire_write_bit(uint8_t bit) {{
+    ONEWIRE_CRITICAL_ENTER();
+    ONEWIRE_DQ_LOW();
+    if (bit) {{
+        onewire_delay_us(6);
+        ONEWIRE_DQ_RELEASE();
+        onewire_delay_us(64);
+    }} else {{
+        onewire_delay_us(60);
+        ONEWIRE_DQ_RELEASE();
+        onewire_delay_us(10);
+    }}
+    ONEWIRE_CRITICAL_EXIT();
+}}
+
+// Read slot: 6 us low, sample 9 us after release, 55 us to end the slot
+uint8_t onewire_read_bit(void) {{
+    ONEWIRE_CRITICAL_ENTER();
+    ONEWIRE_DQ_LOW();
+    onewire_delay_us(6);
+    ONEWIRE_DQ_RELEASE();
+    onewire_delay_us(9);
+    uint8_t bit = ONEWIRE_DQ_READ() ? 1 : 0;
+    ONEWIRE_CRITICAL_EXIT();
+    onewire_delay_us(55);
+    return bit;
+}}
+
+void onewire_write_byte(uint8_t byte) {{
+    for (int i = 0; i < 8; i++) {{
+        onewire_write_bit(byte & 0x01);
+        byte >>= 1;
+    }}
+}}
+
+uint8_t onewire_read_byte(void) {{
+    uint8_t byte = 0;
+    for (int i = 0; i < 8; i++) {{
+        byte |= (uint8_t)(onewire_read_bit() << i);
+    }}
+    return byte;
+}}
+
+// Dallas/Maxim CRC-8, polynomial x^8 + x^5 + x^4 + 1
+uint8_t onewire_crc8(const uint8_t *data, uint8_t len) {{
+    uint8_t crc = 0;
+    while (len--) {{
+        uint8_t byte = *data++;
+        for (int i = 0; i < 8; i++) {{
+            uint8_t mix = (crc ^ byte) & 0x01;
+            crc >>= 1;
+            if (mix) {{
+                crc ^= 0x8C;
+            }}
+            byte >>= 1;
+        }}
+    }}
+    return crc;
+}}
+
+/*
+ * ROM search: every device answers each ROM bit with the bit and its
+ * complement. 0/1 means all remaining devices agree, 0/0 is a
+ * discrepancy. The search takes the 1 branch at the last discrepancy
+ * and the 0 branch at newer ones, so each call finds the next device.
+ */
+static bool onewire_search_next(void) {{
+    if (last_device_flag) {{
+        return false;
+    }}
+    if (!onewire_reset()) {{
+        last_discrepancy = 0;
+        last_device_flag = false;
+        return false;
+    }}
+    onewire_write_byte(ONEWIRE_CMD_SEARCH_ROM);
+
+    uint8_t last_zero = 0;
+    for (uint8_t id_bit_number = 1; id_bit_number <= 64; id_bit_number++) {{
+        uint8_t byte_index = (uint8_t)((id_bit_number - 1) / 8);
+        uint8_t mask = (uint8_t)(1U << ((id_bit_number - 1) % 8));
+        uint8_t id_bit = onewire_read_bit();
+        uint8_t cmp_id_bit = onewire_read_bit();
+        uint8_t direction;
+
+        if (id_bit && cmp_id_bit) {{
+            return false;  // No device answered
+        }}
+        if (id_bit != cmp_id_bit) {{
+            direction = id_bit;
+        }} else if (id_bit_number < last_discrepancy) {{
+            direction = (search_rom[byte_index] & mask) ? 1 : 0;
+        }} else {{
+            direction = (id_bit_number == last_discrepancy) ? 1 : 0;
+        }}
+        if (id_bit == cmp_id_bit && direction == 0) {{
+            last_zero = id_bit_number;
+        }}
+
+        if (direction) {{
+            search_rom[byte_index] |= mask;
+        }} else {{
+            search_rom[byte_index] &= (uint8_t)~mask;
+        }}
+        onewire_write_bit(direction);
+    }}
+
+    if (onewire_crc8(search_rom, 7) != search_rom[7] || search_rom[0] == 0) {{
+        return false;
+    }}
+    last_discrepancy = last_zero;
+    last_device_flag = (last_discrepancy == 0);
+    return true;
+}}
+
+uint8_t onewire_search_all(void) {{
+    last_discrepancy = 0;
+    last_device_flag = false;
+    device_count = 0;
+    while (device_count < ONEWIRE_MAX_DEVICES && onewire_search_next()) {{
+        memcpy(device_roms[device_count++], search_rom, 8);
+    }}
+    return device_count;
+}}
+
+uint8_t onewire_device_count(void) {{
+    return device_count;
+}}
+
+const uint8_t *onewire_device_rom(uint8_t index) {{
+    return index < device_count ? device_roms[index] : NULL;
+}}
+
+static bool onewire_select(uint8_t device_index) {{
+    if (!onewire_reset()) {{
+        return false;
+    }}
+    onewire_write_byte(ONEWIRE_CMD_MATCH_ROM);
+    for (int i = 0; i < 8; i++) {{
+        onewire_write_byte(device_roms[device_index][i]);
+    }}
+    return true;
+}}
+
+/*
+ * Temperature conversion: scratchpad bytes 0/1 hold a signed 16-bit
+ * two's complement value, LSB first, in units of 1/16 degC at 12-bit
+ * resolution:  T[degC] = (int16_t)((MSB << 8) | LSB) / 16.0
+ * e.g. 0x0191 = 401 -> 25.0625 degC, 0xFF5E = -162 -> -10.125 degC.
+ * 0x0550 (85 degC) is the power-on value, read before any conversion.
+ */
+float ds18b20_read_temperature(uint8_t device_index) {{
+    if (device_index >= device_count || device_roms[device_index][0] != DS18B20_FAMILY_CODE) {{
+        return NAN;
+    }}
+    if (!onewire_select(device_index)) {{
+        return NAN;
+    }}
+
+#if ONEWIRE_PARASITE_POWER
+    // Conversion draws up to 1.5 mA from DQ: strong pull-up within 10 us of the command
+    for (int i = 0; i < 7; i++) {{
+        onewire_write_bit((DS18B20_CMD_CONVERT_T >> i) & 0x01);
+    }}
+    {{
+        ONEWIRE_CRITICAL_ENTER();
+        onewire_write_bit((DS18B20_CMD_CONVERT_T >> 7) & 0x01);
+        ONEWIRE_DQ_LATCH_HIGH();
+        ONEWIRE_DQ_LOW();  // Output direction, latch high: push-pull drive
+        ONEWIRE_CRITICAL_EXIT();
+    }}
+    for (int ms = 0; ms < 750; ms++) {{
+        onewire_delay_us(1000);
+    }}
+    ONEWIRE_DQ_RELEASE();
+    ONEWIRE_DQ_LATCH_LOW();
+#else
+    onewire_write_byte(DS18B20_CMD_CONVERT_T);
+    // Externally powered: the sensor holds read slots at 0 until conversion ends
+    uint32_t waited_ms = 0;
+    while (!onewire_read_bit()) {{
+        if (++waited_ms > 750) {{
+            return NAN;
+        }}
+        onewire_delay_us(1000);
+    }}
+#endif
+
+    if (!onewire_select(device_index)) {{
+        return NAN;
+    }}
+    onewire_write_byte(DS18B20_CMD_READ_SCRATCH);
+    uint8_t scratch[9];
+    for (int i = 0; i < 9; i++) {{
+        scratch[i] = onewire_read_byte();
+    }}
+    if (onewire_crc8(scratch, 8) != scratch[8]) {{
+        return NAN;
+    }}
+
+    int16_t raw = (int16_t)((scratch[1] << 8) | scratch[0]);
+    return (float)raw / 16.0f;
+}}
+"#);
+
+    let example = r#"/**
+ * 1-Wire Example
+ * Enumerates the bus and prints every DS18B20 once per second
+ */
+
+#include "onewire.h"
+#include <stdio.h>
+
+int main(void) {
+    onewire_init();
+
+    uint8_t count = onewire_search_all();
+    printf("Found %u device(s)\n", count);
+
+    while (1) {
+        for (uint8_t i = 0; i < count; i++) {
+            const uint8_t *rom = onewire_device_rom(i);
+            float temp = ds18b20_read_temperature(i);
+            printf("%02X%02X%02X%02X%02X%02X%02X%02X: %.2f C\n",
+                   rom[0], rom[1], rom[2], rom[3], rom[4], rom[5], rom[6], rom[7], temp);
+        }
+    }
+}
+"#;
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: Some(example.to_string()),
+        peripheral_type: PeripheralType::GPIO,
+    }
+}
+
+/// CPU cycles per iteration of the generated delay loop (volatile counter + nop)
+const ONEWIRE_CYCLES_PER_LOOP: u32 = 4;
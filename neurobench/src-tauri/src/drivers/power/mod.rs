@@ -0,0 +1,81 @@
+// Power Management Module
+// PMIC configuration and power rail sequencing
+
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// PMIC Configuration
+// ============================================================================
+
+/// Supported PMIC devices
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PmicDevice {
+    MAX77650,
+    BQ25180,
+    TPS65941,
+}
+
+impl PmicDevice {
+    /// 7-bit I2C address
+    pub fn i2c_address(&self) -> u8 {
+        match self {
+            PmicDevice::MAX77650 => 0x48,
+            PmicDevice::BQ25180 => 0x6A,
+            PmicDevice::TPS65941 => 0x48,
+        }
+    }
+
+    /// Number of configurable output rails
+    pub fn rail_count(&self) -> usize {
+        match self {
+            PmicDevice::MAX77650 => 4,  // SBB0, SBB1, SBB2, LDO
+            PmicDevice::BQ25180 => 1,   // SYS
+            PmicDevice::TPS65941 => 9,  // BUCK1-5, LDO1-4
+        }
+    }
+
+    /// Minimum delay between enabling two rails
+    pub fn min_sequence_delay_ms(&self) -> u32 {
+        match self {
+            PmicDevice::MAX77650 => 2,
+            PmicDevice::BQ25180 => 1,
+            PmicDevice::TPS65941 => 1,
+        }
+    }
+}
+
+/// Output power rail
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerRail {
+    pub name: String,
+    pub voltage_mv: u32,
+    pub current_ma: u32,
+    pub enabled_on_boot: bool,
+    pub gpio_enable_pin: Option<String>,
+}
+
+/// Power-up sequence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerSequence {
+    pub order: Vec<String>,  // Rail names, first enabled first
+    pub delay_ms: u32,       // Delay between consecutive rails
+}
+
+impl Default for PowerSequence {
+    fn default() -> Self {
+        Self {
+            order: vec![],
+            delay_ms: 5,
+        }
+    }
+}
+
+/// PMIC configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PmicConfig {
+    pub device: PmicDevice,
+    pub rails: Vec<PowerRail>,
+    pub sequence: PowerSequence,
+}
+
+pub mod pmic;
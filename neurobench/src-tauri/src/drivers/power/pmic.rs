@@ -0,0 +1,415 @@
+// PMIC Code Generator
+// Generates I2C register-level init code for MAX77650, BQ25180 and TPS65941
+
+use super::*;
+use crate::drivers::mcu::McuFamily;
+use crate::drivers::templates::{DriverOutput, PeripheralType};
+
+/// Generate PMIC initialization driver
+pub fn generate_pmic_init(config: &PmicConfig, mcu: McuFamily) -> DriverOutput {
+    let device = config.device;
+    let prefix = format!("{:?}", device);
+    let delay_ms = config.sequence.delay_ms.max(device.min_sequence_delay_ms());
+
+    let registers = match device {
+        PmicDevice::MAX77650 => MAX77650_REGISTERS,
+        PmicDevice::BQ25180 => BQ25180_REGISTERS,
+        PmicDevice::TPS65941 => TPS65941_REGISTERS,
+    };
+    let reg_defines: String = registers.iter()
+        .map(|(name, addr)| format!("#define {}_{:<18} 0x{:02X}\n", prefix, name, addr))
+        .collect();
+
+    let header = format!(r#"/**
+ * PMIC Driver: {prefix}
+ * Auto-generated by NeuroBench
+ */
+
+#ifndef PMIC_H
+#define PMIC_H
+
+#include <stdint.h>
+#include <stdbool.h>
+
+#define PMIC_I2C_ADDR            0x{addr:02X}
+#define PMIC_SEQ_DELAY_MS        {delay_ms}
+#define PMIC_MIN_SEQ_DELAY_MS    {min_delay}
+
+_Static_assert(PMIC_SEQ_DELAY_MS >= PMIC_MIN_SEQ_DELAY_MS,
+               "Power rail sequencing delay below device minimum");
+
+// Register map
+{reg_defines}
+int pmic_init(void);
+int pmic_write_reg(uint8_t reg, uint8_t value);
+
+#endif // PMIC_H
+"#,
+        prefix = prefix,
+        addr = device.i2c_address(),
+        delay_ms = delay_ms,
+        min_delay = device.min_sequence_delay_ms(),
+        reg_defines = reg_defines,
+    );
+
+    let rail_count = config.rails.len().min(device.rail_count());
+    let encode = |index: usize, rail: &PowerRail| match device {
+        PmicDevice::MAX77650 => max77650_rail(index, rail, &prefix),
+        PmicDevice::BQ25180 => bq25180_rail(rail, &prefix),
+        PmicDevice::TPS65941 => tps65941_rail(index, rail, &prefix),
+    };
+
+    let mut notes = String::new();
+    if config.rails.len() > rail_count {
+        notes.push_str(&format!(
+            " * NOTE: {} supports {} rails, {} extra rail(s) ignored\n",
+            prefix, rail_count, config.rails.len() - rail_count
+        ));
+    }
+
+    let mut configure = String::new();
+    for (index, rail) in config.rails[..rail_count].iter().enumerate() {
+        configure.push_str(&encode(index, rail).0);
+    }
+
+    let ordered = sequence_order(config);
+    let mut enable = String::new();
+    for &index in &ordered {
+        let rail = &config.rails[index];
+        enable.push_str(&format!("    // {} ({} mV)\n", rail.name, rail.voltage_mv));
+        enable.push_str(&encode(index, rail).1);
+        if let Some(pin) = &rail.gpio_enable_pin {
+            enable.push_str(&gpio_enable(mcu, pin));
+        }
+        enable.push_str(&format!("    {}\n\n", delay_call(mcu, "PMIC_SEQ_DELAY_MS")));
+    }
+
+    let sequence_comment: String = ordered.iter()
+        .map(|i| config.rails[*i].name.clone())
+        .collect::<Vec<_>>()
+        .join(" -> ");
+
+    let source = format!(r#"/**
+ * PMIC Initialization: {prefix}
+ * MCU: {mcu}
+ * Power-up sequence: {sequence}
+{notes} */
+
+#include "pmic.h"
+{includes}
+{i2c_write}
+int pmic_init(void) {{
+    // Configure rail voltages and current limits (outputs stay off)
+{configure}
+    // Enable rails in sequence
+{enable}    return 0;
+}}
+"#,
+        prefix = prefix,
+        mcu = mcu.display_name(),
+        sequence = if sequence_comment.is_empty() { "none".to_string() } else { sequence_comment },
+        notes = notes,
+        includes = platform_includes(mcu),
+        i2c_write = i2c_write_fn(mcu),
+        configure = configure,
+        enable = enable,
+    );
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: None,
+        peripheral_type: PeripheralType::PMIC,
+    }
+}
+
+/// Indices of boot-enabled rails in power-up order
+fn sequence_order(config: &PmicConfig) -> Vec<usize> {
+    let rail_count = config.rails.len().min(config.device.rail_count());
+    let mut order: Vec<usize> = config.sequence.order.iter()
+        .filter_map(|name| config.rails[..rail_count].iter().position(|r| &r.name == name))
+        .filter(|i| config.rails[*i].enabled_on_boot)
+        .collect();
+    for (i, rail) in config.rails[..rail_count].iter().enumerate() {
+        if rail.enabled_on_boot && !order.contains(&i) {
+            order.push(i);
+        }
+    }
+    order
+}
+
+// ============================================================================
+// Register maps
+// ============================================================================
+
+const MAX77650_REGISTERS: &[(&str, u8)] = &[
+    ("INT_GLBL", 0x00),
+    ("STAT_GLBL", 0x05),
+    ("CNFG_GLBL", 0x10),
+    ("CID", 0x11),
+    ("CNFG_SBB_TOP", 0x28),
+    ("CNFG_SBB0_A", 0x29),
+    ("CNFG_SBB0_B", 0x2A),
+    ("CNFG_SBB1_A", 0x2B),
+    ("CNFG_SBB1_B", 0x2C),
+    ("CNFG_SBB2_A", 0x2D),
+    ("CNFG_SBB2_B", 0x2E),
+    ("CNFG_LDO_A", 0x38),
+    ("CNFG_LDO_B", 0x39),
+];
+
+const BQ25180_REGISTERS: &[(&str, u8)] = &[
+    ("STAT0", 0x00),
+    ("STAT1", 0x01),
+    ("FLAG0", 0x02),
+    ("VBAT_CTRL", 0x03),
+    ("ICHG_CTRL", 0x04),
+    ("CHARGECTRL0", 0x05),
+    ("CHARGECTRL1", 0x06),
+    ("IC_CTRL", 0x07),
+    ("TMR_ILIM", 0x08),
+    ("SHIP_RST", 0x09),
+    ("SYS_REG", 0x0A),
+    ("TS_CONTROL", 0x0B),
+    ("MASK_ID", 0x0C),
+];
+
+const TPS65941_REGISTERS: &[(&str, u8)] = &[
+    ("DEV_REV", 0x01),
+    ("BUCK1_CTRL", 0x04),
+    ("BUCK2_CTRL", 0x06),
+    ("BUCK3_CTRL", 0x08),
+    ("BUCK4_CTRL", 0x0A),
+    ("BUCK5_CTRL", 0x0C),
+    ("BUCK1_VOUT_1", 0x0E),
+    ("BUCK2_VOUT_1", 0x10),
+    ("BUCK3_VOUT_1", 0x12),
+    ("BUCK4_VOUT_1", 0x14),
+    ("BUCK5_VOUT_1", 0x16),
+    ("LDO1_CTRL", 0x1D),
+    ("LDO2_CTRL", 0x1E),
+    ("LDO3_CTRL", 0x1F),
+    ("LDO4_CTRL", 0x20),
+    ("LDO1_VOUT", 0x23),
+    ("LDO2_VOUT", 0x24),
+    ("LDO3_VOUT", 0x25),
+    ("LDO4_VOUT", 0x26),
+];
+
+// ============================================================================
+// Per-device rail encoding
+// ============================================================================
+
+/// Returns (configure, enable) code for a MAX77650 rail
+///
+/// Rails map to SBB0, SBB1, SBB2 and LDO in order.
+fn max77650_rail(index: usize, rail: &PowerRail, prefix: &str) -> (String, String) {
+    // TV_SBBx / TV_LDO: (base mV, step in µV, max code)
+    let (reg, base_mv, step_uv, max_code) = match index {
+        0 => ("SBB0", 800, 25_000, 0x3F),
+        1 => ("SBB1", 800, 12_500, 0x3F),
+        2 => ("SBB2", 800, 50_000, 0x3F),
+        _ => ("LDO", 1350, 12_500, 0x7F),
+    };
+    let code = voltage_code(rail.voltage_mv, base_mv, step_uv, max_code);
+
+    if reg == "LDO" {
+        let configure = format!(
+            "    // {name}: LDO, TV_LDO[6:0] = 0x{code:02X}\n    pmic_write_reg({p}_CNFG_LDO_A, 0x{code:02X});\n    pmic_write_reg({p}_CNFG_LDO_B, 0x04);  // EN_LDO = off\n\n",
+            name = rail.name, code = code, p = prefix,
+        );
+        let enable = format!("    pmic_write_reg({p}_CNFG_LDO_B, 0x06);  // EN_LDO = on\n", p = prefix);
+        return (configure, enable);
+    }
+
+    // IP_SBBx[5:4]: 00 = 1000 mA, 01 = 750 mA, 10 = 500 mA, 11 = 333 mA
+    let ip = match rail.current_ma {
+        751.. => 0b00,
+        501..=750 => 0b01,
+        334..=500 => 0b10,
+        _ => 0b11,
+    };
+    let b_off = (ip << 4) | 0b100;
+    let b_on = (ip << 4) | 0b110;
+
+    let configure = format!(
+        "    // {name}: {reg}, TV_{reg}[5:0] = 0x{code:02X}, IP_{reg} = {ip:02b}\n    pmic_write_reg({p}_CNFG_{reg}_A, 0x{code:02X});\n    pmic_write_reg({p}_CNFG_{reg}_B, 0x{b_off:02X});  // EN_{reg} = off\n\n",
+        name = rail.name, reg = reg, code = code, ip = ip, p = prefix, b_off = b_off,
+    );
+    let enable = format!(
+        "    pmic_write_reg({p}_CNFG_{reg}_B, 0x{b_on:02X});  // EN_{reg} = on\n",
+        p = prefix, reg = reg, b_on = b_on,
+    );
+    (configure, enable)
+}
+
+/// Returns (configure, enable) code for the BQ25180 SYS output
+fn bq25180_rail(rail: &PowerRail, prefix: &str) -> (String, String) {
+    // SYS_REG_CTRL[7:5]: 001 = 4.4 V ... 110 = 4.9 V
+    let sys_code = match rail.voltage_mv {
+        0..=4449 => 0b001,
+        4450..=4549 => 0b010,
+        4550..=4649 => 0b011,
+        4650..=4749 => 0b100,
+        4750..=4849 => 0b101,
+        _ => 0b110,
+    };
+    // ILIM[2:0]: input current limit
+    let ilim = match rail.current_ma {
+        0..=50 => 0b000,
+        51..=100 => 0b001,
+        101..=200 => 0b010,
+        201..=300 => 0b011,
+        301..=400 => 0b100,
+        401..=500 => 0b101,
+        501..=700 => 0b110,
+        _ => 0b111,
+    };
+
+    let configure = format!(
+        "    // {name}: SYS_REG_CTRL = {sys:03b}, ILIM = {ilim:03b}\n    pmic_write_reg({p}_SYS_REG, 0x{sys_reg:02X});\n    pmic_write_reg({p}_TMR_ILIM, 0x{ilim:02X});\n\n",
+        name = rail.name, sys = sys_code, ilim = ilim, p = prefix, sys_reg = sys_code << 5,
+    );
+    // SYS output is live once VIN or VBAT is present; exit ship mode
+    let enable = format!("    pmic_write_reg({p}_SHIP_RST, 0x11);\n", p = prefix);
+    (configure, enable)
+}
+
+/// Returns (configure, enable) code for a TPS65941 rail
+///
+/// Rails map to BUCK1-5 and then LDO1-4 in order.
+fn tps65941_rail(index: usize, rail: &PowerRail, prefix: &str) -> (String, String) {
+    if index < 5 {
+        let n = index + 1;
+        let code = tps65941_buck_code(rail.voltage_mv);
+        let configure = format!(
+            "    // {name}: BUCK{n}, VSET = 0x{code:02X}\n    pmic_write_reg({p}_BUCK{n}_VOUT_1, 0x{code:02X});\n\n",
+            name = rail.name, n = n, code = code, p = prefix,
+        );
+        let enable = format!("    pmic_write_reg({p}_BUCK{n}_CTRL, 0x01);  // BUCK{n}_EN\n", p = prefix, n = n);
+        (configure, enable)
+    } else {
+        let n = index - 4;
+        // LDOn_VSET[6:1]: 0.6 V to 3.3 V in 50 mV steps
+        let code = voltage_code(rail.voltage_mv, 600, 50_000, 0x36);
+        let configure = format!(
+            "    // {name}: LDO{n}, VSET = 0x{code:02X}\n    pmic_write_reg({p}_LDO{n}_VOUT, 0x{reg:02X});\n\n",
+            name = rail.name, n = n, code = code, p = prefix, reg = code << 1,
+        );
+        let enable = format!("    pmic_write_reg({p}_LDO{n}_CTRL, 0x01);  // LDO{n}_EN\n", p = prefix, n = n);
+        (configure, enable)
+    }
+}
+
+/// TPS65941 BUCK VSET encoding (0.6 V to 3.34 V)
+fn tps65941_buck_code(voltage_mv: u32) -> u32 {
+    match voltage_mv {
+        0..=1099 => voltage_code(voltage_mv, 600, 5_000, 0x63) + 0x0F,
+        1100..=1659 => voltage_code(voltage_mv, 1100, 10_000, 0x37) + 0x73,
+        _ => voltage_code(voltage_mv, 1660, 20_000, 0x54) + 0xAB,
+    }
+}
+
+/// Linear voltage code, clamped to the regulator range
+fn voltage_code(voltage_mv: u32, base_mv: u32, step_uv: u32, max_code: u32) -> u32 {
+    // An offset too large to express in µV is past every regulator's range
+    voltage_mv.saturating_sub(base_mv)
+        .checked_mul(1000)
+        .map_or(max_code, |offset_uv| (offset_uv / step_uv).min(max_code))
+}
+
+// ============================================================================
+// Platform glue
+// ============================================================================
+
+fn platform_includes(mcu: McuFamily) -> &'static str {
+    match mcu {
+        McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32H7 |
+        McuFamily::STM32L4 | McuFamily::STM32G4 => "#include \"main.h\"\n\nextern I2C_HandleTypeDef hi2c1;\n",
+        McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3 => "#include \"driver/i2c.h\"\n#include \"driver/gpio.h\"\n#include \"freertos/FreeRTOS.h\"\n#include \"freertos/task.h\"\n",
+        McuFamily::RP2040 => "#include \"pico/stdlib.h\"\n#include \"hardware/i2c.h\"\n",
+        McuFamily::NRF52832 | McuFamily::NRF52840 => "#include \"nrfx_twim.h\"\n#include \"nrf_gpio.h\"\n#include \"nrf_delay.h\"\n\nextern const nrfx_twim_t pmic_twim;\n",
+        McuFamily::LPC1768 | McuFamily::LPC5500 => "#include \"fsl_i2c.h\"\n#include \"fsl_gpio.h\"\n\nextern void pmic_delay_ms(uint32_t ms);\n",
+    }
+}
+
+fn i2c_write_fn(mcu: McuFamily) -> &'static str {
+    match mcu {
+        McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32H7 |
+        McuFamily::STM32L4 | McuFamily::STM32G4 => r#"
+int pmic_write_reg(uint8_t reg, uint8_t value) {
+    return HAL_I2C_Mem_Write(&hi2c1, PMIC_I2C_ADDR << 1, reg,
+                             I2C_MEMADD_SIZE_8BIT, &value, 1, 100) == HAL_OK ? 0 : -1;
+}
+"#,
+        McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3 => r#"
+int pmic_write_reg(uint8_t reg, uint8_t value) {
+    uint8_t buf[2] = { reg, value };
+    return i2c_master_write_to_device(I2C_NUM_0, PMIC_I2C_ADDR, buf, 2,
+                                      pdMS_TO_TICKS(100)) == ESP_OK ? 0 : -1;
+}
+"#,
+        McuFamily::RP2040 => r#"
+int pmic_write_reg(uint8_t reg, uint8_t value) {
+    uint8_t buf[2] = { reg, value };
+    return i2c_write_blocking(i2c0, PMIC_I2C_ADDR, buf, 2, false) == 2 ? 0 : -1;
+}
+"#,
+        McuFamily::NRF52832 | McuFamily::NRF52840 => r#"
+int pmic_write_reg(uint8_t reg, uint8_t value) {
+    uint8_t buf[2] = { reg, value };
+    nrfx_twim_xfer_desc_t xfer = NRFX_TWIM_XFER_DESC_TX(PMIC_I2C_ADDR, buf, 2);
+    return nrfx_twim_xfer(&pmic_twim, &xfer, 0) == NRFX_SUCCESS ? 0 : -1;
+}
+"#,
+        McuFamily::LPC1768 | McuFamily::LPC5500 => r#"
+int pmic_write_reg(uint8_t reg, uint8_t value) {
+    i2c_master_transfer_t xfer = {
+        .slaveAddress = PMIC_I2C_ADDR,
+        .direction = kI2C_Write,
+        .subaddress = reg,
+        .subaddressSize = 1,
+        .data = &value,
+        .dataSize = 1,
+    };
+    return I2C_MasterTransferBlocking(I2C0, &xfer) == kStatus_Success ? 0 : -1;
+}
+"#,
+    }
+}
+
+fn delay_call(mcu: McuFamily, ms: &str) -> String {
+    match mcu {
+        McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32H7 |
+        McuFamily::STM32L4 | McuFamily::STM32G4 => format!("HAL_Delay({});", ms),
+        McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3 => format!("vTaskDelay(pdMS_TO_TICKS({}));", ms),
+        McuFamily::RP2040 => format!("sleep_ms({});", ms),
+        McuFamily::NRF52832 | McuFamily::NRF52840 => format!("nrf_delay_ms({});", ms),
+        McuFamily::LPC1768 | McuFamily::LPC5500 => format!("pmic_delay_ms({});", ms),
+    }
+}
+
+/// Drive an external rail enable pin high
+fn gpio_enable(mcu: McuFamily, pin: &str) -> String {
+    let pin_num: u32 = pin.chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .collect::<String>()
+        .split('.')
+        .next_back()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(0);
+
+    match mcu {
+        McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32H7 |
+        McuFamily::STM32L4 | McuFamily::STM32G4 => {
+            let port = pin.trim_start_matches('P').chars().next().unwrap_or('A');
+            format!("    HAL_GPIO_WritePin(GPIO{}, GPIO_PIN_{}, GPIO_PIN_SET);  // {}\n", port, pin_num, pin)
+        }
+        McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3 =>
+            format!("    gpio_set_level(GPIO_NUM_{}, 1);  // {}\n", pin_num, pin),
+        McuFamily::RP2040 => format!("    gpio_put({}, true);  // {}\n", pin_num, pin),
+        McuFamily::NRF52832 | McuFamily::NRF52840 => format!("    nrf_gpio_pin_set({});  // {}\n", pin_num, pin),
+        McuFamily::LPC1768 | McuFamily::LPC5500 =>
+            format!("    GPIO_PinWrite(GPIO, 0, {}, 1);  // {}\n", pin_num, pin),
+    }
+}
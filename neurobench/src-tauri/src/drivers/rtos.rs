@@ -1,6 +1,7 @@
 // RTOS Generator Module
 // Generates FreeRTOS task and scheduling code for embedded applications
 
+use super::hal_abstraction;
 use super::templates::*;
 
 /// RTOS configuration
@@ -46,6 +47,7 @@ fn generate_freertos(config: &RtosConfig, lang: &DriverLanguage) -> DriverOutput
         DriverLanguage::C => generate_freertos_c(config),
         DriverLanguage::Cpp => generate_freertos_cpp(config),
         DriverLanguage::Rust => generate_freertos_rust(config),
+        DriverLanguage::Portable => hal_abstraction::not_portable("FreeRTOS", PeripheralType::GPIO),
     }
 }
 
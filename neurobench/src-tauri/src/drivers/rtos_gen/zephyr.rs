@@ -3,11 +3,74 @@
 
 use super::*;
 
-pub struct ZephyrHal;
+/// Zephyr kernel release that generated code targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ZephyrVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl ZephyrVersion {
+    /// First release with the `k_event` API
+    pub const K_EVENT: ZephyrVersion = ZephyrVersion { major: 3, minor: 0 };
+    /// First release with `k_event_set_masked`
+    pub const K_EVENT_SET_MASKED: ZephyrVersion = ZephyrVersion { major: 3, minor: 3 };
+
+    pub fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+
+    pub fn has_k_event(&self) -> bool {
+        *self >= Self::K_EVENT
+    }
+
+    /// Read the version of the Zephyr tree at `$ZEPHYR_BASE`
+    pub fn detect() -> Option<Self> {
+        let base = std::env::var("ZEPHYR_BASE").ok()?;
+        let content = std::fs::read_to_string(std::path::Path::new(&base).join("VERSION")).ok()?;
+        Self::parse_version_file(&content)
+    }
+
+    /// Parse a Zephyr `VERSION` file (`VERSION_MAJOR = 3` / `VERSION_MINOR = 6` lines)
+    pub fn parse_version_file(content: &str) -> Option<Self> {
+        let field = |key: &str| {
+            content.lines()
+                .filter_map(|line| line.split_once('='))
+                .find(|(k, _)| k.trim() == key)
+                .and_then(|(_, v)| v.trim().parse().ok())
+        };
+        Some(Self { major: field("VERSION_MAJOR")?, minor: field("VERSION_MINOR")? })
+    }
+}
+
+impl Default for ZephyrVersion {
+    fn default() -> Self {
+        Self { major: 3, minor: 6 }
+    }
+}
+
+impl std::fmt::Display for ZephyrVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+pub struct ZephyrHal {
+    version: ZephyrVersion,
+}
 
 impl ZephyrHal {
     pub fn new() -> Self {
-        Self
+        Self::with_version(ZephyrVersion::default())
+    }
+
+    /// Generate for a specific kernel release; APIs newer than it are avoided
+    pub fn with_version(version: ZephyrVersion) -> Self {
+        Self { version }
+    }
+
+    pub fn version(&self) -> ZephyrVersion {
+        self.version
     }
 }
 
@@ -256,48 +319,62 @@ uint32_t {name}_remaining(void) {{
         )
     }
     
+    /// Event group for the configured Zephyr version
+    ///
+    /// Zephyr 3.0+ gets `k_event`, with a `k_poll_signal` fallback selected
+    /// through `ZEPHYR_VERSION_CODE` in case the tree it is built against is
+    /// older than configured. Older versions get only the `k_poll` version,
+    /// which needs `CONFIG_POLL=y`.
     fn generate_event_group(&self, config: &EventGroupConfig) -> String {
+        let upper = config.name.to_uppercase();
         let bit_defs: String = (0..config.num_bits.min(32))
-            .map(|i| format!("#define {}_BIT_{} BIT({})\n", config.name.to_uppercase(), i, i))
+            .map(|i| format!("#define {}_BIT_{} BIT({})\n", upper, i, i))
             .collect();
-        
-        format!(r#"/**
- * Zephyr Event: {name}
- * Bits: {num_bits}
- */
-
-#include <zephyr/kernel.h>
 
-{bit_defs}
-K_EVENT_DEFINE({name});
+        let body = if self.version.has_k_event() {
+            format!("#if {upper}_USE_K_EVENT\n{}#else\n{}#endif\n", k_event_group(&config.name), k_poll_group(&config.name), upper = upper)
+        } else {
+            k_poll_group(&config.name)
+        };
 
-uint32_t {name}_set(uint32_t events) {{
-    return k_event_set(&{name}, events);
-}}
+        format!(r#"/**
+ * Zephyr Event Group: {name}
+ * Bits: {num_bits}, generated for Zephyr {version}
+ */
 
-uint32_t {name}_clear(uint32_t events) {{
-    return k_event_clear(&{name}, events);
-}}
+#include <{prefix}kernel.h>
+#include <{prefix}sys/atomic.h>
+#include <version.h>
 
-uint32_t {name}_wait(uint32_t events, bool wait_all, k_timeout_t timeout) {{
-    uint32_t options = wait_all ? K_EVENT_WAIT_ALL : K_EVENT_WAIT_ANY;
-    return k_event_wait(&{name}, events, options, timeout);
-}}
+/* k_event arrived in Zephyr 3.0; older kernels build the k_poll version */
+#if defined(ZEPHYR_VERSION_CODE) && ZEPHYR_VERSION_CODE >= ZEPHYR_VERSION(3, 0, 0)
+#define {upper}_USE_K_EVENT 1
+#else
+#define {upper}_USE_K_EVENT 0
+#endif
 
-uint32_t {name}_post(uint32_t events) {{
-    return k_event_post(&{name}, events);
-}}
-"#,
+{bit_defs}
+{body}"#,
             name = config.name,
             num_bits = config.num_bits,
+            version = self.version,
+            prefix = if self.version >= ZephyrVersion::new(3, 1) { "zephyr/" } else { "" },
+            upper = upper,
             bit_defs = bit_defs,
+            body = body,
         )
     }
     
     fn generate_config_header(&self) -> String {
-        r#"/**
+        let events = if self.version.has_k_event() {
+            "# Events\nCONFIG_EVENTS=y\n"
+        } else {
+            "# Polling (event groups)\nCONFIG_POLL=y\n"
+        };
+
+        format!(r#"/**
  * Zephyr prj.conf
- * Auto-generated by NeuroBench
+ * Auto-generated by NeuroBench for Zephyr {version}
  */
 
 # General
@@ -323,9 +400,7 @@ CONFIG_MSGQ=y
 # Timers
 CONFIG_TIMER=y
 
-# Events
-CONFIG_EVENTS=y
-
+{events}
 # Memory Management
 CONFIG_MEM_SLAB=y
 CONFIG_MEMPOOL=y
@@ -345,7 +420,10 @@ CONFIG_THREAD_STACK_INFO=y
 # Power Management
 CONFIG_PM=y
 CONFIG_PM_DEVICE=y
-"#.to_string()
+"#,
+            version = self.version,
+            events = events,
+        )
     }
     
     fn generate_main(&self, tasks: &[TaskConfig]) -> String {
@@ -388,3 +466,82 @@ int main(void) {{
         )
     }
 }
+
+/// Event group on `k_event`
+fn k_event_group(name: &str) -> String {
+    format!(r#"K_EVENT_DEFINE({name});
+
+void {name}_set(uint32_t events) {{
+    k_event_set(&{name}, events);
+}}
+
+void {name}_clear(uint32_t events) {{
+#if ZEPHYR_VERSION_CODE >= ZEPHYR_VERSION({major}, {minor}, 0)
+    k_event_set_masked(&{name}, 0, events);
+#else
+    unsigned int key = irq_lock();
+    k_event_set(&{name}, {name}.events & ~events);
+    irq_unlock(key);
+#endif
+}}
+
+uint32_t {name}_wait(uint32_t events, bool wait_all, k_timeout_t timeout) {{
+    return wait_all ? k_event_wait_all(&{name}, events, false, timeout)
+                    : k_event_wait(&{name}, events, false, timeout);
+}}
+
+void {name}_post(uint32_t events) {{
+    k_event_post(&{name}, events);
+}}
+"#,
+        name = name,
+        major = ZephyrVersion::K_EVENT_SET_MASKED.major,
+        minor = ZephyrVersion::K_EVENT_SET_MASKED.minor,
+    )
+}
+
+/// Event group on an atomic bitmask plus a `k_poll_signal` to wake waiters
+fn k_poll_group(name: &str) -> String {
+    format!(r#"static atomic_t {name}_bits = ATOMIC_INIT(0);
+static struct k_poll_signal {name}_signal = K_POLL_SIGNAL_INITIALIZER({name}_signal);
+
+static void {name}_notify(void) {{
+    k_poll_signal_raise(&{name}_signal, (int)atomic_get(&{name}_bits));
+}}
+
+void {name}_set(uint32_t events) {{
+    atomic_set(&{name}_bits, (atomic_val_t)events);
+    {name}_notify();
+}}
+
+void {name}_clear(uint32_t events) {{
+    atomic_and(&{name}_bits, ~(atomic_val_t)events);
+}}
+
+/* Each wakeup that does not satisfy the wait restarts the timeout */
+uint32_t {name}_wait(uint32_t events, bool wait_all, k_timeout_t timeout) {{
+    struct k_poll_event poll_event = K_POLL_EVENT_INITIALIZER(
+        K_POLL_TYPE_SIGNAL, K_POLL_MODE_NOTIFY_ONLY, &{name}_signal);
+
+    while (1) {{
+        k_poll_signal_reset(&{name}_signal);
+        /* Checked after the reset so a post in between is not lost */
+        uint32_t bits = (uint32_t)atomic_get(&{name}_bits) & events;
+        if (wait_all ? bits == events : bits != 0) {{
+            return bits;
+        }}
+        poll_event.state = K_POLL_STATE_NOT_READY;
+        if (k_poll(&poll_event, 1, timeout) != 0) {{
+            return 0;
+        }}
+    }}
+}}
+
+void {name}_post(uint32_t events) {{
+    atomic_or(&{name}_bits, (atomic_val_t)events);
+    {name}_notify();
+}}
+"#,
+        name = name,
+    )
+}
@@ -0,0 +1,519 @@
+// SD Card (SDIO/SDMMC) Driver Generator
+// Generates STM32 HAL SD card init, block read/write and the FatFS BSP glue
+
+use super::mcu::McuFamily;
+use super::templates::*;
+use serde::{Deserialize, Serialize};
+
+/// Data bus width
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SdmmcBusWidth {
+    Width1Bit,
+    Width4Bit,
+    Width8Bit,
+}
+
+impl SdmmcBusWidth {
+    pub fn bits(&self) -> u8 {
+        match self {
+            SdmmcBusWidth::Width1Bit => 1,
+            SdmmcBusWidth::Width4Bit => 4,
+            SdmmcBusWidth::Width8Bit => 8,
+        }
+    }
+}
+
+/// SD card host configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SdmmcConfig {
+    /// SDMMC instance number; SDIO parts only have 1
+    pub instance: u8,
+    pub bus_width: SdmmcBusWidth,
+    /// CLKDIV value applied after card identification
+    pub clock_div: u32,
+    pub dma_enabled: bool,
+}
+
+impl Default for SdmmcConfig {
+    /// 4-bit bus at 24 MHz on an F4 (48 MHz / (0 + 2))
+    fn default() -> Self {
+        Self {
+            instance: 1,
+            bus_width: SdmmcBusWidth::Width4Bit,
+            clock_div: 0,
+            dma_enabled: true,
+        }
+    }
+}
+
+impl SdmmcConfig {
+    pub fn validate(&self, mcu: McuFamily) -> Result<(), String> {
+        if !supports_sdmmc(mcu) {
+            return Err(format!("SD card generation is not available for {}", mcu.display_name()));
+        }
+        let max_instance = if mcu == McuFamily::STM32H7 { 2 } else { 1 };
+        if !(1..=max_instance).contains(&self.instance) {
+            return Err(format!("{} has no {}", mcu.display_name(), instance_name(self.instance, mcu)));
+        }
+        if self.bus_width == SdmmcBusWidth::Width8Bit {
+            return Err("SD cards support 1- and 4-bit buses; 8-bit is eMMC only".to_string());
+        }
+        let max_div = if uses_sdmmc_ip(mcu) { 1023 } else { 255 };
+        if self.clock_div > max_div {
+            return Err(format!("Clock divider must be 0-{}, got {}", max_div, self.clock_div));
+        }
+        // Default-speed cards top out at 25 MHz; only H7 switches to high speed
+        let max_hz = if mcu == McuFamily::STM32H7 { 50_000_000 } else { 25_000_000 };
+        let card_clock = self.card_clock_hz(mcu);
+        if card_clock > max_hz {
+            return Err(format!(
+                "Card clock {:.1} MHz exceeds {} MHz; raise the clock divider",
+                card_clock as f64 / 1e6,
+                max_hz / 1_000_000
+            ));
+        }
+        if self.dma_enabled && matches!(mcu, McuFamily::STM32F1 | McuFamily::STM32L4) {
+            return Err(format!(
+                "DMA generation is not available for {} (single shared channel); use polling",
+                mcu.display_name()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Card clock after identification
+    ///
+    /// SDIO and the L4 SDMMC divide by CLKDIV + 2; the H7 SDMMC divides by
+    /// 2 x CLKDIV, with 0 passing the kernel clock straight through.
+    pub fn card_clock_hz(&self, mcu: McuFamily) -> u32 {
+        let kernel = kernel_clock_hz(mcu);
+        if mcu == McuFamily::STM32H7 {
+            if self.clock_div == 0 { kernel } else { kernel / (2 * self.clock_div) }
+        } else {
+            kernel / (self.clock_div + 2)
+        }
+    }
+}
+
+/// Families with an SDIO/SDMMC host in the HAL
+pub fn supports_sdmmc(mcu: McuFamily) -> bool {
+    matches!(mcu, McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32H7 | McuFamily::STM32L4)
+}
+
+/// SDMMC peripheral (H7/L4) rather than the older SDIO block (F1/F4)
+fn uses_sdmmc_ip(mcu: McuFamily) -> bool {
+    matches!(mcu, McuFamily::STM32H7 | McuFamily::STM32L4)
+}
+
+fn instance_name(instance: u8, mcu: McuFamily) -> String {
+    if uses_sdmmc_ip(mcu) { format!("SDMMC{}", instance) } else { "SDIO".to_string() }
+}
+
+/// Typical kernel clock feeding the host: 48 MHz from PLLQ, HCLK on F1, PLL1Q on H7
+fn kernel_clock_hz(mcu: McuFamily) -> u32 {
+    match mcu {
+        McuFamily::STM32F1 => 72_000_000,
+        McuFamily::STM32H7 => 200_000_000,
+        _ => 48_000_000,
+    }
+}
+
+fn hal_header(mcu: McuFamily) -> &'static str {
+    match mcu {
+        McuFamily::STM32F1 => "stm32f1xx_hal.h",
+        McuFamily::STM32H7 => "stm32h7xx_hal.h",
+        McuFamily::STM32L4 => "stm32l4xx_hal.h",
+        _ => "stm32f4xx_hal.h",
+    }
+}
+
+/// Pin setup for CK, CMD and the used data lines inside `HAL_SD_MspInit`
+fn gpio_setup(config: &SdmmcConfig, mcu: McuFamily) -> String {
+    // (port, pin, alternate function) for CK, CMD, D0..D3
+    let pins: [(&str, u8, &str); 6] = if config.instance == 2 {
+        [("D", 6, "GPIO_AF11_SDMMC2"), ("D", 7, "GPIO_AF11_SDMMC2"), ("B", 14, "GPIO_AF9_SDMMC2"),
+         ("B", 15, "GPIO_AF9_SDMMC2"), ("B", 3, "GPIO_AF9_SDMMC2"), ("B", 4, "GPIO_AF9_SDMMC2")]
+    } else {
+        let af = match mcu {
+            McuFamily::STM32H7 => "GPIO_AF12_SDIO1",
+            McuFamily::STM32L4 => "GPIO_AF12_SDMMC1",
+            _ => "GPIO_AF12_SDIO",
+        };
+        [("C", 12, af), ("D", 2, af), ("C", 8, af), ("C", 9, af), ("C", 10, af), ("C", 11, af)]
+    };
+    let used = 2 + config.bus_width.bits() as usize;
+
+    let mut ports: Vec<&str> = pins[..used].iter().map(|p| p.0).collect();
+    ports.sort();
+    ports.dedup();
+    let mut code: String = ports.iter().map(|port| format!("    __HAL_RCC_GPIO{}_CLK_ENABLE();\n", port)).collect();
+    code.push_str("\n    GPIO_InitTypeDef gpio = {0};\n");
+    if mcu == McuFamily::STM32F1 {
+        code.push_str("    gpio.Mode = GPIO_MODE_AF_PP;\n    gpio.Speed = GPIO_SPEED_FREQ_HIGH;\n");
+    } else {
+        code.push_str("    gpio.Mode = GPIO_MODE_AF_PP;\n    gpio.Pull = GPIO_PULLUP;\n    gpio.Speed = GPIO_SPEED_FREQ_VERY_HIGH;\n");
+    }
+    for (port, pin, af) in &pins[..used] {
+        if mcu != McuFamily::STM32F1 {
+            code.push_str(&format!("    gpio.Alternate = {};\n", af));
+        }
+        code.push_str(&format!("    gpio.Pin = GPIO_PIN_{};\n    HAL_GPIO_Init(GPIO{}, &gpio);\n", pin, port));
+    }
+    code
+}
+
+/// Generate SD card driver code
+pub fn generate_sdmmc_driver(config: &SdmmcConfig, mcu: McuFamily) -> DriverOutput {
+    let instance = instance_name(config.instance, mcu);
+    let handle = format!("hsd{}", config.instance);
+    let prefix = if uses_sdmmc_ip(mcu) { "SDMMC" } else { "SDIO" };
+    let bits = config.bus_width.bits();
+    let clock_div = config.clock_div;
+    let card_clock = config.card_clock_hz(mcu);
+    let kernel_clock = kernel_clock_hz(mcu);
+    let mode = if config.dma_enabled {
+        if mcu == McuFamily::STM32H7 { "internal DMA" } else { "DMA" }
+    } else {
+        "polling"
+    };
+
+    let header = format!(r#"/**
+ * SD Card Driver - {instance}, {bits}-bit bus, {mode}
+ * Auto-generated by NeuroBench
+ * Card clock: {card_mhz:.2} MHz ({kernel_mhz} MHz kernel clock, CLKDIV {clock_div})
+ */
+
+#ifndef SD_CARD_H
+#define SD_CARD_H
+
+#include "{hal}"
+#include <stdbool.h>
+#include <stdint.h>
+
+#define SD_BLOCK_SIZE   512U
+#define SD_TIMEOUT_MS   1000U
+
+// Card detect switch, active low; leave undefined to assume a card is present
+// #define SD_DETECT_GPIO_PORT GPIOC
+// #define SD_DETECT_PIN       GPIO_PIN_13
+
+typedef enum {{
+    SD_OK = 0,
+    SD_ERROR,
+    SD_NO_CARD,
+    SD_TIMEOUT,
+}} sd_status_t;
+
+extern SD_HandleTypeDef {handle};
+
+bool sd_is_detected(void);
+sd_status_t sd_init(void);
+sd_status_t sd_read_blocks(uint8_t *buffer, uint32_t block, uint32_t count);
+sd_status_t sd_write_blocks(const uint8_t *buffer, uint32_t block, uint32_t count);
+uint32_t sd_block_count(void);
+
+#endif // SD_CARD_H
+"#,
+        card_mhz = card_clock as f64 / 1e6,
+        kernel_mhz = kernel_clock / 1_000_000,
+        hal = hal_header(mcu),
+    );
+
+    let clock_bypass = if mcu == McuFamily::STM32H7 {
+        String::new()
+    } else {
+        format!("    {handle}.Init.ClockBypass = {prefix}_CLOCK_BYPASS_DISABLE;\n")
+    };
+    let irq = if uses_sdmmc_ip(mcu) { format!("SDMMC{}", config.instance) } else { "SDIO".to_string() };
+    let wide_bus = if bits == 4 {
+        format!(r#"
+    // Identification runs on one data line; widen the bus once the card is selected
+    if (HAL_SD_ConfigWideBusOperation(&{handle}, {prefix}_BUS_WIDE_4B) != HAL_OK) {{
+        return SD_ERROR;
+    }}
+"#)
+    } else {
+        String::new()
+    };
+
+    // F4 SDIO has separate RX/TX streams on DMA2; H7 SDMMC has its own IDMA
+    let (dma_globals, dma_msp) = if config.dma_enabled && mcu == McuFamily::STM32F4 {
+        let stream = |name: &str, stream: &str, direction: &str, link: &str| format!(
+            r#"
+    hdma_sdio_{name}.Instance = {stream};
+    hdma_sdio_{name}.Init.Channel = DMA_CHANNEL_4;
+    hdma_sdio_{name}.Init.Direction = {direction};
+    hdma_sdio_{name}.Init.PeriphInc = DMA_PINC_DISABLE;
+    hdma_sdio_{name}.Init.MemInc = DMA_MINC_ENABLE;
+    hdma_sdio_{name}.Init.PeriphDataAlignment = DMA_PDATAALIGN_WORD;
+    hdma_sdio_{name}.Init.MemDataAlignment = DMA_MDATAALIGN_WORD;
+    hdma_sdio_{name}.Init.Mode = DMA_PFCTRL;
+    hdma_sdio_{name}.Init.Priority = DMA_PRIORITY_VERY_HIGH;
+    hdma_sdio_{name}.Init.FIFOMode = DMA_FIFOMODE_ENABLE;
+    hdma_sdio_{name}.Init.FIFOThreshold = DMA_FIFO_THRESHOLD_FULL;
+    hdma_sdio_{name}.Init.MemBurst = DMA_MBURST_INC4;
+    hdma_sdio_{name}.Init.PeriphBurst = DMA_PBURST_INC4;
+    HAL_DMA_Init(&hdma_sdio_{name});
+    __HAL_LINKDMA(hsd, {link}, hdma_sdio_{name});
+    HAL_NVIC_SetPriority({stream}_IRQn, 6, 0);
+    HAL_NVIC_EnableIRQ({stream}_IRQn);
+"#);
+        (
+            "DMA_HandleTypeDef hdma_sdio_rx;\nDMA_HandleTypeDef hdma_sdio_tx;\n\nvoid DMA2_Stream3_IRQHandler(void) {\n    HAL_DMA_IRQHandler(&hdma_sdio_rx);\n}\n\nvoid DMA2_Stream6_IRQHandler(void) {\n    HAL_DMA_IRQHandler(&hdma_sdio_tx);\n}\n".to_string(),
+            format!(
+                "\n    __HAL_RCC_DMA2_CLK_ENABLE();{}{}",
+                stream("rx", "DMA2_Stream3", "DMA_PERIPH_TO_MEMORY", "hdmarx"),
+                stream("tx", "DMA2_Stream6", "DMA_MEMORY_TO_PERIPH", "hdmatx"),
+            ),
+        )
+    } else {
+        (String::new(), String::new())
+    };
+
+    let transfers = if config.dma_enabled {
+        // H7 IDMA reads memory directly, so the D-cache must be maintained
+        let (clean, invalidate) = if mcu == McuFamily::STM32H7 {
+            (
+                "    SCB_CleanDCache_by_Addr((uint32_t *)buffer, (int32_t)(count * SD_BLOCK_SIZE));\n",
+                "    SCB_InvalidateDCache_by_Addr((uint32_t *)buffer, (int32_t)(count * SD_BLOCK_SIZE));\n",
+            )
+        } else {
+            ("", "")
+        };
+        format!(r#"
+static volatile bool sd_rx_done;
+static volatile bool sd_tx_done;
+
+// Completion hooks that CubeMX's DMA sd_diskio.c overrides to unblock FatFS
+__weak void BSP_SD_ReadCpltCallback(void) {{
+}}
+
+__weak void BSP_SD_WriteCpltCallback(void) {{
+}}
+
+void HAL_SD_RxCpltCallback(SD_HandleTypeDef *hsd) {{
+    (void)hsd;
+    sd_rx_done = true;
+    BSP_SD_ReadCpltCallback();
+}}
+
+void HAL_SD_TxCpltCallback(SD_HandleTypeDef *hsd) {{
+    (void)hsd;
+    sd_tx_done = true;
+    BSP_SD_WriteCpltCallback();
+}}
+
+static sd_status_t sd_wait(volatile bool *done) {{
+    uint32_t start = HAL_GetTick();
+    while (!*done) {{
+        if (HAL_GetTick() - start > SD_TIMEOUT_MS) {{
+            return SD_TIMEOUT;
+        }}
+    }}
+    return sd_wait_ready();
+}}
+
+// Buffers must be 4-byte aligned{dtcm_note}
+sd_status_t sd_read_blocks(uint8_t *buffer, uint32_t block, uint32_t count) {{
+    sd_rx_done = false;
+    if (HAL_SD_ReadBlocks_DMA(&{handle}, buffer, block, count) != HAL_OK) {{
+        return SD_ERROR;
+    }}
+    sd_status_t status = sd_wait(&sd_rx_done);
+{invalidate}    return status;
+}}
+
+sd_status_t sd_write_blocks(const uint8_t *buffer, uint32_t block, uint32_t count) {{
+{clean}    sd_tx_done = false;
+    if (HAL_SD_WriteBlocks_DMA(&{handle}, (uint8_t *)buffer, block, count) != HAL_OK) {{
+        return SD_ERROR;
+    }}
+    return sd_wait(&sd_tx_done);
+}}
+"#,
+            dtcm_note = if mcu == McuFamily::STM32H7 { " and outside DTCM (IDMA cannot reach it)" } else { "" },
+        )
+    } else {
+        format!(r#"
+sd_status_t sd_read_blocks(uint8_t *buffer, uint32_t block, uint32_t count) {{
+    if (HAL_SD_ReadBlocks(&{handle}, buffer, block, count, SD_TIMEOUT_MS) != HAL_OK) {{
+        return SD_ERROR;
+    }}
+    return sd_wait_ready();
+}}
+
+sd_status_t sd_write_blocks(const uint8_t *buffer, uint32_t block, uint32_t count) {{
+    if (HAL_SD_WriteBlocks(&{handle}, (uint8_t *)buffer, block, count, SD_TIMEOUT_MS) != HAL_OK) {{
+        return SD_ERROR;
+    }}
+    return sd_wait_ready();
+}}
+"#)
+    };
+
+    let fatfs_dma = if config.dma_enabled {
+        format!(r#"
+uint8_t BSP_SD_ReadBlocks_DMA(uint32_t *pData, uint32_t ReadAddr, uint32_t NumOfBlocks) {{
+    return HAL_SD_ReadBlocks_DMA(&{handle}, (uint8_t *)pData, ReadAddr, NumOfBlocks) == HAL_OK ? MSD_OK : MSD_ERROR;
+}}
+
+uint8_t BSP_SD_WriteBlocks_DMA(uint32_t *pData, uint32_t WriteAddr, uint32_t NumOfBlocks) {{
+    return HAL_SD_WriteBlocks_DMA(&{handle}, (uint8_t *)pData, WriteAddr, NumOfBlocks) == HAL_OK ? MSD_OK : MSD_ERROR;
+}}
+"#)
+    } else {
+        String::new()
+    };
+
+    let source = format!(r#"/**
+ * SD Card Driver - {instance}
+ * Auto-generated by NeuroBench
+ */
+
+#include "sd_card.h"
+
+SD_HandleTypeDef {handle};
+{dma_globals}
+void HAL_SD_MspInit(SD_HandleTypeDef *hsd) {{
+    if (hsd->Instance != {instance}) {{
+        return;
+    }}
+    __HAL_RCC_{instance}_CLK_ENABLE();
+{gpio}{dma_msp}
+    HAL_NVIC_SetPriority({irq}_IRQn, 5, 0);
+    HAL_NVIC_EnableIRQ({irq}_IRQn);
+}}
+
+void {irq}_IRQHandler(void) {{
+    HAL_SD_IRQHandler(&{handle});
+}}
+
+bool sd_is_detected(void) {{
+#ifdef SD_DETECT_GPIO_PORT
+    return HAL_GPIO_ReadPin(SD_DETECT_GPIO_PORT, SD_DETECT_PIN) == GPIO_PIN_RESET;
+#else
+    return true;
+#endif
+}}
+
+static sd_status_t sd_wait_ready(void) {{
+    uint32_t start = HAL_GetTick();
+    while (HAL_SD_GetCardState(&{handle}) != HAL_SD_CARD_TRANSFER) {{
+        if (HAL_GetTick() - start > SD_TIMEOUT_MS) {{
+            return SD_TIMEOUT;
+        }}
+    }}
+    return SD_OK;
+}}
+
+sd_status_t sd_init(void) {{
+    if (!sd_is_detected()) {{
+        return SD_NO_CARD;
+    }}
+
+    // HAL_SD_Init identifies the card at 400 kHz (CMD0, CMD8, ACMD41, CMD2, CMD3)
+    // and then switches to ClockDiv for data transfer
+    {handle}.Instance = {instance};
+    {handle}.Init.ClockEdge = {prefix}_CLOCK_EDGE_RISING;
+{clock_bypass}    {handle}.Init.ClockPowerSave = {prefix}_CLOCK_POWER_SAVE_DISABLE;
+    {handle}.Init.BusWide = {prefix}_BUS_WIDE_1B;
+    {handle}.Init.HardwareFlowControl = {prefix}_HARDWARE_FLOW_CONTROL_DISABLE;
+    {handle}.Init.ClockDiv = {clock_div};
+    if (HAL_SD_Init(&{handle}) != HAL_OK) {{
+        return SD_ERROR;
+    }}
+{wide_bus}    return sd_wait_ready();
+}}
+
+uint32_t sd_block_count(void) {{
+    HAL_SD_CardInfoTypeDef info;
+    HAL_SD_GetCardInfo(&{handle}, &info);
+    return info.LogBlockNbr;
+}}
+{transfers}
+/* ---- FatFS glue: the BSP API called by sd_diskio.c ---- */
+
+#ifndef MSD_OK
+#define MSD_OK          ((uint8_t)0x00)
+#define MSD_ERROR       ((uint8_t)0x01)
+#endif
+#define SD_TRANSFER_OK   ((uint8_t)0x00)
+#define SD_TRANSFER_BUSY ((uint8_t)0x01)
+#define SD_PRESENT       ((uint8_t)0x01)
+#define SD_NOT_PRESENT   ((uint8_t)0x00)
+
+uint8_t BSP_SD_Init(void) {{
+    return sd_init() == SD_OK ? MSD_OK : MSD_ERROR;
+}}
+
+uint8_t BSP_SD_IsDetected(void) {{
+    return sd_is_detected() ? SD_PRESENT : SD_NOT_PRESENT;
+}}
+
+uint8_t BSP_SD_ReadBlocks(uint32_t *pData, uint32_t ReadAddr, uint32_t NumOfBlocks, uint32_t Timeout) {{
+    return HAL_SD_ReadBlocks(&{handle}, (uint8_t *)pData, ReadAddr, NumOfBlocks, Timeout) == HAL_OK ? MSD_OK : MSD_ERROR;
+}}
+
+uint8_t BSP_SD_WriteBlocks(uint32_t *pData, uint32_t WriteAddr, uint32_t NumOfBlocks, uint32_t Timeout) {{
+    return HAL_SD_WriteBlocks(&{handle}, (uint8_t *)pData, WriteAddr, NumOfBlocks, Timeout) == HAL_OK ? MSD_OK : MSD_ERROR;
+}}
+{fatfs_dma}
+uint8_t BSP_SD_GetCardState(void) {{
+    return HAL_SD_GetCardState(&{handle}) == HAL_SD_CARD_TRANSFER ? SD_TRANSFER_OK : SD_TRANSFER_BUSY;
+}}
+
+void BSP_SD_GetCardInfo(HAL_SD_CardInfoTypeDef *CardInfo) {{
+    HAL_SD_GetCardInfo(&{handle}, CardInfo);
+}}
+"#,
+        gpio = gpio_setup(config, mcu),
+    );
+
+    let example = r#"/**
+ * SD Card Example
+ * Mounts the card with FatFS and appends a line to log.txt
+ *
+ * Hardware notes:
+ * - Card detect: most sockets have a switch closing to GND when a card is
+ *   inserted. Wire it to a GPIO with a pull-up and define SD_DETECT_GPIO_PORT
+ *   and SD_DETECT_PIN in sd_card.h; without it a card is assumed present.
+ * - CMD and D0-D3 need 10k-47k pull-ups (the internal ones are enabled but weak).
+ * - Cards signal at 3.3 V in default/high speed. UHS-I modes switch to 1.8 V
+ *   and need a voltage translator/level shifter (e.g. a dedicated SD
+ *   transceiver) between the card and the MCU; this driver stays at 3.3 V.
+ * - On a 1.8 V MCU supply, a translator is required in every mode.
+ */
+
+#include "sd_card.h"
+#include "ff.h"
+
+static FATFS fs;
+static FIL file;
+
+int main(void) {
+    HAL_Init();
+
+    if (sd_init() != SD_OK) {
+        Error_Handler();  // No card, or card not responding
+    }
+
+    if (f_mount(&fs, "", 1) == FR_OK) {
+        if (f_open(&file, "log.txt", FA_OPEN_APPEND | FA_WRITE) == FR_OK) {
+            UINT written;
+            f_write(&file, "boot\r\n", 6, &written);
+            f_close(&file);
+        }
+    }
+
+    while (1) {
+    }
+}
+"#.to_string();
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: Some(example),
+        peripheral_type: PeripheralType::SDMMC,
+    }
+}
@@ -0,0 +1,679 @@
+// Delta OTA Generator
+// Streaming binary patch application and host-side patch creation
+
+use super::*;
+use crate::drivers::templates::{DriverOutput, PeripheralType};
+use std::path::Path;
+use std::process::Command;
+
+/// Generate the on-device delta patch updater
+pub fn generate_delta_patch_updater(config: &DeltaOtaConfig) -> DriverOutput {
+    let (algorithm_name, decoder) = match config.algorithm {
+        DeltaAlgorithm::Janpatch => ("JojoDiff (janpatch)", JANPATCH_DECODER),
+        DeltaAlgorithm::Bsdiff4 => ("ENDSLEY/BSDIFF43, uncompressed", BSDIFF_DECODER),
+    };
+
+    let header = format!(r#"/**
+ * Delta OTA Patch Updater
+ * Auto-generated by NeuroBench
+ * Format: {algorithm_name}
+ */
+
+#ifndef DELTA_OTA_H
+#define DELTA_OTA_H
+
+#include <stdint.h>
+#include <stdbool.h>
+
+#define DELTA_FLASH_SLOT    0x{flash_slot:08X}
+#define DELTA_SLOT_SIZE     0x{slot_size:X}
+#define DELTA_SCRATCH_SIZE  {scratch_size}
+
+// The last sector of the slot holds the commit record, the rest the image
+#define DELTA_IMAGE_MAX     (DELTA_SLOT_SIZE - DELTA_SCRATCH_SIZE)
+#ifndef DELTA_COMMIT_ADDR
+#define DELTA_COMMIT_ADDR   (DELTA_FLASH_SLOT + DELTA_IMAGE_MAX)
+#endif
+
+typedef enum {{
+    DELTA_OK = 0,
+    DELTA_ERR_STATE,
+    DELTA_ERR_FORMAT,
+    DELTA_ERR_BOUNDS,
+    DELTA_ERR_FLASH,
+    DELTA_ERR_CRC,
+}} delta_status_t;
+
+// Platform hooks: erase whole sectors, program at any aligned offset
+bool delta_flash_erase(uint32_t address, uint32_t length);
+bool delta_flash_write(uint32_t address, const uint8_t *data, uint32_t length);
+
+delta_status_t delta_patch_begin(uint32_t source_address, uint32_t source_length);
+delta_status_t delta_patch_feed(const uint8_t *chunk, uint32_t length);
+delta_status_t delta_patch_finish(uint32_t expected_crc32);
+bool delta_slot_committed(uint32_t *image_length);
+
+#endif // DELTA_OTA_H
+"#,
+        flash_slot = config.flash_slot,
+        slot_size = config.slot_size,
+        scratch_size = config.scratch_size,
+    );
+
+    let source = format!(r#"/**
+ * Delta OTA Patch Updater
+ * Auto-generated by NeuroBench
+ *
+ * The patch is streamed in with delta_patch_feed(). Reconstructed bytes are
+ * collected in a {scratch_size}-byte scratch buffer and programmed into the
+ * target slot one sector at a time, so the full image never sits in RAM.
+ * The commit record (length + CRC) is written last: a power failure at any
+ * earlier point leaves the slot uncommitted and the bootloader ignores it.
+ */
+
+#include "delta_ota.h"
+#include <string.h>
+
+#define DELTA_COMMIT_MAGIC 0x544C4544u  // "DELT"
+
+typedef struct {{
+    uint32_t magic;
+    uint32_t length;
+    uint32_t crc32;
+    uint32_t length_inv;
+}} delta_commit_t;
+
+static struct {{
+    bool active;
+    const uint8_t *source;
+    uint32_t source_length;
+    uint32_t source_pos;
+    uint32_t written;
+    uint32_t fill;
+    uint32_t crc;
+    delta_status_t error;
+}} ctx;
+
+static uint8_t scratch[DELTA_SCRATCH_SIZE] __attribute__((aligned(8)));
+
+static uint32_t crc32_update(uint32_t crc, const uint8_t *data, uint32_t length) {{
+    crc = ~crc;
+    while (length--) {{
+        crc ^= *data++;
+        for (int i = 0; i < 8; i++) {{
+            crc = (crc >> 1) ^ (0xEDB88320u & (0u - (crc & 1u)));
+        }}
+    }}
+    return ~crc;
+}}
+
+static bool flush_scratch(void) {{
+    if (ctx.fill == 0) {{
+        return true;
+    }}
+    // Never spill into the commit record sector
+    if (ctx.written + DELTA_SCRATCH_SIZE > DELTA_IMAGE_MAX) {{
+        ctx.error = DELTA_ERR_BOUNDS;
+        return false;
+    }}
+    uint32_t address = DELTA_FLASH_SLOT + ctx.written;
+    if (!delta_flash_erase(address, DELTA_SCRATCH_SIZE) ||
+        !delta_flash_write(address, scratch, ctx.fill)) {{
+        ctx.error = DELTA_ERR_FLASH;
+        return false;
+    }}
+    ctx.crc = crc32_update(ctx.crc, scratch, ctx.fill);
+    ctx.written += ctx.fill;
+    ctx.fill = 0;
+    return true;
+}}
+
+static bool emit(uint8_t byte) {{
+    scratch[ctx.fill++] = byte;
+    return ctx.fill < DELTA_SCRATCH_SIZE || flush_scratch();
+}}
+
+static bool source_byte(uint8_t *byte) {{
+    if (ctx.source_pos >= ctx.source_length) {{
+        ctx.error = DELTA_ERR_BOUNDS;
+        return false;
+    }}
+    *byte = ctx.source[ctx.source_pos++];
+    return true;
+}}
+{decoder}
+delta_status_t delta_patch_begin(uint32_t source_address, uint32_t source_length) {{
+    memset(&ctx, 0, sizeof(ctx));
+    decoder_reset();
+
+    // Invalidate any previous image before touching the slot
+    if (!delta_flash_erase(DELTA_COMMIT_ADDR, DELTA_SCRATCH_SIZE)) {{
+        return DELTA_ERR_FLASH;
+    }}
+    ctx.source = (const uint8_t *)source_address;
+    ctx.source_length = source_length;
+    ctx.active = true;
+    return DELTA_OK;
+}}
+
+delta_status_t delta_patch_feed(const uint8_t *chunk, uint32_t length) {{
+    if (!ctx.active) {{
+        return DELTA_ERR_STATE;
+    }}
+    for (uint32_t i = 0; i < length; i++) {{
+        if (!decoder_step(chunk[i])) {{
+            ctx.active = false;
+            return ctx.error != DELTA_OK ? ctx.error : DELTA_ERR_FORMAT;
+        }}
+    }}
+    return DELTA_OK;
+}}
+
+delta_status_t delta_patch_finish(uint32_t expected_crc32) {{
+    if (!ctx.active) {{
+        return DELTA_ERR_STATE;
+    }}
+    ctx.active = false;
+    if (!decoder_finished() || !flush_scratch()) {{
+        return ctx.error != DELTA_OK ? ctx.error : DELTA_ERR_FORMAT;
+    }}
+
+    // Re-read what landed in flash rather than trusting the write path
+    uint32_t crc = crc32_update(0, (const uint8_t *)DELTA_FLASH_SLOT, ctx.written);
+    if (crc != ctx.crc || crc != expected_crc32) {{
+        return DELTA_ERR_CRC;
+    }}
+
+    // Commit last: only a fully patched, verified image gets a length
+    delta_commit_t record = {{ DELTA_COMMIT_MAGIC, ctx.written, crc, ~ctx.written }};
+    if (!delta_flash_write(DELTA_COMMIT_ADDR, (const uint8_t *)&record, sizeof(record))) {{
+        return DELTA_ERR_FLASH;
+    }}
+    return DELTA_OK;
+}}
+
+bool delta_slot_committed(uint32_t *image_length) {{
+    const delta_commit_t *record = (const delta_commit_t *)DELTA_COMMIT_ADDR;
+    if (record->magic != DELTA_COMMIT_MAGIC || record->length != ~record->length_inv) {{
+        return false;
+    }}
+    if (crc32_update(0, (const uint8_t *)DELTA_FLASH_SLOT, record->length) != record->crc32) {{
+        return false;
+    }}
+    if (image_length) {{
+        *image_length = record->length;
+    }}
+    return true;
+}}
+"#,
+        scratch_size = config.scratch_size,
+    );
+
+    let example = r#"/**
+ * Delta OTA Example
+ * Patches the running image (slot A) into DELTA_FLASH_SLOT
+ */
+
+#include "delta_ota.h"
+
+#define SLOT_A_ADDR 0x08008000
+
+extern uint32_t ota_receive(uint8_t *buf, uint32_t max_len);  // Transport of your choice
+
+void apply_update(uint32_t running_length, uint32_t expected_crc32) {
+    static uint8_t chunk[256];
+
+    if (delta_patch_begin(SLOT_A_ADDR, running_length) != DELTA_OK) {
+        return;
+    }
+    uint32_t n;
+    while ((n = ota_receive(chunk, sizeof(chunk))) > 0) {
+        if (delta_patch_feed(chunk, n) != DELTA_OK) {
+            return;  // Slot stays uncommitted
+        }
+    }
+    if (delta_patch_finish(expected_crc32) == DELTA_OK) {
+        NVIC_SystemReset();  // Bootloader picks up the committed slot
+    }
+}
+"#.to_string();
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: Some(example),
+        peripheral_type: PeripheralType::OTA,
+    }
+}
+
+const JANPATCH_DECODER: &str = r#"
+// ---------------------------------------------------------------------------
+// JojoDiff decoder: ESC-prefixed opcodes, MOD/INS carry data bytes,
+// DEL/EQL/BKT carry a variable-length count
+// ---------------------------------------------------------------------------
+
+#define JD_ESC 0xA7
+#define JD_MOD 0xA6
+#define JD_INS 0xA5
+#define JD_DEL 0xA4
+#define JD_EQL 0xA3
+#define JD_BKT 0xA2
+
+typedef enum {
+    JD_STATE_OPCODE,
+    JD_STATE_DATA,
+    JD_STATE_DATA_ESC,
+    JD_STATE_LENGTH,
+    JD_STATE_LENGTH_EXT,
+} jd_state_t;
+
+static struct {
+    jd_state_t state;
+    uint8_t command;
+    bool pending_esc;
+    uint8_t ext_bytes;
+    uint32_t base;
+    uint32_t length;
+} jd;
+
+static void decoder_reset(void) {
+    memset(&jd, 0, sizeof(jd));
+    jd.state = JD_STATE_OPCODE;
+}
+
+static bool jd_data(uint8_t byte) {
+    if (jd.command == JD_MOD) {
+        ctx.source_pos++;  // Overwrites one source byte
+    }
+    return emit(byte);
+}
+
+static bool jd_run_length(void) {
+    uint8_t byte;
+    switch (jd.command) {
+        case JD_DEL:
+            ctx.source_pos += jd.length;
+            break;
+        case JD_BKT:
+            if (jd.length > ctx.source_pos) {
+                ctx.error = DELTA_ERR_BOUNDS;
+                return false;
+            }
+            ctx.source_pos -= jd.length;
+            break;
+        case JD_EQL:
+            while (jd.length--) {
+                if (!source_byte(&byte) || !emit(byte)) {
+                    return false;
+                }
+            }
+            break;
+    }
+    jd.state = JD_STATE_OPCODE;
+    return true;
+}
+
+static bool jd_start(uint8_t opcode) {
+    jd.command = opcode;
+    jd.length = 0;
+    jd.state = (opcode == JD_MOD || opcode == JD_INS) ? JD_STATE_DATA : JD_STATE_LENGTH;
+    return opcode >= JD_BKT && opcode <= JD_MOD;
+}
+
+static bool decoder_step(uint8_t byte) {
+    switch (jd.state) {
+        case JD_STATE_OPCODE:
+            if (!jd.pending_esc) {
+                if (byte != JD_ESC) {
+                    return false;
+                }
+                jd.pending_esc = true;
+                return true;
+            }
+            jd.pending_esc = false;
+            return jd_start(byte);
+
+        case JD_STATE_DATA:
+            if (byte == JD_ESC) {
+                jd.state = JD_STATE_DATA_ESC;
+                return true;
+            }
+            return jd_data(byte);
+
+        case JD_STATE_DATA_ESC:
+            jd.state = JD_STATE_DATA;
+            if (byte >= JD_BKT && byte <= JD_MOD) {
+                return jd_start(byte);
+            }
+            if (byte == JD_ESC) {
+                return jd_data(JD_ESC);  // Escaped literal
+            }
+            return jd_data(JD_ESC) && jd_data(byte);
+
+        case JD_STATE_LENGTH:
+            if (byte < 252) {
+                jd.length = byte + 1u;
+                return jd_run_length();
+            }
+            if (byte == 255) {
+                return false;
+            }
+            // 252: 253 + one byte, 253: two bytes, 254: four bytes (big-endian)
+            jd.ext_bytes = byte == 252 ? 1 : byte == 253 ? 2 : 4;
+            jd.base = byte == 252 ? 253 : 0;
+            jd.length = 0;
+            jd.state = JD_STATE_LENGTH_EXT;
+            return true;
+
+        case JD_STATE_LENGTH_EXT:
+            jd.length = (jd.length << 8) | byte;
+            if (--jd.ext_bytes > 0) {
+                return true;
+            }
+            jd.length += jd.base;
+            return jd_run_length();
+    }
+    return false;
+}
+
+static bool decoder_finished(void) {
+    return jd.state == JD_STATE_OPCODE || jd.state == JD_STATE_DATA;
+}
+"#;
+
+const BSDIFF_DECODER: &str = r#"
+// ---------------------------------------------------------------------------
+// bsdiff decoder: "ENDSLEY/BSDIFF43" header with the new size, then
+// interleaved blocks of control triple, diff bytes and extra bytes
+// ---------------------------------------------------------------------------
+
+typedef enum {
+    BS_STATE_HEADER,
+    BS_STATE_CONTROL,
+    BS_STATE_DIFF,
+    BS_STATE_EXTRA,
+    BS_STATE_DONE,
+} bs_state_t;
+
+static struct {
+    bs_state_t state;
+    uint8_t buf[24];
+    uint8_t buf_fill;
+    int64_t new_size;
+    int64_t produced;
+    int64_t diff_left;
+    int64_t extra_left;
+    int64_t seek;
+} bs;
+
+static void decoder_reset(void) {
+    memset(&bs, 0, sizeof(bs));
+    bs.state = BS_STATE_HEADER;
+}
+
+// bsdiff integers are sign-magnitude little-endian
+static int64_t bs_offtin(const uint8_t *p) {
+    int64_t y = p[7] & 0x7F;
+    for (int i = 6; i >= 0; i--) {
+        y = (y << 8) | p[i];
+    }
+    return (p[7] & 0x80) ? -y : y;
+}
+
+static bool bs_next_block(void) {
+    if (bs.produced >= bs.new_size) {
+        bs.state = BS_STATE_DONE;
+    } else {
+        bs.state = BS_STATE_CONTROL;
+    }
+    return true;
+}
+
+static bool bs_after_extra(void) {
+    int64_t pos = (int64_t)ctx.source_pos + bs.seek;
+    if (pos < 0 || pos > (int64_t)ctx.source_length) {
+        ctx.error = DELTA_ERR_BOUNDS;
+        return false;
+    }
+    ctx.source_pos = (uint32_t)pos;
+    return bs_next_block();
+}
+
+static bool decoder_step(uint8_t byte) {
+    uint8_t old;
+    switch (bs.state) {
+        case BS_STATE_HEADER:
+            bs.buf[bs.buf_fill++] = byte;
+            if (bs.buf_fill < 24) {
+                return true;
+            }
+            if (memcmp(bs.buf, "ENDSLEY/BSDIFF43", 16) != 0) {
+                return false;
+            }
+            bs.new_size = bs_offtin(&bs.buf[16]);
+            bs.buf_fill = 0;
+            return bs.new_size >= 0 && bs_next_block();
+
+        case BS_STATE_CONTROL:
+            bs.buf[bs.buf_fill++] = byte;
+            if (bs.buf_fill < 24) {
+                return true;
+            }
+            bs.buf_fill = 0;
+            bs.diff_left = bs_offtin(&bs.buf[0]);
+            bs.extra_left = bs_offtin(&bs.buf[8]);
+            bs.seek = bs_offtin(&bs.buf[16]);
+            if (bs.diff_left < 0 || bs.extra_left < 0 ||
+                bs.produced + bs.diff_left + bs.extra_left > bs.new_size) {
+                return false;
+            }
+            bs.state = bs.diff_left ? BS_STATE_DIFF : BS_STATE_EXTRA;
+            if (!bs.diff_left && !bs.extra_left) {
+                return bs_after_extra();
+            }
+            return true;
+
+        case BS_STATE_DIFF:
+            // New byte = old byte + diff byte
+            if (!source_byte(&old) || !emit((uint8_t)(old + byte))) {
+                return false;
+            }
+            bs.produced++;
+            if (--bs.diff_left == 0) {
+                bs.state = BS_STATE_EXTRA;
+                if (bs.extra_left == 0) {
+                    return bs_after_extra();
+                }
+            }
+            return true;
+
+        case BS_STATE_EXTRA:
+            if (!emit(byte)) {
+                return false;
+            }
+            bs.produced++;
+            return --bs.extra_left == 0 ? bs_after_extra() : true;
+
+        case BS_STATE_DONE:
+            return false;  // Trailing data
+    }
+    return false;
+}
+
+static bool decoder_finished(void) {
+    return bs.state == BS_STATE_DONE;
+}
+"#;
+
+/// Host tool that creates patches for `algorithm`
+///
+/// bsdiff patches are built in-process: the stock `bsdiff` tool writes
+/// bzip2-compressed BSDIFF40 patches, which the device decoder cannot read.
+pub fn patch_tool(algorithm: DeltaAlgorithm) -> &'static str {
+    match algorithm {
+        DeltaAlgorithm::Janpatch => "jdiff",
+        DeltaAlgorithm::Bsdiff4 => "built-in bsdiff43",
+    }
+}
+
+/// Result of a host-side patch creation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeltaPatchInfo {
+    pub algorithm: DeltaAlgorithm,
+    pub tool: String,
+    pub patch_path: String,
+    pub old_size: u64,
+    pub new_size: u64,
+    pub patch_size: u64,
+    /// Patch size as a fraction of the full new image
+    pub ratio: f64,
+    pub warnings: Vec<String>,
+}
+
+/// Create `output_patch` turning `old_bin` into `new_bin`
+pub fn create_patch(
+    algorithm: DeltaAlgorithm,
+    old_bin: &Path,
+    new_bin: &Path,
+    output_patch: &Path,
+) -> Result<DeltaPatchInfo, String> {
+    let old_size = std::fs::metadata(old_bin).map_err(|e| format!("{}: {}", old_bin.display(), e))?.len();
+    let new_size = std::fs::metadata(new_bin).map_err(|e| format!("{}: {}", new_bin.display(), e))?.len();
+
+    // A patch left over from an earlier run must not pass for this one
+    match std::fs::remove_file(output_patch) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("Cannot replace {}: {}", output_patch.display(), e)),
+    }
+
+    let (tool, patch_size) = match algorithm {
+        DeltaAlgorithm::Bsdiff4 => {
+            let old = std::fs::read(old_bin).map_err(|e| format!("{}: {}", old_bin.display(), e))?;
+            let new = std::fs::read(new_bin).map_err(|e| format!("{}: {}", new_bin.display(), e))?;
+            let patch = bsdiff43_patch(&old, &new);
+            std::fs::write(output_patch, &patch).map_err(|e| format!("{}: {}", output_patch.display(), e))?;
+            (patch_tool(algorithm).to_string(), patch.len() as u64)
+        }
+        DeltaAlgorithm::Janpatch => run_patch_tool(algorithm, old_bin, new_bin, output_patch)?,
+    };
+
+    let mut warnings = Vec::new();
+    if patch_size >= new_size {
+        warnings.push("Patch is not smaller than the new image; a full update is cheaper".to_string());
+    }
+
+    Ok(DeltaPatchInfo {
+        algorithm,
+        tool,
+        patch_path: output_patch.display().to_string(),
+        old_size,
+        new_size,
+        patch_size,
+        ratio: if new_size > 0 { patch_size as f64 / new_size as f64 } else { 0.0 },
+        warnings,
+    })
+}
+
+/// Run the external diff tool; returns its path and the patch size
+fn run_patch_tool(
+    algorithm: DeltaAlgorithm,
+    old_bin: &Path,
+    new_bin: &Path,
+    output_patch: &Path,
+) -> Result<(String, u64), String> {
+    let tool = patch_tool(algorithm);
+    let tool_path = which::which(tool)
+        .map_err(|_| format!("{} not found in PATH; install it to create {:?} patches", tool, algorithm))?;
+
+    let output = Command::new(&tool_path)
+        .arg(old_bin)
+        .arg(new_bin)
+        .arg(output_patch)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", tool, e))?;
+
+    // jdiff exits 1 when the files differ, errors are 2 and up
+    if !matches!(output.status.code(), Some(0 | 1)) {
+        return Err(format!("{} failed ({}): {}", tool, output.status, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    let patch_size = std::fs::metadata(output_patch).map(|m| m.len()).map_err(|_| {
+        format!("{} did not produce a patch: {}", tool, String::from_utf8_lossy(&output.stderr).trim())
+    })?;
+    Ok((tool_path.display().to_string(), patch_size))
+}
+
+/// Matches of at least this many bytes seed a diff block
+const BSDIFF_SEED_LEN: usize = 8;
+/// Stop extending a match after this many bytes without improvement
+const BSDIFF_SLACK: usize = 64;
+
+/// Build an uncompressed ENDSLEY/BSDIFF43 patch, the format `BSDIFF_DECODER` applies
+///
+/// Each block is a control triple (diff length, extra length, old seek) of
+/// sign-magnitude integers, then `new - old` diff bytes and literal extra bytes.
+pub fn bsdiff43_patch(old: &[u8], new: &[u8]) -> Vec<u8> {
+    // First occurrence of every seed-length window of the old image
+    let mut index = std::collections::HashMap::new();
+    for (pos, window) in old.windows(BSDIFF_SEED_LEN).enumerate() {
+        index.entry(window).or_insert(pos);
+    }
+
+    // Approximate matches as (new start, old start, length), in new order
+    let mut matches: Vec<(usize, usize, usize)> = Vec::new();
+    let mut scan = 0;
+    while scan + BSDIFF_SEED_LEN <= new.len() {
+        let Some(&old_start) = index.get(&new[scan..scan + BSDIFF_SEED_LEN]) else {
+            scan += 1;
+            continue;
+        };
+        // Extend while matches outnumber mismatches, as bsdiff does
+        let (mut score, mut best_score, mut best_len, mut len) = (0i64, 0i64, 0, 0);
+        while scan + len < new.len() && old_start + len < old.len() {
+            score += if new[scan + len] == old[old_start + len] { 1 } else { -1 };
+            len += 1;
+            if score > best_score {
+                best_score = score;
+                best_len = len;
+            } else if len - best_len >= BSDIFF_SLACK {
+                break;
+            }
+        }
+        matches.push((scan, old_start, best_len));
+        scan += best_len;
+    }
+
+    let mut patch = Vec::with_capacity(new.len() / 4 + 32);
+    patch.extend_from_slice(b"ENDSLEY/BSDIFF43");
+    patch.extend_from_slice(&bsdiff_offtout(new.len() as i64));
+
+    // A leading block with no diff bytes carries any literals before the first match
+    let first = matches.first().map_or((new.len(), 0), |&(new_start, old_start, _)| (new_start, old_start));
+    if first.0 > 0 || first.1 > 0 {
+        bsdiff_block(&mut patch, &[], &new[..first.0], first.1 as i64);
+    }
+    for (i, &(new_start, old_start, len)) in matches.iter().enumerate() {
+        let diff: Vec<u8> = (0..len).map(|k| new[new_start + k].wrapping_sub(old[old_start + k])).collect();
+        let (extra_end, next_old) = matches.get(i + 1).map_or((new.len(), old_start + len), |&(n, o, _)| (n, o));
+        let seek = next_old as i64 - (old_start + len) as i64;
+        bsdiff_block(&mut patch, &diff, &new[new_start + len..extra_end], seek);
+    }
+    patch
+}
+
+fn bsdiff_block(patch: &mut Vec<u8>, diff: &[u8], extra: &[u8], seek: i64) {
+    patch.extend_from_slice(&bsdiff_offtout(diff.len() as i64));
+    patch.extend_from_slice(&bsdiff_offtout(extra.len() as i64));
+    patch.extend_from_slice(&bsdiff_offtout(seek));
+    patch.extend_from_slice(diff);
+    patch.extend_from_slice(extra);
+}
+
+/// bsdiff integers are sign-magnitude little-endian
+fn bsdiff_offtout(value: i64) -> [u8; 8] {
+    let mut bytes = value.unsigned_abs().to_le_bytes();
+    if value < 0 {
+        bytes[7] |= 0x80;
+    }
+    bytes
+}
@@ -0,0 +1,463 @@
+// Key Derivation Generator
+// HKDF, PBKDF2 and NIST SP 800-108 counter mode on mbedTLS HMAC-SHA256
+
+use crate::drivers::templates::{DriverOutput, PeripheralType};
+use serde::{Deserialize, Serialize};
+
+/// PBKDF2 iteration floor (NIST SP 800-132 recommends at least 10,000)
+pub const PBKDF2_MIN_ITERATIONS: u32 = 10_000;
+
+/// Key derivation function
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KdfAlgorithm {
+    Hkdf,
+    Pbkdf2,
+    Sp800_108Ctr,
+}
+
+impl KdfAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            KdfAlgorithm::Hkdf => "HKDF-SHA256 (RFC 5869)",
+            KdfAlgorithm::Pbkdf2 => "PBKDF2-HMAC-SHA256 (RFC 8018)",
+            KdfAlgorithm::Sp800_108Ctr => "KDF in counter mode, HMAC-SHA256 (NIST SP 800-108)",
+        }
+    }
+}
+
+/// Key derivation configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KdfConfig {
+    pub algorithm: KdfAlgorithm,
+    pub key_length_bytes: u8,
+    /// Application-specific label bound into every derived key
+    pub info: String,
+    /// Salts from the MCU's TRNG rather than a software DRBG
+    pub use_hardware_rng: bool,
+}
+
+impl Default for KdfConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: KdfAlgorithm::Hkdf,
+            key_length_bytes: 32,
+            info: "session key v1".to_string(),
+            use_hardware_rng: true,
+        }
+    }
+}
+
+impl KdfConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.key_length_bytes < 16 {
+            return Err(format!("Derived keys must be at least 16 bytes, got {}", self.key_length_bytes));
+        }
+        if self.info.len() > 64 {
+            return Err(format!("Info label must be at most 64 bytes, got {}", self.info.len()));
+        }
+        if !self.info.chars().all(|c| c.is_ascii() && !c.is_ascii_control()) {
+            return Err("Info label must be printable ASCII".to_string());
+        }
+        Ok(())
+    }
+
+    /// Caveats to show alongside the generated code
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if !self.use_hardware_rng {
+            warnings.push(
+                "Salts come from a software DRBG: it is only as good as the entropy source registered with \
+                 mbedtls_entropy (MBEDTLS_ENTROPY_HARDWARE_ALT or mbedtls_entropy_add_source). Without a real \
+                 entropy source the salts are predictable."
+                    .to_string(),
+            );
+        }
+        if self.algorithm == KdfAlgorithm::Pbkdf2 {
+            warnings.push(format!(
+                "PBKDF2 runs {} HMAC iterations per 32-byte block; expect hundreds of milliseconds on a Cortex-M4",
+                PBKDF2_MIN_ITERATIONS
+            ));
+        }
+        warnings
+    }
+}
+
+fn c_string(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Generate key derivation code with `derive_session_key()`
+pub fn generate_kdf_code(config: &KdfConfig) -> DriverOutput {
+    let name = config.algorithm.name();
+    let key_length = config.key_length_bytes;
+    let info = c_string(&config.info);
+    let rng = if config.use_hardware_rng { "hardware TRNG" } else { "software DRBG" };
+
+    let algorithm_api = match config.algorithm {
+        KdfAlgorithm::Hkdf => r#"// HKDF-Extract: PRK = HMAC(salt, IKM); a NULL salt means HashLen zero bytes
+int kdf_hkdf_extract(const uint8_t *salt, size_t salt_len,
+                     const uint8_t *ikm, size_t ikm_len, uint8_t prk[KDF_HASH_LEN]);
+
+// HKDF-Expand: OKM = T(1) | T(2) | ..., T(i) = HMAC(PRK, T(i-1) | info | i)
+int kdf_hkdf_expand(const uint8_t prk[KDF_HASH_LEN], const uint8_t *info, size_t info_len,
+                    uint8_t *okm, size_t okm_len);
+"#.to_string(),
+        KdfAlgorithm::Pbkdf2 => format!(r#"#ifndef KDF_PBKDF2_ITERATIONS
+#define KDF_PBKDF2_ITERATIONS {PBKDF2_MIN_ITERATIONS}U
+#endif
+
+int kdf_pbkdf2(const uint8_t *password, size_t password_len,
+               const uint8_t *salt, size_t salt_len, uint32_t iterations,
+               uint8_t *out, size_t out_len);
+"#),
+        KdfAlgorithm::Sp800_108Ctr => r#"// K(i) = HMAC(KI, [i]32 | Label | 0x00 | Context | [L]32)
+int kdf_sp800_108_ctr(const uint8_t *ki, size_t ki_len,
+                      const uint8_t *label, size_t label_len,
+                      const uint8_t *context, size_t context_len,
+                      uint8_t *out, size_t out_len);
+"#.to_string(),
+    };
+
+    let header = format!(r#"/**
+ * Key Derivation: {name}
+ * Auto-generated by NeuroBench
+ * Derived key: {key_length} bytes, salts from {rng}
+ */
+
+#ifndef KDF_H
+#define KDF_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define KDF_HASH_LEN     32
+#define KDF_KEY_LENGTH   {key_length}
+#define KDF_INFO         "{info}"
+#define KDF_MAX_CONTEXT  64
+
+{algorithm_api}
+// Fill `salt` with random bytes
+int kdf_generate_salt(uint8_t *salt, size_t len);
+
+// Derive a KDF_KEY_LENGTH-byte key bound to KDF_INFO and `context`; 0 on success
+int derive_session_key(const uint8_t *master_key, size_t master_key_len,
+                       const uint8_t *context, size_t context_len,
+                       uint8_t session_key[KDF_KEY_LENGTH]);
+
+#endif // KDF_H
+"#);
+
+    let algorithm_source = match config.algorithm {
+        KdfAlgorithm::Hkdf => r#"
+int kdf_hkdf_extract(const uint8_t *salt, size_t salt_len,
+                     const uint8_t *ikm, size_t ikm_len, uint8_t prk[KDF_HASH_LEN]) {
+    static const uint8_t zero_salt[KDF_HASH_LEN] = {0};
+    if (salt == NULL || salt_len == 0) {
+        salt = zero_salt;
+        salt_len = sizeof(zero_salt);
+    }
+    return mbedtls_md_hmac(sha256(), salt, salt_len, ikm, ikm_len, prk);
+}
+
+int kdf_hkdf_expand(const uint8_t prk[KDF_HASH_LEN], const uint8_t *info, size_t info_len,
+                    uint8_t *okm, size_t okm_len) {
+    if (okm_len > 255 * KDF_HASH_LEN) {
+        return -1;
+    }
+    mbedtls_md_context_t ctx;
+    uint8_t t[KDF_HASH_LEN];
+    size_t t_len = 0;
+    size_t done = 0;
+    uint8_t counter = 1;
+
+    mbedtls_md_init(&ctx);
+    int ret = mbedtls_md_setup(&ctx, sha256(), 1);
+    while (ret == 0 && done < okm_len) {
+        ret = mbedtls_md_hmac_starts(&ctx, prk, KDF_HASH_LEN);
+        if (ret == 0) ret = mbedtls_md_hmac_update(&ctx, t, t_len);
+        if (ret == 0) ret = mbedtls_md_hmac_update(&ctx, info, info_len);
+        if (ret == 0) ret = mbedtls_md_hmac_update(&ctx, &counter, 1);
+        if (ret == 0) ret = mbedtls_md_hmac_finish(&ctx, t);
+        if (ret == 0) {
+            size_t n = okm_len - done < KDF_HASH_LEN ? okm_len - done : KDF_HASH_LEN;
+            memcpy(okm + done, t, n);
+            done += n;
+            t_len = KDF_HASH_LEN;
+            counter++;
+        }
+    }
+    mbedtls_md_free(&ctx);
+    mbedtls_platform_zeroize(t, sizeof(t));
+    return ret;
+}
+
+int derive_session_key(const uint8_t *master_key, size_t master_key_len,
+                       const uint8_t *context, size_t context_len,
+                       uint8_t session_key[KDF_KEY_LENGTH]) {
+    uint8_t prk[KDF_HASH_LEN];
+    uint8_t info[sizeof(KDF_INFO) - 1 + KDF_MAX_CONTEXT];
+    if (context_len > KDF_MAX_CONTEXT) {
+        return -1;
+    }
+    // info = label | context, so keys for different purposes never collide
+    memcpy(info, KDF_INFO, sizeof(KDF_INFO) - 1);
+    memcpy(info + sizeof(KDF_INFO) - 1, context, context_len);
+
+    int ret = kdf_hkdf_extract(NULL, 0, master_key, master_key_len, prk);
+    if (ret == 0) {
+        ret = kdf_hkdf_expand(prk, info, sizeof(KDF_INFO) - 1 + context_len, session_key, KDF_KEY_LENGTH);
+    }
+    mbedtls_platform_zeroize(prk, sizeof(prk));
+    return ret;
+}
+"#,
+        KdfAlgorithm::Pbkdf2 => r#"
+int kdf_pbkdf2(const uint8_t *password, size_t password_len,
+               const uint8_t *salt, size_t salt_len, uint32_t iterations,
+               uint8_t *out, size_t out_len) {
+    if (iterations < KDF_PBKDF2_ITERATIONS) {
+        return -1;
+    }
+    mbedtls_md_context_t ctx;
+    uint8_t u[KDF_HASH_LEN];
+    uint8_t t[KDF_HASH_LEN];
+    uint32_t block = 1;
+    size_t done = 0;
+
+    mbedtls_md_init(&ctx);
+    int ret = mbedtls_md_setup(&ctx, sha256(), 1);
+    if (ret == 0) ret = mbedtls_md_hmac_starts(&ctx, password, password_len);
+    while (ret == 0 && done < out_len) {
+        // U1 = HMAC(P, S | INT(i))
+        uint8_t index[4] = { (uint8_t)(block >> 24), (uint8_t)(block >> 16), (uint8_t)(block >> 8), (uint8_t)block };
+        ret = mbedtls_md_hmac_reset(&ctx);
+        if (ret == 0) ret = mbedtls_md_hmac_update(&ctx, salt, salt_len);
+        if (ret == 0) ret = mbedtls_md_hmac_update(&ctx, index, sizeof(index));
+        if (ret == 0) ret = mbedtls_md_hmac_finish(&ctx, u);
+        memcpy(t, u, sizeof(t));
+
+        // T = U1 ^ U2 ^ ... ^ Uc, Uj = HMAC(P, Uj-1)
+        for (uint32_t j = 1; ret == 0 && j < iterations; j++) {
+            ret = mbedtls_md_hmac_reset(&ctx);
+            if (ret == 0) ret = mbedtls_md_hmac_update(&ctx, u, sizeof(u));
+            if (ret == 0) ret = mbedtls_md_hmac_finish(&ctx, u);
+            for (size_t k = 0; k < KDF_HASH_LEN; k++) {
+                t[k] ^= u[k];
+            }
+        }
+        if (ret == 0) {
+            size_t n = out_len - done < KDF_HASH_LEN ? out_len - done : KDF_HASH_LEN;
+            memcpy(out + done, t, n);
+            done += n;
+            block++;
+        }
+    }
+    mbedtls_md_free(&ctx);
+    mbedtls_platform_zeroize(u, sizeof(u));
+    mbedtls_platform_zeroize(t, sizeof(t));
+    return ret;
+}
+
+int derive_session_key(const uint8_t *master_key, size_t master_key_len,
+                       const uint8_t *context, size_t context_len,
+                       uint8_t session_key[KDF_KEY_LENGTH]) {
+    uint8_t salt[sizeof(KDF_INFO) - 1 + KDF_MAX_CONTEXT];
+    if (context_len > KDF_MAX_CONTEXT) {
+        return -1;
+    }
+    // The context (e.g. a stored random salt) is prefixed with the label
+    memcpy(salt, KDF_INFO, sizeof(KDF_INFO) - 1);
+    memcpy(salt + sizeof(KDF_INFO) - 1, context, context_len);
+    return kdf_pbkdf2(master_key, master_key_len, salt, sizeof(KDF_INFO) - 1 + context_len,
+                      KDF_PBKDF2_ITERATIONS, session_key, KDF_KEY_LENGTH);
+}
+"#,
+        KdfAlgorithm::Sp800_108Ctr => r#"
+int kdf_sp800_108_ctr(const uint8_t *ki, size_t ki_len,
+                      const uint8_t *label, size_t label_len,
+                      const uint8_t *context, size_t context_len,
+                      uint8_t *out, size_t out_len) {
+    mbedtls_md_context_t ctx;
+    uint8_t k[KDF_HASH_LEN];
+    const uint8_t separator = 0x00;
+    uint32_t bits = (uint32_t)out_len * 8;
+    uint8_t length[4] = { (uint8_t)(bits >> 24), (uint8_t)(bits >> 16), (uint8_t)(bits >> 8), (uint8_t)bits };
+    uint32_t i = 1;
+    size_t done = 0;
+
+    mbedtls_md_init(&ctx);
+    int ret = mbedtls_md_setup(&ctx, sha256(), 1);
+    if (ret == 0) ret = mbedtls_md_hmac_starts(&ctx, ki, ki_len);
+    while (ret == 0 && done < out_len) {
+        uint8_t counter[4] = { (uint8_t)(i >> 24), (uint8_t)(i >> 16), (uint8_t)(i >> 8), (uint8_t)i };
+        ret = mbedtls_md_hmac_reset(&ctx);
+        if (ret == 0) ret = mbedtls_md_hmac_update(&ctx, counter, sizeof(counter));
+        if (ret == 0) ret = mbedtls_md_hmac_update(&ctx, label, label_len);
+        if (ret == 0) ret = mbedtls_md_hmac_update(&ctx, &separator, 1);
+        if (ret == 0) ret = mbedtls_md_hmac_update(&ctx, context, context_len);
+        if (ret == 0) ret = mbedtls_md_hmac_update(&ctx, length, sizeof(length));
+        if (ret == 0) ret = mbedtls_md_hmac_finish(&ctx, k);
+        if (ret == 0) {
+            size_t n = out_len - done < KDF_HASH_LEN ? out_len - done : KDF_HASH_LEN;
+            memcpy(out + done, k, n);
+            done += n;
+            i++;
+        }
+    }
+    mbedtls_md_free(&ctx);
+    mbedtls_platform_zeroize(k, sizeof(k));
+    return ret;
+}
+
+int derive_session_key(const uint8_t *master_key, size_t master_key_len,
+                       const uint8_t *context, size_t context_len,
+                       uint8_t session_key[KDF_KEY_LENGTH]) {
+    if (context_len > KDF_MAX_CONTEXT) {
+        return -1;
+    }
+    return kdf_sp800_108_ctr(master_key, master_key_len,
+                             (const uint8_t *)KDF_INFO, sizeof(KDF_INFO) - 1,
+                             context, context_len, session_key, KDF_KEY_LENGTH);
+}
+"#,
+    };
+
+    let rng_source = if config.use_hardware_rng {
+        r#"
+extern RNG_HandleTypeDef hrng;
+
+int kdf_generate_salt(uint8_t *salt, size_t len) {
+    while (len > 0) {
+        uint32_t word;
+        if (HAL_RNG_GenerateRandomNumber(&hrng, &word) != HAL_OK) {
+            return -1;
+        }
+        size_t n = len < sizeof(word) ? len : sizeof(word);
+        memcpy(salt, &word, n);
+        salt += n;
+        len -= n;
+    }
+    return 0;
+}
+"#
+    } else {
+        r#"
+/*
+ * WARNING: no hardware RNG. The DRBG below is only as unpredictable as the
+ * entropy sources registered with mbedtls_entropy; provide one through
+ * MBEDTLS_ENTROPY_HARDWARE_ALT or mbedtls_entropy_add_source(), otherwise
+ * every salt can be guessed.
+ */
+#warning "kdf: software DRBG in use - make sure a real entropy source is registered"
+
+static mbedtls_entropy_context entropy;
+static mbedtls_ctr_drbg_context drbg;
+static int drbg_ready;
+
+int kdf_generate_salt(uint8_t *salt, size_t len) {
+    if (!drbg_ready) {
+        static const char personalization[] = "kdf_salt";
+        mbedtls_entropy_init(&entropy);
+        mbedtls_ctr_drbg_init(&drbg);
+        int ret = mbedtls_ctr_drbg_seed(&drbg, mbedtls_entropy_func, &entropy,
+                                        (const uint8_t *)personalization, sizeof(personalization) - 1);
+        if (ret != 0) {
+            return ret;
+        }
+        drbg_ready = 1;
+    }
+    return mbedtls_ctr_drbg_random(&drbg, salt, len);
+}
+"#
+    };
+    let rng_includes = if config.use_hardware_rng {
+        "#include \"main.h\"  // HAL and hrng\n"
+    } else {
+        "#include \"mbedtls/entropy.h\"\n#include \"mbedtls/ctr_drbg.h\"\n"
+    };
+
+    let source = format!(r#"/**
+ * Key Derivation: {name}
+ * Auto-generated by NeuroBench
+ */
+
+#include "kdf.h"
+#include <string.h>
+#include "mbedtls/md.h"
+#include "mbedtls/platform_util.h"
+{rng_includes}
+static const mbedtls_md_info_t *sha256(void) {{
+    return mbedtls_md_info_from_type(MBEDTLS_MD_SHA256);
+}}
+{algorithm_source}{rng_source}"#);
+
+    let example = match config.algorithm {
+        KdfAlgorithm::Pbkdf2 => r#"/**
+ * KDF Example
+ * Derives a storage key from a passphrase and a per-device random salt
+ */
+
+#include "kdf.h"
+#include "mbedtls/platform_util.h"
+#include <string.h>
+
+static uint8_t device_salt[16];  // Persist alongside the encrypted data
+
+int main(void) {
+    HAL_Init();
+
+    const char *passphrase = "correct horse battery staple";
+    uint8_t key[KDF_KEY_LENGTH];
+
+    if (kdf_generate_salt(device_salt, sizeof(device_salt)) != 0 ||
+        derive_session_key((const uint8_t *)passphrase, strlen(passphrase),
+                           device_salt, sizeof(device_salt), key) != 0) {
+        Error_Handler();
+    }
+
+    // ... use key, then wipe it
+    mbedtls_platform_zeroize(key, sizeof(key));
+
+    while (1) {
+    }
+}
+"#,
+        _ => r#"/**
+ * KDF Example
+ * Both peers derive the same session key from a shared master key and a
+ * nonce exchanged at connection time
+ */
+
+#include "kdf.h"
+#include "mbedtls/platform_util.h"
+
+extern const uint8_t master_key[32];  // Provisioned, e.g. from secure storage
+
+int main(void) {
+    HAL_Init();
+
+    uint8_t nonce[16];
+    uint8_t session_key[KDF_KEY_LENGTH];
+
+    // Send the nonce to the peer so it can derive the same key
+    if (kdf_generate_salt(nonce, sizeof(nonce)) != 0 ||
+        derive_session_key(master_key, sizeof(master_key), nonce, sizeof(nonce), session_key) != 0) {
+        Error_Handler();
+    }
+
+    // ... encrypt traffic with session_key, then wipe it
+    mbedtls_platform_zeroize(session_key, sizeof(session_key));
+
+    while (1) {
+    }
+}
+"#,
+    }.to_string();
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: Some(example),
+        peripheral_type: PeripheralType::KDF,
+    }
+}
@@ -225,7 +225,40 @@ impl Default for CryptoConfig {
     }
 }
 
+// ============================================================================
+// Delta OTA Configuration
+// ============================================================================
+
+/// Binary diff format for delta updates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeltaAlgorithm {
+    Janpatch,
+    Bsdiff4,
+}
+
+/// Delta OTA configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeltaOtaConfig {
+    pub algorithm: DeltaAlgorithm,
+    pub flash_slot: u32,     // Address the new image is written to
+    pub slot_size: u32,      // Bytes reserved for the slot, commit record included
+    pub scratch_size: u32,   // One flash sector/page of RAM
+}
+
+impl Default for DeltaOtaConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: DeltaAlgorithm::Janpatch,
+            flash_slot: 0x08040000,
+            slot_size: 0x40000,
+            scratch_size: 4096,
+        }
+    }
+}
+
 pub mod bootloader;
 pub mod ota;
 pub mod secure_boot;
 pub mod crypto;
+pub mod delta;
+pub mod kdf;
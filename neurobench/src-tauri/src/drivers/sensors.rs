@@ -0,0 +1,854 @@
+// Sensor Library Generator
+// Generates drivers for common sensors on top of the generated I2C/SPI drivers
+
+use super::mcu::stm32::Stm32Hal;
+use super::mcu::{McuFamily, McuHal};
+use super::templates::*;
+use serde::{Deserialize, Serialize};
+
+/// Supported sensors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SensorType {
+    Bme280,
+    Mpu6050,
+    Lis3Dh,
+    Ds18b20,
+    Hcsr04,
+}
+
+/// How the sensor is wired to the MCU
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SensorInterface {
+    I2c,
+    Spi,
+    OneWire,
+    Gpio,
+}
+
+impl SensorType {
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            SensorType::Bme280 => "bme280",
+            SensorType::Mpu6050 => "mpu6050",
+            SensorType::Lis3Dh => "lis3dh",
+            SensorType::Ds18b20 => "ds18b20",
+            SensorType::Hcsr04 => "hcsr04",
+        }
+    }
+
+    pub fn interfaces(&self) -> &'static [SensorInterface] {
+        match self {
+            SensorType::Bme280 | SensorType::Lis3Dh => &[SensorInterface::I2c, SensorInterface::Spi],
+            SensorType::Mpu6050 => &[SensorInterface::I2c],
+            SensorType::Ds18b20 => &[SensorInterface::OneWire],
+            SensorType::Hcsr04 => &[SensorInterface::Gpio],
+        }
+    }
+
+    /// Default 7-bit I2C address
+    pub fn default_address(&self) -> Option<u8> {
+        match self {
+            SensorType::Bme280 => Some(0x76),
+            SensorType::Mpu6050 => Some(0x68),
+            SensorType::Lis3Dh => Some(0x18),
+            SensorType::Ds18b20 | SensorType::Hcsr04 => None,
+        }
+    }
+}
+
+/// Sensor driver configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensorLibConfig {
+    pub sensor: SensorType,
+    pub interface: SensorInterface,
+    pub i2c_address: Option<u8>,
+    /// Chip-select pin such as "PA4"; the SPI driver's own CS is used when unset
+    pub spi_cs_pin: Option<String>,
+}
+
+impl SensorLibConfig {
+    pub fn new(sensor: SensorType, interface: SensorInterface) -> Self {
+        Self { sensor, interface, i2c_address: None, spi_cs_pin: None }
+    }
+
+    pub fn validate(&self, mcu: McuFamily) -> Result<(), String> {
+        // The drivers call the STM32 HAL and time 1-Wire/echo pulses with the DWT cycle counter
+        match mcu {
+            McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32H7 | McuFamily::STM32L4 | McuFamily::STM32G4 => {}
+            _ => return Err(format!("Sensor drivers use the STM32 HAL and DWT timing; {} is not supported", mcu.display_name())),
+        }
+        if !self.sensor.interfaces().contains(&self.interface) {
+            return Err(format!("{:?} does not support the {:?} interface", self.sensor, self.interface));
+        }
+        if let Some(address) = self.i2c_address {
+            if address > 0x7F {
+                return Err(format!("I2C address 0x{:02X} is not a 7-bit address", address));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Generate a sensor driver using the generated I2C1/SPI1 driver as transport
+pub fn generate_sensor_driver(config: &SensorLibConfig, mcu: McuFamily) -> DriverOutput {
+    let (header_body, source_body, example_body) = match config.sensor {
+        SensorType::Bme280 => (BME280_HEADER, BME280_SOURCE, BME280_EXAMPLE),
+        SensorType::Mpu6050 => (MPU6050_HEADER, MPU6050_SOURCE, MPU6050_EXAMPLE),
+        SensorType::Lis3Dh => (LIS3DH_HEADER, LIS3DH_SOURCE, LIS3DH_EXAMPLE),
+        SensorType::Ds18b20 => (DS18B20_HEADER, DS18B20_SOURCE, DS18B20_EXAMPLE),
+        SensorType::Hcsr04 => (HCSR04_HEADER, HCSR04_SOURCE, HCSR04_EXAMPLE),
+    };
+    let prefix = config.sensor.prefix();
+    let guard = format!("{}_DRIVER_H", prefix.to_uppercase());
+    let hal_header = Stm32Hal::new(mcu).include_headers()[0];
+
+    let header = format!(r#"/**
+ * {sensor:?} Sensor Driver
+ * Auto-generated by NeuroBench
+ * Interface: {interface:?}
+ */
+
+#ifndef {guard}
+#define {guard}
+
+#include <stdint.h>
+#include <stdbool.h>
+{header_body}
+#endif // {guard}
+"#,
+        sensor = config.sensor,
+        interface = config.interface,
+    );
+
+    let source = format!(r#"/**
+ * {sensor:?} Sensor Driver
+ * Auto-generated by NeuroBench
+ */
+
+#include "{prefix}_driver.h"
+#include "{hal_header}"
+#include <string.h>
+{transport}{source_body}"#,
+        sensor = config.sensor,
+        transport = transport_source(config),
+    );
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: Some(format!("#include \"{}_driver.h\"\n{}", prefix, example_body)),
+        peripheral_type: match config.interface {
+            SensorInterface::I2c => PeripheralType::I2C,
+            SensorInterface::Spi => PeripheralType::SPI,
+            SensorInterface::OneWire | SensorInterface::Gpio => PeripheralType::GPIO,
+        },
+    }
+}
+
+/// Parse "PA4" into ("GPIOA", 4)
+fn parse_pin(pin: &str) -> Option<(String, u8)> {
+    let pin = pin.trim().to_uppercase();
+    let rest = pin.strip_prefix('P')?;
+    let port = rest.chars().next().filter(|c| c.is_ascii_alphabetic())?;
+    let number = rest[1..].parse().ok().filter(|n| *n < 16)?;
+    Some((format!("GPIO{}", port), number))
+}
+
+/// `sensor_write_reg`/`sensor_read_regs` on top of the generated bus driver
+fn transport_source(config: &SensorLibConfig) -> String {
+    // Register auto-increment for burst reads
+    let burst_flag = match (config.sensor, config.interface) {
+        (SensorType::Lis3Dh, SensorInterface::I2c) => 0x80,
+        (SensorType::Lis3Dh, SensorInterface::Spi) => 0x40,
+        _ => 0x00,
+    };
+
+    match config.interface {
+        SensorInterface::I2c => {
+            let address = config.i2c_address.or(config.sensor.default_address()).unwrap_or_default();
+            format!(r#"#include "i2c1_driver.h"
+
+#define SENSOR_I2C_ADDR 0x{address:02X}
+
+// Transport: generated I2C1 driver
+static bool sensor_write_reg(uint8_t reg, uint8_t value) {{
+    return I2C1_WriteReg8(SENSOR_I2C_ADDR, reg, value);
+}}
+
+static bool sensor_read_regs(uint8_t reg, uint8_t *data, uint16_t len) {{
+    if (len > 1) {{
+        reg |= 0x{burst_flag:02X};
+    }}
+    return I2C1_Read(SENSOR_I2C_ADDR, reg, data, len);
+}}
+"#)
+        }
+        SensorInterface::Spi => {
+            let select = match config.spi_cs_pin.as_deref().and_then(parse_pin) {
+                Some((port, pin)) => format!(
+                    "    HAL_GPIO_WritePin({port}, GPIO_PIN_{pin}, active ? GPIO_PIN_RESET : GPIO_PIN_SET);"
+                ),
+                None => "    if (active) {\n        SPI1_CS_Low();\n    } else {\n        SPI1_CS_High();\n    }".to_string(),
+            };
+            format!(r#"#include "spi1_driver.h"
+
+// Transport: generated SPI1 driver
+static void sensor_select(bool active) {{
+{select}
+}}
+
+static bool sensor_write_reg(uint8_t reg, uint8_t value) {{
+    sensor_select(true);
+    SPI1_TransmitReceive(reg & 0x7F);
+    SPI1_TransmitReceive(value);
+    sensor_select(false);
+    return true;
+}}
+
+static bool sensor_read_regs(uint8_t reg, uint8_t *data, uint16_t len) {{
+    sensor_select(true);
+    SPI1_TransmitReceive(reg | 0x80 | (len > 1 ? 0x{burst_flag:02X} : 0x00));
+    for (uint16_t i = 0; i < len; i++) {{
+        data[i] = SPI1_TransmitReceive(0xFF);
+    }}
+    sensor_select(false);
+    return true;
+}}
+"#)
+        }
+        SensorInterface::OneWire | SensorInterface::Gpio => r#"
+// Microsecond timing from the DWT cycle counter
+static void sensor_timer_init(void) {
+    CoreDebug->DEMCR |= CoreDebug_DEMCR_TRCENA_Msk;
+    DWT->CYCCNT = 0;
+    DWT->CTRL |= DWT_CTRL_CYCCNTENA_Msk;
+}
+
+static inline uint32_t sensor_cycles(void) {
+    return DWT->CYCCNT;
+}
+
+// Unsigned subtraction stays correct across one CYCCNT wrap
+static inline uint32_t sensor_elapsed_us(uint32_t start) {
+    return (uint32_t)(DWT->CYCCNT - start) / (SystemCoreClock / 1000000U);
+}
+
+static void sensor_delay_us(uint32_t us) {
+    uint32_t start = sensor_cycles();
+    uint32_t cycles = us * (SystemCoreClock / 1000000U);
+    while ((uint32_t)(DWT->CYCCNT - start) < cycles) {
+    }
+}
+"#.to_string(),
+    }
+}
+
+const BME280_HEADER: &str = r#"
+#define BME280_CHIP_ID 0x60
+
+typedef struct {
+    float temperature_c;
+    float pressure_pa;
+    float humidity_pct;
+} BME280_Data_t;
+
+bool bme280_init(void);
+bool bme280_read(BME280_Data_t *data);
+"#;
+
+const BME280_SOURCE: &str = r#"
+#define BME280_REG_CALIB00   0x88
+#define BME280_REG_CHIP_ID   0xD0
+#define BME280_REG_RESET     0xE0
+#define BME280_REG_CALIB26   0xE1
+#define BME280_REG_CTRL_HUM  0xF2
+#define BME280_REG_CTRL_MEAS 0xF4
+#define BME280_REG_CONFIG    0xF5
+#define BME280_REG_DATA      0xF7
+
+// Factory trimming parameters
+static struct {
+    uint16_t T1; int16_t T2, T3;
+    uint16_t P1; int16_t P2, P3, P4, P5, P6, P7, P8, P9;
+    uint8_t H1, H3; int16_t H2, H4, H5; int8_t H6;
+} calib;
+
+static int32_t t_fine;
+
+static bool bme280_read_calibration(void) {
+    uint8_t buf[26];
+    if (!sensor_read_regs(BME280_REG_CALIB00, buf, 26)) {
+        return false;
+    }
+    calib.T1 = (uint16_t)(buf[1] << 8 | buf[0]);
+    calib.T2 = (int16_t)(buf[3] << 8 | buf[2]);
+    calib.T3 = (int16_t)(buf[5] << 8 | buf[4]);
+    calib.P1 = (uint16_t)(buf[7] << 8 | buf[6]);
+    calib.P2 = (int16_t)(buf[9] << 8 | buf[8]);
+    calib.P3 = (int16_t)(buf[11] << 8 | buf[10]);
+    calib.P4 = (int16_t)(buf[13] << 8 | buf[12]);
+    calib.P5 = (int16_t)(buf[15] << 8 | buf[14]);
+    calib.P6 = (int16_t)(buf[17] << 8 | buf[16]);
+    calib.P7 = (int16_t)(buf[19] << 8 | buf[18]);
+    calib.P8 = (int16_t)(buf[21] << 8 | buf[20]);
+    calib.P9 = (int16_t)(buf[23] << 8 | buf[22]);
+    calib.H1 = buf[25];
+
+    uint8_t hum[7];
+    if (!sensor_read_regs(BME280_REG_CALIB26, hum, 7)) {
+        return false;
+    }
+    calib.H2 = (int16_t)(hum[1] << 8 | hum[0]);
+    calib.H3 = hum[2];
+    calib.H4 = (int16_t)((int8_t)hum[3] * 16 | (hum[4] & 0x0F));
+    calib.H5 = (int16_t)((int8_t)hum[5] * 16 | (hum[4] >> 4));
+    calib.H6 = (int8_t)hum[6];
+    return true;
+}
+
+bool bme280_init(void) {
+    uint8_t id = 0;
+    if (!sensor_read_regs(BME280_REG_CHIP_ID, &id, 1) || id != BME280_CHIP_ID) {
+        return false;
+    }
+    sensor_write_reg(BME280_REG_RESET, 0xB6);
+    HAL_Delay(5);
+
+    if (!bme280_read_calibration()) {
+        return false;
+    }
+    // Humidity x1 must be written before ctrl_meas to take effect
+    sensor_write_reg(BME280_REG_CTRL_HUM, 0x01);
+    sensor_write_reg(BME280_REG_CONFIG, 0xA0);     // 1 s standby, filter off
+    sensor_write_reg(BME280_REG_CTRL_MEAS, 0x27);  // T x1, P x1, normal mode
+    return true;
+}
+
+// Compensation formulas from the BME280 datasheet, section 4.2.3
+static int32_t bme280_compensate_t(int32_t adc_t) {
+    int32_t var1 = ((((adc_t >> 3) - ((int32_t)calib.T1 << 1))) * calib.T2) >> 11;
+    int32_t var2 = (((((adc_t >> 4) - calib.T1) * ((adc_t >> 4) - calib.T1)) >> 12) * calib.T3) >> 14;
+    t_fine = var1 + var2;
+    return (t_fine * 5 + 128) >> 8;
+}
+
+static uint32_t bme280_compensate_p(int32_t adc_p) {
+    int64_t var1 = (int64_t)t_fine - 128000;
+    int64_t var2 = var1 * var1 * calib.P6;
+    var2 += (var1 * calib.P5) << 17;
+    var2 += (int64_t)calib.P4 << 35;
+    var1 = ((var1 * var1 * calib.P3) >> 8) + ((var1 * calib.P2) << 12);
+    var1 = ((((int64_t)1) << 47) + var1) * calib.P1 >> 33;
+    if (var1 == 0) {
+        return 0;
+    }
+    int64_t p = 1048576 - adc_p;
+    p = (((p << 31) - var2) * 3125) / var1;
+    var1 = ((int64_t)calib.P9 * (p >> 13) * (p >> 13)) >> 25;
+    var2 = ((int64_t)calib.P8 * p) >> 19;
+    p = ((p + var1 + var2) >> 8) + ((int64_t)calib.P7 << 4);
+    return (uint32_t)p;  // Q24.8 Pa
+}
+
+static uint32_t bme280_compensate_h(int32_t adc_h) {
+    int32_t v = t_fine - 76800;
+    v = ((((adc_h << 14) - ((int32_t)calib.H4 << 20) - (calib.H5 * v)) + 16384) >> 15) *
+        (((((((v * calib.H6) >> 10) * (((v * calib.H3) >> 11) + 32768)) >> 10) + 2097152) * calib.H2 + 8192) >> 14);
+    v -= ((((v >> 15) * (v >> 15)) >> 7) * calib.H1) >> 4;
+    v = v < 0 ? 0 : v;
+    v = v > 419430400 ? 419430400 : v;
+    return (uint32_t)(v >> 12);  // Q22.10 %RH
+}
+
+bool bme280_read(BME280_Data_t *data) {
+    uint8_t raw[8];
+    if (!sensor_read_regs(BME280_REG_DATA, raw, 8)) {
+        return false;
+    }
+    int32_t adc_p = (int32_t)raw[0] << 12 | (int32_t)raw[1] << 4 | raw[2] >> 4;
+    int32_t adc_t = (int32_t)raw[3] << 12 | (int32_t)raw[4] << 4 | raw[5] >> 4;
+    int32_t adc_h = (int32_t)raw[6] << 8 | raw[7];
+
+    data->temperature_c = bme280_compensate_t(adc_t) / 100.0f;
+    data->pressure_pa = bme280_compensate_p(adc_p) / 256.0f;
+    data->humidity_pct = bme280_compensate_h(adc_h) / 1024.0f;
+    return true;
+}
+"#;
+
+const BME280_EXAMPLE: &str = r#"
+int main(void) {
+    HAL_Init();
+    SystemClock_Config();
+    I2C1_Init();  // or SPI1_Init()
+
+    if (!bme280_init()) {
+        Error_Handler();
+    }
+
+    BME280_Data_t data;
+    while (1) {
+        if (bme280_read(&data)) {
+            printf("%.2f C  %.0f Pa  %.1f %%RH\n", data.temperature_c, data.pressure_pa, data.humidity_pct);
+        }
+        HAL_Delay(1000);
+    }
+}
+"#;
+
+const MPU6050_HEADER: &str = r#"
+#define MPU6050_WHO_AM_I_VALUE 0x68
+
+typedef struct {
+    float accel_g[3];
+    float gyro_dps[3];
+    float temperature_c;
+} MPU6050_Data_t;
+
+bool mpu6050_init(void);
+bool mpu6050_calibrate(uint16_t samples);
+bool mpu6050_read(MPU6050_Data_t *data);
+"#;
+
+const MPU6050_SOURCE: &str = r#"
+#define MPU6050_REG_SMPLRT_DIV   0x19
+#define MPU6050_REG_CONFIG       0x1A
+#define MPU6050_REG_GYRO_CONFIG  0x1B
+#define MPU6050_REG_ACCEL_CONFIG 0x1C
+#define MPU6050_REG_ACCEL_XOUT_H 0x3B
+#define MPU6050_REG_PWR_MGMT_1   0x6B
+#define MPU6050_REG_WHO_AM_I     0x75
+
+#define MPU6050_ACCEL_LSB_PER_G   16384.0f  // +-2 g
+#define MPU6050_GYRO_LSB_PER_DPS  131.0f    // +-250 dps
+
+// Gyro bias measured at rest by mpu6050_calibrate()
+static int16_t gyro_offset[3];
+
+static bool mpu6050_read_raw(int16_t accel[3], int16_t *temp, int16_t gyro[3]) {
+    uint8_t buf[14];
+    if (!sensor_read_regs(MPU6050_REG_ACCEL_XOUT_H, buf, 14)) {
+        return false;
+    }
+    for (int i = 0; i < 3; i++) {
+        accel[i] = (int16_t)(buf[i * 2] << 8 | buf[i * 2 + 1]);
+        gyro[i] = (int16_t)(buf[8 + i * 2] << 8 | buf[9 + i * 2]);
+    }
+    *temp = (int16_t)(buf[6] << 8 | buf[7]);
+    return true;
+}
+
+bool mpu6050_init(void) {
+    uint8_t id = 0;
+    if (!sensor_read_regs(MPU6050_REG_WHO_AM_I, &id, 1) || id != MPU6050_WHO_AM_I_VALUE) {
+        return false;
+    }
+    sensor_write_reg(MPU6050_REG_PWR_MGMT_1, 0x01);    // Wake, PLL with X gyro reference
+    sensor_write_reg(MPU6050_REG_SMPLRT_DIV, 0x07);    // 1 kHz / (1 + 7) = 125 Hz
+    sensor_write_reg(MPU6050_REG_CONFIG, 0x03);        // 44 Hz DLPF
+    sensor_write_reg(MPU6050_REG_GYRO_CONFIG, 0x00);   // +-250 dps
+    sensor_write_reg(MPU6050_REG_ACCEL_CONFIG, 0x00);  // +-2 g
+    HAL_Delay(100);
+    memset(gyro_offset, 0, sizeof(gyro_offset));
+    return true;
+}
+
+bool mpu6050_calibrate(uint16_t samples) {
+    int32_t sum[3] = {0};
+    int16_t accel[3], gyro[3], temp;
+    for (uint16_t n = 0; n < samples; n++) {
+        if (!mpu6050_read_raw(accel, &temp, gyro)) {
+            return false;
+        }
+        for (int i = 0; i < 3; i++) {
+            sum[i] += gyro[i];
+        }
+        HAL_Delay(8);
+    }
+    for (int i = 0; i < 3; i++) {
+        gyro_offset[i] = (int16_t)(sum[i] / samples);
+    }
+    return true;
+}
+
+bool mpu6050_read(MPU6050_Data_t *data) {
+    int16_t accel[3], gyro[3], temp;
+    if (!mpu6050_read_raw(accel, &temp, gyro)) {
+        return false;
+    }
+    for (int i = 0; i < 3; i++) {
+        data->accel_g[i] = accel[i] / MPU6050_ACCEL_LSB_PER_G;
+        data->gyro_dps[i] = (gyro[i] - gyro_offset[i]) / MPU6050_GYRO_LSB_PER_DPS;
+    }
+    data->temperature_c = temp / 340.0f + 36.53f;
+    return true;
+}
+"#;
+
+const MPU6050_EXAMPLE: &str = r#"
+int main(void) {
+    HAL_Init();
+    SystemClock_Config();
+    I2C1_Init();
+
+    if (!mpu6050_init()) {
+        Error_Handler();
+    }
+    mpu6050_calibrate(200);  // Keep the board still
+
+    MPU6050_Data_t data;
+    while (1) {
+        if (mpu6050_read(&data)) {
+            printf("a=%.2f,%.2f,%.2f g  w=%.1f,%.1f,%.1f dps\n",
+                   data.accel_g[0], data.accel_g[1], data.accel_g[2],
+                   data.gyro_dps[0], data.gyro_dps[1], data.gyro_dps[2]);
+        }
+        HAL_Delay(10);
+    }
+}
+"#;
+
+const LIS3DH_HEADER: &str = r#"
+#define LIS3DH_WHO_AM_I_VALUE 0x33
+
+typedef struct {
+    float accel_g[3];
+} LIS3DH_Data_t;
+
+bool lis3dh_init(void);
+bool lis3dh_calibrate(uint16_t samples);
+bool lis3dh_read(LIS3DH_Data_t *data);
+"#;
+
+const LIS3DH_SOURCE: &str = r#"
+#define LIS3DH_REG_WHO_AM_I  0x0F
+#define LIS3DH_REG_CTRL_REG1 0x20
+#define LIS3DH_REG_CTRL_REG4 0x23
+#define LIS3DH_REG_OUT_X_L   0x28
+
+#define LIS3DH_MG_PER_DIGIT  1.0f  // High-resolution, +-2 g
+
+// Zero-g offset in mg, measured lying flat (Z up) by lis3dh_calibrate()
+static float zero_g_offset[3];
+
+static bool lis3dh_read_mg(float mg[3]) {
+    uint8_t buf[6];
+    if (!sensor_read_regs(LIS3DH_REG_OUT_X_L, buf, 6)) {
+        return false;
+    }
+    for (int i = 0; i < 3; i++) {
+        // 12-bit left-justified
+        int16_t raw = (int16_t)(buf[i * 2 + 1] << 8 | buf[i * 2]) >> 4;
+        mg[i] = raw * LIS3DH_MG_PER_DIGIT;
+    }
+    return true;
+}
+
+bool lis3dh_init(void) {
+    uint8_t id = 0;
+    if (!sensor_read_regs(LIS3DH_REG_WHO_AM_I, &id, 1) || id != LIS3DH_WHO_AM_I_VALUE) {
+        return false;
+    }
+    sensor_write_reg(LIS3DH_REG_CTRL_REG1, 0x57);  // 100 Hz, XYZ enabled
+    sensor_write_reg(LIS3DH_REG_CTRL_REG4, 0x88);  // BDU, +-2 g, high resolution
+    HAL_Delay(10);
+    memset(zero_g_offset, 0, sizeof(zero_g_offset));
+    return true;
+}
+
+bool lis3dh_calibrate(uint16_t samples) {
+    float sum[3] = {0};
+    float mg[3];
+    for (uint16_t n = 0; n < samples; n++) {
+        if (!lis3dh_read_mg(mg)) {
+            return false;
+        }
+        for (int i = 0; i < 3; i++) {
+            sum[i] += mg[i];
+        }
+        HAL_Delay(10);
+    }
+    zero_g_offset[0] = sum[0] / samples;
+    zero_g_offset[1] = sum[1] / samples;
+    zero_g_offset[2] = sum[2] / samples - 1000.0f;
+    return true;
+}
+
+bool lis3dh_read(LIS3DH_Data_t *data) {
+    float mg[3];
+    if (!lis3dh_read_mg(mg)) {
+        return false;
+    }
+    for (int i = 0; i < 3; i++) {
+        data->accel_g[i] = (mg[i] - zero_g_offset[i]) / 1000.0f;
+    }
+    return true;
+}
+"#;
+
+const LIS3DH_EXAMPLE: &str = r#"
+int main(void) {
+    HAL_Init();
+    SystemClock_Config();
+    I2C1_Init();  // or SPI1_Init()
+
+    if (!lis3dh_init()) {
+        Error_Handler();
+    }
+    lis3dh_calibrate(100);  // Board lying flat
+
+    LIS3DH_Data_t data;
+    while (1) {
+        if (lis3dh_read(&data)) {
+            printf("%.3f %.3f %.3f g\n", data.accel_g[0], data.accel_g[1], data.accel_g[2]);
+        }
+        HAL_Delay(10);
+    }
+}
+"#;
+
+const DS18B20_HEADER: &str = r#"
+#ifndef DS18B20_PORT
+#define DS18B20_PORT GPIOA
+#define DS18B20_PIN  GPIO_PIN_1
+#endif
+
+bool ds18b20_init(void);
+bool ds18b20_read_celsius(float *temperature);
+void ds18b20_set_offset(float offset_c);
+"#;
+
+const DS18B20_SOURCE: &str = r#"
+#define DS18B20_CMD_SKIP_ROM       0xCC
+#define DS18B20_CMD_CONVERT_T      0x44
+#define DS18B20_CMD_READ_SCRATCH   0xBE
+#define DS18B20_CMD_WRITE_SCRATCH  0x4E
+
+// Per-probe correction against a reference thermometer
+static float calibration_offset_c = 0.0f;
+
+static void onewire_release(void) {
+    HAL_GPIO_WritePin(DS18B20_PORT, DS18B20_PIN, GPIO_PIN_SET);  // Open-drain: external 4.7k pulls high
+}
+
+static void onewire_low(void) {
+    HAL_GPIO_WritePin(DS18B20_PORT, DS18B20_PIN, GPIO_PIN_RESET);
+}
+
+static bool onewire_read_pin(void) {
+    return HAL_GPIO_ReadPin(DS18B20_PORT, DS18B20_PIN) == GPIO_PIN_SET;
+}
+
+static bool onewire_reset(void) {
+    onewire_low();
+    sensor_delay_us(480);
+    onewire_release();
+    sensor_delay_us(70);
+    bool present = !onewire_read_pin();
+    sensor_delay_us(410);
+    return present;
+}
+
+static void onewire_write_byte(uint8_t byte) {
+    for (int i = 0; i < 8; i++) {
+        onewire_low();
+        if (byte & 0x01) {
+            sensor_delay_us(6);
+            onewire_release();
+            sensor_delay_us(64);
+        } else {
+            sensor_delay_us(60);
+            onewire_release();
+            sensor_delay_us(10);
+        }
+        byte >>= 1;
+    }
+}
+
+static uint8_t onewire_read_byte(void) {
+    uint8_t byte = 0;
+    for (int i = 0; i < 8; i++) {
+        onewire_low();
+        sensor_delay_us(3);
+        onewire_release();
+        sensor_delay_us(10);
+        if (onewire_read_pin()) {
+            byte |= 1 << i;
+        }
+        sensor_delay_us(53);
+    }
+    return byte;
+}
+
+// Dallas/Maxim CRC-8, polynomial x^8 + x^5 + x^4 + 1
+static uint8_t onewire_crc8(const uint8_t *data, uint8_t len) {
+    uint8_t crc = 0;
+    while (len--) {
+        uint8_t byte = *data++;
+        for (int i = 0; i < 8; i++) {
+            uint8_t mix = (crc ^ byte) & 0x01;
+            crc >>= 1;
+            if (mix) {
+                crc ^= 0x8C;
+            }
+            byte >>= 1;
+        }
+    }
+    return crc;
+}
+
+bool ds18b20_init(void) {
+    GPIO_InitTypeDef gpio = {0};
+    gpio.Pin = DS18B20_PIN;
+    gpio.Mode = GPIO_MODE_OUTPUT_OD;
+    gpio.Pull = GPIO_NOPULL;
+    gpio.Speed = GPIO_SPEED_FREQ_HIGH;
+    HAL_GPIO_Init(DS18B20_PORT, &gpio);
+
+    sensor_timer_init();
+    onewire_release();
+    if (!onewire_reset()) {
+        return false;
+    }
+    // 12-bit resolution
+    onewire_write_byte(DS18B20_CMD_SKIP_ROM);
+    onewire_write_byte(DS18B20_CMD_WRITE_SCRATCH);
+    onewire_write_byte(0x7F);  // TH
+    onewire_write_byte(0x80);  // TL
+    onewire_write_byte(0x7F);  // Config: 12-bit
+    return true;
+}
+
+bool ds18b20_read_celsius(float *temperature) {
+    if (!onewire_reset()) {
+        return false;
+    }
+    onewire_write_byte(DS18B20_CMD_SKIP_ROM);
+    onewire_write_byte(DS18B20_CMD_CONVERT_T);
+    HAL_Delay(750);  // 12-bit conversion time
+
+    if (!onewire_reset()) {
+        return false;
+    }
+    onewire_write_byte(DS18B20_CMD_SKIP_ROM);
+    onewire_write_byte(DS18B20_CMD_READ_SCRATCH);
+    uint8_t scratch[9];
+    for (int i = 0; i < 9; i++) {
+        scratch[i] = onewire_read_byte();
+    }
+    if (onewire_crc8(scratch, 8) != scratch[8]) {
+        return false;
+    }
+    int16_t raw = (int16_t)(scratch[1] << 8 | scratch[0]);
+    *temperature = raw / 16.0f + calibration_offset_c;
+    return true;
+}
+
+void ds18b20_set_offset(float offset_c) {
+    calibration_offset_c = offset_c;
+}
+"#;
+
+const DS18B20_EXAMPLE: &str = r#"
+int main(void) {
+    HAL_Init();
+    SystemClock_Config();
+    __HAL_RCC_GPIOA_CLK_ENABLE();
+
+    if (!ds18b20_init()) {
+        Error_Handler();  // No presence pulse
+    }
+    ds18b20_set_offset(-0.25f);
+
+    float temperature;
+    while (1) {
+        if (ds18b20_read_celsius(&temperature)) {
+            printf("%.2f C\n", temperature);
+        }
+        HAL_Delay(1000);
+    }
+}
+"#;
+
+const HCSR04_HEADER: &str = r#"
+#ifndef HCSR04_TRIG_PORT
+#define HCSR04_TRIG_PORT GPIOA
+#define HCSR04_TRIG_PIN  GPIO_PIN_8
+#define HCSR04_ECHO_PORT GPIOA
+#define HCSR04_ECHO_PIN  GPIO_PIN_9
+#endif
+
+#define HCSR04_TIMEOUT_US 30000  // ~5 m
+
+void hcsr04_init(void);
+bool hcsr04_read_cm(float *distance);
+void hcsr04_set_temperature(float celsius);
+"#;
+
+const HCSR04_SOURCE: &str = r#"
+// Speed of sound, compensated for air temperature
+static float sound_cm_per_us = 0.0343f;
+
+void hcsr04_init(void) {
+    GPIO_InitTypeDef gpio = {0};
+    gpio.Pin = HCSR04_TRIG_PIN;
+    gpio.Mode = GPIO_MODE_OUTPUT_PP;
+    gpio.Speed = GPIO_SPEED_FREQ_LOW;
+    HAL_GPIO_Init(HCSR04_TRIG_PORT, &gpio);
+
+    gpio.Pin = HCSR04_ECHO_PIN;
+    gpio.Mode = GPIO_MODE_INPUT;
+    gpio.Pull = GPIO_PULLDOWN;
+    HAL_GPIO_Init(HCSR04_ECHO_PORT, &gpio);
+
+    HAL_GPIO_WritePin(HCSR04_TRIG_PORT, HCSR04_TRIG_PIN, GPIO_PIN_RESET);
+    sensor_timer_init();
+}
+
+void hcsr04_set_temperature(float celsius) {
+    sound_cm_per_us = (331.3f + 0.606f * celsius) / 10000.0f;
+}
+
+bool hcsr04_read_cm(float *distance) {
+    // 10 us trigger pulse
+    HAL_GPIO_WritePin(HCSR04_TRIG_PORT, HCSR04_TRIG_PIN, GPIO_PIN_SET);
+    sensor_delay_us(10);
+    HAL_GPIO_WritePin(HCSR04_TRIG_PORT, HCSR04_TRIG_PIN, GPIO_PIN_RESET);
+
+    uint32_t start = sensor_cycles();
+    while (HAL_GPIO_ReadPin(HCSR04_ECHO_PORT, HCSR04_ECHO_PIN) == GPIO_PIN_RESET) {
+        if (sensor_elapsed_us(start) > HCSR04_TIMEOUT_US) {
+            return false;
+        }
+    }
+    uint32_t rise = sensor_cycles();
+    while (HAL_GPIO_ReadPin(HCSR04_ECHO_PORT, HCSR04_ECHO_PIN) == GPIO_PIN_SET) {
+        if (sensor_elapsed_us(rise) > HCSR04_TIMEOUT_US) {
+            return false;
+        }
+    }
+    uint32_t echo_us = sensor_elapsed_us(rise);
+
+    *distance = echo_us * sound_cm_per_us / 2.0f;
+    return true;
+}
+"#;
+
+const HCSR04_EXAMPLE: &str = r#"
+int main(void) {
+    HAL_Init();
+    SystemClock_Config();
+    __HAL_RCC_GPIOA_CLK_ENABLE();
+
+    hcsr04_init();
+    hcsr04_set_temperature(22.0f);
+
+    float distance;
+    while (1) {
+        if (hcsr04_read_cm(&distance)) {
+            printf("%.1f cm\n", distance);
+        }
+        HAL_Delay(100);
+    }
+}
+"#;
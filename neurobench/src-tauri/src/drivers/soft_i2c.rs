@@ -0,0 +1,567 @@
+// Software I2C Generator
+// Generates a bit-banged I2C master on plain GPIOs, API-compatible with the hardware I2C driver
+
+use super::mcu::stm32::Stm32Hal;
+use super::mcu::{McuFamily, McuHal};
+use super::templates::*;
+use serde::{Deserialize, Serialize};
+
+/// Software (bit-bang) I2C configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoftI2cConfig {
+    /// Hardware instance this driver stands in for, e.g. "I2C1": the
+    /// `I2C1_Read`/`I2C1_WriteReg8`/... API and `i2c1_driver.h` header
+    #[serde(default = "default_instance")]
+    pub instance: String,
+    pub sda_pin: String,
+    pub scl_pin: String,
+    pub speed_khz: u32,
+    /// Emulate open-drain by switching the pin between output-low and input
+    /// instead of using a hardware open-drain output
+    pub use_open_drain_simulation: bool,
+}
+
+fn default_instance() -> String {
+    "I2C1".to_string()
+}
+
+impl Default for SoftI2cConfig {
+    fn default() -> Self {
+        Self {
+            instance: default_instance(),
+            sda_pin: "PB7".to_string(),
+            scl_pin: "PB6".to_string(),
+            speed_khz: 100,
+            use_open_drain_simulation: false,
+        }
+    }
+}
+
+impl SoftI2cConfig {
+    pub fn validate(&self, mcu: McuFamily) -> Result<(), String> {
+        let valid_instance = self.instance.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+            && self.instance.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_instance {
+            return Err(format!("Invalid I2C instance name: {}", self.instance));
+        }
+        let sda = parse_pin(mcu, &self.sda_pin)
+            .ok_or_else(|| format!("Invalid SDA pin for {}: {}", mcu.display_name(), self.sda_pin))?;
+        let scl = parse_pin(mcu, &self.scl_pin)
+            .ok_or_else(|| format!("Invalid SCL pin for {}: {}", mcu.display_name(), self.scl_pin))?;
+        if sda == scl {
+            return Err("SDA and SCL must be different pins".to_string());
+        }
+        if self.speed_khz == 0 || self.speed_khz > 1000 {
+            return Err(format!("I2C speed must be 1-1000 kHz, got {}", self.speed_khz));
+        }
+        Ok(())
+    }
+}
+
+/// GPIO port and pin number. Port is 0 for families with a flat GPIO numbering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct PinRef {
+    pub port: u8,
+    pub pin: u8,
+}
+
+/// Parse a pin name in the family's notation: "PB7" (STM32), "P0.26" (nRF52, LPC),
+/// or a plain GPIO number such as "21" / "GPIO21" (ESP32, RP2040, nRF52)
+pub(super) fn parse_pin(mcu: McuFamily, name: &str) -> Option<PinRef> {
+    let name = name.trim().to_uppercase();
+    match mcu {
+        McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32H7 | McuFamily::STM32L4 | McuFamily::STM32G4 => {
+            let rest = name.strip_prefix('P')?;
+            let port = rest.chars().next().filter(|c| ('A'..='K').contains(c))?;
+            let pin = rest[1..].parse().ok().filter(|n| *n < 16)?;
+            Some(PinRef { port: port as u8 - b'A', pin })
+        }
+        McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3 | McuFamily::RP2040 => {
+            let max = match mcu {
+                McuFamily::ESP32 => 40,
+                McuFamily::ESP32S3 => 49,
+                McuFamily::ESP32C3 => 22,
+                _ => 30,
+            };
+            let pin = name.strip_prefix("GPIO").unwrap_or(&name).parse().ok().filter(|n| *n < max)?;
+            Some(PinRef { port: 0, pin })
+        }
+        McuFamily::NRF52832 | McuFamily::NRF52840 | McuFamily::LPC1768 | McuFamily::LPC5500 => {
+            let (port, pin) = match name.strip_prefix('P').and_then(|rest| rest.split_once(['.', '_'])) {
+                Some((port, pin)) => (port.parse().ok()?, pin.parse().ok()?),
+                None => (0, name.parse().ok()?),
+            };
+            let max_port = match mcu {
+                McuFamily::NRF52832 => 0,
+                McuFamily::LPC1768 => 4,
+                _ => 1,
+            };
+            (port <= max_port && pin < 32).then_some(PinRef { port, pin })
+        }
+    }
+}
+
+/// Whether the family's GPIO has a real open-drain output mode
+pub(super) fn has_hw_open_drain(mcu: McuFamily) -> bool {
+    !matches!(mcu, McuFamily::RP2040 | McuFamily::LPC5500)
+}
+
+/// HAL/device header providing the GPIO registers for a family
+pub(super) fn gpio_include(mcu: McuFamily) -> String {
+    match mcu {
+        McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32H7 | McuFamily::STM32L4 | McuFamily::STM32G4 => {
+            format!("#include \"{}\"", Stm32Hal::new(mcu).include_headers()[0])
+        }
+        McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3 => "#include \"driver/gpio.h\"".to_string(),
+        McuFamily::RP2040 => "#include \"hardware/gpio.h\"".to_string(),
+        McuFamily::NRF52832 | McuFamily::NRF52840 => "#include \"nrf_gpio.h\"".to_string(),
+        McuFamily::LPC1768 => "#include \"LPC17xx.h\"".to_string(),
+        McuFamily::LPC5500 => "#include \"fsl_device_registers.h\"".to_string(),
+    }
+}
+
+/// Declarations shared by every `gpio_line` init block
+pub(super) fn gpio_init_prelude(mcu: McuFamily, simulate: bool) -> String {
+    match mcu {
+        McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32H7 | McuFamily::STM32L4 | McuFamily::STM32G4 => {
+            let mode = if simulate { "GPIO_MODE_INPUT" } else { "GPIO_MODE_OUTPUT_OD" };
+            format!(
+                "    GPIO_InitTypeDef gpio = {{0}};\n    gpio.Mode = {mode};\n    gpio.Pull = GPIO_PULLUP;\n    gpio.Speed = GPIO_SPEED_FREQ_HIGH;\n"
+            )
+        }
+        _ => String::new(),
+    }
+}
+
+/// `{prefix}_LOW()`, `{prefix}_RELEASE()` and `{prefix}_READ()` macros for an
+/// open-drain line, plus the init statements leaving it released. In simulated
+/// mode the output latch stays low and only the pin direction changes.
+pub(super) fn gpio_line(mcu: McuFamily, prefix: &str, p: PinRef, simulate: bool) -> (String, String) {
+    match mcu {
+        McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32H7 | McuFamily::STM32L4 | McuFamily::STM32G4 => {
+            let port_char = (b'A' + p.port) as char;
+            let port = format!("GPIO{port_char}");
+            let n = p.pin;
+            let (low, release) = if !simulate {
+                (
+                    format!("{port}->BSRR = (1U << {})", n + 16),
+                    format!("{port}->BSRR = (1U << {n})"),
+                )
+            } else if mcu == McuFamily::STM32F1 {
+                // CNF/MODE nibble: 0x2 = push-pull output 2 MHz, 0x4 = floating input
+                let reg = if n < 8 { "CRL" } else { "CRH" };
+                let shift = (n % 8) * 4;
+                (
+                    format!("{port}->{reg} = ({port}->{reg} & ~(0xFU << {shift})) | (0x2U << {shift})"),
+                    format!("{port}->{reg} = ({port}->{reg} & ~(0xFU << {shift})) | (0x4U << {shift})"),
+                )
+            } else {
+                (
+                    format!("{port}->MODER = ({port}->MODER & ~(3U << {s})) | (1U << {s})", s = n * 2),
+                    format!("{port}->MODER &= ~(3U << {})", n * 2),
+                )
+            };
+            let macros = format!(
+                "#define {prefix}_LOW()     ({low})\n#define {prefix}_RELEASE() ({release})\n#define {prefix}_READ()    ((({port}->IDR) >> {n}) & 1U)\n"
+            );
+            let init = format!(
+                "    __HAL_RCC_GPIO{port_char}_CLK_ENABLE();\n    HAL_GPIO_WritePin({port}, GPIO_PIN_{n}, {level});\n    gpio.Pin = GPIO_PIN_{n};\n    HAL_GPIO_Init({port}, &gpio);\n",
+                level = if simulate { "GPIO_PIN_RESET" } else { "GPIO_PIN_SET" },
+            );
+            (macros, init)
+        }
+        McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3 => {
+            let n = p.pin;
+            if simulate {
+                (
+                    format!(
+                        "#define {prefix}_LOW()     gpio_set_direction({n}, GPIO_MODE_INPUT_OUTPUT)\n#define {prefix}_RELEASE() gpio_set_direction({n}, GPIO_MODE_INPUT)\n#define {prefix}_READ()    gpio_get_level({n})\n"
+                    ),
+                    format!("    gpio_reset_pin({n});\n    gpio_set_pull_mode({n}, GPIO_PULLUP_ONLY);\n    gpio_set_level({n}, 0);\n    gpio_set_direction({n}, GPIO_MODE_INPUT);\n"),
+                )
+            } else {
+                (
+                    format!(
+                        "#define {prefix}_LOW()     gpio_set_level({n}, 0)\n#define {prefix}_RELEASE() gpio_set_level({n}, 1)\n#define {prefix}_READ()    gpio_get_level({n})\n"
+                    ),
+                    format!("    gpio_reset_pin({n});\n    gpio_set_pull_mode({n}, GPIO_PULLUP_ONLY);\n    gpio_set_level({n}, 1);\n    gpio_set_direction({n}, GPIO_MODE_INPUT_OUTPUT_OD);\n"),
+                )
+            }
+        }
+        McuFamily::RP2040 => {
+            // SIO outputs are push-pull only, so the line is always released by switching to input
+            let n = p.pin;
+            (
+                format!(
+                    "#define {prefix}_LOW()     gpio_set_dir({n}, GPIO_OUT)\n#define {prefix}_RELEASE() gpio_set_dir({n}, GPIO_IN)\n#define {prefix}_READ()    gpio_get({n})\n"
+                ),
+                format!("    gpio_init({n});\n    gpio_pull_up({n});\n    gpio_put({n}, 0);\n    gpio_set_dir({n}, GPIO_IN);\n"),
+            )
+        }
+        McuFamily::NRF52832 | McuFamily::NRF52840 => {
+            let n = p.port as u32 * 32 + p.pin as u32;
+            if simulate {
+                (
+                    format!(
+                        "#define {prefix}_LOW()     nrf_gpio_pin_dir_set({n}, NRF_GPIO_PIN_DIR_OUTPUT)\n#define {prefix}_RELEASE() nrf_gpio_pin_dir_set({n}, NRF_GPIO_PIN_DIR_INPUT)\n#define {prefix}_READ()    nrf_gpio_pin_read({n})\n"
+                    ),
+                    format!("    nrf_gpio_pin_clear({n});\n    nrf_gpio_cfg({n}, NRF_GPIO_PIN_DIR_INPUT, NRF_GPIO_PIN_INPUT_CONNECT,\n                 NRF_GPIO_PIN_PULLUP, NRF_GPIO_PIN_S0S1, NRF_GPIO_PIN_NOSENSE);\n"),
+                )
+            } else {
+                (
+                    format!(
+                        "#define {prefix}_LOW()     nrf_gpio_pin_clear({n})\n#define {prefix}_RELEASE() nrf_gpio_pin_set({n})\n#define {prefix}_READ()    nrf_gpio_pin_read({n})\n"
+                    ),
+                    format!("    nrf_gpio_pin_set({n});\n    nrf_gpio_cfg({n}, NRF_GPIO_PIN_DIR_OUTPUT, NRF_GPIO_PIN_INPUT_CONNECT,\n                 NRF_GPIO_PIN_PULLUP, NRF_GPIO_PIN_S0D1, NRF_GPIO_PIN_NOSENSE);\n"),
+                )
+            }
+        }
+        McuFamily::LPC1768 => {
+            let (port, n) = (p.port, p.pin);
+            let (low, release, init) = if simulate {
+                (
+                    format!("LPC_GPIO{port}->FIODIR |= (1U << {n})"),
+                    format!("LPC_GPIO{port}->FIODIR &= ~(1U << {n})"),
+                    format!("    LPC_GPIO{port}->FIOCLR = (1U << {n});\n    LPC_GPIO{port}->FIODIR &= ~(1U << {n});\n"),
+                )
+            } else {
+                (
+                    format!("LPC_GPIO{port}->FIOCLR = (1U << {n})"),
+                    format!("LPC_GPIO{port}->FIOSET = (1U << {n})"),
+                    format!("    LPC_PINCON->PINMODE_OD{port} |= (1U << {n});\n    LPC_GPIO{port}->FIOSET = (1U << {n});\n    LPC_GPIO{port}->FIODIR |= (1U << {n});\n"),
+                )
+            };
+            (
+                format!(
+                    "#define {prefix}_LOW()     ({low})\n#define {prefix}_RELEASE() ({release})\n#define {prefix}_READ()    ((LPC_GPIO{port}->FIOPIN >> {n}) & 1U)\n"
+                ),
+                init,
+            )
+        }
+        McuFamily::LPC5500 => {
+            // IOCON pin function and pull-ups are expected to be set by the board pin mux
+            let (port, n) = (p.port, p.pin);
+            (
+                format!(
+                    "#define {prefix}_LOW()     (GPIO->DIRSET[{port}] = (1U << {n}))\n#define {prefix}_RELEASE() (GPIO->DIRCLR[{port}] = (1U << {n}))\n#define {prefix}_READ()    (GPIO->B[{port}][{n}] & 1U)\n"
+                ),
+                format!("    GPIO->CLR[{port}] = (1U << {n});\n    GPIO->DIRCLR[{port}] = (1U << {n});\n"),
+            )
+        }
+    }
+}
+
+/// Generate a bit-banged I2C master driver
+pub fn generate_soft_i2c(config: &SoftI2cConfig, mcu: McuFamily) -> DriverOutput {
+    let sda = parse_pin(mcu, &config.sda_pin).unwrap_or(PinRef { port: 0, pin: 0 });
+    let scl = parse_pin(mcu, &config.scl_pin).unwrap_or(PinRef { port: 0, pin: 1 });
+    let simulate = config.use_open_drain_simulation || !has_hw_open_drain(mcu);
+    let include = gpio_include(mcu);
+    let (sda_macros, sda_init) = gpio_line(mcu, "SOFT_I2C_SDA", sda, simulate);
+    let (scl_macros, scl_init) = gpio_line(mcu, "SOFT_I2C_SCL", scl, simulate);
+    let line_macros = format!("{}{}", sda_macros, scl_macros);
+    let line_init = format!("{}{}{}", gpio_init_prelude(mcu, simulate), sda_init, scl_init);
+
+    let speed_khz = config.speed_khz;
+    let cpu_hz = mcu.max_frequency_mhz() as u64 * 1_000_000;
+    // Half SCL period in delay loop iterations, less the GPIO access overhead
+    let half_period_loops = (cpu_hz / (speed_khz as u64 * 2000) / SOFT_I2C_CYCLES_PER_LOOP)
+        .saturating_sub(SOFT_I2C_OVERHEAD_LOOPS)
+        .max(1);
+    let drive_mode = if simulate { "simulated open-drain (direction switching)" } else { "hardware open-drain" };
+    let mcu_name = mcu.display_name();
+    let sda_pin = &config.sda_pin;
+    let scl_pin = &config.scl_pin;
+    let instance = config.instance.to_uppercase();
+    let instance_lower = instance.to_lowercase();
+
+    let header = format!(r#"/**
+ * Software I2C Driver (bit-bang)
+ * Auto-generated by NeuroBench
+ * MCU: {mcu_name}, SDA: {sda_pin}, SCL: {scl_pin}, {speed_khz} kHz
+ *
+ * Drop-in replacement for the {instance} hardware driver: save as
+ * {instance_lower}_driver.h, it provides the same {instance}_* API.
+ */
+
+#ifndef {instance}_DRIVER_H
+#define {instance}_DRIVER_H
+
+#include <stdint.h>
+#include <stdbool.h>
+
+#define SOFT_I2C_SPEED_KHZ {speed_khz}
+
+void i2c_init(void);
+bool i2c_is_device_ready(uint8_t addr);
+bool i2c_write(uint8_t addr, uint8_t reg, const uint8_t *data, uint16_t len);
+bool i2c_read(uint8_t addr, uint8_t reg, uint8_t *data, uint16_t len);
+bool i2c_write_reg(uint8_t addr, uint8_t reg, uint8_t value);
+uint8_t i2c_read_reg(uint8_t addr, uint8_t reg);
+
+// Hardware driver API, so code written against {instance} links unchanged
+void {instance}_Init(void);
+bool {instance}_IsDeviceReady(uint8_t addr, uint8_t trials);
+bool {instance}_Write(uint8_t addr, uint8_t reg, const uint8_t *data, uint16_t len);
+bool {instance}_Read(uint8_t addr, uint8_t reg, uint8_t *data, uint16_t len);
+bool {instance}_WriteReg8(uint8_t addr, uint8_t reg, uint8_t value);
+uint8_t {instance}_ReadReg8(uint8_t addr, uint8_t reg);
+
+#endif // {instance}_DRIVER_H
+"#);
+
+    let source = format!(r#"/**
+ * Software I2C Driver (bit-bang)
+ * Auto-generated by NeuroBench
+ * Lines: {drive_mode}, external pull-ups required
+ */
+
+#include "{instance_lower}_driver.h"
+{include}
+
+// Timing calibrated for {cpu_mhz} MHz core clock; override if running slower
+#ifndef SOFT_I2C_CPU_HZ
+#define SOFT_I2C_CPU_HZ          {cpu_hz}UL
+#endif
+#define SOFT_I2C_CYCLES_PER_LOOP {cycles_per_loop}UL
+#define SOFT_I2C_HALF_PERIOD     {half_period_loops}UL  // Delay loops per SCL half period
+#define SOFT_I2C_STRETCH_TIMEOUT 10000UL  // Half periods a slave may hold SCL low
+
+{line_macros}
+static void i2c_delay(void) {{
+    for (volatile uint32_t i = 0; i < SOFT_I2C_HALF_PERIOD; i++) {{
+        __asm volatile ("nop");
+    }}
+}}
+
+// Release SCL and wait for any slave clock stretching to finish
+static bool scl_release(void) {{
+    SOFT_I2C_SCL_RELEASE();
+    uint32_t timeout = SOFT_I2C_STRETCH_TIMEOUT;
+    while (!SOFT_I2C_SCL_READ()) {{
+        if (--timeout == 0) {{
+            return false;
+        }}
+        i2c_delay();
+    }}
+    return true;
+}}
+
+static bool i2c_start(void) {{
+    SOFT_I2C_SDA_RELEASE();
+    if (!scl_release()) {{
+        return false;
+    }}
+    i2c_delay();
+    SOFT_I2C_SDA_LOW();
+    i2c_delay();
+    SOFT_I2C_SCL_LOW();
+    return true;
+}}
+
+static void i2c_stop(void) {{
+    SOFT_I2C_SDA_LOW();
+    i2c_delay();
+    scl_release();
+    i2c_delay();
+    SOFT_I2C_SDA_RELEASE();
+    i2c_delay();
+}}
+
+// Clock out one byte MSB first, returns true if the slave ACKed
+static bool i2c_write_byte(uint8_t byte) {{
+    for (int bit = 7; bit >= 0; bit--) {{
+        if (byte & (1U << bit)) {{
+            SOFT_I2C_SDA_RELEASE();
+        }} else {{
+            SOFT_I2C_SDA_LOW();
+        }}
+        i2c_delay();
+        if (!scl_release()) {{
+            return false;
+        }}
+        i2c_delay();
+        SOFT_I2C_SCL_LOW();
+    }}
+
+    // ACK clock: slave pulls SDA low
+    SOFT_I2C_SDA_RELEASE();
+    i2c_delay();
+    if (!scl_release()) {{
+        return false;
+    }}
+    bool ack = !SOFT_I2C_SDA_READ();
+    i2c_delay();
+    SOFT_I2C_SCL_LOW();
+    return ack;
+}}
+
+static bool i2c_read_byte(uint8_t *byte, bool ack) {{
+    uint8_t value = 0;
+    SOFT_I2C_SDA_RELEASE();
+    for (int bit = 7; bit >= 0; bit--) {{
+        i2c_delay();
+        if (!scl_release()) {{
+            return false;
+        }}
+        if (SOFT_I2C_SDA_READ()) {{
+            value |= (uint8_t)(1U << bit);
+        }}
+        i2c_delay();
+        SOFT_I2C_SCL_LOW();
+    }}
+
+    // Master ACKs every byte but the last
+    if (ack) {{
+        SOFT_I2C_SDA_LOW();
+    }}
+    i2c_delay();
+    if (!scl_release()) {{
+        return false;
+    }}
+    i2c_delay();
+    SOFT_I2C_SCL_LOW();
+    SOFT_I2C_SDA_RELEASE();
+    *byte = value;
+    return true;
+}}
+
+// Clock SCL until a slave stuck mid-byte releases SDA
+static void i2c_bus_recover(void) {{
+    for (int i = 0; i < 9 && !SOFT_I2C_SDA_READ(); i++) {{
+        SOFT_I2C_SCL_LOW();
+        i2c_delay();
+        scl_release();
+        i2c_delay();
+    }}
+    i2c_stop();
+}}
+
+void i2c_init(void) {{
+{line_init}
+    i2c_bus_recover();
+}}
+
+bool i2c_is_device_ready(uint8_t addr) {{
+    if (!i2c_start()) {{
+        return false;
+    }}
+    bool ack = i2c_write_byte((uint8_t)(addr << 1));
+    i2c_stop();
+    return ack;
+}}
+
+bool i2c_write(uint8_t addr, uint8_t reg, const uint8_t *data, uint16_t len) {{
+    if (!i2c_start()) {{
+        return false;
+    }}
+    bool ok = i2c_write_byte((uint8_t)(addr << 1)) && i2c_write_byte(reg);
+    for (uint16_t i = 0; ok && i < len; i++) {{
+        ok = i2c_write_byte(data[i]);
+    }}
+    i2c_stop();
+    return ok;
+}}
+
+bool i2c_read(uint8_t addr, uint8_t reg, uint8_t *data, uint16_t len) {{
+    if (len == 0 || !i2c_start()) {{
+        return false;
+    }}
+    bool ok = i2c_write_byte((uint8_t)(addr << 1)) && i2c_write_byte(reg);
+
+    // Repeated start into read mode
+    if (ok) {{
+        SOFT_I2C_SDA_RELEASE();
+        i2c_delay();
+        ok = i2c_start() && i2c_write_byte((uint8_t)((addr << 1) | 1U));
+    }}
+    for (uint16_t i = 0; ok && i < len; i++) {{
+        ok = i2c_read_byte(&data[i], i + 1 < len);
+    }}
+    i2c_stop();
+    return ok;
+}}
+
+bool i2c_write_reg(uint8_t addr, uint8_t reg, uint8_t value) {{
+    return i2c_write(addr, reg, &value, 1);
+}}
+
+uint8_t i2c_read_reg(uint8_t addr, uint8_t reg) {{
+    uint8_t value = 0;
+    i2c_read(addr, reg, &value, 1);
+    return value;
+}}
+
+// ==================== {instance} API ====================
+
+void {instance}_Init(void) {{
+    i2c_init();
+}}
+
+bool {instance}_IsDeviceReady(uint8_t addr, uint8_t trials) {{
+    for (uint8_t i = 0; i < trials; i++) {{
+        if (i2c_is_device_ready(addr)) {{
+            return true;
+        }}
+    }}
+    return false;
+}}
+
+bool {instance}_Write(uint8_t addr, uint8_t reg, const uint8_t *data, uint16_t len) {{
+    return i2c_write(addr, reg, data, len);
+}}
+
+bool {instance}_Read(uint8_t addr, uint8_t reg, uint8_t *data, uint16_t len) {{
+    return i2c_read(addr, reg, data, len);
+}}
+
+bool {instance}_WriteReg8(uint8_t addr, uint8_t reg, uint8_t value) {{
+    return i2c_write_reg(addr, reg, value);
+}}
+
+uint8_t {instance}_ReadReg8(uint8_t addr, uint8_t reg) {{
+    return i2c_read_reg(addr, reg);
+}}
+"#,
+        cpu_mhz = mcu.max_frequency_mhz(),
+        cycles_per_loop = SOFT_I2C_CYCLES_PER_LOOP,
+    );
+
+    let example = format!(r#"/**
+ * Software I2C Example
+ * Scans the bus and reads the WHO_AM_I register of an MPU6050
+ */
+
+#include "{instance_lower}_driver.h"
+#include <stdio.h>
+
+int main(void) {{
+    {instance}_Init();
+
+    for (uint8_t addr = 0x08; addr < 0x78; addr++) {{
+        if ({instance}_IsDeviceReady(addr, 1)) {{
+            printf("Found device at 0x%02X\n", addr);
+        }}
+    }}
+
+    {instance}_WriteReg8(0x68, 0x6B, 0x00);  // Wake up
+    uint8_t who_am_i = {instance}_ReadReg8(0x68, 0x75);
+    printf("WHO_AM_I: 0x%02X\n", who_am_i);
+
+    while (1) {{
+    }}
+}}
+"#);
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: Some(example),
+        peripheral_type: PeripheralType::I2C,
+    }
+}
+
+/// CPU cycles per iteration of the generated delay loop (volatile counter + nop)
+const SOFT_I2C_CYCLES_PER_LOOP: u64 = 4;
+/// Loop iterations absorbed by GPIO register accesses each half period
+const SOFT_I2C_OVERHEAD_LOOPS: u64 = 2;
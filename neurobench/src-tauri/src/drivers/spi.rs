@@ -1,6 +1,7 @@
 // SPI Driver Generator
 // Generates SPI drivers for various MCU architectures
 
+use super::hal_abstraction;
 use super::templates::*;
 
 /// Generate SPI driver code
@@ -9,6 +10,10 @@ pub fn generate_spi_driver(config: &SpiConfig, arch: &McuArch, lang: &DriverLang
         DriverLanguage::C => generate_spi_c(config, arch),
         DriverLanguage::Cpp => generate_spi_cpp(config, arch),
         DriverLanguage::Rust => generate_spi_rust(config, arch),
+        DriverLanguage::Portable => hal_abstraction::generate_portable_driver(
+            &hal_abstraction::PortablePeripheral::Spi(config.clone()),
+            hal_abstraction::HalBackend::for_arch(arch).hal().as_ref(),
+        ),
     }
 }
 
@@ -32,6 +32,8 @@ pub enum DriverLanguage {
     C,
     Cpp,
     Rust,
+    /// C that only calls the `peripheral_hal_t` interface (see `hal_abstraction`)
+    Portable,
 }
 
 impl Default for DriverLanguage {
@@ -56,6 +58,12 @@ pub enum PeripheralType {
     Ethernet,
     DMA,
     Modbus,
+    PMIC,
+    OTA,
+    CRC,
+    I2S,
+    SDMMC,
+    KDF,
 }
 
 /// Driver output structure
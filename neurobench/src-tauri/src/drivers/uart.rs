@@ -1,6 +1,7 @@
 // UART Driver Generator
 // Generates UART/USART drivers for various MCU architectures
 
+use super::hal_abstraction;
 use super::templates::*;
 
 /// Generate UART driver code
@@ -9,6 +10,10 @@ pub fn generate_uart_driver(config: &UartConfig, arch: &McuArch, lang: &DriverLa
         DriverLanguage::C => generate_uart_c(config, arch),
         DriverLanguage::Cpp => generate_uart_cpp(config, arch),
         DriverLanguage::Rust => generate_uart_rust(config, arch),
+        DriverLanguage::Portable => hal_abstraction::generate_portable_driver(
+            &hal_abstraction::PortablePeripheral::Uart(config.clone()),
+            hal_abstraction::HalBackend::for_arch(arch).hal().as_ref(),
+        ),
     }
 }
 
@@ -0,0 +1,839 @@
+// USB Device Driver Generator
+// Generates STM32 USB device stacks for CDC ACM and HID classes
+
+use super::mcu::McuFamily;
+use super::templates::*;
+use serde::{Deserialize, Serialize};
+
+/// USB device class
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UsbDeviceClass {
+    CdcAcm,
+    Hid,
+    MassStorage,
+    WebUsb,
+    Dfu,
+}
+
+/// USB device configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsbConfig {
+    pub device_class: UsbDeviceClass,
+    pub vid: u16,
+    pub pid: u16,
+    pub manufacturer: String,
+    pub product: String,
+    pub serial: String,
+}
+
+impl Default for UsbConfig {
+    fn default() -> Self {
+        Self {
+            device_class: UsbDeviceClass::CdcAcm,
+            vid: 0x0483,
+            pid: 0x5740,
+            manufacturer: "NeuroBench".to_string(),
+            product: "NeuroBench Virtual COM Port".to_string(),
+            serial: "000000000001".to_string(),
+        }
+    }
+}
+
+/// USB peripheral details for an STM32 family
+pub(super) struct UsbPeripheral {
+    hal_header: &'static str,
+    instance: &'static str,
+    pcd_handle: &'static str,
+    irqn: &'static str,
+    irq_handler: &'static str,
+    /// OTG cores use FIFOs, the FS device core uses packet memory (PMA)
+    otg: bool,
+    /// STM32F1 has no internal D+ pull-up; boards wire one to a GPIO or 3.3V
+    internal_pullup: bool,
+}
+
+pub(super) fn usb_peripheral(mcu: McuFamily) -> Option<UsbPeripheral> {
+    let fs_device = |hal_header, irqn, irq_handler, internal_pullup| UsbPeripheral {
+        hal_header,
+        instance: "USB",
+        pcd_handle: "hpcd_USB_FS",
+        irqn,
+        irq_handler,
+        otg: false,
+        internal_pullup,
+    };
+    let otg_fs = |hal_header| UsbPeripheral {
+        hal_header,
+        instance: "USB_OTG_FS",
+        pcd_handle: "hpcd_USB_OTG_FS",
+        irqn: "OTG_FS_IRQn",
+        irq_handler: "OTG_FS_IRQHandler",
+        otg: true,
+        internal_pullup: true,
+    };
+
+    match mcu {
+        McuFamily::STM32F1 => Some(fs_device("stm32f1xx_hal.h", "USB_LP_CAN1_RX0_IRQn", "USB_LP_CAN1_RX0_IRQHandler", false)),
+        McuFamily::STM32L4 => Some(fs_device("stm32l4xx_hal.h", "USB_IRQn", "USB_IRQHandler", true)),
+        McuFamily::STM32G4 => Some(fs_device("stm32g4xx_hal.h", "USB_LP_IRQn", "USB_LP_IRQHandler", true)),
+        McuFamily::STM32F4 => Some(otg_fs("stm32f4xx_hal.h")),
+        McuFamily::STM32H7 => Some(otg_fs("stm32h7xx_hal.h")),
+        _ => None,
+    }
+}
+
+/// Whether USB device code can be generated for `mcu`
+pub fn supports_usb_device(mcu: McuFamily) -> bool {
+    usb_peripheral(mcu).is_some()
+}
+
+/// Generate a USB device driver for the configured class
+pub fn generate_usb_driver(config: &UsbConfig, mcu: McuFamily) -> Result<DriverOutput, String> {
+    if !supports_usb_device(mcu) {
+        return Err(format!("USB device generation is not available for {}", mcu.display_name()));
+    }
+    match config.device_class {
+        UsbDeviceClass::CdcAcm => Ok(generate_usb_cdc_acm(config, mcu)),
+        UsbDeviceClass::Hid => Ok(generate_usb_hid(config, mcu)),
+        UsbDeviceClass::Dfu => Err("DFU needs a memory map: use generate_usb_dfu_bootloader".to_string()),
+        other => Err(format!("{:?} USB class generator not yet implemented", other)),
+    }
+}
+
+pub(super) fn c_string(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Descriptors, the complete low-level driver, D+ pull-up control and the interrupt handler.
+/// The low-level driver replaces CubeMX's `usbd_conf.c`, which must be left out of the build.
+/// `interface_string` overrides the interface string descriptor (product name by default).
+pub(super) fn usb_core_source(
+    config: &UsbConfig,
+    periph: &UsbPeripheral,
+    endpoints: &[(u8, u16)],
+    interface_string: Option<&str>,
+) -> String {
+    let UsbPeripheral { hal_header, instance, pcd_handle, irqn, irq_handler, .. } = periph;
+    let vid = config.vid;
+    let pid = config.pid;
+    let manufacturer = c_string(&config.manufacturer);
+    let product = c_string(&config.product);
+    let serial = c_string(&config.serial);
+    let (device_class, device_subclass, device_protocol) = match config.device_class {
+        // IAD class triplet so hosts bind both CDC interfaces to one function
+        UsbDeviceClass::CdcAcm => ("0xEF", "0x02", "0x01"),
+        _ => ("0x00", "0x00", "0x00"),
+    };
+
+    let endpoint_setup = if periph.otg {
+        let mut setup = format!("    HAL_PCDEx_SetRxFiFo(&{pcd_handle}, 0x80);\n    HAL_PCDEx_SetTxFiFo(&{pcd_handle}, 0, 0x40);\n");
+        for (i, (address, _)) in endpoints.iter().filter(|(address, _)| address & 0x80 != 0).enumerate() {
+            let size = if i == 0 { "0x80" } else { "0x40" };
+            setup.push_str(&format!("    HAL_PCDEx_SetTxFiFo(&{}, {}, {}); // EP 0x{:02X}\n", pcd_handle, address & 0x0F, size, address));
+        }
+        setup
+    } else {
+        let mut setup = format!("    HAL_PCDEx_PMAConfig(&{pcd_handle}, 0x00, PCD_SNG_BUF, 0x18);\n    HAL_PCDEx_PMAConfig(&{pcd_handle}, 0x80, PCD_SNG_BUF, 0x58);\n");
+        let mut offset = 0x98;
+        for (address, size) in endpoints {
+            setup.push_str(&format!("    HAL_PCDEx_PMAConfig(&{}, 0x{:02X}, PCD_SNG_BUF, 0x{:03X});\n", pcd_handle, address, offset));
+            offset += size;
+        }
+        setup
+    };
+
+    let pullup = if periph.internal_pullup {
+        format!(r#"void usb_dplus_pullup(bool enable) {{
+    if (enable) {{
+        HAL_PCD_DevConnect(&{pcd_handle});
+    }} else {{
+        HAL_PCD_DevDisconnect(&{pcd_handle});
+    }}
+}}"#)
+    } else {
+        r#"void usb_dplus_pullup(bool enable) {
+#ifdef USB_DP_PULLUP_PORT
+    // External 1.5k pull-up switched by a GPIO
+    HAL_GPIO_WritePin(USB_DP_PULLUP_PORT, USB_DP_PULLUP_PIN, enable ? GPIO_PIN_SET : GPIO_PIN_RESET);
+#else
+    // Fixed pull-up: hold D+ (PA12) low briefly so the host sees a re-plug
+    if (!enable) {
+        GPIO_InitTypeDef gpio = {0};
+        __HAL_RCC_GPIOA_CLK_ENABLE();
+        gpio.Pin = GPIO_PIN_12;
+        gpio.Mode = GPIO_MODE_OUTPUT_PP;
+        gpio.Speed = GPIO_SPEED_FREQ_LOW;
+        HAL_GPIO_Init(GPIOA, &gpio);
+        HAL_GPIO_WritePin(GPIOA, GPIO_PIN_12, GPIO_PIN_RESET);
+        HAL_Delay(10);
+        HAL_GPIO_DeInit(GPIOA, GPIO_PIN_12);
+    }
+#endif
+}"#.to_string()
+    };
+
+    let interface_descriptor = match interface_string {
+        Some(text) => format!(r#"#define USBD_INTERFACE_STRING    "{}"
+
+static uint8_t *USBD_FS_InterfaceStrDescriptor(USBD_SpeedTypeDef speed, uint16_t *length) {{
+    (void)speed;
+    USBD_GetString((uint8_t *)USBD_INTERFACE_STRING, USBD_StrDesc, length);
+    return USBD_StrDesc;
+}}"#, c_string(text)),
+        None => r#"static uint8_t *USBD_FS_InterfaceStrDescriptor(USBD_SpeedTypeDef speed, uint16_t *length) {
+    return USBD_FS_ProductStrDescriptor(speed, length);
+}"#.to_string(),
+    };
+
+    let clock_enable = if periph.otg { "__HAL_RCC_USB_OTG_FS_CLK_ENABLE();" } else { "__HAL_RCC_USB_CLK_ENABLE();" };
+    let vid_lo = vid & 0xFF;
+    let vid_hi = vid >> 8;
+    let pid_lo = pid & 0xFF;
+    let pid_hi = pid >> 8;
+
+    format!(r#"// Replaces CubeMX's usbd_conf.c: remove that file from the build
+#include "{hal_header}"
+#include "usbd_core.h"
+#include "usbd_ctlreq.h"
+
+USBD_HandleTypeDef hUsbDeviceFS;
+PCD_HandleTypeDef {pcd_handle};
+
+// ---------------------------------------------------------------------------
+// Descriptors
+// ---------------------------------------------------------------------------
+
+#define USBD_VID                 0x{vid:04X}
+#define USBD_PID                 0x{pid:04X}
+#define USBD_LANGID_STRING       0x0409
+#define USBD_MANUFACTURER_STRING "{manufacturer}"
+#define USBD_PRODUCT_STRING      "{product}"
+#define USBD_SERIAL_STRING       "{serial}"
+
+static uint8_t USBD_FS_DeviceDesc[USB_LEN_DEV_DESC] __ALIGN_END = {{
+    0x12,                   // bLength
+    USB_DESC_TYPE_DEVICE,   // bDescriptorType
+    0x00, 0x02,             // bcdUSB 2.00
+    {device_class},                   // bDeviceClass
+    {device_subclass},                   // bDeviceSubClass
+    {device_protocol},                   // bDeviceProtocol
+    USB_MAX_EP0_SIZE,       // bMaxPacketSize0
+    0x{vid_lo:02X}, 0x{vid_hi:02X},             // idVendor
+    0x{pid_lo:02X}, 0x{pid_hi:02X},             // idProduct
+    0x00, 0x02,             // bcdDevice 2.00
+    USBD_IDX_MFC_STR,
+    USBD_IDX_PRODUCT_STR,
+    USBD_IDX_SERIAL_STR,
+    USBD_MAX_NUM_CONFIGURATION
+}};
+
+static uint8_t USBD_LangIDDesc[USB_LEN_LANGID_STR_DESC] __ALIGN_END = {{
+    USB_LEN_LANGID_STR_DESC,
+    USB_DESC_TYPE_STRING,
+    LOBYTE(USBD_LANGID_STRING),
+    HIBYTE(USBD_LANGID_STRING)
+}};
+
+static uint8_t USBD_StrDesc[USBD_MAX_STR_DESC_SIZ] __ALIGN_END;
+
+static uint8_t *USBD_FS_DeviceDescriptor(USBD_SpeedTypeDef speed, uint16_t *length) {{
+    (void)speed;
+    *length = sizeof(USBD_FS_DeviceDesc);
+    return USBD_FS_DeviceDesc;
+}}
+
+static uint8_t *USBD_FS_LangIDStrDescriptor(USBD_SpeedTypeDef speed, uint16_t *length) {{
+    (void)speed;
+    *length = sizeof(USBD_LangIDDesc);
+    return USBD_LangIDDesc;
+}}
+
+static uint8_t *USBD_FS_ManufacturerStrDescriptor(USBD_SpeedTypeDef speed, uint16_t *length) {{
+    (void)speed;
+    USBD_GetString((uint8_t *)USBD_MANUFACTURER_STRING, USBD_StrDesc, length);
+    return USBD_StrDesc;
+}}
+
+static uint8_t *USBD_FS_ProductStrDescriptor(USBD_SpeedTypeDef speed, uint16_t *length) {{
+    (void)speed;
+    USBD_GetString((uint8_t *)USBD_PRODUCT_STRING, USBD_StrDesc, length);
+    return USBD_StrDesc;
+}}
+
+static uint8_t *USBD_FS_SerialStrDescriptor(USBD_SpeedTypeDef speed, uint16_t *length) {{
+    (void)speed;
+    USBD_GetString((uint8_t *)USBD_SERIAL_STRING, USBD_StrDesc, length);
+    return USBD_StrDesc;
+}}
+
+static uint8_t *USBD_FS_ConfigStrDescriptor(USBD_SpeedTypeDef speed, uint16_t *length) {{
+    return USBD_FS_ProductStrDescriptor(speed, length);
+}}
+
+{interface_descriptor}
+
+USBD_DescriptorsTypeDef FS_Desc = {{
+    USBD_FS_DeviceDescriptor,
+    USBD_FS_LangIDStrDescriptor,
+    USBD_FS_ManufacturerStrDescriptor,
+    USBD_FS_ProductStrDescriptor,
+    USBD_FS_SerialStrDescriptor,
+    USBD_FS_ConfigStrDescriptor,
+    USBD_FS_InterfaceStrDescriptor
+}};
+
+// ---------------------------------------------------------------------------
+// Low-level driver
+// ---------------------------------------------------------------------------
+
+void HAL_PCD_MspInit(PCD_HandleTypeDef *pcd) {{
+    if (pcd->Instance == {instance}) {{
+        {clock_enable}
+        HAL_NVIC_SetPriority({irqn}, 5, 0);
+        HAL_NVIC_EnableIRQ({irqn});
+    }}
+}}
+
+USBD_StatusTypeDef USBD_LL_Init(USBD_HandleTypeDef *pdev) {{
+    {pcd_handle}.pData = pdev;
+    pdev->pData = &{pcd_handle};
+
+    {pcd_handle}.Instance = {instance};
+    {pcd_handle}.Init.dev_endpoints = 8;
+    {pcd_handle}.Init.speed = PCD_SPEED_FULL;
+    {pcd_handle}.Init.phy_itface = PCD_PHY_EMBEDDED;
+    {pcd_handle}.Init.Sof_enable = DISABLE;
+    {pcd_handle}.Init.low_power_enable = DISABLE;
+    {pcd_handle}.Init.lpm_enable = DISABLE;
+    {pcd_handle}.Init.battery_charging_enable = DISABLE;
+    if (HAL_PCD_Init(&{pcd_handle}) != HAL_OK) {{
+        return USBD_FAIL;
+    }}
+
+    // Endpoint buffers
+{endpoint_setup}
+    return USBD_OK;
+}}
+
+static USBD_StatusTypeDef usbd_status(HAL_StatusTypeDef status) {{
+    switch (status) {{
+        case HAL_OK:   return USBD_OK;
+        case HAL_BUSY: return USBD_BUSY;
+        default:       return USBD_FAIL;
+    }}
+}}
+
+USBD_StatusTypeDef USBD_LL_DeInit(USBD_HandleTypeDef *pdev) {{
+    return usbd_status(HAL_PCD_DeInit(pdev->pData));
+}}
+
+USBD_StatusTypeDef USBD_LL_Start(USBD_HandleTypeDef *pdev) {{
+    return usbd_status(HAL_PCD_Start(pdev->pData));
+}}
+
+USBD_StatusTypeDef USBD_LL_Stop(USBD_HandleTypeDef *pdev) {{
+    return usbd_status(HAL_PCD_Stop(pdev->pData));
+}}
+
+USBD_StatusTypeDef USBD_LL_OpenEP(USBD_HandleTypeDef *pdev, uint8_t ep_addr, uint8_t ep_type, uint16_t ep_mps) {{
+    return usbd_status(HAL_PCD_EP_Open(pdev->pData, ep_addr, ep_mps, ep_type));
+}}
+
+USBD_StatusTypeDef USBD_LL_CloseEP(USBD_HandleTypeDef *pdev, uint8_t ep_addr) {{
+    return usbd_status(HAL_PCD_EP_Close(pdev->pData, ep_addr));
+}}
+
+USBD_StatusTypeDef USBD_LL_FlushEP(USBD_HandleTypeDef *pdev, uint8_t ep_addr) {{
+    return usbd_status(HAL_PCD_EP_Flush(pdev->pData, ep_addr));
+}}
+
+USBD_StatusTypeDef USBD_LL_StallEP(USBD_HandleTypeDef *pdev, uint8_t ep_addr) {{
+    return usbd_status(HAL_PCD_EP_SetStall(pdev->pData, ep_addr));
+}}
+
+USBD_StatusTypeDef USBD_LL_ClearStallEP(USBD_HandleTypeDef *pdev, uint8_t ep_addr) {{
+    return usbd_status(HAL_PCD_EP_ClrStall(pdev->pData, ep_addr));
+}}
+
+uint8_t USBD_LL_IsStallEP(USBD_HandleTypeDef *pdev, uint8_t ep_addr) {{
+    PCD_HandleTypeDef *hpcd = (PCD_HandleTypeDef *)pdev->pData;
+    if ((ep_addr & 0x80U) == 0x80U) {{
+        return hpcd->IN_ep[ep_addr & 0x7FU].is_stall;
+    }}
+    return hpcd->OUT_ep[ep_addr & 0x7FU].is_stall;
+}}
+
+USBD_StatusTypeDef USBD_LL_SetUSBAddress(USBD_HandleTypeDef *pdev, uint8_t dev_addr) {{
+    return usbd_status(HAL_PCD_SetAddress(pdev->pData, dev_addr));
+}}
+
+USBD_StatusTypeDef USBD_LL_Transmit(USBD_HandleTypeDef *pdev, uint8_t ep_addr, uint8_t *pbuf, uint32_t size) {{
+    return usbd_status(HAL_PCD_EP_Transmit(pdev->pData, ep_addr, pbuf, size));
+}}
+
+USBD_StatusTypeDef USBD_LL_PrepareReceive(USBD_HandleTypeDef *pdev, uint8_t ep_addr, uint8_t *pbuf, uint32_t size) {{
+    return usbd_status(HAL_PCD_EP_Receive(pdev->pData, ep_addr, pbuf, size));
+}}
+
+uint32_t USBD_LL_GetRxDataSize(USBD_HandleTypeDef *pdev, uint8_t ep_addr) {{
+    return HAL_PCD_EP_GetRxCount((PCD_HandleTypeDef *)pdev->pData, ep_addr);
+}}
+
+void USBD_LL_Delay(uint32_t delay) {{
+    HAL_Delay(delay);
+}}
+
+// Class data for usbd_conf.h's USBD_malloc; raise the size for custom classes
+#ifndef USBD_CLASS_DATA_SIZE
+#define USBD_CLASS_DATA_SIZE 1024
+#endif
+
+void *USBD_static_malloc(uint32_t size) {{
+    static uint32_t class_data[(USBD_CLASS_DATA_SIZE + 3) / 4];
+    return size <= sizeof(class_data) ? class_data : NULL;
+}}
+
+void USBD_static_free(void *p) {{
+    (void)p;
+}}
+
+// ---------------------------------------------------------------------------
+// PCD callbacks -> USB device core
+// ---------------------------------------------------------------------------
+
+void HAL_PCD_SetupStageCallback(PCD_HandleTypeDef *hpcd) {{
+    USBD_LL_SetupStage((USBD_HandleTypeDef *)hpcd->pData, (uint8_t *)hpcd->Setup);
+}}
+
+void HAL_PCD_DataOutStageCallback(PCD_HandleTypeDef *hpcd, uint8_t epnum) {{
+    USBD_LL_DataOutStage((USBD_HandleTypeDef *)hpcd->pData, epnum, hpcd->OUT_ep[epnum].xfer_buff);
+}}
+
+void HAL_PCD_DataInStageCallback(PCD_HandleTypeDef *hpcd, uint8_t epnum) {{
+    USBD_LL_DataInStage((USBD_HandleTypeDef *)hpcd->pData, epnum, hpcd->IN_ep[epnum].xfer_buff);
+}}
+
+void HAL_PCD_SOFCallback(PCD_HandleTypeDef *hpcd) {{
+    USBD_LL_SOF((USBD_HandleTypeDef *)hpcd->pData);
+}}
+
+void HAL_PCD_ResetCallback(PCD_HandleTypeDef *hpcd) {{
+    USBD_LL_SetSpeed((USBD_HandleTypeDef *)hpcd->pData, USBD_SPEED_FULL);
+    USBD_LL_Reset((USBD_HandleTypeDef *)hpcd->pData);
+}}
+
+void HAL_PCD_SuspendCallback(PCD_HandleTypeDef *hpcd) {{
+    USBD_LL_Suspend((USBD_HandleTypeDef *)hpcd->pData);
+}}
+
+void HAL_PCD_ResumeCallback(PCD_HandleTypeDef *hpcd) {{
+    USBD_LL_Resume((USBD_HandleTypeDef *)hpcd->pData);
+}}
+
+void HAL_PCD_ISOOUTIncompleteCallback(PCD_HandleTypeDef *hpcd, uint8_t epnum) {{
+    USBD_LL_IsoOUTIncomplete((USBD_HandleTypeDef *)hpcd->pData, epnum);
+}}
+
+void HAL_PCD_ISOINIncompleteCallback(PCD_HandleTypeDef *hpcd, uint8_t epnum) {{
+    USBD_LL_IsoINIncomplete((USBD_HandleTypeDef *)hpcd->pData, epnum);
+}}
+
+void HAL_PCD_ConnectCallback(PCD_HandleTypeDef *hpcd) {{
+    USBD_LL_DevConnected((USBD_HandleTypeDef *)hpcd->pData);
+}}
+
+void HAL_PCD_DisconnectCallback(PCD_HandleTypeDef *hpcd) {{
+    USBD_LL_DevDisconnected((USBD_HandleTypeDef *)hpcd->pData);
+}}
+
+// D+ pull-up: tells the host a device is attached
+{pullup}
+
+// USB interrupt handler
+void {irq_handler}(void) {{
+    HAL_PCD_IRQHandler(&{pcd_handle});
+}}
+"#)
+}
+
+/// Header shared by all classes
+pub(super) fn usb_header(config: &UsbConfig, class_name: &str, body: &str) -> String {
+    let guard = format!("USB_{}_H", class_name.to_uppercase());
+    format!(r#"/**
+ * USB {class_name} Device Driver
+ * Auto-generated by NeuroBench
+ * VID: 0x{vid:04X}, PID: 0x{pid:04X}
+ * Product: {product}
+ */
+
+#ifndef {guard}
+#define {guard}
+
+#include <stdint.h>
+#include <stdbool.h>
+#include "usbd_def.h"
+
+extern USBD_HandleTypeDef hUsbDeviceFS;
+
+void usb_device_init(void);
+void usb_dplus_pullup(bool enable);
+bool usb_is_configured(void);
+{body}
+#endif // {guard}
+"#,
+        vid = config.vid,
+        pid = config.pid,
+        product = config.product,
+    )
+}
+
+/// Generate a CDC ACM (virtual COM port) device
+pub fn generate_usb_cdc_acm(config: &UsbConfig, mcu: McuFamily) -> DriverOutput {
+    let periph = usb_peripheral(mcu).unwrap_or_else(|| usb_peripheral(McuFamily::STM32F4).unwrap());
+    let endpoints = [(0x01, 0x40), (0x81, 0x40), (0x82, 0x08)];
+
+    let header = usb_header(config, "CDC", r#"
+// Endpoint descriptors
+#define CDC_IN_EP                0x81  // Bulk IN
+#define CDC_OUT_EP               0x01  // Bulk OUT
+#define CDC_CMD_EP               0x82  // Interrupt IN (notifications)
+#define CDC_DATA_FS_MAX_PACKET   64
+#define CDC_CMD_PACKET_SIZE      8
+
+#define USB_CDC_RX_BUFFER_SIZE   512
+
+typedef void (*usb_cdc_rx_callback_t)(const uint8_t *data, uint32_t len);
+
+uint8_t usb_cdc_transmit(const uint8_t *data, uint16_t len);
+void usb_cdc_set_rx_callback(usb_cdc_rx_callback_t callback);
+uint32_t usb_cdc_baudrate(void);
+"#);
+
+    let core = usb_core_source(config, &periph, &endpoints, None);
+    let source = format!(r#"/**
+ * USB CDC ACM Device Driver
+ * Auto-generated by NeuroBench
+ */
+
+#include "usb_cdc.h"
+#include "usbd_cdc.h"
+#include <string.h>
+{core}
+// ---------------------------------------------------------------------------
+// CDC interface
+// ---------------------------------------------------------------------------
+
+static uint8_t cdc_rx_buffer[CDC_DATA_FS_MAX_PACKET];
+static uint8_t cdc_tx_buffer[USB_CDC_RX_BUFFER_SIZE];
+static usb_cdc_rx_callback_t cdc_rx_callback = NULL;
+
+// 115200 8N1 until the host sends SET_LINE_CODING
+static USBD_CDC_LineCodingTypeDef cdc_line_coding = {{ 115200, 0x00, 0x00, 0x08 }};
+
+static int8_t CDC_Init_FS(void) {{
+    USBD_CDC_SetTxBuffer(&hUsbDeviceFS, cdc_tx_buffer, 0);
+    USBD_CDC_SetRxBuffer(&hUsbDeviceFS, cdc_rx_buffer);
+    return USBD_OK;
+}}
+
+static int8_t CDC_DeInit_FS(void) {{
+    return USBD_OK;
+}}
+
+static int8_t CDC_Control_FS(uint8_t cmd, uint8_t *pbuf, uint16_t length) {{
+    (void)length;
+    switch (cmd) {{
+        case CDC_SET_LINE_CODING:
+            cdc_line_coding.bitrate = (uint32_t)(pbuf[0] | (pbuf[1] << 8) | (pbuf[2] << 16) | (pbuf[3] << 24));
+            cdc_line_coding.format = pbuf[4];
+            cdc_line_coding.paritytype = pbuf[5];
+            cdc_line_coding.datatype = pbuf[6];
+            break;
+        case CDC_GET_LINE_CODING:
+            pbuf[0] = (uint8_t)(cdc_line_coding.bitrate);
+            pbuf[1] = (uint8_t)(cdc_line_coding.bitrate >> 8);
+            pbuf[2] = (uint8_t)(cdc_line_coding.bitrate >> 16);
+            pbuf[3] = (uint8_t)(cdc_line_coding.bitrate >> 24);
+            pbuf[4] = cdc_line_coding.format;
+            pbuf[5] = cdc_line_coding.paritytype;
+            pbuf[6] = cdc_line_coding.datatype;
+            break;
+        case CDC_SET_CONTROL_LINE_STATE:
+        default:
+            break;
+    }}
+    return USBD_OK;
+}}
+
+static int8_t CDC_Receive_FS(uint8_t *buf, uint32_t *len) {{
+    if (cdc_rx_callback) {{
+        cdc_rx_callback(buf, *len);
+    }}
+    USBD_CDC_SetRxBuffer(&hUsbDeviceFS, cdc_rx_buffer);
+    USBD_CDC_ReceivePacket(&hUsbDeviceFS);
+    return USBD_OK;
+}}
+
+static USBD_CDC_ItfTypeDef USBD_Interface_fops_FS = {{
+    CDC_Init_FS,
+    CDC_DeInit_FS,
+    CDC_Control_FS,
+    CDC_Receive_FS
+}};
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+void usb_device_init(void) {{
+    usb_dplus_pullup(false);
+
+    if (USBD_Init(&hUsbDeviceFS, &FS_Desc, DEVICE_FS) != USBD_OK) {{
+        Error_Handler();
+    }}
+    if (USBD_RegisterClass(&hUsbDeviceFS, &USBD_CDC) != USBD_OK) {{
+        Error_Handler();
+    }}
+    if (USBD_CDC_RegisterInterface(&hUsbDeviceFS, &USBD_Interface_fops_FS) != USBD_OK) {{
+        Error_Handler();
+    }}
+    if (USBD_Start(&hUsbDeviceFS) != USBD_OK) {{
+        Error_Handler();
+    }}
+
+    usb_dplus_pullup(true);
+}}
+
+bool usb_is_configured(void) {{
+    return hUsbDeviceFS.dev_state == USBD_STATE_CONFIGURED;
+}}
+
+uint8_t usb_cdc_transmit(const uint8_t *data, uint16_t len) {{
+    USBD_CDC_HandleTypeDef *hcdc = (USBD_CDC_HandleTypeDef *)hUsbDeviceFS.pClassData;
+    if (!usb_is_configured() || hcdc == NULL) {{
+        return USBD_FAIL;
+    }}
+    if (hcdc->TxState != 0) {{
+        return USBD_BUSY;
+    }}
+    if (len > sizeof(cdc_tx_buffer)) {{
+        len = sizeof(cdc_tx_buffer);
+    }}
+    memcpy(cdc_tx_buffer, data, len);
+    USBD_CDC_SetTxBuffer(&hUsbDeviceFS, cdc_tx_buffer, len);
+    return USBD_CDC_TransmitPacket(&hUsbDeviceFS);
+}}
+
+void usb_cdc_set_rx_callback(usb_cdc_rx_callback_t callback) {{
+    cdc_rx_callback = callback;
+}}
+
+uint32_t usb_cdc_baudrate(void) {{
+    return cdc_line_coding.bitrate;
+}}
+"#);
+
+    let example = r#"/**
+ * USB CDC Example
+ * Echoes everything received back to the host
+ */
+
+#include "usb_cdc.h"
+#include <string.h>
+
+static void on_rx(const uint8_t *data, uint32_t len) {
+    usb_cdc_transmit(data, (uint16_t)len);
+}
+
+int main(void) {
+    HAL_Init();
+    SystemClock_Config();  // USB needs a 48 MHz clock
+
+    usb_device_init();
+    usb_cdc_set_rx_callback(on_rx);
+
+    const char *hello = "Hello from NeuroBench\r\n";
+    while (1) {
+        if (usb_is_configured()) {
+            usb_cdc_transmit((const uint8_t *)hello, strlen(hello));
+        }
+        HAL_Delay(1000);
+    }
+}
+"#.to_string();
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: Some(example),
+        peripheral_type: PeripheralType::USB,
+    }
+}
+
+/// Generate a HID keyboard device
+pub fn generate_usb_hid(config: &UsbConfig, mcu: McuFamily) -> DriverOutput {
+    let periph = usb_peripheral(mcu).unwrap_or_else(|| usb_peripheral(McuFamily::STM32F4).unwrap());
+    let endpoints = [(0x81, 0x08)];
+
+    let header = usb_header(config, "HID", r#"
+// Endpoint descriptors
+#define HID_EPIN_ADDR            0x81  // Interrupt IN
+#define HID_EPIN_SIZE            0x08
+#define HID_FS_BINTERVAL         0x0A  // 10 ms polling
+
+// Modifier bits
+#define HID_MOD_LCTRL   0x01
+#define HID_MOD_LSHIFT  0x02
+#define HID_MOD_LALT    0x04
+#define HID_MOD_LGUI    0x08
+#define HID_MOD_RCTRL   0x10
+#define HID_MOD_RSHIFT  0x20
+#define HID_MOD_RALT    0x40
+#define HID_MOD_RGUI    0x80
+
+// Boot protocol keyboard input report
+typedef struct __attribute__((packed)) {
+    uint8_t modifiers;
+    uint8_t reserved;
+    uint8_t keys[6];
+} HID_Keyboard_Report_t;
+
+extern const uint8_t HID_Keyboard_ReportDesc[];
+extern const uint16_t HID_Keyboard_ReportDescSize;
+
+uint8_t usb_hid_send_report(const HID_Keyboard_Report_t *report);
+uint8_t usb_hid_press(uint8_t modifiers, uint8_t keycode);
+uint8_t usb_hid_release_all(void);
+"#);
+
+    let core = usb_core_source(config, &periph, &endpoints, None);
+    let source = format!(r#"/**
+ * USB HID Keyboard Device Driver
+ * Auto-generated by NeuroBench
+ *
+ * usbd_hid.c ships a mouse report descriptor: point its
+ * HID_MOUSE_ReportDesc references at HID_Keyboard_ReportDesc and set
+ * HID_MOUSE_REPORT_DESC_SIZE to HID_Keyboard_ReportDescSize.
+ */
+
+#include "usb_hid.h"
+#include "usbd_hid.h"
+{core}
+// ---------------------------------------------------------------------------
+// HID report descriptor (boot keyboard)
+// ---------------------------------------------------------------------------
+
+const uint8_t HID_Keyboard_ReportDesc[] __ALIGN_END = {{
+    0x05, 0x01,        // Usage Page (Generic Desktop)
+    0x09, 0x06,        // Usage (Keyboard)
+    0xA1, 0x01,        // Collection (Application)
+    0x05, 0x07,        //   Usage Page (Key Codes)
+    0x19, 0xE0,        //   Usage Minimum (224)
+    0x29, 0xE7,        //   Usage Maximum (231)
+    0x15, 0x00,        //   Logical Minimum (0)
+    0x25, 0x01,        //   Logical Maximum (1)
+    0x75, 0x01,        //   Report Size (1)
+    0x95, 0x08,        //   Report Count (8)
+    0x81, 0x02,        //   Input (Data, Variable, Absolute) ; Modifier byte
+    0x95, 0x01,        //   Report Count (1)
+    0x75, 0x08,        //   Report Size (8)
+    0x81, 0x01,        //   Input (Constant) ; Reserved byte
+    0x95, 0x05,        //   Report Count (5)
+    0x75, 0x01,        //   Report Size (1)
+    0x05, 0x08,        //   Usage Page (LEDs)
+    0x19, 0x01,        //   Usage Minimum (1)
+    0x29, 0x05,        //   Usage Maximum (5)
+    0x91, 0x02,        //   Output (Data, Variable, Absolute) ; LED report
+    0x95, 0x01,        //   Report Count (1)
+    0x75, 0x03,        //   Report Size (3)
+    0x91, 0x01,        //   Output (Constant) ; LED report padding
+    0x95, 0x06,        //   Report Count (6)
+    0x75, 0x08,        //   Report Size (8)
+    0x15, 0x00,        //   Logical Minimum (0)
+    0x25, 0x65,        //   Logical Maximum (101)
+    0x05, 0x07,        //   Usage Page (Key Codes)
+    0x19, 0x00,        //   Usage Minimum (0)
+    0x29, 0x65,        //   Usage Maximum (101)
+    0x81, 0x00,        //   Input (Data, Array) ; Key array (6 keys)
+    0xC0               // End Collection
+}};
+
+const uint16_t HID_Keyboard_ReportDescSize = sizeof(HID_Keyboard_ReportDesc);
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+void usb_device_init(void) {{
+    usb_dplus_pullup(false);
+
+    if (USBD_Init(&hUsbDeviceFS, &FS_Desc, DEVICE_FS) != USBD_OK) {{
+        Error_Handler();
+    }}
+    if (USBD_RegisterClass(&hUsbDeviceFS, &USBD_HID) != USBD_OK) {{
+        Error_Handler();
+    }}
+    if (USBD_Start(&hUsbDeviceFS) != USBD_OK) {{
+        Error_Handler();
+    }}
+
+    usb_dplus_pullup(true);
+}}
+
+bool usb_is_configured(void) {{
+    return hUsbDeviceFS.dev_state == USBD_STATE_CONFIGURED;
+}}
+
+uint8_t usb_hid_send_report(const HID_Keyboard_Report_t *report) {{
+    if (!usb_is_configured()) {{
+        return USBD_FAIL;
+    }}
+    return USBD_HID_SendReport(&hUsbDeviceFS, (uint8_t *)report, sizeof(HID_Keyboard_Report_t));
+}}
+
+uint8_t usb_hid_press(uint8_t modifiers, uint8_t keycode) {{
+    HID_Keyboard_Report_t report = {{0}};
+    report.modifiers = modifiers;
+    report.keys[0] = keycode;
+    return usb_hid_send_report(&report);
+}}
+
+uint8_t usb_hid_release_all(void) {{
+    HID_Keyboard_Report_t report = {{0}};
+    return usb_hid_send_report(&report);
+}}
+"#);
+
+    let example = r#"/**
+ * USB HID Example
+ * Types "a" once a second
+ */
+
+#include "usb_hid.h"
+
+#define KEY_A 0x04
+
+int main(void) {
+    HAL_Init();
+    SystemClock_Config();  // USB needs a 48 MHz clock
+
+    usb_device_init();
+
+    while (1) {
+        if (usb_is_configured()) {
+            usb_hid_press(0, KEY_A);
+            HAL_Delay(20);
+            usb_hid_release_all();
+        }
+        HAL_Delay(1000);
+    }
+}
+"#.to_string();
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: Some(example),
+        peripheral_type: PeripheralType::USB,
+    }
+}
@@ -0,0 +1,799 @@
+// USB DFU Bootloader Generator
+// Generates a DfuSe-compatible (dfu-util) DFU class with flash download/upload on STM32
+
+use super::mcu::McuFamily;
+use super::templates::*;
+use super::usb::{supports_usb_device, usb_core_source, usb_header, usb_peripheral, UsbConfig, UsbDeviceClass};
+use serde::{Deserialize, Serialize};
+
+/// A run of equally sized flash sectors
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DfuMemoryRegion {
+    pub address: u32,
+    pub sectors: u32,
+    pub size_per_sector: u32,
+    pub writable: bool,
+}
+
+impl DfuMemoryRegion {
+    pub fn end(&self) -> u64 {
+        self.address as u64 + self.sectors as u64 * self.size_per_sector as u64
+    }
+}
+
+/// Start of internal flash on every supported STM32 family
+const FLASH_BASE: u32 = 0x0800_0000;
+
+/// Number, start address and size of the physical erase unit holding `address`
+///
+/// Sectors and pages are numbered from the flash base, not from the first
+/// region of a memory map. STM32F1 erases by address, so its pages are
+/// numbered in 1K units.
+pub fn flash_sector(mcu: McuFamily, address: u32) -> Option<(u32, u32, u32)> {
+    let offset = address.checked_sub(FLASH_BASE)?;
+    let uniform = |size: u32| Some((offset / size, FLASH_BASE + offset / size * size, size));
+    match mcu {
+        McuFamily::STM32F1 => uniform(1024),
+        McuFamily::STM32L4 | McuFamily::STM32G4 => uniform(2048),
+        // Bank 1 only: the erase code always selects FLASH_BANK_1
+        McuFamily::STM32H7 if offset < 1024 * 1024 => uniform(128 * 1024),
+        McuFamily::STM32F4 => {
+            // Each 1 MB bank: 4 x 16K, 1 x 64K, 7 x 128K, 12 sectors
+            let (bank, within) = (offset / (1024 * 1024), offset % (1024 * 1024));
+            let bank_base = FLASH_BASE + bank * 1024 * 1024;
+            let (number, start, size) = match within {
+                w if w < 0x1_0000 => (w / 0x4000, w / 0x4000 * 0x4000, 0x4000),
+                w if w < 0x2_0000 => (4, 0x1_0000, 0x1_0000),
+                w => (5 + (w - 0x2_0000) / 0x2_0000, (w / 0x2_0000) * 0x2_0000, 0x2_0000),
+            };
+            Some((bank * 12 + number, bank_base + start, size))
+        }
+        _ => None,
+    }
+}
+
+/// DFU bootloader configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DfuConfig {
+    pub memory_map: Vec<DfuMemoryRegion>,
+    pub upload_supported: bool,
+    /// Device stays in DFU mode after manifestation instead of needing a reset
+    pub manifestation_tolerant: bool,
+    pub transfer_size: u16,
+}
+
+impl Default for DfuConfig {
+    /// STM32F4 1 MB flash, first 16K sector reserved for the bootloader
+    fn default() -> Self {
+        let region = |address, sectors, size_per_sector, writable| DfuMemoryRegion {
+            address,
+            sectors,
+            size_per_sector,
+            writable,
+        };
+        Self {
+            memory_map: vec![
+                region(0x0800_0000, 1, 16 * 1024, false),
+                region(0x0800_4000, 3, 16 * 1024, true),
+                region(0x0801_0000, 1, 64 * 1024, true),
+                region(0x0802_0000, 7, 128 * 1024, true),
+            ],
+            upload_supported: true,
+            manifestation_tolerant: false,
+            transfer_size: 1024,
+        }
+    }
+}
+
+impl DfuConfig {
+    pub fn validate(&self, mcu: McuFamily) -> Result<(), String> {
+        if !supports_usb_device(mcu) {
+            return Err(format!("USB device generation is not available for {}", mcu.display_name()));
+        }
+        if self.memory_map.is_empty() {
+            return Err("Memory map must contain at least one region".to_string());
+        }
+        if !self.memory_map.iter().any(|r| r.writable) {
+            return Err("Memory map has no writable region to download into".to_string());
+        }
+        for region in &self.memory_map {
+            if region.sectors == 0 || region.size_per_sector == 0 {
+                return Err(format!("Region at 0x{:08X} is empty", region.address));
+            }
+            if region.end() > u32::MAX as u64 + 1 {
+                return Err(format!("Region at 0x{:08X} extends past the 32-bit address space", region.address));
+            }
+            if region.writable {
+                check_sector_layout(region, mcu)?;
+            }
+        }
+        let mut sorted: Vec<&DfuMemoryRegion> = self.memory_map.iter().collect();
+        sorted.sort_by_key(|r| r.address);
+        for pair in sorted.windows(2) {
+            if pair[0].end() > pair[1].address as u64 {
+                return Err(format!(
+                    "Regions at 0x{:08X} and 0x{:08X} overlap",
+                    pair[0].address, pair[1].address
+                ));
+            }
+        }
+        if self.transfer_size < 64 || self.transfer_size > 4096 || self.transfer_size % 64 != 0 {
+            return Err(format!(
+                "Transfer size must be a multiple of 64 between 64 and 4096, got {}",
+                self.transfer_size
+            ));
+        }
+        Ok(())
+    }
+
+    /// DfuSe memory layout string, e.g. `@Internal Flash/0x08000000/01*016Ka,03*016Kg`.
+    /// Contiguous regions share one address block.
+    pub fn memory_map_descriptor(&self) -> String {
+        let mut sorted: Vec<&DfuMemoryRegion> = self.memory_map.iter().collect();
+        sorted.sort_by_key(|r| r.address);
+
+        let mut layout = String::from("@Internal Flash");
+        let mut next_address = None;
+        for region in sorted {
+            if next_address == Some(region.address as u64) {
+                layout.push(',');
+            } else {
+                layout.push_str(&format!("/0x{:08X}/", region.address));
+            }
+            let (size, unit) = match region.size_per_sector {
+                s if s % (1024 * 1024) == 0 => (s / (1024 * 1024), 'M'),
+                s if s % 1024 == 0 => (s / 1024, 'K'),
+                s => (s, 'B'),
+            };
+            // a = readable, f = erasable + writable, g = readable + erasable + writable
+            let memory_type = match (region.writable, self.upload_supported) {
+                (true, true) => 'g',
+                (true, false) => 'f',
+                (false, _) => 'a',
+            };
+            layout.push_str(&format!("{:02}*{:03}{}{}", region.sectors, size, unit, memory_type));
+            next_address = Some(region.end());
+        }
+        layout
+    }
+}
+
+/// Every sector of a writable region must be whole physical sectors (pages may be grouped)
+fn check_sector_layout(region: &DfuMemoryRegion, mcu: McuFamily) -> Result<(), String> {
+    let paged = matches!(mcu, McuFamily::STM32F1 | McuFamily::STM32L4 | McuFamily::STM32G4);
+    for s in 0..region.sectors {
+        let address = region.address as u64 + s as u64 * region.size_per_sector as u64;
+        let physical = u32::try_from(address).ok().and_then(|a| flash_sector(mcu, a).map(|p| (a, p)));
+        let fits = match physical {
+            Some((a, (_, start, size))) if paged => start == a && region.size_per_sector % size == 0,
+            Some((a, (_, start, size))) => start == a && region.size_per_sector == size,
+            None => false,
+        };
+        if !fits {
+            return Err(format!(
+                "Region at 0x{:08X} does not match the {} flash sectors at 0x{:08X}",
+                region.address, mcu.display_name(), address
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Erase and program statements plus the program unit in bytes for a family
+fn flash_ops(mcu: McuFamily) -> (&'static str, &'static str, u32) {
+    match mcu {
+        McuFamily::STM32F1 => (
+            "    erase.TypeErase = FLASH_TYPEERASE_PAGES;\n    erase.PageAddress = sector_base;\n    erase.NbPages = region->sector_size / FLASH_PAGE_SIZE;\n",
+            "HAL_FLASH_Program(FLASH_TYPEPROGRAM_HALFWORD, addr + i, *(const uint16_t *)(data + i))",
+            2,
+        ),
+        McuFamily::STM32L4 | McuFamily::STM32G4 => (
+            "    erase.TypeErase = FLASH_TYPEERASE_PAGES;\n    erase.Banks = FLASH_BANK_1;\n    erase.Page = (sector_base - FLASH_BASE) / FLASH_PAGE_SIZE;\n    erase.NbPages = region->sector_size / FLASH_PAGE_SIZE;\n",
+            "HAL_FLASH_Program(FLASH_TYPEPROGRAM_DOUBLEWORD, addr + i, *(const uint64_t *)(data + i))",
+            8,
+        ),
+        McuFamily::STM32H7 => (
+            "    erase.TypeErase = FLASH_TYPEERASE_SECTORS;\n    erase.Banks = FLASH_BANK_1;\n    erase.Sector = sector;\n    erase.NbSectors = 1;\n    erase.VoltageRange = FLASH_VOLTAGE_RANGE_3;\n",
+            "HAL_FLASH_Program(FLASH_TYPEPROGRAM_FLASHWORD, addr + i, (uint32_t)(data + i))",
+            32,
+        ),
+        _ => (
+            "    erase.TypeErase = FLASH_TYPEERASE_SECTORS;\n    erase.Sector = sector;\n    erase.NbSectors = 1;\n    erase.VoltageRange = FLASH_VOLTAGE_RANGE_3;\n",
+            "HAL_FLASH_Program(FLASH_TYPEPROGRAM_WORD, addr + i, *(const uint32_t *)(data + i))",
+            4,
+        ),
+    }
+}
+
+/// Generate a USB DFU bootloader
+pub fn generate_usb_dfu_bootloader(config: &DfuConfig, mcu: McuFamily) -> DriverOutput {
+    let periph = usb_peripheral(mcu).unwrap_or_else(|| usb_peripheral(McuFamily::STM32F4).unwrap());
+    let usb_config = UsbConfig {
+        device_class: UsbDeviceClass::Dfu,
+        pid: 0xDF11,
+        product: "NeuroBench DFU Bootloader".to_string(),
+        ..UsbConfig::default()
+    };
+    let layout = config.memory_map_descriptor();
+    let core = usb_core_source(&usb_config, &periph, &[], Some(&layout));
+
+    let transfer_size = config.transfer_size;
+    let attributes = 0x01 | (config.upload_supported as u8) << 1 | (config.manifestation_tolerant as u8) << 2;
+    let upload = config.upload_supported as u8;
+    let tolerant = config.manifestation_tolerant as u8;
+    let app_address = config
+        .memory_map
+        .iter()
+        .filter(|r| r.writable)
+        .map(|r| r.address)
+        .min()
+        .unwrap_or_default();
+    let region_count = config.memory_map.len();
+    let mut sorted: Vec<&DfuMemoryRegion> = config.memory_map.iter().collect();
+    sorted.sort_by_key(|r| r.address);
+    let regions: String = sorted
+        .iter()
+        .map(|r| {
+            // Read-only regions are never erased, their sector number is unused
+            let first_sector = flash_sector(mcu, r.address).map_or(0, |(number, _, _)| number);
+            format!(
+                "    {{ 0x{:08X}, {}, 0x{:X}, {}, {} }},\n",
+                r.address, r.sectors, r.size_per_sector, first_sector, r.writable as u8
+            )
+        })
+        .collect();
+    let (erase_setup, program, program_unit) = flash_ops(mcu);
+
+    let header = usb_header(&usb_config, "DFU", &format!(r#"
+#define DFU_XFER_SIZE       {transfer_size}   // wTransferSize
+#define DFU_APP_ADDRESS     0x{app_address:08X}
+#define DFU_UPLOAD_ENABLED  {upload}
+#define DFU_MANIFEST_TOLERANT {tolerant}
+
+// DFU 1.1 states
+typedef enum {{
+    DFU_STATE_APP_IDLE = 0,
+    DFU_STATE_APP_DETACH,
+    DFU_STATE_IDLE,
+    DFU_STATE_DNLOAD_SYNC,
+    DFU_STATE_DNBUSY,
+    DFU_STATE_DNLOAD_IDLE,
+    DFU_STATE_MANIFEST_SYNC,
+    DFU_STATE_MANIFEST,
+    DFU_STATE_MANIFEST_WAIT_RESET,
+    DFU_STATE_UPLOAD_IDLE,
+    DFU_STATE_ERROR
+}} dfu_state_t;
+
+// Bootloader entry: stay in DFU if requested or no valid application
+bool dfu_app_is_valid(void);
+void dfu_jump_to_app(void);
+bool dfu_leave_requested(void);  // Set once a manifest completes
+"#));
+
+    let source = format!(r#"/**
+ * USB DFU Bootloader (DfuSe extensions, dfu-util compatible)
+ * Auto-generated by NeuroBench
+ *
+ * Flash with: dfu-util -a 0 -s 0x{app_address:08X}:leave -D firmware.bin
+ */
+
+#include "usb_dfu.h"
+#include <string.h>
+{core}
+// ---------------------------------------------------------------------------
+// Memory map
+// ---------------------------------------------------------------------------
+
+typedef struct {{
+    uint32_t address;
+    uint32_t sectors;
+    uint32_t sector_size;
+    uint32_t first_sector;  // Flash sector number at `address`, counted from the flash base
+    uint8_t writable;
+}} dfu_region_t;
+
+static const dfu_region_t dfu_regions[{region_count}] = {{
+{regions}}};
+
+#define DFU_REGION_COUNT (sizeof(dfu_regions) / sizeof(dfu_regions[0]))
+#define DFU_PROGRAM_UNIT {program_unit}
+
+static const dfu_region_t *dfu_find_region(uint32_t addr, uint32_t *sector, uint32_t *sector_base) {{
+    for (uint32_t r = 0; r < DFU_REGION_COUNT; r++) {{
+        const dfu_region_t *region = &dfu_regions[r];
+        uint32_t size = region->sectors * region->sector_size;
+        if (addr >= region->address && addr - region->address < size) {{
+            uint32_t offset = (addr - region->address) / region->sector_size;
+            if (sector) {{
+                *sector = region->first_sector + offset;
+            }}
+            if (sector_base) {{
+                *sector_base = region->address + offset * region->sector_size;
+            }}
+            return region;
+        }}
+    }}
+    return NULL;
+}}
+
+// ---------------------------------------------------------------------------
+// Flash access
+// ---------------------------------------------------------------------------
+
+static bool dfu_flash_erase(uint32_t addr) {{
+    uint32_t sector, sector_base;
+    const dfu_region_t *region = dfu_find_region(addr, &sector, &sector_base);
+    if (region == NULL || !region->writable) {{
+        return false;
+    }}
+    (void)sector;
+    (void)sector_base;
+
+    FLASH_EraseInitTypeDef erase = {{0}};
+{erase_setup}    uint32_t error = 0;
+    HAL_FLASH_Unlock();
+    HAL_StatusTypeDef status = HAL_FLASHEx_Erase(&erase, &error);
+    HAL_FLASH_Lock();
+    return status == HAL_OK;
+}}
+
+static bool dfu_flash_write(uint32_t addr, uint8_t *data, uint32_t len) {{
+    const dfu_region_t *region = dfu_find_region(addr, NULL, NULL);
+    const dfu_region_t *last = dfu_find_region(addr + len - 1, NULL, NULL);
+    if (region == NULL || !region->writable || last == NULL || !last->writable || addr % DFU_PROGRAM_UNIT != 0) {{
+        return false;
+    }}
+
+    // Pad the final block to whole program units with erased-flash bytes
+    while (len % DFU_PROGRAM_UNIT != 0) {{
+        data[len++] = 0xFF;
+    }}
+
+    bool ok = true;
+    HAL_FLASH_Unlock();
+    for (uint32_t i = 0; ok && i < len; i += DFU_PROGRAM_UNIT) {{
+        ok = {program} == HAL_OK;
+    }}
+    HAL_FLASH_Lock();
+    return ok && memcmp((const void *)addr, data, len) == 0;
+}}
+
+// ---------------------------------------------------------------------------
+// DFU class
+// ---------------------------------------------------------------------------
+
+#define DFU_REQ_DETACH     0x00
+#define DFU_REQ_DNLOAD     0x01
+#define DFU_REQ_UPLOAD     0x02
+#define DFU_REQ_GETSTATUS  0x03
+#define DFU_REQ_CLRSTATUS  0x04
+#define DFU_REQ_GETSTATE   0x05
+#define DFU_REQ_ABORT      0x06
+
+// DfuSe commands in DNLOAD block 0
+#define DFUSE_CMD_GET_COMMANDS   0x00
+#define DFUSE_CMD_SET_ADDRESS    0x21
+#define DFUSE_CMD_ERASE          0x41
+
+#define DFU_STATUS_OK            0x00
+#define DFU_STATUS_ERR_TARGET    0x01
+#define DFU_STATUS_ERR_WRITE     0x03
+#define DFU_STATUS_ERR_ERASE     0x04
+#define DFU_STATUS_ERR_ADDRESS   0x08
+#define DFU_STATUS_ERR_STALLEDPKT 0x0F
+
+#define DFU_POLL_TIMEOUT_MS      50
+#define DFU_ATTRIBUTES           0x{attributes:02X}  // CanDnload | CanUpload << 1 | ManifestationTolerant << 2
+
+#define DFU_CONFIG_DESC_SIZE     27
+
+static uint8_t dfu_config_desc[DFU_CONFIG_DESC_SIZE] __ALIGN_END = {{
+    // Configuration
+    0x09, USB_DESC_TYPE_CONFIGURATION, DFU_CONFIG_DESC_SIZE, 0x00,
+    0x01,                   // bNumInterfaces
+    0x01,                   // bConfigurationValue
+    0x00,                   // iConfiguration
+    0xC0,                   // Self powered
+    0x32,                   // 100 mA
+    // Interface 0, alt 0: internal flash
+    0x09, USB_DESC_TYPE_INTERFACE, 0x00, 0x00,
+    0x00,                   // bNumEndpoints
+    0xFE, 0x01, 0x02,       // Application specific, DFU, DFU mode protocol
+    USBD_IDX_INTERFACE_STR, // iInterface: DfuSe memory layout
+    // DFU functional descriptor
+    0x09, 0x21,
+    DFU_ATTRIBUTES,
+    0xFF, 0x00,             // wDetachTimeOut
+    LOBYTE(DFU_XFER_SIZE), HIBYTE(DFU_XFER_SIZE),
+    0x1A, 0x01              // bcdDFUVersion 1.1a (DfuSe)
+}};
+
+static struct {{
+    dfu_state_t state;
+    uint8_t status;
+    uint8_t buffer[DFU_XFER_SIZE] __attribute__((aligned(32)));
+    uint8_t status_reply[6];
+    uint32_t address;       // DfuSe address pointer
+    uint16_t block;
+    uint16_t length;
+    bool pending;           // Download block received, not yet executed
+    bool manifest_pending;
+    bool leave;
+}} dfu;
+
+static void dfu_reset_state(void) {{
+    dfu.state = DFU_STATE_IDLE;
+    dfu.status = DFU_STATUS_OK;
+    dfu.pending = false;
+    dfu.manifest_pending = false;
+    dfu.address = DFU_APP_ADDRESS;
+}}
+
+static void dfu_error(uint8_t status) {{
+    dfu.state = DFU_STATE_ERROR;
+    dfu.status = status;
+}}
+
+// Execute a downloaded block (after the GETSTATUS that reported dfuDNBUSY)
+static void dfu_execute_download(void) {{
+    if (dfu.block == 0) {{
+        uint32_t arg = 0;
+        if (dfu.length >= 5) {{
+            memcpy(&arg, &dfu.buffer[1], 4);
+        }}
+        switch (dfu.buffer[0]) {{
+            case DFUSE_CMD_SET_ADDRESS:
+                if (dfu.length != 5 || dfu_find_region(arg, NULL, NULL) == NULL) {{
+                    dfu_error(DFU_STATUS_ERR_ADDRESS);
+                    return;
+                }}
+                dfu.address = arg;
+                break;
+            case DFUSE_CMD_ERASE:
+                if (dfu.length == 1) {{
+                    // Mass erase: every writable sector
+                    for (uint32_t r = 0; r < DFU_REGION_COUNT; r++) {{
+                        for (uint32_t s = 0; dfu_regions[r].writable && s < dfu_regions[r].sectors; s++) {{
+                            if (!dfu_flash_erase(dfu_regions[r].address + s * dfu_regions[r].sector_size)) {{
+                                dfu_error(DFU_STATUS_ERR_ERASE);
+                                return;
+                            }}
+                        }}
+                    }}
+                }} else if (dfu.length != 5 || !dfu_flash_erase(arg)) {{
+                    dfu_error(DFU_STATUS_ERR_ERASE);
+                    return;
+                }}
+                break;
+            default:
+                dfu_error(DFU_STATUS_ERR_STALLEDPKT);
+                return;
+        }}
+    }} else if (dfu.block >= 2) {{
+        uint32_t addr = dfu.address + (uint32_t)(dfu.block - 2) * DFU_XFER_SIZE;
+        if (!dfu_flash_write(addr, dfu.buffer, dfu.length)) {{
+            dfu_error(DFU_STATUS_ERR_WRITE);
+            return;
+        }}
+    }}
+    dfu.pending = false;
+    dfu.state = DFU_STATE_DNLOAD_SYNC;
+}}
+
+static void dfu_get_status(USBD_HandleTypeDef *pdev) {{
+    uint32_t poll_ms = 0;
+    switch (dfu.state) {{
+        case DFU_STATE_DNLOAD_SYNC:
+            if (dfu.pending) {{
+                dfu.state = DFU_STATE_DNBUSY;
+                poll_ms = DFU_POLL_TIMEOUT_MS;
+            }} else {{
+                dfu.state = DFU_STATE_DNLOAD_IDLE;
+            }}
+            break;
+        case DFU_STATE_MANIFEST_SYNC:
+            if (dfu.manifest_pending) {{
+                dfu.state = DFU_STATE_MANIFEST;
+                poll_ms = DFU_POLL_TIMEOUT_MS;
+            }} else {{
+                dfu.state = DFU_STATE_IDLE;  // Manifestation tolerant, manifest done
+            }}
+            break;
+        default:
+            break;
+    }}
+
+    dfu.status_reply[0] = dfu.status;
+    dfu.status_reply[1] = (uint8_t)poll_ms;
+    dfu.status_reply[2] = (uint8_t)(poll_ms >> 8);
+    dfu.status_reply[3] = (uint8_t)(poll_ms >> 16);
+    dfu.status_reply[4] = (uint8_t)dfu.state;
+    dfu.status_reply[5] = 0;  // iString
+    USBD_CtlSendData(pdev, dfu.status_reply, 6);
+}}
+
+static void dfu_download(USBD_HandleTypeDef *pdev, USBD_SetupReqTypedef *req) {{
+    if (dfu.state != DFU_STATE_IDLE && dfu.state != DFU_STATE_DNLOAD_IDLE) {{
+        dfu_error(DFU_STATUS_ERR_STALLEDPKT);
+        USBD_CtlError(pdev, req);
+        return;
+    }}
+    if (req->wLength > 0) {{
+        if (req->wLength > DFU_XFER_SIZE) {{
+            USBD_CtlError(pdev, req);
+            return;
+        }}
+        dfu.block = req->wValue;
+        dfu.length = req->wLength;
+        USBD_CtlPrepareRx(pdev, dfu.buffer, req->wLength);  // -> dfu_ep0_rx_ready
+    }} else if (dfu.state == DFU_STATE_DNLOAD_IDLE) {{
+        // Zero-length download ends the transfer
+        dfu.manifest_pending = true;
+        dfu.state = DFU_STATE_MANIFEST_SYNC;
+    }} else {{
+        dfu_error(DFU_STATUS_ERR_STALLEDPKT);
+        USBD_CtlError(pdev, req);
+    }}
+}}
+
+static void dfu_upload(USBD_HandleTypeDef *pdev, USBD_SetupReqTypedef *req) {{
+#if DFU_UPLOAD_ENABLED
+    if ((dfu.state != DFU_STATE_IDLE && dfu.state != DFU_STATE_UPLOAD_IDLE) || req->wLength > DFU_XFER_SIZE) {{
+        dfu_error(DFU_STATUS_ERR_STALLEDPKT);
+        USBD_CtlError(pdev, req);
+        return;
+    }}
+    dfu.state = DFU_STATE_UPLOAD_IDLE;
+    if (req->wValue == 0) {{
+        // DfuSe: list supported commands
+        dfu.buffer[0] = DFUSE_CMD_GET_COMMANDS;
+        dfu.buffer[1] = DFUSE_CMD_SET_ADDRESS;
+        dfu.buffer[2] = DFUSE_CMD_ERASE;
+        USBD_CtlSendData(pdev, dfu.buffer, req->wLength < 3 ? req->wLength : 3);
+        return;
+    }}
+
+    uint32_t addr = dfu.address + (uint32_t)(req->wValue - 2) * DFU_XFER_SIZE;
+    const dfu_region_t *region = dfu_find_region(addr, NULL, NULL);
+    if (req->wValue < 2 || region == NULL) {{
+        // Short (empty) packet ends the upload
+        dfu.state = DFU_STATE_IDLE;
+        USBD_CtlSendData(pdev, dfu.buffer, 0);
+        return;
+    }}
+    uint32_t len = req->wLength;
+    uint32_t end = region->address + region->sectors * region->sector_size;
+    if (end - addr < len) {{
+        len = end - addr;
+    }}
+    memcpy(dfu.buffer, (const void *)addr, len);
+    USBD_CtlSendData(pdev, dfu.buffer, (uint16_t)len);
+#else
+    dfu_error(DFU_STATUS_ERR_STALLEDPKT);
+    USBD_CtlError(pdev, req);
+#endif
+}}
+
+static uint8_t dfu_init(USBD_HandleTypeDef *pdev, uint8_t cfgidx) {{
+    (void)pdev;
+    (void)cfgidx;
+    dfu_reset_state();
+    return USBD_OK;
+}}
+
+static uint8_t dfu_deinit(USBD_HandleTypeDef *pdev, uint8_t cfgidx) {{
+    (void)pdev;
+    (void)cfgidx;
+    return USBD_OK;
+}}
+
+static uint8_t dfu_setup(USBD_HandleTypeDef *pdev, USBD_SetupReqTypedef *req) {{
+    static uint8_t alt_setting = 0;
+
+    switch (req->bmRequest & USB_REQ_TYPE_MASK) {{
+        case USB_REQ_TYPE_CLASS:
+            switch (req->bRequest) {{
+                case DFU_REQ_DNLOAD:
+                    dfu_download(pdev, req);
+                    break;
+                case DFU_REQ_UPLOAD:
+                    dfu_upload(pdev, req);
+                    break;
+                case DFU_REQ_GETSTATUS:
+                    dfu_get_status(pdev);
+                    break;
+                case DFU_REQ_CLRSTATUS:
+                    if (dfu.state == DFU_STATE_ERROR) {{
+                        dfu.state = DFU_STATE_IDLE;
+                        dfu.status = DFU_STATUS_OK;
+                    }}
+                    break;
+                case DFU_REQ_GETSTATE:
+                    USBD_CtlSendData(pdev, (uint8_t *)&dfu.state, 1);
+                    break;
+                case DFU_REQ_ABORT:
+                    if (dfu.state == DFU_STATE_DNLOAD_IDLE || dfu.state == DFU_STATE_UPLOAD_IDLE || dfu.state == DFU_STATE_IDLE) {{
+                        dfu_reset_state();
+                    }}
+                    break;
+                case DFU_REQ_DETACH:
+                    // Already in DFU mode: nothing to detach from
+                    break;
+                default:
+                    USBD_CtlError(pdev, req);
+                    return USBD_FAIL;
+            }}
+            break;
+
+        case USB_REQ_TYPE_STANDARD:
+            switch (req->bRequest) {{
+                case USB_REQ_GET_DESCRIPTOR:
+                    if ((req->wValue >> 8) == 0x21) {{
+                        USBD_CtlSendData(pdev, &dfu_config_desc[18], req->wLength < 9 ? req->wLength : 9);
+                    }}
+                    break;
+                case USB_REQ_GET_INTERFACE:
+                    USBD_CtlSendData(pdev, &alt_setting, 1);
+                    break;
+                case USB_REQ_SET_INTERFACE:
+                    alt_setting = (uint8_t)req->wValue;
+                    break;
+                default:
+                    break;
+            }}
+            break;
+
+        default:
+            USBD_CtlError(pdev, req);
+            return USBD_FAIL;
+    }}
+    return USBD_OK;
+}}
+
+// Download data stage complete: execute on the next GETSTATUS
+static uint8_t dfu_ep0_rx_ready(USBD_HandleTypeDef *pdev) {{
+    (void)pdev;
+    if (dfu.length > 0) {{
+        dfu.pending = true;
+        dfu.state = DFU_STATE_DNLOAD_SYNC;
+    }}
+    return USBD_OK;
+}}
+
+// GETSTATUS reply sent: run the operation the host is now polling for
+static uint8_t dfu_ep0_tx_sent(USBD_HandleTypeDef *pdev) {{
+    (void)pdev;
+    if (dfu.state == DFU_STATE_DNBUSY) {{
+        dfu_execute_download();
+    }} else if (dfu.state == DFU_STATE_MANIFEST) {{
+        dfu.manifest_pending = false;
+#if DFU_MANIFEST_TOLERANT
+        dfu.state = DFU_STATE_MANIFEST_SYNC;
+#else
+        dfu.state = DFU_STATE_MANIFEST_WAIT_RESET;
+        dfu.leave = true;
+#endif
+    }}
+    return USBD_OK;
+}}
+
+static uint8_t *dfu_get_config_desc(uint16_t *length) {{
+    *length = sizeof(dfu_config_desc);
+    return dfu_config_desc;
+}}
+
+USBD_ClassTypeDef USBD_DFU_NB = {{
+    dfu_init,
+    dfu_deinit,
+    dfu_setup,
+    dfu_ep0_tx_sent,
+    dfu_ep0_rx_ready,
+    NULL,                   // DataIn
+    NULL,                   // DataOut
+    NULL,                   // SOF
+    NULL,
+    NULL,
+    dfu_get_config_desc,    // HS
+    dfu_get_config_desc,    // FS
+    dfu_get_config_desc,    // Other speed
+    NULL,                   // Device qualifier
+}};
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+void usb_device_init(void) {{
+    usb_dplus_pullup(false);
+
+    if (USBD_Init(&hUsbDeviceFS, &FS_Desc, DEVICE_FS) != USBD_OK) {{
+        Error_Handler();
+    }}
+    if (USBD_RegisterClass(&hUsbDeviceFS, &USBD_DFU_NB) != USBD_OK) {{
+        Error_Handler();
+    }}
+    if (USBD_Start(&hUsbDeviceFS) != USBD_OK) {{
+        Error_Handler();
+    }}
+
+    usb_dplus_pullup(true);
+}}
+
+bool usb_is_configured(void) {{
+    return hUsbDeviceFS.dev_state == USBD_STATE_CONFIGURED;
+}}
+
+bool dfu_leave_requested(void) {{
+    return dfu.leave;
+}}
+
+bool dfu_app_is_valid(void) {{
+    uint32_t app_sp = *(const uint32_t *)DFU_APP_ADDRESS;
+    return (app_sp & 0x2FFE0000) == 0x20000000;
+}}
+
+typedef void (*app_entry_t)(void);
+
+void dfu_jump_to_app(void) {{
+    if (!dfu_app_is_valid()) {{
+        return;
+    }}
+    uint32_t app_sp = *(const uint32_t *)DFU_APP_ADDRESS;
+    uint32_t app_reset = *(const uint32_t *)(DFU_APP_ADDRESS + 4);
+
+    USBD_Stop(&hUsbDeviceFS);
+    USBD_DeInit(&hUsbDeviceFS);
+    __disable_irq();
+    SysTick->CTRL = 0;
+    HAL_RCC_DeInit();
+    HAL_DeInit();
+
+    SCB->VTOR = DFU_APP_ADDRESS;
+    __set_MSP(app_sp);
+    __enable_irq();
+    ((app_entry_t)app_reset)();
+
+    while (1);
+}}
+"#);
+
+    let example = r#"/**
+ * USB DFU Bootloader Example
+ * Enters DFU when BOOT_BUTTON is held or no application is present,
+ * otherwise starts the application
+ */
+
+#include "usb_dfu.h"
+#include "main.h"
+
+int main(void) {
+    HAL_Init();
+    SystemClock_Config();  // USB needs a 48 MHz clock
+    MX_GPIO_Init();
+
+    bool button = HAL_GPIO_ReadPin(BOOT_BUTTON_GPIO_Port, BOOT_BUTTON_Pin) == GPIO_PIN_RESET;
+    if (!button && dfu_app_is_valid()) {
+        dfu_jump_to_app();
+    }
+
+    usb_device_init();
+
+    while (1) {
+        if (dfu_leave_requested()) {
+            HAL_Delay(50);  // Let the final GETSTATUS complete
+            usb_dplus_pullup(false);
+            dfu_jump_to_app();
+            NVIC_SystemReset();  // No valid image: restart into DFU
+        }
+    }
+}
+"#.to_string();
+
+    DriverOutput {
+        header_file: Some(header),
+        source_file: source,
+        example_file: Some(example),
+        peripheral_type: PeripheralType::USB,
+    }
+}
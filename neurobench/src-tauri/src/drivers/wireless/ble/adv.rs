@@ -0,0 +1,245 @@
+// BLE Advertising Data Builder
+// Encodes AD structures into a legacy advertising payload
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use thiserror::Error;
+
+/// Legacy (BLE 4.x) advertising payload limit
+pub const MAX_ADV_DATA_LEN: usize = 31;
+
+const AD_FLAGS: u8 = 0x01;
+const AD_UUID16_COMPLETE: u8 = 0x03;
+const AD_NAME_SHORTENED: u8 = 0x08;
+const AD_NAME_COMPLETE: u8 = 0x09;
+const AD_MANUFACTURER_DATA: u8 = 0xFF;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum AdvDataError {
+    #[error("Advertising data is {len} bytes, legacy advertising allows {MAX_ADV_DATA_LEN}")]
+    TooLong { len: usize },
+
+    #[error("{field} is too long for a single AD structure")]
+    FieldTooLong { field: &'static str },
+
+    #[error("Invalid 16-bit service UUID: {0}")]
+    InvalidUuid(String),
+}
+
+/// Flags AD type bits (Core Spec Supplement, Part A, 1.3)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BleAdvFlags {
+    #[serde(default)]
+    pub le_limited_discoverable: bool,
+    #[serde(default)]
+    pub le_general_discoverable: bool,
+    #[serde(default)]
+    pub br_edr_not_supported: bool,
+    #[serde(default)]
+    pub le_br_edr_controller: bool,
+    #[serde(default)]
+    pub le_br_edr_host: bool,
+}
+
+impl BleAdvFlags {
+    pub fn bits(&self) -> u8 {
+        (self.le_limited_discoverable as u8)
+            | (self.le_general_discoverable as u8) << 1
+            | (self.br_edr_not_supported as u8) << 2
+            | (self.le_br_edr_controller as u8) << 3
+            | (self.le_br_edr_host as u8) << 4
+    }
+}
+
+impl Default for BleAdvFlags {
+    /// General discoverable, LE only (0x06)
+    fn default() -> Self {
+        Self {
+            le_limited_discoverable: false,
+            le_general_discoverable: true,
+            br_edr_not_supported: true,
+            le_br_edr_controller: false,
+            le_br_edr_host: false,
+        }
+    }
+}
+
+/// Manufacturer specific data, `company_id` as assigned by the Bluetooth SIG
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManufacturerData {
+    pub company_id: u16,
+    pub data: Vec<u8>,
+}
+
+/// Builds the advertising payload one AD structure (length, type, data) at a time
+///
+/// Structures are written in a fixed order: flags, service UUIDs, local
+/// name, manufacturer data. Setting a field again replaces it.
+#[derive(Debug, Clone, Default)]
+pub struct AdvertisingDataBuilder {
+    flags: Option<BleAdvFlags>,
+    local_name: Option<(String, bool)>,
+    manufacturer_data: Option<ManufacturerData>,
+    service_uuids_16: Vec<u16>,
+}
+
+impl AdvertisingDataBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_flags(&mut self, flags: BleAdvFlags) -> &mut Self {
+        self.flags = Some(flags);
+        self
+    }
+
+    /// Complete local name, or the shortened name type when `shortened`
+    pub fn set_local_name(&mut self, name: &str, shortened: bool) -> &mut Self {
+        self.local_name = Some((name.to_string(), shortened));
+        self
+    }
+
+    pub fn set_manufacturer_data(&mut self, company_id: u16, data: &[u8]) -> &mut Self {
+        self.manufacturer_data = Some(ManufacturerData { company_id, data: data.to_vec() });
+        self
+    }
+
+    /// Complete list of 16-bit service UUIDs
+    pub fn set_service_uuids_16(&mut self, uuids: &[u16]) -> &mut Self {
+        self.service_uuids_16 = uuids.to_vec();
+        self
+    }
+
+    /// AD structures as (description, bytes including the length and type)
+    pub fn structures(&self) -> Result<Vec<(&'static str, Vec<u8>)>, AdvDataError> {
+        let mut structures = Vec::new();
+        if let Some(flags) = &self.flags {
+            structures.push(("Flags", ad_structure(AD_FLAGS, &[flags.bits()], "Flags")?));
+        }
+        if !self.service_uuids_16.is_empty() {
+            let data: Vec<u8> = self.service_uuids_16.iter().flat_map(|uuid| uuid.to_le_bytes()).collect();
+            structures.push(("Complete list of 16-bit service UUIDs", ad_structure(AD_UUID16_COMPLETE, &data, "Service UUID list")?));
+        }
+        if let Some((name, shortened)) = &self.local_name {
+            let (ad_type, description) = if *shortened {
+                (AD_NAME_SHORTENED, "Shortened local name")
+            } else {
+                (AD_NAME_COMPLETE, "Complete local name")
+            };
+            structures.push((description, ad_structure(ad_type, name.as_bytes(), "Local name")?));
+        }
+        if let Some(manufacturer) = &self.manufacturer_data {
+            let mut data = manufacturer.company_id.to_le_bytes().to_vec();
+            data.extend_from_slice(&manufacturer.data);
+            structures.push(("Manufacturer specific data", ad_structure(AD_MANUFACTURER_DATA, &data, "Manufacturer data")?));
+        }
+        Ok(structures)
+    }
+
+    /// The payload without padding
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AdvDataError> {
+        let bytes: Vec<u8> = self.structures()?.into_iter().flat_map(|(_, bytes)| bytes).collect();
+        if bytes.len() > MAX_ADV_DATA_LEN {
+            return Err(AdvDataError::TooLong { len: bytes.len() });
+        }
+        Ok(bytes)
+    }
+
+    /// The payload zero-padded to the full 31 bytes
+    pub fn build(&self) -> Result<[u8; MAX_ADV_DATA_LEN], AdvDataError> {
+        let bytes = self.to_bytes()?;
+        let mut payload = [0u8; MAX_ADV_DATA_LEN];
+        payload[..bytes.len()].copy_from_slice(&bytes);
+        Ok(payload)
+    }
+
+    /// C array definition with one AD structure per line
+    pub fn to_c_array(&self, var_name: &str) -> Result<String, AdvDataError> {
+        let structures = self.structures()?;
+        let len = self.to_bytes()?.len();
+
+        let mut code = format!(
+            "/* Advertising data: {} of {} bytes */\nstatic const uint8_t {}[] = {{\n",
+            len, MAX_ADV_DATA_LEN, var_name
+        );
+        for (description, bytes) in structures {
+            let hex: Vec<String> = bytes.iter().map(|b| format!("0x{:02X}", b)).collect();
+            let _ = writeln!(code, "    /* {} */", description);
+            let _ = writeln!(code, "    {},", hex.join(", "));
+        }
+        code.push_str("};\n");
+        Ok(code)
+    }
+}
+
+fn ad_structure(ad_type: u8, data: &[u8], field: &'static str) -> Result<Vec<u8>, AdvDataError> {
+    // The length byte counts the type byte too
+    let len = u8::try_from(data.len() + 1).map_err(|_| AdvDataError::FieldTooLong { field })?;
+    let mut bytes = Vec::with_capacity(data.len() + 2);
+    bytes.push(len);
+    bytes.push(ad_type);
+    bytes.extend_from_slice(data);
+    Ok(bytes)
+}
+
+/// Parse a 16-bit UUID written as `180D`, `0x180D` or `0000180d-0000-1000-8000-00805f9b34fb`
+pub fn parse_uuid16(text: &str) -> Result<u16, AdvDataError> {
+    let trimmed = text.trim();
+    let short = trimmed.strip_prefix("0x").or_else(|| trimmed.strip_prefix("0X")).unwrap_or(trimmed);
+    let short = match short.to_lowercase().strip_suffix("-0000-1000-8000-00805f9b34fb") {
+        Some(base) if base.len() == 8 && base.starts_with("0000") => base[4..].to_string(),
+        _ => short.to_string(),
+    };
+    if short.len() > 4 {
+        return Err(AdvDataError::InvalidUuid(text.to_string()));
+    }
+    u16::from_str_radix(&short, 16).map_err(|_| AdvDataError::InvalidUuid(text.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_advertising_data() {
+        let mut builder = AdvertisingDataBuilder::new();
+        builder
+            .set_flags(BleAdvFlags::default())
+            .set_service_uuids_16(&[0x180D, 0x180F])
+            .set_local_name("NB", false)
+            .set_manufacturer_data(0x0059, &[0x01, 0x02]);
+
+        let bytes = builder.to_bytes().unwrap();
+        assert_eq!(bytes, vec![
+            0x02, 0x01, 0x06,
+            0x05, 0x03, 0x0D, 0x18, 0x0F, 0x18,
+            0x03, 0x09, b'N', b'B',
+            0x05, 0xFF, 0x59, 0x00, 0x01, 0x02,
+        ]);
+        let payload = builder.build().unwrap();
+        assert_eq!(&payload[..bytes.len()], &bytes[..]);
+        assert!(payload[bytes.len()..].iter().all(|&b| b == 0));
+
+        let c_array = builder.to_c_array("adv_data").unwrap();
+        assert!(c_array.contains("19 of 31 bytes"));
+        assert!(c_array.contains("    0x02, 0x01, 0x06,\n"));
+    }
+
+    #[test]
+    fn test_advertising_data_too_long() {
+        let mut builder = AdvertisingDataBuilder::new();
+        builder.set_flags(BleAdvFlags::default()).set_local_name("NeuroBench Sensor Node 01", false);
+        assert_eq!(builder.to_bytes().unwrap().len(), 30);
+
+        builder.set_manufacturer_data(0xFFFF, &[]);
+        assert_eq!(builder.build().unwrap_err(), AdvDataError::TooLong { len: 34 });
+    }
+
+    #[test]
+    fn test_parse_uuid16() {
+        assert_eq!(parse_uuid16("180D"), Ok(0x180D));
+        assert_eq!(parse_uuid16("0x2a37"), Ok(0x2A37));
+        assert_eq!(parse_uuid16("0000180f-0000-1000-8000-00805f9b34fb"), Ok(0x180F));
+        assert!(parse_uuid16("6e400001-b5a3-f393-e0a9-e50e24dcca9e").is_err());
+    }
+}
@@ -1,6 +1,9 @@
 // BLE GATT Code Generator
 // Generates BLE peripheral/central code for nRF52 and ESP32
 
+pub mod adv;
+pub mod softdevice;
+
 use super::*;
 
 /// Generate BLE GATT service code for nRF52 (Nordic SDK)
@@ -0,0 +1,100 @@
+// SoftDevice Initialization Generator
+// nRF5 SDK bring-up of the S132/S140 BLE stack
+
+/// Split "S140 7.2.0" / "s132_7.2.0" / "7.2.0" into the SoftDevice name and major version
+fn parse_version(version: &str) -> (Option<String>, Option<u32>) {
+    let version = version.trim();
+    let (name, number) = match version.find([' ', '_', '-']) {
+        Some(split) if version.starts_with(['S', 's']) => (Some(version[..split].to_uppercase()), &version[split + 1..]),
+        _ => (None, version.trim_start_matches(['v', 'V'])),
+    };
+    let major = number.split('.').next().and_then(|m| m.trim().parse().ok());
+    (name, major)
+}
+
+/// Generate `softdevice_init()` for the given SoftDevice version
+///
+/// The BLE API version of the SoftDevice headers follows its major version,
+/// so the generated file refuses to build against headers for another one.
+pub fn generate_softdevice_init(version: &str) -> String {
+    let (name, major) = parse_version(version);
+    let name = name.unwrap_or_else(|| "SoftDevice".to_string());
+    let version_check = match major {
+        Some(major) => format!(
+            r#"
+#if defined(NRF_SD_BLE_API_VERSION) && (NRF_SD_BLE_API_VERSION != {major})
+#error "SoftDevice headers do not match {name} v{major}.x"
+#endif
+"#
+        ),
+        None => String::new(),
+    };
+    let version = version.trim();
+
+    format!(
+        r#"/**
+ * SoftDevice Initialization
+ * {name} ({version}), nRF5 SDK
+ *
+ * Link the application after the SoftDevice; nrf_sdh_ble_enable() reports
+ * the RAM start it needs if APP_RAM_START is too low.
+ */
+
+#include "nrf_sdh.h"
+#include "nrf_sdh_ble.h"
+#include "app_error.h"
+{version_check}
+#define APP_BLE_CONN_CFG_TAG    1
+#define APP_BLE_OBSERVER_PRIO   3
+
+static void ble_evt_handler(ble_evt_t const *p_ble_evt, void *p_context) {{
+    (void)p_context;
+
+    switch (p_ble_evt->header.evt_id) {{
+        default:
+            break;
+    }}
+}}
+
+NRF_SDH_BLE_OBSERVER(m_ble_observer, APP_BLE_OBSERVER_PRIO, ble_evt_handler, NULL);
+
+void softdevice_init(void) {{
+    ret_code_t err_code;
+
+    err_code = nrf_sdh_enable_request();
+    APP_ERROR_CHECK(err_code);
+
+    /* Default BLE configuration; ram_start receives the application RAM start */
+    uint32_t ram_start = 0;
+    err_code = nrf_sdh_ble_default_cfg_set(APP_BLE_CONN_CFG_TAG, &ram_start);
+    APP_ERROR_CHECK(err_code);
+
+    err_code = nrf_sdh_ble_enable(&ram_start);
+    APP_ERROR_CHECK(err_code);
+}}
+"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("S140 7.2.0"), (Some("S140".to_string()), Some(7)));
+        assert_eq!(parse_version("s132_6.1.1"), (Some("S132".to_string()), Some(6)));
+        assert_eq!(parse_version("v7.3.0"), (None, Some(7)));
+        assert_eq!(parse_version("latest"), (None, None));
+    }
+
+    #[test]
+    fn test_generate_softdevice_init() {
+        let code = generate_softdevice_init("S140 7.2.0");
+        assert!(code.contains("nrf_sdh_enable_request();"));
+        assert!(code.contains("nrf_sdh_ble_default_cfg_set(APP_BLE_CONN_CFG_TAG, &ram_start);"));
+        assert!(code.contains("NRF_SD_BLE_API_VERSION != 7"));
+
+        assert!(!generate_softdevice_init("latest").contains("#error"));
+    }
+}
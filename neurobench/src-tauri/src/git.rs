@@ -2,11 +2,32 @@
 // Provides version control for generated projects
 
 use git2::{
-    Commit, DiffOptions, Error, IndexAddOption, ObjectType, Oid, Repository, 
+    Commit, DiffOptions, Error, Index, IndexAddOption, ObjectType, Oid, Repository, 
     Signature, StatusOptions, StatusShow, Time,
 };
+use crate::build::BuildSystem;
+use crate::drivers::McuFamily;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::cmp::Ordering;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Errors from tag and release operations
+#[derive(Debug, Error)]
+pub enum GitError {
+    #[error("Git error: {0}")]
+    Git(#[from] Error),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Tag already exists: {0}")]
+    TagExists(String),
+
+    #[error("Not a semantic version: {0}")]
+    InvalidVersion(String),
+}
 
 /// File status in the repository
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,6 +76,139 @@ pub struct RepoStatus {
     pub untracked_count: usize,
 }
 
+/// Tag information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagInfo {
+    pub name: String,
+    /// Commit the tag points at
+    pub target: String,
+    pub short_id: String,
+    pub is_annotated: bool,
+    pub message: Option<String>,
+    pub tagger: Option<String>,
+    /// Tag time for annotated tags, commit time otherwise
+    pub time: i64,
+    /// Parsed semantic version, if the name is one
+    pub version: Option<String>,
+}
+
+/// Version component to increment
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BumpType {
+    Major,
+    Minor,
+    Patch,
+}
+
+/// Semantic version with optional pre-release suffix (`1.4.0-rc.1`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemVer {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Option<String>,
+}
+
+impl SemVer {
+    /// Parse `1.2.3`, `v1.2.3` or `1.2.3-rc.1`; build metadata (`+abc`) is ignored
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.strip_prefix(['v', 'V']).unwrap_or(text);
+        let text = text.split_once('+').map_or(text, |(version, _)| version);
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) if !pre.is_empty() => (core, Some(pre.to_string())),
+            Some(_) => return None,
+            None => (text, None),
+        };
+        let mut parts = core.split('.').map(|n| n.parse::<u64>().ok());
+        let (major, minor, patch) = (parts.next()??, parts.next()??, parts.next()??);
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self { major, minor, patch, pre })
+    }
+
+    /// Next version; a pre-release is finished instead when it already
+    /// is that bump (`1.3.0-rc.1` minor -> `1.3.0`, patch -> `1.3.0`)
+    pub fn bump(&self, bump_type: BumpType) -> Self {
+        let pre = self.pre.is_some();
+        let (major, minor, patch) = match bump_type {
+            BumpType::Major if pre && self.minor == 0 && self.patch == 0 => (self.major, 0, 0),
+            BumpType::Major => (self.major + 1, 0, 0),
+            BumpType::Minor if pre && self.patch == 0 => (self.major, self.minor, 0),
+            BumpType::Minor => (self.major, self.minor + 1, 0),
+            BumpType::Patch if pre => (self.major, self.minor, self.patch),
+            BumpType::Patch => (self.major, self.minor, self.patch + 1),
+        };
+        Self { major, minor, patch, pre: None }
+    }
+}
+
+impl fmt::Display for SemVer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(pre) = &self.pre {
+            write!(f, "-{}", pre)?;
+        }
+        Ok(())
+    }
+}
+
+impl Ord for SemVer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch).cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                // A pre-release sorts before its release
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => compare_pre_release(a, b),
+            })
+    }
+}
+
+impl PartialOrd for SemVer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Dot-separated identifiers; numeric ones compare as numbers and sort first
+fn compare_pre_release(a: &str, b: &str) -> Ordering {
+    let mut a_ids = a.split('.');
+    let mut b_ids = b.split('.');
+    loop {
+        let ordering = match (a_ids.next(), b_ids.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => match (x.parse::<u64>(), y.parse::<u64>()) {
+                (Ok(x), Ok(y)) => x.cmp(&y),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => x.cmp(y),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Where firmware projects usually keep `version.h`
+const VERSION_HEADER_PATHS: &[&str] = &["version.h", "include/version.h", "inc/version.h", "Inc/version.h", "Core/Inc/version.h"];
+
+/// IDE whose project files end up in the repository
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IdeType {
+    VsCode,
+    Keil,
+    Iar,
+    CubeIde,
+    Clion,
+}
+
 /// Initialize a new Git repository
 pub fn init_repo(path: &str) -> Result<String, String> {
     let repo = Repository::init(path)
@@ -319,6 +473,209 @@ pub fn unstage_file(path: &str, file: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Create a tag on HEAD
+pub fn create_tag(repo_path: &str, tag_name: &str, message: Option<&str>, is_annotated: bool) -> Result<(), GitError> {
+    let repo = Repository::open(repo_path)?;
+    if repo.refname_to_id(&format!("refs/tags/{}", tag_name)).is_ok() {
+        return Err(GitError::TagExists(tag_name.to_string()));
+    }
+    let head = repo.head()?.peel(ObjectType::Commit)?;
+
+    if is_annotated {
+        let tagger = repo.signature().or_else(|_| Signature::now("NeuroBench", "neurobench@localhost"))?;
+        repo.tag(tag_name, &head, &tagger, message.unwrap_or(tag_name), false)?;
+    } else {
+        repo.tag_lightweight(tag_name, &head, false)?;
+    }
+    Ok(())
+}
+
+/// List all tags, newest semantic version first, then the rest by name
+pub fn list_tags(repo_path: &str) -> Result<Vec<TagInfo>, GitError> {
+    let repo = Repository::open(repo_path)?;
+    let mut tags = Vec::new();
+
+    for name in repo.tag_names(None)?.iter().flatten() {
+        let reference = repo.find_reference(&format!("refs/tags/{}", name))?;
+        let Ok(commit) = reference.peel_to_commit() else {
+            // Tags of trees or blobs have no version history to show
+            continue;
+        };
+        let annotation = reference.peel_to_tag().ok();
+        let tagger = annotation.as_ref().and_then(|t| t.tagger());
+
+        tags.push(TagInfo {
+            name: name.to_string(),
+            target: commit.id().to_string(),
+            short_id: commit.id().to_string()[..7].to_string(),
+            is_annotated: annotation.is_some(),
+            message: annotation.as_ref().and_then(|t| t.message()).map(|m| m.trim_end().to_string()),
+            tagger: tagger.as_ref().and_then(|t| t.name()).map(String::from),
+            time: tagger.map(|t| t.when().seconds()).unwrap_or_else(|| commit.time().seconds()),
+            version: SemVer::parse(name).map(|v| v.to_string()),
+        });
+    }
+
+    tags.sort_by(|a, b| {
+        let version = |tag: &TagInfo| tag.version.as_deref().and_then(SemVer::parse);
+        match (version(a), version(b)) {
+            (Some(x), Some(y)) => y.cmp(&x),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => a.name.cmp(&b.name),
+        }
+    });
+    Ok(tags)
+}
+
+/// Tag HEAD with the next semantic version and return the new tag name
+///
+/// Starts from the highest semver tag (0.0.0 if there is none) and keeps its
+/// `v` prefix. If the project has a `version.h`, its `FIRMWARE_VERSION` define
+/// is updated and committed first, so the tag points at the bumped header.
+pub fn bump_version(repo_path: &str, bump_type: BumpType) -> Result<String, GitError> {
+    let latest = list_tags(repo_path)?.into_iter()
+        .find_map(|tag| SemVer::parse(&tag.name).map(|version| (tag.name, version)));
+    let (prefix, next) = match latest {
+        Some((name, version)) => {
+            let prefix = if name.starts_with(['v', 'V']) { &name[..1] } else { "" };
+            (prefix.to_string(), version.bump(bump_type))
+        }
+        None => ("v".to_string(), SemVer { major: 0, minor: 0, patch: 0, pre: None }.bump(bump_type)),
+    };
+    let tag_name = format!("{}{}", prefix, next);
+
+    let repo = Repository::open(repo_path)?;
+    if repo.refname_to_id(&format!("refs/tags/{}", tag_name)).is_ok() {
+        return Err(GitError::TagExists(tag_name));
+    }
+    if let Some(relative) = find_version_header(Path::new(repo_path)) {
+        let header = Path::new(repo_path).join(&relative);
+        let content = std::fs::read_to_string(&header)?;
+        let updated = set_firmware_version(&content, &next.to_string());
+        if updated != content {
+            std::fs::write(&header, updated)?;
+            commit_paths(&repo, &[relative.as_path()], &format!("Bump version to {}", next))?;
+        }
+    }
+    create_tag(repo_path, &tag_name, Some(&format!("Release {}", next)), true)?;
+    Ok(tag_name)
+}
+
+/// Version header path relative to the repository root
+fn find_version_header(root: &Path) -> Option<PathBuf> {
+    VERSION_HEADER_PATHS.iter().map(PathBuf::from).find(|p| root.join(p).is_file())
+}
+
+/// Commit only `paths` on top of HEAD, leaving other staged changes staged
+///
+/// The commit tree is HEAD's tree with just `paths` replaced, built in a
+/// separate in-memory index so whatever else is staged stays out of it.
+fn commit_paths(repo: &Repository, paths: &[&Path], message: &str) -> Result<Oid, GitError> {
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let mut tree_index = Index::new()?;
+    if let Some(parent) = &parent {
+        tree_index.read_tree(&parent.tree()?)?;
+    }
+
+    // Stage the paths too, so they do not show as reverted once HEAD moves
+    let mut staged = repo.index()?;
+    for path in paths {
+        staged.add_path(path)?;
+        let entry = staged.get_path(path, 0)
+            .ok_or_else(|| Error::from_str(&format!("{} missing from index", path.display())))?;
+        tree_index.add(&entry)?;
+    }
+    staged.write()?;
+
+    let tree = repo.find_tree(tree_index.write_tree_to(repo)?)?;
+    let sig = repo.signature().or_else(|_| Signature::now("NeuroBench", "neurobench@localhost"))?;
+    let parents: Vec<&Commit> = parent.iter().collect();
+    Ok(repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)?)
+}
+
+/// Replace the `FIRMWARE_VERSION` define, or add one inside the include guard
+fn set_firmware_version(content: &str, version: &str) -> String {
+    let define = format!("#define FIRMWARE_VERSION \"{}\"", version);
+    let is_define = |line: &str| {
+        let mut tokens = line.split_whitespace();
+        matches!((tokens.next(), tokens.next()), (Some("#define"), Some("FIRMWARE_VERSION")))
+    };
+
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    if let Some(line) = lines.iter_mut().find(|l| is_define(l)) {
+        *line = define;
+    } else {
+        match lines.iter().rposition(|l| l.trim_start().starts_with("#endif")) {
+            Some(endif) => {
+                lines.insert(endif, String::new());
+                lines.insert(endif, define);
+            }
+            None => lines.push(define),
+        }
+    }
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    updated
+}
+
+/// `.gitignore` for an embedded project
+///
+/// Ignores build output, IDE user state, toolchain temp files and OS clutter
+/// while re-including the build files a project must track.
+pub fn generate_gitignore(mcu_family: McuFamily, build_system: BuildSystem, ide: Option<IdeType>) -> String {
+    let mut sections: Vec<(&str, Vec<&str>)> = Vec::new();
+
+    let mut build = vec!["build/", "output/"];
+    match build_system {
+        BuildSystem::Make => {}
+        BuildSystem::CMake => build.extend(["cmake-build-*/", "CMakeFiles/", "CMakeCache.txt", "cmake_install.cmake"]),
+        BuildSystem::Cargo => build.push("target/"),
+        BuildSystem::PlatformIO => build.extend([".pio/", ".pioenvs/", ".piolibdeps/"]),
+    }
+    sections.push(("Build output", build));
+    sections.push(("Binaries and linker output", vec!["*.o", "*.d", "*.elf", "*.axf", "*.bin", "*.hex", "*.map", "*.lst"]));
+
+    let target: Vec<&str> = match mcu_family {
+        McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3 => vec!["sdkconfig.old", "managed_components/"],
+        McuFamily::RP2040 => vec!["*.uf2"],
+        McuFamily::NRF52832 | McuFamily::NRF52840 => vec!["_build/"],
+        McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32H7 | McuFamily::STM32L4 | McuFamily::STM32G4
+        | McuFamily::LPC1768 | McuFamily::LPC5500 => vec![],
+    };
+    if !target.is_empty() {
+        sections.push((mcu_family.vendor(), target));
+    }
+
+    let ide_files: Vec<&str> = match ide {
+        // Shared debug configurations stay, per-user settings do not
+        Some(IdeType::VsCode) => vec![".vscode/*", "!.vscode/launch.json"],
+        Some(IdeType::Keil) => vec!["*.uvoptx", "*.uvguix.*", "Objects/", "Listings/", "*.dep", "JLinkLog.txt"],
+        Some(IdeType::Iar) => vec!["*.ewp", "*.ewt", "*.dep", "settings/", "Debug/", "Release/"],
+        Some(IdeType::CubeIde) => vec!["Debug/", "Release/", ".settings/"],
+        Some(IdeType::Clion) => vec![".idea/", "cmake-build-*/"],
+        None => vec![],
+    };
+    if !ide_files.is_empty() {
+        sections.push(("IDE", ide_files));
+    }
+
+    sections.push(("Toolchain temp files", vec!["*.su", "*.cyclo", "*.tmp", "*.bak", ".cache/"]));
+    sections.push(("OS files", vec![".DS_Store", "Thumbs.db", "desktop.ini", "*~"]));
+    sections.push(("Always tracked", vec!["!CMakeLists.txt", "!Makefile", "!compile_commands.json"]));
+
+    let mut gitignore = String::from("# Generated by NeuroBench\n");
+    let mut seen = std::collections::HashSet::new();
+    for (title, patterns) in sections {
+        gitignore.push_str(&format!("\n# {}\n", title));
+        for pattern in patterns.into_iter().filter(|p| seen.insert(*p)) {
+            gitignore.push_str(pattern);
+            gitignore.push('\n');
+        }
+    }
+    gitignore
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -348,4 +705,95 @@ mod tests {
         let status = get_status(path).unwrap();
         assert!(status.is_repo);
     }
+
+    #[test]
+    fn test_generate_gitignore() {
+        let gitignore = generate_gitignore(McuFamily::ESP32, BuildSystem::CMake, Some(IdeType::VsCode));
+        let lines: Vec<&str> = gitignore.lines().collect();
+        for pattern in ["build/", "*.elf", "*.map", "CMakeCache.txt", "sdkconfig.old", ".vscode/*", "!.vscode/launch.json", ".DS_Store", "Thumbs.db"] {
+            assert!(lines.contains(&pattern), "missing {}", pattern);
+        }
+        // Re-includes come last so nothing above can override them
+        assert_eq!(lines[lines.len() - 3..], ["!CMakeLists.txt", "!Makefile", "!compile_commands.json"]);
+        assert!(!lines.contains(&"*.uvoptx"));
+
+        let keil = generate_gitignore(McuFamily::STM32F4, BuildSystem::Make, Some(IdeType::Keil));
+        assert!(keil.lines().any(|l| l == "*.uvoptx"));
+        assert!(!keil.contains("# STMicroelectronics"));
+    }
+
+    #[test]
+    fn test_semver_bump() {
+        let version = SemVer::parse("v1.2.3").unwrap();
+        assert_eq!(version.bump(BumpType::Major).to_string(), "2.0.0");
+        assert_eq!(version.bump(BumpType::Minor).to_string(), "1.3.0");
+        assert_eq!(version.bump(BumpType::Patch).to_string(), "1.2.4");
+
+        let rc = SemVer::parse("1.3.0-rc.1").unwrap();
+        assert_eq!(rc.pre.as_deref(), Some("rc.1"));
+        assert_eq!(rc.bump(BumpType::Minor).to_string(), "1.3.0");
+        assert_eq!(rc.bump(BumpType::Patch).to_string(), "1.3.0");
+        assert_eq!(rc.bump(BumpType::Major).to_string(), "2.0.0");
+
+        assert!(SemVer::parse("1.2.0-rc.2").unwrap() < SemVer::parse("1.2.0-rc.10").unwrap());
+        assert!(SemVer::parse("1.2.0-rc.10").unwrap() < SemVer::parse("1.2.0").unwrap());
+        assert!(SemVer::parse("1.2").is_none());
+        assert!(SemVer::parse("release-1").is_none());
+    }
+
+    #[test]
+    fn test_set_firmware_version() {
+        let header = "#ifndef VERSION_H\n#define VERSION_H\n\n#endif\n";
+        let updated = set_firmware_version(header, "1.0.0");
+        assert_eq!(updated, "#ifndef VERSION_H\n#define VERSION_H\n\n#define FIRMWARE_VERSION \"1.0.0\"\n\n#endif\n");
+        assert_eq!(
+            set_firmware_version(&updated, "1.1.0"),
+            "#ifndef VERSION_H\n#define VERSION_H\n\n#define FIRMWARE_VERSION \"1.1.0\"\n\n#endif\n"
+        );
+    }
+
+    #[test]
+    fn test_tags_and_bump_version() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        init_repo(path).unwrap();
+        fs::write(dir.path().join("version.h"), "#define FIRMWARE_VERSION \"0.0.0\"\n").unwrap();
+        stage_all(path).unwrap();
+        commit(path, "Initial commit", "Test", "test@example.com").unwrap();
+
+        create_tag(path, "v1.2.0", None, false).unwrap();
+        create_tag(path, "v1.3.0-rc.1", Some("Release candidate"), true).unwrap();
+        create_tag(path, "nightly", None, false).unwrap();
+        assert!(matches!(create_tag(path, "v1.2.0", None, false), Err(GitError::TagExists(_))));
+
+        let tags = list_tags(path).unwrap();
+        let names: Vec<&str> = tags.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["v1.3.0-rc.1", "v1.2.0", "nightly"]);
+        assert!(tags[0].is_annotated);
+        assert_eq!(tags[0].message.as_deref(), Some("Release candidate"));
+        assert_eq!(tags[1].target, tags[0].target);
+
+        assert_eq!(bump_version(path, BumpType::Minor).unwrap(), "v1.3.0");
+
+        // Unrelated staged work stays staged and out of the bump commit
+        fs::write(dir.path().join("notes.txt"), "wip\n").unwrap();
+        stage_files(path, &["notes.txt"]).unwrap();
+        assert_eq!(bump_version(path, BumpType::Patch).unwrap(), "v1.3.1");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("version.h")).unwrap(),
+            "#define FIRMWARE_VERSION \"1.3.1\"\n"
+        );
+
+        // The tag points at the commit carrying the bumped header
+        let repo = Repository::open(path).unwrap();
+        let tagged = repo.revparse_single("v1.3.1").unwrap().peel_to_commit().unwrap();
+        assert_eq!(tagged.id(), repo.head().unwrap().peel_to_commit().unwrap().id());
+        let blob = tagged.tree().unwrap().get_path(Path::new("version.h")).unwrap().to_object(&repo).unwrap();
+        assert_eq!(blob.as_blob().unwrap().content(), b"#define FIRMWARE_VERSION \"1.3.1\"\n");
+        assert!(tagged.tree().unwrap().get_path(Path::new("notes.txt")).is_err());
+        let status = get_status(path).unwrap().files;
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].path, "notes.txt");
+        assert!(status[0].staged);
+    }
 }
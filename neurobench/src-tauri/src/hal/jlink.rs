@@ -0,0 +1,153 @@
+// J-Link Commander Script Generator
+// Emits .jlink command files for unattended flashing
+
+use crate::drivers::McuFamily;
+use serde::{Deserialize, Serialize};
+
+/// SEGGER device name for a representative part of each family
+///
+/// Espressif parts are absent: they are flashed over UART with esptool.
+pub const JLINK_DEVICE_MAP: &[(McuFamily, &str)] = &[
+    (McuFamily::STM32F1, "STM32F103C8"),
+    (McuFamily::STM32F4, "STM32F407VG"),
+    (McuFamily::STM32H7, "STM32H743ZI"),
+    (McuFamily::STM32L4, "STM32L476RG"),
+    (McuFamily::STM32G4, "STM32G474RE"),
+    (McuFamily::RP2040, "RP2040_M0_0"),
+    (McuFamily::NRF52832, "nRF52832_xxAA"),
+    (McuFamily::NRF52840, "nRF52840_xxAA"),
+    (McuFamily::LPC1768, "LPC1768"),
+    (McuFamily::LPC5500, "LPC55S69_M33_0"),
+];
+
+/// Highest interface clock a J-Link (PLUS/ULTRA+) supports
+pub const JLINK_MAX_SPEED_KHZ: u32 = 50_000;
+
+/// Target interface selected with `si`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum JLinkInterface {
+    Swd,
+    Jtag,
+}
+
+impl JLinkInterface {
+    pub fn name(&self) -> &'static str {
+        match self {
+            JLinkInterface::Swd => "SWD",
+            JLinkInterface::Jtag => "JTAG",
+        }
+    }
+}
+
+/// SEGGER device name for `mcu`
+pub fn jlink_device(mcu: McuFamily) -> Option<&'static str> {
+    JLINK_DEVICE_MAP.iter().find(|(family, _)| *family == mcu).map(|(_, device)| *device)
+}
+
+/// Families whose debug port is SWD only (no JTAG TAP)
+fn is_swd_only(mcu: McuFamily) -> bool {
+    matches!(mcu, McuFamily::RP2040 | McuFamily::NRF52832 | McuFamily::NRF52840)
+}
+
+/// Check that J-Link can flash `mcu` over `interface` at `speed_khz`
+pub fn check_jlink_support(mcu: McuFamily, interface: JLinkInterface, speed_khz: u32) -> Result<(), String> {
+    if jlink_device(mcu).is_none() {
+        return Err(format!("J-Link has no flash support for {}; use esptool", mcu.display_name()));
+    }
+    if interface == JLinkInterface::Jtag && is_swd_only(mcu) {
+        return Err(format!("{} only exposes SWD", mcu.display_name()));
+    }
+    if speed_khz == 0 || speed_khz > JLINK_MAX_SPEED_KHZ {
+        return Err(format!("J-Link supports speeds up to {} kHz, got {}", JLINK_MAX_SPEED_KHZ, speed_khz));
+    }
+    Ok(())
+}
+
+/// Generate a `.jlink` script over SWD
+pub fn generate_jlink_script(mcu: McuFamily, elf_path: &str, speed_khz: u32, verify: bool) -> String {
+    generate_jlink_script_with_interface(mcu, elf_path, speed_khz, verify, JLinkInterface::Swd)
+}
+
+/// Generate a `.jlink` script: connect, halt, program, optionally verify, reset and run
+pub fn generate_jlink_script_with_interface(
+    mcu: McuFamily,
+    elf_path: &str,
+    speed_khz: u32,
+    verify: bool,
+    interface: JLinkInterface,
+) -> String {
+    let device = jlink_device(mcu).unwrap_or("Unspecified");
+    // Paths with spaces must be quoted or J-Link splits them into arguments
+    let file = if elf_path.contains(char::is_whitespace) {
+        format!("\"{}\"", elf_path)
+    } else {
+        elf_path.to_string()
+    };
+
+    let mut script = format!(
+        "// J-Link Commander script\n\
+         // Auto-generated by NeuroBench\n\
+         // Target: {}, interface: {}\n\
+         //\n\
+         // Run: JLink.exe -commandfile script.jlink (JLinkExe on Linux/macOS)\n",
+        mcu.display_name(),
+        interface.name(),
+    );
+    if interface == JLinkInterface::Jtag {
+        script.push_str("// JTAG needs TDI/TDO/TMS/TCK wired; SWD only needs SWDIO/SWCLK\n");
+    }
+
+    script.push_str(&format!("device {}\n", device));
+    script.push_str(&format!("si {}\n", interface.name()));
+    script.push_str(&format!("speed {}\n", speed_khz));
+    script.push_str("connect\n");
+    script.push_str("r\n");
+    script.push_str("h\n");
+    script.push_str(&format!("loadfile {}\n", file));
+    if verify {
+        script.push_str(&format!("verifyfile {}\n", file));
+    }
+    script.push_str("r\n");
+    script.push_str("go\n");
+    script.push_str("exit\n");
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jlink_script() {
+        let script = generate_jlink_script(McuFamily::STM32F4, "build/firmware.elf", 4000, true);
+        let commands: Vec<&str> = script.lines().filter(|l| !l.starts_with("//")).collect();
+        assert_eq!(commands, vec![
+            "device STM32F407VG",
+            "si SWD",
+            "speed 4000",
+            "connect",
+            "r",
+            "h",
+            "loadfile build/firmware.elf",
+            "verifyfile build/firmware.elf",
+            "r",
+            "go",
+            "exit",
+        ]);
+
+        let script = generate_jlink_script_with_interface(McuFamily::LPC1768, "my build/fw.elf", 1000, false, JLinkInterface::Jtag);
+        assert!(script.contains("si JTAG\n"));
+        assert!(script.contains("loadfile \"my build/fw.elf\"\n"));
+        assert!(!script.contains("verifyfile"));
+    }
+
+    #[test]
+    fn test_check_jlink_support() {
+        assert!(check_jlink_support(McuFamily::STM32H7, JLinkInterface::Jtag, 12000).is_ok());
+        assert!(check_jlink_support(McuFamily::ESP32, JLinkInterface::Swd, 4000).is_err());
+        assert!(check_jlink_support(McuFamily::NRF52840, JLinkInterface::Jtag, 4000).is_err());
+        assert!(check_jlink_support(McuFamily::STM32F4, JLinkInterface::Swd, 0).is_err());
+        assert_eq!(jlink_device(McuFamily::RP2040), Some("RP2040_M0_0"));
+    }
+}
@@ -1,6 +1,8 @@
 // Hardware Abstraction Layer
 // Simulates MCU peripherals for testing
 
+pub mod jlink;
 pub mod simulator;
+pub mod usb_db;
 
 pub use simulator::*;
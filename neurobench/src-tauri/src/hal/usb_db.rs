@@ -0,0 +1,104 @@
+// USB Device Database
+// Identifies debug probes and USB-serial adapters by VID/PID
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// What kind of hardware a USB device is
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceType {
+    DebugProbe,
+    UsbSerial,
+    Unknown,
+}
+
+/// Interfaces a device can drive
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Protocol {
+    Swd,
+    Jtag,
+    Swo,
+    Uart,
+}
+
+/// Known device entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsbDeviceEntry {
+    pub vid: String,
+    pub pid: String,
+    pub name: String,
+    pub device_type: DeviceType,
+    pub protocols: Vec<Protocol>,
+}
+
+/// `(VID, PID)` lookup table
+#[derive(Debug, Default)]
+pub struct UsbDatabase {
+    entries: HashMap<(u16, u16), UsbDeviceEntry>,
+}
+
+lazy_static::lazy_static! {
+    static ref BUNDLED: UsbDatabase = UsbDatabase::from_json(include_str!("usb_devices.json"))
+        .expect("bundled usb_devices.json is valid");
+}
+
+impl UsbDatabase {
+    /// Database shipped with the app
+    pub fn bundled() -> &'static UsbDatabase {
+        &BUNDLED
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let list: Vec<UsbDeviceEntry> = serde_json::from_str(json)
+            .map_err(|e| format!("Invalid USB database: {}", e))?;
+        let mut entries = HashMap::new();
+        for entry in list {
+            let key = (parse_usb_id(&entry.vid)?, parse_usb_id(&entry.pid)?);
+            entries.insert(key, entry);
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn lookup(&self, vid: u16, pid: u16) -> Option<&UsbDeviceEntry> {
+        self.entries.get(&(vid, pid))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Parse a hex VID/PID such as `0483` or `0x0483`
+pub fn parse_usb_id(id: &str) -> Result<u16, String> {
+    let hex = id.trim();
+    let hex = hex.strip_prefix("0x").or_else(|| hex.strip_prefix("0X")).unwrap_or(hex);
+    u16::from_str_radix(hex, 16).map_err(|_| format!("Invalid USB id: {}", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_lookup() {
+        let db = UsbDatabase::bundled();
+        assert!(db.len() > 10);
+
+        let stlink = db.lookup(0x0483, 0x374B).unwrap();
+        assert_eq!(stlink.name, "ST-Link V2-1");
+        assert_eq!(stlink.device_type, DeviceType::DebugProbe);
+        assert!(stlink.protocols.contains(&Protocol::Swd));
+
+        let cp210x = db.lookup(parse_usb_id("10c4").unwrap(), parse_usb_id("0xEA60").unwrap()).unwrap();
+        assert_eq!(cp210x.device_type, DeviceType::UsbSerial);
+
+        assert!(db.lookup(0xFFFF, 0x0001).is_none());
+        assert!(parse_usb_id("xyz").is_err());
+    }
+}
@@ -0,0 +1,213 @@
+// Job Event Fanout
+//
+// Every message a `JobEmitter` processes is also published here, so any number
+// of listeners (frontend windows, metrics, agents) can follow a job without the
+// worker knowing about them.
+
+use super::{EmitterMessage, JobEventHeader, JobId, PROTOCOL_VERSION};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+
+/// Capacity of the all-jobs channel
+pub const FANOUT_CAPACITY: usize = 1024;
+/// Capacity of each per-job channel
+pub const JOB_CHANNEL_CAPACITY: usize = 256;
+/// Ended jobs remembered to turn away late subscribers
+pub const ENDED_JOBS_KEPT: usize = 256;
+
+/// Per-job channels and the jobs that already sent their terminal message
+#[derive(Default)]
+struct JobChannels {
+    senders: HashMap<JobId, broadcast::Sender<EmitterMessage>>,
+    ended: VecDeque<JobId>,
+}
+
+impl JobChannels {
+    fn has_ended(&self, job_id: &str) -> bool {
+        self.ended.iter().any(|id| id == job_id)
+    }
+
+    fn mark_ended(&mut self, job_id: &str) {
+        if self.has_ended(job_id) {
+            return;
+        }
+        if self.ended.len() == ENDED_JOBS_KEPT {
+            self.ended.pop_front();
+        }
+        self.ended.push_back(job_id.to_string());
+    }
+}
+
+/// Broadcasts emitter messages from every job
+#[derive(Clone)]
+pub struct JobFanout {
+    tx: broadcast::Sender<(JobId, EmitterMessage)>,
+    per_job: Arc<Mutex<JobChannels>>,
+}
+
+impl JobFanout {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        Self {
+            tx,
+            per_job: Arc::new(Mutex::new(JobChannels::default())),
+        }
+    }
+
+    /// Publish a message for `job_id` to all subscribers
+    pub fn publish(&self, job_id: &str, msg: EmitterMessage) {
+        {
+            let mut channels = self.per_job.lock().unwrap();
+            if msg.is_terminal() {
+                // Dropping the sender after the terminal message closes the per-job receivers
+                if let Some(sender) = channels.senders.remove(job_id) {
+                    let _ = sender.send(msg.clone());
+                }
+                channels.mark_ended(job_id);
+            } else if let Some(sender) = channels.senders.get(job_id) {
+                if sender.send(msg.clone()).is_err() {
+                    channels.senders.remove(job_id);
+                }
+            }
+        }
+
+        // No subscribers is not an error
+        let _ = self.tx.send((job_id.to_string(), msg));
+    }
+
+    /// Receive messages from every job
+    pub fn subscribe_all(&self) -> broadcast::Receiver<(JobId, EmitterMessage)> {
+        self.tx.subscribe()
+    }
+
+    /// Receive messages for one job; the receiver closes after its terminal message
+    ///
+    /// `None` once the job has sent its terminal message, checked under the same
+    /// lock `publish` takes so a job ending concurrently cannot leave a channel behind.
+    pub fn subscribe_to_job(&self, job_id: &str) -> Option<broadcast::Receiver<EmitterMessage>> {
+        let mut channels = self.per_job.lock().unwrap();
+        if channels.has_ended(job_id) {
+            return None;
+        }
+        let sender = channels.senders
+            .entry(job_id.to_string())
+            .or_insert_with(|| broadcast::channel(JOB_CHANNEL_CAPACITY).0);
+        Some(sender.subscribe())
+    }
+
+    /// Forward one job's messages as `{event_prefix}:{suffix}` events until it ends
+    ///
+    /// Each forwarder stamps its own header, so `seq` counts from 0 for every subscriber.
+    /// `None` when the job has already ended.
+    pub fn spawn_forwarder(
+        &self,
+        job_id: &str,
+        event_prefix: &str,
+        emit_event: impl Fn(String, serde_json::Value) + Send + Sync + 'static,
+    ) -> Option<JoinHandle<()>> {
+        let mut rx = self.subscribe_to_job(job_id)?;
+        let job_id = job_id.to_string();
+        let event_prefix = event_prefix.to_string();
+
+        Some(tokio::spawn(async move {
+            let started_at = Instant::now();
+            let mut seq = 0;
+
+            loop {
+                let msg = match rx.recv().await {
+                    Ok(msg) => msg,
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("Job {} forwarder skipped {} events", job_id, skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                let header = JobEventHeader {
+                    protocol_version: PROTOCOL_VERSION,
+                    job_id: job_id.clone(),
+                    seq,
+                    timestamp_ms: started_at.elapsed().as_millis() as u64,
+                };
+                seq += 1;
+
+                emit_event(format!("{}:{}", event_prefix, msg.event_suffix()), msg.to_event(&header));
+                if msg.is_terminal() {
+                    break;
+                }
+            }
+        }))
+    }
+}
+
+impl Default for JobFanout {
+    fn default() -> Self {
+        Self::new(FANOUT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::{JobEmitter, JobKind, JobManager, JobTerminal};
+    use std::sync::Mutex;
+
+    fn log(line: &str) -> EmitterMessage {
+        EmitterMessage::Log { line: line.to_string() }
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_to_job_filters() {
+        let fanout = JobFanout::default();
+        let mut all = fanout.subscribe_all();
+        let mut flash = fanout.subscribe_to_job("flash_1").unwrap();
+
+        fanout.publish("rtt_1", log("rtt"));
+        fanout.publish("flash_1", log("flash"));
+        fanout.publish("flash_1", EmitterMessage::Terminal {
+            terminal: JobTerminal::Completed { success: true, exit_code: Some(0), duration_ms: 10 },
+        });
+
+        assert_eq!(all.recv().await.unwrap().0, "rtt_1");
+        assert_eq!(all.recv().await.unwrap().0, "flash_1");
+        assert!(matches!(flash.recv().await.unwrap(), EmitterMessage::Log { line } if line == "flash"));
+        assert!(flash.recv().await.unwrap().is_terminal());
+        assert!(matches!(flash.recv().await, Err(RecvError::Closed)));
+
+        // Subscribing after the end neither waits forever nor leaves a channel behind
+        assert!(fanout.subscribe_to_job("flash_1").is_none());
+        assert!(fanout.spawn_forwarder("flash_1", "job", |_, _| {}).is_none());
+        assert!(fanout.per_job.lock().unwrap().senders.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_emitter_publishes_and_forwarder_stops() {
+        let manager = JobManager::new();
+        let (record, _tx) = manager.create_job(JobKind::Flash);
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let forwarder = manager.fanout().spawn_forwarder(&record.id, "job", move |name, payload| {
+            sink.lock().unwrap().push((name, payload));
+        }).unwrap();
+
+        let mut emitter = JobEmitter::new(&record);
+        emitter.process(log("Erasing")).await;
+        emitter.process(EmitterMessage::Custom {
+            event_suffix: "cancelled".to_string(),
+            payload: serde_json::json!({ "type": "cancelled" }),
+        }).await;
+        forwarder.await.unwrap();
+
+        let events = events.lock().unwrap();
+        let names: Vec<&str> = events.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["job:output", "job:cancelled"]);
+        assert_eq!(events[0].1["line"], "Erasing");
+        assert_eq!(events[1].1["header"]["job_id"], record.id.as_str());
+        assert_eq!(events[1].1["header"]["seq"], 1);
+    }
+}
@@ -3,7 +3,7 @@
 // Provides flash operations using the unified job manager.
 // All events flow through JobEmitter (strict single-emitter pattern).
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 use async_trait::async_trait;
@@ -58,18 +58,102 @@ impl FlashPhase {
         }
     }
     
+    /// Slice of the overall 0-100% progress bar covered by each phase
+    pub fn percent_range(&self) -> (f32, f32) {
+        match self {
+            FlashPhase::Connecting => (0.0, 0.0),
+            FlashPhase::Erasing => (0.0, 20.0),
+            FlashPhase::Programming => (20.0, 80.0),
+            FlashPhase::Verifying => (80.0, 100.0),
+            FlashPhase::Resetting => (100.0, 100.0),
+        }
+    }
+
     /// Base percent for each phase
     pub fn base_percent(&self) -> f32 {
-        match self {
-            FlashPhase::Connecting => 0.0,
-            FlashPhase::Erasing => 5.0,
-            FlashPhase::Programming => 20.0,
-            FlashPhase::Verifying => 90.0,
-            FlashPhase::Resetting => 98.0,
+        self.percent_range().0
+    }
+
+    /// Overall percent once `fraction` (0-1) of this phase is done
+    pub fn scaled_percent(&self, fraction: f32) -> f32 {
+        let (start, end) = self.percent_range();
+        start + (end - start) * fraction.clamp(0.0, 1.0)
+    }
+}
+
+/// Byte-count progress of the current phase, turned into overall percentages
+///
+/// Real backends report bytes erased/programmed/verified per phase; each
+/// phase fills its own `percent_range` so the bar never jumps backwards.
+#[derive(Debug, Clone)]
+pub struct PhaseProgress {
+    phase: FlashPhase,
+    done: u64,
+    total: u64,
+}
+
+impl PhaseProgress {
+    pub fn new() -> Self {
+        Self { phase: FlashPhase::Connecting, done: 0, total: 0 }
+    }
+
+    pub fn phase(&self) -> FlashPhase {
+        self.phase
+    }
+
+    /// Enter `phase`, which will cover `total` bytes
+    pub fn start(&mut self, phase: FlashPhase, total: u64) -> FlashMessage {
+        self.phase = phase;
+        self.done = 0;
+        self.total = total;
+        self.message()
+    }
+
+    /// `bytes` more of the current phase are done
+    pub fn advance(&mut self, bytes: u64) -> FlashMessage {
+        self.done = self.done.saturating_add(bytes);
+        self.message()
+    }
+
+    /// The current phase completed, whatever the byte count says
+    pub fn finish(&mut self) -> FlashMessage {
+        self.done = self.total;
+        let mut message = self.message();
+        if let FlashMessage::Progress { percent, .. } = &mut message {
+            *percent = self.phase.percent_range().1;
+        }
+        message
+    }
+
+    fn message(&self) -> FlashMessage {
+        let fraction = if self.total == 0 { 0.0 } else { (self.done as f32 / self.total as f32).min(1.0) };
+        let verb = match self.phase {
+            FlashPhase::Connecting => "Connecting",
+            FlashPhase::Erasing => "Erasing flash",
+            FlashPhase::Programming => "Programming flash",
+            FlashPhase::Verifying => "Verifying",
+            FlashPhase::Resetting => "Resetting target",
+        };
+        FlashMessage::Progress {
+            phase: self.phase,
+            percent: self.phase.scaled_percent(fraction),
+            done_bytes: (self.total > 0).then_some(self.done.min(self.total)),
+            total_bytes: (self.total > 0).then_some(self.total),
+            message: Some(if self.total > 0 {
+                format!("{}: {:.0}%", verb, fraction * 100.0)
+            } else {
+                format!("{}...", verb)
+            }),
         }
     }
 }
 
+impl Default for PhaseProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Flash internal messages (sent to emitter)
 #[derive(Debug, Clone)]
 pub enum FlashMessage {
@@ -189,6 +273,26 @@ impl FlashError {
         }
     }
     
+    pub fn invalid_elf(msg: impl Into<String>) -> Self {
+        Self {
+            code: FlashErrorCode::InvalidElf,
+            message: msg.into(),
+            details: None,
+            retryable: false,
+            os_error_code: None,
+        }
+    }
+    
+    pub fn verify_failed(msg: impl Into<String>) -> Self {
+        Self {
+            code: FlashErrorCode::VerifyFailed,
+            message: msg.into(),
+            details: None,
+            retryable: true,
+            os_error_code: None,
+        }
+    }
+    
     pub fn cancelled() -> Self {
         Self {
             code: FlashErrorCode::Cancelled,
@@ -200,6 +304,75 @@ impl FlashError {
     }
 }
 
+// ==================== Verification ====================
+
+/// A loadable ELF segment at its load (flash) address
+#[derive(Debug, Clone)]
+pub struct LoadSegment {
+    pub address: u64,
+    pub data: Vec<u8>,
+}
+
+/// PT_LOAD segments of a 32-bit ELF, placed at their physical addresses
+pub fn elf_load_segments(path: &Path) -> Result<Vec<LoadSegment>, FlashError> {
+    use object::read::elf::{ElfFile32, ProgramHeader};
+    
+    let bytes = std::fs::read(path).map_err(|e| FlashError {
+        code: FlashErrorCode::IoError,
+        message: format!("Failed to read {}: {}", path.display(), e),
+        details: None,
+        retryable: false,
+        os_error_code: e.raw_os_error(),
+    })?;
+    let elf = ElfFile32::<object::Endianness>::parse(bytes.as_slice())
+        .map_err(|e| FlashError::invalid_elf(format!("Invalid ELF {}: {}", path.display(), e)))?;
+    let endian = elf.endian();
+    
+    let mut segments = Vec::new();
+    for header in elf.elf_program_headers() {
+        if header.p_type(endian) != object::elf::PT_LOAD || header.p_filesz(endian) == 0 {
+            continue;
+        }
+        let data = header.data(endian, bytes.as_slice())
+            .map_err(|_| FlashError::invalid_elf("ELF segment extends past the end of the file"))?;
+        segments.push(LoadSegment {
+            address: u64::from(header.p_paddr(endian)),
+            data: data.to_vec(),
+        });
+    }
+    Ok(segments)
+}
+
+/// Compare the CRC32 of each segment with the same range read back from the target
+pub fn verify_segments(
+    segments: &[LoadSegment],
+    read: impl FnMut(u64, &mut [u8]) -> Result<(), String>,
+) -> Result<(), FlashError> {
+    verify_segments_with_progress(segments, read, |_| {})
+}
+
+/// `verify_segments`, calling `on_verified` with the byte count of each segment that matched
+pub fn verify_segments_with_progress(
+    segments: &[LoadSegment],
+    mut read: impl FnMut(u64, &mut [u8]) -> Result<(), String>,
+    mut on_verified: impl FnMut(u64),
+) -> Result<(), FlashError> {
+    for segment in segments {
+        let mut readback = vec![0u8; segment.data.len()];
+        read(segment.address, &mut readback)
+            .map_err(|e| FlashError::verify_failed(format!("Failed to read back 0x{:08X}: {}", segment.address, e)))?;
+        let (expected, actual) = (crc32fast::hash(&segment.data), crc32fast::hash(&readback));
+        if expected != actual {
+            return Err(FlashError::verify_failed(format!(
+                "CRC mismatch at 0x{:08X} ({} bytes): expected {:08X}, read {:08X}",
+                segment.address, segment.data.len(), expected, actual
+            )));
+        }
+        on_verified(segment.data.len() as u64);
+    }
+    Ok(())
+}
+
 // ==================== ProbeBackend Trait ====================
 
 /// Progress callback for flash operations
@@ -329,8 +502,8 @@ impl ProbeBackend for MockProbeBackend {
             if cancel_check() {
                 return Err(FlashError::cancelled());
             }
-            let percent = 20.0 + (i as f32 * 14.0);
-            let bytes_done = (file_size as f32 * (percent - 20.0) / 70.0) as u64;
+            let percent = FlashPhase::Programming.scaled_percent(i as f32 / 5.0);
+            let bytes_done = file_size * i / 5;
             let _ = progress.send(FlashMessage::Progress {
                 phase: FlashPhase::Programming,
                 percent,
@@ -458,8 +631,20 @@ async fn run_flash_worker<B: ProbeBackend + 'static>(
         emit_event(event_name, payload);
     }
     
+    // Wait for the flash slot; flash jobs run at realtime priority
+    let Some(cancel_token) = job_manager.scheduler().acquire(&record.id, JobKind::Flash, &record.cancel_token).await else {
+        if let Some((event_name, payload)) = emitter.process(EmitterMessage::Terminal {
+            terminal: JobTerminal::Cancelled {
+                reason: CancelReason::UserRequest,
+            },
+        }).await {
+            emit_event(event_name, payload);
+        }
+        job_manager.finish_job(&record.id).await;
+        return;
+    };
+    
     // Spawn backend flash operation
-    let cancel_token = record.cancel_token.clone();
     let flash_config = config.clone();
     let flash_handle = tokio::spawn(async move {
         backend.flash(
@@ -669,4 +854,91 @@ mod tests {
         
         assert!(matches!(result, Err(ref e) if matches!(e.code, FlashErrorCode::FlashFailed)));
     }
+    
+    #[test]
+    fn test_phase_progress_ranges() {
+        let percent = |message: FlashMessage| match message {
+            FlashMessage::Progress { percent, .. } => percent,
+            other => panic!("unexpected {:?}", other),
+        };
+        let mut tracker = PhaseProgress::new();
+
+        assert_eq!(percent(tracker.start(FlashPhase::Erasing, 4096)), 0.0);
+        assert_eq!(percent(tracker.advance(2048)), 10.0);
+        // Sectors larger than expected never leave the erase range
+        assert_eq!(percent(tracker.advance(16384)), 20.0);
+
+        assert_eq!(percent(tracker.start(FlashPhase::Programming, 1000)), 20.0);
+        assert_eq!(percent(tracker.advance(250)), 35.0);
+        match tracker.advance(250) {
+            FlashMessage::Progress { phase, done_bytes, total_bytes, message, .. } => {
+                assert_eq!(phase, FlashPhase::Programming);
+                assert_eq!((done_bytes, total_bytes), (Some(500), Some(1000)));
+                assert_eq!(message.as_deref(), Some("Programming flash: 50%"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(percent(tracker.finish()), 80.0);
+
+        assert_eq!(percent(tracker.start(FlashPhase::Verifying, 1000)), 80.0);
+        assert_eq!(percent(tracker.advance(500)), 90.0);
+        assert_eq!(percent(tracker.finish()), 100.0);
+
+        // Unknown totals stay at the start of the phase until it finishes
+        assert_eq!(percent(tracker.start(FlashPhase::Erasing, 0)), 0.0);
+        assert_eq!(percent(tracker.advance(4096)), 0.0);
+        assert_eq!(percent(tracker.finish()), 20.0);
+    }
+
+    /// Minimal 32-bit little-endian ELF with one PT_LOAD segment
+    fn tiny_elf(paddr: u32, data: &[u8]) -> Vec<u8> {
+        let mut elf = vec![0x7f, b'E', b'L', b'F', 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        elf.extend_from_slice(&2u16.to_le_bytes()); // ET_EXEC
+        elf.extend_from_slice(&40u16.to_le_bytes()); // EM_ARM
+        elf.extend_from_slice(&1u32.to_le_bytes());
+        elf.extend_from_slice(&paddr.to_le_bytes()); // entry
+        elf.extend_from_slice(&52u32.to_le_bytes()); // phoff
+        elf.extend_from_slice(&0u32.to_le_bytes()); // shoff
+        elf.extend_from_slice(&0u32.to_le_bytes()); // flags
+        for field in [52u16, 32, 1, 40, 0, 0] {
+            elf.extend_from_slice(&field.to_le_bytes());
+        }
+        let len = data.len() as u32;
+        for field in [1u32, 84, 0x2000_0000, paddr, len, len, 5, 4] {
+            elf.extend_from_slice(&field.to_le_bytes());
+        }
+        elf.extend_from_slice(data);
+        elf
+    }
+    
+    #[test]
+    fn test_elf_segments_crc_verify() {
+        let image = b"\x00\x00\x02\x20\x09\x01\x00\x08firmware";
+        let elf = NamedTempFile::new().unwrap();
+        std::fs::write(elf.path(), tiny_elf(0x0800_0000, image)).unwrap();
+        
+        let segments = elf_load_segments(elf.path()).unwrap();
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].address, 0x0800_0000);
+        assert_eq!(segments[0].data, image);
+        
+        let flash = |addr: u64, buf: &mut [u8]| {
+            assert_eq!(addr, 0x0800_0000);
+            buf.copy_from_slice(image);
+            Ok(())
+        };
+        assert!(verify_segments(&segments, flash).is_ok());
+        let mut verified = 0;
+        assert!(verify_segments_with_progress(&segments, flash, |bytes| verified += bytes).is_ok());
+        assert_eq!(verified, image.len() as u64);
+        let corrupted = |_: u64, buf: &mut [u8]| {
+            buf.fill(0xFF);
+            Ok(())
+        };
+        let err = verify_segments(&segments, corrupted).unwrap_err();
+        assert!(matches!(err.code, FlashErrorCode::VerifyFailed));
+        
+        std::fs::write(elf.path(), b"not an elf").unwrap();
+        assert!(matches!(elf_load_segments(elf.path()).unwrap_err().code, FlashErrorCode::InvalidElf));
+    }
 }
@@ -7,8 +7,10 @@
 // - Cancellation with terminal event guarantee
 // - Exclusive device lock for hardware operations
 
+pub mod fanout;
 pub mod flash;
 pub mod rtt;
+pub mod scheduler;
 #[cfg(feature = "hardware")]
 pub mod probe_rs_backend;
 use serde::{Deserialize, Serialize};
@@ -20,6 +22,9 @@ use tokio::sync::{mpsc, Mutex, RwLock};
 use tokio_util::sync::CancellationToken;
 use dashmap::DashMap;
 
+pub use fanout::JobFanout;
+pub use scheduler::{JobPriority, JobScheduler};
+
 /// Current protocol version for all job events
 pub const PROTOCOL_VERSION: u32 = 1;
 
@@ -42,6 +47,9 @@ pub enum JobKind {
     Rtt,
     Agent,
     Index,
+    Script,
+    RegisterWatch,
+    Serial,
 }
 
 impl JobKind {
@@ -58,6 +66,9 @@ impl JobKind {
             JobKind::Rtt => "rtt",
             JobKind::Agent => "agent",
             JobKind::Index => "index",
+            JobKind::Script => "script",
+            JobKind::RegisterWatch => "register",
+            JobKind::Serial => "serial",
         }
     }
 }
@@ -134,6 +145,7 @@ pub struct DeviceStatus {
     pub active_rtt_id: Option<String>,
     pub active_flash_id: Option<String>,
     pub active_jobs_count: usize,
+    pub queued_jobs_count: usize,
 }
 
 // ==================== Ring Buffer ====================
@@ -207,6 +219,8 @@ pub struct JobRecord {
     pub terminal_sent: Arc<AtomicBool>,
     pub log: Arc<Mutex<RingBuffer>>,
     pub status: Arc<RwLock<JobStatus>>,
+    /// Set by `JobManager::create_job`; emitters publish every message here
+    pub fanout: Option<JobFanout>,
 }
 
 impl JobRecord {
@@ -219,6 +233,7 @@ impl JobRecord {
             terminal_sent: Arc::new(AtomicBool::new(false)),
             log: Arc::new(Mutex::new(RingBuffer::new(DEFAULT_LOG_LINES, DEFAULT_LOG_BYTES))),
             status: Arc::new(RwLock::new(JobStatus::default())),
+            fanout: None,
         }
     }
     
@@ -245,6 +260,7 @@ impl JobRecord {
 // ==================== Job Emitter ====================
 
 /// Message sent to the single emitter task
+#[derive(Debug, Clone)]
 pub enum EmitterMessage {
     Log { line: String },
     Progress { phase: String, percent: f32, message: Option<String> },
@@ -252,6 +268,67 @@ pub enum EmitterMessage {
     Custom { event_suffix: String, payload: serde_json::Value },
 }
 
+/// Custom suffixes that end a job's event stream like a `Terminal` message
+const TERMINAL_SUFFIXES: &[&str] = &["completed", "cancelled", "internal_error"];
+
+impl EmitterMessage {
+    /// Event name suffix, e.g. `output` in `flash:output`
+    pub fn event_suffix(&self) -> &str {
+        match self {
+            EmitterMessage::Log { .. } => "output",
+            EmitterMessage::Progress { .. } => "progress",
+            EmitterMessage::Terminal { terminal } => match terminal {
+                JobTerminal::Completed { .. } => "completed",
+                JobTerminal::Cancelled { .. } => "cancelled",
+                JobTerminal::InternalError { .. } => "internal_error",
+            },
+            EmitterMessage::Custom { event_suffix, .. } => event_suffix,
+        }
+    }
+    
+    /// Whether this is the last message of a job
+    ///
+    /// Jobs that report their own outcome (builds) send it as a `Custom` with a terminal suffix.
+    pub fn is_terminal(&self) -> bool {
+        match self {
+            EmitterMessage::Terminal { .. } => true,
+            EmitterMessage::Custom { event_suffix, .. } => TERMINAL_SUFFIXES.contains(&event_suffix.as_str()),
+            _ => false,
+        }
+    }
+    
+    /// Event payload stamped with `header`
+    pub fn to_event(&self, header: &JobEventHeader) -> serde_json::Value {
+        match self {
+            EmitterMessage::Log { line } => serde_json::json!({
+                "type": "output",
+                "header": header,
+                "line": line,
+            }),
+            EmitterMessage::Progress { phase, percent, message } => serde_json::json!({
+                "type": "progress",
+                "header": header,
+                "phase": phase,
+                "percent": percent,
+                "message": message,
+            }),
+            EmitterMessage::Terminal { terminal } => serde_json::json!({
+                "type": self.event_suffix(),
+                "header": header,
+                "terminal": terminal,
+            }),
+            EmitterMessage::Custom { payload, .. } => {
+                // Payloads that carry their own header (e.g. `BuildEvent` with `build_id`) keep it
+                let mut event = payload.clone();
+                if event.get("header").is_none() {
+                    event["header"] = serde_json::to_value(header).unwrap_or_default();
+                }
+                event
+            }
+        }
+    }
+}
+
 /// Single-emitter task state
 pub struct JobEmitter {
     pub job_id: JobId,
@@ -261,6 +338,7 @@ pub struct JobEmitter {
     log: Arc<Mutex<RingBuffer>>,
     status: Arc<RwLock<JobStatus>>,
     terminal_sent: Arc<AtomicBool>,
+    fanout: Option<JobFanout>,
 }
 
 impl JobEmitter {
@@ -273,6 +351,7 @@ impl JobEmitter {
             log: record.log.clone(),
             status: record.status.clone(),
             terminal_sent: record.terminal_sent.clone(),
+            fanout: record.fanout.clone(),
         }
     }
     
@@ -290,66 +369,33 @@ impl JobEmitter {
     
     /// Process an emitter message, returns event name and payload
     pub async fn process(&mut self, msg: EmitterMessage) -> Option<(String, serde_json::Value)> {
-        let prefix = self.kind.event_prefix();
-        
-        match msg {
+        match &msg {
             EmitterMessage::Log { line } => {
                 self.log.lock().await.push(line.clone());
-                let header = self.next_header();
-                let event = serde_json::json!({
-                    "type": "output",
-                    "header": header,
-                    "line": line,
-                });
-                Some((format!("{}:output", prefix), event))
             }
             EmitterMessage::Progress { phase, percent, message } => {
-                {
-                    let mut status = self.status.write().await;
-                    status.phase = Some(phase.clone());
-                    status.percent = Some(percent);
-                    status.message = message.clone();
-                }
-                let header = self.next_header();
-                let event = serde_json::json!({
-                    "type": "progress",
-                    "header": header,
-                    "phase": phase,
-                    "percent": percent,
-                    "message": message,
-                });
-                Some((format!("{}:progress", prefix), event))
+                let mut status = self.status.write().await;
+                status.phase = Some(phase.clone());
+                status.percent = Some(*percent);
+                status.message = message.clone();
             }
             EmitterMessage::Terminal { terminal } => {
                 // Only emit if terminal not already sent
-                if !self.terminal_sent.swap(true, Ordering::SeqCst) {
-                    {
-                        let mut status = self.status.write().await;
-                        status.terminal = Some(terminal.clone());
-                    }
-                    let header = self.next_header();
-                    let event_type = match &terminal {
-                        JobTerminal::Completed { .. } => "completed",
-                        JobTerminal::Cancelled { .. } => "cancelled",
-                        JobTerminal::InternalError { .. } => "internal_error",
-                    };
-                    let event = serde_json::json!({
-                        "type": event_type,
-                        "header": header,
-                        "terminal": terminal,
-                    });
-                    Some((format!("{}:{}", prefix, event_type), event))
-                } else {
-                    None
+                if self.terminal_sent.swap(true, Ordering::SeqCst) {
+                    return None;
                 }
+                self.status.write().await.terminal = Some(terminal.clone());
             }
-            EmitterMessage::Custom { event_suffix, payload } => {
-                let header = self.next_header();
-                let mut event = payload;
-                event["header"] = serde_json::to_value(header).unwrap_or_default();
-                Some((format!("{}:{}", prefix, event_suffix), event))
-            }
+            EmitterMessage::Custom { .. } => {}
+        }
+        
+        let header = self.next_header();
+        let event_name = format!("{}:{}", self.kind.event_prefix(), msg.event_suffix());
+        let event = msg.to_event(&header);
+        if let Some(fanout) = &self.fanout {
+            fanout.publish(&self.job_id, msg);
         }
+        Some((event_name, event))
     }
 }
 
@@ -360,6 +406,7 @@ impl JobEmitter {
 pub struct JobInfo {
     pub id: JobId,
     pub kind: JobKind,
+    pub priority: JobPriority,
     pub started_at_ms: u64,
     pub status: JobStatus,
 }
@@ -369,6 +416,10 @@ pub struct JobManager {
     jobs: DashMap<JobId, Arc<JobRecord>>,
     completed_logs: Arc<RwLock<HashMap<JobId, RingBuffer>>>,
     device_lock: Arc<Mutex<Option<JobId>>>,  // Exclusive device access
+    scheduler: JobScheduler,
+    rtt_filters: DashMap<JobId, rtt::SharedRttFilter>,
+    rtt_channels: DashMap<JobId, Vec<u32>>,
+    fanout: JobFanout,
 }
 
 impl JobManager {
@@ -377,9 +428,18 @@ impl JobManager {
             jobs: DashMap::new(),
             completed_logs: Arc::new(RwLock::new(HashMap::new())),
             device_lock: Arc::new(Mutex::new(None)),
+            scheduler: JobScheduler::new(),
+            rtt_filters: DashMap::new(),
+            rtt_channels: DashMap::new(),
+            fanout: JobFanout::default(),
         }
     }
     
+    /// Event fanout shared by every job's emitter
+    pub fn fanout(&self) -> &JobFanout {
+        &self.fanout
+    }
+    
     /// Try to acquire device lock for a job (Flash/RTT)
     pub async fn try_acquire_device(&self, job_id: &str) -> Result<(), String> {
         let mut lock = self.device_lock.lock().await;
@@ -433,13 +493,17 @@ impl JobManager {
             active_rtt_id,
             active_flash_id,
             active_jobs_count: self.jobs.len(),
+            queued_jobs_count: self.scheduler.queued_count(),
         }
     }
     
     /// Create a new job and return (record, sender for emitter)
     pub fn create_job(&self, kind: JobKind) -> (Arc<JobRecord>, mpsc::Sender<EmitterMessage>) {
         let id = format!("{}_{}", kind.event_prefix(), uuid::Uuid::new_v4().to_string().split('-').next().unwrap_or("x"));
-        let record = Arc::new(JobRecord::new(id.clone(), kind));
+        let mut record = JobRecord::new(id.clone(), kind);
+        record.fanout = Some(self.fanout.clone());
+        let record = Arc::new(record);
+        self.scheduler.register(&id, JobPriority::for_kind(kind));
         self.jobs.insert(id, record.clone());
         
         let (tx, _rx) = mpsc::channel(256);
@@ -461,6 +525,7 @@ impl JobManager {
                 result.push(JobInfo {
                     id: record.id.clone(),
                     kind: record.kind,
+                    priority: self.scheduler.priority(&record.id).unwrap_or(JobPriority::Normal),
                     started_at_ms: record.elapsed_ms(),
                     status,
                 });
@@ -469,6 +534,41 @@ impl JobManager {
         result
     }
     
+    /// Scheduler that orders jobs competing for the same slot
+    pub fn scheduler(&self) -> &JobScheduler {
+        &self.scheduler
+    }
+
+    /// Change the scheduling priority of an active job
+    pub fn set_priority(&self, job_id: &str, priority: JobPriority) -> bool {
+        self.jobs.contains_key(job_id) && self.scheduler.set_priority(job_id, priority)
+    }
+    
+    /// Share a running RTT job's filter with `set_rtt_filter`
+    pub fn register_rtt_filter(&self, job_id: &str, filter: rtt::SharedRttFilter) {
+        self.rtt_filters.insert(job_id.to_string(), filter);
+    }
+
+    /// Record the RTT channels a running job reads
+    pub fn register_rtt_channels(&self, job_id: &str, channels: Vec<u32>) {
+        self.rtt_channels.insert(job_id.to_string(), channels);
+    }
+
+    /// RTT channels a running job reads, `None` once it has finished
+    pub fn rtt_channels(&self, job_id: &str) -> Option<Vec<u32>> {
+        self.rtt_channels.get(job_id).map(|c| c.clone())
+    }
+
+    /// Replace the filter of a running RTT job
+    pub async fn set_rtt_filter(&self, job_id: &str, filter: rtt::RttFilter) -> Result<(), String> {
+        filter.compile()?;
+        let shared = self.rtt_filters.get(job_id)
+            .map(|f| f.clone())
+            .ok_or_else(|| format!("No running RTT job {}", job_id))?;
+        *shared.write().await = filter;
+        Ok(())
+    }
+    
     /// Cancel a job
     pub fn cancel_job(&self, job_id: &str) -> bool {
         if let Some(record) = self.jobs.get(job_id) {
@@ -503,6 +603,9 @@ impl JobManager {
     
     /// Move job to completed and release resources
     pub async fn finish_job(&self, job_id: &str) {
+        self.scheduler.forget(job_id);
+        self.rtt_filters.remove(job_id);
+        self.rtt_channels.remove(job_id);
         if let Some((_, record)) = self.jobs.remove(job_id) {
             // Release device lock if held
             if record.kind.requires_device() {
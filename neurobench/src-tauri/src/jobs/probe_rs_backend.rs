@@ -5,48 +5,80 @@
 
 #![cfg(feature = "hardware")]
 
-use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
 use async_trait::async_trait;
+use thiserror::Error;
 use tokio::sync::Mutex;
 
+use probe_rs::config::TargetSelector;
+use probe_rs::flashing::{download_file_with_options, DownloadOptions, FlashProgress, Format, ProgressEvent};
 use probe_rs::probe::list::Lister;
-use probe_rs::Session;
-use probe_rs::flashing::{download_file, Format};
+use probe_rs::probe::Probe;
+use probe_rs::{MemoryInterface, Permissions};
 
 use crate::jobs::flash::{
-    ProbeBackend, ProbeInfo, FlashConfig, FlashResult, FlashError,
-    FlashErrorCode, FlashMessage, FlashPhase, ProgressCallback,
+    elf_load_segments, verify_segments_with_progress, ProbeBackend, ProbeInfo, FlashConfig, FlashResult,
+    FlashError, FlashErrorCode, FlashMessage, FlashPhase, LoadSegment, PhaseProgress, ProgressCallback,
 };
 
-// ==================== Real Backend State ====================
+// ==================== Errors ====================
 
-/// Internal state for the real probe-rs backend
-struct RealState {
+/// Errors opening a probe or identifying its target
+#[derive(Debug, Error)]
+pub enum ProbeError {
+    #[error("No debug probe found. Connect ST-Link, J-Link, or CMSIS-DAP.")]
+    NoProbe,
+
+    #[error("No probe with serial number {0}")]
+    SerialNotFound(String),
+
+    #[error("Failed to open probe: {0}")]
+    OpenFailed(String),
+
+    #[error("Failed to attach: {0}")]
+    AttachFailed(String),
+}
+
+impl From<ProbeError> for FlashError {
+    fn from(e: ProbeError) -> Self {
+        let code = match &e {
+            ProbeError::NoProbe | ProbeError::SerialNotFound(_) => FlashErrorCode::NoProbeFound,
+            ProbeError::OpenFailed(_) => FlashErrorCode::ProbeOpenFailed,
+            ProbeError::AttachFailed(_) => FlashErrorCode::AttachFailed,
+        };
+        FlashError {
+            code,
+            message: e.to_string(),
+            details: None,
+            retryable: true,
+            os_error_code: None,
+        }
+    }
+}
+
+// ==================== Backend State ====================
+
+/// Internal state for the probe-rs backend
+struct BackendState {
     connected: bool,
-    session: Option<Session>,
     chip: Option<String>,
-    speed_khz: u32,
 }
 
-/// Real probe-rs backend implementation
-pub struct RealProbeRsBackend {
-    state: Arc<Mutex<RealState>>,
+/// probe-rs flash backend
+pub struct ProbeRsBackend {
+    state: Arc<Mutex<BackendState>>,
 }
 
-impl RealProbeRsBackend {
+impl ProbeRsBackend {
     pub fn new() -> Self {
         Self {
-            state: Arc::new(Mutex::new(RealState {
+            state: Arc::new(Mutex::new(BackendState {
                 connected: false,
-                session: None,
                 chip: None,
-                speed_khz: 4000,
             })),
         }
     }
-    
+
     /// List all connected probes
     pub fn list_probes_sync() -> Vec<ProbeInfo> {
         let lister = Lister::new();
@@ -58,67 +90,145 @@ impl RealProbeRsBackend {
             serial: p.serial_number,
         }).collect()
     }
-    
-    /// Connect to the first available probe with the given chip
-    pub async fn connect(&self, chip: &str, speed_khz: Option<u32>) -> Result<(), FlashError> {
-        let mut state = self.state.lock().await;
-        
-        // List probes
-        let lister = Lister::new();
-        let probes = lister.list_all();
-        if probes.is_empty() {
-            return Err(FlashError::no_probe());
-        }
-        
-        // Open first probe
-        let probe_info = probes.into_iter().next().unwrap();
-        let mut probe = probe_info.open().map_err(|e| FlashError {
-            code: FlashErrorCode::ProbeOpenFailed,
-            message: format!("Failed to open probe: {}", e),
-            details: Some(e.to_string()),
-            retryable: true,
-            os_error_code: None,
-        })?;
-        
-        // Set speed if provided
-        if let Some(speed) = speed_khz {
-            let _ = probe.set_speed(speed);
-            state.speed_khz = speed;
-        }
-        
-        // Attach to target
-        let session = probe.attach(chip, probe_rs::Permissions::default()).map_err(|e| FlashError {
-            code: FlashErrorCode::AttachFailed,
-            message: format!("Failed to attach to {}: {}", chip, e),
-            details: Some(e.to_string()),
-            retryable: true,
-            os_error_code: None,
-        })?;
-        
-        state.connected = true;
-        state.session = Some(session);
-        state.chip = Some(chip.to_string());
-        
-        Ok(())
+
+    /// Open the probe with `serial`, or the first one found
+    fn open_probe(serial: Option<&str>) -> Result<Probe, ProbeError> {
+        let probes = Lister::new().list_all();
+        let info = match serial {
+            Some(serial) => probes.into_iter()
+                .find(|p| p.serial_number.as_deref() == Some(serial))
+                .ok_or_else(|| ProbeError::SerialNotFound(serial.to_string()))?,
+            None => probes.into_iter().next().ok_or(ProbeError::NoProbe)?,
+        };
+        info.open().map_err(|e| ProbeError::OpenFailed(e.to_string()))
     }
-    
-    /// Disconnect from the probe
-    pub async fn disconnect(&self) {
-        let mut state = self.state.lock().await;
-        state.session = None;
-        state.connected = false;
-        state.chip = None;
+
+    /// Identify the chip attached to a probe (blocking)
+    pub fn detect_chip(probe_serial: Option<&str>) -> Result<String, ProbeError> {
+        let probe = Self::open_probe(probe_serial)?;
+        let session = probe.attach(TargetSelector::Auto, Permissions::default())
+            .map_err(|e| ProbeError::AttachFailed(e.to_string()))?;
+        Ok(session.target().name.clone())
     }
 }
 
-impl Default for RealProbeRsBackend {
+impl Default for ProbeRsBackend {
     fn default() -> Self {
         Self::new()
     }
 }
 
+fn send_progress(progress: &ProgressCallback, phase: FlashPhase, percent: f32, done: Option<u64>, total: Option<u64>, message: &str) {
+    let _ = progress.try_send(FlashMessage::Progress {
+        phase,
+        percent,
+        done_bytes: done,
+        total_bytes: total,
+        message: Some(message.to_string()),
+    });
+}
+
+/// Map probe-rs flashing events onto the erase (0-20%), program (20-80%) and verify (80-100%) ranges
+///
+/// Erase and program totals come from the flash layout probe-rs reports up
+/// front, so whole-sector erases and padded pages still end at 100% of
+/// their phase. Fill events (reading back bytes the image does not cover)
+/// are reported as the start of erasing.
+fn progress_reporter(progress: ProgressCallback, program_total: u64) -> FlashProgress {
+    let state = std::sync::Mutex::new((PhaseProgress::new(), 0u64, program_total));
+    let send = move |message: FlashMessage| {
+        let _ = progress.try_send(message);
+    };
+
+    FlashProgress::new(move |event| {
+        let Ok(mut guard) = state.lock() else { return };
+        let (tracker, erase_total, program_total) = &mut *guard;
+        match event {
+            ProgressEvent::Initialized { flash_layout } => {
+                *erase_total = flash_layout.sectors().iter().map(|s| s.size()).sum();
+                let pages: u64 = flash_layout.pages().iter().map(|p| p.size() as u64).sum();
+                if pages > 0 {
+                    *program_total = pages;
+                }
+            }
+            ProgressEvent::StartedFilling => {
+                let mut message = tracker.start(FlashPhase::Erasing, 0);
+                if let FlashMessage::Progress { message: text, .. } = &mut message {
+                    *text = Some("Reading back unwritten flash...".to_string());
+                }
+                send(message);
+            }
+            ProgressEvent::StartedErasing => send(tracker.start(FlashPhase::Erasing, *erase_total)),
+            ProgressEvent::SectorErased { size, .. } => send(tracker.advance(size as u64)),
+            ProgressEvent::FinishedErasing => send(tracker.finish()),
+            ProgressEvent::StartedProgramming { length } => {
+                send(tracker.start(FlashPhase::Programming, if length > 0 { length as u64 } else { *program_total }));
+            }
+            ProgressEvent::PageProgrammed { size, .. } => send(tracker.advance(size as u64)),
+            ProgressEvent::FinishedProgramming => send(tracker.finish()),
+            _ => {}
+        }
+    })
+}
+
+/// Attach, download, verify and reset; runs on a blocking thread
+fn flash_blocking(
+    config: &FlashConfig,
+    segments: &[LoadSegment],
+    progress: &ProgressCallback,
+) -> Result<FlashResult, FlashError> {
+    let mut probe = ProbeRsBackend::open_probe(None)?;
+    if let Some(speed) = config.speed_khz {
+        let _ = probe.set_speed(speed);
+    }
+
+    let target = match &config.chip {
+        Some(chip) => TargetSelector::from(chip.as_str()),
+        None => TargetSelector::Auto,
+    };
+    let mut session = probe.attach(target, Permissions::default())
+        .map_err(|e| ProbeError::AttachFailed(e.to_string()))?;
+    let chip = session.target().name.clone();
+
+    let total: u64 = segments.iter().map(|s| s.data.len() as u64).sum();
+    let mut options = DownloadOptions::default();
+    options.progress = Some(progress_reporter(progress.clone(), total));
+    download_file_with_options(&mut session, &config.elf_path, Format::Elf, options)
+        .map_err(|e| FlashError {
+            code: FlashErrorCode::FlashFailed,
+            message: format!("Flash failed: {}", e),
+            details: Some(e.to_string()),
+            retryable: true,
+            os_error_code: None,
+        })?;
+
+    let mut core = session.core(0).map_err(|e| FlashError::flash_failed(format!("Failed to access core: {}", e)))?;
+    if config.verify {
+        let mut tracker = PhaseProgress::new();
+        let _ = progress.try_send(tracker.start(FlashPhase::Verifying, total));
+        verify_segments_with_progress(
+            segments,
+            |address, buf| core.read(address, buf).map_err(|e| e.to_string()),
+            |bytes| {
+                let _ = progress.try_send(tracker.advance(bytes));
+            },
+        )?;
+    }
+
+    send_progress(progress, FlashPhase::Resetting, FlashPhase::Resetting.base_percent(), None, None, "Resetting target...");
+    let _ = core.reset();
+
+    Ok(FlashResult {
+        success: true,
+        bytes_written: total,
+        duration_ms: 0, // Will be set by caller
+        verified: config.verify,
+        chip_resolved: Some(chip),
+    })
+}
+
 #[async_trait]
-impl ProbeBackend for RealProbeRsBackend {
+impl ProbeBackend for ProbeRsBackend {
     async fn flash(
         &self,
         config: &FlashConfig,
@@ -129,150 +239,45 @@ impl ProbeBackend for RealProbeRsBackend {
         if !config.elf_path.exists() {
             return Err(FlashError::elf_not_found(&config.elf_path));
         }
-        
-        // Check cancellation before starting
+        let segments = elf_load_segments(&config.elf_path)?;
+
+        // Check cancellation before starting; probe-rs cannot stop a download midway
         if cancel_check() {
             return Err(FlashError::cancelled());
         }
-        
-        // Emit connecting progress
+
         let _ = progress.send(FlashMessage::Progress {
             phase: FlashPhase::Connecting,
             percent: 0.0,
             done_bytes: None,
             total_bytes: None,
-            message: Some("Connecting to probe...".to_string()),
+            message: Some(match &config.chip {
+                Some(chip) => format!("Connecting to {}...", chip),
+                None => "Connecting to probe, detecting chip...".to_string(),
+            }),
         }).await;
-        
-        // Get chip name (required for real hardware)
-        let chip = config.chip.as_ref().ok_or_else(|| FlashError {
-            code: FlashErrorCode::TargetNotFound,
-            message: "Chip not specified. Provide chip parameter (e.g., 'STM32F407VGTx').".to_string(),
-            details: None,
-            retryable: false,
-            os_error_code: None,
-        })?;
-        
-        // Connect to probe
-        self.connect(chip, config.speed_khz).await?;
-        
-        if cancel_check() {
-            self.disconnect().await;
-            return Err(FlashError::cancelled());
+
+        {
+            let mut state = self.state.lock().await;
+            state.connected = true;
+            state.chip = config.chip.clone();
         }
-        
-        // Flash the file
+
+        let job_config = config.clone();
+        let result = tokio::task::spawn_blocking(move || flash_blocking(&job_config, &segments, &progress))
+            .await
+            .unwrap_or_else(|e| Err(FlashError::flash_failed(format!("Flash task failed: {}", e))));
+
         let mut state = self.state.lock().await;
-        let session = state.session.as_mut().ok_or_else(|| FlashError {
-            code: FlashErrorCode::ProbeOpenFailed,
-            message: "No active session".to_string(),
-            details: None,
-            retryable: true,
-            os_error_code: None,
-        })?;
-        
-        // Emit erasing progress
-        let _ = progress.send(FlashMessage::Progress {
-            phase: FlashPhase::Erasing,
-            percent: 5.0,
-            done_bytes: None,
-            total_bytes: None,
-            message: Some("Erasing flash...".to_string()),
-        }).await;
-        
-        if cancel_check() {
-            drop(state);
-            self.disconnect().await;
-            return Err(FlashError::cancelled());
-        }
-        
-        // Get file size for progress
-        let file_size = std::fs::metadata(&config.elf_path)
-            .map(|m| m.len())
-            .unwrap_or(0);
-        
-        // Emit programming progress
-        let _ = progress.send(FlashMessage::Progress {
-            phase: FlashPhase::Programming,
-            percent: 20.0,
-            done_bytes: Some(0),
-            total_bytes: Some(file_size),
-            message: Some("Programming flash...".to_string()),
-        }).await;
-        
-        // Download the file using probe-rs 0.24 API
-        let download_result = download_file(
-            session,
-            &config.elf_path,
-            Format::Elf,
-        );
-        
-        match download_result {
-            Ok(_) => {
-                // Programming complete
-                let _ = progress.send(FlashMessage::Progress {
-                    phase: FlashPhase::Programming,
-                    percent: 80.0,
-                    done_bytes: Some(file_size),
-                    total_bytes: Some(file_size),
-                    message: Some("Programming complete".to_string()),
-                }).await;
-                
-                // Verify if requested
-                if config.verify {
-                    let _ = progress.send(FlashMessage::Progress {
-                        phase: FlashPhase::Verifying,
-                        percent: 90.0,
-                        done_bytes: None,
-                        total_bytes: None,
-                        message: Some("Verifying...".to_string()),
-                    }).await;
-                }
-                
-                // Reset target
-                let _ = progress.send(FlashMessage::Progress {
-                    phase: FlashPhase::Resetting,
-                    percent: 98.0,
-                    done_bytes: None,
-                    total_bytes: None,
-                    message: Some("Resetting target...".to_string()),
-                }).await;
-                
-                // Reset the core
-                if let Ok(mut core) = session.core(0) {
-                    let _ = core.reset();
-                }
-                
-                drop(state);
-                self.disconnect().await;
-                
-                Ok(FlashResult {
-                    success: true,
-                    bytes_written: file_size,
-                    duration_ms: 0, // Will be set by caller
-                    verified: config.verify,
-                    chip_resolved: Some(chip.clone()),
-                })
-            }
-            Err(e) => {
-                drop(state);
-                self.disconnect().await;
-                
-                Err(FlashError {
-                    code: FlashErrorCode::FlashFailed,
-                    message: format!("Flash failed: {}", e),
-                    details: Some(e.to_string()),
-                    retryable: true,
-                    os_error_code: None,
-                })
-            }
-        }
+        state.connected = false;
+        state.chip = None;
+        result
     }
-    
+
     async fn is_connected(&self) -> bool {
         self.state.lock().await.connected
     }
-    
+
     async fn probe_info(&self) -> Option<ProbeInfo> {
         let state = self.state.lock().await;
         if state.connected {
@@ -291,21 +296,28 @@ impl ProbeBackend for RealProbeRsBackend {
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+
     #[test]
     #[ignore] // Requires real hardware
     fn test_real_probe_list() {
-        let probes = RealProbeRsBackend::list_probes_sync();
+        let probes = ProbeRsBackend::list_probes_sync();
         println!("Found {} probes", probes.len());
         for p in &probes {
             println!("  {:?}", p);
         }
     }
-    
+
+    #[test]
+    #[ignore] // Requires real hardware + target board
+    fn test_detect_chip() {
+        let chip = ProbeRsBackend::detect_chip(None).unwrap();
+        assert!(!chip.is_empty());
+    }
+
     #[tokio::test]
     #[ignore] // Requires real hardware + target board
     async fn test_real_flash_smoke() {
-        let backend = RealProbeRsBackend::new();
+        let backend = ProbeRsBackend::new();
         assert!(!backend.is_connected().await);
     }
 }
@@ -11,8 +11,9 @@ use std::collections::VecDeque;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use async_trait::async_trait;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, RwLock};
 
 use crate::jobs::{
     JobManager, JobKind, JobRecord, JobEmitter, EmitterMessage,
@@ -38,6 +39,9 @@ pub struct RttConfig {
     pub max_batch_interval_ms: u64,
     /// Probe speed in kHz
     pub speed_khz: Option<u32>,
+    /// Initial message filter, adjustable while streaming
+    #[serde(default)]
+    pub filter: RttFilter,
 }
 
 impl Default for RttConfig {
@@ -50,10 +54,101 @@ impl Default for RttConfig {
             max_batch_bytes: 4096,
             max_batch_interval_ms: 100, // Emit at least every 100ms
             speed_khz: Some(4000),
+            filter: RttFilter::default(),
         }
     }
 }
 
+// ==================== RTT Filtering ====================
+
+/// defmt log level, ordered from least to most severe
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum DefmtLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl DefmtLevel {
+    /// Level of a decoded defmt line such as `INFO  msg`, `[WARN ] msg` or `0.001 ERROR msg`
+    pub fn detect(text: &str) -> Option<Self> {
+        text.split_whitespace().take(3).find_map(|token| {
+            let token = token.trim_matches(|c: char| matches!(c, '[' | ']' | '<' | '>' | ':'));
+            match token.to_ascii_uppercase().as_str() {
+                "TRACE" => Some(DefmtLevel::Trace),
+                "DEBUG" => Some(DefmtLevel::Debug),
+                "INFO" => Some(DefmtLevel::Info),
+                "WARN" | "WARNING" => Some(DefmtLevel::Warn),
+                "ERROR" => Some(DefmtLevel::Error),
+                _ => None,
+            }
+        })
+    }
+}
+
+/// Which RTT messages reach the UI
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RttFilter {
+    /// Only this channel
+    pub channel: Option<u32>,
+    /// Minimum level; lines without a defmt level always pass
+    pub level: Option<DefmtLevel>,
+    /// Regex applied to the decoded text
+    pub pattern: Option<String>,
+    /// Max filtered lines held before emission
+    pub max_buffer_lines: usize,
+}
+
+impl Default for RttFilter {
+    fn default() -> Self {
+        Self {
+            channel: None,
+            level: None,
+            pattern: None,
+            max_buffer_lines: 1000,
+        }
+    }
+}
+
+impl RttFilter {
+    /// Compile the pattern, rejecting invalid regexes
+    pub fn compile(&self) -> Result<CompiledRttFilter, String> {
+        let regex = self.pattern.as_deref()
+            .filter(|p| !p.is_empty())
+            .map(|p| Regex::new(p).map_err(|e| format!("Invalid RTT filter pattern: {}", e)))
+            .transpose()?;
+        Ok(CompiledRttFilter { filter: self.clone(), regex })
+    }
+}
+
+/// Filter with its pattern compiled, used by the job loop
+#[derive(Debug, Clone)]
+pub struct CompiledRttFilter {
+    pub filter: RttFilter,
+    regex: Option<Regex>,
+}
+
+impl CompiledRttFilter {
+    pub fn matches(&self, msg: &RttMessage) -> bool {
+        if self.filter.channel.is_some_and(|c| c != msg.channel) {
+            return false;
+        }
+        if let Some(min) = self.filter.level {
+            if DefmtLevel::detect(&msg.text).is_some_and(|level| level < min) {
+                return false;
+            }
+        }
+        self.regex.as_ref().is_none_or(|re| re.is_match(&msg.text))
+    }
+}
+
+/// Filter shared between the RTT job loop and `rtt_set_filter`
+pub type SharedRttFilter = Arc<RwLock<RttFilter>>;
+
 // ==================== RTT Message Types ====================
 
 /// Single RTT message
@@ -357,10 +452,14 @@ pub async fn run_rtt_job<B: RttBackend + 'static>(
     if config.chip.is_empty() {
         return Err("Chip not specified".to_string());
     }
+    config.filter.compile()?;
     
     // Create job
     let (record, _tx) = job_manager.create_job(JobKind::Rtt);
     let job_id = record.id.clone();
+    let filter: SharedRttFilter = Arc::new(RwLock::new(config.filter.clone()));
+    job_manager.register_rtt_filter(&job_id, filter.clone());
+    job_manager.register_rtt_channels(&job_id, config.channels.clone());
     
     // Try to acquire device lock (exclusive with Flash)
     if let Err(msg) = job_manager.try_acquire_device(&job_id).await {
@@ -389,6 +488,7 @@ pub async fn run_rtt_job<B: RttBackend + 'static>(
             record_clone,
             backend,
             config,
+            filter,
             job_manager_clone,
             emit_clone,
         ).await;
@@ -402,6 +502,7 @@ async fn run_rtt_worker<B: RttBackend + 'static>(
     record: Arc<JobRecord>,
     backend: Arc<B>,
     config: RttConfig,
+    filter: SharedRttFilter,
     job_manager: Arc<JobManager>,
     emit_event: impl Fn(String, serde_json::Value) + Send + Sync,
 ) {
@@ -409,6 +510,12 @@ async fn run_rtt_worker<B: RttBackend + 'static>(
     let start = Instant::now();
     let mut total_messages = 0u64;
     let mut total_dropped = 0u64;
+    let mut stats = FilterStats::default();
+    // Validated in run_rtt_job; later updates are validated by rtt_set_filter
+    let mut compiled = config.filter.compile().unwrap_or_else(|_| CompiledRttFilter {
+        filter: RttFilter::default(),
+        regex: None,
+    });
     
     // Create channel for RTT data
     let (data_tx, mut data_rx) = mpsc::channel::<RttMessage>(1024);
@@ -457,8 +564,24 @@ async fn run_rtt_worker<B: RttBackend + 'static>(
             msg = data_rx.recv() => {
                 match msg {
                     Some(rtt_msg) => {
+                        // Pick up filter changes without restarting the stream
+                        {
+                            let current = filter.read().await;
+                            if *current != compiled.filter {
+                                if let Ok(updated) = current.compile() {
+                                    compiled = updated;
+                                }
+                            }
+                        }
+                        if !compiled.matches(&rtt_msg) {
+                            stats.dropped_count += 1;
+                            continue;
+                        }
+                        stats.passed_count += 1;
+                        let max_lines = config.max_batch_lines.min(compiled.filter.max_buffer_lines.max(1));
+                        
                         // Add message to batch
-                        if batch.len() >= config.max_batch_lines || 
+                        if batch.len() >= max_lines || 
                            batch.total_bytes >= config.max_batch_bytes {
                             // Batch full, drop oldest if needed
                             total_dropped += 1;
@@ -469,9 +592,9 @@ async fn run_rtt_worker<B: RttBackend + 'static>(
                         }
                         
                         // Emit if batch is full
-                        if batch.len() >= config.max_batch_lines ||
+                        if batch.len() >= max_lines ||
                            batch.total_bytes >= config.max_batch_bytes {
-                            emit_batch(&mut emitter, &mut batch, &emit_event).await;
+                            emit_batch(&mut emitter, &mut batch, &stats, &emit_event).await;
                             last_emit = Instant::now();
                         }
                     }
@@ -486,7 +609,7 @@ async fn run_rtt_worker<B: RttBackend + 'static>(
             _ = tokio::time::sleep(poll_interval) => {
                 // Check if we should emit based on time
                 if !batch.is_empty() && last_emit.elapsed() >= batch_interval {
-                    emit_batch(&mut emitter, &mut batch, &emit_event).await;
+                    emit_batch(&mut emitter, &mut batch, &stats, &emit_event).await;
                     last_emit = Instant::now();
                 }
                 
@@ -500,7 +623,7 @@ async fn run_rtt_worker<B: RttBackend + 'static>(
     
     // Emit any remaining batch
     if !batch.is_empty() {
-        emit_batch(&mut emitter, &mut batch, &emit_event).await;
+        emit_batch(&mut emitter, &mut batch, &stats, &emit_event).await;
     }
     
     // Stop backend
@@ -540,10 +663,18 @@ async fn run_rtt_worker<B: RttBackend + 'static>(
     job_manager.finish_job(&record.id).await;
 }
 
-/// Helper to emit a batch through the emitter
+/// Messages kept and dropped by the filter so far
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FilterStats {
+    pub passed_count: u64,
+    pub dropped_count: u64,
+}
+
+/// Helper to emit a batch, followed by a progress event with filter statistics
 async fn emit_batch(
     emitter: &mut JobEmitter,
     batch: &mut RttBatch,
+    stats: &FilterStats,
     emit_event: &impl Fn(String, serde_json::Value),
 ) {
     if batch.is_empty() {
@@ -565,6 +696,17 @@ async fn emit_batch(
         emit_event(event_name, p);
     }
     
+    if let Some((event_name, payload)) = emitter.process(EmitterMessage::Progress {
+        phase: "streaming".to_string(),
+        percent: 0.0,
+        message: None,
+    }).await {
+        let mut p = payload;
+        p["passed_count"] = serde_json::json!(stats.passed_count);
+        p["dropped_count"] = serde_json::json!(stats.dropped_count);
+        emit_event(event_name, p);
+    }
+    
     batch.clear();
 }
 
@@ -638,6 +780,32 @@ mod tests {
         assert_eq!(batch.total_bytes, 0);
     }
     
+    #[test]
+    fn test_rtt_filter_matching() {
+        let msg = |channel: u32, text: &str| RttMessage { channel, text: text.to_string(), timestamp_ms: 0 };
+        assert_eq!(DefmtLevel::detect("0.000123 WARN  low battery"), Some(DefmtLevel::Warn));
+        assert_eq!(DefmtLevel::detect("[ERROR] fault"), Some(DefmtLevel::Error));
+        assert_eq!(DefmtLevel::detect("[0] Mock RTT message #1"), None);
+
+        let filter = RttFilter {
+            channel: Some(0),
+            level: Some(DefmtLevel::Info),
+            pattern: Some(r"adc=\d+".to_string()),
+            ..Default::default()
+        }.compile().unwrap();
+        assert!(filter.matches(&msg(0, "INFO adc=512")));
+        assert!(!filter.matches(&msg(1, "INFO adc=512")));
+        assert!(!filter.matches(&msg(0, "DEBUG adc=512")));
+        assert!(!filter.matches(&msg(0, "INFO tick")));
+        assert!(filter.matches(&msg(0, "adc=7")));
+
+        assert!(RttFilter { pattern: Some("(".to_string()), ..Default::default() }.compile().is_err());
+
+        // The UI sends only the fields it changed
+        let partial: RttFilter = serde_json::from_str(r#"{"channel":1}"#).unwrap();
+        assert_eq!(partial, RttFilter { channel: Some(1), ..Default::default() });
+    }
+    
     #[tokio::test]
     async fn test_device_lock_exclusivity() {
         let manager = Arc::new(JobManager::new());
@@ -0,0 +1,303 @@
+// Job Scheduler
+//
+// One running job per kind, the rest queued by priority then arrival.
+// Realtime jobs preempt lower-priority jobs of the same kind; the preempted
+// job is re-queued and runs again once the slot frees up. Scripts are never
+// preempted: a rerun would repeat side effects of the lines already executed.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+use super::{JobId, JobKind};
+
+/// Scheduling priority, ordered from most to least urgent
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPriority {
+    Realtime,
+    High,
+    Normal,
+    Low,
+    Background,
+}
+
+impl JobPriority {
+    /// Default priority for a kind of job
+    pub fn for_kind(kind: JobKind) -> Self {
+        match kind {
+            JobKind::Flash => JobPriority::Realtime,
+            JobKind::Rtt | JobKind::RegisterWatch | JobKind::Serial => JobPriority::High,
+            JobKind::Build | JobKind::Script => JobPriority::Normal,
+            JobKind::Agent => JobPriority::Low,
+            JobKind::Index => JobPriority::Background,
+        }
+    }
+}
+
+struct QueuedJob {
+    job_id: JobId,
+    kind: JobKind,
+    seq: u64,
+}
+
+struct RunningJob {
+    kind: JobKind,
+    attempt: CancellationToken,
+    preempted: bool,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    next_seq: u64,
+    priorities: HashMap<JobId, JobPriority>,
+    queued: Vec<QueuedJob>,
+    running: HashMap<JobId, RunningJob>,
+}
+
+impl SchedulerState {
+    fn priority(&self, job_id: &str) -> JobPriority {
+        self.priorities.get(job_id).copied().unwrap_or(JobPriority::Normal)
+    }
+
+    /// Free slot for the kind, and no better-ranked job queued for it
+    fn can_start(&self, job_id: &str, kind: JobKind) -> bool {
+        if self.running.values().any(|r| r.kind == kind) {
+            return false;
+        }
+        let Some(me) = self.queued.iter().find(|q| q.job_id == job_id) else {
+            return false;
+        };
+        let rank = (self.priority(job_id), me.seq);
+        !self.queued.iter().any(|q| q.kind == kind && (self.priority(&q.job_id), q.seq) < rank)
+    }
+
+    /// Cancel the current attempt of lower-priority running jobs of `kind`
+    fn preempt_below(&mut self, kind: JobKind, priority: JobPriority) {
+        if !preemptible(kind) {
+            return;
+        }
+        let priorities = &self.priorities;
+        for (id, running) in self.running.iter_mut() {
+            let running_priority = priorities.get(id).copied().unwrap_or(JobPriority::Normal);
+            if running.kind == kind && running_priority > priority && !running.preempted {
+                log::info!("Preempting {} for a {:?} job", id, priority);
+                running.preempted = true;
+                running.attempt.cancel();
+            }
+        }
+    }
+}
+
+/// Whether a running job of `kind` may be cancelled and restarted from the top
+fn preemptible(kind: JobKind) -> bool {
+    !matches!(kind, JobKind::Script)
+}
+
+/// Priority scheduler shared by the job runners
+#[derive(Default)]
+pub struct JobScheduler {
+    state: Mutex<SchedulerState>,
+    notify: Notify,
+}
+
+impl JobScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, SchedulerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record the priority a job will be scheduled with
+    pub fn register(&self, job_id: &str, priority: JobPriority) {
+        self.state().priorities.insert(job_id.to_string(), priority);
+    }
+
+    pub fn priority(&self, job_id: &str) -> Option<JobPriority> {
+        self.state().priorities.get(job_id).copied()
+    }
+
+    /// Change a job's priority; returns false for unknown jobs
+    pub fn set_priority(&self, job_id: &str, priority: JobPriority) -> bool {
+        let mut state = self.state();
+        let Some(current) = state.priorities.get_mut(job_id) else {
+            return false;
+        };
+        *current = priority;
+        drop(state);
+        // Queued jobs re-check their rank and may now preempt
+        self.notify.notify_waiters();
+        true
+    }
+
+    /// Wait for the job's turn to run
+    ///
+    /// Returns a token for this attempt, cancelled if the job is preempted or
+    /// `cancel` fires. Returns `None` if `cancel` fires while still queued.
+    pub async fn acquire(&self, job_id: &str, kind: JobKind, cancel: &CancellationToken) -> Option<CancellationToken> {
+        {
+            let mut state = self.state();
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.queued.push(QueuedJob { job_id: job_id.to_string(), kind, seq });
+        }
+
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            {
+                let mut state = self.state();
+                if state.can_start(job_id, kind) {
+                    state.queued.retain(|q| q.job_id != job_id);
+                    let attempt = cancel.child_token();
+                    state.running.insert(job_id.to_string(), RunningJob { kind, attempt: attempt.clone(), preempted: false });
+                    return Some(attempt);
+                }
+                let priority = state.priority(job_id);
+                if priority == JobPriority::Realtime {
+                    state.preempt_below(kind, priority);
+                }
+            }
+
+            tokio::select! {
+                _ = &mut notified => {}
+                _ = cancel.cancelled() => {
+                    self.state().queued.retain(|q| q.job_id != job_id);
+                    self.notify.notify_waiters();
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Give up the job's slot; returns true if it was preempted and should be re-queued
+    pub fn release(&self, job_id: &str) -> bool {
+        let preempted = self.state().running.remove(job_id).is_some_and(|r| r.preempted);
+        self.notify.notify_waiters();
+        preempted
+    }
+
+    /// Drop everything known about a finished job
+    pub fn forget(&self, job_id: &str) {
+        let mut state = self.state();
+        state.priorities.remove(job_id);
+        state.queued.retain(|q| q.job_id != job_id);
+        state.running.remove(job_id);
+        drop(state);
+        self.notify.notify_waiters();
+    }
+
+    /// Jobs waiting for a slot
+    pub fn queued_count(&self) -> usize {
+        self.state().queued.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_queue_order_by_priority() {
+        let scheduler = Arc::new(JobScheduler::new());
+        let cancel = CancellationToken::new();
+        scheduler.register("script_a", JobPriority::Normal);
+        scheduler.register("script_b", JobPriority::Low);
+        scheduler.register("script_c", JobPriority::High);
+
+        scheduler.acquire("script_a", JobKind::Script, &cancel).await.unwrap();
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut waiters = Vec::new();
+        for id in ["script_b", "script_c"] {
+            let (scheduler, cancel, order) = (scheduler.clone(), cancel.clone(), order.clone());
+            waiters.push(tokio::spawn(async move {
+                scheduler.acquire(id, JobKind::Script, &cancel).await.unwrap();
+                order.lock().unwrap().push(id);
+                scheduler.release(id);
+            }));
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(scheduler.queued_count(), 2);
+
+        // Other kinds are not held up
+        scheduler.register("agent_x", JobPriority::Low);
+        scheduler.acquire("agent_x", JobKind::Agent, &cancel).await.unwrap();
+
+        assert!(!scheduler.release("script_a"));
+        for waiter in waiters {
+            waiter.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec!["script_c", "script_b"]);
+    }
+
+    #[tokio::test]
+    async fn test_realtime_preempts_and_requeues() {
+        let scheduler = Arc::new(JobScheduler::new());
+        let cancel = CancellationToken::new();
+        scheduler.register("build_low", JobPriority::Normal);
+        let attempt = scheduler.acquire("build_low", JobKind::Build, &cancel).await.unwrap();
+
+        scheduler.register("build_rt", JobPriority::Realtime);
+        let realtime = {
+            let (scheduler, cancel) = (scheduler.clone(), cancel.clone());
+            tokio::spawn(async move { scheduler.acquire("build_rt", JobKind::Build, &cancel).await.is_some() })
+        };
+
+        tokio::time::timeout(Duration::from_secs(1), attempt.cancelled()).await.unwrap();
+        assert!(!cancel.is_cancelled());
+        assert!(scheduler.release("build_low"));
+        assert!(realtime.await.unwrap());
+
+        // The preempted job queues behind the realtime job
+        let requeued = {
+            let (scheduler, cancel) = (scheduler.clone(), cancel.clone());
+            tokio::spawn(async move { scheduler.acquire("build_low", JobKind::Build, &cancel).await.is_some() })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(scheduler.queued_count(), 1);
+        scheduler.forget("build_rt");
+        assert!(requeued.await.unwrap());
+
+        // Cancelling a queued job removes it
+        let waiter = {
+            let scheduler = scheduler.clone();
+            let token = CancellationToken::new();
+            let child = token.clone();
+            let handle = tokio::spawn(async move { scheduler.acquire("script_other", JobKind::Script, &child).await });
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            token.cancel();
+            handle
+        };
+        assert!(waiter.await.unwrap().is_none());
+        assert_eq!(scheduler.queued_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_realtime_script_does_not_preempt() {
+        let scheduler = Arc::new(JobScheduler::new());
+        let cancel = CancellationToken::new();
+        scheduler.register("script_low", JobPriority::Normal);
+        let attempt = scheduler.acquire("script_low", JobKind::Script, &cancel).await.unwrap();
+
+        scheduler.register("script_rt", JobPriority::Realtime);
+        let realtime = {
+            let (scheduler, cancel) = (scheduler.clone(), cancel.clone());
+            tokio::spawn(async move { scheduler.acquire("script_rt", JobKind::Script, &cancel).await.is_some() })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!attempt.is_cancelled());
+        assert_eq!(scheduler.queued_count(), 1);
+
+        // The realtime script runs next, once the running script finishes
+        assert!(!scheduler.release("script_low"));
+        assert!(realtime.await.unwrap());
+    }
+}
@@ -27,6 +27,7 @@ pub mod profiler;
 pub mod registers;
 pub mod performance;
 pub mod toolchain;
+pub mod config;
 
 #[cfg(test)]
 mod tests;
@@ -48,16 +49,23 @@ pub struct AppState {
     pub job_manager: Arc<jobs::JobManager>,
     pub tool_registry: Arc<Mutex<agents::ToolRegistry>>,
     pub audit_log: Arc<Mutex<agents::AuditLog>>,
+    pub serial_logger: Arc<std::sync::Mutex<serial::logger::SerialLogger>>,
+    pub ai_sessions: Arc<ai::memory::SessionStore>,
+    pub config: Arc<std::sync::Mutex<config::AppConfig>>,
 }
 
 impl AppState {
     pub fn new() -> Self {
+        let job_manager = Arc::new(jobs::JobManager::new());
         Self {
             orchestrator: Arc::new(Mutex::new(agents::Orchestrator::new())),
-            build_manager: Arc::new(toolchain::streaming_build::BuildManager::new()),
-            job_manager: Arc::new(jobs::JobManager::new()),
+            build_manager: Arc::new(toolchain::streaming_build::BuildManager::with_job_manager(job_manager.clone())),
+            job_manager,
             tool_registry: Arc::new(Mutex::new(agents::create_default_registry())),
             audit_log: Arc::new(Mutex::new(agents::AuditLog::new())),
+            serial_logger: Arc::new(std::sync::Mutex::new(serial::logger::SerialLogger::new())),
+            ai_sessions: Arc::new(ai::memory::SessionStore::default()),
+            config: Arc::new(std::sync::Mutex::new(config::load_config())),
         }
     }
 }
@@ -73,6 +81,11 @@ pub fn run() {
     // Initialize logging
     env_logger::init();
     log::info!("NeuroBench starting...");
+    terminal::aliases::load_aliases();
+    terminal::history::open_history();
+    snippets::user::open_user_snippets();
+    terminal::themes::load_user_themes();
+    agents::indexer::open_index();
     
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
@@ -82,6 +95,15 @@ pub fn run() {
             commands::project::save_project,
             commands::project::load_project,
             commands::project::list_projects,
+            commands::project::create_project_from_template,
+            commands::project::analyze_dependencies,
+            commands::project::workspace::create_workspace,
+            commands::project::workspace::load_workspace,
+            commands::project::workspace::save_workspace,
+            commands::project::workspace::add_project_to_workspace,
+            commands::project::workspace::remove_project_from_workspace,
+            commands::project::workspace::set_active_project,
+            commands::project::workspace::build_workspace,
             
             // FSM commands
             commands::fsm::add_node,
@@ -91,12 +113,21 @@ pub fn run() {
             commands::fsm::remove_edge,
             commands::fsm::update_edge,
             commands::fsm::simulate_step,
+            commands::fsm::set_simulation_variables,
+            commands::fsm::get_simulation_config,
+            commands::fsm::set_simulation_config,
+            commands::fsm::fsm_export_dot,
+            commands::fsm::fsm_import_dot,
+            commands::fsm::fsm_export_plantuml,
+            commands::fsm::fsm_import_plantuml,
+            commands::fsm::fsm_import_from_c,
             commands::fsm::simulate_run,
             commands::fsm::simulate_stop,
             
             // Code generation
             commands::codegen::generate_code,
             commands::codegen::get_supported_targets,
+            commands::codegen::generate_code_bundle,
             
             // Hardware commands
             commands::hardware::detect_devices,
@@ -104,23 +135,48 @@ pub fn run() {
             commands::hardware::disconnect_device,
             commands::hardware::flash_firmware,
             commands::hardware::read_telemetry,
+            commands::hardware::hardware_get_device_info,
+            commands::hardware::hardware_read_elf_metadata,
+            hardware_compare_versions,
             
             // AI commands
             ai_chat,
             ai_status,
             ai_generate_code,
             ai_parse_fsm,
+            ai_clear_session,
+            ai_get_session_summary,
+            ai_explain_diff,
             
             // Serial port & MCU
-            list_serial_ports,
+            commands::hardware::list_serial_ports,
             get_mcu_list,
+            get_mcu_variants,
             
             // Driver generation
             generate_gpio_driver,
             generate_uart_driver,
             generate_spi_driver,
+            generate_portable_driver,
             generate_i2c_driver,
+            generate_i2s_driver,
             generate_can_driver,
+            import_can_dbc,
+            generate_can_dbc_driver,
+            generate_usb_driver,
+            generate_usb_dfu_bootloader,
+            generate_sdmmc_driver,
+            generate_sensor_driver,
+            generate_display_driver,
+            generate_crc_code,
+            generate_button_debounce,
+            generate_dma_pingpong_buffer,
+            generate_protocol_framer,
+            generate_soft_i2c_driver,
+            generate_onewire_driver,
+            generate_micropython_driver,
+            generate_encoder_driver,
+            generate_stepper_driver,
             generate_modbus_driver,
             generate_rtos_code,
             generate_driver_ai,
@@ -141,6 +197,7 @@ pub fn run() {
             generate_adc_code,
             generate_dac_code,
             generate_pwm_code,
+            generate_temperature_sensor_code,
             
             // Multi-MCU support
             get_supported_mcus,
@@ -158,6 +215,8 @@ pub fn run() {
             
             // Wireless generation
             generate_ble_service,
+            generate_ble_advertising_data,
+            generate_softdevice_init,
             generate_wifi_config,
             generate_lora_config,
             
@@ -171,8 +230,13 @@ pub fn run() {
             // Security generation
             generate_bootloader,
             generate_ota_client,
+            delta_ota_create_patch,
             generate_secure_boot,
             generate_crypto_utils,
+            generate_kdf_code,
+            
+            // Power management generation
+            generate_pmic_config,
             
             // Export commands
             export_code_to_file,
@@ -182,6 +246,10 @@ pub fn run() {
             // Terminal commands
             execute_terminal_command,
             get_terminal_welcome,
+            terminal_spawn_pty,
+            terminal_pty_write,
+            terminal_pty_resize,
+            terminal_pty_kill,
             
             // Agent commands
             list_agents,
@@ -190,10 +258,13 @@ pub fn run() {
             agent_chat,
             execute_tool,
             update_fsm_context,
+            agent_generate_tests,
             
             // Project persistence
             save_project_file,
             load_project_file,
+            project_get_schema_version,
+            project_close,
             
             // System info
             get_system_info,
@@ -209,6 +280,10 @@ pub fn run() {
             git_commit,
             git_history,
             git_diff,
+            git_create_tag,
+            git_list_tags,
+            git_bump_version,
+            git_generate_gitignore,
             
             // QEMU simulation
             qemu_check,
@@ -221,32 +296,48 @@ pub fn run() {
             cloud_import_project,
             cloud_generate_share_id,
             cloud_collect_files,
+            cloud_upload_project,
+            cloud_download_project,
+            cloud_sync_project,
             
             // Templates
             templates_get_all,
             templates_get_by_id,
             templates_get_categories,
+            templates_instantiate,
             
             // Snippets
             snippets_get_all,
             snippets_search,
             snippets_get_by_id,
+            snippets_add,
+            snippets_update,
+            snippets_delete,
+            snippets_list_by_tag,
+            snippets_export,
+            snippets_refine_with_ai,
+            snippets_generate_from_description,
             
             // Memory analyzer
             memory_estimate,
+            memory_visualize_map,
             memory_get_mcu_configs,
             
             // Power estimator
-            power_estimate,
+            power_estimate_detailed,
             power_get_mcu_specs,
+            power_get_peripherals,
             
             // Pin configuration
             pins_get_packages,
             pins_generate_code,
+            pins_find_pins_for_signal,
+            pins_get_pin_functions,
             
             // Build system
             build_generate_makefile,
             build_generate_cmake,
+            build_generate_compile_commands,
             build_check_toolchain,
             
             // Serial monitor
@@ -255,25 +346,53 @@ pub fn run() {
             serial_format_data,
             serial_parse_escape,
             serial_calculate_checksum,
+            serial_decode_protocol,
+            serial_analyze_i2c,
+            serial_analyze_spi,
+            serial_render_scope,
+            serial_start_logging,
+            serial_stop_logging,
+            serial_monitor_start,
+            serial_monitor_stop,
             
             // Documentation generator
             docs_generate,
             docs_generate_doxyfile,
             docs_extract_functions,
+            docs_generate_ai,
+            docs_generate_register_map,
+            docs_generate_traceability,
             
             // Profiler
             profiler_analyze,
             profiler_estimate_timing,
+            profiler_estimate_isr_latency,
+            profiler_estimate_wcet,
+            profiler_analyze_dma,
+            profiler_symbolize_stack,
             
             // Registers
             registers_get_peripherals,
             registers_get_gpio,
             registers_generate_code,
+            registers_load_svd,
+            registers_get_svd_peripherals,
+            registers_read_live,
+            registers_write_live,
+            registers_watch_live,
             
             // Advanced Terminal
             terminal_execute_advanced,
+            terminal_execute_pipeline,
+            terminal_search_history,
+            terminal_clear_history,
+            terminal_execute_script,
+            terminal_generate_flash_script,
             terminal_get_completions,
+            terminal_set_completion_pins,
             terminal_get_themes,
+            terminal_import_theme,
+            terminal_export_theme,
             terminal_get_welcome,
             terminal_parse_command,
             
@@ -281,13 +400,25 @@ pub fn run() {
             performance_get_system_metrics,
             performance_get_process_list,
             performance_get_embedded_metrics,
+            performance_start_embedded_metrics,
+            generate_rtos_metrics_task,
+            performance_get_throttle_status,
+            
+            // App Config
+            get_app_config,
+            set_app_config,
             
             // Toolchain & IDE Loop
             toolchain_discover,
+            toolchain_download,
             toolchain_build,
             toolchain_clean,
             toolchain_size_report,
             toolchain_parse_map,
+            toolchain_bloat_report,
+            generate_cmake_toolchain_file,
+            generate_openocd_config,
+            generate_jlink_script,
             probe_list,
             probe_connect,
             probe_disconnect,
@@ -297,6 +428,7 @@ pub fn run() {
             probe_resume,
             probe_read_memory,
             probe_read_registers,
+            probe_detect_softdevice,
             rtt_start,
             rtt_read,
             rtt_stop,
@@ -310,6 +442,7 @@ pub fn run() {
             streaming_build_get_diagnostics,
             streaming_build_get_latest_artifacts,
             streaming_build_get_artifacts,
+            streaming_build_sign_artifact,
             
             // Flash (live progress + cancel)
             flash_start,
@@ -318,12 +451,19 @@ pub fn run() {
             // RTT Job Streaming (batched events + stop)
             rtt_stream_start,
             rtt_stream_stop,
+            rtt_set_filter,
             
             // Generic Job Management
             job_list,
             job_get_status,
             job_get_log,
             job_cancel,
+            job_subscribe,
+            job_set_priority,
+            
+            // Workspace Index
+            index_workspace,
+            index_query,
             
             // Run Chain (build → flash → rtt)
             run_chain,
@@ -342,12 +482,29 @@ pub fn run() {
             patch_apply,
             patch_reject,
             patch_get_pending,
+            patch_undo,
+            patch_redo,
+            patch_history_state,
+            patch_clear_history,
+            agent_get_reasoning_trace,
             
             // AI Model Management
             ai_get_providers,
             ai_set_provider,
         ])
         .manage(AppState::new())
+        .setup(|app| {
+            let handle = app.handle().clone();
+            terminal::pty::set_event_sink(move |event| match event {
+                terminal::pty::PtyEvent::Output(output) => {
+                    let _ = handle.emit(terminal::pty::PTY_OUTPUT_EVENT, &output);
+                }
+                terminal::pty::PtyEvent::Exit(exit) => {
+                    let _ = handle.emit(terminal::pty::PTY_EXIT_EVENT, &exit);
+                }
+            });
+            Ok(())
+        })
         .run(tauri::generate_context!())
         .expect("error while running NeuroBench");
 }
@@ -366,14 +523,53 @@ fn get_system_info() -> serde_json::Value {
     })
 }
 
-/// Chat with AI assistant
+/// Chat with AI assistant, remembering earlier turns of `session_id`
 #[tauri::command]
-async fn ai_chat(message: String) -> Result<String, String> {
+async fn ai_chat(state: State<'_, AppState>, message: String, session_id: Option<String>) -> Result<String, String> {
     let service = AIService::new();
     if !service.is_available() {
         return Err("AI not configured. Set GEMINI_API_KEY environment variable.".to_string());
     }
-    service.chat(&message, None).await
+    let sessions = state.ai_sessions.clone();
+    let history = match &session_id {
+        Some(id) => ai::memory::session_context(&sessions, id).await,
+        None => None,
+    };
+    let symbols = {
+        let message = message.clone();
+        tokio::task::spawn_blocking(move || agents::indexer::index_context(&message)).await.ok().flatten()
+    };
+    let context = [commands::project::workspace::active_project_context(), symbols, history]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let reply = service.chat(&message, (!context.is_empty()).then_some(context.as_str())).await?;
+    if let Some(id) = &session_id {
+        ai::memory::record_exchange(&sessions, id, &message, &reply, &service).await;
+    }
+    Ok(reply)
+}
+
+/// Forget a chat session
+#[tauri::command]
+async fn ai_clear_session(state: State<'_, AppState>, session_id: String) -> Result<serde_json::Value, String> {
+    let cleared = state.ai_sessions.lock().await.remove(&session_id).is_some();
+    Ok(serde_json::json!({ "session_id": session_id, "cleared": cleared }))
+}
+
+/// Summary and turn count of a chat session
+#[tauri::command]
+async fn ai_get_session_summary(state: State<'_, AppState>, session_id: String) -> Result<serde_json::Value, String> {
+    let sessions = state.ai_sessions.lock().await;
+    let memory = sessions.get(&session_id)
+        .ok_or_else(|| format!("Unknown session: {}", session_id))?;
+    Ok(serde_json::json!({
+        "session_id": session_id,
+        "summary": memory.summary,
+        "turns": memory.turns.len(),
+        "max_turns": memory.max_turns,
+    }))
 }
 
 /// Check AI status
@@ -397,6 +593,11 @@ async fn ai_generate_code(
     if !service.is_available() {
         return Err("AI not configured. Set GEMINI_API_KEY environment variable.".to_string());
     }
+    // Target the active workspace project's MCU when there is one
+    let language = match commands::project::workspace::active_mcu() {
+        Some(mcu) => format!("{} for {}", language, mcu),
+        None => language,
+    };
     service.generate_fsm_code(&nodes, &edges, &language).await
 }
 
@@ -410,37 +611,14 @@ async fn ai_parse_fsm(description: String) -> Result<String, String> {
     service.parse_fsm_from_description(&description).await
 }
 
-/// List available serial ports
+/// Explain the difference between two versions of code
 #[tauri::command]
-fn list_serial_ports() -> Result<Vec<serde_json::Value>, String> {
-    match serialport::available_ports() {
-        Ok(ports) => {
-            let result: Vec<serde_json::Value> = ports.iter().map(|p| {
-                let port_type = match &p.port_type {
-                    serialport::SerialPortType::UsbPort(info) => {
-                        serde_json::json!({
-                            "type": "USB",
-                            "vid": format!("{:04X}", info.vid),
-                            "pid": format!("{:04X}", info.pid),
-                            "manufacturer": info.manufacturer.clone().unwrap_or_default(),
-                            "product": info.product.clone().unwrap_or_default(),
-                            "serial": info.serial_number.clone().unwrap_or_default(),
-                        })
-                    },
-                    serialport::SerialPortType::PciPort => serde_json::json!({"type": "PCI"}),
-                    serialport::SerialPortType::BluetoothPort => serde_json::json!({"type": "Bluetooth"}),
-                    serialport::SerialPortType::Unknown => serde_json::json!({"type": "Unknown"}),
-                };
-                serde_json::json!({
-                    "name": p.port_name,
-                    "info": port_type,
-                })
-            }).collect();
-            log::info!("Found {} serial ports", result.len());
-            Ok(result)
-        },
-        Err(e) => Err(format!("Failed to list ports: {}", e)),
+async fn ai_explain_diff(original_code: String, modified_code: String, language: String) -> Result<String, String> {
+    let service = AIService::new();
+    if !service.is_available() {
+        return Err("AI not configured. Set GEMINI_API_KEY environment variable.".to_string());
     }
+    service.explain_diff(&original_code, &modified_code, &language).await
 }
 
 /// Get list of supported MCUs
@@ -459,16 +637,44 @@ fn get_mcu_list() -> Vec<serde_json::Value> {
     }).collect()
 }
 
+/// Get the part-level variants of an MCU family
+#[tauri::command]
+fn get_mcu_variants(family: String) -> Result<serde_json::Value, String> {
+    let family: registry::McuFamily = serde_json::from_value(serde_json::Value::String(family.to_uppercase()))
+        .map_err(|_| format!("Unknown MCU family: {}", family))?;
+    serde_json::to_value(registry::get_variants(family)).map_err(|e| e.to_string())
+}
+
 // ==================== Driver Generation Commands ====================
 
-/// Generate GPIO driver
+/// Parse an MCU family name from the frontend (`"stm32f4"`, `"ESP32"`)
+fn parse_mcu_family(name: &str) -> Result<drivers::McuFamily, String> {
+    serde_json::from_value(serde_json::Value::String(name.to_uppercase()))
+        .map_err(|_| format!("Unknown MCU family: {}", name))
+}
+
+/// Parse an optional MCU family, `default` when the frontend sent none
+fn parse_mcu_family_or(name: Option<&str>, default: drivers::McuFamily) -> Result<drivers::McuFamily, String> {
+    name.map_or(Ok(default), parse_mcu_family)
+}
+
+/// Generate GPIO driver, checking the pin against `variant` when given
 #[tauri::command]
 fn generate_gpio_driver(
     port: String,
     pin: u8,
     mode: String,
     language: String,
+    variant: Option<String>,
 ) -> Result<serde_json::Value, String> {
+    if let Some(id) = variant {
+        let variant = registry::get_variant(&id)
+            .ok_or_else(|| format!("Unknown MCU variant: {}", id))?;
+        if !variant.has_pin(&port, pin) {
+            return Err(format!("P{}{} is not available on {} ({:?})", port, pin, variant.name, variant.package));
+        }
+    }
+    
     let gpio_mode = match mode.to_lowercase().as_str() {
         "input" => GpioMode::Input,
         "output" => GpioMode::Output,
@@ -491,6 +697,7 @@ fn generate_gpio_driver(
         "c" => DriverLanguage::C,
         "cpp" | "c++" => DriverLanguage::Cpp,
         "rust" => DriverLanguage::Rust,
+        "portable" => DriverLanguage::Portable,
         _ => DriverLanguage::C,
     };
     
@@ -529,6 +736,7 @@ fn generate_uart_driver(
         "c" => DriverLanguage::C,
         "cpp" | "c++" => DriverLanguage::Cpp,
         "rust" => DriverLanguage::Rust,
+        "portable" => DriverLanguage::Portable,
         _ => DriverLanguage::C,
     };
     
@@ -577,6 +785,7 @@ fn generate_spi_driver(
         "c" => DriverLanguage::C,
         "cpp" | "c++" => DriverLanguage::Cpp,
         "rust" => DriverLanguage::Rust,
+        "portable" => DriverLanguage::Portable,
         _ => DriverLanguage::C,
     };
     
@@ -590,6 +799,87 @@ fn generate_spi_driver(
     }))
 }
 
+/// Generate a GPIO/UART/SPI driver against the portable HAL, bound to `hal_backend`
+#[tauri::command]
+fn generate_portable_driver(
+    peripheral: String,
+    config: serde_json::Value,
+    hal_backend: String,
+) -> Result<serde_json::Value, String> {
+    use drivers::hal_abstraction::{check_portable, generate_portable_driver as gen_portable, HalBackend, PortablePeripheral};
+    use drivers::templates::SpiMode;
+    
+    let backend = HalBackend::parse(&hal_backend)
+        .ok_or_else(|| format!("Unknown HAL backend: {} (expected stm32, esp_idf or arduino)", hal_backend))?;
+    let text = |key: &str| config.get(key).and_then(|v| v.as_str()).map(str::to_string);
+    let number = |key: &str| config.get(key).and_then(|v| v.as_u64());
+    
+    let target = match peripheral.to_lowercase().as_str() {
+        "gpio" => {
+            let mut gpio = GpioConfig::default();
+            if let Some(port) = text("port") {
+                gpio.port = port.to_uppercase();
+            }
+            let pin = number("pin").unwrap_or(0);
+            gpio.pin = u8::try_from(pin)
+                .ok()
+                .filter(|pin| *pin < 32)
+                .ok_or_else(|| format!("GPIO pin {} is out of range (0-31)", pin))?;
+            gpio.mode = match text("mode").unwrap_or_default().to_lowercase().as_str() {
+                "output" => GpioMode::Output,
+                "analog" => GpioMode::Analog,
+                _ => GpioMode::Input,
+            };
+            gpio.pull = match text("pull").unwrap_or_default().to_lowercase().as_str() {
+                "up" => GpioPull::Up,
+                "down" => GpioPull::Down,
+                _ => GpioPull::None,
+            };
+            PortablePeripheral::Gpio(gpio)
+        }
+        "uart" => {
+            let mut uart = UartConfig::default();
+            if let Some(instance) = text("instance") {
+                uart.instance = instance;
+            }
+            if let Some(baud) = number("baud_rate") {
+                uart.baud_rate = baud as u32;
+            }
+            PortablePeripheral::Uart(uart)
+        }
+        "spi" => {
+            let mut spi = SpiConfig::default();
+            if let Some(instance) = text("instance") {
+                spi.instance = instance;
+            }
+            if let Some(clock_hz) = number("clock_hz") {
+                spi.clock_hz = clock_hz as u32;
+            }
+            spi.mode = match number("mode").unwrap_or(0) {
+                1 => SpiMode::Mode1,
+                2 => SpiMode::Mode2,
+                3 => SpiMode::Mode3,
+                _ => SpiMode::Mode0,
+            };
+            PortablePeripheral::Spi(spi)
+        }
+        other => return Err(format!("Portable drivers support gpio, uart and spi, not {}", other)),
+    };
+    
+    let hal = backend.hal();
+    check_portable(&target, hal.as_ref())?;
+    let output = gen_portable(&target, hal.as_ref());
+    
+    Ok(serde_json::json!({
+        "header": output.header_file,
+        "source": output.source_file,
+        "backend": output.example_file,
+        "backend_file": hal.file_name(),
+        "hal_backend": backend,
+        "peripheral": peripheral.to_uppercase(),
+    }))
+}
+
 /// Generate I2C driver
 #[tauri::command]
 fn generate_i2c_driver(
@@ -630,14 +920,81 @@ fn generate_i2c_driver(
     }))
 }
 
+/// Generate I2S audio driver (HAL I2S with circular DMA)
+#[tauri::command]
+fn generate_i2s_driver(
+    instance: String,
+    role: String,
+    standard: String,
+    sample_rate: u32,
+    data_bits: u8,
+    channel_length: Option<u8>,
+    mck_enabled: Option<bool>,
+    dma_enabled: Option<bool>,
+    kernel_clock_hz: Option<u32>,
+    mcu: Option<String>,
+) -> Result<serde_json::Value, String> {
+    use drivers::i2s::{I2sConfig, I2sRole, I2sStandard, generate_i2s_driver as gen_i2s};
+    
+    let i2s_role = match role.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+        "mastertx" | "master" | "tx" => I2sRole::MasterTx,
+        "masterrx" | "rx" => I2sRole::MasterRx,
+        "slavetx" => I2sRole::SlaveTx,
+        "slaverx" | "slave" => I2sRole::SlaveRx,
+        _ => return Err(format!("Unknown I2S role: {}", role)),
+    };
+    let i2s_standard = match standard.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+        "philips" | "i2s" => I2sStandard::Philips,
+        "msbjustified" | "msb" | "leftjustified" => I2sStandard::MsbJustified,
+        "lsbjustified" | "lsb" | "rightjustified" => I2sStandard::LsbJustified,
+        "pcm" | "pcmshort" => I2sStandard::Pcm,
+        _ => return Err(format!("Unknown I2S standard: {}", standard)),
+    };
+    
+    let family = parse_mcu_family_or(mcu.as_deref(), drivers::McuFamily::STM32F4)?;
+    
+    let defaults = I2sConfig::default();
+    let config = I2sConfig {
+        instance,
+        role: i2s_role,
+        standard: i2s_standard,
+        sample_rate,
+        data_bits,
+        channel_length: channel_length.unwrap_or(if data_bits > 16 { 32 } else { 16 }),
+        // MCK only exists on the clock master
+        mck_enabled: mck_enabled.unwrap_or(i2s_role.is_master() && defaults.mck_enabled),
+        dma_enabled: dma_enabled.unwrap_or(defaults.dma_enabled),
+        kernel_clock_hz,
+    };
+    config.validate(family)?;
+    
+    let output = gen_i2s(&config, family);
+    // Without a known kernel clock the divider is only settled on the target
+    let clock = match kernel_clock_hz {
+        Some(hz) if i2s_role.is_master() => config.clock(hz).ok(),
+        _ => None,
+    };
+    
+    Ok(serde_json::json!({
+        "header": output.header_file,
+        "source": output.source_file,
+        "example": output.example_file,
+        "peripheral": "I2S",
+        "clock": clock,
+    }))
+}
+
 /// Generate driver using AI
 #[tauri::command]
 async fn generate_driver_ai(
     peripheral: String,
     description: String,
-    mcu: String,
+    mcu: Option<String>,
     language: String,
 ) -> Result<serde_json::Value, String> {
+    let mcu = mcu
+        .or_else(commands::project::workspace::active_mcu)
+        .ok_or_else(|| "No MCU given and no active workspace project".to_string())?;
     let output = drivers::generate_driver_with_ai(&peripheral, &description, &mcu, &language).await?;
     
     Ok(serde_json::json!({
@@ -698,176 +1055,776 @@ fn generate_can_driver(
     }))
 }
 
-/// Generate Modbus driver
+/// Parse a CAN database (DBC) file
 #[tauri::command]
-fn generate_modbus_driver(
-    uart_instance: String,
-    baud_rate: u32,
-    address: u8,
-    mode: String,
-    language: String,
+fn import_can_dbc(dbc_content: String) -> Result<serde_json::Value, String> {
+    let db = drivers::can::dbc::parse_dbc(&dbc_content).map_err(|e| e.to_string())?;
+    
+    Ok(serde_json::json!({
+        "message_count": db.messages.len(),
+        "signal_count": db.signal_count(),
+        "database": db,
+    }))
+}
+
+/// Generate signal pack/unpack code from a CAN database (DBC) file
+#[tauri::command]
+fn generate_can_dbc_driver(
+    dbc_content: String,
+    mcu_family: drivers::mcu::McuFamily,
 ) -> Result<serde_json::Value, String> {
-    use drivers::modbus::{ModbusConfig, ModbusMode, generate_modbus_driver as gen_modbus};
+    use drivers::can::dbc::{generate_can_from_dbc, parse_dbc};
     
-    let modbus_mode = match mode.to_lowercase().as_str() {
-        "slave" => ModbusMode::RtuSlave,
-        _ => ModbusMode::RtuMaster,
-    };
+    let db = parse_dbc(&dbc_content).map_err(|e| e.to_string())?;
+    let output = generate_can_from_dbc(&db, mcu_family);
     
-    let config = ModbusConfig {
-        mode: modbus_mode,
-        address,
-        uart_instance,
-        baud_rate,
+    Ok(serde_json::json!({
+        "header": output.header_file,
+        "source": output.source_file,
+        "example": output.example_file,
+        "peripheral": "CAN",
+        "message_count": db.messages.len(),
+    }))
+}
+
+/// Generate USB device driver (CDC ACM or HID)
+#[tauri::command]
+fn generate_usb_driver(
+    device_class: String,
+    vid: u16,
+    pid: u16,
+    manufacturer: String,
+    product: String,
+    mcu: Option<String>,
+) -> Result<serde_json::Value, String> {
+    use drivers::usb::{UsbConfig, UsbDeviceClass, generate_usb_driver as gen_usb};
+    
+    let class = match device_class.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+        "cdc" | "cdcacm" | "serial" => UsbDeviceClass::CdcAcm,
+        "hid" | "keyboard" => UsbDeviceClass::Hid,
+        "msc" | "massstorage" => UsbDeviceClass::MassStorage,
+        "webusb" => UsbDeviceClass::WebUsb,
+        "dfu" => UsbDeviceClass::Dfu,
+        _ => return Err(format!("Unknown USB device class: {}", device_class)),
     };
     
-    let lang = match language.to_lowercase().as_str() {
-        "c" => DriverLanguage::C,
-        "cpp" | "c++" => DriverLanguage::Cpp,
-        "rust" => DriverLanguage::Rust,
-        _ => DriverLanguage::C,
+    let family = parse_mcu_family_or(mcu.as_deref(), drivers::McuFamily::STM32F4)?;
+    
+    let config = UsbConfig {
+        device_class: class,
+        vid,
+        pid,
+        manufacturer,
+        product,
+        ..UsbConfig::default()
     };
     
-    let output = gen_modbus(&config, &McuArch::Stm32, &lang);
+    let output = gen_usb(&config, family)?;
     
     Ok(serde_json::json!({
         "header": output.header_file,
         "source": output.source_file,
         "example": output.example_file,
-        "peripheral": "Modbus",
+        "peripheral": "USB",
     }))
 }
 
-/// Get MCU pinout for visual configurator
+/// Generate USB DFU bootloader (dfu-util / DfuSe compatible)
 #[tauri::command]
-fn get_mcu_pinout(mcu_id: String) -> Result<serde_json::Value, String> {
-    use drivers::pins::get_mcu_pinout as get_pinout;
+fn generate_usb_dfu_bootloader(
+    memory_regions: Vec<drivers::usb_dfu::DfuMemoryRegion>,
+    upload_supported: bool,
+    manifestation_tolerant: Option<bool>,
+    transfer_size: Option<u16>,
+    mcu: Option<String>,
+) -> Result<serde_json::Value, String> {
+    use drivers::usb_dfu::{DfuConfig, generate_usb_dfu_bootloader as gen_dfu};
     
-    match get_pinout(&mcu_id) {
-        Some(pinout) => Ok(serde_json::json!({
-            "mcu_id": pinout.mcu_id,
-            "name": pinout.name,
-            "package": format!("{:?}", pinout.package),
-            "pins": pinout.pins.iter().map(|p| {
-                serde_json::json!({
-                    "port": p.port,
-                    "pin": p.pin,
-                    "name": p.name,
-                    "functions": p.functions.iter().map(|f| format!("{:?}", f)).collect::<Vec<_>>(),
-                    "currentFunction": p.current_function.as_ref().map(|f| format!("{:?}", f)),
-                    "x": p.x,
-                    "y": p.y,
-                })
-            }).collect::<Vec<_>>(),
-        })),
-        None => Err(format!("Unknown MCU: {}", mcu_id)),
-    }
+    let family = parse_mcu_family_or(mcu.as_deref(), drivers::McuFamily::STM32F4)?;
+    
+    let defaults = DfuConfig::default();
+    let config = DfuConfig {
+        memory_map: if memory_regions.is_empty() { defaults.memory_map } else { memory_regions },
+        upload_supported,
+        manifestation_tolerant: manifestation_tolerant.unwrap_or(defaults.manifestation_tolerant),
+        transfer_size: transfer_size.unwrap_or(defaults.transfer_size),
+    };
+    config.validate(family)?;
+    
+    let output = gen_dfu(&config, family);
+    
+    Ok(serde_json::json!({
+        "header": output.header_file,
+        "source": output.source_file,
+        "example": output.example_file,
+        "peripheral": "USB",
+        "memory_layout": config.memory_map_descriptor(),
+    }))
 }
 
-/// Generate RTOS code
+/// Generate SD card driver (SDIO/SDMMC with FatFS glue)
 #[tauri::command]
-fn generate_rtos_code(
-    tasks: Vec<serde_json::Value>,
-    heap_size_kb: u32,
-    language: String,
+fn generate_sdmmc_driver(
+    instance: u8,
+    bus_width: u8,
+    clock_div: u32,
+    dma_enabled: Option<bool>,
+    mcu: Option<String>,
 ) -> Result<serde_json::Value, String> {
-    use drivers::rtos::{RtosConfig, RtosType, TaskConfig, generate_rtos_code as gen_rtos};
-    
-    let task_configs: Vec<TaskConfig> = tasks.iter().map(|t| {
-        TaskConfig {
-            name: t.get("name").and_then(|v| v.as_str()).unwrap_or("Task").to_string(),
-            priority: t.get("priority").and_then(|v| v.as_u64()).unwrap_or(1) as u8,
-            stack_size: t.get("stackSize").and_then(|v| v.as_u64()).unwrap_or(256) as u32,
-            period_ms: t.get("periodMs").and_then(|v| v.as_u64()).map(|v| v as u32),
-            handler_name: t.get("handler").and_then(|v| v.as_str())
-                .unwrap_or(&format!("{}_Handler", t.get("name").and_then(|v| v.as_str()).unwrap_or("Task"))).to_string(),
-        }
-    }).collect();
+    use drivers::sdmmc::{SdmmcBusWidth, SdmmcConfig, generate_sdmmc_driver as gen_sdmmc};
     
-    let config = RtosConfig {
-        rtos_type: RtosType::FreeRtos,
-        tasks: task_configs,
-        use_queues: true,
-        use_semaphores: true,
-        use_mutexes: true,
-        heap_size_kb,
+    let width = match bus_width {
+        1 => SdmmcBusWidth::Width1Bit,
+        4 => SdmmcBusWidth::Width4Bit,
+        8 => SdmmcBusWidth::Width8Bit,
+        _ => return Err(format!("Bus width must be 1, 4 or 8 bits, got {}", bus_width)),
     };
     
-    let lang = match language.to_lowercase().as_str() {
-        "c" => DriverLanguage::C,
-        "cpp" | "c++" => DriverLanguage::Cpp,
-        "rust" => DriverLanguage::Rust,
-        _ => DriverLanguage::C,
+    let family = parse_mcu_family_or(mcu.as_deref(), drivers::McuFamily::STM32F4)?;
+    
+    let config = SdmmcConfig {
+        instance,
+        bus_width: width,
+        clock_div,
+        dma_enabled: dma_enabled.unwrap_or(SdmmcConfig::default().dma_enabled),
     };
+    config.validate(family)?;
     
-    let output = gen_rtos(&config, &lang);
+    let output = gen_sdmmc(&config, family);
     
     Ok(serde_json::json!({
         "header": output.header_file,
         "source": output.source_file,
         "example": output.example_file,
-        "peripheral": "RTOS",
+        "peripheral": "SDMMC",
+        "card_clock_hz": config.card_clock_hz(family),
     }))
 }
 
-/// Execute a terminal command via the Rust backend (legacy API - kept for compatibility)
+/// Generate sensor driver (BME280, MPU6050, LIS3DH, DS18B20, HC-SR04)
 #[tauri::command]
-fn execute_terminal_command(command: String, args: Vec<String>) -> terminal::TerminalResult {
-    // Create a parsed command from legacy format
-    let parsed = terminal::parser::ParsedCommand {
-        command: command.clone(),
-        args,
-        flags: std::collections::HashMap::new(),
-        operator: terminal::parser::CommandOperator::None,
-        next: None,
+fn generate_sensor_driver(
+    sensor: String,
+    interface: String,
+    address: Option<u8>,
+    spi_cs_pin: Option<String>,
+    mcu: Option<String>,
+) -> Result<serde_json::Value, String> {
+    use drivers::sensors::{SensorInterface, SensorLibConfig, SensorType, generate_sensor_driver as gen_sensor};
+    
+    let sensor_type = match sensor.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+        "bme280" => SensorType::Bme280,
+        "mpu6050" => SensorType::Mpu6050,
+        "lis3dh" => SensorType::Lis3Dh,
+        "ds18b20" => SensorType::Ds18b20,
+        "hcsr04" => SensorType::Hcsr04,
+        _ => return Err(format!("Unknown sensor: {}", sensor)),
+    };
+    
+    let sensor_interface = match interface.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+        "i2c" => SensorInterface::I2c,
+        "spi" => SensorInterface::Spi,
+        "onewire" | "1wire" => SensorInterface::OneWire,
+        "gpio" => SensorInterface::Gpio,
+        _ => return Err(format!("Unknown sensor interface: {}", interface)),
+    };
+    
+    let family = parse_mcu_family_or(mcu.as_deref(), drivers::McuFamily::STM32F4)?;
+    
+    let config = SensorLibConfig {
+        i2c_address: address,
+        spi_cs_pin,
+        ..SensorLibConfig::new(sensor_type, sensor_interface)
     };
-    terminal::commands::process_embedded_command(&parsed)
+    config.validate(family)?;
+    
+    let output = gen_sensor(&config, family);
+    
+    Ok(serde_json::json!({
+        "header": output.header_file,
+        "source": output.source_file,
+        "example": output.example_file,
+        "peripheral": format!("{:?}", output.peripheral_type),
+        "sensor": sensor_type,
+    }))
 }
 
-/// Get terminal welcome message
+/// Generate OLED/TFT display driver (SSD1306, SH1106, ILI9341, ST7789)
 #[tauri::command]
-fn get_terminal_welcome() -> Vec<terminal::TerminalLine> {
-    terminal::get_welcome_message()
-}
+fn generate_display_driver(
+    controller: String,
+    interface: String,
+    width: Option<u16>,
+    height: Option<u16>,
+    dc_pin: Option<String>,
+    cs_pin: Option<String>,
+    rst_pin: Option<String>,
+    mcu: Option<String>,
+) -> Result<serde_json::Value, String> {
+    use drivers::display::{DisplayConfig, DisplayController, DisplayInterface, generate_display_driver as gen_display};
+
+    let display_controller = match controller.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+        "ssd1306" => DisplayController::Ssd1306,
+        "sh1106" => DisplayController::Sh1106,
+        "ili9341" => DisplayController::Ili9341,
+        "st7789" => DisplayController::St7789,
+        _ => return Err(format!("Unknown display controller: {}", controller)),
+    };
 
-/// List available agents
-#[tauri::command]
-async fn list_agents(state: State<'_, AppState>) -> Result<Vec<agents::AgentInfo>, String> {
-    let orchestrator = state.orchestrator.lock().await;
-    Ok(orchestrator.list_agents())
-}
+    let display_interface = match interface.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+        "i2c" => DisplayInterface::I2c,
+        "spi" => DisplayInterface::Spi,
+        _ => return Err(format!("Unknown display interface: {}", interface)),
+    };
 
-/// Get currently active agent
-#[tauri::command]
-async fn get_active_agent(state: State<'_, AppState>) -> Result<Option<agents::AgentInfo>, String> {
-    let orchestrator = state.orchestrator.lock().await;
-    Ok(orchestrator.get_active_agent())
-}
+    let family = parse_mcu_family_or(mcu.as_deref(), drivers::McuFamily::STM32F4)?;
 
-/// Set active agent
-#[tauri::command]
-async fn set_active_agent(state: State<'_, AppState>, agent_id: String) -> Result<(), String> {
-    let mut orchestrator = state.orchestrator.lock().await;
-    orchestrator.set_active_agent(&agent_id)
-}
+    let defaults = DisplayConfig::new(display_controller, display_interface);
+    let config = DisplayConfig {
+        width: width.unwrap_or(defaults.width),
+        height: height.unwrap_or(defaults.height),
+        dc_pin: dc_pin.unwrap_or(defaults.dc_pin.clone()),
+        cs_pin,
+        rst_pin,
+        ..defaults
+    };
+    config.validate(family)?;
 
-/// Chat with active agent
-#[tauri::command]
-async fn agent_chat(state: State<'_, AppState>, message: String) -> Result<agents::AgentResponse, String> {
-    let orch = state.orchestrator.clone();
-    // Drop the State reference before await
-    drop(state);
-    let orchestrator = orch.lock().await;
-    orchestrator.process(&message).await
-}
+    let output = gen_display(&config, family);
 
-/// Execute a tool call from an agent
-#[tauri::command]
-fn execute_tool(tool: String, params: serde_json::Value) -> agents::ToolResult {
-    agents::ToolExecutor::execute(&tool, &params)
+    Ok(serde_json::json!({
+        "header": output.header_file,
+        "source": output.source_file,
+        "example": output.example_file,
+        "peripheral": format!("{:?}", output.peripheral_type),
+        "controller": display_controller,
+    }))
 }
 
-/// Update FSM context in agent state (sync FSM canvas to agents)
+/// Generate CRC driver (hardware CRC unit or table-driven software)
+#[tauri::command]
+fn generate_crc_code(
+    polynomial: String,
+    use_hardware: bool,
+    initial_value: Option<u32>,
+    mcu: Option<String>,
+) -> Result<serde_json::Value, String> {
+    use drivers::crc::{CrcConfig, CrcPolynomial, generate_crc_driver};
+    
+    let crc_polynomial = match polynomial.to_lowercase().replace(['-', '_', '/', ' '], "").as_str() {
+        "crc32mpeg2" | "mpeg2" => CrcPolynomial::Crc32Mpeg2,
+        "crc32" => CrcPolynomial::Crc32,
+        "crc16ccitt" | "ccitt" | "crc16ccittfalse" => CrcPolynomial::Crc16Ccitt,
+        "crc16" | "crc16arc" => CrcPolynomial::Crc16,
+        "crc8" | "crc8smbus" => CrcPolynomial::Crc8,
+        _ => return Err(format!("Unknown CRC polynomial: {}", polynomial)),
+    };
+    
+    let family = parse_mcu_family_or(mcu.as_deref(), drivers::McuFamily::STM32F4)?;
+    
+    let preset = CrcConfig::preset(crc_polynomial);
+    let config = CrcConfig {
+        initial_value: initial_value.unwrap_or(preset.initial_value),
+        use_hardware,
+        ..preset
+    };
+    config.validate()?;
+    
+    let output = generate_crc_driver(&config, family);
+    
+    Ok(serde_json::json!({
+        "header": output.header_file,
+        "source": output.source_file,
+        "example": output.example_file,
+        "peripheral": "CRC",
+        "check_value": config.compute(b"123456789"),
+    }))
+}
+
+/// Generate button debounce driver (press, long-press and repeat events)
+#[tauri::command]
+fn generate_button_debounce(
+    num_buttons: u8,
+    debounce_ms: u32,
+    long_press_ms: u32,
+    repeat_interval_ms: Option<u32>,
+) -> Result<serde_json::Value, String> {
+    use drivers::debounce::{DebounceConfig, generate_debounce_driver};
+    
+    let config = DebounceConfig {
+        num_buttons,
+        debounce_ms,
+        long_press_ms,
+        repeat_enabled: repeat_interval_ms.is_some(),
+        repeat_interval_ms: repeat_interval_ms.unwrap_or(DebounceConfig::default().repeat_interval_ms),
+    };
+    config.validate()?;
+    
+    let output = generate_debounce_driver(&config);
+    
+    Ok(serde_json::json!({
+        "header": output.header_file,
+        "source": output.source_file,
+        "example": output.example_file,
+        "peripheral": format!("{:?}", output.peripheral_type),
+    }))
+}
+
+/// Generate DMA ping-pong (double) buffer driver
+#[tauri::command]
+fn generate_dma_pingpong_buffer(
+    peripheral: String,
+    buffer_size: usize,
+    element_type: String,
+    mcu: Option<String>,
+) -> Result<serde_json::Value, String> {
+    use drivers::dma_buffer::{DmaPingPongConfig, generate_dma_pingpong};
+    
+    let family = parse_mcu_family_or(mcu.as_deref(), drivers::McuFamily::STM32F4)?;
+    
+    let config = DmaPingPongConfig {
+        peripheral,
+        buffer_size,
+        element_type,
+        callback_on_half: true,
+    };
+    config.validate(family)?;
+    
+    let output = generate_dma_pingpong(&config, family);
+    
+    Ok(serde_json::json!({
+        "header": output.header_file,
+        "source": output.source_file,
+        "example": output.example_file,
+        "peripheral": format!("{:?}", output.peripheral_type),
+    }))
+}
+
+/// Generate packet framer (COBS, SLIP, PPP/HDLC or custom delimiter)
+#[tauri::command]
+fn generate_protocol_framer(
+    protocol: String,
+    max_frame_size: usize,
+    delimiter: Option<u8>,
+) -> Result<serde_json::Value, String> {
+    use drivers::framing::{FrameProtocol, generate_framer};
+    
+    let frame_protocol = match protocol.to_lowercase().as_str() {
+        "cobs" => FrameProtocol::Cobs,
+        "slip" => FrameProtocol::Slip,
+        "ppp" | "hdlc" | "ppphdlc" | "ppp_hdlc" => FrameProtocol::PppHdlc,
+        "custom" | "delimiter" => FrameProtocol::CustomDelimiter(
+            delimiter.ok_or("Custom framing requires a delimiter byte")?,
+        ),
+        _ => return Err(format!("Unknown framing protocol: {}", protocol)),
+    };
+    if max_frame_size == 0 || max_frame_size > i32::MAX as usize {
+        return Err(format!("Invalid maximum frame size: {}", max_frame_size));
+    }
+    
+    let output = generate_framer(frame_protocol, max_frame_size);
+    
+    Ok(serde_json::json!({
+        "header": output.header_file,
+        "source": output.source_file,
+        "example": output.example_file,
+        "peripheral": format!("{:?}", output.peripheral_type),
+        "max_encoded_size": frame_protocol.max_encoded_size(max_frame_size),
+    }))
+}
+
+/// Generate bit-banged software I2C driver
+#[tauri::command]
+fn generate_soft_i2c_driver(
+    sda_pin: String,
+    scl_pin: String,
+    speed_khz: u32,
+    use_open_drain_simulation: Option<bool>,
+    mcu: Option<String>,
+    instance: Option<String>,
+) -> Result<serde_json::Value, String> {
+    use drivers::soft_i2c::{SoftI2cConfig, generate_soft_i2c};
+
+    let family = parse_mcu_family_or(mcu.as_deref(), drivers::McuFamily::STM32F4)?;
+
+    let config = SoftI2cConfig {
+        instance: instance.unwrap_or_else(|| "I2C1".to_string()),
+        sda_pin,
+        scl_pin,
+        speed_khz,
+        use_open_drain_simulation: use_open_drain_simulation.unwrap_or(false),
+    };
+    config.validate(family)?;
+
+    let output = generate_soft_i2c(&config, family);
+
+    Ok(serde_json::json!({
+        "header_name": format!("{}_driver.h", config.instance.to_lowercase()),
+        "header": output.header_file,
+        "source": output.source_file,
+        "example": output.example_file,
+        "peripheral": format!("{:?}", output.peripheral_type),
+    }))
+}
+
+/// Generate 1-Wire driver with ROM search and DS18B20 readout
+#[tauri::command]
+fn generate_onewire_driver(
+    data_pin: String,
+    parasite_power: bool,
+    max_devices: u8,
+    mcu: Option<String>,
+) -> Result<serde_json::Value, String> {
+    use drivers::onewire::{OneWireConfig, generate_onewire_driver as gen_onewire};
+
+    let family = parse_mcu_family_or(mcu.as_deref(), drivers::McuFamily::STM32F4)?;
+
+    let config = OneWireConfig {
+        data_pin,
+        use_parasite_power: parasite_power,
+        max_devices,
+    };
+    config.validate(family)?;
+
+    let output = gen_onewire(&config, family);
+
+    Ok(serde_json::json!({
+        "header": output.header_file,
+        "source": output.source_file,
+        "example": output.example_file,
+        "peripheral": format!("{:?}", output.peripheral_type),
+    }))
+}
+
+/// Generate a MicroPython driver module from a UART/SPI/I2C/GPIO/ADC config
+#[tauri::command]
+fn generate_micropython_driver(
+    peripheral: String,
+    mcu: Option<String>,
+    config: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    use drivers::micropython::{MicroPythonPeripheral, generate_micropython_driver as gen_micropython};
+
+    let family = parse_mcu_family_or(mcu.as_deref(), drivers::McuFamily::RP2040)?;
+
+    let peripheral = MicroPythonPeripheral::from_json(&peripheral, config)?;
+    let output = gen_micropython(&peripheral, family)?;
+
+    Ok(serde_json::json!({
+        "header": output.header_file,
+        "source": output.source_file,
+        "example": output.example_file,
+        "peripheral": format!("{:?}", output.peripheral_type),
+    }))
+}
+
+/// Generate quadrature / step-direction encoder driver
+#[tauri::command]
+fn generate_encoder_driver(
+    timer: String,
+    mode: String,
+    count_range: i32,
+    pin_a: String,
+    pin_b: String,
+    filter: Option<u8>,
+    mcu: Option<String>,
+) -> Result<serde_json::Value, String> {
+    use drivers::encoder::{EncoderConfig, EncoderMode, generate_encoder_driver as gen_encoder};
+
+    let encoder_mode = match mode.to_lowercase().replace(['-', '_', ' ', '/'], "").as_str() {
+        "x1" | "quadraturex1" => EncoderMode::QuadratureX1,
+        "x2" | "quadraturex2" => EncoderMode::QuadratureX2,
+        "x4" | "quadraturex4" | "quadrature" => EncoderMode::QuadratureX4,
+        "stepdirection" | "stepdir" => EncoderMode::StepDirection,
+        _ => return Err(format!("Unknown encoder mode: {}", mode)),
+    };
+
+    let family = parse_mcu_family_or(mcu.as_deref(), drivers::McuFamily::STM32F4)?;
+
+    let config = EncoderConfig {
+        timer_instance: timer,
+        mode: encoder_mode,
+        count_range,
+        filter_prescaler: filter.unwrap_or(EncoderConfig::default().filter_prescaler),
+        pin_a,
+        pin_b,
+    };
+    config.validate(family)?;
+
+    let output = gen_encoder(&config, family);
+
+    Ok(serde_json::json!({
+        "header": output.header_file,
+        "source": output.source_file,
+        "example": output.example_file,
+        "peripheral": format!("{:?}", output.peripheral_type),
+    }))
+}
+
+/// Generate step/direction stepper motor driver with trapezoidal ramps
+#[tauri::command]
+fn generate_stepper_driver(
+    step_pin: String,
+    dir_pin: String,
+    timer: String,
+    max_freq_hz: u32,
+    microstepping: u8,
+    en_pin: Option<String>,
+    limit_pin: Option<String>,
+    steps_per_rev: Option<u32>,
+    mcu: Option<String>,
+) -> Result<serde_json::Value, String> {
+    use drivers::motor::stepper::{StepperConfig, generate_stepper_driver as gen_stepper};
+
+    let family = parse_mcu_family_or(mcu.as_deref(), drivers::McuFamily::STM32F4)?;
+
+    let config = StepperConfig {
+        step_pin,
+        dir_pin,
+        en_pin,
+        timer_instance: timer,
+        max_freq_hz,
+        microstepping,
+        steps_per_rev: steps_per_rev.unwrap_or(StepperConfig::default().steps_per_rev),
+        limit_pin,
+    };
+    config.validate(family)?;
+
+    let output = gen_stepper(&config, family);
+
+    Ok(serde_json::json!({
+        "header": output.header_file,
+        "source": output.source_file,
+        "example": output.example_file,
+        "peripheral": format!("{:?}", output.peripheral_type),
+    }))
+}
+
+/// Generate Modbus driver
+#[tauri::command]
+fn generate_modbus_driver(
+    uart_instance: String,
+    baud_rate: u32,
+    address: u8,
+    mode: String,
+    language: String,
+) -> Result<serde_json::Value, String> {
+    use drivers::modbus::{ModbusConfig, ModbusMode, generate_modbus_driver as gen_modbus};
+    
+    let modbus_mode = match mode.to_lowercase().as_str() {
+        "slave" => ModbusMode::RtuSlave,
+        _ => ModbusMode::RtuMaster,
+    };
+    
+    let config = ModbusConfig {
+        mode: modbus_mode,
+        address,
+        uart_instance,
+        baud_rate,
+    };
+    
+    let lang = match language.to_lowercase().as_str() {
+        "c" => DriverLanguage::C,
+        "cpp" | "c++" => DriverLanguage::Cpp,
+        "rust" => DriverLanguage::Rust,
+        _ => DriverLanguage::C,
+    };
+    
+    let output = gen_modbus(&config, &McuArch::Stm32, &lang);
+    
+    Ok(serde_json::json!({
+        "header": output.header_file,
+        "source": output.source_file,
+        "example": output.example_file,
+        "peripheral": "Modbus",
+    }))
+}
+
+/// Get MCU pinout for visual configurator
+#[tauri::command]
+fn get_mcu_pinout(mcu_id: String) -> Result<serde_json::Value, String> {
+    use drivers::pins::get_mcu_pinout as get_pinout;
+    
+    match get_pinout(&mcu_id) {
+        Some(pinout) => Ok(serde_json::json!({
+            "mcu_id": pinout.mcu_id,
+            "name": pinout.name,
+            "package": format!("{:?}", pinout.package),
+            "pins": pinout.pins.iter().map(|p| {
+                serde_json::json!({
+                    "port": p.port,
+                    "pin": p.pin,
+                    "name": p.name,
+                    "functions": p.functions.iter().map(|f| format!("{:?}", f)).collect::<Vec<_>>(),
+                    "currentFunction": p.current_function.as_ref().map(|f| format!("{:?}", f)),
+                    "x": p.x,
+                    "y": p.y,
+                })
+            }).collect::<Vec<_>>(),
+        })),
+        None => Err(format!("Unknown MCU: {}", mcu_id)),
+    }
+}
+
+/// Generate RTOS code
+#[tauri::command]
+fn generate_rtos_code(
+    tasks: Vec<serde_json::Value>,
+    heap_size_kb: u32,
+    language: String,
+) -> Result<serde_json::Value, String> {
+    use drivers::rtos::{RtosConfig, RtosType, TaskConfig, generate_rtos_code as gen_rtos};
+    
+    let task_configs: Vec<TaskConfig> = tasks.iter().map(|t| {
+        TaskConfig {
+            name: t.get("name").and_then(|v| v.as_str()).unwrap_or("Task").to_string(),
+            priority: t.get("priority").and_then(|v| v.as_u64()).unwrap_or(1) as u8,
+            stack_size: t.get("stackSize").and_then(|v| v.as_u64()).unwrap_or(256) as u32,
+            period_ms: t.get("periodMs").and_then(|v| v.as_u64()).map(|v| v as u32),
+            handler_name: t.get("handler").and_then(|v| v.as_str())
+                .unwrap_or(&format!("{}_Handler", t.get("name").and_then(|v| v.as_str()).unwrap_or("Task"))).to_string(),
+        }
+    }).collect();
+    
+    let config = RtosConfig {
+        rtos_type: RtosType::FreeRtos,
+        tasks: task_configs,
+        use_queues: true,
+        use_semaphores: true,
+        use_mutexes: true,
+        heap_size_kb,
+    };
+    
+    let lang = match language.to_lowercase().as_str() {
+        "c" => DriverLanguage::C,
+        "cpp" | "c++" => DriverLanguage::Cpp,
+        "rust" => DriverLanguage::Rust,
+        _ => DriverLanguage::C,
+    };
+    
+    let output = gen_rtos(&config, &lang);
+    
+    Ok(serde_json::json!({
+        "header": output.header_file,
+        "source": output.source_file,
+        "example": output.example_file,
+        "peripheral": "RTOS",
+    }))
+}
+
+/// Execute a terminal command via the Rust backend (legacy API - kept for compatibility)
+#[tauri::command]
+fn execute_terminal_command(command: String, args: Vec<String>, session_id: Option<String>) -> terminal::TerminalResult {
+    let line = std::iter::once(command.as_str()).chain(args.iter().map(String::as_str)).collect::<Vec<_>>().join(" ");
+    // Create a parsed command from legacy format
+    let parsed = terminal::parser::ParsedCommand {
+        command: command.clone(),
+        args,
+        flags: std::collections::HashMap::new(),
+        operator: terminal::parser::CommandOperator::None,
+        next: None,
+        redirect_stdout: None,
+        redirect_stdin: None,
+    };
+    let result = terminal::commands::process_embedded_command(&parsed);
+    terminal::history::record(&line, result.exit_code, session_id.as_deref().unwrap_or(terminal::history::app_session_id()));
+    result
+}
+
+/// Run a program on a native PTY, returns the session id
+///
+/// Output arrives as `pty:output` events with raw bytes, and `pty:exit` when
+/// the program ends.
+#[tauri::command]
+fn terminal_spawn_pty(command: String, args: Vec<String>, env: std::collections::HashMap<String, String>) -> Result<String, String> {
+    terminal::pty::spawn_attached(&command, &args, &env, None).map_err(|e| e.to_string())
+}
+
+/// Send input bytes to a PTY session
+#[tauri::command]
+fn terminal_pty_write(session_id: String, data: Vec<u8>) -> Result<(), String> {
+    terminal::pty::write_session(&session_id, &data).map_err(|e| e.to_string())
+}
+
+/// Resize a PTY session's terminal
+#[tauri::command]
+fn terminal_pty_resize(session_id: String, cols: u16, rows: u16) -> Result<(), String> {
+    terminal::pty::resize_session(&session_id, cols, rows).map_err(|e| e.to_string())
+}
+
+/// Terminate the program of a PTY session
+#[tauri::command]
+fn terminal_pty_kill(session_id: String) -> Result<(), String> {
+    terminal::pty::kill_session(&session_id).map_err(|e| e.to_string())
+}
+
+/// Get terminal welcome message
+#[tauri::command]
+fn get_terminal_welcome() -> Vec<terminal::TerminalLine> {
+    terminal::get_welcome_message()
+}
+
+/// List available agents
+#[tauri::command]
+async fn list_agents(state: State<'_, AppState>) -> Result<Vec<agents::AgentInfo>, String> {
+    let orchestrator = state.orchestrator.lock().await;
+    Ok(orchestrator.list_agents())
+}
+
+/// Get currently active agent
+#[tauri::command]
+async fn get_active_agent(state: State<'_, AppState>) -> Result<Option<agents::AgentInfo>, String> {
+    let orchestrator = state.orchestrator.lock().await;
+    Ok(orchestrator.get_active_agent())
+}
+
+/// Set active agent
+#[tauri::command]
+async fn set_active_agent(state: State<'_, AppState>, agent_id: String) -> Result<(), String> {
+    let mut orchestrator = state.orchestrator.lock().await;
+    orchestrator.set_active_agent(&agent_id)
+}
+
+/// Chat with active agent
+#[tauri::command]
+async fn agent_chat(state: State<'_, AppState>, app: tauri::AppHandle, message: String, session_id: Option<String>) -> Result<agents::AgentResponse, String> {
+    let orch = state.orchestrator.clone();
+    let sessions = state.ai_sessions.clone();
+    // Drop the State reference before await
+    drop(state);
+    let history = match &session_id {
+        Some(id) => ai::memory::session_context(&sessions, id).await,
+        None => None,
+    };
+    let orchestrator = orch.lock().await;
+    let mut response = orchestrator.process_with_history(&message, history.as_deref()).await?;
+    // Follow a delegation the active agent asked for
+    let delegation = response.tool_calls.iter().find_map(agents::delegation_request);
+    if let (Some((to_agent, task)), Some(active)) = (delegation, orchestrator.get_active_agent()) {
+        response = orchestrator.delegate_with_events(&task, &active.id, &to_agent, |event| {
+            let _ = app.emit("agent:delegated", &event);
+        }).await?;
+    }
+    drop(orchestrator);
+    if let Some(id) = &session_id {
+        ai::memory::record_exchange(&sessions, id, &message, &response.message, &AIService::new()).await;
+    }
+    Ok(response)
+}
+
+/// Generate unit tests for a function (framework: unity, cpputest or rust)
+#[tauri::command]
+fn agent_generate_tests(code_snippet: String, function_name: String, framework: String) -> Result<String, String> {
+    agents::test_agent::TestAgent::new().generate_tests(&code_snippet, &function_name, &framework)
+}
+
+/// Execute a tool call from an agent
+#[tauri::command]
+fn execute_tool(tool: String, params: serde_json::Value) -> agents::ToolResult {
+    agents::ToolExecutor::execute(&tool, &params)
+}
+
+/// Update FSM context in agent state (sync FSM canvas to agents)
 #[tauri::command]
 async fn update_fsm_context(
     state: State<'_, AppState>,
@@ -880,19 +1837,12 @@ async fn update_fsm_context(
     Ok(())
 }
 
-/// Project data for save/load
-#[derive(Serialize, Deserialize)]
-pub struct ProjectData {
-    pub name: String,
-    pub nodes: Vec<serde_json::Value>,
-    pub edges: Vec<serde_json::Value>,
-    pub mcu: String,
-    pub language: String,
-}
+pub use commands::project::ProjectData;
 
 /// Save project to file
 #[tauri::command]
-fn save_project_file(path: String, project: ProjectData) -> Result<(), String> {
+fn save_project_file(path: String, mut project: ProjectData) -> Result<(), String> {
+    project.schema_version = commands::project::CURRENT_SCHEMA_VERSION.to_string();
     let json = serde_json::to_string_pretty(&project)
         .map_err(|e| format!("Serialization error: {}", e))?;
     std::fs::write(&path, json)
@@ -901,17 +1851,46 @@ fn save_project_file(path: String, project: ProjectData) -> Result<(), String> {
     Ok(())
 }
 
-/// Load project from file
+/// Load project from file, migrating older schema versions
+///
+/// Opening a project closes the previous one, so its undo/redo history is dropped.
 #[tauri::command]
-fn load_project_file(path: String) -> Result<ProjectData, String> {
+async fn load_project_file(state: State<'_, AppState>, path: String) -> Result<ProjectData, String> {
+    use commands::project::migrate;
+    
     let content = std::fs::read_to_string(&path)
         .map_err(|e| format!("File read error: {}", e))?;
-    let project: ProjectData = serde_json::from_str(&content)
+    let data: serde_json::Value = serde_json::from_str(&content)
         .map_err(|e| format!("Parse error: {}", e))?;
+    let version = migrate::schema_version(&data);
+    let project = migrate::migrate_project(data, &version, commands::project::CURRENT_SCHEMA_VERSION)
+        .map_err(|e| e.to_string())?;
+    if version != project.schema_version {
+        log::info!("Migrated project {} from schema v{} to v{}", path, version, project.schema_version);
+    }
+    terminal::autocomplete::set_project_pins(project.pins.clone());
+    state.audit_log.lock().await.clear_history();
     log::info!("Project loaded from: {}", path);
     Ok(project)
 }
 
+/// Close the open project, dropping its undo/redo history
+#[tauri::command]
+async fn project_close(state: State<'_, AppState>) -> Result<(), String> {
+    state.audit_log.lock().await.clear_history();
+    Ok(())
+}
+
+/// Schema version of a project file, "1" for files written before versioning
+#[tauri::command]
+fn project_get_schema_version(path: String) -> Result<String, String> {
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("File read error: {}", e))?;
+    let data: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Parse error: {}", e))?;
+    Ok(commands::project::migrate::schema_version(&data))
+}
+
 /// Generate EXTI interrupt initialization code
 #[tauri::command]
 fn generate_interrupt_code(
@@ -1268,6 +2247,24 @@ fn generate_pwm_code(
     }))
 }
 
+/// Generate calibrated internal temperature sensor code
+#[tauri::command]
+fn generate_temperature_sensor_code(mcu_family: String) -> Result<serde_json::Value, String> {
+    use drivers::analog::temp_calibration::{generate_temp_calibration, temp_sensor_calibration};
+
+    let family = parse_mcu_family(&mcu_family)?;
+    let calibration = temp_sensor_calibration(family)
+        .ok_or_else(|| format!("{} has no factory temperature sensor calibration", family.display_name()))?;
+
+    Ok(serde_json::json!({
+        "code": generate_temp_calibration(family),
+        "adc_instance": calibration.adc_instance,
+        "ts_cal1_addr": format!("0x{:08X}", calibration.cal1_addr),
+        "ts_cal2_addr": format!("0x{:08X}", calibration.cal2_addr),
+        "calibration_bits": calibration.cal_bits,
+    }))
+}
+
 /// Get all supported MCUs
 #[tauri::command]
 fn get_supported_mcus() -> Result<serde_json::Value, String> {
@@ -1678,9 +2675,16 @@ fn generate_rtos_timer(
 }
 
 /// Generate RTOS configuration file
+///
+/// For Zephyr, `zephyr_version` wins over the tree at `$ZEPHYR_BASE`, which
+/// wins over the default release; the one used is returned.
 #[tauri::command]
-fn generate_rtos_config(rtos: String) -> Result<serde_json::Value, String> {
-    use drivers::rtos_gen::{RtosType, get_rtos_hal};
+fn generate_rtos_config(
+    rtos: String,
+    zephyr_version: Option<drivers::rtos_gen::zephyr::ZephyrVersion>,
+) -> Result<serde_json::Value, String> {
+    use drivers::rtos_gen::{RtosHal, RtosType, get_rtos_hal};
+    use drivers::rtos_gen::zephyr::{ZephyrHal, ZephyrVersion};
     
     let rtos_type = match rtos.to_lowercase().as_str() {
         "freertos" => RtosType::FreeRtos,
@@ -1688,7 +2692,12 @@ fn generate_rtos_config(rtos: String) -> Result<serde_json::Value, String> {
         _ => RtosType::FreeRtos,
     };
     
-    let hal = get_rtos_hal(rtos_type);
+    let version = (rtos_type == RtosType::Zephyr)
+        .then(|| zephyr_version.or_else(ZephyrVersion::detect).unwrap_or_default());
+    let hal: Box<dyn RtosHal> = match version {
+        Some(version) => Box::new(ZephyrHal::with_version(version)),
+        None => get_rtos_hal(rtos_type),
+    };
     let code = hal.generate_config_header();
     
     let filename = match rtos_type {
@@ -1701,6 +2710,7 @@ fn generate_rtos_config(rtos: String) -> Result<serde_json::Value, String> {
         "code": code,
         "rtos": rtos,
         "filename": filename,
+        "zephyr_version": version,
     }))
 }
 
@@ -1766,6 +2776,63 @@ fn generate_ble_service(
     }))
 }
 
+/// Generate a legacy BLE advertising payload
+///
+/// A device name that does not fit is cut down and sent as the shortened
+/// local name.
+#[tauri::command]
+fn generate_ble_advertising_data(
+    device_name: String,
+    flags: Option<drivers::wireless::ble::adv::BleAdvFlags>,
+    manufacturer_data: Option<drivers::wireless::ble::adv::ManufacturerData>,
+    service_uuids: Vec<String>,
+) -> Result<serde_json::Value, String> {
+    use drivers::wireless::ble::adv::{AdvDataError, AdvertisingDataBuilder, parse_uuid16};
+    
+    let uuids = service_uuids.iter()
+        .map(|uuid| parse_uuid16(uuid))
+        .collect::<Result<Vec<u16>, _>>()
+        .map_err(|e| e.to_string())?;
+    
+    let mut builder = AdvertisingDataBuilder::new();
+    builder.set_flags(flags.unwrap_or_default()).set_service_uuids_16(&uuids);
+    if let Some(manufacturer) = &manufacturer_data {
+        builder.set_manufacturer_data(manufacturer.company_id, &manufacturer.data);
+    }
+    
+    let mut name_shortened = false;
+    if !device_name.is_empty() {
+        builder.set_local_name(&device_name, false);
+        if let Err(AdvDataError::TooLong { len }) = builder.to_bytes() {
+            let mut keep = device_name.len().saturating_sub(len - drivers::wireless::ble::adv::MAX_ADV_DATA_LEN);
+            while !device_name.is_char_boundary(keep) {
+                keep -= 1;
+            }
+            if keep > 0 {
+                builder.set_local_name(&device_name[..keep], true);
+                name_shortened = true;
+            }
+        }
+    }
+    
+    let bytes = builder.to_bytes().map_err(|e| e.to_string())?;
+    let c_array = builder.to_c_array("adv_data").map_err(|e| e.to_string())?;
+    
+    Ok(serde_json::json!({
+        "bytes": bytes,
+        "hex": bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>(),
+        "c_array": c_array,
+        "length": bytes.len(),
+        "name_shortened": name_shortened,
+    }))
+}
+
+/// Generate nRF5 SDK SoftDevice initialization code
+#[tauri::command]
+fn generate_softdevice_init(version: String) -> String {
+    drivers::wireless::ble::softdevice::generate_softdevice_init(&version)
+}
+
 /// Generate WiFi configuration code
 #[tauri::command]
 fn generate_wifi_config(
@@ -2140,6 +3207,34 @@ fn generate_ota_client(
     }))
 }
 
+/// Create a delta OTA patch (bsdiff in-process, janpatch with jdiff)
+#[tauri::command]
+fn delta_ota_create_patch(
+    old_bin: String,
+    new_bin: String,
+    output_patch: String,
+    algorithm: Option<String>,
+) -> Result<serde_json::Value, String> {
+    use drivers::security::DeltaAlgorithm;
+    use drivers::security::delta::create_patch;
+    
+    let delta_algorithm = match algorithm.as_deref().map(str::to_lowercase).as_deref() {
+        Some("bsdiff") | Some("bsdiff4") => DeltaAlgorithm::Bsdiff4,
+        Some("janpatch") | Some("jojodiff") | None => DeltaAlgorithm::Janpatch,
+        Some(other) => return Err(format!("Unknown delta algorithm: {}", other)),
+    };
+    
+    let info = create_patch(
+        delta_algorithm,
+        std::path::Path::new(&old_bin),
+        std::path::Path::new(&new_bin),
+        std::path::Path::new(&output_patch),
+    )?;
+    log::info!("Created {:?} patch {} ({} bytes, {:.0}% of image)", info.algorithm, info.patch_path, info.patch_size, info.ratio * 100.0);
+    
+    serde_json::to_value(info).map_err(|e| e.to_string())
+}
+
 /// Generate secure boot verification code
 #[tauri::command]
 fn generate_secure_boot(
@@ -2217,6 +3312,77 @@ fn generate_crypto_utils(
     }))
 }
 
+/// Generate key derivation code (HKDF, PBKDF2 or SP 800-108)
+#[tauri::command]
+fn generate_kdf_code(
+    algorithm: String,
+    key_length: u8,
+    info: String,
+    use_hardware_rng: bool,
+) -> Result<serde_json::Value, String> {
+    use drivers::security::kdf::{KdfAlgorithm, KdfConfig, generate_kdf_code as gen_kdf};
+
+    let algorithm = match algorithm.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+        "hkdf" => KdfAlgorithm::Hkdf,
+        "pbkdf2" => KdfAlgorithm::Pbkdf2,
+        "sp800108" | "sp800108ctr" => KdfAlgorithm::Sp800_108Ctr,
+        other => return Err(format!("Unknown KDF algorithm: {}", other)),
+    };
+    let config = KdfConfig {
+        algorithm,
+        key_length_bytes: key_length,
+        info,
+        use_hardware_rng,
+    };
+    config.validate()?;
+
+    let output = gen_kdf(&config);
+    Ok(serde_json::json!({
+        "header": output.header_file,
+        "source": output.source_file,
+        "example": output.example_file,
+        "algorithm": algorithm.name(),
+        "warnings": config.warnings(),
+    }))
+}
+
+// ============================================================================
+// Power Management Generation Commands
+// ============================================================================
+
+/// Generate PMIC rail configuration and power-up sequence
+#[tauri::command]
+fn generate_pmic_config(
+    device: String,
+    rails: Vec<drivers::power::PowerRail>,
+    mcu: Option<drivers::mcu::McuFamily>,
+    sequence: Option<drivers::power::PowerSequence>,
+) -> Result<serde_json::Value, String> {
+    use drivers::power::{PmicConfig, PmicDevice};
+    use drivers::power::pmic::generate_pmic_init;
+    
+    let pmic_device = match device.to_uppercase().as_str() {
+        "MAX77650" => PmicDevice::MAX77650,
+        "BQ25180" => PmicDevice::BQ25180,
+        "TPS65941" => PmicDevice::TPS65941,
+        _ => return Err(format!("Unknown PMIC device: {}", device)),
+    };
+    
+    let config = PmicConfig {
+        device: pmic_device,
+        rails,
+        sequence: sequence.unwrap_or_default(),
+    };
+    
+    let output = generate_pmic_init(&config, mcu.unwrap_or(drivers::mcu::McuFamily::STM32F4));
+    
+    Ok(serde_json::json!({
+        "header": output.header_file,
+        "source": output.source_file,
+        "device": device,
+    }))
+}
+
 // ============================================================================
 // Export Commands
 // ============================================================================
@@ -2378,11 +3544,84 @@ fn git_history(path: String, limit: usize) -> Result<serde_json::Value, String>
     Ok(serde_json::to_value(history).map_err(|e| e.to_string())?)
 }
 
-/// Get diff between working tree and HEAD
+/// Get diff between working tree and HEAD
+#[tauri::command]
+fn git_diff(path: String) -> Result<serde_json::Value, String> {
+    let diff = git::get_diff(&path)?;
+    Ok(serde_json::to_value(diff).map_err(|e| e.to_string())?)
+}
+
+/// Tag HEAD, annotated when `is_annotated` is set
+#[tauri::command]
+fn git_create_tag(
+    path: String,
+    tag_name: String,
+    message: Option<String>,
+    is_annotated: bool,
+) -> Result<serde_json::Value, String> {
+    git::create_tag(&path, &tag_name, message.as_deref(), is_annotated).map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({
+        "success": true,
+        "tag": tag_name,
+    }))
+}
+
+/// List tags, newest semantic version first
+#[tauri::command]
+fn git_list_tags(path: String) -> Result<serde_json::Value, String> {
+    let tags = git::list_tags(&path).map_err(|e| e.to_string())?;
+    Ok(serde_json::to_value(tags).map_err(|e| e.to_string())?)
+}
+
+/// Generate a .gitignore for an embedded project
+///
+/// With `project_path` the file is also written to the project root; an
+/// existing .gitignore is only replaced when `overwrite` is set.
+#[tauri::command]
+fn git_generate_gitignore(
+    mcu_family: String,
+    build_system: String,
+    ide: Option<git::IdeType>,
+    project_path: Option<String>,
+    overwrite: Option<bool>,
+) -> Result<serde_json::Value, String> {
+    let family = parse_mcu_family(&mcu_family)?;
+    let system = match build_system.to_lowercase().as_str() {
+        "make" => build::BuildSystem::Make,
+        "cmake" => build::BuildSystem::CMake,
+        "cargo" => build::BuildSystem::Cargo,
+        "platformio" | "pio" => build::BuildSystem::PlatformIO,
+        _ => return Err(format!("Unknown build system: {}", build_system)),
+    };
+    let content = git::generate_gitignore(family, system, ide);
+    
+    let written = match project_path {
+        Some(root) => {
+            let path = std::path::Path::new(&root).join(".gitignore");
+            if path.exists() && !overwrite.unwrap_or(false) {
+                return Err(format!("{} already exists", path.display()));
+            }
+            std::fs::write(&path, &content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            Some(path.to_string_lossy().to_string())
+        }
+        None => None,
+    };
+    
+    Ok(serde_json::json!({
+        "content": content,
+        "path": written,
+    }))
+}
+
+/// Tag HEAD with the next major/minor/patch version
 #[tauri::command]
-fn git_diff(path: String) -> Result<serde_json::Value, String> {
-    let diff = git::get_diff(&path)?;
-    Ok(serde_json::to_value(diff).map_err(|e| e.to_string())?)
+fn git_bump_version(path: String, bump_type: git::BumpType) -> Result<serde_json::Value, String> {
+    let tag = git::bump_version(&path, bump_type).map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({
+        "success": true,
+        "tag": tag,
+        "version": tag.trim_start_matches(['v', 'V']),
+    }))
 }
 
 // === QEMU Simulation Commands ===
@@ -2474,6 +3713,56 @@ fn cloud_collect_files(dir: String, extensions: Vec<String>) -> Result<serde_jso
     Ok(serde_json::to_value(files).map_err(|e| e.to_string())?)
 }
 
+/// Upload a project to the share server, emitting `cloud:upload_progress`
+#[tauri::command]
+async fn cloud_upload_project(
+    app: tauri::AppHandle,
+    share_id: String,
+    project_json: String,
+    server_url: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let server_url = cloud::client::resolve_server_url(server_url.as_deref());
+    let url = cloud::client::upload_project_with_progress(&share_id, &project_json, &server_url, |progress| {
+        let _ = app.emit("cloud:upload_progress", &progress);
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(serde_json::json!({
+        "success": true,
+        "shareId": share_id,
+        "url": url,
+        "contentHash": cloud::client::sha256_hex(&project_json),
+    }))
+}
+
+/// Download a shared project, verified against the server's content hash
+#[tauri::command]
+async fn cloud_download_project(share_id: String, server_url: Option<String>) -> Result<serde_json::Value, String> {
+    let server_url = cloud::client::resolve_server_url(server_url.as_deref());
+    let json = cloud::client::download_project(&share_id, &server_url)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(serde_json::json!({
+        "success": true,
+        "shareId": share_id,
+        "json": json,
+    }))
+}
+
+/// Three-way merge a local project with its shared copy and re-upload
+#[tauri::command]
+async fn cloud_sync_project(
+    local_path: String,
+    share_id: String,
+    server_url: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let server_url = cloud::client::resolve_server_url(server_url.as_deref());
+    let outcome = cloud::sync::sync_project(std::path::Path::new(&local_path), &share_id, &server_url).await?;
+    Ok(serde_json::to_value(outcome).map_err(|e| e.to_string())?)
+}
+
 // === Templates Commands ===
 
 /// Get all templates
@@ -2498,12 +3787,35 @@ fn templates_get_categories() -> Result<serde_json::Value, String> {
     Ok(serde_json::json!({ "categories": categories }))
 }
 
+/// Create a multi-file project from a template in `output_dir`
+///
+/// The project name comes from the `PROJECT_NAME` param, or the output directory name.
+#[tauri::command]
+fn templates_instantiate(
+    id: String,
+    params: Option<std::collections::HashMap<String, String>>,
+    output_dir: String,
+) -> Result<serde_json::Value, String> {
+    let output = std::path::Path::new(&output_dir);
+    let mut params = params.unwrap_or_default();
+    let project_name = params.keys()
+        .find(|k| k.eq_ignore_ascii_case("PROJECT_NAME"))
+        .cloned()
+        .and_then(|k| params.remove(&k))
+        .or_else(|| output.file_name().map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "project".to_string());
+
+    let (project, files) = templates::instantiate_files(&id, &project_name, output, params)
+        .map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({ "project": project, "files": files, "output_dir": output_dir }))
+}
+
 // === Snippets Commands ===
 
 /// Get all snippets
 #[tauri::command]
 fn snippets_get_all() -> Result<serde_json::Value, String> {
-    let snippets = snippets::get_snippets();
+    let snippets = snippets::get_all_snippets();
     Ok(serde_json::to_value(snippets).map_err(|e| e.to_string())?)
 }
 
@@ -2522,6 +3834,104 @@ fn snippets_get_by_id(id: String) -> Result<serde_json::Value, String> {
     Ok(serde_json::to_value(snippet).map_err(|e| e.to_string())?)
 }
 
+/// Row id of a user snippet, rejecting built-in ids
+fn user_snippet_row_id(id: &str) -> Result<snippets::user::SnippetId, String> {
+    snippets::user::parse_user_snippet_id(id)
+        .ok_or_else(|| format!("'{}' is not a user snippet", id))
+}
+
+/// Add a user-defined snippet
+#[tauri::command]
+fn snippets_add(
+    name: String,
+    code: String,
+    language: String,
+    tags: Vec<String>,
+    description: Option<String>,
+    category: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let snippet = snippets::CodeSnippet {
+        id: String::new(),
+        name,
+        description: description.unwrap_or_default(),
+        category: category.unwrap_or_else(|| "User".to_string()),
+        language,
+        code,
+        tags,
+        is_user_defined: true,
+    };
+    let id = snippets::user::with_store(|store| store.add(snippet)).map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({
+        "success": true,
+        "id": snippets::user::user_snippet_id(id),
+    }))
+}
+
+/// Replace a user-defined snippet
+#[tauri::command]
+fn snippets_update(id: String, snippet: snippets::CodeSnippet) -> Result<serde_json::Value, String> {
+    let row_id = user_snippet_row_id(&id)?;
+    snippets::user::with_store(|store| store.update(row_id, snippet)).map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({ "success": true, "id": id }))
+}
+
+/// Delete a user-defined snippet
+#[tauri::command]
+fn snippets_delete(id: String) -> Result<serde_json::Value, String> {
+    let row_id = user_snippet_row_id(&id)?;
+    snippets::user::with_store(|store| store.delete(row_id)).map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({ "success": true }))
+}
+
+/// User snippets with a tag
+#[tauri::command]
+fn snippets_list_by_tag(tag: String) -> Result<serde_json::Value, String> {
+    let results = snippets::user::with_store(|store| store.list_by_tag(&tag)).map_err(|e| e.to_string())?;
+    Ok(serde_json::to_value(results).map_err(|e| e.to_string())?)
+}
+
+/// Export snippets to a JSON file; all user snippets when no ids are given
+#[tauri::command]
+fn snippets_export(path: String, ids: Option<Vec<String>>) -> Result<serde_json::Value, String> {
+    let selected = match ids {
+        Some(ids) => ids.iter()
+            .map(|id| snippets::get_snippet_by_id(id).ok_or_else(|| format!("Snippet '{}' not found", id)))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => snippets::user::list_user_snippets(),
+    };
+    let count = snippets::user::export_snippets(selected, std::path::Path::new(&path))
+        .map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({ "success": true, "count": count, "path": path }))
+}
+
+/// Refine a snippet with minimal AI edits, returning the change as a diff
+#[tauri::command]
+async fn snippets_refine_with_ai(snippet_id: String, instruction: String) -> Result<serde_json::Value, String> {
+    let snippet = snippets::get_snippet_by_id(&snippet_id)
+        .ok_or_else(|| format!("Snippet '{}' not found", snippet_id))?;
+    let ai_service = AIService::new();
+    let refined = snippets::refine::refine_snippet(&snippet, &instruction, &ai_service).await?;
+    Ok(serde_json::to_value(refined).map_err(|e| e.to_string())?)
+}
+
+/// Generate a snippet with AI, saving it to the user store when `save` is set
+#[tauri::command]
+async fn snippets_generate_from_description(
+    description: String,
+    language: String,
+    mcu_family: String,
+    save: Option<bool>,
+) -> Result<snippets::CodeSnippet, String> {
+    let ai_service = AIService::new();
+    let mut snippet = snippets::refine::generate_snippet(&description, &language, &mcu_family, &ai_service).await?;
+    if save.unwrap_or(false) {
+        let id = snippets::user::with_store(|store| store.add(snippet.clone())).map_err(|e| e.to_string())?;
+        snippet.id = snippets::user::user_snippet_id(id);
+        snippet.is_user_defined = true;
+    }
+    Ok(snippet)
+}
+
 // === Memory Analyzer Commands ===
 
 /// Estimate memory usage
@@ -2538,18 +3948,26 @@ fn memory_get_mcu_configs() -> Result<serde_json::Value, String> {
     Ok(serde_json::to_value(configs).map_err(|e| e.to_string())?)
 }
 
+/// Place each function and variable of a GNU ld map file in flash/RAM
+#[tauri::command]
+fn memory_visualize_map(map_path: String) -> Result<serde_json::Value, String> {
+    let visualization = memory::map_visualizer::parse_and_visualize(std::path::Path::new(&map_path))
+        .map_err(|e| e.to_string())?;
+    Ok(serde_json::to_value(visualization).map_err(|e| e.to_string())?)
+}
+
 // === Power Estimator Commands ===
 
-/// Estimate power consumption
+/// Estimate power consumption from sleep mode and wake events
 #[tauri::command]
-fn power_estimate(
+fn power_estimate_detailed(
     mcu: String,
-    peripherals: Vec<String>,
-    duty_cycle: f32,
-    battery_mah: Option<f32>,
-) -> Result<serde_json::Value, String> {
-    let estimation = power::estimate_power(&mcu, &peripherals, duty_cycle, battery_mah)?;
-    Ok(serde_json::to_value(estimation).map_err(|e| e.to_string())?)
+    peripherals: Vec<power::PeripheralPower>,
+    sleep_mode: drivers::clock::LowPowerMode,
+    wake_events: Vec<power::WakeEvent>,
+    battery_mah: f32,
+) -> Result<power::PowerEstimation, String> {
+    power::estimate_power_detailed(&mcu, &peripherals, sleep_mode, &wake_events, battery_mah)
 }
 
 /// Get MCU power specs
@@ -2559,6 +3977,12 @@ fn power_get_mcu_specs() -> Result<serde_json::Value, String> {
     Ok(serde_json::to_value(specs).map_err(|e| e.to_string())?)
 }
 
+/// Get typical peripheral currents
+#[tauri::command]
+fn power_get_peripherals() -> Vec<power::PeripheralPower> {
+    power::get_peripheral_power()
+}
+
 // === Pin Configuration Commands ===
 
 /// Get MCU packages
@@ -2568,26 +3992,51 @@ fn pins_get_packages() -> Result<serde_json::Value, String> {
     Ok(serde_json::to_value(packages).map_err(|e| e.to_string())?)
 }
 
-/// Generate pin init code
+/// Generate pin init code, refusing configs with conflicting pin assignments
 #[tauri::command]
-fn pins_generate_code(configs: Vec<serde_json::Value>) -> Result<serde_json::Value, String> {
+fn pins_generate_code(configs: Vec<serde_json::Value>, mcu: Option<String>) -> Result<serde_json::Value, String> {
     let pin_configs: Vec<pins::PinConfig> = configs
         .into_iter()
         .filter_map(|c| serde_json::from_value(c).ok())
         .collect();
     
+    let (errors, warnings): (Vec<_>, Vec<_>) = pins::conflict::check_configs(&pin_configs, mcu.as_deref())
+        .into_iter()
+        .partition(|c| c.is_error());
+    if !errors.is_empty() {
+        let messages: Vec<String> = errors.iter().map(|c| c.message.clone()).collect();
+        return Err(format!("Pin conflicts: {}", messages.join("; ")));
+    }
+    
     let code = pins::generate_pin_init_code(&pin_configs);
-    Ok(serde_json::json!({ "code": code }))
+    Ok(serde_json::json!({ "code": code, "warnings": warnings }))
+}
+
+/// Pins that can carry a peripheral signal such as `USART1_TX`
+#[tauri::command]
+fn pins_find_pins_for_signal(mcu_id: String, peripheral_signal: String) -> Result<Vec<pins::af_table::PinOption>, String> {
+    pins::af_table::find_pins_for_signal(&mcu_id, &peripheral_signal).map_err(|e| e.to_string())
+}
+
+/// All functions a pin can be configured as
+#[tauri::command]
+fn pins_get_pin_functions(mcu_id: String, pin_name: String) -> Result<serde_json::Value, String> {
+    let functions = pins::af_table::get_pin_functions(&mcu_id, &pin_name).map_err(|e| e.to_string())?;
+    Ok(serde_json::to_value(functions).map_err(|e| e.to_string())?)
 }
 
 // === Build System Commands ===
 
-/// Generate Makefile
+/// Generate Makefile, with per-file dependency tracking when `incremental` is set
 #[tauri::command]
-fn build_generate_makefile(config: serde_json::Value) -> Result<serde_json::Value, String> {
+fn build_generate_makefile(config: serde_json::Value, incremental: Option<bool>) -> Result<serde_json::Value, String> {
     let build_config: build::BuildConfig = serde_json::from_value(config)
         .map_err(|e| e.to_string())?;
-    let makefile = build::generate_makefile(&build_config);
+    let makefile = if incremental.unwrap_or(false) {
+        build::makefile::generate_incremental_makefile(&build_config)
+    } else {
+        build::generate_makefile(&build_config)
+    };
     Ok(serde_json::json!({ "makefile": makefile }))
 }
 
@@ -2600,6 +4049,18 @@ fn build_generate_cmake(config: serde_json::Value) -> Result<serde_json::Value,
     Ok(serde_json::json!({ "cmake": cmake }))
 }
 
+/// Generate compile_commands.json for clangd, relative paths based at `project_dir`
+#[tauri::command]
+fn build_generate_compile_commands(config: serde_json::Value, project_dir: Option<String>) -> Result<serde_json::Value, String> {
+    let build_config: build::BuildConfig = serde_json::from_value(config)
+        .map_err(|e| e.to_string())?;
+    let compile_commands = match project_dir {
+        Some(dir) => build::compile_db::generate_compile_commands_in(&build_config, std::path::Path::new(&dir)),
+        None => build::compile_db::generate_compile_commands(&build_config),
+    };
+    Ok(serde_json::json!({ "compile_commands": compile_commands }))
+}
+
 /// Check toolchain availability
 #[tauri::command]
 fn build_check_toolchain() -> Result<serde_json::Value, String> {
@@ -2644,6 +4105,117 @@ fn serial_calculate_checksum(data: Vec<u8>, algorithm: String) -> Result<serde_j
     Ok(serde_json::json!({ "checksum": checksum }))
 }
 
+/// Decode framed binary protocol data
+#[tauri::command]
+fn serial_decode_protocol(data_hex: String, config: serial::protocol::FrameConfig) -> Result<serde_json::Value, String> {
+    config.validate()?;
+    let data = serial::protocol::parse_hex(&data_hex)?;
+    let frames = serial::protocol::decode_framed(&data, &config);
+    Ok(serde_json::json!({ "frames": frames, "count": frames.len() }))
+}
+
+/// Decode an I2C logic analyzer capture into transactions
+#[tauri::command]
+fn serial_analyze_i2c(capture_hex: String) -> Result<serde_json::Value, String> {
+    use serial::protocol_analyzer::{analyze_i2c, format_i2c_table};
+
+    let capture = serial::protocol::parse_hex(&capture_hex)?;
+    let transactions = analyze_i2c(&capture);
+    let lines: Vec<TerminalLine> = format_i2c_table(&transactions).iter().map(|l| TerminalLine::output(l)).collect();
+    Ok(serde_json::json!({ "transactions": transactions, "lines": lines }))
+}
+
+/// Decode an SPI logic analyzer capture into transactions
+#[tauri::command]
+fn serial_analyze_spi(capture_hex: String, cs_active_low: Option<bool>) -> Result<serde_json::Value, String> {
+    use serial::protocol_analyzer::{analyze_spi, format_spi_table};
+
+    let capture = serial::protocol::parse_hex(&capture_hex)?;
+    let transactions = analyze_spi(&capture, cs_active_low.unwrap_or(true));
+    let lines: Vec<TerminalLine> = format_spi_table(&transactions).iter().map(|l| TerminalLine::output(l)).collect();
+    Ok(serde_json::json!({ "transactions": transactions, "lines": lines }))
+}
+
+/// Render ADC samples as an ASCII oscilloscope trace
+#[tauri::command]
+fn serial_render_scope(
+    data: Vec<f32>,
+    sample_rate_hz: f32,
+    width: usize,
+    height: usize,
+    trigger_level: Option<f32>,
+) -> Result<Vec<TerminalLine>, String> {
+    if width < 20 || height < 5 {
+        return Err("Scope needs at least 20 columns and 5 rows".to_string());
+    }
+    let lines = serial::visualize::render_scope(&data, width, height, sample_rate_hz, trigger_level);
+    Ok(lines.iter().map(|l| TerminalLine::output(l)).collect())
+}
+
+/// Start logging decoded frames from a serial port to a file
+#[tauri::command]
+async fn serial_start_logging(
+    state: State<'_, AppState>,
+    port: String,
+    baud: u32,
+    fields: Vec<serial::protocol::FieldDef>,
+    path: String,
+    format: Option<serial::logger::LogFormat>,
+    start_byte: Option<u8>,
+) -> Result<String, String> {
+    use serial::logger::LogFormat;
+    
+    let path = std::path::PathBuf::from(path);
+    let format = format.unwrap_or_else(|| LogFormat::from_path(&path));
+    
+    let session_id = state.serial_logger.lock().unwrap()
+        .start_session(&path, format, fields, start_byte)
+        .map_err(|e| e.to_string())?;
+    
+    if let Err(e) = serial::logger::spawn_port_reader(state.serial_logger.clone(), session_id.clone(), &port, baud) {
+        let _ = state.serial_logger.lock().unwrap().stop_session(&session_id);
+        return Err(e.to_string());
+    }
+    
+    Ok(session_id)
+}
+
+/// Stop a serial logging session
+#[tauri::command]
+async fn serial_stop_logging(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<serial::logger::SessionSummary, String> {
+    state.serial_logger.lock().unwrap()
+        .stop_session(&session_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Stream a serial port as `serial:data` events, returns the job id
+#[tauri::command]
+async fn serial_monitor_start(
+    app: tauri::AppHandle,
+    port: String,
+    baud: u32,
+    hex: Option<bool>,
+    timestamps: Option<bool>,
+) -> Result<String, String> {
+    let options = terminal::monitor::MonitorOptions {
+        hex: hex.unwrap_or(false),
+        timestamps: timestamps.unwrap_or(false),
+    };
+    terminal::monitor::cmd_monitor_uart_stream(&port, baud, options, &app).await
+}
+
+/// Stop a serial monitor job
+#[tauri::command]
+async fn serial_monitor_stop(
+    state: State<'_, AppState>,
+    job_id: String,
+) -> Result<bool, String> {
+    Ok(state.job_manager.cancel_job(&job_id))
+}
+
 // === Documentation Generator Commands ===
 
 /// Generate documentation for code
@@ -2667,13 +4239,75 @@ fn docs_extract_functions(code: String) -> Result<serde_json::Value, String> {
     Ok(serde_json::to_value(functions).map_err(|e| e.to_string())?)
 }
 
+/// Insert AI-generated Doxygen comments above each function
+#[tauri::command]
+async fn docs_generate_ai(code: String, filename: String, language: Option<docs::ai_annotate::Language>) -> Result<serde_json::Value, String> {
+    let language = language.unwrap_or_else(|| docs::ai_annotate::Language::from_filename(&filename));
+    let ai_service = AIService::new();
+    let annotated = docs::ai_annotate::generate_doxygen_comments(&code, language, &ai_service).await?;
+    Ok(serde_json::json!({ "code": annotated, "filename": filename }))
+}
+
+/// Generate register map documentation for a peripheral
+#[tauri::command]
+fn docs_generate_register_map(peripheral: String, mcu_family: drivers::mcu::McuFamily) -> Result<serde_json::Value, String> {
+    let markdown = docs::register_map::generate_register_docs(&peripheral, mcu_family);
+    let html = docs::register_map::markdown_to_html(&markdown);
+    Ok(serde_json::json!({ "markdown": markdown, "html": html }))
+}
+
+/// Generate a requirements traceability matrix from `/* REQ-xxx */` tags in the code
+#[tauri::command]
+fn docs_generate_traceability(requirements_json: String, code_files: Vec<String>) -> Result<serde_json::Value, String> {
+    use docs::requirements::{RequirementsConfig, build_traceability, render_traceability_markdown};
+
+    let requirements = serde_json::from_str(&requirements_json)
+        .map_err(|e| format!("Invalid requirements: {}", e))?;
+    let config = RequirementsConfig { requirements, code_files };
+    let report = build_traceability(&config);
+    let markdown = render_traceability_markdown(&config.requirements, &report);
+    Ok(serde_json::json!({
+        "markdown": markdown,
+        "coverage_percent": report.coverage_percent(config.requirements.len()),
+        "report": report,
+    }))
+}
+
 // === Profiler Commands ===
 
 /// Analyze code performance
 #[tauri::command]
-fn profiler_analyze(code: String, mcu_freq_mhz: u32) -> Result<serde_json::Value, String> {
+fn profiler_analyze(code: String, mcu_freq_mhz: u32, elf_path: Option<String>) -> Result<serde_json::Value, String> {
     let result = profiler::analyze_performance(&code, mcu_freq_mhz);
-    Ok(serde_json::to_value(result).map_err(|e| e.to_string())?)
+    let mut value = serde_json::to_value(result).map_err(|e| e.to_string())?;
+
+    // Memory map chart when the linker left a map file next to the ELF
+    if let Some(map_path) = elf_path.as_deref().and_then(find_map_file) {
+        match memory::map_visualizer::parse_and_visualize(&map_path) {
+            Ok(visualization) => {
+                value["memory_map_chart"] = serde_json::Value::String(visualization.to_bar_chart(10));
+            }
+            Err(e) => log::warn!("Skipping memory map {}: {}", map_path.display(), e),
+        }
+    }
+    Ok(value)
+}
+
+/// `firmware.map` for `firmware.elf`, else the only `.map` file in the ELF's directory
+fn find_map_file(elf_path: &str) -> Option<std::path::PathBuf> {
+    let elf = std::path::Path::new(elf_path);
+    let sibling = elf.with_extension("map");
+    if sibling.is_file() {
+        return Some(sibling);
+    }
+    let maps: Vec<std::path::PathBuf> = std::fs::read_dir(elf.parent()?).ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "map"))
+        .collect();
+    match maps.as_slice() {
+        [only] => Some(only.clone()),
+        _ => None,
+    }
 }
 
 /// Estimate function timing
@@ -2683,61 +4317,260 @@ fn profiler_estimate_timing(code: String, mcu_freq_mhz: u32) -> Result<serde_jso
     Ok(serde_json::to_value(timing).map_err(|e| e.to_string())?)
 }
 
-// === Register Commands ===
-
-/// Get all peripherals
+/// Estimate worst-case interrupt latency of ISR handlers
+#[tauri::command]
+fn profiler_estimate_isr_latency(code: String, mcu_family: drivers::mcu::McuFamily, irq_priority: u8) -> Result<serde_json::Value, String> {
+    let report = profiler::interrupt::estimate_isr_latency(&code, mcu_family, irq_priority)?;
+    Ok(serde_json::to_value(report).map_err(|e| e.to_string())?)
+}
+
+/// Estimate worst-case execution time of a function and instrument it with the cycle counter
+#[tauri::command]
+fn profiler_estimate_wcet(code: String, function_name: String, mcu_family: drivers::mcu::McuFamily, freq_mhz: u32) -> Result<serde_json::Value, String> {
+    if freq_mhz == 0 {
+        return Err("Clock frequency must be non-zero".to_string());
+    }
+    let report = profiler::wcet::estimate_wcet(&code, &function_name, mcu_family)?;
+    let estimated_us = report.estimated_cycles.map(|cycles| cycles as f32 / freq_mhz as f32);
+    let mut value = serde_json::to_value(report).map_err(|e| e.to_string())?;
+    value["estimated_us"] = serde_json::json!(estimated_us);
+    Ok(value)
+}
+
+/// Analyze DMA bandwidth and bus contention
+#[tauri::command]
+fn profiler_analyze_dma(code: String, mcu_family: drivers::mcu::McuFamily, bus_freq_mhz: u32) -> Result<serde_json::Value, String> {
+    let report = profiler::dma::analyze_dma_usage(&code, mcu_family, bus_freq_mhz);
+    Ok(serde_json::to_value(report).map_err(|e| e.to_string())?)
+}
+
+/// Symbolize stack trace addresses (e.g. from an RTT panic log) against an ELF
+#[tauri::command]
+fn profiler_symbolize_stack(addresses: Vec<u32>, elf_path: String) -> Result<serde_json::Value, String> {
+    let frames = profiler::symbolize::symbolize_addresses(&addresses, std::path::Path::new(&elf_path))
+        .map_err(|e| e.to_string())?;
+    Ok(serde_json::to_value(frames).map_err(|e| e.to_string())?)
+}
+
+// === Register Commands ===
+
+/// Get all peripherals
+#[tauri::command]
+fn registers_get_peripherals() -> Result<serde_json::Value, String> {
+    let peripherals = registers::get_peripherals();
+    Ok(serde_json::to_value(peripherals).map_err(|e| e.to_string())?)
+}
+
+/// Get GPIO registers for a port
+#[tauri::command]
+fn registers_get_gpio(port: String) -> Result<serde_json::Value, String> {
+    let port_char = port.chars().next().unwrap_or('A');
+    let gpio = registers::get_gpio_registers(port_char);
+    Ok(serde_json::to_value(gpio).map_err(|e| e.to_string())?)
+}
+
+/// Generate register access code, bit-field aware when `field` is given
+#[tauri::command]
+fn registers_generate_code(
+    peripheral: String,
+    reg: String,
+    operation: String,
+    value: Option<u32>,
+    field: Option<String>,
+    mcu_family: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let code = match field {
+        Some(field) => {
+            let map = registers::find_peripheral(&peripheral, mcu_family.as_deref())
+                .ok_or_else(|| format!("Unknown peripheral: {}", peripheral))?;
+            registers::generate_field_code(&map, &reg, &field, &operation, value)?
+        }
+        None => registers::generate_register_code(&peripheral, &reg, &operation, value),
+    };
+    Ok(serde_json::json!({ "code": code }))
+}
+
+/// Parse a CMSIS-SVD file and cache its register map by MCU family
+#[tauri::command]
+fn registers_load_svd(svd_path: String) -> Result<serde_json::Value, String> {
+    let map = registers::svd::load_and_cache(std::path::Path::new(&svd_path))
+        .map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({
+        "success": true,
+        "device": map.device,
+        "mcuFamily": map.family(),
+        "peripheralCount": map.peripherals.len(),
+        "registerCount": map.peripherals.iter().map(|p| p.registers.len()).sum::<usize>(),
+    }))
+}
+
+/// Peripherals from the SVD loaded for an MCU family
+#[tauri::command]
+fn registers_get_svd_peripherals(mcu_family: String) -> Result<serde_json::Value, String> {
+    let map = registers::svd::cached_svd(&mcu_family)
+        .ok_or_else(|| format!("No SVD loaded for {}", mcu_family))?;
+    Ok(serde_json::to_value(map.peripherals).map_err(|e| e.to_string())?)
+}
+
+/// Read a register from the connected target
+#[tauri::command]
+async fn registers_read_live(peripheral: String, register: String, mcu_family: Option<String>) -> Result<serde_json::Value, String> {
+    let reg = registers::live::find_register(&peripheral, &register, mcu_family.as_deref())?;
+    let manager = get_probe_manager().lock().await;
+    let value = registers::live::read_register(&manager, &reg).await?;
+    Ok(serde_json::json!({
+        "peripheral": peripheral,
+        "register": reg.name,
+        "address": reg.address,
+        "value": value,
+        "fields": registers::live::decode_fields(&reg, value),
+    }))
+}
+
+/// Read-modify-write the bits of a register selected by `mask`
+#[tauri::command]
+async fn registers_write_live(
+    peripheral: String,
+    register: String,
+    value: u32,
+    mask: u32,
+    mcu_family: Option<String>,
+) -> Result<(), String> {
+    let reg = registers::live::find_register(&peripheral, &register, mcu_family.as_deref())?;
+    let mut manager = get_probe_manager().lock().await;
+    registers::live::write_register(&mut manager, &reg, value, mask).await?;
+    Ok(())
+}
+
+/// Poll a register as a job, emitting `register:changed` when it changes
+#[tauri::command]
+async fn registers_watch_live(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    peripheral: String,
+    register: String,
+    poll_ms: u64,
+    mcu_family: Option<String>,
+) -> Result<String, String> {
+    let reg = registers::live::find_register(&peripheral, &register, mcu_family.as_deref())?;
+    let emit_event = move |event_name: String, payload: serde_json::Value| {
+        let _ = app.emit(&event_name, &payload);
+    };
+    
+    registers::live::run_watch_job(
+        state.job_manager.clone(),
+        get_probe_manager(),
+        peripheral,
+        reg,
+        poll_ms,
+        emit_event,
+    ).await
+}
+
+// ==================== Advanced Terminal Commands ====================
+
+/// Execute an advanced terminal command with parsing and autocomplete
+#[tauri::command]
+fn terminal_execute_advanced(
+    command: String,
+    variables: Option<std::collections::HashMap<String, String>>,
+    session_id: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let vars = variables.unwrap_or_default();
+    let stages = terminal::parser::parse_pipeline(&command, &vars).map_err(|e| e.to_string())?;
+    let result = terminal::commands::execute_pipeline(&stages);
+    terminal::history::record(&command, result.exit_code, session_id.as_deref().unwrap_or(terminal::history::app_session_id()));
+    
+    Ok(serde_json::json!({
+        "success": result.success,
+        "output": result.output,
+        "command_count": stages.iter().map(|s| s.len()).sum::<usize>()
+    }))
+}
+
+/// Execute a `|` pipeline, feeding each stage's output to the next
 #[tauri::command]
-fn registers_get_peripherals() -> Result<serde_json::Value, String> {
-    let peripherals = registers::get_peripherals();
-    Ok(serde_json::to_value(peripherals).map_err(|e| e.to_string())?)
+fn terminal_execute_pipeline(
+    command_line: String,
+    variables: Option<std::collections::HashMap<String, String>>,
+    session_id: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let vars = variables.unwrap_or_default();
+    let stages = terminal::parser::parse_pipeline(&command_line, &vars).map_err(|e| e.to_string())?;
+    let result = terminal::commands::execute_pipeline(&stages);
+    terminal::history::record(&command_line, result.exit_code, session_id.as_deref().unwrap_or(terminal::history::app_session_id()));
+    
+    Ok(serde_json::json!({
+        "success": result.success,
+        "output": result.output,
+        "exit_code": result.exit_code,
+        "stage_count": stages.len()
+    }))
 }
 
-/// Get GPIO registers for a port
+/// Search persistent command history, prefix matches first
 #[tauri::command]
-fn registers_get_gpio(port: String) -> Result<serde_json::Value, String> {
-    let port_char = port.chars().next().unwrap_or('A');
-    let gpio = registers::get_gpio_registers(port_char);
-    Ok(serde_json::to_value(gpio).map_err(|e| e.to_string())?)
+fn terminal_search_history(query: String, limit: Option<usize>) -> Result<serde_json::Value, String> {
+    let entries = terminal::history::search_history(&query, limit.unwrap_or(50)).map_err(|e| e.to_string())?;
+    Ok(serde_json::to_value(entries).map_err(|e| e.to_string())?)
 }
 
-/// Generate register access code
+/// Clear persistent command history, optionally only entries older than N days
 #[tauri::command]
-fn registers_generate_code(peripheral: String, reg: String, operation: String, value: Option<u32>) -> Result<serde_json::Value, String> {
-    let code = registers::generate_register_code(&peripheral, &reg, &operation, value);
-    Ok(serde_json::json!({ "code": code }))
+fn terminal_clear_history(older_than_days: Option<u32>) -> Result<serde_json::Value, String> {
+    let deleted = terminal::history::clear_history(older_than_days).map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({ "deleted": deleted }))
 }
 
-// ==================== Advanced Terminal Commands ====================
-
-/// Execute an advanced terminal command with parsing and autocomplete
+/// Run a `.nbsh` script as a job, output streams as `script:output` events
 #[tauri::command]
-fn terminal_execute_advanced(command: String, variables: Option<std::collections::HashMap<String, String>>) -> Result<serde_json::Value, String> {
-    let vars = variables.unwrap_or_default();
-    let parsed_commands = terminal::parser::parse_command_line(&command, &vars);
-    
-    let mut all_output = Vec::new();
-    let mut overall_success = true;
-    
-    for parsed_cmd in &parsed_commands {
-        let result = terminal::commands::process_embedded_command(parsed_cmd);
-        overall_success = overall_success && result.success;
-        all_output.extend(result.output);
-    }
+async fn terminal_execute_script(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    path: String,
+    variables: Option<std::collections::HashMap<String, String>>,
+    session_id: Option<String>,
+) -> Result<String, String> {
+    let app_clone = app.clone();
+    let emit_event = move |event_name: String, payload: serde_json::Value| {
+        let _ = app_clone.emit(&event_name, &payload);
+    };
     
-    Ok(serde_json::json!({
-        "success": overall_success,
-        "output": all_output,
-        "command_count": parsed_commands.len()
-    }))
+    terminal::script::run_script_job(
+        state.job_manager.clone(),
+        std::path::PathBuf::from(path),
+        variables.unwrap_or_default(),
+        session_id.unwrap_or_else(|| terminal::history::app_session_id().to_string()),
+        emit_event,
+    ).await
+}
+
+/// Generate a build, flash and monitor script
+#[tauri::command]
+fn terminal_generate_flash_script(elf_path: String, probe: Option<String>, port: Option<String>, baud: Option<u32>) -> Result<serde_json::Value, String> {
+    let script = terminal::script::generate_flash_script(
+        &elf_path,
+        probe.as_deref().unwrap_or("stlink"),
+        port.as_deref().unwrap_or("COM3"),
+        baud.unwrap_or(115200),
+    );
+    Ok(serde_json::json!({ "script": script }))
 }
 
 /// Get tab completions for current input
 #[tauri::command]
-fn terminal_get_completions(input: String, cursor_pos: usize) -> Result<serde_json::Value, String> {
-    let completions = terminal::autocomplete::get_completions(&input, cursor_pos);
+async fn terminal_get_completions(input: String, cursor_pos: usize) -> Result<serde_json::Value, String> {
+    let completions = terminal::autocomplete::get_completions(&input, cursor_pos).await;
     Ok(serde_json::to_value(completions).map_err(|e| e.to_string())?)
 }
 
+/// Set the project pinout used for pin completion
+#[tauri::command]
+fn terminal_set_completion_pins(pins: Vec<pins::PinConfig>) -> Result<(), String> {
+    terminal::autocomplete::set_project_pins(pins);
+    Ok(())
+}
+
 /// Get available terminal themes
 #[tauri::command]
 fn terminal_get_themes() -> Result<serde_json::Value, String> {
@@ -2748,6 +4581,21 @@ fn terminal_get_themes() -> Result<serde_json::Value, String> {
     Ok(serde_json::to_value(themes).map_err(|e| e.to_string())?)
 }
 
+/// Import a theme JSON file and make it available by name
+#[tauri::command]
+fn terminal_import_theme(path: String) -> Result<serde_json::Value, String> {
+    let theme = terminal::themes::import_theme(std::path::Path::new(&path)).map_err(|e| e.to_string())?;
+    Ok(serde_json::to_value(theme).map_err(|e| e.to_string())?)
+}
+
+/// Export a built-in or imported theme as JSON
+#[tauri::command]
+fn terminal_export_theme(theme_name: String, path: String) -> Result<(), String> {
+    let theme = terminal::themes::find_theme(&theme_name)
+        .ok_or_else(|| format!("Unknown theme: {}", theme_name))?;
+    terminal::themes::save_theme_to_file(&theme, std::path::Path::new(&path)).map_err(|e| e.to_string())
+}
+
 /// Get terminal welcome message
 #[tauri::command]
 fn terminal_get_welcome() -> Result<serde_json::Value, String> {
@@ -2759,7 +4607,7 @@ fn terminal_get_welcome() -> Result<serde_json::Value, String> {
 #[tauri::command]
 fn terminal_parse_command(command: String) -> Result<serde_json::Value, String> {
     let vars = std::collections::HashMap::new();
-    let parsed = terminal::parser::parse_command_line(&command, &vars);
+    let parsed = terminal::parser::parse_command_line(&command, &vars).map_err(|e| e.to_string())?;
     Ok(serde_json::to_value(parsed).map_err(|e| e.to_string())?)
 }
 
@@ -2767,8 +4615,11 @@ fn terminal_parse_command(command: String) -> Result<serde_json::Value, String>
 
 /// Get current system performance metrics
 #[tauri::command]
-fn performance_get_system_metrics() -> Result<serde_json::Value, String> {
-    let metrics = performance::get_system_metrics();
+async fn performance_get_system_metrics() -> Result<serde_json::Value, String> {
+    // Throttle detection may sample the RAPL energy counter for 100 ms
+    let metrics = tokio::task::spawn_blocking(performance::get_system_metrics)
+        .await
+        .map_err(|e| e.to_string())?;
     Ok(serde_json::to_value(metrics).map_err(|e| e.to_string())?)
 }
 
@@ -2786,11 +4637,74 @@ fn performance_get_embedded_metrics(port: Option<String>) -> Result<serde_json::
     Ok(serde_json::to_value(metrics).map_err(|e| e.to_string())?)
 }
 
+/// Decode RTOS metrics frames from a running RTT job and emit `metrics:update`
+#[tauri::command]
+async fn performance_start_embedded_metrics(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    rtt_job_id: String,
+) -> Result<String, String> {
+    performance::rtt_metrics::start_metrics_listener(app, state.job_manager.clone(), &rtt_job_id).await
+}
+
+/// Generate the firmware task that reports metrics over RTT
+#[tauri::command]
+fn generate_rtos_metrics_task(rtos: String, interval_ms: Option<u32>) -> Result<serde_json::Value, String> {
+    use drivers::rtos_gen::RtosType;
+
+    let rtos_type = match rtos.to_lowercase().as_str() {
+        "freertos" => RtosType::FreeRtos,
+        "zephyr" => RtosType::Zephyr,
+        "baremetal" | "bare_metal" => RtosType::BareMetal,
+        _ => return Err(format!("Unknown RTOS: {}", rtos)),
+    };
+    let code = performance::rtt_metrics::generate_rtos_metrics_task(rtos_type, interval_ms.unwrap_or(1000))?;
+
+    Ok(serde_json::json!({
+        "code": code,
+        "filename": "metrics_task.c",
+        "rtt_channel": performance::rtt_metrics::METRICS_CHANNEL,
+    }))
+}
+
+/// Check whether the host CPU is thermally or power throttled
+#[tauri::command]
+async fn performance_get_throttle_status() -> Result<serde_json::Value, String> {
+    let status = tokio::task::spawn_blocking(performance::throttle::detect_throttle)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(serde_json::to_value(status).map_err(|e| e.to_string())?)
+}
+
+// ==================== App Config Commands ====================
+
+/// Get the persisted user configuration
+#[tauri::command]
+fn get_app_config(state: State<'_, AppState>) -> Result<serde_json::Value, String> {
+    let config = state.config.lock().unwrap();
+    serde_json::to_value(&*config).map_err(|e| e.to_string())
+}
+
+/// Update the user configuration; fields left out keep their current value
+#[tauri::command]
+fn set_app_config(state: State<'_, AppState>, config: serde_json::Value) -> Result<serde_json::Value, String> {
+    let mut current = state.config.lock().unwrap();
+    let mut merged = serde_json::to_value(&*current).map_err(|e| e.to_string())?;
+    match (merged.as_object_mut(), config) {
+        (Some(fields), serde_json::Value::Object(updates)) => fields.extend(updates),
+        _ => return Err("Config must be a JSON object".to_string()),
+    }
+    let updated: config::AppConfig = serde_json::from_value(merged).map_err(|e| format!("Invalid config: {}", e))?;
+    config::save_config(&updated).map_err(|e| e.to_string())?;
+    *current = updated;
+    serde_json::to_value(&*current).map_err(|e| e.to_string())
+}
+
 // ==================== Toolchain & IDE Loop Commands ====================
 
 use toolchain::{
     BuildConfig, BuildResult, SizeReport, MapFileInfo,
-    probe::{ProbeConfig, ProbeInfo, FlashResult, CpuState, RegisterSet, RttChannel, RttMessage, ResetMode},
+    probe::{ProbeConfig, ProbeInfo, FlashResult, CpuState, RegisterSet, RttChannel, RttMessage, ResetMode, SoftDeviceInfo},
 };
 use std::sync::OnceLock;
 
@@ -2810,6 +4724,17 @@ fn toolchain_discover() -> Result<serde_json::Value, String> {
     Ok(serde_json::to_value(toolchains).map_err(|e| e.to_string())?)
 }
 
+/// Download and install the ARM GNU Toolchain, emitting `toolchain:download_progress`
+#[tauri::command]
+async fn toolchain_download(app: tauri::AppHandle, version: Option<String>, target_dir: String) -> Result<serde_json::Value, String> {
+    let version = version.unwrap_or_else(|| toolchain::discovery::DEFAULT_ARM_GCC_VERSION.to_string());
+    let release = version.clone();
+    let info = toolchain::discovery::download_arm_gcc(&version, std::path::Path::new(&target_dir), move |progress| {
+        let _ = app.emit("toolchain:download_progress", serde_json::json!({ "version": release, "progress": progress }));
+    }).await.map_err(|e| e.to_string())?;
+    Ok(serde_json::to_value(info).map_err(|e| e.to_string())?)
+}
+
 /// Build project using discovered toolchain
 #[tauri::command]
 async fn toolchain_build(config: BuildConfig) -> Result<BuildResult, String> {
@@ -2871,8 +4796,93 @@ fn toolchain_parse_map(map_path: String) -> Result<MapFileInfo, String> {
     gcc.parse_map(std::path::Path::new(&map_path)).map_err(|e| e.to_string())
 }
 
+/// Rank the symbols and object files taking the most space in a linker map
+///
+/// Symbols of `threshold_bytes` or less are left out of the symbol list.
+#[tauri::command]
+fn toolchain_bloat_report(map_path: String, top_n: usize, threshold_bytes: Option<u64>) -> Result<serde_json::Value, String> {
+    let content = std::fs::read_to_string(&map_path)
+        .map_err(|e| format!("Failed to read {}: {}", map_path, e))?;
+    let report = toolchain::bloat::bloat_report(&content, top_n, threshold_bytes.unwrap_or(0))
+        .map_err(|e| e.to_string())?;
+    let total: u64 = report.section_summary.iter().map(|(_, size)| size).sum();
+    
+    Ok(serde_json::json!({
+        "top_symbols": report.top_symbols,
+        "top_files": report.top_files,
+        "section_summary": report.section_summary,
+        "total_bytes": total,
+        "threshold_bytes": threshold_bytes.unwrap_or(0),
+    }))
+}
+
+/// Generate a CMake toolchain file for a discovered toolchain and MCU
+#[tauri::command]
+fn generate_cmake_toolchain_file(toolchain_id: String, mcu_family: String) -> Result<serde_json::Value, String> {
+    use toolchain::cmake_toolchain::{TOOLCHAIN_FILENAME, check_supported, generate_cmake_toolchain};
+
+    let toolchains = toolchain::discovery::discover_all();
+    let tc = toolchains.iter()
+        .find(|t| t.id == toolchain_id)
+        .ok_or_else(|| format!("Toolchain not found: {}", toolchain_id))?;
+    let family = parse_mcu_family(&mcu_family)?;
+    check_supported(tc, family)?;
+
+    Ok(serde_json::json!({
+        "content": generate_cmake_toolchain(tc, family),
+        "filename": TOOLCHAIN_FILENAME,
+    }))
+}
+
 // ==================== Probe Commands ====================
 
+/// Generate openocd.cfg for a probe/MCU combination
+#[tauri::command]
+fn generate_openocd_config(probe_type: String, mcu_family: String, speed_khz: u32) -> Result<serde_json::Value, String> {
+    use toolchain::openocd::{ProbeType, check_compatibility, generate_openocd_cfg};
+
+    let probe = match probe_type.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+        "stlink" | "stlinkv2" => ProbeType::StLinkV2,
+        "stlinkv3" => ProbeType::StLinkV3,
+        "jlink" => ProbeType::JLink,
+        "daplink" | "cmsisdap" => ProbeType::DapLink,
+        _ => return Err(format!("Unknown probe type: {}", probe_type)),
+    };
+    let family = parse_mcu_family(&mcu_family)?;
+    check_compatibility(probe, family, speed_khz)?;
+
+    Ok(serde_json::json!({
+        "config": generate_openocd_cfg(probe, family, speed_khz),
+        "filename": "openocd.cfg",
+    }))
+}
+
+/// Generate a J-Link Commander script that flashes an ELF
+#[tauri::command]
+fn generate_jlink_script(
+    mcu_family: String,
+    elf_path: String,
+    speed_khz: u32,
+    verify: bool,
+    interface: Option<String>,
+) -> Result<serde_json::Value, String> {
+    use hal::jlink::{JLinkInterface, check_jlink_support, generate_jlink_script_with_interface};
+
+    let family = parse_mcu_family(&mcu_family)?;
+    let interface = match interface.as_deref().map(str::to_lowercase).as_deref() {
+        None | Some("swd") => JLinkInterface::Swd,
+        Some("jtag") => JLinkInterface::Jtag,
+        Some(other) => return Err(format!("Unknown J-Link interface: {}", other)),
+    };
+    check_jlink_support(family, interface, speed_khz)?;
+
+    Ok(serde_json::json!({
+        "script": generate_jlink_script_with_interface(family, &elf_path, speed_khz, verify, interface),
+        "command": "JLink.exe -commandfile script.jlink",
+        "filename": "script.jlink",
+    }))
+}
+
 /// List connected debug probes
 #[tauri::command]
 fn probe_list() -> Result<Vec<ProbeInfo>, String> {
@@ -2951,6 +4961,14 @@ async fn probe_read_registers() -> Result<RegisterSet, String> {
     manager.read_registers().await.map_err(|e| e.to_string())
 }
 
+/// Identify the Nordic SoftDevice installed on the target, if any
+#[tauri::command]
+async fn probe_detect_softdevice() -> Result<Option<SoftDeviceInfo>, String> {
+    let pm = get_probe_manager();
+    let manager = pm.lock().await;
+    manager.detect_softdevice().await.map_err(|e| e.to_string())
+}
+
 // ==================== RTT Commands ====================
 
 /// Start RTT streaming
@@ -2978,6 +4996,28 @@ async fn rtt_stop() -> Result<(), String> {
     Ok(())
 }
 
+/// Compare an ELF's embedded version with the firmware running on the device
+///
+/// Without `device_version`, the version is taken from the latest RTT output.
+#[tauri::command]
+async fn hardware_compare_versions(elf_path: String, device_version: Option<String>) -> Result<serde_json::Value, String> {
+    use commands::hardware::elf_info;
+
+    let metadata = elf_info::read_firmware_metadata(std::path::Path::new(&elf_path))
+        .map_err(|e| e.to_string())?;
+    let device_version = match device_version {
+        Some(version) => version,
+        None => {
+            let pm = get_probe_manager();
+            let manager = pm.lock().await;
+            let messages = manager.read_rtt().await.map_err(|e| e.to_string())?;
+            elf_info::version_from_rtt(messages.iter().map(|m| m.data.as_str()))
+                .ok_or("Device did not report a firmware version over RTT")?
+        }
+    };
+    serde_json::to_value(elf_info::compare_firmware(&metadata, &device_version)).map_err(|e| e.to_string())
+}
+
 /// Decode HardFault from stack dump
 #[tauri::command]
 fn decode_hardfault(stack_hex: String, elf_path: Option<String>) -> Result<serde_json::Value, String> {
@@ -3042,7 +5082,7 @@ fn ai_set_provider(provider: String, _model: Option<String>, _api_key: Option<St
 
 // ==================== Streaming Build Commands ====================
 
-use toolchain::streaming_build::{StreamingBuildConfig, BuildEvent, BuildId};
+use toolchain::streaming_build::{StreamingBuildConfig, BuildId};
 
 /// Start a streaming build - returns build_id immediately, emits events via Tauri
 #[tauri::command]
@@ -3054,49 +5094,28 @@ async fn streaming_build_start(
     // Parse config
     let build_config: StreamingBuildConfig = serde_json::from_value(config)
         .map_err(|e| format!("Invalid build config: {}", e))?;
+    if build_config.build_system == toolchain::streaming_build::BuildSystem::Ninja && which::which("ninja").is_err() {
+        return Err("Ninja build selected but ninja was not found on PATH".to_string());
+    }
+    if build_config.build_system == toolchain::streaming_build::BuildSystem::Cargo && which::which("cargo").is_err() {
+        return Err("Cargo build selected but cargo was not found on PATH".to_string());
+    }
+    if build_config.build_system == toolchain::streaming_build::BuildSystem::CMake {
+        return Err("CMake builds are not supported yet; use Make or Ninja".to_string());
+    }
     
-    // Start build
-    let build_id = state.build_manager.start_build(build_config).await;
-    
-    // Spawn event forwarder to Tauri
-    let mut rx = state.build_manager.subscribe();
-    let bid = build_id.clone();
-    
-    tokio::spawn(async move {
-        while let Ok(event) = rx.recv().await {
-            // Extract build_id from header
-            let event_build_id = match &event {
-                BuildEvent::Started { header, .. } => &header.build_id,
-                BuildEvent::Output { header, .. } => &header.build_id,
-                BuildEvent::Diagnostic { header, .. } => &header.build_id,
-                BuildEvent::Progress { header, .. } => &header.build_id,
-                BuildEvent::Completed { header, .. } => &header.build_id,
-                BuildEvent::Cancelled { header, .. } => &header.build_id,
-                BuildEvent::InternalError { header, .. } => &header.build_id,
-            };
-            
-            if event_build_id == &bid {
-                // Emit to frontend
-                let event_name = match &event {
-                    BuildEvent::Started { .. } => "build:started",
-                    BuildEvent::Output { .. } => "build:output",
-                    BuildEvent::Diagnostic { .. } => "build:diagnostic",
-                    BuildEvent::Progress { .. } => "build:progress",
-                    BuildEvent::Completed { .. } => "build:completed",
-                    BuildEvent::Cancelled { .. } => "build:cancelled",
-                    BuildEvent::InternalError { .. } => "build:internal_error",
-                };
-                
-                let _ = app.emit(event_name, &event);
-                
-                // Stop forwarding after terminal events
-                if matches!(event, BuildEvent::Completed { .. } | BuildEvent::Cancelled { .. } | BuildEvent::InternalError { .. }) {
-                    break;
-                }
-            }
-        }
+    // Register the build as a job so it queues for the build slot at its priority
+    let (record, _tx) = state.job_manager.create_job(JobKind::Build);
+    let build_id = record.id.clone();
+    
+    // Forward this build's events to Tauri; subscribe first so `build:started` is not missed
+    state.job_manager.fanout().spawn_forwarder(&build_id, "build", move |event_name, payload| {
+        let _ = app.emit(&event_name, &payload);
     });
     
+    // Start build
+    let build_id = state.build_manager.start_build_with_id(build_id, build_config).await;
+    
     Ok(build_id)
 }
 
@@ -3159,10 +5178,23 @@ async fn streaming_build_get_artifacts(
     Ok(serde_json::json!({ "artifacts": artifacts }))
 }
 
+/// Sign a finished build's firmware with a PKCS#8 private key
+#[tauri::command]
+async fn streaming_build_sign_artifact(
+    state: State<'_, AppState>,
+    build_id: String,
+    key_path: String,
+) -> Result<serde_json::Value, String> {
+    let signed = state.build_manager.sign_artifacts(&build_id, std::path::PathBuf::from(key_path)).await?;
+    Ok(serde_json::to_value(signed).map_err(|e| e.to_string())?)
+}
+
 // ==================== Flash Commands ====================
 
-use jobs::{JobKind, JobInfo, JobStatus};
-use jobs::flash::{FlashConfig, MockProbeBackend, ProbeBackend};
+use jobs::{JobKind, JobInfo, JobPriority, JobStatus};
+use jobs::flash::{FlashConfig, ProbeBackend};
+#[cfg(not(feature = "hardware"))]
+use jobs::flash::MockProbeBackend;
 use jobs::flash::run_flash_job;
 
 /// Start flash operation
@@ -3196,7 +5228,10 @@ async fn flash_start(
         speed_khz: Some(4000),
     };
     
-    // Use mock backend for now
+    // Real probes need the "hardware" feature; otherwise flash against the mock
+    #[cfg(feature = "hardware")]
+    let backend = Arc::new(jobs::probe_rs_backend::ProbeRsBackend::new());
+    #[cfg(not(feature = "hardware"))]
     let backend = Arc::new(MockProbeBackend::new());
     
     // Create event emitter closure that uses Tauri
@@ -3225,7 +5260,7 @@ async fn flash_cancel(
 
 // ==================== RTT Commands ====================
 
-use jobs::rtt::{RttConfig, MockRttBackend as MockRtt, run_rtt_job};
+use jobs::rtt::{RttConfig, RttFilter, MockRttBackend as MockRtt, run_rtt_job};
 
 /// Start RTT streaming job (uses job manager, emits batched events)
 #[tauri::command]
@@ -3235,11 +5270,13 @@ async fn rtt_stream_start(
     chip: String,
     channels: Option<Vec<u32>>,
     poll_interval_ms: Option<u64>,
+    filter: Option<RttFilter>,
 ) -> Result<String, String> {
     let config = RttConfig {
         chip,
         channels: channels.unwrap_or(vec![0]),
         poll_interval_ms: poll_interval_ms.unwrap_or(10),
+        filter: filter.unwrap_or_default(),
         ..Default::default()
     };
     
@@ -3269,6 +5306,16 @@ async fn rtt_stream_stop(
     Ok(state.job_manager.cancel_job(&rtt_id))
 }
 
+/// Update a running RTT job's filter without restarting it
+#[tauri::command]
+async fn rtt_set_filter(
+    state: State<'_, AppState>,
+    rtt_id: String,
+    filter: RttFilter,
+) -> Result<(), String> {
+    state.job_manager.set_rtt_filter(&rtt_id, filter).await
+}
+
 // ==================== Run Chain Command ====================
 
 /// Run chain guidance: returns the workflow steps for build → flash → rtt
@@ -3374,6 +5421,10 @@ async fn job_list(
         "flash" => JobKind::Flash,
         "rtt" => JobKind::Rtt,
         "agent" => JobKind::Agent,
+        "index" => JobKind::Index,
+        "script" => JobKind::Script,
+        "register_watch" => JobKind::RegisterWatch,
+        "serial" => JobKind::Serial,
         _ => JobKind::Build,
     });
     Ok(state.job_manager.list_jobs(kind).await)
@@ -3409,6 +5460,65 @@ async fn job_cancel(
     Ok(state.job_manager.cancel_job(&job_id))
 }
 
+/// Follow a running job (or build) as `job:*` events until it ends - returns immediately
+#[tauri::command]
+async fn job_subscribe(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    job_id: String,
+) -> Result<(), String> {
+    let running = state.job_manager.get_job(&job_id).is_some()
+        || state.build_manager.active_builds().await.contains(&job_id);
+    if !running {
+        return Err(format!("Job {} is not running", job_id));
+    }
+    
+    // The job may have ended since the check; the fanout refuses ended jobs
+    let forwarder = state.job_manager.fanout().spawn_forwarder(&job_id, "job", move |event_name, payload| {
+        let _ = app.emit(&event_name, &payload);
+    });
+    if forwarder.is_none() {
+        return Err(format!("Job {} is not running", job_id));
+    }
+    Ok(())
+}
+
+/// Change a job's scheduling priority
+#[tauri::command]
+async fn job_set_priority(
+    state: State<'_, AppState>,
+    job_id: String,
+    priority: JobPriority,
+) -> Result<bool, String> {
+    Ok(state.job_manager.set_priority(&job_id, priority))
+}
+
+// ==================== Workspace Index Commands ====================
+
+/// Index a project's sources as a job, each file reported as `index:file_processed`
+#[tauri::command]
+async fn index_workspace(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    project_path: String,
+) -> Result<String, String> {
+    let emit_event = move |event_name: String, payload: serde_json::Value| {
+        let _ = app.emit(&event_name, &payload);
+    };
+
+    agents::indexer::run_index_job(
+        state.job_manager.clone(),
+        std::path::PathBuf::from(project_path),
+        emit_event,
+    ).await
+}
+
+/// Search indexed symbols by relevance to `query`
+#[tauri::command]
+fn index_query(query: String, top_k: usize) -> Result<Vec<agents::indexer::IndexEntry>, String> {
+    agents::indexer::query_index(&query, top_k).map_err(|e| e.to_string())
+}
+
 // ==================== Tool Registry Commands ====================
 
 use agents::typed_tools::{ToolContext, ToolPermission};
@@ -3458,14 +5568,16 @@ async fn tool_list(
 #[tauri::command]
 async fn tool_execute(
     state: State<'_, AppState>,
+    app: tauri::AppHandle,
     tool_name: String,
     input: serde_json::Value,
     agent_id: Option<String>,
 ) -> Result<serde_json::Value, String> {
     let registry = state.tool_registry.lock().await;
+    let agent_id = agent_id.unwrap_or_else(|| "user".to_string());
     
     // Create context with permissions
-    let ctx = ToolContext::new(agent_id.unwrap_or_else(|| "user".to_string()))
+    let ctx = ToolContext::new(agent_id.clone())
         .with_permissions(vec![
             ToolPermission::ReadFSM,
             ToolPermission::WriteFSM,
@@ -3474,8 +5586,22 @@ async fn tool_execute(
             ToolPermission::RunBuild,
         ]);
     
-    registry.execute(&tool_name, input, &ctx)
-        .map_err(|e| format!("{}", e))
+    let output = registry.execute(&tool_name, input, &ctx)
+        .map_err(|e| format!("{}", e))?;
+    drop(registry);
+    
+    if tool_name != agents::DELEGATE_TOOL {
+        return Ok(output);
+    }
+    
+    // Run the target agent now that the request is validated
+    let to_agent = output["to_agent"].as_str().unwrap_or_default();
+    let message = output["message"].as_str().unwrap_or_default();
+    let orchestrator = state.orchestrator.lock().await;
+    let response = orchestrator.delegate_with_events(message, &agent_id, to_agent, |event| {
+        let _ = app.emit("agent:delegated", &event);
+    }).await?;
+    serde_json::to_value(response).map_err(|e| e.to_string())
 }
 
 /// Get JSON schemas for all tools (for AI function calling)
@@ -3501,26 +5627,64 @@ async fn patch_propose(
     target_type: String,
     operations: serde_json::Value,
 ) -> Result<serde_json::Value, String> {
+    // Explain the patch with the reasoning behind the agent's latest answer
+    let reasoning = state.orchestrator.lock().await.last_reasoning(&agent_id).await;
     let mut audit_log = state.audit_log.lock().await;
     
-    // Parse target
-    let target = match target_type.as_str() {
-        "fsm_graph" => PatchTarget::FsmGraph,
-        "project_settings" => PatchTarget::ProjectSettings,
-        _ => return Err(format!("Unknown target type: {}", target_type)),
+    let patch = match target_type.as_str() {
+        // Code edits arrive as both versions of the file: { path, original, modified }
+        "generated_file" => {
+            let field = |name: &str| operations.get(name)
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| format!("Missing '{}' for generated_file patch", name));
+            let (path, original, modified) = (field("path")?, field("original")?, field("modified")?);
+            Patch::c_source_diff(description, std::path::PathBuf::from(path), original, modified)
+        }
+        _ => {
+            // Parse target
+            let target = match target_type.as_str() {
+                "fsm_graph" => PatchTarget::FsmGraph,
+                "project_settings" => PatchTarget::ProjectSettings,
+                _ => return Err(format!("Unknown target type: {}", target_type)),
+            };
+            
+            // Parse operations as JSON patch
+            let ops: Vec<JsonPatchOp> = serde_json::from_value(operations)
+                .map_err(|e| format!("Invalid patch operations: {}", e))?;
+            Patch::json_patch(description, target, ops)
+        }
     };
-    
-    // Parse operations as JSON patch
-    let ops: Vec<JsonPatchOp> = serde_json::from_value(operations)
-        .map_err(|e| format!("Invalid patch operations: {}", e))?;
-    
-    let patch = Patch::json_patch(description, target, ops);
-    let entry_id = audit_log.record_proposal(&agent_id, patch.clone());
+    let has_reasoning = reasoning.is_some();
+    let entry_id = audit_log.record_proposal_with_reasoning(&agent_id, patch.clone(), reasoning);
     
     Ok(serde_json::json!({
         "entry_id": entry_id,
         "patch_id": patch.id,
-        "status": "pending"
+        "status": "pending",
+        "semantic_changes": patch.semantic_changes,
+        "has_reasoning": has_reasoning,
+    }))
+}
+
+/// Get the reasoning trace recorded with a patch proposal
+#[tauri::command]
+async fn agent_get_reasoning_trace(
+    state: State<'_, AppState>,
+    entry_id: String,
+) -> Result<serde_json::Value, String> {
+    let audit_log = state.audit_log.lock().await;
+    let entry = audit_log.get(&entry_id)
+        .ok_or_else(|| format!("Unknown patch: {}", entry_id))?;
+    let trace = entry.reasoning.as_ref()
+        .ok_or_else(|| format!("No reasoning was captured for patch {}", entry_id))?;
+    
+    Ok(serde_json::json!({
+        "entry_id": entry.id,
+        "agent_id": entry.agent_id,
+        "description": entry.patch.description,
+        "steps": trace.steps,
+        "final_answer": trace.final_answer,
+        "confidence": trace.confidence,
     }))
 }
 
@@ -3528,22 +5692,141 @@ async fn patch_propose(
 #[tauri::command]
 async fn patch_apply(
     state: State<'_, AppState>,
+    app: tauri::AppHandle,
     entry_id: String,
+    document: Option<serde_json::Value>,
 ) -> Result<serde_json::Value, String> {
     let mut audit_log = state.audit_log.lock().await;
-    audit_log.record_applied(&entry_id);
+    let patch = audit_log.get(&entry_id)
+        .map(|entry| entry.patch.clone())
+        .ok_or_else(|| format!("Unknown patch: {}", entry_id))?;
+    
+    // Apply to the caller's copy of the FSM/config state when given
+    let patched = match &document {
+        Some(original) => {
+            let mut patched = original.clone();
+            agents::diff_engine::apply_patch(&patch, &mut patched).map_err(|e| e.to_string())?;
+            Some(patched)
+        }
+        None => None,
+    };
+    match &document {
+        Some(original) => audit_log.record_applied_to(&entry_id, original),
+        None => audit_log.record_applied(&entry_id),
+    }
+    drop(audit_log);
     
-    // In a real implementation, this would:
-    // 1. Get the patch from the audit log
-    // 2. Apply it to the actual FSM/config state
-    // 3. Emit an event to update the UI
+    // Explain the change in the background; the explanation arrives as an event
+    if let (Some(original), Some(modified)) = (&document, &patched) {
+        let (original, modified, language) = match (original, modified) {
+            (serde_json::Value::String(original), serde_json::Value::String(modified)) => {
+                (original.clone(), modified.clone(), "c")
+            }
+            _ => (
+                serde_json::to_string_pretty(original).unwrap_or_default(),
+                serde_json::to_string_pretty(modified).unwrap_or_default(),
+                "json",
+            ),
+        };
+        let id = entry_id.clone();
+        tokio::spawn(async move {
+            let service = AIService::new();
+            if !service.is_available() {
+                return;
+            }
+            match service.explain_diff(&original, &modified, language).await {
+                Ok(explanation) => {
+                    let _ = app.emit("audit:explanation_ready", serde_json::json!({
+                        "entry_id": id,
+                        "explanation": explanation,
+                    }));
+                }
+                Err(e) => log::warn!("Could not explain patch {}: {}", id, e),
+            }
+        });
+    }
     
     Ok(serde_json::json!({
         "entry_id": entry_id,
-        "status": "applied"
+        "status": "applied",
+        "document": patched,
+    }))
+}
+
+/// Undo/redo response with the patched document; notifies the FSM editor
+fn history_patch_result(
+    app: &tauri::AppHandle,
+    audit_log: &agents::AuditLog,
+    patch: Option<Patch>,
+    document: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let Some(patch) = patch else {
+        return Ok(serde_json::json!({
+            "applied": false,
+            "document": document,
+            "can_undo": audit_log.can_undo(),
+            "can_redo": audit_log.can_redo(),
+        }));
+    };
+    let _ = app.emit("fsm:graph_updated", serde_json::json!({
+        "description": patch.description,
+        "document": document,
+    }));
+    
+    Ok(serde_json::json!({
+        "applied": true,
+        "description": patch.description,
+        "document": document,
+        "can_undo": audit_log.can_undo(),
+        "can_redo": audit_log.can_redo(),
+    }))
+}
+
+/// Undo the most recently applied patch
+#[tauri::command]
+async fn patch_undo(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    mut document: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let mut audit_log = state.audit_log.lock().await;
+    let patch = audit_log.undo(&mut document).map_err(|e| e.to_string())?;
+    history_patch_result(&app, &audit_log, patch, document)
+}
+
+/// Re-apply the most recently undone patch
+#[tauri::command]
+async fn patch_redo(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    mut document: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let mut audit_log = state.audit_log.lock().await;
+    let patch = audit_log.redo(&mut document).map_err(|e| e.to_string())?;
+    history_patch_result(&app, &audit_log, patch, document)
+}
+
+/// Undo/redo availability for the toolbar buttons
+#[tauri::command]
+async fn patch_history_state(
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    let audit_log = state.audit_log.lock().await;
+    Ok(serde_json::json!({
+        "can_undo": audit_log.can_undo(),
+        "can_redo": audit_log.can_redo(),
     }))
 }
 
+/// Drop undo/redo history when the project is closed
+#[tauri::command]
+async fn patch_clear_history(
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.audit_log.lock().await.clear_history();
+    Ok(())
+}
+
 /// Reject a pending patch
 #[tauri::command]
 async fn patch_reject(
@@ -64,6 +64,86 @@ pub struct McuDefinition {
     pub specs: McuSpec,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Package {
+    Sop8,
+    Tssop20,
+    Tqfp32,
+    Qfn32,
+    Lqfp48,
+    Qfn48,
+    Ufqfpn48,
+    Qfn56,
+    Lqfp64,
+    Aqfn73,
+    Lqfp100,
+    Tqfp100,
+    Lqfp144,
+    Module,
+}
+
+impl Package {
+    /// Number of physical pins; 0 for modules
+    pub fn pin_count(&self) -> u32 {
+        match self {
+            Package::Sop8 => 8,
+            Package::Tssop20 => 20,
+            Package::Tqfp32 | Package::Qfn32 => 32,
+            Package::Lqfp48 | Package::Qfn48 | Package::Ufqfpn48 => 48,
+            Package::Qfn56 => 56,
+            Package::Lqfp64 => 64,
+            Package::Aqfn73 => 73,
+            Package::Lqfp100 | Package::Tqfp100 => 100,
+            Package::Lqfp144 => 144,
+            Package::Module => 0,
+        }
+    }
+}
+
+/// How many instances of a peripheral a variant has
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeripheralAvailability {
+    pub name: String,
+    pub count: u8,
+    pub restrictions: Option<String>,
+}
+
+/// A GPIO bank and the pins bonded out on a given package
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpioPort {
+    /// Port letter or number; empty for single-bank parts numbered GPIO0..n
+    pub name: String,
+    pub pins: Vec<u8>,
+}
+
+/// A specific part number within a family
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McuVariant {
+    pub id: String,
+    pub name: String,
+    pub flash_kb: u32,
+    pub ram_kb: u32,
+    pub package: Package,
+    pub peripherals: Vec<PeripheralAvailability>,
+    pub gpio_ports: Vec<GpioPort>,
+}
+
+impl McuVariant {
+    /// Whether `port`/`pin` is bonded out, e.g. ("A", 5) for PA5 or ("", 21) for GPIO21
+    pub fn has_pin(&self, port: &str, pin: u8) -> bool {
+        let port = port.trim();
+        let port = port.strip_prefix('P').filter(|p| !p.is_empty()).unwrap_or(port);
+        self.gpio_ports.iter().any(|p| {
+            (p.name.is_empty() || p.name.eq_ignore_ascii_case(port)) && p.pins.contains(&pin)
+        })
+    }
+
+    pub fn peripheral(&self, name: &str) -> Option<&PeripheralAvailability> {
+        self.peripherals.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+}
+
 /// Get all registered MCUs
 pub fn get_all_mcus() -> Vec<McuDefinition> {
     vec![
@@ -199,6 +279,225 @@ pub fn get_mcus_by_family(family: McuFamily) -> Vec<McuDefinition> {
     get_all_mcus().into_iter().filter(|m| m.family == family).collect()
 }
 
+fn periph(name: &str, count: u8, restrictions: Option<&str>) -> PeripheralAvailability {
+    PeripheralAvailability {
+        name: name.to_string(),
+        count,
+        restrictions: restrictions.map(String::from),
+    }
+}
+
+fn port(name: &str, pins: impl IntoIterator<Item = u8>) -> GpioPort {
+    GpioPort { name: name.to_string(), pins: pins.into_iter().collect() }
+}
+
+/// Full 16-pin ports, as on larger STM32/GD32 packages
+fn full_ports(names: &[&str]) -> Vec<GpioPort> {
+    names.iter().map(|name| port(name, 0..16)).collect()
+}
+
+fn variant(
+    id: &str,
+    name: &str,
+    (flash_kb, ram_kb): (u32, u32),
+    package: Package,
+    peripherals: Vec<PeripheralAvailability>,
+    gpio_ports: Vec<GpioPort>,
+) -> McuVariant {
+    McuVariant {
+        id: id.to_string(),
+        name: name.to_string(),
+        flash_kb,
+        ram_kb,
+        package,
+        peripherals,
+        gpio_ports,
+    }
+}
+
+/// Get the part-level variants of a family
+pub fn get_variants(family: McuFamily) -> Vec<McuVariant> {
+    match family {
+        McuFamily::Stm32 => vec![
+            variant("stm32f401cc", "STM32F401CCU6", (256, 64), Package::Ufqfpn48, vec![
+                periph("USART", 3, Some("USART6 shares PA11/PA12 with USB OTG FS")),
+                periph("SPI", 3, None),
+                periph("I2C", 3, None),
+                periph("ADC", 1, Some("10 external channels on 48-pin")),
+                periph("USB", 1, None),
+            ], [full_ports(&["A"]), vec![
+                port("B", (0..=10).chain(12..16)),
+                port("C", 13..16),
+                port("H", 0..2),
+            ]].concat()),
+            variant("stm32f401re", "STM32F401RET6", (512, 96), Package::Lqfp64, vec![
+                periph("USART", 3, None),
+                periph("SPI", 4, Some("SPI4 only on 100-pin packages")),
+                periph("I2C", 3, None),
+                periph("ADC", 1, Some("16 external channels")),
+                periph("SDIO", 1, None),
+                periph("USB", 1, None),
+            ], [full_ports(&["A", "C"]), vec![
+                port("B", (0..=10).chain(12..16)),
+                port("D", [2]),
+                port("H", 0..2),
+            ]].concat()),
+            variant("stm32f407vg", "STM32F407VGT6", (1024, 192), Package::Lqfp100, vec![
+                periph("USART", 6, Some("UART4/UART5 are asynchronous only")),
+                periph("SPI", 3, None),
+                periph("I2C", 3, None),
+                periph("CAN", 2, Some("CAN2 requires CAN1 clock enabled")),
+                periph("ADC", 3, None),
+                periph("ETH", 1, None),
+                periph("USB", 2, Some("OTG HS runs at full speed without an external ULPI PHY")),
+            ], [full_ports(&["A", "B", "C", "D", "E"]), vec![port("H", 0..2)]].concat()),
+            variant("stm32f407zg", "STM32F407ZGT6", (1024, 192), Package::Lqfp144, vec![
+                periph("USART", 6, Some("UART4/UART5 are asynchronous only")),
+                periph("SPI", 3, None),
+                periph("I2C", 3, None),
+                periph("CAN", 2, Some("CAN2 requires CAN1 clock enabled")),
+                periph("ADC", 3, None),
+                periph("ETH", 1, None),
+                periph("FSMC", 1, Some("Full address bus only on 144-pin")),
+                periph("USB", 2, None),
+            ], [full_ports(&["A", "B", "C", "D", "E", "F", "G"]), vec![port("H", 0..2)]].concat()),
+            variant("stm32f103c8", "STM32F103C8T6", (64, 20), Package::Lqfp48, vec![
+                periph("USART", 3, None),
+                periph("SPI", 2, None),
+                periph("I2C", 2, None),
+                periph("CAN", 1, Some("Shares SRAM with USB; cannot run at the same time")),
+                periph("ADC", 2, None),
+                periph("USB", 1, None),
+            ], [full_ports(&["A", "B"]), vec![port("C", 13..16), port("D", 0..2)]].concat()),
+            variant("stm32f103rb", "STM32F103RBT6", (128, 20), Package::Lqfp64, vec![
+                periph("USART", 3, None),
+                periph("SPI", 2, None),
+                periph("I2C", 2, None),
+                periph("CAN", 1, Some("Shares SRAM with USB; cannot run at the same time")),
+                periph("ADC", 2, None),
+                periph("USB", 1, None),
+            ], [full_ports(&["A", "B", "C"]), vec![port("D", 0..3)]].concat()),
+            variant("stm32h743vi", "STM32H743VIT6", (2048, 1024), Package::Lqfp100, vec![
+                periph("USART", 8, Some("UART7/UART8 are asynchronous only")),
+                periph("SPI", 6, None),
+                periph("I2C", 4, None),
+                periph("FDCAN", 2, None),
+                periph("ADC", 3, None),
+                periph("ETH", 1, Some("RMII only")),
+                periph("USB", 2, None),
+            ], [full_ports(&["A", "B", "C", "D", "E"]), vec![port("H", 0..2)]].concat()),
+        ],
+        McuFamily::Esp32 => vec![
+            variant("esp32-wroom-32", "ESP32-WROOM-32", (4096, 520), Package::Module, vec![
+                periph("UART", 3, None),
+                periph("SPI", 2, Some("SPI0/SPI1 are reserved for flash")),
+                periph("I2C", 2, None),
+                periph("ADC", 2, Some("ADC2 is unavailable while WiFi is active")),
+                periph("DAC", 2, None),
+            ], vec![port("", (0..=5).chain(12..=19).chain([21, 22, 23, 25, 26, 27]).chain(32..=39))]),
+            variant("esp32c3", "ESP32-C3", (4096, 400), Package::Qfn32, vec![
+                periph("UART", 2, None),
+                periph("SPI", 1, Some("SPI0/SPI1 are reserved for flash")),
+                periph("I2C", 1, None),
+                periph("ADC", 2, Some("ADC2 is unavailable while WiFi is active")),
+                periph("USB", 1, Some("USB Serial/JTAG only")),
+            ], vec![port("", 0..22)]),
+            variant("esp32s3", "ESP32-S3", (8192, 512), Package::Qfn56, vec![
+                periph("UART", 3, None),
+                periph("SPI", 2, Some("SPI0/SPI1 are reserved for flash")),
+                periph("I2C", 2, None),
+                periph("ADC", 2, Some("ADC2 is unavailable while WiFi is active")),
+                periph("USB", 1, None),
+            ], vec![port("", (0..22).chain(26..49))]),
+        ],
+        McuFamily::Rp2040 => vec![
+            variant("rp2040", "RP2040", (0, 264), Package::Qfn56, vec![
+                periph("UART", 2, None),
+                periph("SPI", 2, None),
+                periph("I2C", 2, None),
+                periph("ADC", 1, Some("4 external channels on GPIO26-29")),
+                periph("PIO", 2, None),
+                periph("USB", 1, None),
+            ], vec![port("", 0..30)]),
+        ],
+        McuFamily::Nrf => vec![
+            variant("nrf52840", "nRF52840-QIAA", (1024, 256), Package::Aqfn73, vec![
+                periph("UARTE", 2, None),
+                periph("SPIM", 4, Some("SPIM3 is the only instance above 8 MHz")),
+                periph("TWIM", 2, Some("Shares instances with SPIM0/SPIM1")),
+                periph("SAADC", 1, None),
+                periph("USB", 1, None),
+            ], vec![port("0", 0..32), port("1", 0..16)]),
+            variant("nrf52832", "nRF52832-QFAA", (512, 64), Package::Qfn48, vec![
+                periph("UARTE", 1, None),
+                periph("SPIM", 3, None),
+                periph("TWIM", 2, Some("Shares instances with SPIM0/SPIM1")),
+                periph("SAADC", 1, None),
+            ], vec![port("0", 0..32)]),
+        ],
+        McuFamily::Avr => vec![
+            variant("atmega328p", "ATmega328P-AU", (32, 2), Package::Tqfp32, vec![
+                periph("USART", 1, None),
+                periph("SPI", 1, None),
+                periph("TWI", 1, None),
+                periph("ADC", 1, Some("ADC6/ADC7 are analog-only on TQFP")),
+            ], vec![port("B", 0..8), port("C", 0..7), port("D", 0..8)]),
+            variant("atmega2560", "ATmega2560-16AU", (256, 8), Package::Tqfp100, vec![
+                periph("USART", 4, None),
+                periph("SPI", 1, None),
+                periph("TWI", 1, None),
+                periph("ADC", 1, None),
+            ], [
+                ["A", "B", "C", "D", "E", "F", "H", "J", "K", "L"].iter().map(|name| port(name, 0..8)).collect(),
+                vec![port("G", 0..6)],
+            ].concat()),
+        ],
+        McuFamily::Ch32 => vec![
+            variant("ch32v003f4p6", "CH32V003F4P6", (16, 2), Package::Tssop20, vec![
+                periph("USART", 1, None),
+                periph("SPI", 1, None),
+                periph("I2C", 1, None),
+                periph("ADC", 1, None),
+            ], vec![port("A", 1..3), port("C", 0..8), port("D", 0..8)]),
+            variant("ch32v003j4m6", "CH32V003J4M6", (16, 2), Package::Sop8, vec![
+                periph("USART", 1, Some("TX and RX share pins with SWIO")),
+                periph("I2C", 1, None),
+                periph("ADC", 1, None),
+            ], vec![port("A", 1..3), port("C", [1, 2, 4]), port("D", [1, 4, 5, 6])]),
+        ],
+        McuFamily::Gd32 => vec![
+            variant("gd32vf103cb", "GD32VF103CBT6", (128, 32), Package::Lqfp48, vec![
+                periph("USART", 3, None),
+                periph("SPI", 2, None),
+                periph("I2C", 2, None),
+                periph("CAN", 2, None),
+                periph("ADC", 2, None),
+                periph("USB", 1, None),
+            ], [full_ports(&["A", "B"]), vec![port("C", 13..16), port("D", 0..2)]].concat()),
+            variant("gd32vf103vb", "GD32VF103VBT6", (128, 32), Package::Lqfp100, vec![
+                periph("USART", 5, Some("UART3/UART4 are asynchronous only")),
+                periph("SPI", 3, None),
+                periph("I2C", 2, None),
+                periph("CAN", 2, None),
+                periph("ADC", 2, None),
+                periph("USB", 1, None),
+            ], full_ports(&["A", "B", "C", "D", "E"])),
+        ],
+        McuFamily::Samd | McuFamily::Pic | McuFamily::Msp430 => vec![],
+    }
+}
+
+/// Find a variant by part ID across all families
+pub fn get_variant(id: &str) -> Option<McuVariant> {
+    [
+        McuFamily::Stm32, McuFamily::Nrf, McuFamily::Esp32, McuFamily::Rp2040, McuFamily::Samd,
+        McuFamily::Avr, McuFamily::Pic, McuFamily::Msp430, McuFamily::Gd32, McuFamily::Ch32,
+    ]
+    .into_iter()
+    .flat_map(get_variants)
+    .find(|v| v.id.eq_ignore_ascii_case(id))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -212,4 +511,23 @@ mod tests {
         assert!(stm32.is_some());
         assert_eq!(stm32.unwrap().arch, Architecture::ArmCortexM4);
     }
+    
+    #[test]
+    fn test_variants() {
+        let stm32 = get_variants(McuFamily::Stm32);
+        let f401 = stm32.iter().find(|v| v.id == "stm32f401cc").unwrap();
+        let f407 = stm32.iter().find(|v| v.id == "stm32f407vg").unwrap();
+        assert_eq!(f401.peripheral("USART").unwrap().count, 3);
+        assert_eq!(f407.peripheral("usart").unwrap().count, 6);
+        
+        assert!(f401.has_pin("A", 5));
+        assert!(f401.has_pin("PC", 13));
+        assert!(!f401.has_pin("B", 11));
+        assert!(!f401.has_pin("E", 0));
+        assert!(f407.has_pin("E", 0));
+        
+        let rp2040 = get_variant("RP2040").unwrap();
+        assert!(rp2040.has_pin("", 29));
+        assert!(!rp2040.has_pin("", 30));
+    }
 }
@@ -0,0 +1,439 @@
+// Linker Map Visualizer
+// Places every function and variable of a GNU ld map file in its flash/RAM region
+
+use super::{format_bytes, MemoryRegion};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ParseError {
+    #[error("Failed to read map file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("No allocated sections found; is this a GNU ld map file?")]
+    NoSections,
+}
+
+/// One symbol (or anonymous piece of an input section) at its final address
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryBlock {
+    pub symbol: String,
+    pub address: u64,
+    pub size: u64,
+    pub percentage_of_region: f32,
+    /// Object file or archive member the section came from
+    pub file: Option<String>,
+}
+
+/// Blocks sorted largest first, with the regions they were placed in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryVisualization {
+    pub flash_sections: Vec<MemoryBlock>,
+    pub ram_sections: Vec<MemoryBlock>,
+    pub regions: Vec<MemoryRegion>,
+}
+
+/// Input section from the memory map part of the file
+pub(crate) struct InputSection {
+    /// Output section it was placed in, e.g. `.text`
+    pub(crate) output: String,
+    pub(crate) name: String,
+    pub(crate) address: u64,
+    pub(crate) size: u64,
+    pub(crate) file: Option<String>,
+    symbols: Vec<(u64, String)>,
+}
+
+impl InputSection {
+    /// Whether the section is loaded on the target (not debug info or notes)
+    pub(crate) fn is_allocated(&self) -> bool {
+        !NON_ALLOC_SECTIONS.iter().any(|prefix| self.output.starts_with(prefix))
+    }
+
+    /// `(name, address, size)` per global symbol, sized up to the next symbol or
+    /// the section end; bytes before the first symbol are named after the section
+    pub(crate) fn symbol_sizes(&self) -> Vec<(String, u64, u64)> {
+        let Some(end) = self.address.checked_add(self.size) else {
+            return Vec::new();
+        };
+        let mut symbols = self.symbols.clone();
+        symbols.sort_by_key(|(address, _)| *address);
+        symbols.dedup_by_key(|(address, _)| *address);
+
+        let first = symbols.first().map(|(address, _)| *address).unwrap_or(end);
+        let mut sizes = vec![(section_symbol(&self.name), self.address, first - self.address)];
+        for (i, (address, name)) in symbols.iter().enumerate() {
+            let next = symbols.get(i + 1).map(|(address, _)| *address).unwrap_or(end);
+            sizes.push((name.clone(), *address, next - address));
+        }
+        sizes.retain(|(_, _, size)| *size > 0);
+        sizes
+    }
+}
+
+/// Output sections that are not loaded on the target
+const NON_ALLOC_SECTIONS: &[&str] = &[".debug", ".comment", ".ARM.attributes", ".riscv.attributes", ".stab", ".note"];
+
+const FLASH_SECTIONS: &[&str] = &[".isr_vector", ".text", ".rodata", ".ARM", ".init", ".fini", ".preinit_array", ".flash"];
+
+fn parse_hex(token: &str) -> Option<u64> {
+    u64::from_str_radix(token.strip_prefix("0x")?, 16).ok()
+}
+
+/// Symbol name from an input section name: `.text.main` -> `main`
+fn section_symbol(name: &str) -> String {
+    match name.strip_prefix('.').and_then(|rest| rest.split_once('.')) {
+        Some((_, symbol)) if !symbol.is_empty() => symbol.to_string(),
+        _ => name.to_string(),
+    }
+}
+
+/// Parse and place every allocated symbol of the map file at `map_path`
+pub fn parse_and_visualize(map_path: &Path) -> Result<MemoryVisualization, ParseError> {
+    let content = std::fs::read_to_string(map_path)?;
+    parse_map(&content)
+}
+
+/// Parse map file text
+///
+/// Regions come from the "Memory Configuration" table; a region with the
+/// `w` attribute is RAM. Global symbols split their input section, and
+/// sections without one (static functions, string literals) are named after
+/// the section. Initialized data is shown where it runs, in RAM.
+pub fn parse_map(content: &str) -> Result<MemoryVisualization, ParseError> {
+    let (mut regions, sections) = parse_sections(content);
+
+    let mut flash_sections = Vec::new();
+    let mut ram_sections = Vec::new();
+    let has_regions = !regions.is_empty();
+    for section in sections.iter().filter(|s| s.is_allocated()) {
+        let region = regions.iter_mut()
+            .find(|(_, origin, length)| section.address >= *origin && section.address < origin.saturating_add(*length));
+        let is_ram = match region {
+            Some((region, _, _)) => {
+                region.used = region.used.saturating_add(section.size as u32);
+                region.region_type == "ram"
+            }
+            // No region table: go by the output section name
+            None if !has_regions => !FLASH_SECTIONS.iter().any(|prefix| section.output.starts_with(prefix)),
+            None => continue,
+        };
+        let blocks = if is_ram { &mut ram_sections } else { &mut flash_sections };
+        blocks.extend(section.symbol_sizes().into_iter().map(|(symbol, address, size)| MemoryBlock {
+            symbol,
+            address,
+            size,
+            percentage_of_region: 0.0,
+            file: section.file.clone(),
+        }));
+    }
+    if flash_sections.is_empty() && ram_sections.is_empty() {
+        return Err(ParseError::NoSections);
+    }
+
+    let region_size = |address: u64, blocks: &[MemoryBlock]| {
+        regions.iter()
+            .find(|(_, origin, length)| address >= *origin && address < origin.saturating_add(*length))
+            .map(|(_, _, length)| *length)
+            .unwrap_or_else(|| blocks.iter().map(|b| b.size).sum())
+    };
+    for blocks in [&mut flash_sections, &mut ram_sections] {
+        let sizes: Vec<u64> = blocks.iter().map(|b| region_size(b.address, blocks)).collect();
+        for (block, size) in blocks.iter_mut().zip(sizes) {
+            block.percentage_of_region = if size > 0 { block.size as f32 / size as f32 * 100.0 } else { 0.0 };
+        }
+        blocks.sort_by(|a, b| b.size.cmp(&a.size).then(a.address.cmp(&b.address)));
+    }
+
+    Ok(MemoryVisualization {
+        flash_sections,
+        ram_sections,
+        regions: regions.into_iter().map(|(region, _, _)| region).collect(),
+    })
+}
+
+/// Memory regions (with origin and length) and input sections of a map file
+pub(crate) fn parse_sections(content: &str) -> (Vec<(MemoryRegion, u64, u64)>, Vec<InputSection>) {
+    let mut regions: Vec<(MemoryRegion, u64, u64)> = Vec::new();
+    let mut sections: Vec<InputSection> = Vec::new();
+    let mut in_config = false;
+    let mut in_map = false;
+    let mut output = String::new();
+    // Section names too long for their column put the address on the next line
+    let mut pending_output = false;
+    let mut pending_input: Option<String> = None;
+
+    for line in content.lines() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if line.starts_with("Memory Configuration") {
+            in_config = true;
+            continue;
+        }
+        if line.starts_with("Linker script and memory map") {
+            in_config = false;
+            in_map = true;
+            continue;
+        }
+
+        if in_config {
+            if let [name, origin, length, rest @ ..] = tokens.as_slice() {
+                if let (Some(origin), Some(length)) = (parse_hex(origin), parse_hex(length)) {
+                    if *name != "*default*" {
+                        let is_ram = rest.first().is_some_and(|attrs| attrs.contains('w'));
+                        regions.push((
+                            MemoryRegion {
+                                name: name.to_string(),
+                                start: origin as u32,
+                                size: length as u32,
+                                used: 0,
+                                region_type: if is_ram { "ram" } else { "flash" }.to_string(),
+                            },
+                            origin,
+                            length,
+                        ));
+                    }
+                }
+            }
+            continue;
+        }
+        if !in_map || tokens.is_empty() {
+            continue;
+        }
+
+        if !line.starts_with(' ') {
+            // Output section: `.text  0x08000190  0x1234`
+            pending_input = None;
+            if line.starts_with('.') {
+                output = tokens[0].to_string();
+                pending_output = tokens.len() == 1;
+            } else {
+                output.clear();
+            }
+            continue;
+        }
+        if pending_output {
+            pending_output = false;
+            continue;
+        }
+
+        let indented_name = line.starts_with(' ') && !line.starts_with("  ");
+        if indented_name && !tokens[0].starts_with('*') {
+            // Input section: ` .text.main  0x080001ec  0x48 build/main.o`
+            match tokens.as_slice() {
+                [name] => pending_input = Some(name.to_string()),
+                [name, address, size, file @ ..] => {
+                    pending_input = None;
+                    push_section(&mut sections, &output, name, address, size, file);
+                }
+                _ => pending_input = None,
+            }
+            continue;
+        }
+
+        if let Some(name) = pending_input.take() {
+            if let [address, size, file @ ..] = tokens.as_slice() {
+                if parse_hex(size).is_some() {
+                    push_section(&mut sections, &output, &name, address, size, file);
+                    continue;
+                }
+            }
+        }
+
+        // Global symbol: `0x080001ec  main`
+        if let [address, symbol] = tokens.as_slice() {
+            if let (Some(address), None) = (parse_hex(address), parse_hex(symbol)) {
+                if let Some(section) = sections.last_mut() {
+                    let in_section = address >= section.address
+                        && section.address.checked_add(section.size).is_some_and(|end| address < end);
+                    if in_section {
+                        section.symbols.push((address, symbol.to_string()));
+                    }
+                }
+            }
+        }
+    }
+
+    (regions, sections)
+}
+
+fn push_section(sections: &mut Vec<InputSection>, output: &str, name: &str, address: &str, size: &str, file: &[&str]) {
+    let (Some(address), Some(size)) = (parse_hex(address), parse_hex(size)) else {
+        return;
+    };
+    // Empty, or running past the end of the address space in a corrupt map
+    if size == 0 || address.checked_add(size).is_none() {
+        return;
+    }
+    sections.push(InputSection {
+        output: output.to_string(),
+        name: name.to_string(),
+        address,
+        size,
+        file: (!file.is_empty()).then(|| file.join(" ")),
+        symbols: Vec::new(),
+    });
+}
+
+impl MemoryVisualization {
+    /// Text bar chart of region usage and the largest `top` blocks of each region type
+    pub fn to_bar_chart(&self, top: usize) -> String {
+        const REGION_BAR: usize = 30;
+        const BLOCK_BAR: usize = 20;
+        let bar = |fraction: f32, width: usize| {
+            let filled = ((fraction.clamp(0.0, 1.0) * width as f32).round() as usize).min(width);
+            format!("[{}{}]", "#".repeat(filled), ".".repeat(width - filled))
+        };
+
+        let mut chart = String::new();
+        for region in &self.regions {
+            let fraction = if region.size > 0 { region.used as f32 / region.size as f32 } else { 0.0 };
+            let _ = writeln!(
+                chart,
+                "{:<10} {} {:>5.1}%  {} of {}",
+                region.name,
+                bar(fraction, REGION_BAR),
+                fraction * 100.0,
+                format_bytes(region.used),
+                format_bytes(region.size)
+            );
+        }
+
+        for (title, blocks) in [("Flash", &self.flash_sections), ("RAM", &self.ram_sections)] {
+            if blocks.is_empty() {
+                continue;
+            }
+            // Bars relative to the largest block so small symbols stay visible
+            let largest = blocks[0].size.max(1) as f32;
+            let _ = writeln!(chart, "\nLargest in {}", title);
+            for block in blocks.iter().take(top) {
+                let file = block.file.as_deref()
+                    .map(|f| f.rsplit(['/', '\\']).next().unwrap_or(f))
+                    .unwrap_or("");
+                let _ = writeln!(
+                    chart,
+                    "  {:<28} {} {:>5.1}%  {:>10}  {}",
+                    block.symbol,
+                    bar(block.size as f32 / largest, BLOCK_BAR),
+                    block.percentage_of_region,
+                    format_bytes(block.size as u32),
+                    file
+                );
+            }
+        }
+        chart
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = r#"Archive member included to satisfy reference by file (symbol)
+
+Discarded input sections
+
+ .text          0x0000000000000000        0x0 build/main.o
+ .text.unused   0x0000000000000000       0x20 build/main.o
+
+Memory Configuration
+
+Name             Origin             Length             Attributes
+FLASH            0x0000000008000000 0x0000000000080000 xr
+RAM              0x0000000020000000 0x0000000000020000 xrw
+*default*        0x0000000000000000 0xffffffffffffffff
+
+Linker script and memory map
+
+LOAD build/main.o
+                0x0000000020020000                _estack = (ORIGIN (RAM) + LENGTH (RAM))
+
+.isr_vector     0x0000000008000000      0x188
+                0x0000000008000000                . = ALIGN (0x4)
+ *(.isr_vector)
+ .isr_vector    0x0000000008000000      0x188 build/startup_stm32f407xx.o
+                0x0000000008000000                g_pfnVectors
+
+.text           0x0000000008000190      0x1a0
+ *(.text*)
+ .text.main     0x0000000008000190       0x60 build/main.o
+                0x0000000008000190                main
+ .text.led_blink_helper
+                0x00000000080001f0       0x40 build/main.o
+ .text          0x0000000008000230      0x100 /opt/arm/lib/libc_nano.a(lib_a-memcpy.o)
+                0x0000000008000230                memcpy
+                0x00000000080002b0                memset
+ *fill*         0x0000000008000330        0x0
+
+.data           0x0000000020000000        0x8 load address 0x0000000008000330
+ .data.counter  0x0000000020000000        0x4 build/main.o
+                0x0000000020000000                counter
+ .data.mode     0x0000000020000004        0x4 build/main.o
+
+.bss            0x0000000020000008      0x404
+ COMMON         0x0000000020000008      0x400 build/main.o
+                0x0000000020000008                rx_buffer
+ .bss.ticks     0x0000000020000408        0x4 build/main.o
+                0x0000000020000408                ticks
+
+.debug_info     0x0000000000000000     0x1234
+ .debug_info    0x0000000000000000     0x1234 build/main.o
+"#;
+
+    #[test]
+    fn test_parse_map_places_symbols() {
+        let map = parse_map(MAP).unwrap();
+
+        let flash: Vec<(&str, u64)> = map.flash_sections.iter().map(|b| (b.symbol.as_str(), b.size)).collect();
+        assert_eq!(flash, vec![
+            ("g_pfnVectors", 0x188),
+            ("memcpy", 0x80),
+            ("memset", 0x80),
+            ("main", 0x60),
+            ("led_blink_helper", 0x40),
+        ]);
+        let ram: Vec<(&str, u64)> = map.ram_sections.iter().map(|b| (b.symbol.as_str(), b.size)).collect();
+        assert_eq!(ram, vec![("rx_buffer", 0x400), ("counter", 4), ("mode", 4), ("ticks", 4)]);
+
+        let main = &map.flash_sections[3];
+        assert_eq!(main.address, 0x0800_0190);
+        assert_eq!(main.file.as_deref(), Some("build/main.o"));
+        assert!((map.ram_sections[0].percentage_of_region - 0.78125).abs() < 1e-4);
+        assert_eq!(map.flash_sections[1].file.as_deref(), Some("/opt/arm/lib/libc_nano.a(lib_a-memcpy.o)"));
+
+        assert_eq!(map.regions.len(), 2);
+        assert_eq!(map.regions[0].used, 0x188 + 0x1a0);
+        assert_eq!(map.regions[1].region_type, "ram");
+        assert_eq!(map.regions[1].used, 0x40c);
+    }
+
+    #[test]
+    fn test_bar_chart() {
+        let chart = parse_map(MAP).unwrap().to_bar_chart(3);
+        assert!(chart.contains("RAM        [..............................]   0.8%  1.01 KB of 128.00 KB"));
+        assert!(chart.contains("Largest in Flash"));
+        assert!(chart.contains("  g_pfnVectors                 [####################]"));
+        assert!(chart.contains("lib_a-memcpy.o)"));
+        assert!(!chart.contains("led_blink_helper"));
+    }
+
+    #[test]
+    fn test_overflowing_section_is_skipped() {
+        let corrupt = MAP.replace(
+            " .data.mode     0x0000000020000004        0x4 build/main.o",
+            " .data.mode     0xfffffffffffffff0      0x100 build/main.o\n                0xfffffffffffffff8                mode",
+        );
+        let (_, sections) = parse_sections(&corrupt);
+        assert!(!sections.iter().any(|s| s.name == ".data.mode"));
+
+        let map = parse_map(&corrupt).unwrap();
+        assert!(!map.ram_sections.iter().any(|b| b.symbol == "mode"));
+        assert!(map.ram_sections.iter().any(|b| b.symbol == "counter"));
+    }
+
+    #[test]
+    fn test_not_a_map_file() {
+        assert!(matches!(parse_map("hello\nworld\n"), Err(ParseError::NoSections)));
+    }
+}
@@ -1,6 +1,8 @@
 // Memory Analyzer Module
 // RAM/Flash usage visualization and analysis
 
+pub mod map_visualizer;
+
 use serde::{Deserialize, Serialize};
 
 /// Memory region
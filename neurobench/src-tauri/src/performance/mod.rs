@@ -5,6 +5,9 @@ use serde::{Deserialize, Serialize};
 use sysinfo::{System, Disks, Networks, Pid, ProcessesToUpdate};
 use std::collections::HashMap;
 
+pub mod throttle;
+pub mod rtt_metrics;
+
 /// System metrics snapshot
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemMetrics {
@@ -12,6 +15,7 @@ pub struct SystemMetrics {
     pub memory: MemoryMetrics,
     pub disks: Vec<DiskMetrics>,
     pub network: NetworkMetrics,
+    pub throttle: throttle::ThrottleStatus,
     pub uptime: u64,
     pub timestamp: u64,
 }
@@ -97,6 +101,9 @@ pub struct EmbeddedMetrics {
     pub ram_used_kb: u32,
     pub flash_total_kb: u32,
     pub ram_total_kb: u32,
+    /// Live RTOS metrics from the RTT metrics channel, when a listener is running
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rtos: Option<rtt_metrics::MetricsFrame>,
 }
 
 /// Performance history for graphs (last 60 data points)
@@ -235,6 +242,7 @@ pub fn get_system_metrics() -> SystemMetrics {
         memory,
         disks,
         network,
+        throttle: throttle::detect_throttle(),
         uptime: System::uptime(),
         timestamp: std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -306,6 +314,7 @@ pub fn get_embedded_metrics(port: Option<&str>) -> EmbeddedMetrics {
         ram_used_kb: 12,
         flash_total_kb: 256,
         ram_total_kb: 64,
+        rtos: rtt_metrics::latest_metrics(),
     }
 }
 
@@ -0,0 +1,409 @@
+// Embedded Metrics over RTT
+// Live RTOS metrics reported by the firmware on RTT channel 1
+//
+// Frames are newline-terminated JSON objects:
+//   {"cpu":85,"heap_free":4096,"task_counts":[1,2,5,0]}
+// where task_counts is [running, ready, blocked, suspended].
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, EventId, Listener};
+
+use crate::drivers::rtos_gen::RtosType;
+use crate::jobs::{JobKind, JobManager};
+
+/// RTT up-channel the firmware reports metrics on
+pub const METRICS_CHANNEL: u32 = 1;
+
+/// Event carrying decoded frames to the UI
+pub const METRICS_EVENT: &str = "metrics:update";
+
+/// Longest partial frame kept while waiting for its newline
+const MAX_PENDING_BYTES: usize = 1024;
+
+/// One metrics report from the target
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsFrame {
+    /// CPU load in percent
+    pub cpu: f32,
+    /// Free RTOS heap in bytes
+    pub heap_free: u32,
+    /// Tasks per state: running, ready, blocked, suspended
+    #[serde(default)]
+    pub task_counts: Vec<u32>,
+}
+
+lazy_static::lazy_static! {
+    /// Most recent frame and the RTT job it came from
+    static ref LATEST: Mutex<Option<(String, MetricsFrame)>> = Mutex::new(None);
+    /// Message listener of each RTT job being decoded
+    static ref LISTENERS: Mutex<HashMap<String, EventId>> = Mutex::new(HashMap::new());
+}
+
+/// Most recent frame received from a running metrics listener
+pub fn latest_metrics() -> Option<MetricsFrame> {
+    LATEST.lock().unwrap().as_ref().map(|(_, frame)| frame.clone())
+}
+
+fn record_latest(rtt_job_id: &str, frame: MetricsFrame) {
+    *LATEST.lock().unwrap() = Some((rtt_job_id.to_string(), frame));
+}
+
+/// Forget the latest frame if `rtt_job_id` sent it, so a stopped stream reports nothing
+fn clear_latest(rtt_job_id: &str) {
+    let mut latest = LATEST.lock().unwrap();
+    if latest.as_ref().is_some_and(|(job_id, _)| job_id == rtt_job_id) {
+        *latest = None;
+    }
+}
+
+/// Reassembles metrics frames from RTT channel 1 text
+///
+/// RTT reads can split a frame or carry several; text is buffered until a
+/// newline. Lines that aren't valid frames are counted and skipped.
+#[derive(Debug, Default)]
+pub struct MetricsChannel {
+    pending: String,
+    pub invalid_frames: u64,
+}
+
+impl MetricsChannel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed text read from the metrics channel, returning completed frames
+    pub fn push(&mut self, text: &str) -> Vec<MetricsFrame> {
+        self.pending.push_str(text);
+        let mut frames = Vec::new();
+        while let Some(end) = self.pending.find('\n') {
+            let line: String = self.pending.drain(..=end).collect();
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(frame) => frames.push(frame),
+                Err(_) => self.invalid_frames += 1,
+            }
+        }
+        if self.pending.len() > MAX_PENDING_BYTES {
+            self.pending.clear();
+            self.invalid_frames += 1;
+        }
+        frames
+    }
+
+    /// Feed an `rtt:message` batch payload, using only the metrics channel
+    pub fn push_batch(&mut self, payload: &serde_json::Value) -> Vec<MetricsFrame> {
+        let Some(messages) = payload["messages"].as_array() else {
+            return Vec::new();
+        };
+        messages
+            .iter()
+            .filter(|m| m["channel"].as_u64() == Some(METRICS_CHANNEL as u64))
+            .filter_map(|m| m["text"].as_str())
+            .flat_map(|text| self.push(text))
+            .collect()
+    }
+}
+
+/// Listen to a running RTT job's batches and emit `metrics:update` for each frame
+///
+/// Returns the listener id; starting again for the same job returns the same id.
+/// Listening stops when the RTT job ends, and its last frame is dropped.
+pub async fn start_metrics_listener(
+    app: AppHandle,
+    job_manager: Arc<JobManager>,
+    rtt_job_id: &str,
+) -> Result<String, String> {
+    let record = job_manager.get_job(rtt_job_id)
+        .ok_or_else(|| format!("RTT job not found: {}", rtt_job_id))?;
+    if record.kind != JobKind::Rtt {
+        return Err(format!("Job {} is not an RTT stream", rtt_job_id));
+    }
+    if record.status.read().await.terminal.is_some() {
+        return Err(format!("RTT job {} has already finished", rtt_job_id));
+    }
+    let channels = job_manager.rtt_channels(rtt_job_id).unwrap_or_default();
+    if !channels.contains(&METRICS_CHANNEL) {
+        return Err(format!(
+            "RTT job {} reads channels {:?}, not metrics channel {}; restart it with channel {}",
+            rtt_job_id, channels, METRICS_CHANNEL, METRICS_CHANNEL
+        ));
+    }
+
+    // Held until the listener is registered, so concurrent starts share one
+    let mut running = LISTENERS.lock().unwrap();
+    if let Some(id) = running.get(rtt_job_id) {
+        return Ok(id.to_string());
+    }
+
+    let prefix = JobKind::Rtt.event_prefix();
+    let job_id = rtt_job_id.to_string();
+    let is_this_job = move |payload: &serde_json::Value| payload["header"]["job_id"] == job_id.as_str();
+
+    let channel = Mutex::new(MetricsChannel::new());
+    let emitter = app.clone();
+    let matches = is_this_job.clone();
+    let rtt_job = rtt_job_id.to_string();
+    let message_id = app.listen(format!("{}:message", prefix), move |event| {
+        let Ok(payload) = serde_json::from_str::<serde_json::Value>(event.payload()) else {
+            return;
+        };
+        if !matches(&payload) {
+            return;
+        }
+        for frame in channel.lock().unwrap().push_batch(&payload) {
+            record_latest(&rtt_job, frame.clone());
+            let _ = emitter.emit(METRICS_EVENT, serde_json::json!({
+                "rtt_job_id": rtt_job,
+                "metrics": frame,
+            }));
+        }
+    });
+
+    running.insert(rtt_job_id.to_string(), message_id);
+    drop(running);
+
+    // Stop listening once the stream terminates
+    let listeners: Arc<Mutex<Vec<EventId>>> = Arc::new(Mutex::new(vec![message_id]));
+    for terminal in ["completed", "cancelled", "internal_error"] {
+        let handle = app.clone();
+        let matches = is_this_job.clone();
+        let registered = listeners.clone();
+        let rtt_job = rtt_job_id.to_string();
+        let id = app.listen(format!("{}:{}", prefix, terminal), move |event| {
+            let ended = serde_json::from_str::<serde_json::Value>(event.payload())
+                .is_ok_and(|payload| matches(&payload));
+            if ended {
+                for id in registered.lock().unwrap().drain(..) {
+                    handle.unlisten(id);
+                }
+                LISTENERS.lock().unwrap().remove(&rtt_job);
+                clear_latest(&rtt_job);
+            }
+        });
+        listeners.lock().unwrap().push(id);
+    }
+
+    Ok(message_id.to_string())
+}
+
+/// Generate the firmware-side task that reports metrics on RTT channel 1
+pub fn generate_rtos_metrics_task(rtos: RtosType, interval_ms: u32) -> Result<String, String> {
+    if interval_ms == 0 {
+        return Err("Metrics interval must be at least 1 ms".to_string());
+    }
+    let header = format!(
+        "/**\n * RTOS metrics reporter - Generated by NeuroBench\n *\n * Sends one JSON frame every {} ms on RTT channel {}:\n *   {{\"cpu\":85,\"heap_free\":4096,\"task_counts\":[running,ready,blocked,suspended]}}\n",
+        interval_ms, METRICS_CHANNEL
+    );
+
+    match rtos {
+        RtosType::FreeRtos => Ok(format!(r#"{header} *
+ * FreeRTOSConfig.h needs:
+ *   configUSE_TRACE_FACILITY 1, configGENERATE_RUN_TIME_STATS 1,
+ *   INCLUDE_xTaskGetIdleTaskHandle 1
+ * Call metrics_task_start() before vTaskStartScheduler().
+ */
+
+#include "FreeRTOS.h"
+#include "task.h"
+#include "SEGGER_RTT.h"
+#include <stdio.h>
+
+#define METRICS_RTT_CHANNEL  {channel}
+#define METRICS_INTERVAL_MS  {interval}
+#define METRICS_MAX_TASKS    16
+
+static char metrics_rtt_buffer[256];
+
+static void metrics_task(void *arg) {{
+    static TaskStatus_t tasks[METRICS_MAX_TASKS];
+    uint32_t last_total = 0, last_idle = 0;
+    char frame[128];
+    (void)arg;
+
+    SEGGER_RTT_ConfigUpBuffer(METRICS_RTT_CHANNEL, "Metrics", metrics_rtt_buffer,
+                              sizeof(metrics_rtt_buffer), SEGGER_RTT_MODE_NO_BLOCK_SKIP);
+
+    for (;;) {{
+        uint32_t total = 0, idle = 0;
+        unsigned counts[4] = {{0}};
+        UBaseType_t n = uxTaskGetSystemState(tasks, METRICS_MAX_TASKS, &total);
+
+        for (UBaseType_t i = 0; i < n; i++) {{
+            if (tasks[i].xHandle == xTaskGetIdleTaskHandle()) {{
+                idle = tasks[i].ulRunTimeCounter;
+            }}
+            switch (tasks[i].eCurrentState) {{
+                case eRunning:   counts[0]++; break;
+                case eReady:     counts[1]++; break;
+                case eBlocked:   counts[2]++; break;
+                case eSuspended: counts[3]++; break;
+                default: break;
+            }}
+        }}
+
+        /* Load over the last interval = share of run time not spent idle */
+        uint32_t d_total = total - last_total;
+        uint32_t d_idle = idle - last_idle;
+        unsigned cpu = (d_total > 0 && d_idle <= d_total)
+            ? 100u - (unsigned)((uint64_t)d_idle * 100u / d_total) : 0u;
+        last_total = total;
+        last_idle = idle;
+
+        int len = snprintf(frame, sizeof(frame),
+                           "{{\"cpu\":%u,\"heap_free\":%u,\"task_counts\":[%u,%u,%u,%u]}}\n",
+                           cpu, (unsigned)xPortGetFreeHeapSize(),
+                           counts[0], counts[1], counts[2], counts[3]);
+        if (len > 0) {{
+            SEGGER_RTT_Write(METRICS_RTT_CHANNEL, frame, (unsigned)len);
+        }}
+        vTaskDelay(pdMS_TO_TICKS(METRICS_INTERVAL_MS));
+    }}
+}}
+
+void metrics_task_start(void) {{
+    xTaskCreate(metrics_task, "metrics", 256, NULL, tskIDLE_PRIORITY + 1, NULL);
+}}
+"#,
+            header = header, channel = METRICS_CHANNEL, interval = interval_ms,
+        )),
+        RtosType::Zephyr => Ok(format!(r#"{header} *
+ * prj.conf needs:
+ *   CONFIG_USE_SEGGER_RTT=y, CONFIG_SCHED_THREAD_USAGE_ALL=y,
+ *   CONFIG_SYS_HEAP_RUNTIME_STATS=y, CONFIG_THREAD_MONITOR=y
+ * The thread starts on its own at boot.
+ */
+
+#include <zephyr/kernel.h>
+#include <zephyr/sys/sys_heap.h>
+#include <SEGGER_RTT.h>
+#include <stdio.h>
+#include <string.h>
+
+#define METRICS_RTT_CHANNEL  {channel}
+#define METRICS_INTERVAL_MS  {interval}
+
+extern struct k_heap _system_heap;
+
+static char metrics_rtt_buffer[256];
+
+static void count_thread(const struct k_thread *thread, void *user_data) {{
+    unsigned *counts = user_data;
+    char state[32];
+
+    if (thread == k_current_get()) {{
+        counts[0]++;
+        return;
+    }}
+    k_thread_state_str((k_tid_t)thread, state, sizeof(state));
+    if (strstr(state, "suspended") != NULL) {{
+        counts[3]++;
+    }} else if (strstr(state, "pending") != NULL || strstr(state, "sleeping") != NULL) {{
+        counts[2]++;
+    }} else {{
+        counts[1]++;
+    }}
+}}
+
+static void metrics_thread(void *p1, void *p2, void *p3) {{
+    uint64_t last_total = 0, last_idle = 0;
+    char frame[128];
+
+    SEGGER_RTT_ConfigUpBuffer(METRICS_RTT_CHANNEL, "Metrics", metrics_rtt_buffer,
+                              sizeof(metrics_rtt_buffer), SEGGER_RTT_MODE_NO_BLOCK_SKIP);
+
+    for (;;) {{
+        k_thread_runtime_stats_t stats;
+        struct sys_memory_stats heap;
+        unsigned counts[4] = {{0}};
+
+        k_thread_runtime_stats_all_get(&stats);
+        sys_heap_runtime_stats_get(&_system_heap.heap, &heap);
+        k_thread_foreach(count_thread, counts);
+
+        /* Load over the last interval = share of cycles not spent idle */
+        uint64_t d_total = stats.total_cycles + stats.idle_cycles - last_total;
+        uint64_t d_idle = stats.idle_cycles - last_idle;
+        unsigned cpu = d_total > 0 ? 100u - (unsigned)(d_idle * 100u / d_total) : 0u;
+        last_total = stats.total_cycles + stats.idle_cycles;
+        last_idle = stats.idle_cycles;
+
+        int len = snprintf(frame, sizeof(frame),
+                           "{{\"cpu\":%u,\"heap_free\":%u,\"task_counts\":[%u,%u,%u,%u]}}\n",
+                           cpu, (unsigned)heap.free_bytes,
+                           counts[0], counts[1], counts[2], counts[3]);
+        if (len > 0) {{
+            SEGGER_RTT_Write(METRICS_RTT_CHANNEL, frame, (unsigned)len);
+        }}
+        k_msleep(METRICS_INTERVAL_MS);
+    }}
+}}
+
+K_THREAD_DEFINE(metrics_tid, 1024, metrics_thread, NULL, NULL, NULL,
+                K_LOWEST_APPLICATION_THREAD_PRIO, 0, 0);
+"#,
+            header = header, channel = METRICS_CHANNEL, interval = interval_ms,
+        )),
+        RtosType::BareMetal => Err("The metrics reporter needs an RTOS for task and heap statistics".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames_split_across_reads() {
+        let mut channel = MetricsChannel::new();
+        assert!(channel.push("{\"cpu\":85,\"heap_").is_empty());
+        let frames = channel.push("free\":4096,\"task_counts\":[1,2,5,0]}\n{\"cpu\":12,\"heap_free\":8000}\n");
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0], MetricsFrame { cpu: 85.0, heap_free: 4096, task_counts: vec![1, 2, 5, 0] });
+        assert!(frames[1].task_counts.is_empty());
+
+        assert!(channel.push("boot ok\n").is_empty());
+        assert_eq!(channel.invalid_frames, 1);
+
+        let batch = serde_json::json!({
+            "messages": [
+                { "channel": 0, "text": "{\"cpu\":1,\"heap_free\":1}\n", "timestamp_ms": 1 },
+                { "channel": 1, "text": "{\"cpu\":50,\"heap_free\":2048}\n", "timestamp_ms": 2 },
+            ]
+        });
+        let frames = channel.push_batch(&batch);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].heap_free, 2048);
+    }
+
+    #[test]
+    fn test_latest_cleared_when_its_stream_stops() {
+        let frame = MetricsFrame { cpu: 40.0, heap_free: 1024, task_counts: vec![] };
+        record_latest("rtt_metrics_test", frame.clone());
+        clear_latest("rtt_other_job");
+        assert_eq!(latest_metrics(), Some(frame));
+        clear_latest("rtt_metrics_test");
+        assert_eq!(latest_metrics(), None);
+    }
+
+    #[test]
+    fn test_generate_metrics_task() {
+        let freertos = generate_rtos_metrics_task(RtosType::FreeRtos, 500).unwrap();
+        assert!(freertos.contains("#define METRICS_INTERVAL_MS  500"));
+        assert!(freertos.contains("uxTaskGetSystemState(tasks, METRICS_MAX_TASKS, &total)"));
+        assert!(freertos.contains("\"{\\\"cpu\\\":%u,\\\"heap_free\\\":%u,\\\"task_counts\\\":[%u,%u,%u,%u]}\\n\""));
+        assert!(freertos.contains("SEGGER_RTT_Write(METRICS_RTT_CHANNEL, frame, (unsigned)len);"));
+
+        let zephyr = generate_rtos_metrics_task(RtosType::Zephyr, 1000).unwrap();
+        assert!(zephyr.contains("k_thread_runtime_stats_all_get(&stats);"));
+        assert!(zephyr.contains("K_THREAD_DEFINE(metrics_tid"));
+
+        assert!(generate_rtos_metrics_task(RtosType::BareMetal, 1000).is_err());
+        assert!(generate_rtos_metrics_task(RtosType::FreeRtos, 0).is_err());
+    }
+}
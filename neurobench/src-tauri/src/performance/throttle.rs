@@ -0,0 +1,279 @@
+// Host Throttle Detection
+// Thermal, power limit and frequency scaling checks that slow down builds
+
+use serde::{Deserialize, Serialize};
+
+/// Zone temperature treated as throttling when no passive trip point is exposed
+const DEFAULT_THERMAL_LIMIT_C: f32 = 95.0;
+
+/// Package power at or above this share of the RAPL limit counts as limited
+const POWER_LIMIT_RATIO: f32 = 0.95;
+
+/// Busy cores running below this share of max frequency are being scaled down
+const FREQ_SCALING_RATIO: f32 = 0.6;
+
+/// Last RAPL energy reading, so polling callers get power without sleeping
+#[cfg(target_os = "linux")]
+static LAST_ENERGY: std::sync::Mutex<Option<(std::time::Instant, u64)>> = std::sync::Mutex::new(None);
+
+/// Why the host is running slower than it could
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThrottleReason {
+    Thermal,
+    PowerLimit,
+    FrequencyScaling,
+}
+
+/// Host throttling snapshot
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThrottleStatus {
+    pub is_throttling: bool,
+    pub reason: Option<ThrottleReason>,
+    pub cpu_freq_mhz: u32,
+    pub cpu_max_mhz: u32,
+    pub thermal_zone_c: Vec<f32>,
+    pub package_power_w: Option<f32>,
+    pub power_limit_w: Option<f32>,
+}
+
+/// Inputs gathered from the platform, shared by the classifier
+#[derive(Debug, Default)]
+struct HostReadings {
+    cpu_freq_mhz: f32,
+    cpu_max_mhz: f32,
+    thermal_zone_c: Vec<f32>,
+    thermal_limit_c: f32,
+    package_power_w: Option<f32>,
+    power_limit_w: Option<f32>,
+    busy: bool,
+    /// macOS reports an explicit scheduler/thermal speed limit
+    speed_limit_pct: Option<u32>,
+}
+
+/// Detect whether the host CPU is currently throttled
+///
+/// On Linux the first call, or one after a long gap, samples the RAPL
+/// energy counter for 100 ms, so run it off the async runtime.
+pub fn detect_throttle() -> ThrottleStatus {
+    let readings = read_host();
+    let reason = classify(&readings);
+
+    ThrottleStatus {
+        is_throttling: reason.is_some(),
+        reason,
+        cpu_freq_mhz: readings.cpu_freq_mhz.round() as u32,
+        cpu_max_mhz: readings.cpu_max_mhz.round() as u32,
+        thermal_zone_c: readings.thermal_zone_c,
+        package_power_w: readings.package_power_w,
+        power_limit_w: readings.power_limit_w,
+    }
+}
+
+/// Pick the most likely cause, thermal first since it also drops frequency
+fn classify(r: &HostReadings) -> Option<ThrottleReason> {
+    if r.thermal_zone_c.iter().any(|&t| t >= r.thermal_limit_c) || r.speed_limit_pct.is_some_and(|p| p < 100) {
+        return Some(ThrottleReason::Thermal);
+    }
+    if let (Some(power), Some(limit)) = (r.package_power_w, r.power_limit_w) {
+        if limit > 0.0 && power >= limit * POWER_LIMIT_RATIO {
+            return Some(ThrottleReason::PowerLimit);
+        }
+    }
+    // Idle cores clock down on purpose, only a busy CPU counts
+    if r.busy && r.cpu_max_mhz > 0.0 && r.cpu_freq_mhz > 0.0 && r.cpu_freq_mhz < r.cpu_max_mhz * FREQ_SCALING_RATIO {
+        return Some(ThrottleReason::FrequencyScaling);
+    }
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn read_host() -> HostReadings {
+    use std::fs;
+
+    let cpu_freq_mhz = fs::read_to_string("/proc/cpuinfo")
+        .ok()
+        .and_then(|s| parse_cpuinfo_mhz(&s))
+        .unwrap_or(0.0);
+
+    // cpufreq reports kHz
+    let cpu_max_mhz = fs::read_to_string("/sys/devices/system/cpu/cpu0/cpufreq/cpuinfo_max_freq")
+        .ok()
+        .and_then(|s| s.trim().parse::<f32>().ok())
+        .map(|khz| khz / 1000.0)
+        .unwrap_or(0.0);
+
+    let mut thermal_zone_c = Vec::new();
+    let mut thermal_limit_c = DEFAULT_THERMAL_LIMIT_C;
+    let mut zones: Vec<_> = fs::read_dir("/sys/class/thermal")
+        .map(|entries| {
+            entries.flatten()
+                .filter(|e| e.file_name().to_string_lossy().starts_with("thermal_zone"))
+                .map(|e| e.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    zones.sort();
+    for zone in zones {
+        let Some(temp) = read_millidegrees(&zone.join("temp")) else { continue };
+        thermal_zone_c.push(temp);
+        for trip in 0.. {
+            let Ok(kind) = fs::read_to_string(zone.join(format!("trip_point_{}_type", trip))) else { break };
+            if kind.trim() == "passive" {
+                if let Some(limit) = read_millidegrees(&zone.join(format!("trip_point_{}_temp", trip))) {
+                    thermal_limit_c = thermal_limit_c.min(limit);
+                }
+            }
+        }
+    }
+
+    let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1) as f32;
+    let busy = fs::read_to_string("/proc/loadavg")
+        .ok()
+        .and_then(|s| s.split_whitespace().next()?.parse::<f32>().ok())
+        .is_some_and(|load| load / cores > 0.7);
+
+    let (package_power_w, power_limit_w) = read_rapl();
+
+    HostReadings {
+        cpu_freq_mhz,
+        cpu_max_mhz,
+        thermal_zone_c,
+        thermal_limit_c,
+        package_power_w,
+        power_limit_w,
+        busy,
+        speed_limit_pct: None,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_millidegrees(path: &std::path::Path) -> Option<f32> {
+    let value: f32 = std::fs::read_to_string(path).ok()?.trim().parse().ok()?;
+    Some(value / 1000.0)
+}
+
+/// Package power from the Intel RAPL energy counter and its long-term limit
+#[cfg(target_os = "linux")]
+fn read_rapl() -> (Option<f32>, Option<f32>) {
+    use std::time::{Duration, Instant};
+
+    let base = std::path::Path::new("/sys/class/powercap/intel-rapl:0");
+    let read_u64 = |name: &str| -> Option<u64> {
+        std::fs::read_to_string(base.join(name)).ok()?.trim().parse().ok()
+    };
+
+    let limit_w = read_u64("constraint_0_power_limit_uw").map(|uw| uw as f32 / 1e6);
+    let Some(energy) = read_u64("energy_uj") else {
+        return (None, limit_w);
+    };
+
+    let previous = *LAST_ENERGY.lock().unwrap();
+    let (prev_at, prev_energy) = match previous {
+        Some(sample) if sample.0.elapsed() < Duration::from_secs(10) && energy >= sample.1 => sample,
+        // No recent sample, take a short one now. This blocks the calling
+        // thread, so async callers go through spawn_blocking.
+        _ => {
+            let at = Instant::now();
+            std::thread::sleep(Duration::from_millis(100));
+            match read_u64("energy_uj") {
+                Some(now) if now >= energy => {
+                    *LAST_ENERGY.lock().unwrap() = Some((Instant::now(), now));
+                    let secs = at.elapsed().as_secs_f32();
+                    return (Some((now - energy) as f32 / 1e6 / secs), limit_w);
+                }
+                _ => return (None, limit_w),
+            }
+        }
+    };
+
+    let now = Instant::now();
+    let secs = now.duration_since(prev_at).as_secs_f32();
+    *LAST_ENERGY.lock().unwrap() = Some((now, energy));
+    let power = (secs > 0.0).then(|| (energy - prev_energy) as f32 / 1e6 / secs);
+    (power, limit_w)
+}
+
+#[cfg(target_os = "macos")]
+fn read_host() -> HostReadings {
+    let sysctl = |key: &str| -> Option<f32> {
+        let output = std::process::Command::new("sysctl").args(["-n", key]).output().ok()?;
+        String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+    };
+
+    // Reported in Hz on Intel Macs, missing on Apple Silicon
+    let cpu_freq_mhz = sysctl("hw.cpufrequency").map(|hz| hz / 1e6).unwrap_or(0.0);
+    let cpu_max_mhz = sysctl("hw.cpufrequency_max").map(|hz| hz / 1e6).unwrap_or(cpu_freq_mhz);
+
+    let speed_limit_pct = std::process::Command::new("pmset")
+        .args(["-g", "therm"])
+        .output()
+        .ok()
+        .and_then(|o| parse_speed_limit(&String::from_utf8_lossy(&o.stdout)));
+
+    HostReadings {
+        cpu_freq_mhz,
+        cpu_max_mhz,
+        thermal_limit_c: DEFAULT_THERMAL_LIMIT_C,
+        speed_limit_pct,
+        ..Default::default()
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn read_host() -> HostReadings {
+    HostReadings {
+        thermal_limit_c: DEFAULT_THERMAL_LIMIT_C,
+        ..Default::default()
+    }
+}
+
+/// Average of the per-core `cpu MHz` lines
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_cpuinfo_mhz(cpuinfo: &str) -> Option<f32> {
+    let freqs: Vec<f32> = cpuinfo.lines()
+        .filter(|l| l.starts_with("cpu MHz"))
+        .filter_map(|l| l.split(':').nth(1)?.trim().parse().ok())
+        .collect();
+    (!freqs.is_empty()).then(|| freqs.iter().sum::<f32>() / freqs.len() as f32)
+}
+
+/// `CPU_Speed_Limit = 80` from `pmset -g therm`
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_speed_limit(therm: &str) -> Option<u32> {
+    therm.lines()
+        .find(|l| l.contains("CPU_Speed_Limit"))
+        .and_then(|l| l.split('=').nth(1)?.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_readings() {
+        let cpuinfo = "processor\t: 0\ncpu MHz\t\t: 1200.000\nprocessor\t: 1\ncpu MHz\t\t: 1800.000\n";
+        assert_eq!(parse_cpuinfo_mhz(cpuinfo), Some(1500.0));
+        assert_eq!(parse_speed_limit("Note: No thermal warning level has been recorded\n\tCPU_Speed_Limit \t= 70\n"), Some(70));
+    }
+
+    #[test]
+    fn test_classify_throttle_reason() {
+        let base = HostReadings {
+            cpu_freq_mhz: 1500.0,
+            cpu_max_mhz: 4000.0,
+            thermal_zone_c: vec![55.0],
+            thermal_limit_c: DEFAULT_THERMAL_LIMIT_C,
+            ..Default::default()
+        };
+        // Low clocks while idle are normal power management
+        assert_eq!(classify(&base), None);
+        assert_eq!(classify(&HostReadings { busy: true, ..base }), Some(ThrottleReason::FrequencyScaling));
+
+        let hot = HostReadings { thermal_zone_c: vec![55.0, 97.5], busy: true, ..Default::default() };
+        assert_eq!(classify(&HostReadings { thermal_limit_c: DEFAULT_THERMAL_LIMIT_C, ..hot }), Some(ThrottleReason::Thermal));
+
+        let limited = HostReadings { package_power_w: Some(44.0), power_limit_w: Some(45.0), thermal_limit_c: 100.0, ..Default::default() };
+        assert_eq!(classify(&limited), Some(ThrottleReason::PowerLimit));
+    }
+}
@@ -0,0 +1,244 @@
+// Alternate Function Table
+// Per-pin AF mux numbers for the pin assignment assistant
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Pin lookup errors
+#[derive(Debug, Error)]
+pub enum PinError {
+    #[error("No alternate function table for {0}")]
+    UnknownMcu(String),
+
+    #[error("{mcu} has no pin {pin}")]
+    UnknownPin { mcu: String, pin: String },
+}
+
+/// One signal a pin can be muxed to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AlternateFunction {
+    pub af_number: u8,
+    pub peripheral: String,
+    pub signal: String,
+    pub compatible_modes: Vec<String>,
+}
+
+/// A pin that can carry a requested signal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PinOption {
+    pub pin_name: String,
+    pub af_number: u8,
+    pub notes: String,
+}
+
+/// Everything a pin can be configured as
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PinFunctions {
+    pub pin_name: String,
+    pub gpio: bool,
+    pub alternate_functions: Vec<AlternateFunction>,
+    pub analog: Vec<String>,
+}
+
+/// (pin, AF number, signal, part prefixes the mapping is limited to)
+type AfEntry = (&'static str, u8, &'static str, &'static [&'static str]);
+
+const ALL: &[&str] = &[];
+const F40X: &[&str] = &["STM32F405", "STM32F407"];
+const F401_F411: &[&str] = &["STM32F401", "STM32F411"];
+
+/// STM32F4 AF mux for the pins exposed by the supported packages
+const STM32F4_AF: &[AfEntry] = &[
+    ("PA0", 1, "TIM2_CH1", ALL), ("PA0", 2, "TIM5_CH1", ALL), ("PA0", 7, "USART2_CTS", ALL),
+    ("PA1", 1, "TIM2_CH2", ALL), ("PA1", 2, "TIM5_CH2", ALL), ("PA1", 7, "USART2_RTS", ALL),
+    ("PA2", 1, "TIM2_CH3", ALL), ("PA2", 2, "TIM5_CH3", ALL), ("PA2", 3, "TIM9_CH1", ALL), ("PA2", 7, "USART2_TX", ALL),
+    ("PA3", 1, "TIM2_CH4", ALL), ("PA3", 2, "TIM5_CH4", ALL), ("PA3", 3, "TIM9_CH2", ALL), ("PA3", 7, "USART2_RX", ALL),
+    ("PA4", 5, "SPI1_NSS", ALL), ("PA4", 6, "SPI3_NSS", ALL), ("PA4", 7, "USART2_CK", ALL),
+    ("PA5", 1, "TIM2_CH1", ALL), ("PA5", 5, "SPI1_SCK", ALL),
+    ("PA6", 1, "TIM1_BKIN", ALL), ("PA6", 2, "TIM3_CH1", ALL), ("PA6", 5, "SPI1_MISO", ALL),
+    ("PA7", 1, "TIM1_CH1N", ALL), ("PA7", 2, "TIM3_CH2", ALL), ("PA7", 5, "SPI1_MOSI", ALL),
+    ("PA8", 0, "RCC_MCO1", ALL), ("PA8", 1, "TIM1_CH1", ALL), ("PA8", 4, "I2C3_SCL", ALL), ("PA8", 7, "USART1_CK", ALL),
+    ("PA9", 1, "TIM1_CH2", ALL), ("PA9", 4, "I2C3_SMBA", ALL), ("PA9", 7, "USART1_TX", ALL),
+    ("PA10", 1, "TIM1_CH3", ALL), ("PA10", 7, "USART1_RX", ALL),
+    ("PA11", 1, "TIM1_CH4", ALL), ("PA11", 7, "USART1_CTS", ALL), ("PA11", 8, "USART6_TX", F401_F411),
+    ("PA11", 9, "CAN1_RX", F40X), ("PA11", 10, "OTG_FS_DM", ALL),
+    ("PA12", 1, "TIM1_ETR", ALL), ("PA12", 7, "USART1_RTS", ALL), ("PA12", 8, "USART6_RX", F401_F411),
+    ("PA12", 9, "CAN1_TX", F40X), ("PA12", 10, "OTG_FS_DP", ALL),
+    ("PA13", 0, "SYS_SWDIO", ALL),
+    ("PA14", 0, "SYS_SWCLK", ALL),
+    ("PA15", 0, "SYS_JTDI", ALL), ("PA15", 1, "TIM2_CH1", ALL), ("PA15", 5, "SPI1_NSS", ALL), ("PA15", 6, "SPI3_NSS", ALL),
+    ("PB0", 1, "TIM1_CH2N", ALL), ("PB0", 2, "TIM3_CH3", ALL),
+    ("PB1", 1, "TIM1_CH3N", ALL), ("PB1", 2, "TIM3_CH4", ALL),
+    ("PB3", 0, "SYS_SWO", ALL), ("PB3", 1, "TIM2_CH2", ALL), ("PB3", 5, "SPI1_SCK", ALL), ("PB3", 6, "SPI3_SCK", ALL),
+    ("PB4", 0, "SYS_NJTRST", ALL), ("PB4", 2, "TIM3_CH1", ALL), ("PB4", 5, "SPI1_MISO", ALL), ("PB4", 6, "SPI3_MISO", ALL),
+    ("PB5", 2, "TIM3_CH2", ALL), ("PB5", 4, "I2C1_SMBA", ALL), ("PB5", 5, "SPI1_MOSI", ALL), ("PB5", 6, "SPI3_MOSI", ALL),
+    ("PB6", 2, "TIM4_CH1", ALL), ("PB6", 4, "I2C1_SCL", ALL), ("PB6", 7, "USART1_TX", ALL),
+    ("PB7", 2, "TIM4_CH2", ALL), ("PB7", 4, "I2C1_SDA", ALL), ("PB7", 7, "USART1_RX", ALL),
+    ("PB8", 2, "TIM4_CH3", ALL), ("PB8", 3, "TIM10_CH1", ALL), ("PB8", 4, "I2C1_SCL", ALL), ("PB8", 9, "CAN1_RX", F40X),
+    ("PB9", 2, "TIM4_CH4", ALL), ("PB9", 3, "TIM11_CH1", ALL), ("PB9", 4, "I2C1_SDA", ALL), ("PB9", 5, "SPI2_NSS", ALL),
+    ("PB9", 9, "CAN1_TX", F40X),
+    ("PB10", 1, "TIM2_CH3", ALL), ("PB10", 4, "I2C2_SCL", ALL), ("PB10", 5, "SPI2_SCK", ALL), ("PB10", 7, "USART3_TX", F40X),
+    ("PB11", 1, "TIM2_CH4", ALL), ("PB11", 4, "I2C2_SDA", F40X), ("PB11", 7, "USART3_RX", F40X),
+    ("PB12", 1, "TIM1_BKIN", ALL), ("PB12", 4, "I2C2_SMBA", ALL), ("PB12", 5, "SPI2_NSS", ALL),
+    ("PB13", 1, "TIM1_CH1N", ALL), ("PB13", 5, "SPI2_SCK", ALL),
+    ("PB14", 1, "TIM1_CH2N", ALL), ("PB14", 5, "SPI2_MISO", ALL),
+    ("PB15", 1, "TIM1_CH3N", ALL), ("PB15", 5, "SPI2_MOSI", ALL),
+];
+
+/// Analog channels, which bypass the AF mux
+const STM32F4_ANALOG: &[(&str, &str, &[&str])] = &[
+    ("PA0", "ADC1_IN0", ALL), ("PA1", "ADC1_IN1", ALL), ("PA2", "ADC1_IN2", ALL), ("PA3", "ADC1_IN3", ALL),
+    ("PA4", "ADC1_IN4", ALL), ("PA5", "ADC1_IN5", ALL), ("PA6", "ADC1_IN6", ALL), ("PA7", "ADC1_IN7", ALL),
+    ("PB0", "ADC1_IN8", ALL), ("PB1", "ADC1_IN9", ALL),
+    ("PA4", "DAC_OUT1", F40X), ("PA5", "DAC_OUT2", F40X),
+];
+
+/// GPIO-only pins in the supported packages
+const STM32F4_GPIO_ONLY: &[&str] = &["PB2", "PC13", "PC14", "PC15"];
+
+/// Part prefix check: `STM32F407VG` is an `STM32F407`
+fn normalize_mcu(mcu_id: &str) -> Result<String, PinError> {
+    let mcu = mcu_id.trim().to_uppercase();
+    if mcu.starts_with("STM32F4") {
+        Ok(mcu)
+    } else {
+        Err(PinError::UnknownMcu(mcu_id.to_string()))
+    }
+}
+
+/// Whether a mapping limited to `parts` exists on `mcu`
+fn on_part(mcu: &str, parts: &[&str]) -> bool {
+    parts.is_empty() || parts.iter().any(|p| mcu.starts_with(p))
+}
+
+fn entries_for(mcu: &str) -> impl Iterator<Item = &'static AfEntry> + '_ {
+    STM32F4_AF.iter().filter(move |(.., parts)| on_part(mcu, parts))
+}
+
+fn compatible_modes(signal: &str) -> Vec<String> {
+    let modes: &[&str] = if signal.starts_with("I2C") {
+        &["alternate_open_drain"]
+    } else if signal.contains("_RX") || signal.ends_with("MISO") {
+        &["alternate"]
+    } else {
+        &["alternate", "alternate_open_drain"]
+    };
+    modes.iter().map(|m| m.to_string()).collect()
+}
+
+fn to_alternate_function(af_number: u8, name: &str) -> AlternateFunction {
+    let (peripheral, signal) = name.rsplit_once('_').unwrap_or((name, name));
+    AlternateFunction {
+        af_number,
+        peripheral: peripheral.to_string(),
+        signal: signal.to_string(),
+        compatible_modes: compatible_modes(name),
+    }
+}
+
+fn pin_exists(mcu: &str, pin: &str) -> bool {
+    entries_for(mcu).any(|(p, ..)| *p == pin)
+        || STM32F4_ANALOG.iter().any(|(p, ..)| *p == pin)
+        || STM32F4_GPIO_ONLY.contains(&pin)
+}
+
+/// AF mux options of a pin, ordered by AF number
+pub fn get_alternate_functions(mcu_id: &str, pin_name: &str) -> Result<Vec<AlternateFunction>, PinError> {
+    let mcu = normalize_mcu(mcu_id)?;
+    let pin = pin_name.trim().to_uppercase();
+    if !pin_exists(&mcu, &pin) {
+        return Err(PinError::UnknownPin { mcu: mcu_id.to_string(), pin: pin_name.to_string() });
+    }
+
+    let mut functions: Vec<AlternateFunction> = entries_for(&mcu)
+        .filter(|(p, ..)| *p == pin)
+        .map(|(_, af, signal, _)| to_alternate_function(*af, signal))
+        .collect();
+    functions.sort_by_key(|f| f.af_number);
+    Ok(functions)
+}
+
+/// GPIO, AF and analog functions of a pin
+pub fn get_pin_functions(mcu_id: &str, pin_name: &str) -> Result<PinFunctions, PinError> {
+    let alternate_functions = get_alternate_functions(mcu_id, pin_name)?;
+    let mcu = normalize_mcu(mcu_id)?;
+    let pin = pin_name.trim().to_uppercase();
+    Ok(PinFunctions {
+        gpio: true,
+        analog: STM32F4_ANALOG.iter()
+            .filter(|(p, _, parts)| *p == pin && on_part(&mcu, parts))
+            .map(|(_, signal, _)| signal.to_string())
+            .collect(),
+        pin_name: pin,
+        alternate_functions,
+    })
+}
+
+/// Pins able to carry a signal such as `USART1_TX`, with their AF numbers
+pub fn find_pins_for_signal(mcu_id: &str, signal: &str) -> Result<Vec<PinOption>, PinError> {
+    let mcu = normalize_mcu(mcu_id)?;
+    let wanted = signal.trim().to_uppercase();
+    Ok(entries_for(&mcu)
+        .filter(|(_, _, s, _)| *s == wanted)
+        .map(|(pin, af, s, _)| PinOption {
+            pin_name: pin.to_string(),
+            af_number: *af,
+            notes: pin_notes(pin, s),
+        })
+        .collect())
+}
+
+/// Caveats worth showing next to a pin choice
+fn pin_notes(pin: &str, signal: &str) -> String {
+    let mut notes = Vec::new();
+    if matches!(pin, "PA13" | "PA14") && !signal.starts_with("SYS_") {
+        notes.push("SWD debug pin; reassigning it disables debugging");
+    }
+    if matches!(pin, "PA15" | "PB3" | "PB4") && !signal.starts_with("SYS_") {
+        notes.push("JTAG pin after reset; free when only SWD is used");
+    }
+    if matches!(pin, "PA11" | "PA12") && !signal.starts_with("OTG_FS") {
+        notes.push("Shared with USB OTG FS");
+    }
+    if signal.starts_with("I2C") {
+        notes.push("Open-drain, needs pull-ups");
+    }
+    notes.join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_pins_for_signal() {
+        let options = find_pins_for_signal("STM32F401", "usart1_tx").unwrap();
+        let pins: Vec<_> = options.iter().map(|o| (o.pin_name.as_str(), o.af_number)).collect();
+        assert_eq!(pins, vec![("PA9", 7), ("PB6", 7)]);
+
+        assert!(find_pins_for_signal("STM32F401", "CAN1_TX").unwrap().is_empty());
+        let can = find_pins_for_signal("STM32F407VG", "CAN1_TX").unwrap();
+        assert_eq!(can[0].pin_name, "PA12");
+        assert!(can[0].notes.contains("USB"));
+
+        assert!(matches!(find_pins_for_signal("RP2040", "UART0_TX"), Err(PinError::UnknownMcu(_))));
+    }
+
+    #[test]
+    fn test_pin_functions() {
+        let afs = get_alternate_functions("STM32F401", "PB7").unwrap();
+        assert_eq!(afs[0], AlternateFunction {
+            af_number: 2,
+            peripheral: "TIM4".to_string(),
+            signal: "CH2".to_string(),
+            compatible_modes: vec!["alternate".to_string(), "alternate_open_drain".to_string()],
+        });
+        assert_eq!(afs[1].compatible_modes, vec!["alternate_open_drain"]);
+
+        let pa4 = get_pin_functions("STM32F407", "pa4").unwrap();
+        assert_eq!(pa4.analog, vec!["ADC1_IN4", "DAC_OUT1"]);
+        assert_eq!(get_pin_functions("STM32F401", "PA4").unwrap().analog, vec!["ADC1_IN4"]);
+        assert!(get_pin_functions("STM32F401", "PC13").unwrap().alternate_functions.is_empty());
+        assert!(matches!(get_alternate_functions("STM32F401", "PZ9"), Err(PinError::UnknownPin { .. })));
+    }
+}
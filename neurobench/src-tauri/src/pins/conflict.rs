@@ -0,0 +1,211 @@
+// Pin Conflict Detection
+// Finds physical pins assigned incompatible functions before code is generated
+
+use super::af_table::{find_pins_for_signal, get_pin_functions, PinError};
+use super::PinConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Whether a conflict blocks code generation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictSeverity {
+    /// Incompatible assignment, code generation is refused
+    Error,
+    /// Works with arbitration or is redundant, reported as a warning
+    Advisory,
+}
+
+/// Functions competing for one physical pin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PinConflict {
+    pub pin_name: String,
+    pub conflicting_functions: Vec<String>,
+    /// Indices into the checked config list
+    pub conflicting_configs: Vec<usize>,
+    pub severity: ConflictSeverity,
+    pub message: String,
+}
+
+impl PinConflict {
+    pub fn is_error(&self) -> bool {
+        self.severity == ConflictSeverity::Error
+    }
+}
+
+/// Physical pin of a config, `PA9` from port `GPIOA` and pin 9, else its `pin_name`
+fn physical_pin(config: &PinConfig) -> String {
+    match config.port.trim().chars().last() {
+        Some(port) if port.is_ascii_alphabetic() => format!("P{}{}", port.to_ascii_uppercase(), config.pin_number),
+        _ => config.pin_name.trim().to_uppercase(),
+    }
+}
+
+fn is_gpio(function: &str) -> bool {
+    matches!(function, "GPIO" | "INPUT" | "OUTPUT" | "EXTI") || function.starts_with("GPIO_")
+}
+
+fn is_spi_nss(function: &str) -> bool {
+    function.starts_with("SPI") && (function.ends_with("_NSS") || function.ends_with("_CS"))
+}
+
+/// Conflicts between configs sharing a physical pin
+pub fn detect_conflicts(configs: &[PinConfig]) -> Vec<PinConflict> {
+    let mut by_pin: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (index, config) in configs.iter().enumerate() {
+        by_pin.entry(physical_pin(config)).or_default().push(index);
+    }
+
+    let mut conflicts = Vec::new();
+    for (pin_name, indices) in by_pin {
+        if indices.len() < 2 {
+            continue;
+        }
+
+        let mut functions: Vec<String> = indices.iter()
+            .map(|&i| configs[i].function.trim().to_uppercase())
+            .collect();
+        functions.sort();
+        functions.dedup();
+
+        let (severity, message) = if functions.len() == 1 {
+            (ConflictSeverity::Advisory, format!("{} is configured {} times as {}", pin_name, indices.len(), functions[0]))
+        } else if functions.len() == 2 && functions.iter().any(|f| is_spi_nss(f)) && functions.iter().any(|f| is_gpio(f)) {
+            (ConflictSeverity::Advisory, format!(
+                "{} is shared by hardware SPI NSS and GPIO; use software NSS (SSM=1) and drive it as GPIO, or drop the GPIO config",
+                pin_name
+            ))
+        } else {
+            (ConflictSeverity::Error, format!("{} cannot be both {}", pin_name, functions.join(" and ")))
+        };
+
+        conflicts.push(PinConflict {
+            pin_name,
+            conflicting_functions: functions,
+            conflicting_configs: indices,
+            severity,
+            message,
+        });
+    }
+    conflicts
+}
+
+/// Check functions against the MCU's alternate function table
+///
+/// A signal the table routes to other pins only is an error. Pins missing
+/// from the table are advisories, since it covers the common packages only,
+/// and functions the table does not describe are not checked.
+pub fn detect_unavailable(configs: &[PinConfig], mcu_id: &str) -> Vec<PinConflict> {
+    let mut conflicts = Vec::new();
+    for (index, config) in configs.iter().enumerate() {
+        let pin_name = physical_pin(config);
+        let function = config.function.trim().to_uppercase();
+
+        let (severity, message) = match get_pin_functions(mcu_id, &pin_name) {
+            Err(PinError::UnknownMcu(_)) => return Vec::new(),
+            Err(PinError::UnknownPin { .. }) => (
+                ConflictSeverity::Advisory,
+                format!("{} is not in the {} pin table; {} was not checked", pin_name, mcu_id, function),
+            ),
+            Ok(_) if is_gpio(&function) => continue,
+            Ok(pin) => {
+                let routed = pin.alternate_functions.iter().any(|af| format!("{}_{}", af.peripheral, af.signal) == function)
+                    || pin.analog.contains(&function);
+                let others: Vec<String> = find_pins_for_signal(mcu_id, &function)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|option| option.pin_name)
+                    .collect();
+                if routed || others.is_empty() {
+                    continue;
+                }
+                (
+                    ConflictSeverity::Error,
+                    format!("{} cannot be routed to {} on {}; use {}", function, pin_name, mcu_id, others.join(" or ")),
+                )
+            }
+        };
+
+        conflicts.push(PinConflict {
+            pin_name,
+            conflicting_functions: vec![function],
+            conflicting_configs: vec![index],
+            severity,
+            message,
+        });
+    }
+    conflicts
+}
+
+/// Same-pin conflicts, plus the function table check when the MCU is known
+pub fn check_configs(configs: &[PinConfig], mcu_id: Option<&str>) -> Vec<PinConflict> {
+    let mut conflicts = detect_conflicts(configs);
+    if let Some(mcu_id) = mcu_id {
+        conflicts.extend(detect_unavailable(configs, mcu_id));
+    }
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(port: &str, pin: u8, function: &str) -> PinConfig {
+        PinConfig {
+            pin_name: format!("P{}{}", port, pin),
+            port: format!("GPIO{}", port),
+            pin_number: pin,
+            function: function.to_string(),
+            mode: "alternate".to_string(),
+            pull: "none".to_string(),
+            speed: "high".to_string(),
+            alternate_function: None,
+            label: None,
+        }
+    }
+
+    #[test]
+    fn test_detect_conflicts() {
+        let configs = vec![
+            config("A", 9, "USART1_TX"),
+            config("A", 10, "USART1_RX"),
+            config("A", 9, "TIM1_CH2"),
+            config("A", 4, "SPI1_NSS"),
+            config("A", 4, "GPIO"),
+        ];
+        let conflicts = detect_conflicts(&configs);
+        assert_eq!(conflicts.len(), 2);
+
+        let nss = &conflicts[0];
+        assert_eq!(nss.pin_name, "PA4");
+        assert_eq!(nss.severity, ConflictSeverity::Advisory);
+
+        let pa9 = &conflicts[1];
+        assert!(pa9.is_error());
+        assert_eq!(pa9.conflicting_functions, vec!["TIM1_CH2", "USART1_TX"]);
+        assert_eq!(pa9.conflicting_configs, vec![0, 2]);
+    }
+
+    #[test]
+    fn test_function_table_check() {
+        let configs = vec![
+            config("A", 9, "USART1_TX"),
+            config("A", 9, "SPI1_MOSI"),
+            config("C", 13, "GPIO"),
+            config("A", 9, "TIM1_CH2"),
+            config("D", 2, "GPIO"),
+        ];
+        let unavailable = detect_unavailable(&configs, "stm32f401");
+        assert_eq!(unavailable.len(), 2);
+        assert!(unavailable[0].is_error());
+        assert!(unavailable[0].message.contains("SPI1_MOSI cannot be routed to PA9"));
+        assert!(unavailable[0].message.contains("PA7 or PB5"));
+        assert_eq!(unavailable[1].severity, ConflictSeverity::Advisory);
+        assert_eq!(unavailable[1].pin_name, "PD2");
+
+        assert_eq!(check_configs(&configs[..1], Some("STM32F401")).len(), 0);
+        assert_eq!(check_configs(&configs[2..4], Some("STM32F401")).len(), 0);
+        assert_eq!(check_configs(&configs[1..2], None).len(), 0);
+        assert_eq!(check_configs(&configs[1..2], Some("RP2040")).len(), 0);
+    }
+}
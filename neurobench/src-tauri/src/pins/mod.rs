@@ -4,6 +4,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+pub mod af_table;
+pub mod conflict;
+
 /// Pin function type
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PinFunction {
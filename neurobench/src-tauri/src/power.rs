@@ -3,6 +3,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::drivers::clock::LowPowerMode;
+
 /// Power profile for MCU state
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PowerProfile {
@@ -29,6 +31,10 @@ pub struct PowerEstimation {
     pub battery_life_hours: Option<f32>,
     pub breakdown: Vec<PowerBreakdown>,
     pub recommendations: Vec<String>,
+    pub average_ma: f32,
+    pub peak_ma: f32,
+    pub daily_mwh: f32,
+    pub peripheral_breakdown: PeripheralBreakdown,
 }
 
 /// Power breakdown by component
@@ -39,6 +45,55 @@ pub struct PowerBreakdown {
     pub percent: f32,
 }
 
+/// Per-peripheral share of the average current
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PeripheralBreakdown {
+    pub peripherals: Vec<PowerBreakdown>,
+    pub dominant: Option<String>,
+}
+
+impl PeripheralBreakdown {
+    fn from_entries(peripherals: Vec<PowerBreakdown>) -> Self {
+        let dominant = peripherals
+            .iter()
+            .max_by(|a, b| a.current_ma.total_cmp(&b.current_ma))
+            .map(|p| p.component.clone());
+        Self { peripherals, dominant }
+    }
+}
+
+/// Source that brings the MCU out of low-power mode
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum WakeSource {
+    RtcAlarm,
+    RtcWakeup,
+    Gpio,
+    Uart,
+    Timer,
+    Radio,
+}
+
+impl WakeSource {
+    /// Typical time spent in run mode to service one wake-up
+    pub fn typical_active_ms(&self) -> f32 {
+        match self {
+            WakeSource::RtcAlarm => 5.0,
+            WakeSource::RtcWakeup => 2.0,
+            WakeSource::Gpio => 1.0,
+            WakeSource::Uart => 10.0,
+            WakeSource::Timer => 0.5,
+            WakeSource::Radio => 20.0,
+        }
+    }
+}
+
+/// Periodic wake-up event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WakeEvent {
+    pub source: WakeSource,
+    pub frequency_hz: f32,
+}
+
 /// MCU power specifications
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McuPowerSpec {
@@ -164,73 +219,91 @@ pub fn get_peripheral_power() -> Vec<PeripheralPower> {
     ]
 }
 
-/// Estimate power consumption
-pub fn estimate_power(
+/// Sleep-mode current of an MCU in mA
+fn sleep_current_ma(spec: &McuPowerSpec, mode: LowPowerMode) -> f32 {
+    match mode {
+        LowPowerMode::Sleep => spec.sleep_current_ma,
+        LowPowerMode::Stop => spec.stop_current_ua / 1000.0,
+        LowPowerMode::Standby => spec.standby_current_ua / 1000.0,
+    }
+}
+
+/// Estimate power consumption from a wake-event model
+///
+/// The active fraction is the sum of `frequency_hz × active time` over all
+/// wake events; the remainder of each period is spent in `sleep_mode`.
+pub fn estimate_power_detailed(
     mcu: &str,
-    peripherals: &[String],
-    duty_cycle_percent: f32,
-    battery_mah: Option<f32>,
+    active_peripherals: &[PeripheralPower],
+    sleep_mode: LowPowerMode,
+    wake_events: &[WakeEvent],
+    battery_mah: f32,
 ) -> Result<PowerEstimation, String> {
     let mcu_spec = get_mcu_power_specs()
         .into_iter()
         .find(|s| s.name.to_lowercase().contains(&mcu.to_lowercase()))
         .ok_or_else(|| format!("MCU {} not found", mcu))?;
 
-    let peripheral_power = get_peripheral_power();
-    
-    // Calculate MCU power
-    let mcu_active = mcu_spec.run_current_ma * (duty_cycle_percent / 100.0);
-    let mcu_sleep = mcu_spec.sleep_current_ma * ((100.0 - duty_cycle_percent) / 100.0);
-    let mcu_total = mcu_active + mcu_sleep;
-
-    // Calculate peripheral power
-    let mut peripheral_total = 0.0f32;
-    let mut breakdown = vec![
-        PowerBreakdown {
-            component: "MCU Core".to_string(),
-            current_ma: mcu_total,
-            percent: 0.0,  // Will be calculated
-        },
-    ];
-
-    for periph_name in peripherals {
-        if let Some(periph) = peripheral_power.iter().find(|p| 
-            p.name.to_lowercase() == periph_name.to_lowercase()
-        ) {
-            let current = periph.active_current_ma * (duty_cycle_percent / 100.0);
-            peripheral_total += current;
-            breakdown.push(PowerBreakdown {
-                component: periph.name.clone(),
-                current_ma: current,
-                percent: 0.0,
-            });
-        }
+    let active_fraction = wake_events
+        .iter()
+        .map(|e| e.frequency_hz.max(0.0) * e.source.typical_active_ms() / 1000.0)
+        .sum::<f32>()
+        .min(1.0);
+    let sleep_fraction = 1.0 - active_fraction;
+
+    let mcu_avg = mcu_spec.run_current_ma * active_fraction
+        + sleep_current_ma(&mcu_spec, sleep_mode) * sleep_fraction;
+
+    let mut peripheral_entries = Vec::new();
+    for periph in active_peripherals {
+        let current = periph.active_current_ma * active_fraction
+            + periph.sleep_current_ma * sleep_fraction;
+        peripheral_entries.push(PowerBreakdown {
+            component: periph.name.clone(),
+            current_ma: current,
+            percent: 0.0,
+        });
     }
 
-    let total_current = mcu_total + peripheral_total;
-    let power_mw = total_current * mcu_spec.voltage_typical;
+    let average_ma = mcu_avg + peripheral_entries.iter().map(|p| p.current_ma).sum::<f32>();
+    let peak_ma = mcu_spec.run_current_ma
+        + active_peripherals.iter().map(|p| p.active_current_ma).sum::<f32>();
+    let power_mw = average_ma * mcu_spec.voltage_typical;
 
-    // Calculate percentages
-    for b in &mut breakdown {
-        b.percent = (b.current_ma / total_current) * 100.0;
+    for p in &mut peripheral_entries {
+        p.percent = if average_ma > 0.0 { (p.current_ma / average_ma) * 100.0 } else { 0.0 };
     }
 
-    // Battery life calculation
-    let battery_life = battery_mah.map(|mah| mah / total_current);
+    let mut breakdown = vec![PowerBreakdown {
+        component: "MCU Core".to_string(),
+        current_ma: mcu_avg,
+        percent: if average_ma > 0.0 { (mcu_avg / average_ma) * 100.0 } else { 0.0 },
+    }];
+    breakdown.extend(peripheral_entries.iter().cloned());
+
+    let battery_life = if average_ma > 0.0 { Some(battery_mah / average_ma) } else { None };
 
-    // Generate recommendations
     let mut recommendations = vec![];
-    
-    if duty_cycle_percent > 50.0 {
-        recommendations.push("Consider reducing active duty cycle for better battery life".to_string());
+
+    if active_fraction >= 1.0 {
+        recommendations.push("Wake events keep the MCU permanently active - reduce wake frequency".to_string());
+    } else if active_fraction > 0.5 {
+        recommendations.push("MCU is active more than half the time - batch work per wake-up".to_string());
     }
-    
-    if peripherals.iter().any(|p| p.to_lowercase() == "wifi") {
-        recommendations.push("WiFi is power hungry - use modem sleep when possible".to_string());
+
+    if sleep_mode == LowPowerMode::Sleep && mcu_avg > 0.0
+        && mcu_spec.sleep_current_ma * sleep_fraction > mcu_avg * 0.5
+    {
+        recommendations.push("Sleep current dominates - consider Stop or Standby mode".to_string());
     }
-    
-    if total_current > 100.0 {
-        recommendations.push("High current draw - consider low-power MCU variants".to_string());
+
+    let peripheral_breakdown = PeripheralBreakdown::from_entries(peripheral_entries);
+    if let Some(dominant) = &peripheral_breakdown.dominant {
+        if peripheral_breakdown.peripherals.iter()
+            .any(|p| &p.component == dominant && p.current_ma > mcu_avg)
+        {
+            recommendations.push(format!("{} draws more than the MCU core - gate it between wake-ups", dominant));
+        }
     }
 
     if battery_life.map(|h| h < 24.0).unwrap_or(false) {
@@ -239,11 +312,15 @@ pub fn estimate_power(
 
     Ok(PowerEstimation {
         mcu: mcu_spec.name,
-        total_current_ma: total_current,
+        total_current_ma: average_ma,
         power_mw,
         battery_life_hours: battery_life,
         breakdown,
         recommendations,
+        average_ma,
+        peak_ma,
+        daily_mwh: power_mw * 24.0,
+        peripheral_breakdown,
     })
 }
 
@@ -258,13 +335,45 @@ mod tests {
     }
 
     #[test]
-    fn test_estimate_power() {
-        let result = estimate_power(
-            "STM32F407",
-            &["UART".to_string(), "SPI".to_string()],
-            50.0,
-            Some(1000.0),
-        );
-        assert!(result.is_ok());
+    fn test_wake_event_duty_cycle() {
+        // 100 timer wake-ups of 0.5 ms each: active 5% of the time
+        let wake_events = [WakeEvent { source: WakeSource::Timer, frequency_hz: 100.0 }];
+        let result = estimate_power_detailed("STM32L476", &[], LowPowerMode::Stop, &wake_events, 1000.0).unwrap();
+
+        let expected_ma = 12.0 * 0.05 + 0.0008 * 0.95;
+        assert!((result.average_ma - expected_ma).abs() < 1e-4);
+        assert_eq!(result.peak_ma, 12.0);
+        assert!((result.battery_life_hours.unwrap() - 1000.0 / expected_ma).abs() < 1.0);
+        assert!((result.daily_mwh - expected_ma * 3.3 * 24.0).abs() < 1e-2);
+        assert!(result.peripheral_breakdown.dominant.is_none());
+
+        // Wake events longer than their period saturate at always-on
+        let busy = [WakeEvent { source: WakeSource::Radio, frequency_hz: 100.0 }];
+        let result = estimate_power_detailed("STM32L476", &[], LowPowerMode::Stop, &busy, 1000.0).unwrap();
+        assert_eq!(result.average_ma, result.peak_ma);
+        assert!(result.recommendations.iter().any(|r| r.contains("permanently active")));
+
+        assert!(estimate_power_detailed("PIC16", &[], LowPowerMode::Sleep, &[], 1000.0).is_err());
+    }
+
+    #[test]
+    fn test_estimate_power_detailed() {
+        let peripherals: Vec<PeripheralPower> = get_peripheral_power()
+            .into_iter()
+            .filter(|p| p.name == "BLE" || p.name == "I2C")
+            .collect();
+        let wake_events = [WakeEvent { source: WakeSource::RtcWakeup, frequency_hz: 1.0 }];
+
+        let result = estimate_power_detailed(
+            "STM32L476",
+            &peripherals,
+            LowPowerMode::Stop,
+            &wake_events,
+            1000.0,
+        ).unwrap();
+
+        assert!(result.average_ma < result.peak_ma);
+        assert!(result.battery_life_hours.unwrap() > 24.0);
+        assert_eq!(result.peripheral_breakdown.dominant.as_deref(), Some("BLE"));
     }
 }
@@ -0,0 +1,490 @@
+// DMA Bandwidth Analyzer
+// Per-channel bandwidth and AHB bus load from DMA configuration code
+
+use crate::drivers::mcu::McuFamily;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Bus cycles lost to arbitration at the start of every burst
+const ARBITRATION_CYCLES: f64 = 4.0;
+
+/// Combined utilization of one DMA master above which contention is flagged
+const CONTENTION_THRESHOLD_PCT: f64 = 50.0;
+
+/// Bandwidth of one configured DMA channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DmaChannelStats {
+    pub name: String,
+    pub controller: String,
+    pub trigger: String,
+    pub line: u32,
+    pub data_width_bytes: u32,
+    pub burst_beats: u32,
+    pub buffer_items: Option<u32>,
+    pub circular: bool,
+    pub priority: String,
+    pub transfers_per_sec: f64,
+    pub bandwidth_bytes_per_sec: f64,
+    pub bus_utilization_pct: f64,
+    pub rate_source: String,  // How the trigger rate was determined
+}
+
+/// DMA bandwidth report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DmaBandwidthReport {
+    pub channels: Vec<DmaChannelStats>,
+    pub total_bandwidth_bytes_per_sec: f64,
+    pub ahb_capacity_bytes_per_sec: f64,
+    pub ahb_utilization_pct: f64,
+    pub contention_warning: Option<String>,
+    pub priority_guidance: Vec<String>,
+}
+
+/// Configuration collected for one channel before it is committed
+#[derive(Debug, Clone, Default)]
+struct PendingChannel {
+    fields: HashMap<String, String>,
+    line: usize,
+}
+
+/// Analyze DMA configuration in `code` for bandwidth and bus contention
+///
+/// Recognises STM32 HAL/SPL `DMA_InitTypeDef`/`DMA_HandleTypeDef` setup and
+/// Pico SDK `dma_channel_config`. Trigger rates come from baud rates and
+/// prescalers found in the code, otherwise from typical peripheral rates.
+pub fn analyze_dma_usage(code: &str, mcu: McuFamily, bus_freq_mhz: u32) -> DmaBandwidthReport {
+    let bus_hz = bus_freq_mhz.max(1) as f64 * 1e6;
+    let rates = PeripheralRates::from_code(code, bus_hz);
+
+    let channels: Vec<DmaChannelStats> = parse_channels(code)
+        .into_iter()
+        .map(|(name, pending)| channel_stats(&name, &pending, mcu, bus_hz, &rates))
+        .collect();
+
+    let total_bandwidth: f64 = channels.iter().map(|c| c.bandwidth_bytes_per_sec).sum();
+    let total_utilization: f64 = channels.iter().map(|c| c.bus_utilization_pct).sum();
+
+    // Channels on the same controller share one AHB master
+    let mut by_controller: BTreeMap<&str, Vec<&DmaChannelStats>> = BTreeMap::new();
+    for channel in &channels {
+        by_controller.entry(channel.controller.as_str()).or_default().push(channel);
+    }
+
+    let contention: Vec<String> = by_controller.iter()
+        .filter(|(_, chans)| chans.len() > 1)
+        .filter_map(|(controller, chans)| {
+            let load: f64 = chans.iter().map(|c| c.bus_utilization_pct).sum();
+            (load > CONTENTION_THRESHOLD_PCT).then(|| {
+                let names: Vec<&str> = chans.iter().map(|c| c.name.as_str()).collect();
+                format!("{} channels {} use {:.0}% of the AHB master", controller, names.join(", "), load)
+            })
+        })
+        .collect();
+    let contention_warning = (!contention.is_empty())
+        .then(|| format!("Possible arbitration contention: {}", contention.join("; ")));
+
+    let priority_guidance = priority_guidance(&channels, &by_controller);
+
+    DmaBandwidthReport {
+        channels,
+        total_bandwidth_bytes_per_sec: total_bandwidth,
+        ahb_capacity_bytes_per_sec: bus_hz * 4.0,
+        ahb_utilization_pct: total_utilization,
+        contention_warning,
+        priority_guidance,
+    }
+}
+
+/// Collect DMA channel configurations in source order
+fn parse_channels(code: &str) -> Vec<(String, PendingChannel)> {
+    let mut vars: HashMap<String, PendingChannel> = HashMap::new();
+    let mut committed: Vec<(String, PendingChannel)> = Vec::new();
+    let mut committed_vars: Vec<String> = Vec::new();
+
+    for (index, raw) in code.lines().enumerate() {
+        let line = raw.split("//").next().unwrap_or("").trim();
+
+        // Declarations
+        for ty in ["DMA_InitTypeDef", "DMA_HandleTypeDef", "dma_channel_config"] {
+            if let Some(rest) = line.split(ty).nth(1).filter(|r| r.starts_with(char::is_whitespace)) {
+                let var: String = rest.trim()
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || *c == '_')
+                    .collect();
+                if !var.is_empty() {
+                    vars.entry(var).or_insert_with(|| PendingChannel { line: index, ..Default::default() });
+                }
+            }
+        }
+
+        // Field assignments: var.Init.Field = VALUE; or var.DMA_Field = VALUE;
+        if let Some((lhs, rhs)) = line.split_once('=') {
+            let mut path = lhs.trim().split('.');
+            if let Some(pending) = path.next().and_then(|var| vars.get_mut(var)) {
+                if let Some(field) = path.next_back() {
+                    let key = field.trim_start_matches("DMA_").to_string();
+                    pending.fields.insert(key, rhs.trim().trim_end_matches(';').trim().to_string());
+                }
+            }
+        }
+
+        // Pico SDK channel configuration calls
+        if let Some(args) = call_args(line, "channel_config_set_transfer_data_size") {
+            set_field(&mut vars, &args, "DataSize");
+        }
+        if let Some(args) = call_args(line, "channel_config_set_dreq") {
+            set_field(&mut vars, &args, "Request");
+        }
+        if let Some(args) = call_args(line, "channel_config_set_high_priority") {
+            set_field(&mut vars, &args, "HighPriority");
+        }
+        if let Some(args) = call_args(line, "channel_config_set_ring") {
+            set_field(&mut vars, &args, "Ring");
+        }
+
+        // Commit points
+        if let Some(args) = call_args(line, "HAL_DMA_Init") {
+            let var = args[0].trim_start_matches('&').to_string();
+            if let Some(pending) = vars.get(&var) {
+                let name = pending.fields.get("Instance").cloned().unwrap_or_else(|| var.clone());
+                committed.push((format!("{} ({})", name, var), PendingChannel { line: index, ..pending.clone() }));
+                committed_vars.push(var);
+            }
+        } else if let Some(args) = call_args(line, "DMA_Init").filter(|a| a.len() >= 2) {
+            let var = args[1].trim_start_matches('&').to_string();
+            if let Some(pending) = vars.get(&var) {
+                let mut channel = PendingChannel { line: index, ..pending.clone() };
+                channel.fields.insert("Instance".to_string(), args[0].clone());
+                committed.push((args[0].clone(), channel));
+                committed_vars.push(var);
+            }
+        } else if let Some(args) = call_args(line, "dma_channel_configure").filter(|a| a.len() >= 5) {
+            let var = args[1].trim_start_matches('&').to_string();
+            if let Some(pending) = vars.get(&var) {
+                let mut channel = PendingChannel { line: index, ..pending.clone() };
+                channel.fields.insert("BufferSize".to_string(), args[4].clone());
+                committed.push((format!("DMA channel {}", args[0]), channel));
+                committed_vars.push(var);
+            }
+        }
+    }
+
+    // Configurations that were never passed to an init call
+    let mut leftover: Vec<(String, PendingChannel)> = vars.into_iter()
+        .filter(|(var, pending)| !committed_vars.contains(var) && !pending.fields.is_empty())
+        .collect();
+    leftover.sort_by_key(|(_, p)| p.line);
+    committed.extend(leftover);
+    committed
+}
+
+fn set_field(vars: &mut HashMap<String, PendingChannel>, args: &[String], key: &str) {
+    let var = args[0].trim_start_matches('&');
+    if let Some(pending) = vars.get_mut(var) {
+        let value = args.get(1).cloned().unwrap_or_else(|| "true".to_string());
+        pending.fields.insert(key.to_string(), value);
+    }
+}
+
+/// Arguments of `name(...)` when the line calls it
+fn call_args(line: &str, name: &str) -> Option<Vec<String>> {
+    let at = line.find(&format!("{}(", name))?;
+    // Reject longer identifiers ending in `name`
+    if line[..at].chars().next_back().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+    let inner = &line[at + name.len() + 1..];
+    let inner = &inner[..inner.rfind(')')?];
+    Some(inner.split(',').map(|a| a.trim().to_string()).collect())
+}
+
+/// Trigger rates that can be read from peripheral setup code
+struct PeripheralRates {
+    uart_baud: Option<f64>,
+    spi_sck_hz: Option<f64>,
+    i2c_hz: Option<f64>,
+}
+
+impl PeripheralRates {
+    fn from_code(code: &str, bus_hz: f64) -> Self {
+        let number_after = |key: &str| -> Option<f64> {
+            code.lines()
+                .filter_map(|l| l.split_once(key))
+                .find_map(|(_, rest)| {
+                    let digits: String = rest.trim_start_matches([' ', '=', ',', '('])
+                        .chars()
+                        .take_while(|c| c.is_ascii_digit())
+                        .collect();
+                    digits.parse().ok()
+                })
+        };
+        Self {
+            uart_baud: number_after("BaudRate").or_else(|| {
+                code.lines().filter_map(|l| call_args(l, "uart_init")).find_map(|a| a.get(1)?.parse().ok())
+            }),
+            spi_sck_hz: number_after("BAUDRATEPRESCALER_")
+                .or_else(|| number_after("BaudRatePrescaler_"))
+                .map(|div| bus_hz / div),
+            i2c_hz: number_after("ClockSpeed"),
+        }
+    }
+}
+
+fn channel_stats(name: &str, pending: &PendingChannel, mcu: McuFamily, bus_hz: f64, rates: &PeripheralRates) -> DmaChannelStats {
+    let field = |keys: &[&str]| keys.iter().find_map(|k| pending.fields.get(*k)).map(|v| v.to_uppercase());
+
+    let data_width_bytes = field(&["PeriphDataAlignment", "PeripheralDataSize", "DataSize", "MemDataAlignment", "MemoryDataSize"])
+        .map(|v| width_from(&v))
+        .unwrap_or(1);
+    let burst_beats = ["MemBurst", "PeriphBurst", "MemoryBurst", "PeripheralBurst"].iter()
+        .filter_map(|k| pending.fields.get(*k))
+        .map(|v| burst_from(&v.to_uppercase()))
+        .max()
+        .unwrap_or(1);
+    let buffer_items = field(&["BufferSize"]).and_then(|v| v.trim_end_matches('U').parse().ok());
+    let circular = field(&["Mode"]).is_some_and(|v| v.contains("CIRCULAR")) || pending.fields.contains_key("Ring");
+
+    let priority = if pending.fields.contains_key("HighPriority") {
+        "High".to_string()
+    } else {
+        match field(&["Priority"]) {
+            Some(v) if v.contains("VERY_HIGH") || v.contains("VERYHIGH") => "VeryHigh".to_string(),
+            Some(v) if v.contains("HIGH") => "High".to_string(),
+            Some(v) if v.contains("MEDIUM") => "Medium".to_string(),
+            _ => "Low".to_string(),
+        }
+    };
+
+    let instance = pending.fields.get("Instance").cloned().unwrap_or_default();
+    let controller = controller_name(&instance, mcu);
+    let trigger = trigger_source(name, pending);
+
+    let (transfers_per_sec, rate_source) = trigger_rate(&trigger, data_width_bytes, mcu, bus_hz, burst_beats, rates);
+    let bus_cycles_per_transfer = 2.0 + ARBITRATION_CYCLES / burst_beats as f64;
+    let bus_utilization_pct = (transfers_per_sec * bus_cycles_per_transfer / bus_hz * 100.0).min(100.0);
+
+    DmaChannelStats {
+        name: name.to_string(),
+        controller,
+        trigger,
+        line: pending.line as u32 + 1,
+        data_width_bytes,
+        burst_beats,
+        buffer_items,
+        circular,
+        priority,
+        transfers_per_sec,
+        bandwidth_bytes_per_sec: transfers_per_sec * data_width_bytes as f64,
+        bus_utilization_pct,
+        rate_source,
+    }
+}
+
+fn width_from(value: &str) -> u32 {
+    if value.contains("HALFWORD") || value.contains("SIZE_16") {
+        2
+    } else if value.contains("WORD") || value.contains("SIZE_32") {
+        4
+    } else {
+        1
+    }
+}
+
+fn burst_from(value: &str) -> u32 {
+    ["INC16", "INC8", "INC4"].iter()
+        .find(|b| value.contains(*b))
+        .and_then(|b| b[3..].parse().ok())
+        .unwrap_or(1)
+}
+
+fn controller_name(instance: &str, mcu: McuFamily) -> String {
+    let upper = instance.to_uppercase();
+    if let Some(at) = upper.find("DMA") {
+        let id: String = upper[at..].chars().take_while(|c| c.is_alphanumeric()).collect();
+        if id.len() > 3 {
+            return id;
+        }
+    }
+    match mcu {
+        McuFamily::ESP32S3 | McuFamily::ESP32C3 => "GDMA".to_string(),
+        McuFamily::STM32F1 | McuFamily::STM32F4 | McuFamily::STM32H7 | McuFamily::STM32L4 | McuFamily::STM32G4 => "DMA1".to_string(),
+        _ => "DMA".to_string(),
+    }
+}
+
+/// Peripheral that paces the channel, from the request line or handle name
+fn trigger_source(name: &str, pending: &PendingChannel) -> String {
+    let direction = pending.fields.get("Direction").map(|d| d.to_uppercase()).unwrap_or_default();
+    let request = pending.fields.get("Request").map(|r| r.to_uppercase()).unwrap_or_default();
+    // DREQ_FORCE is the Pico SDK's unpaced request
+    if direction.contains("MEMORY_TO_MEMORY") || direction.contains("MEMORYTOMEMORY") || request == "DREQ_FORCE" {
+        return "MEM2MEM".to_string();
+    }
+    let source = request
+        .trim_start_matches("DMA_REQUEST_")
+        .trim_start_matches("DREQ_")
+        .to_string();
+    if !source.is_empty() && !source.starts_with("DMA_") {
+        return source;
+    }
+
+    // HAL handles are conventionally named hdma_<peripheral>_<dir>
+    let lower = name.to_lowercase();
+    for periph in ["adc", "dac", "usart", "uart", "spi", "i2c", "i2s", "sai", "tim"] {
+        if let Some(at) = lower.find(&format!("dma_{}", periph)) {
+            let id: String = lower[at + 4..].chars().take_while(|c| c.is_alphanumeric()).collect();
+            return id.to_uppercase();
+        }
+    }
+    "UNKNOWN".to_string()
+}
+
+/// Transfers per second for a trigger source and how it was determined
+fn trigger_rate(trigger: &str, width: u32, mcu: McuFamily, bus_hz: f64, burst: u32, rates: &PeripheralRates) -> (f64, String) {
+    let kind: String = trigger.chars().take_while(|c| c.is_alphabetic()).collect();
+    match kind.as_str() {
+        "ADC" => {
+            let sps = match mcu {
+                McuFamily::RP2040 => 500_000.0,
+                McuFamily::STM32F4 | McuFamily::STM32H7 | McuFamily::STM32G4 => 2_000_000.0,
+                _ => 1_000_000.0,
+            };
+            (sps, "typical ADC max sample rate".to_string())
+        }
+        "UART" | "USART" | "LPUART" => match rates.uart_baud {
+            Some(baud) => (baud / 10.0, format!("{} baud from code", baud)),
+            None => (11_520.0, "assumed 115200 baud".to_string()),
+        },
+        "SPI" => {
+            let (sck, source) = match rates.spi_sck_hz {
+                Some(sck) => (sck, "SPI prescaler from code".to_string()),
+                None => (bus_hz / 8.0, "assumed SPI clock of bus/8".to_string()),
+            };
+            (sck / (8.0 * width as f64), source)
+        }
+        "I2C" => {
+            let hz = rates.i2c_hz.unwrap_or(400_000.0);
+            (hz / 9.0, format!("{} Hz I2C clock", hz))
+        }
+        "I2S" | "SAI" => (96_000.0, "assumed 48 kHz stereo audio".to_string()),
+        "DAC" | "TIM" => (100_000.0, "assumed 100 kHz timer trigger".to_string()),
+        // Memory-to-memory runs back to back at bus speed
+        "MEM" => (bus_hz / (2.0 + ARBITRATION_CYCLES / burst as f64), "unpaced memory-to-memory".to_string()),
+        _ => (10_000.0, "unknown trigger, assumed 10k transfers/s".to_string()),
+    }
+}
+
+fn priority_guidance(channels: &[DmaChannelStats], by_controller: &BTreeMap<&str, Vec<&DmaChannelStats>>) -> Vec<String> {
+    let mut guidance = Vec::new();
+
+    // Overrun-sensitive peripheral sources should outrank everything else
+    let mut ranked: Vec<&DmaChannelStats> = channels.iter().filter(|c| c.trigger != "MEM2MEM").collect();
+    ranked.sort_by(|a, b| b.transfers_per_sec.total_cmp(&a.transfers_per_sec));
+    if let Some(fastest) = ranked.first() {
+        if fastest.priority != "VeryHigh" && fastest.priority != "High" {
+            guidance.push(format!(
+                "{} ({}) has the highest request rate; raise it to High or VeryHigh priority to avoid overruns",
+                fastest.name, fastest.trigger
+            ));
+        }
+    }
+
+    for channel in channels {
+        if channel.trigger == "MEM2MEM" && channel.priority != "Low" {
+            guidance.push(format!(
+                "{} is memory-to-memory; use Low priority so it does not starve peripheral requests",
+                channel.name
+            ));
+        }
+        if channel.bus_utilization_pct > 10.0 && channel.burst_beats == 1 {
+            guidance.push(format!(
+                "{} uses single transfers at {:.0}% bus load; enable the FIFO with INC4 bursts to cut arbitration overhead",
+                channel.name, channel.bus_utilization_pct
+            ));
+        }
+    }
+
+    for (controller, chans) in by_controller {
+        let mut seen: HashMap<&str, &str> = HashMap::new();
+        for c in chans {
+            if let Some(other) = seen.insert(c.priority.as_str(), c.name.as_str()) {
+                guidance.push(format!(
+                    "{} and {} on {} share {} priority; arbitration falls back to the lower stream number",
+                    other, c.name, controller, c.priority
+                ));
+            }
+        }
+    }
+
+    guidance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STM32_HAL: &str = r#"
+DMA_HandleTypeDef hdma_adc1;
+DMA_HandleTypeDef hdma_spi1_tx;
+
+void MX_DMA_Init(void) {
+    hdma_adc1.Instance = DMA2_Stream0;
+    hdma_adc1.Init.Channel = DMA_CHANNEL_0;
+    hdma_adc1.Init.Direction = DMA_PERIPH_TO_MEMORY;
+    hdma_adc1.Init.PeriphDataAlignment = DMA_PDATAALIGN_HALFWORD;
+    hdma_adc1.Init.MemDataAlignment = DMA_MDATAALIGN_HALFWORD;
+    hdma_adc1.Init.Mode = DMA_CIRCULAR;
+    hdma_adc1.Init.Priority = DMA_PRIORITY_LOW;
+    HAL_DMA_Init(&hdma_adc1);
+
+    hdma_spi1_tx.Instance = DMA2_Stream3;
+    hdma_spi1_tx.Init.PeriphDataAlignment = DMA_PDATAALIGN_BYTE;
+    hdma_spi1_tx.Init.MemBurst = DMA_MBURST_INC4;
+    hdma_spi1_tx.Init.Priority = DMA_PRIORITY_LOW;
+    HAL_DMA_Init(&hdma_spi1_tx);
+}
+
+hspi1.Init.BaudRatePrescaler = SPI_BAUDRATEPRESCALER_4;
+"#;
+
+    #[test]
+    fn test_stm32_hal_channels() {
+        let report = analyze_dma_usage(STM32_HAL, McuFamily::STM32F4, 84);
+        assert_eq!(report.channels.len(), 2);
+
+        let adc = &report.channels[0];
+        assert_eq!(adc.controller, "DMA2");
+        assert_eq!(adc.trigger, "ADC1");
+        assert_eq!(adc.data_width_bytes, 2);
+        assert!(adc.circular);
+        assert!((adc.bandwidth_bytes_per_sec - 4_000_000.0).abs() < 1.0);
+
+        let spi = &report.channels[1];
+        assert_eq!(spi.burst_beats, 4);
+        assert!((spi.transfers_per_sec - 84e6 / 4.0 / 8.0).abs() < 1.0);
+
+        assert!(report.contention_warning.is_none());
+        assert!(report.priority_guidance.iter().any(|g| g.contains("share Low priority")));
+    }
+
+    #[test]
+    fn test_pico_mem_to_mem_contention() {
+        let code = r#"
+dma_channel_config c = dma_channel_get_default_config(0);
+channel_config_set_transfer_data_size(&c, DMA_SIZE_32);
+channel_config_set_dreq(&c, DREQ_ADC);
+dma_channel_configure(0, &c, buf, &adc_hw->fifo, 1024, true);
+
+dma_channel_config m = dma_channel_get_default_config(1);
+channel_config_set_transfer_data_size(&m, DMA_SIZE_32);
+channel_config_set_dreq(&m, DREQ_FORCE);
+dma_channel_configure(1, &m, dst, src, 4096, true);
+"#;
+        let report = analyze_dma_usage(code, McuFamily::RP2040, 125);
+        assert_eq!(report.channels.len(), 2);
+        assert_eq!(report.channels[0].trigger, "ADC");
+        assert_eq!(report.channels[0].buffer_items, Some(1024));
+        assert_eq!(report.channels[1].trigger, "MEM2MEM");
+        assert!(report.contention_warning.unwrap().contains("DMA"));
+    }
+}
@@ -0,0 +1,537 @@
+// Interrupt Latency Estimator
+// Static worst-case timing of `_IRQHandler` functions
+
+use crate::drivers::mcu::McuFamily;
+use serde::{Deserialize, Serialize};
+
+/// Loop iterations assumed when the bound is not a literal
+const DEFAULT_LOOP_ITERATIONS: u32 = 10;
+
+/// Cycle costs for one core type
+#[derive(Debug, Clone, Copy)]
+pub(super) struct InstructionTiming {
+    alu: u32,
+    load_store: u32,
+    multiply: u32,
+    divide: u32,
+    pub(super) branch: u32,
+    pub(super) call: u32,
+    float_op: u32,
+    float_div: u32,
+}
+
+/// Exception entry/exit behaviour for one core type
+#[derive(Debug, Clone, Copy)]
+pub(super) struct ExceptionTiming {
+    entry: u32,
+    exit: u32,
+    /// Extra cycles to stack/unstack S0-S15 and FPSCR when the ISR touches the FPU
+    fpu_context: u32,
+}
+
+pub(super) fn core_timing(mcu: McuFamily) -> (InstructionTiming, ExceptionTiming) {
+    match mcu {
+        // Cortex-M0+: no hardware divide or FPU, single-cycle multiplier on RP2040
+        McuFamily::RP2040 => (
+            InstructionTiming { alu: 1, load_store: 2, multiply: 1, divide: 40, branch: 3, call: 4, float_op: 60, float_div: 200 },
+            ExceptionTiming { entry: 15, exit: 13, fpu_context: 0 },
+        ),
+        // Cortex-M3: hardware divide, software float
+        McuFamily::STM32F1 | McuFamily::LPC1768 => (
+            InstructionTiming { alu: 1, load_store: 2, multiply: 1, divide: 12, branch: 3, call: 4, float_op: 50, float_div: 150 },
+            ExceptionTiming { entry: 12, exit: 10, fpu_context: 0 },
+        ),
+        // Cortex-M7: dual issue, faster branches
+        McuFamily::STM32H7 => (
+            InstructionTiming { alu: 1, load_store: 1, multiply: 1, divide: 10, branch: 2, call: 3, float_op: 1, float_div: 16 },
+            ExceptionTiming { entry: 12, exit: 10, fpu_context: 17 },
+        ),
+        // Xtensa cores dispatch through the ESP-IDF interrupt wrapper
+        McuFamily::ESP32 | McuFamily::ESP32S3 => (
+            InstructionTiming { alu: 1, load_store: 2, multiply: 2, divide: 12, branch: 3, call: 6, float_op: 1, float_div: 30 },
+            ExceptionTiming { entry: 50, exit: 30, fpu_context: 0 },
+        ),
+        McuFamily::ESP32C3 => (
+            InstructionTiming { alu: 1, load_store: 2, multiply: 1, divide: 33, branch: 3, call: 4, float_op: 60, float_div: 200 },
+            ExceptionTiming { entry: 30, exit: 20, fpu_context: 0 },
+        ),
+        // Cortex-M4(F) and M33
+        mcu => {
+            let fpu = mcu.has_fpu();
+            (
+                InstructionTiming {
+                    alu: 1,
+                    load_store: 2,
+                    multiply: 1,
+                    divide: 12,
+                    branch: 3,
+                    call: 4,
+                    float_op: if fpu { 1 } else { 50 },
+                    float_div: if fpu { 14 } else { 150 },
+                },
+                // Lazy stacking keeps entry at 12 cycles until the first FP instruction
+                ExceptionTiming { entry: 12, exit: 10, fpu_context: if fpu { 17 } else { 0 } },
+            )
+        }
+    }
+}
+
+/// Timing of one interrupt handler
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IsrEstimate {
+    pub name: String,
+    pub line: u32,
+    pub execution_cycles: u32,
+    pub uses_fpu: bool,
+}
+
+/// Interrupt latency report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IrqLatencyReport {
+    pub architecture: String,
+    pub irq_priority: u8,
+    pub handlers: Vec<IsrEstimate>,
+    pub hardware_entry_cycles: u32,
+    pub nvic_blocking_cycles: u32,
+    pub entry_latency_cycles: u32,
+    pub worst_case_execution_cycles: u32,
+    pub exit_latency_cycles: u32,
+    pub notes: Vec<String>,
+}
+
+impl IrqLatencyReport {
+    /// Cycles from the interrupt request until the handler has returned
+    pub fn total_cycles(&self) -> u32 {
+        self.entry_latency_cycles
+            .saturating_add(self.worst_case_execution_cycles)
+            .saturating_add(self.exit_latency_cycles)
+    }
+
+    /// Whether the worst case completes within `deadline_us` at `freq_mhz`
+    pub fn meets_deadline(&self, deadline_us: f32, freq_mhz: u32) -> bool {
+        freq_mhz > 0 && self.total_cycles() as f32 / freq_mhz as f32 <= deadline_us
+    }
+}
+
+/// Estimate worst-case latency of the `_IRQHandler` functions in `code`
+///
+/// Entry latency is the hardware exception entry plus NVIC blocking: the
+/// longest interrupt-masked critical section or equal-priority handler that
+/// must finish first, plus one preemption per higher priority level (up to
+/// the number of other handlers). Execution is the slowest handler body.
+///
+/// Fails when a loop is malformed or the cycle count does not fit in a `u32`.
+pub fn estimate_isr_latency(code: &str, mcu_family: McuFamily, irq_priority: u8) -> Result<IrqLatencyReport, String> {
+    let (timing, exception) = core_timing(mcu_family);
+    let lines: Vec<&str> = code.lines().collect();
+    let mut notes = Vec::new();
+
+    let mut handlers = Vec::new();
+    for (start, body) in find_isr_bodies(&lines) {
+        let name = handler_name(lines[start]).unwrap_or_default();
+        let (cycles, unbounded) = body_cycles(&body, &timing)
+            .map_err(|e| format!("{}: {}", name, e))?;
+        if unbounded {
+            notes.push(format!("{}: loop bound not constant, assumed {} iterations", name, DEFAULT_LOOP_ITERATIONS));
+        }
+        let uses_fpu = body.iter().any(|l| is_float_line(l));
+        handlers.push(IsrEstimate {
+            name,
+            line: start as u32 + 1,
+            execution_cycles: cycles.saturating_add(if uses_fpu { exception.fpu_context } else { 0 }),
+            uses_fpu,
+        });
+    }
+
+    if handlers.is_empty() {
+        notes.push("No functions ending in _IRQHandler found".to_string());
+    }
+
+    let worst = handlers.iter().max_by_key(|h| h.execution_cycles);
+    let worst_case_execution_cycles = worst.map_or(0, |h| h.execution_cycles);
+    let worst_name = worst.map(|h| h.name.clone());
+
+    // Handlers other than the analysed one can hold off entry
+    let mut others: Vec<u32> = handlers.iter()
+        .filter(|h| Some(&h.name) != worst_name.as_ref())
+        .map(|h| h.execution_cycles.saturating_add(exception.entry + exception.exit))
+        .collect();
+    others.sort_unstable_by(|a, b| b.cmp(a));
+
+    let critical_section = critical_section_cycles(&lines, &timing)?;
+    let same_priority = others.first().copied().unwrap_or(0);
+    let preemptions = (irq_priority as usize).min(others.len());
+    let higher_priority = others.iter().take(preemptions).fold(0u32, |acc, c| acc.saturating_add(*c));
+    let nvic_blocking_cycles = critical_section.max(same_priority).saturating_add(higher_priority);
+
+    if critical_section > 0 {
+        notes.push(format!("Interrupts masked for up to {} cycles by __disable_irq sections", critical_section));
+    }
+    if irq_priority > 0 && preemptions > 0 {
+        notes.push(format!("Priority {} can be preempted by {} higher-priority handler(s)", irq_priority, preemptions));
+    }
+    if handlers.iter().any(|h| h.uses_fpu) && exception.fpu_context > 0 {
+        notes.push("FPU used in ISR: lazy stacking adds FP context save/restore".to_string());
+    }
+    if matches!(mcu_family, McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3) {
+        notes.push("Entry/exit include an approximate ESP-IDF dispatcher overhead".to_string());
+    }
+
+    let fpu_exit = if handlers.iter().any(|h| h.uses_fpu) { exception.fpu_context } else { 0 };
+
+    Ok(IrqLatencyReport {
+        architecture: mcu_family.architecture().to_string(),
+        irq_priority,
+        handlers,
+        hardware_entry_cycles: exception.entry,
+        nvic_blocking_cycles,
+        entry_latency_cycles: exception.entry.saturating_add(nvic_blocking_cycles),
+        worst_case_execution_cycles,
+        exit_latency_cycles: exception.exit + fpu_exit,
+        notes,
+    })
+}
+
+fn handler_name(line: &str) -> Option<String> {
+    let before = &line[..line.find('(')?];
+    let name = before.split_whitespace().next_back()?.trim_start_matches('*');
+    name.ends_with("_IRQHandler").then(|| name.to_string())
+}
+
+/// Definition line and body of every ISR
+///
+/// Only signatures at file scope count, so handler calls inside other
+/// functions are not mistaken for definitions. The body is split at the
+/// braces, which keeps code sharing a line with `{` or `}` and handles
+/// single-line handlers and braces on the line after the signature.
+fn find_isr_bodies<'a>(lines: &[&'a str]) -> Vec<(usize, Vec<&'a str>)> {
+    let mut bodies = Vec::new();
+    let mut depth = 0usize;
+    // Handler signature seen at file scope, waiting for its opening brace
+    let mut pending: Option<usize> = None;
+    let mut current: Option<(usize, Vec<&'a str>)> = None;
+
+    for (i, &raw) in lines.iter().enumerate() {
+        let line = strip_comment(raw);
+        if depth == 0 && handler_name(line).is_some() {
+            pending = Some(i);
+        }
+
+        let mut segment_start = 0;
+        for (k, c) in line.char_indices() {
+            match c {
+                '{' => {
+                    depth += 1;
+                    if depth == 1 {
+                        if let Some(start) = pending.take() {
+                            current = Some((start, Vec::new()));
+                            segment_start = k + 1;
+                        }
+                    }
+                }
+                '}' => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        if let Some((start, mut body)) = current.take() {
+                            body.push(&line[segment_start..k]);
+                            bodies.push((start, body));
+                        }
+                    }
+                }
+                // A prototype ends before any body opens
+                ';' if depth == 0 => pending = None,
+                _ => {}
+            }
+        }
+        if let Some((_, body)) = current.as_mut() {
+            body.push(&line[segment_start..]);
+        }
+    }
+    // An unterminated body runs to the end of the file
+    bodies.extend(current);
+    bodies
+}
+
+/// Cycles for a block of statements, with loop bodies multiplied out
+///
+/// Also returns whether any loop bound had to be assumed. Fails on a
+/// malformed loop header or when the total does not fit in a `u32`.
+pub(super) fn body_cycles(lines: &[&str], timing: &InstructionTiming) -> Result<(u32, bool), String> {
+    let mut total = 0u32;
+    let mut unbounded = false;
+    // (brace depth the loop opened at, iteration multiplier)
+    let mut loops: Vec<(i32, u32)> = Vec::new();
+    let mut depth = 0i32;
+
+    for raw in lines {
+        let line = strip_comment(raw).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let multiplier = loops.iter()
+            .try_fold(1u32, |acc, (_, n)| acc.checked_mul(*n))
+            .ok_or_else(|| overflow(line))?
+            .max(1);
+        let mut cost = statement_cycles(line, timing);
+
+        let keyword = line.split(|c: char| !c.is_alphanumeric() && c != '_').next().unwrap_or("");
+        if matches!(keyword, "for" | "while") {
+            let iterations = loop_iterations(line)?.unwrap_or_else(|| {
+                unbounded = true;
+                DEFAULT_LOOP_ITERATIONS
+            });
+            // Condition and back branch run every iteration
+            cost = cost.checked_mul(iterations).ok_or_else(|| overflow(line))?;
+            if line.ends_with('{') {
+                loops.push((depth, iterations));
+            }
+        }
+
+        total = cost.checked_mul(multiplier)
+            .and_then(|c| total.checked_add(c))
+            .ok_or_else(|| overflow(line))?;
+
+        depth += line.matches('{').count() as i32;
+        depth -= line.matches('}').count() as i32;
+        while loops.last().is_some_and(|(d, _)| depth <= *d) {
+            loops.pop();
+        }
+    }
+
+    Ok((total, unbounded))
+}
+
+fn overflow(line: &str) -> String {
+    format!("cycle count overflows at `{}`", line)
+}
+
+/// Cycle cost of a single source line
+fn statement_cycles(line: &str, timing: &InstructionTiming) -> u32 {
+    let float = is_float_line(line);
+    let mut cycles = 0;
+
+    // Memory accesses through pointers and arrays
+    let accesses = line.matches("->").count() + line.matches('[').count();
+    cycles += accesses as u32 * timing.load_store;
+    if is_assignment(line) {
+        cycles += timing.load_store;
+    }
+
+    let alu_ops = [" + ", " - ", " & ", " | ", " ^ ", "<<", ">>", "++", "--", "+=", "-=", "|=", "&=", "^=", "==", "!=", " < ", " > "]
+        .iter()
+        .map(|op| line.matches(op).count() as u32)
+        .sum::<u32>();
+    let mul_ops = (line.matches(" * ").count() + line.matches("*=").count()) as u32;
+    let div_ops = (line.matches(" / ").count() + line.matches(" % ").count() + line.matches("/=").count()) as u32;
+
+    if float {
+        cycles += (alu_ops + mul_ops) * timing.float_op + div_ops * timing.float_div;
+    } else {
+        cycles += alu_ops * timing.alu + mul_ops * timing.multiply + div_ops * timing.divide;
+    }
+
+    let keyword = line.split(|c: char| !c.is_alphanumeric() && c != '_').next().unwrap_or("");
+    if matches!(keyword, "if" | "else" | "switch" | "case" | "for" | "while" | "return" | "break" | "continue") {
+        cycles += timing.branch;
+    }
+    cycles += function_calls(line) * timing.call;
+
+    // Every statement costs at least one instruction
+    cycles.max(if line == "{" || line == "}" { 0 } else { timing.alu })
+}
+
+pub(super) fn function_calls(line: &str) -> u32 {
+    let bytes = line.as_bytes();
+    let mut calls = 0;
+    for (i, _) in line.match_indices('(') {
+        let name: String = line[..i].chars().rev()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect();
+        let name: String = name.chars().rev().collect();
+        let is_keyword = matches!(name.as_str(), "" | "if" | "for" | "while" | "switch" | "return" | "sizeof");
+        let is_cast = i > 0 && bytes[i - 1] == b'(';
+        if !is_keyword && !is_cast && !name.chars().next().is_some_and(|c| c.is_ascii_digit()) {
+            calls += 1;
+        }
+    }
+    calls
+}
+
+/// Literal bound of `for (...; i < N; ...)` or `while (n < N)`
+///
+/// `Ok(None)` when the bound is not a literal; an error when the header's
+/// parentheses do not balance or the bound does not fit in a `u32`.
+fn loop_iterations(line: &str) -> Result<Option<u32>, String> {
+    let (Some(open), Some(close)) = (line.find('('), line.rfind(')')) else {
+        return Err(format!("unbalanced parentheses in `{}`", line));
+    };
+    if close < open || line.matches('(').count() != line.matches(')').count() {
+        return Err(format!("unbalanced parentheses in `{}`", line));
+    }
+    let condition = if line.starts_with("for") {
+        match line.split(';').nth(1) {
+            Some(condition) => condition,
+            None => return Ok(None),
+        }
+    } else {
+        &line[open + 1..close]
+    };
+    let Some((_, bound)) = condition.split_once("<=").or_else(|| condition.split_once('<')) else {
+        return Ok(None);
+    };
+    let bound = bound.trim().trim_end_matches(['U', 'u', 'L']);
+    let value = if let Some(hex) = bound.strip_prefix("0x") {
+        u32::from_str_radix(hex, 16)
+    } else {
+        bound.parse()
+    };
+    let value = match value {
+        Ok(value) => value,
+        Err(e) if matches!(e.kind(), std::num::IntErrorKind::PosOverflow) => {
+            return Err(format!("loop bound `{}` does not fit in 32 bits", bound));
+        }
+        Err(_) => return Ok(None),
+    };
+    if condition.contains("<=") {
+        value.checked_add(1)
+            .map(Some)
+            .ok_or_else(|| format!("loop bound `{}` does not fit in 32 bits", bound))
+    } else {
+        Ok(Some(value))
+    }
+}
+
+/// Longest run of statements between `__disable_irq()` and `__enable_irq()`
+fn critical_section_cycles(lines: &[&str], timing: &InstructionTiming) -> Result<u32, String> {
+    let mut longest = 0;
+    let mut start: Option<usize> = None;
+    for (i, line) in lines.iter().enumerate() {
+        if line.contains("__disable_irq") {
+            start = Some(i + 1);
+        } else if line.contains("__enable_irq") {
+            if let Some(s) = start.take() {
+                longest = longest.max(body_cycles(&lines[s..i], timing)?.0);
+            }
+        }
+    }
+    Ok(longest)
+}
+
+fn is_assignment(line: &str) -> bool {
+    line.char_indices().any(|(i, c)| {
+        c == '=' && {
+            let prev = line[..i].chars().next_back().unwrap_or(' ');
+            let next = line[i + 1..].chars().next().unwrap_or(' ');
+            !matches!(prev, '=' | '!' | '<' | '>') && next != '='
+        }
+    })
+}
+
+pub(super) fn is_float_line(line: &str) -> bool {
+    let line = strip_comment(line);
+    line.contains("float") || line.contains("double") || line.contains("sqrtf") || has_float_literal(line)
+}
+
+fn has_float_literal(line: &str) -> bool {
+    let chars: Vec<char> = line.chars().collect();
+    chars.windows(3).any(|w| w[0].is_ascii_digit() && w[1] == '.' && w[2].is_ascii_digit())
+}
+
+pub(super) fn strip_comment(line: &str) -> &str {
+    line.split("//").next().unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ISR: &str = r#"
+void TIM2_IRQHandler(void) {
+    if (TIM2->SR & TIM_SR_UIF) {
+        TIM2->SR &= ~TIM_SR_UIF;
+        for (int i = 0; i < 8; i++) {
+            buffer[i] = buffer[i] + 1;
+        }
+    }
+}
+
+void USART1_IRQHandler(void) {
+    uint8_t data = USART1->DR;
+    float scaled = data * 0.5f;
+    process(scaled);
+}
+"#;
+
+    #[test]
+    fn test_isr_latency_cortex_m4() {
+        let report = estimate_isr_latency(ISR, McuFamily::STM32F4, 0).unwrap();
+        assert_eq!(report.handlers.len(), 2);
+        assert_eq!(report.hardware_entry_cycles, 12);
+        assert!(report.handlers[1].uses_fpu);
+        assert_eq!(report.exit_latency_cycles, 10 + 17);
+
+        // The loop body dominates the timer handler
+        assert!(report.handlers[0].execution_cycles > 8 * 5);
+        assert_eq!(report.worst_case_execution_cycles, report.handlers[0].execution_cycles);
+        assert!(report.meets_deadline(10.0, 168));
+        assert!(!report.meets_deadline(0.1, 168));
+    }
+
+    #[test]
+    fn test_priority_and_core_affect_latency() {
+        let m4 = estimate_isr_latency(ISR, McuFamily::STM32F4, 0).unwrap();
+        let m4_low = estimate_isr_latency(ISR, McuFamily::STM32F4, 3).unwrap();
+        assert!(m4_low.entry_latency_cycles > m4.entry_latency_cycles);
+
+        let m0 = estimate_isr_latency(ISR, McuFamily::RP2040, 0).unwrap();
+        assert_eq!(m0.hardware_entry_cycles, 15);
+        assert!(m0.handlers[1].execution_cycles > m4.handlers[1].execution_cycles);
+    }
+
+    #[test]
+    fn test_malformed_loops_are_errors() {
+        assert_eq!(loop_iterations("for (i = 0; i < 0x10; i++) {"), Ok(Some(16)));
+        assert_eq!(loop_iterations("while (ready) {"), Ok(None));
+        assert!(loop_iterations("for (i = 0; i <= 0xFFFFFFFF; i++) {").is_err());
+        assert!(loop_iterations("while (n < 99999999999) {").is_err());
+        assert!(loop_iterations("while x) (y < 3 {").is_err());
+        assert!(loop_iterations("while (n < 3 {").is_err());
+
+        // Nested loop bounds that multiply past u32 fail instead of wrapping
+        let nested = "void TIM2_IRQHandler(void) {\n    for (i = 0; i < 100000; i++) {\n        for (j = 0; j < 100000; j++) {\n            x++;\n        }\n    }\n}\n";
+        assert!(estimate_isr_latency(nested, McuFamily::STM32F4, 0).is_err());
+        assert!(estimate_isr_latency("void TIM2_IRQHandler(void) {\n    while x) (y < 3 {\n}\n", McuFamily::STM32F4, 0).is_err());
+    }
+
+    #[test]
+    fn test_isr_brace_styles() {
+        let code = r#"
+void TIM2_IRQHandler(void);
+
+void TIM2_IRQHandler(void)
+{
+    HAL_TIM_IRQHandler(&htim2);  // clears the update flag
+    counter = counter + 1;
+}
+
+void EXTI0_IRQHandler(void) { pressed = 1; }
+
+void poll(void) {
+    USART1_IRQHandler();  // drain the FIFO from thread mode
+}
+"#;
+        let report = estimate_isr_latency(code, McuFamily::STM32F4, 0).unwrap();
+        let names: Vec<&str> = report.handlers.iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(names, ["TIM2_IRQHandler", "EXTI0_IRQHandler"]);
+        assert_eq!(report.handlers[0].line, 4);
+        assert_eq!(report.handlers[1].line, 10);
+
+        // Body lines are costed whichever line the braces sit on
+        let (timing, _) = core_timing(McuFamily::STM32F4);
+        let next_line = body_cycles(&["HAL_TIM_IRQHandler(&htim2);", "counter = counter + 1;"], &timing).unwrap().0;
+        assert_eq!(report.handlers[0].execution_cycles, next_line);
+        assert_eq!(report.handlers[1].execution_cycles, body_cycles(&["pressed = 1;"], &timing).unwrap().0);
+        assert!(report.handlers[1].execution_cycles > 0);
+    }
+}
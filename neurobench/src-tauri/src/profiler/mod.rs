@@ -4,6 +4,11 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+pub mod dma;
+pub mod interrupt;
+pub mod symbolize;
+pub mod wcet;
+
 /// Code complexity metrics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodeMetrics {
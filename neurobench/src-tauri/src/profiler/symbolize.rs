@@ -0,0 +1,142 @@
+// Stack Trace Symbolization
+// Maps firmware addresses from crash logs to function, file and line via the ELF's DWARF
+
+use std::borrow::Cow;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// One symbolized address
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymbolizedFrame {
+    pub address: u32,
+    /// Demangled name, `??` when the address is outside any known function
+    pub function: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SymbolizeError {
+    #[error("ELF file not found: {0}")]
+    NotFound(String),
+    #[error("Failed to load ELF debug info: {0}")]
+    Load(String),
+    #[error("Lookup failed at 0x{address:08X}: {message}")]
+    Lookup { address: u32, message: String },
+}
+
+/// Symbolize code addresses, e.g. the PCs of a panic or HardFault backtrace
+///
+/// The Thumb bit is ignored, so raw LR/PC values can be passed as is. Addresses
+/// inside inlined code resolve to the innermost inlined function. Without DWARF
+/// the ELF symbol table still gives the function name.
+pub fn symbolize_addresses(addresses: &[u32], elf_path: &Path) -> Result<Vec<SymbolizedFrame>, SymbolizeError> {
+    if !elf_path.exists() {
+        return Err(SymbolizeError::NotFound(elf_path.display().to_string()));
+    }
+    let loader = addr2line::Loader::new(elf_path).map_err(|e| SymbolizeError::Load(e.to_string()))?;
+
+    addresses.iter().map(|&address| {
+        let probe = (address & !1) as u64;
+        let lookup_err = |e: &dyn std::fmt::Display| SymbolizeError::Lookup { address, message: e.to_string() };
+
+        let mut frames = loader.find_frames(probe).map_err(|e| lookup_err(&e))?;
+        let innermost = frames.next().map_err(|e| lookup_err(&e))?;
+
+        let function = innermost.as_ref()
+            .and_then(|frame| frame.function.as_ref())
+            .and_then(|name| name.demangle().ok().map(Cow::into_owned))
+            .or_else(|| loader.find_symbol(probe).map(|sym| addr2line::demangle_auto(Cow::Borrowed(sym), None).into_owned()))
+            .unwrap_or_else(|| "??".to_string());
+        let location = match innermost.and_then(|frame| frame.location) {
+            Some(location) => Some(location),
+            None => loader.find_location(probe).map_err(|e| lookup_err(&e))?,
+        };
+
+        Ok(SymbolizedFrame {
+            address,
+            function,
+            file: location.as_ref().and_then(|l| l.file).map(str::to_string),
+            line: location.and_then(|l| l.line),
+        })
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal 32-bit ARM ELF whose symbol table holds one function, no DWARF
+    fn elf_with_function(name: &str, address: u32, size: u32) -> Vec<u8> {
+        let strtab = format!("\0{}\0", name).into_bytes();
+        let shstrtab = b"\0.text\0.symtab\0.strtab\0.shstrtab\0";
+        let text_offset = 52u32;
+        let symtab_offset = text_offset + size;
+        let strtab_offset = symtab_offset + 32;
+        let shstrtab_offset = strtab_offset + strtab.len() as u32;
+        let shoff = (shstrtab_offset + shstrtab.len() as u32 + 3) & !3;
+
+        let mut elf = vec![0x7f, b'E', b'L', b'F', 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        elf.extend_from_slice(&2u16.to_le_bytes()); // ET_EXEC
+        elf.extend_from_slice(&40u16.to_le_bytes()); // EM_ARM
+        for field in [1u32, address | 1, 0, shoff, 0] {
+            elf.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [52u16, 32, 0, 40, 5, 4] {
+            elf.extend_from_slice(&field.to_le_bytes());
+        }
+        elf.resize(elf.len() + size as usize, 0);
+
+        // Null symbol, then a global function in .text
+        elf.extend_from_slice(&[0; 16]);
+        for field in [1u32, address, size] {
+            elf.extend_from_slice(&field.to_le_bytes());
+        }
+        elf.extend_from_slice(&[0x12, 0, 1, 0]);
+        elf.extend_from_slice(&strtab);
+        elf.extend_from_slice(shstrtab);
+        elf.resize(shoff as usize, 0);
+
+        let sections: [[u32; 10]; 5] = [
+            [0; 10],
+            [1, 1, 6, address, text_offset, size, 0, 0, 4, 0],
+            [7, 2, 0, 0, symtab_offset, 32, 3, 1, 4, 16],
+            [15, 3, 0, 0, strtab_offset, strtab.len() as u32, 0, 0, 1, 0],
+            [23, 3, 0, 0, shstrtab_offset, shstrtab.len() as u32, 0, 0, 1, 0],
+        ];
+        for field in sections.iter().flatten() {
+            elf.extend_from_slice(&field.to_le_bytes());
+        }
+        elf
+    }
+
+    #[test]
+    fn test_symbol_table_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("firmware.elf");
+        std::fs::write(&path, elf_with_function("Reset_Handler", 0x0800_0100, 0x40)).unwrap();
+
+        // A Thumb LR value and an address before any function
+        let frames = symbolize_addresses(&[0x0800_0121, 0x0800_0000], &path).unwrap();
+        assert_eq!(frames, vec![
+            SymbolizedFrame { address: 0x0800_0121, function: "Reset_Handler".to_string(), file: None, line: None },
+            SymbolizedFrame { address: 0x0800_0000, function: "??".to_string(), file: None, line: None },
+        ]);
+    }
+
+    #[test]
+    fn test_missing_elf() {
+        let err = symbolize_addresses(&[0x0800_0101], Path::new("/nonexistent/firmware.elf")).unwrap_err();
+        assert!(matches!(err, SymbolizeError::NotFound(_)));
+    }
+
+    #[test]
+    fn test_not_an_elf() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("firmware.elf");
+        std::fs::write(&path, b"not an elf file").unwrap();
+        let err = symbolize_addresses(&[0x0800_0101], &path).unwrap_err();
+        assert!(matches!(err, SymbolizeError::Load(_)));
+    }
+}
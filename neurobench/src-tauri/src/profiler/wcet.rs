@@ -0,0 +1,355 @@
+// Worst-Case Execution Time Estimator
+// Static cycle estimate plus cycle-counter instrumentation of one function
+
+use super::interrupt::{body_cycles, core_timing, function_calls, is_float_line, strip_comment};
+use crate::drivers::mcu::McuFamily;
+use serde::{Deserialize, Serialize};
+
+/// WCET estimate and instrumented source for one function
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WcetReport {
+    /// Input code with the function wrapped in cycle counter reads;
+    /// unchanged when the function could not be instrumented
+    pub instrumented_code: String,
+    /// `None` when a loop bound is unknown or the function was not found
+    pub estimated_cycles: Option<u32>,
+    pub uncertainty_percent: f32,
+    pub timing_notes: Vec<String>,
+}
+
+/// Base uncertainty of the static model per core
+///
+/// In-order cores without caches follow the model closely; caches, flash
+/// wait states and branch prediction widen the spread.
+fn base_uncertainty(mcu: McuFamily) -> f32 {
+    match mcu {
+        McuFamily::RP2040 | McuFamily::STM32F1 | McuFamily::LPC1768 => 10.0,
+        McuFamily::STM32H7 => 35.0,
+        McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3 => 40.0,
+        _ => 20.0,
+    }
+}
+
+/// Cycle counter access for a core: (include, one-time enable, read expression)
+fn cycle_counter(mcu: McuFamily) -> Option<(&'static str, &'static str, &'static str)> {
+    match mcu {
+        // Cortex-M0+ has no DWT cycle counter
+        McuFamily::RP2040 => None,
+        McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3 => {
+            Some(("#include \"esp_cpu.h\"\n", "", "(uint32_t)esp_cpu_get_cycle_count()"))
+        }
+        // M7 ships with the DWT software lock engaged
+        McuFamily::STM32H7 => Some((
+            "",
+            "        CoreDebug->DEMCR |= CoreDebug_DEMCR_TRCENA_Msk;\n        DWT->LAR = 0xC5ACCE55;\n        DWT->CYCCNT = 0;\n        DWT->CTRL |= DWT_CTRL_CYCCNTENA_Msk;\n",
+            "DWT->CYCCNT",
+        )),
+        _ => Some((
+            "",
+            "        CoreDebug->DEMCR |= CoreDebug_DEMCR_TRCENA_Msk;\n        DWT->CYCCNT = 0;\n        DWT->CTRL |= DWT_CTRL_CYCCNTENA_Msk;\n",
+            "DWT->CYCCNT",
+        )),
+    }
+}
+
+/// A function definition located in the source
+struct FunctionDef {
+    /// Line holding the name
+    line: usize,
+    /// Text before the name, e.g. `static uint32_t`
+    prefix: String,
+    params: String,
+    /// Body lines between the braces
+    body: std::ops::Range<usize>,
+    /// Line with the closing brace
+    end: usize,
+}
+
+fn find_function(lines: &[&str], name: &str) -> Option<FunctionDef> {
+    let pattern = format!("{}(", name);
+    for (i, line) in lines.iter().enumerate() {
+        // Definitions start in column 0; indented matches are calls
+        if line.starts_with(char::is_whitespace) || line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+        let Some(at) = line.find(&pattern) else { continue };
+        let prefix = &line[..at];
+        if prefix.ends_with(|c: char| c.is_alphanumeric() || c == '_') || prefix.contains(['=', '(']) {
+            continue;
+        }
+
+        // Signature runs until the opening brace; a ';' first means a prototype
+        let mut signature = String::new();
+        let mut open = None;
+        for (j, l) in lines.iter().enumerate().skip(i) {
+            let l = strip_comment(l);
+            if let Some(brace) = l.find('{') {
+                signature.push_str(&l[..brace]);
+                open = Some(j);
+                break;
+            }
+            if l.contains(';') {
+                break;
+            }
+            signature.push_str(l);
+            signature.push(' ');
+        }
+        let Some(open) = open else { continue };
+
+        let params_start = signature.find(&pattern)? + pattern.len();
+        let params_end = signature.rfind(')')?;
+        let mut depth = 0i32;
+        let mut end = lines.len() - 1;
+        for (j, l) in lines.iter().enumerate().skip(open) {
+            depth += l.matches('{').count() as i32 - l.matches('}').count() as i32;
+            if depth <= 0 {
+                end = j;
+                break;
+            }
+        }
+        return Some(FunctionDef {
+            line: i,
+            prefix: prefix.trim().to_string(),
+            params: signature[params_start..params_end].trim().to_string(),
+            body: open + 1..end.max(open + 1),
+            end,
+        });
+    }
+    None
+}
+
+/// Argument names to forward from a parameter list, `None` for variadics
+fn param_names(params: &str) -> Option<Vec<String>> {
+    if params.is_empty() || params == "void" {
+        return Some(Vec::new());
+    }
+    params.split(',').map(|param| {
+        let param = param.trim();
+        if param == "..." {
+            return None;
+        }
+        let param = param.split('[').next().unwrap_or(param).trim_end();
+        let name: String = param.chars().rev().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+        (!name.is_empty()).then(|| name.chars().rev().collect())
+    }).collect()
+}
+
+/// Wrap `function` so every call records its cycle count
+fn instrument(lines: &[&str], name: &str, function: &FunctionDef, mcu: McuFamily) -> Option<String> {
+    let (include, enable, read) = cycle_counter(mcu)?;
+    let args = param_names(&function.params)?.join(", ");
+
+    let is_static = function.prefix.split_whitespace().any(|w| w == "static");
+    let return_type: Vec<&str> = function.prefix.split_whitespace()
+        .filter(|w| !matches!(*w, "static" | "inline" | "__inline" | "__STATIC_INLINE" | "extern"))
+        .collect();
+    let return_type = return_type.join(" ");
+    if return_type.is_empty() {
+        return None;
+    }
+    let storage = if is_static { "static " } else { "" };
+    let body_name = format!("{}_wcet_body", name);
+
+    let enable_block = if enable.is_empty() {
+        String::new()
+    } else {
+        format!("    static int wcet_counter_enabled;\n    if (!wcet_counter_enabled) {{\n{enable}        wcet_counter_enabled = 1;\n    }}\n")
+    };
+    let (call, ret) = if return_type == "void" {
+        (format!("    {}({});\n", body_name, args), String::new())
+    } else {
+        (format!("    {} result = {}({});\n", return_type, body_name, args), "    return result;\n".to_string())
+    };
+    let wrapper = format!(r#"
+/* WCET instrumentation: {name}_wcet_max holds the longest call seen, in cycles */
+volatile uint32_t {name}_wcet_last;
+volatile uint32_t {name}_wcet_max;
+
+{storage}{return_type} {name}({params}) {{
+{enable_block}    uint32_t wcet_start = {read};
+{call}    uint32_t wcet_cycles = {read} - wcet_start;
+    {name}_wcet_last = wcet_cycles;
+    if (wcet_cycles > {name}_wcet_max) {{
+        {name}_wcet_max = wcet_cycles;
+    }}
+{ret}}}
+"#,
+        params = if function.params.is_empty() { "void" } else { &function.params },
+    );
+
+    let mut out = String::new();
+    out.push_str(include);
+    for (i, line) in lines.iter().enumerate() {
+        if i == function.line {
+            // The original body becomes a static helper behind the wrapper
+            let renamed = line.replacen(&format!("{}(", name), &format!("{}(", body_name), 1);
+            if is_static {
+                out.push_str(&renamed);
+            } else {
+                out.push_str("static ");
+                out.push_str(&renamed);
+            }
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+        if i == function.end {
+            out.push_str(&wrapper);
+        }
+    }
+    Some(out)
+}
+
+/// Estimate the worst-case execution time of `function_name` in `code`
+///
+/// The static estimate uses the same per-core instruction timing as the
+/// interrupt latency model, with loops multiplied out by their literal bounds.
+/// The instrumented code measures the real figure on target via the cycle
+/// counter, which is the number to trust once hardware is available.
+/// Fails when a loop header is malformed or the estimate overflows a `u32`.
+pub fn estimate_wcet(code: &str, function_name: &str, mcu: McuFamily) -> Result<WcetReport, String> {
+    let lines: Vec<&str> = code.lines().collect();
+    let mut notes = Vec::new();
+    let mut uncertainty = base_uncertainty(mcu);
+
+    let Some(function) = find_function(&lines, function_name) else {
+        return Ok(WcetReport {
+            instrumented_code: code.to_string(),
+            estimated_cycles: None,
+            uncertainty_percent: 100.0,
+            timing_notes: vec![format!("No definition of {}() found", function_name)],
+        });
+    };
+
+    let (timing, _) = core_timing(mcu);
+    let body = &lines[function.body.clone()];
+    let (cycles, unbounded) = body_cycles(body, &timing).map_err(|e| format!("{}: {}", function_name, e))?;
+    let estimated_cycles = if unbounded {
+        notes.push("Loop bound is not a literal constant; no static estimate (measure on target)".to_string());
+        None
+    } else {
+        // Call and return of the function itself
+        Some(cycles.saturating_add(timing.call + timing.branch))
+    };
+
+    let calls: u32 = body.iter().map(|l| function_calls(strip_comment(l))).sum();
+    if calls > 0 {
+        notes.push(format!("{} call(s) to other functions counted as call overhead only", calls));
+        uncertainty += 10.0;
+    }
+    if body.iter().any(|l| is_float_line(l)) && !mcu.has_fpu() {
+        notes.push("Floating point runs in software on this core; library routines vary with operands".to_string());
+        uncertainty += 15.0;
+    }
+
+    match mcu {
+        McuFamily::STM32H7 => {
+            notes.push("Cortex-M7 I/D caches: a cold-cache call can take several times the warm figure; measure after a cache flush for the worst case".to_string());
+            notes.push("Cortex-M7 branch prediction and dual issue make short loops faster than modelled once warmed up".to_string());
+        }
+        McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3 => {
+            notes.push("Code runs from external flash through a cache; a miss costs tens of cycles, so keep time-critical code in IRAM".to_string());
+        }
+        McuFamily::STM32F4 | McuFamily::STM32G4 | McuFamily::STM32L4 => {
+            notes.push("Flash wait states are hidden by the ART accelerator/prefetch only on sequential code; taken branches may stall".to_string());
+        }
+        _ => {}
+    }
+    if mcu == McuFamily::RP2040 {
+        notes.push("Cortex-M0+ has no DWT cycle counter; time the function with SysTick or a hardware timer".to_string());
+    }
+    notes.push("Interrupts taken during the call are included in the measured cycles; mask them to isolate the function".to_string());
+
+    let instrumented_code = match instrument(&lines, function_name, &function, mcu) {
+        Some(code) => {
+            notes.push("Measured cycles include a few cycles of wrapper call overhead".to_string());
+            code
+        }
+        None => {
+            if cycle_counter(mcu).is_some() {
+                notes.push("Signature could not be wrapped (variadic or unparsed); code left unchanged".to_string());
+            }
+            code.to_string()
+        }
+    };
+
+    Ok(WcetReport {
+        instrumented_code,
+        estimated_cycles,
+        uncertainty_percent: uncertainty.min(100.0),
+        timing_notes: notes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODE: &str = r#"#include <stdint.h>
+
+static uint32_t checksum(const uint8_t *data, uint32_t len) {
+    uint32_t sum = 0;
+    for (int i = 0; i < 16; i++) {
+        sum += data[i];
+    }
+    return sum;
+}
+
+void drain(volatile uint32_t *fifo) {
+    while (*fifo != 0) {
+        *fifo = *fifo - 1;
+    }
+}
+
+int main(void) {
+    uint8_t buf[16];
+    return checksum(buf, 16);
+}
+"#;
+
+    #[test]
+    fn test_estimate_wcet() {
+        let report = estimate_wcet(CODE, "checksum", McuFamily::STM32F4).unwrap();
+        let cycles = report.estimated_cycles.unwrap();
+        assert!(cycles > 16 * 4, "loop should dominate: {}", cycles);
+        assert_eq!(report.uncertainty_percent, 20.0);
+
+        let code = &report.instrumented_code;
+        assert!(code.contains("static uint32_t checksum_wcet_body(const uint8_t *data, uint32_t len) {"));
+        assert!(code.contains("static uint32_t checksum(const uint8_t *data, uint32_t len) {"));
+        assert!(code.contains("    uint32_t result = checksum_wcet_body(data, len);"));
+        assert!(code.contains("DWT_CTRL_CYCCNTENA_Msk"));
+        // Calls elsewhere are left alone and reach the wrapper
+        assert!(code.contains("    return checksum(buf, 16);"));
+
+        // The M7 needs the DWT unlocked and carries cache caveats
+        let h7 = estimate_wcet(CODE, "checksum", McuFamily::STM32H7).unwrap();
+        assert!(h7.instrumented_code.contains("DWT->LAR = 0xC5ACCE55;"));
+        assert!(h7.uncertainty_percent > report.uncertainty_percent);
+        assert!(h7.timing_notes.iter().any(|n| n.contains("cache")));
+    }
+
+    #[test]
+    fn test_wcet_unbounded_and_missing() {
+        let report = estimate_wcet(CODE, "drain", McuFamily::STM32F4).unwrap();
+        assert!(report.estimated_cycles.is_none());
+        assert!(report.instrumented_code.contains("static void drain_wcet_body(volatile uint32_t *fifo) {"));
+        assert!(report.instrumented_code.contains("    drain_wcet_body(fifo);\n    uint32_t wcet_cycles"));
+
+        let m0 = estimate_wcet(CODE, "checksum", McuFamily::RP2040).unwrap();
+        assert_eq!(m0.instrumented_code, CODE);
+        assert!(m0.estimated_cycles.is_some());
+
+        let missing = estimate_wcet(CODE, "check", McuFamily::STM32F4).unwrap();
+        assert!(missing.estimated_cycles.is_none());
+        assert_eq!(missing.instrumented_code, CODE);
+    }
+
+    #[test]
+    fn test_param_names() {
+        assert_eq!(param_names("void"), Some(vec![]));
+        assert_eq!(param_names("const uint8_t *data, size_t len"), Some(vec!["data".to_string(), "len".to_string()]));
+        assert_eq!(param_names("int16_t samples[64]"), Some(vec!["samples".to_string()]));
+        assert_eq!(param_names("const char *fmt, ..."), None);
+    }
+}
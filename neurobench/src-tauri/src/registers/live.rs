@@ -0,0 +1,221 @@
+// Live Register Access
+// Reads, writes and watches peripheral registers on the connected target
+
+use super::{find_peripheral, Register};
+use crate::jobs::{CancelReason, EmitterMessage, InternalErrorCode, JobEmitter, JobKind, JobManager, JobRecord, JobTerminal};
+use crate::toolchain::probe::ProbeManager;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Fastest polling interval accepted by `run_watch_job`
+pub const MIN_POLL_MS: u64 = 10;
+
+/// Decoded value of one bit field
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldReading {
+    pub name: String,
+    pub value: u32,
+    pub label: Option<String>,
+}
+
+/// Register definition by peripheral and name, SVD first then the built-in maps
+pub fn find_register(peripheral: &str, register: &str, mcu_family: Option<&str>) -> Result<Register, String> {
+    let map = find_peripheral(peripheral, mcu_family)
+        .ok_or_else(|| format!("Unknown peripheral: {}", peripheral))?;
+    map.registers.into_iter()
+        .find(|r| r.name.eq_ignore_ascii_case(register))
+        .ok_or_else(|| format!("{} has no register {}", peripheral, register))
+}
+
+/// Refuse writes to read-only registers and to read-only fields under `mask`
+pub fn check_writable(register: &Register, mask: u32) -> Result<(), String> {
+    if register.access == "r" {
+        return Err(format!("{} is read-only", register.name));
+    }
+    let read_only: Vec<&str> = register.fields.iter()
+        .filter(|f| f.access == "r" && f.mask().is_some_and(|m| m & mask != 0))
+        .map(|f| f.name.as_str())
+        .collect();
+    if !read_only.is_empty() {
+        return Err(format!("{} has read-only fields under the write mask: {}", register.name, read_only.join(", ")));
+    }
+    Ok(())
+}
+
+fn register_bytes(register: &Register) -> usize {
+    (register.size as usize / 8).clamp(1, 4)
+}
+
+/// Current value of a register on the target
+pub async fn read_register(probe: &ProbeManager, register: &Register) -> Result<u32, String> {
+    let bytes = probe.read_memory(register.address, register_bytes(register)).await
+        .map_err(|e| e.to_string())?;
+    Ok(bytes.iter().rev().fold(0u32, |acc, b| (acc << 8) | *b as u32))
+}
+
+/// Read-modify-write of the bits in `mask`, returning the value written
+pub async fn write_register(probe: &mut ProbeManager, register: &Register, value: u32, mask: u32) -> Result<u32, String> {
+    check_writable(register, mask)?;
+    let current = read_register(probe, register).await?;
+    let updated = (current & !mask) | (value & mask);
+    let bytes = updated.to_le_bytes();
+    probe.write_memory(register.address, &bytes[..register_bytes(register)]).await
+        .map_err(|e| e.to_string())?;
+    log::info!("{} @ 0x{:08X}: 0x{:08X} -> 0x{:08X}", register.name, register.address, current, updated);
+    Ok(updated)
+}
+
+/// Split a register value into its fields, naming enumerated values
+pub fn decode_fields(register: &Register, value: u32) -> Vec<FieldReading> {
+    register.fields.iter()
+        .map(|f| {
+            let field_value = f.mask().map_or(0, |m| (value & m) >> f.bit_offset);
+            FieldReading {
+                name: f.name.clone(),
+                value: field_value,
+                label: f.values.iter().find(|v| v.value == field_value).map(|v| v.name.clone()),
+            }
+        })
+        .collect()
+}
+
+// ==================== Watch Job ====================
+
+/// Poll a register as a job, emitting `register:changed` whenever its value changes
+pub async fn run_watch_job(
+    job_manager: Arc<JobManager>,
+    probe: &'static Mutex<ProbeManager>,
+    peripheral: String,
+    register: Register,
+    poll_ms: u64,
+    emit_event: impl Fn(String, serde_json::Value) + Send + Sync + 'static,
+) -> Result<String, String> {
+    // Fail before creating the job when the probe cannot read
+    read_register(&*probe.lock().await, &register).await?;
+
+    let (record, _tx) = job_manager.create_job(JobKind::RegisterWatch);
+    let job_id = record.id.clone();
+    let interval = Duration::from_millis(poll_ms.max(MIN_POLL_MS));
+
+    tokio::spawn(async move {
+        watch_worker(record, probe, peripheral, register, interval, job_manager, emit_event).await;
+    });
+
+    Ok(job_id)
+}
+
+async fn watch_worker(
+    record: Arc<JobRecord>,
+    probe: &'static Mutex<ProbeManager>,
+    peripheral: String,
+    register: Register,
+    interval: Duration,
+    job_manager: Arc<JobManager>,
+    emit_event: impl Fn(String, serde_json::Value) + Send + Sync,
+) {
+    let mut emitter = JobEmitter::new(&record);
+    let start = std::time::Instant::now();
+    let mut last: Option<u32> = None;
+
+    let terminal = loop {
+        let reading = read_register(&*probe.lock().await, &register).await;
+        match reading {
+            Ok(value) if last != Some(value) => {
+                let payload = serde_json::json!({
+                    "type": "changed",
+                    "peripheral": peripheral,
+                    "register": register.name,
+                    "address": register.address,
+                    "old_value": last,
+                    "value": value,
+                    "fields": decode_fields(&register, value),
+                });
+                if let Some((event_name, payload)) = emitter.process(EmitterMessage::Custom {
+                    event_suffix: "changed".to_string(),
+                    payload,
+                }).await {
+                    emit_event(event_name, payload);
+                }
+                last = Some(value);
+            }
+            Ok(_) => {}
+            Err(message) => break JobTerminal::InternalError {
+                error_code: InternalErrorCode::ProbeConnectionFailed,
+                message,
+                retryable: true,
+            },
+        }
+
+        tokio::select! {
+            _ = record.cancel_token.cancelled() => {
+                break JobTerminal::Cancelled { reason: CancelReason::UserRequest };
+            }
+            _ = tokio::time::sleep(interval) => {}
+        }
+    };
+
+    log::info!("Stopped watching {}->{} after {} ms", peripheral, register.name, start.elapsed().as_millis());
+    if let Some((event_name, payload)) = emitter.process(EmitterMessage::Terminal { terminal }).await {
+        emit_event(event_name, payload);
+    }
+    job_manager.finish_job(&record.id).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toolchain::probe::ProbeConfig;
+
+    async fn connected_probe() -> &'static Mutex<ProbeManager> {
+        let mut manager = ProbeManager::new();
+        manager.connect(ProbeConfig::default()).await.unwrap();
+        Box::leak(Box::new(Mutex::new(manager)))
+    }
+
+    #[tokio::test]
+    async fn test_read_modify_write() {
+        let probe = connected_probe().await;
+        let moder = find_register("GPIOA", "moder", None).unwrap();
+        let mut manager = probe.lock().await;
+
+        write_register(&mut manager, &moder, 0xFFFF_FFFF, 0x0000_0C00).await.unwrap();
+        let value = write_register(&mut manager, &moder, 0x0000_0000, 0x0000_0800).await.unwrap();
+        assert_eq!(value, 0x0000_0400);
+        assert_eq!(read_register(&manager, &moder).await.unwrap(), 0x0000_0400);
+        let fields = decode_fields(&moder, value);
+        assert_eq!(fields[5].label.as_deref(), Some("Output"));
+
+        let idr = find_register("GPIOA", "IDR", None).unwrap();
+        assert!(write_register(&mut manager, &idr, 1, 1).await.unwrap_err().contains("read-only"));
+        let cr = find_register("RCC", "CR", None).unwrap();
+        assert!(check_writable(&cr, 1 << 1).unwrap_err().contains("HSIRDY"));
+        assert!(check_writable(&cr, 1 << 16).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_watch_emits_changes() {
+        let probe = connected_probe().await;
+        let odr = find_register("GPIOA", "ODR", None).unwrap();
+        let job_manager = Arc::new(JobManager::new());
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+
+        let job_id = run_watch_job(job_manager.clone(), probe, "GPIOA".to_string(), odr.clone(), 10, move |name, payload| {
+            sink.lock().unwrap().push((name, payload));
+        }).await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        write_register(&mut *probe.lock().await, &odr, 1 << 13, 1 << 13).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(job_manager.cancel_job(&job_id));
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        let events = events.lock().unwrap();
+        let changes: Vec<_> = events.iter().filter(|(name, _)| name == "register:changed").collect();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[1].1["value"], 1 << 13);
+        assert_eq!(changes[1].1["old_value"], 0);
+    }
+}
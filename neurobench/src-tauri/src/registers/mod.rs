@@ -4,6 +4,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+pub mod live;
+pub mod svd;
+
 /// Register definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Register {
@@ -11,10 +14,16 @@ pub struct Register {
     pub address: u32,
     pub size: u8,  // bits: 8, 16, 32
     pub reset_value: u32,
+    #[serde(default = "default_access")]
+    pub access: String,  // r, w, rw
     pub description: String,
     pub fields: Vec<RegisterField>,
 }
 
+fn default_access() -> String {
+    "rw".to_string()
+}
+
 /// Register field (bit field)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RegisterField {
@@ -26,6 +35,17 @@ pub struct RegisterField {
     pub values: Vec<FieldValue>,
 }
 
+impl RegisterField {
+    /// Mask of the field in a 32-bit register, `None` when it does not fit
+    pub fn mask(&self) -> Option<u32> {
+        let end = u32::from(self.bit_offset) + u32::from(self.bit_width);
+        if self.bit_width == 0 || end > 32 {
+            return None;
+        }
+        Some((u32::MAX >> (32 - u32::from(self.bit_width))) << self.bit_offset)
+    }
+}
+
 /// Possible field value
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FieldValue {
@@ -64,6 +84,7 @@ pub fn get_gpio_registers(port: char) -> Peripheral {
                 address: base,
                 size: 32,
                 reset_value: 0x0000_0000,
+                access: "rw".to_string(),
                 description: "GPIO port mode register".to_string(),
                 fields: (0..16).map(|i| RegisterField {
                     name: format!("MODER{}", i),
@@ -84,6 +105,7 @@ pub fn get_gpio_registers(port: char) -> Peripheral {
                 address: base + 0x14,
                 size: 32,
                 reset_value: 0x0000_0000,
+                access: "rw".to_string(),
                 description: "GPIO port output data register".to_string(),
                 fields: (0..16).map(|i| RegisterField {
                     name: format!("ODR{}", i),
@@ -102,6 +124,7 @@ pub fn get_gpio_registers(port: char) -> Peripheral {
                 address: base + 0x10,
                 size: 32,
                 reset_value: 0x0000_0000,
+                access: "r".to_string(),
                 description: "GPIO port input data register".to_string(),
                 fields: (0..16).map(|i| RegisterField {
                     name: format!("IDR{}", i),
@@ -120,6 +143,7 @@ pub fn get_gpio_registers(port: char) -> Peripheral {
                 address: base + 0x0C,
                 size: 32,
                 reset_value: 0x0000_0000,
+                access: "rw".to_string(),
                 description: "GPIO port pull-up/pull-down register".to_string(),
                 fields: (0..16).map(|i| RegisterField {
                     name: format!("PUPDR{}", i),
@@ -152,6 +176,7 @@ pub fn get_rcc_registers() -> Peripheral {
                 address: base,
                 size: 32,
                 reset_value: 0x0000_0083,
+                access: "rw".to_string(),
                 description: "Clock control register".to_string(),
                 fields: vec![
                     RegisterField {
@@ -205,6 +230,7 @@ pub fn get_rcc_registers() -> Peripheral {
                 address: base + 0x30,
                 size: 32,
                 reset_value: 0x0010_0000,
+                access: "rw".to_string(),
                 description: "AHB1 peripheral clock enable register".to_string(),
                 fields: vec![
                     RegisterField {
@@ -272,6 +298,81 @@ pub fn generate_register_code(peripheral: &str, reg: &str, operation: &str, valu
     }
 }
 
+/// Peripheral by name from the SVD loaded for `mcu_family`, else the built-in maps
+pub fn find_peripheral(name: &str, mcu_family: Option<&str>) -> Option<Peripheral> {
+    let from_svd = match mcu_family {
+        Some(family) => svd::cached_svd(family).and_then(|map| map.peripheral(name).cloned()),
+        None => svd::cached_maps().iter().find_map(|map| map.peripheral(name).cloned()),
+    };
+    from_svd.or_else(|| get_peripherals().into_iter().find(|p| p.name.eq_ignore_ascii_case(name)))
+}
+
+/// Generate masked read/write code for one bit field
+pub fn generate_field_code(
+    peripheral: &Peripheral,
+    reg: &str,
+    field: &str,
+    operation: &str,
+    value: Option<u32>,
+) -> Result<String, String> {
+    let register = peripheral.registers.iter()
+        .find(|r| r.name.eq_ignore_ascii_case(reg))
+        .ok_or_else(|| format!("{} has no register {}", peripheral.name, reg))?;
+    let bit_field = register.fields.iter()
+        .find(|f| f.name.eq_ignore_ascii_case(field))
+        .ok_or_else(|| format!("{}->{} has no field {}", peripheral.name, register.name, field))?;
+
+    let target = format!("{}->{}", peripheral.name, register.name);
+    let pos = bit_field.bit_offset;
+    let mask = bit_field.mask().ok_or_else(|| format!(
+        "{} (offset {}, width {}) does not fit in a 32-bit register",
+        bit_field.name, pos, bit_field.bit_width,
+    ))?;
+    let prefix = format!("{}_{}_{}", peripheral.name, register.name, bit_field.name);
+    let defines = format!(
+        "#define {prefix}_Pos  ({pos}U)
+#define {prefix}_Msk  (0x{mask:08X}U)
+",
+        prefix = prefix,
+        pos = pos,
+        mask = mask,
+    );
+
+    let code = match operation {
+        "read" => {
+            if bit_field.access == "w" {
+                return Err(format!("{} is write-only", bit_field.name));
+            }
+            format!("uint32_t {} = ({} & {p}_Msk) >> {p}_Pos;
+", bit_field.name.to_lowercase(), target, p = prefix)
+        }
+        "write" | "clear" => {
+            if bit_field.access == "r" {
+                return Err(format!("{} is read-only", bit_field.name));
+            }
+            let value = if operation == "clear" { 0 } else { value.ok_or("write needs a value")? };
+            let max = mask >> pos;
+            if value > max {
+                return Err(format!("{} does not fit in {} ({} bits)", value, bit_field.name, bit_field.bit_width));
+            }
+            let label = bit_field.values.iter()
+                .find(|v| v.value == value)
+                .map(|v| format!("  // {}", v.name))
+                .unwrap_or_default();
+            format!(
+                "{t} = ({t} & ~{p}_Msk) | (({v}U << {p}_Pos) & {p}_Msk);{label}\n",
+                t = target,
+                p = prefix,
+                v = value,
+                label = label,
+            )
+        }
+        _ => return Err(format!("Unknown field operation: {}", operation)),
+    };
+
+    Ok(defines + &code)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -289,4 +390,21 @@ mod tests {
         assert!(code.contains("GPIOA->ODR"));
         assert!(code.contains("13"));
     }
+
+    #[test]
+    fn test_generate_field_code() {
+        let gpio = get_gpio_registers('A');
+        let code = generate_field_code(&gpio, "MODER", "MODER5", "write", Some(1)).unwrap();
+        assert!(code.contains("#define GPIOA_MODER_MODER5_Msk  (0x00000C00U)"));
+        assert!(code.contains("GPIOA->MODER = (GPIOA->MODER & ~GPIOA_MODER_MODER5_Msk) | ((1U << GPIOA_MODER_MODER5_Pos) & GPIOA_MODER_MODER5_Msk);  // Output"));
+
+        assert!(generate_field_code(&gpio, "MODER", "MODER5", "write", Some(4)).is_err());
+        assert!(generate_field_code(&gpio, "IDR", "IDR0", "write", Some(1)).is_err());
+        assert!(generate_field_code(&gpio, "IDR", "IDR0", "read", None).unwrap().contains(">> GPIOA_IDR_IDR0_Pos"));
+
+        let mut wide = gpio.clone();
+        wide.registers[0].fields[0].bit_offset = 40;
+        let name = wide.registers[0].fields[0].name.clone();
+        assert!(generate_field_code(&wide, &wide.registers[0].name.clone(), &name, "read", None).is_err());
+    }
 }
@@ -0,0 +1,487 @@
+// CMSIS-SVD Import
+// Parses vendor SVD files into complete peripheral register maps
+
+use super::{FieldValue, Peripheral, Register, RegisterField};
+use roxmltree::{Document, Node};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use thiserror::Error;
+
+/// SVD import errors
+#[derive(Debug, Error)]
+pub enum SvdError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("XML error: {0}")]
+    Xml(#[from] roxmltree::Error),
+
+    #[error("Missing <{element}> in {context}")]
+    MissingElement { element: String, context: String },
+
+    #[error("Invalid number '{value}' in {context}")]
+    InvalidNumber { value: String, context: String },
+
+    #[error("Bits {range} of {context} do not fit in a {size}-bit register")]
+    InvalidBitRange { range: String, context: String, size: u8 },
+
+    #[error("Peripheral {peripheral} is derived from unknown peripheral {base}")]
+    UnknownBase { peripheral: String, base: String },
+
+    #[error("Address of {context} does not fit in 32 bits")]
+    AddressOverflow { context: String },
+}
+
+lazy_static::lazy_static! {
+    /// Parsed SVD files keyed by uppercase MCU family
+    static ref SVD_CACHE: Mutex<HashMap<String, SvdPeripheralMap>> = Mutex::new(HashMap::new());
+}
+
+/// Register map of one device loaded from an SVD file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SvdPeripheralMap {
+    pub device: String,
+    pub series: Option<String>,
+    pub description: String,
+    pub cpu: Option<String>,
+    pub peripherals: Vec<Peripheral>,
+}
+
+impl SvdPeripheralMap {
+    /// Family the map is cached under: the SVD `<series>`, else the device name
+    pub fn family(&self) -> String {
+        self.series.clone().unwrap_or_else(|| self.device.clone()).to_uppercase()
+    }
+
+    pub fn peripheral(&self, name: &str) -> Option<&Peripheral> {
+        self.peripherals.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Size, reset value and access inherited from device to peripheral to register
+#[derive(Debug, Clone)]
+struct RegisterProperties {
+    size: u8,
+    reset_value: u32,
+    access: String,
+}
+
+impl Default for RegisterProperties {
+    fn default() -> Self {
+        Self { size: 32, reset_value: 0, access: "rw".to_string() }
+    }
+}
+
+impl RegisterProperties {
+    fn inherit(&self, node: Node, context: &str) -> Result<Self, SvdError> {
+        Ok(Self {
+            size: match child_text(node, "size") {
+                Some(size) => parse_number(size, context)? as u8,
+                None => self.size,
+            },
+            reset_value: match child_text(node, "resetValue") {
+                Some(value) => parse_number(value, context)? as u32,
+                None => self.reset_value,
+            },
+            access: child_text(node, "access").map(access_code).unwrap_or(&self.access).to_string(),
+        })
+    }
+}
+
+/// Parse an SVD file
+pub fn load_svd(path: &Path) -> Result<SvdPeripheralMap, SvdError> {
+    parse_svd(&std::fs::read_to_string(path)?)
+}
+
+/// Parse SVD XML
+pub fn parse_svd(xml: &str) -> Result<SvdPeripheralMap, SvdError> {
+    let doc = Document::parse(xml)?;
+    let device = doc.root_element();
+    let device_name = required_text(device, "name", "device")?.to_string();
+    let defaults = RegisterProperties::default().inherit(device, &device_name)?;
+
+    let mut peripherals = Vec::new();
+    let mut derived = Vec::new();
+    if let Some(list) = child(device, "peripherals") {
+        for node in children(list, "peripheral") {
+            let peripheral = parse_peripheral(node, &defaults)?;
+            if let Some(base) = node.attribute("derivedFrom") {
+                derived.push((peripherals.len(), base.to_string()));
+            }
+            peripherals.push(peripheral);
+        }
+    }
+
+    // Derived peripherals reuse the base's registers at their own address
+    for (index, base_name) in derived {
+        let base = peripherals.iter()
+            .find(|p| p.name == base_name)
+            .cloned()
+            .ok_or_else(|| SvdError::UnknownBase { peripheral: peripherals[index].name.clone(), base: base_name })?;
+        let peripheral = &mut peripherals[index];
+        if peripheral.registers.is_empty() {
+            peripheral.registers = base.registers.into_iter()
+                .map(|mut reg| {
+                    reg.address = (reg.address - base.base_address)
+                        .checked_add(peripheral.base_address)
+                        .ok_or_else(|| address_overflow(&format!("{}{}", peripheral.name, reg.name)))?;
+                    Ok(reg)
+                })
+                .collect::<Result<_, SvdError>>()?;
+        }
+        if peripheral.description.is_empty() {
+            peripheral.description = base.description;
+        }
+    }
+
+    Ok(SvdPeripheralMap {
+        series: child_text(device, "series").map(str::to_string),
+        description: child_text(device, "description").map(clean_text).unwrap_or_default(),
+        cpu: child(device, "cpu").and_then(|cpu| child_text(cpu, "name")).map(str::to_string),
+        device: device_name,
+        peripherals,
+    })
+}
+
+fn parse_peripheral(node: Node, defaults: &RegisterProperties) -> Result<Peripheral, SvdError> {
+    let name = required_text(node, "name", "peripheral")?.to_string();
+    let base_address = parse_address(required_text(node, "baseAddress", &name)?, &name)?;
+    let properties = defaults.inherit(node, &name)?;
+
+    let mut registers = Vec::new();
+    if let Some(list) = child(node, "registers") {
+        collect_registers(list, base_address, "", &properties, &mut registers)?;
+    }
+
+    Ok(Peripheral {
+        description: child_text(node, "description").map(clean_text).unwrap_or_default(),
+        name,
+        base_address,
+        registers,
+    })
+}
+
+/// Registers of a `<registers>` or `<cluster>` block, clusters flattened as `CLUSTER_REG`
+fn collect_registers(
+    parent: Node,
+    base_address: u32,
+    prefix: &str,
+    defaults: &RegisterProperties,
+    registers: &mut Vec<Register>,
+) -> Result<(), SvdError> {
+    for node in parent.children().filter(Node::is_element) {
+        let is_cluster = node.has_tag_name("cluster");
+        if !is_cluster && !node.has_tag_name("register") {
+            continue;
+        }
+
+        let name = required_text(node, "name", "register")?;
+        let context = format!("{}{}", prefix, name);
+        let offset = parse_address(required_text(node, "addressOffset", &context)?, &context)?;
+        let properties = defaults.inherit(node, &context)?;
+
+        for (instance, increment) in expand_dim(node, name, &context)? {
+            let address = base_address.checked_add(offset)
+                .and_then(|a| a.checked_add(increment))
+                .ok_or_else(|| address_overflow(&context))?;
+            let full_name = format!("{}{}", prefix, instance);
+            if is_cluster {
+                collect_registers(node, address, &format!("{}_", full_name), &properties, registers)?;
+                continue;
+            }
+
+            let mut fields = Vec::new();
+            if let Some(list) = child(node, "fields") {
+                for field in children(list, "field") {
+                    fields.push(parse_field(field, &properties, &full_name)?);
+                }
+            }
+            fields.sort_by_key(|f| f.bit_offset);
+
+            registers.push(Register {
+                name: full_name,
+                address,
+                size: properties.size,
+                reset_value: properties.reset_value,
+                access: properties.access.clone(),
+                description: child_text(node, "description").map(clean_text).unwrap_or_default(),
+                fields,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Instance names and address increments for `<dim>` arrays, or the element itself
+fn expand_dim(node: Node, name: &str, context: &str) -> Result<Vec<(String, u32)>, SvdError> {
+    let Some(dim) = child_text(node, "dim") else {
+        return Ok(vec![(name.to_string(), 0)]);
+    };
+    let count = parse_number(dim, context)? as u32;
+    let increment = parse_address(required_text(node, "dimIncrement", context)?, context)?;
+
+    let indices: Vec<String> = match child_text(node, "dimIndex") {
+        Some(list) => match list.split_once('-') {
+            Some((start, end)) if !list.contains(',') => {
+                match (start.trim().parse::<u32>(), end.trim().parse::<u32>()) {
+                    (Ok(start), Ok(end)) => (start..=end).map(|i| i.to_string()).collect(),
+                    // Letter ranges such as A-E
+                    _ => (start.trim().bytes().next().unwrap_or(b'A')..=end.trim().bytes().next().unwrap_or(b'A'))
+                        .map(|c| (c as char).to_string())
+                        .collect(),
+                }
+            }
+            _ => list.split(',').map(|s| s.trim().to_string()).collect(),
+        },
+        None => (0..count).map(|i| i.to_string()).collect(),
+    };
+
+    indices.into_iter()
+        .take(count as usize)
+        .enumerate()
+        .map(|(i, index)| {
+            let offset = (i as u32).checked_mul(increment).ok_or_else(|| address_overflow(context))?;
+            Ok((name.replace("[%s]", &index).replace("%s", &index), offset))
+        })
+        .collect()
+}
+
+fn parse_field(node: Node, register_properties: &RegisterProperties, register: &str) -> Result<RegisterField, SvdError> {
+    let name = required_text(node, "name", register)?.to_string();
+    let context = format!("{}.{}", register, name);
+    let size = register_properties.size;
+    let bad_range = |range: String| SvdError::InvalidBitRange { range, context: context.clone(), size };
+    // Width of msb..=lsb, rejecting msb < lsb
+    let span = |lsb: u64, msb: u64| msb.checked_sub(lsb).map(|d| (lsb, d + 1)).ok_or_else(|| bad_range(format!("[{}:{}]", msb, lsb)));
+
+    let (bit_offset, bit_width) = if let Some(offset) = child_text(node, "bitOffset") {
+        let width = child_text(node, "bitWidth").unwrap_or("1");
+        (parse_number(offset, &context)?, parse_number(width, &context)?)
+    } else if let (Some(lsb), Some(msb)) = (child_text(node, "lsb"), child_text(node, "msb")) {
+        span(parse_number(lsb, &context)?, parse_number(msb, &context)?)?
+    } else {
+        // [msb:lsb]
+        let range = required_text(node, "bitRange", &context)?;
+        let (msb, lsb) = range.trim_matches(|c| c == '[' || c == ']')
+            .split_once(':')
+            .ok_or_else(|| SvdError::InvalidNumber { value: range.to_string(), context: context.clone() })?;
+        span(parse_number(lsb, &context)?, parse_number(msb, &context)?)?
+    };
+    // Everything downstream shifts by offset and width, so the field must fit the register
+    match bit_offset.checked_add(bit_width) {
+        Some(end) if bit_width > 0 && end <= u64::from(size) => {}
+        _ => return Err(bad_range(format!("{}+{}", bit_offset, bit_width))),
+    }
+
+    let mut values = Vec::new();
+    for list in children(node, "enumeratedValues") {
+        for value in children(list, "enumeratedValue") {
+            // `isDefault` entries and don't-care patterns have no single value
+            let Some(Ok(number)) = child_text(value, "value").map(|v| parse_number(v, &context)) else { continue };
+            values.push(FieldValue {
+                value: number as u32,
+                name: child_text(value, "name").unwrap_or_default().to_string(),
+                description: child_text(value, "description").map(clean_text).unwrap_or_default(),
+            });
+        }
+    }
+
+    Ok(RegisterField {
+        access: child_text(node, "access").map(access_code).unwrap_or(&register_properties.access).to_string(),
+        description: child_text(node, "description").map(clean_text).unwrap_or_default(),
+        name,
+        bit_offset: bit_offset as u8,
+        bit_width: bit_width as u8,
+        values,
+    })
+}
+
+// ==================== XML Helpers ====================
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+fn children<'a, 'input: 'a>(node: Node<'a, 'input>, name: &'a str) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |n| n.has_tag_name(name))
+}
+
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    child(node, name).and_then(|n| n.text()).map(str::trim)
+}
+
+fn required_text<'a>(node: Node<'a, '_>, name: &str, context: &str) -> Result<&'a str, SvdError> {
+    child_text(node, name).ok_or_else(|| SvdError::MissingElement {
+        element: name.to_string(),
+        context: context.to_string(),
+    })
+}
+
+/// SVD scaled non-negative integer: decimal, `0x` hex or `#` binary
+fn parse_number(text: &str, context: &str) -> Result<u64, SvdError> {
+    let text = text.trim();
+    let parsed = if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16)
+    } else if let Some(binary) = text.strip_prefix('#') {
+        u64::from_str_radix(binary, 2)
+    } else {
+        text.parse()
+    };
+    parsed.map_err(|_| SvdError::InvalidNumber { value: text.to_string(), context: context.to_string() })
+}
+
+/// A 32-bit address, offset or increment
+fn parse_address(text: &str, context: &str) -> Result<u32, SvdError> {
+    u32::try_from(parse_number(text, context)?).map_err(|_| address_overflow(context))
+}
+
+fn address_overflow(context: &str) -> SvdError {
+    SvdError::AddressOverflow { context: context.to_string() }
+}
+
+/// SVD access type as the `r`/`w`/`rw` used by the built-in maps
+fn access_code(access: &str) -> &'static str {
+    match access {
+        "read-only" => "r",
+        "write-only" | "writeOnce" => "w",
+        _ => "rw",
+    }
+}
+
+/// Collapse the line breaks and indentation vendors put inside descriptions
+fn clean_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// ==================== Cache ====================
+
+/// Parse an SVD file and cache it under its family, returning the family
+pub fn load_and_cache(path: &Path) -> Result<SvdPeripheralMap, SvdError> {
+    let map = load_svd(path)?;
+    log::info!("Loaded SVD for {} ({} peripherals)", map.device, map.peripherals.len());
+    SVD_CACHE.lock().unwrap().insert(map.family(), map.clone());
+    Ok(map)
+}
+
+/// Cached map for a family, matching prefixes either way (`STM32F4` / `STM32F407`)
+pub fn cached_svd(mcu_family: &str) -> Option<SvdPeripheralMap> {
+    let family = mcu_family.trim().to_uppercase();
+    let cache = SVD_CACHE.lock().unwrap();
+    cache.get(&family).cloned().or_else(|| {
+        cache.iter()
+            .find(|(key, map)| {
+                key.starts_with(&family) || family.starts_with(key.as_str()) || map.device.to_uppercase().starts_with(&family)
+            })
+            .map(|(_, map)| map.clone())
+    })
+}
+
+/// Every cached map
+pub fn cached_maps() -> Vec<SvdPeripheralMap> {
+    SVD_CACHE.lock().unwrap().values().cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SVD: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<device schemaVersion="1.1">
+  <name>STM32F407</name>
+  <series>STM32F4</series>
+  <description>STM32F407
+    Cortex-M4 MCU</description>
+  <cpu><name>CM4</name></cpu>
+  <size>32</size>
+  <resetValue>0x0</resetValue>
+  <peripherals>
+    <peripheral>
+      <name>GPIOA</name>
+      <baseAddress>0x40020000</baseAddress>
+      <registers>
+        <register>
+          <name>MODER</name>
+          <addressOffset>0x0</addressOffset>
+          <resetValue>0xA8000000</resetValue>
+          <fields>
+            <field>
+              <name>MODER1</name>
+              <bitOffset>2</bitOffset>
+              <bitWidth>2</bitWidth>
+              <enumeratedValues>
+                <enumeratedValue><name>Input</name><value>0</value></enumeratedValue>
+                <enumeratedValue><name>Output</name><value>#01</value></enumeratedValue>
+                <enumeratedValue><name>Other</name><isDefault>true</isDefault></enumeratedValue>
+              </enumeratedValues>
+            </field>
+            <field><name>MODER0</name><bitRange>[1:0]</bitRange></field>
+          </fields>
+        </register>
+        <register>
+          <dim>2</dim>
+          <dimIncrement>4</dimIncrement>
+          <name>AFR%s</name>
+          <addressOffset>0x20</addressOffset>
+          <access>read-only</access>
+        </register>
+      </registers>
+    </peripheral>
+    <peripheral derivedFrom="GPIOA">
+      <name>GPIOB</name>
+      <baseAddress>0x40020400</baseAddress>
+    </peripheral>
+  </peripherals>
+</device>"#;
+
+    #[test]
+    fn test_parse_svd() {
+        let map = parse_svd(SVD).unwrap();
+        assert_eq!(map.family(), "STM32F4");
+        assert_eq!(map.description, "STM32F407 Cortex-M4 MCU");
+        assert_eq!(map.cpu.as_deref(), Some("CM4"));
+
+        let gpioa = map.peripheral("gpioa").unwrap();
+        let moder = &gpioa.registers[0];
+        assert_eq!(moder.reset_value, 0xA800_0000);
+        assert_eq!(moder.fields[0].name, "MODER0");
+        assert_eq!((moder.fields[0].bit_offset, moder.fields[0].bit_width), (0, 2));
+        let moder1 = &moder.fields[1];
+        assert_eq!(moder1.values.len(), 2);
+        assert_eq!(moder1.values[1].value, 1);
+
+        let afr: Vec<_> = gpioa.registers[1..].iter().map(|r| (r.name.as_str(), r.address, r.access.as_str())).collect();
+        assert_eq!(afr, vec![("AFR0", 0x4002_0020, "r"), ("AFR1", 0x4002_0024, "r")]);
+
+        let gpiob = map.peripheral("GPIOB").unwrap();
+        assert_eq!(gpiob.registers.len(), 3);
+        assert_eq!(gpiob.registers[0].address, 0x4002_0400);
+    }
+
+    #[test]
+    fn test_svd_errors_and_cache() {
+        assert!(matches!(parse_svd("<device><peripherals/></device>"), Err(SvdError::MissingElement { .. })));
+        assert!(matches!(parse_number("0xZZ", "x"), Err(SvdError::InvalidNumber { .. })));
+        assert_eq!(parse_number("#101", "x").unwrap(), 5);
+        let field = |bits: &str| SVD.replacen("<bitRange>[1:0]</bitRange>", bits, 1);
+        assert!(matches!(parse_svd(&field("<lsb>4</lsb><msb>2</msb>")), Err(SvdError::InvalidBitRange { .. })));
+        assert!(matches!(parse_svd(&field("<bitRange>[3:5]</bitRange>")), Err(SvdError::InvalidBitRange { .. })));
+        assert!(matches!(parse_svd(&field("<bitOffset>40</bitOffset>")), Err(SvdError::InvalidBitRange { .. })));
+
+        // Addresses past 4 GiB are rejected rather than wrapped or truncated
+        let overflows = |from: &str, to: &str| matches!(parse_svd(&SVD.replacen(from, to, 1)), Err(SvdError::AddressOverflow { .. }));
+        assert!(overflows("0x40020000", "0x100000000"));
+        assert!(overflows("0x40020000", "0xFFFFFFF0"));
+        assert!(overflows("0x40020400", "0xFFFFFFF0"));
+        assert!(overflows("<dimIncrement>4</dimIncrement>", "<dimIncrement>0xFFFFFFFF</dimIncrement>"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("STM32F407.svd");
+        std::fs::write(&path, SVD).unwrap();
+        load_and_cache(&path).unwrap();
+        assert!(cached_svd("stm32f4").is_some());
+        assert!(cached_svd("STM32F407VG").is_some());
+        assert!(cached_svd("RP2040").is_none());
+    }
+}
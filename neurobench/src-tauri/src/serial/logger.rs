@@ -0,0 +1,439 @@
+// Serial Data Logger
+// Timestamped CSV/TSV/JSON logging of decoded serial frames
+
+use super::protocol::FieldDef;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+pub type SessionId = String;
+
+/// Longest time a decoded row may sit in the write buffer
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Largest frame a field layout may describe
+const MAX_FRAME_LEN: usize = 4096;
+
+/// Logger errors
+#[derive(Debug, Error)]
+pub enum LogError {
+    #[error("Unknown logging session: {0}")]
+    UnknownSession(String),
+
+    #[error("Frame too short: expected {expected} bytes, got {actual}")]
+    FrameTooShort { expected: usize, actual: usize },
+
+    #[error("Invalid field layout: {0}")]
+    InvalidLayout(String),
+
+    #[error("Serial port error: {0}")]
+    Port(String),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+/// Output file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LogFormat {
+    Csv,
+    Tsv,
+    Json,
+}
+
+impl LogFormat {
+    /// Guess the format from a file extension, defaulting to CSV
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+            Some("tsv") => LogFormat::Tsv,
+            Some("json") | Some("jsonl") | Some("ndjson") => LogFormat::Json,
+            _ => LogFormat::Csv,
+        }
+    }
+
+    fn separator(&self) -> &'static str {
+        match self {
+            LogFormat::Tsv => "\t",
+            _ => ",",
+        }
+    }
+
+    /// Quote a header cell holding the separator, quotes or line breaks (RFC 4180)
+    fn quote(&self, cell: &str) -> String {
+        if cell.contains(self.separator()) || cell.contains(['"', '\n', '\r']) {
+            format!("\"{}\"", cell.replace('"', "\"\""))
+        } else {
+            cell.to_string()
+        }
+    }
+}
+
+/// Summary returned when a session stops
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub session_id: SessionId,
+    pub sample_count: u64,
+    pub duration_secs: f64,
+    pub file_path: PathBuf,
+    pub file_size_bytes: u64,
+}
+
+struct LogSession {
+    writer: BufWriter<File>,
+    path: PathBuf,
+    format: LogFormat,
+    fields: Vec<FieldDef>,
+    frame_len: usize,
+    start_byte: Option<u8>,
+    started: Instant,
+    last_flush: Instant,
+    sample_count: u64,
+}
+
+impl LogSession {
+    fn flush_if_due(&mut self) -> std::io::Result<()> {
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.writer.flush()?;
+            self.last_flush = Instant::now();
+        }
+        Ok(())
+    }
+}
+
+/// Cuts a byte stream into fixed-size frames
+///
+/// With a start byte, each frame must begin with it; bytes before one are
+/// dropped, so a reader that joins mid-frame or loses a byte lines up again
+/// on the next frame. Returned frames exclude the start byte.
+pub struct FrameSync {
+    start_byte: Option<u8>,
+    frame_len: usize,
+    buffer: Vec<u8>,
+}
+
+impl FrameSync {
+    pub fn new(start_byte: Option<u8>, frame_len: usize) -> Self {
+        Self { start_byte, frame_len: frame_len.max(1), buffer: Vec::new() }
+    }
+
+    /// Feed received bytes, returning every frame they complete
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<Vec<u8>> {
+        let mut frames = Vec::new();
+        for &byte in bytes {
+            match self.start_byte {
+                Some(start) if self.buffer.is_empty() => {
+                    if byte == start {
+                        // Mark the frame as open; the marker is dropped once it is full
+                        self.buffer.push(byte);
+                    }
+                    continue;
+                }
+                _ => self.buffer.push(byte),
+            }
+
+            let header = self.start_byte.map_or(0, |_| 1);
+            if self.buffer.len() == header + self.frame_len {
+                frames.push(self.buffer.split_off(header));
+                self.buffer.clear();
+            }
+        }
+        frames
+    }
+}
+
+/// Manages concurrent logging sessions
+#[derive(Default)]
+pub struct SerialLogger {
+    sessions: HashMap<SessionId, LogSession>,
+}
+
+impl SerialLogger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create the log file and write the header row
+    ///
+    /// With `start_byte`, frames on the wire are that byte followed by the
+    /// field layout, and the reader resynchronises on it.
+    pub fn start_session(
+        &mut self,
+        path: &Path,
+        format: LogFormat,
+        fields: Vec<FieldDef>,
+        start_byte: Option<u8>,
+    ) -> Result<SessionId, LogError> {
+        let frame_len = layout_frame_len(&fields)?;
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        if format != LogFormat::Json {
+            let mut columns = vec!["timestamp".to_string(), "elapsed_ms".to_string()];
+            columns.extend(fields.iter().map(|f| {
+                let title = if f.unit.is_empty() { f.name.clone() } else { format!("{} ({})", f.name, f.unit) };
+                format.quote(&title)
+            }));
+            writeln!(writer, "{}", columns.join(format.separator()))?;
+            writer.flush()?;
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        self.sessions.insert(id.clone(), LogSession {
+            writer,
+            path: path.to_path_buf(),
+            format,
+            fields,
+            frame_len,
+            start_byte,
+            started: Instant::now(),
+            last_flush: Instant::now(),
+            sample_count: 0,
+        });
+
+        log::info!("Serial logging session {} started: {}", id, path.display());
+        Ok(id)
+    }
+
+    /// Decode one frame with the session field layout and append a row
+    pub fn append_line(&mut self, session_id: &str, data: &[u8]) -> Result<(), LogError> {
+        let session = self.sessions.get_mut(session_id)
+            .ok_or_else(|| LogError::UnknownSession(session_id.to_string()))?;
+
+        if data.len() < session.frame_len {
+            return Err(LogError::FrameTooShort { expected: session.frame_len, actual: data.len() });
+        }
+
+        let timestamp = chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false);
+        let elapsed_ms = session.started.elapsed().as_millis() as u64;
+        let values: Vec<f64> = session.fields.iter()
+            .map(|f| f.field_type.decode(data, f.byte_offset, false).unwrap_or(0.0) * f.scale)
+            .collect();
+
+        match session.format {
+            LogFormat::Json => {
+                let mut row = serde_json::Map::new();
+                row.insert("timestamp".to_string(), timestamp.into());
+                row.insert("elapsed_ms".to_string(), elapsed_ms.into());
+                for (field, value) in session.fields.iter().zip(&values) {
+                    row.insert(field.name.clone(), (*value).into());
+                }
+                writeln!(session.writer, "{}", serde_json::Value::Object(row))?;
+            }
+            format => {
+                let mut columns = vec![timestamp, elapsed_ms.to_string()];
+                columns.extend(values.iter().map(|v| v.to_string()));
+                writeln!(session.writer, "{}", columns.join(format.separator()))?;
+            }
+        }
+
+        session.sample_count += 1;
+        session.flush_if_due()?;
+        Ok(())
+    }
+
+    /// Flush rows still buffered after `FLUSH_INTERVAL`, for when the port goes quiet
+    pub fn flush_pending(&mut self, session_id: &str) -> Result<(), LogError> {
+        let session = self.sessions.get_mut(session_id)
+            .ok_or_else(|| LogError::UnknownSession(session_id.to_string()))?;
+        session.flush_if_due()?;
+        Ok(())
+    }
+
+    /// Flush and close the session file
+    pub fn stop_session(&mut self, session_id: &str) -> Result<SessionSummary, LogError> {
+        let mut session = self.sessions.remove(session_id)
+            .ok_or_else(|| LogError::UnknownSession(session_id.to_string()))?;
+        session.writer.flush()?;
+
+        let file_size_bytes = std::fs::metadata(&session.path)?.len();
+        log::info!("Serial logging session {} stopped after {} samples", session_id, session.sample_count);
+
+        Ok(SessionSummary {
+            session_id: session_id.to_string(),
+            sample_count: session.sample_count,
+            duration_secs: session.started.elapsed().as_secs_f64(),
+            file_path: session.path,
+            file_size_bytes,
+        })
+    }
+
+    /// Frame size implied by the session field layout
+    pub fn frame_len(&self, session_id: &str) -> Option<usize> {
+        self.sessions.get(session_id).map(|s| s.frame_len)
+    }
+}
+
+/// Frame size covered by a field layout, rejecting layouts no frame can carry
+fn layout_frame_len(fields: &[FieldDef]) -> Result<usize, LogError> {
+    if fields.is_empty() {
+        return Err(LogError::InvalidLayout("No fields to log".to_string()));
+    }
+    let mut frame_len = 0;
+    for field in fields {
+        let end = field.byte_offset.checked_add(field.field_type.size())
+            .filter(|&end| end <= MAX_FRAME_LEN)
+            .ok_or_else(|| LogError::InvalidLayout(format!(
+                "Field '{}' at byte {} lies outside the {}-byte frame limit",
+                field.name, field.byte_offset, MAX_FRAME_LEN,
+            )))?;
+        frame_len = frame_len.max(end);
+    }
+    Ok(frame_len)
+}
+
+/// Read fixed-size frames from a serial port into a logging session
+///
+/// The reader thread exits once the session is stopped.
+pub fn spawn_port_reader(
+    logger: Arc<Mutex<SerialLogger>>,
+    session_id: SessionId,
+    port: &str,
+    baud: u32,
+) -> Result<(), LogError> {
+    let mut sync = {
+        let logger = logger.lock().unwrap();
+        let session = logger.sessions.get(&session_id)
+            .ok_or_else(|| LogError::UnknownSession(session_id.clone()))?;
+        FrameSync::new(session.start_byte, session.frame_len)
+    };
+
+    let mut serial = serialport::new(port, baud)
+        .timeout(Duration::from_millis(100))
+        .open()
+        .map_err(|e| LogError::Port(e.to_string()))?;
+
+    std::thread::spawn(move || {
+        let mut chunk = [0u8; 256];
+        loop {
+            let received = match serial.read(&mut chunk) {
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => 0,
+                Err(e) => {
+                    log::error!("Serial logger read failed: {}", e);
+                    break;
+                }
+            };
+
+            let mut logger = logger.lock().unwrap();
+            let frames = sync.push(&chunk[..received]);
+            let result = if frames.is_empty() {
+                logger.flush_pending(&session_id)
+            } else {
+                frames.iter().try_for_each(|frame| logger.append_line(&session_id, frame))
+            };
+            match result {
+                Err(LogError::UnknownSession(_)) => break,
+                Err(e) => log::warn!("Serial logger: {}", e),
+                Ok(()) => {}
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serial::protocol::FieldType;
+
+    #[test]
+    fn test_logging_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("adc.csv");
+        let fields = vec![
+            FieldDef { name: "ch0".to_string(), field_type: FieldType::U16, byte_offset: 0, scale: 0.5, unit: "mV".to_string() },
+            FieldDef { name: "ch1".to_string(), field_type: FieldType::U8, byte_offset: 2, scale: 1.0, unit: String::new() },
+        ];
+
+        let mut logger = SerialLogger::new();
+        let id = logger.start_session(&path, LogFormat::Csv, fields, None).unwrap();
+        logger.append_line(&id, &[0x10, 0x00, 0x07]).unwrap();
+        logger.append_line(&id, &[0x20, 0x00, 0x08]).unwrap();
+        assert!(logger.append_line(&id, &[0x01]).is_err());
+
+        let summary = logger.stop_session(&id).unwrap();
+        assert_eq!(summary.sample_count, 2);
+        assert!(summary.file_size_bytes > 0);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines[0], "timestamp,elapsed_ms,ch0 (mV),ch1");
+        assert!(lines[1].ends_with(",8,7"));
+        assert!(logger.append_line(&id, &[0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_header_quoting() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("quoted.csv");
+        let fields = vec![
+            FieldDef { name: "temp, \"outer\"".to_string(), field_type: FieldType::U8, byte_offset: 0, scale: 1.0, unit: "C".to_string() },
+        ];
+        let mut logger = SerialLogger::new();
+        let id = logger.start_session(&path, LogFormat::Csv, fields, None).unwrap();
+        logger.stop_session(&id).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().next().unwrap(), "timestamp,elapsed_ms,\"temp, \"\"outer\"\" (C)\"");
+        assert_eq!(LogFormat::Tsv.quote("a,b"), "a,b");
+    }
+
+    #[test]
+    fn test_rejects_empty_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("empty.csv");
+        let mut logger = SerialLogger::new();
+        let result = logger.start_session(&path, LogFormat::Csv, vec![], None);
+        assert!(matches!(result, Err(LogError::InvalidLayout(_))));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_rejects_overflowing_offset() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("overflow.csv");
+        let fields = vec![
+            FieldDef { name: "x".to_string(), field_type: FieldType::U32, byte_offset: usize::MAX - 1, scale: 1.0, unit: String::new() },
+        ];
+        let mut logger = SerialLogger::new();
+        let result = logger.start_session(&path, LogFormat::Csv, fields, None);
+        assert!(matches!(result, Err(LogError::InvalidLayout(_))));
+    }
+
+    #[test]
+    fn test_rejects_oversized_frame() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("huge.csv");
+        let fields = vec![
+            FieldDef { name: "x".to_string(), field_type: FieldType::U8, byte_offset: MAX_FRAME_LEN, scale: 1.0, unit: String::new() },
+        ];
+        let mut logger = SerialLogger::new();
+        let result = logger.start_session(&path, LogFormat::Csv, fields, None);
+        assert!(matches!(result, Err(LogError::InvalidLayout(_))));
+
+        let last = vec![
+            FieldDef { name: "x".to_string(), field_type: FieldType::U8, byte_offset: MAX_FRAME_LEN - 1, scale: 1.0, unit: String::new() },
+        ];
+        let id = logger.start_session(&path, LogFormat::Csv, last, None).unwrap();
+        assert_eq!(logger.frame_len(&id), Some(MAX_FRAME_LEN));
+    }
+
+    #[test]
+    fn test_frame_sync_resyncs_on_start_byte() {
+        let mut sync = FrameSync::new(Some(0xAA), 2);
+        // Joined mid-frame: the stray payload bytes are dropped
+        assert_eq!(sync.push(&[0x02, 0x03, 0xAA, 0x10]), Vec::<Vec<u8>>::new());
+        assert_eq!(sync.push(&[0x11, 0xAA, 0x20, 0x21]), vec![vec![0x10, 0x11], vec![0x20, 0x21]]);
+        // A lost byte garbles one frame, then the stream lines up on the next start byte
+        let frames = sync.push(&[0xAA, 0x30, 0xAA, 0x40, 0x41, 0xAA, 0x50, 0x51]);
+        assert_eq!(frames, vec![vec![0x30, 0xAA], vec![0x50, 0x51]]);
+
+        let mut raw = FrameSync::new(None, 3);
+        assert_eq!(raw.push(&[1, 2, 3, 4]), vec![vec![1, 2, 3]]);
+    }
+}
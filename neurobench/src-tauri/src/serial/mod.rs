@@ -6,6 +6,11 @@ use std::io::{Read, Write};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+pub mod protocol;
+pub mod visualize;
+pub mod logger;
+pub mod protocol_analyzer;
+
 /// Serial port configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SerialConfig {
@@ -0,0 +1,450 @@
+// Binary Protocol Decoder
+// Splits a raw serial capture into frames and decodes payload fields
+
+use serde::{Deserialize, Serialize};
+
+/// Frame checksum algorithm
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChecksumType {
+    #[default]
+    None,
+    Xor,
+    Sum8,
+    Crc8,
+    Crc16Modbus,
+    Crc16Ccitt,
+}
+
+impl ChecksumType {
+    /// Size of the checksum field in bytes
+    pub fn size(&self) -> usize {
+        match self {
+            ChecksumType::None => 0,
+            ChecksumType::Xor | ChecksumType::Sum8 | ChecksumType::Crc8 => 1,
+            ChecksumType::Crc16Modbus | ChecksumType::Crc16Ccitt => 2,
+        }
+    }
+
+    /// Compute the checksum over `data`
+    pub fn compute(&self, data: &[u8]) -> u16 {
+        match self {
+            ChecksumType::None => 0,
+            ChecksumType::Xor => data.iter().fold(0u8, |acc, &b| acc ^ b) as u16,
+            ChecksumType::Sum8 => data.iter().fold(0u8, |acc, &b| acc.wrapping_add(b)) as u16,
+            ChecksumType::Crc8 => {
+                let mut crc = 0u8;
+                for &byte in data {
+                    crc ^= byte;
+                    for _ in 0..8 {
+                        crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+                    }
+                }
+                crc as u16
+            }
+            ChecksumType::Crc16Modbus => {
+                let mut crc = 0xFFFFu16;
+                for &byte in data {
+                    crc ^= byte as u16;
+                    for _ in 0..8 {
+                        crc = if crc & 1 != 0 { (crc >> 1) ^ 0xA001 } else { crc >> 1 };
+                    }
+                }
+                crc
+            }
+            ChecksumType::Crc16Ccitt => {
+                let mut crc = 0xFFFFu16;
+                for &byte in data {
+                    crc ^= (byte as u16) << 8;
+                    for _ in 0..8 {
+                        crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+                    }
+                }
+                crc
+            }
+        }
+    }
+
+    /// Read the transmitted checksum (Modbus CRC is little-endian, the rest big-endian)
+    fn read(&self, bytes: &[u8]) -> u16 {
+        match self {
+            ChecksumType::None => 0,
+            ChecksumType::Crc16Modbus => u16::from_le_bytes([bytes[0], bytes[1]]),
+            ChecksumType::Crc16Ccitt => u16::from_be_bytes([bytes[0], bytes[1]]),
+            _ => bytes[0] as u16,
+        }
+    }
+}
+
+/// Payload field data type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FieldType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+}
+
+impl FieldType {
+    pub fn size(&self) -> usize {
+        match self {
+            FieldType::U8 | FieldType::I8 => 1,
+            FieldType::U16 | FieldType::I16 => 2,
+            FieldType::U32 | FieldType::I32 | FieldType::F32 => 4,
+        }
+    }
+
+    /// Decode a value from `bytes`, returns None if out of range
+    pub fn decode(&self, bytes: &[u8], offset: usize, big_endian: bool) -> Option<f64> {
+        let raw = bytes.get(offset..offset.checked_add(self.size())?)?;
+        let mut buf = [0u8; 4];
+        buf[..raw.len()].copy_from_slice(raw);
+        if big_endian {
+            buf[..raw.len()].reverse();
+        }
+        Some(match self {
+            FieldType::U8 => buf[0] as f64,
+            FieldType::I8 => buf[0] as i8 as f64,
+            FieldType::U16 => u16::from_le_bytes([buf[0], buf[1]]) as f64,
+            FieldType::I16 => i16::from_le_bytes([buf[0], buf[1]]) as f64,
+            FieldType::U32 => u32::from_le_bytes(buf) as f64,
+            FieldType::I32 => i32::from_le_bytes(buf) as f64,
+            FieldType::F32 => f32::from_le_bytes(buf) as f64,
+        })
+    }
+}
+
+/// Field layout inside a frame payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldDef {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: FieldType,
+    pub byte_offset: usize,
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    #[serde(default)]
+    pub unit: String,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+/// Decoded payload field
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldValue {
+    pub name: String,
+    pub raw: f64,
+    pub value: f64,
+    pub unit: String,
+}
+
+/// Frame layout description
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameConfig {
+    pub start_byte: Option<u8>,
+    pub end_byte: Option<u8>,
+    pub length_field_offset: Option<usize>,  // From frame start, including start byte
+    pub length_field_size: u8,               // 1, 2 or 4 bytes
+    pub length_includes_header: bool,        // Length counts the whole frame
+    pub checksum: ChecksumType,
+    #[serde(default)]
+    pub big_endian: bool,
+    #[serde(default)]
+    pub fields: Vec<FieldDef>,
+}
+
+impl Default for FrameConfig {
+    fn default() -> Self {
+        Self {
+            start_byte: Some(0xAA),
+            end_byte: None,
+            length_field_offset: Some(1),
+            length_field_size: 1,
+            length_includes_header: false,
+            checksum: ChecksumType::Xor,
+            big_endian: false,
+            fields: vec![],
+        }
+    }
+}
+
+impl FrameConfig {
+    /// Reject layouts the decoder cannot handle
+    pub fn validate(&self) -> Result<(), String> {
+        if self.length_field_offset.is_some() && !(1..=4).contains(&self.length_field_size) {
+            return Err(format!("Length field must be 1 to 4 bytes, got {}", self.length_field_size));
+        }
+        let header = header_len(self).ok_or("Length field offset is out of range")?;
+
+        let max_payload = self.max_payload_len(header);
+        for field in &self.fields {
+            let fits = field.byte_offset.checked_add(field.field_type.size())
+                .is_some_and(|end| max_payload.is_none_or(|max| end <= max));
+            if !fits {
+                return Err(format!(
+                    "Field '{}' at byte {} lies outside the largest payload the frame can carry",
+                    field.name, field.byte_offset,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Largest payload the length field can describe, None when unbounded
+    fn max_payload_len(&self, header: usize) -> Option<usize> {
+        self.length_field_offset?;
+        let max_length = usize::try_from((1u64 << (8 * self.length_field_size as u32)) - 1).unwrap_or(usize::MAX);
+        Some(if self.length_includes_header {
+            max_length.saturating_sub(header.saturating_add(trailer_len(self)))
+        } else {
+            max_length
+        })
+    }
+}
+
+/// A single decoded frame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecodedFrame {
+    pub offset: usize,
+    pub raw: Vec<u8>,
+    pub payload: Vec<u8>,
+    pub checksum_valid: bool,
+    pub fields: Vec<FieldValue>,
+}
+
+/// Split `data` into frames and decode each one
+///
+/// The checksum covers every byte after the start byte up to the checksum
+/// itself. Bytes that cannot be framed are skipped until the next start byte.
+pub fn decode_framed(data: &[u8], config: &FrameConfig) -> Vec<DecodedFrame> {
+    let mut frames = Vec::new();
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let start = match config.start_byte {
+            Some(sb) => match data[pos..].iter().position(|&b| b == sb) {
+                Some(i) => pos + i,
+                None => break,
+            },
+            None => pos,
+        };
+
+        let end = match frame_length(&data[start..], config) {
+            Some(len) if len > 0 => start.checked_add(len),
+            Some(_) => None,
+            // Length field cut off at the end of the capture
+            None => break,
+        };
+
+        let decoded = end
+            .filter(|&end| end <= data.len())
+            .and_then(|end| decode_frame(&data[start..end], start, config).map(|frame| (end, frame)));
+        match decoded {
+            Some((end, frame)) => {
+                frames.push(frame);
+                pos = end;
+            }
+            // Framing mismatch (bad length or end byte), resync after the start byte
+            None => pos = start + 1,
+        }
+    }
+
+    frames
+}
+
+/// None when the length field offset overflows
+fn header_len(config: &FrameConfig) -> Option<usize> {
+    match config.length_field_offset {
+        Some(offset) => offset.checked_add(config.length_field_size as usize),
+        None => Some(config.start_byte.map_or(0, |_| 1)),
+    }
+}
+
+fn trailer_len(config: &FrameConfig) -> usize {
+    config.checksum.size() + config.end_byte.map_or(0, |_| 1)
+}
+
+/// Total frame length starting at `data[0]`, if it can be determined
+fn frame_length(data: &[u8], config: &FrameConfig) -> Option<usize> {
+    if let Some(offset) = config.length_field_offset {
+        let size = config.length_field_size as usize;
+        let field = data.get(offset..offset.checked_add(size)?)?;
+        let mut value = 0usize;
+        if config.big_endian {
+            for &b in field {
+                value = (value << 8) | b as usize;
+            }
+        } else {
+            for &b in field.iter().rev() {
+                value = (value << 8) | b as usize;
+            }
+        }
+        return if config.length_includes_header {
+            Some(value)
+        } else {
+            value.checked_add(header_len(config)?.checked_add(trailer_len(config))?)
+        };
+    }
+
+    if let Some(eb) = config.end_byte {
+        let search_from = header_len(config)?;
+        return data.get(search_from..)?
+            .iter()
+            .position(|&b| b == eb)
+            .map(|i| search_from + i + 1);
+    }
+
+    Some(data.len())
+}
+
+fn decode_frame(raw: &[u8], offset: usize, config: &FrameConfig) -> Option<DecodedFrame> {
+    let header = header_len(config)?;
+    let trailer = trailer_len(config);
+    if raw.len() < header.checked_add(trailer)? {
+        return None;
+    }
+    if let Some(eb) = config.end_byte {
+        if raw[raw.len() - 1] != eb {
+            return None;
+        }
+    }
+
+    let checksum_at = raw.len() - trailer;
+    let payload = raw[header..checksum_at].to_vec();
+
+    let checksum_valid = match config.checksum {
+        ChecksumType::None => true,
+        checksum => {
+            let covered_from = config.start_byte.map_or(0, |_| 1);
+            let expected = checksum.compute(&raw[covered_from..checksum_at]);
+            expected == checksum.read(&raw[checksum_at..])
+        }
+    };
+
+    let fields = config.fields.iter()
+        .filter_map(|def| {
+            let raw_value = def.field_type.decode(&payload, def.byte_offset, config.big_endian)?;
+            Some(FieldValue {
+                name: def.name.clone(),
+                raw: raw_value,
+                value: raw_value * def.scale,
+                unit: def.unit.clone(),
+            })
+        })
+        .collect();
+
+    Some(DecodedFrame {
+        offset,
+        raw: raw.to_vec(),
+        payload,
+        checksum_valid,
+        fields,
+    })
+}
+
+/// Parse a hex string such as "AA 03 01 02" or "aa0301"
+pub fn parse_hex(input: &str) -> Result<Vec<u8>, String> {
+    if let Some(c) = input.chars().find(|c| !c.is_ascii()) {
+        return Err(format!("Invalid hex character '{}'", c));
+    }
+    let digits: String = input.chars()
+        .filter(|c| !c.is_whitespace() && *c != ',')
+        .collect();
+    let digits = digits.replace("0x", "").replace("0X", "");
+    if digits.len() % 2 == 1 {
+        return Err("Hex data must have an even number of digits".to_string());
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16)
+            .map_err(|_| format!("Invalid hex byte '{}'", &digits[i..i + 2])))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_length_prefixed_frames() {
+        // AA | len | payload (u16 LE) | xor
+        let data = [0x00, 0xAA, 0x02, 0xE8, 0x03, 0xE9, 0xAA, 0x02, 0x10, 0x00, 0xFF];
+        let config = FrameConfig {
+            fields: vec![FieldDef {
+                name: "temp".to_string(),
+                field_type: FieldType::U16,
+                byte_offset: 0,
+                scale: 0.1,
+                unit: "C".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        let frames = decode_framed(&data, &config);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].offset, 1);
+        assert!(frames[0].checksum_valid);
+        assert!((frames[0].fields[0].value - 100.0).abs() < 1e-9);
+        assert!(!frames[1].checksum_valid);
+    }
+
+    #[test]
+    fn test_decode_delimited_frames() {
+        let data = parse_hex("7E 01 02 7F 7E 03 7F").unwrap();
+        let config = FrameConfig {
+            start_byte: Some(0x7E),
+            end_byte: Some(0x7F),
+            length_field_offset: None,
+            length_field_size: 0,
+            length_includes_header: false,
+            checksum: ChecksumType::None,
+            big_endian: false,
+            fields: vec![],
+        };
+
+        let frames = decode_framed(&data, &config);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].payload, vec![0x01, 0x02]);
+        assert_eq!(frames[1].payload, vec![0x03]);
+    }
+
+    #[test]
+    fn test_resync_and_bad_input() {
+        // The first frame claims 0x40 payload bytes; the real frame follows it
+        let data = parse_hex("AA 40 AA 01 05 04").unwrap();
+        let frames = decode_framed(&data, &FrameConfig::default());
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].offset, 2);
+        assert!(frames[0].checksum_valid);
+
+        assert!(parse_hex("AA é1").is_err());
+        assert!(FrameConfig { length_field_size: 9, ..Default::default() }.validate().is_err());
+        assert!(FrameConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_out_of_frame_fields_and_overflow() {
+        let field = |byte_offset| FieldDef {
+            name: "x".to_string(),
+            field_type: FieldType::U16,
+            byte_offset,
+            scale: 1.0,
+            unit: String::new(),
+        };
+
+        // A 1-byte length field carries at most 255 payload bytes
+        assert!(FrameConfig { fields: vec![field(253)], ..Default::default() }.validate().is_ok());
+        assert!(FrameConfig { fields: vec![field(254)], ..Default::default() }.validate().is_err());
+        assert!(FrameConfig { fields: vec![field(usize::MAX)], ..Default::default() }.validate().is_err());
+        assert!(FrameConfig { length_field_offset: Some(usize::MAX), ..Default::default() }.validate().is_err());
+
+        assert_eq!(FieldType::U32.decode(&[0; 8], usize::MAX - 1, false), None);
+
+        let config = FrameConfig { length_field_offset: Some(usize::MAX), ..Default::default() };
+        assert!(decode_framed(&[0xAA, 0x01, 0x02], &config).is_empty());
+    }
+}
@@ -0,0 +1,274 @@
+// I2C/SPI Protocol Analyzer
+// Decodes logic analyzer sample dumps into bus transactions
+//
+// Each capture byte is one sample of the probed lines:
+//   I2C: bit 0 = SCL, bit 1 = SDA
+//   SPI: bit 0 = SCLK, bit 1 = MOSI, bit 2 = MISO, bit 3 = CS
+
+use serde::{Deserialize, Serialize};
+
+const I2C_SCL: u8 = 1 << 0;
+const I2C_SDA: u8 = 1 << 1;
+
+const SPI_SCLK: u8 = 1 << 0;
+const SPI_MOSI: u8 = 1 << 1;
+const SPI_MISO: u8 = 1 << 2;
+const SPI_CS: u8 = 1 << 3;
+
+/// One I2C transfer between a START (or repeated START) and the next START/STOP
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct I2cTransaction {
+    /// 7-bit device address
+    pub address: u8,
+    pub is_write: bool,
+    pub data: Vec<u8>,
+    /// Byte index that was NAKed; 0 is the address frame, n is `data[n - 1]`.
+    /// The master NAK that ends a read is normal and not reported.
+    pub nacked_at: Option<usize>,
+}
+
+/// Bytes exchanged during one CS assertion
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpiTransaction {
+    pub mosi: Vec<u8>,
+    pub miso: Vec<u8>,
+}
+
+/// Bit-level I2C decoder state for the frame being received
+struct I2cFrame {
+    transaction: Option<I2cTransaction>,
+    shift: u8,
+    bits: u8,
+    bytes: usize,
+}
+
+impl I2cFrame {
+    fn new() -> Self {
+        Self { transaction: None, shift: 0, bits: 0, bytes: 0 }
+    }
+
+    /// Clock in one bit sampled on an SCL rising edge
+    fn clock(&mut self, sda: bool) {
+        if self.bits < 8 {
+            self.shift = (self.shift << 1) | sda as u8;
+            self.bits += 1;
+            return;
+        }
+
+        // Ninth bit: ACK (low) or NAK (high)
+        let nak = sda;
+        let byte = self.shift;
+        match &mut self.transaction {
+            None => {
+                self.transaction = Some(I2cTransaction {
+                    address: byte >> 1,
+                    is_write: byte & 1 == 0,
+                    data: Vec::new(),
+                    nacked_at: nak.then_some(0),
+                });
+            }
+            Some(t) => {
+                t.data.push(byte);
+                if nak && t.is_write && t.nacked_at.is_none() {
+                    t.nacked_at = Some(self.bytes);
+                }
+            }
+        }
+        self.bytes += 1;
+        self.shift = 0;
+        self.bits = 0;
+    }
+
+    /// Finish the frame at a START/STOP condition, dropping partial bytes
+    fn finish(&mut self, out: &mut Vec<I2cTransaction>) {
+        if let Some(t) = self.transaction.take() {
+            out.push(t);
+        }
+        *self = Self::new();
+    }
+}
+
+/// Decode an I2C capture into transactions
+///
+/// START and STOP are SDA edges while SCL is high; data and ACK bits are
+/// sampled on SCL rising edges. A repeated START closes the current
+/// transaction and opens the next one.
+pub fn analyze_i2c(capture: &[u8]) -> Vec<I2cTransaction> {
+    let mut transactions = Vec::new();
+    let mut frame = I2cFrame::new();
+    let mut in_frame = false;
+
+    for pair in capture.windows(2) {
+        let (prev, cur) = (pair[0], pair[1]);
+        let (prev_scl, scl) = (prev & I2C_SCL != 0, cur & I2C_SCL != 0);
+        let (prev_sda, sda) = (prev & I2C_SDA != 0, cur & I2C_SDA != 0);
+
+        if prev_scl && scl && prev_sda != sda {
+            frame.finish(&mut transactions);
+            // Falling SDA is START, rising SDA is STOP
+            in_frame = !sda;
+        } else if in_frame && !prev_scl && scl {
+            frame.clock(sda);
+        }
+    }
+    frame.finish(&mut transactions);
+    transactions
+}
+
+/// Decode an SPI capture into transactions (mode 0, MSB first)
+///
+/// Bytes are grouped by CS assertion and sampled on SCLK rising edges.
+/// Trailing bits that don't make a full byte are dropped.
+pub fn analyze_spi(capture: &[u8], cs_active_low: bool) -> Vec<SpiTransaction> {
+    let selected = |sample: u8| (sample & SPI_CS != 0) != cs_active_low;
+
+    let mut transactions = Vec::new();
+    let mut current: Option<SpiTransaction> = None;
+    let (mut mosi, mut miso, mut bits) = (0u8, 0u8, 0u8);
+    let mut prev: Option<u8> = None;
+
+    for &sample in capture {
+        if !selected(sample) {
+            transactions.extend(current.take());
+            prev = Some(sample);
+            continue;
+        }
+        if current.is_none() {
+            current = Some(SpiTransaction { mosi: Vec::new(), miso: Vec::new() });
+            (mosi, miso, bits) = (0, 0, 0);
+        }
+
+        let rising = prev.is_some_and(|p| p & SPI_SCLK == 0) && sample & SPI_SCLK != 0;
+        if rising {
+            mosi = (mosi << 1) | (sample & SPI_MOSI != 0) as u8;
+            miso = (miso << 1) | (sample & SPI_MISO != 0) as u8;
+            bits += 1;
+            if bits == 8 {
+                if let Some(t) = current.as_mut() {
+                    t.mosi.push(mosi);
+                    t.miso.push(miso);
+                }
+                (mosi, miso, bits) = (0, 0, 0);
+            }
+        }
+        prev = Some(sample);
+    }
+    transactions.extend(current);
+    transactions
+}
+
+fn hex_bytes(data: &[u8]) -> String {
+    if data.is_empty() {
+        return "-".to_string();
+    }
+    data.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ")
+}
+
+/// Render I2C transactions as a text table
+pub fn format_i2c_table(transactions: &[I2cTransaction]) -> Vec<String> {
+    let mut lines = vec![
+        format!("{:>3}  {:<6}  {:<3}  {:<8}  {}", "#", "Addr", "R/W", "Status", "Data"),
+        "-".repeat(48),
+    ];
+    for (i, t) in transactions.iter().enumerate() {
+        let status = match t.nacked_at {
+            None => "ACK".to_string(),
+            Some(0) => "NAK addr".to_string(),
+            Some(n) => format!("NAK @{}", n),
+        };
+        lines.push(format!(
+            "{:>3}  0x{:02X}    {:<3}  {:<8}  {}",
+            i,
+            t.address,
+            if t.is_write { "W" } else { "R" },
+            status,
+            hex_bytes(&t.data)
+        ));
+    }
+    lines.push(format!("{} transaction(s)", transactions.len()));
+    lines
+}
+
+/// Render SPI transactions as a text table
+pub fn format_spi_table(transactions: &[SpiTransaction]) -> Vec<String> {
+    let mut lines = vec![
+        format!("{:>3}  {:>5}  {:<24}  {}", "#", "Bytes", "MOSI", "MISO"),
+        "-".repeat(64),
+    ];
+    for (i, t) in transactions.iter().enumerate() {
+        lines.push(format!(
+            "{:>3}  {:>5}  {:<24}  {}",
+            i,
+            t.mosi.len(),
+            hex_bytes(&t.mosi),
+            hex_bytes(&t.miso)
+        ));
+    }
+    lines.push(format!("{} transaction(s)", transactions.len()));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build I2C samples: START, each byte with the given ACK level, STOP
+    fn i2c_capture(bytes: &[(u8, bool)]) -> Vec<u8> {
+        let both = I2C_SCL | I2C_SDA;
+        let mut samples = vec![both, both, I2C_SCL, 0];
+        for &(byte, nak) in bytes {
+            let bits = (0..8).rev().map(|i| (byte >> i) & 1 != 0).chain([nak]);
+            for bit in bits {
+                let sda = if bit { I2C_SDA } else { 0 };
+                samples.extend([sda, sda | I2C_SCL, sda]);
+            }
+            samples.push(0);
+        }
+        samples.extend([0, I2C_SCL, both, both]);
+        samples
+    }
+
+    #[test]
+    fn test_i2c_write_and_nak() {
+        let capture = i2c_capture(&[(0x48 << 1, false), (0x01, false), (0x60, false)]);
+        let t = analyze_i2c(&capture);
+        assert_eq!(t.len(), 1);
+        assert_eq!(t[0].address, 0x48);
+        assert!(t[0].is_write);
+        assert_eq!(t[0].data, vec![0x01, 0x60]);
+        assert_eq!(t[0].nacked_at, None);
+
+        let missing = analyze_i2c(&i2c_capture(&[(0x50 << 1, true)]));
+        assert_eq!(missing[0].nacked_at, Some(0));
+
+        let read = analyze_i2c(&i2c_capture(&[((0x48 << 1) | 1, false), (0xAB, false), (0xCD, true)]));
+        assert!(!read[0].is_write);
+        assert_eq!(read[0].data, vec![0xAB, 0xCD]);
+        assert_eq!(read[0].nacked_at, None);
+        assert!(format_i2c_table(&read)[2].contains("AB CD"));
+    }
+
+    #[test]
+    fn test_spi_grouped_by_cs() {
+        let mut capture = vec![SPI_CS];
+        for byte in [0x9Fu8, 0x00, 0x00] {
+            for i in (0..8).rev() {
+                let mosi = if (byte >> i) & 1 != 0 { SPI_MOSI } else { 0 };
+                capture.extend([mosi, mosi | SPI_MISO | SPI_SCLK]);
+            }
+        }
+        capture.extend([0, SPI_CS, SPI_CS]);
+        for i in (0..8).rev() {
+            let mosi = if (0x06u8 >> i) & 1 != 0 { SPI_MOSI } else { 0 };
+            capture.extend([mosi, mosi | SPI_SCLK]);
+        }
+        capture.push(SPI_CS);
+
+        let t = analyze_spi(&capture, true);
+        assert_eq!(t.len(), 2);
+        assert_eq!(t[0].mosi, vec![0x9F, 0x00, 0x00]);
+        assert_eq!(t[0].miso, vec![0xFF, 0xFF, 0xFF]);
+        assert_eq!(t[1].mosi, vec![0x06]);
+        assert_eq!(format_spi_table(&t).len(), 5);
+    }
+}
@@ -0,0 +1,269 @@
+// Serial Data Visualization
+// Text-mode oscilloscope for ADC samples streamed over UART
+
+/// Width of the amplitude label column, including the axis character
+const LABEL_WIDTH: usize = 9;
+
+/// Render samples as an ASCII oscilloscope trace
+///
+/// Returns exactly `height` lines of exactly `width` characters: a status
+/// line, the plot area and a time axis. The vertical range is picked from the
+/// visible samples, and with a `trigger_level` the frame starts a quarter
+/// screen before the first rising edge through that level.
+pub fn render_scope(
+    samples: &[f32],
+    width: usize,
+    height: usize,
+    sample_rate_hz: f32,
+    trigger_level: Option<f32>,
+) -> Vec<String> {
+    let plot_rows = height.saturating_sub(2);
+    let plot_cols = width.saturating_sub(LABEL_WIDTH).max(1);
+
+    // Samples per column when the capture is longer than the screen
+    let bucket = (samples.len() / plot_cols).max(1);
+
+    let trigger_index = trigger_level.and_then(|level| find_rising_edge(samples, level));
+    let start = trigger_index
+        .map(|i| i.saturating_sub((plot_cols / 4) * bucket))
+        .unwrap_or(0);
+    let visible = &samples[start.min(samples.len())..];
+
+    // Per-column min/max envelope
+    let columns: Vec<(f32, f32)> = visible
+        .chunks(bucket)
+        .take(plot_cols)
+        .map(|chunk| {
+            chunk.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &s| (lo.min(s), hi.max(s)))
+        })
+        .collect();
+
+    let (range_lo, range_hi) = auto_range(&columns, trigger_level);
+    let row_of = |value: f32| -> usize {
+        if plot_rows == 0 {
+            return 0;
+        }
+        let norm = ((value - range_lo) / (range_hi - range_lo)).clamp(0.0, 1.0);
+        (plot_rows - 1) - (norm * (plot_rows - 1) as f32).round() as usize
+    };
+
+    let mut grid = vec![vec![' '; plot_cols]; plot_rows];
+    if plot_rows > 0 {
+        // Zero line when it is in range
+        if range_lo < 0.0 && range_hi > 0.0 {
+            let zero_row = row_of(0.0);
+            for cell in grid[zero_row].iter_mut() {
+                *cell = '.';
+            }
+        }
+        for (col, &(lo, hi)) in columns.iter().enumerate() {
+            let (top, bottom) = (row_of(hi), row_of(lo));
+            for row in grid.iter_mut().take(bottom + 1).skip(top) {
+                row[col] = if top == bottom { '*' } else { '|' };
+            }
+        }
+    }
+
+    let trigger_row = trigger_level.filter(|_| plot_rows > 0).map(row_of);
+    let trigger_col = trigger_index.map(|i| (i - start) / bucket).filter(|&c| c < plot_cols);
+
+    let mut lines = Vec::with_capacity(height);
+    if height == 0 {
+        return lines;
+    }
+
+    lines.push(fit(&status_line(samples, sample_rate_hz, trigger_level, trigger_index.is_some()), width));
+
+    for (r, row) in grid.iter().enumerate() {
+        let label = if r == 0 {
+            format_amplitude(range_hi)
+        } else if r == plot_rows - 1 {
+            format_amplitude(range_lo)
+        } else if r == plot_rows / 2 {
+            format_amplitude((range_hi + range_lo) / 2.0)
+        } else {
+            String::new()
+        };
+        let axis = if trigger_row == Some(r) { '>' } else { '|' };
+        let mut line = format!("{:>w$}{}", label, axis, w = LABEL_WIDTH - 1);
+        line.extend(row.iter());
+        lines.push(fit(&line, width));
+    }
+
+    if height >= 2 {
+        let mut axis: Vec<char> = format!("{:>w$}+", "", w = LABEL_WIDTH - 1).chars().collect();
+        axis.extend("-".repeat(plot_cols).chars());
+        if let Some(col) = trigger_col {
+            axis[LABEL_WIDTH + col] = 'T';
+        }
+        let span = if sample_rate_hz > 0.0 {
+            format!(" {} ", format_time((plot_cols * bucket) as f32 / sample_rate_hz))
+        } else {
+            format!(" {} samples ", plot_cols * bucket)
+        };
+        if span.len() < plot_cols {
+            let at = axis.len() - span.len();
+            for (i, c) in span.chars().enumerate() {
+                axis[at + i] = c;
+            }
+        }
+        lines.push(fit(&axis.into_iter().collect::<String>(), width));
+    }
+
+    lines
+}
+
+/// Index of the first rising crossing of `level`
+fn find_rising_edge(samples: &[f32], level: f32) -> Option<usize> {
+    samples.windows(2)
+        .position(|w| w[0] < level && w[1] >= level)
+        .map(|i| i + 1)
+}
+
+/// Vertical range rounded out to a 1-2-5 step
+fn auto_range(columns: &[(f32, f32)], trigger_level: Option<f32>) -> (f32, f32) {
+    let mut lo = columns.iter().map(|c| c.0).fold(f32::INFINITY, f32::min);
+    let mut hi = columns.iter().map(|c| c.1).fold(f32::NEG_INFINITY, f32::max);
+    if let Some(t) = trigger_level {
+        lo = lo.min(t);
+        hi = hi.max(t);
+    }
+    if !lo.is_finite() || !hi.is_finite() {
+        return (-1.0, 1.0);
+    }
+
+    let span = (hi - lo).max(hi.abs().max(lo.abs()) * 0.1).max(1e-6);
+    let step = nice_step(span / 4.0);
+    let range_lo = (lo / step).floor() * step;
+    let mut range_hi = (hi / step).ceil() * step;
+    if range_hi <= range_lo {
+        range_hi = range_lo + step;
+    }
+    (range_lo, range_hi)
+}
+
+fn nice_step(raw: f32) -> f32 {
+    let magnitude = 10f32.powf(raw.log10().floor());
+    let fraction = raw / magnitude;
+    let nice = if fraction <= 1.0 {
+        1.0
+    } else if fraction <= 2.0 {
+        2.0
+    } else if fraction <= 5.0 {
+        5.0
+    } else {
+        10.0
+    };
+    nice * magnitude
+}
+
+fn status_line(samples: &[f32], sample_rate_hz: f32, trigger_level: Option<f32>, triggered: bool) -> String {
+    if samples.is_empty() {
+        return "No samples".to_string();
+    }
+
+    let peak = samples.iter().fold(0f32, |acc, s| acc.max(s.abs()));
+    let rms = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+    let freq = estimate_frequency(samples, sample_rate_hz)
+        .map(format_frequency)
+        .unwrap_or_else(|| "--".to_string());
+    let trigger = match trigger_level {
+        Some(level) if triggered => format!("TRIG {:.2}", level),
+        Some(_) => "TRIG ?".to_string(),
+        None => "AUTO".to_string(),
+    };
+
+    format!("Freq: {}  Peak: {:.3}  RMS: {:.3}  {}", freq, peak, rms, trigger)
+}
+
+/// Frequency from rising crossings of the mean level
+fn estimate_frequency(samples: &[f32], sample_rate_hz: f32) -> Option<f32> {
+    if sample_rate_hz <= 0.0 {
+        return None;
+    }
+    let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+    let crossings: Vec<usize> = samples.windows(2)
+        .enumerate()
+        .filter(|(_, w)| w[0] < mean && w[1] >= mean)
+        .map(|(i, _)| i + 1)
+        .collect();
+    if crossings.len() < 2 {
+        return None;
+    }
+    let periods = (crossings.len() - 1) as f32;
+    let duration = (crossings[crossings.len() - 1] - crossings[0]) as f32 / sample_rate_hz;
+    Some(periods / duration)
+}
+
+fn format_amplitude(value: f32) -> String {
+    let text = if value.abs() >= 1000.0 {
+        format!("{:.0}", value)
+    } else if value.abs() >= 10.0 {
+        format!("{:.1}", value)
+    } else {
+        format!("{:.2}", value)
+    };
+    // Keep the label column aligned even for very large values
+    text.chars().take(LABEL_WIDTH - 1).collect()
+}
+
+fn format_time(seconds: f32) -> String {
+    if seconds >= 1.0 {
+        format!("{:.2}s", seconds)
+    } else if seconds >= 1e-3 {
+        format!("{:.2}ms", seconds * 1e3)
+    } else {
+        format!("{:.1}us", seconds * 1e6)
+    }
+}
+
+fn format_frequency(hz: f32) -> String {
+    if hz >= 1e6 {
+        format!("{:.2}MHz", hz / 1e6)
+    } else if hz >= 1e3 {
+        format!("{:.2}kHz", hz / 1e3)
+    } else {
+        format!("{:.2}Hz", hz)
+    }
+}
+
+/// Pad or truncate to exactly `width` characters
+fn fit(line: &str, width: usize) -> String {
+    let truncated: String = line.chars().take(width).collect();
+    format!("{:<w$}", truncated, w = width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_dimensions() {
+        let sine: Vec<f32> = (0..500)
+            .map(|i| (i as f32 * 2.0 * std::f32::consts::PI / 50.0).sin() * 1.5)
+            .collect();
+        let inputs: [&[f32]; 4] = [&[], &[3.3; 10], &sine, &sine[..20]];
+
+        for samples in inputs {
+            for (width, height) in [(80, 24), (40, 10), (20, 5), (120, 3)] {
+                for trigger in [None, Some(0.5), Some(100.0)] {
+                    let lines = render_scope(samples, width, height, 1000.0, trigger);
+                    assert_eq!(lines.len(), height);
+                    assert!(lines.iter().all(|l| l.chars().count() == width));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_status_line_frequency() {
+        let sine: Vec<f32> = (0..1000)
+            .map(|i| (i as f32 * 2.0 * std::f32::consts::PI / 100.0).sin())
+            .collect();
+        let lines = render_scope(&sine, 80, 20, 10_000.0, Some(0.0));
+
+        assert!(lines[0].contains("Freq: 100.00Hz"));
+        assert!(lines[0].contains("TRIG"));
+        assert!(lines.last().unwrap().contains('T'));
+    }
+}
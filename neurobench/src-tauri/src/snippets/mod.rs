@@ -3,6 +3,9 @@
 
 use serde::{Deserialize, Serialize};
 
+pub mod refine;
+pub mod user;
+
 /// Code snippet definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodeSnippet {
@@ -13,6 +16,8 @@ pub struct CodeSnippet {
     pub language: String,
     pub code: String,
     pub tags: Vec<String>,
+    #[serde(default)]
+    pub is_user_defined: bool,
 }
 
 /// Get all available snippets
@@ -26,6 +31,7 @@ pub fn get_snippets() -> Vec<CodeSnippet> {
             category: "GPIO".to_string(),
             language: "c".to_string(),
             tags: vec!["gpio".to_string(), "output".to_string(), "pin".to_string()],
+            is_user_defined: false,
             code: r#"// Configure GPIO pin as output
 void gpio_output_init(GPIO_TypeDef* port, uint8_t pin) {
     port->MODER &= ~(3U << (pin * 2));
@@ -44,6 +50,7 @@ void gpio_output_init(GPIO_TypeDef* port, uint8_t pin) {
             category: "GPIO".to_string(),
             language: "c".to_string(),
             tags: vec!["gpio".to_string(), "input".to_string(), "pullup".to_string()],
+            is_user_defined: false,
             code: r#"// Configure GPIO pin as input with pull-up
 void gpio_input_init(GPIO_TypeDef* port, uint8_t pin) {
     port->MODER &= ~(3U << (pin * 2));  // Input mode
@@ -65,6 +72,7 @@ uint8_t gpio_read(GPIO_TypeDef* port, uint8_t pin) {
             category: "Timer".to_string(),
             language: "c".to_string(),
             tags: vec!["timer".to_string(), "delay".to_string(), "systick".to_string()],
+            is_user_defined: false,
             code: r#"volatile uint32_t systick_ms = 0;
 
 void SysTick_Handler(void) {
@@ -92,6 +100,7 @@ uint32_t millis(void) {
             category: "Timer".to_string(),
             language: "c".to_string(),
             tags: vec!["timer".to_string(), "interrupt".to_string(), "irq".to_string()],
+            is_user_defined: false,
             code: r#"void timer_init(uint32_t freq_hz) {
     RCC->APB1ENR |= RCC_APB1ENR_TIM2EN;
     
@@ -120,6 +129,7 @@ void TIM2_IRQHandler(void) {
             category: "UART".to_string(),
             language: "c".to_string(),
             tags: vec!["uart".to_string(), "printf".to_string(), "serial".to_string()],
+            is_user_defined: false,
             code: r#"#include <stdio.h>
 
 // Retarget printf to UART
@@ -142,6 +152,7 @@ int _write(int file, char *ptr, int len) {
             category: "UART".to_string(),
             language: "c".to_string(),
             tags: vec!["uart".to_string(), "dma".to_string(), "async".to_string()],
+            is_user_defined: false,
             code: r#"void uart_dma_init(void) {
     // Enable DMA1 clock
     RCC->AHB1ENR |= RCC_AHB1ENR_DMA1EN;
@@ -171,6 +182,7 @@ void uart_dma_send(const char* data, uint16_t len) {
             category: "Interrupt".to_string(),
             language: "c".to_string(),
             tags: vec!["interrupt".to_string(), "button".to_string(), "exti".to_string()],
+            is_user_defined: false,
             code: r#"void button_exti_init(void) {
     // Enable SYSCFG clock
     RCC->APB2ENR |= RCC_APB2ENR_SYSCFGEN;
@@ -199,6 +211,7 @@ void EXTI0_IRQHandler(void) {
             category: "SPI".to_string(),
             language: "c".to_string(),
             tags: vec!["spi".to_string(), "master".to_string(), "init".to_string()],
+            is_user_defined: false,
             code: r#"void spi_init(void) {
     RCC->APB2ENR |= RCC_APB2ENR_SPI1EN;
     RCC->AHB1ENR |= RCC_AHB1ENR_GPIOAEN;
@@ -227,6 +240,7 @@ uint8_t spi_transfer(uint8_t data) {
             category: "Power".to_string(),
             language: "c".to_string(),
             tags: vec!["power".to_string(), "sleep".to_string(), "lowpower".to_string()],
+            is_user_defined: false,
             code: r#"void enter_sleep(void) {
     // Enable sleep on exit from ISR
     SCB->SCR &= ~SCB_SCR_SLEEPDEEP_Msk;
@@ -256,6 +270,7 @@ void enter_standby(void) {
             category: "Data Structure".to_string(),
             language: "c".to_string(),
             tags: vec!["buffer".to_string(), "fifo".to_string(), "circular".to_string()],
+            is_user_defined: false,
             code: r#"#define BUFFER_SIZE 256
 
 typedef struct {
@@ -292,18 +307,88 @@ uint8_t rb_pop(RingBuffer* rb) {
     ]
 }
 
-/// Search snippets by query
+/// Built-in snippets followed by user-defined ones
+pub fn get_all_snippets() -> Vec<CodeSnippet> {
+    let mut snippets = get_snippets();
+    snippets.extend(user::list_user_snippets());
+    snippets
+}
+
+/// Search built-in and user snippets, best matches first
 pub fn search_snippets(query: &str) -> Vec<CodeSnippet> {
-    let query_lower = query.to_lowercase();
-    get_snippets()
+    rank_snippets(get_all_snippets(), query)
+}
+
+/// Keep snippets matching every query word, ordered by score
+pub fn rank_snippets(snippets: Vec<CodeSnippet>, query: &str) -> Vec<CodeSnippet> {
+    let mut scored: Vec<(u32, CodeSnippet)> = snippets
         .into_iter()
-        .filter(|s| {
-            s.name.to_lowercase().contains(&query_lower)
-                || s.description.to_lowercase().contains(&query_lower)
-                || s.category.to_lowercase().contains(&query_lower)
-                || s.tags.iter().any(|t| t.to_lowercase().contains(&query_lower))
-        })
-        .collect()
+        .map(|s| (snippet_score(&s, query), s))
+        .filter(|(score, _)| *score > 0)
+        .collect();
+    scored.sort_by(|(a, sa), (b, sb)| b.cmp(a).then_with(|| sa.name.cmp(&sb.name)));
+    scored.into_iter().map(|(_, s)| s).collect()
+}
+
+/// Sum of per-word scores, 0 if any word matches nothing
+///
+/// Tags are matched fuzzily and weigh the most; name, category and
+/// description only count on a substring match.
+fn snippet_score(snippet: &CodeSnippet, query: &str) -> u32 {
+    let name = snippet.name.to_lowercase();
+    let description = snippet.description.to_lowercase();
+    let category = snippet.category.to_lowercase();
+
+    let mut total = 0;
+    for word in query.to_lowercase().split_whitespace() {
+        let tag_score = snippet.tags.iter()
+            .map(|tag| fuzzy_score(word, &tag.to_lowercase()))
+            .max()
+            .unwrap_or(0);
+        let text_score = if name.contains(word) || category.contains(word) {
+            50
+        } else if description.contains(word) {
+            20
+        } else {
+            0
+        };
+        let score = tag_score.max(text_score);
+        if score == 0 {
+            return 0;
+        }
+        total += score;
+    }
+    total
+}
+
+/// How well `query` matches `candidate`: exact, prefix, substring, then in-order subsequence
+pub fn fuzzy_score(query: &str, candidate: &str) -> u32 {
+    if query.is_empty() {
+        return 0;
+    }
+    if candidate == query {
+        return 100;
+    }
+    if candidate.starts_with(query) {
+        return 80;
+    }
+    if candidate.contains(query) {
+        return 60;
+    }
+
+    // Subsequence match, penalised by the characters skipped between hits
+    let mut chars = candidate.chars();
+    let mut skipped = 0;
+    for q in query.chars() {
+        loop {
+            match chars.next() {
+                Some(c) if c == q => break,
+                Some(_) => skipped += 1,
+                None => return 0,
+            }
+        }
+    }
+    40u32.saturating_sub(skipped * 5).max(5)
 }
 
 /// Get snippets by category
@@ -314,9 +399,12 @@ pub fn get_snippets_by_category(category: &str) -> Vec<CodeSnippet> {
         .collect()
 }
 
-/// Get snippet by ID
+/// Get snippet by ID, built-in or user-defined
 pub fn get_snippet_by_id(id: &str) -> Option<CodeSnippet> {
-    get_snippets().into_iter().find(|s| s.id == id)
+    get_snippets()
+        .into_iter()
+        .find(|s| s.id == id)
+        .or_else(|| user::get_user_snippet(id))
 }
 
 /// Get all categories
@@ -346,6 +434,18 @@ mod tests {
         assert!(!results.is_empty());
     }
 
+    #[test]
+    fn test_fuzzy_ranking() {
+        assert_eq!(fuzzy_score("gpio", "gpio"), 100);
+        assert_eq!(fuzzy_score("sys", "systick"), 80);
+        assert!(fuzzy_score("stck", "systick") > 0);
+        assert_eq!(fuzzy_score("xyz", "systick"), 0);
+
+        let results = rank_snippets(get_snippets(), "gpio input");
+        assert_eq!(results[0].id, "gpio-input");
+        assert!(results.iter().all(|s| s.id != "systick-delay"));
+    }
+
     #[test]
     fn test_get_categories() {
         let categories = get_snippet_categories();
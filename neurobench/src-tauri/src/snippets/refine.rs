@@ -0,0 +1,149 @@
+// AI Snippet Refinement
+// Targeted AI edits to existing snippets and generation of new ones
+
+use super::CodeSnippet;
+use crate::agents::diff_engine::unified_diff;
+use crate::ai::AIService;
+use serde::{Deserialize, Serialize};
+
+/// Result of refining a snippet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefinedSnippet {
+    pub original_code: String,
+    pub refined_code: String,
+    pub changes_summary: String,
+    pub diff: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AiRefinement {
+    refined_code: String,
+    #[serde(default)]
+    changes_summary: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AiSnippet {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    code: String,
+}
+
+/// Apply `instruction` to a snippet with as few changes as possible
+pub async fn refine_snippet(
+    snippet: &CodeSnippet,
+    instruction: &str,
+    ai_service: &AIService,
+) -> Result<RefinedSnippet, String> {
+    if !ai_service.is_available() {
+        return Err("AI not available. Set GEMINI_API_KEY.".to_string());
+    }
+
+    let message = format!(
+        r#"Modify the {language} snippet "{name}" according to this instruction: {instruction}
+
+Make minimal, targeted changes. Keep the existing structure, names, formatting and comments
+unless the instruction requires changing them. Do not rewrite unrelated code.
+
+Respond with JSON only, using this exact structure:
+{{
+  "refined_code": "the complete updated snippet",
+  "changes_summary": "one or two sentences describing what changed"
+}}"#,
+        language = snippet.language,
+        name = snippet.name,
+        instruction = instruction,
+    );
+    let context = format!("Snippet code:\n```{}\n{}\n```", snippet.language, snippet.code);
+
+    let response = ai_service.chat(&message, Some(&context)).await?;
+    let refinement: AiRefinement = parse_json_response(&response)?;
+
+    Ok(RefinedSnippet {
+        diff: unified_diff(&snippet.id, &snippet.code, &refinement.refined_code),
+        original_code: snippet.code.clone(),
+        refined_code: refinement.refined_code,
+        changes_summary: refinement.changes_summary,
+    })
+}
+
+/// Generate a new, unsaved snippet from a description
+pub async fn generate_snippet(
+    description: &str,
+    language: &str,
+    mcu_family: &str,
+    ai_service: &AIService,
+) -> Result<CodeSnippet, String> {
+    if !ai_service.is_available() {
+        return Err("AI not available. Set GEMINI_API_KEY.".to_string());
+    }
+
+    let message = format!(
+        r#"Write a short, reusable {language} code snippet for a {mcu} microcontroller that does the following: {description}
+
+Keep it self-contained and comment non-obvious register accesses.
+
+Respond with JSON only, using this exact structure:
+{{
+  "name": "short title",
+  "description": "one-line description",
+  "category": "GPIO|Timer|UART|SPI|I2C|ADC|DMA|Interrupt|Utility",
+  "tags": ["lowercase", "keywords"],
+  "code": "the snippet source"
+}}"#,
+        language = language,
+        mcu = mcu_family,
+        description = description,
+    );
+
+    let response = ai_service.chat(&message, None).await?;
+    let generated: AiSnippet = parse_json_response(&response)?;
+
+    let mut tags = generated.tags;
+    tags.push(mcu_family.to_lowercase());
+    Ok(CodeSnippet {
+        id: String::new(),
+        name: generated.name,
+        description: generated.description,
+        category: generated.category.unwrap_or_else(|| "User".to_string()),
+        language: language.to_string(),
+        code: generated.code,
+        tags,
+        is_user_defined: false,
+    })
+}
+
+/// Parse a JSON reply, tolerating a surrounding Markdown code fence
+fn parse_json_response<T: for<'de> Deserialize<'de>>(response: &str) -> Result<T, String> {
+    let json = response.trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim();
+    serde_json::from_str(json).map_err(|e| format!("Unexpected AI response: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_refinement_and_diff() {
+        let response = "```json\n{\"refined_code\": \"int x = 2;\\nint y = 1;\", \"changes_summary\": \"Set x to 2\"}\n```";
+        let refinement: AiRefinement = parse_json_response(response).unwrap();
+        assert_eq!(refinement.changes_summary, "Set x to 2");
+
+        let diff = unified_diff("gpio-output", "int x = 1;\nint y = 1;", &refinement.refined_code);
+        assert!(diff.starts_with("--- a/gpio-output\n+++ b/gpio-output\n@@"));
+        assert!(diff.contains("-int x = 1;\n+int x = 2;\n"));
+        assert!(!diff.contains("int y"));
+
+        assert!(unified_diff("same", "a", "a").is_empty());
+        assert!(parse_json_response::<AiSnippet>("not json").is_err());
+    }
+}
@@ -0,0 +1,313 @@
+// User Snippets
+// SQLite-backed store for user-defined snippets with tags and JSON export
+
+use super::CodeSnippet;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use thiserror::Error;
+
+/// Row id of a user snippet; exposed to the frontend as `user-<id>`
+pub type SnippetId = i64;
+
+/// Prefix distinguishing user snippet ids from built-in ones
+pub const USER_ID_PREFIX: &str = "user-";
+
+/// Snippet store errors
+#[derive(Debug, Error)]
+pub enum DbError {
+    #[error("Snippet database is not open")]
+    NotOpen,
+
+    #[error("Snippet not found: {0}")]
+    NotFound(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+lazy_static::lazy_static! {
+    /// User snippet database opened at startup
+    static ref USER_SNIPPETS: Mutex<Option<UserSnippetStore>> = Mutex::new(None);
+}
+
+/// File written by `export_snippets`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnippetExport {
+    pub version: u32,
+    pub exported_at: String,
+    pub snippets: Vec<CodeSnippet>,
+}
+
+/// `user-<id>` for a row id
+pub fn user_snippet_id(id: SnippetId) -> String {
+    format!("{}{}", USER_ID_PREFIX, id)
+}
+
+/// Row id from a `user-<id>` snippet id
+pub fn parse_user_snippet_id(id: &str) -> Option<SnippetId> {
+    id.strip_prefix(USER_ID_PREFIX)?.parse().ok()
+}
+
+/// Lowercased, trimmed and deduplicated tags
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = tags.iter()
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    normalized.sort();
+    normalized.dedup();
+    normalized
+}
+
+/// User-defined snippets stored in SQLite
+pub struct UserSnippetStore {
+    conn: Connection,
+}
+
+impl UserSnippetStore {
+    /// Open (or create) the snippet database at `path`
+    pub fn open(path: &Path) -> Result<Self, DbError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, DbError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, DbError> {
+        conn.execute_batch(
+            "PRAGMA foreign_keys = ON;
+            CREATE TABLE IF NOT EXISTS snippets (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                description TEXT NOT NULL,
+                category TEXT NOT NULL,
+                language TEXT NOT NULL,
+                code TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS snippet_tags (
+                snippet_id INTEGER NOT NULL REFERENCES snippets(id) ON DELETE CASCADE,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (snippet_id, tag)
+            );
+            CREATE INDEX IF NOT EXISTS snippet_tags_tag ON snippet_tags(tag);",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// Store a new snippet; its `id` and `is_user_defined` are ignored
+    pub fn add(&self, snippet: CodeSnippet) -> Result<SnippetId, DbError> {
+        self.conn.execute(
+            "INSERT INTO snippets (name, description, category, language, code, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![snippet.name, snippet.description, snippet.category, snippet.language, snippet.code, Utc::now().timestamp()],
+        )?;
+        let id = self.conn.last_insert_rowid();
+        self.set_tags(id, &snippet.tags)?;
+        Ok(id)
+    }
+
+    /// Replace every field and the tags of an existing snippet
+    pub fn update(&self, id: SnippetId, snippet: CodeSnippet) -> Result<(), DbError> {
+        let changed = self.conn.execute(
+            "UPDATE snippets SET name = ?1, description = ?2, category = ?3, language = ?4, code = ?5, updated_at = ?6
+             WHERE id = ?7",
+            params![snippet.name, snippet.description, snippet.category, snippet.language, snippet.code, Utc::now().timestamp(), id],
+        )?;
+        if changed == 0 {
+            return Err(DbError::NotFound(user_snippet_id(id)));
+        }
+        self.set_tags(id, &snippet.tags)
+    }
+
+    pub fn delete(&self, id: SnippetId) -> Result<(), DbError> {
+        match self.conn.execute("DELETE FROM snippets WHERE id = ?1", params![id])? {
+            0 => Err(DbError::NotFound(user_snippet_id(id))),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn get(&self, id: SnippetId) -> Result<Option<CodeSnippet>, DbError> {
+        let snippet = self.conn.query_row(
+            "SELECT id, name, description, category, language, code FROM snippets WHERE id = ?1",
+            params![id],
+            Self::row_to_snippet,
+        ).optional()?;
+        snippet.map(|s| self.with_tags(s)).transpose()
+    }
+
+    /// All user snippets, most recently edited first
+    pub fn list(&self) -> Result<Vec<CodeSnippet>, DbError> {
+        self.query_snippets(
+            "SELECT id, name, description, category, language, code FROM snippets ORDER BY updated_at DESC, id DESC",
+            [],
+        )
+    }
+
+    /// Snippets carrying `tag`, compared case-insensitively
+    pub fn list_by_tag(&self, tag: &str) -> Result<Vec<CodeSnippet>, DbError> {
+        self.query_snippets(
+            "SELECT s.id, s.name, s.description, s.category, s.language, s.code
+             FROM snippets s JOIN snippet_tags t ON t.snippet_id = s.id
+             WHERE t.tag = ?1 ORDER BY s.updated_at DESC, s.id DESC",
+            params![tag.trim().to_lowercase()],
+        )
+    }
+
+    fn set_tags(&self, id: SnippetId, tags: &[String]) -> Result<(), DbError> {
+        self.conn.execute("DELETE FROM snippet_tags WHERE snippet_id = ?1", params![id])?;
+        for tag in normalize_tags(tags) {
+            self.conn.execute("INSERT INTO snippet_tags (snippet_id, tag) VALUES (?1, ?2)", params![id, tag])?;
+        }
+        Ok(())
+    }
+
+    fn with_tags(&self, mut snippet: CodeSnippet) -> Result<CodeSnippet, DbError> {
+        let id = parse_user_snippet_id(&snippet.id).unwrap_or_default();
+        let mut stmt = self.conn.prepare("SELECT tag FROM snippet_tags WHERE snippet_id = ?1 ORDER BY tag")?;
+        snippet.tags = stmt.query_map(params![id], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(snippet)
+    }
+
+    fn query_snippets(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<CodeSnippet>, DbError> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let snippets = stmt.query_map(params, Self::row_to_snippet)?
+            .collect::<Result<Vec<_>, _>>()?;
+        snippets.into_iter().map(|s| self.with_tags(s)).collect()
+    }
+
+    fn row_to_snippet(row: &rusqlite::Row) -> rusqlite::Result<CodeSnippet> {
+        Ok(CodeSnippet {
+            id: user_snippet_id(row.get(0)?),
+            name: row.get(1)?,
+            description: row.get(2)?,
+            category: row.get(3)?,
+            language: row.get(4)?,
+            code: row.get(5)?,
+            tags: Vec::new(),
+            is_user_defined: true,
+        })
+    }
+}
+
+/// Write snippets to a JSON file for sharing
+pub fn export_snippets(snippets: Vec<CodeSnippet>, path: &Path) -> Result<usize, DbError> {
+    let count = snippets.len();
+    let export = SnippetExport {
+        version: 1,
+        exported_at: Utc::now().to_rfc3339(),
+        snippets,
+    };
+    std::fs::write(path, serde_json::to_string_pretty(&export)?)?;
+    Ok(count)
+}
+
+// ==================== Shared Store ====================
+
+/// Default snippet database in the app data directory
+pub fn default_snippets_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("neurobench")
+        .join("snippets.db")
+}
+
+/// Open the shared snippet database
+pub fn open_user_snippets() {
+    let path = default_snippets_path();
+    match UserSnippetStore::open(&path) {
+        Ok(store) => *USER_SNIPPETS.lock().unwrap() = Some(store),
+        Err(e) => log::warn!("Failed to open user snippets {}: {}", path.display(), e),
+    }
+}
+
+/// Run `f` against the shared store
+pub fn with_store<T>(f: impl FnOnce(&UserSnippetStore) -> Result<T, DbError>) -> Result<T, DbError> {
+    match USER_SNIPPETS.lock().unwrap().as_ref() {
+        Some(store) => f(store),
+        None => Err(DbError::NotOpen),
+    }
+}
+
+/// User snippets from the shared store, empty when it is not open
+pub fn list_user_snippets() -> Vec<CodeSnippet> {
+    with_store(|store| store.list()).unwrap_or_default()
+}
+
+/// A user snippet by its `user-<id>` id
+pub fn get_user_snippet(id: &str) -> Option<CodeSnippet> {
+    let id = parse_user_snippet_id(id)?;
+    with_store(|store| store.get(id)).ok().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(name: &str, tags: &[&str]) -> CodeSnippet {
+        CodeSnippet {
+            id: String::new(),
+            name: name.to_string(),
+            description: String::new(),
+            category: "User".to_string(),
+            language: "c".to_string(),
+            code: "void f(void) {}".to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            is_user_defined: false,
+        }
+    }
+
+    #[test]
+    fn test_user_snippet_crud() {
+        let store = UserSnippetStore::open_in_memory().unwrap();
+        let id = store.add(snippet("Debounce", &["GPIO", "button", "gpio"])).unwrap();
+        store.add(snippet("CRC8", &["checksum"])).unwrap();
+
+        let saved = store.get(id).unwrap().unwrap();
+        assert_eq!(saved.id, user_snippet_id(id));
+        assert!(saved.is_user_defined);
+        assert_eq!(saved.tags, vec!["button", "gpio"]);
+        assert_eq!(store.list_by_tag("Gpio").unwrap().len(), 1);
+
+        store.update(id, snippet("Debounce v2", &["input"])).unwrap();
+        assert!(store.list_by_tag("gpio").unwrap().is_empty());
+        assert_eq!(store.list_by_tag("input").unwrap()[0].name, "Debounce v2");
+
+        store.delete(id).unwrap();
+        assert!(store.get(id).unwrap().is_none());
+        assert!(matches!(store.delete(id), Err(DbError::NotFound(_))));
+        assert_eq!(store.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_export_and_ranking() {
+        let store = UserSnippetStore::open_in_memory().unwrap();
+        store.add(snippet("Button Debounce", &["gpio", "debounce"])).unwrap();
+        let user = store.list().unwrap();
+
+        let ranked = crate::snippets::rank_snippets(user.clone(), "debounce");
+        assert_eq!(ranked[0].name, "Button Debounce");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snippets.json");
+        assert_eq!(export_snippets(user, &path).unwrap(), 1);
+        let export: SnippetExport = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(export.snippets[0].tags, vec!["debounce", "gpio"]);
+        assert_eq!(parse_user_snippet_id("user-42"), Some(42));
+        assert_eq!(parse_user_snippet_id("gpio-output"), None);
+    }
+}
@@ -1,8 +1,36 @@
 // Project Templates Module
 // Pre-built starter projects for common embedded scenarios
 
+use crate::commands::project::{ProjectData, CURRENT_SCHEMA_VERSION};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
+use thiserror::Error;
+
+/// Template instantiation errors
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error("Template '{0}' not found")]
+    NotFound(String),
+
+    #[error("Missing template parameter: {0}")]
+    MissingParam(String),
+
+    #[error("Unclosed block in {file}: {block}")]
+    UnclosedBlock { file: String, block: String },
+
+    #[error("Invalid condition in {file}: {condition}")]
+    InvalidCondition { file: String, condition: String },
+
+    #[error("File already exists: {0}")]
+    FileExists(String),
+
+    #[error("Path escapes the project directory: {0}")]
+    UnsafePath(String),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
 
 /// Project template definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +43,17 @@ pub struct ProjectTemplate {
     pub files: Vec<TemplateFile>,
     pub dependencies: Vec<String>,
     pub difficulty: String,  // beginner, intermediate, advanced
+    /// Placeholders the template accepts, with defaults
+    #[serde(default)]
+    pub params: Vec<TemplateParam>,
+}
+
+/// Template parameter, referenced as `{{NAME}}` or `{{#IF NAME}}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateParam {
+    pub name: String,
+    pub description: String,
+    pub default: Option<String>,
 }
 
 /// Template file
@@ -23,6 +62,9 @@ pub struct TemplateFile {
     pub path: String,
     pub content: String,
     pub description: String,
+    /// Only write the file when this holds, e.g. `use_rtos == "true"`
+    #[serde(default)]
+    pub condition: Option<String>,
 }
 
 /// Get all available templates
@@ -36,11 +78,13 @@ pub fn get_templates() -> Vec<ProjectTemplate> {
             category: "Basic".to_string(),
             mcu_targets: vec!["STM32F4".to_string(), "STM32F1".to_string(), "ESP32".to_string()],
             difficulty: "beginner".to_string(),
+            params: vec![],
             dependencies: vec![],
             files: vec![
                 TemplateFile {
                     path: "main.c".to_string(),
                     description: "Main application".to_string(),
+                    condition: None,
                     content: r#"/**
  * Blinky LED Example
  * Classic LED toggle demonstrating GPIO control
@@ -93,11 +137,13 @@ int main(void) {
             category: "Communication".to_string(),
             mcu_targets: vec!["STM32F4".to_string(), "STM32F1".to_string()],
             difficulty: "beginner".to_string(),
+            params: vec![],
             dependencies: vec![],
             files: vec![
                 TemplateFile {
                     path: "main.c".to_string(),
                     description: "Main application".to_string(),
+                    condition: None,
                     content: r#"/**
  * UART Echo Example
  * Echoes back any received serial data
@@ -165,11 +211,13 @@ int main(void) {
             category: "RTOS".to_string(),
             mcu_targets: vec!["STM32F4".to_string()],
             difficulty: "intermediate".to_string(),
+            params: vec![],
             dependencies: vec!["FreeRTOS".to_string()],
             files: vec![
                 TemplateFile {
                     path: "main.c".to_string(),
                     description: "Main application with RTOS".to_string(),
+                    condition: None,
                     content: r#"/**
  * FreeRTOS Multi-LED Blinky
  * Demonstrates task creation and scheduling
@@ -226,11 +274,13 @@ int main(void) {
             category: "Analog".to_string(),
             mcu_targets: vec!["STM32F4".to_string()],
             difficulty: "beginner".to_string(),
+            params: vec![],
             dependencies: vec![],
             files: vec![
                 TemplateFile {
                     path: "main.c".to_string(),
                     description: "ADC reading example".to_string(),
+                    condition: None,
                     content: r#"/**
  * ADC Reading Example
  * Read analog sensor values
@@ -282,11 +332,13 @@ int main(void) {
             category: "Timers".to_string(),
             mcu_targets: vec!["STM32F4".to_string()],
             difficulty: "intermediate".to_string(),
+            params: vec![],
             dependencies: vec![],
             files: vec![
                 TemplateFile {
                     path: "main.c".to_string(),
                     description: "PWM output example".to_string(),
+                    condition: None,
                     content: r#"/**
  * PWM Output Example
  * Generate PWM signal for motor/LED control
@@ -350,11 +402,13 @@ int main(void) {
             category: "Communication".to_string(),
             mcu_targets: vec!["STM32F4".to_string()],
             difficulty: "intermediate".to_string(),
+            params: vec![],
             dependencies: vec![],
             files: vec![
                 TemplateFile {
                     path: "main.c".to_string(),
                     description: "I2C sensor reading".to_string(),
+                    condition: None,
                     content: r#"/**
  * I2C Sensor Example
  * Read data from I2C devices
@@ -415,6 +469,734 @@ int main(void) {
     
     return 0;
 }
+"#.to_string(),
+                },
+            ],
+        },
+
+        // UART Blink (parameterized)
+        ProjectTemplate {
+            id: "uart-blink".to_string(),
+            name: "UART Blink".to_string(),
+            description: "LED blink with status messages over UART, optionally as FreeRTOS tasks".to_string(),
+            category: "Communication".to_string(),
+            mcu_targets: vec!["STM32F4".to_string()],
+            difficulty: "beginner".to_string(),
+            dependencies: vec![],
+            params: vec![
+                TemplateParam {
+                    name: "LED_PIN".to_string(),
+                    description: "GPIOC pin driving the LED".to_string(),
+                    default: Some("13".to_string()),
+                },
+                TemplateParam {
+                    name: "BAUDRATE".to_string(),
+                    description: "USART2 baud rate".to_string(),
+                    default: Some("115200".to_string()),
+                },
+                TemplateParam {
+                    name: "BLINK_MS".to_string(),
+                    description: "LED toggle period in milliseconds".to_string(),
+                    default: Some("500".to_string()),
+                },
+                TemplateParam {
+                    name: "USE_RTOS".to_string(),
+                    description: "Run blink and UART as FreeRTOS tasks".to_string(),
+                    default: Some("false".to_string()),
+                },
+            ],
+            files: vec![
+                TemplateFile {
+                    path: "src/main.c".to_string(),
+                    description: "Main application".to_string(),
+                    condition: None,
+                    content: r#"/**
+ * {{PROJECT_NAME}}
+ * LED blink on PC{{LED_PIN}} with UART status at {{BAUDRATE}} baud
+ */
+
+#include "stm32f4xx.h"
+{{#IF USE_RTOS}}
+#include "FreeRTOS.h"
+#include "task.h"
+{{/IF}}
+
+#define LED_PIN     {{LED_PIN}}
+#define BAUDRATE    {{BAUDRATE}}
+#define APB1_CLOCK  42000000
+
+static void gpio_init(void) {
+    RCC->AHB1ENR |= RCC_AHB1ENR_GPIOCEN;
+    GPIOC->MODER &= ~(3U << (LED_PIN * 2));
+    GPIOC->MODER |= (1U << (LED_PIN * 2));
+}
+
+static void uart_init(void) {
+    RCC->APB1ENR |= RCC_APB1ENR_USART2EN;
+    RCC->AHB1ENR |= RCC_AHB1ENR_GPIOAEN;
+    GPIOA->MODER |= (2U << 4) | (2U << 6);
+    GPIOA->AFR[0] |= (7U << 8) | (7U << 12);
+    USART2->BRR = APB1_CLOCK / BAUDRATE;
+    USART2->CR1 = USART_CR1_TE | USART_CR1_RE | USART_CR1_UE;
+}
+
+static void uart_print(const char* str) {
+    while (*str) {
+        while (!(USART2->SR & USART_SR_TXE));
+        USART2->DR = *str++;
+    }
+}
+
+{{#IF USE_RTOS}}
+static void blink_task(void* pvParameters) {
+    while (1) {
+        GPIOC->ODR ^= (1U << LED_PIN);
+        uart_print("toggle\r\n");
+        vTaskDelay(pdMS_TO_TICKS({{BLINK_MS}}));
+    }
+}
+
+int main(void) {
+    gpio_init();
+    uart_init();
+    uart_print("{{PROJECT_NAME}} ready\r\n");
+
+    xTaskCreate(blink_task, "BLINK", 128, NULL, 1, NULL);
+    vTaskStartScheduler();
+
+    while (1);
+}
+{{#ELSE}}
+static void delay_ms(uint32_t ms) {
+    for (volatile uint32_t i = 0; i < ms * 4000; i++);
+}
+
+int main(void) {
+    gpio_init();
+    uart_init();
+    uart_print("{{PROJECT_NAME}} ready\r\n");
+
+    while (1) {
+        GPIOC->ODR ^= (1U << LED_PIN);
+        uart_print("toggle\r\n");
+        delay_ms({{BLINK_MS}});
+    }
+}
+{{/IF}}
+"#.to_string(),
+                },
+                TemplateFile {
+                    path: "{{#IF USE_RTOS}}inc/FreeRTOSConfig.h{{/IF}}".to_string(),
+                    description: "FreeRTOS kernel configuration".to_string(),
+                    condition: None,
+                    content: r#"#ifndef FREERTOS_CONFIG_H
+#define FREERTOS_CONFIG_H
+
+#define configUSE_PREEMPTION            1
+#define configCPU_CLOCK_HZ              ((unsigned long)84000000)
+#define configTICK_RATE_HZ              ((TickType_t)1000)
+#define configMAX_PRIORITIES            5
+#define configMINIMAL_STACK_SIZE        ((unsigned short)128)
+#define configTOTAL_HEAP_SIZE           ((size_t)(16 * 1024))
+#define configUSE_IDLE_HOOK             0
+#define configUSE_TICK_HOOK             0
+#define configUSE_16_BIT_TICKS          0
+#define INCLUDE_vTaskDelay              1
+
+#endif /* FREERTOS_CONFIG_H */
+"#.to_string(),
+                },
+            ],
+        },
+
+        // Bare-metal STM32 (CMSIS only)
+        ProjectTemplate {
+            id: "bare_metal_stm32".to_string(),
+            name: "Bare-Metal STM32".to_string(),
+            description: "CMSIS-only STM32 project with startup code, linker script and Makefile".to_string(),
+            category: "Project".to_string(),
+            mcu_targets: vec!["STM32F4".to_string()],
+            difficulty: "intermediate".to_string(),
+            dependencies: vec!["CMSIS device headers".to_string()],
+            params: stm32_params(),
+            files: vec![
+                TemplateFile {
+                    path: "src/main.c".to_string(),
+                    description: "SysTick time base and LED blink".to_string(),
+                    condition: None,
+                    content: r#"/**
+ * {{PROJECT_NAME}}
+ * Bare-metal {{MCU}} starter: SysTick time base and LED on P{{LED_PORT}}{{LED_PIN}}
+ */
+
+#include "board.h"
+
+static volatile uint32_t ticks_ms;
+
+void SysTick_Handler(void) {
+    ticks_ms++;
+}
+
+static void delay_ms(uint32_t ms) {
+    uint32_t start = ticks_ms;
+    while ((ticks_ms - start) < ms) {
+        __WFI();
+    }
+}
+
+int main(void) {
+    SysTick_Config(SYSTEM_CLOCK_HZ / 1000);
+    board_led_init();
+
+    while (1) {
+        board_led_toggle();
+        delay_ms({{BLINK_MS}});
+    }
+}
+"#.to_string(),
+                },
+                stm32_board_header(),
+                stm32_startup(),
+                stm32_linker_script(),
+                TemplateFile {
+                    path: "Makefile".to_string(),
+                    description: "GNU Make build for arm-none-eabi-gcc".to_string(),
+                    condition: None,
+                    content: r#"# {{PROJECT_NAME}} - {{MCU}}
+
+CC      = arm-none-eabi-gcc
+OBJCOPY = arm-none-eabi-objcopy
+SIZE    = arm-none-eabi-size
+
+CPU     = -mcpu=cortex-m4 -mthumb -mfpu=fpv4-sp-d16 -mfloat-abi=hard
+CFLAGS  = $(CPU) -Og -g3 -Wall -Wextra -ffunction-sections -fdata-sections -D{{MCU}} -Iinc
+LDFLAGS = $(CPU) -T{{MCU}}_FLASH.ld -Wl,--gc-sections -specs=nano.specs -specs=nosys.specs
+
+SRCS = src/main.c src/startup.c
+OBJS = $(SRCS:%.c=build/%.o)
+
+all: build/firmware.bin
+
+build/%.o: %.c
+	@mkdir -p $(dir $@)
+	$(CC) $(CFLAGS) -c $< -o $@
+
+build/firmware.elf: $(OBJS)
+	$(CC) $(LDFLAGS) $^ -o $@
+	$(SIZE) $@
+
+build/firmware.bin: build/firmware.elf
+	$(OBJCOPY) -O binary $< $@
+
+clean:
+	rm -rf build
+
+.PHONY: all clean
+"#.to_string(),
+                },
+            ],
+        },
+
+        // FreeRTOS on STM32
+        ProjectTemplate {
+            id: "freertos_stm32".to_string(),
+            name: "FreeRTOS STM32".to_string(),
+            description: "STM32 CMake project with FreeRTOS tasks, or a superloop when the RTOS is disabled".to_string(),
+            category: "Project".to_string(),
+            mcu_targets: vec!["STM32F4".to_string()],
+            difficulty: "intermediate".to_string(),
+            dependencies: vec!["CMSIS device headers".to_string(), "FreeRTOS-Kernel in lib/".to_string()],
+            params: {
+                let mut params = stm32_params();
+                params.push(TemplateParam {
+                    name: "USE_RTOS".to_string(),
+                    description: "Build with the FreeRTOS kernel".to_string(),
+                    default: Some("true".to_string()),
+                });
+                params.push(TemplateParam {
+                    name: "HEAP_KB".to_string(),
+                    description: "FreeRTOS heap size in KB".to_string(),
+                    default: Some("16".to_string()),
+                });
+                params
+            },
+            files: vec![
+                TemplateFile {
+                    path: "src/main.c".to_string(),
+                    description: "Blink task".to_string(),
+                    condition: None,
+                    content: r#"/**
+ * {{PROJECT_NAME}}
+ * {{MCU}} LED blink on P{{LED_PORT}}{{LED_PIN}}
+ */
+
+#include "board.h"
+{{#IF USE_RTOS}}
+#include "FreeRTOS.h"
+#include "task.h"
+
+static void blink_task(void *arg) {
+    (void)arg;
+    for (;;) {
+        board_led_toggle();
+        vTaskDelay(pdMS_TO_TICKS({{BLINK_MS}}));
+    }
+}
+
+int main(void) {
+    board_led_init();
+    xTaskCreate(blink_task, "blink", configMINIMAL_STACK_SIZE, NULL, tskIDLE_PRIORITY + 1, NULL);
+    vTaskStartScheduler();
+
+    /* Only reached if the idle task could not be created */
+    while (1);
+}
+{{#ELSE}}
+
+static volatile uint32_t ticks_ms;
+
+void SysTick_Handler(void) {
+    ticks_ms++;
+}
+
+int main(void) {
+    SysTick_Config(SYSTEM_CLOCK_HZ / 1000);
+    board_led_init();
+
+    uint32_t last = 0;
+    while (1) {
+        if ((ticks_ms - last) >= {{BLINK_MS}}) {
+            last = ticks_ms;
+            board_led_toggle();
+        }
+    }
+}
+{{/IF}}
+"#.to_string(),
+                },
+                stm32_board_header(),
+                stm32_startup(),
+                stm32_linker_script(),
+                TemplateFile {
+                    path: "inc/FreeRTOSConfig.h".to_string(),
+                    description: "FreeRTOS kernel configuration".to_string(),
+                    condition: Some("use_rtos == \"true\"".to_string()),
+                    content: r#"#ifndef FREERTOS_CONFIG_H
+#define FREERTOS_CONFIG_H
+
+#include "board.h"
+
+#define configUSE_PREEMPTION                    1
+#define configCPU_CLOCK_HZ                      SYSTEM_CLOCK_HZ
+#define configTICK_RATE_HZ                      ((TickType_t)1000)
+#define configMAX_PRIORITIES                    5
+#define configMINIMAL_STACK_SIZE                ((uint16_t)128)
+#define configTOTAL_HEAP_SIZE                   ((size_t)({{HEAP_KB}} * 1024))
+#define configUSE_16_BIT_TICKS                  0
+#define configUSE_IDLE_HOOK                     0
+#define configUSE_TICK_HOOK                     0
+#define configUSE_MALLOC_FAILED_HOOK            1
+#define configCHECK_FOR_STACK_OVERFLOW          2
+#define configPRIO_BITS                         4
+#define configKERNEL_INTERRUPT_PRIORITY         (15 << (8 - configPRIO_BITS))
+#define configMAX_SYSCALL_INTERRUPT_PRIORITY    (5 << (8 - configPRIO_BITS))
+
+#define INCLUDE_vTaskDelay                      1
+#define INCLUDE_vTaskDelete                     1
+
+/* Route the Cortex-M exception handlers to the port layer */
+#define vPortSVCHandler     SVC_Handler
+#define xPortPendSVHandler  PendSV_Handler
+#define xPortSysTickHandler SysTick_Handler
+
+#endif /* FREERTOS_CONFIG_H */
+"#.to_string(),
+                },
+                TemplateFile {
+                    path: "src/freertos_hooks.c".to_string(),
+                    description: "FreeRTOS failure hooks".to_string(),
+                    condition: Some("use_rtos == \"true\"".to_string()),
+                    content: r#"#include "FreeRTOS.h"
+#include "task.h"
+
+void vApplicationMallocFailedHook(void) {
+    taskDISABLE_INTERRUPTS();
+    for (;;);
+}
+
+void vApplicationStackOverflowHook(TaskHandle_t task, char *name) {
+    (void)task;
+    (void)name;
+    taskDISABLE_INTERRUPTS();
+    for (;;);
+}
+"#.to_string(),
+                },
+                TemplateFile {
+                    path: "CMakeLists.txt".to_string(),
+                    description: "CMake build, configure with the arm-none-eabi toolchain file".to_string(),
+                    condition: None,
+                    content: r#"cmake_minimum_required(VERSION 3.20)
+project({{PROJECT_ID}} C ASM)
+
+set(CMAKE_C_STANDARD 11)
+set(MCU_FLAGS -mcpu=cortex-m4 -mthumb -mfpu=fpv4-sp-d16 -mfloat-abi=hard)
+
+add_executable(firmware
+    src/main.c
+    src/startup.c
+{{#IF USE_RTOS}}
+    src/freertos_hooks.c
+{{/IF}}
+)
+target_include_directories(firmware PRIVATE inc)
+target_compile_definitions(firmware PRIVATE {{MCU}})
+target_compile_options(firmware PRIVATE ${MCU_FLAGS} -Wall -Wextra -ffunction-sections -fdata-sections)
+target_link_options(firmware PRIVATE ${MCU_FLAGS} -T${CMAKE_SOURCE_DIR}/{{MCU}}_FLASH.ld -Wl,--gc-sections -specs=nano.specs -specs=nosys.specs)
+{{#IF USE_RTOS}}
+
+add_library(freertos_config INTERFACE)
+target_include_directories(freertos_config SYSTEM INTERFACE inc)
+set(FREERTOS_PORT GCC_ARM_CM4F CACHE STRING "")
+set(FREERTOS_HEAP 4 CACHE STRING "")
+add_subdirectory(lib/FreeRTOS-Kernel)
+target_link_libraries(firmware PRIVATE freertos_kernel)
+{{/IF}}
+"#.to_string(),
+                },
+            ],
+        },
+
+        // ESP-IDF project
+        ProjectTemplate {
+            id: "esp_idf_project".to_string(),
+            name: "ESP-IDF Project".to_string(),
+            description: "ESP-IDF application with a FreeRTOS blink task and per-target sdkconfig defaults".to_string(),
+            category: "Project".to_string(),
+            mcu_targets: vec!["ESP32".to_string(), "ESP32S3".to_string(), "ESP32C3".to_string()],
+            difficulty: "beginner".to_string(),
+            dependencies: vec!["ESP-IDF v5".to_string()],
+            params: vec![
+                TemplateParam {
+                    name: "MCU".to_string(),
+                    description: "IDF target: ESP32, ESP32S3 or ESP32C3".to_string(),
+                    default: Some("ESP32".to_string()),
+                },
+                TemplateParam {
+                    name: "LED_GPIO".to_string(),
+                    description: "GPIO driving the LED".to_string(),
+                    default: Some("2".to_string()),
+                },
+                TemplateParam {
+                    name: "BLINK_MS".to_string(),
+                    description: "LED toggle period in milliseconds".to_string(),
+                    default: Some("500".to_string()),
+                },
+            ],
+            files: vec![
+                TemplateFile {
+                    path: "CMakeLists.txt".to_string(),
+                    description: "IDF project file".to_string(),
+                    condition: None,
+                    content: r#"cmake_minimum_required(VERSION 3.16)
+include($ENV{IDF_PATH}/tools/cmake/project.cmake)
+project({{PROJECT_ID}})
+"#.to_string(),
+                },
+                TemplateFile {
+                    path: "main/CMakeLists.txt".to_string(),
+                    description: "Main component".to_string(),
+                    condition: None,
+                    content: "idf_component_register(SRCS \"main.c\" INCLUDE_DIRS \".\")\n".to_string(),
+                },
+                TemplateFile {
+                    path: "main/main.c".to_string(),
+                    description: "Blink task".to_string(),
+                    condition: None,
+                    content: r#"/**
+ * {{PROJECT_NAME}}
+ * {{MCU}} LED blink on GPIO{{LED_GPIO}}
+ */
+
+#include "freertos/FreeRTOS.h"
+#include "freertos/task.h"
+#include "driver/gpio.h"
+#include "esp_log.h"
+
+#define LED_GPIO  GPIO_NUM_{{LED_GPIO}}
+
+static const char *TAG = "{{PROJECT_ID}}";
+
+static void blink_task(void *arg) {
+    int level = 0;
+    for (;;) {
+        level = !level;
+        gpio_set_level(LED_GPIO, level);
+        vTaskDelay(pdMS_TO_TICKS({{BLINK_MS}}));
+    }
+}
+
+void app_main(void) {
+    gpio_reset_pin(LED_GPIO);
+    gpio_set_direction(LED_GPIO, GPIO_MODE_OUTPUT);
+    ESP_LOGI(TAG, "started");
+    xTaskCreate(blink_task, "blink", 2048, NULL, 5, NULL);
+}
+"#.to_string(),
+                },
+                TemplateFile {
+                    path: "sdkconfig.defaults".to_string(),
+                    description: "Defaults for all targets".to_string(),
+                    condition: None,
+                    content: "CONFIG_FREERTOS_HZ=1000\nCONFIG_ESPTOOLPY_FLASHSIZE_4MB=y\n".to_string(),
+                },
+                TemplateFile {
+                    path: "sdkconfig.defaults.{{MCU_LOWER}}".to_string(),
+                    description: "Target-specific defaults".to_string(),
+                    condition: None,
+                    content: "CONFIG_IDF_TARGET=\"{{MCU_LOWER}}\"\n".to_string(),
+                },
+            ],
+        },
+
+        // Embedded Rust on RP2040
+        ProjectTemplate {
+            id: "rust_embedded_rp2040".to_string(),
+            name: "Embedded Rust RP2040".to_string(),
+            description: "no_std Rust blinky for the Raspberry Pi Pico using rp2040-hal and probe-rs".to_string(),
+            category: "Project".to_string(),
+            mcu_targets: vec!["RP2040".to_string()],
+            difficulty: "intermediate".to_string(),
+            dependencies: vec!["thumbv6m-none-eabi target".to_string(), "probe-rs".to_string()],
+            params: vec![
+                TemplateParam {
+                    name: "LED_PIN".to_string(),
+                    description: "GPIO driving the LED (25 on the Pico)".to_string(),
+                    default: Some("25".to_string()),
+                },
+                TemplateParam {
+                    name: "BLINK_MS".to_string(),
+                    description: "LED toggle period in milliseconds".to_string(),
+                    default: Some("500".to_string()),
+                },
+            ],
+            files: vec![
+                TemplateFile {
+                    path: "Cargo.toml".to_string(),
+                    description: "Crate manifest".to_string(),
+                    condition: None,
+                    content: r#"[package]
+name = "{{PROJECT_ID}}"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+cortex-m = "0.7"
+cortex-m-rt = "0.7"
+embedded-hal = "1.0"
+panic-halt = "1.0"
+rp2040-boot2 = "0.3"
+rp2040-hal = { version = "0.10", features = ["rt", "critical-section-impl"] }
+
+[profile.release]
+debug = 2
+lto = true
+opt-level = "s"
+"#.to_string(),
+                },
+                TemplateFile {
+                    path: ".cargo/config.toml".to_string(),
+                    description: "Target and probe-rs runner".to_string(),
+                    condition: None,
+                    content: r#"[build]
+target = "thumbv6m-none-eabi"
+
+[target.thumbv6m-none-eabi]
+runner = "probe-rs run --chip RP2040"
+rustflags = ["-C", "link-arg=--nmagic", "-C", "link-arg=-Tlink.x"]
+"#.to_string(),
+                },
+                TemplateFile {
+                    path: "memory.x".to_string(),
+                    description: "Memory layout with the boot2 section".to_string(),
+                    condition: None,
+                    content: r#"MEMORY {
+    BOOT2 : ORIGIN = 0x10000000, LENGTH = 0x100
+    FLASH : ORIGIN = 0x10000100, LENGTH = 2048K - 0x100
+    RAM   : ORIGIN = 0x20000000, LENGTH = 256K
+}
+
+EXTERN(BOOT2_FIRMWARE)
+
+SECTIONS {
+    .boot2 ORIGIN(BOOT2) :
+    {
+        KEEP(*(.boot2));
+    } > BOOT2
+} INSERT BEFORE .text;
+"#.to_string(),
+                },
+                TemplateFile {
+                    path: "build.rs".to_string(),
+                    description: "Puts memory.x on the linker search path".to_string(),
+                    condition: None,
+                    content: r#"use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+fn main() {
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    fs::write(out.join("memory.x"), include_bytes!("memory.x")).unwrap();
+    println!("cargo:rustc-link-search={}", out.display());
+    println!("cargo:rerun-if-changed=memory.x");
+}
+"#.to_string(),
+                },
+                TemplateFile {
+                    path: "src/main.rs".to_string(),
+                    description: "Blinky".to_string(),
+                    condition: None,
+                    content: r#"//! {{PROJECT_NAME}}
+//! RP2040 LED blink on GPIO{{LED_PIN}}
+
+#![no_std]
+#![no_main]
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::StatefulOutputPin;
+use panic_halt as _;
+use rp2040_hal::{self as hal, pac};
+
+/// Second-stage bootloader for the W25Q080 flash on the Pico
+#[link_section = ".boot2"]
+#[used]
+pub static BOOT2: [u8; 256] = rp2040_boot2::BOOT_LOADER_W25Q080;
+
+const XTAL_FREQ_HZ: u32 = 12_000_000;
+
+#[hal::entry]
+fn main() -> ! {
+    let mut pac = pac::Peripherals::take().unwrap();
+    let mut watchdog = hal::Watchdog::new(pac.WATCHDOG);
+    let clocks = hal::clocks::init_clocks_and_plls(
+        XTAL_FREQ_HZ,
+        pac.XOSC,
+        pac.CLOCKS,
+        pac.PLL_SYS,
+        pac.PLL_USB,
+        &mut pac.RESETS,
+        &mut watchdog,
+    )
+    .ok()
+    .unwrap();
+
+    let mut timer = hal::Timer::new(pac.TIMER, &mut pac.RESETS, &clocks);
+    let sio = hal::Sio::new(pac.SIO);
+    let pins = hal::gpio::Pins::new(pac.IO_BANK0, pac.PADS_BANK0, sio.gpio_bank0, &mut pac.RESETS);
+    let mut led = pins.gpio{{LED_PIN}}.into_push_pull_output();
+
+    loop {
+        led.toggle().unwrap();
+        timer.delay_ms({{BLINK_MS}});
+    }
+}
+"#.to_string(),
+                },
+            ],
+        },
+
+        // NeuroBench-managed project
+        ProjectTemplate {
+            id: "neurobench_managed".to_string(),
+            name: "NeuroBench Managed Project".to_string(),
+            description: "STM32 project laid out for the NeuroBench build, flash and clangd workflow, with a ready compile_commands.json".to_string(),
+            category: "Project".to_string(),
+            mcu_targets: vec!["STM32F4".to_string()],
+            difficulty: "beginner".to_string(),
+            dependencies: vec!["CMSIS device headers".to_string()],
+            params: stm32_params(),
+            files: vec![
+                TemplateFile {
+                    path: "src/main.c".to_string(),
+                    description: "Application entry".to_string(),
+                    condition: None,
+                    content: r#"/**
+ * {{PROJECT_NAME}}
+ * Built, flashed and indexed by NeuroBench
+ */
+
+#include "board.h"
+#include "app.h"
+
+static volatile uint32_t ticks_ms;
+
+void SysTick_Handler(void) {
+    ticks_ms++;
+}
+
+int main(void) {
+    SysTick_Config(SYSTEM_CLOCK_HZ / 1000);
+    board_led_init();
+    app_init();
+
+    while (1) {
+        app_step(ticks_ms);
+    }
+}
+"#.to_string(),
+                },
+                TemplateFile {
+                    path: "src/app.c".to_string(),
+                    description: "Application logic".to_string(),
+                    condition: None,
+                    content: r#"#include "app.h"
+#include "board.h"
+
+static uint32_t last_toggle;
+
+void app_init(void) {
+    last_toggle = 0;
+}
+
+void app_step(uint32_t now_ms) {
+    if ((now_ms - last_toggle) >= {{BLINK_MS}}) {
+        last_toggle = now_ms;
+        board_led_toggle();
+    }
+}
+"#.to_string(),
+                },
+                TemplateFile {
+                    path: "inc/app.h".to_string(),
+                    description: "Application interface".to_string(),
+                    condition: None,
+                    content: r#"#ifndef APP_H
+#define APP_H
+
+#include <stdint.h>
+
+void app_init(void);
+void app_step(uint32_t now_ms);
+
+#endif /* APP_H */
+"#.to_string(),
+                },
+                stm32_board_header(),
+                stm32_startup(),
+                stm32_linker_script(),
+                TemplateFile {
+                    path: "compile_commands.json".to_string(),
+                    description: "Compilation database for clangd, regenerated on build".to_string(),
+                    condition: None,
+                    content: "{{COMPILE_COMMANDS}}\n".to_string(),
+                },
+                TemplateFile {
+                    path: ".clangd".to_string(),
+                    description: "clangd settings for the cross compiler".to_string(),
+                    condition: None,
+                    content: r#"CompileFlags:
+  Remove: [-mfpu=*, -mfloat-abi=*]
+  Add: [--target=arm-none-eabi]
 "#.to_string(),
                 },
             ],
@@ -422,6 +1204,196 @@ int main(void) {
     ]
 }
 
+/// Parameters shared by the STM32 project templates
+fn stm32_params() -> Vec<TemplateParam> {
+    vec![
+        TemplateParam {
+            name: "MCU".to_string(),
+            description: "CMSIS device define, also names the linker script".to_string(),
+            default: Some("STM32F401xE".to_string()),
+        },
+        TemplateParam {
+            name: "DEVICE_HEADER".to_string(),
+            description: "CMSIS device header".to_string(),
+            default: Some("stm32f4xx.h".to_string()),
+        },
+        TemplateParam {
+            name: "FLASH_KB".to_string(),
+            description: "Flash size in KB".to_string(),
+            default: Some("512".to_string()),
+        },
+        TemplateParam {
+            name: "RAM_KB".to_string(),
+            description: "SRAM size in KB".to_string(),
+            default: Some("96".to_string()),
+        },
+        TemplateParam {
+            name: "LED_PORT".to_string(),
+            description: "GPIO port of the LED".to_string(),
+            default: Some("C".to_string()),
+        },
+        TemplateParam {
+            name: "LED_PIN".to_string(),
+            description: "GPIO pin of the LED".to_string(),
+            default: Some("13".to_string()),
+        },
+        TemplateParam {
+            name: "BLINK_MS".to_string(),
+            description: "LED toggle period in milliseconds".to_string(),
+            default: Some("500".to_string()),
+        },
+    ]
+}
+
+fn stm32_board_header() -> TemplateFile {
+    TemplateFile {
+        path: "inc/board.h".to_string(),
+        description: "Board definitions".to_string(),
+        condition: None,
+        content: r#"#ifndef BOARD_H
+#define BOARD_H
+
+#include "{{DEVICE_HEADER}}"
+
+/* HSI after reset; raise it here when configuring the PLL */
+#define SYSTEM_CLOCK_HZ  16000000U
+
+#define LED_GPIO         GPIO{{LED_PORT}}
+#define LED_PIN          {{LED_PIN}}U
+
+static inline void board_led_init(void) {
+    RCC->AHB1ENR |= RCC_AHB1ENR_GPIO{{LED_PORT}}EN;
+    LED_GPIO->MODER = (LED_GPIO->MODER & ~(3U << (LED_PIN * 2))) | (1U << (LED_PIN * 2));
+}
+
+static inline void board_led_toggle(void) {
+    LED_GPIO->ODR ^= (1U << LED_PIN);
+}
+
+#endif /* BOARD_H */
+"#.to_string(),
+    }
+}
+
+fn stm32_startup() -> TemplateFile {
+    TemplateFile {
+        path: "src/startup.c".to_string(),
+        description: "Vector table and reset handler".to_string(),
+        condition: None,
+        content: r#"/**
+ * Startup code for {{MCU}}
+ * Core exception vectors only; add peripheral IRQs after SysTick as needed.
+ */
+
+#include <stdint.h>
+
+extern uint32_t _estack, _sidata, _sdata, _edata, _sbss, _ebss;
+extern int main(void);
+
+void Reset_Handler(void);
+
+void Default_Handler(void) {
+    while (1);
+}
+
+void NMI_Handler(void)       __attribute__((weak, alias("Default_Handler")));
+void HardFault_Handler(void) __attribute__((weak, alias("Default_Handler")));
+void SVC_Handler(void)       __attribute__((weak, alias("Default_Handler")));
+void PendSV_Handler(void)    __attribute__((weak, alias("Default_Handler")));
+void SysTick_Handler(void)   __attribute__((weak, alias("Default_Handler")));
+
+__attribute__((section(".isr_vector"), used))
+static void (*const vectors[16])(void) = {
+    (void (*)(void))&_estack,
+    Reset_Handler,
+    NMI_Handler,
+    HardFault_Handler,
+    Default_Handler,    /* MemManage */
+    Default_Handler,    /* BusFault */
+    Default_Handler,    /* UsageFault */
+    0, 0, 0, 0,
+    SVC_Handler,
+    Default_Handler,    /* DebugMon */
+    0,
+    PendSV_Handler,
+    SysTick_Handler,
+};
+
+void Reset_Handler(void) {
+    uint32_t *src = &_sidata;
+    uint32_t *dst = &_sdata;
+
+    while (dst < &_edata) {
+        *dst++ = *src++;
+    }
+    for (dst = &_sbss; dst < &_ebss; ) {
+        *dst++ = 0;
+    }
+
+    /* Enable the FPU (CPACR CP10/CP11 full access) for the hard-float ABI */
+    *(volatile uint32_t *)0xE000ED88 |= (0xFU << 20);
+
+    main();
+    while (1);
+}
+"#.to_string(),
+    }
+}
+
+fn stm32_linker_script() -> TemplateFile {
+    TemplateFile {
+        path: "{{MCU}}_FLASH.ld".to_string(),
+        description: "Linker script".to_string(),
+        condition: None,
+        content: r#"/* {{MCU}} linker script - {{PROJECT_NAME}} */
+
+ENTRY(Reset_Handler)
+
+MEMORY
+{
+    FLASH (rx)  : ORIGIN = 0x08000000, LENGTH = {{FLASH_KB}}K
+    RAM   (rwx) : ORIGIN = 0x20000000, LENGTH = {{RAM_KB}}K
+}
+
+_estack = ORIGIN(RAM) + LENGTH(RAM);
+
+SECTIONS
+{
+    .isr_vector :
+    {
+        KEEP(*(.isr_vector))
+    } > FLASH
+
+    .text :
+    {
+        *(.text*)
+        *(.rodata*)
+        . = ALIGN(4);
+    } > FLASH
+
+    _sidata = LOADADDR(.data);
+
+    .data :
+    {
+        _sdata = .;
+        *(.data*)
+        . = ALIGN(4);
+        _edata = .;
+    } > RAM AT > FLASH
+
+    .bss (NOLOAD) :
+    {
+        _sbss = .;
+        *(.bss*)
+        *(COMMON)
+        . = ALIGN(4);
+        _ebss = .;
+    } > RAM
+}
+"#.to_string(),
+    }
+}
+
 /// Get templates by category
 pub fn get_templates_by_category(category: &str) -> Vec<ProjectTemplate> {
     get_templates()
@@ -446,6 +1418,313 @@ pub fn get_categories() -> Vec<String> {
     categories
 }
 
+// ==================== Instantiation ====================
+
+/// Create a project from a template in `output_dir`
+///
+/// `{{NAME}}` placeholders are replaced from `params` (case-insensitive, falling
+/// back to the template defaults) and `{{PROJECT_NAME}}` is always available.
+/// Files whose path renders empty are skipped, so a path wrapped in
+/// `{{#IF USE_RTOS}}...{{/IF}}` is only written when that flag is set.
+pub fn instantiate(
+    template_id: &str,
+    project_name: &str,
+    output_dir: &Path,
+    params: HashMap<String, String>,
+) -> Result<ProjectData, TemplateError> {
+    instantiate_files(template_id, project_name, output_dir, params).map(|(project, _)| project)
+}
+
+/// Like [`instantiate`], also returning the relative paths of the files written
+///
+/// Besides the template parameters, these values are derived:
+/// - `{{MCU}}` defaults to the first MCU target, `{{MCU_LOWER}}` is its lowercase form
+/// - `{{PROJECT_ID}}` is the project name as a lowercase identifier
+/// - `{{COMPILE_COMMANDS}}` is a compilation database for the C sources being written
+///
+/// A file with a `condition` is skipped when the condition is false.
+pub fn instantiate_files(
+    template_id: &str,
+    project_name: &str,
+    output_dir: &Path,
+    params: HashMap<String, String>,
+) -> Result<(ProjectData, Vec<String>), TemplateError> {
+    let template = get_template_by_id(template_id)
+        .ok_or_else(|| TemplateError::NotFound(template_id.to_string()))?;
+
+    let mut values: HashMap<String, String> = template.params.iter()
+        .filter_map(|p| Some((p.name.to_uppercase(), p.default.clone()?)))
+        .collect();
+    values.extend(params.into_iter().map(|(k, v)| (k.to_uppercase(), v)));
+    values.insert("PROJECT_NAME".to_string(), project_name.to_string());
+    values.insert("PROJECT_ID".to_string(), project_id(project_name));
+    if let Some(mcu) = values.get("MCU").cloned().or_else(|| template.mcu_targets.first().cloned()) {
+        values.insert("MCU_LOWER".to_string(), mcu.to_lowercase());
+        values.insert("MCU".to_string(), mcu);
+    }
+
+    // Resolve which files are written and where before rendering any content
+    // Fail on a bad project name before any file is written
+    project_file_name(output_dir, project_name)?;
+
+    let mut selected = Vec::new();
+    for file in &template.files {
+        if let Some(condition) = &file.condition {
+            if !evaluate_condition(condition, &values, &file.path)? {
+                continue;
+            }
+        }
+        let path = render_template(&file.path, &values, &file.path)?;
+        let path = path.trim().to_string();
+        if path.is_empty() {
+            continue;
+        }
+        check_relative_path(output_dir, &path)?;
+        if output_dir.join(&path).exists() {
+            return Err(TemplateError::FileExists(output_dir.join(&path).display().to_string()));
+        }
+        selected.push((path, file));
+    }
+
+    if selected.iter().any(|(_, file)| file.content.contains("{{COMPILE_COMMANDS}}")) {
+        let paths: Vec<&str> = selected.iter().map(|(path, _)| path.as_str()).collect();
+        values.insert("COMPILE_COMMANDS".to_string(), template_compile_commands(&paths, &values, output_dir));
+    }
+
+    let mut files = Vec::new();
+    for (path, file) in &selected {
+        files.push((path.clone(), render_template(&file.content, &values, path)?));
+    }
+
+    for (path, content) in &files {
+        let target = output_dir.join(path);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(target, content)?;
+    }
+
+    let language = if files.iter().any(|(p, _)| p.ends_with(".rs")) {
+        "rust"
+    } else if files.iter().any(|(p, _)| p.ends_with(".cpp")) {
+        "cpp"
+    } else {
+        "c"
+    };
+    let project = ProjectData {
+        schema_version: CURRENT_SCHEMA_VERSION.to_string(),
+        name: project_name.to_string(),
+        description: template.description.clone(),
+        nodes: vec![],
+        edges: vec![],
+        mcu: values.get("MCU").cloned().unwrap_or_default(),
+        language: language.to_string(),
+        pins: vec![],
+    };
+    let project_file = output_dir.join(project_file_name(output_dir, project_name)?);
+    let json = serde_json::to_string_pretty(&project)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    std::fs::write(project_file, json)?;
+
+    Ok((project, files.into_iter().map(|(path, _)| path).collect()))
+}
+
+/// Reject a rendered file path that would land outside `output_dir`
+///
+/// Parameters such as `{{MCU}}` come from the user, so `../` segments,
+/// absolute paths and drive prefixes are refused rather than normalized.
+fn check_relative_path(output_dir: &Path, path: &str) -> Result<(), TemplateError> {
+    use std::path::Component;
+
+    let unsafe_path = || TemplateError::UnsafePath(path.to_string());
+    // Windows separators are not components on Unix; treat them the same everywhere
+    let normalized = path.replace('\\', "/");
+    let relative = Path::new(&normalized);
+    if relative.components().any(|c| !matches!(c, Component::Normal(_) | Component::CurDir)) {
+        return Err(unsafe_path());
+    }
+    if !output_dir.join(relative).starts_with(output_dir) {
+        return Err(unsafe_path());
+    }
+    Ok(())
+}
+
+/// `.nbp` file name for `project_name`, as a single component inside `output_dir`
+fn project_file_name(output_dir: &Path, project_name: &str) -> Result<String, TemplateError> {
+    let stem = project_name.trim().replace([' ', '/', '\\'], "_");
+    let name = format!("{}.nbp", stem);
+    check_relative_path(output_dir, &name)?;
+    if stem.is_empty() || Path::new(&name).components().count() != 1 {
+        return Err(TemplateError::UnsafePath(name));
+    }
+    Ok(name)
+}
+
+/// Project name as a lowercase identifier usable for crates, CMake projects and C names
+fn project_id(name: &str) -> String {
+    let id: String = name.trim().chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    match id.chars().next() {
+        None => "project".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{}", id),
+        Some(_) => id,
+    }
+}
+
+/// Compilation database for the C sources among `paths`, rooted at `output_dir`
+fn template_compile_commands(paths: &[&str], values: &HashMap<String, String>, output_dir: &Path) -> String {
+    let mut include_paths: Vec<String> = paths.iter()
+        .filter(|p| p.ends_with(".h"))
+        .filter_map(|p| Path::new(p).parent().map(|dir| dir.to_string_lossy().to_string()))
+        .filter(|dir| !dir.is_empty())
+        .collect();
+    include_paths.sort();
+    include_paths.dedup();
+
+    let config = crate::build::BuildConfig {
+        system: "make".to_string(),
+        target: values.get("PROJECT_ID").cloned().unwrap_or_default(),
+        optimization: "Og".to_string(),
+        debug_symbols: true,
+        defines: values.get("MCU").cloned().into_iter().collect(),
+        include_paths,
+        source_files: paths.iter().filter(|p| p.ends_with(".c")).map(|p| p.to_string()).collect(),
+        linker_script: paths.iter().find(|p| p.ends_with(".ld")).map(|p| p.to_string()),
+        use_lto: false,
+        lto_type: Default::default(),
+        lto_job_count: None,
+    };
+    let directory = std::path::absolute(output_dir).unwrap_or_else(|_| output_dir.to_path_buf());
+    crate::build::compile_db::generate_compile_commands_in(&config, &directory)
+}
+
+/// Evaluate a file condition such as `use_rtos == "true"`
+///
+/// Supports `NAME == "value"`, `NAME != "value"`, a bare `NAME` (truthy) and
+/// `!NAME`, joined with `&&` and `||` (`&&` binds tighter). Names are
+/// case-insensitive; values are compared case-insensitively.
+pub fn evaluate_condition(
+    condition: &str,
+    values: &HashMap<String, String>,
+    file: &str,
+) -> Result<bool, TemplateError> {
+    let invalid = || TemplateError::InvalidCondition {
+        file: file.to_string(),
+        condition: condition.to_string(),
+    };
+
+    let term = |term: &str| -> Result<bool, TemplateError> {
+        let term = term.trim();
+        let compare = |op: &str| {
+            term.split_once(op).map(|(name, value)| {
+                let name = name.trim();
+                let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+                let current = values.get(&name.to_uppercase()).map(|v| v.trim().to_lowercase());
+                (name, current.as_deref() == Some(value.to_lowercase().as_str()))
+            })
+        };
+        if let Some((name, equal)) = compare("!=") {
+            return if name.is_empty() { Err(invalid()) } else { Ok(!equal) };
+        }
+        if let Some((name, equal)) = compare("==") {
+            return if name.is_empty() { Err(invalid()) } else { Ok(equal) };
+        }
+        let (negate, name) = match term.strip_prefix('!') {
+            Some(name) => (true, name.trim()),
+            None => (false, term),
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(invalid());
+        }
+        Ok(is_truthy(values.get(&name.to_uppercase())) != negate)
+    };
+
+    for any in condition.split("||") {
+        let mut all = true;
+        for part in any.split("&&") {
+            all &= term(part)?;
+        }
+        if all {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Render `{{NAME}}` placeholders and `{{#IF NAME}}...{{#ELSE}}...{{/IF}}` blocks
+pub fn render_template(
+    source: &str,
+    values: &HashMap<String, String>,
+    file: &str,
+) -> Result<String, TemplateError> {
+    let mut out = String::new();
+    let mut rest = source;
+
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or_else(|| TemplateError::UnclosedBlock {
+            file: file.to_string(),
+            block: "{{".to_string(),
+        })?;
+        let tag = after[..end].trim();
+        rest = &after[end + 2..];
+
+        if let Some(condition) = tag.strip_prefix("#IF") {
+            let (then_part, else_part, remaining) = split_if_block(rest).ok_or_else(|| TemplateError::UnclosedBlock {
+                file: file.to_string(),
+                block: format!("{{{{{}}}}}", tag),
+            })?;
+            let branch = if is_truthy(values.get(&condition.trim().to_uppercase())) { then_part } else { else_part };
+            // Drop the newline after a block tag that sits on its own line
+            let branch = branch.strip_prefix('\n').unwrap_or(branch);
+            out.push_str(&render_template(branch, values, file)?);
+            rest = remaining.strip_prefix('\n').filter(|_| out.ends_with('\n') || out.is_empty()).unwrap_or(remaining);
+        } else {
+            let value = values.get(&tag.to_uppercase())
+                .ok_or_else(|| TemplateError::MissingParam(tag.to_string()))?;
+            out.push_str(value);
+        }
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Split the body after `{{#IF ..}}` into then/else parts and the text after `{{/IF}}`
+fn split_if_block(body: &str) -> Option<(&str, &str, &str)> {
+    let mut depth = 0;
+    let mut else_at = None;
+    let mut pos = 0;
+
+    while let Some(offset) = body[pos..].find("{{") {
+        let start = pos + offset;
+        let end = start + body[start..].find("}}")? + 2;
+        let tag = body[start + 2..end - 2].trim();
+
+        if tag.starts_with("#IF") {
+            depth += 1;
+        } else if tag == "#ELSE" && depth == 0 {
+            else_at = Some((start, end));
+        } else if tag == "/IF" {
+            if depth == 0 {
+                return Some(match else_at {
+                    Some((else_start, else_end)) => (&body[..else_start], &body[else_end..start], &body[end..]),
+                    None => (&body[..start], "", &body[end..]),
+                });
+            }
+            depth -= 1;
+        }
+        pos = end;
+    }
+    None
+}
+
+fn is_truthy(value: Option<&String>) -> bool {
+    value.is_some_and(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1" | "yes" | "on"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -463,6 +1742,132 @@ mod tests {
         assert_eq!(template.unwrap().name, "Blinky LED");
     }
 
+    #[test]
+    fn test_instantiate_uart_blink() {
+        let plain = tempfile::tempdir().unwrap();
+        let project = instantiate("uart-blink", "Status LED", plain.path(), HashMap::from([
+            ("led_pin".to_string(), "5".to_string()),
+            ("BAUDRATE".to_string(), "9600".to_string()),
+        ])).unwrap();
+        assert_eq!(project.mcu, "STM32F4");
+        assert_eq!(project.language, "c");
+
+        let main = std::fs::read_to_string(plain.path().join("src/main.c")).unwrap();
+        assert!(main.contains("#define LED_PIN     5"));
+        assert!(main.contains("#define BAUDRATE    9600"));
+        assert!(main.contains("delay_ms(500);"));
+        assert!(!main.contains("FreeRTOS") && !main.contains("{{"));
+        assert!(!plain.path().join("inc/FreeRTOSConfig.h").exists());
+        assert!(plain.path().join("Status_LED.nbp").exists());
+
+        let rtos = tempfile::tempdir().unwrap();
+        instantiate("uart-blink", "Status LED", rtos.path(), HashMap::from([
+            ("use_rtos".to_string(), "true".to_string()),
+        ])).unwrap();
+        let main = std::fs::read_to_string(rtos.path().join("src/main.c")).unwrap();
+        assert!(main.contains("#include \"task.h\"") && main.contains("vTaskDelay(pdMS_TO_TICKS(500));"));
+        assert!(!main.contains("delay_ms"));
+        assert!(rtos.path().join("inc/FreeRTOSConfig.h").exists());
+
+        // Refuses to overwrite an existing project
+        assert!(matches!(
+            instantiate("uart-blink", "Status LED", rtos.path(), HashMap::new()),
+            Err(TemplateError::FileExists(_))
+        ));
+    }
+
+    #[test]
+    fn test_rendered_paths_stay_in_output_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let output = root.path().join("project");
+        std::fs::create_dir(&output).unwrap();
+        for mcu in ["../../escaped", "/tmp/escaped", "..\\escaped", "src/../../escaped"] {
+            let result = instantiate_files("freertos_stm32", "Pump Ctrl", &output, HashMap::from([
+                ("MCU".to_string(), mcu.to_string()),
+            ]));
+            assert!(matches!(result, Err(TemplateError::UnsafePath(_))), "{}", mcu);
+        }
+        // Nothing was written for the rejected projects
+        assert_eq!(std::fs::read_dir(&output).unwrap().count(), 0);
+        assert_eq!(std::fs::read_dir(root.path()).unwrap().count(), 1);
+
+        assert!(check_relative_path(&output, "./src/main.c").is_ok());
+
+        // The project file name is flattened to one component
+        assert_eq!(project_file_name(&output, "../x").unwrap(), ".._x.nbp");
+        assert_eq!(project_file_name(&output, "a/b c").unwrap(), "a_b_c.nbp");
+        assert!(matches!(project_file_name(&output, "  "), Err(TemplateError::UnsafePath(_))));
+    }
+
+    #[test]
+    fn test_project_template_conditions() {
+        let rtos = tempfile::tempdir().unwrap();
+        let (project, files) = instantiate_files("freertos_stm32", "Pump Ctrl", rtos.path(), HashMap::from([
+            ("MCU".to_string(), "STM32F411xE".to_string()),
+        ])).unwrap();
+        assert_eq!(project.mcu, "STM32F411xE");
+        assert!(files.contains(&"STM32F411xE_FLASH.ld".to_string()));
+        assert!(rtos.path().join("inc/FreeRTOSConfig.h").exists());
+        let cmake = std::fs::read_to_string(rtos.path().join("CMakeLists.txt")).unwrap();
+        assert!(cmake.contains("project(pump_ctrl C ASM)"));
+        assert!(cmake.contains("src/freertos_hooks.c") && cmake.contains("add_subdirectory(lib/FreeRTOS-Kernel)"));
+
+        let bare = tempfile::tempdir().unwrap();
+        let (_, files) = instantiate_files("freertos_stm32", "Pump Ctrl", bare.path(), HashMap::from([
+            ("use_rtos".to_string(), "false".to_string()),
+        ])).unwrap();
+        assert!(!files.iter().any(|f| f.contains("FreeRTOS") || f.contains("freertos")));
+        let main = std::fs::read_to_string(bare.path().join("src/main.c")).unwrap();
+        assert!(main.contains("void SysTick_Handler(void)") && !main.contains("vTaskStartScheduler"));
+
+        let esp = tempfile::tempdir().unwrap();
+        instantiate("esp_idf_project", "Sensor Node", esp.path(), HashMap::from([
+            ("mcu".to_string(), "ESP32S3".to_string()),
+        ])).unwrap();
+        let defaults = std::fs::read_to_string(esp.path().join("sdkconfig.defaults.esp32s3")).unwrap();
+        assert_eq!(defaults, "CONFIG_IDF_TARGET=\"esp32s3\"\n");
+
+        let rust = tempfile::tempdir().unwrap();
+        let project = instantiate("rust_embedded_rp2040", "pico blink", rust.path(), HashMap::new()).unwrap();
+        assert_eq!(project.language, "rust");
+        assert!(std::fs::read_to_string(rust.path().join("Cargo.toml")).unwrap().contains("name = \"pico_blink\""));
+        assert!(std::fs::read_to_string(rust.path().join("src/main.rs")).unwrap().contains("pins.gpio25.into_push_pull_output()"));
+    }
+
+    #[test]
+    fn test_managed_project_compile_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        instantiate("neurobench_managed", "Managed", dir.path(), HashMap::new()).unwrap();
+        let db: Vec<crate::build::compile_db::CompileCommand> =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join("compile_commands.json")).unwrap()).unwrap();
+        let files: Vec<&str> = db.iter().map(|c| c.file.as_str()).collect();
+        assert_eq!(files, vec!["src/main.c", "src/app.c", "src/startup.c"]);
+        assert!(db[0].arguments.contains(&"-Iinc".to_string()));
+        assert!(db[0].arguments.contains(&"-DSTM32F401xE".to_string()));
+        assert!(Path::new(&db[0].directory).is_absolute());
+    }
+
+    #[test]
+    fn test_evaluate_condition() {
+        let values = HashMap::from([
+            ("USE_RTOS".to_string(), "True".to_string()),
+            ("MCU".to_string(), "ESP32".to_string()),
+        ]);
+        assert!(evaluate_condition("use_rtos == \"true\"", &values, "a").unwrap());
+        assert!(!evaluate_condition("mcu != 'esp32'", &values, "a").unwrap());
+        assert!(evaluate_condition("!USE_USB || mcu == \"esp32\" && use_rtos", &values, "a").unwrap());
+        assert!(!evaluate_condition("USE_USB", &values, "a").unwrap());
+        assert!(matches!(evaluate_condition("== \"x\"", &values, "a"), Err(TemplateError::InvalidCondition { .. })));
+    }
+
+    #[test]
+    fn test_render_errors() {
+        let values = HashMap::new();
+        assert!(matches!(render_template("{{MISSING}}", &values, "a.c"), Err(TemplateError::MissingParam(_))));
+        assert!(matches!(render_template("{{#IF X}}open", &values, "a.c"), Err(TemplateError::UnclosedBlock { .. })));
+        assert_eq!(render_template("a{{#IF X}}b{{#IF Y}}c{{/IF}}{{#ELSE}}d{{/IF}}e", &values, "a.c").unwrap(), "ade");
+    }
+
     #[test]
     fn test_get_categories() {
         let categories = get_categories();
@@ -0,0 +1,199 @@
+// Command Aliases
+// User-defined command shortcuts persisted as JSON in the app data directory
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use thiserror::Error;
+
+/// Alias errors
+#[derive(Debug, Error)]
+pub enum AliasError {
+    #[error("Invalid alias name: '{0}'")]
+    InvalidName(String),
+
+    #[error("Alias cycle detected: {0}")]
+    Cycle(String),
+}
+
+lazy_static::lazy_static! {
+    /// Aliases shared by all terminal sessions
+    pub static ref ALIASES: Mutex<AliasManager> = Mutex::new(AliasManager::new());
+}
+
+/// Alias table with optional backing file
+#[derive(Debug, Default)]
+pub struct AliasManager {
+    aliases: BTreeMap<String, String>,
+    path: Option<PathBuf>,
+}
+
+impl AliasManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Define or replace an alias, rejecting definitions that would cycle
+    pub fn define(&mut self, name: &str, expansion: &str) -> Result<(), AliasError> {
+        let valid = !name.is_empty()
+            && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+            && !matches!(name, "alias" | "unalias");
+        if !valid {
+            return Err(AliasError::InvalidName(name.to_string()));
+        }
+
+        let previous = self.aliases.insert(name.to_string(), expansion.trim().to_string());
+        if let Err(e) = self.check_cycle(name) {
+            match previous {
+                Some(old) => self.aliases.insert(name.to_string(), old),
+                None => self.aliases.remove(name),
+            };
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Remove an alias, returns whether it existed
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.aliases.remove(name).is_some()
+    }
+
+    /// Expansion text of an alias
+    pub fn resolve(&self, name: &str) -> Option<String> {
+        self.aliases.get(name).cloned()
+    }
+
+    /// Expansion of an alias after checking it does not expand to itself
+    pub fn expand(&self, name: &str) -> Result<Option<String>, AliasError> {
+        match self.aliases.get(name) {
+            Some(expansion) => {
+                self.check_cycle(name)?;
+                Ok(Some(expansion.clone()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// All aliases sorted by name
+    pub fn list(&self) -> Vec<(String, String)> {
+        self.aliases.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+    }
+
+    /// Write aliases as a JSON object and remember the path
+    pub fn save(&mut self, path: &Path) -> Result<(), io::Error> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(&self.aliases)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, json)?;
+        self.path = Some(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Replace aliases with the contents of a JSON file, a missing file is empty
+    pub fn load(&mut self, path: &Path) -> Result<(), io::Error> {
+        self.path = Some(path.to_path_buf());
+        self.aliases = match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(())
+    }
+
+    /// Save to the file aliases were loaded from, if any
+    pub fn persist(&mut self) -> Result<(), io::Error> {
+        match self.path.clone() {
+            Some(path) => self.save(&path),
+            None => Ok(()),
+        }
+    }
+
+    /// Follow every command word of the expansion through the alias table
+    fn check_cycle(&self, name: &str) -> Result<(), AliasError> {
+        let mut chain = vec![name.to_string()];
+        self.walk(name, &mut chain)
+    }
+
+    fn walk(&self, name: &str, chain: &mut Vec<String>) -> Result<(), AliasError> {
+        let Some(expansion) = self.aliases.get(name) else { return Ok(()) };
+        for word in command_words(expansion) {
+            if chain.contains(&word) {
+                chain.push(word);
+                return Err(AliasError::Cycle(chain.join(" -> ")));
+            }
+            if self.aliases.contains_key(&word) {
+                chain.push(word.clone());
+                self.walk(&word, chain)?;
+                chain.pop();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// First word of every command in an expansion (after `&&`, `||`, `|`, `&`)
+fn command_words(expansion: &str) -> Vec<String> {
+    expansion
+        .split(['&', '|', ';'])
+        .filter_map(|part| part.split_whitespace().next())
+        .map(|word| word.to_string())
+        .collect()
+}
+
+/// Default alias file in the app data directory
+pub fn default_alias_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("neurobench")
+        .join("aliases.json")
+}
+
+/// Load the shared alias table from the app data directory
+pub fn load_aliases() {
+    let path = default_alias_path();
+    if let Err(e) = ALIASES.lock().unwrap().load(&path) {
+        log::warn!("Failed to load aliases from {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_define_save_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("aliases.json");
+
+        let mut aliases = AliasManager::new();
+        aliases.define("fl", "flash --probe stlink").unwrap();
+        aliases.define("go", "build && fl").unwrap();
+        aliases.save(&path).unwrap();
+
+        let mut loaded = AliasManager::new();
+        loaded.load(&path).unwrap();
+        assert_eq!(loaded.resolve("go"), Some("build && fl".to_string()));
+        assert_eq!(loaded.list().len(), 2);
+        assert!(loaded.remove("fl"));
+        assert_eq!(loaded.resolve("fl"), None);
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        let mut aliases = AliasManager::new();
+        assert!(matches!(aliases.define("ls", "ls -la"), Err(AliasError::Cycle(_))));
+
+        aliases.define("a", "b --fast").unwrap();
+        aliases.define("b", "build").unwrap();
+        let err = aliases.define("b", "clean && a").unwrap_err();
+        assert_eq!(err.to_string(), "Alias cycle detected: b -> a -> b");
+
+        // The previous definition is kept
+        assert_eq!(aliases.resolve("b"), Some("build".to_string()));
+        assert!(aliases.define("alias", "help").is_err());
+    }
+}
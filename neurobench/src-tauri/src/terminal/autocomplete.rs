@@ -1,14 +1,24 @@
 // Tab Completion Engine
 // Dynamic autocomplete for commands, paths, pins, and peripherals
 
+use crate::drivers::mcu::get_all_mcus;
+use crate::pins::PinConfig;
+use crate::toolchain::probe::{ProbeManager, ProbeType};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::RwLock;
+
+lazy_static::lazy_static! {
+    /// Pinout of the open project, used for pin completion
+    static ref PROJECT_PINS: RwLock<Vec<PinConfig>> = RwLock::new(Vec::new());
+}
 
 /// Completion item with metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompletionItem {
     pub text: String,
     pub display: String,
+    /// Tooltip text shown next to the suggestion
     pub description: String,
     pub kind: CompletionKind,
     pub insert_text: Option<String>,
@@ -25,42 +35,67 @@ pub enum CompletionKind {
     Path,
     Variable,
     McuTarget,
+    Port,
+}
+
+/// Replace the project pinout offered for pin completion
+pub fn set_project_pins(pins: Vec<PinConfig>) {
+    *PROJECT_PINS.write().unwrap() = pins;
 }
 
 /// Get completions for the current input
-pub fn get_completions(input: &str, cursor_pos: usize) -> Vec<CompletionItem> {
+///
+/// Async because probe and serial port suggestions query the host.
+pub async fn get_completions(input: &str, cursor_pos: usize) -> Vec<CompletionItem> {
     let before_cursor = &input[..cursor_pos.min(input.len())];
-    let parts: Vec<&str> = before_cursor.split_whitespace().collect();
+    let mut parts: Vec<&str> = before_cursor.split_whitespace().collect();
     
     if parts.is_empty() || (parts.len() == 1 && !before_cursor.ends_with(' ')) {
         // Completing command name
-        let prefix = parts.first().map(|s| *s).unwrap_or("");
+        let prefix = parts.first().copied().unwrap_or("");
         return complete_commands(prefix);
     }
     
-    let command = parts[0];
-    let last_part = parts.last().map(|s| *s).unwrap_or("");
+    // A trailing space starts a new, empty word
+    if before_cursor.ends_with(' ') {
+        parts.push("");
+    }
+    
+    let command = parts[0].to_lowercase();
+    let last_part = parts.last().copied().unwrap_or("");
+    let prev = parts[parts.len() - 2];
     
     // Check if we're completing a flag value
-    if parts.len() >= 2 {
-        let prev = parts[parts.len() - 2];
-        if prev.starts_with('-') {
-            return complete_flag_value(command, prev, last_part);
-        }
+    if prev.starts_with('-') && !last_part.starts_with('-') {
+        return complete_flag_value(&command, prev, last_part).await;
     }
     
     // Check if completing a flag
     if last_part.starts_with('-') {
-        return complete_flags(command, last_part);
+        return complete_flags(&command, last_part);
+    }
+    
+    // Positional arguments before the word being completed
+    let args: Vec<&str> = parts[1..parts.len() - 1]
+        .iter()
+        .copied()
+        .filter(|a| !a.starts_with('-'))
+        .collect();
+    
+    match (command.as_str(), args.as_slice()) {
+        ("mcu" | "target", []) => return complete_mcu_targets(last_part),
+        ("monitor", ["uart"]) => return complete_serial_ports(last_part).await,
+        ("generate_gpio", []) => return complete_pins(last_part),
+        _ => {}
     }
     
     // Check for pin completion (PA, PB, etc.)
-    if last_part.len() >= 1 && last_part.chars().next().map(|c| c == 'P').unwrap_or(false) {
+    if last_part.starts_with('P') {
         return complete_pins(last_part);
     }
     
     // Complete based on command context
-    complete_command_args(command, last_part)
+    complete_command_args(&command, last_part)
 }
 
 /// Complete command names
@@ -78,6 +113,7 @@ fn complete_commands(prefix: &str) -> Vec<CompletionItem> {
         ("serial", "Serial port commands", "serial list|open <port>"),
         ("fsm", "FSM operations", "fsm simulate|step|validate"),
         ("driver", "Generate drivers", "driver gpio|uart|spi|i2c"),
+        ("generate_gpio", "Generate a GPIO driver", "generate_gpio <pin> [mode]"),
         ("mcu", "Set target MCU", "mcu <target>"),
         ("target", "Set target MCU", "target <mcu>"),
         ("gpio", "GPIO control", "gpio config|set|read <pin>"),
@@ -126,6 +162,7 @@ fn complete_flags(command: &str, prefix: &str) -> Vec<CompletionItem> {
             ("--baud", "-b", "Baud rate"),
             ("--filter", "-f", "Filter pattern"),
             ("--hex", "-x", "Hex output"),
+            ("--timestamps", "-t", "Elapsed time prefix"),
         ],
         "build" => vec![
             ("--release", "-r", "Release build"),
@@ -165,23 +202,11 @@ fn complete_flags(command: &str, prefix: &str) -> Vec<CompletionItem> {
 }
 
 /// Complete flag values
-fn complete_flag_value(command: &str, flag: &str, prefix: &str) -> Vec<CompletionItem> {
+async fn complete_flag_value(command: &str, flag: &str, prefix: &str) -> Vec<CompletionItem> {
     let flag_name = flag.trim_start_matches('-');
     
     match (command.to_lowercase().as_str(), flag_name) {
-        ("flash", "probe" | "p") => {
-            vec!["stlink", "jlink", "cmsis-dap", "blackmagic"]
-                .into_iter()
-                .filter(|p| p.starts_with(prefix))
-                .map(|p| CompletionItem {
-                    text: p.to_string(),
-                    display: p.to_string(),
-                    description: format!("{} debug probe", p.to_uppercase()),
-                    kind: CompletionKind::Argument,
-                    insert_text: None,
-                })
-                .collect()
-        }
+        ("flash", "probe" | "p") => complete_probes(prefix).await,
         ("flash" | "build", "target" | "t") | ("mcu", _) => complete_mcu_targets(prefix),
         ("flash", "speed" | "s") => {
             vec!["1000", "4000", "8000", "12000"]
@@ -213,39 +238,115 @@ fn complete_flag_value(command: &str, flag: &str, prefix: &str) -> Vec<Completio
     }
 }
 
-/// Complete MCU targets
-fn complete_mcu_targets(prefix: &str) -> Vec<CompletionItem> {
-    let targets = vec![
-        ("stm32f401", "STM32F401 (Cortex-M4, 84MHz, 256KB Flash)"),
-        ("stm32f103", "STM32F103 (Cortex-M3, 72MHz, 64KB Flash)"),
-        ("stm32f407", "STM32F407 (Cortex-M4, 168MHz, 1MB Flash)"),
-        ("stm32f746", "STM32F746 (Cortex-M7, 216MHz, 1MB Flash)"),
-        ("stm32h7", "STM32H7 (Cortex-M7, 480MHz)"),
-        ("esp32", "ESP32 (Dual Xtensa, 240MHz, WiFi+BT)"),
-        ("esp32-s3", "ESP32-S3 (Dual Xtensa, 240MHz, AI)"),
-        ("esp8266", "ESP8266 (Tensilica, 80MHz, WiFi)"),
-        ("nrf52832", "nRF52832 (Cortex-M4, 64MHz, BLE)"),
-        ("nrf52840", "nRF52840 (Cortex-M4, 64MHz, BLE+USB)"),
-        ("rp2040", "RP2040 (Dual Cortex-M0+, 133MHz)"),
-        ("atmega328p", "ATMega328P (AVR, 16MHz)"),
-        ("atmega2560", "ATMega2560 (AVR, 16MHz)"),
-    ];
+/// Complete debug probes, connected ones first
+async fn complete_probes(prefix: &str) -> Vec<CompletionItem> {
+    let detected = tokio::task::spawn_blocking(ProbeManager::list_probes)
+        .await
+        .unwrap_or_default();
+    
+    let mut completions: Vec<CompletionItem> = Vec::new();
+    for probe in &detected {
+        let Some(name) = probe_flag_name(probe.probe_type) else { continue };
+        if name.starts_with(prefix) && !completions.iter().any(|c| c.text == name) {
+            let serial = probe.serial.as_ref().map(|s| format!(" ({})", s)).unwrap_or_default();
+            completions.push(CompletionItem {
+                text: name.to_string(),
+                display: name.to_string(),
+                description: format!("Connected: {}{}", probe.name, serial),
+                kind: CompletionKind::Argument,
+                insert_text: None,
+            });
+        }
+    }
+    
+    for name in ["stlink", "jlink", "cmsis-dap", "blackmagic"] {
+        if name.starts_with(prefix) && !completions.iter().any(|c| c.text == name) {
+            completions.push(CompletionItem {
+                text: name.to_string(),
+                display: name.to_string(),
+                description: format!("{} debug probe", name.to_uppercase()),
+                kind: CompletionKind::Argument,
+                insert_text: None,
+            });
+        }
+    }
+    completions
+}
+
+/// Value accepted by `flash --probe` for a detected probe
+fn probe_flag_name(probe_type: ProbeType) -> Option<&'static str> {
+    match probe_type {
+        ProbeType::StLink => Some("stlink"),
+        ProbeType::JLink => Some("jlink"),
+        ProbeType::CmsisDap => Some("cmsis-dap"),
+        ProbeType::Unknown => None,
+    }
+}
+
+/// Complete serial ports currently present on the host
+async fn complete_serial_ports(prefix: &str) -> Vec<CompletionItem> {
+    let ports = tokio::task::spawn_blocking(crate::serial::list_ports)
+        .await
+        .ok()
+        .and_then(|r| r.ok())
+        .unwrap_or_default();
     
-    targets
+    ports
         .into_iter()
+        .filter(|p| p.name.to_lowercase().starts_with(&prefix.to_lowercase()))
+        .map(|p| CompletionItem {
+            text: p.name.clone(),
+            display: p.name,
+            description: format!("{} ({})", p.description, p.port_type),
+            kind: CompletionKind::Port,
+            insert_text: None,
+        })
+        .collect()
+}
+
+/// Complete MCU targets from the supported families
+fn complete_mcu_targets(prefix: &str) -> Vec<CompletionItem> {
+    get_all_mcus()
+        .into_iter()
+        .map(|mcu| (format!("{:?}", mcu.family).to_lowercase(), mcu))
         .filter(|(name, _)| name.starts_with(&prefix.to_lowercase()))
-        .map(|(name, desc)| CompletionItem {
-            text: name.to_string(),
-            display: name.to_uppercase(),
-            description: desc.to_string(),
+        .map(|(name, mcu)| CompletionItem {
+            text: name,
+            display: mcu.display_name.clone(),
+            description: format!(
+                "{} {}, {}MHz, {}KB Flash, {}KB RAM",
+                mcu.vendor, mcu.architecture, mcu.max_freq_mhz, mcu.flash_kb, mcu.ram_kb
+            ),
             kind: CompletionKind::McuTarget,
             insert_text: None,
         })
         .collect()
 }
 
-/// Complete GPIO pins
+/// Complete GPIO pins, from the project pinout when one is loaded
 fn complete_pins(prefix: &str) -> Vec<CompletionItem> {
+    pin_completions(&PROJECT_PINS.read().unwrap(), prefix)
+}
+
+/// Pins of `project_pins` matching `prefix`, or generic port pins without a pinout
+fn pin_completions(project_pins: &[PinConfig], prefix: &str) -> Vec<CompletionItem> {
+    if !project_pins.is_empty() {
+        return project_pins
+            .iter()
+            .filter(|p| p.pin_name.to_lowercase().starts_with(&prefix.to_lowercase()))
+            .map(|p| CompletionItem {
+                text: p.pin_name.clone(),
+                display: match &p.label {
+                    Some(label) => format!("{} ({})", p.pin_name, label),
+                    None => p.pin_name.clone(),
+                },
+                description: format!("{} - {}", p.function, p.mode),
+                kind: CompletionKind::Pin,
+                insert_text: None,
+            })
+            .collect();
+    }
+    
     let mut completions = Vec::new();
     
     let ports = ['A', 'B', 'C', 'D', 'E', 'F', 'G', 'H'];
@@ -411,22 +512,61 @@ fn complete_command_args(command: &str, prefix: &str) -> Vec<CompletionItem> {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_command_completion() {
-        let completions = get_completions("fl", 2);
+    #[tokio::test]
+    async fn test_command_completion() {
+        let completions = get_completions("fl", 2).await;
         assert!(!completions.is_empty());
         assert!(completions.iter().any(|c| c.text == "flash"));
     }
 
-    #[test]
-    fn test_flag_completion() {
-        let completions = get_completions("flash --p", 9);
+    #[tokio::test]
+    async fn test_flag_completion() {
+        let completions = get_completions("flash --p", 9).await;
         assert!(completions.iter().any(|c| c.text == "--probe"));
+        
+        let probes = get_completions("flash --probe ", 14).await;
+        assert!(probes.iter().any(|c| c.text == "jlink"));
     }
 
-    #[test]
-    fn test_pin_completion() {
-        let completions = get_completions("gpio config PA", 14);
+    #[tokio::test]
+    async fn test_pin_completion() {
+        let completions = get_completions("gpio config PA", 14).await;
         assert!(completions.iter().any(|c| c.text.starts_with("PA")));
     }
+
+    #[tokio::test]
+    async fn test_generate_gpio_completes_pins() {
+        let pins = get_completions("generate_gpio PA", 16).await;
+        assert!(!pins.is_empty());
+        assert!(pins.iter().all(|c| c.kind == CompletionKind::Pin && c.text.starts_with("PA")));
+
+        // The pin is the only positional argument; the mode after it is not a pin
+        let mode = get_completions("generate_gpio PC13 ", 19).await;
+        assert!(mode.iter().all(|c| c.kind != CompletionKind::Pin));
+
+        let project = [PinConfig {
+            pin_name: "PC13".to_string(),
+            port: "C".to_string(),
+            pin_number: 13,
+            function: "GPIO_Output".to_string(),
+            mode: "output".to_string(),
+            pull: "none".to_string(),
+            speed: "low".to_string(),
+            alternate_function: None,
+            label: Some("LED".to_string()),
+        }];
+        let pins = pin_completions(&project, "");
+        assert_eq!(pins.len(), 1);
+        assert_eq!((pins[0].text.as_str(), pins[0].display.as_str()), ("PC13", "PC13 (LED)"));
+    }
+
+    #[tokio::test]
+    async fn test_context_aware_arguments() {
+        let mcus = get_completions("mcu stm32f", 10).await;
+        assert!(mcus.iter().any(|c| c.text == "stm32f4" && c.kind == CompletionKind::McuTarget));
+        assert!(mcus.iter().all(|c| !c.description.is_empty()));
+
+        let ports = get_completions("monitor uart ", 13).await;
+        assert!(ports.iter().all(|c| c.kind == CompletionKind::Port));
+    }
 }
@@ -2,15 +2,31 @@
 // 30+ specialized commands for embedded development
 
 use super::{TerminalResult, TerminalLine};
-use super::parser::ParsedCommand;
+use super::aliases::ALIASES;
+use super::parser::{ParsedCommand, CommandOperator, RedirectMode};
 use std::collections::HashMap;
+use std::io::Write;
 
 /// Process an embedded system command
 pub fn process_embedded_command(cmd: &ParsedCommand) -> TerminalResult {
-    match cmd.command.to_lowercase().as_str() {
+    let name = cmd.command.to_lowercase();
+    if name != "alias" && name != "unalias" {
+        let expansion = ALIASES.lock().unwrap().expand(&cmd.command);
+        match expansion {
+            Ok(Some(expansion)) => return run_alias(&expansion, cmd),
+            Ok(None) => {}
+            Err(e) => return TerminalResult::error(&e.to_string()),
+        }
+    }
+
+    match name.as_str() {
         // === Help ===
         "help" => cmd_help(&cmd.args),
         
+        // === Alias Commands ===
+        "alias" => cmd_alias(cmd),
+        "unalias" => cmd_unalias(cmd),
+        
         // === Flash Commands ===
         "flash" => cmd_flash(cmd),
         "verify" => cmd_verify(cmd),
@@ -65,6 +81,7 @@ pub fn process_embedded_command(cmd: &ParsedCommand) -> TerminalResult {
         
         // === GPIO Commands ===
         "gpio" => cmd_gpio(cmd),
+        "generate_gpio" => cmd_generate_gpio(cmd),
         
         // Unknown
         _ => TerminalResult::error(&format!(
@@ -74,6 +91,164 @@ pub fn process_embedded_command(cmd: &ParsedCommand) -> TerminalResult {
     }
 }
 
+/// Execute pipeline stages from `parse_pipeline`
+///
+/// Commands within a stage follow `&&`/`||` semantics. The non-error output
+/// of a stage is its stdout and is appended to the args of every command in
+/// the next stage, error lines pass straight through like stderr. A command's
+/// `< file` lines are appended to its args the same way, and `> file`/`>> file`
+/// sends its stdout to the file instead.
+pub fn execute_pipeline(stages: &[Vec<ParsedCommand>]) -> TerminalResult {
+    let mut output = Vec::new();
+    let mut stdin: Vec<TerminalLine> = Vec::new();
+    let mut result = TerminalResult::success(vec![]);
+
+    for (index, stage) in stages.iter().enumerate() {
+        let last_stage = index + 1 == stages.len();
+        let mut stage_output = Vec::new();
+        let mut last_success = true;
+
+        for (i, cmd) in stage.iter().enumerate() {
+            if i > 0 {
+                match stage[i - 1].operator {
+                    CommandOperator::And if !last_success => continue,
+                    CommandOperator::Or if last_success => continue,
+                    _ => {}
+                }
+            }
+
+            let mut cmd = cmd.clone();
+            cmd.args.extend(stdin.iter().map(|line| line.content.clone()));
+            result = match &cmd.redirect_stdin {
+                Some(path) => match std::fs::read_to_string(path) {
+                    Ok(content) => {
+                        cmd.args.extend(content.lines().map(str::to_string));
+                        process_embedded_command(&cmd)
+                    }
+                    Err(e) => TerminalResult::error(&format!("{}: {}", path, e)),
+                },
+                None => process_embedded_command(&cmd),
+            };
+            if let Some((mode, path)) = &cmd.redirect_stdout {
+                redirect_output(&mut result, *mode, path);
+            }
+            last_success = result.success;
+            stage_output.extend(result.output.clone());
+        }
+
+        if last_stage {
+            output.extend(stage_output);
+        } else {
+            let (errors, lines): (Vec<_>, Vec<_>) = stage_output.into_iter()
+                .partition(|line| line.line_type == "error");
+            output.extend(errors);
+            stdin = lines;
+        }
+    }
+
+    // Like a shell, the pipeline status is the status of the last stage
+    TerminalResult {
+        output,
+        ..result
+    }
+}
+
+/// Move a result's stdout lines into `path`, leaving errors on screen
+fn redirect_output(result: &mut TerminalResult, mode: RedirectMode, path: &str) {
+    let (errors, lines): (Vec<_>, Vec<_>) = std::mem::take(&mut result.output).into_iter()
+        .partition(|line| line.line_type == "error");
+    result.output = errors;
+
+    let mut options = std::fs::OpenOptions::new();
+    match mode {
+        RedirectMode::Truncate => options.write(true).create(true).truncate(true),
+        RedirectMode::Append => options.append(true).create(true),
+    };
+    let written = options.open(path).and_then(|mut file| {
+        lines.iter().try_for_each(|line| writeln!(file, "{}", line.content))
+    });
+
+    if let Err(e) = written {
+        result.output.push(TerminalLine::error(&format!("{}: {}", path, e)));
+        result.success = false;
+        result.exit_code = Some(1);
+    }
+}
+
+/// Run an alias expansion with the invocation's args and flags appended
+fn run_alias(expansion: &str, cmd: &ParsedCommand) -> TerminalResult {
+    let mut stages = match super::parser::parse_pipeline(expansion, &HashMap::new()) {
+        Ok(stages) => stages,
+        Err(e) => return TerminalResult::error(&e.to_string()),
+    };
+    if let Some(last) = stages.last_mut().and_then(|stage| stage.last_mut()) {
+        last.args.extend(cmd.args.iter().cloned());
+        last.flags.extend(cmd.flags.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+    execute_pipeline(&stages)
+}
+
+// ===== Alias Commands =====
+fn cmd_alias(cmd: &ParsedCommand) -> TerminalResult {
+    let mut aliases = ALIASES.lock().unwrap();
+
+    if cmd.args.is_empty() {
+        let list = aliases.list();
+        if list.is_empty() {
+            return TerminalResult::info("No aliases defined. Usage: alias name=\"expansion\"");
+        }
+        let lines = list.iter()
+            .map(|(name, expansion)| TerminalLine::output(&format!("  {}='{}'", name, expansion)))
+            .collect();
+        return TerminalResult::success(lines);
+    }
+
+    // Unquoted expansions get split by the parser, put flags back on the end
+    let mut definition = cmd.args.join(" ");
+    for (key, value) in &cmd.flags {
+        let dashes = if key.len() == 1 { "-" } else { "--" };
+        definition.push_str(&format!(" {}{}", dashes, key));
+        if let Some(v) = value {
+            definition.push_str(&format!(" {}", v));
+        }
+    }
+
+    let Some((name, expansion)) = definition.split_once('=') else {
+        return match aliases.resolve(&definition) {
+            Some(expansion) => TerminalResult::success(vec![
+                TerminalLine::output(&format!("  {}='{}'", definition, expansion)),
+            ]),
+            None => TerminalResult::error(&format!("alias: {}: not found", definition)),
+        };
+    };
+
+    let expansion = expansion.trim().trim_matches(|c| c == '"' || c == '\'');
+    if let Err(e) = aliases.define(name.trim(), expansion) {
+        return TerminalResult::error(&e.to_string());
+    }
+    if let Err(e) = aliases.persist() {
+        return TerminalResult::error(&format!("Alias defined but not saved: {}", e));
+    }
+    TerminalResult::success(vec![
+        TerminalLine::success(&format!("✓ alias {}='{}'", name.trim(), expansion)),
+    ])
+}
+
+fn cmd_unalias(cmd: &ParsedCommand) -> TerminalResult {
+    let Some(name) = cmd.args.first() else {
+        return TerminalResult::info("Usage: unalias name");
+    };
+
+    let mut aliases = ALIASES.lock().unwrap();
+    if !aliases.remove(name) {
+        return TerminalResult::error(&format!("unalias: {}: not found", name));
+    }
+    if let Err(e) = aliases.persist() {
+        return TerminalResult::error(&format!("Alias removed but not saved: {}", e));
+    }
+    TerminalResult::success(vec![TerminalLine::success(&format!("✓ Removed alias {}", name))])
+}
+
 // ===== Help Command =====
 fn cmd_help(args: &[String]) -> TerminalResult {
     if let Some(topic) = args.first() {
@@ -90,12 +265,12 @@ fn cmd_help(args: &[String]) -> TerminalResult {
         TerminalLine::output("    erase [--full|--sector N]                     Erase flash memory"),
         TerminalLine::output("    dfu enter|exit                                DFU bootloader mode"),
         TerminalLine::info("  📡 Monitor & Trace"),
-        TerminalLine::output("    monitor uart [port] --baud N                  UART serial monitor"),
+        TerminalLine::output("    monitor uart [port] --baud N [--hex] [-t]     UART serial monitor"),
         TerminalLine::output("    monitor can --filter ID                       CAN bus monitor"),
         TerminalLine::output("    monitor gpio PIN                              GPIO waveform monitor"),
         TerminalLine::output("    trace start|stop swo FREQ                     ITM/SWO tracing"),
         TerminalLine::info("  🐛 Debug"),
-        TerminalLine::output("    gdb connect|disconnect                        GDB server control"),
+        TerminalLine::output("    gdb connect [elf]|disconnect                  Interactive GDB session"),
         TerminalLine::output("    debug launch                                  Start debug session"),
         TerminalLine::output("    bp add|remove|list ADDR                       Breakpoint management"),
         TerminalLine::info("  ⚡ Power"),
@@ -109,6 +284,7 @@ fn cmd_help(args: &[String]) -> TerminalResult {
         TerminalLine::output("    gpio config PIN MODE [SPEED]                  Configure GPIO pin"),
         TerminalLine::output("    gpio set|clear|toggle PIN                     Set GPIO state"),
         TerminalLine::output("    gpio read PIN                                 Read GPIO state"),
+        TerminalLine::output("    generate_gpio PIN [MODE]                      Print a C driver for a pin"),
         TerminalLine::info("  🤖 AI Assistant"),
         TerminalLine::output("    ai \"question\"                                 Ask AI about your code"),
         TerminalLine::output("    ai explain [topic]                            Get explanations"),
@@ -117,6 +293,9 @@ fn cmd_help(args: &[String]) -> TerminalResult {
         TerminalLine::output("    fsm simulate                                  Simulate FSM in terminal"),
         TerminalLine::output("    fsm step                                      Step through FSM"),
         TerminalLine::output("    fsm validate                                  Validate FSM structure"),
+        TerminalLine::info("  🧩 Shell"),
+        TerminalLine::output("    alias [name=\"expansion\"]                      Define or list aliases"),
+        TerminalLine::output("    unalias name                                  Remove an alias"),
         TerminalLine::with_ansi("╚══════════════════════════════════════════════════════════════════════╝", "\x1b[38;5;99m"),
         TerminalLine::output("  Type 'help <command>' for detailed usage. Use Tab for autocomplete."),
     ])
@@ -154,6 +333,8 @@ fn help_topic(topic: &str) -> TerminalResult {
             TerminalLine::output("Options:"),
             TerminalLine::output("  --baud, -b      Baud rate for UART (default: 115200)"),
             TerminalLine::output("  --filter, -f    Filter pattern or ID"),
+            TerminalLine::output("  --hex, -x       Show UART bytes as hex instead of text"),
+            TerminalLine::output("  --timestamps, -t  Prefix UART lines with elapsed time"),
         ]),
         "ai" => TerminalResult::success(vec![
             TerminalLine::info("ai - AI-powered embedded systems assistant"),
@@ -267,16 +448,21 @@ fn cmd_monitor(cmd: &ParsedCommand) -> TerminalResult {
                 .or(cmd.flags.get("b"))
                 .and_then(|v| v.clone())
                 .unwrap_or_else(|| "115200".to_string());
-            TerminalResult::success(vec![
-                TerminalLine::info(&format!("📡 Opening UART monitor on {} @ {} baud", port, baud)),
-                TerminalLine::success("✓ Connected"),
-                TerminalLine::output("─────────────────────────────────────"),
-                TerminalLine::output("[RX] System initialized"),
-                TerminalLine::output("[RX] ADC: 2048 (1.65V)"),
-                TerminalLine::output("[RX] Temperature: 25.3°C"),
-                TerminalLine::output("─────────────────────────────────────"),
-                TerminalLine::info("Press Ctrl+C to close monitor"),
-            ])
+            let Ok(baud) = baud.parse::<u32>() else {
+                return TerminalResult::error(&format!("Invalid baud rate: {}", baud));
+            };
+            // The frontend opens the port through `serial_monitor_start`; lines arrive as `serial:data`
+            let options = super::monitor::MonitorOptions::from_command(cmd);
+            TerminalResult {
+                success: true,
+                output: vec![
+                    TerminalLine::info(&format!("📡 Opening UART monitor on {} @ {} baud", port, baud)),
+                    TerminalLine::serial_monitor(port, baud, options),
+                    TerminalLine::info("Press Ctrl+C to close monitor"),
+                ],
+                exit_code: None,
+                streaming: true,
+            }
         }
         "can" => {
             TerminalResult::success(vec![
@@ -322,17 +508,34 @@ fn cmd_gdb(cmd: &ParsedCommand) -> TerminalResult {
         .unwrap_or_else(|| "3333".to_string());
 
     match action {
-        "connect" => TerminalResult::success(vec![
-            TerminalLine::info("🐛 Starting GDB server..."),
-            TerminalLine::output(&format!("  OpenOCD listening on port {}", port)),
-            TerminalLine::success("✓ GDB server ready"),
-            TerminalLine::output(&format!("  Connect with: arm-none-eabi-gdb -ex 'target remote :{}'", port)),
-        ]),
+        "connect" => {
+            // Interactive GDB on a PTY, attached to the GDB server on `port`
+            let gdb = ["arm-none-eabi-gdb", "gdb-multiarch"].into_iter()
+                .find(|bin| which::which(bin).is_ok())
+                .unwrap_or("arm-none-eabi-gdb");
+            let mut gdb_args = vec!["-q".to_string(), "-ex".to_string(), format!("target extended-remote :{}", port)];
+            if let Some(elf) = cmd.args.get(1) {
+                gdb_args.push(elf.clone());
+            }
+
+            match super::pty::spawn_attached(gdb, &gdb_args, &HashMap::new(), None) {
+                Ok(session_id) => TerminalResult {
+                    success: true,
+                    output: vec![
+                        TerminalLine::info(&format!("🐛 {} connected to :{}", gdb, port)),
+                        TerminalLine::pty_session(&session_id),
+                    ],
+                    exit_code: None,
+                    streaming: true,
+                },
+                Err(e) => TerminalResult::error(&e.to_string()),
+            }
+        }
         "disconnect" => TerminalResult::success(vec![
             TerminalLine::info("Stopping GDB server..."),
             TerminalLine::success("✓ GDB server stopped"),
         ]),
-        _ => TerminalResult::info("Usage: gdb connect|disconnect [--port N]"),
+        _ => TerminalResult::info("Usage: gdb connect [firmware.elf] [--port N] | gdb disconnect"),
     }
 }
 
@@ -611,11 +814,45 @@ fn cmd_echo(args: &[String]) -> TerminalResult {
 }
 
 fn cmd_export(cmd: &ParsedCommand) -> TerminalResult {
-    if cmd.args.is_empty() {
-        return TerminalResult::info("Usage: export VAR=value");
+    let file_flag = cmd.flags.get("file").and_then(|v| v.clone());
+    if cmd.args.is_empty() && file_flag.is_none() {
+        return TerminalResult::info("Usage: export VAR=value | <cmd> | export FILE | <cmd> | export --file FILE");
     }
-    
-    let assignment = &cmd.args[0];
+
+    // Piped input: export FILE with the previous stage's lines as extra args.
+    // A bare identifier is never taken as a file name, so `export PATH /usr/bin`
+    // does not write a file called PATH.
+    let (target, lines) = match file_flag {
+        Some(path) => (path, &cmd.args[..]),
+        None if !cmd.args[0].contains('=') && cmd.args.len() > 1 => {
+            if !looks_like_path(&cmd.args[0]) {
+                return TerminalResult::error(&format!(
+                    "export: '{}' is not a file path; use {}=value or export --file {}",
+                    cmd.args[0], cmd.args[0], cmd.args[0]
+                ));
+            }
+            (cmd.args[0].clone(), &cmd.args[1..])
+        }
+        None => return set_variable(&cmd.args[0]),
+    };
+
+    let content = lines.join("\n") + "\n";
+    match std::fs::write(&target, content) {
+        Ok(()) => TerminalResult::success(vec![
+            TerminalLine::success(&format!("✓ Wrote {} lines to {}", lines.len(), target)),
+        ]),
+        Err(e) => TerminalResult::error(&format!("Failed to write {}: {}", target, e)),
+    }
+}
+
+/// A path separator or a file extension marks an export target as a file
+fn looks_like_path(target: &str) -> bool {
+    target.contains('/')
+        || target.contains('\\')
+        || std::path::Path::new(target).extension().is_some()
+}
+
+fn set_variable(assignment: &str) -> TerminalResult {
     if let Some(eq_pos) = assignment.find('=') {
         let var = &assignment[..eq_pos];
         let val = &assignment[eq_pos + 1..];
@@ -713,6 +950,39 @@ fn cmd_gpio(cmd: &ParsedCommand) -> TerminalResult {
     }
 }
 
+/// Print a C GPIO driver for a pin such as `PA5`, pipe or redirect it to save it
+fn cmd_generate_gpio(cmd: &ParsedCommand) -> TerminalResult {
+    use crate::drivers::gpio::generate_gpio_driver;
+    use crate::drivers::templates::{DriverLanguage, GpioConfig, GpioMode, McuArch};
+
+    let Some(pin_name) = cmd.args.first() else {
+        return TerminalResult::info("Usage: generate_gpio PIN [input|output|analog]");
+    };
+    let upper = pin_name.to_uppercase();
+    let parsed = upper.strip_prefix('P')
+        .filter(|rest| rest.len() >= 2 && rest.as_bytes()[0].is_ascii_uppercase())
+        .and_then(|rest| Some((rest[..1].to_string(), rest[1..].parse::<u8>().ok()?)))
+        .filter(|(_, pin)| *pin < 16);
+    let Some((port, pin)) = parsed else {
+        return TerminalResult::error(&format!("Invalid pin: {} (expected e.g. PA5)", pin_name));
+    };
+    let mode = match cmd.args.get(1).map(|m| m.to_lowercase()).as_deref() {
+        None | Some("output") => GpioMode::Output,
+        Some("input") => GpioMode::Input,
+        Some("analog") => GpioMode::Analog,
+        Some(other) => return TerminalResult::error(&format!("Unknown GPIO mode: {}", other)),
+    };
+
+    let config = GpioConfig { port, pin, mode, ..GpioConfig::default() };
+    let output = generate_gpio_driver(&config, &McuArch::Stm32, &DriverLanguage::C);
+    let lines = output.header_file.iter()
+        .chain(std::iter::once(&output.source_file))
+        .flat_map(|file| file.lines())
+        .map(TerminalLine::output)
+        .collect();
+    TerminalResult::success(lines)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -725,6 +995,8 @@ mod tests {
             flags: HashMap::new(),
             operator: super::super::parser::CommandOperator::None,
             next: None,
+            redirect_stdout: None,
+            redirect_stdin: None,
         };
         let result = process_embedded_command(&cmd);
         assert!(result.success);
@@ -742,8 +1014,101 @@ mod tests {
             flags,
             operator: super::super::parser::CommandOperator::None,
             next: None,
+            redirect_stdout: None,
+            redirect_stdin: None,
         };
         let result = process_embedded_command(&cmd);
         assert!(result.success);
     }
+
+    #[test]
+    fn test_pipeline_ls_echo() {
+        let stages = super::super::parser::parse_pipeline("ls | echo", &HashMap::new()).unwrap();
+        let result = execute_pipeline(&stages);
+        assert!(result.success);
+        assert_eq!(result.output.len(), 1);
+        assert!(result.output[0].content.contains("📂 ."));
+    }
+
+    #[test]
+    fn test_pipeline_fsm_stats_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stats.txt");
+        let line = format!("fsm stats | export {}", path.display());
+
+        let result = execute_pipeline(&super::super::parser::parse_pipeline(&line, &HashMap::new()).unwrap());
+        assert!(result.success);
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("States: 4"));
+        assert_eq!(content.lines().count(), 5);
+    }
+
+    #[test]
+    fn test_export_rejects_bare_identifier_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let cwd = std::env::current_dir().unwrap();
+        let result = run("export PATH /usr/bin");
+        assert!(!result.success);
+        assert!(!cwd.join("PATH").exists());
+
+        // An explicit --file accepts any name
+        let path = dir.path().join("PATH");
+        let result = run(&format!("fsm stats | export --file {}", path.display()));
+        assert!(result.success);
+        assert!(std::fs::read_to_string(&path).unwrap().contains("States: 4"));
+    }
+
+    fn run(line: &str) -> TerminalResult {
+        execute_pipeline(&super::super::parser::parse_pipeline(line, &HashMap::new()).unwrap())
+    }
+
+    #[test]
+    fn test_generate_gpio() {
+        let result = run("generate_gpio pc13 input");
+        assert!(result.success);
+        assert!(result.output.iter().any(|l| l.content.contains("GPIO_PIN_13")));
+        assert!(!run("generate_gpio PZ99").success);
+        assert!(!run("generate_gpio PA5 sideways").success);
+    }
+
+    #[test]
+    fn test_redirect_truncate_and_append() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+
+        let result = run(&format!("echo first > {}", path.display()));
+        assert!(result.success);
+        assert!(result.output.is_empty());
+        run(&format!("echo second >> {}", path.display()));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+
+        run(&format!("echo third > {}", path.display()));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "third\n");
+    }
+
+    #[test]
+    fn test_redirect_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capture.hex");
+        std::fs::write(&path, "AA 55\n01 02\n").unwrap();
+
+        let result = run(&format!("echo < {}", path.display()));
+        assert!(result.success);
+        assert_eq!(result.output[0].content, "AA 55 01 02");
+
+        let result = run(&format!("echo < {}", dir.path().join("missing.hex").display()));
+        assert!(!result.success);
+        assert_eq!(result.output[0].line_type, "error");
+    }
+
+    #[test]
+    fn test_redirect_write_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("no_such_dir").join("out.txt");
+
+        let result = run(&format!("echo hello > {}", path.display()));
+        assert!(!result.success);
+        assert_eq!(result.output.len(), 1);
+        assert_eq!(result.output[0].line_type, "error");
+    }
 }
@@ -0,0 +1,265 @@
+// Persistent Command History
+// SQLite-backed terminal history shared across sessions and app restarts
+
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use thiserror::Error;
+
+/// Entries kept before the oldest are rotated out
+pub const MAX_ENTRIES: i64 = 10_000;
+
+/// History errors
+#[derive(Debug, Error)]
+pub enum HistoryError {
+    #[error("History database is not open")]
+    NotOpen,
+
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+lazy_static::lazy_static! {
+    /// History database opened at startup
+    static ref HISTORY: Mutex<Option<PersistentHistory>> = Mutex::new(None);
+
+    /// Session for commands whose caller has no terminal session of its own
+    static ref APP_SESSION_ID: String = uuid::Uuid::new_v4().to_string();
+}
+
+/// One executed command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub command: String,
+    pub exit_code: Option<i32>,
+    pub session_id: String,
+}
+
+/// Command history stored in SQLite with a full-text index
+pub struct PersistentHistory {
+    conn: Connection,
+}
+
+impl PersistentHistory {
+    /// Open (or create) the history database at `path`
+    pub fn open(path: &Path) -> Result<Self, HistoryError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, HistoryError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, HistoryError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                command TEXT NOT NULL,
+                exit_code INTEGER,
+                session_id TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS history_timestamp ON history(timestamp);
+            CREATE VIRTUAL TABLE IF NOT EXISTS history_fts USING fts5(
+                command, content='history', content_rowid='id'
+            );
+            CREATE TRIGGER IF NOT EXISTS history_ai AFTER INSERT ON history BEGIN
+                INSERT INTO history_fts(rowid, command) VALUES (new.id, new.command);
+            END;
+            CREATE TRIGGER IF NOT EXISTS history_ad AFTER DELETE ON history BEGIN
+                INSERT INTO history_fts(history_fts, rowid, command) VALUES ('delete', old.id, old.command);
+            END;",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// Record a command and rotate out entries beyond `MAX_ENTRIES`
+    pub fn append(&self, command: &str, exit_code: Option<i32>, session_id: &str) -> Result<(), HistoryError> {
+        self.conn.execute(
+            "INSERT INTO history (timestamp, command, exit_code, session_id) VALUES (?1, ?2, ?3, ?4)",
+            params![Utc::now().timestamp(), command, exit_code, session_id],
+        )?;
+        // Ids only grow, so the newest MAX_ENTRIES ids are the ones to keep
+        self.conn.execute(
+            "DELETE FROM history WHERE id <= (SELECT MAX(id) FROM history) - ?1",
+            params![MAX_ENTRIES],
+        )?;
+        Ok(())
+    }
+
+    /// Newest matching entries, prefix matches first then full-text matches
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<HistoryEntry>, HistoryError> {
+        let query = query.trim();
+        let limit = limit as i64;
+
+        if query.is_empty() {
+            return self.query_entries(
+                "SELECT id, timestamp, command, exit_code, session_id FROM history
+                 ORDER BY id DESC LIMIT ?1",
+                params![limit],
+            );
+        }
+
+        let pattern = format!("{}%", query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+        let mut entries = self.query_entries(
+            "SELECT id, timestamp, command, exit_code, session_id FROM history
+             WHERE command LIKE ?1 ESCAPE '\\' ORDER BY id DESC LIMIT ?2",
+            params![pattern, limit],
+        )?;
+
+        if (entries.len() as i64) < limit {
+            // Every word must appear, each as a prefix of a token
+            let fts_query = query.split_whitespace()
+                .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let text_matches = self.query_entries(
+                "SELECT h.id, h.timestamp, h.command, h.exit_code, h.session_id
+                 FROM history_fts JOIN history h ON h.id = history_fts.rowid
+                 WHERE history_fts MATCH ?1 ORDER BY h.id DESC LIMIT ?2",
+                params![fts_query, limit],
+            )?;
+            for entry in text_matches {
+                if (entries.len() as i64) >= limit {
+                    break;
+                }
+                if !entries.iter().any(|e| e.id == entry.id) {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Delete entries older than `older_than_days`, or everything
+    pub fn clear(&self, older_than_days: Option<u32>) -> Result<usize, HistoryError> {
+        let deleted = match older_than_days {
+            Some(days) => {
+                let cutoff = Utc::now().timestamp() - days as i64 * 86_400;
+                self.conn.execute("DELETE FROM history WHERE timestamp < ?1", params![cutoff])?
+            }
+            None => self.conn.execute("DELETE FROM history", [])?,
+        };
+        Ok(deleted)
+    }
+
+    pub fn len(&self) -> Result<usize, HistoryError> {
+        let count: i64 = self.conn.query_row("SELECT COUNT(*) FROM history", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    pub fn is_empty(&self) -> Result<bool, HistoryError> {
+        Ok(self.len()? == 0)
+    }
+
+    fn query_entries(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<HistoryEntry>, HistoryError> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let rows = stmt.query_map(params, |row| {
+            let timestamp: i64 = row.get(1)?;
+            Ok(HistoryEntry {
+                id: row.get(0)?,
+                timestamp: Utc.timestamp_opt(timestamp, 0).single().unwrap_or_default(),
+                command: row.get(2)?,
+                exit_code: row.get(3)?,
+                session_id: row.get(4)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+}
+
+/// Default history database in the app data directory
+pub fn default_history_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("neurobench")
+        .join("history.db")
+}
+
+/// Open the shared history database
+pub fn open_history() {
+    let path = default_history_path();
+    match PersistentHistory::open(&path) {
+        Ok(history) => *HISTORY.lock().unwrap() = Some(history),
+        Err(e) => log::warn!("Failed to open terminal history {}: {}", path.display(), e),
+    }
+}
+
+/// Append to the shared history, a no-op when it is not open
+pub fn record(command: &str, exit_code: Option<i32>, session_id: &str) {
+    if let Some(history) = HISTORY.lock().unwrap().as_ref() {
+        if let Err(e) = history.append(command, exit_code, session_id) {
+            log::warn!("Failed to record terminal history: {}", e);
+        }
+    }
+}
+
+/// Session id of this app run, for entry points without a terminal session
+pub fn app_session_id() -> &'static str {
+    &APP_SESSION_ID
+}
+
+/// Search the shared history
+pub fn search_history(query: &str, limit: usize) -> Result<Vec<HistoryEntry>, HistoryError> {
+    HISTORY.lock().unwrap().as_ref().ok_or(HistoryError::NotOpen)?.search(query, limit)
+}
+
+/// Clear the shared history, returns the number of deleted entries
+pub fn clear_history(older_than_days: Option<u32>) -> Result<usize, HistoryError> {
+    HISTORY.lock().unwrap().as_ref().ok_or(HistoryError::NotOpen)?.clear(older_than_days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_and_full_text_search() {
+        let history = PersistentHistory::open_in_memory().unwrap();
+        history.append("flash firmware.elf --probe stlink", Some(0), "s1").unwrap();
+        history.append("monitor uart --baud 115200", Some(0), "s1").unwrap();
+        history.append("build --release", Some(1), "s2").unwrap();
+        history.append("flash --probe jlink", Some(0), "s2").unwrap();
+
+        let prefix = history.search("flash", 10).unwrap();
+        assert_eq!(prefix.len(), 2);
+        assert_eq!(prefix[0].command, "flash --probe jlink");
+
+        let text = history.search("stlink", 10).unwrap();
+        assert_eq!(text.len(), 1);
+        assert_eq!(text[0].session_id, "s1");
+
+        assert_eq!(history.search("bau", 10).unwrap()[0].command, "monitor uart --baud 115200");
+        assert_eq!(history.search("", 2).unwrap().len(), 2);
+
+        // Callers without a terminal session share one id per app run
+        assert_eq!(app_session_id(), app_session_id());
+        assert!(uuid::Uuid::parse_str(app_session_id()).is_ok());
+    }
+
+    #[test]
+    fn test_rotation_and_clear() {
+        let history = PersistentHistory::open_in_memory().unwrap();
+        for i in 0..MAX_ENTRIES + 5 {
+            history.append(&format!("echo {}", i), Some(0), "s").unwrap();
+        }
+        assert_eq!(history.len().unwrap(), MAX_ENTRIES as usize);
+        assert!(history.search("echo 4", 1).unwrap()[0].command.starts_with("echo 4"));
+
+        assert_eq!(history.clear(Some(1)).unwrap(), 0);
+        assert_eq!(history.clear(None).unwrap(), MAX_ENTRIES as usize);
+        assert!(history.is_empty().unwrap());
+        assert!(history.search("echo", 5).unwrap().is_empty());
+    }
+}
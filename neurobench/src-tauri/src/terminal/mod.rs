@@ -6,11 +6,16 @@ pub mod executor;
 pub mod commands;
 pub mod autocomplete;
 pub mod themes;
+pub mod aliases;
+pub mod history;
+pub mod script;
+pub mod pty;
+pub mod monitor;
 
 use serde::{Deserialize, Serialize};
 pub use parser::{ParsedCommand, CommandOperator};
 pub use executor::{ExecutionResult, StreamOutput};
-pub use commands::process_embedded_command;
+pub use commands::{process_embedded_command, execute_pipeline};
 pub use autocomplete::{get_completions, CompletionItem};
 pub use themes::{TerminalTheme, get_theme};
 
@@ -128,6 +133,29 @@ impl TerminalLine {
         }
     }
 
+    /// Tells the frontend to attach to a PTY session, `content` is its id
+    pub fn pty_session(session_id: &str) -> Self {
+        Self {
+            line_type: "pty".to_string(),
+            content: session_id.to_string(),
+            ansi: None,
+        }
+    }
+
+    /// Tells the frontend to start `serial_monitor_start`, `content` is its JSON arguments
+    pub fn serial_monitor(port: &str, baud: u32, options: monitor::MonitorOptions) -> Self {
+        Self {
+            line_type: "serial_monitor".to_string(),
+            content: serde_json::json!({
+                "port": port,
+                "baud": baud,
+                "hex": options.hex,
+                "timestamps": options.timestamps,
+            }).to_string(),
+            ansi: None,
+        }
+    }
+
     pub fn with_ansi(content: &str, ansi_code: &str) -> Self {
         Self {
             line_type: "ansi".to_string(),
@@ -168,6 +196,7 @@ impl TerminalSession {
         if !command.is_empty() {
             self.history.push(command.to_string());
             self.history_index = self.history.len();
+            history::record(command, None, &self.id);
         }
     }
 
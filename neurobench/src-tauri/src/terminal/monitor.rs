@@ -0,0 +1,253 @@
+// UART Monitor
+// Streams a serial port as terminal lines through a `JobKind::Serial` job
+
+use super::parser::ParsedCommand;
+use super::TerminalLine;
+use crate::jobs::{CancelReason, EmitterMessage, InternalErrorCode, JobEmitter, JobKind, JobManager, JobRecord, JobTerminal};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncReadExt;
+use tokio_serial::{SerialPortBuilderExt, SerialStream};
+
+/// Bytes per row in hex mode
+pub const HEX_ROW_BYTES: usize = 16;
+/// A text line longer than this without a newline is flushed as is
+pub const MAX_LINE_BYTES: usize = 256;
+/// A partial line is flushed after the port has been quiet this long
+const IDLE_FLUSH: Duration = Duration::from_millis(100);
+
+/// `monitor uart` display options
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonitorOptions {
+    /// Show every byte as hex instead of detecting text
+    pub hex: bool,
+    /// Prefix lines with the time since the monitor started
+    pub timestamps: bool,
+}
+
+impl MonitorOptions {
+    /// `--hex`/`-x` and `--timestamps`/`-t`
+    pub fn from_command(cmd: &ParsedCommand) -> Self {
+        let has = |long: &str, short: &str| cmd.flags.contains_key(long) || cmd.flags.contains_key(short);
+        Self {
+            hex: has("hex", "x"),
+            timestamps: has("timestamps", "t"),
+        }
+    }
+}
+
+/// Turns a byte stream into terminal lines
+///
+/// Lines that are valid UTF-8 without control characters are shown as text,
+/// anything else falls back to a hex dump.
+pub struct LineDecoder {
+    options: MonitorOptions,
+    pending: Vec<u8>,
+}
+
+impl LineDecoder {
+    pub fn new(options: MonitorOptions) -> Self {
+        Self { options, pending: Vec::new() }
+    }
+
+    /// Feed received bytes, returning every line they complete
+    pub fn push(&mut self, bytes: &[u8], elapsed: Duration) -> Vec<TerminalLine> {
+        self.pending.extend_from_slice(bytes);
+        let mut lines = Vec::new();
+
+        if self.options.hex {
+            while self.pending.len() >= HEX_ROW_BYTES {
+                let row: Vec<u8> = self.pending.drain(..HEX_ROW_BYTES).collect();
+                lines.push(self.line(&hex_dump(&row), elapsed));
+            }
+            return lines;
+        }
+
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let raw: Vec<u8> = self.pending.drain(..=end).collect();
+            lines.push(self.decode(&raw[..end], elapsed));
+        }
+        if self.pending.len() > MAX_LINE_BYTES {
+            let raw = std::mem::take(&mut self.pending);
+            lines.push(self.decode(&raw, elapsed));
+        }
+        lines
+    }
+
+    /// Emit whatever partial line is buffered
+    pub fn flush(&mut self, elapsed: Duration) -> Option<TerminalLine> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let raw = std::mem::take(&mut self.pending);
+        Some(if self.options.hex {
+            self.line(&hex_dump(&raw), elapsed)
+        } else {
+            self.decode(&raw, elapsed)
+        })
+    }
+
+    fn decode(&self, raw: &[u8], elapsed: Duration) -> TerminalLine {
+        let raw = raw.strip_suffix(b"\r").unwrap_or(raw);
+        match std::str::from_utf8(raw) {
+            Ok(text) if !text.chars().any(|c| c.is_control() && c != '\t') => self.line(text, elapsed),
+            _ => self.line(&hex_dump(raw), elapsed),
+        }
+    }
+
+    fn line(&self, content: &str, elapsed: Duration) -> TerminalLine {
+        if self.options.timestamps {
+            TerminalLine::output(&format!("[{:>9.3}] {}", elapsed.as_secs_f64(), content))
+        } else {
+            TerminalLine::output(content)
+        }
+    }
+}
+
+fn hex_dump(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ")
+}
+
+// ==================== Monitor Job ====================
+
+/// Stream `port` as `serial:data` events until the job is cancelled or the port closes
+pub async fn run_serial_monitor_job(
+    job_manager: Arc<JobManager>,
+    port: String,
+    baud: u32,
+    options: MonitorOptions,
+    emit_event: impl Fn(String, serde_json::Value) + Send + Sync + 'static,
+) -> Result<String, String> {
+    // Fail before creating the job when the port cannot be opened
+    let stream = tokio_serial::new(&port, baud)
+        .open_native_async()
+        .map_err(|e| format!("Failed to open {}: {}", port, e))?;
+
+    let (record, _tx) = job_manager.create_job(JobKind::Serial);
+    let job_id = record.id.clone();
+
+    tokio::spawn(async move {
+        monitor_worker(record, stream, port, baud, options, job_manager, emit_event).await;
+    });
+
+    Ok(job_id)
+}
+
+/// Open a UART monitor from the terminal, returning its job id
+pub async fn cmd_monitor_uart_stream(
+    port: &str,
+    baud: u32,
+    options: MonitorOptions,
+    app: &tauri::AppHandle,
+) -> Result<String, String> {
+    use tauri::{Emitter, Manager};
+
+    let job_manager = app.state::<crate::AppState>().job_manager.clone();
+    let app = app.clone();
+    run_serial_monitor_job(job_manager, port.to_string(), baud, options, move |event_name, payload| {
+        let _ = app.emit(&event_name, &payload);
+    }).await
+}
+
+async fn monitor_worker(
+    record: Arc<JobRecord>,
+    mut stream: SerialStream,
+    port: String,
+    baud: u32,
+    options: MonitorOptions,
+    job_manager: Arc<JobManager>,
+    emit_event: impl Fn(String, serde_json::Value) + Send + Sync,
+) {
+    let mut emitter = JobEmitter::new(&record);
+    let start = Instant::now();
+    let mut decoder = LineDecoder::new(options);
+    let mut buffer = [0u8; 1024];
+
+    if let Some((event_name, payload)) = emitter.process(EmitterMessage::Custom {
+        event_suffix: "started".to_string(),
+        payload: serde_json::json!({ "type": "started", "port": port, "baud": baud, "options": options }),
+    }).await {
+        emit_event(event_name, payload);
+    }
+
+    let terminal = loop {
+        let read = tokio::select! {
+            _ = record.cancel_token.cancelled() => {
+                break JobTerminal::Cancelled { reason: CancelReason::UserRequest };
+            }
+            read = tokio::time::timeout(IDLE_FLUSH, stream.read(&mut buffer)) => read,
+        };
+
+        let lines = match read {
+            Ok(Ok(0)) => break JobTerminal::Completed {
+                success: true,
+                exit_code: Some(0),
+                duration_ms: start.elapsed().as_millis() as u64,
+            },
+            Ok(Ok(n)) => decoder.push(&buffer[..n], start.elapsed()),
+            Ok(Err(e)) => break JobTerminal::InternalError {
+                error_code: InternalErrorCode::IoError,
+                message: format!("{}: {}", port, e),
+                retryable: true,
+            },
+            Err(_) => decoder.flush(start.elapsed()).into_iter().collect(),
+        };
+        if lines.is_empty() {
+            continue;
+        }
+
+        for line in &lines {
+            // Keep the job log in step with what the terminal shows
+            record.log.lock().await.push(line.content.clone());
+        }
+        if let Some((event_name, payload)) = emitter.process(EmitterMessage::Custom {
+            event_suffix: "data".to_string(),
+            payload: serde_json::json!({ "type": "data", "port": port, "lines": lines }),
+        }).await {
+            emit_event(event_name, payload);
+        }
+    };
+
+    if let Some((event_name, payload)) = emitter.process(EmitterMessage::Terminal { terminal }).await {
+        emit_event(event_name, payload);
+    }
+    job_manager.finish_job(&record.id).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_and_binary_lines() {
+        let mut decoder = LineDecoder::new(MonitorOptions::default());
+        let lines = decoder.push(b"ADC: 2048\r\nTemp: 25.3", Duration::ZERO);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].content, "ADC: 2048");
+
+        let lines = decoder.push(b"\xC2\xB0C\n\x01\x02\xFF\n", Duration::ZERO);
+        let content: Vec<&str> = lines.iter().map(|l| l.content.as_str()).collect();
+        assert_eq!(content, vec!["Temp: 25.3°C", "01 02 FF"]);
+
+        decoder.push(b"partial", Duration::ZERO);
+        assert_eq!(decoder.flush(Duration::ZERO).unwrap().content, "partial");
+        assert!(decoder.flush(Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn test_hex_mode_and_timestamps() {
+        let mut decoder = LineDecoder::new(MonitorOptions { hex: true, timestamps: true });
+        let bytes: Vec<u8> = (0..20).collect();
+        let lines = decoder.push(&bytes, Duration::from_millis(1500));
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].content, "[    1.500] 00 01 02 03 04 05 06 07 08 09 0A 0B 0C 0D 0E 0F");
+        assert_eq!(decoder.flush(Duration::from_millis(1600)).unwrap().content, "[    1.600] 10 11 12 13");
+    }
+
+    #[test]
+    fn test_options_from_command() {
+        let cmd = super::super::parser::parse_command_line("monitor uart COM3 --hex -t", &Default::default()).unwrap();
+        assert_eq!(MonitorOptions::from_command(&cmd[0]), MonitorOptions { hex: true, timestamps: true });
+    }
+}
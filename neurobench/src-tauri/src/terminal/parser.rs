@@ -3,6 +3,7 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use thiserror::Error;
 
 /// Command operator for chaining
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -14,6 +15,13 @@ pub enum CommandOperator {
     Background,     // &
 }
 
+/// How `>`/`>>` open the target file
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RedirectMode {
+    Truncate,       // >
+    Append,         // >>
+}
+
 /// Parsed command with flags and arguments
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParsedCommand {
@@ -22,6 +30,36 @@ pub struct ParsedCommand {
     pub flags: HashMap<String, Option<String>>,
     pub operator: CommandOperator,
     pub next: Option<Box<ParsedCommand>>,
+    /// `> file` or `>> file`
+    #[serde(default)]
+    pub redirect_stdout: Option<(RedirectMode, String)>,
+    /// `< file`
+    #[serde(default)]
+    pub redirect_stdin: Option<String>,
+}
+
+/// Redirect operator waiting for its file name
+#[derive(Clone, Copy)]
+enum PendingRedirect {
+    Stdout(RedirectMode),
+    Stdin,
+}
+
+impl PendingRedirect {
+    fn operator(self) -> &'static str {
+        match self {
+            PendingRedirect::Stdout(RedirectMode::Truncate) => ">",
+            PendingRedirect::Stdout(RedirectMode::Append) => ">>",
+            PendingRedirect::Stdin => "<",
+        }
+    }
+}
+
+/// Command line errors
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ParseError {
+    #[error("syntax error: `{0}` needs a file name")]
+    MissingRedirectTarget(&'static str),
 }
 
 /// Command flag definition for autocomplete
@@ -35,13 +73,13 @@ pub struct FlagDef {
 }
 
 /// Parse a full command line (may contain multiple commands)
-pub fn parse_command_line(input: &str, variables: &HashMap<String, String>) -> Vec<ParsedCommand> {
+pub fn parse_command_line(input: &str, variables: &HashMap<String, String>) -> Result<Vec<ParsedCommand>, ParseError> {
     let expanded = expand_variables(input, variables);
     let mut commands = Vec::new();
     let mut remaining = expanded.as_str().trim();
 
     while !remaining.is_empty() {
-        let (cmd, rest, operator) = parse_single_command(remaining);
+        let (cmd, rest, operator) = parse_single_command(remaining)?;
         if !cmd.command.is_empty() {
             commands.push(cmd);
         }
@@ -53,11 +91,33 @@ pub fn parse_command_line(input: &str, variables: &HashMap<String, String>) -> V
         }
     }
 
-    commands
+    Ok(commands)
+}
+
+/// Parse a command line into pipeline stages
+///
+/// Each stage is the command sequence between two `|` operators, so
+/// `fsm stats && fsm validate | export out.txt` has two stages.
+pub fn parse_pipeline(input: &str, variables: &HashMap<String, String>) -> Result<Vec<Vec<ParsedCommand>>, ParseError> {
+    let mut stages = Vec::new();
+    let mut stage = Vec::new();
+
+    for cmd in parse_command_line(input, variables)? {
+        let ends_stage = cmd.operator == CommandOperator::Pipe;
+        stage.push(cmd);
+        if ends_stage {
+            stages.push(std::mem::take(&mut stage));
+        }
+    }
+    if !stage.is_empty() {
+        stages.push(stage);
+    }
+
+    Ok(stages)
 }
 
 /// Parse a single command with its flags and arguments
-fn parse_single_command(input: &str) -> (ParsedCommand, &str, CommandOperator) {
+fn parse_single_command(input: &str) -> Result<(ParsedCommand, &str, CommandOperator), ParseError> {
     let mut tokens = Vec::new();
     let mut current = String::new();
     let mut in_quotes = false;
@@ -65,6 +125,18 @@ fn parse_single_command(input: &str) -> (ParsedCommand, &str, CommandOperator) {
     let mut chars = input.chars().peekable();
     let mut operator = CommandOperator::None;
     let mut consumed = 0;
+    let mut pending: Option<PendingRedirect> = None;
+    let mut redirect_stdout = None;
+    let mut redirect_stdin = None;
+
+    // A finished word is either the target of a pending redirect or a token
+    let mut finish_word = |word: String, pending: &mut Option<PendingRedirect>, tokens: &mut Vec<String>| {
+        match pending.take() {
+            Some(PendingRedirect::Stdout(mode)) => redirect_stdout = Some((mode, word)),
+            Some(PendingRedirect::Stdin) => redirect_stdin = Some(word),
+            None => tokens.push(word),
+        }
+    };
 
     while let Some(c) = chars.next() {
         consumed += c.len_utf8();
@@ -99,10 +171,28 @@ fn parse_single_command(input: &str) -> (ParsedCommand, &str, CommandOperator) {
                     break;
                 }
             }
+            '>' if !in_quotes => {
+                if !current.is_empty() {
+                    finish_word(std::mem::take(&mut current), &mut pending, &mut tokens);
+                }
+                let mode = if chars.peek() == Some(&'>') {
+                    chars.next();
+                    consumed += 1;
+                    RedirectMode::Append
+                } else {
+                    RedirectMode::Truncate
+                };
+                pending = Some(PendingRedirect::Stdout(mode));
+            }
+            '<' if !in_quotes => {
+                if !current.is_empty() {
+                    finish_word(std::mem::take(&mut current), &mut pending, &mut tokens);
+                }
+                pending = Some(PendingRedirect::Stdin);
+            }
             ' ' | '\t' if !in_quotes => {
                 if !current.is_empty() {
-                    tokens.push(current.clone());
-                    current.clear();
+                    finish_word(std::mem::take(&mut current), &mut pending, &mut tokens);
                 }
             }
             _ => current.push(c),
@@ -110,7 +200,11 @@ fn parse_single_command(input: &str) -> (ParsedCommand, &str, CommandOperator) {
     }
 
     if !current.is_empty() {
-        tokens.push(current);
+        finish_word(current, &mut pending, &mut tokens);
+    }
+    // `cmd >` or `cmd > && next` leaves the redirect without a target
+    if let Some(redirect) = pending {
+        return Err(ParseError::MissingRedirectTarget(redirect.operator()));
     }
 
     let (command, args, flags) = if tokens.is_empty() {
@@ -123,17 +217,19 @@ fn parse_single_command(input: &str) -> (ParsedCommand, &str, CommandOperator) {
 
     let remaining = &input[consumed..];
 
-    (
+    Ok((
         ParsedCommand {
             command,
             args,
             flags,
             operator: operator.clone(),
             next: None,
+            redirect_stdout,
+            redirect_stdin,
         },
         remaining,
         operator,
-    )
+    ))
 }
 
 /// Parse arguments and flags from tokens
@@ -311,7 +407,7 @@ mod tests {
     #[test]
     fn test_simple_command() {
         let vars = HashMap::new();
-        let cmds = parse_command_line("flash firmware.elf", &vars);
+        let cmds = parse_command_line("flash firmware.elf", &vars).unwrap();
         assert_eq!(cmds.len(), 1);
         assert_eq!(cmds[0].command, "flash");
         assert_eq!(cmds[0].args, vec!["firmware.elf"]);
@@ -320,7 +416,7 @@ mod tests {
     #[test]
     fn test_command_with_flags() {
         let vars = HashMap::new();
-        let cmds = parse_command_line("flash --probe stlink --speed 8000 firmware.elf", &vars);
+        let cmds = parse_command_line("flash --probe stlink --speed 8000 firmware.elf", &vars).unwrap();
         assert_eq!(cmds[0].flags.get("probe"), Some(&Some("stlink".to_string())));
         assert_eq!(cmds[0].flags.get("speed"), Some(&Some("8000".to_string())));
     }
@@ -328,18 +424,66 @@ mod tests {
     #[test]
     fn test_chained_commands() {
         let vars = HashMap::new();
-        let cmds = parse_command_line("build && flash && monitor uart", &vars);
+        let cmds = parse_command_line("build && flash && monitor uart", &vars).unwrap();
         assert_eq!(cmds.len(), 3);
         assert_eq!(cmds[0].command, "build");
         assert_eq!(cmds[1].command, "flash");
         assert_eq!(cmds[2].command, "monitor");
     }
 
+    #[test]
+    fn test_parse_pipeline() {
+        let vars = HashMap::new();
+        let stages = parse_pipeline("build && fsm stats | export stats.txt", &vars).unwrap();
+        assert_eq!(stages.len(), 2);
+        assert_eq!(stages[0].len(), 2);
+        assert_eq!(stages[0][1].operator, CommandOperator::Pipe);
+        assert_eq!(stages[1][0].command, "export");
+
+        // || is an operator, not a pipe
+        assert_eq!(parse_pipeline("build || clean", &vars).unwrap().len(), 1);
+    }
+
     #[test]
     fn test_variable_expansion() {
         let mut vars = HashMap::new();
         vars.insert("MCU".to_string(), "STM32F401".to_string());
-        let cmds = parse_command_line("flash --target $MCU", &vars);
+        let cmds = parse_command_line("flash --target $MCU", &vars).unwrap();
         assert_eq!(cmds[0].flags.get("target"), Some(&Some("STM32F401".to_string())));
     }
+
+    #[test]
+    fn test_output_redirects() {
+        let vars = HashMap::new();
+        let cmds = parse_command_line("fsm stats > stats.txt", &vars).unwrap();
+        assert_eq!(cmds[0].args, vec!["stats"]);
+        assert_eq!(cmds[0].redirect_stdout, Some((RedirectMode::Truncate, "stats.txt".to_string())));
+
+        let cmds = parse_command_line("echo done >>build.log && flash", &vars).unwrap();
+        assert_eq!(cmds[0].args, vec!["done"]);
+        assert_eq!(cmds[0].redirect_stdout, Some((RedirectMode::Append, "build.log".to_string())));
+        assert_eq!(cmds[1].redirect_stdout, None);
+
+        // Quoted operators are plain text
+        let cmds = parse_command_line("echo \"a > b\"", &vars).unwrap();
+        assert_eq!(cmds[0].args, vec!["a > b"]);
+        assert_eq!(cmds[0].redirect_stdout, None);
+    }
+
+    #[test]
+    fn test_input_redirect() {
+        let vars = HashMap::new();
+        let cmds = parse_command_line("serial decode < capture.hex > decoded.txt", &vars).unwrap();
+        assert_eq!(cmds[0].args, vec!["decode"]);
+        assert_eq!(cmds[0].redirect_stdin.as_deref(), Some("capture.hex"));
+        assert_eq!(cmds[0].redirect_stdout, Some((RedirectMode::Truncate, "decoded.txt".to_string())));
+    }
+
+    #[test]
+    fn test_redirect_without_target() {
+        let vars = HashMap::new();
+        assert_eq!(parse_command_line("fsm stats >", &vars).unwrap_err(), ParseError::MissingRedirectTarget(">"));
+        assert_eq!(parse_command_line("echo hi >> && flash", &vars).unwrap_err(), ParseError::MissingRedirectTarget(">>"));
+        assert_eq!(parse_pipeline("serial decode < | echo", &vars).unwrap_err(), ParseError::MissingRedirectTarget("<"));
+    }
 }
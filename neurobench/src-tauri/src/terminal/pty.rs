@@ -0,0 +1,250 @@
+// Native PTY Sessions
+// Run interactive programs (gdb, minicom, shells) with full TTY behavior
+
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Event carrying raw output bytes of a session
+pub const PTY_OUTPUT_EVENT: &str = "pty:output";
+/// Event sent once when a session's process exits
+pub const PTY_EXIT_EVENT: &str = "pty:exit";
+
+const DEFAULT_COLS: u16 = 120;
+const DEFAULT_ROWS: u16 = 32;
+
+type EventSink = Box<dyn Fn(PtyEvent) + Send + Sync>;
+/// Shared so a write can run without holding the session table's lock
+type PtyWriter = Arc<Mutex<Box<dyn Write + Send>>>;
+
+lazy_static::lazy_static! {
+    /// Running sessions keyed by session id
+    static ref SESSIONS: Mutex<HashMap<String, PtySession>> = Mutex::new(HashMap::new());
+    /// Where `spawn_attached` sessions report, set once the app is up
+    static ref EVENT_SINK: RwLock<Option<EventSink>> = RwLock::new(None);
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PtyError {
+    #[error("No PTY session {0}")]
+    NotFound(String),
+    #[error("Failed to start {command}: {message}")]
+    Spawn { command: String, message: String },
+    #[error("PTY I/O error: {0}")]
+    Io(String),
+}
+
+/// `pty:output` payload; `data` is raw bytes including escape sequences
+#[derive(Debug, Clone, Serialize)]
+pub struct PtyOutput {
+    pub session_id: String,
+    pub data: Vec<u8>,
+}
+
+/// `pty:exit` payload
+#[derive(Debug, Clone, Serialize)]
+pub struct PtyExit {
+    pub session_id: String,
+    /// `None` when the exit status could not be collected
+    pub exit_code: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub enum PtyEvent {
+    Output(PtyOutput),
+    Exit(PtyExit),
+}
+
+/// Running program attached to a pseudo-terminal
+pub struct PtySession {
+    pub command: String,
+    master: Box<dyn MasterPty + Send>,
+    writer: PtyWriter,
+    child: Box<dyn Child + Send + Sync>,
+}
+
+impl PtySession {
+    /// Start `command` on a new PTY
+    ///
+    /// Returns the session and the reader for the terminal's output.
+    pub fn spawn(
+        command: &str,
+        args: &[String],
+        env: &HashMap<String, String>,
+        cwd: Option<&str>,
+    ) -> Result<(Self, Box<dyn Read + Send>), PtyError> {
+        let spawn_err = |e: &dyn std::fmt::Display| PtyError::Spawn { command: command.to_string(), message: e.to_string() };
+
+        let pair = native_pty_system()
+            .openpty(PtySize { rows: DEFAULT_ROWS, cols: DEFAULT_COLS, pixel_width: 0, pixel_height: 0 })
+            .map_err(|e| spawn_err(&e))?;
+
+        let mut cmd = CommandBuilder::new(command);
+        cmd.args(args);
+        cmd.env("TERM", "xterm-256color");
+        for (key, value) in env {
+            cmd.env(key, value);
+        }
+        if let Some(dir) = cwd {
+            cmd.cwd(dir);
+        }
+
+        let child = pair.slave.spawn_command(cmd).map_err(|e| spawn_err(&e))?;
+        // The child holds its own handle; keeping ours would hide EOF after it exits
+        drop(pair.slave);
+
+        let reader = pair.master.try_clone_reader().map_err(|e| spawn_err(&e))?;
+        let writer = pair.master.take_writer().map_err(|e| spawn_err(&e))?;
+        Ok((Self { command: command.to_string(), master: pair.master, writer: Arc::new(Mutex::new(writer)), child }, reader))
+    }
+
+    pub fn write(&self, data: &[u8]) -> Result<(), PtyError> {
+        write_all(&self.writer, data)
+    }
+
+    pub fn resize(&self, cols: u16, rows: u16) -> Result<(), PtyError> {
+        self.master
+            .resize(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 })
+            .map_err(|e| PtyError::Io(e.to_string()))
+    }
+
+    pub fn kill(&mut self) -> Result<(), PtyError> {
+        self.child.kill().map_err(|e| PtyError::Io(e.to_string()))
+    }
+
+    pub fn process_id(&self) -> Option<u32> {
+        self.child.process_id()
+    }
+}
+
+/// Spawn a session and pump its output on a background thread
+///
+/// `on_output` gets each chunk as it is read; `on_exit` runs once the
+/// terminal closes, after the session has been removed. Returns the session id.
+pub fn spawn_session(
+    command: &str,
+    args: &[String],
+    env: &HashMap<String, String>,
+    cwd: Option<&str>,
+    mut on_output: impl FnMut(PtyOutput) + Send + 'static,
+    on_exit: impl FnOnce(PtyExit) + Send + 'static,
+) -> Result<String, PtyError> {
+    let (session, mut reader) = PtySession::spawn(command, args, env, cwd)?;
+    let session_id = uuid::Uuid::new_v4().to_string();
+    SESSIONS.lock().unwrap().insert(session_id.clone(), session);
+
+    let id = session_id.clone();
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => on_output(PtyOutput { session_id: id.clone(), data: buf[..n].to_vec() }),
+            }
+        }
+
+        // Take the session out before waiting so other calls don't block on the lock
+        let session = SESSIONS.lock().unwrap().remove(&id);
+        let exit_code = session.and_then(|mut s| s.child.wait().ok()).map(|status| status.exit_code());
+        on_exit(PtyExit { session_id: id, exit_code });
+    });
+
+    Ok(session_id)
+}
+
+/// Route events of sessions started with `spawn_attached`
+pub fn set_event_sink(sink: impl Fn(PtyEvent) + Send + Sync + 'static) {
+    *EVENT_SINK.write().unwrap() = Some(Box::new(sink));
+}
+
+fn emit(event: PtyEvent) {
+    if let Some(sink) = EVENT_SINK.read().unwrap().as_ref() {
+        sink(event);
+    }
+}
+
+/// Spawn a session whose output and exit go to the registered event sink
+pub fn spawn_attached(
+    command: &str,
+    args: &[String],
+    env: &HashMap<String, String>,
+    cwd: Option<&str>,
+) -> Result<String, PtyError> {
+    spawn_session(command, args, env, cwd, |output| emit(PtyEvent::Output(output)), |exit| emit(PtyEvent::Exit(exit)))
+}
+
+fn write_all(writer: &PtyWriter, data: &[u8]) -> Result<(), PtyError> {
+    let mut writer = writer.lock().unwrap();
+    writer.write_all(data).and_then(|_| writer.flush()).map_err(|e| PtyError::Io(e.to_string()))
+}
+
+fn with_session<T>(session_id: &str, f: impl FnOnce(&mut PtySession) -> Result<T, PtyError>) -> Result<T, PtyError> {
+    let mut sessions = SESSIONS.lock().unwrap();
+    let session = sessions.get_mut(session_id).ok_or_else(|| PtyError::NotFound(session_id.to_string()))?;
+    f(session)
+}
+
+/// Send input to a session
+pub fn write_session(session_id: &str, data: &[u8]) -> Result<(), PtyError> {
+    // A write blocks while the program isn't reading, so do it outside the session table's lock
+    let writer = with_session(session_id, |s| Ok(s.writer.clone()))?;
+    write_all(&writer, data)
+}
+
+/// Resize a session's terminal
+pub fn resize_session(session_id: &str, cols: u16, rows: u16) -> Result<(), PtyError> {
+    with_session(session_id, |s| s.resize(cols, rows))
+}
+
+/// Terminate a session's process; the output thread then reports the exit
+pub fn kill_session(session_id: &str) -> Result<(), PtyError> {
+    with_session(session_id, |s| s.kill())
+}
+
+/// Ids of running sessions with their commands
+pub fn list_sessions() -> Vec<(String, String)> {
+    SESSIONS.lock().unwrap().iter().map(|(id, s)| (id.clone(), s.command.clone())).collect()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_pty_session_roundtrip() {
+        let (out_tx, out_rx) = mpsc::channel();
+        let (exit_tx, exit_rx) = mpsc::channel();
+        let id = spawn_session(
+            "cat",
+            &[],
+            &HashMap::new(),
+            None,
+            move |chunk| { let _ = out_tx.send(chunk.data); },
+            move |exit| { let _ = exit_tx.send(exit); },
+        ).unwrap();
+
+        assert!(list_sessions().iter().any(|(sid, cmd)| sid == &id && cmd == "cat"));
+        resize_session(&id, 80, 24).unwrap();
+        write_session(&id, b"hello pty\n").unwrap();
+
+        let mut echoed = Vec::new();
+        while !String::from_utf8_lossy(&echoed).contains("hello pty") {
+            echoed.extend(out_rx.recv_timeout(Duration::from_secs(5)).unwrap());
+        }
+
+        kill_session(&id).unwrap();
+        let exit = exit_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(exit.session_id, id);
+        assert!(matches!(write_session(&id, b"x"), Err(PtyError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_spawn_missing_program() {
+        let result = spawn_session("definitely-not-a-program-xyz", &[], &HashMap::new(), None, |_| {}, |_| {});
+        assert!(matches!(result, Err(PtyError::Spawn { .. })));
+    }
+}
@@ -0,0 +1,339 @@
+// Script Execution
+// Runs `.nbsh` files line by line through the embedded command pipeline
+
+use super::commands::execute_pipeline;
+use super::parser::{expand_variables, parse_pipeline};
+use super::{TerminalLine, TerminalResult};
+use crate::jobs::{EmitterMessage, InternalErrorCode, JobEmitter, JobKind, JobManager, JobRecord, JobTerminal, CancelReason};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+/// Interpreter named in the shebang of NeuroBench scripts
+pub const SHEBANG: &str = "#!/usr/bin/neurobench";
+
+/// Script errors
+#[derive(Debug, Error)]
+pub enum ScriptError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Unsupported interpreter: {0}")]
+    UnsupportedInterpreter(String),
+
+    #[error("Line {line}: command failed: {command}")]
+    CommandFailed { line: usize, command: String },
+
+    #[error("Script cancelled")]
+    Cancelled,
+}
+
+/// Whether a file is a NeuroBench script, by extension or shebang
+pub fn is_nbsh_script(path: &Path) -> bool {
+    if path.extension().is_some_and(|ext| ext == "nbsh") {
+        return true;
+    }
+    std::fs::read_to_string(path)
+        .map(|content| content.lines().next().is_some_and(is_neurobench_shebang))
+        .unwrap_or(false)
+}
+
+/// `#!/usr/bin/neurobench` or `#!/usr/bin/env neurobench`
+fn is_neurobench_shebang(line: &str) -> bool {
+    let Some(interpreter) = line.trim().strip_prefix("#!") else { return false };
+    let mut parts = interpreter.split_whitespace();
+    match parts.next() {
+        Some(program) if program.ends_with("/env") => parts.next() == Some("neurobench"),
+        Some(program) => program.rsplit('/').next() == Some("neurobench"),
+        None => false,
+    }
+}
+
+/// Run a script and collect all output lines
+pub fn execute_script(path: &Path, variables: HashMap<String, String>) -> Result<Vec<TerminalLine>, ScriptError> {
+    let (tx, mut rx) = unbounded_channel();
+    stream_script(path, variables, &tx)?;
+    drop(tx);
+
+    let mut lines = Vec::new();
+    while let Ok(line) = rx.try_recv() {
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+/// Run a script, sending output lines as each command finishes
+///
+/// Stops at the first failed command unless the script runs `set +e`.
+/// Dropping the receiver cancels the script before its next line.
+pub fn stream_script(
+    path: &Path,
+    variables: HashMap<String, String>,
+    tx: &UnboundedSender<TerminalLine>,
+) -> Result<(), ScriptError> {
+    let content = std::fs::read_to_string(path)?;
+    run_script_source(&content, variables, tx)
+}
+
+fn run_script_source(
+    source: &str,
+    mut variables: HashMap<String, String>,
+    tx: &UnboundedSender<TerminalLine>,
+) -> Result<(), ScriptError> {
+    let mut errexit = true;
+
+    for (index, raw) in source.lines().enumerate() {
+        let line_no = index + 1;
+        let line = raw.trim();
+
+        if line_no == 1 && line.starts_with("#!") {
+            if !is_neurobench_shebang(line) {
+                return Err(ScriptError::UnsupportedInterpreter(line.trim_start_matches("#!").trim().to_string()));
+            }
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if tx.is_closed() {
+            return Err(ScriptError::Cancelled);
+        }
+
+        match line {
+            "set -e" => {
+                errexit = true;
+                continue;
+            }
+            "set +e" => {
+                errexit = false;
+                continue;
+            }
+            _ => {}
+        }
+
+        if let Some((name, value)) = parse_assignment(line) {
+            let value = expand_variables(value, &variables);
+            variables.insert(name.to_string(), value);
+            continue;
+        }
+
+        // A syntax error fails the line like a failed command
+        let result = match parse_pipeline(line, &variables) {
+            Ok(stages) => execute_pipeline(&stages),
+            Err(e) => TerminalResult::error(&e.to_string()),
+        };
+        for output in result.output {
+            // A closed channel is picked up before the next command
+            let _ = tx.send(output);
+        }
+
+        if !result.success && errexit {
+            return Err(ScriptError::CommandFailed { line: line_no, command: line.to_string() });
+        }
+    }
+
+    Ok(())
+}
+
+/// `NAME=value` with an identifier name, quotes around the value are dropped
+fn parse_assignment(line: &str) -> Option<(&str, &str)> {
+    let (name, value) = line.split_once('=')?;
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return None;
+    }
+
+    let value = value.trim();
+    let unquoted = value.strip_prefix('"').and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value);
+    Some((name, unquoted))
+}
+
+/// Template for the common build, flash and monitor cycle
+pub fn generate_flash_script(elf_path: &str, probe: &str, port: &str, baud: u32) -> String {
+    format!(
+        "{shebang}\n\
+         # Build, flash and open a serial monitor\n\
+         \n\
+         ELF=\"{elf}\"\n\
+         PROBE={probe}\n\
+         PORT={port}\n\
+         BAUD={baud}\n\
+         \n\
+         build\n\
+         flash $ELF --probe $PROBE\n\
+         verify $ELF\n\
+         monitor uart $PORT --baud $BAUD\n",
+        shebang = SHEBANG,
+        elf = elf_path,
+        probe = probe,
+        port = port,
+        baud = baud,
+    )
+}
+
+// ==================== Script Job Runner ====================
+
+/// Run a script as a job, streaming output through `JobEmitter`
+///
+/// The script run is recorded in `session_id`'s command history once it ends.
+pub async fn run_script_job(
+    job_manager: Arc<JobManager>,
+    path: PathBuf,
+    variables: HashMap<String, String>,
+    session_id: String,
+    emit_event: impl Fn(String, serde_json::Value) + Send + Sync + 'static,
+) -> Result<String, String> {
+    if !path.exists() {
+        return Err(format!("Script not found: {}", path.display()));
+    }
+
+    let (record, _tx) = job_manager.create_job(JobKind::Script);
+    let job_id = record.id.clone();
+
+    tokio::spawn(async move {
+        run_script_worker(record, path, variables, session_id, job_manager, emit_event).await;
+    });
+
+    Ok(job_id)
+}
+
+async fn run_script_worker(
+    record: Arc<JobRecord>,
+    path: PathBuf,
+    variables: HashMap<String, String>,
+    session_id: String,
+    job_manager: Arc<JobManager>,
+    emit_event: impl Fn(String, serde_json::Value) + Send + Sync,
+) {
+    let mut emitter = JobEmitter::new(&record);
+    let start = std::time::Instant::now();
+
+    if let Some((event_name, payload)) = emitter.process(EmitterMessage::Custom {
+        event_suffix: "started".to_string(),
+        payload: serde_json::json!({ "type": "started", "path": path.display().to_string() }),
+    }).await {
+        emit_event(event_name, payload);
+    }
+
+    // Queue behind other scripts; scripts are not preemptible, so once running
+    // a script only stops when it finishes or is cancelled
+    let scheduler = job_manager.scheduler();
+    let (cancelled, result) = match scheduler.acquire(&record.id, JobKind::Script, &record.cancel_token).await {
+        None => (true, Ok(Ok(()))),
+        Some(attempt) => {
+            let (tx, mut rx) = unbounded_channel();
+            let script_path = path.clone();
+            let worker = tokio::task::spawn_blocking(move || stream_script(&script_path, variables, &tx));
+
+            let mut stopped = false;
+            loop {
+                tokio::select! {
+                    _ = attempt.cancelled(), if !stopped => {
+                        // Closing the channel stops the script before its next line
+                        rx.close();
+                        stopped = true;
+                    }
+                    line = rx.recv() => match line {
+                        Some(line) => {
+                            if let Some((event_name, payload)) = emitter.process(EmitterMessage::Log { line: line.content }).await {
+                                emit_event(event_name, payload);
+                            }
+                        }
+                        None => break,
+                    }
+                }
+            }
+            let result = worker.await;
+            scheduler.release(&record.id);
+            (stopped, result)
+        }
+    };
+
+    let terminal = match result {
+        _ if cancelled => JobTerminal::Cancelled { reason: CancelReason::UserRequest },
+        Ok(Ok(())) => JobTerminal::Completed {
+            success: true,
+            exit_code: Some(0),
+            duration_ms: start.elapsed().as_millis() as u64,
+        },
+        Ok(Err(ScriptError::CommandFailed { line, command })) => {
+            let message = format!("Script stopped at line {}: {}", line, command);
+            if let Some((event_name, payload)) = emitter.process(EmitterMessage::Log { line: message }).await {
+                emit_event(event_name, payload);
+            }
+            JobTerminal::Completed {
+                success: false,
+                exit_code: Some(1),
+                duration_ms: start.elapsed().as_millis() as u64,
+            }
+        }
+        Ok(Err(e)) => JobTerminal::InternalError {
+            error_code: InternalErrorCode::IoError,
+            message: e.to_string(),
+            retryable: false,
+        },
+        Err(e) => JobTerminal::InternalError {
+            error_code: InternalErrorCode::Unknown,
+            message: e.to_string(),
+            retryable: false,
+        },
+    };
+
+    let exit_code = match &terminal {
+        JobTerminal::Completed { exit_code, .. } => *exit_code,
+        _ => None,
+    };
+    super::history::record(&format!("script {}", path.display()), exit_code, &session_id);
+
+    if let Some((event_name, payload)) = emitter.process(EmitterMessage::Terminal { terminal }).await {
+        emit_event(event_name, payload);
+    }
+    job_manager.finish_job(&record.id).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_variables_and_errexit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deploy.nbsh");
+        std::fs::write(&path, "#!/usr/bin/neurobench\n# comment\n\nNAME=\"blinky\"\necho $NAME\nnot-a-command\necho unreachable\n").unwrap();
+        assert!(is_nbsh_script(&path));
+
+        let err = execute_script(&path, HashMap::new()).unwrap_err();
+        assert!(matches!(err, ScriptError::CommandFailed { line: 6, .. }));
+
+        let (tx, mut rx) = unbounded_channel();
+        run_script_source("set +e\nnot-a-command\necho $BOARD done", HashMap::from([("BOARD".to_string(), "f4".to_string())]), &tx).unwrap();
+        drop(tx);
+        let mut last = None;
+        while let Ok(line) = rx.try_recv() {
+            last = Some(line.content);
+        }
+        assert_eq!(last.as_deref(), Some("f4 done"));
+    }
+
+    #[test]
+    fn test_shebang_and_flash_template() {
+        assert!(is_neurobench_shebang("#!/usr/bin/env neurobench"));
+        assert!(!is_neurobench_shebang("#!/bin/bash"));
+
+        let (tx, _rx) = unbounded_channel();
+        assert!(matches!(run_script_source("#!/bin/sh\necho hi", HashMap::new(), &tx), Err(ScriptError::UnsupportedInterpreter(_))));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("flash.nbsh");
+        std::fs::write(&path, generate_flash_script("build/app.elf", "jlink", "/dev/ttyUSB0", 921600)).unwrap();
+        let lines = execute_script(&path, HashMap::new()).unwrap();
+        assert!(lines.iter().any(|l| l.content.contains("Programming build/app.elf")));
+        assert!(lines.iter().any(|l| l.content.contains("/dev/ttyUSB0 @ 921600 baud")));
+    }
+}
@@ -1,7 +1,45 @@
 // Terminal Color Themes
-// Dracula, OneDark Pro, Monokai, Solarized, and custom embedded themes
+// Dracula, OneDark Pro, Monokai, Solarized, Tokyo Night, Catppuccin, GitHub Dark and custom embedded themes
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use thiserror::Error;
+
+/// `TerminalLine::line_type` values every theme must color
+pub const LINE_TYPES: &[&str] = &["output", "error", "success", "info", "system", "warning"];
+
+/// Theme errors
+#[derive(Debug, Error)]
+pub enum ThemeError {
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+
+    #[error("Invalid theme JSON: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    #[error("Theme name is empty")]
+    EmptyName,
+
+    #[error("Invalid color for '{field}': {value}")]
+    InvalidColor { field: String, value: String },
+
+    #[error("Missing ANSI color for line type '{0}'")]
+    MissingLineType(String),
+
+    #[error("Invalid ANSI sequence for line type '{line_type}': {value:?}")]
+    InvalidAnsi { line_type: String, value: String },
+
+    #[error("'{0}' is the name of a built-in theme")]
+    BuiltInName(String),
+}
+
+lazy_static::lazy_static! {
+    /// Themes imported by the user, keyed by `theme_key`
+    static ref USER_THEMES: RwLock<BTreeMap<String, TerminalTheme>> = RwLock::new(BTreeMap::new());
+}
 
 /// Terminal color theme
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,11 +79,30 @@ pub struct TerminalTheme {
     pub warning: String,
     pub error: String,
     pub info: String,
+    
+    /// ANSI SGR sequence per `TerminalLine::line_type`
+    #[serde(default = "default_line_colors")]
+    pub line_colors: BTreeMap<String, String>,
 }
 
-/// Get available theme names
+/// ANSI colors matching the `TerminalLine` constructors
+pub fn default_line_colors() -> BTreeMap<String, String> {
+    [
+        ("output", "\x1b[0m"),
+        ("error", "\x1b[31m"),
+        ("success", "\x1b[32m"),
+        ("info", "\x1b[36m"),
+        ("system", "\x1b[35m"),
+        ("warning", "\x1b[33m"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect()
+}
+
+/// Get available theme names, user themes last
 pub fn get_available_themes() -> Vec<String> {
-    vec![
+    let mut names = vec![
         "dracula".to_string(),
         "one_dark_pro".to_string(),
         "monokai".to_string(),
@@ -54,12 +111,28 @@ pub fn get_available_themes() -> Vec<String> {
         "nord".to_string(),
         "gruvbox".to_string(),
         "embedded_dark".to_string(),
-    ]
+        "tokyo_night".to_string(),
+        "catppuccin_mocha".to_string(),
+        "github_dark".to_string(),
+    ];
+    names.extend(USER_THEMES.read().unwrap().keys().cloned());
+    names
 }
 
 /// Get a theme by name
 pub fn get_theme(name: &str) -> TerminalTheme {
-    match name.to_lowercase().as_str() {
+    find_theme(name).unwrap_or_else(dracula_theme) // Default
+}
+
+/// Look up a built-in or imported theme, `solarized-dark` and `solarized_dark` are equal
+pub fn find_theme(name: &str) -> Option<TerminalTheme> {
+    let key = theme_key(name);
+    builtin_theme(&key).or_else(|| USER_THEMES.read().unwrap().get(&key).cloned())
+}
+
+/// Built-in theme for a `theme_key`, aliases included
+fn builtin_theme(key: &str) -> Option<TerminalTheme> {
+    let theme = match key {
         "dracula" => dracula_theme(),
         "one_dark_pro" | "onedarkpro" => one_dark_pro_theme(),
         "monokai" => monokai_theme(),
@@ -68,7 +141,135 @@ pub fn get_theme(name: &str) -> TerminalTheme {
         "nord" => nord_theme(),
         "gruvbox" => gruvbox_theme(),
         "embedded_dark" | "embedded" => embedded_dark_theme(),
-        _ => dracula_theme(), // Default
+        "tokyo_night" | "tokyonight" => tokyo_night_theme(),
+        "catppuccin_mocha" | "catppuccin" => catppuccin_mocha_theme(),
+        "github_dark" => github_dark_theme(),
+        _ => return None,
+    };
+    Some(theme)
+}
+
+/// Lookup key for a theme name, e.g. "Solarized Dark" -> "solarized_dark"
+pub fn theme_key(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+// ==================== Import / Export ====================
+
+/// Check every color field is a hex color and every line type has a valid SGR sequence
+pub fn validate_theme(theme: &TerminalTheme) -> Result<(), ThemeError> {
+    if theme.name.trim().is_empty() {
+        return Err(ThemeError::EmptyName);
+    }
+
+    let colors = [
+        ("background", &theme.background), ("foreground", &theme.foreground),
+        ("cursor", &theme.cursor), ("selection", &theme.selection),
+        ("black", &theme.black), ("red", &theme.red), ("green", &theme.green),
+        ("yellow", &theme.yellow), ("blue", &theme.blue), ("magenta", &theme.magenta),
+        ("cyan", &theme.cyan), ("white", &theme.white),
+        ("bright_black", &theme.bright_black), ("bright_red", &theme.bright_red),
+        ("bright_green", &theme.bright_green), ("bright_yellow", &theme.bright_yellow),
+        ("bright_blue", &theme.bright_blue), ("bright_magenta", &theme.bright_magenta),
+        ("bright_cyan", &theme.bright_cyan), ("bright_white", &theme.bright_white),
+        ("register", &theme.register), ("address", &theme.address), ("pin", &theme.pin),
+        ("peripheral", &theme.peripheral), ("success", &theme.success),
+        ("warning", &theme.warning), ("error", &theme.error), ("info", &theme.info),
+    ];
+    for (field, value) in colors {
+        if !is_hex_color(value) {
+            return Err(ThemeError::InvalidColor { field: field.to_string(), value: value.clone() });
+        }
+    }
+
+    for line_type in LINE_TYPES {
+        let Some(code) = theme.line_colors.get(*line_type) else {
+            return Err(ThemeError::MissingLineType(line_type.to_string()));
+        };
+        if !is_sgr_sequence(code) {
+            return Err(ThemeError::InvalidAnsi { line_type: line_type.to_string(), value: code.clone() });
+        }
+    }
+    Ok(())
+}
+
+/// `#rgb` or `#rrggbb`
+fn is_hex_color(value: &str) -> bool {
+    value.strip_prefix('#')
+        .is_some_and(|hex| matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// `ESC [ n;n;... m` with each parameter at most 255
+fn is_sgr_sequence(code: &str) -> bool {
+    let Some(params) = code.strip_prefix("\x1b[").and_then(|c| c.strip_suffix('m')) else {
+        return false;
+    };
+    params.is_empty() || params.split(';').all(|p| !p.is_empty() && p.len() <= 3 && p.parse::<u8>().is_ok())
+}
+
+/// Read and validate a theme JSON file
+pub fn load_theme_from_file(path: &Path) -> Result<TerminalTheme, ThemeError> {
+    let content = std::fs::read_to_string(path)?;
+    let theme: TerminalTheme = serde_json::from_str(&content)?;
+    validate_theme(&theme)?;
+    Ok(theme)
+}
+
+/// Write a theme as pretty JSON
+pub fn save_theme_to_file(theme: &TerminalTheme, path: &Path) -> Result<(), io::Error> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(theme)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    std::fs::write(path, json)
+}
+
+/// Directory imported themes are copied to
+pub fn user_theme_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("neurobench")
+        .join("themes")
+}
+
+/// Load a theme file meant to be registered as a user theme
+///
+/// Built-in names are refused: the built-in would shadow the user theme on lookup.
+fn load_user_theme(path: &Path) -> Result<TerminalTheme, ThemeError> {
+    let theme = load_theme_from_file(path)?;
+    if builtin_theme(&theme_key(&theme.name)).is_some() {
+        return Err(ThemeError::BuiltInName(theme.name));
+    }
+    Ok(theme)
+}
+
+/// Import a theme file, keep a copy in the user theme directory and register it
+pub fn import_theme(path: &Path) -> Result<TerminalTheme, ThemeError> {
+    let theme = load_user_theme(path)?;
+    let key = theme_key(&theme.name);
+    save_theme_to_file(&theme, &user_theme_dir().join(format!("{}.json", key)))?;
+    USER_THEMES.write().unwrap().insert(key, theme.clone());
+    Ok(theme)
+}
+
+/// Register every valid theme in the user theme directory
+pub fn load_user_themes() {
+    let Ok(entries) = std::fs::read_dir(user_theme_dir()) else { return };
+    let mut themes = USER_THEMES.write().unwrap();
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.extension().is_some_and(|ext| ext == "json") {
+            match load_user_theme(&path) {
+                Ok(theme) => {
+                    themes.insert(theme_key(&theme.name), theme);
+                }
+                Err(e) => log::warn!("Skipping theme {}: {}", path.display(), e),
+            }
+        }
     }
 }
 
@@ -107,6 +308,7 @@ fn dracula_theme() -> TerminalTheme {
         warning: "#ffb86c".to_string(),
         error: "#ff5555".to_string(),
         info: "#8be9fd".to_string(),
+        line_colors: default_line_colors(),
     }
 }
 
@@ -145,6 +347,7 @@ fn one_dark_pro_theme() -> TerminalTheme {
         warning: "#e5c07b".to_string(),
         error: "#e06c75".to_string(),
         info: "#56b6c2".to_string(),
+        line_colors: default_line_colors(),
     }
 }
 
@@ -183,6 +386,7 @@ fn monokai_theme() -> TerminalTheme {
         warning: "#f4bf75".to_string(),
         error: "#f92672".to_string(),
         info: "#66d9ef".to_string(),
+        line_colors: default_line_colors(),
     }
 }
 
@@ -221,6 +425,7 @@ fn solarized_dark_theme() -> TerminalTheme {
         warning: "#b58900".to_string(),
         error: "#dc322f".to_string(),
         info: "#2aa198".to_string(),
+        line_colors: default_line_colors(),
     }
 }
 
@@ -259,6 +464,7 @@ fn solarized_light_theme() -> TerminalTheme {
         warning: "#b58900".to_string(),
         error: "#dc322f".to_string(),
         info: "#2aa198".to_string(),
+        line_colors: default_line_colors(),
     }
 }
 
@@ -297,6 +503,7 @@ fn nord_theme() -> TerminalTheme {
         warning: "#ebcb8b".to_string(),
         error: "#bf616a".to_string(),
         info: "#88c0d0".to_string(),
+        line_colors: default_line_colors(),
     }
 }
 
@@ -335,6 +542,7 @@ fn gruvbox_theme() -> TerminalTheme {
         warning: "#fabd2f".to_string(),
         error: "#fb4934".to_string(),
         info: "#8ec07c".to_string(),
+        line_colors: default_line_colors(),
     }
 }
 
@@ -374,6 +582,124 @@ fn embedded_dark_theme() -> TerminalTheme {
         warning: "#d29922".to_string(),
         error: "#f85149".to_string(),
         info: "#58a6ff".to_string(),
+        line_colors: default_line_colors(),
+    }
+}
+
+/// Tokyo Night theme
+fn tokyo_night_theme() -> TerminalTheme {
+    TerminalTheme {
+        name: "Tokyo Night".to_string(),
+        background: "#1a1b26".to_string(),
+        foreground: "#c0caf5".to_string(),
+        cursor: "#c0caf5".to_string(),
+        selection: "#33467c".to_string(),
+        
+        black: "#15161e".to_string(),
+        red: "#f7768e".to_string(),
+        green: "#9ece6a".to_string(),
+        yellow: "#e0af68".to_string(),
+        blue: "#7aa2f7".to_string(),
+        magenta: "#bb9af7".to_string(),
+        cyan: "#7dcfff".to_string(),
+        white: "#a9b1d6".to_string(),
+        
+        bright_black: "#414868".to_string(),
+        bright_red: "#f7768e".to_string(),
+        bright_green: "#9ece6a".to_string(),
+        bright_yellow: "#e0af68".to_string(),
+        bright_blue: "#7aa2f7".to_string(),
+        bright_magenta: "#bb9af7".to_string(),
+        bright_cyan: "#7dcfff".to_string(),
+        bright_white: "#c0caf5".to_string(),
+        
+        register: "#bb9af7".to_string(),
+        address: "#ff9e64".to_string(),
+        pin: "#9ece6a".to_string(),
+        peripheral: "#7dcfff".to_string(),
+        success: "#9ece6a".to_string(),
+        warning: "#e0af68".to_string(),
+        error: "#f7768e".to_string(),
+        info: "#7aa2f7".to_string(),
+        line_colors: default_line_colors(),
+    }
+}
+
+/// Catppuccin Mocha theme - pastel dark
+fn catppuccin_mocha_theme() -> TerminalTheme {
+    TerminalTheme {
+        name: "Catppuccin Mocha".to_string(),
+        background: "#1e1e2e".to_string(),
+        foreground: "#cdd6f4".to_string(),
+        cursor: "#f5e0dc".to_string(),
+        selection: "#585b70".to_string(),
+        
+        black: "#45475a".to_string(),
+        red: "#f38ba8".to_string(),
+        green: "#a6e3a1".to_string(),
+        yellow: "#f9e2af".to_string(),
+        blue: "#89b4fa".to_string(),
+        magenta: "#f5c2e7".to_string(),
+        cyan: "#94e2d5".to_string(),
+        white: "#bac2de".to_string(),
+        
+        bright_black: "#585b70".to_string(),
+        bright_red: "#f38ba8".to_string(),
+        bright_green: "#a6e3a1".to_string(),
+        bright_yellow: "#f9e2af".to_string(),
+        bright_blue: "#89b4fa".to_string(),
+        bright_magenta: "#f5c2e7".to_string(),
+        bright_cyan: "#94e2d5".to_string(),
+        bright_white: "#a6adc8".to_string(),
+        
+        register: "#cba6f7".to_string(),
+        address: "#fab387".to_string(),
+        pin: "#a6e3a1".to_string(),
+        peripheral: "#89dceb".to_string(),
+        success: "#a6e3a1".to_string(),
+        warning: "#f9e2af".to_string(),
+        error: "#f38ba8".to_string(),
+        info: "#89b4fa".to_string(),
+        line_colors: default_line_colors(),
+    }
+}
+
+/// GitHub Dark theme
+fn github_dark_theme() -> TerminalTheme {
+    TerminalTheme {
+        name: "GitHub Dark".to_string(),
+        background: "#0d1117".to_string(),
+        foreground: "#c9d1d9".to_string(),
+        cursor: "#58a6ff".to_string(),
+        selection: "#264f78".to_string(),
+        
+        black: "#484f58".to_string(),
+        red: "#ff7b72".to_string(),
+        green: "#3fb950".to_string(),
+        yellow: "#d29922".to_string(),
+        blue: "#58a6ff".to_string(),
+        magenta: "#bc8cff".to_string(),
+        cyan: "#39c5cf".to_string(),
+        white: "#b1bac4".to_string(),
+        
+        bright_black: "#6e7681".to_string(),
+        bright_red: "#ffa198".to_string(),
+        bright_green: "#56d364".to_string(),
+        bright_yellow: "#e3b341".to_string(),
+        bright_blue: "#79c0ff".to_string(),
+        bright_magenta: "#d2a8ff".to_string(),
+        bright_cyan: "#56d4dd".to_string(),
+        bright_white: "#f0f6fc".to_string(),
+        
+        register: "#d2a8ff".to_string(),
+        address: "#ffa657".to_string(),
+        pin: "#7ee787".to_string(),
+        peripheral: "#79c0ff".to_string(),
+        success: "#3fb950".to_string(),
+        warning: "#d29922".to_string(),
+        error: "#f85149".to_string(),
+        info: "#58a6ff".to_string(),
+        line_colors: default_line_colors(),
     }
 }
 
@@ -461,10 +787,51 @@ mod tests {
         assert!(css.contains("#282c34"));
     }
 
+    #[test]
+    fn test_theme_file_roundtrip_and_validation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mono.json");
+
+        let mut theme = get_theme("solarized-dark");
+        assert_eq!(theme.name, "Solarized Dark");
+        theme.name = "Mono".to_string();
+        save_theme_to_file(&theme, &path).unwrap();
+        let loaded = load_theme_from_file(&path).unwrap();
+        assert_eq!(loaded.line_colors["error"], "\x1b[31m");
+
+        theme.line_colors.insert("error".to_string(), "\x1b[31;999m".to_string());
+        assert!(matches!(validate_theme(&theme), Err(ThemeError::InvalidAnsi { .. })));
+        theme.line_colors.remove("error");
+        assert!(matches!(validate_theme(&theme), Err(ThemeError::MissingLineType(_))));
+
+        let mut json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        json["cursor"] = serde_json::json!("blue");
+        std::fs::write(&path, json.to_string()).unwrap();
+        assert!(matches!(load_theme_from_file(&path), Err(ThemeError::InvalidColor { .. })));
+    }
+
     #[test]
     fn test_available_themes() {
         let themes = get_available_themes();
-        assert!(themes.len() >= 8);
+        assert!(themes.len() >= 11);
         assert!(themes.contains(&"dracula".to_string()));
+        for name in ["tokyo_night", "catppuccin_mocha", "github_dark"] {
+            let theme = find_theme(name).unwrap();
+            assert_eq!(theme_key(&theme.name), name);
+            assert!(validate_theme(&theme).is_ok(), "{} is invalid", name);
+        }
+    }
+
+    #[test]
+    fn test_user_theme_cannot_shadow_builtin() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("monokai.json");
+        save_theme_to_file(&get_theme("monokai"), &path).unwrap();
+        assert!(matches!(load_user_theme(&path), Err(ThemeError::BuiltInName(_))));
+
+        let mut alias = get_theme("nord");
+        alias.name = "Tokyonight".to_string();
+        save_theme_to_file(&alias, &path).unwrap();
+        assert!(matches!(import_theme(&path), Err(ThemeError::BuiltInName(_))));
     }
 }
@@ -52,6 +52,102 @@ mod spi_tests {
     }
 }
 
+#[cfg(test)]
+mod hal_abstraction_tests {
+    use crate::drivers::gpio::*;
+    use crate::drivers::hal_abstraction::*;
+    use crate::drivers::templates::*;
+
+    #[test]
+    fn test_portable_gpio_source_matches_on_stm32_and_esp32() {
+        // PA5 exists on both targets (GPIO5 on ESP32)
+        let config = GpioConfig { port: "A".to_string(), pin: 5, mode: GpioMode::Output, ..GpioConfig::default() };
+        let gpio = PortablePeripheral::Gpio(config.clone());
+        assert!(check_portable(&gpio, &Stm32HalImpl).is_ok());
+        assert!(check_portable(&gpio, &EspIdfHalImpl).is_ok());
+        let stm32 = generate_gpio_driver(&config, &McuArch::Stm32, &DriverLanguage::Portable);
+        let esp = generate_gpio_driver(&config, &McuArch::Esp32, &DriverLanguage::Portable);
+
+        // Both backends get byte-identical driver files: only the binding differs
+        assert_eq!(stm32.header_file, esp.header_file);
+        assert_eq!(stm32.source_file, esp.source_file);
+        assert!(stm32.header_file.as_ref().unwrap().contains("} peripheral_hal_t;"));
+        assert!(stm32.source_file.contains("peripheral_hal.write_gpio(PA5_PORT, PA5_PIN, state)"));
+        assert!(!stm32.source_file.contains("stm32f4xx_hal.h"));
+        assert!(stm32.example_file.as_ref().unwrap().contains("#include \"main.h\""));
+
+        for (backend, symbol) in [(stm32.example_file.unwrap(), "stm32"), (esp.example_file.unwrap(), "esp_idf")] {
+            assert!(backend.contains("#include \"gpio_A5_portable.h\""));
+            for op in ["init_gpio", "read_gpio", "write_gpio", "init_uart", "uart_transmit", "uart_receive", "init_spi", "spi_transfer"] {
+                assert!(backend.contains(&format!("    {}_{},\n", symbol, op)), "{} backend is missing {}", symbol, op);
+            }
+        }
+
+        // A pin the backend cannot map fails the build instead of returning PHAL_ERROR at init
+        let pc13 = GpioConfig { port: "C".to_string(), pin: 13, ..GpioConfig::default() };
+        let esp = generate_gpio_driver(&pc13, &McuArch::Esp32, &DriverLanguage::Portable);
+        assert!(esp.source_file.contains("#error \"ESP-IDF has no GPIO port C"));
+        assert!(esp.example_file.is_none());
+    }
+
+    #[test]
+    fn test_portable_uart_and_spi() {
+        let uart = UartConfig { instance: "USART1".to_string(), ..UartConfig::default() };
+        let output = generate_portable_driver(&PortablePeripheral::Uart(uart), &ArduinoHalImpl);
+        assert!(output.header_file.unwrap().contains("#define UART1_INSTANCE    1u"));
+        assert!(output.example_file.unwrap().contains("serial->begin(baud);"));
+        let uart2 = UartConfig { instance: "USART2".to_string(), ..UartConfig::default() };
+        let output = generate_portable_driver(&PortablePeripheral::Uart(uart2), &ArduinoHalImpl);
+        assert!(output.source_file.contains("#error \"Arduino has no UART instance 2"));
+
+        let spi = SpiConfig { instance: "SPI3".to_string(), mode: SpiMode::Mode3, ..SpiConfig::default() };
+        let output = generate_portable_driver(&PortablePeripheral::Spi(spi), &Stm32HalImpl);
+        assert!(output.source_file.contains("peripheral_hal.spi_transfer(SPI3_INSTANCE, data, NULL, len)"));
+        assert_eq!(HalBackend::parse("ESP-IDF"), Some(HalBackend::EspIdf));
+        assert_eq!(ArduinoHalImpl.file_name(), "hal_arduino.cpp");
+    }
+
+    #[test]
+    fn test_non_portable_peripherals_are_reported() {
+        let output = crate::drivers::i2c::generate_i2c_driver(&I2cConfig::default(), &McuArch::Esp32, &DriverLanguage::Portable);
+        assert!(output.source_file.contains("#error \"I2C has no portable HAL driver yet"));
+        assert!(!output.source_file.contains("HAL_I2C_"));
+
+        // ESP32 pins are GPIO0-39: port A and the bottom of port B
+        assert!(EspIdfHalImpl.check_gpio("A", 5).is_ok());
+        assert!(EspIdfHalImpl.check_gpio("B", 7).is_ok());
+        assert!(EspIdfHalImpl.check_gpio("B", 8).is_err());
+        assert!(EspIdfHalImpl.check_gpio("C", 13).is_err());
+        assert!(Stm32HalImpl.check_gpio("C", 13).is_ok());
+        assert!(Stm32HalImpl.check_gpio("G", 2).is_ok());
+        assert!(Stm32HalImpl.check_gpio("A", 16).is_err());
+        assert!(Stm32HalImpl.check_gpio("Z", 1).is_err());
+    }
+
+    #[test]
+    fn test_portable_instances_are_checked_per_backend() {
+        let spi = PortablePeripheral::Spi(SpiConfig::default());
+        let backends: [&dyn PeripheralHal; 3] = [&Stm32HalImpl, &EspIdfHalImpl, &ArduinoHalImpl];
+        for hal in backends {
+            assert!(check_portable(&spi, hal).is_ok(), "SPI1 rejected by {}", hal.display_name());
+        }
+        assert!(generate_portable_driver(&spi, &ArduinoHalImpl).example_file.unwrap().contains("if (instance != 1) {"));
+        assert!(generate_portable_driver(&spi, &EspIdfHalImpl)
+            .example_file
+            .unwrap()
+            .contains("spi_host_device_t host = (spi_host_device_t)instance;"));
+
+        let spi3 = PortablePeripheral::Spi(SpiConfig { instance: "SPI3".to_string(), ..SpiConfig::default() });
+        assert!(check_portable(&spi3, &Stm32HalImpl).is_ok());
+        assert!(check_portable(&spi3, &EspIdfHalImpl).is_err());
+        assert!(check_portable(&spi3, &ArduinoHalImpl).is_err());
+
+        let uart2 = PortablePeripheral::Uart(UartConfig { instance: "USART2".to_string(), ..UartConfig::default() });
+        assert!(check_portable(&uart2, &EspIdfHalImpl).is_ok());
+        assert!(check_portable(&uart2, &ArduinoHalImpl).is_err());
+    }
+}
+
 #[cfg(test)]
 mod i2c_tests {
     use crate::drivers::i2c::*;
@@ -140,6 +236,29 @@ mod rtos_tests {
         
         assert!(!code.is_empty(), "Zephyr task code should not be empty");
     }
+
+    #[test]
+    fn test_zephyr_event_group_versions() {
+        use zephyr::{ZephyrHal, ZephyrVersion};
+        let config = EventGroupConfig { name: "sys_events".to_string(), num_bits: 4 };
+
+        let code = ZephyrHal::with_version(ZephyrVersion::new(3, 6)).generate_event_group(&config);
+        assert!(code.contains("K_EVENT_DEFINE(sys_events)"));
+        assert!(code.contains("k_event_post(&sys_events, events)"));
+        assert!(code.contains("#if SYS_EVENTS_USE_K_EVENT"));
+        assert!(code.contains("k_poll_signal_raise"), "k_event code should keep the k_poll fallback");
+
+        let code = ZephyrHal::with_version(ZephyrVersion::new(2, 7)).generate_event_group(&config);
+        assert!(!code.contains("k_event_"));
+        assert!(code.contains("k_poll(&poll_event, 1, timeout)"));
+        assert!(code.contains("#include <kernel.h>"));
+        assert!(ZephyrHal::with_version(ZephyrVersion::new(2, 7)).generate_config_header().contains("CONFIG_POLL=y"));
+
+        assert_eq!(
+            ZephyrVersion::parse_version_file("VERSION_MAJOR = 3\nVERSION_MINOR = 5\nPATCHLEVEL = 99\n"),
+            Some(ZephyrVersion::new(3, 5))
+        );
+    }
 }
 
 #[cfg(test)]
@@ -192,3 +311,870 @@ mod interrupt_tests {
         assert!(!code.is_empty());
     }
 }
+
+#[cfg(test)]
+mod pmic_tests {
+    use crate::drivers::mcu::McuFamily;
+    use crate::drivers::power::*;
+    use crate::drivers::power::pmic::*;
+
+    fn rail(name: &str, voltage_mv: u32) -> PowerRail {
+        PowerRail {
+            name: name.to_string(),
+            voltage_mv,
+            current_ma: 500,
+            enabled_on_boot: true,
+            gpio_enable_pin: None,
+        }
+    }
+
+    #[test]
+    fn test_max77650_generation() {
+        let config = PmicConfig {
+            device: PmicDevice::MAX77650,
+            rails: vec![rail("CORE", 1200), rail("IO", 1800)],
+            sequence: PowerSequence { order: vec!["IO".to_string(), "CORE".to_string()], delay_ms: 0 },
+        };
+        let output = generate_pmic_init(&config, McuFamily::STM32L4);
+        let header = output.header_file.unwrap();
+
+        assert!(header.contains("MAX77650_CNFG_SBB0_A"));
+        assert!(header.contains("PMIC_SEQ_DELAY_MS        2"));
+        // 1200 mV on SBB0 = (1200 - 800) / 25
+        assert!(output.source_file.contains("pmic_write_reg(MAX77650_CNFG_SBB0_A, 0x10)"));
+        assert!(output.source_file.contains("Power-up sequence: IO -> CORE"));
+    }
+
+    #[test]
+    fn test_out_of_range_voltage_clamps_to_max_code() {
+        let config = PmicConfig {
+            device: PmicDevice::MAX77650,
+            rails: vec![rail("CORE", u32::MAX)],
+            sequence: PowerSequence { order: vec!["CORE".to_string()], delay_ms: 0 },
+        };
+        let output = generate_pmic_init(&config, McuFamily::STM32L4);
+        assert!(output.source_file.contains("pmic_write_reg(MAX77650_CNFG_SBB0_A, 0x3F)"));
+    }
+}
+
+#[cfg(test)]
+mod usb_tests {
+    use crate::drivers::mcu::McuFamily;
+    use crate::drivers::usb::*;
+
+    #[test]
+    fn test_usb_cdc_generation() {
+        let config = UsbConfig { vid: 0x1209, pid: 0x0001, ..UsbConfig::default() };
+        let output = generate_usb_cdc_acm(&config, McuFamily::STM32F4);
+
+        assert!(output.source_file.contains("USBD_RegisterClass(&hUsbDeviceFS, &USBD_CDC)"));
+        assert!(output.source_file.contains("0x09, 0x12,"));
+        assert!(output.source_file.contains("void OTG_FS_IRQHandler(void)"));
+        for symbol in ["USBD_LL_Start(", "USBD_LL_OpenEP(", "USBD_LL_IsStallEP(", "USBD_LL_PrepareReceive(", "HAL_PCD_DataOutStageCallback("] {
+            assert!(output.source_file.contains(symbol), "missing {}", symbol);
+        }
+        assert!(output.header_file.unwrap().contains("uint8_t usb_cdc_transmit(const uint8_t *data, uint16_t len);"));
+
+        let f1 = generate_usb_cdc_acm(&config, McuFamily::STM32F1);
+        assert!(f1.source_file.contains("USB_LP_CAN1_RX0_IRQHandler"));
+        assert!(f1.source_file.contains("HAL_PCDEx_PMAConfig(&hpcd_USB_FS, 0x82"));
+    }
+
+    #[test]
+    fn test_usb_hid_generation() {
+        let config = UsbConfig { device_class: UsbDeviceClass::Hid, ..UsbConfig::default() };
+        let output = generate_usb_driver(&config, McuFamily::STM32G4).unwrap();
+
+        assert!(output.header_file.unwrap().contains("HID_Keyboard_Report_t"));
+        assert!(output.source_file.contains("HID_Keyboard_ReportDesc[]"));
+        assert!(output.source_file.contains("HAL_PCD_DevConnect"));
+
+        assert!(generate_usb_driver(&config, McuFamily::ESP32).is_err());
+        let msc = UsbConfig { device_class: UsbDeviceClass::MassStorage, ..UsbConfig::default() };
+        assert!(generate_usb_driver(&msc, McuFamily::STM32F4).is_err());
+    }
+}
+
+#[cfg(test)]
+mod sensor_tests {
+    use crate::drivers::mcu::McuFamily;
+    use crate::drivers::sensors::*;
+
+    #[test]
+    fn test_bme280_over_i2c_and_spi() {
+        let config = SensorLibConfig::new(SensorType::Bme280, SensorInterface::I2c);
+        let output = generate_sensor_driver(&config, McuFamily::STM32F1);
+        assert!(output.source_file.contains("#include \"stm32f1xx_hal.h\""));
+        assert!(output.source_file.contains("#define SENSOR_I2C_ADDR 0x76"));
+        assert!(output.source_file.contains("I2C1_Read(SENSOR_I2C_ADDR, reg, data, len)"));
+        assert!(output.source_file.contains("bme280_compensate_p"));
+
+        let spi = SensorLibConfig { spi_cs_pin: Some("PB12".to_string()), ..SensorLibConfig::new(SensorType::Bme280, SensorInterface::Spi) };
+        let output = generate_sensor_driver(&spi, McuFamily::STM32F4);
+        assert!(output.source_file.contains("SPI1_TransmitReceive(reg & 0x7F)"));
+        assert!(output.source_file.contains("HAL_GPIO_WritePin(GPIOB, GPIO_PIN_12"));
+    }
+
+    #[test]
+    fn test_sensor_interface_validation() {
+        assert!(SensorLibConfig::new(SensorType::Mpu6050, SensorInterface::Spi).validate(McuFamily::STM32F4).is_err());
+        assert!(SensorLibConfig::new(SensorType::Ds18b20, SensorInterface::OneWire).validate(McuFamily::STM32F4).is_ok());
+        let bad_address = SensorLibConfig { i2c_address: Some(0xD0), ..SensorLibConfig::new(SensorType::Lis3Dh, SensorInterface::I2c) };
+        assert!(bad_address.validate(McuFamily::STM32F4).is_err());
+        // DWT timing and the STM32 HAL are not available elsewhere
+        let hcsr04 = SensorLibConfig::new(SensorType::Hcsr04, SensorInterface::Gpio);
+        assert!(hcsr04.validate(McuFamily::STM32L4).is_ok());
+        assert!(hcsr04.validate(McuFamily::ESP32).is_err());
+        assert!(hcsr04.validate(McuFamily::RP2040).is_err());
+    }
+
+    #[test]
+    fn test_hcsr04_timing_survives_cyccnt_wrap() {
+        let output = generate_sensor_driver(&SensorLibConfig::new(SensorType::Hcsr04, SensorInterface::Gpio), McuFamily::STM32F4);
+        assert!(output.source_file.contains("return (uint32_t)(DWT->CYCCNT - start) / (SystemCoreClock / 1000000U);"));
+        assert!(output.source_file.contains("uint32_t rise = sensor_cycles();"));
+        assert!(output.source_file.contains("uint32_t echo_us = sensor_elapsed_us(rise);"));
+        assert!(!output.source_file.contains("DWT->CYCCNT / "));
+    }
+}
+
+#[cfg(test)]
+mod display_tests {
+    use crate::drivers::display::*;
+    use crate::drivers::mcu::McuFamily;
+
+    #[test]
+    fn test_ssd1306_over_i2c() {
+        let config = DisplayConfig::new(DisplayController::Ssd1306, DisplayInterface::I2c);
+        assert!(config.validate(McuFamily::STM32F4).is_ok());
+        let output = generate_display_driver(&config, McuFamily::STM32F4);
+        assert!(output.header_file.unwrap().contains("void display_draw_bitmap("));
+        assert!(output.source_file.contains("0xA8, 1, 0x3F,  // Multiplex ratio"));
+        assert!(output.source_file.contains("0x8D, 1, 0x14,  // Charge pump on"));
+        assert!(output.source_file.contains("0xDA, 1, 0x12,  // COM pins"));
+        assert!(output.source_file.contains("I2C1_Write(DISPLAY_I2C_ADDR, 0x40, data, len);"));
+        assert!(output.source_file.contains("static uint8_t framebuffer[DISPLAY_WIDTH * DISPLAY_HEIGHT / 8];"));
+
+        let short = DisplayConfig { height: 32, ..config };
+        let output = generate_display_driver(&short, McuFamily::STM32F4);
+        assert!(output.source_file.contains("0xA8, 1, 0x1F,"));
+        assert!(output.source_file.contains("0xDA, 1, 0x02,"));
+    }
+
+    #[test]
+    fn test_ili9341_over_spi() {
+        let config = DisplayConfig {
+            cs_pin: Some("PA4".to_string()),
+            rst_pin: Some("PB1".to_string()),
+            ..DisplayConfig::new(DisplayController::Ili9341, DisplayInterface::Spi)
+        };
+        assert!(config.validate(McuFamily::STM32F4).is_ok());
+        let output = generate_display_driver(&config, McuFamily::STM32F4);
+        assert!(output.header_file.unwrap().contains("#define DISPLAY_WIDTH  320"));
+        assert!(output.source_file.contains("0x36, 1, 0x28,  // Memory access control"));
+        assert!(output.source_file.contains("0x3A, 1, 0x55,  // Pixel format: 16-bit RGB565"));
+        assert!(output.source_file.contains("0x11, 0 | DISPLAY_INIT_DELAY, 120,  // Sleep out"));
+        // Hardware reset replaces the software reset
+        assert!(!output.source_file.contains("Software reset"));
+        assert!(output.source_file.contains("HAL_GPIO_WritePin(GPIOA, GPIO_PIN_4, active ? GPIO_PIN_RESET : GPIO_PIN_SET);"));
+        assert!(output.source_file.contains("display_write_command(0x2A, columns, 4);"));
+    }
+
+    #[test]
+    fn test_display_validation() {
+        assert!(DisplayConfig::new(DisplayController::St7789, DisplayInterface::I2c).validate(McuFamily::STM32F4).is_err());
+        let tall = DisplayConfig { height: 60, ..DisplayConfig::new(DisplayController::Sh1106, DisplayInterface::I2c) };
+        assert!(tall.validate(McuFamily::STM32F4).is_err());
+        let shared = DisplayConfig { cs_pin: Some("PB0".to_string()), ..DisplayConfig::new(DisplayController::Ili9341, DisplayInterface::Spi) };
+        assert!(shared.validate(McuFamily::STM32F4).is_err());
+        assert!(DisplayConfig::new(DisplayController::Ssd1306, DisplayInterface::I2c).validate(McuFamily::ESP32).is_err());
+    }
+}
+
+#[cfg(test)]
+mod delta_ota_tests {
+    use crate::drivers::security::delta::*;
+    use crate::drivers::security::{DeltaAlgorithm, DeltaOtaConfig};
+
+    #[test]
+    fn test_delta_updater_generation() {
+        let config = DeltaOtaConfig { flash_slot: 0x08060000, ..DeltaOtaConfig::default() };
+        let output = generate_delta_patch_updater(&config);
+        let header = output.header_file.unwrap();
+        assert!(header.contains("#define DELTA_FLASH_SLOT    0x08060000"));
+        assert!(header.contains("#define DELTA_SCRATCH_SIZE  4096"));
+        // Commit record sits in the slot's last sector, not next to the running image
+        assert!(header.contains("#define DELTA_COMMIT_ADDR   (DELTA_FLASH_SLOT + DELTA_IMAGE_MAX)"));
+        assert!(output.source_file.contains("ctx.written + DELTA_SCRATCH_SIZE > DELTA_IMAGE_MAX"));
+        assert!(output.source_file.contains("#define JD_ESC 0xA7"));
+        // Commit record is only written after the CRC check
+        let crc_check = output.source_file.find("return DELTA_ERR_CRC;").unwrap();
+        let commit = output.source_file.find("delta_flash_write(DELTA_COMMIT_ADDR").unwrap();
+        assert!(crc_check < commit);
+
+        let bsdiff = DeltaOtaConfig { algorithm: DeltaAlgorithm::Bsdiff4, ..DeltaOtaConfig::default() };
+        assert!(generate_delta_patch_updater(&bsdiff).source_file.contains("ENDSLEY/BSDIFF43"));
+    }
+
+    /// Apply an uncompressed BSDIFF43 patch the way the device decoder does
+    fn apply_bsdiff43(old: &[u8], patch: &[u8]) -> Option<Vec<u8>> {
+        let offtin = |p: &[u8]| {
+            let y = i64::from_le_bytes(p[..8].try_into().unwrap()) & i64::MAX;
+            if p[7] & 0x80 != 0 { -y } else { y }
+        };
+        if patch.len() < 24 || &patch[..16] != b"ENDSLEY/BSDIFF43" {
+            return None;
+        }
+        let new_size = offtin(&patch[16..24]) as usize;
+        let (mut pos, mut source_pos, mut new) = (24, 0i64, Vec::new());
+        while new.len() < new_size {
+            let control = patch.get(pos..pos + 24)?;
+            let (diff_len, extra_len, seek) = (offtin(&control[0..]), offtin(&control[8..]), offtin(&control[16..]));
+            pos += 24;
+            if diff_len < 0 || extra_len < 0 || new.len() + (diff_len + extra_len) as usize > new_size {
+                return None;
+            }
+            for &byte in patch.get(pos..pos + diff_len as usize)? {
+                new.push(old.get(source_pos as usize)?.wrapping_add(byte));
+                source_pos += 1;
+            }
+            pos += diff_len as usize;
+            new.extend_from_slice(patch.get(pos..pos + extra_len as usize)?);
+            pos += extra_len as usize;
+            source_pos += seek;
+            if source_pos < 0 || source_pos > old.len() as i64 {
+                return None;
+            }
+        }
+        // Trailing data is an error
+        (pos == patch.len()).then_some(new)
+    }
+
+    #[test]
+    fn test_bsdiff43_patch_round_trip() {
+        // A firmware-like image with a moved block, patched constants and an appended tail
+        let old: Vec<u8> = (0..4096u32).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8).collect();
+        let mut new = old[1024..2048].to_vec();
+        new.extend_from_slice(&old[..1024]);
+        new.extend_from_slice(&old[2048..]);
+        for i in (100..new.len()).step_by(397) {
+            new[i] = new[i].wrapping_add(3);
+        }
+        new.extend_from_slice(b"version 2.0.1");
+
+        let dir = tempfile::tempdir().unwrap();
+        let (old_path, new_path, patch_path) = (dir.path().join("old.bin"), dir.path().join("new.bin"), dir.path().join("app.patch"));
+        std::fs::write(&old_path, &old).unwrap();
+        std::fs::write(&new_path, &new).unwrap();
+
+        let info = create_patch(DeltaAlgorithm::Bsdiff4, &old_path, &new_path, &patch_path).unwrap();
+        assert_eq!(info.tool, "built-in bsdiff43");
+        let patch = std::fs::read(&patch_path).unwrap();
+        assert_eq!(patch.len() as u64, info.patch_size);
+        assert!(patch.starts_with(b"ENDSLEY/BSDIFF43"));
+        assert_eq!(apply_bsdiff43(&old, &patch).unwrap(), new);
+
+        // Unrelated and empty images still decode
+        for (old, new) in [(&b""[..], &b"fresh image"[..]), (&b"old image"[..], &b""[..]), (&old[..], &old[..])] {
+            assert_eq!(apply_bsdiff43(old, &bsdiff43_patch(old, new)).unwrap(), new);
+        }
+    }
+}
+
+#[cfg(test)]
+mod kdf_tests {
+    use crate::drivers::security::kdf::*;
+
+    #[test]
+    fn test_kdf_generation() {
+        let config = KdfConfig { key_length_bytes: 48, info: "link \"A\"".to_string(), ..KdfConfig::default() };
+        assert!(config.validate().is_ok());
+        let output = generate_kdf_code(&config);
+        let header = output.header_file.unwrap();
+        assert!(header.contains("#define KDF_KEY_LENGTH   48"));
+        assert!(header.contains(r#"#define KDF_INFO         "link \"A\"""#));
+        assert!(output.source_file.contains("int kdf_hkdf_extract("));
+        assert!(output.source_file.contains("int derive_session_key("));
+        assert!(output.source_file.contains("HAL_RNG_GenerateRandomNumber"));
+        assert!(config.warnings().is_empty());
+
+        let pbkdf2 = KdfConfig { algorithm: KdfAlgorithm::Pbkdf2, ..KdfConfig::default() };
+        let output = generate_kdf_code(&pbkdf2);
+        assert!(output.header_file.unwrap().contains("#define KDF_PBKDF2_ITERATIONS 10000U"));
+        assert!(output.source_file.contains("t[k] ^= u[k];"));
+
+        let ctr = KdfConfig { algorithm: KdfAlgorithm::Sp800_108Ctr, ..KdfConfig::default() };
+        assert!(generate_kdf_code(&ctr).source_file.contains("int kdf_sp800_108_ctr("));
+    }
+
+    #[test]
+    fn test_kdf_software_rng_warning() {
+        let config = KdfConfig { use_hardware_rng: false, ..KdfConfig::default() };
+        let output = generate_kdf_code(&config);
+        assert!(output.source_file.contains("#warning"));
+        assert!(output.source_file.contains("mbedtls_ctr_drbg_seed"));
+        assert!(config.warnings()[0].contains("entropy source"));
+
+        assert!(KdfConfig { key_length_bytes: 8, ..KdfConfig::default() }.validate().is_err());
+        assert!(KdfConfig { info: "x".repeat(65), ..KdfConfig::default() }.validate().is_err());
+    }
+}
+
+#[cfg(test)]
+mod debounce_tests {
+    use crate::drivers::debounce::*;
+
+    #[test]
+    fn test_debounce_generation() {
+        let config = DebounceConfig { num_buttons: 3, repeat_enabled: true, ..DebounceConfig::default() };
+        assert!(config.validate().is_ok());
+        let output = generate_debounce_driver(&config);
+        let header = output.header_file.unwrap();
+        assert!(header.contains("#define BUTTON_COUNT              3"));
+        assert!(header.contains("#define BUTTON_REPEAT_ENABLED     1"));
+        assert!(header.contains("ButtonEvent button_get_event(uint8_t n);"));
+        assert!(output.source_file.contains("case BTN_STATE_HELD:"));
+        assert!(output.example_file.unwrap().contains("GPIO_PIN_0, GPIO_PIN_1, GPIO_PIN_2"));
+    }
+
+    #[test]
+    fn test_debounce_validation() {
+        assert!(DebounceConfig { num_buttons: 0, ..DebounceConfig::default() }.validate().is_err());
+        assert!(DebounceConfig { long_press_ms: 10, ..DebounceConfig::default() }.validate().is_err());
+    }
+}
+
+#[cfg(test)]
+mod dma_pingpong_tests {
+    use crate::drivers::dma_buffer::*;
+    use crate::drivers::mcu::McuFamily;
+
+    #[test]
+    fn test_adc_pingpong_generation() {
+        let config = DmaPingPongConfig::default();
+        assert!(config.validate(McuFamily::STM32F4).is_ok());
+        let output = generate_dma_pingpong(&config, McuFamily::STM32F4);
+        let header = output.header_file.unwrap();
+        assert!(header.contains("#define DMA_PINGPONG_SIZE 256"));
+        assert!(header.contains("typedef uint16_t dma_elem_t;"));
+        assert!(output.source_file.contains("HAL_ADC_Start_DMA(&hadc1, (uint32_t *)dma_pingpong, 2 * DMA_PINGPONG_SIZE)"));
+        assert!(output.source_file.contains("void HAL_ADC_ConvHalfCpltCallback(ADC_HandleTypeDef *hadc)"));
+        assert!(output.source_file.contains("dma_pingpong_complete(1);"));
+
+        let h7 = generate_dma_pingpong(&config, McuFamily::STM32H7);
+        assert!(h7.source_file.contains("SCB_InvalidateDCache_by_Addr"));
+    }
+
+    #[test]
+    fn test_pingpong_without_half_callback() {
+        let config = DmaPingPongConfig {
+            peripheral: "USART2".to_string(),
+            element_type: "uint8_t".to_string(),
+            callback_on_half: false,
+            ..DmaPingPongConfig::default()
+        };
+        assert!(config.validate(McuFamily::STM32F4).is_ok());
+        let output = generate_dma_pingpong(&config, McuFamily::STM32F4);
+        assert!(!output.source_file.contains("RxHalfCpltCallback"));
+        assert!(output.source_file.contains("__HAL_DMA_DISABLE_IT(huart2.hdmarx, DMA_IT_HT);"));
+        assert!(output.source_file.contains("process_buffer(data, 2 * DMA_PINGPONG_SIZE);"));
+    }
+
+    #[test]
+    fn test_pingpong_validation() {
+        let uart16 = DmaPingPongConfig { peripheral: "USART1".to_string(), ..DmaPingPongConfig::default() };
+        assert!(uart16.validate(McuFamily::STM32F4).is_err());
+        let unknown = DmaPingPongConfig { peripheral: "TIM3".to_string(), ..DmaPingPongConfig::default() };
+        assert!(unknown.validate(McuFamily::STM32F4).is_err());
+        let bad_type = DmaPingPongConfig { element_type: "double".to_string(), ..DmaPingPongConfig::default() };
+        assert!(bad_type.validate(McuFamily::STM32F4).is_err());
+        let config = DmaPingPongConfig::default();
+        assert!(config.validate(McuFamily::STM32G4).is_ok());
+        assert!(config.validate(McuFamily::ESP32).is_err());
+        assert!(config.validate(McuFamily::NRF52840).is_err());
+    }
+}
+
+#[cfg(test)]
+mod framing_tests {
+    use crate::drivers::framing::*;
+
+    #[test]
+    fn test_framer_generation() {
+        let cobs = generate_framer(FrameProtocol::Cobs, 256);
+        let header = cobs.header_file.unwrap();
+        assert!(header.contains("#define FRAME_MAX_SIZE    256"));
+        assert!(header.contains("#define FRAME_MAX_ENCODED 259"));
+        assert!(header.contains("uint8_t pending_zero;"));
+        assert!(cobs.source_file.contains("if (++code == 0xFF)"));
+
+        let slip = generate_framer(FrameProtocol::Slip, 128);
+        assert!(slip.header_file.unwrap().contains("#define SLIP_ESC_END 0xDC"));
+        assert!(slip.source_file.contains("RFC 1055"));
+
+        let ppp = generate_framer(FrameProtocol::PppHdlc, 128);
+        assert!(ppp.source_file.contains("fcs != PPP_FCS_GOOD"));
+
+        let custom = generate_framer(FrameProtocol::CustomDelimiter(0x1B), 64);
+        let header = custom.header_file.unwrap();
+        assert!(header.contains("#define FRAME_DELIMITER 0x1B"));
+        assert!(header.contains("#define FRAME_ESC       0x7D"));
+    }
+
+    #[test]
+    fn test_max_encoded_size() {
+        assert_eq!(FrameProtocol::Cobs.max_encoded_size(0), 2);
+        assert_eq!(FrameProtocol::Cobs.max_encoded_size(254), 257);
+        assert_eq!(FrameProtocol::Slip.max_encoded_size(10), 22);
+        assert_eq!(FrameProtocol::PppHdlc.max_encoded_size(10), 26);
+    }
+}
+
+#[cfg(test)]
+mod soft_i2c_tests {
+    use crate::drivers::soft_i2c::*;
+    use crate::drivers::mcu::McuFamily;
+
+    #[test]
+    fn test_soft_i2c_stm32() {
+        let config = SoftI2cConfig::default();
+        assert!(config.validate(McuFamily::STM32F4).is_ok());
+        let output = generate_soft_i2c(&config, McuFamily::STM32F4);
+        let header = output.header_file.unwrap();
+        assert!(header.contains("bool i2c_write_reg(uint8_t addr, uint8_t reg, uint8_t value);"));
+        assert!(header.contains("uint8_t i2c_read_reg(uint8_t addr, uint8_t reg);"));
+        // Same names as the hardware driver, so the sensor drivers link against it
+        assert!(header.contains("#ifndef I2C1_DRIVER_H"));
+        assert!(header.contains("bool I2C1_Read(uint8_t addr, uint8_t reg, uint8_t *data, uint16_t len);"));
+        assert!(header.contains("bool I2C1_WriteReg8(uint8_t addr, uint8_t reg, uint8_t value);"));
+        assert!(output.source_file.contains("#include \"i2c1_driver.h\""));
+        assert!(output.source_file.contains("bool I2C1_Read(uint8_t addr, uint8_t reg, uint8_t *data, uint16_t len) {\n    return i2c_read(addr, reg, data, len);"));
+        assert!(output.source_file.contains("#define SOFT_I2C_HALF_PERIOD     208UL"));
+        assert!(output.source_file.contains("#define SOFT_I2C_SCL_LOW()     (GPIOB->BSRR = (1U << 22))"));
+        assert!(output.source_file.contains("gpio.Mode = GPIO_MODE_OUTPUT_OD;"));
+        assert!(output.source_file.contains("static bool scl_release(void)"));
+
+        let simulated = SoftI2cConfig { use_open_drain_simulation: true, ..SoftI2cConfig::default() };
+        let f1 = generate_soft_i2c(&simulated, McuFamily::STM32F1);
+        assert!(f1.source_file.contains("GPIOB->CRL = (GPIOB->CRL & ~(0xFU << 28)) | (0x4U << 28)"));
+    }
+
+    #[test]
+    fn test_soft_i2c_rp2040_always_simulated() {
+        let config = SoftI2cConfig {
+            sda_pin: "GPIO4".to_string(),
+            scl_pin: "5".to_string(),
+            ..SoftI2cConfig::default()
+        };
+        assert!(config.validate(McuFamily::RP2040).is_ok());
+        let output = generate_soft_i2c(&config, McuFamily::RP2040);
+        assert!(output.source_file.contains("#define SOFT_I2C_SDA_RELEASE() gpio_set_dir(4, GPIO_IN)"));
+    }
+
+    #[test]
+    fn test_soft_i2c_validation() {
+        let same = SoftI2cConfig { scl_pin: "PB7".to_string(), ..SoftI2cConfig::default() };
+        assert!(same.validate(McuFamily::STM32F4).is_err());
+        let too_fast = SoftI2cConfig { speed_khz: 3400, ..SoftI2cConfig::default() };
+        assert!(too_fast.validate(McuFamily::STM32F4).is_err());
+        assert!(SoftI2cConfig::default().validate(McuFamily::ESP32).is_err());
+        let bad_instance = SoftI2cConfig { instance: "I2C 1".to_string(), ..SoftI2cConfig::default() };
+        assert!(bad_instance.validate(McuFamily::STM32F4).is_err());
+    }
+}
+
+#[cfg(test)]
+mod onewire_tests {
+    use crate::drivers::onewire::*;
+    use crate::drivers::mcu::McuFamily;
+
+    #[test]
+    fn test_onewire_generation() {
+        let config = OneWireConfig::default();
+        assert!(config.validate(McuFamily::STM32F4).is_ok());
+        let output = generate_onewire_driver(&config, McuFamily::STM32F4);
+        let header = output.header_file.unwrap();
+        assert!(header.contains("#define ONEWIRE_MAX_DEVICES   4"));
+        assert!(header.contains("float ds18b20_read_temperature(uint8_t device_index);"));
+        assert!(output.source_file.contains("#define ONEWIRE_LOOPS_PER_US 42UL"));
+        assert!(output.source_file.contains("GPIOA->MODER = (GPIOA->MODER & ~(3U << 2)) | (1U << 2)"));
+        assert!(output.source_file.contains("static bool onewire_search_next(void)"));
+        assert!(output.source_file.contains("T[degC] = (int16_t)((MSB << 8) | LSB) / 16.0"));
+    }
+
+    #[test]
+    fn test_onewire_parasite_and_validation() {
+        let config = OneWireConfig {
+            data_pin: "GPIO15".to_string(),
+            use_parasite_power: true,
+            max_devices: 8,
+        };
+        assert!(config.validate(McuFamily::RP2040).is_ok());
+        let output = generate_onewire_driver(&config, McuFamily::RP2040);
+        assert!(output.header_file.unwrap().contains("#define ONEWIRE_PARASITE_POWER 1"));
+        assert!(output.source_file.contains("#define ONEWIRE_DQ_LATCH_HIGH() gpio_put(15, 1)"));
+        assert!(output.source_file.contains("save_and_disable_interrupts()"));
+
+        let none = OneWireConfig { max_devices: 0, ..OneWireConfig::default() };
+        assert!(none.validate(McuFamily::STM32F4).is_err());
+        assert!(OneWireConfig::default().validate(McuFamily::ESP32).is_err());
+    }
+}
+
+#[cfg(test)]
+mod micropython_tests {
+    use crate::drivers::micropython::*;
+    use crate::drivers::mcu::McuFamily;
+    use crate::drivers::templates::*;
+
+    #[test]
+    fn test_micropython_bus_drivers() {
+        let uart = MicroPythonPeripheral::Uart(UartConfig { instance: "UART1".to_string(), ..UartConfig::default() });
+        let output = generate_micropython_driver(&uart, McuFamily::RP2040).unwrap();
+        assert!(output.header_file.is_none());
+        assert!(output.source_file.contains("uart = UART(1, baudrate=115200, bits=8, parity=None, stop=1, tx=Pin(4), rx=Pin(5))"));
+        assert!(output.example_file.unwrap().contains("uart_driver.readline()"));
+
+        let spi = MicroPythonPeripheral::from_json("spi", serde_json::to_value(SpiConfig::default()).unwrap()).unwrap();
+        let output = generate_micropython_driver(&spi, McuFamily::ESP32).unwrap();
+        assert!(output.source_file.contains("sck=Pin(14), mosi=Pin(13), miso=Pin(12)"));
+        assert!(output.source_file.contains("spi.write_readinto(data, rx)"));
+
+        let i2c = MicroPythonPeripheral::I2c(I2cConfig { address: Some(0x68), ..I2cConfig::default() });
+        let output = generate_micropython_driver(&i2c, McuFamily::STM32F4).unwrap();
+        assert!(output.source_file.contains("i2c = I2C(1, freq=400000)"));
+        assert!(output.source_file.contains("DEVICE_ADDR = 0x68"));
+    }
+
+    #[test]
+    fn test_micropython_pins_and_errors() {
+        let gpio = MicroPythonPeripheral::Gpio(GpioConfig { port: "B".to_string(), pin: 6, mode: GpioMode::Output, ..GpioConfig::default() });
+        let output = generate_micropython_driver(&gpio, McuFamily::STM32F4).unwrap();
+        assert!(output.source_file.contains("pin = Pin(\"B6\", Pin.OUT, value=0)"));
+
+        let adc = MicroPythonPeripheral::Adc(AdcConfig { channels: vec![0, 4], ..AdcConfig::default() });
+        let output = generate_micropython_driver(&adc, McuFamily::RP2040).unwrap();
+        assert!(output.source_file.contains("ADC(Pin(26)),"));
+        assert!(output.source_file.contains("ADC(4),"));
+
+        assert!(generate_micropython_driver(&gpio, McuFamily::LPC1768).is_err());
+        assert!(MicroPythonPeripheral::from_json("can", serde_json::json!({})).is_err());
+    }
+}
+
+#[cfg(test)]
+mod encoder_tests {
+    use crate::drivers::encoder::*;
+    use crate::drivers::mcu::McuFamily;
+
+    #[test]
+    fn test_stm32_quadrature_encoder() {
+        let config = EncoderConfig::default();
+        assert!(config.validate(McuFamily::STM32F4).is_ok());
+        let output = generate_encoder_driver(&config, McuFamily::STM32F4);
+        assert!(output.header_file.unwrap().contains("#define ENCODER_COUNT_RANGE 4096"));
+        assert!(output.source_file.contains("TIM_Encoder_InitTypeDef encoder = {0};"));
+        assert!(output.source_file.contains("encoder.EncoderMode = TIM_ENCODERMODE_TI12;"));
+        assert!(output.source_file.contains("encoder.IC1Filter = 6;"));
+        assert!(output.source_file.contains("gpio.Alternate = GPIO_AF2_TIM3;"));
+        assert!(output.source_file.contains("HAL_TIM_Encoder_Start(&htim3, TIM_CHANNEL_ALL);"));
+
+        let x1 = EncoderConfig { mode: EncoderMode::QuadratureX1, ..EncoderConfig::default() };
+        let output = generate_encoder_driver(&x1, McuFamily::STM32F4);
+        assert!(output.source_file.contains("#define ENCODER_COUNT_DIV 2"));
+        assert!(output.source_file.contains("Init.Period = 8191;"));
+    }
+
+    #[test]
+    fn test_step_direction_encoder() {
+        let config = EncoderConfig { mode: EncoderMode::StepDirection, ..EncoderConfig::default() };
+        let f4 = generate_encoder_driver(&config, McuFamily::STM32F4);
+        assert!(f4.header_file.unwrap().contains("void encoder_dir_exti_callback(void);"));
+        assert!(f4.source_file.contains("slave.SlaveMode = TIM_SLAVEMODE_EXTERNAL1;"));
+
+        let g4 = generate_encoder_driver(&config, McuFamily::STM32G4);
+        assert!(g4.source_file.contains("TIM_ENCODERMODE_CLOCKPLUSDIRECTION_X1"));
+    }
+
+    #[test]
+    fn test_software_encoder_and_validation() {
+        let config = EncoderConfig { pin_a: "2".to_string(), pin_b: "3".to_string(), ..EncoderConfig::default() };
+        assert!(config.validate(McuFamily::RP2040).is_ok());
+        let output = generate_encoder_driver(&config, McuFamily::RP2040);
+        assert!(output.source_file.contains("void encoder_edge_isr(void)"));
+        assert!(output.source_file.contains("#define ENCODER_B_READ()    gpio_get(3)"));
+
+        let bad_timer = EncoderConfig { timer_instance: "TIM6".to_string(), ..EncoderConfig::default() };
+        assert!(bad_timer.validate(McuFamily::STM32F4).is_err());
+        let too_wide = EncoderConfig { count_range: 70000, ..EncoderConfig::default() };
+        assert!(too_wide.validate(McuFamily::STM32F4).is_err());
+    }
+}
+
+#[cfg(test)]
+mod stepper_tests {
+    use crate::drivers::mcu::McuFamily;
+    use crate::drivers::motor::stepper::*;
+
+    #[test]
+    fn test_stepper_timer_pwm() {
+        let config = StepperConfig::default();
+        assert!(config.validate(McuFamily::STM32F4).is_ok());
+        let output = generate_stepper_driver(&config, McuFamily::STM32F4);
+        let header = output.header_file.unwrap();
+        assert!(header.contains("int stepper_step_relative(int32_t steps);"));
+        assert!(header.contains("#define STEPPER_MICROSTEPS    16"));
+        assert!(output.source_file.contains("gpio.Alternate = GPIO_AF2_TIM3;"));
+        assert!(output.source_file.contains("oc.OCMode = TIM_OCMODE_PWM2;"));
+        assert!(output.source_file.contains("__HAL_TIM_SET_AUTORELOAD(&htim3, ticks - 1U);"));
+        assert!(output.source_file.contains("HAL_TIM_PWM_Start(&htim3, TIM_CHANNEL_1);"));
+        assert!(output.source_file.contains("void TIM3_IRQHandler(void)"));
+        assert!(output.source_file.contains("uint32_t pclk = HAL_RCC_GetPCLK1Freq();"));
+        assert!(output.source_file.contains("(RCC->CFGR & RCC_CFGR_PPRE1) != RCC_CFGR_PPRE1_DIV1"));
+        assert!(output.source_file.contains("period_q8 -= (2U * period_q8) / (4U * steps_done + 1U);"));
+        // No limit switch: homing only zeroes the position
+        assert!(!output.source_file.contains("STEPPER_LIMIT_ACTIVE"));
+
+        let advanced = StepperConfig {
+            step_pin: "PA9".to_string(),
+            timer_instance: "TIM1".to_string(),
+            limit_pin: Some("PB12".to_string()),
+            ..StepperConfig::default()
+        };
+        assert!(advanced.validate(McuFamily::STM32F4).is_ok());
+        let output = generate_stepper_driver(&advanced, McuFamily::STM32F4);
+        assert!(output.source_file.contains("TIM_CHANNEL_2"));
+        assert!(output.source_file.contains("void TIM1_UP_TIM10_IRQHandler(void)"));
+        assert!(output.source_file.contains("uint32_t pclk = HAL_RCC_GetPCLK2Freq();"));
+        assert!(output.source_file.contains("HAL_GPIO_ReadPin(GPIOB, GPIO_PIN_12) == GPIO_PIN_RESET"));
+        assert!(output.source_file.contains("__HAL_RCC_GPIOB_CLK_ENABLE();"));
+    }
+
+    #[test]
+    fn test_stepper_validation() {
+        let wrong_channel = StepperConfig { step_pin: "PB6".to_string(), ..StepperConfig::default() };
+        assert!(wrong_channel.validate(McuFamily::STM32F4).is_err());
+        let shared_pin = StepperConfig { dir_pin: "PA6".to_string(), ..StepperConfig::default() };
+        assert!(shared_pin.validate(McuFamily::STM32F4).is_err());
+        let microsteps = StepperConfig { microstepping: 12, ..StepperConfig::default() };
+        assert!(microsteps.validate(McuFamily::STM32F4).is_err());
+        let too_fast = StepperConfig { max_freq_hz: 500_000, ..StepperConfig::default() };
+        assert!(too_fast.validate(McuFamily::STM32F4).is_err());
+        assert!(StepperConfig::default().validate(McuFamily::RP2040).is_err());
+    }
+}
+
+#[cfg(test)]
+mod usb_dfu_tests {
+    use crate::drivers::mcu::McuFamily;
+    use crate::drivers::usb_dfu::*;
+
+    #[test]
+    fn test_dfu_memory_layout() {
+        let config = DfuConfig::default();
+        assert!(config.validate(McuFamily::STM32F4).is_ok());
+        assert_eq!(
+            config.memory_map_descriptor(),
+            "@Internal Flash/0x08000000/01*016Ka,03*016Kg,01*064Kg,07*128Kg"
+        );
+
+        let split = DfuConfig {
+            memory_map: vec![
+                DfuMemoryRegion { address: 0x0800_4000, sectors: 2, size_per_sector: 2048, writable: true },
+                DfuMemoryRegion { address: 0x1FFF_7800, sectors: 1, size_per_sector: 512, writable: false },
+            ],
+            upload_supported: false,
+            ..DfuConfig::default()
+        };
+        assert_eq!(split.memory_map_descriptor(), "@Internal Flash/0x08004000/02*002Kf/0x1FFF7800/01*512Ba");
+    }
+
+    #[test]
+    fn test_dfu_bootloader_generation() {
+        let config = DfuConfig::default();
+        let output = generate_usb_dfu_bootloader(&config, McuFamily::STM32F4);
+        let header = output.header_file.unwrap();
+        assert!(header.contains("#define DFU_APP_ADDRESS     0x08004000"));
+        assert!(header.contains("DFU_STATE_MANIFEST_WAIT_RESET"));
+        assert!(output.source_file.contains("#define USBD_INTERFACE_STRING    \"@Internal Flash/0x08000000/"));
+        assert!(output.source_file.contains("#define DFU_ATTRIBUTES           0x03"));
+        assert!(output.source_file.contains("case DFUSE_CMD_SET_ADDRESS:"));
+        assert!(output.source_file.contains("FLASH_TYPEPROGRAM_WORD"));
+
+        let l4 = generate_usb_dfu_bootloader(&config, McuFamily::STM32L4);
+        assert!(l4.source_file.contains("erase.TypeErase = FLASH_TYPEERASE_PAGES;"));
+        assert!(l4.source_file.contains("erase.Page = (sector_base - FLASH_BASE) / FLASH_PAGE_SIZE;"));
+    }
+
+    #[test]
+    fn test_dfu_sector_numbers_from_flash_base() {
+        assert_eq!(flash_sector(McuFamily::STM32F4, 0x0800_4000), Some((1, 0x0800_4000, 16 * 1024)));
+        assert_eq!(flash_sector(McuFamily::STM32F4, 0x0802_0000), Some((5, 0x0802_0000, 128 * 1024)));
+        assert_eq!(flash_sector(McuFamily::STM32F4, 0x0810_4000), Some((13, 0x0810_4000, 16 * 1024)));
+        assert_eq!(flash_sector(McuFamily::STM32L4, 0x0800_4000), Some((8, 0x0800_4000, 2048)));
+        assert_eq!(flash_sector(McuFamily::STM32F4, 0x1FFF_0000), None);
+
+        // Without the bootloader entry the table still starts at sector 1, not 0
+        let app_only = DfuConfig {
+            memory_map: DfuConfig::default().memory_map.into_iter().filter(|r| r.writable).collect(),
+            ..DfuConfig::default()
+        };
+        assert!(app_only.validate(McuFamily::STM32F4).is_ok());
+        let source = generate_usb_dfu_bootloader(&app_only, McuFamily::STM32F4).source_file;
+        assert!(source.contains("    { 0x08004000, 3, 0x4000, 1, 1 },\n"));
+        assert!(source.contains("    { 0x08010000, 1, 0x10000, 4, 1 },\n"));
+        assert!(source.contains("*sector = region->first_sector + offset;"));
+
+        let misaligned = DfuConfig {
+            memory_map: vec![DfuMemoryRegion { address: 0x0800_4000, sectors: 2, size_per_sector: 32 * 1024, writable: true }],
+            ..DfuConfig::default()
+        };
+        assert!(misaligned.validate(McuFamily::STM32F4).is_err());
+    }
+
+    #[test]
+    fn test_dfu_validation() {
+        assert!(DfuConfig::default().validate(McuFamily::ESP32).is_err());
+        let read_only = DfuConfig {
+            memory_map: vec![DfuMemoryRegion { address: 0x0800_0000, sectors: 1, size_per_sector: 1024, writable: false }],
+            ..DfuConfig::default()
+        };
+        assert!(read_only.validate(McuFamily::STM32F4).is_err());
+        let odd_size = DfuConfig { transfer_size: 1000, ..DfuConfig::default() };
+        assert!(odd_size.validate(McuFamily::STM32F4).is_err());
+    }
+}
+
+#[cfg(test)]
+mod i2s_tests {
+    use crate::drivers::i2s::*;
+    use crate::drivers::mcu::McuFamily;
+
+    #[test]
+    fn test_i2s_clock_divider() {
+        // 168 MHz / (256 * 48 kHz) = 13.67, rounded to 14: I2SDIV 7, ODD 0
+        let config = I2sConfig::default();
+        let clock = config.clock(168_000_000).unwrap();
+        assert_eq!((clock.divider, clock.odd), (7, false));
+        assert_eq!(clock.actual_sample_rate, 46_875);
+        assert_eq!(clock.mck_hz, 12_000_000);
+
+        // Without MCK it runs off the 32-bit stereo frame: 120 MHz / (32 * 44.1 kHz) = 85.03 -> 85
+        let no_mck = I2sConfig { sample_rate: 44_100, mck_enabled: false, ..I2sConfig::default() };
+        let clock = no_mck.clock(120_000_000).unwrap();
+        assert_eq!((clock.divider, clock.odd, clock.mck_hz), (42, true, 0));
+
+        let too_slow = I2sConfig { sample_rate: 8_000, mck_enabled: false, ..I2sConfig::default() };
+        assert!(too_slow.clock(480_000_000).is_err());
+    }
+
+    #[test]
+    fn test_i2s_driver_generation() {
+        let config = I2sConfig::default();
+        assert!(config.validate(McuFamily::STM32F4).is_ok());
+        let output = generate_i2s_driver(&config, McuFamily::STM32F4);
+        assert!(output.source_file.contains("hi2s2.Instance = SPI2;"));
+        assert!(output.source_file.contains("hi2s2.Init.Standard = I2S_STANDARD_PHILIPS;"));
+        assert!(output.source_file.contains("hi2s2.Init.MCLKOutput = I2S_MCLKOUTPUT_ENABLE;"));
+        assert!(output.source_file.contains("HAL_I2S_Transmit_DMA(&hi2s2, (uint16_t *)i2s_buffer"));
+        assert!(output.source_file.contains("hdma_i2s.Instance = DMA1_Stream4;"));
+        assert!(output.source_file.contains("void DMA1_Stream4_IRQHandler(void)"));
+        let header = output.header_file.unwrap();
+        assert!(header.contains("uint32_t i2s_kernel_clock_hz(void);"));
+        assert!(!header.contains("I2S_MCK_HZ"));
+        assert!(output.source_file.contains("HAL_RCCEx_GetPeriphCLKFreq(RCC_PERIPHCLK_I2S_APB1)"));
+
+        // A PLLI2S tuned to 2048 x 48 kHz gives an exact rate
+        let tuned = I2sConfig { kernel_clock_hz: Some(98_304_000), ..I2sConfig::default() };
+        let header = generate_i2s_driver(&tuned, McuFamily::STM32F4).header_file.unwrap();
+        assert!(header.contains("#define I2S_MCK_HZ          12288000U"));
+        assert!(header.contains("#define I2S_ACTUAL_FS_HZ    48000U  // +0 ppm"));
+
+        let mic = I2sConfig {
+            instance: "I2S3".to_string(),
+            role: I2sRole::SlaveRx,
+            standard: I2sStandard::MsbJustified,
+            data_bits: 24,
+            channel_length: 32,
+            mck_enabled: false,
+            ..I2sConfig::default()
+        };
+        assert!(mic.validate(McuFamily::STM32H7).is_ok());
+        let output = generate_i2s_driver(&mic, McuFamily::STM32H7);
+        assert!(output.source_file.contains("hi2s3.Init.DataFormat = I2S_DATAFORMAT_24B;"));
+        assert!(output.source_file.contains("HAL_I2S_Receive_DMA(&hi2s3"));
+        assert!(output.source_file.contains("I2S_WS_INVERSION_DISABLE"));
+        assert!(output.header_file.unwrap().contains("I2S_PACK(x)"));
+    }
+
+    #[test]
+    fn test_i2s_validation() {
+        assert!(I2sConfig::default().validate(McuFamily::STM32L4).is_err());
+        let bad_format = I2sConfig { data_bits: 24, channel_length: 16, ..I2sConfig::default() };
+        assert!(bad_format.validate(McuFamily::STM32F4).is_err());
+        let slave_mck = I2sConfig { role: I2sRole::SlaveTx, ..I2sConfig::default() };
+        assert!(slave_mck.validate(McuFamily::STM32F4).is_err());
+        let bad_instance = I2sConfig { instance: "SPI7".to_string(), ..I2sConfig::default() };
+        assert!(bad_instance.validate(McuFamily::STM32F4).is_err());
+
+        // 8 kHz without MCK needs I2SDIV 328 from 168 MHz, but the clock is only known when given
+        let low_rate = I2sConfig { sample_rate: 8_000, mck_enabled: false, ..I2sConfig::default() };
+        assert!(low_rate.validate(McuFamily::STM32F4).is_ok());
+        let from_sysclk = I2sConfig { kernel_clock_hz: Some(168_000_000), ..low_rate };
+        assert!(from_sysclk.validate(McuFamily::STM32F4).unwrap_err().contains("divider 328"));
+    }
+}
+
+#[cfg(test)]
+mod sdmmc_tests {
+    use crate::drivers::mcu::McuFamily;
+    use crate::drivers::sdmmc::*;
+
+    #[test]
+    fn test_sdmmc_card_clock() {
+        let config = SdmmcConfig::default();
+        assert_eq!(config.card_clock_hz(McuFamily::STM32F4), 24_000_000);
+        let h7 = SdmmcConfig { clock_div: 4, ..SdmmcConfig::default() };
+        assert_eq!(h7.card_clock_hz(McuFamily::STM32H7), 25_000_000);
+        let bypass = SdmmcConfig { clock_div: 0, ..SdmmcConfig::default() };
+        assert_eq!(bypass.card_clock_hz(McuFamily::STM32H7), 200_000_000);
+        assert!(bypass.validate(McuFamily::STM32H7).is_err());
+    }
+
+    #[test]
+    fn test_sdmmc_driver_generation() {
+        let config = SdmmcConfig::default();
+        assert!(config.validate(McuFamily::STM32F4).is_ok());
+        let output = generate_sdmmc_driver(&config, McuFamily::STM32F4);
+        assert!(output.source_file.contains("hsd1.Instance = SDIO;"));
+        assert!(output.source_file.contains("HAL_SD_ConfigWideBusOperation(&hsd1, SDIO_BUS_WIDE_4B)"));
+        assert!(output.source_file.contains("hdma_sdio_rx.Instance = DMA2_Stream3;"));
+        assert!(output.source_file.contains("HAL_SD_ReadBlocks_DMA(&hsd1, buffer, block, count)"));
+        assert!(output.source_file.contains("uint8_t BSP_SD_Init(void)"));
+        assert!(output.source_file.contains("    sd_rx_done = true;\n    BSP_SD_ReadCpltCallback();"));
+        assert!(output.source_file.contains("gpio.Pin = GPIO_PIN_11;"));
+        assert!(output.example_file.unwrap().contains("voltage translator"));
+
+        let h7 = SdmmcConfig { instance: 2, bus_width: SdmmcBusWidth::Width1Bit, clock_div: 4, dma_enabled: false };
+        assert!(h7.validate(McuFamily::STM32H7).is_ok());
+        let output = generate_sdmmc_driver(&h7, McuFamily::STM32H7);
+        assert!(output.source_file.contains("hsd2.Instance = SDMMC2;"));
+        assert!(output.source_file.contains("HAL_SD_ReadBlocks(&hsd2, buffer, block, count, SD_TIMEOUT_MS)"));
+        assert!(!output.source_file.contains("ConfigWideBusOperation"));
+        assert!(!output.source_file.contains("ClockBypass"));
+        // CK, CMD and D0 only
+        assert_eq!(output.source_file.matches("HAL_GPIO_Init(").count(), 3);
+    }
+
+    #[test]
+    fn test_sdmmc_validation() {
+        assert!(SdmmcConfig::default().validate(McuFamily::STM32G4).is_err());
+        let wide = SdmmcConfig { bus_width: SdmmcBusWidth::Width8Bit, ..SdmmcConfig::default() };
+        assert!(wide.validate(McuFamily::STM32F4).is_err());
+        let second = SdmmcConfig { instance: 2, ..SdmmcConfig::default() };
+        assert!(second.validate(McuFamily::STM32F4).is_err());
+        let l4_dma = SdmmcConfig { clock_div: 1, ..SdmmcConfig::default() };
+        assert!(l4_dma.validate(McuFamily::STM32L4).is_err());
+        assert!(SdmmcConfig { dma_enabled: false, ..l4_dma }.validate(McuFamily::STM32L4).is_ok());
+    }
+}
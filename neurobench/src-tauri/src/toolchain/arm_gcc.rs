@@ -6,10 +6,14 @@ use super::{
     BuildConfig, BuildResult, SizeReport, MapFileInfo, MemoryRegion,
     output_parser,
 };
+use crate::build::lto;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::Instant;
 
+/// Symbols and files listed in the bloat report of `parse_map`
+const DEFAULT_BLOAT_TOP_N: usize = 20;
+
 /// ARM GCC Toolchain implementation
 pub struct ArmGcc {
     info: ToolchainInfo,
@@ -73,8 +77,13 @@ impl ArmGcc {
         Ok(Self::new(info))
     }
     
+    /// Path of arm-none-eabi-gcc
+    pub fn gcc_path(&self) -> &Path {
+        &self.gcc_path
+    }
+    
     /// Get CPU flags for target
-    fn cpu_flags(&self, target: &str) -> Vec<String> {
+    pub(super) fn cpu_flags(&self, target: &str) -> Vec<String> {
         match target.to_lowercase().as_str() {
             "cortex-m0" | "cortex-m0+" => vec![
                 "-mcpu=cortex-m0".to_string(),
@@ -138,6 +147,10 @@ impl ArmGcc {
         
         // Debug info
         cmd.arg("-g3");
+
+        if config.use_lto {
+            cmd.args(lto::lto_compile_flags(config.lto_type));
+        }
         
         // Include paths
         for inc in &config.include_paths {
@@ -190,6 +203,12 @@ impl ArmGcc {
         if let Some(ref ld_script) = config.linker_script {
             cmd.arg("-T").arg(ld_script);
         }
+
+        // LTO re-optimizes at link time, so it needs the optimization level too
+        if config.use_lto {
+            cmd.arg(config.optimization.as_gcc_flag());
+            cmd.args(lto::lto_link_flags(config.lto_type, config.lto_job_count));
+        }
         
         // Input objects
         for obj in objects {
@@ -271,6 +290,10 @@ impl Toolchain for ArmGcc {
             });
         }
         
+        if config.use_lto {
+            lto::verify_lto_objects(&objects).map_err(ToolchainError::BuildFailed)?;
+        }
+
         // Link
         let elf_path = build_dir.join("firmware.elf");
         let link_output = self.link_objects(&objects, &elf_path, config)?;
@@ -343,6 +366,7 @@ impl Toolchain for ArmGcc {
             memory_regions,
             symbols,
             sections: vec![],
+            bloat: super::bloat::bloat_report(&content, DEFAULT_BLOAT_TOP_N, 0).ok(),
         })
     }
 }
@@ -0,0 +1,171 @@
+// Symbol Bloat Analyzer
+// Ranks the symbols and object files that take the most space in a GNU ld map file
+
+use super::ToolchainError;
+use crate::memory::map_visualizer::parse_sections;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// One symbol and the bytes it occupies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolBloat {
+    pub mangled: String,
+    pub demangled: String,
+    pub size: u64,
+    /// Output section, e.g. `.text` or `.bss`
+    pub section: String,
+    /// Object file or archive member the symbol came from
+    pub object: String,
+}
+
+/// Total size contributed by one object file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileBloat {
+    pub object: String,
+    pub size: u64,
+    pub symbol_count: usize,
+}
+
+/// Largest symbols and files, each sorted by size descending
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BloatReport {
+    pub top_symbols: Vec<SymbolBloat>,
+    pub top_files: Vec<FileBloat>,
+    /// Bytes per output section
+    pub section_summary: Vec<(String, u64)>,
+}
+
+/// Demangle a Rust (legacy or v0) or Itanium C++ symbol, returning it unchanged otherwise
+pub fn demangle(symbol: &str) -> String {
+    if let Ok(rust) = rustc_demangle::try_demangle(symbol) {
+        // Alternate format drops the hash suffix
+        return format!("{:#}", rust);
+    }
+    if symbol.starts_with("_Z") {
+        if let Ok(cpp) = cpp_demangle::Symbol::new(symbol) {
+            if let Ok(demangled) = cpp.demangle(&cpp_demangle::DemangleOptions::default()) {
+                return demangled;
+            }
+        }
+    }
+    symbol.to_string()
+}
+
+/// Build a bloat report from map file text
+///
+/// Only symbols larger than `threshold_bytes` are listed, but file and
+/// section totals always count every allocated byte.
+pub fn bloat_report(content: &str, top_n: usize, threshold_bytes: u64) -> Result<BloatReport, ToolchainError> {
+    let (_, sections) = parse_sections(content);
+
+    let mut symbols = Vec::new();
+    let mut files: HashMap<String, FileBloat> = HashMap::new();
+    let mut section_totals: HashMap<String, u64> = HashMap::new();
+    for section in sections.iter().filter(|s| s.is_allocated()) {
+        let object = section.file.clone().unwrap_or_else(|| "<linker>".to_string());
+        *section_totals.entry(section.output.clone()).or_default() += section.size;
+
+        for (name, _, size) in section.symbol_sizes() {
+            let file = files.entry(object.clone()).or_insert_with(|| FileBloat {
+                object: object.clone(),
+                size: 0,
+                symbol_count: 0,
+            });
+            file.size += size;
+            file.symbol_count += 1;
+
+            if size > threshold_bytes {
+                symbols.push(SymbolBloat {
+                    demangled: demangle(&name),
+                    mangled: name,
+                    size,
+                    section: section.output.clone(),
+                    object: object.clone(),
+                });
+            }
+        }
+    }
+    if section_totals.is_empty() {
+        return Err(ToolchainError::ParseError("No allocated sections found in map file".to_string()));
+    }
+
+    symbols.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.mangled.cmp(&b.mangled)));
+    symbols.truncate(top_n);
+    let mut top_files: Vec<FileBloat> = files.into_values().collect();
+    top_files.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.object.cmp(&b.object)));
+    top_files.truncate(top_n);
+    let mut section_summary: Vec<(String, u64)> = section_totals.into_iter().collect();
+    section_summary.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    Ok(BloatReport {
+        top_symbols: symbols,
+        top_files,
+        section_summary,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = r#"Memory Configuration
+
+Name             Origin             Length             Attributes
+FLASH            0x0000000008000000 0x0000000000080000 xr
+RAM              0x0000000020000000 0x0000000000020000 xrw
+
+Linker script and memory map
+
+.text           0x0000000008000000      0x300
+ .text._ZN7Display4drawEv
+                0x0000000008000000      0x180 build/display.o
+                0x0000000008000000                _ZN7Display4drawEv
+ .text._ZN8firmware4main17h0123456789abcdefE
+                0x0000000008000180       0x80 build/firmware.o
+                0x0000000008000180                _ZN8firmware4main17h0123456789abcdefE
+ .text.main     0x0000000008000200       0x40 build/main.o
+                0x0000000008000200                main
+ .text          0x0000000008000240       0xc0 /opt/arm/lib/libc_nano.a(lib_a-memcpy.o)
+                0x0000000008000240                memcpy
+                0x00000000080002c0                memset
+
+.bss            0x0000000020000000      0x200
+ .bss.frame     0x0000000020000000      0x200 build/display.o
+                0x0000000020000000                frame
+
+.debug_info     0x0000000000000000     0x1234
+ .debug_info    0x0000000000000000     0x1234 build/main.o
+"#;
+
+    #[test]
+    fn test_demangle() {
+        assert_eq!(demangle("_ZN7Display4drawEv"), "Display::draw()");
+        assert_eq!(demangle("_ZN8firmware4main17h0123456789abcdefE"), "firmware::main");
+        assert_eq!(demangle("memcpy"), "memcpy");
+    }
+
+    #[test]
+    fn test_bloat_report() {
+        let report = bloat_report(MAP, 3, 0).unwrap();
+
+        let top: Vec<(&str, u64, &str)> = report.top_symbols.iter()
+            .map(|s| (s.demangled.as_str(), s.size, s.section.as_str()))
+            .collect();
+        assert_eq!(top, vec![
+            ("frame", 0x200, ".bss"),
+            ("Display::draw()", 0x180, ".text"),
+            ("firmware::main", 0x80, ".text"),
+        ]);
+        assert_eq!(report.top_files[0].object, "build/display.o");
+        assert_eq!(report.top_files[0].size, 0x380);
+        assert_eq!(report.top_files[0].symbol_count, 2);
+        assert_eq!(report.section_summary, vec![(".text".to_string(), 0x300), (".bss".to_string(), 0x200)]);
+
+        // memset (0x40) and main (0x40) fall under the threshold
+        let report = bloat_report(MAP, 10, 0x40).unwrap();
+        assert_eq!(report.top_symbols.len(), 4);
+        assert!(report.top_symbols.iter().all(|s| s.size > 0x40));
+
+        assert!(bloat_report("not a map file", 10, 0).is_err());
+    }
+}
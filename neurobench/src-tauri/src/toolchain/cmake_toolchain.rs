@@ -0,0 +1,164 @@
+// CMake Toolchain File Generator
+// Emits a cross-compilation toolchain.cmake for a discovered toolchain and MCU
+
+use super::{ToolchainInfo, ToolchainType};
+use crate::drivers::McuFamily;
+use std::path::Path;
+
+pub const TOOLCHAIN_FILENAME: &str = "arm-none-eabi-toolchain.cmake";
+
+/// Core and FPU flags for a Cortex-M family, `None` for non-ARM MCUs
+pub fn mcu_flags(mcu: McuFamily) -> Option<&'static str> {
+    match mcu {
+        McuFamily::STM32F1 | McuFamily::LPC1768 => Some("-mcpu=cortex-m3 -mthumb"),
+        McuFamily::STM32F4 | McuFamily::STM32L4 | McuFamily::STM32G4 | McuFamily::NRF52832 | McuFamily::NRF52840 => {
+            Some("-mcpu=cortex-m4 -mthumb -mfpu=fpv4-sp-d16 -mfloat-abi=hard")
+        }
+        McuFamily::STM32H7 => Some("-mcpu=cortex-m7 -mthumb -mfpu=fpv5-d16 -mfloat-abi=hard"),
+        McuFamily::LPC5500 => Some("-mcpu=cortex-m33 -mthumb -mfpu=fpv5-sp-d16 -mfloat-abi=hard"),
+        McuFamily::RP2040 => Some("-mcpu=cortex-m0plus -mthumb"),
+        McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3 => None,
+    }
+}
+
+/// Check that a CMake toolchain file can be generated for this pair
+pub fn check_supported(info: &ToolchainInfo, mcu: McuFamily) -> Result<(), String> {
+    if info.toolchain_type == ToolchainType::RustEmbedded {
+        return Err(format!("{} is driven by cargo, not CMake", info.name));
+    }
+    if mcu_flags(mcu).is_none() {
+        return Err(format!("{} is not an ARM Cortex-M target; use the vendor SDK toolchain", mcu.display_name()));
+    }
+    Ok(())
+}
+
+/// Directory holding the compiler executables, if the toolchain path names one
+fn bin_dir(path: &Path) -> Option<String> {
+    let dir = if path.extension().is_some_and(|ext| ext == "exe")
+        || path.file_name().is_some_and(|name| name.to_string_lossy().contains("gcc") || name.to_string_lossy().contains("clang"))
+    {
+        path.parent()?
+    } else {
+        path
+    };
+    let dir = dir.to_string_lossy().replace('\\', "/");
+    (!dir.is_empty()).then_some(dir)
+}
+
+/// Generate a CMake toolchain file for cross-compiling to `mcu`
+pub fn generate_cmake_toolchain(info: &ToolchainInfo, mcu: McuFamily) -> String {
+    let flags = mcu_flags(mcu).unwrap_or("-mcpu=cortex-m4 -mthumb");
+    let ext = if info.path.to_string_lossy().ends_with(".exe") { ".exe" } else { "" };
+    let bin = bin_dir(&info.path);
+    let tool = |name: &str| match &bin {
+        Some(_) => format!("${{TOOLCHAIN_BIN_DIR}}/{}{}", name, ext),
+        None => format!("{}{}", name, ext),
+    };
+
+    let mut out = format!(
+        "# CMake toolchain file: {} {} for {}\n\
+         # Auto-generated by NeuroBench\n\
+         #\n\
+         # Pass it when configuring (it must be known before project()):\n\
+         #   cmake -B build -DCMAKE_TOOLCHAIN_FILE={}\n\
+         # or set it in CMakeLists.txt before the project() call:\n\
+         #   set(CMAKE_TOOLCHAIN_FILE ${{CMAKE_CURRENT_SOURCE_DIR}}/{})\n\n\
+         set(CMAKE_SYSTEM_NAME Generic)\n\
+         set(CMAKE_SYSTEM_PROCESSOR arm)\n\n",
+        info.name,
+        info.version,
+        mcu.display_name(),
+        TOOLCHAIN_FILENAME,
+        TOOLCHAIN_FILENAME,
+    );
+    if let Some(dir) = &bin {
+        out.push_str(&format!("set(TOOLCHAIN_BIN_DIR \"{}\")\n", dir));
+    }
+
+    let (cc, cxx, objcopy, size, extra_c, linker) = match info.toolchain_type {
+        ToolchainType::Clang => (
+            tool("armclang"),
+            tool("armclang"),
+            tool("fromelf"),
+            tool("fromelf"),
+            "--target=arm-arm-none-eabi ",
+            "",
+        ),
+        _ => (
+            tool("arm-none-eabi-gcc"),
+            tool("arm-none-eabi-g++"),
+            tool("arm-none-eabi-objcopy"),
+            tool("arm-none-eabi-size"),
+            "",
+            " -specs=nano.specs -specs=nosys.specs",
+        ),
+    };
+    out.push_str(&format!(
+        "set(CMAKE_C_COMPILER {cc})\n\
+         set(CMAKE_CXX_COMPILER {cxx})\n\
+         set(CMAKE_ASM_COMPILER ${{CMAKE_C_COMPILER}})\n\
+         set(CMAKE_OBJCOPY {objcopy})\n\
+         set(CMAKE_SIZE {size})\n\n\
+         # try_compile() cannot link without a linker script\n\
+         set(CMAKE_TRY_COMPILE_TARGET_TYPE STATIC_LIBRARY)\n\n\
+         set(MCU_FLAGS \"{extra_c}{flags}\")\n\
+         set(CMAKE_C_FLAGS_INIT \"${{MCU_FLAGS}} -ffunction-sections -fdata-sections\")\n\
+         set(CMAKE_CXX_FLAGS_INIT \"${{MCU_FLAGS}} -ffunction-sections -fdata-sections -fno-exceptions -fno-rtti\")\n\
+         set(CMAKE_ASM_FLAGS_INIT \"${{MCU_FLAGS}} -x assembler-with-cpp\")\n\
+         set(CMAKE_EXE_LINKER_FLAGS_INIT \"${{MCU_FLAGS}}{linker} -Wl,--gc-sections\")\n\n"
+    ));
+
+    // Libraries and headers come from the toolchain sysroot, programs from the host
+    let root = match (&bin, &info.toolchain_type) {
+        (Some(_), ToolchainType::Clang) => "${TOOLCHAIN_BIN_DIR}/..".to_string(),
+        (Some(_), _) => "${TOOLCHAIN_BIN_DIR}/../arm-none-eabi".to_string(),
+        (None, _) => "/usr/arm-none-eabi".to_string(),
+    };
+    out.push_str(&format!(
+        "set(CMAKE_FIND_ROOT_PATH \"{root}\")\n\
+         set(CMAKE_FIND_ROOT_PATH_MODE_PROGRAM NEVER)\n\
+         set(CMAKE_FIND_ROOT_PATH_MODE_LIBRARY ONLY)\n\
+         set(CMAKE_FIND_ROOT_PATH_MODE_INCLUDE ONLY)\n\
+         set(CMAKE_FIND_ROOT_PATH_MODE_PACKAGE ONLY)\n"
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn gcc(path: &str) -> ToolchainInfo {
+        ToolchainInfo {
+            id: "arm-gcc".to_string(),
+            name: "ARM GNU Toolchain".to_string(),
+            version: "13.2.1".to_string(),
+            path: PathBuf::from(path),
+            toolchain_type: ToolchainType::ArmGcc,
+            targets: vec![],
+        }
+    }
+
+    #[test]
+    fn test_gcc_toolchain_file() {
+        let content = generate_cmake_toolchain(&gcc("/opt/arm-gnu/bin/arm-none-eabi-gcc"), McuFamily::STM32F4);
+        assert!(content.contains("set(CMAKE_SYSTEM_NAME Generic)"));
+        assert!(content.contains("set(TOOLCHAIN_BIN_DIR \"/opt/arm-gnu/bin\")"));
+        assert!(content.contains("set(CMAKE_C_COMPILER ${TOOLCHAIN_BIN_DIR}/arm-none-eabi-gcc)"));
+        assert!(content.contains("set(CMAKE_CXX_COMPILER ${TOOLCHAIN_BIN_DIR}/arm-none-eabi-g++)"));
+        assert!(content.contains("-mcpu=cortex-m4 -mthumb -mfpu=fpv4-sp-d16 -mfloat-abi=hard"));
+        assert!(content.contains("set(CMAKE_FIND_ROOT_PATH \"${TOOLCHAIN_BIN_DIR}/../arm-none-eabi\")"));
+        assert!(content.contains("-DCMAKE_TOOLCHAIN_FILE=arm-none-eabi-toolchain.cmake"));
+    }
+
+    #[test]
+    fn test_windows_bin_dir_and_support() {
+        let content = generate_cmake_toolchain(&gcc(r"C:\arm\13.2\bin"), McuFamily::RP2040);
+        assert!(content.contains("set(TOOLCHAIN_BIN_DIR \"C:/arm/13.2/bin\")"));
+        assert!(content.contains("-mcpu=cortex-m0plus -mthumb"));
+
+        assert!(check_supported(&gcc("arm-none-eabi-gcc"), McuFamily::STM32H7).is_ok());
+        assert!(check_supported(&gcc("arm-none-eabi-gcc"), McuFamily::ESP32).is_err());
+    }
+}
@@ -2,8 +2,9 @@
 // Auto-detect installed toolchains (ARM GCC, Clang, Rust embedded)
 
 use super::{ToolchainInfo, ToolchainType, ToolchainError};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use thiserror::Error;
 
 /// Discover all available toolchains on the system
 pub fn discover_all() -> Vec<ToolchainInfo> {
@@ -50,15 +51,7 @@ pub fn discover_arm_gcc() -> Option<ToolchainInfo> {
                     version,
                     path,
                     toolchain_type: ToolchainType::ArmGcc,
-                    targets: vec![
-                        "cortex-m0".to_string(),
-                        "cortex-m0+".to_string(),
-                        "cortex-m3".to_string(),
-                        "cortex-m4".to_string(),
-                        "cortex-m4f".to_string(),
-                        "cortex-m7".to_string(),
-                        "cortex-m33".to_string(),
-                    ],
+                    targets: arm_gcc_targets(),
                 });
             }
         }
@@ -89,15 +82,7 @@ pub fn discover_arm_gcc() -> Option<ToolchainInfo> {
                                     version,
                                     path: entry.path().join("bin"),
                                     toolchain_type: ToolchainType::ArmGcc,
-                                    targets: vec![
-                                        "cortex-m0".to_string(),
-                                        "cortex-m0+".to_string(),
-                                        "cortex-m3".to_string(),
-                                        "cortex-m4".to_string(),
-                                        "cortex-m4f".to_string(),
-                                        "cortex-m7".to_string(),
-                                        "cortex-m33".to_string(),
-                                    ],
+                                    targets: arm_gcc_targets(),
                                 });
                             }
                         }
@@ -110,6 +95,14 @@ pub fn discover_arm_gcc() -> Option<ToolchainInfo> {
     None
 }
 
+/// Cortex-M cores supported by ARM GCC
+fn arm_gcc_targets() -> Vec<String> {
+    ["cortex-m0", "cortex-m0+", "cortex-m3", "cortex-m4", "cortex-m4f", "cortex-m7", "cortex-m33"]
+        .iter()
+        .map(|t| t.to_string())
+        .collect()
+}
+
 /// Discover ARM Clang toolchain
 fn discover_arm_clang() -> Option<ToolchainInfo> {
     let executables = [
@@ -238,6 +231,204 @@ fn which_path(exe: &str) -> Option<PathBuf> {
     None
 }
 
+// ==================== Toolchain Download ====================
+
+/// ARM GNU Toolchain releases `download_arm_gcc` can install
+pub const SUPPORTED_ARM_GCC_VERSIONS: &[&str] = &["13.2.rel1", "12.3.rel1"];
+
+/// Release installed when none is requested
+pub const DEFAULT_ARM_GCC_VERSION: &str = "13.2.rel1";
+
+const ARM_GCC_CDN: &str = "https://developer.arm.com/-/media/Files/downloads/gnu";
+
+/// Toolchain download errors
+#[derive(Debug, Error)]
+pub enum DownloadError {
+    #[error("Unsupported ARM GCC version {0}, expected one of: 13.2.rel1, 12.3.rel1")]
+    UnsupportedVersion(String),
+
+    #[error("No ARM GCC release for {os}/{arch}")]
+    UnsupportedPlatform { os: String, arch: String },
+
+    #[error("Download failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Download of {url} failed with status {status}")]
+    Status { url: String, status: u16 },
+
+    #[error("Malformed checksum file")]
+    InvalidChecksum,
+
+    #[error("Checksum mismatch: expected {expected}, got {actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+
+    #[error("Extraction failed: {0}")]
+    Extract(String),
+
+    #[error("arm-none-eabi-gcc not found in {0}")]
+    GccNotFound(PathBuf),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+/// Release archive name for a host, e.g. `arm-gnu-toolchain-13.2.rel1-x86_64-arm-none-eabi.tar.xz`
+pub fn arm_gcc_archive_name(version: &str, os: &str, arch: &str) -> Result<String, DownloadError> {
+    let (host, extension) = match (os, arch) {
+        ("linux", "x86_64") => ("x86_64", "tar.xz"),
+        ("linux", "aarch64") => ("aarch64", "tar.xz"),
+        ("macos", "x86_64") => ("darwin-x86_64", "tar.xz"),
+        ("macos", "aarch64") => ("darwin-arm64", "tar.xz"),
+        ("windows", _) => ("mingw-w64-i686", "zip"),
+        _ => return Err(DownloadError::UnsupportedPlatform { os: os.to_string(), arch: arch.to_string() }),
+    };
+    Ok(format!("arm-gnu-toolchain-{}-{}-arm-none-eabi.{}", version, host, extension))
+}
+
+/// Download, verify and extract an ARM GNU Toolchain release into `target_dir`
+///
+/// Each release is installed to `target_dir/arm-gnu-toolchain-<version>`;
+/// an existing install is returned without downloading again. `progress`
+/// receives the downloaded fraction from 0.0 to 1.0.
+pub async fn download_arm_gcc(
+    version: &str,
+    target_dir: &Path,
+    progress: impl Fn(f32),
+) -> Result<ToolchainInfo, DownloadError> {
+    download_arm_gcc_from(ARM_GCC_CDN, version, target_dir, progress).await
+}
+
+async fn download_arm_gcc_from(
+    base_url: &str,
+    version: &str,
+    target_dir: &Path,
+    progress: impl Fn(f32),
+) -> Result<ToolchainInfo, DownloadError> {
+    if !SUPPORTED_ARM_GCC_VERSIONS.contains(&version) {
+        return Err(DownloadError::UnsupportedVersion(version.to_string()));
+    }
+
+    let install_dir = target_dir.join(format!("arm-gnu-toolchain-{}", version));
+    if let Some(bin) = find_gcc_bin(&install_dir) {
+        progress(1.0);
+        return Ok(downloaded_arm_gcc(version, bin));
+    }
+
+    let archive = arm_gcc_archive_name(version, std::env::consts::OS, std::env::consts::ARCH)?;
+    let url = format!("{}/{}/binrel/{}", base_url, version, archive);
+    let client = reqwest::Client::new();
+
+    // Arm publishes `<archive>.sha256asc` next to each release
+    let checksum_file = fetch(&client, &format!("{}.sha256asc", url)).await?.text().await?;
+    let expected = checksum_file.split_whitespace().next()
+        .filter(|h| h.len() == 64 && h.chars().all(|c| c.is_ascii_hexdigit()))
+        .ok_or(DownloadError::InvalidChecksum)?
+        .to_lowercase();
+
+    std::fs::create_dir_all(target_dir)?;
+    let archive_path = target_dir.join(&archive);
+    let actual = download_file(&client, &url, &archive_path, &progress).await?;
+    if actual != expected {
+        let _ = std::fs::remove_file(&archive_path);
+        return Err(DownloadError::ChecksumMismatch { expected, actual });
+    }
+
+    // Extract next to the final location so a failed run leaves no half install
+    let staging = target_dir.join(format!(".arm-gnu-toolchain-{}.partial", version));
+    let _ = std::fs::remove_dir_all(&staging);
+    let (from, to) = (archive_path.clone(), staging.clone());
+    tokio::task::spawn_blocking(move || extract_archive(&from, &to))
+        .await
+        .map_err(|e| DownloadError::Extract(e.to_string()))??;
+    let _ = std::fs::remove_dir_all(&install_dir);
+    std::fs::rename(&staging, &install_dir)?;
+    let _ = std::fs::remove_file(&archive_path);
+
+    let bin = find_gcc_bin(&install_dir).ok_or_else(|| DownloadError::GccNotFound(install_dir.clone()))?;
+    Ok(downloaded_arm_gcc(version, bin))
+}
+
+async fn fetch(client: &reqwest::Client, url: &str) -> Result<reqwest::Response, DownloadError> {
+    let response = client.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(DownloadError::Status { url: url.to_string(), status: response.status().as_u16() });
+    }
+    Ok(response)
+}
+
+/// Stream `url` to `path`, returning the SHA-256 of the contents
+async fn download_file(
+    client: &reqwest::Client,
+    url: &str,
+    path: &Path,
+    progress: &impl Fn(f32),
+) -> Result<String, DownloadError> {
+    use sha2::{Digest, Sha256};
+    use std::io::Write;
+
+    let mut response = fetch(client, url).await?;
+    let total = response.content_length();
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let mut hasher = Sha256::new();
+    let mut received = 0u64;
+    let mut last_percent = 0u64;
+
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk)?;
+        hasher.update(&chunk);
+        received += chunk.len() as u64;
+        if let Some(total) = total.filter(|t| *t > 0) {
+            let percent = received * 100 / total;
+            if percent > last_percent {
+                last_percent = percent;
+                progress(received as f32 / total as f32);
+            }
+        }
+    }
+    file.flush()?;
+    progress(1.0);
+
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+fn extract_archive(archive: &Path, dest: &Path) -> Result<(), DownloadError> {
+    let file = std::fs::File::open(archive)?;
+    if archive.extension().is_some_and(|e| e == "zip") {
+        zip::ZipArchive::new(file)
+            .and_then(|mut zip| zip.extract(dest))
+            .map_err(|e| DownloadError::Extract(e.to_string()))
+    } else {
+        tar::Archive::new(xz2::read::XzDecoder::new(file))
+            .unpack(dest)
+            .map_err(|e| DownloadError::Extract(e.to_string()))
+    }
+}
+
+/// `bin` directory holding arm-none-eabi-gcc, directly or one level below `dir`
+fn find_gcc_bin(dir: &Path) -> Option<PathBuf> {
+    let gcc = if cfg!(target_os = "windows") { "arm-none-eabi-gcc.exe" } else { "arm-none-eabi-gcc" };
+    let has_gcc = |root: &Path| root.join("bin").join(gcc).is_file();
+    if has_gcc(dir) {
+        return Some(dir.join("bin"));
+    }
+    std::fs::read_dir(dir).ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| has_gcc(path))
+        .map(|path| path.join("bin"))
+}
+
+fn downloaded_arm_gcc(version: &str, bin: PathBuf) -> ToolchainInfo {
+    ToolchainInfo {
+        id: format!("arm-gcc-{}", version),
+        name: "ARM GNU Toolchain".to_string(),
+        version: version.to_string(),
+        path: bin,
+        toolchain_type: ToolchainType::ArmGcc,
+        targets: arm_gcc_targets(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -257,4 +448,68 @@ mod tests {
             println!("  - {} v{}", tc.name, tc.version);
         }
     }
+
+    /// Serve one canned binary response per connection
+    async fn mock_server(responses: Vec<Vec<u8>>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for body in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let head = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len());
+                socket.write_all(head.as_bytes()).await.unwrap();
+                socket.write_all(&body).await.unwrap();
+            }
+        });
+        url
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_download_arm_gcc() {
+        use sha2::{Digest, Sha256};
+
+        let mut builder = tar::Builder::new(xz2::write::XzEncoder::new(Vec::new(), 1));
+        let script = b"#!/bin/sh\necho 13.2.1\n";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(script.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder.append_data(&mut header, "arm-gnu-toolchain-13.2.Rel1-x86_64-arm-none-eabi/bin/arm-none-eabi-gcc", &script[..]).unwrap();
+        let archive = builder.into_inner().unwrap().finish().unwrap();
+        let hash: String = Sha256::digest(&archive).iter().map(|b| format!("{:02x}", b)).collect();
+
+        let dir = tempfile::tempdir().unwrap();
+        let bad = mock_server(vec![format!("{}  x.tar.xz\n", "0".repeat(64)).into_bytes(), archive.clone()]).await;
+        assert!(matches!(
+            download_arm_gcc_from(&bad, "13.2.rel1", dir.path(), |_| {}).await,
+            Err(DownloadError::ChecksumMismatch { .. })
+        ));
+
+        let server = mock_server(vec![format!("{}  x.tar.xz\n", hash).into_bytes(), archive]).await;
+        let reports = std::sync::Mutex::new(Vec::new());
+        let info = download_arm_gcc_from(&server, "13.2.rel1", dir.path(), |p| reports.lock().unwrap().push(p)).await.unwrap();
+        assert!(info.path.join("arm-none-eabi-gcc").is_file());
+        assert_eq!(reports.lock().unwrap().last(), Some(&1.0));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        // Installed releases are reused without contacting the server
+        let cached = download_arm_gcc_from("http://127.0.0.1:9", "13.2.rel1", dir.path(), |_| {}).await.unwrap();
+        assert_eq!(cached.path, info.path);
+        assert!(matches!(download_arm_gcc("11.0.rel1", dir.path(), |_| {}).await, Err(DownloadError::UnsupportedVersion(_))));
+    }
+
+    #[test]
+    fn test_arm_gcc_archive_name() {
+        assert_eq!(arm_gcc_archive_name("12.3.rel1", "linux", "x86_64").unwrap(), "arm-gnu-toolchain-12.3.rel1-x86_64-arm-none-eabi.tar.xz");
+        assert_eq!(arm_gcc_archive_name("13.2.rel1", "windows", "x86_64").unwrap(), "arm-gnu-toolchain-13.2.rel1-mingw-w64-i686-arm-none-eabi.zip");
+        assert!(arm_gcc_archive_name("13.2.rel1", "freebsd", "x86_64").is_err());
+    }
 }
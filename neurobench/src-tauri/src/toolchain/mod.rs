@@ -5,12 +5,18 @@ pub mod discovery;
 pub mod arm_gcc;
 pub mod output_parser;
 pub mod probe;
+pub mod openocd;
+pub mod cmake_toolchain;
+pub mod signing;
 pub mod streaming_build;
+pub mod ninja;
+pub mod bloat;
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use thiserror::Error;
+use crate::build::LtoType;
 
 /// Toolchain errors
 #[derive(Debug, Error)]
@@ -65,6 +71,14 @@ pub struct BuildConfig {
     pub source_files: Vec<PathBuf>,
     pub linker_script: Option<PathBuf>,
     pub toolchain_id: Option<String>,
+    /// Link-time optimization
+    #[serde(default)]
+    pub use_lto: bool,
+    #[serde(default)]
+    pub lto_type: LtoType,
+    /// Parallel LTRANS jobs for the LTO link
+    #[serde(default)]
+    pub lto_job_count: Option<u8>,
 }
 
 impl Default for BuildConfig {
@@ -79,6 +93,9 @@ impl Default for BuildConfig {
             source_files: vec![],
             linker_script: None,
             toolchain_id: None,
+            use_lto: false,
+            lto_type: LtoType::Full,
+            lto_job_count: None,
         }
     }
 }
@@ -180,6 +197,9 @@ pub struct MapFileInfo {
     pub memory_regions: Vec<MemoryRegion>,
     pub symbols: Vec<SymbolEntry>,
     pub sections: Vec<SectionInfo>,
+    /// Largest symbols and object files; `None` when the map could not be analyzed
+    #[serde(default)]
+    pub bloat: Option<bloat::BloatReport>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,336 @@
+// Ninja Build Backend
+// Generates build.ninja so Ninja can schedule compiles in parallel and track header dependencies
+
+use super::arm_gcc::ArmGcc;
+use super::streaming_build::StreamingBuildConfig;
+use super::{
+    Toolchain, ToolchainInfo, ToolchainError,
+    BuildConfig, BuildResult, SizeReport, MapFileInfo,
+    output_parser,
+};
+use crate::build::lto;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Instant;
+
+/// Generated file name inside the build directory
+pub const NINJA_FILE: &str = "build.ninja";
+
+/// Everything a generated `build.ninja` needs
+///
+/// Ninja runs commands from the build directory, so sources, include paths
+/// and the linker script are made absolute against `project_path`.
+#[derive(Debug, Clone)]
+pub struct NinjaFile {
+    pub cc: PathBuf,
+    pub objcopy: PathBuf,
+    pub size: PathBuf,
+    pub cflags: Vec<String>,
+    pub ldflags: Vec<String>,
+    pub project_path: PathBuf,
+    pub sources: Vec<PathBuf>,
+    pub linker_script: Option<PathBuf>,
+}
+
+impl NinjaFile {
+    /// Same flags `ArmGcc::build` passes
+    pub fn for_build_config(config: &BuildConfig, gcc: &ArmGcc) -> Self {
+        let project = &config.project_path;
+        let cpu_flags = gcc.cpu_flags(&config.mcu_target);
+
+        let mut cflags = cpu_flags.clone();
+        cflags.push(config.optimization.as_gcc_flag().to_string());
+        cflags.extend(["-Wall", "-Wextra", "-ffunction-sections", "-fdata-sections", "-ffreestanding", "-nostdlib", "-g3"].map(String::from));
+        if config.use_lto {
+            cflags.extend(lto::lto_compile_flags(config.lto_type));
+        }
+        cflags.extend(config.include_paths.iter().map(|inc| format!("-I{}", project.join(inc).display())));
+        // Sorted so an unchanged config gives an unchanged command line and no rebuild
+        let mut defines: Vec<_> = config.defines.iter().collect();
+        defines.sort();
+        cflags.extend(defines.into_iter().map(|(key, value)| {
+            if value.is_empty() { format!("-D{}", key) } else { format!("-D{}={}", key, value) }
+        }));
+
+        let mut ldflags = cpu_flags;
+        ldflags.extend(["-Wl,--gc-sections", "-Wl,-Map=firmware.map", "--specs=nosys.specs", "--specs=nano.specs"].map(String::from));
+        if config.use_lto {
+            ldflags.push(config.optimization.as_gcc_flag().to_string());
+            ldflags.extend(lto::lto_link_flags(config.lto_type, config.lto_job_count));
+        }
+
+        Self::with_tools(gcc.gcc_path(), cflags, ldflags, project, &config.source_files, config.linker_script.as_deref())
+    }
+
+    /// Same flags the streaming build's own compile loop passes
+    pub fn for_streaming_config(config: &StreamingBuildConfig, gcc: &Path) -> Self {
+        let project = &config.project_path;
+        let mut absolute = config.clone();
+        absolute.include_paths = config.include_paths.iter().map(|inc| project.join(inc)).collect();
+
+        let mut ldflags = vec![
+            format!("-mcpu={}", config.mcu_target),
+            "-mthumb".to_string(),
+            "-Wl,--gc-sections".to_string(),
+        ];
+        if config.post_build.generate_map {
+            ldflags.push("-Wl,-Map=firmware.map".to_string());
+        }
+        ldflags.extend(["--specs=nosys.specs", "--specs=nano.specs"].map(String::from));
+        if config.use_lto {
+            ldflags.push(format!("-{}", config.optimization));
+            ldflags.extend(lto::lto_link_flags(config.lto_type, config.lto_job_count));
+        }
+
+        Self::with_tools(gcc, absolute.compile_flags(), ldflags, project, &config.source_files, config.linker_script.as_deref())
+    }
+
+    fn with_tools(
+        gcc: &Path,
+        cflags: Vec<String>,
+        mut ldflags: Vec<String>,
+        project: &Path,
+        sources: &[PathBuf],
+        linker_script: Option<&Path>,
+    ) -> Self {
+        let linker_script = linker_script.map(|ld| project.join(ld));
+        if let Some(ld) = &linker_script {
+            ldflags.push(format!("-T{}", ld.display()));
+        }
+        Self {
+            cc: gcc.to_path_buf(),
+            objcopy: sibling_tool(gcc, "arm-none-eabi-objcopy"),
+            size: sibling_tool(gcc, "arm-none-eabi-size"),
+            cflags,
+            ldflags,
+            project_path: project.to_path_buf(),
+            sources: sources.iter().map(|src| project.join(src)).collect(),
+            linker_script,
+        }
+    }
+
+    /// Object file for a source, mirroring its place in the project under `obj/`
+    pub fn object_path(&self, source: &Path) -> String {
+        let relative = source.strip_prefix(&self.project_path)
+            .unwrap_or_else(|_| Path::new(source.file_name().unwrap_or_default()));
+        Path::new("obj").join(relative).with_extension("o").to_string_lossy().replace('\\', "/")
+    }
+
+    /// Render `build.ninja`
+    pub fn render(&self) -> String {
+        let mut out = String::from(
+            "# build.ninja\n\
+             # Auto-generated by NeuroBench, rewritten on every build\n\n\
+             ninja_required_version = 1.3\n\n",
+        );
+        out.push_str(&format!("cc = {}\n", escape_value(&self.cc.display().to_string())));
+        out.push_str(&format!("objcopy = {}\n", escape_value(&self.objcopy.display().to_string())));
+        out.push_str(&format!("size = {}\n", escape_value(&self.size.display().to_string())));
+        out.push_str(&format!("cflags = {}\n", flags_value(&self.cflags)));
+        out.push_str(&format!("ldflags = {}\n\n", flags_value(&self.ldflags)));
+
+        out.push_str(
+            "rule cc\n  command = $cc -MMD -MF $out.d $cflags -c $in -o $out\n  depfile = $out.d\n  deps = gcc\n  description = CC $out\n\n\
+             rule link\n  command = $cc $ldflags $in -o $out\n  description = LINK $out\n\n\
+             rule objcopy\n  command = $objcopy -O binary $in $out\n  description = OBJCOPY $out\n\n\
+             rule size\n  command = $size $in\n  description = SIZE $in\n\n\
+             rule clean\n  command = ninja -t clean\n  description = CLEAN\n\n",
+        );
+
+        let objects: Vec<String> = self.sources.iter().map(|src| self.object_path(src)).collect();
+        for (source, object) in self.sources.iter().zip(&objects) {
+            out.push_str(&format!("build {}: cc {}\n", escape_path(object), escape_path(&source.display().to_string())));
+        }
+
+        let objects: Vec<String> = objects.iter().map(|obj| escape_path(obj)).collect();
+        let implicit = match &self.linker_script {
+            Some(ld) => format!(" | {}", escape_path(&ld.display().to_string())),
+            None => String::new(),
+        };
+        out.push_str(&format!("\nbuild firmware.elf: link {}{}\n", objects.join(" "), implicit));
+        out.push_str("build firmware.bin: objcopy firmware.elf\n\n");
+
+        // size and clean never produce their output, so they run every time
+        out.push_str("build all: phony firmware.elf firmware.bin\n");
+        out.push_str("build size: size firmware.elf\n");
+        out.push_str("build clean: clean\n\n");
+        out.push_str("default all\n");
+        out
+    }
+}
+
+/// `arm-none-eabi-objcopy` next to `arm-none-eabi-gcc`, keeping an `.exe` suffix
+fn sibling_tool(gcc: &Path, name: &str) -> PathBuf {
+    let mut tool = gcc.with_file_name(name);
+    if let Some(ext) = gcc.extension() {
+        tool.set_extension(ext);
+    }
+    tool
+}
+
+/// Escape a path in a `build` line
+fn escape_path(path: &str) -> String {
+    path.replace('$', "$$").replace(' ', "$ ").replace(':', "$:")
+}
+
+fn escape_value(value: &str) -> String {
+    value.replace('$', "$$")
+}
+
+/// Flags as one variable value, quoting any with spaces for the shell
+fn flags_value(flags: &[String]) -> String {
+    flags.iter()
+        .map(|flag| {
+            if flag.contains(char::is_whitespace) { format!("\"{}\"", escape_value(flag)) } else { escape_value(flag) }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A `[finished/total] description` status line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NinjaStatus<'a> {
+    pub finished: usize,
+    pub total: usize,
+    pub description: &'a str,
+}
+
+/// Parse Ninja's default `[%f/%t] ` status prefix
+pub fn parse_status_line(line: &str) -> Option<NinjaStatus<'_>> {
+    let (counts, description) = line.strip_prefix('[')?.split_once(']')?;
+    let (finished, total) = counts.split_once('/')?;
+    Some(NinjaStatus {
+        finished: finished.trim().parse().ok()?,
+        total: total.trim().parse().ok()?,
+        description: description.trim(),
+    })
+}
+
+/// ARM GCC driven through a generated `build.ninja`
+pub struct NinjaBuild {
+    gcc: ArmGcc,
+    ninja_path: PathBuf,
+}
+
+impl NinjaBuild {
+    pub fn new(info: ToolchainInfo) -> Self {
+        Self {
+            gcc: ArmGcc::new(info),
+            ninja_path: which::which("ninja").unwrap_or_else(|_| PathBuf::from("ninja")),
+        }
+    }
+}
+
+impl Toolchain for NinjaBuild {
+    fn info(&self) -> &ToolchainInfo {
+        self.gcc.info()
+    }
+
+    fn build(&self, config: &BuildConfig) -> Result<BuildResult, ToolchainError> {
+        let start = Instant::now();
+        let build_dir = config.output_dir.clone()
+            .unwrap_or_else(|| config.project_path.join("build"));
+        std::fs::create_dir_all(&build_dir)?;
+        std::fs::write(build_dir.join(NINJA_FILE), NinjaFile::for_build_config(config, &self.gcc).render())?;
+
+        let output = Command::new(&self.ninja_path)
+            .arg("-C")
+            .arg(&build_dir)
+            .output()?;
+        let all_output = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr),
+        );
+        let (errors, warnings) = output_parser::parse_compiler_output(&all_output);
+
+        let elf_path = build_dir.join("firmware.elf");
+        let bin_path = build_dir.join("firmware.bin");
+        let success = output.status.success() && elf_path.exists();
+
+        Ok(BuildResult {
+            success,
+            binary_path: (success && bin_path.exists()).then_some(bin_path),
+            elf_path: success.then_some(elf_path),
+            errors,
+            warnings,
+            duration_ms: start.elapsed().as_millis() as u64,
+            output: all_output,
+        })
+    }
+
+    fn clean(&self, project_path: &Path) -> Result<(), ToolchainError> {
+        let build_dir = project_path.join("build");
+        if !build_dir.join(NINJA_FILE).exists() {
+            return Ok(());
+        }
+        let status = Command::new(&self.ninja_path)
+            .arg("-C")
+            .arg(&build_dir)
+            .args(["-t", "clean"])
+            .status()?;
+        if !status.success() {
+            return Err(ToolchainError::BuildFailed("ninja -t clean failed".to_string()));
+        }
+        Ok(())
+    }
+
+    fn size(&self, elf_path: &Path) -> Result<SizeReport, ToolchainError> {
+        self.gcc.size(elf_path)
+    }
+
+    fn parse_map(&self, map_path: &Path) -> Result<MapFileInfo, ToolchainError> {
+        self.gcc.parse_map(map_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toolchain::ToolchainType;
+
+    fn arm_gcc() -> ArmGcc {
+        ArmGcc::new(ToolchainInfo {
+            id: "arm-gcc".to_string(),
+            name: "ARM GCC".to_string(),
+            version: "13.2".to_string(),
+            path: PathBuf::from("/opt/arm/bin"),
+            toolchain_type: ToolchainType::ArmGcc,
+            targets: vec![],
+        })
+    }
+
+    #[test]
+    fn test_render_build_ninja() {
+        let config = BuildConfig {
+            project_path: PathBuf::from("/work/blinky"),
+            source_files: vec![PathBuf::from("src/main.c"), PathBuf::from("drivers/uart.c")],
+            include_paths: vec![PathBuf::from("inc")],
+            linker_script: Some(PathBuf::from("STM32F407.ld")),
+            ..Default::default()
+        };
+        let ninja = NinjaFile::for_build_config(&config, &arm_gcc()).render();
+
+        assert!(ninja.contains("cc = /opt/arm/bin/arm-none-eabi-gcc\n"));
+        assert!(ninja.contains("objcopy = /opt/arm/bin/arm-none-eabi-objcopy\n"));
+        assert!(ninja.contains("  depfile = $out.d\n  deps = gcc\n"));
+        assert!(ninja.contains("-I/work/blinky/inc"));
+        assert!(ninja.contains("build obj/src/main.o: cc /work/blinky/src/main.c\n"));
+        assert!(ninja.contains("build obj/drivers/uart.o: cc /work/blinky/drivers/uart.c\n"));
+        assert!(ninja.contains("build firmware.elf: link obj/src/main.o obj/drivers/uart.o | /work/blinky/STM32F407.ld\n"));
+        for target in ["build all: phony", "build size: size", "build clean: clean", "default all"] {
+            assert!(ninja.contains(target), "missing {}", target);
+        }
+    }
+
+    #[test]
+    fn test_escaping_and_status_lines() {
+        assert_eq!(escape_path("C:/my proj/main.c"), "C$:/my$ proj/main.c");
+        assert_eq!(flags_value(&["-DPRICE=$5".to_string(), "-I/my proj".to_string()]), "-DPRICE=$$5 \"-I/my proj\"");
+
+        let status = parse_status_line("[3/10] CC obj/src/main.o").unwrap();
+        assert_eq!((status.finished, status.total, status.description), (3, 10, "CC obj/src/main.o"));
+        assert!(parse_status_line("src/main.c:4:1: error: expected ';'").is_none());
+        assert!(parse_status_line("[tool] note").is_none());
+    }
+}
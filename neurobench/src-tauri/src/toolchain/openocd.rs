@@ -0,0 +1,167 @@
+// OpenOCD Configuration Generator
+// Emits openocd.cfg with the interface and target scripts for a probe/MCU pair
+
+use crate::drivers::McuFamily;
+use serde::{Deserialize, Serialize};
+
+/// Debug probe as OpenOCD sees it (interface script and speed limits differ per generation)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeType {
+    StLinkV2,
+    StLinkV3,
+    JLink,
+    DapLink,
+}
+
+impl ProbeType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProbeType::StLinkV2 => "ST-Link V2",
+            ProbeType::StLinkV3 => "ST-Link V3",
+            ProbeType::JLink => "J-Link",
+            ProbeType::DapLink => "DAPLink (CMSIS-DAP)",
+        }
+    }
+
+    /// Interface script under OpenOCD's `interface/` directory
+    pub fn interface_script(&self) -> &'static str {
+        match self {
+            // stlink.cfg covers V2, V2-1 and V3; stlink-v2.cfg is deprecated
+            ProbeType::StLinkV2 | ProbeType::StLinkV3 => "interface/stlink.cfg",
+            ProbeType::JLink => "interface/jlink.cfg",
+            ProbeType::DapLink => "interface/cmsis-dap.cfg",
+        }
+    }
+
+    /// Highest SWD/JTAG clock the probe supports
+    pub fn max_speed_khz(&self) -> u32 {
+        match self {
+            ProbeType::StLinkV2 => 4000,
+            ProbeType::StLinkV3 => 24000,
+            ProbeType::JLink => 50000,
+            ProbeType::DapLink => 10000,
+        }
+    }
+}
+
+/// Target script under OpenOCD's `target/` directory, if upstream ships one
+pub fn target_script(mcu: McuFamily) -> Option<&'static str> {
+    match mcu {
+        McuFamily::STM32F1 => Some("target/stm32f1x.cfg"),
+        McuFamily::STM32F4 => Some("target/stm32f4x.cfg"),
+        McuFamily::STM32H7 => Some("target/stm32h7x.cfg"),
+        McuFamily::STM32L4 => Some("target/stm32l4x.cfg"),
+        McuFamily::STM32G4 => Some("target/stm32g4x.cfg"),
+        McuFamily::ESP32 => Some("target/esp32.cfg"),
+        McuFamily::ESP32S3 => Some("target/esp32s3.cfg"),
+        McuFamily::ESP32C3 => Some("target/esp32c3.cfg"),
+        McuFamily::RP2040 => Some("target/rp2040.cfg"),
+        McuFamily::NRF52832 | McuFamily::NRF52840 => Some("target/nrf52.cfg"),
+        McuFamily::LPC1768 => Some("target/lpc17xx.cfg"),
+        McuFamily::LPC5500 => None,
+    }
+}
+
+fn is_espressif(mcu: McuFamily) -> bool {
+    matches!(mcu, McuFamily::ESP32 | McuFamily::ESP32S3 | McuFamily::ESP32C3)
+}
+
+/// Check that OpenOCD can drive `mcu` through `probe` at `speed_khz`
+pub fn check_compatibility(probe: ProbeType, mcu: McuFamily, speed_khz: u32) -> Result<(), String> {
+    if target_script(mcu).is_none() {
+        return Err(format!("OpenOCD has no target script for {}; use probe-rs or pyOCD", mcu.display_name()));
+    }
+    let st_link = matches!(probe, ProbeType::StLinkV2 | ProbeType::StLinkV3);
+    if st_link && is_espressif(mcu) {
+        return Err(format!("{} needs a JTAG probe; ST-Link only supports ARM SWD targets", mcu.display_name()));
+    }
+    if st_link && mcu == McuFamily::RP2040 {
+        return Err("RP2040 uses multi-drop SWD, which ST-Link does not support".to_string());
+    }
+    if speed_khz == 0 || speed_khz > probe.max_speed_khz() {
+        return Err(format!(
+            "{} supports adapter speeds up to {} kHz, got {}",
+            probe.name(),
+            probe.max_speed_khz(),
+            speed_khz
+        ));
+    }
+    Ok(())
+}
+
+/// Generate `openocd.cfg` for a probe/MCU combination
+pub fn generate_openocd_cfg(probe: ProbeType, mcu: McuFamily, speed_khz: u32) -> String {
+    let transport = if is_espressif(mcu) {
+        "jtag"
+    } else {
+        match probe {
+            // High-level adapter mode: the ST-Link firmware runs the SWD protocol
+            ProbeType::StLinkV2 | ProbeType::StLinkV3 => "hla_swd",
+            ProbeType::JLink | ProbeType::DapLink => "swd",
+        }
+    };
+
+    let mut cfg = format!(
+        "# OpenOCD configuration\n\
+         # Auto-generated by NeuroBench\n\
+         # Probe: {}, Target: {}\n\
+         #\n\
+         # Flash: openocd -f openocd.cfg -c \"program build/firmware.elf verify reset exit\"\n\
+         # Debug: openocd -f openocd.cfg, then connect GDB to localhost:3333\n\n",
+        probe.name(),
+        mcu.display_name(),
+    );
+    if is_espressif(mcu) {
+        cfg.push_str("# Espressif targets need the openocd-esp32 fork shipped with ESP-IDF\n");
+    }
+
+    cfg.push_str(&format!("source [find {}]\n", probe.interface_script()));
+    cfg.push_str(&format!("transport select {}\n\n", transport));
+
+    match target_script(mcu) {
+        Some(target) => cfg.push_str(&format!("source [find {}]\n", target)),
+        None => cfg.push_str(&format!(
+            "# OpenOCD ships no target script for {}\n# source [find target/<target>.cfg]\n",
+            mcu.display_name()
+        )),
+    }
+    // Target scripts set their own default speed, so ours has to come after them
+    cfg.push_str(&format!("adapter speed {}\n\n", speed_khz));
+
+    cfg.push_str("# Uncomment when NRST is wired to the probe\n");
+    cfg.push_str("# reset_config srst_only srst_nogate connect_assert_srst\n");
+    cfg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stlink_stm32f4_config() {
+        let cfg = generate_openocd_cfg(ProbeType::StLinkV2, McuFamily::STM32F4, 4000);
+        assert!(cfg.contains("source [find interface/stlink.cfg]"));
+        assert!(cfg.contains("transport select hla_swd"));
+        assert!(cfg.contains("adapter speed 4000"));
+        assert!(cfg.contains("source [find target/stm32f4x.cfg]"));
+        assert!(cfg.find("adapter speed 4000") > cfg.find("source [find target/stm32f4x.cfg]"));
+        assert!(check_compatibility(ProbeType::StLinkV2, McuFamily::STM32F4, 4000).is_ok());
+        assert!(check_compatibility(ProbeType::StLinkV2, McuFamily::STM32F4, 8000).is_err());
+    }
+
+    #[test]
+    fn test_probe_target_combinations() {
+        let cfg = generate_openocd_cfg(ProbeType::DapLink, McuFamily::RP2040, 5000);
+        assert!(cfg.contains("source [find interface/cmsis-dap.cfg]"));
+        assert!(cfg.contains("transport select swd"));
+        assert!(cfg.contains("source [find target/rp2040.cfg]"));
+
+        let esp = generate_openocd_cfg(ProbeType::JLink, McuFamily::ESP32, 5000);
+        assert!(esp.contains("transport select jtag"));
+
+        assert!(check_compatibility(ProbeType::StLinkV3, McuFamily::RP2040, 4000).is_err());
+        assert!(check_compatibility(ProbeType::StLinkV3, McuFamily::ESP32C3, 4000).is_err());
+        assert!(check_compatibility(ProbeType::JLink, McuFamily::LPC5500, 4000).is_err());
+    }
+}
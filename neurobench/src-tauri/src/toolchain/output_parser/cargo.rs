@@ -0,0 +1,256 @@
+// Cargo Output Parser
+// Parse `cargo build --message-format=json-diagnostic-rendered-ansi` lines into diagnostics
+
+use crate::toolchain::streaming_build::{DiagnosticCategory, DiagnosticSeverity, EnhancedDiagnostic};
+use serde::Deserialize;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+/// One JSON line from cargo
+#[derive(Debug, Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<RustcDiagnostic>,
+    /// Set on `compiler-artifact` lines for binaries
+    executable: Option<String>,
+}
+
+/// rustc's JSON diagnostic
+#[derive(Debug, Deserialize)]
+struct RustcDiagnostic {
+    message: String,
+    code: Option<RustcCode>,
+    level: String,
+    #[serde(default)]
+    spans: Vec<RustcSpan>,
+    #[serde(default)]
+    children: Vec<RustcDiagnostic>,
+    rendered: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustcCode {
+    code: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustcSpan {
+    file_name: String,
+    line_start: u32,
+    line_end: u32,
+    column_start: u32,
+    column_end: u32,
+    is_primary: bool,
+    suggested_replacement: Option<String>,
+}
+
+/// Parse one line of cargo JSON output
+///
+/// Only `compiler-message` lines carry diagnostics; `compiler-artifact`,
+/// `build-script-executed` and `build-finished` give `None`, as do non-JSON
+/// lines and rustc's summaries ("aborting due to ...", "generated N warnings",
+/// "N warnings emitted").
+/// Crate-level errors without a span get line 0. Span paths are kept as cargo
+/// reports them, relative to the workspace root for local crates.
+pub fn parse_cargo_json_line(line: &str) -> Option<EnhancedDiagnostic> {
+    let msg: CargoMessage = serde_json::from_str(line.trim()).ok()?;
+    match msg.reason.as_str() {
+        "compiler-message" => to_diagnostic(msg.message?),
+        _ => None,
+    }
+}
+
+/// Binary a `compiler-artifact` line produced, e.g. `target/thumbv7em-none-eabihf/release/blinky`
+pub fn parse_cargo_executable(line: &str) -> Option<String> {
+    let msg: CargoMessage = serde_json::from_str(line.trim()).ok()?;
+    match msg.reason.as_str() {
+        "compiler-artifact" => msg.executable,
+        _ => None,
+    }
+}
+
+fn to_diagnostic(diag: RustcDiagnostic) -> Option<EnhancedDiagnostic> {
+    let severity = match diag.level.as_str() {
+        "error" | "error: internal compiler error" => DiagnosticSeverity::Error,
+        "warning" => DiagnosticSeverity::Warning,
+        "note" => DiagnosticSeverity::Note,
+        "help" => DiagnosticSeverity::Help,
+        _ => return None,
+    };
+
+    let span = diag.spans.iter().find(|s| s.is_primary).or_else(|| diag.spans.first());
+    if span.is_none() && is_summary(&diag.message) {
+        return None;
+    }
+    let category = if diag.message.starts_with("linking with") {
+        DiagnosticCategory::Link
+    } else {
+        DiagnosticCategory::Compile
+    };
+
+    let file = span.map(|s| s.file_name.clone()).unwrap_or_default();
+    let line = span.map(|s| s.line_start).unwrap_or(0);
+    let code = diag.code.map(|c| c.code);
+    let rendered = diag.rendered.as_deref().map(strip_ansi).unwrap_or_default();
+    let message = if diag.message.is_empty() {
+        rendered.lines().next().unwrap_or_default().to_string()
+    } else {
+        diag.message
+    };
+
+    let diagnostic_id = {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        file.hash(&mut hasher);
+        line.hash(&mut hasher);
+        message.hash(&mut hasher);
+        format!("{:08x}", hasher.finish() as u32)
+    };
+
+    let suggestion = suggest_fix(&message, code.as_deref()).or_else(|| rustc_help(&diag.children));
+
+    Some(EnhancedDiagnostic {
+        diagnostic_id,
+        severity,
+        category,
+        is_external: Path::new(&file).is_absolute(),
+        file_absolute: file.clone(),
+        file,
+        line,
+        column: span.map(|s| s.column_start),
+        end_line: span.map(|s| s.line_end),
+        end_column: span.map(|s| s.column_end),
+        message,
+        code,
+        suggestion,
+        tool: "rustc".to_string(),
+        raw_line: rendered,
+    })
+}
+
+/// rustc's closing counts, which are not diagnostics of their own
+fn is_summary(message: &str) -> bool {
+    message.starts_with("aborting due to")
+        || message.contains(" generated ")
+        || message.ends_with(" warning emitted")
+        || message.ends_with(" warnings emitted")
+}
+
+/// rustc's own first `help:` child, with its replacement when it has one
+fn rustc_help(children: &[RustcDiagnostic]) -> Option<String> {
+    let help = children.iter().find(|c| c.level == "help")?;
+    match help.spans.iter().find_map(|s| s.suggested_replacement.as_deref()) {
+        Some(replacement) if !replacement.is_empty() => Some(format!("{}: `{}`", help.message, replacement)),
+        _ => Some(help.message.clone()),
+    }
+}
+
+fn strip_ansi(text: &str) -> String {
+    let re = regex::Regex::new(r"\x1b\[[0-9;]*m").unwrap();
+    re.replace_all(text, "").trim_end().to_string()
+}
+
+/// Fixes for common no_std / embedded Rust errors
+fn suggest_fix(message: &str, code: Option<&str>) -> Option<String> {
+    let msg_lower = message.to_lowercase();
+
+    if msg_lower.contains("can't find crate for `core`") || msg_lower.contains("can't find crate for `compiler_builtins`") {
+        return Some("Install the target with `rustup target add <target>`, e.g. thumbv7em-none-eabihf".to_string());
+    }
+    if msg_lower.contains("can't find crate for `std`") {
+        return Some("Add #![no_std] to the crate root and use core/alloc instead of std".to_string());
+    }
+    if msg_lower.contains("#[panic_handler]") {
+        return Some("Add a panic handler crate such as panic-halt or panic-probe, and `use` it".to_string());
+    }
+    if msg_lower.contains("no global memory allocator") {
+        return Some("Declare a #[global_allocator], e.g. embedded-alloc's Heap, and initialize it at startup".to_string());
+    }
+    if msg_lower.contains("requires `start` lang_item") || msg_lower.contains("`main` function not found") {
+        return Some("Add #![no_main] and mark the entry function with #[entry] from cortex-m-rt".to_string());
+    }
+    if msg_lower.contains("memory.x") {
+        return Some("Add a memory.x with FLASH and RAM regions to the crate root and copy it in build.rs".to_string());
+    }
+    if msg_lower.contains("use of mutable static") || code == Some("static_mut_refs") {
+        return Some("Share the static through critical_section::Mutex or an atomic instead of static mut".to_string());
+    }
+    if code == Some("unused_variables") {
+        return Some("Prefix the name with an underscore if it is intentionally unused".to_string());
+    }
+    if code == Some("unused_must_use") {
+        return Some("Handle the Result, or discard it explicitly with `let _ =`".to_string());
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNUSED: &str = r#"{"reason":"compiler-message","package_id":"blinky 0.1.0","manifest_path":"/work/blinky/Cargo.toml","target":{"name":"blinky"},"message":{"rendered":"\u001b[0m\u001b[1m\u001b[33mwarning\u001b[0m: unused variable: `led`\n --> src/main.rs:12:9\n","children":[{"children":[],"code":null,"level":"help","message":"if this is intentional, prefix it with an underscore","rendered":null,"spans":[{"byte_end":210,"byte_start":207,"column_end":12,"column_start":9,"expansion":null,"file_name":"src/main.rs","is_primary":true,"label":null,"line_end":12,"line_start":12,"suggested_replacement":"_led","suggestion_applicability":"MachineApplicable","text":[]}]}],"code":{"code":"unused_variables","explanation":null},"level":"warning","message":"unused variable: `led`","spans":[{"byte_end":210,"byte_start":207,"column_end":12,"column_start":9,"expansion":null,"file_name":"src/main.rs","is_primary":true,"label":null,"line_end":12,"line_start":12,"suggested_replacement":null,"suggestion_applicability":null,"text":[]}]}}"#;
+
+    #[test]
+    fn test_parse_cargo_warning() {
+        let diag = parse_cargo_json_line(UNUSED).unwrap();
+        assert_eq!(diag.severity, DiagnosticSeverity::Warning);
+        assert_eq!(diag.file, "src/main.rs");
+        assert_eq!((diag.line, diag.column, diag.end_column), (12, Some(9), Some(12)));
+        assert_eq!(diag.code.as_deref(), Some("unused_variables"));
+        assert_eq!(diag.message, "unused variable: `led`");
+        assert!(diag.raw_line.starts_with("warning: unused variable"));
+        assert!(diag.suggestion.unwrap().contains("underscore"));
+        assert!(!diag.is_external);
+    }
+
+    #[test]
+    fn test_parse_cargo_error_and_other_lines() {
+        let line = r#"{"reason":"compiler-message","message":{"rendered":"error: `#[panic_handler]` function required, but not found\n\n","children":[],"code":null,"level":"error","message":"`#[panic_handler]` function required, but not found","spans":[]}}"#;
+        let diag = parse_cargo_json_line(line).unwrap();
+        assert_eq!((diag.file.as_str(), diag.line), ("", 0));
+        assert!(diag.suggestion.unwrap().contains("panic-halt"));
+
+        let summary = r#"{"reason":"compiler-message","message":{"rendered":"error: aborting due to 1 previous error\n","children":[],"code":null,"level":"error","message":"aborting due to 1 previous error","spans":[]}}"#;
+        assert!(serde_json::from_str::<serde_json::Value>(summary).is_ok());
+        assert!(parse_cargo_json_line(summary).is_none());
+        for count in ["1 warning emitted", "3 warnings emitted"] {
+            let summary = format!(
+                r#"{{"reason":"compiler-message","message":{{"rendered":"warning: {0}\n\n","children":[],"code":null,"level":"warning","message":"{0}","spans":[]}}}}"#,
+                count,
+            );
+            assert!(serde_json::from_str::<serde_json::Value>(&summary).is_ok(), "{}", count);
+            assert!(parse_cargo_json_line(&summary).is_none(), "{}", count);
+        }
+
+        let link = r#"{"reason":"compiler-message","message":{"rendered":"error: linking with `rust-lld` failed\n","children":[],"code":null,"level":"error","message":"linking with `rust-lld` failed: exit status: 1","spans":[]}}"#;
+        let diag = parse_cargo_json_line(link).unwrap();
+        assert_eq!(diag.severity, DiagnosticSeverity::Error);
+        assert!(matches!(diag.category, DiagnosticCategory::Link));
+        assert_eq!(diag.line, 0);
+
+        assert!(parse_cargo_json_line(r#"{"reason":"compiler-artifact","package_id":"blinky 0.1.0","filenames":[]}"#).is_none());
+        let build_script = r#"{"reason":"build-script-executed","package_id":"cortex-m-rt 0.7.3","linked_libs":[],"linked_paths":["/work/blinky/target/thumbv7em-none-eabihf/release/build/cortex-m-rt-1f2e/out"],"cfgs":["cortex_m"],"env":[],"out_dir":"/work/blinky/target/thumbv7em-none-eabihf/release/build/cortex-m-rt-1f2e/out"}"#;
+        assert!(serde_json::from_str::<serde_json::Value>(build_script).is_ok());
+        assert!(parse_cargo_json_line(build_script).is_none());
+        assert!(parse_cargo_executable(build_script).is_none());
+        assert!(parse_cargo_json_line(r#"{"reason":"build-finished","success":false}"#).is_none());
+        assert!(parse_cargo_json_line("   Compiling blinky v0.1.0").is_none());
+    }
+
+    #[test]
+    fn test_parse_cargo_executable() {
+        let bin = r#"{"reason":"compiler-artifact","package_id":"blinky 0.1.0","filenames":["/work/blinky/target/thumbv7em-none-eabihf/release/blinky"],"executable":"/work/blinky/target/thumbv7em-none-eabihf/release/blinky","fresh":false}"#;
+        assert_eq!(parse_cargo_executable(bin).as_deref(), Some("/work/blinky/target/thumbv7em-none-eabihf/release/blinky"));
+
+        let lib = r#"{"reason":"compiler-artifact","package_id":"cortex-m 0.7.7","filenames":["libcortex_m.rlib"],"executable":null,"fresh":true}"#;
+        assert!(parse_cargo_executable(lib).is_none());
+        assert!(parse_cargo_executable(UNUSED).is_none());
+    }
+
+    #[test]
+    fn test_suggest_fix() {
+        assert!(suggest_fix("can't find crate for `core`", Some("E0463")).unwrap().contains("rustup target add"));
+        assert!(suggest_fix("no global memory allocator found but one is required", None).is_some());
+        assert!(suggest_fix("mismatched types", Some("E0308")).is_none());
+    }
+}
@@ -1,6 +1,8 @@
 // Compiler Output Parser
 // Parse GCC/Clang output into structured diagnostics
 
+pub mod cargo;
+
 use super::{CompilerDiagnostic, DiagnosticSeverity, SizeReport, SectionInfo, SectionType};
 use regex::Regex;
 use std::path::PathBuf;
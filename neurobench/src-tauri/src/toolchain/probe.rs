@@ -3,6 +3,7 @@
 
 use super::ToolchainError;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -36,6 +37,12 @@ pub struct ProbeConfig {
     pub speed_khz: u32,
     pub target: String,
     pub reset_mode: ResetMode,
+    /// Refuse to flash images that would overwrite an nRF52 SoftDevice
+    #[serde(default)]
+    pub protect_softdevice: bool,
+    /// SoftDevice size (MBR included); read from the target when unset
+    #[serde(default)]
+    pub softdevice_size_kb: Option<u32>,
 }
 
 impl Default for ProbeConfig {
@@ -45,6 +52,8 @@ impl Default for ProbeConfig {
             speed_khz: 4000,
             target: "STM32F407VGTx".to_string(),
             reset_mode: ResetMode::HaltAfterReset,
+            protect_softdevice: false,
+            softdevice_size_kb: None,
         }
     }
 }
@@ -137,6 +146,55 @@ pub struct RttMessage {
     pub data: String,
 }
 
+/// Nordic SoftDevice found on the target
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SoftDeviceInfo {
+    /// e.g. "S140"
+    pub name: String,
+    pub id: u32,
+    /// e.g. "7.2.0"
+    pub version: String,
+    pub firmware_id: u16,
+    /// Flash taken by MBR + SoftDevice, i.e. where the application starts
+    pub size_bytes: u32,
+}
+
+/// SoftDevice info struct (after the 4 KB MBR), see `nrf_sdm.h`
+pub const SOFTDEVICE_INFO_ADDRESS: u32 = 0x3000;
+const SOFTDEVICE_INFO_LEN: usize = 0x18;
+const SOFTDEVICE_MAGIC: u32 = 0x51B1_E5DB;
+
+/// Decode the SoftDevice info struct, `None` when the magic number is missing
+pub fn parse_softdevice_info(info: &[u8]) -> Option<SoftDeviceInfo> {
+    let word = |offset: usize| -> Option<u32> {
+        info.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    if word(0x04)? != SOFTDEVICE_MAGIC {
+        return None;
+    }
+    let id = word(0x10)?;
+    let version = word(0x14)?;
+    Some(SoftDeviceInfo {
+        name: format!("S{}", id),
+        id,
+        version: format!("{}.{}.{}", version / 1_000_000, version / 1000 % 1000, version % 1000),
+        firmware_id: word(0x0C)? as u16,
+        size_bytes: word(0x08)?,
+    })
+}
+
+/// Error if any part of the image lies below the end of the SoftDevice
+fn check_softdevice_overlap(load_address: u64, softdevice_size: u64) -> Result<(), ToolchainError> {
+    if load_address < softdevice_size {
+        return Err(ToolchainError::FlashFailed(format!(
+            "Image loads at 0x{:08X}, inside the SoftDevice (0x00000000-0x{:08X}). \
+             Link the application at 0x{:08X} or disable SoftDevice protection.",
+            load_address, softdevice_size, softdevice_size
+        )));
+    }
+    Ok(())
+}
+
 /// Symbolicated backtrace
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SymbolicatedBacktrace {
@@ -170,6 +228,8 @@ pub struct ProbeManager {
     config: Option<ProbeConfig>,
     rtt_active: bool,
     rtt_buffer: Arc<Mutex<Vec<RttMessage>>>,
+    /// Target memory written while running without probe-rs
+    sim_memory: HashMap<u32, u8>,
 }
 
 impl ProbeManager {
@@ -179,6 +239,7 @@ impl ProbeManager {
             config: None,
             rtt_active: false,
             rtt_buffer: Arc::new(Mutex::new(Vec::new())),
+            sim_memory: HashMap::new(),
         }
     }
     
@@ -272,6 +333,23 @@ impl ProbeManager {
             return Err(ToolchainError::ProbeError("Not connected to probe".to_string()));
         }
         
+        if let Some(config) = self.config.as_ref().filter(|c| c.protect_softdevice) {
+            let softdevice_size = match config.softdevice_size_kb {
+                // In u64 so any configured size is representable
+                Some(kb) => u64::from(kb) * 1024,
+                None => self.detect_softdevice().await?
+                    .map(|sd| u64::from(sd.size_bytes))
+                    .ok_or_else(|| ToolchainError::FlashFailed(
+                        "SoftDevice protection is on but no SoftDevice was found; set softdevice_size_kb".to_string()
+                    ))?,
+            };
+            let segments = crate::jobs::flash::elf_load_segments(elf_path)
+                .map_err(|e| ToolchainError::FlashFailed(e.message))?;
+            if let Some(lowest) = segments.iter().map(|s| s.address).min() {
+                check_softdevice_overlap(lowest, softdevice_size)?;
+            }
+        }
+        
         #[cfg(feature = "hardware")]
         {
             use probe_rs::flashing;
@@ -302,6 +380,12 @@ impl ProbeManager {
         }
     }
     
+    /// Read the SoftDevice info struct to find which SoftDevice is installed
+    pub async fn detect_softdevice(&self) -> Result<Option<SoftDeviceInfo>, ToolchainError> {
+        let info = self.read_memory(SOFTDEVICE_INFO_ADDRESS, SOFTDEVICE_INFO_LEN).await?;
+        Ok(parse_softdevice_info(&info))
+    }
+    
     /// Reset the target
     pub async fn reset(&self, mode: ResetMode) -> Result<(), ToolchainError> {
         if !self.connected {
@@ -350,7 +434,23 @@ impl ProbeManager {
         
         // With probe-rs: core.read_8(address, &mut buffer)?
         
-        Ok(vec![0u8; length])
+        Ok((0..length as u32)
+            .map(|offset| self.sim_memory.get(&address.wrapping_add(offset)).copied().unwrap_or(0))
+            .collect())
+    }
+    
+    /// Write memory
+    pub async fn write_memory(&mut self, address: u32, data: &[u8]) -> Result<(), ToolchainError> {
+        if !self.connected {
+            return Err(ToolchainError::ProbeError("Not connected".to_string()));
+        }
+        
+        // With probe-rs: core.write_8(address, data)?
+        
+        for (offset, byte) in data.iter().enumerate() {
+            self.sim_memory.insert(address.wrapping_add(offset as u32), *byte);
+        }
+        Ok(())
     }
     
     /// Read registers
@@ -448,10 +548,20 @@ pub fn decode_hardfault(
     })
 }
 
-/// Symbolicate stack frames using addr2line
+/// Symbolicate stack frames from the ELF's DWARF, falling back to arm-none-eabi-addr2line
 fn symbolicate_frames(frames: &mut [StackFrame], elf_path: &Path) {
     use std::process::Command;
     
+    let addresses: Vec<u32> = frames.iter().map(|f| f.address).collect();
+    if let Ok(symbols) = crate::profiler::symbolize::symbolize_addresses(&addresses, elf_path) {
+        for (frame, symbol) in frames.iter_mut().zip(symbols) {
+            frame.function = (symbol.function != "??").then_some(symbol.function);
+            frame.file = symbol.file;
+            frame.line = symbol.line;
+        }
+        return;
+    }
+    
     // Try arm-none-eabi-addr2line
     for frame in frames.iter_mut() {
         let output = Command::new("arm-none-eabi-addr2line")
@@ -601,4 +711,45 @@ mod tests {
         assert_eq!(bt.frames.len(), 2);
         assert_eq!(bt.frames[0].address, 0x08000000);
     }
+    
+    #[test]
+    fn test_parse_softdevice_info() {
+        let mut info = [0u8; SOFTDEVICE_INFO_LEN];
+        info[0x00] = 0x18;
+        info[0x04..0x08].copy_from_slice(&SOFTDEVICE_MAGIC.to_le_bytes());
+        info[0x08..0x0C].copy_from_slice(&0x27000u32.to_le_bytes());
+        info[0x0C..0x10].copy_from_slice(&0xFFFF_0100u32.to_le_bytes());
+        info[0x10..0x14].copy_from_slice(&140u32.to_le_bytes());
+        info[0x14..0x18].copy_from_slice(&7_002_000u32.to_le_bytes());
+        
+        let sd = parse_softdevice_info(&info).unwrap();
+        assert_eq!(sd.name, "S140");
+        assert_eq!(sd.version, "7.2.0");
+        assert_eq!(sd.firmware_id, 0x0100);
+        assert_eq!(sd.size_bytes, 0x27000);
+        
+        // Erased flash
+        assert!(parse_softdevice_info(&[0xFF; SOFTDEVICE_INFO_LEN]).is_none());
+    }
+    
+    #[test]
+    fn test_softdevice_overlap() {
+        assert!(check_softdevice_overlap(0x27000, 156 * 1024).is_ok());
+        assert!(matches!(check_softdevice_overlap(0x1000, 156 * 1024), Err(ToolchainError::FlashFailed(_))));
+        // A size past 4 GiB covers every 32-bit load address
+        assert!(check_softdevice_overlap(0xFFFF_0000, u64::from(u32::MAX) * 1024).is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_flash_refuses_softdevice_region() {
+        let mut pm = ProbeManager::new();
+        pm.connect(ProbeConfig {
+            protect_softdevice: true,
+            ..ProbeConfig::default()
+        }).await.unwrap();
+        
+        // Nothing installed in simulation and no size given
+        let err = pm.flash(Path::new("missing.elf"), false).await.unwrap_err();
+        assert!(err.to_string().contains("softdevice_size_kb"));
+    }
 }
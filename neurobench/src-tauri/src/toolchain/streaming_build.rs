@@ -3,6 +3,8 @@
 // Event Contract Guarantees:
 // 1. build:started → zero or more updates → exactly ONE terminal event
 // 2. Terminal events: build:completed | build:cancelled | build:internal_error
+//    (build:post_build_complete, when post-build steps ran, precedes build:completed;
+//    build:requeued means a realtime build preempted this one and it will run again)
 // 3. Every event has: protocol_version, build_id, seq (monotonic), timestamp_ms
 // 4. Cancellation kills processes, cleans temp files, prevents race with completion
 //
@@ -12,14 +14,20 @@
 // - Diagnostics: normalized paths, tool name, raw line, stable diagnostic_id
 
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::{broadcast, Mutex, RwLock};
-use tokio::process::Command;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, ChildStderr, ChildStdout, Command};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader, Lines};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, AtomicBool, Ordering};
 use std::hash::{Hash, Hasher};
+pub use tokio_util::sync::CancellationToken;
+use super::ninja::{self, NinjaFile, NINJA_FILE};
+use super::output_parser::cargo;
+use super::signing::{detect_algorithm, sign_artifact, SignError, SignedArtifact};
+use crate::build::lto::{self, LtoType};
+use crate::jobs::{EmitterMessage, JobFanout, JobKind, JobManager};
 
 /// Current event protocol version
 pub const PROTOCOL_VERSION: u32 = 1;
@@ -95,6 +103,17 @@ pub enum BuildEvent {
         files_compiled: usize,
         files_total: usize,
     },
+    /// Post-build steps finished after a successful link
+    PostBuildComplete {
+        #[serde(flatten)]
+        header: EventHeader,
+        success: bool,
+        hex_path: Option<String>,
+        srec_path: Option<String>,
+        map_path: Option<String>,
+        script_exit_code: Option<i32>,
+        artifact_paths: Vec<String>,    // Every file the build produced
+    },
     /// Build completed (terminal - build ran to completion, success can be true/false)
     Completed {
         #[serde(flatten)]
@@ -114,6 +133,12 @@ pub enum BuildEvent {
         terminated_by: TerminatedBy,    // completed | cancelled | killed
         reason: CancelReason,
     },
+    /// Preempted by a realtime build; runs again from the top once the slot frees up
+    Requeued {
+        #[serde(flatten)]
+        header: EventHeader,
+        reason: String,
+    },
     /// Internal error (terminal - NeuroBench couldn't run the build)
     InternalError {
         #[serde(flatten)]
@@ -127,6 +152,47 @@ pub enum BuildEvent {
     },
 }
 
+impl BuildEvent {
+    pub fn header(&self) -> &EventHeader {
+        match self {
+            BuildEvent::Started { header, .. }
+            | BuildEvent::Output { header, .. }
+            | BuildEvent::Diagnostic { header, .. }
+            | BuildEvent::Progress { header, .. }
+            | BuildEvent::PostBuildComplete { header, .. }
+            | BuildEvent::Requeued { header, .. }
+            | BuildEvent::Completed { header, .. }
+            | BuildEvent::Cancelled { header, .. }
+            | BuildEvent::InternalError { header, .. } => header,
+        }
+    }
+    
+    /// Event name suffix, e.g. `output` in `build:output`
+    pub fn event_suffix(&self) -> &'static str {
+        match self {
+            BuildEvent::Started { .. } => "started",
+            BuildEvent::Output { .. } => "output",
+            BuildEvent::Diagnostic { .. } => "diagnostic",
+            BuildEvent::Progress { .. } => "progress",
+            BuildEvent::PostBuildComplete { .. } => "post_build_complete",
+            BuildEvent::Requeued { .. } => "requeued",
+            BuildEvent::Completed { .. } => "completed",
+            BuildEvent::Cancelled { .. } => "cancelled",
+            BuildEvent::InternalError { .. } => "internal_error",
+        }
+    }
+    
+    /// Wrap as a fanout message; the payload keeps `header.build_id` for the build panel
+    pub fn to_emitter_message(&self) -> EmitterMessage {
+        let mut payload = serde_json::to_value(self).unwrap_or_default();
+        payload["header"] = serde_json::to_value(self.header()).unwrap_or_default();
+        EmitterMessage::Custom {
+            event_suffix: self.event_suffix().to_string(),
+            payload,
+        }
+    }
+}
+
 /// Machine-readable internal error codes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -213,12 +279,17 @@ pub struct BuildArtifacts {
     pub elf_path: String,
     pub bin_path: Option<String>,
     pub hex_path: Option<String>,
+    #[serde(default)]
+    pub srec_path: Option<String>,
     pub map_path: Option<String>,
     pub size_report: Option<SizeInfo>,
     // Existence checks for UI to validate before enabling actions
     pub elf_exists: bool,
     pub bin_exists: bool,
     pub map_exists: bool,
+    /// Signed binary, when the build was signed
+    #[serde(default)]
+    pub signed: Option<SignedArtifact>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -246,45 +317,118 @@ pub struct StreamingBuildConfig {
     pub toolchain_id: Option<String>,
     pub toolchain_kind: Option<String>, // "arm_gcc", "clang", "rust"
     pub profile: Option<String>,        // "debug", "release", "minsize"
+    /// Private key to sign the firmware with after a successful link
+    #[serde(default)]
+    pub sign_after_build: Option<PathBuf>,
+    /// Link-time optimization
+    #[serde(default)]
+    pub use_lto: bool,
+    #[serde(default)]
+    pub lto_type: LtoType,
+    /// Parallel LTRANS jobs for the LTO link
+    #[serde(default)]
+    pub lto_job_count: Option<u8>,
+    /// Extra outputs produced after a successful link
+    #[serde(default)]
+    pub post_build: PostBuildConfig,
+    /// How sources are compiled and linked
+    #[serde(default)]
+    pub build_system: BuildSystem,
 }
 
-impl StreamingBuildConfig {
-    /// Generate a hash for cache invalidation
-    pub fn config_hash(&self) -> String {
-        use std::hash::{Hash, Hasher};
-        let mut hasher = std::collections::hash_map::DefaultHasher::new();
-        format!("{:?}", self).hash(&mut hasher);
-        format!("{:016x}", hasher.finish())
-    }
+/// Build backend of a streaming build
+///
+/// Make projects are compiled file by file with gcc; Ninja gets a generated
+/// `build.ninja` and schedules the compiles in parallel itself. Cargo builds the
+/// crate at the project path for the target set in its `.cargo/config.toml`.
+/// CMake is accepted in configs but rejected at build time until it is implemented.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildSystem {
+    #[default]
+    Make,
+    Ninja,
+    CMake,
+    Cargo,
 }
 
-// ==================== Cancellation ====================
-
-/// Cancellation token with atomic state
-#[derive(Debug, Clone)]
-pub struct CancellationToken {
-    cancelled: Arc<AtomicBool>,
+/// Steps run on the linked ELF
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostBuildConfig {
+    /// Intel HEX (`firmware.hex`)
+    pub generate_hex: bool,
+    /// Motorola S-record (`firmware.srec`)
+    pub generate_srec: bool,
+    /// Linker map (`firmware.map`)
+    pub generate_map: bool,
+    /// Shell command run after the build; a non-zero exit fails the build
+    ///
+    /// The ELF path is always in `ELF_PATH`. `sh` also binds it to `$1`; on Windows
+    /// `cmd /C` only passes it on as `%1` when the command is a batch file.
+    pub run_custom_script: Option<String>,
 }
 
-impl CancellationToken {
-    pub fn new() -> Self {
+impl Default for PostBuildConfig {
+    fn default() -> Self {
         Self {
-            cancelled: Arc::new(AtomicBool::new(false)),
+            generate_hex: false,
+            generate_srec: false,
+            generate_map: true,
+            run_custom_script: None,
         }
     }
-    
-    pub fn cancel(&self) {
-        self.cancelled.store(true, Ordering::SeqCst);
-    }
-    
-    pub fn is_cancelled(&self) -> bool {
-        self.cancelled.load(Ordering::SeqCst)
+}
+
+impl PostBuildConfig {
+    /// objcopy output format and file for each requested conversion
+    pub fn objcopy_outputs(&self, build_dir: &std::path::Path) -> Vec<(&'static str, PathBuf)> {
+        let mut outputs = Vec::new();
+        if self.generate_hex {
+            outputs.push(("ihex", build_dir.join("firmware.hex")));
+        }
+        if self.generate_srec {
+            outputs.push(("srec", build_dir.join("firmware.srec")));
+        }
+        outputs
     }
 }
 
-impl Default for CancellationToken {
-    fn default() -> Self {
-        Self::new()
+impl StreamingBuildConfig {
+    /// Generate a hash for cache invalidation
+    pub fn config_hash(&self) -> String {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        format!("{:?}", self).hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
+    /// Flags passed to the compiler for every source file
+    pub fn compile_flags(&self) -> Vec<String> {
+        let mut flags = vec![
+            format!("-mcpu={}", self.mcu_target),
+            "-mthumb".to_string(),
+            format!("-{}", self.optimization),
+            "-g3".to_string(),
+            "-Wall".to_string(),
+            "-Wextra".to_string(),
+            "-ffunction-sections".to_string(),
+            "-fdata-sections".to_string(),
+        ];
+        flags.extend(self.include_paths.iter().map(|inc| format!("-I{}", inc.display())));
+        let mut defines: Vec<_> = self.defines.iter().collect();
+        defines.sort();
+        for (key, value) in defines {
+            if value.is_empty() {
+                flags.push(format!("-D{}", key));
+            } else {
+                flags.push(format!("-D{}={}", key, value));
+            }
+        }
+        if self.use_lto {
+            flags.extend(lto::lto_compile_flags(self.lto_type));
+        }
+        flags
     }
 }
 
@@ -370,6 +514,19 @@ impl BuildJob {
             timestamp_ms: self.elapsed_ms(),
         }
     }
+    
+    /// The same build, stopped by `attempt` instead of the job's own token
+    fn for_attempt(&self, attempt: CancellationToken) -> BuildJob {
+        BuildJob {
+            id: self.id.clone(),
+            config: self.config.clone(),
+            cancel_token: attempt,
+            started_at: self.started_at,
+            seq_counter: self.seq_counter.clone(),
+            log: self.log.clone(),
+            terminal_sent: self.terminal_sent.clone(),
+        }
+    }
 }
 
 // ==================== Artifact Registry ====================
@@ -402,6 +559,17 @@ impl ArtifactRegistry {
     pub fn latest_elf_path(&self) -> Option<&str> {
         self.get_latest().map(|a| a.elf_path.as_str())
     }
+    
+    /// Attach a signed binary to a build's artifacts
+    pub fn set_signed(&mut self, build_id: &str, signed: SignedArtifact) -> bool {
+        match self.artifacts.get_mut(build_id) {
+            Some(artifacts) => {
+                artifacts.signed = Some(signed);
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 // ==================== Build Manager ====================
@@ -412,6 +580,9 @@ pub struct BuildManager {
     completed_logs: Arc<RwLock<HashMap<BuildId, BuildLog>>>,
     artifacts: Arc<RwLock<ArtifactRegistry>>,
     event_tx: broadcast::Sender<BuildEvent>,
+    fanout: Option<JobFanout>,
+    fanout_bridged: AtomicBool,
+    job_manager: Option<Arc<JobManager>>,
 }
 
 impl BuildManager {
@@ -422,6 +593,28 @@ impl BuildManager {
             completed_logs: Arc::new(RwLock::new(HashMap::new())),
             artifacts: Arc::new(RwLock::new(ArtifactRegistry::new())),
             event_tx: tx,
+            fanout: None,
+            fanout_bridged: AtomicBool::new(false),
+            job_manager: None,
+        }
+    }
+    
+    /// Also publish build events to the job fanout, keyed by build_id
+    pub fn with_fanout(fanout: JobFanout) -> Self {
+        Self {
+            fanout: Some(fanout),
+            ..Self::new()
+        }
+    }
+    
+    /// Schedule builds created with `JobManager::create_job(JobKind::Build)`
+    ///
+    /// Such builds wait for the build slot, and a realtime build preempts and
+    /// re-queues a running one of lower priority. Events go to the manager's fanout.
+    pub fn with_job_manager(job_manager: Arc<JobManager>) -> Self {
+        Self {
+            job_manager: Some(job_manager.clone()),
+            ..Self::with_fanout(job_manager.fanout().clone())
         }
     }
     
@@ -430,10 +623,49 @@ impl BuildManager {
         self.event_tx.subscribe()
     }
     
+    /// Relay build events into the fanout (once, on the first build)
+    fn bridge_fanout(&self) {
+        let Some(fanout) = self.fanout.clone() else { return };
+        if self.fanout_bridged.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        
+        let mut rx = self.event_tx.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => fanout.publish(&event.header().build_id, event.to_emitter_message()),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+    
+    /// Allocate an id for a build that has not started yet
+    pub fn new_build_id() -> BuildId {
+        format!("build_{}", uuid::Uuid::new_v4())
+    }
+    
     /// Start a build job
     pub async fn start_build(&self, config: StreamingBuildConfig) -> BuildId {
-        let build_id = format!("build_{}", uuid::Uuid::new_v4());
-        let cancel_token = CancellationToken::new();
+        let build_id = match &self.job_manager {
+            Some(job_manager) => job_manager.create_job(JobKind::Build).0.id.clone(),
+            None => Self::new_build_id(),
+        };
+        self.start_build_with_id(build_id, config).await
+    }
+    
+    /// Start a build job under a pre-allocated id
+    ///
+    /// Lets callers subscribe to the job's fanout channel before `build:started` is sent.
+    /// An id from `JobManager::create_job` makes the build a scheduled job that shares
+    /// its cancellation token; any other id runs straight away.
+    pub async fn start_build_with_id(&self, build_id: BuildId, config: StreamingBuildConfig) -> BuildId {
+        self.bridge_fanout();
+        let record = self.job_manager.as_ref().and_then(|job_manager| job_manager.get_job(&build_id));
+        let job_manager = record.as_ref().and(self.job_manager.clone());
+        let cancel_token = record.map_or_else(CancellationToken::new, |record| record.cancel_token.clone());
         
         let job = Arc::new(BuildJob {
             id: build_id.clone(),
@@ -474,7 +706,14 @@ impl BuildManager {
         let event_tx = self.event_tx.clone();
         
         tokio::spawn(async move {
-            run_build(job.clone(), event_tx, jobs, completed_logs, artifacts).await;
+            match job_manager {
+                Some(job_manager) => run_scheduled_build(job, event_tx, jobs, completed_logs, artifacts, job_manager).await,
+                None => {
+                    if run_build(job.clone(), event_tx.clone(), jobs.clone(), completed_logs.clone(), artifacts).await.is_err() {
+                        finish_cancelled(&job, &event_tx, &jobs, &completed_logs, CancelReason::UserRequest).await;
+                    }
+                }
+            }
         });
         
         build_id
@@ -564,6 +803,16 @@ impl BuildManager {
     pub async fn get_artifacts(&self, build_id: &str) -> Option<BuildArtifacts> {
         self.artifacts.read().await.get(build_id).cloned()
     }
+    
+    /// Sign a finished build's ELF and record the result in its artifacts
+    pub async fn sign_artifacts(&self, build_id: &str, key_path: PathBuf) -> Result<SignedArtifact, String> {
+        let elf_path = self.get_artifacts(build_id).await
+            .map(|a| PathBuf::from(a.elf_path))
+            .ok_or_else(|| format!("No artifacts for build {}", build_id))?;
+        let signed = sign_elf(elf_path, key_path).await.map_err(|e| e.to_string())?;
+        self.artifacts.write().await.set_signed(build_id, signed.clone());
+        Ok(signed)
+    }
 }
 
 impl Default for BuildManager {
@@ -574,13 +823,59 @@ impl Default for BuildManager {
 
 // ==================== Build Execution ====================
 
-async fn run_build(
+/// Run a build whenever it holds the build slot, re-queueing it each time it is preempted
+///
+/// A preempted build restarts from the top rather than resuming. That is safe
+/// because every step rewrites the same outputs from the same inputs: ninja
+/// skips objects that are already up to date, objcopy and signing overwrite
+/// their files, and only the final attempt reports artifacts. A post-build
+/// script runs again on restart, so it must be idempotent too.
+async fn run_scheduled_build(
     job: Arc<BuildJob>,
     event_tx: broadcast::Sender<BuildEvent>,
     jobs: Arc<Mutex<HashMap<BuildId, Arc<BuildJob>>>>,
     completed_logs: Arc<RwLock<HashMap<BuildId, BuildLog>>>,
     artifacts: Arc<RwLock<ArtifactRegistry>>,
+    job_manager: Arc<JobManager>,
 ) {
+    let scheduler = job_manager.scheduler();
+    loop {
+        let Some(attempt) = scheduler.acquire(&job.id, JobKind::Build, &job.cancel_token).await else {
+            finish_cancelled(&job, &event_tx, &jobs, &completed_logs, CancelReason::UserRequest).await;
+            break;
+        };
+        
+        let attempt_job = Arc::new(job.for_attempt(attempt));
+        let stopped = run_build(attempt_job, event_tx.clone(), jobs.clone(), completed_logs.clone(), artifacts.clone()).await.is_err();
+        let preempted = scheduler.release(&job.id);
+        if stopped && preempted && !job.cancel_token.is_cancelled() {
+            // The rerun reports its own diagnostics
+            job.log.lock().await.diagnostics.clear();
+            let _ = event_tx.send(BuildEvent::Requeued {
+                header: job.make_header(),
+                reason: "preempted".to_string(),
+            });
+            continue;
+        }
+        if stopped {
+            finish_cancelled(&job, &event_tx, &jobs, &completed_logs, CancelReason::UserRequest).await;
+        }
+        break;
+    }
+    job_manager.finish_job(&job.id).await;
+}
+
+/// Run one attempt at a build
+///
+/// Returns `BuildStop::Cancelled` without a terminal event if the attempt was
+/// cancelled, so the caller can report it or run the build again.
+async fn run_build(
+    job: Arc<BuildJob>,
+    event_tx: broadcast::Sender<BuildEvent>,
+    jobs: Arc<Mutex<HashMap<BuildId, Arc<BuildJob>>>>,
+    completed_logs: Arc<RwLock<HashMap<BuildId, BuildLog>>>,
+    artifacts: Arc<RwLock<ArtifactRegistry>>,
+) -> Result<(), BuildStop> {
     let start = std::time::Instant::now();
     let config = &job.config;
     let project_path = config.project_path.clone();
@@ -597,19 +892,442 @@ async fn run_build(
         .unwrap_or_else(|| project_path.join("build"));
     let _ = tokio::fs::create_dir_all(&build_dir).await;
     
+    let source_count = config.source_files.len();
+    let elf_path = build_dir.join("firmware.elf");
+    let map_path = build_dir.join("firmware.map");
+    
+    let linked = match config.build_system {
+        BuildSystem::Ninja => run_ninja(&job, &event_tx, &gcc, &build_dir).await,
+        BuildSystem::Make => compile_and_link(&job, &event_tx, &gcc, &build_dir, &elf_path, &map_path).await,
+        BuildSystem::Cargo => run_cargo(&job, &event_tx, &elf_path).await,
+        BuildSystem::CMake => {
+            emit_output(&job, &event_tx, "CMake builds are not supported yet; use Make or Ninja", OutputStream::Stderr, Some("build")).await;
+            Err(BuildStop::Failed)
+        }
+    };
+    let link_success = match linked {
+        Ok(success) => success,
+        Err(BuildStop::Cancelled) => return Err(BuildStop::Cancelled),
+        Err(BuildStop::Failed) => {
+            finish_completed(&job, &event_tx, &jobs, &completed_logs, &artifacts, false, None, start, None).await;
+            return Ok(());
+        }
+    };
+    
+    // Post-processing: generate binary
+    emit_progress(&job, &event_tx, BuildPhase::PostProcessing, 95, "Generating binary...", source_count, source_count);
+    
+    let bin_path = build_dir.join("firmware.bin");
+    let objcopy = which::which("arm-none-eabi-objcopy")
+        .unwrap_or_else(|_| PathBuf::from("arm-none-eabi-objcopy"));
+    
+    let bin_success = if link_success && elf_path.exists() {
+        unless_cancelled(&job, run_objcopy(&objcopy, "binary", &elf_path, &bin_path)).await?
+    } else {
+        false
+    };
+    
+    // Build artifacts
+    let elf_exists = elf_path.exists();
+    let bin_exists = bin_success && bin_path.exists();
+    let map_exists = config.post_build.generate_map && map_path.exists();
+    
+    let mut post_build_ok = true;
+    let build_artifacts = if link_success && elf_exists {
+        let post_build = run_post_build(&job, &event_tx, &objcopy, &elf_path, &build_dir).await?;
+        post_build_ok = post_build.success;
+        
+        let mut artifact_paths = vec![elf_path.display().to_string()];
+        artifact_paths.extend(bin_exists.then(|| bin_path.display().to_string()));
+        artifact_paths.extend(post_build.hex_path.clone());
+        artifact_paths.extend(post_build.srec_path.clone());
+        artifact_paths.extend(map_exists.then(|| map_path.display().to_string()));
+        let _ = event_tx.send(BuildEvent::PostBuildComplete {
+            header: job.make_header(),
+            success: post_build.success,
+            hex_path: post_build.hex_path.clone(),
+            srec_path: post_build.srec_path.clone(),
+            map_path: map_exists.then(|| map_path.display().to_string()),
+            script_exit_code: post_build.script_exit_code,
+            artifact_paths,
+        });
+        
+        Some(BuildArtifacts {
+            elf_path: elf_path.display().to_string(),
+            bin_path: if bin_exists { Some(bin_path.display().to_string()) } else { None },
+            hex_path: post_build.hex_path,
+            srec_path: post_build.srec_path,
+            map_path: map_exists.then(|| map_path.display().to_string()),
+            size_report: get_size_report(&elf_path).await,
+            elf_exists,
+            bin_exists,
+            map_exists,
+            signed: None,
+        })
+    } else {
+        None
+    };
+    
+    // Sign when requested; a build that should be signed but is not counts as failed
+    let mut build_artifacts = build_artifacts;
+    let mut signed_ok = true;
+    if let (Some(key_path), Some(built)) = (config.sign_after_build.clone(), build_artifacts.as_mut()) {
+        emit_progress(&job, &event_tx, BuildPhase::PostProcessing, 98, "Signing firmware...", source_count, source_count);
+        match sign_elf(elf_path.clone(), key_path).await {
+            Ok(signed) => {
+                emit_output(&job, &event_tx, &format!("Signed {} ({:?})", signed.binary_path, signed.algorithm), OutputStream::Stdout, Some("sign")).await;
+                built.signed = Some(signed);
+            }
+            Err(e) => {
+                emit_output(&job, &event_tx, &format!("Signing failed: {}", e), OutputStream::Stderr, Some("sign")).await;
+                signed_ok = false;
+            }
+        }
+    }
+    
+    // Keep the IDE's compilation database in step with what was just built
+    if link_success && elf_exists {
+        let commands = crate::build::compile_db::streaming_compile_commands(config);
+        if let Err(e) = crate::build::compile_db::write_compile_commands(&project_path, &commands) {
+            log::warn!("Failed to write compile_commands.json: {}", e);
+        }
+    }
+    
+    finish_completed(&job, &event_tx, &jobs, &completed_logs, &artifacts, link_success && elf_exists && post_build_ok && signed_ok, None, start, build_artifacts).await;
+    Ok(())
+}
+
+/// What the post-build steps produced
+struct PostBuildOutputs {
+    success: bool,
+    hex_path: Option<String>,
+    srec_path: Option<String>,
+    script_exit_code: Option<i32>,
+}
+
+/// Convert the ELF to the requested formats, then run the custom script
+///
+/// A cancelled build kills whichever step is running and reports
+/// `BuildStop::Cancelled`, so no post-build event follows the terminal one.
+async fn run_post_build(
+    job: &BuildJob,
+    tx: &broadcast::Sender<BuildEvent>,
+    objcopy: &PathBuf,
+    elf_path: &PathBuf,
+    build_dir: &std::path::Path,
+) -> Result<PostBuildOutputs, BuildStop> {
+    let post_build = &job.config.post_build;
+    let mut outputs = PostBuildOutputs { success: true, hex_path: None, srec_path: None, script_exit_code: None };
+    
+    for (format, path) in post_build.objcopy_outputs(build_dir) {
+        if !unless_cancelled(job, run_objcopy(objcopy, format, elf_path, &path)).await? || !path.exists() {
+            emit_output(job, tx, &format!("objcopy -O {} failed", format), OutputStream::Stderr, Some("objcopy")).await;
+            outputs.success = false;
+            continue;
+        }
+        let path = Some(path.display().to_string());
+        match format {
+            "ihex" => outputs.hex_path = path,
+            _ => outputs.srec_path = path,
+        }
+    }
+    
+    if let Some(script) = &post_build.run_custom_script {
+        // sh -c '<script>' sh <elf> makes the ELF path $1; cmd has no equivalent, so
+        // ELF_PATH carries it on every platform
+        let mut cmd = if cfg!(windows) {
+            let mut cmd = Command::new("cmd");
+            cmd.arg("/C").arg(script);
+            cmd
+        } else {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c").arg(script).arg("sh");
+            cmd
+        };
+        cmd.arg(elf_path)
+            .env("ELF_PATH", elf_path)
+            .current_dir(&job.config.project_path)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true);
+        
+        match unless_cancelled(job, cmd.output()).await? {
+            Ok(output) => {
+                for line in String::from_utf8_lossy(&output.stdout).lines() {
+                    emit_output(job, tx, line, OutputStream::Stdout, Some("post_build")).await;
+                }
+                for line in String::from_utf8_lossy(&output.stderr).lines() {
+                    emit_output(job, tx, line, OutputStream::Stderr, Some("post_build")).await;
+                }
+                outputs.script_exit_code = output.status.code();
+                outputs.success &= output.status.success();
+            }
+            Err(e) => {
+                emit_output(job, tx, &format!("Failed to run post-build script: {}", e), OutputStream::Stderr, Some("post_build")).await;
+                outputs.success = false;
+            }
+        }
+    }
+    
+    Ok(outputs)
+}
+
+/// Run `future` unless the build is cancelled first
+///
+/// Dropping the future kills its child, since every command here is spawned
+/// with `kill_on_drop`.
+async fn unless_cancelled<T>(job: &BuildJob, future: impl std::future::Future<Output = T>) -> Result<T, BuildStop> {
+    tokio::select! {
+        _ = job.cancel_token.cancelled() => Err(BuildStop::Cancelled),
+        output = future => Ok(output),
+    }
+}
+
+/// `objcopy -O <format> <elf> <output>`
+async fn run_objcopy(objcopy: &PathBuf, format: &str, elf_path: &PathBuf, output: &PathBuf) -> bool {
+    Command::new(objcopy)
+        .arg("-O").arg(format)
+        .arg(elf_path)
+        .arg(output)
+        .kill_on_drop(true)
+        .status()
+        .await
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+/// Generate `build.ninja` and run Ninja, turning its `[n/total]` status lines into progress
+async fn run_ninja(
+    job: &BuildJob,
+    event_tx: &broadcast::Sender<BuildEvent>,
+    gcc: &Path,
+    build_dir: &Path,
+) -> Result<bool, BuildStop> {
+    let config = &job.config;
+    let ninja_file = NinjaFile::for_streaming_config(config, gcc);
+    if let Err(e) = tokio::fs::write(build_dir.join(NINJA_FILE), ninja_file.render()).await {
+        emit_output(job, event_tx, &format!("Failed to write {}: {}", NINJA_FILE, e), OutputStream::Stderr, Some("ninja")).await;
+        return Err(BuildStop::Failed);
+    }
+    
+    // Build the objects first so an LTO link never silently skips plain ones
+    if config.use_lto {
+        let objects: Vec<String> = ninja_file.sources.iter().map(|src| ninja_file.object_path(src)).collect();
+        if !run_ninja_targets(job, event_tx, build_dir, &objects).await? {
+            return Err(BuildStop::Failed);
+        }
+        let object_files: Vec<PathBuf> = objects.iter().map(|obj| build_dir.join(obj)).collect();
+        if let Err(e) = lto::verify_lto_objects(&object_files) {
+            emit_output(job, event_tx, &e, OutputStream::Stderr, Some("ld")).await;
+            return Err(BuildStop::Failed);
+        }
+    }
+    
+    run_ninja_targets(job, event_tx, build_dir, &[]).await
+}
+
+/// Run `ninja -C <build_dir> [targets]`, returning whether it succeeded
+async fn run_ninja_targets(
+    job: &BuildJob,
+    event_tx: &broadcast::Sender<BuildEvent>,
+    build_dir: &Path,
+    targets: &[String],
+) -> Result<bool, BuildStop> {
+    let ninja = which::which("ninja").unwrap_or_else(|_| PathBuf::from("ninja"));
+    let mut cmd = Command::new(ninja);
+    cmd.arg("-C").arg(build_dir).args(targets);
+    cmd.stdout(std::process::Stdio::piped());
+    cmd.stderr(std::process::Stdio::piped());
+    cmd.kill_on_drop(true);
+    
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            emit_output(job, event_tx, &format!("Failed to spawn ninja: {}", e), OutputStream::Stderr, Some("ninja")).await;
+            return Err(BuildStop::Failed);
+        }
+    };
+    
+    let mut lines = ChildLines::new(&mut child);
+    loop {
+        tokio::select! {
+            _ = job.cancel_token.cancelled() => {
+                let _ = child.kill().await;
+                return Err(BuildStop::Cancelled);
+            }
+            line = lines.next() => match line {
+                // Ninja prints its status lines and the compilers' output on stdout
+                Some((OutputStream::Stdout, line)) => emit_ninja_line(job, event_tx, &line).await,
+                Some((stream, line)) => emit_output(job, event_tx, &line, stream, Some("ninja")).await,
+                None => break,
+            },
+        }
+    }
+    
+    wait_for_child(job, &mut child).await
+}
+
+/// `cargo build` with JSON messages, copying the built binary to `elf_path`
+async fn run_cargo(
+    job: &BuildJob,
+    event_tx: &broadcast::Sender<BuildEvent>,
+    elf_path: &Path,
+) -> Result<bool, BuildStop> {
+    let config = &job.config;
+    let cargo = which::which("cargo").unwrap_or_else(|_| PathBuf::from("cargo"));
+    let mut cmd = Command::new(cargo);
+    cmd.arg("build").arg("--message-format=json-diagnostic-rendered-ansi");
+    if config.profile.as_deref() != Some("debug") {
+        cmd.arg("--release");
+    }
+    cmd.current_dir(&config.project_path);
+    cmd.stdout(std::process::Stdio::piped());
+    cmd.stderr(std::process::Stdio::piped());
+    cmd.kill_on_drop(true);
+    
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            emit_output(job, event_tx, &format!("Failed to spawn cargo: {}", e), OutputStream::Stderr, Some("cargo")).await;
+            return Err(BuildStop::Failed);
+        }
+    };
+    
+    emit_progress(job, event_tx, BuildPhase::Compiling, 10, "cargo build", 0, 0);
+    let mut executable = None;
+    let mut lines = ChildLines::new(&mut child);
+    loop {
+        tokio::select! {
+            _ = job.cancel_token.cancelled() => {
+                let _ = child.kill().await;
+                return Err(BuildStop::Cancelled);
+            }
+            line = lines.next() => match line {
+                // stdout is one JSON message per line; only diagnostics and binaries matter
+                Some((OutputStream::Stdout, line)) => {
+                    if let Some(diag) = cargo::parse_cargo_json_line(&line) {
+                        for rendered in diag.raw_line.lines() {
+                            emit_output(job, event_tx, rendered, OutputStream::Stdout, Some("rustc")).await;
+                        }
+                        emit_diagnostic(job, event_tx, diag).await;
+                    } else if let Some(path) = cargo::parse_cargo_executable(&line) {
+                        executable = Some(PathBuf::from(path));
+                    }
+                }
+                // "Compiling ...", "Finished ..." status lines
+                Some((stream, line)) => emit_output(job, event_tx, &line, stream, Some("cargo")).await,
+                None => break,
+            },
+        }
+    }
+    
+    if !wait_for_child(job, &mut child).await? {
+        return Ok(false);
+    }
+    let Some(executable) = executable else {
+        emit_output(job, event_tx, "cargo built no binary; is this a bin crate?", OutputStream::Stderr, Some("cargo")).await;
+        return Ok(false);
+    };
+    if let Err(e) = tokio::fs::copy(&executable, elf_path).await {
+        emit_output(job, event_tx, &format!("Failed to copy {}: {}", executable.display(), e), OutputStream::Stderr, Some("cargo")).await;
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// A child's stdout and stderr, read together so neither pipe can fill up and stall it
+struct ChildLines {
+    stdout: Option<Lines<BufReader<ChildStdout>>>,
+    stderr: Option<Lines<BufReader<ChildStderr>>>,
+}
+
+impl ChildLines {
+    fn new(child: &mut Child) -> Self {
+        Self {
+            stdout: child.stdout.take().map(|out| BufReader::new(out).lines()),
+            stderr: child.stderr.take().map(|err| BufReader::new(err).lines()),
+        }
+    }
+    
+    /// Next line from either pipe, `None` once both have closed
+    async fn next(&mut self) -> Option<(OutputStream, String)> {
+        while self.stdout.is_some() || self.stderr.is_some() {
+            tokio::select! {
+                line = next_line(&mut self.stdout) => match line {
+                    Some(line) => return Some((OutputStream::Stdout, line)),
+                    None => self.stdout = None,
+                },
+                line = next_line(&mut self.stderr) => match line {
+                    Some(line) => return Some((OutputStream::Stderr, line)),
+                    None => self.stderr = None,
+                },
+            }
+        }
+        None
+    }
+}
+
+/// Wait for a child whose pipes have closed, killing it if the build is cancelled first
+async fn wait_for_child(job: &BuildJob, child: &mut Child) -> Result<bool, BuildStop> {
+    tokio::select! {
+        _ = job.cancel_token.cancelled() => {
+            let _ = child.kill().await;
+            Err(BuildStop::Cancelled)
+        }
+        status = child.wait() => Ok(status.map(|s| s.success()).unwrap_or(false)),
+    }
+}
+
+/// Next line of a pipe, or pending forever once the pipe has closed
+async fn next_line<R: AsyncBufRead + Unpin>(lines: &mut Option<Lines<R>>) -> Option<String> {
+    match lines {
+        Some(lines) => lines.next_line().await.ok().flatten(),
+        None => std::future::pending().await,
+    }
+}
+
+/// Forward one line of ninja's stdout as progress, output and diagnostics
+async fn emit_ninja_line(job: &BuildJob, event_tx: &broadcast::Sender<BuildEvent>, line: &str) {
+    if let Some(status) = ninja::parse_status_line(line) {
+        let phase = if status.description.starts_with("LINK") { BuildPhase::Linking } else { BuildPhase::Compiling };
+        let percent = (status.finished * 90 / status.total.max(1)) as u8;
+        emit_progress(job, event_tx, phase, percent, status.description, status.finished, status.total);
+    }
+    emit_output(job, event_tx, line, OutputStream::Stdout, Some("ninja")).await;
+    if let Some(diag) = parse_gcc_diagnostic(line, &job.config.project_path) {
+        emit_diagnostic(job, event_tx, diag).await;
+    }
+}
+
+/// Why a build stopped before linking finished
+enum BuildStop {
+    Cancelled,
+    Failed,
+}
+
+/// Compile each source with gcc and link them, returning whether the link succeeded
+async fn compile_and_link(
+    job: &BuildJob,
+    event_tx: &broadcast::Sender<BuildEvent>,
+    gcc: &Path,
+    build_dir: &Path,
+    elf_path: &Path,
+    map_path: &Path,
+) -> Result<bool, BuildStop> {
     // Compile each source file
+    let config = &job.config;
+    let project_path = &config.project_path;
     let source_count = config.source_files.len();
     let mut object_files = Vec::new();
     
     for (idx, source) in config.source_files.iter().enumerate() {
         // Check cancellation
         if job.cancel_token.is_cancelled() {
-            finish_cancelled(&job, &event_tx, &jobs, &completed_logs, CancelReason::UserRequest).await;
-            return;
+            return Err(BuildStop::Cancelled);
         }
         
         let percent = ((idx as f32 / source_count as f32) * 70.0) as u8;
-        emit_progress(&job, &event_tx, BuildPhase::Compiling, percent,
+        emit_progress(job, event_tx, BuildPhase::Compiling, percent,
             &format!("Compiling {}", source.file_name().unwrap_or_default().to_string_lossy()),
             idx, source_count);
         
@@ -617,31 +1335,12 @@ async fn run_build(
         let obj_path = build_dir.join(format!("{}.o", obj_name));
         
         // Build compile command
-        let mut cmd = Command::new(&gcc);
+        let mut cmd = Command::new(gcc);
         cmd.arg("-c")
            .arg(source)
            .arg("-o")
            .arg(&obj_path)
-           .arg(format!("-mcpu={}", config.mcu_target))
-           .arg("-mthumb")
-           .arg(format!("-{}", config.optimization))
-           .arg("-g3")
-           .arg("-Wall")
-           .arg("-Wextra")
-           .arg("-ffunction-sections")
-           .arg("-fdata-sections");
-        
-        for inc in &config.include_paths {
-            cmd.arg("-I").arg(inc);
-        }
-        
-        for (key, value) in &config.defines {
-            if value.is_empty() {
-                cmd.arg(format!("-D{}", key));
-            } else {
-                cmd.arg(format!("-D{}={}", key, value));
-            }
-        }
+           .args(config.compile_flags());
         
         cmd.stdout(std::process::Stdio::piped());
         cmd.stderr(std::process::Stdio::piped());
@@ -654,10 +1353,10 @@ async fn run_build(
                     let mut lines = reader.lines();
                     
                     while let Ok(Some(line)) = lines.next_line().await {
-                        emit_output(&job, &event_tx, &line, OutputStream::Stderr, Some("gcc")).await;
+                        emit_output(job, event_tx, &line, OutputStream::Stderr, Some("gcc")).await;
                         
-                        if let Some(diag) = parse_gcc_diagnostic(&line, &project_path) {
-                            emit_diagnostic(&job, &event_tx, diag).await;
+                        if let Some(diag) = parse_gcc_diagnostic(&line, project_path) {
+                            emit_diagnostic(job, event_tx, diag).await;
                         }
                     }
                 }
@@ -668,7 +1367,7 @@ async fn run_build(
                 }
             }
             Err(e) => {
-                emit_output(&job, &event_tx, &format!("Failed to spawn compiler: {}", e), OutputStream::Stderr, Some("build")).await;
+                emit_output(job, event_tx, &format!("Failed to spawn compiler: {}", e), OutputStream::Stderr, Some("build")).await;
             }
         }
     }
@@ -676,102 +1375,83 @@ async fn run_build(
     // Check for errors
     let error_count = job.log.lock().await.error_count();
     if error_count > 0 {
-        finish_completed(&job, &event_tx, &jobs, &completed_logs, &artifacts, false, None, start, None).await;
-        return;
+        return Err(BuildStop::Failed);
     }
     
     // Check cancellation before linking
     if job.cancel_token.is_cancelled() {
-        finish_cancelled(&job, &event_tx, &jobs, &completed_logs, CancelReason::UserRequest).await;
-        return;
+        return Err(BuildStop::Cancelled);
     }
     
+    // An LTO link with plain objects mixed in would silently skip them
+    if config.use_lto {
+        if let Err(e) = lto::verify_lto_objects(&object_files) {
+            emit_output(job, event_tx, &e, OutputStream::Stderr, Some("ld")).await;
+            return Err(BuildStop::Failed);
+        }
+    }
+
     // Link
-    emit_progress(&job, &event_tx, BuildPhase::Linking, 80, "Linking...", source_count, source_count);
+    emit_progress(job, event_tx, BuildPhase::Linking, 80, "Linking...", source_count, source_count);
     
-    let elf_path = build_dir.join("firmware.elf");
-    let mut link_cmd = Command::new(&gcc);
+    let mut link_cmd = Command::new(gcc);
     
     link_cmd
         .arg(format!("-mcpu={}", config.mcu_target))
         .arg("-mthumb")
-        .arg("-Wl,--gc-sections")
-        .arg("-Wl,-Map").arg(build_dir.join("firmware.map"))
+        .arg("-Wl,--gc-sections");
+    if config.post_build.generate_map {
+        link_cmd.arg("-Wl,-Map").arg(map_path);
+    }
+    link_cmd
         .arg("--specs=nosys.specs")
         .arg("--specs=nano.specs");
     
     if let Some(ref ld) = config.linker_script {
         link_cmd.arg("-T").arg(ld);
     }
+
+    // LTO re-optimizes at link time, so it needs the optimization level too
+    if config.use_lto {
+        link_cmd.arg(format!("-{}", config.optimization));
+        link_cmd.args(lto::lto_link_flags(config.lto_type, config.lto_job_count));
+    }
     
     for obj in &object_files {
         link_cmd.arg(obj);
     }
     
-    link_cmd.arg("-o").arg(&elf_path);
+    link_cmd.arg("-o").arg(elf_path);
     link_cmd.stdout(std::process::Stdio::piped());
     link_cmd.stderr(std::process::Stdio::piped());
     link_cmd.kill_on_drop(true);
     
-    let link_success = match link_cmd.spawn() {
+    Ok(match link_cmd.spawn() {
         Ok(mut child) => {
             if let Some(stderr) = child.stderr.take() {
                 let reader = BufReader::new(stderr);
                 let mut lines = reader.lines();
                 while let Ok(Some(line)) = lines.next_line().await {
-                    emit_output(&job, &event_tx, &line, OutputStream::Stderr, Some("ld")).await;
+                    emit_output(job, event_tx, &line, OutputStream::Stderr, Some("ld")).await;
                 }
             }
             child.wait().await.map(|s| s.success()).unwrap_or(false)
         }
         Err(e) => {
-            emit_output(&job, &event_tx, &format!("Linker error: {}", e), OutputStream::Stderr, Some("ld")).await;
+            emit_output(job, event_tx, &format!("Linker error: {}", e), OutputStream::Stderr, Some("ld")).await;
             false
         }
-    };
-    
-    // Post-processing: generate binary
-    emit_progress(&job, &event_tx, BuildPhase::PostProcessing, 95, "Generating binary...", source_count, source_count);
-    
-    let bin_path = build_dir.join("firmware.bin");
-    let objcopy = which::which("arm-none-eabi-objcopy")
-        .unwrap_or_else(|_| PathBuf::from("arm-none-eabi-objcopy"));
-    
-    let bin_success = if link_success && elf_path.exists() {
-        Command::new(&objcopy)
-            .arg("-O").arg("binary")
-            .arg(&elf_path)
-            .arg(&bin_path)
-            .kill_on_drop(true)
-            .status()
-            .await
-            .map(|s| s.success())
-            .unwrap_or(false)
-    } else {
-        false
-    };
-    
-    // Build artifacts
-    let elf_exists = elf_path.exists();
-    let bin_exists = bin_success && bin_path.exists();
-    let map_exists = build_dir.join("firmware.map").exists();
-    
-    let build_artifacts = if link_success && elf_exists {
-        Some(BuildArtifacts {
-            elf_path: elf_path.display().to_string(),
-            bin_path: if bin_exists { Some(bin_path.display().to_string()) } else { None },
-            hex_path: None,
-            map_path: Some(build_dir.join("firmware.map").display().to_string()),
-            size_report: get_size_report(&elf_path).await,
-            elf_exists,
-            bin_exists,
-            map_exists,
-        })
-    } else {
-        None
-    };
-    
-    finish_completed(&job, &event_tx, &jobs, &completed_logs, &artifacts, link_success && elf_exists, None, start, build_artifacts).await;
+    })
+}
+
+/// Sign an ELF with the algorithm of its key, off the async runtime
+async fn sign_elf(elf_path: PathBuf, key_path: PathBuf) -> Result<SignedArtifact, SignError> {
+    tokio::task::spawn_blocking(move || {
+        let algorithm = detect_algorithm(&key_path)?;
+        sign_artifact(&elf_path, &key_path, algorithm)
+    })
+    .await
+    .map_err(|e| SignError::IoError(std::io::Error::other(e)))?
 }
 
 // ==================== Helper Functions ====================
@@ -984,6 +1664,7 @@ fn suggest_fix(message: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::jobs::JobPriority;
     
     #[test]
     fn test_parse_gcc_diagnostic() {
@@ -999,6 +1680,170 @@ mod tests {
         assert!(diag.suggestion.is_some());
     }
     
+    #[test]
+    fn test_post_build_config() {
+        let config: PostBuildConfig = serde_json::from_value(serde_json::json!({ "generate_srec": true })).unwrap();
+        assert!(config.generate_map);
+        assert!(config.run_custom_script.is_none());
+        
+        let build_dir = PathBuf::from("/project/build");
+        assert_eq!(config.objcopy_outputs(&build_dir), vec![("srec", build_dir.join("firmware.srec"))]);
+        
+        let both = PostBuildConfig { generate_hex: true, ..config };
+        let formats: Vec<&str> = both.objcopy_outputs(&build_dir).into_iter().map(|(f, _)| f).collect();
+        assert_eq!(formats, vec!["ihex", "srec"]);
+        assert!(PostBuildConfig::default().objcopy_outputs(&build_dir).is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_forwarded_build_event_keeps_build_id() {
+        let fanout = JobFanout::default();
+        let build_id = BuildManager::new_build_id();
+        let header = EventHeader {
+            protocol_version: PROTOCOL_VERSION,
+            build_id: build_id.clone(),
+            seq: 0,
+            timestamp_ms: 0,
+        };
+        
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let forwarder = fanout.spawn_forwarder(&build_id, "build", move |name, payload| {
+            sink.lock().unwrap().push((name, payload));
+        }).unwrap();
+        
+        fanout.publish(&build_id, BuildEvent::Output {
+            header: header.clone(),
+            line: "arm-none-eabi-gcc -c main.c".to_string(),
+            stream: OutputStream::Stdout,
+            tool: None,
+        }.to_emitter_message());
+        fanout.publish(&build_id, BuildEvent::Cancelled {
+            header,
+            terminated_by: TerminatedBy::Cancelled,
+            reason: CancelReason::UserRequest,
+        }.to_emitter_message());
+        forwarder.await.unwrap();
+        
+        let events = events.lock().unwrap();
+        assert_eq!(events[0].0, "build:output");
+        assert_eq!(events[0].1["header"]["build_id"], build_id.as_str());
+        assert_eq!(events[0].1["line"], "arm-none-eabi-gcc -c main.c");
+        assert_eq!(events[1].0, "build:cancelled");
+        assert_eq!(events[1].1["header"]["build_id"], build_id.as_str());
+    }
+    
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_post_build_script_is_killed_on_cancel() {
+        let dir = tempfile::tempdir().unwrap();
+        let config: StreamingBuildConfig = serde_json::from_value(serde_json::json!({
+            "project_path": dir.path(),
+            "mcu_target": "STM32F4",
+            "optimization": "Os",
+            "defines": {},
+            "include_paths": [],
+            "source_files": [],
+            "post_build": { "run_custom_script": "sleep 30" },
+        })).unwrap();
+        let job = BuildJob {
+            id: BuildManager::new_build_id(),
+            config,
+            cancel_token: CancellationToken::new(),
+            started_at: std::time::Instant::now(),
+            seq_counter: Arc::new(AtomicU64::new(0)),
+            log: Arc::new(Mutex::new(BuildLog::new(100))),
+            terminal_sent: Arc::new(AtomicBool::new(false)),
+        };
+        let token = job.cancel_token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            token.cancel();
+        });
+        
+        let (tx, _rx) = broadcast::channel(16);
+        let elf = dir.path().join("firmware.elf");
+        let started = std::time::Instant::now();
+        let result = run_post_build(&job, &tx, &PathBuf::from("objcopy"), &elf, dir.path()).await;
+        assert!(matches!(result, Err(BuildStop::Cancelled)));
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+    }
+    
+    #[tokio::test]
+    async fn test_realtime_build_preempts_and_requeues_running_build() {
+        if which::which("cargo").is_err() {
+            return;
+        }
+        // A crate whose build script outlives the test keeps the first build running
+        let slow = tempfile::tempdir().unwrap();
+        std::fs::create_dir(slow.path().join("src")).unwrap();
+        std::fs::write(slow.path().join("Cargo.toml"), "[package]\nname = \"slow\"\nversion = \"0.1.0\"\nedition = \"2021\"\n").unwrap();
+        std::fs::write(slow.path().join("build.rs"), "fn main() { std::thread::sleep(std::time::Duration::from_secs(60)); }\n").unwrap();
+        std::fs::write(slow.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+        let fast = tempfile::tempdir().unwrap();
+        let config = |dir: &Path, build_system: &str| -> StreamingBuildConfig {
+            serde_json::from_value(serde_json::json!({
+                "project_path": dir,
+                "mcu_target": "cortex-m4",
+                "optimization": "Os",
+                "defines": {},
+                "include_paths": [],
+                "source_files": [],
+                "build_system": build_system,
+            })).unwrap()
+        };
+        
+        let job_manager = Arc::new(JobManager::new());
+        let manager = BuildManager::with_job_manager(job_manager.clone());
+        let mut events = manager.subscribe();
+        async fn next_event(events: &mut broadcast::Receiver<BuildEvent>) -> BuildEvent {
+            tokio::time::timeout(std::time::Duration::from_secs(60), events.recv()).await
+                .expect("timed out waiting for a build event")
+                .unwrap()
+        }
+        async fn wait_until_compiling(events: &mut broadcast::Receiver<BuildEvent>, build_id: &str) {
+            loop {
+                if let BuildEvent::Progress { header, phase: BuildPhase::Compiling, .. } = next_event(events).await {
+                    if header.build_id == build_id {
+                        return;
+                    }
+                }
+            }
+        }
+        
+        let normal = manager.start_build(config(slow.path(), "cargo")).await;
+        assert_eq!(job_manager.scheduler().priority(&normal), Some(JobPriority::Normal));
+        wait_until_compiling(&mut events, &normal).await;
+        
+        let (record, _tx) = job_manager.create_job(JobKind::Build);
+        assert!(job_manager.set_priority(&record.id, JobPriority::Realtime));
+        let realtime = manager.start_build_with_id(record.id.clone(), config(fast.path(), "make")).await;
+        
+        let (mut requeued, mut completed) = (None, None);
+        while requeued.is_none() || completed.is_none() {
+            match next_event(&mut events).await {
+                BuildEvent::Requeued { header, reason } => requeued = Some((header.build_id, reason)),
+                BuildEvent::Completed { header, .. } => completed = Some(header.build_id),
+                BuildEvent::Cancelled { header, .. } => panic!("{} was cancelled", header.build_id),
+                _ => {}
+            }
+        }
+        assert_eq!(requeued, Some((normal.clone(), "preempted".to_string())));
+        assert_eq!(completed, Some(realtime.clone()));
+        
+        // The preempted build runs again once the realtime build is done
+        wait_until_compiling(&mut events, &normal).await;
+        assert!(manager.cancel_build(&normal).await);
+        for _ in 0..100 {
+            if job_manager.get_job(&normal).is_none() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert!(job_manager.get_job(&normal).is_none());
+        assert!(job_manager.get_job(&realtime).is_none());
+    }
+    
     #[test]
     fn test_cancellation_token() {
         let token = CancellationToken::new();
@@ -388,6 +388,9 @@ function App() {
     }).catch(e => console.warn("Failed to sync FSM context:", e));
   });
 
+  // Pinout of the loaded project, written back unchanged on save
+  let projectPins: unknown[] = [];
+
   // Save project to file
   async function saveProject() {
     const projectData = {
@@ -396,6 +399,7 @@ function App() {
       edges: edges().map(e => ({ ...e })),
       mcu: "STM32F401",
       language: codeLanguage(),
+      pins: projectPins,
     };
     const path = `${projectName().replace(/\s+/g, '_')}.neurobench.json`;
     try {
@@ -414,6 +418,7 @@ function App() {
       setNodes(project.nodes);
       setEdges(project.edges);
       setCodeLanguage(project.language);
+      projectPins = project.pins ?? [];
       addLog("PROJECT", `Loaded: ${path}`, "success");
     } catch (e) {
       addLog("ERROR", `Load failed: ${e}`, "error");
@@ -693,6 +698,7 @@ function App() {
   // Button handlers
   const handleNewProject = async () => {
     try {
+      await invoke("project_close");
       const project = await invoke("create_project", { name: "New Project", targetMcu: "stm32f401" });
       setProjectName((project as any).name);
       addLog("PROJECT", "Created new project", "success");
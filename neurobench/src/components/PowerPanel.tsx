@@ -1,4 +1,4 @@
-import { createSignal, For, Show } from "solid-js";
+import { createSignal, createResource, For, Show } from "solid-js";
 import { invoke } from "@tauri-apps/api/core";
 
 interface PowerBreakdown {
@@ -7,6 +7,12 @@ interface PowerBreakdown {
   percent: number;
 }
 
+interface PeripheralPower {
+  name: string;
+  active_current_ma: number;
+  sleep_current_ma: number;
+}
+
 interface PowerEstimation {
   mcu: string;
   total_current_ma: number;
@@ -14,6 +20,10 @@ interface PowerEstimation {
   battery_life_hours: number | null;
   breakdown: PowerBreakdown[];
   recommendations: string[];
+  average_ma: number;
+  peak_ma: number;
+  daily_mwh: number;
+  peripheral_breakdown: { peripherals: PowerBreakdown[]; dominant: string | null };
 }
 
 interface PowerPanelProps {
@@ -23,14 +33,18 @@ interface PowerPanelProps {
 export function PowerPanel(props: PowerPanelProps) {
   const [selectedMcu, setSelectedMcu] = createSignal("STM32F407");
   const [peripherals, setPeripherals] = createSignal<string[]>([]);
-  const [dutyCycle, setDutyCycle] = createSignal(50);
+  const [sleepMode, setSleepMode] = createSignal("Stop");
+  const [wakeSource, setWakeSource] = createSignal("RtcWakeup");
+  const [wakeHz, setWakeHz] = createSignal(1);
   const [batteryMah, setBatteryMah] = createSignal(1000);
   const [estimation, setEstimation] = createSignal<PowerEstimation | null>(null);
   const [isLoading, setIsLoading] = createSignal(false);
 
-  const availablePeripherals = [
-    "UART", "SPI", "I2C", "ADC", "DAC", "Timer", "DMA", "USB", "WiFi", "BLE"
-  ];
+  const [peripheralTable] = createResource(() => invoke("power_get_peripherals") as Promise<PeripheralPower[]>);
+  const availablePeripherals = () => (peripheralTable() ?? []).map(p => p.name);
+
+  const sleepModes = ["Sleep", "Stop", "Standby"];
+  const wakeSources = ["RtcAlarm", "RtcWakeup", "Gpio", "Uart", "Timer", "Radio"];
 
   const mcuOptions = [
     "STM32F407", "STM32F103", "STM32L476", "ESP32", "nRF52832"
@@ -47,15 +61,16 @@ export function PowerPanel(props: PowerPanelProps) {
   const estimatePower = async () => {
     setIsLoading(true);
     try {
-      const result = await invoke("power_estimate", {
+      const result = await invoke("power_estimate_detailed", {
         mcu: selectedMcu(),
-        peripherals: peripherals(),
-        dutyCycle: dutyCycle(),
+        peripherals: (peripheralTable() ?? []).filter(p => peripherals().includes(p.name)),
+        sleepMode: sleepMode(),
+        wakeEvents: [{ source: wakeSource(), frequency_hz: wakeHz() }],
         batteryMah: batteryMah(),
       }) as PowerEstimation;
       
       setEstimation(result);
-      props.onLog?.("Power", `Estimated: ${result.total_current_ma.toFixed(2)}mA`, "info");
+      props.onLog?.("Power", `Estimated: ${result.average_ma.toFixed(2)}mA average, ${result.peak_ma.toFixed(2)}mA peak`, "info");
     } catch (e) {
       props.onLog?.("Power", `Estimation failed: ${e}`, "error");
     }
@@ -91,7 +106,7 @@ export function PowerPanel(props: PowerPanelProps) {
       <div class="config-section">
         <label>Active Peripherals</label>
         <div class="periph-grid">
-          <For each={availablePeripherals}>
+          <For each={availablePeripherals()}>
             {(periph) => (
               <button 
                 class={`periph-btn ${peripherals().includes(periph) ? "active" : ""}`}
@@ -104,15 +119,30 @@ export function PowerPanel(props: PowerPanelProps) {
         </div>
       </div>
 
-      {/* Duty Cycle */}
+      {/* Sleep Mode */}
+      <div class="config-section">
+        <label>Sleep Mode</label>
+        <select value={sleepMode()} onChange={(e) => setSleepMode(e.target.value)}>
+          <For each={sleepModes}>
+            {(mode) => <option value={mode}>{mode}</option>}
+          </For>
+        </select>
+      </div>
+
+      {/* Wake Events */}
       <div class="config-section">
-        <label>Active Duty Cycle: {dutyCycle()}%</label>
+        <label>Wake Source: {wakeHz()} Hz</label>
+        <select value={wakeSource()} onChange={(e) => setWakeSource(e.target.value)}>
+          <For each={wakeSources}>
+            {(source) => <option value={source}>{source}</option>}
+          </For>
+        </select>
         <input 
-          type="range" 
-          min="1" 
-          max="100" 
-          value={dutyCycle()}
-          onInput={(e) => setDutyCycle(parseInt(e.target.value))}
+          type="number" 
+          min="0" 
+          step="0.1" 
+          value={wakeHz()}
+          onInput={(e) => setWakeHz(parseFloat(e.target.value) || 0)}
         />
       </div>
 
@@ -135,8 +165,12 @@ export function PowerPanel(props: PowerPanelProps) {
         <div class="results">
           <div class="summary">
             <div class="stat">
-              <span class="stat-value">{estimation()!.total_current_ma.toFixed(2)}</span>
-              <span class="stat-label">mA</span>
+              <span class="stat-value">{estimation()!.average_ma.toFixed(2)}</span>
+              <span class="stat-label">mA avg</span>
+            </div>
+            <div class="stat">
+              <span class="stat-value">{estimation()!.peak_ma.toFixed(2)}</span>
+              <span class="stat-label">mA peak</span>
             </div>
             <div class="stat">
               <span class="stat-value">{estimation()!.power_mw.toFixed(1)}</span>
@@ -3,6 +3,7 @@
 
 import { createSignal, For, Show, onMount, createEffect, batch } from "solid-js";
 import { invoke } from "@tauri-apps/api/core";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
 
 interface TerminalLine {
   type: "input" | "output" | "error" | "success" | "info" | "system" | "warning" | "ansi";
@@ -11,6 +12,16 @@ interface TerminalLine {
   ansi?: string;  // ANSI color codes
 }
 
+type BackendLine = { line_type: string, content: string, ansi?: string };
+
+// Arguments of a `serial_monitor` line, passed on to `serial_monitor_start`
+interface SerialMonitorArgs {
+  port: string;
+  baud: number;
+  hex: boolean;
+  timestamps: boolean;
+}
+
 interface CompletionItem {
   text: string;
   display: string;
@@ -107,6 +118,9 @@ export function Terminal(props: TerminalProps) {
   const [searchResults, setSearchResults] = createSignal<number[]>([]);
   const [currentSearchIndex, setCurrentSearchIndex] = createSignal(0);
   
+  // Groups this terminal's commands in the persistent history
+  const sessionId = crypto.randomUUID();
+
   let terminalRef: HTMLDivElement | undefined;
   let inputRef: HTMLInputElement | undefined;
   let searchInputRef: HTMLInputElement | undefined;
@@ -133,18 +147,65 @@ export function Terminal(props: TerminalProps) {
     });
   }
 
-  function addLines(newLines: Array<{line_type: string, content: string, ansi?: string}>) {
+  function addLines(newLines: BackendLine[]) {
     batch(() => {
       newLines.forEach(line => {
-        addLine(line.line_type as TerminalLine["type"], line.content, line.ansi);
+        if (line.line_type === "serial_monitor") {
+          startSerialMonitor(JSON.parse(line.content) as SerialMonitorArgs);
+        } else {
+          addLine(line.line_type as TerminalLine["type"], line.content, line.ansi);
+        }
       });
     });
   }
 
+  // Running `monitor uart` job; its `serial:*` events are printed until it ends
+  let serialJobId: string | null = null;
+  let serialUnlisteners: UnlistenFn[] = [];
+
+  function closeSerialMonitor() {
+    for (const unlisten of serialUnlisteners) {
+      unlisten();
+    }
+    serialUnlisteners = [];
+    serialJobId = null;
+  }
+
+  async function startSerialMonitor(args: SerialMonitorArgs) {
+    if (serialJobId) {
+      await invoke("serial_monitor_stop", { jobId: serialJobId });
+    }
+    closeSerialMonitor();
+
+    // Listen before starting so the first lines are not missed
+    serialUnlisteners.push(await listen<{ port: string, lines: BackendLine[] }>("serial:data", (event) => {
+      if (event.payload.port === args.port) {
+        addLines(event.payload.lines);
+      }
+    }));
+    for (const outcome of ["completed", "cancelled", "internal_error"]) {
+      serialUnlisteners.push(await listen<{ header: { job_id: string }, terminal: { message?: string } }>(`serial:${outcome}`, (event) => {
+        if (event.payload.header.job_id !== serialJobId) return;
+        if (outcome === "internal_error") {
+          addLine("error", `Monitor error: ${event.payload.terminal.message ?? "unknown"}`);
+        }
+        addLine("info", `📡 Monitor on ${args.port} closed`);
+        closeSerialMonitor();
+      }));
+    }
+
+    try {
+      serialJobId = await invoke("serial_monitor_start", { ...args }) as string;
+    } catch (e) {
+      addLine("error", `Error: ${e}`);
+      closeSerialMonitor();
+    }
+  }
+
   // Load welcome message on mount
   onMount(async () => {
     try {
-      const welcome = await invoke("terminal_get_welcome") as Array<{line_type: string, content: string, ansi?: string}>;
+      const welcome = await invoke("terminal_get_welcome") as BackendLine[];
       addLines(welcome);
     } catch (e) {
       addLine("system", "NeuroBench Advanced Terminal v2.0");
@@ -232,8 +293,9 @@ export function Terminal(props: TerminalProps) {
       // Use advanced terminal backend for all other commands
       const result = await invoke("terminal_execute_advanced", { 
         command: trimmed,
-        variables: variables()
-      }) as { success: boolean, output: Array<{line_type: string, content: string, ansi?: string}>, command_count: number };
+        variables: variables(),
+        sessionId,
+      }) as { success: boolean, output: BackendLine[], command_count: number };
 
       if (result.output && result.output.length > 0) {
         addLines(result.output);
@@ -304,9 +366,12 @@ export function Terminal(props: TerminalProps) {
       setLines([]);
       addLine("system", "Terminal cleared");
     } else if (e.ctrlKey && e.key === "c") {
-      // Cancel current input
+      // Cancel current input and close a running monitor
       setInput("");
       addLine("output", "^C");
+      if (serialJobId) {
+        invoke("serial_monitor_stop", { jobId: serialJobId });
+      }
     }
   }
 